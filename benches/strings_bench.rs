@@ -0,0 +1,59 @@
+use bulu::runtime::bytes;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const SIZES: &[usize] = &[4 * 1024, 1024 * 1024];
+
+/// Text of `size` bytes with the needle only at the very end
+fn haystack(size: usize) -> String {
+    let mut text = "lorem ipsum dolor sit amet, ".repeat(size / 28 + 1);
+    text.truncate(size - 6);
+    text.push_str("needle");
+    text
+}
+
+/// The byte-at-a-time search the runtime used before
+fn scalar_index_of(haystack: &str, needle: &str) -> Option<usize> {
+    haystack.as_bytes().windows(needle.len()).position(|window| window == needle.as_bytes())
+}
+
+fn search_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("string_index_of");
+    for &size in SIZES {
+        let text = haystack(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new("scalar", size), &text, |b, text| {
+            b.iter(|| scalar_index_of(black_box(text), black_box("needle")))
+        });
+        group.bench_with_input(BenchmarkId::new("memmem", size), &text, |b, text| {
+            b.iter(|| bytes::index_of(black_box(text), black_box("needle")))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("string_count");
+    let text = haystack(1024 * 1024);
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_function("std", |b| b.iter(|| black_box(&text).matches(black_box("sit")).count()));
+    group.bench_function("memmem", |b| b.iter(|| bytes::count(black_box(&text), black_box("sit"))));
+    group.finish();
+}
+
+fn utf8_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("utf8_validation");
+    for (name, text) in [
+        ("ascii", haystack(1024 * 1024)),
+        ("mixed", "Grüße, 世界! ".repeat(1024 * 1024 / 19)),
+    ] {
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::new("std", name), text.as_bytes(), |b, data| {
+            b.iter(|| std::str::from_utf8(black_box(data)).is_ok())
+        });
+        group.bench_with_input(BenchmarkId::new("simd", name), text.as_bytes(), |b, data| {
+            b.iter(|| bytes::validate_utf8(black_box(data)).is_ok())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, search_benchmark, utf8_benchmark);
+criterion_main!(benches);
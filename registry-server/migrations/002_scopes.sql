@@ -0,0 +1,7 @@
+-- Package scopes (@scope/name), owned by the publish token that first claimed them
+CREATE TABLE IF NOT EXISTS scopes (
+    id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    owner_token_hash TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Release notes sent with a version at publish time, from the package's CHANGELOG
+ALTER TABLE package_versions ADD COLUMN IF NOT EXISTS release_notes TEXT
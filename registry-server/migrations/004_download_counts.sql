@@ -0,0 +1,17 @@
+-- Downloads of each version per UTC day, counted as they happen
+CREATE TABLE IF NOT EXISTS download_counts (
+    package_version_id BIGINT NOT NULL,
+    day DATE NOT NULL,
+    downloads BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (package_version_id, day),
+    FOREIGN KEY (package_version_id) REFERENCES package_versions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_download_counts_day ON download_counts(day);
+
+-- Downloads recorded before the daily counts existed
+INSERT INTO download_counts (package_version_id, day, downloads)
+SELECT package_version_id, (downloaded_at AT TIME ZONE 'UTC')::date, COUNT(*)
+FROM download_stats
+WHERE NOT EXISTS (SELECT 1 FROM download_counts)
+GROUP BY 1, 2
@@ -0,0 +1,3 @@
+-- README.md and lang.toml of each version, read from its tarball at publish time
+ALTER TABLE package_versions ADD COLUMN IF NOT EXISTS readme TEXT;
+ALTER TABLE package_versions ADD COLUMN IF NOT EXISTS manifest TEXT
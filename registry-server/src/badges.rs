@@ -0,0 +1,264 @@
+//! README badges in the shields.io flat style
+//!
+//! `/badge/v/:name` shows the latest version of a package and `/badge/d/:name`
+//! its total downloads. A trailing `.svg` on the name is ignored, so badge
+//! URLs can end the way image URLs usually do. Rendered badges are cached for
+//! `BADGE_CACHE_SECS` (300 by default), which is also the `max-age` clients
+//! and proxies such as GitHub's camo get, so READMEs don't reach the database
+//! on every view.
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::AppState;
+
+pub const DEFAULT_CACHE_SECS: u64 = 300;
+
+const BLUE: &str = "#007ec6";
+const ORANGE: &str = "#fe7d37";
+const BRIGHT_GREEN: &str = "#4c1";
+const LIGHT_GREY: &str = "#9f9f9f";
+
+/// Horizontal padding on each side of a badge's label and message
+const PADDING: u32 = 5;
+
+/// Badges rendered in the last TTL, by route and package name
+pub struct BadgeCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, String)>>,
+}
+
+impl BadgeCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(key)
+            .filter(|(rendered, _)| rendered.elapsed() < self.ttl)
+            .map(|(_, svg)| svg.clone())
+    }
+
+    fn insert(&self, key: String, svg: String) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (rendered, _)| rendered.elapsed() < self.ttl);
+        entries.insert(key, (Instant::now(), svg));
+    }
+}
+
+/// GET /badge/v/:name - the latest version
+pub async fn version_badge(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    badge(&state, "v", &name).await
+}
+
+/// GET /badge/d/:name - total downloads of every version
+pub async fn downloads_badge(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    badge(&state, "d", &name).await
+}
+
+async fn badge(
+    state: &AppState,
+    kind: &str,
+    name: &str,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let name = name.strip_suffix(".svg").unwrap_or(name);
+    let label = if kind == "v" { "version" } else { "downloads" };
+    let key = format!("{}/{}", kind, name);
+
+    let (status, svg) = match state.badges.get(&key) {
+        Some(svg) => (StatusCode::OK, svg),
+        None => {
+            let package = state
+                .db
+                .get_package(name)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            match package {
+                // Unknown names are not cached, so they can't grow the cache
+                None => (StatusCode::NOT_FOUND, render(label, "package not found", LIGHT_GREY)),
+                Some(package) => {
+                    let svg = if kind == "v" {
+                        // Versions are ordered newest first
+                        let versions = state
+                            .db
+                            .get_package_versions(package.id)
+                            .await
+                            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+                        match versions.first() {
+                            Some(latest) => {
+                                let color = if latest.version.contains('-') { ORANGE } else { BLUE };
+                                render(label, &format!("v{}", latest.version), color)
+                            }
+                            None => render(label, "none", LIGHT_GREY),
+                        }
+                    } else {
+                        let downloads = state
+                            .db
+                            .get_total_downloads(package.id)
+                            .await
+                            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+                        render(label, &format_count(downloads), BRIGHT_GREEN)
+                    };
+                    state.badges.insert(key, svg.clone());
+                    (StatusCode::OK, svg)
+                }
+            }
+        }
+    };
+
+    Ok((
+        status,
+        [
+            (header::CONTENT_TYPE, "image/svg+xml;charset=utf-8".to_string()),
+            (
+                header::CACHE_CONTROL,
+                format!("max-age={}", state.badges.ttl().as_secs()),
+            ),
+        ],
+        svg,
+    ))
+}
+
+/// A count the way shields.io shows it: `999`, `1.2k`, `12k`, `3.4M`
+pub fn format_count(count: i64) -> String {
+    const PREFIXES: [&str; 4] = ["k", "M", "G", "T"];
+
+    if count < 1000 {
+        return count.max(0).to_string();
+    }
+    let mut value = count as f64;
+    let mut prefix = "";
+    for next in PREFIXES {
+        if value < 1000.0 {
+            break;
+        }
+        value /= 1000.0;
+        prefix = next;
+    }
+    if value < 10.0 {
+        // Round down so a badge never overstates
+        let tenths = (value * 10.0).floor() / 10.0;
+        format!("{}{}", tenths, prefix)
+    } else {
+        format!("{}{}", value.floor(), prefix)
+    }
+}
+
+/// Render a flat badge with a grey label on the left and `message` on `color`
+pub fn render(label: &str, message: &str, color: &str) -> String {
+    let label_width = text_width(label) + 2 * PADDING;
+    let message_width = text_width(message) + 2 * PADDING;
+    let width = label_width + message_width;
+    let (label, message) = (escape_xml(label), escape_xml(message));
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"20\" role=\"img\" aria-label=\"{label}: {message}\">\
+<title>{label}: {message}</title>\
+<linearGradient id=\"s\" x2=\"0\" y2=\"100%\"><stop offset=\"0\" stop-color=\"#bbb\" stop-opacity=\".1\"/><stop offset=\"1\" stop-opacity=\".1\"/></linearGradient>\
+<clipPath id=\"r\"><rect width=\"{width}\" height=\"20\" rx=\"3\" fill=\"#fff\"/></clipPath>\
+<g clip-path=\"url(#r)\"><rect width=\"{label_width}\" height=\"20\" fill=\"#555\"/><rect x=\"{label_width}\" width=\"{message_width}\" height=\"20\" fill=\"{color}\"/><rect width=\"{width}\" height=\"20\" fill=\"url(#s)\"/></g>\
+<g fill=\"#fff\" text-anchor=\"middle\" font-family=\"Verdana,Geneva,DejaVu Sans,sans-serif\" text-rendering=\"geometricPrecision\" font-size=\"110\">"
+    );
+    // Text is drawn at ten times the size and scaled down, as shields.io does,
+    // with a shadow one pixel below it
+    for (text, x, text_width) in [
+        (&label, label_width * 5, (label_width - 2 * PADDING) * 10),
+        (&message, (label_width * 2 + message_width) * 5, (message_width - 2 * PADDING) * 10),
+    ] {
+        let _ = write!(
+            svg,
+            "<text aria-hidden=\"true\" x=\"{x}\" y=\"150\" fill=\"#010101\" fill-opacity=\".3\" transform=\"scale(.1)\" textLength=\"{text_width}\">{text}</text>\
+<text x=\"{x}\" y=\"140\" transform=\"scale(.1)\" fill=\"#fff\" textLength=\"{text_width}\">{text}</text>"
+        );
+    }
+    svg.push_str("</g></svg>");
+    svg
+}
+
+/// Approximate width in pixels of `text` in 11px Verdana
+fn text_width(text: &str) -> u32 {
+    let tenths: u32 = text
+        .chars()
+        .map(|c| match c {
+            'i' | 'j' | 'l' | '.' | ',' | ':' | ';' | '!' | '|' | '\'' => 34,
+            ' ' | 'f' | 'r' | 't' | '(' | ')' | '[' | ']' | '-' | '/' => 45,
+            'm' | 'w' => 96,
+            'M' | 'W' | '@' => 100,
+            c if c.is_ascii_uppercase() => 75,
+            _ => 70,
+        })
+        .sum();
+    tenths.div_ceil(10)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_count() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(1000), "1k");
+        assert_eq!(format_count(1299), "1.2k");
+        assert_eq!(format_count(12_345), "12k");
+        assert_eq!(format_count(999_999), "999k");
+        assert_eq!(format_count(3_400_000), "3.4M");
+    }
+
+    #[test]
+    fn test_render_sizes_segments_to_their_text() {
+        let svg = render("version", "v1.2.0", BLUE);
+        let width = |text: &str| text_width(text) + 2 * PADDING;
+        assert!(svg.starts_with(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\"",
+            width("version") + width("v1.2.0")
+        )));
+        assert!(svg.contains(&format!("<rect x=\"{}\" width=\"{}\" height=\"20\" fill=\"#007ec6\"/>", width("version"), width("v1.2.0"))));
+        assert!(svg.contains("<title>version: v1.2.0</title>"));
+        assert!(render("a&b", "<x>", BLUE).contains("<title>a&amp;b: &lt;x&gt;</title>"));
+    }
+
+    #[test]
+    fn test_cache_expires_entries() {
+        let cache = BadgeCache::new(Duration::from_secs(60));
+        cache.insert("v/json".to_string(), "svg".to_string());
+        assert_eq!(cache.get("v/json").as_deref(), Some("svg"));
+        assert_eq!(cache.get("d/json"), None);
+
+        let expired = BadgeCache::new(Duration::ZERO);
+        expired.insert("v/json".to_string(), "svg".to_string());
+        assert_eq!(expired.get("v/json"), None);
+    }
+}
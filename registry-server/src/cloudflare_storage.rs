@@ -8,6 +8,7 @@ use aws_sdk_s3::{
     Client,
 };
 use crate::error::RegistryError;
+use crate::storage::StoredObject;
 
 pub struct CloudflareStorage {
     client: Client,
@@ -150,6 +151,63 @@ impl CloudflareStorage {
         
         Ok(versions)
     }
+
+    /// Check that the bucket exists and the credentials can reach it
+    pub async fn check_bucket(&self) -> Result<(), RegistryError> {
+        self.client
+            .head_bucket()
+            .bucket(&self.bucket_name)
+            .send()
+            .await
+            .map_err(|e| RegistryError::StorageError(format!("R2 bucket check failed: {}", e)))?;
+        Ok(())
+    }
+
+    /// List every tarball under `packages/`, following continuation tokens
+    pub async fn list_objects(&self) -> Result<Vec<StoredObject>, RegistryError> {
+        let mut objects = Vec::new();
+        let mut continuation_token: Option<String> = None;
+
+        loop {
+            let response = self.client
+                .list_objects_v2()
+                .bucket(&self.bucket_name)
+                .prefix("packages/")
+                .set_continuation_token(continuation_token.take())
+                .send()
+                .await
+                .map_err(|e| RegistryError::StorageError(format!("R2 list failed: {}", e)))?;
+
+            for object in response.contents() {
+                // Keys look like packages/name/version.tar.gz; names may themselves contain '/'
+                let Some(path) = object.key().and_then(|key| key.strip_prefix("packages/")) else {
+                    continue;
+                };
+                let Some((package_name, filename)) = path.rsplit_once('/') else {
+                    continue;
+                };
+                let Some(version) = filename.strip_suffix(".tar.gz") else {
+                    continue;
+                };
+                objects.push(StoredObject {
+                    package_name: package_name.to_string(),
+                    version: version.to_string(),
+                    last_modified: object
+                        .last_modified()
+                        .and_then(|modified| chrono::DateTime::from_timestamp(modified.secs(), 0)),
+                });
+            }
+
+            match response.next_continuation_token() {
+                Some(token) if response.is_truncated() == Some(true) => {
+                    continuation_token = Some(token.to_string());
+                }
+                _ => break,
+            }
+        }
+
+        Ok(objects)
+    }
 }
 
 // Implement StorageBackend trait
@@ -186,4 +244,12 @@ impl crate::storage::StorageBackend for CloudflareStorage {
     ) -> Result<Vec<String>, RegistryError> {
         self.list_versions(package_name).await
     }
+
+    async fn list_objects(&self) -> Result<Vec<StoredObject>, RegistryError> {
+        self.list_objects().await
+    }
+
+    async fn check_access(&self) -> Result<(), RegistryError> {
+        self.check_bucket().await
+    }
 }
@@ -1,14 +1,112 @@
 //! Database operations using SeaORM
 
+use sea_orm::sea_query::Expr;
 use sea_orm::*;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use crate::entities::{self, package, package_version, package_author, package_keyword, package_dependency, download_stat};
+use crate::entities::{self, package, package_version, package_author, package_keyword, package_dependency, download_stat, download_count, scope};
 
 #[derive(Clone)]
 pub struct Database {
     pub db: DatabaseConnection,
 }
 
+/// Connection pool usage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Connections currently open, idle or not
+    pub open: u32,
+    pub idle: u32,
+    pub max: u32,
+}
+
+/// Largest page the list and search endpoints return
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+/// How package lists are ordered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageSort {
+    Name,
+    /// Most downloaded first, counting every version
+    Downloads,
+    /// Most recently published first
+    Recent,
+    /// Best match for the search query first: the exact name, then names
+    /// starting with the query, then other names, then descriptions
+    Relevance,
+}
+
+/// A package with what list pages show of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageSummary {
+    pub package: package::Model,
+    pub latest: Option<package_version::Model>,
+    pub downloads: i64,
+    pub keywords: Vec<String>,
+}
+
+/// One page of packages, and how many there are in all
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackagePage {
+    pub packages: Vec<PackageSummary>,
+    pub total: u64,
+}
+
+/// Longest download history the downloads endpoint returns, in buckets
+pub const MAX_DOWNLOAD_PERIODS: usize = 366;
+
+/// How downloads are grouped over time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadInterval {
+    Daily,
+    /// Weeks starting on Monday
+    Weekly,
+}
+
+impl DownloadInterval {
+    /// First day of the bucket `day` falls in
+    pub fn bucket_start(self, day: chrono::NaiveDate) -> chrono::NaiveDate {
+        match self {
+            DownloadInterval::Daily => day,
+            DownloadInterval::Weekly => {
+                day - chrono::Duration::days(chrono::Datelike::weekday(&day).num_days_from_monday() as i64)
+            }
+        }
+    }
+
+    fn days(self) -> i64 {
+        match self {
+            DownloadInterval::Daily => 1,
+            DownloadInterval::Weekly => 7,
+        }
+    }
+}
+
+/// Downloads of some versions over the last few buckets
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadHistory {
+    pub interval: DownloadInterval,
+    /// First day of each bucket, oldest first; the last one holds today
+    pub starts: Vec<chrono::NaiveDate>,
+    /// Downloads per bucket of each version with any, by version id
+    pub versions: HashMap<i64, Vec<i64>>,
+}
+
+impl DownloadHistory {
+    /// Downloads per bucket of all the versions together
+    pub fn totals(&self) -> Vec<i64> {
+        let mut totals = vec![0; self.starts.len()];
+        for counts in self.versions.values() {
+            for (total, count) in totals.iter_mut().zip(counts) {
+                *total += count;
+            }
+        }
+        totals
+    }
+}
+
 impl Database {
     /// Create a new database connection
     pub async fn new(database_url: &str) -> Result<Self, DbErr> {
@@ -23,9 +121,15 @@ impl Database {
     /// Run database migrations
     async fn run_migrations(db: &DatabaseConnection) -> Result<(), DbErr> {
         tracing::info!("🔄 Running database migrations...");
-        let migration_sql = include_str!("../migrations/001_initial_schema.sql");
+        let migrations = [
+            include_str!("../migrations/001_initial_schema.sql"),
+            include_str!("../migrations/002_scopes.sql"),
+            include_str!("../migrations/003_release_notes.sql"),
+            include_str!("../migrations/004_download_counts.sql"),
+            include_str!("../migrations/005_package_files.sql"),
+        ];
         
-        let statements: Vec<&str> = migration_sql.split(';').collect();
+        let statements: Vec<&str> = migrations.iter().flat_map(|sql| sql.split(';')).collect();
         tracing::info!("📝 Found {} SQL statements", statements.len());
         
         for (i, statement) in statements.iter().enumerate() {
@@ -44,6 +148,26 @@ impl Database {
         Ok(())
     }
 
+    /// Check that the database answers
+    pub async fn ping(&self) -> Result<(), DbErr> {
+        self.db.ping().await
+    }
+
+    /// Connection pool usage, when connected through a Postgres pool
+    pub fn pool_stats(&self) -> Option<PoolStats> {
+        match &self.db {
+            DatabaseConnection::SqlxPostgresPoolConnection(_) => {
+                let pool = self.db.get_postgres_connection_pool();
+                Some(PoolStats {
+                    open: pool.size(),
+                    idle: pool.num_idle() as u32,
+                    max: pool.options().get_max_connections(),
+                })
+            }
+            _ => None,
+        }
+    }
+
     /// Create or update a package
     pub async fn upsert_package(
         &self,
@@ -114,6 +238,38 @@ impl Database {
         Ok(result.id)
     }
 
+    /// Attach the release notes of a package version
+    pub async fn set_release_notes(&self, version_id: i64, notes: &str) -> Result<(), DbErr> {
+        let version = package_version::Entity::find_by_id(version_id)
+            .one(&self.db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Package version not found".to_string()))?;
+
+        let mut active_model: package_version::ActiveModel = version.into();
+        active_model.release_notes = Set(Some(notes.to_string()));
+        active_model.update(&self.db).await?;
+        Ok(())
+    }
+
+    /// Store the README and manifest read from a version's tarball
+    pub async fn set_package_files(
+        &self,
+        version_id: i64,
+        readme: Option<&str>,
+        manifest: Option<&str>,
+    ) -> Result<(), DbErr> {
+        let version = package_version::Entity::find_by_id(version_id)
+            .one(&self.db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("Package version not found".to_string()))?;
+
+        let mut active_model: package_version::ActiveModel = version.into();
+        active_model.readme = Set(readme.map(str::to_string));
+        active_model.manifest = Set(manifest.map(str::to_string));
+        active_model.update(&self.db).await?;
+        Ok(())
+    }
+
     /// Add authors to a package version
     pub async fn add_authors(&self, version_id: i64, authors: &[String]) -> Result<(), DbErr> {
         for author in authors {
@@ -238,7 +394,7 @@ impl Database {
         Ok(dependencies)
     }
 
-    /// Increment download counter
+    /// Count a download of a version, in its total and in today's bucket
     pub async fn increment_downloads(&self, version_id: i64) -> Result<(), DbErr> {
         // Update downloads count
         let version = package_version::Entity::find_by_id(version_id)
@@ -260,27 +416,66 @@ impl Database {
             ..Default::default()
         };
         stat.insert(&self.db).await?;
-        
+
+        let today = download_count::ActiveModel {
+            package_version_id: Set(version_id),
+            day: Set(now.date_naive()),
+            downloads: Set(1),
+        };
+        download_count::Entity::insert(today)
+            .on_conflict(
+                sea_query::OnConflict::columns([download_count::Column::PackageVersionId, download_count::Column::Day])
+                    .value(
+                        download_count::Column::Downloads,
+                        Expr::col((download_count::Entity, download_count::Column::Downloads)).add(1),
+                    )
+                    .to_owned(),
+            )
+            .exec_without_returning(&self.db)
+            .await?;
+
         Ok(())
     }
 
-    /// Search packages
-    pub async fn search_packages(&self, query: &str, limit: u64) -> Result<Vec<package::Model>, DbErr> {
-        let search_pattern = format!("%{}%", query);
-        
-        // Search in package names and descriptions
-        let packages = package::Entity::find()
-            .filter(
-                Condition::any()
-                    .add(package::Column::Name.like(&search_pattern))
-                    .add(package::Column::Description.like(&search_pattern))
-            )
-            .order_by_asc(package::Column::Name)
-            .limit(limit)
+    /// A page of the packages matching `query` in their name or description,
+    /// or of all packages without a query
+    pub async fn packages_page(
+        &self,
+        query: Option<&str>,
+        sort: PackageSort,
+        limit: u64,
+        offset: u64,
+    ) -> Result<PackagePage, DbErr> {
+        let select = packages_query(query, sort);
+        let total = select.clone().count(&self.db).await?;
+        let packages = select
+            .limit(limit.min(MAX_PAGE_SIZE))
+            .offset(offset)
             .all(&self.db)
             .await?;
-        
-        Ok(packages)
+        Ok(PackagePage {
+            packages: self.package_summaries(packages).await?,
+            total,
+        })
+    }
+
+    /// Latest version, downloads and keywords of each package, loaded with
+    /// one query for all the versions and one for all the keywords
+    pub async fn package_summaries(&self, packages: Vec<package::Model>) -> Result<Vec<PackageSummary>, DbErr> {
+        let ids: Vec<i64> = packages.iter().map(|pkg| pkg.id).collect();
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let versions = package_version::Entity::find()
+            .filter(package_version::Column::PackageId.is_in(ids.clone()))
+            .order_by_desc(package_version::Column::PublishedAt)
+            .all(&self.db)
+            .await?;
+        let keywords = package_keyword::Entity::find()
+            .filter(package_keyword::Column::PackageId.is_in(ids))
+            .all(&self.db)
+            .await?;
+        Ok(summarize(packages, versions, keywords))
     }
 
     /// Get total downloads for a package
@@ -294,6 +489,71 @@ impl Database {
         Ok(total)
     }
     
+    /// Downloads per day over the last `days` days, oldest first, including days without downloads
+    pub async fn get_daily_downloads(
+        &self,
+        package_id: i64,
+        days: i64,
+    ) -> Result<Vec<(chrono::NaiveDate, i64)>, DbErr> {
+        let version_ids = self
+            .get_package_versions(package_id)
+            .await?
+            .into_iter()
+            .map(|v| v.id)
+            .collect();
+        let history = self
+            .download_history(version_ids, DownloadInterval::Daily, days.max(0) as usize)
+            .await?;
+        Ok(history.starts.iter().copied().zip(history.totals()).collect())
+    }
+
+    /// Downloads of the versions over the last `periods` buckets of `interval`
+    pub async fn download_history(
+        &self,
+        version_ids: Vec<i64>,
+        interval: DownloadInterval,
+        periods: usize,
+    ) -> Result<DownloadHistory, DbErr> {
+        let starts = bucket_starts(interval, periods, chrono::Utc::now().date_naive());
+        let counts = match starts.first() {
+            Some(first) if !version_ids.is_empty() => {
+                download_count::Entity::find()
+                    .filter(download_count::Column::PackageVersionId.is_in(version_ids))
+                    .filter(download_count::Column::Day.gte(*first))
+                    .all(&self.db)
+                    .await?
+            }
+            _ => Vec::new(),
+        };
+        Ok(DownloadHistory {
+            versions: bucket_counts(interval, &starts, counts),
+            interval,
+            starts,
+        })
+    }
+
+    /// Downloads of each package over the last `days` days, by package id,
+    /// for the packages with any
+    pub async fn recent_downloads(&self, package_ids: Vec<i64>, days: i64) -> Result<HashMap<i64, i64>, DbErr> {
+        if package_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let since = chrono::Utc::now().date_naive() - chrono::Duration::days(days - 1);
+        let rows: Vec<(i64, i64)> = download_count::Entity::find()
+            .select_only()
+            .column(package_version::Column::PackageId)
+            // SUM of a BIGINT is a NUMERIC in Postgres
+            .column_as(Expr::cust("CAST(SUM(download_counts.downloads) AS BIGINT)"), "downloads")
+            .inner_join(package_version::Entity)
+            .filter(package_version::Column::PackageId.is_in(package_ids))
+            .filter(download_count::Column::Day.gte(since))
+            .group_by(package_version::Column::PackageId)
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+        Ok(rows.into_iter().collect())
+    }
+
     /// Delete a package version
     pub async fn delete_package_version(&self, version_id: i64) -> Result<(), DbErr> {
         package_version::Entity::delete_by_id(version_id)
@@ -301,4 +561,282 @@ impl Database {
             .await?;
         Ok(())
     }
+
+    /// List the versions of every package
+    pub async fn list_all_versions(&self) -> Result<Vec<package_version::Model>, DbErr> {
+        package_version::Entity::find()
+            .order_by_asc(package_version::Column::PackageId)
+            .all(&self.db)
+            .await
+    }
+
+    /// Delete a package (cascade will remove its versions and keywords)
+    pub async fn delete_package(&self, package_id: i64) -> Result<(), DbErr> {
+        package::Entity::delete_by_id(package_id)
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Get a scope by name, without its leading `@`
+    pub async fn get_scope(&self, name: &str) -> Result<Option<scope::Model>, DbErr> {
+        scope::Entity::find()
+            .filter(scope::Column::Name.eq(name))
+            .one(&self.db)
+            .await
+    }
+
+    /// Claim an unowned scope and return its owner afterwards, which is someone
+    /// else when a concurrent publish claimed it first
+    pub async fn claim_scope(&self, name: &str, owner_token_hash: &str) -> Result<scope::Model, DbErr> {
+        let new_scope = scope::ActiveModel {
+            name: Set(name.to_string()),
+            owner_token_hash: Set(owner_token_hash.to_string()),
+            created_at: Set(chrono::Utc::now().into()),
+            ..Default::default()
+        };
+
+        scope::Entity::insert(new_scope)
+            .on_conflict(
+                sea_query::OnConflict::column(scope::Column::Name)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(&self.db)
+            .await?;
+
+        self.get_scope(name)
+            .await?
+            .ok_or_else(|| DbErr::RecordNotFound(format!("scope {}", name)))
+    }
+}
+
+/// Packages matching `query`, in `sort` order. Name breaks ties, so pages
+/// don't overlap or skip packages.
+fn packages_query(query: Option<&str>, sort: PackageSort) -> Select<package::Entity> {
+    let mut select = package::Entity::find();
+    // `%` and `_` in the query match themselves in LIKE patterns
+    let escaped = query.map(|query| query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+    if let Some(escaped) = &escaped {
+        let pattern = format!("%{}%", escaped);
+        select = select.filter(
+            Condition::any()
+                .add(package::Column::Name.like(&pattern))
+                .add(package::Column::Description.like(&pattern)),
+        );
+    }
+
+    select = match sort {
+        PackageSort::Name => select,
+        PackageSort::Downloads => select.order_by(
+            Expr::cust(
+                "(SELECT COALESCE(SUM(package_versions.downloads), 0) FROM package_versions \
+                 WHERE package_versions.package_id = packages.id)",
+            ),
+            Order::Desc,
+        ),
+        PackageSort::Recent => select.order_by_desc(package::Column::UpdatedAt),
+        PackageSort::Relevance => match query.zip(escaped.as_deref()) {
+            // The exact match compares with the query as typed
+            Some((query, escaped)) => select.order_by(
+                Expr::cust_with_values(
+                    "CASE WHEN packages.name = $1 THEN 0 WHEN packages.name LIKE $2 THEN 1 \
+                     WHEN packages.name LIKE $3 THEN 2 ELSE 3 END",
+                    [query.to_string(), format!("{}%", escaped), format!("%{}%", escaped)],
+                ),
+                Order::Asc,
+            ),
+            None => select,
+        },
+    };
+    select.order_by_asc(package::Column::Name)
+}
+
+/// First day of each of the last `periods` buckets of `interval`, oldest
+/// first, the last one holding `today`
+fn bucket_starts(interval: DownloadInterval, periods: usize, today: chrono::NaiveDate) -> Vec<chrono::NaiveDate> {
+    let last = interval.bucket_start(today);
+    (0..periods as i64)
+        .rev()
+        .map(|i| last - chrono::Duration::days(i * interval.days()))
+        .collect()
+}
+
+/// Add up daily counts into the buckets starting at `starts`, per version.
+/// Days before the first bucket are left out.
+fn bucket_counts(
+    interval: DownloadInterval,
+    starts: &[chrono::NaiveDate],
+    counts: Vec<download_count::Model>,
+) -> HashMap<i64, Vec<i64>> {
+    let mut versions: HashMap<i64, Vec<i64>> = HashMap::new();
+    let Some(first) = starts.first() else {
+        return versions;
+    };
+    for count in counts {
+        let index = (interval.bucket_start(count.day) - *first).num_days() / interval.days();
+        if (0..starts.len() as i64).contains(&index) {
+            versions
+                .entry(count.package_version_id)
+                .or_insert_with(|| vec![0; starts.len()])[index as usize] += count.downloads;
+        }
+    }
+    versions
+}
+
+/// Pair packages with their versions, newest first, and keywords
+fn summarize(
+    packages: Vec<package::Model>,
+    versions: Vec<package_version::Model>,
+    keywords: Vec<package_keyword::Model>,
+) -> Vec<PackageSummary> {
+    let mut versions_by_package: HashMap<i64, Vec<package_version::Model>> = HashMap::new();
+    for version in versions {
+        versions_by_package.entry(version.package_id).or_default().push(version);
+    }
+    let mut keywords_by_package: HashMap<i64, Vec<String>> = HashMap::new();
+    for keyword in keywords {
+        keywords_by_package.entry(keyword.package_id).or_default().push(keyword.keyword);
+    }
+
+    packages
+        .into_iter()
+        .map(|package| {
+            let versions = versions_by_package.remove(&package.id).unwrap_or_default();
+            PackageSummary {
+                downloads: versions.iter().map(|v| v.downloads).sum(),
+                latest: versions.into_iter().max_by_key(|v| v.published_at),
+                keywords: keywords_by_package.remove(&package.id).unwrap_or_default(),
+                package,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sql(query: Option<&str>, sort: PackageSort) -> String {
+        packages_query(query, sort).build(DbBackend::Postgres).to_string()
+    }
+
+    #[test]
+    fn test_packages_query_orders_and_escapes() {
+        assert!(sql(None, PackageSort::Name).ends_with(r#"ORDER BY "packages"."name" ASC"#));
+        assert!(sql(None, PackageSort::Recent).ends_with(r#"ORDER BY "packages"."updated_at" DESC, "packages"."name" ASC"#));
+        assert!(sql(None, PackageSort::Downloads).contains("SUM(package_versions.downloads)"));
+
+        let relevance = sql(Some("http"), PackageSort::Relevance);
+        assert!(relevance.contains(r#""packages"."name" LIKE '%http%'"#), "{}", relevance);
+        assert!(
+            relevance.contains("CASE WHEN packages.name = 'http' THEN 0 WHEN packages.name LIKE 'http%' THEN 1"),
+            "{}",
+            relevance
+        );
+        // Without a query there is nothing to rank by
+        assert_eq!(sql(None, PackageSort::Relevance), sql(None, PackageSort::Name));
+
+        let wildcard = sql(Some("100%_sure"), PackageSort::Name);
+        // Rendered as an escape string, so each backslash shows twice
+        assert!(wildcard.contains(r"LIKE E'%100\\%\\_sure%'"), "{}", wildcard);
+
+        // The exact match is not escaped, so names with `_` still rank first
+        let underscore = sql(Some("my_pkg"), PackageSort::Relevance);
+        assert!(
+            underscore.contains(r"CASE WHEN packages.name = 'my_pkg' THEN 0 WHEN packages.name LIKE E'my\\_pkg%' THEN 1"),
+            "{}",
+            underscore
+        );
+    }
+
+    #[test]
+    fn test_summarize_groups_versions_and_keywords() {
+        let at = |day: u32| {
+            chrono::NaiveDate::from_ymd_opt(2026, 10, day)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+                .and_utc()
+                .fixed_offset()
+        };
+        let package = |id: i64, name: &str| package::Model {
+            id,
+            name: name.to_string(),
+            description: None,
+            repository: None,
+            created_at: at(1),
+            updated_at: at(1),
+        };
+        let version = |id: i64, package_id: i64, version: &str, day: u32, downloads: i64| package_version::Model {
+            id,
+            package_id,
+            version: version.to_string(),
+            description: None,
+            license: None,
+            checksum: String::new(),
+            tarball_s3_key: String::new(),
+            tarball_size: 0,
+            published_at: at(day),
+            downloads,
+            release_notes: None,
+            readme: None,
+            manifest: None,
+        };
+        let keyword = |package_id: i64, keyword: &str| package_keyword::Model {
+            id: 0,
+            package_id,
+            keyword: keyword.to_string(),
+        };
+
+        let summaries = summarize(
+            vec![package(1, "http"), package(2, "empty")],
+            vec![version(10, 1, "1.0.0", 2, 5), version(11, 1, "1.1.0", 3, 7)],
+            vec![keyword(1, "net"), keyword(1, "web")],
+        );
+        assert_eq!(summaries[0].latest.as_ref().map(|v| v.version.as_str()), Some("1.1.0"));
+        assert_eq!(summaries[0].downloads, 12);
+        assert_eq!(summaries[0].keywords, vec!["net", "web"]);
+        assert_eq!(
+            (summaries[1].package.name.as_str(), summaries[1].latest.is_none(), summaries[1].downloads),
+            ("empty", true, 0)
+        );
+    }
+
+    #[test]
+    fn test_downloads_are_bucketed_by_day_and_week() {
+        let day = |day: u32| chrono::NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+        let count = |version: i64, on: u32, downloads: i64| download_count::Model {
+            package_version_id: version,
+            day: day(on),
+            downloads,
+        };
+        // Friday 16 October 2026
+        let today = day(16);
+
+        let daily = bucket_starts(DownloadInterval::Daily, 3, today);
+        assert_eq!(daily, vec![day(14), day(15), day(16)]);
+        let counts = bucket_counts(
+            DownloadInterval::Daily,
+            &daily,
+            vec![count(1, 13, 9), count(1, 14, 2), count(1, 16, 1), count(2, 16, 4)],
+        );
+        assert_eq!(counts[&1], vec![2, 0, 1]);
+        assert_eq!(counts[&2], vec![0, 0, 4]);
+
+        let weekly = bucket_starts(DownloadInterval::Weekly, 2, today);
+        assert_eq!(weekly, vec![day(5), day(12)]);
+        let history = DownloadHistory {
+            interval: DownloadInterval::Weekly,
+            versions: bucket_counts(
+                DownloadInterval::Weekly,
+                &weekly,
+                vec![count(1, 4, 9), count(1, 5, 2), count(1, 11, 3), count(1, 12, 1), count(2, 16, 4)],
+            ),
+            starts: weekly,
+        };
+        assert_eq!(history.versions[&1], vec![5, 1]);
+        assert_eq!(history.totals(), vec![5, 5]);
+        assert!(bucket_starts(DownloadInterval::Weekly, 0, today).is_empty());
+    }
 }
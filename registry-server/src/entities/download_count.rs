@@ -0,0 +1,31 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Downloads of a version on one UTC day
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "download_counts")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub package_version_id: i64,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub day: Date,
+    pub downloads: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::package_version::Entity",
+        from = "Column::PackageVersionId",
+        to = "super::package_version::Column::Id"
+    )]
+    PackageVersion,
+}
+
+impl Related<super::package_version::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PackageVersion.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
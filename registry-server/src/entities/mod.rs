@@ -4,6 +4,8 @@ pub mod package_author;
 pub mod package_keyword;
 pub mod package_dependency;
 pub mod download_stat;
+pub mod download_count;
+pub mod scope;
 
 pub use package::Entity as Package;
 pub use package_version::Entity as PackageVersion;
@@ -11,3 +13,5 @@ pub use package_author::Entity as PackageAuthor;
 pub use package_keyword::Entity as PackageKeyword;
 pub use package_dependency::Entity as PackageDependency;
 pub use download_stat::Entity as DownloadStat;
+pub use download_count::Entity as DownloadCount;
+pub use scope::Entity as Scope;
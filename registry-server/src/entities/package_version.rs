@@ -15,6 +15,11 @@ pub struct Model {
     pub tarball_size: i64,
     pub published_at: DateTimeWithTimeZone,
     pub downloads: i64,
+    pub release_notes: Option<String>,
+    /// README.md of the tarball, read at publish time
+    pub readme: Option<String>,
+    /// lang.toml of the tarball, read at publish time
+    pub manifest: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,311 @@
+//! Storage garbage collection and orphan audit
+//!
+//! Deleting a version removes its tarball before its database row, and a publish
+//! uploads the tarball before creating any row, so a failure halfway through either
+//! leaves storage and the database out of step. The audit compares both sides and
+//! reports tarballs no version references, versions whose tarball is missing and
+//! packages left without a single usable version. Unless it is a dry run, those
+//! are then removed.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::database::Database;
+use crate::entities::{package, package_version};
+use crate::error::RegistryError;
+use crate::storage::{StorageBackend, StoredObject};
+
+/// Anything younger than this may belong to a publish or delete still in progress
+pub const DEFAULT_GRACE_PERIOD_SECS: i64 = 3600;
+
+/// How a garbage collection run behaves
+#[derive(Debug, Clone, Copy)]
+pub struct GcOptions {
+    /// Only report what would be removed
+    pub dry_run: bool,
+    /// Candidates newer than this are left alone
+    pub grace_period: Duration,
+}
+
+/// A tarball in storage that no package version references
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OrphanedObject {
+    pub package: String,
+    pub version: String,
+}
+
+/// A package version whose tarball is missing from storage
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DanglingVersion {
+    pub id: i64,
+    pub package: String,
+    pub version: String,
+}
+
+/// A package with no version left once dangling versions are removed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EmptyPackage {
+    pub id: i64,
+    pub name: String,
+}
+
+/// What an audit found and, outside dry runs, what it removed
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GcReport {
+    pub dry_run: bool,
+    pub orphaned_objects: Vec<OrphanedObject>,
+    pub dangling_versions: Vec<DanglingVersion>,
+    pub empty_packages: Vec<EmptyPackage>,
+    /// Candidates skipped because they are within the grace period or of unknown age
+    pub skipped_recent: usize,
+    /// Objects and rows actually deleted
+    pub removed: usize,
+    pub errors: Vec<String>,
+}
+
+impl GcReport {
+    /// Number of problems found, whether or not they were cleaned up
+    pub fn findings(&self) -> usize {
+        self.orphaned_objects.len() + self.dangling_versions.len() + self.empty_packages.len()
+    }
+}
+
+/// Compare the database with storage and list everything out of step, ignoring
+/// anything modified after `cutoff`
+pub fn plan(
+    packages: &[package::Model],
+    versions: &[package_version::Model],
+    objects: &[StoredObject],
+    cutoff: DateTime<Utc>,
+) -> GcReport {
+    let mut report = GcReport::default();
+
+    let names: HashMap<i64, &str> = packages
+        .iter()
+        .map(|pkg| (pkg.id, pkg.name.as_str()))
+        .collect();
+    let stored: HashSet<(&str, &str)> = objects
+        .iter()
+        .map(|object| (object.package_name.as_str(), object.version.as_str()))
+        .collect();
+    let referenced: HashSet<(&str, &str)> = versions
+        .iter()
+        .filter_map(|v| names.get(&v.package_id).map(|name| (*name, v.version.as_str())))
+        .collect();
+
+    for object in objects {
+        if referenced.contains(&(object.package_name.as_str(), object.version.as_str())) {
+            continue;
+        }
+        match object.last_modified {
+            Some(modified) if modified < cutoff => report.orphaned_objects.push(OrphanedObject {
+                package: object.package_name.clone(),
+                version: object.version.clone(),
+            }),
+            _ => report.skipped_recent += 1,
+        }
+    }
+
+    // Packages keeping at least one version, including versions too recent to judge
+    let mut live_packages = HashSet::new();
+    for v in versions {
+        let Some(name) = names.get(&v.package_id) else {
+            continue;
+        };
+        if stored.contains(&(*name, v.version.as_str())) {
+            live_packages.insert(v.package_id);
+        } else if v.published_at.with_timezone(&Utc) < cutoff {
+            report.dangling_versions.push(DanglingVersion {
+                id: v.id,
+                package: name.to_string(),
+                version: v.version.clone(),
+            });
+        } else {
+            live_packages.insert(v.package_id);
+            report.skipped_recent += 1;
+        }
+    }
+
+    for pkg in packages {
+        if live_packages.contains(&pkg.id) {
+            continue;
+        }
+        if pkg.updated_at.with_timezone(&Utc) < cutoff {
+            report.empty_packages.push(EmptyPackage {
+                id: pkg.id,
+                name: pkg.name.clone(),
+            });
+        } else {
+            report.skipped_recent += 1;
+        }
+    }
+
+    report
+}
+
+/// Audit storage against the database and, unless `options.dry_run`, clean up.
+/// Individual deletions that fail are recorded in the report instead of aborting the run.
+pub async fn run(
+    db: &Database,
+    storage: &(dyn StorageBackend + Send + Sync),
+    options: GcOptions,
+) -> Result<GcReport, RegistryError> {
+    let packages = db.list_packages().await?;
+    let versions = db.list_all_versions().await?;
+    let objects = storage.list_objects().await?;
+
+    let mut report = plan(&packages, &versions, &objects, Utc::now() - options.grace_period);
+    report.dry_run = options.dry_run;
+
+    if !options.dry_run {
+        for object in &report.orphaned_objects {
+            match storage.delete_tarball(&object.package, &object.version).await {
+                Ok(()) => report.removed += 1,
+                Err(e) => report.errors.push(format!("{} v{}: {}", object.package, object.version, e)),
+            }
+        }
+        for version in &report.dangling_versions {
+            match db.delete_package_version(version.id).await {
+                Ok(()) => report.removed += 1,
+                Err(e) => report.errors.push(format!("{} v{}: {}", version.package, version.version, e)),
+            }
+        }
+        for pkg in &report.empty_packages {
+            match db.delete_package(pkg.id).await {
+                Ok(()) => report.removed += 1,
+                Err(e) => report.errors.push(format!("{}: {}", pkg.name, e)),
+            }
+        }
+    }
+
+    info!(
+        "🧹 Storage audit{}: {} findings ({} orphaned objects, {} dangling versions, {} empty packages), {} removed",
+        if options.dry_run { " (dry run)" } else { "" },
+        report.findings(),
+        report.orphaned_objects.len(),
+        report.dangling_versions.len(),
+        report.empty_packages.len(),
+        report.removed
+    );
+    for error in &report.errors {
+        warn!("⚠️  Storage audit: {}", error);
+    }
+
+    Ok(report)
+}
+
+/// Run the audit every `interval` in the background
+pub fn spawn_periodic(
+    db: Database,
+    storage: Arc<dyn StorageBackend + Send + Sync>,
+    interval: std::time::Duration,
+    options: GcOptions,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = run(&db, storage.as_ref(), options).await {
+                warn!("⚠️  Storage audit failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hours_ago: i64) -> DateTime<Utc> {
+        Utc::now() - Duration::hours(hours_ago)
+    }
+
+    fn package(id: i64, name: &str, hours_ago: i64) -> package::Model {
+        package::Model {
+            id,
+            name: name.to_string(),
+            description: None,
+            repository: None,
+            created_at: at(hours_ago).fixed_offset(),
+            updated_at: at(hours_ago).fixed_offset(),
+        }
+    }
+
+    fn version(id: i64, package_id: i64, version: &str, hours_ago: i64) -> package_version::Model {
+        package_version::Model {
+            id,
+            package_id,
+            version: version.to_string(),
+            description: None,
+            license: None,
+            checksum: String::new(),
+            tarball_s3_key: String::new(),
+            tarball_size: 0,
+            published_at: at(hours_ago).fixed_offset(),
+            downloads: 0,
+            release_notes: None,
+            readme: None,
+            manifest: None,
+        }
+    }
+
+    fn object(package_name: &str, version: &str, hours_ago: Option<i64>) -> StoredObject {
+        StoredObject {
+            package_name: package_name.to_string(),
+            version: version.to_string(),
+            last_modified: hours_ago.map(at),
+        }
+    }
+
+    #[test]
+    fn test_plan_finds_orphans_and_dangling_rows() {
+        let packages = vec![package(1, "http", 48), package(2, "json", 48), package(3, "gone", 48)];
+        let versions = vec![
+            version(10, 1, "1.0.0", 48),
+            version(11, 1, "1.1.0", 48),
+            version(20, 2, "0.1.0", 48),
+            version(30, 3, "2.0.0", 48),
+        ];
+        let objects = vec![
+            object("http", "1.0.0", Some(48)),
+            object("http", "0.9.0", Some(48)),
+            object("json", "0.1.0", Some(48)),
+            object("removed", "1.0.0", Some(48)),
+        ];
+
+        let report = plan(&packages, &versions, &objects, at(1));
+        assert_eq!(
+            report.orphaned_objects,
+            vec![
+                OrphanedObject { package: "http".to_string(), version: "0.9.0".to_string() },
+                OrphanedObject { package: "removed".to_string(), version: "1.0.0".to_string() },
+            ]
+        );
+        assert_eq!(
+            report.dangling_versions,
+            vec![
+                DanglingVersion { id: 11, package: "http".to_string(), version: "1.1.0".to_string() },
+                DanglingVersion { id: 30, package: "gone".to_string(), version: "2.0.0".to_string() },
+            ]
+        );
+        assert_eq!(report.empty_packages, vec![EmptyPackage { id: 3, name: "gone".to_string() }]);
+        assert_eq!(report.skipped_recent, 0);
+        assert_eq!(report.findings(), 5);
+    }
+
+    #[test]
+    fn test_plan_skips_recent_and_undated_candidates() {
+        // A publish in progress: the tarball is uploaded and the package row exists,
+        // but the version row has not been written yet
+        let packages = vec![package(1, "fresh", 0), package(2, "slow", 48)];
+        let versions = vec![version(20, 2, "1.0.0", 0)];
+        let objects = vec![object("fresh", "1.0.0", Some(0)), object("mystery", "1.0.0", None)];
+
+        let report = plan(&packages, &versions, &objects, at(1));
+        assert_eq!(report.findings(), 0);
+        assert_eq!(report.skipped_recent, 4);
+    }
+}
@@ -1,33 +1,53 @@
+mod badges;
 mod cloudflare_storage;
 mod database;
 mod entities;
 mod error;
+mod gc;
+mod metrics;
+mod names;
+mod package_files;
 mod storage;
+mod web;
 
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
+    middleware,
     response::{IntoResponse, Json},
     routing::{delete, get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
 use tracing::info;
 use tracing_subscriber;
 
-use database::Database;
+use database::{Database, DownloadInterval, PackageSort, PackageSummary};
 use error::RegistryError;
+use names::PackageName;
 use storage::StorageBackend;
 
 #[derive(Clone)]
 struct AppState {
     db: Database,
     storage: Arc<dyn StorageBackend + Send + Sync>,
+    /// Bearer token for /api/admin endpoints; they are disabled when unset
+    admin_token: Option<String>,
+    gc_grace_period: chrono::Duration,
+    metrics: Arc<metrics::Metrics>,
+    /// Set once a shutdown signal arrives, so readiness fails while requests drain
+    shutting_down: Arc<AtomicBool>,
+    badges: Arc<badges::BadgeCache>,
 }
 
+/// How long a readiness check may take before the dependency counts as down
+const READINESS_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[derive(Debug, Serialize, Deserialize)]
 struct PublishRequest {
     name: String,
@@ -39,6 +59,9 @@ struct PublishRequest {
     keywords: Vec<String>,
     dependencies: std::collections::HashMap<String, String>,
     tarball: Vec<u8>,
+    /// The version's CHANGELOG section, absent from older clients
+    #[serde(default)]
+    release_notes: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -51,10 +74,35 @@ struct PackageInfo {
     total_downloads: i64,
 }
 
+/// A page of `GET /api/packages`
+#[derive(Debug, Serialize)]
+struct PackageListResponse {
+    packages: Vec<PackageListItem>,
+    /// Packages on every page
+    total: u64,
+    limit: u64,
+    offset: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct PackageListItem {
+    name: String,
+    latest_version: Option<String>,
+    description: Option<String>,
+    repository: Option<String>,
+    keywords: Vec<String>,
+    downloads: i64,
+    updated_at: String,
+}
+
+/// A page of `GET /api/search`, with the latest version of each match
 #[derive(Debug, Serialize)]
 struct SearchResponse {
     packages: Vec<SearchPackage>,
-    total: usize,
+    /// Matches on every page
+    total: u64,
+    limit: u64,
+    offset: u64,
 }
 
 #[derive(Debug, Serialize)]
@@ -63,9 +111,48 @@ struct SearchPackage {
     version: String,
     description: Option<String>,
     downloads: i64,
+    /// Downloads over the last `RECENT_DOWNLOAD_DAYS` days
+    recent_downloads: i64,
     updated_at: String,
 }
 
+/// Days `recent_downloads` covers in search results
+const RECENT_DOWNLOAD_DAYS: i64 = 7;
+
+/// `GET /api/packages/:name/readme`: what `lang info` shows of a version
+#[derive(Debug, Serialize)]
+struct ReadmeResponse {
+    name: String,
+    version: String,
+    description: Option<String>,
+    license: Option<String>,
+    readme: Option<String>,
+    /// The version's `lang.toml`
+    manifest: Option<String>,
+}
+
+/// `GET /api/packages/:name/downloads`: downloads per day or week, oldest first
+#[derive(Debug, Serialize)]
+struct DownloadsResponse {
+    name: String,
+    interval: DownloadInterval,
+    /// First day of each bucket; the last one holds today
+    buckets: Vec<chrono::NaiveDate>,
+    /// Downloads per bucket of every version together
+    downloads: Vec<i64>,
+    /// Most recently published first
+    versions: Vec<VersionDownloads>,
+}
+
+#[derive(Debug, Serialize)]
+struct VersionDownloads {
+    version: String,
+    /// Downloads per bucket
+    downloads: Vec<i64>,
+    /// Downloads since the version was published
+    total: i64,
+}
+
 #[derive(Debug, Serialize)]
 struct VersionInfo {
     version: String,
@@ -76,19 +163,85 @@ struct VersionInfo {
     published_at: chrono::DateTime<chrono::FixedOffset>,
     downloads: i64,
     checksum: String,
+    release_notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+    limit: Option<u64>,
+    offset: Option<u64>,
+    sort: Option<PackageSort>,
+}
+
+impl ListQuery {
+    /// Clients that page or sort get a `PackageListResponse`; the others get
+    /// every package with all its versions, as before pages existed
+    fn is_paged(&self) -> bool {
+        self.limit.is_some() || self.offset.is_some() || self.sort.is_some()
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct SearchQuery {
     q: String,
     #[serde(default = "default_limit")]
-    limit: i64,
+    limit: u64,
+    #[serde(default)]
+    offset: u64,
+    #[serde(default = "default_search_sort")]
+    sort: PackageSort,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadmeQuery {
+    /// The latest version unless given
+    version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DownloadsQuery {
+    #[serde(default = "default_download_interval")]
+    interval: DownloadInterval,
+    /// Buckets to return: 30 days or 12 weeks unless given
+    periods: Option<usize>,
+}
+
+fn default_download_interval() -> DownloadInterval {
+    DownloadInterval::Daily
 }
 
-fn default_limit() -> i64 {
+fn default_limit() -> u64 {
     20
 }
 
+fn default_search_sort() -> PackageSort {
+    PackageSort::Relevance
+}
+
+#[derive(Debug, Deserialize)]
+struct GcQuery {
+    #[serde(default = "default_dry_run")]
+    dry_run: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+struct ReadinessResponse {
+    status: &'static str,
+    checks: std::collections::BTreeMap<&'static str, DependencyCheck>,
+}
+
+#[derive(Debug, Serialize)]
+struct DependencyCheck {
+    ok: bool,
+    latency_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize tracing
@@ -144,99 +297,227 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             )))
         };
 
+    // Storage garbage collection
+    let admin_token = std::env::var("ADMIN_TOKEN").ok().filter(|token| !token.is_empty());
+    let gc_grace_period = chrono::Duration::seconds(
+        std::env::var("GC_GRACE_PERIOD_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .unwrap_or(gc::DEFAULT_GRACE_PERIOD_SECS),
+    );
+    if let Some(interval) = std::env::var("GC_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+    {
+        let dry_run = std::env::var("GC_DRY_RUN").is_ok_and(|value| value == "true" || value == "1");
+        info!(
+            "🧹 Storage audit every {}s{}",
+            interval,
+            if dry_run { " (dry run)" } else { "" }
+        );
+        gc::spawn_periodic(
+            db.clone(),
+            storage.clone(),
+            std::time::Duration::from_secs(interval),
+            gc::GcOptions {
+                dry_run,
+                grace_period: gc_grace_period,
+            },
+        );
+    }
+
+    let badge_cache_secs = std::env::var("BADGE_CACHE_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(badges::DEFAULT_CACHE_SECS);
+
     // Create application state
-    let state = Arc::new(AppState { db, storage });
+    let state = Arc::new(AppState {
+        db,
+        storage,
+        admin_token,
+        gc_grace_period,
+        metrics: Arc::new(metrics::Metrics::new()),
+        shutting_down: Arc::new(AtomicBool::new(false)),
+        badges: Arc::new(badges::BadgeCache::new(Duration::from_secs(badge_cache_secs))),
+    });
+
+    let app = router(state.clone());
+
+    // Start the server
+    let port = std::env::var("PORT").unwrap_or_else(|_| "3000".to_string());
+    let addr = format!("0.0.0.0:{}", port);
+    let listener = TcpListener::bind(&addr).await?;
+    info!("🚀 Registry server listening on {}", addr);
 
-    // Build our application with routes
-    let app = Router::new()
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal(state.shutting_down.clone()))
+        .await?;
+    info!("👋 Registry server stopped");
+    Ok(())
+}
+
+/// Every route the registry serves
+fn router(state: Arc<AppState>) -> Router {
+    Router::new()
         .route("/api/packages", get(list_packages))
         .route("/api/packages/:name", get(get_package_info))
+        .route("/api/packages/:name/downloads", get(package_downloads))
+        .route("/api/packages/:name/readme", get(package_readme))
         .route("/api/packages/:name/:version", post(publish_package))
         .route("/api/packages/:name/:version", delete(delete_package))
         .route("/api/download/:name/:version", get(download_package))
         .route("/api/search", get(search_packages))
         .route("/health", get(health_check))
-        .with_state(state);
+        .route("/ready", get(readiness_check))
+        .route("/metrics", get(metrics_endpoint))
+        .route("/api/admin/gc", post(run_gc))
+        // README badges
+        .route("/badge/v/:name", get(badges::version_badge))
+        .route("/badge/d/:name", get(badges::downloads_badge))
+        // Web UI
+        .route("/", get(web::index))
+        .route("/search", get(web::search))
+        .route("/packages/:name", get(web::package_page))
+        .route("/packages/:name/:version", get(web::package_version_page))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            metrics::track_requests,
+        ))
+        .with_state(state)
+}
 
-    // Start the server
-    let port = std::env::var("PORT").unwrap_or_else(|_| "3000".to_string());
-    let addr = format!("0.0.0.0:{}", port);
-    let listener = TcpListener::bind(&addr).await?;
-    info!("🚀 Registry server listening on {}", addr);
+/// Resolve on Ctrl+C or SIGTERM; the server then stops accepting connections
+/// and waits for in-flight requests to finish
+async fn shutdown_signal(shutting_down: Arc<AtomicBool>) {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
 
-    axum::serve(listener, app).await?;
-    Ok(())
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    shutting_down.store(true, Ordering::SeqCst);
+    info!("🛑 Shutdown requested, draining in-flight requests...");
 }
 
+/// Liveness: the process is up and serving requests
 async fn health_check() -> impl IntoResponse {
     (StatusCode::OK, "OK")
 }
 
-async fn list_packages(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<PackageInfo>>, (StatusCode, String)> {
-    let packages = state
-        .db
-        .list_packages()
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+/// Readiness: the database and storage are reachable and the server is not shutting down
+async fn readiness_check(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let mut checks = std::collections::BTreeMap::new();
+    checks.insert("database", timed_check(state.db.ping()).await);
+    checks.insert("storage", timed_check(state.storage.check_access()).await);
+
+    let (status_code, status) = if state.shutting_down.load(Ordering::SeqCst) {
+        (StatusCode::SERVICE_UNAVAILABLE, "shutting_down")
+    } else if checks.values().all(|check| check.ok) {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    };
 
-    let mut result = Vec::new();
-    for pkg in packages {
-        let versions = state
-            .db
-            .get_package_versions(pkg.id)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    (status_code, Json(ReadinessResponse { status, checks }))
+}
 
-        let keywords = state
-            .db
-            .get_keywords(pkg.id)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+async fn timed_check<E: std::fmt::Display>(
+    check: impl std::future::Future<Output = Result<(), E>>,
+) -> DependencyCheck {
+    let started = Instant::now();
+    let error = match tokio::time::timeout(READINESS_TIMEOUT, check).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("timed out after {}s", READINESS_TIMEOUT.as_secs())),
+    };
+    DependencyCheck {
+        ok: error.is_none(),
+        latency_ms: started.elapsed().as_millis(),
+        error,
+    }
+}
+
+async fn metrics_endpoint(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(state.db.pool_stats()),
+    )
+}
 
-        let total_downloads = state
+/// A page of packages with their latest version, `?limit=&offset=&sort=`
+async fn list_packages(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListQuery>,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    if !query.is_paged() {
+        let packages = state
             .db
-            .get_total_downloads(pkg.id)
+            .list_packages()
             .await
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-        let mut version_infos = Vec::new();
-        for v in versions {
-            let authors = state
-                .db
-                .get_authors(v.id)
-                .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-            let dependencies = state
-                .db
-                .get_dependencies(v.id)
-                .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-            version_infos.push(VersionInfo {
-                version: v.version,
-                description: v.description,
-                license: v.license,
-                authors,
-                dependencies,
-                published_at: v.published_at,
-                downloads: v.downloads,
-                checksum: v.checksum,
-            });
+        let mut result = Vec::with_capacity(packages.len());
+        for package in packages {
+            result.push(package_info(&state, package).await?);
         }
-
-        result.push(PackageInfo {
-            name: pkg.name,
-            description: pkg.description,
-            repository: pkg.repository,
-            versions: version_infos,
-            keywords,
-            total_downloads,
-        });
+        return Ok(Json(result).into_response());
     }
 
-    Ok(Json(result))
+    let limit = query.limit.unwrap_or_else(default_limit).min(database::MAX_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0);
+    let page = state
+        .db
+        .packages_page(None, query.sort.unwrap_or(PackageSort::Name), limit, offset)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let packages = page
+        .packages
+        .into_iter()
+        .map(|summary| PackageListItem {
+            updated_at: updated_at(&summary),
+            name: summary.package.name,
+            latest_version: summary.latest.map(|v| v.version),
+            description: summary.package.description,
+            repository: summary.package.repository,
+            keywords: summary.keywords,
+            downloads: summary.downloads,
+        })
+        .collect();
+
+    Ok(Json(PackageListResponse {
+        packages,
+        total: page.total,
+        limit,
+        offset,
+    })
+    .into_response())
+}
+
+/// When a package's latest version was published
+fn updated_at(summary: &PackageSummary) -> String {
+    summary
+        .latest
+        .as_ref()
+        .map_or(summary.package.updated_at, |v| v.published_at)
+        .to_rfc3339()
 }
 
 async fn get_package_info(
@@ -249,7 +530,11 @@ async fn get_package_info(
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
         .ok_or((StatusCode::NOT_FOUND, "Package not found".to_string()))?;
+    Ok(Json(package_info(&state, package).await?))
+}
 
+/// A package with every version, its authors and its dependencies
+async fn package_info(state: &AppState, package: entities::package::Model) -> Result<PackageInfo, (StatusCode, String)> {
     let versions = state
         .db
         .get_package_versions(package.id)
@@ -290,22 +575,24 @@ async fn get_package_info(
             published_at: v.published_at,
             downloads: v.downloads,
             checksum: v.checksum,
+            release_notes: v.release_notes,
         });
     }
 
-    Ok(Json(PackageInfo {
+    Ok(PackageInfo {
         name: package.name,
         description: package.description,
         repository: package.repository,
         versions: version_infos,
         keywords,
         total_downloads,
-    }))
+    })
 }
 
 async fn publish_package(
     State(state): State<Arc<AppState>>,
     Path((name, version)): Path<(String, String)>,
+    headers: HeaderMap,
     Json(req): Json<PublishRequest>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
     info!("📦 Publishing package: {} v{}", name, version);
@@ -319,6 +606,12 @@ async fn publish_package(
         ));
     }
 
+    let package_name = PackageName::parse(&name).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    for dependency in req.dependencies.keys() {
+        PackageName::parse(dependency).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+    authorize_scope(&state, &headers, &package_name, true).await?;
+
     // Calculate checksum
     let checksum = format!("{:x}", sha2::Sha256::digest(&req.tarball));
 
@@ -357,6 +650,21 @@ async fn publish_package(
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
+    let files = package_files::read(&req.tarball);
+    state
+        .db
+        .set_package_files(version_id, files.readme.as_deref(), files.manifest.as_deref())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if let Some(notes) = req.release_notes.as_deref().filter(|notes| !notes.trim().is_empty()) {
+        state
+            .db
+            .set_release_notes(version_id, notes)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
     // Add authors
     state
         .db
@@ -386,6 +694,111 @@ async fn publish_package(
     })))
 }
 
+/// README, manifest, description and license of a version, `?version=`
+async fn package_readme(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(query): Query<ReadmeQuery>,
+) -> Result<Json<ReadmeResponse>, (StatusCode, String)> {
+    let package = state
+        .db
+        .get_package(&name)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Package not found".to_string()))?;
+    let version = match &query.version {
+        Some(version) => state
+            .db
+            .get_package_version(package.id, version)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .ok_or((StatusCode::NOT_FOUND, "Version not found".to_string()))?,
+        None => state
+            .db
+            .get_package_versions(package.id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .into_iter()
+            .next()
+            .ok_or((StatusCode::NOT_FOUND, "Package has no published versions".to_string()))?,
+    };
+
+    // Versions published before the files were stored have them read from their tarball
+    let (readme, manifest) = if version.readme.is_none() && version.manifest.is_none() {
+        let tarball = state
+            .storage
+            .retrieve_tarball(&package.name, &version.version)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Storage error: {}", e)))?;
+        let files = package_files::read(&tarball);
+        (files.readme, files.manifest)
+    } else {
+        (version.readme, version.manifest)
+    };
+
+    Ok(Json(ReadmeResponse {
+        name: package.name,
+        version: version.version,
+        description: version.description.or(package.description),
+        license: version.license,
+        readme,
+        manifest,
+    }))
+}
+
+/// Downloads of a package per day or week, `?interval=daily|weekly&periods=`
+async fn package_downloads(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(query): Query<DownloadsQuery>,
+) -> Result<Json<DownloadsResponse>, (StatusCode, String)> {
+    let package = state
+        .db
+        .get_package(&name)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Package not found".to_string()))?;
+    let versions = state
+        .db
+        .get_package_versions(package.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let periods = query
+        .periods
+        .unwrap_or(match query.interval {
+            DownloadInterval::Daily => 30,
+            DownloadInterval::Weekly => 12,
+        })
+        .min(database::MAX_DOWNLOAD_PERIODS);
+    let mut history = state
+        .db
+        .download_history(versions.iter().map(|v| v.id).collect(), query.interval, periods)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let downloads = history.totals();
+    let versions = versions
+        .into_iter()
+        .map(|v| VersionDownloads {
+            version: v.version,
+            downloads: history
+                .versions
+                .remove(&v.id)
+                .unwrap_or_else(|| vec![0; history.starts.len()]),
+            total: v.downloads,
+        })
+        .collect();
+
+    Ok(Json(DownloadsResponse {
+        name: package.name,
+        interval: history.interval,
+        buckets: history.starts,
+        downloads,
+        versions,
+    }))
+}
+
 async fn download_package(
     State(state): State<Arc<AppState>>,
     Path((name, version)): Path<(String, String)>,
@@ -444,9 +857,13 @@ async fn download_package(
 async fn delete_package(
     State(state): State<Arc<AppState>>,
     Path((name, version)): Path<(String, String)>,
+    headers: HeaderMap,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
     info!("🗑️  Delete request: {} v{}", name, version);
 
+    let package_name = PackageName::parse(&name).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    authorize_scope(&state, &headers, &package_name, false).await?;
+
     // Get package from database
     let package = state
         .db
@@ -490,43 +907,691 @@ async fn delete_package(
     })))
 }
 
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+/// Scoped packages may only be published or deleted with the token that owns
+/// their scope; the admin token may act on any scope. With `claim`, an unowned
+/// scope is given to the caller, so the first publish to a scope claims it.
+async fn authorize_scope(
+    state: &AppState,
+    headers: &HeaderMap,
+    name: &PackageName,
+    claim: bool,
+) -> Result<(), (StatusCode, String)> {
+    let Some(scope) = &name.scope else {
+        return Ok(());
+    };
+    let token = bearer_token(headers).ok_or((
+        StatusCode::UNAUTHORIZED,
+        format!("Packages in @{} require a bearer token", scope),
+    ))?;
+    if state.admin_token.as_deref() == Some(token) {
+        return Ok(());
+    }
+
+    let token_hash = names::token_hash(token);
+    let owner = match state
+        .db
+        .get_scope(scope)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    {
+        Some(owner) => owner,
+        None if claim => {
+            let owner = state
+                .db
+                .claim_scope(scope, &token_hash)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            if owner.owner_token_hash == token_hash {
+                info!("🏷️  Scope @{} claimed", scope);
+            }
+            owner
+        }
+        None => return Err((StatusCode::NOT_FOUND, format!("Scope @{} does not exist", scope))),
+    };
+
+    if owner.owner_token_hash != token_hash {
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!("Scope @{} belongs to another publisher", scope),
+        ));
+    }
+    Ok(())
+}
+
 async fn search_packages(
     State(state): State<Arc<AppState>>,
     Query(query): Query<SearchQuery>,
 ) -> Result<Json<SearchResponse>, (StatusCode, String)> {
     info!("🔍 Search query: {}", query.q);
 
-    let packages = state
+    let limit = query.limit.min(database::MAX_PAGE_SIZE);
+    let page = state
         .db
-        .search_packages(&query.q, query.limit as u64)
+        .packages_page(Some(&query.q), query.sort, limit, query.offset)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let recent = state
+        .db
+        .recent_downloads(page.packages.iter().map(|s| s.package.id).collect(), RECENT_DOWNLOAD_DAYS)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    let mut result = Vec::new();
-    
-    // Flatten: create one SearchPackage entry per version
-    for pkg in packages {
-        let versions = state
-            .db
-            .get_package_versions(pkg.id)
+    let packages = page
+        .packages
+        .into_iter()
+        .map(|summary| SearchPackage {
+            recent_downloads: recent.get(&summary.package.id).copied().unwrap_or(0),
+            updated_at: updated_at(&summary),
+            version: summary.latest.as_ref().map(|v| v.version.clone()).unwrap_or_default(),
+            description: summary
+                .latest
+                .and_then(|v| v.description)
+                .or(summary.package.description),
+            name: summary.package.name,
+            downloads: summary.downloads,
+        })
+        .collect();
+
+    Ok(Json(SearchResponse {
+        packages,
+        total: page.total,
+        limit,
+        offset: query.offset,
+    }))
+}
+
+/// Audit storage against the database; only deletes when called with `?dry_run=false`
+async fn run_gc(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<GcQuery>,
+) -> Result<Json<gc::GcReport>, (StatusCode, String)> {
+    let expected = state
+        .admin_token
+        .as_deref()
+        .ok_or((StatusCode::NOT_FOUND, "Admin endpoints are disabled".to_string()))?;
+    if bearer_token(&headers) != Some(expected) {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid admin token".to_string()));
+    }
+
+    info!("🧹 Storage audit requested (dry_run={})", query.dry_run);
+
+    let report = gc::run(
+        &state.db,
+        state.storage.as_ref(),
+        gc::GcOptions {
+            dry_run: query.dry_run,
+            grace_period: state.gc_grace_period,
+        },
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use entities::{
+        DownloadCount, DownloadStat, Package, PackageAuthor, PackageDependency, PackageKeyword, PackageVersion, Scope,
+    };
+    use sea_orm::{ConnectionTrait, DatabaseConnection, EntityTrait, Schema};
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+    use storage::StoredObject;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tower::ServiceExt;
+
+    /// Tarball contents and modification time, by package name and version
+    type Objects = BTreeMap<(String, String), (Vec<u8>, chrono::DateTime<chrono::Utc>)>;
+
+    /// Tarballs kept in memory, with a switch to make storage unreachable
+    /// and a delay to keep readiness checks in flight
+    #[derive(Default)]
+    struct MemoryStorage {
+        objects: Mutex<Objects>,
+        unavailable: bool,
+        delay: Duration,
+    }
+
+    impl MemoryStorage {
+        fn with_object(self, package_name: &str, version: &str, age: chrono::Duration) -> Self {
+            self.objects.lock().unwrap().insert(
+                (package_name.to_string(), version.to_string()),
+                (Vec::new(), chrono::Utc::now() - age),
+            );
+            self
+        }
+
+        fn contains(&self, package_name: &str, version: &str) -> bool {
+            self.objects
+                .lock()
+                .unwrap()
+                .contains_key(&(package_name.to_string(), version.to_string()))
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl StorageBackend for MemoryStorage {
+        async fn store_tarball(&self, package_name: &str, version: &str, tarball_data: &[u8]) -> Result<String, RegistryError> {
+            self.objects.lock().unwrap().insert(
+                (package_name.to_string(), version.to_string()),
+                (tarball_data.to_vec(), chrono::Utc::now()),
+            );
+            Ok(format!("{}/{}.tar.gz", package_name, version))
+        }
+
+        async fn retrieve_tarball(&self, package_name: &str, version: &str) -> Result<Vec<u8>, RegistryError> {
+            self.objects
+                .lock()
+                .unwrap()
+                .get(&(package_name.to_string(), version.to_string()))
+                .map(|(data, _)| data.clone())
+                .ok_or_else(|| RegistryError::NotFound(format!("{} v{}", package_name, version)))
+        }
+
+        async fn delete_tarball(&self, package_name: &str, version: &str) -> Result<(), RegistryError> {
+            self.objects
+                .lock()
+                .unwrap()
+                .remove(&(package_name.to_string(), version.to_string()))
+                .map(|_| ())
+                .ok_or_else(|| RegistryError::NotFound(format!("{} v{}", package_name, version)))
+        }
+
+        async fn list_versions(&self, package_name: &str) -> Result<Vec<String>, RegistryError> {
+            Ok(self
+                .objects
+                .lock()
+                .unwrap()
+                .keys()
+                .filter(|(name, _)| name == package_name)
+                .map(|(_, version)| version.clone())
+                .collect())
+        }
+
+        async fn list_objects(&self) -> Result<Vec<StoredObject>, RegistryError> {
+            Ok(self
+                .objects
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|((package_name, version), (_, modified))| StoredObject {
+                    package_name: package_name.clone(),
+                    version: version.clone(),
+                    last_modified: Some(*modified),
+                })
+                .collect())
+        }
+
+        async fn check_access(&self) -> Result<(), RegistryError> {
+            tokio::time::sleep(self.delay).await;
+            if self.unavailable {
+                return Err(RegistryError::StorageError("bucket unreachable".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    /// An in-memory SQLite database with the registry's tables
+    async fn test_db() -> DatabaseConnection {
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        async fn create<E: EntityTrait>(db: &DatabaseConnection, entity: E) {
+            let schema = Schema::new(db.get_database_backend());
+            db.execute(db.get_database_backend().build(&schema.create_table_from_entity(entity)))
+                .await
+                .unwrap();
+        }
+        create(&db, Package).await;
+        create(&db, PackageVersion).await;
+        create(&db, PackageAuthor).await;
+        create(&db, PackageKeyword).await;
+        create(&db, PackageDependency).await;
+        create(&db, DownloadStat).await;
+        create(&db, DownloadCount).await;
+        create(&db, Scope).await;
+        db
+    }
+
+    fn test_state(db: DatabaseConnection, storage: Arc<MemoryStorage>) -> Arc<AppState> {
+        Arc::new(AppState {
+            db: Database { db },
+            storage,
+            admin_token: Some("secret".to_string()),
+            gc_grace_period: chrono::Duration::hours(1),
+            metrics: Arc::new(metrics::Metrics::new()),
+            shutting_down: Arc::new(AtomicBool::new(false)),
+            badges: Arc::new(badges::BadgeCache::new(Duration::from_secs(60))),
+        })
+    }
+
+    /// Send a request through the router and return its status and body
+    async fn send(state: &Arc<AppState>, request: Request<Body>) -> (StatusCode, String) {
+        let response = router(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    async fn get(state: &Arc<AppState>, uri: &str) -> (StatusCode, String) {
+        send(state, Request::get(uri).body(Body::empty()).unwrap()).await
+    }
+
+    /// Add a package version row, without its tarball
+    async fn add_version(db: &Database, name: &str, version: &str) -> i64 {
+        let package_id = db.upsert_package(name, None, None).await.unwrap();
+        db.create_package_version(package_id, version, None, None, "", "", 0)
             .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_readiness_checks_database_and_storage() {
+        let storage = Arc::new(MemoryStorage::default());
+        let state = test_state(test_db().await, storage);
+        let (status, body) = get(&state, "/ready").await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["status"], "ready");
+        assert_eq!(body["checks"]["database"]["ok"], true);
+        assert_eq!(body["checks"]["storage"]["ok"], true);
+
+        let storage = Arc::new(MemoryStorage { unavailable: true, ..Default::default() });
+        let state = test_state(DatabaseConnection::Disconnected, storage);
+        let (status, body) = get(&state, "/ready").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["status"], "unavailable");
+        assert_eq!(body["checks"]["database"]["ok"], false);
+        assert_eq!(body["checks"]["storage"]["error"], "Storage error: bucket unreachable");
+
+        // Liveness does not depend on either
+        assert_eq!(get(&state, "/health").await, (StatusCode::OK, "OK".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_routed_requests() {
+        let state = test_state(test_db().await, Arc::new(MemoryStorage::default()));
+        get(&state, "/health").await;
+        get(&state, "/health").await;
+        let (status, body) = get(&state, "/metrics").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(
+            body.lines()
+                .any(|line| line == "registry_http_requests_total{method=\"GET\",route=\"/health\",status=\"200\"} 2"),
+            "{}",
+            body
+        );
+        // The metrics request itself is still in flight while it renders
+        assert!(body.lines().any(|line| line == "registry_http_requests_in_flight 1"), "{}", body);
+        // Only Postgres pools are reported
+        assert!(!body.contains("registry_db_pool"));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_fails_readiness_and_drains_in_flight_requests() {
+        let storage = Arc::new(MemoryStorage { delay: Duration::from_millis(300), ..Default::default() });
+        let state = test_state(test_db().await, storage);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let (shutdown, signal) = tokio::sync::oneshot::channel::<()>();
+        let shutting_down = state.shutting_down.clone();
+        let app = router(state.clone());
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app)
+                .with_graceful_shutdown(async move {
+                    let _ = signal.await;
+                    shutting_down.store(true, Ordering::SeqCst);
+                })
+                .await
+        });
+
+        // A readiness check that is still waiting on storage when shutdown starts
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /ready HTTP/1.1\r\nHost: registry\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        shutdown.send(()).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 503"), "{}", response);
+        assert!(response.contains("\"status\":\"shutting_down\""), "{}", response);
 
-        // Add each version as a separate search result
-        for v in versions {
-            result.push(SearchPackage {
-                name: pkg.name.clone(),
-                version: v.version,
-                description: v.description.or(pkg.description.clone()),
-                downloads: v.downloads,
-                updated_at: v.published_at.to_rfc3339(),
-            });
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("server should stop once drained")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_gc_endpoint_reports_then_removes_orphans() {
+        let storage = Arc::new(
+            MemoryStorage::default()
+                .with_object("http", "1.0.0", chrono::Duration::hours(2))
+                .with_object("http", "0.9.0", chrono::Duration::hours(2)),
+        );
+        let state = test_state(test_db().await, storage.clone());
+        // Rows written by this test are older than the audit that follows
+        let state = Arc::new(AppState {
+            gc_grace_period: chrono::Duration::zero(),
+            ..(*state).clone()
+        });
+        add_version(&state.db, "http", "1.0.0").await;
+        add_version(&state.db, "http", "1.1.0").await;
+        add_version(&state.db, "gone", "2.0.0").await;
+
+        let gc = |query: &str, token: Option<&str>| {
+            let mut request = Request::post(format!("/api/admin/gc{}", query));
+            if let Some(token) = token {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+            }
+            request.body(Body::empty()).unwrap()
+        };
+        assert_eq!(send(&state, gc("", None)).await.0, StatusCode::UNAUTHORIZED);
+        assert_eq!(send(&state, gc("", Some("wrong"))).await.0, StatusCode::UNAUTHORIZED);
+
+        // Dry run by default
+        let (status, body) = send(&state, gc("", Some("secret"))).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let report: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(report["dry_run"], true);
+        assert_eq!(report["orphaned_objects"], serde_json::json!([{ "package": "http", "version": "0.9.0" }]));
+        let dangling: Vec<_> = report["dangling_versions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| format!("{} {}", v["package"].as_str().unwrap(), v["version"].as_str().unwrap()))
+            .collect();
+        assert_eq!(dangling, vec!["http 1.1.0", "gone 2.0.0"]);
+        assert_eq!(report["empty_packages"][0]["name"], "gone");
+        assert_eq!(report["removed"], 0);
+        assert!(storage.contains("http", "0.9.0"));
+
+        let (status, body) = send(&state, gc("?dry_run=false", Some("secret"))).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let report: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(report["removed"], 4, "{}", body);
+        assert_eq!(report["errors"], serde_json::json!([]));
+        assert!(!storage.contains("http", "0.9.0"));
+        assert!(storage.contains("http", "1.0.0"));
+        let versions: Vec<_> = state.db.list_all_versions().await.unwrap().into_iter().map(|v| v.version).collect();
+        assert_eq!(versions, vec!["1.0.0"]);
+        let packages: Vec<_> = state.db.list_packages().await.unwrap().into_iter().map(|p| p.name).collect();
+        assert_eq!(packages, vec!["http"]);
+
+        // Nothing is left to find
+        let (_, body) = send(&state, gc("?dry_run=false", Some("secret"))).await;
+        let report: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!((report["removed"].as_u64(), report["skipped_recent"].as_u64()), (Some(0), Some(0)), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_gc_endpoint_is_disabled_without_an_admin_token() {
+        let state = test_state(test_db().await, Arc::new(MemoryStorage::default()));
+        let state = Arc::new(AppState {
+            admin_token: None,
+            ..(*state).clone()
+        });
+        let request = Request::post("/api/admin/gc")
+            .header(header::AUTHORIZATION, "Bearer secret")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            send(&state, request).await,
+            (StatusCode::NOT_FOUND, "Admin endpoints are disabled".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_badges_show_the_latest_version_and_downloads() {
+        let storage = Arc::new(MemoryStorage::default().with_object("json", "1.0.0", chrono::Duration::zero()));
+        let state = test_state(test_db().await, storage);
+        add_version(&state.db, "json", "1.0.0").await;
+        for _ in 0..2 {
+            assert_eq!(get(&state, "/api/download/json/1.0.0").await.0, StatusCode::OK);
         }
+
+        let response = router(state.clone())
+            .oneshot(Request::get("/badge/v/json.svg").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/svg+xml;charset=utf-8");
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "max-age=60");
+        let svg = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&svg).contains("<title>version: v1.0.0</title>"));
+
+        let (status, svg) = get(&state, "/badge/d/json").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(svg.contains("<title>downloads: 2</title>"), "{}", svg);
+
+        // Badges are served from the cache until it expires
+        add_version(&state.db, "json", "1.1.0-beta").await;
+        assert!(get(&state, "/badge/v/json").await.1.contains("<title>version: v1.0.0</title>"));
+        let fresh = Arc::new(AppState {
+            badges: Arc::new(badges::BadgeCache::new(Duration::ZERO)),
+            ..(*state).clone()
+        });
+        let (_, svg) = get(&fresh, "/badge/v/json").await;
+        assert!(svg.contains("<title>version: v1.1.0-beta</title>"), "{}", svg);
+        assert!(svg.contains("fill=\"#fe7d37\""), "prereleases are orange: {}", svg);
+
+        let (status, svg) = get(&state, "/badge/v/missing").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(svg.contains("<title>version: package not found</title>"));
     }
 
-    let total = result.len();
-    Ok(Json(SearchResponse {
-        packages: result,
-        total,
-    }))
+    #[tokio::test]
+    async fn test_list_and_search_are_paged_and_sorted() {
+        let storage = Arc::new(
+            MemoryStorage::default()
+                .with_object("http", "1.0.0", chrono::Duration::zero())
+                .with_object("http-client", "0.1.0", chrono::Duration::zero()),
+        );
+        let state = test_state(test_db().await, storage);
+        add_version(&state.db, "yaml", "1.0.0").await;
+        add_version(&state.db, "http-client", "0.1.0").await;
+        add_version(&state.db, "http", "1.0.0").await;
+        let json = state.db.upsert_package("json", Some("Parser for http APIs"), None).await.unwrap();
+        state.db.create_package_version(json, "2.0.0", None, None, "", "", 0).await.unwrap();
+        state.db.add_keywords(json, &["parser".to_string()]).await.unwrap();
+        for path in ["/api/download/http/1.0.0", "/api/download/http/1.0.0", "/api/download/http-client/0.1.0"] {
+            assert_eq!(get(&state, path).await.0, StatusCode::OK);
+        }
+
+        let page = |uri: &str| {
+            let state = state.clone();
+            let uri = uri.to_string();
+            async move {
+                let (status, body) = get(&state, &uri).await;
+                assert_eq!(status, StatusCode::OK, "{}: {}", uri, body);
+                let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+                let names: Vec<String> = body["packages"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|package| package["name"].as_str().unwrap().to_string())
+                    .collect();
+                (names, body)
+            }
+        };
+
+        // Without paging parameters the list keeps its original shape
+        let (status, body) = get(&state, "/api/packages").await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let all: serde_json::Value = serde_json::from_str(&body).unwrap();
+        let names: Vec<&str> = all.as_array().unwrap().iter().map(|p| p["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["http", "http-client", "json", "yaml"]);
+        assert_eq!(all[0]["total_downloads"], 2);
+        assert_eq!(all[2]["versions"][0]["version"], "2.0.0");
+        assert_eq!(page("/api/packages?sort=name").await.1["limit"], 20);
+
+        let (names, body) = page("/api/packages?limit=2").await;
+        assert_eq!(names, ["http", "http-client"]);
+        assert_eq!((body["total"].as_u64(), body["limit"].as_u64(), body["offset"].as_u64()), (Some(4), Some(2), Some(0)));
+        let (names, body) = page("/api/packages?limit=2&offset=2").await;
+        assert_eq!(names, ["json", "yaml"]);
+        assert_eq!(body["packages"][0]["keywords"], serde_json::json!(["parser"]));
+        assert_eq!(body["packages"][0]["latest_version"], "2.0.0");
+        assert_eq!(page("/api/packages?limit=1000").await.1["limit"], 100);
+
+        // Downloads break ties by name
+        assert_eq!(page("/api/packages?sort=downloads").await.0, ["http", "http-client", "json", "yaml"]);
+        assert_eq!(page("/api/packages?sort=recent").await.0, ["json", "http", "http-client", "yaml"]);
+        assert_eq!(get(&state, "/api/packages?sort=stars").await.0, StatusCode::BAD_REQUEST);
+
+        // The exact name first, then names starting with the query, then descriptions
+        let (names, body) = page("/api/search?q=http").await;
+        assert_eq!(names, ["http", "http-client", "json"]);
+        assert_eq!(body["total"], 3);
+        assert_eq!(body["packages"][0]["downloads"], 2);
+        assert_eq!(body["packages"][0]["recent_downloads"], 2);
+        assert_eq!(page("/api/search?q=http&limit=1&offset=1").await.0, ["http-client"]);
+        assert_eq!(page("/api/search?q=http&sort=name").await.0, ["http", "http-client", "json"]);
+        assert!(page("/api/search?q=toml").await.0.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_download_history_is_bucketed_per_version() {
+        let storage = Arc::new(
+            MemoryStorage::default()
+                .with_object("http", "1.0.0", chrono::Duration::zero())
+                .with_object("http", "1.1.0", chrono::Duration::zero()),
+        );
+        let state = test_state(test_db().await, storage);
+        let old = add_version(&state.db, "http", "1.0.0").await;
+        add_version(&state.db, "http", "1.1.0").await;
+        for path in ["/api/download/http/1.0.0", "/api/download/http/1.0.0", "/api/download/http/1.1.0"] {
+            assert_eq!(get(&state, path).await.0, StatusCode::OK);
+        }
+        // Downloads from ten days ago fall outside the last three days but inside the last three weeks
+        let today = chrono::Utc::now().date_naive();
+        DownloadCount::insert(entities::download_count::ActiveModel {
+            package_version_id: sea_orm::Set(old),
+            day: sea_orm::Set(today - chrono::Duration::days(10)),
+            downloads: sea_orm::Set(5),
+        })
+        .exec(&state.db.db)
+        .await
+        .unwrap();
+
+        let history = |uri: &'static str| {
+            let state = state.clone();
+            async move {
+                let (status, body) = get(&state, uri).await;
+                assert_eq!(status, StatusCode::OK, "{}", body);
+                serde_json::from_str::<serde_json::Value>(&body).unwrap()
+            }
+        };
+        let version = |body: &serde_json::Value, version: &str| {
+            body["versions"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|v| v["version"] == version)
+                .cloned()
+                .unwrap()
+        };
+
+        let daily = history("/api/packages/http/downloads?periods=3").await;
+        assert_eq!(daily["interval"], "daily");
+        assert_eq!(daily["buckets"].as_array().unwrap().len(), 3);
+        assert_eq!(daily["buckets"][2], today.to_string());
+        assert_eq!(daily["downloads"], serde_json::json!([0, 0, 3]));
+        assert_eq!(version(&daily, "1.0.0")["downloads"], serde_json::json!([0, 0, 2]));
+        assert_eq!(version(&daily, "1.0.0")["total"], 2);
+        assert_eq!(version(&daily, "1.1.0")["downloads"], serde_json::json!([0, 0, 1]));
+        assert_eq!(version(&daily, "1.1.0")["total"], 1);
+
+        let weekly = history("/api/packages/http/downloads?interval=weekly&periods=3").await;
+        assert_eq!(weekly["interval"], "weekly");
+        let buckets: Vec<chrono::NaiveDate> = serde_json::from_value(weekly["buckets"].clone()).unwrap();
+        assert_eq!(buckets.len(), 3);
+        assert!(buckets.iter().all(|start| chrono::Datelike::weekday(start) == chrono::Weekday::Mon));
+        assert!(buckets[2] <= today && today - buckets[2] < chrono::Duration::days(7));
+        let downloads: Vec<i64> = serde_json::from_value(weekly["downloads"].clone()).unwrap();
+        assert_eq!(downloads.iter().sum::<i64>(), 8);
+        assert!(downloads[2] >= 3);
+        let old_weekly: Vec<i64> = serde_json::from_value(version(&weekly, "1.0.0")["downloads"].clone()).unwrap();
+        assert_eq!(old_weekly.iter().sum::<i64>(), 7);
+
+        assert_eq!(get(&state, "/api/packages/http/downloads?interval=hourly").await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(get(&state, "/api/packages/missing/downloads").await.0, StatusCode::NOT_FOUND);
+    }
+
+    /// Publish a version whose tarball holds the given files
+    async fn publish(state: &Arc<AppState>, name: &str, version: &str, files: &[(&str, &str)]) -> (StatusCode, String) {
+        let body = serde_json::json!({
+            "name": name,
+            "version": version,
+            "description": "Fast JSON",
+            "license": "MIT",
+            "repository": null,
+            "authors": [],
+            "keywords": [],
+            "dependencies": {},
+            "tarball": package_files::tests::tarball(files),
+        });
+        let request = Request::post(format!("/api/packages/{}/{}", name, version))
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        send(state, request).await
+    }
+
+    #[tokio::test]
+    async fn test_readme_comes_from_the_published_tarball() {
+        let state = test_state(test_db().await, Arc::new(MemoryStorage::default()));
+        let manifest = "[package]\nname = \"json\"\n";
+        let (status, body) = publish(&state, "json", "1.0.0", &[("README.md", "# json 1.0"), ("lang.toml", manifest)]).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let (status, body) = publish(&state, "json", "1.1.0", &[("README.md", "# json 1.1")]).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+
+        let readme = |uri: &'static str| {
+            let state = state.clone();
+            async move {
+                let (status, body) = get(&state, uri).await;
+                assert_eq!(status, StatusCode::OK, "{}", body);
+                serde_json::from_str::<serde_json::Value>(&body).unwrap()
+            }
+        };
+        let latest = readme("/api/packages/json/readme").await;
+        assert_eq!(latest["version"], "1.1.0");
+        assert_eq!(latest["readme"], "# json 1.1");
+        assert_eq!(latest["manifest"], serde_json::Value::Null);
+        assert_eq!(latest["description"], "Fast JSON");
+        assert_eq!(latest["license"], "MIT");
+        let first = readme("/api/packages/json/readme?version=1.0.0").await;
+        assert_eq!(first["readme"], "# json 1.0");
+        assert_eq!(first["manifest"], manifest);
+
+        // Versions stored before publishing kept the files are read from their tarball
+        add_version(&state.db, "legacy", "0.1.0").await;
+        state
+            .storage
+            .store_tarball("legacy", "0.1.0", &package_files::tests::tarball(&[("readme.md", "# legacy")]))
+            .await
+            .unwrap();
+        assert_eq!(readme("/api/packages/legacy/readme").await["readme"], "# legacy");
+
+        assert_eq!(get(&state, "/api/packages/json/readme?version=9.9.9").await.0, StatusCode::NOT_FOUND);
+        assert_eq!(get(&state, "/api/packages/missing/readme").await.0, StatusCode::NOT_FOUND);
+    }
 }
@@ -0,0 +1,239 @@
+//! Request metrics in the Prometheus text exposition format
+//!
+//! Every routed request is timed by [`track_requests`]. Publish and download
+//! counters are derived from the same samples, so handlers need no bookkeeping.
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::database::PoolStats;
+use crate::AppState;
+
+/// Upper bounds of the latency histogram buckets, in seconds
+const LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+const PUBLISH_ROUTE: &str = "/api/packages/:name/:version";
+const DOWNLOAD_ROUTE: &str = "/api/download/:name/:version";
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct RequestKey {
+    method: String,
+    route: String,
+    status: u16,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Observations per bucket, not cumulative
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(index) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[index] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Outcomes {
+    success: u64,
+    failure: u64,
+}
+
+impl Outcomes {
+    fn record(&mut self, status: u16) {
+        if (200..300).contains(&status) {
+            self.success += 1;
+        } else {
+            self.failure += 1;
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Samples {
+    requests: BTreeMap<RequestKey, Histogram>,
+    publishes: Outcomes,
+    downloads: Outcomes,
+}
+
+/// Metrics collected since the server started
+#[derive(Debug, Default)]
+pub struct Metrics {
+    samples: Mutex<Samples>,
+    in_flight: AtomicI64,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a finished request against the route pattern it matched
+    pub fn observe_request(&self, method: &Method, route: &str, status: u16, seconds: f64) {
+        let mut samples = self.samples.lock().unwrap();
+        samples
+            .requests
+            .entry(RequestKey {
+                method: method.to_string(),
+                route: route.to_string(),
+                status,
+            })
+            .or_default()
+            .observe(seconds);
+
+        match (method, route) {
+            (&Method::POST, PUBLISH_ROUTE) => samples.publishes.record(status),
+            (&Method::GET, DOWNLOAD_ROUTE) => samples.downloads.record(status),
+            _ => {}
+        }
+    }
+
+    /// Render every metric, plus the database pool when its stats are known
+    pub fn render(&self, pool: Option<PoolStats>) -> String {
+        let samples = self.samples.lock().unwrap();
+        let mut out = String::new();
+
+        header(&mut out, "registry_http_requests_total", "HTTP requests handled, by route and status", "counter");
+        for (key, histogram) in &samples.requests {
+            let _ = writeln!(out, "registry_http_requests_total{{{}}} {}", request_labels(key), histogram.count);
+        }
+
+        header(&mut out, "registry_http_request_duration_seconds", "HTTP request latency", "histogram");
+        for (key, histogram) in &samples.requests {
+            let labels = request_labels(key);
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "registry_http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "registry_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, histogram.count
+            );
+            let _ = writeln!(out, "registry_http_request_duration_seconds_sum{{{}}} {}", labels, histogram.sum);
+            let _ = writeln!(out, "registry_http_request_duration_seconds_count{{{}}} {}", labels, histogram.count);
+        }
+
+        header(&mut out, "registry_http_requests_in_flight", "HTTP requests currently being handled", "gauge");
+        let _ = writeln!(out, "registry_http_requests_in_flight {}", self.in_flight.load(Ordering::Relaxed));
+
+        for (name, help, outcomes) in [
+            ("registry_publishes_total", "Package publish attempts", &samples.publishes),
+            ("registry_downloads_total", "Package download attempts", &samples.downloads),
+        ] {
+            header(&mut out, name, help, "counter");
+            let _ = writeln!(out, "{}{{result=\"success\"}} {}", name, outcomes.success);
+            let _ = writeln!(out, "{}{{result=\"failure\"}} {}", name, outcomes.failure);
+        }
+
+        if let Some(pool) = pool {
+            header(&mut out, "registry_db_pool_connections", "Database pool connections, by state", "gauge");
+            let _ = writeln!(out, "registry_db_pool_connections{{state=\"idle\"}} {}", pool.idle);
+            let _ = writeln!(out, "registry_db_pool_connections{{state=\"in_use\"}} {}", pool.open.saturating_sub(pool.idle));
+            header(&mut out, "registry_db_pool_max_connections", "Database pool size limit", "gauge");
+            let _ = writeln!(out, "registry_db_pool_max_connections {}", pool.max);
+        }
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn request_labels(key: &RequestKey) -> String {
+    format!(
+        "method=\"{}\",route=\"{}\",status=\"{}\"",
+        escape_label(&key.method),
+        escape_label(&key.route),
+        key.status
+    )
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Middleware timing each routed request; install with `Router::route_layer`
+/// so the matched route pattern is known and label cardinality stays bounded
+pub async fn track_requests(
+    State(state): State<Arc<AppState>>,
+    matched: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    let route = matched.map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+
+    state.metrics.in_flight.fetch_add(1, Ordering::Relaxed);
+    let started = Instant::now();
+    let response = next.run(request).await;
+    state.metrics.in_flight.fetch_sub(1, Ordering::Relaxed);
+
+    state.metrics.observe_request(
+        &method,
+        &route,
+        response.status().as_u16(),
+        started.elapsed().as_secs_f64(),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_histograms_and_counters() {
+        let metrics = Metrics::new();
+        metrics.observe_request(&Method::GET, "/health", 200, 0.002);
+        metrics.observe_request(&Method::GET, "/health", 200, 0.3);
+        metrics.observe_request(&Method::POST, PUBLISH_ROUTE, 200, 1.5);
+        metrics.observe_request(&Method::POST, PUBLISH_ROUTE, 400, 0.01);
+        metrics.observe_request(&Method::GET, DOWNLOAD_ROUTE, 404, 0.01);
+
+        let text = metrics.render(Some(PoolStats { open: 5, idle: 3, max: 10 }));
+        for line in [
+            "# TYPE registry_http_request_duration_seconds histogram",
+            "registry_http_requests_total{method=\"GET\",route=\"/health\",status=\"200\"} 2",
+            "registry_http_request_duration_seconds_bucket{method=\"GET\",route=\"/health\",status=\"200\",le=\"0.005\"} 1",
+            "registry_http_request_duration_seconds_bucket{method=\"GET\",route=\"/health\",status=\"200\",le=\"0.25\"} 1",
+            "registry_http_request_duration_seconds_bucket{method=\"GET\",route=\"/health\",status=\"200\",le=\"0.5\"} 2",
+            "registry_http_request_duration_seconds_bucket{method=\"GET\",route=\"/health\",status=\"200\",le=\"+Inf\"} 2",
+            "registry_http_request_duration_seconds_count{method=\"GET\",route=\"/health\",status=\"200\"} 2",
+            "registry_http_requests_in_flight 0",
+            "registry_publishes_total{result=\"success\"} 1",
+            "registry_publishes_total{result=\"failure\"} 1",
+            "registry_downloads_total{result=\"success\"} 0",
+            "registry_downloads_total{result=\"failure\"} 1",
+            "registry_db_pool_connections{state=\"in_use\"} 2",
+            "registry_db_pool_max_connections 10",
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {:?} in\n{}", line, text);
+        }
+        assert!(!metrics.render(None).contains("registry_db_pool"));
+    }
+}
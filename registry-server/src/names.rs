@@ -0,0 +1,79 @@
+//! Package name validation and scopes
+//!
+//! Names are either plain (`http-utils`) or scoped to an organisation
+//! (`@acme/http-utils`). Clients percent-encode the `/` of a scoped name so it
+//! travels as a single path segment; storage keys keep it, which puts scoped
+//! tarballs under `packages/@acme/http-utils/`.
+
+/// Longest accepted scope or package segment
+const MAX_SEGMENT_LEN: usize = 64;
+
+/// A validated package name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageName {
+    /// Organisation owning the package, without the leading `@`
+    pub scope: Option<String>,
+    pub name: String,
+}
+
+impl PackageName {
+    pub fn parse(full: &str) -> Result<Self, String> {
+        let (scope, name) = match full.strip_prefix('@') {
+            Some(scoped) => {
+                let (scope, name) = scoped
+                    .split_once('/')
+                    .ok_or_else(|| format!("Invalid package name '{}': expected @scope/name", full))?;
+                validate_segment(full, scope)?;
+                if scope.chars().any(|c| c.is_ascii_uppercase()) {
+                    return Err(format!("Invalid package name '{}': scopes must be lowercase", full));
+                }
+                (Some(scope.to_string()), name)
+            }
+            None => (None, full),
+        };
+        validate_segment(full, name)?;
+
+        Ok(Self {
+            scope,
+            name: name.to_string(),
+        })
+    }
+}
+
+fn validate_segment(full: &str, segment: &str) -> Result<(), String> {
+    let valid = !segment.is_empty()
+        && segment.len() <= MAX_SEGMENT_LEN
+        && segment.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && segment.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid package name '{}': each part must start with a letter or digit and contain only letters, digits, '-' and '_' (at most {} characters)",
+            full, MAX_SEGMENT_LEN
+        ))
+    }
+}
+
+/// Hash of a publish token as stored against the scopes it owns
+pub fn token_hash(token: &str) -> String {
+    use sha2::Digest;
+    format!("{:x}", sha2::Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_package_names() {
+        assert_eq!(
+            PackageName::parse("@acme/http-utils").unwrap(),
+            PackageName { scope: Some("acme".to_string()), name: "http-utils".to_string() }
+        );
+        assert_eq!(PackageName::parse("json").unwrap().scope, None);
+        for name in ["", "@acme", "@/x", "@Acme/x", "@acme/x/y", "acme/x", "..", "@acme/..", "a b"] {
+            assert!(PackageName::parse(name).is_err(), "{:?} should be rejected", name);
+        }
+    }
+}
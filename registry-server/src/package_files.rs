@@ -0,0 +1,87 @@
+//! Files read from published package tarballs
+//!
+//! The README and `lang.toml` of a version are read once, when it is
+//! published, and stored with it. Versions published before that have them
+//! read from the tarball when they are asked for.
+
+use std::io::Read;
+
+/// Text files of a package tarball
+#[derive(Debug, Default, PartialEq)]
+pub struct PackageFiles {
+    pub readme: Option<String>,
+    /// The package's `lang.toml`
+    pub manifest: Option<String>,
+    /// Path and content of each `.bu` source
+    pub sources: Vec<(String, String)>,
+}
+
+/// Read a gzipped package tarball. An unreadable tarball yields no files,
+/// and files that aren't UTF-8 text are skipped.
+pub fn read(tarball: &[u8]) -> PackageFiles {
+    let mut files = PackageFiles::default();
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(tarball));
+    let Ok(entries) = archive.entries() else {
+        return files;
+    };
+
+    for entry in entries.flatten() {
+        let mut entry = entry;
+        let Ok(path) = entry.path().map(|p| p.to_string_lossy().trim_start_matches("./").to_string()) else {
+            continue;
+        };
+        let mut content = String::new();
+        if entry.read_to_string(&mut content).is_err() {
+            continue;
+        }
+
+        if path.eq_ignore_ascii_case("README.md") {
+            files.readme = Some(content);
+        } else if path == "lang.toml" {
+            files.manifest = Some(content);
+        } else if path.ends_with(".bu") {
+            files.sources.push((path, content));
+        }
+    }
+
+    files.sources.sort();
+    files
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn tarball(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default()));
+        for (path, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, content.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn test_read_package_files() {
+        let files = read(&tarball(&[
+            ("src/util.bu", "func util() {}"),
+            ("./lang.toml", "[package]\nname = \"json\"\n"),
+            ("readme.md", "# json"),
+            ("src/main.bu", "func main() {}"),
+            ("examples/lang.toml", "ignored"),
+        ]));
+        assert_eq!(files.readme.as_deref(), Some("# json"));
+        assert_eq!(files.manifest.as_deref(), Some("[package]\nname = \"json\"\n"));
+        assert_eq!(
+            files.sources,
+            vec![
+                ("src/main.bu".to_string(), "func main() {}".to_string()),
+                ("src/util.bu".to_string(), "func util() {}".to_string()),
+            ]
+        );
+        assert_eq!(read(b"not a tarball"), PackageFiles::default());
+    }
+}
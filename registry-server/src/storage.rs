@@ -1,9 +1,19 @@
 //! Storage abstraction for package tarballs
 
 use crate::error::RegistryError;
+use chrono::{DateTime, Utc};
 use std::path::PathBuf;
 use tokio::fs;
 
+/// A tarball found in storage, as listed by [`StorageBackend::list_objects`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredObject {
+    pub package_name: String,
+    pub version: String,
+    /// When the object was last written, if the backend reports it
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
 /// Storage backend trait for different storage implementations
 #[async_trait::async_trait]
 pub trait StorageBackend: Send + Sync {
@@ -30,6 +40,12 @@ pub trait StorageBackend: Send + Sync {
         &self,
         package_name: &str,
     ) -> Result<Vec<String>, RegistryError>;
+
+    /// List every package tarball in storage, across all packages
+    async fn list_objects(&self) -> Result<Vec<StoredObject>, RegistryError>;
+
+    /// Check that storage is reachable, for readiness probes
+    async fn check_access(&self) -> Result<(), RegistryError>;
 }
 
 /// Local filesystem storage implementation
@@ -117,4 +133,86 @@ impl StorageBackend for LocalStorage {
 
         Ok(versions)
     }
+
+    async fn list_objects(&self) -> Result<Vec<StoredObject>, RegistryError> {
+        let packages_dir = self.base_path.join("packages");
+
+        if !packages_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut packages = fs::read_dir(&packages_dir).await
+            .map_err(|e| RegistryError::StorageError(format!("Failed to read directory: {}", e)))?;
+
+        let mut objects = Vec::new();
+        while let Some(package_entry) = packages.next_entry().await
+            .map_err(|e| RegistryError::StorageError(format!("Failed to read entry: {}", e)))? {
+
+            let package_name = match package_entry.file_name().to_str() {
+                Some(name) if package_entry.path().is_dir() => name.to_string(),
+                _ => continue,
+            };
+
+            // Scoped packages live one level deeper, under packages/@scope/name
+            if package_name.starts_with('@') {
+                let mut scoped = fs::read_dir(package_entry.path()).await
+                    .map_err(|e| RegistryError::StorageError(format!("Failed to read directory: {}", e)))?;
+                while let Some(entry) = scoped.next_entry().await
+                    .map_err(|e| RegistryError::StorageError(format!("Failed to read entry: {}", e)))? {
+
+                    if let Some(name) = entry.file_name().to_str().filter(|_| entry.path().is_dir()) {
+                        let scoped_name = format!("{}/{}", package_name, name);
+                        list_tarballs(&entry.path(), &scoped_name, &mut objects).await?;
+                    }
+                }
+            } else {
+                list_tarballs(&package_entry.path(), &package_name, &mut objects).await?;
+            }
+        }
+
+        Ok(objects)
+    }
+
+    async fn check_access(&self) -> Result<(), RegistryError> {
+        let packages_dir = self.base_path.join("packages");
+        fs::create_dir_all(&packages_dir).await
+            .map_err(|e| RegistryError::StorageError(format!("Failed to create directory: {}", e)))?;
+
+        let metadata = fs::metadata(&packages_dir).await
+            .map_err(|e| RegistryError::StorageError(format!("Failed to read metadata: {}", e)))?;
+        if metadata.permissions().readonly() {
+            return Err(RegistryError::StorageError(format!("{} is read-only", packages_dir.display())));
+        }
+        Ok(())
+    }
+}
+
+/// Collect the tarballs of one package directory
+async fn list_tarballs(
+    package_dir: &std::path::Path,
+    package_name: &str,
+    objects: &mut Vec<StoredObject>,
+) -> Result<(), RegistryError> {
+    let mut files = fs::read_dir(package_dir).await
+        .map_err(|e| RegistryError::StorageError(format!("Failed to read directory: {}", e)))?;
+
+    while let Some(entry) = files.next_entry().await
+        .map_err(|e| RegistryError::StorageError(format!("Failed to read entry: {}", e)))? {
+
+        let Some(version) = entry.file_name().to_str().and_then(|name| name.strip_suffix(".tar.gz")).map(str::to_string) else {
+            continue;
+        };
+        let last_modified = entry.metadata().await
+            .ok()
+            .and_then(|metadata| metadata.modified().ok())
+            .map(DateTime::<Utc>::from);
+
+        objects.push(StoredObject {
+            package_name: package_name.to_string(),
+            version,
+            last_modified,
+        });
+    }
+
+    Ok(())
 }
@@ -0,0 +1,703 @@
+//! Server-rendered web UI for browsing the registry
+//!
+//! Pages are plain HTML built on the server: the package list, search
+//! results and a page per package with its versions, README, API docs and
+//! a download chart. READMEs are stored at publish time; docs are read from
+//! the published tarballs.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Html,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::database::{PackageSort, PackageSummary};
+use crate::entities::{package, package_version};
+use crate::package_files::{self, PackageFiles};
+use crate::AppState;
+
+type PageResult = Result<Html<String>, (StatusCode, Html<String>)>;
+
+/// Number of days shown in the download chart
+const CHART_DAYS: i64 = 30;
+
+/// Maximum number of packages shown on a search results page
+const SEARCH_LIMIT: u64 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    #[serde(default)]
+    q: String,
+}
+
+/// A documented declaration found in a package's source files
+#[derive(Debug, Clone, PartialEq)]
+struct DocEntry {
+    file: String,
+    signature: String,
+    doc: String,
+}
+
+/// GET / - every package with its latest version
+pub async fn index(State(state): State<Arc<AppState>>) -> PageResult {
+    let packages = state.db.list_packages().await.map_err(internal_error)?;
+    let summaries = state.db.package_summaries(packages).await.map_err(internal_error)?;
+    let rows = package_rows(summaries);
+
+    let body = format!(
+        "<h1>Packages</h1>\n<p class=\"muted\">{} package{} published</p>\n{}",
+        rows.len(),
+        if rows.len() == 1 { "" } else { "s" },
+        package_table(&rows)
+    );
+    Ok(page("Bulu packages", "", &body))
+}
+
+/// GET /search?q= - packages whose name or description match
+pub async fn search(State(state): State<Arc<AppState>>, Query(params): Query<SearchParams>) -> PageResult {
+    let query = params.q.trim();
+    if query.is_empty() {
+        let body = "<h1>Search</h1>\n<p class=\"muted\">Type a package name or a word from its description.</p>";
+        return Ok(page("Search", "", body));
+    }
+
+    let results = state
+        .db
+        .packages_page(Some(query), PackageSort::Relevance, SEARCH_LIMIT, 0)
+        .await
+        .map_err(internal_error)?;
+    let rows = package_rows(results.packages);
+
+    let body = format!(
+        "<h1>Results for &ldquo;{}&rdquo;</h1>\n<p class=\"muted\">{} package{} found{}</p>\n{}",
+        escape_html(query),
+        results.total,
+        if results.total == 1 { "" } else { "s" },
+        if results.total > rows.len() as u64 {
+            format!(", showing the best {}", rows.len())
+        } else {
+            String::new()
+        },
+        package_table(&rows)
+    );
+    Ok(page(&format!("{} - Search", query), query, &body))
+}
+
+/// GET /packages/:name - the latest version of a package
+pub async fn package_page(State(state): State<Arc<AppState>>, Path(name): Path<String>) -> PageResult {
+    render_package(&state, &name, None).await
+}
+
+/// GET /packages/:name/:version - a specific version of a package
+pub async fn package_version_page(
+    State(state): State<Arc<AppState>>,
+    Path((name, version)): Path<(String, String)>,
+) -> PageResult {
+    render_package(&state, &name, Some(&version)).await
+}
+
+/// A package as listed on the index and search pages
+struct PackageRow {
+    name: String,
+    latest_version: Option<String>,
+    description: Option<String>,
+    downloads: i64,
+    updated_at: String,
+}
+
+fn package_rows(summaries: Vec<PackageSummary>) -> Vec<PackageRow> {
+    summaries
+        .into_iter()
+        .map(|summary| PackageRow {
+            name: summary.package.name,
+            latest_version: summary.latest.as_ref().map(|v| v.version.clone()),
+            description: summary
+                .latest
+                .and_then(|v| v.description)
+                .or(summary.package.description),
+            downloads: summary.downloads,
+            updated_at: summary.package.updated_at.format("%Y-%m-%d").to_string(),
+        })
+        .collect()
+}
+
+fn package_table(rows: &[PackageRow]) -> String {
+    if rows.is_empty() {
+        return "<p>No packages.</p>".to_string();
+    }
+
+    let mut html = String::from(
+        "<table>\n<thead><tr><th>Package</th><th>Version</th><th>Description</th><th class=\"num\">Downloads</th><th>Updated</th></tr></thead>\n<tbody>\n",
+    );
+    for row in rows {
+        html.push_str(&format!(
+            "<tr><td><a href=\"/packages/{}\">{}</a></td><td>{}</td><td>{}</td><td class=\"num\">{}</td><td>{}</td></tr>\n",
+            escape_attribute(&encode_query(&row.name)),
+            escape_html(&row.name),
+            escape_html(row.latest_version.as_deref().unwrap_or("-")),
+            escape_html(row.description.as_deref().unwrap_or("")),
+            row.downloads,
+            row.updated_at
+        ));
+    }
+    html.push_str("</tbody>\n</table>");
+    html
+}
+
+async fn render_package(state: &AppState, name: &str, version: Option<&str>) -> PageResult {
+    let package = state
+        .db
+        .get_package(name)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| error_page(StatusCode::NOT_FOUND, &format!("Package '{}' not found", name)))?;
+
+    let versions = state
+        .db
+        .get_package_versions(package.id)
+        .await
+        .map_err(internal_error)?;
+    let selected = match version {
+        Some(version) => versions.iter().find(|v| v.version == version).ok_or_else(|| {
+            error_page(
+                StatusCode::NOT_FOUND,
+                &format!("Version {} of '{}' not found", version, name),
+            )
+        })?,
+        None => versions.first().ok_or_else(|| {
+            error_page(StatusCode::NOT_FOUND, &format!("Package '{}' has no published versions", name))
+        })?,
+    };
+
+    let keywords = state.db.get_keywords(package.id).await.map_err(internal_error)?;
+    let authors = state.db.get_authors(selected.id).await.map_err(internal_error)?;
+    let dependencies = state
+        .db
+        .get_dependencies(selected.id)
+        .await
+        .map_err(internal_error)?;
+    let total_downloads = state
+        .db
+        .get_total_downloads(package.id)
+        .await
+        .map_err(internal_error)?;
+    let daily_downloads = state
+        .db
+        .get_daily_downloads(package.id, CHART_DAYS)
+        .await
+        .map_err(internal_error)?;
+
+    // A missing tarball only hides the docs, and the README of versions
+    // published before READMEs were stored
+    let files = match state.storage.retrieve_tarball(name, &selected.version).await {
+        Ok(tarball) => package_files::read(&tarball),
+        Err(e) => {
+            tracing::warn!("Could not read tarball of {} v{}: {}", name, selected.version, e);
+            PackageFiles::default()
+        }
+    };
+    let readme = selected.readme.as_ref().or(files.readme.as_ref());
+    let docs: Vec<DocEntry> = files
+        .sources
+        .iter()
+        .flat_map(|(path, source)| extract_docs(path, source))
+        .collect();
+
+    let mut body = format!(
+        "<h1>{} <span class=\"version\">{}</span></h1>\n",
+        escape_html(&package.name),
+        escape_html(&selected.version)
+    );
+    if let Some(description) = selected.description.as_ref().or(package.description.as_ref()) {
+        body.push_str(&format!("<p class=\"lead\">{}</p>\n", escape_html(description)));
+    }
+
+    body.push_str("<div class=\"columns\">\n<div class=\"main\">\n");
+    if let Some(notes) = &selected.release_notes {
+        body.push_str(&format!(
+            "<h2>Release notes</h2>\n<div class=\"readme\">\n{}</div>\n",
+            render_markdown(notes)
+        ));
+    }
+    body.push_str("<h2>Readme</h2>\n");
+    match readme {
+        Some(readme) => body.push_str(&format!("<div class=\"readme\">\n{}</div>\n", render_markdown(readme))),
+        None => body.push_str("<p class=\"muted\">This version has no README.</p>\n"),
+    }
+
+    body.push_str("<h2>Documentation</h2>\n");
+    if docs.is_empty() {
+        body.push_str("<p class=\"muted\">No documented declarations.</p>\n");
+    }
+    for entry in &docs {
+        body.push_str(&format!(
+            "<div class=\"doc\"><pre><code>{}</code></pre><p class=\"muted\">{}</p>{}</div>\n",
+            escape_html(&entry.signature),
+            escape_html(&entry.file),
+            render_markdown(&entry.doc)
+        ));
+    }
+
+    body.push_str("<h2>Versions</h2>\n<table>\n<thead><tr><th>Version</th><th>Published</th><th class=\"num\">Downloads</th></tr></thead>\n<tbody>\n");
+    for v in &versions {
+        let class = if v.id == selected.id { " class=\"selected\"" } else { "" };
+        body.push_str(&format!(
+            "<tr{}><td><a href=\"/packages/{}/{}\">{}</a></td><td>{}</td><td class=\"num\">{}</td></tr>\n",
+            class,
+            escape_attribute(&encode_query(&package.name)),
+            escape_attribute(&v.version),
+            escape_html(&v.version),
+            v.published_at.format("%Y-%m-%d"),
+            v.downloads
+        ));
+    }
+    body.push_str("</tbody>\n</table>\n</div>\n");
+
+    body.push_str(&package_sidebar(&package, selected, &keywords, &authors, &dependencies, total_downloads));
+    body.push_str(&format!(
+        "<h3>Downloads, last {} days</h3>\n{}\n</aside>\n</div>\n",
+        CHART_DAYS,
+        downloads_chart(&daily_downloads)
+    ));
+
+    Ok(page(&format!("{} {}", package.name, selected.version), "", &body))
+}
+
+/// Install instructions and metadata; the caller closes the `<aside>`
+fn package_sidebar(
+    package: &package::Model,
+    selected: &package_version::Model,
+    keywords: &[String],
+    authors: &[String],
+    dependencies: &std::collections::HashMap<String, String>,
+    total_downloads: i64,
+) -> String {
+    let mut html = format!(
+        "<aside>\n<h3>Install</h3>\n<pre><code>lang add {}@{}</code></pre>\n<dl>\n",
+        escape_html(&package.name),
+        escape_html(&selected.version)
+    );
+    html.push_str(&format!("<dt>Downloads</dt><dd>{} total, {} for this version</dd>\n", total_downloads, selected.downloads));
+    if let Some(license) = &selected.license {
+        html.push_str(&format!("<dt>License</dt><dd>{}</dd>\n", escape_html(license)));
+    }
+    if let Some(repository) = &package.repository {
+        html.push_str(&format!(
+            "<dt>Repository</dt><dd><a href=\"{}\">{}</a></dd>\n",
+            escape_attribute(&safe_url(repository)),
+            escape_html(repository)
+        ));
+    }
+    if !authors.is_empty() {
+        let authors: Vec<String> = authors.iter().map(|a| escape_html(a)).collect();
+        html.push_str(&format!("<dt>Authors</dt><dd>{}</dd>\n", authors.join("<br>")));
+    }
+    if !keywords.is_empty() {
+        let keywords: Vec<String> = keywords
+            .iter()
+            .map(|k| format!("<a class=\"tag\" href=\"/search?q={}\">{}</a>", escape_attribute(&encode_query(k)), escape_html(k)))
+            .collect();
+        html.push_str(&format!("<dt>Keywords</dt><dd>{}</dd>\n", keywords.join(" ")));
+    }
+
+    html.push_str("<dt>Dependencies</dt><dd>");
+    if dependencies.is_empty() {
+        html.push_str("None");
+    } else {
+        let mut names: Vec<&String> = dependencies.keys().collect();
+        names.sort();
+        let items: Vec<String> = names
+            .into_iter()
+            .map(|dep| {
+                format!(
+                    "<a href=\"/packages/{}\">{}</a> {}",
+                    escape_attribute(&encode_query(dep)),
+                    escape_html(dep),
+                    escape_html(&dependencies[dep])
+                )
+            })
+            .collect();
+        html.push_str(&items.join("<br>"));
+    }
+    html.push_str("</dd>\n</dl>\n");
+    html
+}
+
+/// Wrap a page body in the shared layout
+fn page(title: &str, query: &str, body: &str) -> Html<String> {
+    Html(format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="UTF-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>{}</title>
+<style>{}</style>
+</head>
+<body>
+<header>
+<a class="brand" href="/">Bulu packages</a>
+<form action="/search" method="get"><input type="search" name="q" value="{}" placeholder="Search packages"></form>
+</header>
+<main>
+{}
+</main>
+</body>
+</html>
+"#,
+        escape_html(title),
+        STYLE,
+        escape_attribute(query),
+        body
+    ))
+}
+
+const STYLE: &str = "
+body { margin: 0; font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', sans-serif; color: #1f2328; line-height: 1.5; }
+header { display: flex; align-items: center; gap: 1.5rem; padding: 0.75rem 2rem; background: #24292f; }
+header .brand { color: #fff; font-weight: 600; text-decoration: none; }
+header form { flex: 1; max-width: 32rem; }
+header input { width: 100%; padding: 0.4rem 0.6rem; border: 0; border-radius: 4px; }
+main { max-width: 72rem; margin: 0 auto; padding: 1rem 2rem 3rem; }
+a { color: #0969da; }
+table { width: 100%; border-collapse: collapse; }
+th, td { text-align: left; padding: 0.4rem 0.6rem; border-bottom: 1px solid #d0d7de; vertical-align: top; }
+td.num, th.num { text-align: right; }
+tr.selected { background: #f6f8fa; font-weight: 600; }
+pre { background: #f6f8fa; padding: 0.75rem; border-radius: 4px; overflow-x: auto; }
+code { font-family: SFMono-Regular, Consolas, monospace; font-size: 0.9em; }
+.muted { color: #656d76; }
+.lead { font-size: 1.15rem; }
+.version { color: #656d76; font-weight: normal; }
+.columns { display: flex; gap: 2rem; align-items: flex-start; }
+.main { flex: 1; min-width: 0; }
+aside { width: 20rem; }
+dt { font-weight: 600; margin-top: 0.5rem; }
+dd { margin: 0; }
+.tag { display: inline-block; padding: 0 0.5rem; border-radius: 1rem; background: #ddf4ff; text-decoration: none; }
+.doc { border-bottom: 1px solid #d0d7de; margin-bottom: 1rem; }
+.chart rect { fill: #2da44e; }
+.chart text { font-size: 10px; fill: #656d76; }
+@media (max-width: 800px) { .columns { flex-direction: column; } aside { width: 100%; } }
+";
+
+fn error_page(status: StatusCode, message: &str) -> (StatusCode, Html<String>) {
+    let body = format!("<h1>{}</h1>\n<p>{}</p>", status, escape_html(message));
+    (status, page(message, "", &body))
+}
+
+fn internal_error<E: std::fmt::Display>(error: E) -> (StatusCode, Html<String>) {
+    error_page(StatusCode::INTERNAL_SERVER_ERROR, &error.to_string())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn escape_attribute(text: &str) -> String {
+    escape_html(text).replace('"', "&quot;").replace('\'', "&#39;")
+}
+
+/// Percent-encode a query string value or a single path segment
+fn encode_query(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Links from package metadata may only use http(s) or stay on the registry
+fn safe_url(url: &str) -> String {
+    let lower = url.trim().to_ascii_lowercase();
+    if lower.starts_with("http://") || lower.starts_with("https://") || lower.starts_with('/') || lower.starts_with('#') {
+        url.trim().to_string()
+    } else {
+        "#".to_string()
+    }
+}
+
+/// Render the common subset of Markdown used in READMEs: headings, fenced
+/// code, lists, paragraphs, and inline code, bold, italics and links
+fn render_markdown(text: &str) -> String {
+    let mut html = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut list: Option<&str> = None;
+    let mut code: Option<Vec<&str>> = None;
+
+    fn flush_paragraph(html: &mut String, paragraph: &mut Vec<&str>) {
+        if !paragraph.is_empty() {
+            html.push_str(&format!("<p>{}</p>\n", render_inline(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+    }
+    fn close_list(html: &mut String, list: &mut Option<&str>) {
+        if let Some(tag) = list.take() {
+            html.push_str(&format!("</{}>\n", tag));
+        }
+    }
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+
+        if let Some(lines) = code.as_mut() {
+            if trimmed.starts_with("```") {
+                html.push_str(&format!("<pre><code>{}</code></pre>\n", escape_html(&lines.join("\n"))));
+                code = None;
+            } else {
+                lines.push(line);
+            }
+            continue;
+        }
+
+        if trimmed.starts_with("```") {
+            flush_paragraph(&mut html, &mut paragraph);
+            close_list(&mut html, &mut list);
+            code = Some(Vec::new());
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            flush_paragraph(&mut html, &mut paragraph);
+            close_list(&mut html, &mut list);
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|&c| c == '#').count();
+        if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            flush_paragraph(&mut html, &mut paragraph);
+            close_list(&mut html, &mut list);
+            // README headings sit below the page's own h1 and h2
+            let tag_level = (level + 2).min(6);
+            html.push_str(&format!(
+                "<h{}>{}</h{}>\n",
+                tag_level,
+                render_inline(trimmed[level..].trim()),
+                tag_level
+            ));
+            continue;
+        }
+
+        let item = if let Some(rest) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            Some(("ul", rest))
+        } else {
+            let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+            match trimmed[digits..].strip_prefix(". ") {
+                Some(rest) if digits > 0 => Some(("ol", rest)),
+                _ => None,
+            }
+        };
+        if let Some((tag, rest)) = item {
+            flush_paragraph(&mut html, &mut paragraph);
+            if list != Some(tag) {
+                close_list(&mut html, &mut list);
+                html.push_str(&format!("<{}>\n", tag));
+                list = Some(tag);
+            }
+            html.push_str(&format!("<li>{}</li>\n", render_inline(rest)));
+            continue;
+        }
+
+        close_list(&mut html, &mut list);
+        paragraph.push(trimmed);
+    }
+
+    if let Some(lines) = code {
+        html.push_str(&format!("<pre><code>{}</code></pre>\n", escape_html(&lines.join("\n"))));
+    }
+    flush_paragraph(&mut html, &mut paragraph);
+    close_list(&mut html, &mut list);
+    html
+}
+
+/// Inline Markdown: `code` spans are kept verbatim, the rest gets links and emphasis
+fn render_inline(text: &str) -> String {
+    let mut html = String::new();
+    for (i, part) in text.split('`').enumerate() {
+        if i % 2 == 1 {
+            html.push_str(&format!("<code>{}</code>", escape_html(part)));
+        } else {
+            html.push_str(&render_emphasis(&render_links(part)));
+        }
+    }
+    html
+}
+
+/// Escape text and turn `[label](url)` into links
+fn render_links(text: &str) -> String {
+    let mut html = String::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find("](").map(|i| open + i) else {
+            break;
+        };
+        let Some(end) = rest[close + 2..].find(')').map(|i| close + 2 + i) else {
+            break;
+        };
+        html.push_str(&escape_html(&rest[..open]));
+        html.push_str(&format!(
+            "<a href=\"{}\">{}</a>",
+            escape_attribute(&safe_url(&rest[close + 2..end])),
+            escape_html(&rest[open + 1..close])
+        ));
+        rest = &rest[end + 1..];
+    }
+    html.push_str(&escape_html(rest));
+    html
+}
+
+/// `**bold**` and `*italic*`, applied to already escaped text
+fn render_emphasis(html: &str) -> String {
+    fn wrap(text: &str, marker: &str, tag: &str) -> String {
+        let parts: Vec<&str> = text.split(marker).collect();
+        // An unmatched marker is left as it is
+        if parts.len() < 3 {
+            return text.to_string();
+        }
+        let mut out = String::new();
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                let closing = i % 2 == 0;
+                if !closing && i == parts.len() - 1 {
+                    out.push_str(marker);
+                } else {
+                    out.push_str(&format!("<{}{}>", if closing { "/" } else { "" }, tag));
+                }
+            }
+            out.push_str(part);
+        }
+        out
+    }
+    wrap(&wrap(html, "**", "strong"), "*", "em")
+}
+
+/// Bar chart of downloads per day as inline SVG
+fn downloads_chart(days: &[(chrono::NaiveDate, i64)]) -> String {
+    const WIDTH: f64 = 300.0;
+    const HEIGHT: f64 = 80.0;
+
+    let max = days.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1);
+    let bar_width = WIDTH / days.len().max(1) as f64;
+    let mut svg = format!(
+        "<svg class=\"chart\" viewBox=\"0 0 {} {}\" width=\"100%\" role=\"img\" aria-label=\"Downloads per day\">\n",
+        WIDTH,
+        HEIGHT + 14.0
+    );
+    for (i, (day, count)) in days.iter().enumerate() {
+        let height = (*count as f64 / max as f64) * HEIGHT;
+        svg.push_str(&format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\"><title>{}: {} download{}</title></rect>\n",
+            i as f64 * bar_width + 1.0,
+            HEIGHT - height,
+            (bar_width - 2.0).max(1.0),
+            height,
+            day.format("%Y-%m-%d"),
+            count,
+            if *count == 1 { "" } else { "s" }
+        ));
+    }
+    if let (Some((first, _)), Some((last, _))) = (days.first(), days.last()) {
+        svg.push_str(&format!(
+            "<text x=\"0\" y=\"{}\">{}</text>\n<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\n",
+            HEIGHT + 12.0,
+            first.format("%b %d"),
+            WIDTH,
+            HEIGHT + 12.0,
+            last.format("%b %d")
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Declarations preceded by a `/** ... */` doc comment
+fn extract_docs(file: &str, source: &str) -> Vec<DocEntry> {
+    let mut docs = Vec::new();
+    let mut pending: Option<String> = None;
+    let mut lines = source.lines();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+
+        if let Some(rest) = trimmed.strip_prefix("/**") {
+            let mut comment = Vec::new();
+            let mut current = rest;
+            loop {
+                if let Some(end) = current.find("*/") {
+                    comment.push(&current[..end]);
+                    break;
+                }
+                comment.push(current);
+                match lines.next() {
+                    Some(next) => current = next,
+                    None => break,
+                }
+            }
+            let text: Vec<&str> = comment
+                .iter()
+                .map(|l| {
+                    let l = l.trim();
+                    l.strip_prefix("* ").or_else(|| l.strip_prefix('*')).unwrap_or(l)
+                })
+                .collect();
+            pending = Some(text.join("\n").trim().to_string());
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if let Some(doc) = pending.take() {
+            let declaration = trimmed.trim_start_matches("export ").trim_start_matches("pub ");
+            let is_declaration = ["func ", "async func ", "struct ", "interface ", "type ", "const ", "let "]
+                .iter()
+                .any(|keyword| declaration.starts_with(keyword));
+            if is_declaration {
+                docs.push(DocEntry {
+                    file: file.to_string(),
+                    signature: trimmed.trim_end_matches('{').trim_end().to_string(),
+                    doc,
+                });
+            }
+        }
+    }
+
+    docs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_markdown() {
+        let html = render_markdown(
+            "# Title\n\nSome *nice* **text** with `a < b` and [docs](https://example.com).\n\n- one\n- two\n\n```\nlet x = <y>\n```\n",
+        );
+        assert_eq!(
+            html,
+            "<h3>Title</h3>\n<p>Some <em>nice</em> <strong>text</strong> with <code>a &lt; b</code> and <a href=\"https://example.com\">docs</a>.</p>\n<ul>\n<li>one</li>\n<li>two</li>\n</ul>\n<pre><code>let x = &lt;y&gt;</code></pre>\n"
+        );
+        assert!(render_markdown("[x](javascript:alert(1))").contains("href=\"#\""));
+    }
+
+    #[test]
+    fn test_extract_docs() {
+        let source = "/**\n * Adds two numbers\n */\nexport func add(a: int32, b: int32): int32 {\n    return a + b\n}\n\n/** Not attached */\n\n// plain comment\nfunc helper() {}\n";
+        let docs = extract_docs("src/math.bu", source);
+        assert_eq!(
+            docs,
+            vec![DocEntry {
+                file: "src/math.bu".to_string(),
+                signature: "export func add(a: int32, b: int32): int32".to_string(),
+                doc: "Adds two numbers".to_string(),
+            }]
+        );
+    }
+}
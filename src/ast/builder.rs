@@ -22,6 +22,7 @@ impl AstBuilder {
     pub fn program(statements: Vec<Statement>) -> Program {
         Program {
             statements,
+            attributes: Vec::new(),
             position: Self::dummy_pos(),
         }
     }
@@ -280,6 +281,7 @@ impl AstBuilder {
         Type::Array(ArrayType {
             element_type: Box::new(element_type),
             size,
+            size_expr: None,
         })
     }
     
@@ -10,9 +10,18 @@ use crate::lexer::token::Position;
 #[derive(Debug, Clone, PartialEq)]
 pub struct Program {
     pub statements: Vec<Statement>,
+    /// File attributes such as `@no_prelude`, written before any statement
+    pub attributes: Vec<Attribute>,
     pub position: Position,
 }
 
+impl Program {
+    /// Whether the file has the attribute `@name`
+    pub fn has_attribute(&self, name: &str) -> bool {
+        self.attributes.iter().any(|attribute| attribute.name == name)
+    }
+}
+
 /// All possible statement types in Bulu
 #[derive(Debug, Clone, PartialEq)]
 pub enum Statement {
@@ -78,6 +87,7 @@ pub enum Expression {
     // Collection literals
     Array(ArrayExpr),
     Map(MapExpr),
+    Set(SetExpr),
     StructLiteral(StructLiteralExpr),
     
     // Function expressions
@@ -94,6 +104,9 @@ pub enum Expression {
     Cast(CastExpr),
     TypeOf(TypeOfExpr),
     
+    // Error propagation: expr?
+    Propagate(PropagateExpr),
+    
     // Range expressions
     Range(RangeExpr),
     
@@ -206,6 +219,16 @@ pub struct StructField {
     pub name: String,
     pub field_type: Type,
     pub is_private: bool,
+    /// Attributes written before the field, such as `@json("user_id")`
+    pub attributes: Vec<Attribute>,
+    pub position: Position,
+}
+
+/// Attribute on a declaration: `@name` or `@name(args)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    pub name: String,
+    pub args: Vec<Expression>,
     pub position: Position,
 }
 
@@ -220,16 +243,56 @@ pub struct InterfaceDecl {
     pub position: Position,
 }
 
-/// Interface method signature
+/// Interface method signature, optionally with a default implementation
 #[derive(Debug, Clone, PartialEq)]
 pub struct InterfaceMethod {
     pub name: String,
     pub params: Vec<Parameter>,
     pub return_type: Option<Type>,
+    pub default_body: Option<BlockStmt>,
     pub is_private: bool,
     pub position: Position,
 }
 
+impl InterfaceMethod {
+    /// The default implementation as a method declaration, if the interface provides one
+    pub fn default_method(&self) -> Option<FunctionDecl> {
+        let body = self.default_body.clone()?;
+        Some(FunctionDecl {
+            name: self.name.clone(),
+            type_params: Vec::new(),
+            params: self.params.clone(),
+            return_type: self.return_type.clone(),
+            body,
+            is_async: false,
+            doc_comment: None,
+            is_exported: false,
+            is_private: self.is_private,
+            position: self.position,
+        })
+    }
+}
+
+impl InterfaceDecl {
+    /// Default methods a struct inherits from this interface: the defaults it does not
+    /// override, provided it defines every method that has no default
+    pub fn inherited_defaults(&self, struct_decl: &StructDecl) -> Vec<FunctionDecl> {
+        let defines = |name: &str| struct_decl.methods.iter().any(|method| method.name == name);
+        let conforms = self
+            .methods
+            .iter()
+            .all(|method| method.default_body.is_some() || defines(&method.name));
+        if !conforms {
+            return Vec::new();
+        }
+        self.methods
+            .iter()
+            .filter(|method| !defines(&method.name))
+            .filter_map(InterfaceMethod::default_method)
+            .collect()
+    }
+}
+
 
 /// Type alias declaration
 #[derive(Debug, Clone, PartialEq)]
@@ -509,6 +572,14 @@ pub struct MapExpr {
     pub position: Position,
 }
 
+/// Set literal expression: set{a, b} or set<T>{a, b}
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetExpr {
+    pub element_type: Option<Type>,
+    pub elements: Vec<Expression>,
+    pub position: Position,
+}
+
 /// Map entry
 #[derive(Debug, Clone, PartialEq)]
 pub struct MapEntry {
@@ -521,6 +592,7 @@ pub struct MapEntry {
 #[derive(Debug, Clone, PartialEq)]
 pub struct StructLiteralExpr {
     pub type_name: String,
+    pub type_args: Vec<Type>,
     pub fields: Vec<StructFieldInit>,
     pub position: Position,
 }
@@ -626,6 +698,14 @@ pub struct TypeOfExpr {
     pub position: Position,
 }
 
+/// Error propagation expression (expr?): unwraps an ok Result and returns
+/// an error Result from the enclosing function
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropagateExpr {
+    pub expr: Box<Expression>,
+    pub position: Position,
+}
+
 /// Range expression
 #[derive(Debug, Clone, PartialEq)]
 pub struct RangeExpr {
@@ -692,6 +772,7 @@ pub enum Type {
     Array(ArrayType),
     Slice(SliceType),
     Map(MapType),
+    Set(SetType),
     Tuple(TupleType),
     Function(FunctionType),
     
@@ -708,6 +789,10 @@ pub enum Type {
     // Async types
     Promise(PromiseType),
     
+    // Union types (T | U, and T? for T | null)
+    Union(UnionType),
+    Null,
+    
     // Named type (identifier)
     Named(String),
 }
@@ -717,6 +802,9 @@ pub enum Type {
 pub struct ArrayType {
     pub element_type: Box<Type>,
     pub size: Option<usize>,
+    /// Size written as a constant expression (`[N]T`, `[N * 2]T`), until the
+    /// optimizer's constant evaluation turns it into `size`
+    pub size_expr: Option<Box<Expression>>,
 }
 
 /// Slice type
@@ -732,6 +820,12 @@ pub struct MapType {
     pub value_type: Box<Type>,
 }
 
+/// Set type: set<T>
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetType {
+    pub element_type: Box<Type>,
+}
+
 /// Tuple type
 #[derive(Debug, Clone, PartialEq)]
 pub struct TupleType {
@@ -781,6 +875,12 @@ pub struct PromiseType {
     pub position: Position,
 }
 
+/// Union type: a value of any one of the member types
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnionType {
+    pub types: Vec<Type>,
+}
+
 /// Type parameter for generics
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypeParam {
@@ -804,6 +904,8 @@ pub enum Pattern {
     Tuple(TuplePattern),
     Range(RangePattern),
     Or(OrPattern),
+    Binding(BindingPattern),
+    Type(TypePattern),
 }
 
 /// Struct pattern
@@ -852,6 +954,22 @@ pub struct OrPattern {
     pub position: Position,
 }
 
+/// Binding pattern (name @ pattern): matches `pattern` and binds the whole value to `name`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BindingPattern {
+    pub name: String,
+    pub pattern: Box<Pattern>,
+    pub position: Position,
+}
+
+/// Type pattern (name: Type or _: Type): matches values of a union member type
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypePattern {
+    pub name: Option<String>,
+    pub pattern_type: Type,
+    pub position: Position,
+}
+
 // ============================================================================
 // OPERATORS AND ENUMS
 // ============================================================================
@@ -921,6 +1039,8 @@ pub enum LiteralValue {
     Integer(i64),
     Float(f64),
     String(String),
+    /// `b"..."`, a `[]byte`
+    ByteString(Vec<u8>),
     Char(char),
     Boolean(bool),
     Null,
@@ -981,6 +1101,7 @@ impl HasPosition for Expression {
             Expression::Match(node) => node.position,
             Expression::Array(node) => node.position,
             Expression::Map(node) => node.position,
+            Expression::Set(node) => node.position,
             Expression::Lambda(node) => node.position,
             Expression::Async(node) => node.position,
             Expression::Await(node) => node.position,
@@ -989,6 +1110,7 @@ impl HasPosition for Expression {
             Expression::Select(node) => node.position,
             Expression::Cast(node) => node.position,
             Expression::TypeOf(node) => node.position,
+            Expression::Propagate(node) => node.position,
             Expression::Range(node) => node.position,
             Expression::Yield(node) => node.position,
             Expression::Parenthesized(node) => node.position,
@@ -1010,6 +1132,8 @@ impl HasPosition for Pattern {
             Pattern::Tuple(node) => node.position,
             Pattern::Range(node) => node.position,
             Pattern::Or(node) => node.position,
+            Pattern::Binding(node) => node.position,
+            Pattern::Type(node) => node.position,
         }
     }
 }
\ No newline at end of file
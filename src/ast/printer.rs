@@ -46,6 +46,10 @@ impl AstPrinter {
         writeln!(output, "Program {{").unwrap();
 
         self.with_increased_indent(|printer| {
+            for attribute in &program.attributes {
+                let attribute_str = printer.print_attribute(attribute);
+                writeln!(output, "{}{}", printer.indent(), attribute_str).unwrap();
+            }
             for stmt in &program.statements {
                 let stmt_str = printer.print_statement(stmt);
                 for line in stmt_str.lines() {
@@ -115,6 +119,7 @@ impl AstPrinter {
             Expression::Select(expr) => self.print_select_expr(expr),
             Expression::Array(expr) => self.print_array_expr(expr),
             Expression::Map(expr) => self.print_map_expr(expr),
+            Expression::Set(expr) => self.print_set_expr(expr),
             Expression::Lambda(expr) => self.print_lambda_expr(expr),
             Expression::Async(expr) => format!("Async({})", self.print_expression(&expr.expr)),
             Expression::Await(expr) => format!("Await({})", self.print_expression(&expr.expr)),
@@ -128,6 +133,7 @@ impl AstPrinter {
                 )
             }
             Expression::TypeOf(expr) => format!("TypeOf({})", self.print_expression(&expr.expr)),
+            Expression::Propagate(expr) => format!("Propagate({})", self.print_expression(&expr.expr)),
             Expression::Range(expr) => self.print_range_expr(expr),
             Expression::Yield(expr) => match &expr.value {
                 Some(val) => format!("Yield({})", self.print_expression(val)),
@@ -277,6 +283,10 @@ impl AstPrinter {
             for field in &decl.fields {
                 result.push('\n');
                 result.push_str(&printer.indent());
+                for attribute in &field.attributes {
+                    result.push_str(&printer.print_attribute(attribute));
+                    result.push(' ');
+                }
                 result.push_str(&format!(
                     "{}: {}",
                     field.name,
@@ -289,6 +299,14 @@ impl AstPrinter {
         result
     }
 
+    fn print_attribute(&mut self, attribute: &Attribute) -> String {
+        if attribute.args.is_empty() {
+            return format!("@{}", attribute.name);
+        }
+        let args: Vec<String> = attribute.args.iter().map(|arg| self.print_expression(arg)).collect();
+        format!("@{}({})", attribute.name, args.join(", "))
+    }
+
     fn print_interface_decl(&mut self, decl: &InterfaceDecl) -> String {
         let mut result = format!("Interface {} {{", decl.name);
 
@@ -313,6 +331,10 @@ impl AstPrinter {
                 if let Some(ref return_type) = method.return_type {
                     result.push_str(&format!(": {}", printer.print_type(return_type)));
                 }
+                if let Some(ref body) = method.default_body {
+                    result.push(' ');
+                    result.push_str(&printer.print_block_stmt(body));
+                }
             }
         });
 
@@ -517,6 +539,7 @@ impl AstPrinter {
             LiteralValue::Integer(n) => n.to_string(),
             LiteralValue::Float(f) => f.to_string(),
             LiteralValue::String(s) => format!("\"{}\"", s),
+            LiteralValue::ByteString(bytes) => byte_string_literal(bytes),
             LiteralValue::Char(c) => format!("'{}'", c),
             LiteralValue::Boolean(b) => b.to_string(),
             LiteralValue::Null => "null".to_string(),
@@ -663,6 +686,22 @@ impl AstPrinter {
         result
     }
 
+    fn print_set_expr(&mut self, expr: &SetExpr) -> String {
+        let mut result = String::from("set");
+        if let Some(element_type) = &expr.element_type {
+            result.push_str(&format!("<{}>", self.print_type(element_type)));
+        }
+        result.push('{');
+        for (i, element) in expr.elements.iter().enumerate() {
+            if i > 0 {
+                result.push_str(", ");
+            }
+            result.push_str(&self.print_expression(element));
+        }
+        result.push('}');
+        result
+    }
+
     fn print_lambda_expr(&mut self, expr: &LambdaExpr) -> String {
         let mut result = String::from("(");
         for (i, param) in expr.params.iter().enumerate() {
@@ -724,6 +763,8 @@ impl AstPrinter {
             Pattern::Tuple(pat) => self.print_tuple_pattern(pat),
             Pattern::Range(pat) => self.print_range_pattern(pat),
             Pattern::Or(pat) => self.print_or_pattern(pat),
+            Pattern::Binding(pat) => self.print_binding_pattern(pat),
+            Pattern::Type(pat) => self.print_type_pattern(pat),
         }
     }
 
@@ -777,6 +818,15 @@ impl AstPrinter {
         )
     }
 
+    fn print_binding_pattern(&mut self, pat: &BindingPattern) -> String {
+        format!("{} @ {}", pat.name, self.print_pattern(&pat.pattern))
+    }
+
+    fn print_type_pattern(&mut self, pat: &TypePattern) -> String {
+        let name = pat.name.as_deref().unwrap_or("_");
+        format!("{}: {}", name, self.print_type(&pat.pattern_type))
+    }
+
     fn print_or_pattern(&mut self, pat: &OrPattern) -> String {
         let mut result = String::new();
         for (i, pattern) in pat.patterns.iter().enumerate() {
@@ -793,6 +843,7 @@ impl AstPrinter {
             LiteralValue::Integer(n) => n.to_string(),
             LiteralValue::Float(f) => f.to_string(),
             LiteralValue::String(s) => format!("\"{}\"", s),
+            LiteralValue::ByteString(bytes) => byte_string_literal(bytes),
             LiteralValue::Char(c) => format!("'{}'", c),
             LiteralValue::Boolean(b) => b.to_string(),
             LiteralValue::Null => "null".to_string(),
@@ -825,6 +876,7 @@ impl AstPrinter {
                 self.print_type(&map.key_type),
                 self.print_type(&map.value_type)
             ),
+            Type::Set(set) => format!("set<{}>", self.print_type(&set.element_type)),
             Type::Function(func) => {
                 let mut result = String::from("func(");
                 for (i, param_type) in func.param_types.iter().enumerate() {
@@ -889,6 +941,17 @@ impl AstPrinter {
             }
             Type::Void => "void".to_string(),
             Type::Promise(promise) => format!("Promise<{}>", self.print_type(&promise.result_type)),
+            Type::Union(union) => {
+                let mut result = String::new();
+                for (i, member) in union.types.iter().enumerate() {
+                    if i > 0 {
+                        result.push_str(" | ");
+                    }
+                    result.push_str(&self.print_type(member));
+                }
+                result
+            }
+            Type::Null => "null".to_string(),
         }
     }
 
@@ -922,7 +985,18 @@ impl AstPrinter {
     }
 
     fn print_struct_literal_expr(&mut self, expr: &StructLiteralExpr) -> String {
-        let mut result = format!("{} {{", expr.type_name);
+        let mut result = expr.type_name.clone();
+        if !expr.type_args.is_empty() {
+            result.push('<');
+            for (i, type_arg) in expr.type_args.iter().enumerate() {
+                if i > 0 {
+                    result.push_str(", ");
+                }
+                result.push_str(&self.print_type(type_arg));
+            }
+            result.push('>');
+        }
+        result.push_str(" {");
         for (i, field) in expr.fields.iter().enumerate() {
             if i > 0 {
                 result.push_str(", ");
@@ -983,3 +1057,20 @@ impl Default for AstPrinter {
         Self::new()
     }
 }
+
+/// Source form of a byte string, escaping quotes, backslashes and non-printable bytes
+fn byte_string_literal(bytes: &[u8]) -> String {
+    let mut out = String::from("b\"");
+    for &byte in bytes {
+        match byte {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b'\n' => out.push_str("\\n"),
+            b'\t' => out.push_str("\\t"),
+            0x20..=0x7e => out.push(byte as char),
+            _ => out.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    out.push('"');
+    out
+}
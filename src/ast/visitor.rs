@@ -56,6 +56,7 @@ pub trait Visitor<T> {
     fn visit_match_expr(&mut self, expr: &MatchExpr) -> T;
     fn visit_array_expr(&mut self, expr: &ArrayExpr) -> T;
     fn visit_map_expr(&mut self, expr: &MapExpr) -> T;
+    fn visit_set_expr(&mut self, expr: &SetExpr) -> T;
     fn visit_lambda_expr(&mut self, expr: &LambdaExpr) -> T;
     fn visit_async_expr(&mut self, expr: &AsyncExpr) -> T;
     fn visit_await_expr(&mut self, expr: &AwaitExpr) -> T;
@@ -64,6 +65,7 @@ pub trait Visitor<T> {
     fn visit_select_expr(&mut self, expr: &SelectExpr) -> T;
     fn visit_cast_expr(&mut self, expr: &CastExpr) -> T;
     fn visit_typeof_expr(&mut self, expr: &TypeOfExpr) -> T;
+    fn visit_propagate_expr(&mut self, expr: &PropagateExpr) -> T;
     fn visit_range_expr(&mut self, expr: &RangeExpr) -> T;
     fn visit_yield_expr(&mut self, expr: &YieldExpr) -> T;
     fn visit_parenthesized_expr(&mut self, expr: &ParenthesizedExpr) -> T;
@@ -129,6 +131,7 @@ pub trait MutVisitor {
     fn visit_select_expr(&mut self, expr: &mut SelectExpr);
     fn visit_array_expr(&mut self, expr: &mut ArrayExpr);
     fn visit_map_expr(&mut self, expr: &mut MapExpr);
+    fn visit_set_expr(&mut self, expr: &mut SetExpr);
     fn visit_lambda_expr(&mut self, expr: &mut LambdaExpr);
     fn visit_async_expr(&mut self, expr: &mut AsyncExpr);
     fn visit_await_expr(&mut self, expr: &mut AwaitExpr);
@@ -136,6 +139,7 @@ pub trait MutVisitor {
     fn visit_channel_expr(&mut self, expr: &mut ChannelExpr);
     fn visit_cast_expr(&mut self, expr: &mut CastExpr);
     fn visit_typeof_expr(&mut self, expr: &mut TypeOfExpr);
+    fn visit_propagate_expr(&mut self, expr: &mut PropagateExpr);
     fn visit_range_expr(&mut self, expr: &mut RangeExpr);
     fn visit_yield_expr(&mut self, expr: &mut YieldExpr);
     fn visit_parenthesized_expr(&mut self, expr: &mut ParenthesizedExpr);
@@ -216,6 +220,7 @@ pub fn walk_expression<T, V: Visitor<T>>(visitor: &mut V, expression: &Expressio
         Expression::Match(expr) => visitor.visit_match_expr(expr),
         Expression::Array(expr) => visitor.visit_array_expr(expr),
         Expression::Map(expr) => visitor.visit_map_expr(expr),
+        Expression::Set(expr) => visitor.visit_set_expr(expr),
         Expression::Lambda(expr) => visitor.visit_lambda_expr(expr),
         Expression::Async(expr) => visitor.visit_async_expr(expr),
         Expression::Await(expr) => visitor.visit_await_expr(expr),
@@ -224,6 +229,7 @@ pub fn walk_expression<T, V: Visitor<T>>(visitor: &mut V, expression: &Expressio
         Expression::Select(expr) => visitor.visit_select_expr(expr),
         Expression::Cast(expr) => visitor.visit_cast_expr(expr),
         Expression::TypeOf(expr) => visitor.visit_typeof_expr(expr),
+        Expression::Propagate(expr) => visitor.visit_propagate_expr(expr),
         Expression::Range(expr) => visitor.visit_range_expr(expr),
         Expression::Yield(expr) => visitor.visit_yield_expr(expr),
         Expression::Parenthesized(expr) => visitor.visit_parenthesized_expr(expr),
@@ -286,6 +292,7 @@ pub fn walk_expression_mut<V: MutVisitor>(visitor: &mut V, expression: &mut Expr
         Expression::Match(expr) => visitor.visit_match_expr(expr),
         Expression::Array(expr) => visitor.visit_array_expr(expr),
         Expression::Map(expr) => visitor.visit_map_expr(expr),
+        Expression::Set(expr) => visitor.visit_set_expr(expr),
         Expression::Lambda(expr) => visitor.visit_lambda_expr(expr),
         Expression::Async(expr) => visitor.visit_async_expr(expr),
         Expression::Await(expr) => visitor.visit_await_expr(expr),
@@ -294,6 +301,7 @@ pub fn walk_expression_mut<V: MutVisitor>(visitor: &mut V, expression: &mut Expr
         Expression::Select(expr) => visitor.visit_select_expr(expr),
         Expression::Cast(expr) => visitor.visit_cast_expr(expr),
         Expression::TypeOf(expr) => visitor.visit_typeof_expr(expr),
+        Expression::Propagate(expr) => visitor.visit_propagate_expr(expr),
         Expression::Range(expr) => visitor.visit_range_expr(expr),
         Expression::Yield(expr) => visitor.visit_yield_expr(expr),
         Expression::Parenthesized(expr) => visitor.visit_parenthesized_expr(expr),
@@ -39,6 +39,7 @@ fn main() {
         Ok(_result) => {
             // Program executed successfully - no output needed
         }
+        Err(BuluError::ExitRequested(code)) => process::exit(code),
         Err(e) => {
             eprintln!("Error: {}", e);
             process::exit(1);
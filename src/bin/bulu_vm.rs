@@ -2,12 +2,15 @@
 //!
 //! This executable can run Bulu bytecode files generated by the langc compiler.
 
+use bulu::logging::{self, Verbosity};
 use bulu::runtime::Interpreter;
+use bulu::terminal;
 use bulu::{BuluError, Result};
 use clap::{Arg, Command};
 use colored::*;
 use std::path::PathBuf;
 use std::process;
+use tracing::debug;
 
 fn main() -> Result<()> {
     let matches = Command::new("bulu")
@@ -21,24 +24,40 @@ fn main() -> Result<()> {
                 .index(1)
                 .value_parser(clap::value_parser!(PathBuf))
         )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .help("Only print warnings and errors")
+                .action(clap::ArgAction::SetTrue)
+        )
         .arg(
             Arg::new("verbose")
                 .short('v')
                 .long("verbose")
-                .help("Enable verbose output")
-                .action(clap::ArgAction::SetTrue)
+                .help("Report loading and execution; -vv prints everything")
+                .action(clap::ArgAction::Count)
+        )
+        .arg(
+            Arg::new("color")
+                .long("color")
+                .value_name("WHEN")
+                .help("When to color output: auto, always or never")
+                .value_parser(clap::builder::PossibleValuesParser::new(terminal::COLOR_CHOICES.iter().copied()))
+                .default_value("auto")
         )
         .get_matches();
 
+    let color = matches.get_one::<String>("color").map_or("auto", |choice| choice.as_str());
+    terminal::init(color.parse().map_err(BuluError::Other)?);
+    let verbosity = Verbosity::from_flags(matches.get_flag("quiet"), matches.get_count("verbose"));
+    logging::init(verbosity).map_err(BuluError::Other)?;
+
     let input_file = matches.get_one::<PathBuf>("input").unwrap();
-    let verbose = matches.get_flag("verbose");
 
-    if verbose {
-        println!("{}", "Bulu Bytecode Interpreter".bright_blue().bold());
-        println!("Version: {}", bulu::VERSION);
-        println!("Input: {}", input_file.display());
-        println!();
-    }
+    debug!("{}", "Bulu Bytecode Interpreter".bright_blue().bold());
+    debug!("Version: {}", bulu::VERSION);
+    debug!("Input: {}", input_file.display());
 
     // Validate input file
     if !input_file.exists() {
@@ -49,17 +68,12 @@ fn main() -> Result<()> {
     // Create interpreter and load bytecode
     let mut interpreter = Interpreter::new();
     
-    if verbose {
-        println!("{}", "Loading bytecode...".bright_yellow());
-    }
+    debug!("{}", "Loading bytecode...".bright_yellow());
     
     match interpreter.load_bytecode(input_file) {
         Ok(_) => {
-            if verbose {
-                println!("{}", "Bytecode loaded successfully".bright_green());
-                println!("{}", "Executing program...".bright_yellow());
-                println!();
-            }
+            debug!("{}", "Bytecode loaded successfully".bright_green());
+            debug!("{}", "Executing program...".bright_yellow());
         }
         Err(e) => {
             eprintln!("{}: Failed to load bytecode: {}", "error".bright_red().bold(), e);
@@ -70,13 +84,11 @@ fn main() -> Result<()> {
     // Execute the program
     match interpreter.execute() {
         Ok(result) => {
-            if verbose {
-                println!();
-                println!("{}", "Program executed successfully".bright_green().bold());
-                println!("Result: {}", result.to_string());
-            }
+            debug!("{}", "Program executed successfully".bright_green().bold());
+            debug!("Result: {}", result.to_string());
             Ok(())
         }
+        Err(BuluError::ExitRequested(code)) => process::exit(code),
         Err(e) => {
             eprintln!("{}: Runtime error: {}", "error".bright_red().bold(), e);
             process::exit(1);
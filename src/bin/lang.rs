@@ -2,18 +2,25 @@
 //!
 //! High-level command-line tool for Bulu project management
 
-use bulu::build::{run_executable, BuildOptions, Builder};
+use bulu::build::{run_executable, BuildOptions, Builder, CheckOptions, Checker};
 use bulu::compiler::symbol_resolver::SymbolType;
-use bulu::compiler::{IrGenerator, SemanticAnalyzer, SymbolResolver};
+use bulu::config::Config;
+use bulu::logging::{self, Verbosity};
+use bulu::terminal::{self, ColorChoice};
+use bulu::compiler::{IrGenerator, Optimizer, SemanticAnalyzer, SymbolResolver};
 use bulu::docs::{DocFormat, DocGenerator, DocOptions};
 use bulu::formatter::{create_default_format_config, load_format_config, Formatter};
 use bulu::lexer::Lexer;
-use bulu::linter::{create_default_lint_config, load_lint_config, Linter};
+use bulu::linter::{create_default_lint_config, load_lint_config, LintPolicy, Linter};
 use bulu::package::commands::{PackageManager, PackageOptions};
+use bulu::package::lockfile::{LockFile, LockFileManager, RootPackageInfo};
+use bulu::package::resolver::{select_version, ResolutionMode};
 use bulu::parser::Parser;
-use bulu::project::{create_project, Project};
-use bulu::runtime::{ast_interpreter::AstInterpreter, Interpreter};
+use bulu::project::{create_project, DependencySpec, Project};
+use bulu::runtime::{ast_interpreter::AstInterpreter, simplify::simplify, Interpreter};
 use bulu::testing::{BenchmarkRunner, TestOptions, TestRunner};
+use bulu::todo::{GroupBy, TodoOptions, TodoScanner};
+use bulu::changelog::{ChangeEntry, ChangeSource, ChangelogManager};
 use bulu::types::{primitive::RuntimeValue, TypeChecker};
 use bulu::{BuluError, Result};
 use clap::{Arg, Command};
@@ -21,11 +28,22 @@ use colored::*;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process;
+use tracing::{debug, error, info, warn, Level};
 
 fn main() -> Result<()> {
     let matches = Command::new("lang")
         .version(bulu::VERSION)
         .about("Bulu Language Tool - High-level project management")
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("KEY=VALUE")
+                .help("Override a configuration setting, e.g. fmt.indent_size=2")
+                .global(true)
+                .action(clap::ArgAction::Append),
+        )
+        .arg(color_arg().global(true))
+        .args(verbosity_args().map(|arg| arg.global(true)))
         .subcommand(
             Command::new("build")
                 .about("Build the current project")
@@ -35,18 +53,29 @@ fn main() -> Result<()> {
                         .help("Build in release mode")
                         .action(clap::ArgAction::SetTrue),
                 )
-                .arg(
-                    Arg::new("verbose")
-                        .short('v')
-                        .long("verbose")
-                        .help("Verbose output")
-                        .action(clap::ArgAction::SetTrue),
-                )
                 .arg(
                     Arg::new("target")
                         .long("target")
                         .help("Target architecture")
                         .value_name("TARGET"),
+                )
+                .arg(
+                    Arg::new("deny")
+                        .long("deny")
+                        .help("Fail on a lint: 'warnings' for all of them, or a rule ID such as unused-import")
+                        .value_name("LINT")
+                        .action(clap::ArgAction::Append),
+                ),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Type check the current project without generating code")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("How to report diagnostics")
+                        .value_parser(["human", "json"])
+                        .default_value("human"),
                 ),
         )
         .subcommand(
@@ -72,19 +101,42 @@ fn main() -> Result<()> {
                         .help("Run in release mode (only for source)")
                         .action(clap::ArgAction::SetTrue),
                 )
+                .arg(
+                    Arg::new("profile-heap")
+                        .long("profile-heap")
+                        .help("Run from source and write allocations per call site to heap-profile.folded (flamegraph format)")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("profile-cpu")
+                        .long("profile-cpu")
+                        .help("Run from source, sampling Bulu call stacks into cpu-profile.speedscope.json")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("trace-args")
+                        .long("trace-args")
+                        .help("Run from source, showing the arguments of each call in stack traces")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("race")
+                        .long("race")
+                        .help("Run from source, reporting unsynchronized accesses to variables shared by goroutines")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("stack-stats")
+                        .long("stack-stats")
+                        .help("Run from source, then show the peak stack use of each goroutine")
+                        .action(clap::ArgAction::SetTrue),
+                )
                 .allow_external_subcommands(false)
                 .disable_help_subcommand(false),
         )
         .subcommand(
             Command::new("test")
                 .about("Run tests")
-                .arg(
-                    Arg::new("verbose")
-                        .short('v')
-                        .long("verbose")
-                        .help("Verbose test output")
-                        .action(clap::ArgAction::SetTrue),
-                )
                 .arg(
                     Arg::new("coverage")
                         .long("coverage")
@@ -96,6 +148,47 @@ fn main() -> Result<()> {
                         .long("filter")
                         .help("Filter tests by name")
                         .value_name("PATTERN"),
+                )
+                .arg(
+                    Arg::new("profile-cpu")
+                        .long("profile-cpu")
+                        .help("Sample Bulu call stacks and write cpu-profile.speedscope.json")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("backends")
+                        .long("backends")
+                        .help("Run the test programs on these backends and compare the results (interpreter, vm, native or all)")
+                        .value_name("LIST"),
+                )
+                .arg(
+                    Arg::new("no-cache")
+                        .long("no-cache")
+                        .help("Run every test file, even those whose inputs are unchanged since they last passed")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("conformance")
+                .about("Run the language conformance suite")
+                .arg(
+                    Arg::new("dir")
+                        .help("Directory of spec-tagged conformance programs")
+                        .value_name("DIR")
+                        .default_value(bulu::testing::conformance::DEFAULT_DIR),
+                )
+                .arg(
+                    Arg::new("feature")
+                        .long("feature")
+                        .help("Only run the programs tagged with this feature")
+                        .value_name("NAME"),
+                )
+                .arg(
+                    Arg::new("backend")
+                        .long("backend")
+                        .help("Backend to run the programs on (interpreter, vm or native)")
+                        .value_name("NAME")
+                        .default_value("interpreter"),
                 ),
         )
         .subcommand(
@@ -107,13 +200,6 @@ fn main() -> Result<()> {
                         .help("Check if files are formatted without modifying them")
                         .action(clap::ArgAction::SetTrue),
                 )
-                .arg(
-                    Arg::new("verbose")
-                        .short('v')
-                        .long("verbose")
-                        .help("Verbose output")
-                        .action(clap::ArgAction::SetTrue),
-                )
                 .arg(
                     Arg::new("init")
                         .long("init")
@@ -130,18 +216,18 @@ fn main() -> Result<()> {
                         .help("Automatically fix issues where possible")
                         .action(clap::ArgAction::SetTrue),
                 )
-                .arg(
-                    Arg::new("verbose")
-                        .short('v')
-                        .long("verbose")
-                        .help("Verbose output")
-                        .action(clap::ArgAction::SetTrue),
-                )
                 .arg(
                     Arg::new("init")
                         .long("init")
                         .help("Create a default .langlint.toml configuration file")
                         .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("deny")
+                        .long("deny")
+                        .help("Fail on a lint: 'warnings' for all of them, or a rule ID such as unused-import")
+                        .value_name("LINT")
+                        .action(clap::ArgAction::Append),
                 ),
         )
         .subcommand(
@@ -162,6 +248,26 @@ fn main() -> Result<()> {
                         .value_parser(["html", "markdown", "json"])
                         .default_value("html"),
                 )
+                .arg(
+                    Arg::new("document-private-items")
+                        .long("document-private-items")
+                        .help("Document items that are not exported as well")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("coverage")
+                        .long("coverage")
+                        .help("Report the share of exported items with doc comments per module instead of generating documentation")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("fail-under")
+                        .long("fail-under")
+                        .help("With --coverage, fail when the coverage is below this percentage")
+                        .value_name("PERCENT")
+                        .value_parser(clap::value_parser!(f64))
+                        .requires("coverage"),
+                )
                 .arg(
                     Arg::new("serve")
                         .long("serve")
@@ -177,13 +283,159 @@ fn main() -> Result<()> {
                 ),
         )
         .subcommand(
-            Command::new("clean").about("Clean build artifacts").arg(
-                Arg::new("verbose")
-                    .short('v')
-                    .long("verbose")
-                    .help("Verbose output")
-                    .action(clap::ArgAction::SetTrue),
-            ),
+            Command::new("todo")
+                .about("Report TODO, FIXME, HACK and XXX comments and @deprecated tags")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Report format")
+                        .value_parser(["markdown", "json"])
+                        .default_value("markdown"),
+                )
+                .arg(
+                    Arg::new("group-by")
+                        .long("group-by")
+                        .help("Group markdown reports by file or by author")
+                        .value_parser(["file", "author"])
+                        .default_value("file"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .help("Write the report to a file instead of stdout")
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("no-blame")
+                        .long("no-blame")
+                        .help("Do not attribute items with git blame")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("changelog")
+                .about("Manage CHANGELOG.md from conventional-commit entries")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("add")
+                        .about("Record a pending change in .changes/")
+                        .arg(
+                            Arg::new("type")
+                                .help("Change type, e.g. feat, fix, perf, docs")
+                                .required(true)
+                                .index(1),
+                        )
+                        .arg(
+                            Arg::new("description")
+                                .help("What changed")
+                                .required(true)
+                                .index(2),
+                        )
+                        .arg(
+                            Arg::new("scope")
+                                .long("scope")
+                                .help("Part of the project the change is about")
+                                .value_name("SCOPE"),
+                        )
+                        .arg(
+                            Arg::new("breaking")
+                                .long("breaking")
+                                .help("Mark the change as breaking")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("release")
+                        .about("Move the pending changes into a section of CHANGELOG.md")
+                        .arg(
+                            Arg::new("version")
+                                .long("version")
+                                .help("Version of the section (defaults to the version in lang.toml)")
+                                .value_name("VERSION"),
+                        )
+                        .arg(
+                            Arg::new("from-git")
+                                .long("from-git")
+                                .help("Use the conventional commits since the last tag instead of .changes/")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("check")
+                        .about("Check that CHANGELOG.md has a section for a version")
+                        .arg(
+                            Arg::new("version")
+                                .long("version")
+                                .help("Version to check (defaults to the version in lang.toml)")
+                                .value_name("VERSION"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("notes")
+                        .about("Print the release notes of a version")
+                        .arg(
+                            Arg::new("version")
+                                .help("Version (defaults to the version in lang.toml)")
+                                .index(1),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Inspect the layered configuration")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("show")
+                        .about("Show every setting with the layer it comes from"),
+                ),
+        )
+        .subcommand(
+            Command::new("cache")
+                .about("Inspect the package cache")
+                .subcommand_required(true)
+                .subcommand(Command::new("ls").about("List the cached package versions"))
+                .subcommand(Command::new("clean").about("Remove every cached package, quarantined ones included"))
+                .subcommand(
+                    Command::new("verify")
+                        .about("Check every cached package against its checksum, quarantining corrupted ones"),
+                ),
+        )
+        .subcommand(
+            Command::new("clean")
+                .about("Clean build artifacts")
+                .arg(
+                    Arg::new("profile")
+                        .long("profile")
+                        .value_name("PROFILE")
+                        .help("Only clean the artifacts of this profile (debug or release)"),
+                ),
+        )
+        .subcommand(
+            Command::new("nm")
+                .about("List the Bulu functions of an object file or executable")
+                .arg(
+                    Arg::new("file")
+                        .help("Object file or executable")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .help("Also list symbols that are not Bulu functions")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("demangle")
+                .about("Demangle the Bulu symbols in standard input, e.g. a stack trace")
+                .arg(
+                    Arg::new("symbols")
+                        .help("Symbols to demangle instead of standard input")
+                        .num_args(0..)
+                        .index(1),
+                ),
         )
         .subcommand(
             Command::new("new")
@@ -201,15 +453,7 @@ fn main() -> Result<()> {
                         .value_name("PATH"),
                 ),
         )
-        .subcommand(
-            Command::new("bench").about("Run benchmarks").arg(
-                Arg::new("verbose")
-                    .short('v')
-                    .long("verbose")
-                    .help("Verbose output")
-                    .action(clap::ArgAction::SetTrue),
-            ),
-        )
+        .subcommand(Command::new("bench").about("Run benchmarks"))
         .subcommand(
             Command::new("add")
                 .about("Add a dependency")
@@ -219,14 +463,7 @@ fn main() -> Result<()> {
                         .required(true)
                         .index(1),
                 )
-                .arg(Arg::new("version").help("Version constraint").index(2))
-                .arg(
-                    Arg::new("verbose")
-                        .short('v')
-                        .long("verbose")
-                        .help("Verbose output")
-                        .action(clap::ArgAction::SetTrue),
-                ),
+                .arg(Arg::new("version").help("Version constraint").index(2)),
         )
         .subcommand(
             Command::new("remove")
@@ -236,42 +473,19 @@ fn main() -> Result<()> {
                         .help("Package name")
                         .required(true)
                         .index(1),
-                )
-                .arg(
-                    Arg::new("verbose")
-                        .short('v')
-                        .long("verbose")
-                        .help("Verbose output")
-                        .action(clap::ArgAction::SetTrue),
                 ),
         )
         .subcommand(
-            Command::new("update").about("Update dependencies").arg(
-                Arg::new("verbose")
-                    .short('v')
-                    .long("verbose")
-                    .help("Verbose output")
-                    .action(clap::ArgAction::SetTrue),
-            ),
-        )
-        .subcommand(
-            Command::new("install").about("Install dependencies").arg(
-                Arg::new("verbose")
-                    .short('v')
-                    .long("verbose")
-                    .help("Verbose output")
-                    .action(clap::ArgAction::SetTrue),
-            ),
+            Command::new("update")
+                .about("Update dependencies")
+                .args(resolution_args()),
         )
         .subcommand(
-            Command::new("list").about("List dependencies").arg(
-                Arg::new("verbose")
-                    .short('v')
-                    .long("verbose")
-                    .help("Verbose output")
-                    .action(clap::ArgAction::SetTrue),
-            ),
+            Command::new("install")
+                .about("Install dependencies")
+                .args(resolution_args()),
         )
+        .subcommand(Command::new("list").about("List dependencies"))
         .subcommand(
             Command::new("search")
                 .about("Search for packages")
@@ -290,15 +504,24 @@ fn main() -> Result<()> {
                 ),
         )
         .subcommand(
-            Command::new("publish")
-                .about("Publish package to registry")
+            Command::new("info")
+                .about("Show a registry package's description, license, features and README")
                 .arg(
-                    Arg::new("verbose")
-                        .short('v')
-                        .long("verbose")
-                        .help("Verbose output")
-                        .action(clap::ArgAction::SetTrue),
+                    Arg::new("package")
+                        .help("Package name")
+                        .required(true)
+                        .index(1),
                 )
+                .arg(
+                    Arg::new("version")
+                        .long("version")
+                        .help("Version to show instead of the latest")
+                        .value_name("VERSION"),
+                ),
+        )
+        .subcommand(
+            Command::new("publish")
+                .about("Publish package to registry")
                 .arg(
                     Arg::new("dry-run")
                         .long("dry-run")
@@ -309,13 +532,6 @@ fn main() -> Result<()> {
         .subcommand(
             Command::new("vendor")
                 .about("Vendor dependencies locally")
-                .arg(
-                    Arg::new("verbose")
-                        .short('v')
-                        .long("verbose")
-                        .help("Verbose output")
-                        .action(clap::ArgAction::SetTrue),
-                )
                 .arg(
                     Arg::new("force")
                         .long("force")
@@ -325,16 +541,36 @@ fn main() -> Result<()> {
         )
         .get_matches();
 
+    // `--config`, `--color`, `-q` and `-v` may follow the subcommand, where clap records them
+    let config_matches = matches.subcommand().map_or(&matches, |(_, sub_matches)| sub_matches);
+    terminal::init(color_choice(config_matches)?);
+    logging::init(verbosity(config_matches)).map_err(BuluError::Other)?;
+    let overrides = config_matches
+        .get_many::<String>("config")
+        .into_iter()
+        .flatten()
+        .map(|setting| bulu::config::parse_override(setting))
+        .collect::<Result<Vec<_>>>()?;
+    bulu::config::set_cli_overrides(overrides);
+
     let result = match matches.subcommand() {
         Some(("build", sub_matches)) => {
             let release = sub_matches.get_flag("release");
-            let verbose = sub_matches.get_flag("verbose");
             let target = sub_matches.get_one::<String>("target").map(|s| s.as_str());
-            build_project(release, verbose, target)
+            build_project(release, target, &denied_lints(sub_matches))
+        }
+        Some(("check", sub_matches)) => {
+            let format = sub_matches.get_one::<String>("format").unwrap();
+            check_project(format)
         }
         Some(("run", sub_matches)) => {
             let release = sub_matches.get_flag("release");
             let is_source = sub_matches.get_flag("source");
+            let profile_heap = sub_matches.get_flag("profile-heap");
+            let profile_cpu = sub_matches.get_flag("profile-cpu");
+            let trace_args = sub_matches.get_flag("trace-args");
+            let race = sub_matches.get_flag("race");
+            let stack_stats = sub_matches.get_flag("stack-stats");
             
             // Get all positional arguments (file + args)
             let positional: Vec<String> = sub_matches
@@ -349,87 +585,160 @@ fn main() -> Result<()> {
                 Vec::new()
             };
             
-            run_project(file, release, is_source, args)
+            // Sampled call stacks, race detection and goroutine stacks are
+            // Bulu-level, so all of them run from source
+            let is_source = is_source || race || stack_stats;
+            let run = move || {
+                if profile_cpu {
+                    with_cpu_profile("bulu run", || run_project(file, release, true, profile_heap, trace_args, args))
+                } else {
+                    run_project(file, release, is_source, profile_heap, trace_args, args)
+                }
+            };
+            let run = move || if race { with_race_detector(run) } else { run() };
+            if stack_stats {
+                with_stack_stats(run)
+            } else {
+                run()
+            }
         }
         Some(("test", sub_matches)) => {
-            let verbose = sub_matches.get_flag("verbose");
             let coverage = sub_matches.get_flag("coverage");
             let filter = sub_matches.get_one::<String>("filter").map(|s| s.as_str());
-            run_tests(verbose, coverage, filter)
+            let incremental = !sub_matches.get_flag("no-cache");
+            if let Some(backends) = sub_matches.get_one::<String>("backends") {
+                run_differential_tests(backends, filter)
+            } else if sub_matches.get_flag("profile-cpu") {
+                with_cpu_profile("bulu test", || run_tests(coverage, filter, incremental))
+            } else {
+                run_tests(coverage, filter, incremental)
+            }
+        }
+        Some(("conformance", sub_matches)) => {
+            let dir = sub_matches.get_one::<String>("dir").unwrap();
+            let feature = sub_matches.get_one::<String>("feature").map(|s| s.as_str());
+            let backend = sub_matches.get_one::<String>("backend").unwrap();
+            run_conformance_suite(Path::new(dir), feature, backend)
         }
         Some(("fmt", sub_matches)) => {
             let check = sub_matches.get_flag("check");
-            let verbose = sub_matches.get_flag("verbose");
             let init = sub_matches.get_flag("init");
-            format_code(check, verbose, init)
+            format_code(check, init)
         }
         Some(("lint", sub_matches)) => {
             let fix = sub_matches.get_flag("fix");
-            let verbose = sub_matches.get_flag("verbose");
             let init = sub_matches.get_flag("init");
-            lint_code(fix, verbose, init)
+            lint_code(fix, init, &denied_lints(sub_matches))
+        }
+        Some(("todo", sub_matches)) => {
+            let format = sub_matches.get_one::<String>("format").unwrap();
+            let group_by = sub_matches.get_one::<String>("group-by").unwrap();
+            let output = sub_matches.get_one::<String>("output").map(PathBuf::from);
+            let blame = !sub_matches.get_flag("no-blame");
+            todo_report(format, group_by, output.as_deref(), blame)
+        }
+        Some(("doc", sub_matches)) if sub_matches.get_flag("coverage") => {
+            let format = sub_matches.get_one::<String>("format").unwrap();
+            let fail_under = sub_matches.get_one::<f64>("fail-under").copied();
+            doc_coverage(format, fail_under)
         }
         Some(("doc", sub_matches)) => {
             let output = sub_matches.get_one::<String>("output").unwrap();
             let format = sub_matches.get_one::<String>("format").unwrap();
             let serve = sub_matches.get_flag("serve");
+            let document_private_items = sub_matches.get_flag("document-private-items");
             let port = sub_matches
                 .get_one::<String>("port")
                 .unwrap()
                 .parse()
                 .unwrap_or(8080);
-            generate_docs(output, format, serve, port)
+            generate_docs(output, format, serve, port, document_private_items)
         }
+        Some(("changelog", sub_matches)) => match sub_matches.subcommand() {
+            Some(("add", add_matches)) => {
+                let kind = add_matches.get_one::<String>("type").unwrap();
+                let description = add_matches.get_one::<String>("description").unwrap();
+                let scope = add_matches.get_one::<String>("scope").map(|s| s.as_str());
+                changelog_add(kind, scope, add_matches.get_flag("breaking"), description)
+            }
+            Some(("release", release_matches)) => {
+                let version = release_matches.get_one::<String>("version").map(|s| s.as_str());
+                let source = if release_matches.get_flag("from-git") {
+                    ChangeSource::Git
+                } else {
+                    ChangeSource::Directory
+                };
+                changelog_release(version, source)
+            }
+            Some(("check", check_matches)) => {
+                changelog_check(check_matches.get_one::<String>("version").map(|s| s.as_str()))
+            }
+            Some(("notes", notes_matches)) => {
+                changelog_notes(notes_matches.get_one::<String>("version").map(|s| s.as_str()))
+            }
+            _ => unreachable!("a changelog subcommand is required"),
+        },
+        Some(("config", sub_matches)) => match sub_matches.subcommand() {
+            Some(("show", _)) => show_config(),
+            _ => unreachable!("a config subcommand is required"),
+        },
+        Some(("cache", sub_matches)) => match sub_matches.subcommand() {
+            Some(("ls", _)) => list_cache(),
+            Some(("clean", _)) => clean_cache(),
+            Some(("verify", _)) => verify_cache(),
+            _ => unreachable!("a cache subcommand is required"),
+        },
         Some(("clean", sub_matches)) => {
-            let verbose = sub_matches.get_flag("verbose");
-            clean_project(verbose)
+            let profile = sub_matches.get_one::<String>("profile").map(|s| s.as_str());
+            clean_project(profile)
+        }
+        Some(("nm", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").unwrap();
+            list_symbols(Path::new(file), sub_matches.get_flag("all"))
+        }
+        Some(("demangle", sub_matches)) => {
+            let symbols: Vec<&String> = sub_matches.get_many::<String>("symbols").into_iter().flatten().collect();
+            demangle_symbols(&symbols)
         }
         Some(("new", sub_matches)) => {
             let name = sub_matches.get_one::<String>("name").unwrap();
             let path = sub_matches.get_one::<String>("path").map(|s| Path::new(s));
             create_new_project(name, path)
         }
-        Some(("bench", sub_matches)) => {
-            let verbose = sub_matches.get_flag("verbose");
-            run_benchmarks(verbose)
-        }
+        Some(("bench", _)) => run_benchmarks(),
         Some(("add", sub_matches)) => {
             let package = sub_matches.get_one::<String>("package").unwrap();
             let version = sub_matches.get_one::<String>("version").map(|s| s.as_str());
-            let verbose = sub_matches.get_flag("verbose");
-            add_dependency(package, version, verbose)
+            add_dependency(package, version)
         }
         Some(("remove", sub_matches)) => {
             let package = sub_matches.get_one::<String>("package").unwrap();
-            let verbose = sub_matches.get_flag("verbose");
-            remove_dependency(package, verbose)
+            remove_dependency(package)
         }
         Some(("update", sub_matches)) => {
-            let verbose = sub_matches.get_flag("verbose");
-            update_dependencies(verbose)
+            update_dependencies(resolution_mode(sub_matches))
         }
         Some(("install", sub_matches)) => {
-            let verbose = sub_matches.get_flag("verbose");
-            install_dependencies(verbose)
-        }
-        Some(("list", sub_matches)) => {
-            let verbose = sub_matches.get_flag("verbose");
-            list_dependencies(verbose)
+            install_dependencies(resolution_mode(sub_matches))
         }
+        Some(("list", _)) => list_dependencies(),
         Some(("search", sub_matches)) => {
             let query = sub_matches.get_one::<String>("query").unwrap();
             let limit = sub_matches.get_one::<String>("limit").unwrap().parse().ok();
             search_packages(query, limit)
         }
+        Some(("info", sub_matches)) => {
+            let package = sub_matches.get_one::<String>("package").unwrap();
+            let version = sub_matches.get_one::<String>("version").map(|s| s.as_str());
+            package_info(package, version)
+        }
         Some(("publish", sub_matches)) => {
-            let verbose = sub_matches.get_flag("verbose");
             let dry_run = sub_matches.get_flag("dry-run");
-            publish_package(verbose, dry_run)
+            publish_package(dry_run)
         }
         Some(("vendor", sub_matches)) => {
-            let verbose = sub_matches.get_flag("verbose");
             let force = sub_matches.get_flag("force");
-            vendor_dependencies(verbose, force)
+            vendor_dependencies(force)
         }
         _ => {
             println!("No subcommand provided. Use 'lang --help' for usage information.");
@@ -439,6 +748,7 @@ fn main() -> Result<()> {
 
     match result {
         Ok(()) => Ok(()),
+        Err(BuluError::ExitRequested(code)) => process::exit(code),
         Err(e) => {
             eprintln!("{} {}", "Error:".red().bold(), e);
             process::exit(1);
@@ -446,19 +756,54 @@ fn main() -> Result<()> {
     }
 }
 
-fn build_project(release: bool, verbose: bool, target: Option<&str>) -> Result<()> {
+/// `--locked`, `--frozen` and `--minimal-versions`
+fn resolution_args() -> [Arg; 3] {
+    [
+        Arg::new("locked")
+            .long("locked")
+            .help("Fail if lang.lock would change")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("frozen")
+            .long("frozen")
+            .help("Like --locked, without contacting the registry")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("minimal-versions")
+            .long("minimal-versions")
+            .help("Choose the lowest version satisfying each requirement")
+            .action(clap::ArgAction::SetTrue),
+    ]
+}
+
+fn resolution_mode(matches: &clap::ArgMatches) -> ResolutionMode {
+    let frozen = matches.get_flag("frozen");
+    ResolutionMode {
+        locked: frozen || matches.get_flag("locked"),
+        offline: frozen,
+        minimal_versions: matches.get_flag("minimal-versions"),
+    }
+}
+
+/// Values of `--deny`
+fn denied_lints(matches: &clap::ArgMatches) -> Vec<String> {
+    matches
+        .get_many::<String>("deny")
+        .map(|lints| lints.cloned().collect())
+        .unwrap_or_default()
+}
+
+fn build_project(release: bool, target: Option<&str>, deny: &[String]) -> Result<()> {
     let project = Project::load_current()?;
 
+    // Parallelism, incremental builds and denied lints come from the layered
+    // configuration; `--deny` adds to the lints it denies
     let mut options = BuildOptions {
         release,
-        verbose,
         target: target.map(|s| s.to_string()),
-        ..BuildOptions::default()
+        ..Config::load(Some(&project.root))?.build_options()?
     };
-
-    // Override with project configuration
-    options.parallel = project.config.build.parallel;
-    options.incremental = project.config.build.incremental;
+    for lint in deny {
+        options.lint_policy.deny(lint);
+    }
 
     let builder = Builder::new(project, options);
     let result = builder.build()?;
@@ -520,7 +865,22 @@ fn find_project_entrypoint() -> Result<PathBuf> {
     ))
 }
 
-fn run_project(file: Option<&String>, _release: bool, is_source: bool, args: Vec<String>) -> Result<()> {
+fn run_project(
+    file: Option<&String>,
+    _release: bool,
+    is_source: bool,
+    profile_heap: bool,
+    trace_args: bool,
+    args: Vec<String>,
+) -> Result<()> {
+    // Heap profiles are per AST call site, so profiling runs from source
+    let heap_profile = if profile_heap {
+        Some(Path::new(HEAP_PROFILE_FILE))
+    } else {
+        None
+    };
+    let is_source = is_source || profile_heap || trace_args;
+
     if let Some(file_path) = file {
         // Run a specific file
         let path = Path::new(file_path);
@@ -530,7 +890,7 @@ fn run_project(file: Option<&String>, _release: bool, is_source: bool, args: Vec
 
         if is_source {
             // Treat as source code
-            execute_source_file_with_args(path, Some(args))?;
+            execute_source_file_with_args(path, Some(args), heap_profile, trace_args)?;
         } else {
             // Treat as bytecode (default)
             execute_bytecode_file(path)?;
@@ -540,7 +900,7 @@ fn run_project(file: Option<&String>, _release: bool, is_source: bool, args: Vec
         // No file specified - look for project entrypoint
         if is_source {
             let entrypoint = find_project_entrypoint()?;
-            execute_source_file_with_args(&entrypoint, Some(args))?;
+            execute_source_file_with_args(&entrypoint, Some(args), heap_profile, trace_args)?;
         } else {
             // Look for compiled bytecode in target/debug
             let bytecode_path = find_project_bytecode()?;
@@ -550,13 +910,105 @@ fn run_project(file: Option<&String>, _release: bool, is_source: bool, args: Vec
     }
 }
 
+/// Where `bulu run --profile-heap` writes its report
+const HEAP_PROFILE_FILE: &str = "heap-profile.folded";
+
+/// Where `--profile-cpu` writes its report
+const CPU_PROFILE_FILE: &str = "cpu-profile.speedscope.json";
+
+/// Run `body` under the CPU sampling profiler, writing the profile even if `body` fails
+fn with_cpu_profile<T>(name: &str, body: impl FnOnce() -> Result<T>) -> Result<T> {
+    use bulu::runtime::profiler::{start_cpu_profile, stop_cpu_profile, DEFAULT_SAMPLE_INTERVAL};
+
+    start_cpu_profile(DEFAULT_SAMPLE_INTERVAL).map_err(BuluError::Other)?;
+    let result = body();
+    if let Some(profile) = stop_cpu_profile() {
+        write_cpu_profile(Path::new(CPU_PROFILE_FILE), name, &profile)?;
+    }
+    result
+}
+
+/// Run `body` under the race detector. Races are reported as they are found;
+/// if there were any, the run fails with `RACE_EXIT_CODE` unless it failed already.
+fn with_race_detector<T>(body: impl FnOnce() -> Result<T>) -> Result<T> {
+    use bulu::runtime::race::{start_race_detector, stop_race_detector, RACE_EXIT_CODE};
+
+    start_race_detector().map_err(BuluError::Other)?;
+    let result = body();
+    let races = stop_race_detector().unwrap_or_default();
+    if races.is_empty() {
+        return result;
+    }
+    eprintln!("Found {} data race(s)", races.len());
+    match result {
+        Ok(_) | Err(BuluError::ExitRequested(0)) => Err(BuluError::ExitRequested(RACE_EXIT_CODE)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Run `body`, then list on stderr how much stack the goroutines used at most
+fn with_stack_stats<T>(body: impl FnOnce() -> Result<T>) -> Result<T> {
+    use bulu::runtime::stack::stack_usage;
+
+    let result = body();
+    eprintln!("Goroutine stacks (peak bytes, segments grown):");
+    for usage in stack_usage() {
+        eprintln!("  goroutine {:<6} {:>12} {:>6}", usage.goroutine, usage.peak, usage.segments);
+    }
+    result
+}
+
+/// Write a CPU profile in speedscope format and summarise it on stderr
+fn write_cpu_profile(path: &Path, name: &str, profile: &bulu::runtime::profiler::CpuProfile) -> Result<()> {
+    fs::write(path, profile.speedscope_json(name))
+        .map_err(|e| BuluError::Other(format!("Failed to write CPU profile: {}", e)))?;
+
+    eprintln!(
+        "CPU profile: {} samples every {:?} over {:.2?}, written to {}",
+        profile.total_samples(),
+        profile.interval(),
+        profile.duration(),
+        path.display()
+    );
+    let total = profile.total_samples().max(1) as f64;
+    for (function, own, cumulative) in profile.by_function().iter().take(10) {
+        eprintln!(
+            "  {:>6.1}% self {:>6.1}% total  {}",
+            *own as f64 * 100.0 / total,
+            *cumulative as f64 * 100.0 / total,
+            function
+        );
+    }
+    Ok(())
+}
+
 /// Execute a Bulu source file with full compilation pipeline
 fn execute_source_file(path: &Path) -> Result<RuntimeValue> {
-    execute_source_file_with_args(path, None)
+    execute_source_file_with_args(path, None, None, false)
 }
 
-/// Execute a Bulu source file with optional program arguments
-fn execute_source_file_with_args(path: &Path, extra_args: Option<Vec<String>>) -> Result<RuntimeValue> {
+/// The `prelude` setting of the package in the current directory, for files
+/// under its `src/`; files elsewhere have the prelude
+fn package_prelude(path: &Path) -> bool {
+    let Ok(project) = Project::load_current() else {
+        return true;
+    };
+    let in_package = match (path.canonicalize(), project.src_dir.canonicalize()) {
+        (Ok(path), Ok(src_dir)) => path.starts_with(src_dir),
+        _ => false,
+    };
+    !in_package || project.config.package.prelude
+}
+
+/// Execute a Bulu source file with optional program arguments, writing a heap
+/// profile to `heap_profile` when given; runtime errors carry a stack trace,
+/// with the arguments of each call if `trace_args` is set
+fn execute_source_file_with_args(
+    path: &Path,
+    extra_args: Option<Vec<String>>,
+    heap_profile: Option<&Path>,
+    trace_args: bool,
+) -> Result<RuntimeValue> {
     // Initialize program arguments for os module
     let file_path_str = path.to_string_lossy().to_string();
     let mut program_args = vec![file_path_str.clone()];
@@ -575,16 +1027,15 @@ fn execute_source_file_with_args(path: &Path, extra_args: Option<Vec<String>>) -
     // Get file path for module resolution
     let file_path = path.to_string_lossy().to_string();
 
-    // Tokenize
-    let mut lexer = Lexer::new(&source);
-    let tokens = lexer.tokenize()?;
-
-    // Parse
-    let mut parser = Parser::new(tokens);
-    let mut ast = parser.parse()?;
+    // Tokenize and parse, streaming tokens from the lexer
+    let lexer = Lexer::new(&source);
+    let mut parser = Parser::new(Vec::new());
+    let mut ast = parser.parse_stream(lexer)?;
 
     // Symbol resolution for imports/exports
+    let prelude = package_prelude(path);
     let mut symbol_resolver = SymbolResolver::new();
+    symbol_resolver.set_prelude(prelude);
     symbol_resolver.set_current_module(file_path.clone());
 
     // Set the current directory for the module resolver to the file's directory
@@ -597,9 +1048,15 @@ fn execute_source_file_with_args(path: &Path, extra_args: Option<Vec<String>>) -
 
     symbol_resolver.resolve_program(&mut ast)?;
 
+    // Constant evaluation
+    let mut optimizer = Optimizer::new();
+    optimizer.set_file_path(Some(file_path.clone()));
+    let mut ast = optimizer.optimize(ast)?;
+
     // Type checking
     let mut type_checker = TypeChecker::new();
     type_checker.set_file_path(Some(file_path.clone()));
+    type_checker.set_prelude(prelude);
 
     // Import symbols from the symbol resolver
     type_checker.import_symbols_from_resolver(&symbol_resolver);
@@ -612,19 +1069,60 @@ fn execute_source_file_with_args(path: &Path, extra_args: Option<Vec<String>>) -
 
     type_checker.check(&ast)?;
 
+    // Fold constants and prune dead branches once, instead of on every evaluation
+    simplify(&mut ast);
+
     // Use AST interpreter for better module support
     use bulu::runtime::ast_interpreter::AstInterpreter;
     let mut ast_interpreter = AstInterpreter::with_file(file_path.clone());
+    if heap_profile.is_some() {
+        ast_interpreter.enable_heap_profile();
+    }
+    ast_interpreter.set_capture_arguments(trace_args);
     
     // Execute the program (defines functions, imports, etc.)
     ast_interpreter.execute_program(&ast)?;
     
     // Call main() if it exists
-    if let Some(main_func) = ast_interpreter.get_function_definition("main") {
+    let result = if let Some(main_func) = ast_interpreter.get_function_definition("main") {
         ast_interpreter.call_user_function(&main_func, &[])
     } else {
         Ok(RuntimeValue::Null)
+    };
+    // An exit in a goroutine that main did not observe still ends the program
+    let result = match (result, ast_interpreter.exit_requested()) {
+        (Ok(_), Some(code)) => Err(BuluError::ExitRequested(code)),
+        (result, _) => result,
+    };
+    // atExit hooks run when main returns and when the program exits
+    let result = match result {
+        Ok(_) | Err(BuluError::ExitRequested(_)) => ast_interpreter.run_exit_hooks().and(result),
+        result => result,
+    };
+    let result = result.map_err(|e| ast_interpreter.with_stack_trace(e));
+
+    if let (Some(report_path), Some(profile)) = (heap_profile, ast_interpreter.heap_profile()) {
+        write_heap_profile(report_path, &profile)?;
+    }
+    result
+}
+
+/// Write a heap profile in folded stack format and summarise it on stderr
+fn write_heap_profile(path: &Path, profile: &bulu::runtime::memory::HeapProfile) -> Result<()> {
+    fs::write(path, profile.folded())
+        .map_err(|e| BuluError::Other(format!("Failed to write heap profile: {}", e)))?;
+
+    let total = profile.total();
+    eprintln!(
+        "Heap profile: {} allocations, {} bytes, written to {}",
+        total.count,
+        total.bytes,
+        path.display()
+    );
+    for (function, site) in profile.by_function().iter().take(10) {
+        eprintln!("  {:>12} bytes {:>8} allocs  {}", site.bytes, site.count, function);
     }
+    Ok(())
 }
 
 /// Execute a Bulu executable or bytecode file
@@ -715,7 +1213,7 @@ fn execute_bytecode_with_interpreter(path: &Path) -> Result<RuntimeValue> {
 
     // For now, we'll create a simple bytecode execution
     // This is a placeholder - in a real implementation, we'd parse and execute the bytecode
-    println!("Executing Bulu bytecode...");
+    debug!("Executing Bulu bytecode...");
 
     // Parse the bytecode and execute it
     execute_simple_bytecode(&bytecode)
@@ -831,6 +1329,16 @@ fn find_project_bytecode() -> Result<PathBuf> {
         return Ok(debug_bytecode_path);
     }
 
+    // Executables built by `lang build` in target/<profile>/<target>
+    if let Ok(project) = Project::load_current() {
+        for release in [false, true] {
+            let path = project.executable_path(release);
+            if path.exists() {
+                return Ok(path);
+            }
+        }
+    }
+
     // Then look for native executables - these are generated in release mode
     let executable_path = current_dir.join(project_name);
     if executable_path.exists() {
@@ -880,13 +1388,13 @@ fn find_project_bytecode() -> Result<PathBuf> {
     )))
 }
 
-fn run_tests(verbose: bool, coverage: bool, filter: Option<&str>) -> Result<()> {
+fn run_tests(coverage: bool, filter: Option<&str>, incremental: bool) -> Result<()> {
     let project = Project::load_current()?;
 
     let options = TestOptions {
-        verbose,
         coverage,
         filter: filter.map(|s| s.to_string()),
+        incremental,
         ..TestOptions::default()
     };
 
@@ -904,7 +1412,46 @@ fn run_tests(verbose: bool, coverage: bool, filter: Option<&str>) -> Result<()>
     Ok(())
 }
 
-fn format_code(check: bool, verbose: bool, init: bool) -> Result<()> {
+/// Run the project's test programs on several backends and report where
+/// their results differ
+fn run_differential_tests(backends: &str, filter: Option<&str>) -> Result<()> {
+    use bulu::testing::differential::{Backend, DifferentialRunner};
+
+    let runner = DifferentialRunner::new(Backend::parse_list(backends)?);
+    let project = Project::load_current()?;
+    let programs: Vec<PathBuf> = project
+        .test_files()?
+        .into_iter()
+        .filter(|path| filter.map_or(true, |filter| path.to_string_lossy().contains(filter)))
+        .collect();
+
+    let mismatches = runner.run_files(&programs);
+    if mismatches > 0 {
+        return Err(BuluError::Other(format!("{} programs differ between backends", mismatches)));
+    }
+    Ok(())
+}
+
+/// Run the conformance programs under `dir` and report the results by feature
+fn run_conformance_suite(dir: &Path, feature: Option<&str>, backend: &str) -> Result<()> {
+    use bulu::testing::conformance::ConformanceRunner;
+    use bulu::testing::differential::Backend;
+
+    let mut runner = ConformanceRunner::new().with_backend(Backend::parse(backend)?);
+    if let Some(feature) = feature {
+        runner = runner.with_feature(feature);
+    }
+
+    let report = runner.run_dir(dir)?;
+    report.print();
+    let failures = report.failures().len();
+    if failures > 0 {
+        return Err(BuluError::Other(format!("{} conformance programs failed", failures)));
+    }
+    Ok(())
+}
+
+fn format_code(check: bool, init: bool) -> Result<()> {
     if init {
         // Create default configuration file
         let current_dir = std::env::current_dir()
@@ -916,7 +1463,6 @@ fn format_code(check: bool, verbose: bool, init: bool) -> Result<()> {
 
     let mut options = load_format_config(&project.root)?;
     options.check_only = check;
-    options.verbose = verbose;
 
     let formatter = Formatter::new(project, options);
     let results = formatter.format_project()?;
@@ -931,7 +1477,31 @@ fn format_code(check: bool, verbose: bool, init: bool) -> Result<()> {
     Ok(())
 }
 
-fn lint_code(fix: bool, verbose: bool, init: bool) -> Result<()> {
+fn check_project(format: &str) -> Result<()> {
+    let project = Project::load_current()?;
+
+    // Checks share the build's parallelism and incremental settings
+    let build_options = Config::load(Some(&project.root))?.build_options()?;
+    let options = CheckOptions {
+        parallel: build_options.parallel,
+        incremental: build_options.incremental,
+    };
+
+    let report = Checker::new(project, options).check()?;
+    match format {
+        "json" => println!("{}", report.to_json()),
+        _ => report.print_human(),
+    }
+
+    if !report.success() {
+        // The diagnostics were already reported
+        return Err(BuluError::ExitRequested(1));
+    }
+
+    Ok(())
+}
+
+fn lint_code(fix: bool, init: bool, deny: &[String]) -> Result<()> {
     if init {
         // Create default configuration file
         let current_dir = std::env::current_dir()
@@ -943,7 +1513,7 @@ fn lint_code(fix: bool, verbose: bool, init: bool) -> Result<()> {
 
     let mut options = load_lint_config(&project.root)?;
     options.fix = fix;
-    options.verbose = verbose;
+    options.policy = LintPolicy::from_deny(deny);
 
     let linter = Linter::new(project, options);
     let result = linter.lint_project()?;
@@ -958,7 +1528,32 @@ fn lint_code(fix: bool, verbose: bool, init: bool) -> Result<()> {
     Ok(())
 }
 
-fn generate_docs(output: &str, format: &str, serve: bool, port: u16) -> Result<()> {
+fn todo_report(format: &str, group_by: &str, output: Option<&Path>, blame: bool) -> Result<()> {
+    let project = Project::load_current()?;
+
+    let report = TodoScanner::new(project, TodoOptions { blame }).scan()?;
+    let group_by = match group_by {
+        "author" => GroupBy::Author,
+        _ => GroupBy::File,
+    };
+    let text = match format {
+        "json" => report.to_json(),
+        _ => report.to_markdown(group_by),
+    };
+
+    match output {
+        Some(path) => {
+            fs::write(path, text)
+                .map_err(|e| BuluError::Other(format!("Failed to write {}: {}", path.display(), e)))?;
+            info!("{} {}, written to {}", "Finished".green().bold(), report.summary(), path.display());
+        }
+        None => println!("{}", text.trim_end()),
+    }
+
+    Ok(())
+}
+
+fn generate_docs(output: &str, format: &str, serve: bool, port: u16, document_private_items: bool) -> Result<()> {
     let project = Project::load_current()?;
 
     let doc_format = match format {
@@ -973,8 +1568,7 @@ fn generate_docs(output: &str, format: &str, serve: bool, port: u16) -> Result<(
         format: doc_format,
         serve,
         port,
-        verbose: true,
-        ..DocOptions::default()
+        document_private_items,
     };
 
     let generator = DocGenerator::new(project, options);
@@ -983,17 +1577,189 @@ fn generate_docs(output: &str, format: &str, serve: bool, port: u16) -> Result<(
     Ok(())
 }
 
-fn clean_project(verbose: bool) -> Result<()> {
+fn changelog_add(kind: &str, scope: Option<&str>, breaking: bool, description: &str) -> Result<()> {
     let project = Project::load_current()?;
+    let entry = ChangeEntry::new(kind, scope, breaking, description)?;
+    let path = ChangelogManager::new(&project.root).add(&entry)?;
+    let path = path.strip_prefix(&project.root).unwrap_or(&path);
+    info!("{} {} to {}", "Added".green().bold(), entry, path.display());
+    Ok(())
+}
 
-    let options = BuildOptions {
-        verbose,
-        ..BuildOptions::default()
-    };
+fn changelog_release(version: Option<&str>, source: ChangeSource) -> Result<()> {
+    let project = Project::load_current()?;
+    let version = version.unwrap_or(&project.config.package.version);
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
 
-    let builder = Builder::new(project, options);
-    builder.clean()?;
+    let section = ChangelogManager::new(&project.root).release(version, &date, source)?;
+    println!("{}", section.trim_end());
+    info!("{} the section for {} to CHANGELOG.md", "Added".green().bold(), version);
+    Ok(())
+}
+
+fn changelog_check(version: Option<&str>) -> Result<()> {
+    let project = Project::load_current()?;
+    let version = version.unwrap_or(&project.config.package.version);
+
+    match ChangelogManager::new(&project.root).release_notes(version)? {
+        Some(_) => {
+            info!("{} CHANGELOG.md has a section for {}", "Finished".green().bold(), version);
+            Ok(())
+        }
+        None => Err(BuluError::Other(
+            "CHANGELOG.md not found; create it with `lang changelog release`".to_string(),
+        )),
+    }
+}
+
+fn changelog_notes(version: Option<&str>) -> Result<()> {
+    let project = Project::load_current()?;
+    let version = version.unwrap_or(&project.config.package.version);
+
+    match ChangelogManager::new(&project.root).release_notes(version)? {
+        Some(notes) => {
+            println!("{}", notes);
+            Ok(())
+        }
+        None => Err(BuluError::Other("CHANGELOG.md not found".to_string())),
+    }
+}
+
+fn doc_coverage(format: &str, fail_under: Option<f64>) -> Result<()> {
+    let project = Project::load_current()?;
+
+    let report = DocGenerator::new(project, DocOptions::default()).coverage_report()?;
+    match format {
+        "json" => println!("{}", report.to_json()),
+        _ => print!("{}", report.to_text()),
+    }
+
+    if let Some(minimum) = fail_under {
+        if report.percentage < minimum {
+            eprintln!(
+                "{} documentation coverage {:.1}% is below {}%",
+                "error:".red().bold(),
+                report.percentage,
+                minimum
+            );
+            return Err(BuluError::ExitRequested(1));
+        }
+    }
+
+    Ok(())
+}
+
+fn show_config() -> Result<()> {
+    // Outside a project only the user, environment and command line layers apply
+    let project = Project::load_current().ok();
+    let config = Config::load(project.as_ref().map(|project| project.root.as_path()))?;
+    print!("{}", config.show());
+    Ok(())
+}
+
+/// The package cache of the current project's configuration, or of the user's
+/// outside a project
+fn package_cache() -> Result<bulu::package::PackageCache> {
+    let project = Project::load_current().ok();
+    let config = Config::load(project.as_ref().map(|project| project.root.as_path()))?;
+    Ok(bulu::package::PackageCache::new(config.package_config()?.cache_dir))
+}
+
+fn list_cache() -> Result<()> {
+    let cache = package_cache()?;
+    let entries = cache.entries()?;
+    if entries.is_empty() {
+        println!("The package cache at {} is empty", cache.root().display());
+    }
+    for entry in entries {
+        let size = entry.size.map_or_else(|| "missing".red().to_string(), |size| format!("{} bytes", size));
+        println!("{} {} {} {}", entry.name.cyan(), entry.version.green(), &entry.checksum[..12.min(entry.checksum.len())], size);
+    }
+    let quarantined = cache.quarantined()?;
+    if !quarantined.is_empty() {
+        println!("{} corrupted tarballs in {}", quarantined.len(), cache.root().join("quarantine").display());
+    }
+    Ok(())
+}
+
+fn clean_cache() -> Result<()> {
+    let cache = package_cache()?;
+    let freed = cache.clean()?;
+    info!("{} Removed {} bytes from {}", "Success".green().bold(), freed, cache.root().display());
+    Ok(())
+}
+
+fn verify_cache() -> Result<()> {
+    let cache = package_cache()?;
+    let report = cache.verify()?;
+    for checksum in &report.quarantined {
+        warn!("{} {} does not match its checksum and was quarantined", "Corrupted".red().bold(), checksum);
+    }
+    info!(
+        "{} {} cached packages intact, {} quarantined",
+        "Verified".green().bold(),
+        report.verified,
+        report.quarantined.len()
+    );
+    if report.quarantined.is_empty() {
+        Ok(())
+    } else {
+        Err(BuluError::ExitRequested(1))
+    }
+}
+
+fn clean_project(profile: Option<&str>) -> Result<()> {
+    let project = Project::load_current()?;
+
+    let builder = Builder::new(project, BuildOptions::default());
+    match profile {
+        Some(profile) => builder.clean_profile(profile)?,
+        None => builder.clean()?,
+    }
+
+    Ok(())
+}
+
+/// List the symbols of `file` as `nm` does, with Bulu functions demangled
+fn list_symbols(file: &Path, all: bool) -> Result<()> {
+    let output = process::Command::new("nm")
+        .arg(file)
+        .output()
+        .map_err(|e| BuluError::Other(format!("Failed to run nm: {}", e)))?;
+    if !output.status.success() {
+        return Err(BuluError::Other(format!(
+            "nm failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        )));
+    }
+
+    // Lines are `[address] type name`; undefined symbols have no address
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((prefix, symbol)) = line.rsplit_once(' ') else {
+            continue;
+        };
+        match bulu::compiler::mangle::demangle(symbol) {
+            Some(demangled) => println!("{} {}", prefix, demangled),
+            None if all => println!("{}", line),
+            None => {}
+        }
+    }
+    Ok(())
+}
 
+/// Print `symbols`, or each line of standard input, with Bulu symbols demangled
+fn demangle_symbols(symbols: &[&String]) -> Result<()> {
+    use std::io::BufRead;
+
+    if !symbols.is_empty() {
+        for symbol in symbols {
+            println!("{}", bulu::compiler::mangle::demangle_text(symbol));
+        }
+        return Ok(());
+    }
+    for line in std::io::stdin().lock().lines() {
+        println!("{}", bulu::compiler::mangle::demangle_text(&line?));
+    }
     Ok(())
 }
 
@@ -1017,17 +1783,9 @@ fn create_new_project(name: &str, path: Option<&Path>) -> Result<()> {
     Ok(())
 }
 
-fn run_benchmarks(verbose: bool) -> Result<()> {
+fn run_benchmarks() -> Result<()> {
     let project = Project::load_current()?;
 
-    if verbose {
-        println!(
-            "{} Running benchmarks for '{}'...",
-            "Benchmarking".green().bold(),
-            project.config.package.name
-        );
-    }
-
     let runner = BenchmarkRunner::new(project);
     runner.run_benchmarks()?;
 
@@ -1035,8 +1793,8 @@ fn run_benchmarks(verbose: bool) -> Result<()> {
 }
 // Package management functions
 
-fn add_dependency(package: &str, version: Option<&str>, verbose: bool) -> Result<()> {
-    use bulu::package::http_client::RegistryHttpClient;
+fn add_dependency(package: &str, version: Option<&str>) -> Result<()> {
+    use bulu::package::RegistrySettings;
     use std::fs;
     use std::io::Write;
 
@@ -1044,19 +1802,16 @@ fn add_dependency(package: &str, version: Option<&str>, verbose: bool) -> Result
         .map_err(|e| BuluError::Other(format!("Failed to create async runtime: {}", e)))?;
 
     rt.block_on(async {
-        if verbose {
-            println!("{} Adding dependency: {}", "Adding".green().bold(), package);
-        }
+        info!("{} Adding dependency: {}", "Adding".green().bold(), package);
+
+        bulu::package::PackageName::parse(package).map_err(BuluError::Other)?;
 
         // Load project
         let mut project = Project::load_current()?;
         
-        // Get registry URL from config or use default
-        let registry_url = std::env::var("BULU_REGISTRY")
-            .unwrap_or_else(|_| "https://bulu-language.onrender.com".to_string());
-
-        // Create HTTP client
-        let client = RegistryHttpClient::new(registry_url);
+        // Scoped packages may come from a private registry
+        let registries = RegistrySettings::load(Some(&project.config))?;
+        let client = registries.client_for(package)?;
 
         // Find the version to use
         let version_to_use = if let Some(v) = version {
@@ -1069,9 +1824,7 @@ fn add_dependency(package: &str, version: Option<&str>, verbose: bool) -> Result
                 .clone()
         };
 
-        if verbose {
-            println!("  {} Using version: {}", "→".blue(), version_to_use);
-        }
+        debug!("  {} Using version: {}", "→".blue(), version_to_use);
 
         // Add to dependencies in lang.toml
         let version_spec = if version.is_some() {
@@ -1093,9 +1846,7 @@ fn add_dependency(package: &str, version: Option<&str>, verbose: bool) -> Result
             .map_err(|e| BuluError::Other(format!("Failed to write lang.toml: {}", e)))?;
 
         // Download and install the package
-        if verbose {
-            println!("  {} Downloading {}...", "→".blue(), package);
-        }
+        debug!("  {} Downloading {}...", "→".blue(), package);
 
         let tarball = client.download_package(package, &version_to_use).await?;
 
@@ -1116,18 +1867,16 @@ fn add_dependency(package: &str, version: Option<&str>, verbose: bool) -> Result
         archive.unpack(&vendor_dir)
             .map_err(|e| BuluError::Other(format!("Failed to extract package: {}", e)))?;
 
-        println!("{} Added {} v{}", "Success".green().bold(), package, version_to_use);
+        info!("{} Added {} v{}", "Success".green().bold(), package, version_to_use);
 
         Ok(())
     })
 }
 
-fn remove_dependency(package: &str, verbose: bool) -> Result<()> {
+fn remove_dependency(package: &str) -> Result<()> {
     use std::fs;
 
-    if verbose {
-        println!("{} Removing dependency: {}", "Removing".red().bold(), package);
-    }
+    info!("{} Removing dependency: {}", "Removing".red().bold(), package);
 
     let mut project = Project::load_current()?;
 
@@ -1152,162 +1901,220 @@ fn remove_dependency(package: &str, verbose: bool) -> Result<()> {
             .map_err(|e| BuluError::Other(format!("Failed to remove vendor directory: {}", e)))?;
     }
 
-    println!("{} Removed dependency: {}", "Success".green().bold(), package);
+    info!("{} Removed dependency: {}", "Success".green().bold(), package);
 
     Ok(())
 }
 
-fn update_dependencies(verbose: bool) -> Result<()> {
-    use bulu::package::http_client::RegistryHttpClient;
-    use std::fs;
+fn update_dependencies(mode: ResolutionMode) -> Result<()> {
+    sync_dependencies(mode, true)
+}
+
+fn install_dependencies(mode: ResolutionMode) -> Result<()> {
+    sync_dependencies(mode, false)
+}
+
+/// Resolve the dependencies into lang.lock and install them into vendor/.
+/// Installing reuses lang.lock when it is current; updating always re-resolves.
+fn sync_dependencies(mode: ResolutionMode, update: bool) -> Result<()> {
+    use bulu::package::RegistrySettings;
 
     let rt = tokio::runtime::Runtime::new()
         .map_err(|e| BuluError::Other(format!("Failed to create async runtime: {}", e)))?;
 
     rt.block_on(async {
-        if verbose {
-            println!("{} Updating dependencies...", "Updating".blue().bold());
+        if update {
+            info!("{} Updating dependencies...", "Updating".blue().bold());
+        } else {
+            info!("{} Installing dependencies...", "Installing".blue().bold());
         }
 
         let project = Project::load_current()?;
         
         if project.config.dependencies.is_empty() {
-            println!("No dependencies to update");
+            info!("No dependencies to {}", if update { "update" } else { "install" });
             return Ok(());
         }
 
-        let registry_url = std::env::var("BULU_REGISTRY")
-            .unwrap_or_else(|_| "https://bulu-language.onrender.com".to_string());
-
-        let client = RegistryHttpClient::new(registry_url);
-
-        let mut updated = 0;
-
-        for (name, _spec) in &project.config.dependencies {
-            if verbose {
-                println!("  {} Updating {}...", "→".blue(), name);
-            }
-
-            // Get latest version
-            let versions = client.get_package_versions(name).await?;
-            let latest_version = versions.last()
-                .ok_or_else(|| BuluError::Other(format!("No versions found for {}", name)))?;
-
-            // Download
-            let tarball = client.download_package(name, latest_version).await?;
-
-            // Remove old version
-            let vendor_dir = project.root.join("vendor").join(name);
-            if vendor_dir.exists() {
-                fs::remove_dir_all(&vendor_dir)
-                    .map_err(|e| BuluError::Other(format!("Failed to remove old version: {}", e)))?;
-            }
-
-            // Extract new version
-            fs::create_dir_all(&vendor_dir)
-                .map_err(|e| BuluError::Other(format!("Failed to create vendor directory: {}", e)))?;
+        let mode = mode.with_config(&project.config.resolver);
+        let registries = RegistrySettings::load(Some(&project.config))?;
 
-            use flate2::read::GzDecoder;
-            use tar::Archive;
-            use std::io::Cursor;
-
-            let cursor = Cursor::new(tarball);
-            let decoder = GzDecoder::new(cursor);
-            let mut archive = Archive::new(decoder);
-            
-            archive.unpack(&vendor_dir)
-                .map_err(|e| BuluError::Other(format!("Failed to extract package: {}", e)))?;
+        for warning in project.config.patch_warnings() {
+            warn!("{} {}", "Warning".yellow().bold(), warning);
+        }
 
-            if verbose {
-                println!("    {} {} v{}", "✓".green(), name, latest_version);
-            }
+        let lock_file = lock_dependencies(&project, &registries, mode, update).await?;
+        let installed = install_locked_dependencies(&project, &registries, &lock_file, mode, update).await?;
 
-            updated += 1;
+        if update {
+            info!("{} Updated {} dependencies", "Success".green().bold(), installed);
+        } else {
+            info!("{} Installed {} dependencies", "Success".green().bold(), installed);
         }
 
-        println!("{} Updated {} dependencies", "Success".green().bold(), updated);
-
         Ok(())
     })
 }
 
-fn install_dependencies(verbose: bool) -> Result<()> {
-    use bulu::package::http_client::RegistryHttpClient;
-    use std::fs;
-
-    let rt = tokio::runtime::Runtime::new()
-        .map_err(|e| BuluError::Other(format!("Failed to create async runtime: {}", e)))?;
+/// Resolve the registry dependencies of `project` and save them to lang.lock,
+/// reusing the current lock file unless `update` is set. Patched packages are
+/// built from their patch and left out.
+async fn lock_dependencies(
+    project: &Project,
+    registries: &bulu::package::RegistrySettings,
+    mode: ResolutionMode,
+    update: bool,
+) -> Result<LockFile> {
+    use bulu::package::{DependencySource, ResolvedDependency, VersionConstraint};
+    use std::collections::HashMap;
+
+    let dependencies: HashMap<String, DependencySpec> = project
+        .config
+        .dependencies
+        .iter()
+        .filter(|(name, _)| !project.config.patch.contains_key(*name))
+        .map(|(name, spec)| (name.clone(), spec.clone()))
+        .collect();
+
+    let lock_manager = LockFileManager::new(&project.root);
+    let existing = if lock_manager.exists() {
+        Some(lock_manager.load_or_create()?)
+    } else {
+        None
+    };
 
-    rt.block_on(async {
-        if verbose {
-            println!("{} Installing dependencies...", "Installing".blue().bold());
+    if let Some(lock) = existing.as_ref().filter(|lock| lock.is_up_to_date(&dependencies)) {
+        // Minimal versions are only checked when the registry can be reached
+        if mode.offline || (!update && !mode.minimal_versions) {
+            return Ok(lock.clone());
         }
+    }
+    mode.check_online()?;
+
+    let strategy = mode.strategy();
+    let mut resolved = HashMap::new();
+    for (name, spec) in &dependencies {
+        let requirement = match spec {
+            DependencySpec::Simple(v) => v.clone(),
+            DependencySpec::Detailed { version, .. } => version.clone().unwrap_or_else(|| "*".to_string()),
+        };
+        let constraint = VersionConstraint::parse(&requirement)
+            .map_err(|e| BuluError::Other(format!("Invalid version constraint for {}: {}", name, e)))?;
+
+        let registry = registries.registry_for(name)?;
+        let client = registry.client();
+        let versions = client.get_package_versions(name).await?;
+        let version = select_version(&versions, &constraint, &strategy)
+            .ok_or_else(|| BuluError::Other(format!("No version of {} satisfies {}", name, requirement)))?;
+        // Installs check the tarball against it before unpacking
+        let checksum = client.verified_checksum(name, version).await?;
+
+        resolved.insert(name.clone(), ResolvedDependency {
+            name: name.clone(),
+            version: version.clone(),
+            source: DependencySource::Registry { url: registry.url },
+            dependencies: HashMap::new(),
+            checksum: Some(checksum).filter(|checksum| !checksum.is_empty()),
+            patched: false,
+        });
+    }
 
-        let project = Project::load_current()?;
-        
-        if project.config.dependencies.is_empty() {
-            println!("No dependencies to install");
-            return Ok(());
-        }
+    let root_package = RootPackageInfo {
+        name: project.config.package.name.clone(),
+        version: project.config.package.version.clone(),
+    };
+    let lock_file = LockFile::from_resolved_dependencies(&resolved, Some(root_package));
 
-        let registry_url = std::env::var("BULU_REGISTRY")
-            .unwrap_or_else(|_| "https://bulu-language.onrender.com".to_string());
+    // Keep lang.lock untouched when nothing changed
+    if let Some(existing) = existing.as_ref().filter(|existing| lock_file.changes_from(existing).is_empty()) {
+        return Ok(existing.clone());
+    }
+    mode.check_lock(existing.as_ref(), &lock_file)?;
+    lock_manager.save(&lock_file)?;
 
-        let client = RegistryHttpClient::new(registry_url);
+    Ok(lock_file)
+}
 
-        let mut installed = 0;
+/// Install the registry packages of `lock_file` into vendor/, from the package
+/// cache when it has their checksum and downloading them otherwise. Offline,
+/// they have to be in vendor/ or the cache already.
+async fn install_locked_dependencies(
+    project: &Project,
+    registries: &bulu::package::RegistrySettings,
+    lock_file: &LockFile,
+    mode: ResolutionMode,
+    reinstall: bool,
+) -> Result<usize> {
+    use bulu::package::PackageCache;
+    use flate2::read::GzDecoder;
+    use std::io::Cursor;
+    use tar::Archive;
+
+    let cache = PackageCache::new(Config::load(Some(&project.root))?.package_config()?.cache_dir);
+    let mut installed = 0;
+
+    for dep in lock_file.get_registry_dependencies() {
+        let vendor_dir = project.root.join("vendor").join(&dep.name);
+        if mode.offline && vendor_dir.exists() {
+            installed += 1;
+            continue;
+        }
 
-        for (name, spec) in &project.config.dependencies {
-            if verbose {
-                println!("  {} Installing {}...", "→".blue(), name);
-            }
+        let cached = match &dep.checksum {
+            Some(checksum) => cache.get(checksum)?,
+            None => None,
+        };
+        if mode.offline && cached.is_none() {
+            return Err(BuluError::Other(format!(
+                "{} v{} is neither in vendor/ nor in the package cache, and --frozen prevents downloading it",
+                dep.name, dep.version
+            )));
+        }
 
-            // Parse version spec
-            let version_str = match spec {
-                bulu::project::DependencySpec::Simple(v) => v.clone(),
-                bulu::project::DependencySpec::Detailed { version, .. } => {
-                    version.clone().unwrap_or_else(|| "*".to_string())
+        debug!("  {} Installing {}...", "→".blue(), dep.name);
+
+        let tarball = match cached {
+            Some(tarball) => tarball,
+            None => {
+                let tarball = registries.client_for(&dep.name)?.download_verified(&dep.name, &dep.version).await?;
+                let checksum = sha256::digest(tarball.as_slice());
+                if let Some(expected) = dep.checksum.as_ref().filter(|expected| !expected.eq_ignore_ascii_case(&checksum)) {
+                    return Err(BuluError::Other(format!(
+                        "Checksum mismatch for {} v{}: lang.lock expects {}, the registry served {}",
+                        dep.name, dep.version, expected, checksum
+                    )));
                 }
-            };
-
-            // Get versions and find matching one
-            let versions = client.get_package_versions(name).await?;
-            let version_to_use = versions.last()
-                .ok_or_else(|| BuluError::Other(format!("No versions found for {}", name)))?;
-
-            // Download
-            let tarball = client.download_package(name, version_to_use).await?;
-
-            // Extract
-            let vendor_dir = project.root.join("vendor").join(name);
-            fs::create_dir_all(&vendor_dir)
-                .map_err(|e| BuluError::Other(format!("Failed to create vendor directory: {}", e)))?;
+                cache.insert(&dep.name, &dep.version, &tarball)?;
+                tarball
+            }
+        };
 
-            use flate2::read::GzDecoder;
-            use tar::Archive;
-            use std::io::Cursor;
+        // Remove old version
+        if reinstall && vendor_dir.exists() {
+            fs::remove_dir_all(&vendor_dir)
+                .map_err(|e| BuluError::Other(format!("Failed to remove old version: {}", e)))?;
+        }
 
-            let cursor = Cursor::new(tarball);
-            let decoder = GzDecoder::new(cursor);
-            let mut archive = Archive::new(decoder);
-            
-            archive.unpack(&vendor_dir)
-                .map_err(|e| BuluError::Other(format!("Failed to extract package: {}", e)))?;
+        // Extract
+        fs::create_dir_all(&vendor_dir)
+            .map_err(|e| BuluError::Other(format!("Failed to create vendor directory: {}", e)))?;
 
-            if verbose {
-                println!("    {} {} v{}", "✓".green(), name, version_to_use);
-            }
+        let cursor = Cursor::new(tarball);
+        let decoder = GzDecoder::new(cursor);
+        let mut archive = Archive::new(decoder);
+        
+        archive.unpack(&vendor_dir)
+            .map_err(|e| BuluError::Other(format!("Failed to extract package: {}", e)))?;
 
-            installed += 1;
-        }
+        debug!("    {} {} v{}", "✓".green(), dep.name, dep.version);
 
-        println!("{} Installed {} dependencies", "Success".green().bold(), installed);
+        installed += 1;
+    }
 
-        Ok(())
-    })
+    Ok(installed)
 }
 
-fn list_dependencies(verbose: bool) -> Result<()> {
+fn list_dependencies() -> Result<()> {
     let project = Project::load_current()?;
 
     if project.config.dependencies.is_empty() {
@@ -1338,7 +2145,7 @@ fn list_dependencies(verbose: bool) -> Result<()> {
             version_str.green()
         );
 
-        if verbose {
+        if tracing::enabled!(Level::DEBUG) {
             // Check if installed
             let vendor_path = project.root.join("vendor").join(name);
             if vendor_path.exists() {
@@ -1353,18 +2160,18 @@ fn list_dependencies(verbose: bool) -> Result<()> {
 }
 
 fn search_packages(query: &str, limit: Option<usize>) -> Result<()> {
-    use bulu::package::http_client::RegistryHttpClient;
+    use bulu::package::RegistrySettings;
 
     let rt = tokio::runtime::Runtime::new()
         .map_err(|e| BuluError::Other(format!("Failed to create async runtime: {}", e)))?;
 
     rt.block_on(async {
-        println!("{} Searching for: {}", "Searching".blue().bold(), query);
+        info!("{} Searching for: {}", "Searching".blue().bold(), query);
 
-        let registry_url = std::env::var("BULU_REGISTRY")
-            .unwrap_or_else(|_| "https://bulu-language.onrender.com".to_string());
-
-        let client = RegistryHttpClient::new(registry_url);
+        // Search the default registry, with the current project's registries if any
+        let project = Project::load_current().ok();
+        let registries = RegistrySettings::load(project.as_ref().map(|p| &p.config))?;
+        let client = registries.default_registry()?.client();
         let results = client.search(query, limit).await?;
 
         if results.packages.is_empty() {
@@ -1380,9 +2187,13 @@ fn search_packages(query: &str, limit: Option<usize>) -> Result<()> {
                 package.version.green(),
                 package.description.as_deref().unwrap_or("No description").dimmed()
             );
-            println!("    {} downloads", 
-                package.downloads.to_string().yellow()
-            );
+            match package.recent_downloads {
+                Some(recent) => println!("    {} downloads, {} this week",
+                    package.downloads.to_string().yellow(),
+                    recent.to_string().yellow()
+                ),
+                None => println!("    {} downloads", package.downloads.to_string().yellow()),
+            }
         }
 
         if results.total > results.packages.len() {
@@ -1393,8 +2204,72 @@ fn search_packages(query: &str, limit: Option<usize>) -> Result<()> {
     })
 }
 
-fn publish_package(verbose: bool, dry_run: bool) -> Result<()> {
-    use bulu::package::http_client::{RegistryHttpClient, PublishRequest};
+fn package_info(name: &str, version: Option<&str>) -> Result<()> {
+    use bulu::package::RegistrySettings;
+
+    let rt = tokio::runtime::Runtime::new()
+        .map_err(|e| BuluError::Other(format!("Failed to create async runtime: {}", e)))?;
+
+    rt.block_on(async {
+        // Packages are looked up where they would be installed from
+        let project = Project::load_current().ok();
+        let registries = RegistrySettings::load(project.as_ref().map(|p| &p.config))?;
+        let info = registries.client_for(name)?.get_readme(name, version).await?;
+        let config = info.project_config();
+
+        println!("{} {}", info.name.cyan().bold(), info.version.green());
+        if let Some(description) = &info.description {
+            println!("{}", description);
+        }
+        println!();
+        println!("  {:<12} {}", "License:".bold(), info.license.as_deref().unwrap_or("not specified"));
+        if let Some(package) = config.as_ref().map(|config| &config.package) {
+            if let Some(repository) = &package.repository {
+                println!("  {:<12} {}", "Repository:".bold(), repository);
+            }
+            if !package.authors.is_empty() {
+                println!("  {:<12} {}", "Authors:".bold(), package.authors.join(", "));
+            }
+            if let Some(keywords) = package.keywords.as_ref().filter(|keywords| !keywords.is_empty()) {
+                println!("  {:<12} {}", "Keywords:".bold(), keywords.join(", "));
+            }
+        }
+        if let Some(build) = config.as_ref().map(|config| &config.build) {
+            let features = if build.features.is_empty() {
+                "none".dimmed().to_string()
+            } else {
+                build.features.join(", ")
+            };
+            println!("  {:<12} {}", "Features:".bold(), features);
+        }
+
+        match &info.readme {
+            Some(readme) => {
+                println!();
+                let mut in_code = false;
+                for line in readme.lines() {
+                    // Headings stand out; the rest is printed as written
+                    if line.trim_start().starts_with("```") {
+                        in_code = !in_code;
+                    }
+                    if !in_code && line.starts_with('#') {
+                        println!("{}", line.trim_start_matches('#').trim().bold().underline());
+                    } else {
+                        println!("{}", line);
+                    }
+                }
+            }
+            None => println!("
+{}", "This version has no README.".dimmed()),
+        }
+
+        Ok(())
+    })
+}
+
+fn publish_package(dry_run: bool) -> Result<()> {
+    use bulu::package::http_client::PublishRequest;
+    use bulu::package::RegistrySettings;
     use std::fs;
     use std::io::Read;
 
@@ -1402,18 +2277,25 @@ fn publish_package(verbose: bool, dry_run: bool) -> Result<()> {
         .map_err(|e| BuluError::Other(format!("Failed to create async runtime: {}", e)))?;
 
     rt.block_on(async {
-        println!("{} Loading project configuration...", "→".blue());
+        debug!("{} Loading project configuration...", "→".blue());
         let project = Project::load_current()?;
-        println!("{} Project loaded: {}", "✓".green(), project.config.package.name);
+        debug!("{} Project loaded: {}", "✓".green(), project.config.package.name);
 
-        println!("{} Publishing package: {} v{}", 
+        info!("{} Publishing package: {} v{}", 
             "Publishing".blue().bold(), 
             project.config.package.name,
             project.config.package.version
         );
 
+        // A changelog must describe the version before it is published
+        let release_notes = ChangelogManager::new(&project.root)
+            .release_notes(&project.config.package.version)?;
+        if release_notes.is_none() {
+            warn!("  {} No CHANGELOG.md; publishing without release notes", "⚠".yellow());
+        }
+
         // Create tarball
-        println!("  {} Creating tarball...", "→".blue());
+        debug!("  {} Creating tarball...", "→".blue());
 
         let tarball_path = project.root.join(format!("{}-{}.tar.gz", 
             project.config.package.name, 
@@ -1434,27 +2316,34 @@ fn publish_package(verbose: bool, dry_run: bool) -> Result<()> {
         // Add src directory
         let src_dir = project.root.join("src");
         if src_dir.exists() {
-            println!("    {} Adding src directory: {}", "→".blue(), src_dir.display());
+            debug!("    {} Adding src directory: {}", "→".blue(), src_dir.display());
             builder.append_dir_all("src", &src_dir)
                 .map_err(|e| BuluError::Other(format!("Failed to add src: {}", e)))?;
-            println!("    {} src directory added", "✓".green());
+            debug!("    {} src directory added", "✓".green());
         } else {
-            println!("    {} src directory not found", "⚠".yellow());
+            warn!("    {} src directory not found", "⚠".yellow());
         }
 
         // Add lang.toml
-        println!("    {} Adding lang.toml", "→".blue());
+        debug!("    {} Adding lang.toml", "→".blue());
         builder.append_path_with_name(project.root.join("lang.toml"), "lang.toml")
             .map_err(|e| BuluError::Other(format!("Failed to add lang.toml: {}", e)))?;
-        println!("    {} lang.toml added", "✓".green());
+        debug!("    {} lang.toml added", "✓".green());
 
         // Add README if exists
         let readme_path = project.root.join("README.md");
         if readme_path.exists() {
-            println!("    {} Adding README.md", "→".blue());
+            debug!("    {} Adding README.md", "→".blue());
             builder.append_path_with_name(&readme_path, "README.md")
                 .map_err(|e| BuluError::Other(format!("Failed to add README: {}", e)))?;
-            println!("    {} README.md added", "✓".green());
+            debug!("    {} README.md added", "✓".green());
+        }
+
+        if release_notes.is_some() {
+            debug!("    {} Adding CHANGELOG.md", "→".blue());
+            builder.append_path_with_name(project.root.join("CHANGELOG.md"), "CHANGELOG.md")
+                .map_err(|e| BuluError::Other(format!("Failed to add CHANGELOG: {}", e)))?;
+            debug!("    {} CHANGELOG.md added", "✓".green());
         }
 
         let encoder = builder.into_inner()
@@ -1463,10 +2352,10 @@ fn publish_package(verbose: bool, dry_run: bool) -> Result<()> {
         encoder.finish()
             .map_err(|e| BuluError::Other(format!("Failed to finish gzip encoder: {}", e)))?;
 
-        println!("  {} Tarball created: {}", "✓".green(), tarball_path.display());
+        debug!("  {} Tarball created: {}", "✓".green(), tarball_path.display());
 
         // Read and encode tarball
-        println!("  {} Reading tarball...", "→".blue());
+        debug!("  {} Reading tarball...", "→".blue());
         let mut tarball_file = fs::File::open(&tarball_path)
             .map_err(|e| BuluError::Other(format!("Failed to read tarball: {}", e)))?;
         
@@ -1474,17 +2363,20 @@ fn publish_package(verbose: bool, dry_run: bool) -> Result<()> {
         tarball_file.read_to_end(&mut tarball_data)
             .map_err(|e| BuluError::Other(format!("Failed to read tarball data: {}", e)))?;
 
-        println!("  {} Tarball size: {} bytes", "✓".green(), tarball_data.len());
+        debug!("  {} Tarball size: {} bytes", "✓".green(), tarball_data.len());
 
         if dry_run {
             println!("Would publish: {} v{}", project.config.package.name, project.config.package.version);
             println!("  Tarball size: {} bytes", tarball_data.len());
+            if let Some(notes) = &release_notes {
+                println!("  Release notes:\n{}", notes);
+            }
             fs::remove_file(&tarball_path).ok();
             return Ok(());
         }
 
         // Prepare dependencies
-        println!("  {} Preparing package metadata...", "→".blue());
+        debug!("  {} Preparing package metadata...", "→".blue());
         let mut dependencies = std::collections::HashMap::new();
         for (name, spec) in &project.config.dependencies {
             let version_str = match spec {
@@ -1495,10 +2387,10 @@ fn publish_package(verbose: bool, dry_run: bool) -> Result<()> {
             };
             dependencies.insert(name.clone(), version_str);
         }
-        println!("  {} Dependencies: {}", "✓".green(), dependencies.len());
+        debug!("  {} Dependencies: {}", "✓".green(), dependencies.len());
 
         // Create publish request
-        println!("  {} Creating publish request...", "→".blue());
+        debug!("  {} Creating publish request...", "→".blue());
         let request = PublishRequest {
             name: project.config.package.name.clone(),
             version: project.config.package.version.clone(),
@@ -1509,33 +2401,34 @@ fn publish_package(verbose: bool, dry_run: bool) -> Result<()> {
             keywords: project.config.package.keywords.clone().unwrap_or_default(),
             dependencies,
             tarball: tarball_data,
+            release_notes,
         };
 
-        // Publish
-        let registry_url = std::env::var("BULU_REGISTRY")
-            .unwrap_or_else(|_| "https://bulu-language.onrender.com".to_string());
+        // Publish to the registry serving the package's scope
+        let registry = RegistrySettings::load(Some(&project.config))?.registry_for(&request.name)?;
 
-        println!("  {} Uploading to registry: {}", "→".blue(), registry_url);
-        println!("  {} Package: {} v{}", "→".blue(), request.name, request.version);
+        debug!("  {} Uploading to registry: {} ({})", "→".blue(), registry.name, registry.url);
+        debug!("  {} Package: {} v{}", "→".blue(), request.name, request.version);
 
-        let client = RegistryHttpClient::new(registry_url.clone());
+        // Scoped packages can only be published by the token owning their scope
+        let client = registry.client();
         
         match client.publish(request).await {
             Ok(_) => {
-                println!("  {} Upload successful!", "✓".green());
+                debug!("  {} Upload successful!", "✓".green());
             }
             Err(e) => {
-                eprintln!("  {} Upload failed: {}", "✗".red(), e);
+                error!("  {} Upload failed: {}", "✗".red(), e);
                 fs::remove_file(&tarball_path).ok();
                 return Err(e);
             }
         }
 
         // Clean up tarball
-        println!("  {} Cleaning up tarball...", "→".blue());
+        debug!("  {} Cleaning up tarball...", "→".blue());
         fs::remove_file(&tarball_path).ok();
 
-        println!("{} Published: {} v{}", 
+        info!("{} Published: {} v{}", 
             "Success".green().bold(), 
             project.config.package.name, 
             project.config.package.version
@@ -1545,7 +2438,7 @@ fn publish_package(verbose: bool, dry_run: bool) -> Result<()> {
     })
 }
 
-fn vendor_dependencies(verbose: bool, force: bool) -> Result<()> {
+fn vendor_dependencies(force: bool) -> Result<()> {
     let rt = tokio::runtime::Runtime::new()
         .map_err(|e| BuluError::Other(format!("Failed to create async runtime: {}", e)))?;
 
@@ -1554,11 +2447,48 @@ fn vendor_dependencies(verbose: bool, force: bool) -> Result<()> {
         let package_manager = PackageManager::new(project)?;
 
         let options = PackageOptions {
-            verbose,
             dry_run: false,
             force,
+            ..PackageOptions::default()
         };
 
         package_manager.vendor_dependencies(&options).await
     })
 }
+
+/// The `--color` option shared by the subcommands
+fn color_arg() -> Arg {
+    Arg::new("color")
+        .long("color")
+        .value_name("WHEN")
+        .help("When to color output: auto, always or never")
+        .value_parser(clap::builder::PossibleValuesParser::new(terminal::COLOR_CHOICES.iter().copied()))
+        .default_value("auto")
+}
+
+fn color_choice(matches: &clap::ArgMatches) -> Result<ColorChoice> {
+    matches
+        .get_one::<String>("color")
+        .map_or(Ok(ColorChoice::Auto), |choice| choice.parse())
+        .map_err(BuluError::Other)
+}
+
+/// `-q`/`--quiet` and `-v`/`--verbose`, repeatable for more detail
+fn verbosity_args() -> [Arg; 2] {
+    [
+        Arg::new("quiet")
+            .short('q')
+            .long("quiet")
+            .help("Only print warnings and errors")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("verbose")
+            .short('v')
+            .long("verbose")
+            .help("Print more details; -vv prints everything")
+            .action(clap::ArgAction::Count),
+    ]
+}
+
+fn verbosity(matches: &clap::ArgMatches) -> Verbosity {
+    Verbosity::from_flags(matches.get_flag("quiet"), matches.get_count("verbose"))
+}
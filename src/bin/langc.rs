@@ -3,12 +3,14 @@
 //! Command-line compiler for the Bulu programming language
 
 use bulu::compiler::{
-    CodeGenerator, IrGenerator, IrOptimizer, OptLevel as CompilerOptLevel, SemanticAnalyzer,
-    SymbolResolver,
+    CodeGenerator, IrGenerator, IrOptimizer, OptLevel as CompilerOptLevel, Optimizer,
+    SemanticAnalyzer, SymbolResolver,
 };
 use bulu::error_reporter::ErrorReporter;
 use bulu::lexer::Lexer;
+use bulu::logging::{self, Verbosity};
 use bulu::parser::Parser;
+use bulu::terminal;
 use bulu::types::TypeChecker;
 use bulu::{BuluError, Result};
 use clap::{Arg, ArgAction, Command};
@@ -16,6 +18,7 @@ use colored::Colorize;
 use std::fs;
 use std::path::PathBuf;
 use std::process;
+use tracing::{debug, trace};
 
 /// Optimization levels
 #[derive(Debug, Clone, Copy)]
@@ -97,6 +100,20 @@ impl Target {
     fn default() -> Self {
         Target::Native
     }
+
+    /// Name accepted by `--target`, with `native` resolved to the host
+    fn name(&self) -> String {
+        match self {
+            Target::LinuxAmd64 => "linux-amd64".to_string(),
+            Target::LinuxArm64 => "linux-arm64".to_string(),
+            Target::WindowsAmd64 => "windows-amd64".to_string(),
+            Target::WindowsArm64 => "windows-arm64".to_string(),
+            Target::DarwinAmd64 => "darwin-amd64".to_string(),
+            Target::DarwinArm64 => "darwin-arm64".to_string(),
+            Target::Wasm => "wasm".to_string(),
+            Target::Native => bulu::build::host_target(),
+        }
+    }
 }
 
 /// Compiler configuration
@@ -109,6 +126,10 @@ struct CompilerConfig {
     target: Target,
     debug: bool,
     static_link: bool,
+    /// Whether builtins are in scope without `import "std/builtin"`
+    prelude: bool,
+    /// Package the generated functions are named after
+    package: String,
 }
 
 fn main() -> Result<()> {
@@ -118,6 +139,31 @@ fn main() -> Result<()> {
         .long_about("Bulu Language Compiler (langc) compiles .bu source files into executable binaries or intermediate representations.")
         .subcommand_required(true)
         .arg_required_else_help(true)
+        .arg(
+            Arg::new("color")
+                .long("color")
+                .value_name("WHEN")
+                .help("When to color output: auto, always or never")
+                .value_parser(clap::builder::PossibleValuesParser::new(terminal::COLOR_CHOICES.iter().copied()))
+                .default_value("auto")
+                .global(true),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .help("Only print warnings and errors")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .help("Report each compilation step; -vv also dumps symbol tables")
+                .action(ArgAction::Count)
+                .global(true),
+        )
         .subcommand(
             Command::new("build")
                 .about("Compile Bulu source files")
@@ -171,12 +217,18 @@ fn main() -> Result<()> {
                         .action(ArgAction::SetTrue)
                 )
                 .arg(
-                    Arg::new("verbose")
-                        .short('v')
-                        .long("verbose")
-                        .help("Enable verbose output")
+                    Arg::new("no-prelude")
+                        .long("no-prelude")
+                        .help("Require builtins to be imported from std/builtin, as `prelude = false` in lang.toml does")
                         .action(ArgAction::SetTrue)
                 )
+                .arg(
+                    Arg::new("package")
+                        .long("package")
+                        .value_name("PATH")
+                        .help("Package path the symbols of generated functions name")
+                        .default_value(bulu::compiler::mangle::DEFAULT_PACKAGE)
+                )
         )
         .subcommand(
             Command::new("emit")
@@ -206,29 +258,28 @@ fn main() -> Result<()> {
         )
         .get_matches();
 
+    let color_matches = matches.subcommand().map_or(&matches, |(_, sub_matches)| sub_matches);
+    let color = color_matches.get_one::<String>("color").map_or("auto", |choice| choice.as_str());
+    terminal::init(color.parse().map_err(BuluError::Other)?);
+    let verbosity = Verbosity::from_flags(color_matches.get_flag("quiet"), color_matches.get_count("verbose"));
+    logging::init(verbosity).map_err(BuluError::Other)?;
+
     match matches.subcommand() {
         Some(("build", sub_matches)) => {
             let config = parse_build_config(sub_matches)?;
-            let verbose = sub_matches.get_flag("verbose");
-
-            if verbose {
-                println!("{}", "Bulu Language Compiler".bright_blue().bold());
-                println!("Version: {}", bulu::VERSION);
-                println!("Input: {}", config.input_file.display());
-                if let Some(ref output) = config.output_file {
-                    println!("Output: {}", output.display());
-                }
-                println!("Optimization: {:?}", config.opt_level);
-                println!("Target: {:?}", config.target);
-                println!();
+            debug!("{}", "Bulu Language Compiler".bright_blue().bold());
+            debug!("Version: {}", bulu::VERSION);
+            debug!("Input: {}", config.input_file.display());
+            if let Some(ref output) = config.output_file {
+                debug!("Output: {}", output.display());
             }
+            debug!("Optimization: {:?}", config.opt_level);
+            debug!("Target: {:?}", config.target);
 
             // Compile the source file or project
-            match compile(&config, verbose) {
+            match compile(&config) {
                 Ok(_) => {
-                    if verbose {
-                        println!("{}", "Compilation successful!".bright_green().bold());
-                    }
+                    debug!("{}", "Compilation successful!".bright_green().bold());
                     Ok(())
                 }
                 Err(_) => {
@@ -240,10 +291,8 @@ fn main() -> Result<()> {
         }
         Some(("emit", sub_matches)) => {
             let config = parse_emit_config(sub_matches)?;
-            let verbose = false; // emit commands are typically not verbose
-
             // Compile with emit mode
-            match compile(&config, verbose) {
+            match compile(&config) {
                 Ok(_) => Ok(()),
                 Err(_) => {
                     process::exit(1);
@@ -345,12 +394,12 @@ fn parse_build_config(matches: &clap::ArgMatches) -> Result<CompilerConfig> {
     let output_file = if let Some(output) = matches.get_one::<PathBuf>("output") {
         Some(output.clone())
     } else {
-        // Create output in target/debug or target/release directory (like Rust)
-
+        // Create output in target/<profile>/<target>, as `lang build` does
         let target_dir = std::env::current_dir()
             .map_err(|e| BuluError::IoError(format!("Cannot get current directory: {}", e)))?
             .join("target")
-            .join(build_mode);
+            .join(build_mode)
+            .join(target.name());
 
         // Create target directory if it doesn't exist
         if let Err(e) = std::fs::create_dir_all(&target_dir) {
@@ -375,6 +424,8 @@ fn parse_build_config(matches: &clap::ArgMatches) -> Result<CompilerConfig> {
         target,
         debug: matches.get_flag("debug"),
         static_link: matches.get_flag("static"),
+        prelude: !matches.get_flag("no-prelude"),
+        package: matches.get_one::<String>("package").unwrap().clone(),
     })
 }
 
@@ -410,10 +461,12 @@ fn parse_emit_config(matches: &clap::ArgMatches) -> Result<CompilerConfig> {
         target: Target::Native,
         debug: false,
         static_link: false,
+        prelude: true,
+        package: bulu::compiler::mangle::DEFAULT_PACKAGE.to_string(),
     })
 }
 
-fn compile(config: &CompilerConfig, verbose: bool) -> Result<()> {
+fn compile(config: &CompilerConfig) -> Result<()> {
     // Read source code
     let source = fs::read_to_string(&config.input_file).map_err(|e| {
         BuluError::IoError(format!(
@@ -429,29 +482,25 @@ fn compile(config: &CompilerConfig, verbose: bool) -> Result<()> {
         Some(config.input_file.to_string_lossy().to_string()),
     );
 
-    if verbose {
-        println!("{}", "Lexical analysis...".bright_yellow());
-    }
+    debug!("{}", "Lexical analysis...".bright_yellow());
 
     // Tokenization with file information
     let file_path = config.input_file.to_string_lossy().to_string();
     let mut lexer = Lexer::with_file(&source, file_path.clone());
-    let tokens = lexer.tokenize().map_err(|e| {
-        eprintln!("{}", error_reporter.format_error(&e));
-        e
-    })?;
-
     if matches!(config.emit_type, EmitType::Tokens) {
+        let tokens = lexer.tokenize().map_err(|e| {
+            eprintln!("{}", error_reporter.format_error(&e));
+            e
+        })?;
         return emit_tokens(&tokens, &config.output_file);
     }
 
-    if verbose {
-        println!("{}", "Parsing...".bright_yellow());
-    }
+    debug!("{}", "Parsing...".bright_yellow());
 
-    // Parsing with file information
-    let mut parser = Parser::with_file(tokens, file_path.clone());
-    let mut ast = parser.parse().map_err(|e| {
+    // Parsing with file information; tokens are streamed from the lexer one
+    // top-level declaration at a time
+    let mut parser = Parser::with_file(Vec::new(), file_path.clone());
+    let mut ast = parser.parse_stream(&mut lexer).map_err(|e| {
         eprintln!("{}", error_reporter.format_error(&e));
         e
     })?;
@@ -460,24 +509,22 @@ fn compile(config: &CompilerConfig, verbose: bool) -> Result<()> {
         return emit_ast(&ast, &config.output_file);
     }
 
-    if verbose {
-        println!("{}", "Symbol resolution...".bright_yellow());
-    }
+    debug!("{}", "Symbol resolution...".bright_yellow());
 
     // Symbol resolution for imports/exports
     let mut symbol_resolver = SymbolResolver::new();
+    symbol_resolver.set_prelude(config.prelude);
     symbol_resolver.set_current_module(file_path.clone());
     symbol_resolver.resolve_program(&mut ast).map_err(|e| {
         eprintln!("{}", error_reporter.format_error(&e));
         e
     })?;
 
-    if verbose {
-        println!("{}", "Symbol resolution...".bright_yellow());
-    }
+    debug!("{}", "Symbol resolution...".bright_yellow());
 
     // Symbol resolution for imports/exports
     let mut symbol_resolver = SymbolResolver::new();
+    symbol_resolver.set_prelude(config.prelude);
     symbol_resolver.set_current_module(file_path.clone());
 
     // Set the current directory for the module resolver
@@ -494,38 +541,38 @@ fn compile(config: &CompilerConfig, verbose: bool) -> Result<()> {
             e
         })?;
 
-    if verbose {
-        let symbol_table = symbol_resolver.symbol_table();
-        println!(
-            "Imported symbols: {:?}",
-            symbol_table.imported_symbols.keys().collect::<Vec<_>>()
-        );
-        println!(
-            "Local symbols: {:?}",
-            symbol_table.local_symbols.keys().collect::<Vec<_>>()
-        );
-    }
+    let symbol_table = symbol_resolver.symbol_table();
+    trace!(
+        "Imported symbols: {:?}",
+        symbol_table.imported_symbols.keys().collect::<Vec<_>>()
+    );
+    trace!(
+        "Local symbols: {:?}",
+        symbol_table.local_symbols.keys().collect::<Vec<_>>()
+    );
 
-    if verbose {
-        println!("{}", "Type checking...".bright_yellow());
-    }
+    debug!("{}", "Constant evaluation...".bright_yellow());
+
+    let mut optimizer = Optimizer::new();
+    optimizer.set_file_path(Some(file_path.clone()));
+    let ast = optimizer.optimize(ast).map_err(|e| {
+        eprintln!("{}", error_reporter.format_error(&e));
+        e
+    })?;
+
+    debug!("{}", "Type checking...".bright_yellow());
 
     // Type checking and semantic analysis with enhanced error reporting
     let mut type_checker = TypeChecker::new();
+    type_checker.set_prelude(config.prelude);
 
     // Import symbols from the symbol resolver
     type_checker.import_symbols_from_resolver(&symbol_resolver);
 
-    if verbose {
-        println!(
-            "TypeChecker global scope symbols: {:?}",
-            type_checker
-                .scopes
-                .first()
-                .map(|s| s.keys().collect::<Vec<_>>())
-                .unwrap_or_default()
-        );
-    }
+    trace!(
+        "TypeChecker global scope symbols: {:?}",
+        type_checker.scopes.globals().keys().collect::<Vec<_>>()
+    );
 
     type_checker.check(&ast).map_err(|e| {
         eprintln!("{}", error_reporter.format_error(&e));
@@ -538,9 +585,7 @@ fn compile(config: &CompilerConfig, verbose: bool) -> Result<()> {
         e
     })?;
 
-    if verbose {
-        println!("{}", "IR generation...".bright_yellow());
-    }
+    debug!("{}", "IR generation...".bright_yellow());
 
     // Combine main AST with all imported modules
     let combined_ast = combine_ast_with_imports(&ast, &symbol_resolver)?;
@@ -554,9 +599,7 @@ fn compile(config: &CompilerConfig, verbose: bool) -> Result<()> {
 
     // IR optimization
     if !matches!(config.opt_level, OptLevel::O0) {
-        if verbose {
-            println!("{}", "IR optimization...".bright_yellow());
-        }
+        debug!("{}", "IR optimization...".bright_yellow());
 
         let mut optimizer = IrOptimizer::new();
         let compiler_opt_level = match config.opt_level {
@@ -577,9 +620,7 @@ fn compile(config: &CompilerConfig, verbose: bool) -> Result<()> {
         return emit_ir(&ir_program, &config.output_file);
     }
 
-    if verbose {
-        println!("{}", "Code generation...".bright_yellow());
-    }
+    debug!("{}", "Code generation...".bright_yellow());
 
     // Code generation with enhanced error reporting
     let mut code_generator = CodeGenerator::new();
@@ -596,6 +637,7 @@ fn compile(config: &CompilerConfig, verbose: bool) -> Result<()> {
     code_generator.set_target(target_str);
     code_generator.set_debug(config.debug);
     code_generator.set_static_link(config.static_link);
+    code_generator.set_package(&config.package);
 
     match config.emit_type {
         EmitType::Assembly => {
@@ -821,6 +863,7 @@ fn combine_ast_with_imports(
 
     Ok(Program {
         statements: combined_statements,
+        attributes: main_ast.attributes.clone(),
         position: main_ast.position,
     })
 }
@@ -0,0 +1,223 @@
+//! Type checking a project without generating code (`lang check`)
+//!
+//! Every source file is lexed, parsed, resolved and type checked. Results are
+//! cached in `target/check/cache.toml`: a file is not checked again while it
+//! is unchanged, unless it imports project modules and another source file
+//! changed.
+
+use crate::compiler::CompileSession;
+use crate::project::Project;
+use crate::{BuluError, Result};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use super::Fingerprint;
+
+pub use crate::compiler::{Diagnostic, Stage};
+
+/// Check options
+#[derive(Debug, Clone)]
+pub struct CheckOptions {
+    /// Check files on several threads
+    pub parallel: bool,
+    /// Reuse the results of files that did not change
+    pub incremental: bool,
+}
+
+impl Default for CheckOptions {
+    fn default() -> Self {
+        Self {
+            parallel: true,
+            incremental: true,
+        }
+    }
+}
+
+/// Outcome of checking a project
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CheckReport {
+    pub files_checked: usize,
+    /// Files whose results came from the cache
+    pub files_cached: usize,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl CheckReport {
+    pub fn success(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("check reports serialize")
+    }
+
+    /// Print diagnostics and a summary for people
+    pub fn print_human(&self) {
+        for diagnostic in &self.diagnostics {
+            println!("{}", diagnostic.to_human());
+        }
+        if !self.diagnostics.is_empty() {
+            println!();
+        }
+
+        let mut summary = format!("Checked {} files", self.files_checked);
+        if self.files_cached > 0 {
+            summary.push_str(&format!(" ({} unchanged)", self.files_cached));
+        }
+        if self.success() {
+            println!("{} {}, no errors found", "Finished".green().bold(), summary);
+        } else {
+            println!("{} {}, {} errors", "Failed".red().bold(), summary, self.diagnostics.len());
+        }
+    }
+}
+
+/// Results recorded for one file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CachedFile {
+    hash: String,
+    /// Whether the file imports only the standard library, so its result
+    /// depends on nothing but its own contents
+    standalone: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// What `target/check/cache.toml` holds
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CheckCache {
+    compiler_version: String,
+    /// lang.toml, lang.lock and vendored sources, as in build fingerprints
+    dependencies: BTreeMap<String, String>,
+    files: BTreeMap<String, CachedFile>,
+}
+
+impl CheckCache {
+    fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        toml::from_str(&content).ok()
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| BuluError::Other(format!("Failed to create check cache directory: {}", e)))?;
+        }
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| BuluError::Other(format!("Failed to serialize check cache: {}", e)))?;
+        fs::write(path, content).map_err(|e| BuluError::Other(format!("Failed to write check cache: {}", e)))
+    }
+}
+
+/// Type checks a project's sources
+pub struct Checker {
+    project: Project,
+    options: CheckOptions,
+}
+
+impl Checker {
+    pub fn new(project: Project, options: CheckOptions) -> Self {
+        Self { project, options }
+    }
+
+    /// Path of the results cache
+    pub fn cache_path(&self) -> PathBuf {
+        self.project.target_dir.join("check").join("cache.toml")
+    }
+
+    /// Check every source file of the project
+    pub fn check(&self) -> Result<CheckReport> {
+        let fingerprint = Fingerprint::compute(&self.project, "check", "", "")?;
+        let previous = if self.options.incremental {
+            CheckCache::load(&self.cache_path())
+                .filter(|cache| {
+                    cache.compiler_version == fingerprint.compiler_version
+                        && cache.dependencies == fingerprint.dependencies
+                })
+                .unwrap_or_default()
+        } else {
+            CheckCache::default()
+        };
+        // Files importing project modules are only reused when no source changed
+        let sources_unchanged = fingerprint.sources.len() == previous.files.len()
+            && fingerprint
+                .sources
+                .iter()
+                .all(|(file, hash)| previous.files.get(file).is_some_and(|cached| &cached.hash == hash));
+
+        let mut files = BTreeMap::new();
+        let mut pending = Vec::new();
+        for (file, hash) in &fingerprint.sources {
+            match previous.files.get(file) {
+                Some(cached) if &cached.hash == hash && (cached.standalone || sources_unchanged) => {
+                    debug!("Unchanged {}", file);
+                    files.insert(file.clone(), cached.clone());
+                }
+                _ => pending.push((file.clone(), hash.clone())),
+            }
+        }
+        let files_cached = files.len();
+
+        for (file, cached) in self.check_files(pending) {
+            files.insert(file, cached);
+        }
+
+        let cache = CheckCache {
+            compiler_version: fingerprint.compiler_version,
+            dependencies: fingerprint.dependencies,
+            files,
+        };
+        if self.options.incremental {
+            cache.save(&self.cache_path())?;
+        }
+
+        Ok(CheckReport {
+            files_checked: cache.files.len(),
+            files_cached,
+            diagnostics: cache.files.into_values().flat_map(|cached| cached.diagnostics).collect(),
+        })
+    }
+
+    /// Check files, on as many threads as there are cores when checking in parallel
+    fn check_files(&self, files: Vec<(String, String)>) -> Vec<(String, CachedFile)> {
+        let check = |(file, hash): (String, String)| {
+            debug!("Checking {}", file);
+            let prelude = self.project.config.package.prelude;
+            let (standalone, diagnostics) = check_file(&self.project.root.join(&file), &file, prelude);
+            (file, CachedFile { hash, standalone, diagnostics })
+        };
+        let threads = std::thread::available_parallelism().map_or(1, |count| count.get());
+        if !self.options.parallel || threads < 2 || files.len() < 2 {
+            return files.into_iter().map(check).collect();
+        }
+
+        let chunk_size = files.len().div_ceil(threads);
+        let chunks: Vec<Vec<(String, String)>> = files.chunks(chunk_size).map(|chunk| chunk.to_vec()).collect();
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = chunks
+                .into_iter()
+                .map(|chunk| scope.spawn(move || chunk.into_iter().map(check).collect::<Vec<_>>()))
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("check thread panicked"))
+                .collect()
+        })
+    }
+}
+
+/// Check one file, returning whether it imports only the standard library
+/// and the errors found. `name` is how diagnostics refer to the file, and
+/// `prelude` is the package's `prelude` setting.
+pub fn check_file(path: &Path, name: &str, prelude: bool) -> (bool, Vec<Diagnostic>) {
+    let mut session = CompileSession::from_file(path)
+        .with_file_name(name)
+        .with_prelude(prelude);
+    // Files that do not parse have no imports to depend on
+    let standalone = session.imports_only_std().unwrap_or(true);
+    let _ = session.check();
+    (standalone, session.diagnostics().to_vec())
+}
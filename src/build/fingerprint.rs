@@ -0,0 +1,108 @@
+//! Build fingerprints deciding whether an artifact in `target/` can be reused
+
+use crate::project::Project;
+use crate::{BuluError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Everything a build artifact was produced from. An artifact is reused only
+/// when the fingerprint recorded next to it equals the current one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// Version of the compiler that produced the artifact
+    pub compiler_version: String,
+    pub profile: String,
+    pub target: String,
+    pub opt_level: String,
+    /// SHA-256 of every source file, keyed by its path relative to the project root
+    pub sources: BTreeMap<String, String>,
+    /// SHA-256 of lang.toml, lang.lock and the vendored dependency sources
+    pub dependencies: BTreeMap<String, String>,
+}
+
+impl Fingerprint {
+    /// Fingerprint the current state of `project` for a build with these options
+    pub fn compute(project: &Project, profile: &str, target: &str, opt_level: &str) -> Result<Self> {
+        let mut sources = BTreeMap::new();
+        for file in project.source_files()? {
+            sources.insert(relative_key(&project.root, &file), hash_file(&file)?);
+        }
+
+        let mut dependencies = BTreeMap::new();
+        for name in ["lang.toml", "lang.lock"] {
+            let path = project.root.join(name);
+            if path.exists() {
+                dependencies.insert(name.to_string(), hash_file(&path)?);
+            }
+        }
+        let mut vendored = Vec::new();
+        collect_files(&project.root.join("vendor"), &mut vendored)?;
+        for file in vendored {
+            dependencies.insert(relative_key(&project.root, &file), hash_file(&file)?);
+        }
+
+        Ok(Self {
+            compiler_version: crate::VERSION.to_string(),
+            profile: profile.to_string(),
+            target: target.to_string(),
+            opt_level: opt_level.to_string(),
+            sources,
+            dependencies,
+        })
+    }
+
+    /// Load a recorded fingerprint; a missing or unreadable file is no fingerprint
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        toml::from_str(&content).ok()
+    }
+
+    /// Record this fingerprint at `path`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| BuluError::Other(format!("Failed to create fingerprint directory: {}", e)))?;
+        }
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| BuluError::Other(format!("Failed to serialize fingerprint: {}", e)))?;
+        fs::write(path, content)
+            .map_err(|e| BuluError::Other(format!("Failed to write fingerprint: {}", e)))
+    }
+}
+
+pub(crate) fn hash_file(path: &Path) -> Result<String> {
+    let content = fs::read(path)
+        .map_err(|e| BuluError::Other(format!("Failed to read {}: {}", path.display(), e)))?;
+    Ok(sha256::digest(content.as_slice()))
+}
+
+/// Path relative to `root` with `/` separators, so fingerprints are portable
+pub(crate) fn relative_key(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)
+        .map_err(|e| BuluError::Other(format!("Failed to read directory {}: {}", dir.display(), e)))?
+    {
+        let path = entry
+            .map_err(|e| BuluError::Other(format!("Failed to read directory entry: {}", e)))?
+            .path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
@@ -1,31 +1,41 @@
 //! Build system for Bulu projects
 
+pub mod check;
+pub mod fingerprint;
+
+pub use check::{CheckOptions, CheckReport, Checker};
+pub use fingerprint::Fingerprint;
+
 use crate::{BuluError, Result};
 use crate::project::Project;
 use crate::runtime::Interpreter;
 use crate::error_reporter::ErrorReporter;
+use crate::linter::{load_lint_config, LintLevel, LintPolicy, LintResult, Linter};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use colored::*;
+use tracing::{info, warn, Level};
 
 /// Build options
 #[derive(Debug, Clone)]
 pub struct BuildOptions {
     pub release: bool,
-    pub verbose: bool,
     pub target: Option<String>,
     pub parallel: bool,
     pub incremental: bool,
+    /// Lints that fail the build; when it denies anything, the sources are
+    /// linted before they are compiled
+    pub lint_policy: LintPolicy,
 }
 
 impl Default for BuildOptions {
     fn default() -> Self {
         Self {
             release: false,
-            verbose: false,
             target: None,
             parallel: true,
             incremental: true,
+            lint_policy: LintPolicy::default(),
         }
     }
 }
@@ -50,11 +60,73 @@ impl Builder {
         Self { project, options }
     }
 
+    /// Build profile: `release` or `debug`
+    pub fn profile(&self) -> &'static str {
+        if self.options.release {
+            "release"
+        } else {
+            "debug"
+        }
+    }
+
+    /// Target platform, with `native` resolved to the host
+    pub fn target(&self) -> String {
+        let target = self.options.target.as_deref().unwrap_or(&self.project.config.build.target);
+        if target == "native" {
+            host_target()
+        } else {
+            target.to_string()
+        }
+    }
+
+    fn opt_level(&self) -> &'static str {
+        if self.options.release {
+            "3"
+        } else {
+            "0"
+        }
+    }
+
+    /// Path of the executable: `target/<profile>/<target>/<name>`
+    pub fn output_path(&self) -> PathBuf {
+        let extension = if self.target().starts_with("windows") { ".exe" } else { "" };
+        self.project
+            .profile_dir(self.options.release, &self.target())
+            .join(format!("{}{}", self.project.config.package.name, extension))
+    }
+
+    /// Path of the fingerprint recorded for the executable
+    pub fn fingerprint_path(&self) -> PathBuf {
+        self.project
+            .profile_dir(self.options.release, &self.target())
+            .join(".fingerprint")
+            .join(format!("{}.toml", self.project.config.package.name))
+    }
+
+    /// Fingerprint of the current sources, dependencies and options
+    pub fn fingerprint(&self) -> Result<Fingerprint> {
+        Fingerprint::compute(&self.project, self.profile(), &self.target(), self.opt_level())
+    }
+
+    /// Whether the executable exists and was built from the current fingerprint
+    pub fn is_fresh(&self) -> Result<bool> {
+        if !self.options.incremental || !self.output_path().exists() {
+            return Ok(false);
+        }
+        Ok(Fingerprint::load(&self.fingerprint_path()) == Some(self.fingerprint()?))
+    }
+
+    /// Lint the sources with the project's lint configuration and the
+    /// build's lint policy
+    pub fn lint(&self) -> Result<LintResult> {
+        let mut options = load_lint_config(&self.project.root)?;
+        options.policy = self.options.lint_policy.clone();
+        Linter::new(self.project.clone(), options).lint_project()
+    }
+
     /// Build the project
     pub fn build(&self) -> Result<BuildResult> {
-        if self.options.verbose {
-            println!("{} Building project '{}'...", "Building".green().bold(), self.project.config.package.name);
-        }
+        info!("{} Building project '{}'...", "Building".green().bold(), self.project.config.package.name);
 
         // Get main source file
         let main_file = self.project.src_dir.join("main.bu");
@@ -62,14 +134,58 @@ impl Builder {
             return Err(BuluError::Other("No main.bu file found in src directory".to_string()));
         }
 
-        // Determine output path
-        let output_name = if self.options.release {
-            format!("{}-release", self.project.config.package.name)
-        } else {
-            self.project.config.package.name.clone()
-        };
-        
-        let output_path = self.project.target_dir.join(&output_name);
+        // Patched dependencies are never meant to ship, so always say so
+        let patch_warnings = self.project.config.patch_warnings();
+        for warning in &patch_warnings {
+            warn!("{} {}", "Warning".yellow().bold(), warning);
+        }
+
+        // Denied lints fail the build even when the executable is up to date
+        if !self.options.lint_policy.is_empty() {
+            let lint = self.lint()?;
+            if lint.errors > 0 {
+                let describe = |level: LintLevel| {
+                    lint.issues
+                        .iter()
+                        .filter(|issue| issue.level == level)
+                        .map(|issue| {
+                            format!(
+                                "{}:{}:{}: {} [{}]",
+                                issue.file.display(),
+                                issue.line,
+                                issue.column,
+                                issue.message,
+                                issue.rule
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                };
+                let mut warnings = patch_warnings;
+                warnings.extend(describe(LintLevel::Warn));
+                return Ok(BuildResult {
+                    success: false,
+                    output_path: None,
+                    errors: describe(LintLevel::Error),
+                    warnings,
+                });
+            }
+        }
+
+        let output_path = self.output_path();
+        let fingerprint_path = self.fingerprint_path();
+        let fingerprint = self.fingerprint()?;
+
+        if self.is_fresh()? {
+            info!("{} {} is up to date", "Fresh".green().bold(), output_path.display());
+            return Ok(BuildResult {
+                success: true,
+                output_path: Some(output_path),
+                errors: Vec::new(),
+                warnings: patch_warnings,
+            });
+        }
+
+        std::fs::create_dir_all(output_path.parent().expect("output path has a parent"))?;
 
         // Use langc to compile
         let langc_path = std::env::current_exe()?
@@ -78,31 +194,41 @@ impl Builder {
             .join("langc");
 
         let mut cmd = Command::new(&langc_path);
-        cmd.arg(&main_file)
+        cmd.arg("build")
+            .arg(&main_file)
             .arg("-o")
-            .arg(&output_path);
+            .arg(&output_path)
+            .arg("-O")
+            .arg(self.opt_level())
+            .arg("--target")
+            .arg(self.target())
+            .arg("--package")
+            .arg(&self.project.config.package.name);
 
-        if self.options.release {
-            cmd.arg("-O3");
+        if !self.project.config.package.prelude {
+            cmd.arg("--no-prelude");
         }
 
-        if self.options.verbose {
+        // langc reports each compilation step when asked for details
+        if tracing::enabled!(Level::DEBUG) {
             cmd.arg("--verbose");
         }
 
         let output = cmd.output()?;
 
         if output.status.success() {
-            if self.options.verbose {
-                println!("{} Build completed successfully", "Finished".green().bold());
-            }
+            fingerprint.save(&fingerprint_path)?;
+            info!("{} Build completed successfully", "Finished".green().bold());
             Ok(BuildResult {
                 success: true,
                 output_path: Some(output_path),
                 errors: Vec::new(),
-                warnings: Vec::new(),
+                warnings: patch_warnings,
             })
         } else {
+            // The previous artifact no longer matches what was asked for
+            let _ = std::fs::remove_file(&fingerprint_path);
+
             let error_msg = String::from_utf8_lossy(&output.stderr);
             let stdout_msg = String::from_utf8_lossy(&output.stdout);
             
@@ -117,7 +243,7 @@ impl Builder {
             
             // Extract errors and warnings from compiler output
             let mut errors = Vec::new();
-            let mut warnings = Vec::new();
+            let mut warnings = patch_warnings;
             
             for line in error_msg.lines().chain(stdout_msg.lines()) {
                 if line.contains("error") || line.contains("Error") {
@@ -138,22 +264,53 @@ impl Builder {
 
     /// Clean build artifacts
     pub fn clean(&self) -> Result<()> {
-        if self.options.verbose {
-            println!("{} Cleaning build artifacts...", "Cleaning".yellow().bold());
-        }
+        info!("{} Cleaning build artifacts...", "Cleaning".yellow().bold());
 
         if self.project.target_dir.exists() {
             std::fs::remove_dir_all(&self.project.target_dir)?;
         }
 
-        if self.options.verbose {
-            println!("{} Clean completed", "Finished".green().bold());
+        info!("{} Clean completed", "Finished".green().bold());
+
+        Ok(())
+    }
+
+    /// Clean the artifacts of a single profile, `debug` or `release`
+    pub fn clean_profile(&self, profile: &str) -> Result<()> {
+        if profile != "debug" && profile != "release" {
+            return Err(BuluError::Other(format!(
+                "Unknown profile '{}', expected 'debug' or 'release'",
+                profile
+            )));
         }
 
+        info!("{} Cleaning {} artifacts...", "Cleaning".yellow().bold(), profile);
+
+        let profile_dir = self.project.target_dir.join(profile);
+        if profile_dir.exists() {
+            std::fs::remove_dir_all(&profile_dir)?;
+        }
+
+        info!("{} Clean completed", "Finished".green().bold());
+
         Ok(())
     }
 }
 
+/// Target name of the host, e.g. `linux-amd64`, as accepted by `langc --target`
+pub fn host_target() -> String {
+    let os = match std::env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        arch => arch,
+    };
+    format!("{}-{}", os, arch)
+}
+
 /// Run a Bulu program by interpreting the source directly
 pub fn run_executable(exe_path: &Path, _args: &[String]) -> Result<()> {
     // For now, we'll interpret the source directly instead of trying to run compiled bytecode
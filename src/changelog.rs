@@ -0,0 +1,466 @@
+//! Changelog management for a project (`lang changelog`)
+//!
+//! Changes are written as conventional-commit entries,
+//! `type(scope)!: description`, where the scope and the `!` marking a
+//! breaking change are optional. Pending entries live in the project's
+//! `.changes/` directory, one file per change and one entry per line, until
+//! a release moves them into a `## [version] - date` section of
+//! `CHANGELOG.md`. A release can also be written from the subjects of the
+//! git commits since the last tag.
+//!
+//! `lang publish` requires `CHANGELOG.md`, when there is one, to have a
+//! section for the version being published, and sends that section to the
+//! registry as the version's release notes.
+
+use crate::{BuluError, Result};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The changelog file at the project root
+pub const CHANGELOG_FILE: &str = "CHANGELOG.md";
+
+/// The directory of pending changes at the project root
+pub const CHANGES_DIR: &str = ".changes";
+
+const HEADER: &str = "# Changelog\n\nAll notable changes to this project are documented in this file.\n";
+
+/// Section titles by change type, in the order sections are written.
+/// Breaking changes come first whatever their type, and types not listed
+/// here go under "Other Changes".
+const SECTIONS: [(&str, &str); 5] = [
+    ("feat", "Features"),
+    ("fix", "Fixes"),
+    ("perf", "Performance"),
+    ("refactor", "Refactoring"),
+    ("docs", "Documentation"),
+];
+
+/// A conventional-commit entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeEntry {
+    /// `feat`, `fix`, ...
+    pub kind: String,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub description: String,
+}
+
+impl ChangeEntry {
+    pub fn new(kind: &str, scope: Option<&str>, breaking: bool, description: &str) -> Result<Self> {
+        let entry = Self {
+            kind: kind.trim().to_lowercase(),
+            scope: scope.map(|scope| scope.trim().to_string()).filter(|scope| !scope.is_empty()),
+            breaking,
+            description: description.trim().to_string(),
+        };
+        if !is_word(&entry.kind) {
+            return Err(BuluError::Other(format!("Invalid change type '{}'", kind)));
+        }
+        if entry.description.is_empty() {
+            return Err(BuluError::Other("A change needs a description".to_string()));
+        }
+        Ok(entry)
+    }
+
+    /// Parse `type(scope)!: description`, or `None` if `line` is not an entry
+    pub fn parse(line: &str) -> Option<Self> {
+        let (head, description) = line.trim().split_once(':')?;
+        let (head, breaking) = match head.strip_suffix('!') {
+            Some(head) => (head, true),
+            None => (head, false),
+        };
+        let (kind, scope) = match head.split_once('(') {
+            Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?)),
+            None => (head, None),
+        };
+        if !is_word(kind) || scope.is_some_and(|scope| scope.is_empty() || scope.contains(['(', ')'])) {
+            return None;
+        }
+        Self::new(kind, scope, breaking, description).ok()
+    }
+
+    /// The changelog line, e.g. `- **parser:** accept trailing commas`
+    pub fn to_markdown(&self) -> String {
+        match &self.scope {
+            Some(scope) => format!("- **{}:** {}", scope, self.description),
+            None => format!("- {}", self.description),
+        }
+    }
+
+    fn section(&self) -> &'static str {
+        if self.breaking {
+            return "Breaking Changes";
+        }
+        SECTIONS
+            .iter()
+            .find(|(kind, _)| *kind == self.kind)
+            .map_or("Other Changes", |(_, title)| title)
+    }
+}
+
+impl fmt::Display for ChangeEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(scope) = &self.scope {
+            write!(f, "({})", scope)?;
+        }
+        if self.breaking {
+            write!(f, "!")?;
+        }
+        write!(f, ": {}", self.description)
+    }
+}
+
+fn is_word(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// A `## [version] - date` section with the entries grouped by section title
+pub fn render_section(version: &str, date: &str, entries: &[ChangeEntry]) -> String {
+    let titles = std::iter::once("Breaking Changes")
+        .chain(SECTIONS.iter().map(|(_, title)| *title))
+        .chain(std::iter::once("Other Changes"));
+
+    let mut section = format!("## [{}] - {}\n", version, date);
+    for title in titles {
+        let lines: Vec<String> = entries
+            .iter()
+            .filter(|entry| entry.section() == title)
+            .map(ChangeEntry::to_markdown)
+            .collect();
+        if !lines.is_empty() {
+            section.push_str(&format!("\n### {}\n\n{}\n", title, lines.join("\n")));
+        }
+    }
+    section
+}
+
+/// The text of a changelog
+#[derive(Debug, Clone, PartialEq)]
+pub struct Changelog {
+    content: String,
+}
+
+impl Changelog {
+    pub fn new(content: &str) -> Self {
+        Self {
+            content: content.to_string(),
+        }
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// The versions with a section, newest first as they are written
+    pub fn versions(&self) -> Vec<String> {
+        self.content.lines().filter_map(heading_version).collect()
+    }
+
+    /// The body of the section for `version`, without its heading
+    pub fn section(&self, version: &str) -> Option<String> {
+        let mut lines = self.content.lines();
+        lines.find(|line| heading_version(line).as_deref() == Some(version))?;
+        let body: Vec<&str> = lines.take_while(|line| !line.starts_with("## ")).collect();
+        Some(body.join("\n").trim().to_string())
+    }
+
+    /// Insert `section` above the newest version's, below the header and any
+    /// `Unreleased` section
+    pub fn insert_section(&mut self, section: &str) {
+        let mut offset = 0;
+        let mut insert_at = None;
+        for line in self.content.split_inclusive('\n') {
+            if heading_version(line).is_some() {
+                insert_at = Some(offset);
+                break;
+            }
+            offset += line.len();
+        }
+
+        let section = format!("{}\n", section.trim_end());
+        match insert_at {
+            Some(at) => self.content.insert_str(at, &format!("{}\n", section)),
+            None => {
+                let content = self.content.trim_end();
+                self.content = format!("{}\n\n{}", content, section);
+            }
+        }
+    }
+}
+
+/// The version of a `## [1.2.0] - date`, `## 1.2.0` or `## v1.2.0` heading
+fn heading_version(line: &str) -> Option<String> {
+    let heading = line.strip_prefix("## ")?.trim();
+    let version = heading.split_whitespace().next()?;
+    let version = version.trim_start_matches('[').trim_end_matches(']');
+    let version = version.strip_prefix('v').unwrap_or(version);
+    if version.starts_with(|c: char| c.is_ascii_digit()) {
+        Some(version.to_string())
+    } else {
+        None
+    }
+}
+
+/// A pending change and the file it was read from
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingChange {
+    pub path: PathBuf,
+    pub entry: ChangeEntry,
+}
+
+/// Where a release takes its entries from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeSource {
+    /// The files of `.changes/`
+    Directory,
+    /// The subjects of the git commits since the last tag
+    Git,
+}
+
+/// The changelog and pending changes of a project
+pub struct ChangelogManager {
+    root: PathBuf,
+}
+
+impl ChangelogManager {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn changelog_path(&self) -> PathBuf {
+        self.root.join(CHANGELOG_FILE)
+    }
+
+    /// The project's changelog, if it has one
+    pub fn load(&self) -> Result<Option<Changelog>> {
+        let path = self.changelog_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| BuluError::Other(format!("Failed to read {}: {}", path.display(), e)))?;
+        Ok(Some(Changelog::new(&content)))
+    }
+
+    /// Record a pending change in a new file of `.changes/`
+    pub fn add(&self, entry: &ChangeEntry) -> Result<PathBuf> {
+        let dir = self.root.join(CHANGES_DIR);
+        fs::create_dir_all(&dir)
+            .map_err(|e| BuluError::Other(format!("Failed to create {}: {}", dir.display(), e)))?;
+
+        // Timestamped names keep the files in the order changes were added,
+        // even when several are added within the same millisecond
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis());
+        let latest = fs::read_dir(&dir)
+            .map_err(|e| BuluError::Other(format!("Failed to read {}: {}", dir.display(), e)))?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                name.split('-').next()?.parse::<u128>().ok()
+            })
+            .max();
+        let stamp = latest.map_or(now, |latest| now.max(latest + 1));
+        let slug: String = entry
+            .description
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .take(5)
+            .collect::<Vec<_>>()
+            .join("-")
+            .to_lowercase();
+        let path = dir.join(format!("{}-{}.md", stamp, slug));
+
+        fs::write(&path, format!("{}\n", entry))
+            .map_err(|e| BuluError::Other(format!("Failed to write {}: {}", path.display(), e)))?;
+        Ok(path)
+    }
+
+    /// The entries of `.changes/`, in file name order
+    pub fn pending(&self) -> Result<Vec<PendingChange>> {
+        let dir = self.root.join(CHANGES_DIR);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+            .map_err(|e| BuluError::Other(format!("Failed to read {}: {}", dir.display(), e)))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+            .collect();
+        paths.sort();
+
+        let mut changes = Vec::new();
+        for path in paths {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| BuluError::Other(format!("Failed to read {}: {}", path.display(), e)))?;
+            for (index, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let entry = ChangeEntry::parse(line).ok_or_else(|| {
+                    BuluError::Other(format!(
+                        "{}:{}: expected `type(scope): description`, found '{}'",
+                        path.display(),
+                        index + 1,
+                        line.trim()
+                    ))
+                })?;
+                changes.push(PendingChange {
+                    path: path.clone(),
+                    entry,
+                });
+            }
+        }
+        Ok(changes)
+    }
+
+    /// The conventional subjects of the commits since the last tag, oldest
+    /// first. Other subjects are skipped.
+    pub fn commits_since_last_tag(&self) -> Result<Vec<ChangeEntry>> {
+        let range = match self.git(&["describe", "--tags", "--abbrev=0"]) {
+            Ok(tag) => format!("{}..HEAD", tag.trim()),
+            // No tag yet: the whole history
+            Err(_) => "HEAD".to_string(),
+        };
+        let subjects = self.git(&["log", "--reverse", "--format=%s", &range])?;
+        Ok(subjects.lines().filter_map(ChangeEntry::parse).collect())
+    }
+
+    fn git(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(&self.root)
+            .output()
+            .map_err(|e| BuluError::Other(format!("Failed to run git: {}", e)))?;
+        if !output.status.success() {
+            return Err(BuluError::Other(format!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim_end()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Write the section for `version` into the changelog, creating it if
+    /// needed, and return the section. Changes taken from `.changes/` are
+    /// deleted once the changelog is written.
+    pub fn release(&self, version: &str, date: &str, source: ChangeSource) -> Result<String> {
+        let mut changelog = self.load()?.unwrap_or_else(|| Changelog::new(HEADER));
+        if changelog.section(version).is_some() {
+            return Err(BuluError::Other(format!(
+                "{} already has a section for {}",
+                CHANGELOG_FILE, version
+            )));
+        }
+
+        let (entries, consumed) = match source {
+            ChangeSource::Directory => {
+                let pending = self.pending()?;
+                let mut paths: Vec<PathBuf> = pending.iter().map(|change| change.path.clone()).collect();
+                paths.dedup();
+                (pending.into_iter().map(|change| change.entry).collect(), paths)
+            }
+            ChangeSource::Git => (self.commits_since_last_tag()?, Vec::new()),
+        };
+        if entries.is_empty() {
+            return Err(BuluError::Other(match source {
+                ChangeSource::Directory => format!(
+                    "No pending changes in {}; add some with `lang changelog add`",
+                    CHANGES_DIR
+                ),
+                ChangeSource::Git => "No conventional commits since the last tag".to_string(),
+            }));
+        }
+
+        let section = render_section(version, date, &entries);
+        changelog.insert_section(&section);
+        let path = self.changelog_path();
+        fs::write(&path, changelog.content())
+            .map_err(|e| BuluError::Other(format!("Failed to write {}: {}", path.display(), e)))?;
+        for path in consumed {
+            fs::remove_file(&path)
+                .map_err(|e| BuluError::Other(format!("Failed to remove {}: {}", path.display(), e)))?;
+        }
+        Ok(section)
+    }
+
+    /// The release notes of `version`: `None` without a changelog, and an
+    /// error when the changelog has no section for `version`
+    pub fn release_notes(&self, version: &str) -> Result<Option<String>> {
+        let Some(changelog) = self.load()? else {
+            return Ok(None);
+        };
+        match changelog.section(version) {
+            Some(notes) => Ok(Some(notes)),
+            None => Err(BuluError::Other(format!(
+                "{} has no section for version {}; write one with `lang changelog release`",
+                CHANGELOG_FILE, version
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_entries() {
+        assert_eq!(
+            ChangeEntry::parse("feat(parser)!: accept trailing commas"),
+            Some(ChangeEntry {
+                kind: "feat".to_string(),
+                scope: Some("parser".to_string()),
+                breaking: true,
+                description: "accept trailing commas".to_string(),
+            })
+        );
+        let fix = ChangeEntry::parse("Fix: off by one").unwrap();
+        assert_eq!((fix.kind.as_str(), fix.scope.as_deref(), fix.breaking), ("fix", None, false));
+        assert_eq!(fix.to_string(), "fix: off by one");
+
+        assert_eq!(ChangeEntry::parse("Merge branch 'main'"), None);
+        assert_eq!(ChangeEntry::parse("feat(): empty scope"), None);
+        assert_eq!(ChangeEntry::parse("fix:   "), None);
+        assert_eq!(ChangeEntry::parse("see http://example.com: a link"), None);
+    }
+
+    #[test]
+    fn test_render_and_insert_section() {
+        let entries: Vec<ChangeEntry> = [
+            "chore: bump dependencies",
+            "fix(lexer): handle CRLF",
+            "feat: add zip",
+            "refactor!: rename Vec to Array",
+        ]
+        .iter()
+        .filter_map(|line| ChangeEntry::parse(line))
+        .collect();
+        let section = render_section("1.1.0", "2026-10-17", &entries);
+        assert_eq!(
+            section,
+            "## [1.1.0] - 2026-10-17\n\n\
+             ### Breaking Changes\n\n- rename Vec to Array\n\n\
+             ### Features\n\n- add zip\n\n\
+             ### Fixes\n\n- **lexer:** handle CRLF\n\n\
+             ### Other Changes\n\n- bump dependencies\n"
+        );
+
+        let mut changelog = Changelog::new("# Changelog\n\n## [Unreleased]\n\n## 1.0.0\n\n- first\n");
+        changelog.insert_section(&section);
+        assert_eq!(changelog.versions(), vec!["1.1.0", "1.0.0"]);
+        assert_eq!(changelog.section("1.0.0").as_deref(), Some("- first"));
+        assert!(changelog.content().starts_with("# Changelog\n\n## [Unreleased]\n\n## [1.1.0]"));
+        assert!(changelog
+            .section("1.1.0")
+            .unwrap()
+            .ends_with("### Other Changes\n\n- bump dependencies"));
+        assert_eq!(changelog.section("0.9.0"), None);
+    }
+}
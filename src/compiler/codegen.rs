@@ -11,6 +11,8 @@ pub struct CodeGenerator {
     target: String,
     debug: bool,
     static_link: bool,
+    /// Package whose functions are generated, which their symbols name
+    package: String,
 }
 
 impl CodeGenerator {
@@ -20,6 +22,7 @@ impl CodeGenerator {
             target: "native".to_string(),
             debug: false,
             static_link: false,
+            package: crate::compiler::mangle::DEFAULT_PACKAGE.to_string(),
         }
     }
 
@@ -38,6 +41,11 @@ impl CodeGenerator {
         self.static_link = static_link;
     }
 
+    /// Set the package functions are named after, e.g. `geo/plane`
+    pub fn set_package(&mut self, package: &str) {
+        self.package = package.to_string();
+    }
+
     /// Generate assembly code from IR program
     pub fn generate_assembly(&mut self, ir_program: &IrProgram) -> Result<String> {
         let mut assembly = String::new();
@@ -95,7 +103,7 @@ impl CodeGenerator {
         } else {
             // Release mode: generate native executable (Go-style)
             use crate::compiler::native_backend::NativeBackend;
-            let backend = NativeBackend::new();
+            let backend = NativeBackend::new().with_package(&self.package).with_target(&self.target);
             backend.generate_executable(ir_program)
         }
     }
@@ -538,8 +546,9 @@ del "%TEMP_BYTECODE%"
         let mut assembly = String::new();
 
         // Function label
-        assembly.push_str(&format!(".globl {}\n", function.name));
-        assembly.push_str(&format!("{}:\n", function.name));
+        let symbol = crate::compiler::mangle::mangle_function(&self.package, function);
+        assembly.push_str(&format!(".globl {}\n", symbol));
+        assembly.push_str(&format!("{}:\n", symbol));
 
         // Function prologue (simplified)
         assembly.push_str("    push %rbp\n");
@@ -0,0 +1,206 @@
+//! Intrinsics: machine instructions Bulu programs can ask for by name
+//!
+//! ```text
+//! let bits = intrinsic("popcount", mask)
+//! let start = intrinsic("rdtsc")
+//! intrinsic("fence")
+//! ```
+//!
+//! The native backend emits an intrinsic inline, as the instructions listed
+//! for the target's architecture. The type checker checks the name, the
+//! arguments and the result of each call; the backend rejects an intrinsic
+//! the target architecture has no instructions for, and the interpreters
+//! reject every intrinsic, since they have no machine code to run.
+//!
+//! Arguments are passed in `%rdi` and `%rsi`, and the result is left in
+//! `%rax`; the instructions may clobber `%rcx` and `%rdx`.
+
+/// An instruction, or short sequence of them, callable through `intrinsic`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Intrinsic {
+    pub name: &'static str,
+    /// Bulu types of the arguments
+    pub params: &'static [&'static str],
+    /// Bulu type of the result, if there is one
+    pub result: Option<&'static str>,
+    /// One line description, for documentation and tools
+    pub summary: &'static str,
+    /// The instructions for each architecture that has them
+    pub lowerings: &'static [(&'static str, &'static [&'static str])],
+}
+
+impl Intrinsic {
+    /// The instructions for `arch`, or None if the architecture lacks them
+    pub fn lowering(&self, arch: &str) -> Option<&'static [&'static str]> {
+        self.lowerings
+            .iter()
+            .find(|(lowering_arch, _)| *lowering_arch == arch)
+            .map(|(_, instructions)| *instructions)
+    }
+
+    /// Architectures the intrinsic is available on
+    pub fn arches(&self) -> Vec<&'static str> {
+        self.lowerings.iter().map(|(arch, _)| *arch).collect()
+    }
+}
+
+/// Every intrinsic, by name
+pub const INTRINSICS: &[Intrinsic] = &[
+    Intrinsic {
+        name: "popcount",
+        params: &["int64"],
+        result: Some("int64"),
+        summary: "Number of bits set",
+        lowerings: &[("x86_64", &["popcnt %rdi, %rax"])],
+    },
+    Intrinsic {
+        name: "clz",
+        params: &["int64"],
+        result: Some("int64"),
+        summary: "Number of leading zero bits, 64 for 0",
+        lowerings: &[(
+            "x86_64",
+            &[
+                "mov $-1, %rcx",
+                "bsr %rdi, %rax",
+                "cmovz %rcx, %rax",
+                "mov $63, %rcx",
+                "sub %rax, %rcx",
+                "mov %rcx, %rax",
+            ],
+        )],
+    },
+    Intrinsic {
+        name: "ctz",
+        params: &["int64"],
+        result: Some("int64"),
+        summary: "Number of trailing zero bits, 64 for 0",
+        lowerings: &[("x86_64", &["mov $64, %rcx", "bsf %rdi, %rax", "cmovz %rcx, %rax"])],
+    },
+    Intrinsic {
+        name: "bswap",
+        params: &["int64"],
+        result: Some("int64"),
+        summary: "The bytes in reverse order",
+        lowerings: &[("x86_64", &["mov %rdi, %rax", "bswap %rax"])],
+    },
+    Intrinsic {
+        name: "rotl",
+        params: &["int64", "int64"],
+        result: Some("int64"),
+        summary: "The bits rotated left by the second argument, modulo 64",
+        lowerings: &[("x86_64", &["mov %rdi, %rax", "mov %rsi, %rcx", "rol %cl, %rax"])],
+    },
+    Intrinsic {
+        name: "rotr",
+        params: &["int64", "int64"],
+        result: Some("int64"),
+        summary: "The bits rotated right by the second argument, modulo 64",
+        lowerings: &[("x86_64", &["mov %rdi, %rax", "mov %rsi, %rcx", "ror %cl, %rax"])],
+    },
+    Intrinsic {
+        name: "crc32c",
+        params: &["int64", "int64"],
+        result: Some("int64"),
+        summary: "CRC-32C of the 8 bytes of the second argument, continuing the first (SSE4.2)",
+        lowerings: &[("x86_64", &["mov %rdi, %rax", "crc32q %rsi, %rax"])],
+    },
+    Intrinsic {
+        name: "rdtsc",
+        params: &[],
+        result: Some("int64"),
+        summary: "The processor's time stamp counter",
+        lowerings: &[("x86_64", &["rdtsc", "shl $32, %rdx", "or %rdx, %rax"])],
+    },
+    Intrinsic {
+        name: "pause",
+        params: &[],
+        result: None,
+        summary: "Hint that the program is in a spin loop",
+        lowerings: &[("x86_64", &["pause"])],
+    },
+    Intrinsic {
+        name: "fence",
+        params: &[],
+        result: None,
+        summary: "Full memory fence: no load or store moves across it",
+        lowerings: &[("x86_64", &["mfence"])],
+    },
+];
+
+/// The intrinsic called `name`
+pub fn lookup(name: &str) -> Option<&'static Intrinsic> {
+    INTRINSICS.iter().find(|intrinsic| intrinsic.name == name)
+}
+
+/// Names of every intrinsic, for error messages
+pub fn names() -> Vec<&'static str> {
+    INTRINSICS.iter().map(|intrinsic| intrinsic.name).collect()
+}
+
+/// The architecture of a target as accepted by `langc --target`, e.g.
+/// `x86_64` for `linux-amd64`; `native` is the host
+pub fn target_arch(target: &str) -> String {
+    if target == "native" {
+        return std::env::consts::ARCH.to_string();
+    }
+    match target.rsplit('-').next().unwrap_or(target) {
+        "amd64" => "x86_64".to_string(),
+        "arm64" => "aarch64".to_string(),
+        "wasm" => "wasm32".to_string(),
+        arch => arch.to_string(),
+    }
+}
+
+/// The instructions of intrinsic `name` on `arch`, or why there are none
+pub fn lower(name: &str, arch: &str) -> Result<&'static [&'static str], String> {
+    let intrinsic = lookup(name)
+        .ok_or_else(|| format!("Unknown intrinsic '{}', expected one of {}", name, names().join(", ")))?;
+    intrinsic.lowering(arch).ok_or_else(|| {
+        format!(
+            "Intrinsic '{}' is not available on {}; it is available on {}",
+            name,
+            arch,
+            intrinsic.arches().join(", ")
+        )
+    })
+}
+
+/// The error for calling an intrinsic without the native backend
+pub fn interpreted_message(name: &str) -> String {
+    format!(
+        "Intrinsic '{}' needs the native backend; build the program with `langc build` to run it",
+        name
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_availability_per_target() {
+        assert_eq!(target_arch("linux-amd64"), "x86_64");
+        assert_eq!(target_arch("darwin-arm64"), "aarch64");
+        assert_eq!(target_arch("wasm"), "wasm32");
+        assert_eq!(lower("bswap", "x86_64"), Ok(&["mov %rdi, %rax", "bswap %rax"][..]));
+        assert_eq!(
+            lower("popcount", "aarch64").unwrap_err(),
+            "Intrinsic 'popcount' is not available on aarch64; it is available on x86_64"
+        );
+        assert!(lower("cpuid", "x86_64").unwrap_err().starts_with("Unknown intrinsic 'cpuid', expected one of popcount, clz"));
+    }
+
+    #[test]
+    fn test_signatures_use_supported_types() {
+        for intrinsic in INTRINSICS {
+            assert!(intrinsic.params.len() <= 2, "{}", intrinsic.name);
+            assert!(
+                intrinsic.params.iter().chain(intrinsic.result.iter()).all(|ty| *ty == "int64"),
+                "{}",
+                intrinsic.name
+            );
+            assert!(!intrinsic.lowerings.is_empty(), "{}", intrinsic.name);
+        }
+    }
+}
@@ -11,8 +11,6 @@ use std::collections::{HashMap, HashSet};
 
 /// IR optimizer that applies various optimization passes
 pub struct IrOptimizer {
-    /// Whether to enable aggressive optimizations
-    aggressive: bool,
     /// Optimization level
     level: OptLevel,
 }
@@ -20,7 +18,6 @@ pub struct IrOptimizer {
 impl IrOptimizer {
     pub fn new() -> Self {
         Self { 
-            aggressive: false,
             level: OptLevel::O0,
         }
     }
@@ -28,25 +25,66 @@ impl IrOptimizer {
     /// Set the optimization level
     pub fn set_level(&mut self, level: OptLevel) {
         self.level = level;
-        self.aggressive = matches!(self.level, OptLevel::O3);
     }
 
     /// Apply all optimization passes to an IR program
+    ///
+    /// O1 only folds constants. O2 and O3 also inline small functions (larger
+    /// ones at O3), remove unreachable blocks and drop unused locals; Os does the
+    /// same without inlining.
     pub fn optimize(&mut self, mut program: IrProgram) -> Result<IrProgram> {
-        // Apply optimization passes in order
-        // ONLY run constant folding for now - other passes break the native backend
         program = self.constant_folding(program)?;
 
-        if self.aggressive {
+        if matches!(self.level, OptLevel::O0 | OptLevel::O1) {
+            return Ok(program);
+        }
+
+        if self.inline_threshold() > 0 {
             program = self.function_inlining(program)?;
-            program = self.loop_optimization(program)?;
+        }
+
+        program = self.dead_block_elimination(program)?;
+        program = self.dead_code_elimination(program)?;
+
+        Ok(program)
+    }
+
+    /// Dead block elimination pass
+    /// Resolves branches on constant conditions and removes the blocks that
+    /// can no longer be reached from the entry block
+    pub fn dead_block_elimination(&mut self, mut program: IrProgram) -> Result<IrProgram> {
+        let analyzer = ControlFlowAnalyzer::new();
+
+        for function in &mut program.functions {
+            for block in &mut function.basic_blocks {
+                if let IrTerminator::ConditionalBranch {
+                    condition: IrValue::Constant(IrConstant::Boolean(condition)),
+                    true_label,
+                    false_label,
+                } = &block.terminator
+                {
+                    let target = if *condition { true_label } else { false_label };
+                    block.terminator = IrTerminator::Branch(target.clone());
+                }
+            }
+
+            let cfg = analyzer.build_cfg(function)?;
+            let unreachable = analyzer.find_unreachable_blocks(&cfg);
+            if !unreachable.is_empty() {
+                let mut index = 0;
+                function.basic_blocks.retain(|_| {
+                    index += 1;
+                    !unreachable.contains(&(index - 1))
+                });
+            }
         }
 
         Ok(program)
     }
 
     /// Dead code elimination pass
-    /// Removes instructions that have no effect on program output
+    /// Removes instructions that have no effect on program output, and the
+    /// locals they were the only uses of
     pub fn dead_code_elimination(&mut self, mut program: IrProgram) -> Result<IrProgram> {
         for function in &mut program.functions {
             self.eliminate_dead_code_in_function(function)?;
@@ -90,29 +128,23 @@ impl IrOptimizer {
             }
         }
 
-        // Propagate liveness backwards
+        // Propagate liveness backwards. Locals are assigned once per write, so
+        // every instruction defining a live register keeps its operands live.
         while let Some(register) = worklist.pop() {
-            // Find the instruction that defines this register
             for block in &function.basic_blocks {
                 for instruction in &block.instructions {
-                    if let Some(result_reg) = instruction.result {
-                        if result_reg == register {
-                            // Mark all operands of this instruction as used
-                            for operand in &instruction.operands {
-                                self.mark_value_as_used(
-                                    operand,
-                                    &mut used_registers,
-                                    &mut worklist,
-                                );
-                            }
-                            break;
+                    if instruction.result == Some(register) {
+                        for operand in &instruction.operands {
+                            self.mark_value_as_used(
+                                operand,
+                                &mut used_registers,
+                                &mut worklist,
+                            );
                         }
                     }
                 }
             }
         }
-        
-
 
         // Remove dead instructions
         for block in &mut function.basic_blocks {
@@ -126,6 +158,16 @@ impl IrOptimizer {
             });
         }
 
+        // Remove locals that are no longer written
+        let written: HashSet<IrRegister> = function
+            .basic_blocks
+            .iter()
+            .flat_map(|block| block.instructions.iter().filter_map(|instruction| instruction.result))
+            .collect();
+        function
+            .locals
+            .retain(|local| written.contains(&local.register) || used_registers.contains(&local.register));
+
         Ok(())
     }
 
@@ -150,7 +192,7 @@ impl IrOptimizer {
             IrOpcode::Store | IrOpcode::Alloca => true,
 
             // Function calls may have side effects
-            IrOpcode::Call | IrOpcode::CallIndirect => true,
+            IrOpcode::Call | IrOpcode::CallIndirect | IrOpcode::CallAsync => true,
 
             // Channel operations have side effects
             IrOpcode::ChannelSend | IrOpcode::ChannelReceive | IrOpcode::ChannelClose => true,
@@ -587,83 +629,67 @@ impl IrOptimizer {
         Ok(())
     }
 
-    /// Function inlining pass (aggressive optimization)
-    /// Inlines small functions at their call sites
-    fn function_inlining(&mut self, mut program: IrProgram) -> Result<IrProgram> {
-        // Identify functions suitable for inlining
-        let inlinable_functions = self.identify_inlinable_functions(&program);
-        
-        // Clone the functions for reference during inlining
-        let function_map: HashMap<String, IrFunction> = program.functions.iter()
-            .map(|f| (f.name.clone(), f.clone()))
+    /// Function inlining pass
+    /// Replaces calls to small, non-recursive functions with a copy of their body
+    pub fn function_inlining(&mut self, mut program: IrProgram) -> Result<IrProgram> {
+        // Bodies are taken from the program before inlining, so inlined code is
+        // never inlined into again and mutually recursive functions terminate
+        let inlinable: HashMap<String, IrFunction> = program
+            .functions
+            .iter()
+            .filter(|function| self.is_function_inlinable(function))
+            .map(|function| (function.name.clone(), function.clone()))
             .collect();
-        
-        // Inline functions in each function
+
+        if inlinable.is_empty() {
+            return Ok(program);
+        }
+
         for function in &mut program.functions {
-            self.inline_calls_in_function(function, &inlinable_functions, &function_map)?;
+            self.inline_calls_in_function(function, &inlinable)?;
         }
-        
+
         Ok(program)
     }
-    
-    /// Identify functions that are suitable for inlining
-    fn identify_inlinable_functions(&self, program: &IrProgram) -> HashSet<String> {
-        let mut inlinable = HashSet::new();
-        
-        for function in &program.functions {
-            if self.is_function_inlinable(function) {
-                inlinable.insert(function.name.clone());
-            }
-        }
-        
-        inlinable
-    }
-    
+
     /// Check if a function is suitable for inlining
     fn is_function_inlinable(&self, function: &IrFunction) -> bool {
-        // Don't inline recursive functions
-        if self.is_recursive_function(function) {
+        if function.name == "main" || function.is_async || self.is_recursive_function(function) {
             return false;
         }
-        
-        // Don't inline async functions (complex control flow)
-        if function.is_async {
-            return false;
-        }
-        
-        // Count total instructions
-        let total_instructions: usize = function.basic_blocks.iter()
-            .map(|block| block.instructions.len())
-            .sum();
-        
-        // Only inline small functions (threshold: 10 instructions)
-        if total_instructions > 10 {
-            return false;
-        }
-        
-        // Don't inline functions with complex control flow (multiple blocks)
-        if function.basic_blocks.len() > 3 {
+
+        if self.estimate_inline_cost(function) > self.inline_threshold() {
             return false;
         }
-        
-        // Don't inline functions that contain calls to other functions
-        for block in &function.basic_blocks {
-            for instruction in &block.instructions {
-                if matches!(instruction.opcode, IrOpcode::Call | IrOpcode::CallIndirect) {
-                    return false;
-                }
-            }
+
+        // Generators, phi nodes and stack allocations depend on the function's own frame
+        function.basic_blocks.iter().all(|block| {
+            block.instructions.iter().all(|instruction| {
+                !matches!(
+                    instruction.opcode,
+                    IrOpcode::Yield | IrOpcode::Phi | IrOpcode::Alloca
+                )
+            })
+        })
+    }
+
+    /// Largest function, in instructions, that is inlined at the current level
+    fn inline_threshold(&self) -> usize {
+        match self.level {
+            OptLevel::O2 => 12,
+            OptLevel::O3 => 40,
+            _ => 0,
         }
-        
-        true
     }
-    
+
     /// Check if a function is recursive
     fn is_recursive_function(&self, function: &IrFunction) -> bool {
         for block in &function.basic_blocks {
             for instruction in &block.instructions {
                 if let IrOpcode::Call = instruction.opcode {
-                    if let Some(IrValue::Function(called_func)) = instruction.operands.first() {
+                    if let Some(IrValue::Global(called_func) | IrValue::Function(called_func)) =
+                        instruction.operands.first()
+                    {
                         if called_func == &function.name {
                             return true;
                         }
@@ -673,131 +699,213 @@ impl IrOptimizer {
         }
         false
     }
-    
-    /// Inline function calls in a function
+
+    /// Inline the calls to `inlinable` functions made directly by `function`
     fn inline_calls_in_function(
         &mut self,
         function: &mut IrFunction,
-        inlinable_functions: &HashSet<String>,
-        function_map: &HashMap<String, IrFunction>,
+        inlinable: &HashMap<String, IrFunction>,
     ) -> Result<()> {
-        let mut changed = true;
-        
-        // Keep inlining until no more changes
-        while changed {
-            changed = false;
-            
-            for block in &mut function.basic_blocks {
-                let mut new_instructions = Vec::new();
-                
-                for instruction in &block.instructions {
-                    if let IrOpcode::Call = instruction.opcode {
-                        if let Some(IrValue::Function(called_func)) = instruction.operands.first() {
-                            if inlinable_functions.contains(called_func) {
-                                // Find the function to inline
-                                if let Some(target_func) = function_map.get(called_func) {
-                                    
-                                    // Inline the function
-                                    let inlined_instructions = self.inline_function_call(
-                                        instruction,
-                                        target_func,
-                                    )?;
-                                    
-                                    new_instructions.extend(inlined_instructions);
-                                    changed = true;
-                                    continue;
-                                }
-                            }
-                        }
-                    }
-                    
-                    // Keep the original instruction
-                    new_instructions.push(instruction.clone());
-                }
-                
-                block.instructions = new_instructions;
+        let mut next_register = max_register_id(function) + 1;
+        let mut inlined_count = 0;
+        let mut block_index = 0;
+        let mut search_from = 0;
+
+        while block_index < function.basic_blocks.len() {
+            let block = &function.basic_blocks[block_index];
+            let call = block.instructions[search_from..]
+                .iter()
+                .position(|instruction| inline_target(instruction, &function.name, inlinable).is_some())
+                .map(|offset| search_from + offset);
+
+            let Some(call_index) = call else {
+                block_index += 1;
+                search_from = 0;
+                continue;
+            };
+
+            let call = block.instructions[call_index].clone();
+            let callee = inline_target(&call, &function.name, inlinable).expect("call target is inlinable");
+
+            let register_offset = next_register;
+            next_register += max_register_id(callee) + 1;
+            inlined_count += 1;
+
+            let mut body = self.inline_function_call(&call, callee, register_offset, inlined_count);
+            function.locals.append(&mut body.locals);
+
+            if let Some(instructions) = body.single_block {
+                // Straight-line callee: splice its instructions in place of the call
+                let spliced = instructions.len();
+                function.basic_blocks[block_index]
+                    .instructions
+                    .splice(call_index..=call_index, instructions);
+                search_from = call_index + spliced;
+                continue;
             }
+
+            // Split the caller's block around the call: the head jumps into the
+            // inlined blocks, which return to a continuation holding the rest
+            let block = &mut function.basic_blocks[block_index];
+            let tail = block.instructions.split_off(call_index + 1);
+            block.instructions.pop();
+            block.instructions.extend(body.parameter_copies);
+            let continuation = IrBasicBlock {
+                label: body.continuation_label,
+                instructions: tail,
+                terminator: std::mem::replace(&mut block.terminator, IrTerminator::Branch(body.entry_label)),
+            };
+
+            let inlined_blocks = body.blocks.len();
+            let insert_at = block_index + 1;
+            function
+                .basic_blocks
+                .splice(insert_at..insert_at, body.blocks.into_iter().chain(std::iter::once(continuation)));
+
+            // Resume after the call, in the continuation block
+            block_index = insert_at + inlined_blocks;
+            search_from = 0;
         }
-        
+
         Ok(())
     }
-    
-    /// Inline a single function call
+
+    /// Copy the body of `target_function` for the call `call_instruction`, with
+    /// registers shifted by `register_offset` and labels made unique by `inline_id`
     fn inline_function_call(
-        &mut self,
+        &self,
         call_instruction: &IrInstruction,
         target_function: &IrFunction,
-    ) -> Result<Vec<IrInstruction>> {
-        let mut inlined_instructions = Vec::new();
-        let mut register_map = HashMap::new();
-        
-        // Map parameters to arguments
-        for (i, param) in target_function.params.iter().enumerate() {
-            if let Some(arg_value) = call_instruction.operands.get(i + 1) { // Skip function name
-                register_map.insert(param.register, arg_value.clone());
-            }
-        }
-        
-        // Generate new registers for the inlined function's locals and results
-        // Start with a high register ID to avoid conflicts
-        let mut next_reg_id = 1000;
-        
-        // Inline instructions from the target function
+        register_offset: u32,
+        inline_id: usize,
+    ) -> InlinedBody {
+        let rename = |register: IrRegister| IrRegister { id: register.id + register_offset };
+        let rename_value = |value: &IrValue| match value {
+            IrValue::Register(register) => IrValue::Register(rename(*register)),
+            other => other.clone(),
+        };
+        let rename_label = |label: &str| format!("inl{}_{}", inline_id, label);
+        let continuation_label = format!("inl{}_cont", inline_id);
+
+        // Parameters may be reassigned by the callee, so each gets its own register
+        let parameter_copies: Vec<IrInstruction> = target_function
+            .params
+            .iter()
+            .zip(&call_instruction.operands[1..])
+            .map(|(param, argument)| IrInstruction {
+                opcode: IrOpcode::Copy,
+                result: Some(rename(param.register)),
+                result_type: Some(param.param_type.clone()),
+                operands: vec![argument.clone()],
+                position: call_instruction.position,
+            })
+            .collect();
+
+        let locals = target_function
+            .params
+            .iter()
+            .map(|param| IrLocal {
+                name: format!("{}.{}", target_function.name, param.name),
+                local_type: param.param_type.clone(),
+                register: rename(param.register),
+                is_mutable: true,
+            })
+            .chain(target_function.locals.iter().map(|local| IrLocal {
+                name: format!("{}.{}", target_function.name, local.name),
+                register: rename(local.register),
+                ..local.clone()
+            }))
+            .collect();
+
+        // The value returned by the callee becomes the call's result
+        let return_copy = |value: &Option<IrValue>| {
+            call_instruction.result.map(|result| IrInstruction {
+                opcode: IrOpcode::Copy,
+                result: Some(result),
+                result_type: call_instruction.result_type.clone(),
+                operands: vec![value
+                    .as_ref()
+                    .map(rename_value)
+                    .unwrap_or(IrValue::Constant(IrConstant::Null))],
+                position: call_instruction.position,
+            })
+        };
+
+        let mut blocks = Vec::new();
         for block in &target_function.basic_blocks {
-            for instruction in &block.instructions {
-                let mut inlined_inst = instruction.clone();
-                
-                // Rename registers
-                if let Some(result_reg) = inlined_inst.result {
-                    let new_reg = IrRegister { id: next_reg_id };
-                    next_reg_id += 1;
-                    register_map.insert(result_reg, IrValue::Register(new_reg));
-                    inlined_inst.result = Some(new_reg);
-                }
-                
-                // Update operands
-                for operand in &mut inlined_inst.operands {
-                    if let IrValue::Register(reg) = operand {
-                        if let Some(mapped_value) = register_map.get(reg) {
-                            *operand = mapped_value.clone();
-                        }
-                    }
+            let mut instructions: Vec<IrInstruction> = block
+                .instructions
+                .iter()
+                .map(|instruction| IrInstruction {
+                    result: instruction.result.map(rename),
+                    operands: instruction.operands.iter().map(rename_value).collect(),
+                    ..instruction.clone()
+                })
+                .collect();
+
+            let terminator = match &block.terminator {
+                IrTerminator::Return(value) => {
+                    instructions.extend(return_copy(value));
+                    IrTerminator::Branch(continuation_label.clone())
                 }
-                
-                inlined_instructions.push(inlined_inst);
-            }
+                IrTerminator::Branch(label) => IrTerminator::Branch(rename_label(label)),
+                IrTerminator::ConditionalBranch {
+                    condition,
+                    true_label,
+                    false_label,
+                } => IrTerminator::ConditionalBranch {
+                    condition: rename_value(condition),
+                    true_label: rename_label(true_label),
+                    false_label: rename_label(false_label),
+                },
+                IrTerminator::Switch {
+                    value,
+                    cases,
+                    default_label,
+                } => IrTerminator::Switch {
+                    value: rename_value(value),
+                    cases: cases
+                        .iter()
+                        .map(|(case, label)| (rename_value(case), rename_label(label)))
+                        .collect(),
+                    default_label: default_label.as_deref().map(rename_label),
+                },
+                IrTerminator::Unreachable => IrTerminator::Unreachable,
+            };
+
+            blocks.push(IrBasicBlock {
+                label: rename_label(&block.label),
+                instructions,
+                terminator,
+            });
         }
-        
-        // Handle return value
-        if let Some(result_reg) = call_instruction.result {
-            // Find the return value from the inlined function
-            for block in &target_function.basic_blocks {
-                if let IrTerminator::Return(Some(return_value)) = &block.terminator {
-                    let mapped_return = if let IrValue::Register(reg) = return_value {
-                        register_map.get(reg).cloned().unwrap_or(return_value.clone())
-                    } else {
-                        return_value.clone()
-                    };
-                    
-                    // Add a copy instruction to assign the return value
-                    inlined_instructions.push(IrInstruction {
-                        opcode: IrOpcode::Copy,
-                        result: Some(result_reg),
-                        result_type: None,
-                        operands: vec![mapped_return],
-                        position: call_instruction.position,
-                    });
-                    break;
-                }
+
+        let single_block = match target_function.basic_blocks.as_slice() {
+            [block] if matches!(block.terminator, IrTerminator::Return(_)) => {
+                let body = blocks.pop().expect("single block").instructions;
+                Some(parameter_copies.iter().cloned().chain(body).collect())
             }
+            _ => None,
+        };
+
+        InlinedBody {
+            entry_label: blocks
+                .first()
+                .map(|block| block.label.clone())
+                .unwrap_or_else(|| continuation_label.clone()),
+            continuation_label,
+            parameter_copies,
+            blocks,
+            locals,
+            single_block,
         }
-        
-        Ok(inlined_instructions)
     }
 
-    /// Loop optimization pass (aggressive optimization)
-    /// Applies various loop optimizations like loop unrolling
-    fn loop_optimization(&mut self, mut program: IrProgram) -> Result<IrProgram> {
+    /// Loop optimization pass
+    /// Applies various loop optimizations like loop unrolling. Not part of
+    /// `optimize`: invariance and unrolling assume each register is written
+    /// once, while locals are reassigned in place.
+    pub fn loop_optimization(&mut self, mut program: IrProgram) -> Result<IrProgram> {
         let analyzer = ControlFlowAnalyzer::new();
         
         for function in &mut program.functions {
@@ -1080,3 +1188,70 @@ impl IrOptimizer {
         Ok(())
     }
 }
+
+/// A callee body copied for one call site
+struct InlinedBody {
+    entry_label: String,
+    /// Label of the block holding the caller's instructions after the call
+    continuation_label: String,
+    /// Arguments copied into the callee's renamed parameters
+    parameter_copies: Vec<IrInstruction>,
+    blocks: Vec<IrBasicBlock>,
+    locals: Vec<IrLocal>,
+    /// Parameter copies and body of a callee made of a single returning block
+    single_block: Option<Vec<IrInstruction>>,
+}
+
+/// The inlinable function called directly by `instruction`, if any
+fn inline_target<'a>(
+    instruction: &IrInstruction,
+    caller: &str,
+    inlinable: &'a HashMap<String, IrFunction>,
+) -> Option<&'a IrFunction> {
+    if instruction.opcode != IrOpcode::Call {
+        return None;
+    }
+    // Direct calls name their callee as a global
+    let (Some(IrValue::Global(name)) | Some(IrValue::Function(name))) = instruction.operands.first() else {
+        return None;
+    };
+    inlinable
+        .get(name)
+        .filter(|callee| callee.name != caller && callee.params.len() + 1 == instruction.operands.len())
+}
+
+/// Highest register id mentioned anywhere in `function`
+fn max_register_id(function: &IrFunction) -> u32 {
+    let value_register = |value: &IrValue| match value {
+        IrValue::Register(register) => Some(register.id),
+        _ => None,
+    };
+
+    let params = function.params.iter().map(|param| param.register.id);
+    let locals = function.locals.iter().map(|local| local.register.id);
+    let instructions = function.basic_blocks.iter().flat_map(|block| {
+        block.instructions.iter().flat_map(|instruction| {
+            instruction
+                .result
+                .map(|register| register.id)
+                .into_iter()
+                .chain(instruction.operands.iter().filter_map(value_register))
+        })
+    });
+    let terminators = function.basic_blocks.iter().flat_map(|block| match &block.terminator {
+        IrTerminator::Return(Some(value)) => vec![value_register(value)],
+        IrTerminator::ConditionalBranch { condition, .. } => vec![value_register(condition)],
+        IrTerminator::Switch { value, cases, .. } => std::iter::once(value)
+            .chain(cases.iter().map(|(case, _)| case))
+            .map(value_register)
+            .collect(),
+        _ => Vec::new(),
+    });
+
+    params
+        .chain(locals)
+        .chain(instructions)
+        .chain(terminators.flatten())
+        .max()
+        .unwrap_or(0)
+}
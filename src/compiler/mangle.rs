@@ -0,0 +1,279 @@
+//! Symbol names of Bulu functions in native objects
+//!
+//! The native backend names each function after its package, its name and
+//! its signature, so that objects of separately built libraries can be linked
+//! into one program without their functions colliding. The scheme is stable:
+//! the same function gets the same symbol from every compiler version.
+//!
+//! ```text
+//! symbol    = "_B" "P" segment+ "F" segment+ "H" hash
+//! segment   = length ["_"] identifier      identifier of [A-Za-z0-9_] only
+//!           | "x" length "_" hex           any other UTF-8 text, as hex digits
+//! hash      = 16 lowercase hex digits
+//! ```
+//!
+//! The package path is split at `/` and the function name at `.`, so the
+//! method `Point.norm` of package `geo/plane` becomes
+//! `_BP3geo5planeF5Point4normH…`. Lengths are decimal and count the bytes
+//! that follow, not counting the `_` that separates the length from an
+//! identifier starting with a digit or `_`. The hash is the 64-bit FNV-1a
+//! hash of the signature written as Bulu types, e.g. `(float64,float64)float64`
+//! or `()` for a function without parameters or result; it tells overloads
+//! of different libraries apart and makes a changed signature fail to link.
+//!
+//! Demangled, a symbol reads as `geo/plane.Point.norm`.
+
+use crate::compiler::ir::{IrFunction, IrType};
+use std::fmt;
+
+/// Package of programs compiled outside of a project
+pub const DEFAULT_PACKAGE: &str = "main";
+
+const PREFIX: &str = "_B";
+
+/// The symbol of `function` in `package`
+pub fn mangle_function(package: &str, function: &IrFunction) -> String {
+    mangle(package, &function.name, &signature(function))
+}
+
+/// The symbol of function `name` of `package` with the given signature
+pub fn mangle(package: &str, name: &str, signature: &str) -> String {
+    let mut symbol = String::from(PREFIX);
+    symbol.push('P');
+    for segment in package.split('/') {
+        push_segment(&mut symbol, segment);
+    }
+    symbol.push('F');
+    for segment in name.split('.') {
+        push_segment(&mut symbol, segment);
+    }
+    symbol.push_str(&format!("H{:016x}", fnv1a(signature.as_bytes())));
+    symbol
+}
+
+/// The signature of `function` as hashed into its symbol, e.g. `(int32,string)bool`
+pub fn signature(function: &IrFunction) -> String {
+    let params: Vec<String> = function.params.iter().map(|param| type_name(&param.param_type)).collect();
+    let result = match &function.return_type {
+        None | Some(IrType::Void) => String::new(),
+        Some(return_type) => type_name(return_type),
+    };
+    format!("({}){}", params.join(","), result)
+}
+
+fn type_name(ir_type: &IrType) -> String {
+    match ir_type {
+        IrType::I8 => "int8".to_string(),
+        IrType::I16 => "int16".to_string(),
+        IrType::I32 => "int32".to_string(),
+        IrType::I64 => "int64".to_string(),
+        IrType::U8 => "uint8".to_string(),
+        IrType::U16 => "uint16".to_string(),
+        IrType::U32 => "uint32".to_string(),
+        IrType::U64 => "uint64".to_string(),
+        IrType::F32 => "float32".to_string(),
+        IrType::F64 => "float64".to_string(),
+        IrType::Bool => "bool".to_string(),
+        IrType::Char => "char".to_string(),
+        IrType::String => "string".to_string(),
+        IrType::Any => "any".to_string(),
+        IrType::Void => "void".to_string(),
+        IrType::Array(element, Some(size)) => format!("[{}]{}", size, type_name(element)),
+        IrType::Array(element, None) | IrType::Slice(element) => format!("[]{}", type_name(element)),
+        IrType::Map(key, value) => format!("map[{}]{}", type_name(key), type_name(value)),
+        IrType::Tuple(types) => format!("({})", types.iter().map(type_name).collect::<Vec<_>>().join(",")),
+        IrType::Function(params, result) => format!(
+            "func({}){}",
+            params.iter().map(type_name).collect::<Vec<_>>().join(","),
+            result.as_deref().map_or(String::new(), type_name)
+        ),
+        IrType::Struct(name) | IrType::Interface(name) => name.clone(),
+        IrType::Channel(element) => format!("chan {}", type_name(element)),
+        IrType::Promise(element) => format!("Promise<{}>", type_name(element)),
+        IrType::Pointer(element) => format!("*{}", type_name(element)),
+    }
+}
+
+fn push_segment(symbol: &mut String, segment: &str) {
+    if segment.is_empty() || !segment.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'_') {
+        let hex: String = segment.bytes().map(|byte| format!("{:02x}", byte)).collect();
+        symbol.push_str(&format!("x{}_{}", hex.len(), hex));
+        return;
+    }
+    symbol.push_str(&segment.len().to_string());
+    if segment.starts_with(|c: char| c.is_ascii_digit() || c == '_') {
+        symbol.push('_');
+    }
+    symbol.push_str(segment);
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// A symbol read back into its parts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Demangled {
+    /// Package path, e.g. `geo/plane`
+    pub package: String,
+    /// Function name, with the type of methods, e.g. `Point.norm`
+    pub name: String,
+    /// Hash of the signature
+    pub hash: u64,
+}
+
+impl fmt::Display for Demangled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.package, self.name)
+    }
+}
+
+/// Read a symbol made by `mangle`, or None for any other symbol
+pub fn demangle(symbol: &str) -> Option<Demangled> {
+    let (demangled, rest) = demangle_prefix(symbol)?;
+    rest.is_empty().then_some(demangled)
+}
+
+/// Replace every mangled symbol in `text`, e.g. a stack trace or linker
+/// output, with its demangled form
+pub fn demangle_text(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(PREFIX) {
+        let preceded_by_word = rest[..start].ends_with(|c: char| c.is_ascii_alphanumeric() || c == '_');
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match demangle_prefix(rest) {
+            Some((demangled, after)) if !preceded_by_word && !after.starts_with(|c: char| c.is_ascii_alphanumeric()) => {
+                result.push_str(&demangled.to_string());
+                rest = after;
+            }
+            _ => {
+                result.push_str(PREFIX);
+                rest = &rest[PREFIX.len()..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// A symbol at the start of `text`, and the text after it
+fn demangle_prefix(text: &str) -> Option<(Demangled, &str)> {
+    let rest = text.strip_prefix(PREFIX)?.strip_prefix('P')?;
+    let (package, rest) = segments(rest)?;
+    let rest = rest.strip_prefix('F')?;
+    let (name, rest) = segments(rest)?;
+    let rest = rest.strip_prefix('H')?;
+    let digits = rest.get(..16)?;
+    if !digits.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f')) {
+        return None;
+    }
+    let hash = u64::from_str_radix(digits, 16).ok()?;
+    let demangled = Demangled {
+        package: package.join("/"),
+        name: name.join("."),
+        hash,
+    };
+    Some((demangled, &rest[16..]))
+}
+
+/// One or more segments, and the text after them
+fn segments(mut text: &str) -> Option<(Vec<String>, &str)> {
+    let mut segments = Vec::new();
+    loop {
+        let hex = text.starts_with('x');
+        let body = if hex { &text[1..] } else { text };
+        let digits = body.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            break;
+        }
+        let length: usize = body[..digits].parse().ok()?;
+        let mut rest = &body[digits..];
+        if hex {
+            rest = rest.strip_prefix('_')?;
+        } else {
+            rest = rest
+                .strip_prefix('_')
+                .filter(|after| after.starts_with(|c: char| c.is_ascii_digit() || c == '_'))
+                .unwrap_or(rest);
+        }
+        let segment = rest.get(..length)?;
+        if hex {
+            if !length.is_multiple_of(2) {
+                return None;
+            }
+            let bytes = (0..length)
+                .step_by(2)
+                .map(|index| u8::from_str_radix(&segment[index..index + 2], 16).ok())
+                .collect::<Option<Vec<u8>>>()?;
+            segments.push(String::from_utf8(bytes).ok()?);
+        } else {
+            segments.push(segment.to_string());
+        }
+        text = &rest[length..];
+    }
+    (!segments.is_empty()).then_some((segments, text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbols_round_trip() {
+        let cases = [
+            ("main", "main", "()"),
+            ("geo/plane", "Point.norm", "(Point)float64"),
+            ("my-lib/2d", "_private", "(int32)"),
+            ("example.com/ünï", "lambda_12", "()any"),
+        ];
+        for (package, name, signature) in cases {
+            let symbol = mangle(package, name, signature);
+            assert!(
+                symbol.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'_'),
+                "{}",
+                symbol
+            );
+            let demangled = demangle(&symbol).unwrap();
+            assert_eq!(demangled.package, package);
+            assert_eq!(demangled.name, name);
+            assert_eq!(demangled.hash, fnv1a(signature.as_bytes()));
+            assert_eq!(demangled.to_string(), format!("{}.{}", package, name));
+        }
+    }
+
+    #[test]
+    fn test_scheme_is_stable() {
+        assert_eq!(mangle("main", "main", "()"), "_BP4mainF4mainH07e11f07b4a6665a");
+        assert_eq!(mangle("geo/plane", "Point.norm", "()float64"), "_BP3geo5planeF5Point4normHa2879dc55b193d6a");
+        assert_eq!(mangle("a", "2d", "()"), "_BP1aF2_2dH07e11f07b4a6665a");
+        assert_ne!(mangle("a", "f", "(int32)"), mangle("a", "f", "(int64)"));
+        assert_ne!(mangle("a/b", "f", "()"), mangle("a", "b.f", "()"));
+    }
+
+    #[test]
+    fn test_other_symbols_are_not_demangled() {
+        for symbol in ["main", "_start", "__malloc", "_BP4mainF4main", "_BP4mainF4mainHxyz", "_BP4main"] {
+            assert_eq!(demangle(symbol), None, "{}", symbol);
+        }
+        let symbol = mangle("app", "parse", "(string)int32");
+        assert_eq!(demangle(&format!("{}x", symbol)), None);
+    }
+
+    #[test]
+    fn test_demangle_text() {
+        let parse = mangle("app", "parse", "(string)int32");
+        let main = mangle("app", "main", "()");
+        let trace = format!(
+            "#0  0x401a2c in {} ()\n#1  0x401b00 in {} ()\nundefined reference to `{}'\nmy_BPsymbol",
+            parse, main, parse
+        );
+        assert_eq!(
+            demangle_text(&trace),
+            "#0  0x401a2c in app.parse ()\n#1  0x401b00 in app.main ()\nundefined reference to `app.parse'\nmy_BPsymbol"
+        );
+    }
+}
@@ -11,20 +11,39 @@ pub mod ir_optimizer;
 pub mod control_flow;
 pub mod symbol_resolver;
 pub mod native_backend;
+pub mod intrinsics;
+pub mod mangle;
+pub mod session;
 
 pub use semantic::SemanticAnalyzer;
 pub use codegen::CodeGenerator;
 pub use ir::{IrGenerator, IrProgram};
+pub use optimizer::Optimizer;
 pub use ir_optimizer::IrOptimizer;
 pub use control_flow::ControlFlowAnalyzer;
 pub use symbol_resolver::SymbolResolver;
+pub use session::{CompileSession, Diagnostic, DiagnosticSink, Stage};
 
 /// Optimization levels
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OptLevel {
     O0, // No optimization
     O1, // Basic optimization
     O2, // Standard optimization
     O3, // Aggressive optimization
     Os, // Optimize for size
-}
\ No newline at end of file
+}
+
+impl OptLevel {
+    /// Parse a level as written after `-O` or in `[build] optimization`
+    pub fn parse(level: &str) -> crate::Result<Self> {
+        match level {
+            "0" => Ok(OptLevel::O0),
+            "1" => Ok(OptLevel::O1),
+            "2" => Ok(OptLevel::O2),
+            "3" => Ok(OptLevel::O3),
+            "s" => Ok(OptLevel::Os),
+            _ => Err(crate::BuluError::Other(format!("Invalid optimization level: {}", level))),
+        }
+    }
+}
@@ -3,20 +3,37 @@
 use crate::compiler::ir::{
     IrConstant, IrFunction, IrInstruction, IrOpcode, IrProgram, IrTerminator, IrValue,
 };
+use crate::compiler::{intrinsics, mangle};
 use crate::error::{BuluError, Result};
 use std::collections::HashMap;
 
 pub struct NativeBackend {
+    /// Architecture intrinsics are lowered for, e.g. `x86_64`
     target_arch: String,
+    /// Package the functions are named after, see `mangle`
+    package: String,
 }
 
 impl NativeBackend {
     pub fn new() -> Self {
         Self {
             target_arch: "x86_64".to_string(),
+            package: mangle::DEFAULT_PACKAGE.to_string(),
         }
     }
 
+    /// Name the program's functions after `package`
+    pub fn with_package(mut self, package: &str) -> Self {
+        self.package = package.to_string();
+        self
+    }
+
+    /// Build for `target`, as accepted by `langc --target`
+    pub fn with_target(mut self, target: &str) -> Self {
+        self.target_arch = intrinsics::target_arch(target);
+        self
+    }
+
     /// Generate a native executable from IR (Go-style)
     pub fn generate_executable(&self, ir_program: &IrProgram) -> Result<Vec<u8>> {
         self.generate_executable_with_name(ir_program, "program")
@@ -34,6 +51,8 @@ impl NativeBackend {
 
     /// Generate assembly code from IR program
     fn generate_assembly(&self, ir_program: &IrProgram) -> Result<String> {
+        let symbols = self.function_symbols(ir_program);
+        let ir_program = &with_symbol_names(ir_program, &symbols);
         let mut asm = String::new();
 
         // Data section for strings and globals
@@ -94,7 +113,7 @@ impl NativeBackend {
         // Entry point
         asm.push_str("_start:\n");
         asm.push_str("    call __init_heap\n");
-        asm.push_str("    call main\n");
+        asm.push_str(&format!("    call {}\n", symbols.get("main").map_or("main", String::as_str)));
         asm.push_str("    mov $60, %rax    # sys_exit\n");
         asm.push_str("    xor %rdi, %rdi   # exit code 0\n");
         asm.push_str("    syscall\n");
@@ -102,6 +121,15 @@ impl NativeBackend {
         Ok(asm)
     }
 
+    /// The symbol of each function of the program, by name
+    fn function_symbols(&self, ir_program: &IrProgram) -> HashMap<String, String> {
+        ir_program
+            .functions
+            .iter()
+            .map(|func| (func.name.clone(), mangle::mangle_function(&self.package, func)))
+            .collect()
+    }
+
     /// Generate runtime helper functions
     fn generate_runtime(&self, asm: &mut String) -> Result<()> {
         // Compiler intrinsic: print integer function
@@ -464,6 +492,7 @@ impl NativeBackend {
         func: &IrFunction,
         strings: &HashMap<String, usize>,
     ) -> Result<()> {
+        asm.push_str(&format!(".globl {}\n", func.name));
         asm.push_str(&format!("{}:\n", func.name));
         asm.push_str("    push %rbp\n");
         asm.push_str("    mov %rsp, %rbp\n");
@@ -1106,6 +1135,8 @@ impl NativeBackend {
                                 _ => {}
                             }
                         }
+                    } else if name == "intrinsic" {
+                        self.generate_intrinsic_call(asm, inst, reg_map)?;
                     } else {
                         // Normal function call - treat all user functions the same
                         self.generate_normal_function_call(asm, inst, reg_map, strings, name, func_name, label_counter)?;
@@ -1586,6 +1617,58 @@ impl NativeBackend {
         Ok(())
     }
 
+    /// Emit the instructions of an `intrinsic("name", args...)` call inline
+    fn generate_intrinsic_call(
+        &self,
+        asm: &mut String,
+        inst: &IrInstruction,
+        reg_map: &HashMap<u32, i32>,
+    ) -> Result<()> {
+        let name = match inst.operands.get(1) {
+            Some(IrValue::Constant(IrConstant::String(name))) => name,
+            _ => {
+                return Err(BuluError::Other(
+                    "The first argument to 'intrinsic' must be a string literal naming the intrinsic".to_string(),
+                ))
+            }
+        };
+        let instructions = intrinsics::lower(name, &self.target_arch).map_err(BuluError::Other)?;
+
+        for (arg, reg) in inst.operands.iter().skip(2).zip(["%rdi", "%rsi"]) {
+            match arg {
+                IrValue::Register(arg_reg) => {
+                    let offset = reg_map.get(&arg_reg.id).ok_or_else(|| {
+                        BuluError::Other(format!(
+                            "Argument {} to intrinsic '{}' has no stack slot",
+                            arg_reg, name
+                        ))
+                    })?;
+                    asm.push_str(&format!("    movq {}(%rbp), {}\n", offset, reg));
+                }
+                IrValue::Constant(IrConstant::Integer(val)) => {
+                    asm.push_str(&format!("    movq ${}, {}\n", val, reg));
+                }
+                other => {
+                    return Err(BuluError::Other(format!(
+                        "Unsupported argument {} to intrinsic '{}'",
+                        other, name
+                    )))
+                }
+            }
+        }
+        asm.push_str(&format!("    # intrinsic {}\n", name));
+        for instruction in instructions {
+            asm.push_str(&format!("    {}\n", instruction));
+        }
+
+        if let Some(result) = inst.result {
+            if let Some(&res_offset) = reg_map.get(&result.id) {
+                asm.push_str(&format!("    movq %rax, {}(%rbp)\n", res_offset));
+            }
+        }
+        Ok(())
+    }
+
     /// Generate assembly for a terminator instruction
     fn generate_terminator(
         &self,
@@ -1724,7 +1807,7 @@ _start:\n\
         if !output.status.success() {
             return Err(BuluError::Other(format!(
                 "Linking failed: {}",
-                String::from_utf8_lossy(&output.stderr)
+                mangle::demangle_text(&String::from_utf8_lossy(&output.stderr))
             )));
         }
 
@@ -1738,3 +1821,28 @@ _start:\n\
         Ok(exe_bytes)
     }
 }
+
+/// The program with its functions, and the calls and references to them,
+/// renamed to their symbols. Builtins and runtime helpers keep their names.
+fn with_symbol_names(ir_program: &IrProgram, symbols: &HashMap<String, String>) -> IrProgram {
+    let rename = |value: &mut IrValue| {
+        if let IrValue::Function(name) | IrValue::Global(name) = value {
+            if let Some(symbol) = symbols.get(name) {
+                *name = symbol.clone();
+            }
+        }
+    };
+    let mut ir_program = ir_program.clone();
+    for func in &mut ir_program.functions {
+        func.name = symbols[&func.name].clone();
+        for bb in &mut func.basic_blocks {
+            for inst in &mut bb.instructions {
+                inst.operands.iter_mut().for_each(rename);
+            }
+            if let IrTerminator::Return(Some(value)) = &mut bb.terminator {
+                rename(value);
+            }
+        }
+    }
+    ir_program
+}
@@ -1,17 +1,829 @@
-//! Code optimization (stub)
+//! AST optimizations
+//!
+//! The optimizer runs constant evaluation before type checking. It folds
+//! arithmetic, string concatenation and `len()` of literals, checks that every
+//! `const` initializer is a constant expression, and resolves array sizes written
+//! with constants (`[SIZE]int32`, `[SIZE * 2]int32`).
+//!
+//! Outside `const` initializers and array sizes only literals are folded, so a
+//! constant's declared type is never lost by inlining its value.
 
-use crate::ast::Program;
-use crate::error::Result;
+use crate::ast::*;
+use crate::error::{BuluError, Result};
+use crate::lexer::token::Position;
+use std::collections::HashMap;
 
-pub struct Optimizer;
+/// A value known at compile time
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    String(String),
+    Bytes(Vec<u8>),
+    Array(Vec<ConstValue>),
+    Null,
+}
+
+impl ConstValue {
+    /// The value as a literal expression
+    pub fn to_expression(&self, position: Position) -> Expression {
+        let value = match self {
+            ConstValue::Integer(value) => LiteralValue::Integer(*value),
+            ConstValue::Float(value) => LiteralValue::Float(*value),
+            ConstValue::Bool(value) => LiteralValue::Boolean(*value),
+            ConstValue::Char(value) => LiteralValue::Char(*value),
+            ConstValue::String(value) => LiteralValue::String(value.clone()),
+            ConstValue::Bytes(value) => LiteralValue::ByteString(value.clone()),
+            ConstValue::Null => LiteralValue::Null,
+            ConstValue::Array(items) => {
+                return Expression::Array(ArrayExpr {
+                    elements: items.iter().map(|item| item.to_expression(position)).collect(),
+                    position,
+                })
+            }
+        };
+        Expression::Literal(LiteralExpr { value, position })
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            ConstValue::Integer(_) => "int",
+            ConstValue::Float(_) => "float",
+            ConstValue::Bool(_) => "bool",
+            ConstValue::Char(_) => "char",
+            ConstValue::String(_) => "string",
+            ConstValue::Bytes(_) => "[]byte",
+            ConstValue::Array(_) => "array",
+            ConstValue::Null => "null",
+        }
+    }
+}
+
+impl From<&LiteralValue> for ConstValue {
+    fn from(value: &LiteralValue) -> Self {
+        match value {
+            LiteralValue::Integer(value) => ConstValue::Integer(*value),
+            LiteralValue::Float(value) => ConstValue::Float(*value),
+            LiteralValue::Boolean(value) => ConstValue::Bool(*value),
+            LiteralValue::Char(value) => ConstValue::Char(*value),
+            LiteralValue::String(value) => ConstValue::String(value.clone()),
+            LiteralValue::ByteString(value) => ConstValue::Bytes(value.clone()),
+            LiteralValue::Null => ConstValue::Null,
+        }
+    }
+}
+
+/// The value of an expression made only of literals and operators. `None` when
+/// it has other operands or no valid value, e.g. a division by zero, which is
+/// then left to fail at run time.
+pub fn literal_value(expr: &Expression) -> Option<ConstValue> {
+    match expr {
+        Expression::Literal(literal) => Some(ConstValue::from(&literal.value)),
+        Expression::Parenthesized(inner) => literal_value(&inner.expr),
+        Expression::Unary(unary) => eval_unary(unary.operator, literal_value(&unary.operand)?, unary.position).ok(),
+        Expression::Binary(binary) => eval_binary(
+            binary.operator,
+            literal_value(&binary.left)?,
+            literal_value(&binary.right)?,
+            binary.position,
+        )
+        .ok(),
+        _ => None,
+    }
+}
+
+/// Why an expression could not be evaluated at compile time
+enum EvalError {
+    /// The expression depends on something only known at run time
+    NotConstant(String, Position),
+    /// The expression is constant but has no valid value, e.g. a division by zero
+    Invalid(String, Position),
+}
+
+type EvalResult = std::result::Result<ConstValue, EvalError>;
+
+pub struct Optimizer {
+    /// Names in scope, innermost last; `None` marks a binding that is not a constant
+    scopes: Vec<HashMap<String, Option<ConstValue>>>,
+    file_path: Option<String>,
+}
 
 impl Optimizer {
     pub fn new() -> Self {
-        Self
+        Self {
+            scopes: vec![HashMap::new()],
+            file_path: None,
+        }
+    }
+
+    /// Set the file reported in errors
+    pub fn set_file_path(&mut self, file_path: Option<String>) {
+        self.file_path = file_path;
     }
-    
-    pub fn optimize(&mut self, program: Program) -> Result<Program> {
-        // TODO: Implement optimizations
+
+    pub fn optimize(&mut self, mut program: Program) -> Result<Program> {
+        // A file without the prelude may declare its own `len`
+        for statement in &program.statements {
+            if let Statement::FunctionDecl(decl) = statement {
+                self.declare(&decl.name, None);
+            }
+        }
+
+        // Top-level constants first, so functions may use constants declared after them
+        for statement in &mut program.statements {
+            if is_const_declaration(statement) {
+                self.optimize_statement(statement)?;
+            }
+        }
+        for statement in &mut program.statements {
+            if !is_const_declaration(statement) {
+                self.optimize_statement(statement)?;
+            }
+        }
         Ok(program)
     }
-}
\ No newline at end of file
+
+    fn optimize_statement(&mut self, statement: &mut Statement) -> Result<()> {
+        match statement {
+            Statement::VariableDecl(decl) => self.optimize_binding(
+                decl.is_const,
+                &decl.name,
+                decl.type_annotation.as_mut(),
+                decl.initializer.as_mut(),
+                decl.position,
+            ),
+            Statement::MultipleVariableDecl(decl) => {
+                for single in &mut decl.declarations {
+                    self.optimize_binding(
+                        decl.is_const,
+                        &single.name,
+                        single.type_annotation.as_mut(),
+                        single.initializer.as_mut(),
+                        decl.position,
+                    )?;
+                }
+                Ok(())
+            }
+            Statement::DestructuringDecl(decl) => {
+                self.fold_expression(&mut decl.initializer)?;
+                if decl.is_const {
+                    self.eval(&decl.initializer, true).map_err(|e| {
+                        self.const_error("Destructured constant must be a constant expression", e)
+                    })?;
+                }
+                self.declare_pattern(&decl.pattern);
+                Ok(())
+            }
+            Statement::MultipleAssignment(stmt) => {
+                for value in &mut stmt.values {
+                    self.fold_expression(value)?;
+                }
+                Ok(())
+            }
+            Statement::FunctionDecl(decl) => self.optimize_function(decl),
+            Statement::StructDecl(decl) => {
+                for field in &mut decl.fields {
+                    self.resolve_type(&mut field.field_type)?;
+                }
+                for method in &mut decl.methods {
+                    self.optimize_function(method)?;
+                }
+                Ok(())
+            }
+            Statement::InterfaceDecl(decl) => {
+                for method in &mut decl.methods {
+                    self.in_scope(|this| {
+                        this.optimize_parameters(&mut method.params)?;
+                        if let Some(return_type) = &mut method.return_type {
+                            this.resolve_type(return_type)?;
+                        }
+                        match &mut method.default_body {
+                            Some(body) => this.optimize_statements(&mut body.statements),
+                            None => Ok(()),
+                        }
+                    })?;
+                }
+                Ok(())
+            }
+            Statement::TypeAlias(decl) => self.resolve_type(&mut decl.target_type),
+            Statement::If(stmt) => {
+                self.fold_expression(&mut stmt.condition)?;
+                self.optimize_block(&mut stmt.then_branch)?;
+                match &mut stmt.else_branch {
+                    Some(else_branch) => self.optimize_statement(else_branch),
+                    None => Ok(()),
+                }
+            }
+            Statement::While(stmt) => {
+                self.fold_expression(&mut stmt.condition)?;
+                self.optimize_block(&mut stmt.body)
+            }
+            Statement::For(stmt) => {
+                self.fold_expression(&mut stmt.iterable)?;
+                self.in_scope(|this| {
+                    this.declare(&stmt.variable, None);
+                    if let Some(index) = &stmt.index_variable {
+                        this.declare(index, None);
+                    }
+                    this.optimize_statements(&mut stmt.body.statements)
+                })
+            }
+            Statement::Match(stmt) => {
+                self.fold_expression(&mut stmt.expr)?;
+                for arm in &mut stmt.arms {
+                    self.in_scope(|this| {
+                        this.declare_pattern(&arm.pattern);
+                        if let Some(guard) = &mut arm.guard {
+                            this.fold_expression(guard)?;
+                        }
+                        this.optimize_statement(&mut arm.body)
+                    })?;
+                }
+                Ok(())
+            }
+            Statement::Select(stmt) => {
+                for arm in &mut stmt.arms {
+                    self.in_scope(|this| {
+                        if let Some(op) = &mut arm.channel_op {
+                            this.optimize_channel_operation(op)?;
+                        }
+                        this.optimize_statement(&mut arm.body)
+                    })?;
+                }
+                Ok(())
+            }
+            Statement::Return(stmt) => match &mut stmt.value {
+                Some(value) => self.fold_expression(value),
+                None => Ok(()),
+            },
+            Statement::Defer(stmt) => self.optimize_statement(&mut stmt.stmt),
+            Statement::Try(stmt) => {
+                self.optimize_block(&mut stmt.body)?;
+                match &mut stmt.catch_clause {
+                    Some(catch) => self.in_scope(|this| {
+                        if let Some(error_var) = &catch.error_var {
+                            this.declare(error_var, None);
+                        }
+                        this.optimize_statements(&mut catch.body.statements)
+                    }),
+                    None => Ok(()),
+                }
+            }
+            Statement::Fail(stmt) => self.fold_expression(&mut stmt.message),
+            Statement::Export(stmt) => self.optimize_statement(&mut stmt.item),
+            Statement::Expression(stmt) => self.fold_expression(&mut stmt.expr),
+            Statement::Block(stmt) => self.optimize_block(stmt),
+            Statement::Break(_) | Statement::Continue(_) | Statement::Import(_) => Ok(()),
+        }
+    }
+
+    /// Fold a `let` or `const` binding and bring it into scope
+    fn optimize_binding(
+        &mut self,
+        is_const: bool,
+        name: &str,
+        type_annotation: Option<&mut Type>,
+        initializer: Option<&mut Expression>,
+        position: Position,
+    ) -> Result<()> {
+        // Resolve sizes before the initializer so its length can be checked
+        let type_annotation = match type_annotation {
+            Some(type_annotation) => {
+                self.resolve_type(type_annotation)?;
+                Some(&*type_annotation)
+            }
+            None => None,
+        };
+
+        let Some(initializer) = initializer else {
+            self.declare(name, None);
+            return Ok(());
+        };
+        self.fold_expression(initializer)?;
+
+        if let (Some(Type::Array(ArrayType { size: Some(size), .. })), Expression::Array(array)) =
+            (type_annotation, &*initializer)
+        {
+            if array.elements.len() != *size {
+                return Err(self.error(
+                    format!(
+                        "Array of size {} cannot be initialized with {} elements",
+                        size,
+                        array.elements.len()
+                    ),
+                    array.position,
+                ));
+            }
+        }
+
+        if !is_const {
+            self.declare(name, None);
+            return Ok(());
+        }
+
+        let mut value = self
+            .eval(initializer, true)
+            .map_err(|e| {
+                self.const_error(&format!("The value of constant '{}' must be a constant expression", name), e)
+            })?;
+        // `const RATE: float64 = 2` holds a float
+        if let (Some(Type::Float32 | Type::Float64), ConstValue::Integer(int)) = (type_annotation, &value) {
+            value = ConstValue::Float(*int as f64);
+        }
+        if !matches!(value, ConstValue::Array(_)) {
+            *initializer = value.to_expression(position_of(initializer, position));
+        }
+        self.declare(name, Some(value));
+        Ok(())
+    }
+
+    fn optimize_function(&mut self, decl: &mut FunctionDecl) -> Result<()> {
+        if let Some(return_type) = &mut decl.return_type {
+            self.resolve_type(return_type)?;
+        }
+        self.in_scope(|this| {
+            this.optimize_parameters(&mut decl.params)?;
+            this.optimize_statements(&mut decl.body.statements)
+        })
+    }
+
+    /// Resolve parameter types and bring the parameters into the current scope
+    fn optimize_parameters(&mut self, params: &mut [Parameter]) -> Result<()> {
+        for param in params {
+            self.resolve_type(&mut param.param_type)?;
+            if let Some(default) = &mut param.default_value {
+                self.fold_expression(default)?;
+            }
+            self.declare(&param.name, None);
+        }
+        Ok(())
+    }
+
+    fn optimize_channel_operation(&mut self, op: &mut ChannelOperation) -> Result<()> {
+        self.fold_expression(&mut op.channel)?;
+        if let Some(value) = &mut op.value {
+            self.fold_expression(value)?;
+        }
+        if let Some(variable) = &op.variable {
+            self.declare(variable, None);
+        }
+        Ok(())
+    }
+
+    fn optimize_block(&mut self, block: &mut BlockStmt) -> Result<()> {
+        self.in_scope(|this| this.optimize_statements(&mut block.statements))
+    }
+
+    fn optimize_statements(&mut self, statements: &mut [Statement]) -> Result<()> {
+        for statement in statements {
+            self.optimize_statement(statement)?;
+        }
+        Ok(())
+    }
+
+    /// Fold the constant parts of `expr`, innermost first
+    fn fold_expression(&mut self, expr: &mut Expression) -> Result<()> {
+        match expr {
+            Expression::Literal(_) | Expression::Identifier(_) => return Ok(()),
+            Expression::Binary(binary) => {
+                self.fold_expression(&mut binary.left)?;
+                self.fold_expression(&mut binary.right)?;
+            }
+            Expression::Unary(unary) => self.fold_expression(&mut unary.operand)?,
+            Expression::Call(call) => {
+                self.fold_expression(&mut call.callee)?;
+                for arg in &mut call.args {
+                    self.fold_expression(arg)?;
+                }
+                for type_arg in &mut call.type_args {
+                    self.resolve_type(type_arg)?;
+                }
+            }
+            Expression::MemberAccess(access) => self.fold_expression(&mut access.object)?,
+            Expression::Index(index) => {
+                self.fold_expression(&mut index.object)?;
+                self.fold_expression(&mut index.index)?;
+            }
+            Expression::Assignment(assignment) => self.fold_expression(&mut assignment.value)?,
+            Expression::If(if_expr) => {
+                self.fold_expression(&mut if_expr.condition)?;
+                self.fold_expression(&mut if_expr.then_expr)?;
+                self.fold_expression(&mut if_expr.else_expr)?;
+            }
+            Expression::Match(match_expr) => {
+                self.fold_expression(&mut match_expr.expr)?;
+                for arm in &mut match_expr.arms {
+                    self.in_scope(|this| {
+                        this.declare_pattern(&arm.pattern);
+                        if let Some(guard) = &mut arm.guard {
+                            this.fold_expression(guard)?;
+                        }
+                        this.fold_expression(&mut arm.expr)
+                    })?;
+                }
+            }
+            Expression::Set(set) => {
+                for element in &mut set.elements {
+                    self.fold_expression(element)?;
+                }
+            }
+            Expression::Array(array) => {
+                for element in &mut array.elements {
+                    self.fold_expression(element)?;
+                }
+            }
+            Expression::Tuple(tuple) => {
+                for element in &mut tuple.elements {
+                    self.fold_expression(element)?;
+                }
+            }
+            Expression::Map(map) => {
+                for entry in &mut map.entries {
+                    self.fold_expression(&mut entry.key)?;
+                    self.fold_expression(&mut entry.value)?;
+                }
+            }
+            Expression::StructLiteral(literal) => {
+                for type_arg in &mut literal.type_args {
+                    self.resolve_type(type_arg)?;
+                }
+                for field in &mut literal.fields {
+                    self.fold_expression(&mut field.value)?;
+                }
+            }
+            Expression::Lambda(lambda) => {
+                if let Some(return_type) = &mut lambda.return_type {
+                    self.resolve_type(return_type)?;
+                }
+                self.in_scope(|this| {
+                    this.optimize_parameters(&mut lambda.params)?;
+                    this.fold_expression(&mut lambda.body)
+                })?;
+            }
+            Expression::Async(inner) => self.fold_expression(&mut inner.expr)?,
+            Expression::Await(inner) => self.fold_expression(&mut inner.expr)?,
+            Expression::Run(inner) => self.fold_expression(&mut inner.expr)?,
+            Expression::Channel(channel) => {
+                self.fold_expression(&mut channel.channel)?;
+                if let Some(value) = &mut channel.value {
+                    self.fold_expression(value)?;
+                }
+            }
+            Expression::Select(select) => {
+                for arm in &mut select.arms {
+                    self.in_scope(|this| {
+                        if let Some(op) = &mut arm.channel_op {
+                            this.optimize_channel_operation(op)?;
+                        }
+                        this.fold_expression(&mut arm.expr)
+                    })?;
+                }
+            }
+            Expression::Cast(cast) => {
+                self.fold_expression(&mut cast.expr)?;
+                self.resolve_type(&mut cast.target_type)?;
+            }
+            Expression::TypeOf(inner) => self.fold_expression(&mut inner.expr)?,
+            Expression::Propagate(inner) => self.fold_expression(&mut inner.expr)?,
+            Expression::Range(range) => {
+                self.fold_expression(&mut range.start)?;
+                self.fold_expression(&mut range.end)?;
+                if let Some(step) = &mut range.step {
+                    self.fold_expression(step)?;
+                }
+            }
+            Expression::Yield(yield_expr) => {
+                if let Some(value) = &mut yield_expr.value {
+                    self.fold_expression(value)?;
+                }
+            }
+            Expression::Parenthesized(inner) => self.fold_expression(&mut inner.expr)?,
+            Expression::Block(block) => {
+                self.in_scope(|this| this.optimize_statements(&mut block.statements))?;
+            }
+        }
+
+        if matches!(
+            expr,
+            Expression::Binary(_) | Expression::Unary(_) | Expression::Parenthesized(_) | Expression::Call(_)
+        ) {
+            if let Ok(value) = self.eval(expr, false) {
+                if !matches!(value, ConstValue::Array(_)) {
+                    *expr = value.to_expression(expr.position());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace array sizes written as constant expressions with their value
+    fn resolve_type(&mut self, type_node: &mut Type) -> Result<()> {
+        match type_node {
+            Type::Array(array) => {
+                self.resolve_type(&mut array.element_type)?;
+                if let Some(size_expr) = array.size_expr.take() {
+                    let position = size_expr.position();
+                    let size = match self.eval(&size_expr, true) {
+                        Ok(ConstValue::Integer(size)) if size >= 0 => size as usize,
+                        Ok(value) => {
+                            return Err(self.error(
+                                format!("Array size must be a non-negative integer, found {} {:?}", value.type_name(), value),
+                                position,
+                            ))
+                        }
+                        Err(e) => return Err(self.const_error("Array size must be a constant expression", e)),
+                    };
+                    array.size = Some(size);
+                }
+            }
+            Type::Slice(slice) => self.resolve_type(&mut slice.element_type)?,
+            Type::Map(map) => {
+                self.resolve_type(&mut map.key_type)?;
+                self.resolve_type(&mut map.value_type)?;
+            }
+            Type::Tuple(tuple) => {
+                for element in &mut tuple.element_types {
+                    self.resolve_type(element)?;
+                }
+            }
+            Type::Function(function) => {
+                for param in &mut function.param_types {
+                    self.resolve_type(param)?;
+                }
+                if let Some(return_type) = &mut function.return_type {
+                    self.resolve_type(return_type)?;
+                }
+            }
+            Type::Struct(StructType { type_args, .. }) | Type::Interface(InterfaceType { type_args, .. }) => {
+                for arg in type_args {
+                    self.resolve_type(arg)?;
+                }
+            }
+            Type::Channel(channel) => self.resolve_type(&mut channel.element_type)?,
+            Type::Promise(promise) => self.resolve_type(&mut promise.result_type)?,
+            Type::Union(union) => {
+                for member in &mut union.types {
+                    self.resolve_type(member)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Evaluate `expr`; names resolve to constants in scope only with `resolve_names`
+    fn eval(&self, expr: &Expression, resolve_names: bool) -> EvalResult {
+        match expr {
+            Expression::Literal(literal) => Ok(ConstValue::from(&literal.value)),
+            Expression::Identifier(ident) => {
+                let not_constant = || EvalError::NotConstant(format!("'{}' is not a constant", ident.name), ident.position);
+                if !resolve_names {
+                    return Err(not_constant());
+                }
+                match self.lookup(&ident.name) {
+                    Some(Some(value)) => Ok(value.clone()),
+                    _ => Err(not_constant()),
+                }
+            }
+            Expression::Parenthesized(inner) => self.eval(&inner.expr, resolve_names),
+            Expression::Unary(unary) => {
+                let operand = self.eval(&unary.operand, resolve_names)?;
+                eval_unary(unary.operator, operand, unary.position)
+            }
+            Expression::Binary(binary) => {
+                let left = self.eval(&binary.left, resolve_names)?;
+                let right = self.eval(&binary.right, resolve_names)?;
+                eval_binary(binary.operator, left, right, binary.position)
+            }
+            Expression::Array(array) => array
+                .elements
+                .iter()
+                .map(|element| self.eval(element, resolve_names))
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map(ConstValue::Array),
+            Expression::Call(call) => {
+                let is_builtin_len = matches!(&*call.callee, Expression::Identifier(ident)
+                    if ident.name == "len" && self.lookup("len").is_none());
+                if !is_builtin_len || call.args.len() != 1 {
+                    return Err(EvalError::NotConstant(
+                        "only len() can be called in a constant expression".to_string(),
+                        call.position,
+                    ));
+                }
+                match self.eval(&call.args[0], resolve_names)? {
+                    ConstValue::String(value) => Ok(ConstValue::Integer(value.len() as i64)),
+                    ConstValue::Bytes(value) => Ok(ConstValue::Integer(value.len() as i64)),
+                    ConstValue::Array(items) => Ok(ConstValue::Integer(items.len() as i64)),
+                    other => Err(EvalError::Invalid(
+                        format!("len() cannot be applied to {}", other.type_name()),
+                        call.position,
+                    )),
+                }
+            }
+            other => Err(EvalError::NotConstant(
+                "it is evaluated at run time".to_string(),
+                other.position(),
+            )),
+        }
+    }
+
+    fn in_scope<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.scopes.push(HashMap::new());
+        let result = f(self);
+        self.scopes.pop();
+        result
+    }
+
+    fn declare(&mut self, name: &str, value: Option<ConstValue>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), value);
+        }
+    }
+
+    /// Shadow every name a pattern binds
+    fn declare_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Identifier(name, _) => self.declare(name, None),
+            Pattern::Binding(binding) => {
+                self.declare(&binding.name, None);
+                self.declare_pattern(&binding.pattern);
+            }
+            Pattern::Struct(pattern) => {
+                for field in &pattern.fields {
+                    self.declare_pattern(&field.pattern);
+                }
+            }
+            Pattern::Array(ArrayPattern { elements, .. })
+            | Pattern::Tuple(TuplePattern { elements, .. })
+            | Pattern::Or(OrPattern { patterns: elements, .. }) => {
+                for element in elements {
+                    self.declare_pattern(element);
+                }
+            }
+            Pattern::Type(pattern) => {
+                if let Some(name) = &pattern.name {
+                    self.declare(name, None);
+                }
+            }
+            Pattern::Wildcard(_) | Pattern::Literal(..) | Pattern::Range(_) => {}
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Option<ConstValue>> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// Report `error`, prefixing the reason an expression is not constant with `context`
+    fn const_error(&self, context: &str, error: EvalError) -> BuluError {
+        match error {
+            EvalError::NotConstant(reason, position) => {
+                self.error(format!("{}: {}", context, reason), position)
+            }
+            EvalError::Invalid(message, position) => self.error(message, position),
+        }
+    }
+
+    fn error(&self, message: String, position: Position) -> BuluError {
+        BuluError::type_error(message, position.line, position.column, self.file_path.clone())
+    }
+}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_const_declaration(statement: &Statement) -> bool {
+    match statement {
+        Statement::VariableDecl(decl) => decl.is_const,
+        Statement::MultipleVariableDecl(decl) => decl.is_const,
+        Statement::Export(export) => is_const_declaration(&export.item),
+        _ => false,
+    }
+}
+
+/// Folded initializers keep their own position, unless they have none
+fn position_of(expr: &Expression, fallback: Position) -> Position {
+    let position = expr.position();
+    if position.line == 0 {
+        fallback
+    } else {
+        position
+    }
+}
+
+fn eval_unary(operator: UnaryOperator, operand: ConstValue, position: Position) -> EvalResult {
+    match (operator, operand) {
+        (UnaryOperator::Minus, ConstValue::Integer(value)) => value
+            .checked_neg()
+            .map(ConstValue::Integer)
+            .ok_or_else(|| EvalError::Invalid("Integer overflow in constant expression".to_string(), position)),
+        (UnaryOperator::Minus, ConstValue::Float(value)) => Ok(ConstValue::Float(-value)),
+        (UnaryOperator::Plus, value @ (ConstValue::Integer(_) | ConstValue::Float(_))) => Ok(value),
+        (UnaryOperator::Not, ConstValue::Bool(value)) => Ok(ConstValue::Bool(!value)),
+        (UnaryOperator::BitwiseNot, ConstValue::Integer(value)) => Ok(ConstValue::Integer(!value)),
+        (operator, operand) => Err(EvalError::Invalid(
+            format!("Operator {:?} cannot be applied to {} in a constant expression", operator, operand.type_name()),
+            position,
+        )),
+    }
+}
+
+fn eval_binary(operator: BinaryOperator, left: ConstValue, right: ConstValue, position: Position) -> EvalResult {
+    use BinaryOperator::*;
+
+    let overflow = || EvalError::Invalid("Integer overflow in constant expression".to_string(), position);
+    let division_by_zero = || EvalError::Invalid("Division by zero in constant expression".to_string(), position);
+
+    let value = match (&left, &right) {
+        (ConstValue::Integer(a), ConstValue::Integer(b)) => {
+            let (a, b) = (*a, *b);
+            match operator {
+                Add => ConstValue::Integer(a.checked_add(b).ok_or_else(overflow)?),
+                Subtract => ConstValue::Integer(a.checked_sub(b).ok_or_else(overflow)?),
+                Multiply => ConstValue::Integer(a.checked_mul(b).ok_or_else(overflow)?),
+                Divide | Modulo if b == 0 => return Err(division_by_zero()),
+                Divide => ConstValue::Integer(a.checked_div(b).ok_or_else(overflow)?),
+                Modulo => ConstValue::Integer(a.checked_rem(b).ok_or_else(overflow)?),
+                Power => {
+                    let exponent = u32::try_from(b).map_err(|_| {
+                        EvalError::Invalid("Negative exponent in integer constant expression".to_string(), position)
+                    })?;
+                    ConstValue::Integer(a.checked_pow(exponent).ok_or_else(overflow)?)
+                }
+                BitwiseAnd => ConstValue::Integer(a & b),
+                BitwiseOr => ConstValue::Integer(a | b),
+                BitwiseXor => ConstValue::Integer(a ^ b),
+                LeftShift | RightShift => {
+                    let shift = u32::try_from(b).ok().filter(|shift| *shift < 64).ok_or_else(overflow)?;
+                    ConstValue::Integer(if operator == LeftShift { a << shift } else { a >> shift })
+                }
+                Equal => ConstValue::Bool(a == b),
+                NotEqual => ConstValue::Bool(a != b),
+                Less => ConstValue::Bool(a < b),
+                Greater => ConstValue::Bool(a > b),
+                LessEqual => ConstValue::Bool(a <= b),
+                GreaterEqual => ConstValue::Bool(a >= b),
+                And | Or => return Err(mismatch(operator, &left, &right, position)),
+            }
+        }
+        (ConstValue::Float(a), ConstValue::Float(b)) => {
+            let (a, b) = (*a, *b);
+            match operator {
+                Add => ConstValue::Float(a + b),
+                Subtract => ConstValue::Float(a - b),
+                Multiply => ConstValue::Float(a * b),
+                // IEEE 754, as at run time: x / 0.0 is an infinity or NaN
+                Divide => ConstValue::Float(a / b),
+                Modulo => ConstValue::Float(a % b),
+                Power => ConstValue::Float(a.powf(b)),
+                Equal => ConstValue::Bool(a == b),
+                NotEqual => ConstValue::Bool(a != b),
+                Less => ConstValue::Bool(a < b),
+                Greater => ConstValue::Bool(a > b),
+                LessEqual => ConstValue::Bool(a <= b),
+                GreaterEqual => ConstValue::Bool(a >= b),
+                _ => return Err(mismatch(operator, &left, &right, position)),
+            }
+        }
+        (ConstValue::String(a), ConstValue::String(b)) => match operator {
+            Add => ConstValue::String(format!("{}{}", a, b)),
+            Equal => ConstValue::Bool(a == b),
+            NotEqual => ConstValue::Bool(a != b),
+            _ => return Err(mismatch(operator, &left, &right, position)),
+        },
+        (ConstValue::Bool(a), ConstValue::Bool(b)) => match operator {
+            And => ConstValue::Bool(*a && *b),
+            Or => ConstValue::Bool(*a || *b),
+            Equal => ConstValue::Bool(a == b),
+            NotEqual => ConstValue::Bool(a != b),
+            _ => return Err(mismatch(operator, &left, &right, position)),
+        },
+        (ConstValue::Char(a), ConstValue::Char(b)) => match operator {
+            Equal => ConstValue::Bool(a == b),
+            NotEqual => ConstValue::Bool(a != b),
+            Less => ConstValue::Bool(a < b),
+            Greater => ConstValue::Bool(a > b),
+            LessEqual => ConstValue::Bool(a <= b),
+            GreaterEqual => ConstValue::Bool(a >= b),
+            _ => return Err(mismatch(operator, &left, &right, position)),
+        },
+        _ => return Err(mismatch(operator, &left, &right, position)),
+    };
+    Ok(value)
+}
+
+fn mismatch(operator: BinaryOperator, left: &ConstValue, right: &ConstValue, position: Position) -> EvalError {
+    EvalError::Invalid(
+        format!(
+            "Operator {:?} cannot be applied to {} and {} in a constant expression",
+            operator,
+            left.type_name(),
+            right.type_name()
+        ),
+        position,
+    )
+}
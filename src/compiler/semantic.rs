@@ -557,7 +557,7 @@ impl SemanticAnalyzer {
             "print" | "println" | "printf" | "input" |
             "len" | "cap" | "append" | "make" | "copy" | "clone" |
             "panic" | "recover" | "assert" |
-            "typeof" | "instanceof" |
+            "typeof" | "instanceof" | "hash" | "intrinsic" |
             // Type conversion functions
             "int8" | "int16" | "int32" | "int64" |
             "uint8" | "uint16" | "uint32" | "uint64" |
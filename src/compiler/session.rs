@@ -0,0 +1,494 @@
+//! Compiler driver for tools that embed Bulu
+//!
+//! `CompileSession` is the supported way to compile Bulu code from Rust. It
+//! sets every stage up the way the command-line tools do (module lookup,
+//! builtins, standard library types), so linters, editors and build
+//! scripts do not have to chain the lexer, parser and checkers themselves.
+//!
+//! Stages can be run one at a time; each runs the stages before it when they
+//! have not run yet, and keeps its result for the next one:
+//!
+//! ```no_run
+//! use bulu::compiler::{CompileSession, OptLevel};
+//!
+//! # fn main() -> bulu::Result<()> {
+//! let mut session = CompileSession::from_file("src/main.bu")
+//!     .with_opt_level(OptLevel::O2)
+//!     .with_diagnostics(|diagnostic: &bulu::compiler::Diagnostic| eprintln!("{}", diagnostic.to_human()));
+//! session.check()?;
+//! let ir = session.ir()?;
+//! println!("{} functions", ir.functions.len());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A stage that fails returns the error and reports it as a [`Diagnostic`]
+//! to the session's sink. Stages that depend on it return the same error
+//! without running or reporting it again.
+
+use super::{CodeGenerator, IrGenerator, IrOptimizer, IrProgram, OptLevel, Optimizer, SemanticAnalyzer, SymbolResolver};
+use crate::ast::nodes::{Program, Statement};
+use crate::lexer::{Lexer, Token};
+use crate::parser::Parser;
+use crate::project::Project;
+use crate::types::checker::TypeChecker;
+use crate::{BuluError, Result};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// The compiler stage that reported a diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    Lex,
+    Parse,
+    Resolve,
+    Type,
+    Ir,
+    Codegen,
+}
+
+/// An error found in a source file. Lines and columns are 1-based; they are
+/// missing for errors without a position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// How the session names the file, see [`CompileSession::with_file_name`]
+    pub file: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub stage: Stage,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn from_error(file: &str, stage: Stage, error: &BuluError) -> Self {
+        let (stage, message) = match error {
+            BuluError::LexError { message, .. } => (Stage::Lex, message.clone()),
+            BuluError::ParseError { message, .. } => (Stage::Parse, message.clone()),
+            BuluError::TypeError { message, .. } => (Stage::Type, message.clone()),
+            BuluError::Other(message) => (stage, message.clone()),
+            other => (stage, other.to_string()),
+        };
+        Self {
+            file: file.to_string(),
+            line: error.line().filter(|line| *line > 0),
+            column: error.column().filter(|column| *column > 0),
+            stage,
+            message,
+        }
+    }
+
+    /// `file:line:column: error: message`, as the linter prints issues
+    pub fn to_human(&self) -> String {
+        let location = match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", self.file, line, column),
+            (Some(line), None) => format!("{}:{}", self.file, line),
+            _ => self.file.clone(),
+        };
+        format!("{}: {}: {}", location, "error".red().bold(), self.message)
+    }
+}
+
+/// Receives the diagnostics of a session as they are found
+pub trait DiagnosticSink {
+    fn report(&mut self, diagnostic: &Diagnostic);
+}
+
+impl<F: FnMut(&Diagnostic)> DiagnosticSink for F {
+    fn report(&mut self, diagnostic: &Diagnostic) {
+        self(diagnostic)
+    }
+}
+
+/// Where the source comes from
+enum Input {
+    File(PathBuf),
+    Source(String),
+}
+
+/// Compiles one Bulu source file, stage by stage
+pub struct CompileSession {
+    input: Input,
+    file_name: String,
+    search_paths: Vec<PathBuf>,
+    /// In-memory modules by import path
+    modules: Vec<(String, String)>,
+    opt_level: OptLevel,
+    features: Vec<String>,
+    target: String,
+    debug: bool,
+    prelude: bool,
+    package: String,
+    sink: Option<Box<dyn DiagnosticSink>>,
+    diagnostics: Vec<Diagnostic>,
+    /// The error of the stage that failed
+    failure: Option<BuluError>,
+
+    tokens: Option<Vec<Token>>,
+    /// The parsed program; imports are resolved in place
+    parsed: Option<Program>,
+    resolver: Option<SymbolResolver>,
+    /// The program after constant evaluation and type checking
+    checked: Option<Program>,
+    ir: Option<IrProgram>,
+}
+
+impl CompileSession {
+    /// Compile a file. Imports are resolved relative to its directory.
+    pub fn from_file(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let file_name = path.to_string_lossy().to_string();
+        Self::with_input(Input::File(path), file_name)
+    }
+
+    /// Compile source text; `name` is how errors refer to it
+    pub fn from_source(name: impl Into<String>, source: impl Into<String>) -> Self {
+        Self::with_input(Input::Source(source.into()), name.into())
+    }
+
+    /// Compile a project's `src/main.bu` with the optimization level, target
+    /// and features of its `[build]` section, and its package's prelude setting
+    pub fn for_project(project: &Project) -> Result<Self> {
+        let build = &project.config.build;
+        let mut session = Self::from_file(project.main_source_file())
+            .with_search_path(project.src_dir.clone())
+            .with_opt_level(OptLevel::parse(&build.optimization)?)
+            .with_target(build.target.clone())
+            .with_prelude(project.config.package.prelude)
+            .with_package(project.config.package.name.clone());
+        session.features = build.features.clone();
+        Ok(session)
+    }
+
+    fn with_input(input: Input, file_name: String) -> Self {
+        Self {
+            input,
+            file_name,
+            search_paths: Vec::new(),
+            modules: Vec::new(),
+            opt_level: OptLevel::O0,
+            features: Vec::new(),
+            target: "native".to_string(),
+            debug: false,
+            prelude: true,
+            package: crate::compiler::mangle::DEFAULT_PACKAGE.to_string(),
+            sink: None,
+            diagnostics: Vec::new(),
+            failure: None,
+            tokens: None,
+            parsed: None,
+            resolver: None,
+            checked: None,
+            ir: None,
+        }
+    }
+
+    /// Name diagnostics use for the file, e.g. a path relative to the project
+    pub fn with_file_name(mut self, name: impl Into<String>) -> Self {
+        self.file_name = name.into();
+        self
+    }
+
+    /// Also look for imported modules in `path`
+    pub fn with_search_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.search_paths.push(path.into());
+        self
+    }
+
+    /// Provide the source of the module imported as `path`, instead of
+    /// reading it from disk; useful for editors with unsaved files
+    pub fn with_module(mut self, path: impl Into<String>, source: impl Into<String>) -> Self {
+        self.modules.push((path.into(), source.into()));
+        self
+    }
+
+    /// How much the IR is optimized; `O0` by default
+    pub fn with_opt_level(mut self, opt_level: OptLevel) -> Self {
+        self.opt_level = opt_level;
+        self
+    }
+
+    /// Enable a feature, as listed in `[build] features`. The compiler
+    /// stages do not depend on features; they are kept for the tools that do.
+    pub fn with_feature(mut self, feature: impl Into<String>) -> Self {
+        self.features.push(feature.into());
+        self
+    }
+
+    /// Target platform as accepted by `langc --target`; `native` by default
+    pub fn with_target(mut self, target: impl Into<String>) -> Self {
+        self.target = target.into();
+        self
+    }
+
+    /// Include debug information in generated code
+    pub fn with_debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    /// Whether builtins are in scope without `import "std/builtin"`; `true` by
+    /// default. Files starting with `@no_prelude` go without it either way.
+    pub fn with_prelude(mut self, prelude: bool) -> Self {
+        self.prelude = prelude;
+        self
+    }
+
+    /// Package the generated functions are named after; `main` by default
+    pub fn with_package(mut self, package: impl Into<String>) -> Self {
+        self.package = package.into();
+        self
+    }
+
+    /// Send diagnostics to `sink` as they are found
+    pub fn with_diagnostics(mut self, sink: impl DiagnosticSink + 'static) -> Self {
+        self.sink = Some(Box::new(sink));
+        self
+    }
+
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    pub fn opt_level(&self) -> OptLevel {
+        self.opt_level
+    }
+
+    pub fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.iter().any(|enabled| enabled == feature)
+    }
+
+    /// Diagnostics reported so far
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    fn path(&self) -> Option<&Path> {
+        match &self.input {
+            Input::File(path) => Some(path),
+            Input::Source(_) => None,
+        }
+    }
+
+    /// Report `error` as a diagnostic of `stage` and return it
+    fn fail<T>(&mut self, stage: Stage, error: BuluError) -> Result<T> {
+        let diagnostic = Diagnostic::from_error(&self.file_name, stage, &error);
+        if let Some(sink) = &mut self.sink {
+            sink.report(&diagnostic);
+        }
+        self.diagnostics.push(diagnostic);
+        self.failure = Some(error.clone());
+        Err(error)
+    }
+
+    /// The error of an earlier failed stage, which later stages return
+    fn failed(&self) -> Result<()> {
+        match &self.failure {
+            Some(error) => Err(error.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Split the source into tokens
+    pub fn tokens(&mut self) -> Result<&[Token]> {
+        if self.tokens.is_none() {
+            self.failed()?;
+            debug!("Lexing {}", self.file_name);
+            let source = match &self.input {
+                Input::File(path) => fs::read_to_string(path)
+                    .map_err(|e| BuluError::Other(format!("Failed to read {}: {}", path.display(), e))),
+                Input::Source(source) => Ok(source.clone()),
+            };
+            let tokens = source.and_then(|source| Lexer::with_file(&source, self.file_name.clone()).tokenize());
+            match tokens {
+                Ok(tokens) => self.tokens = Some(tokens),
+                Err(error) => return self.fail(Stage::Lex, error),
+            }
+        }
+        Ok(self.tokens.as_deref().expect("tokens were just set"))
+    }
+
+    /// Parse the tokens into a program
+    pub fn parse(&mut self) -> Result<&Program> {
+        if self.parsed.is_none() {
+            self.failed()?;
+            let tokens = self.tokens()?.to_vec();
+            debug!("Parsing {}", self.file_name);
+            match Parser::with_file(tokens, self.file_name.clone()).parse() {
+                Ok(program) => self.parsed = Some(program),
+                Err(error) => return self.fail(Stage::Parse, error),
+            }
+        }
+        Ok(self.parsed.as_ref().expect("program was just parsed"))
+    }
+
+    /// Whether the program imports only the standard library, so that
+    /// checking it depends on nothing but its own source
+    pub fn imports_only_std(&mut self) -> Result<bool> {
+        Ok(self.parse()?.statements.iter().all(|statement| match statement {
+            Statement::Import(import) => import.path.starts_with("std/") || import.path.starts_with("std."),
+            _ => true,
+        }))
+    }
+
+    /// Load the imported modules and resolve the names they export
+    pub fn resolve(&mut self) -> Result<&SymbolResolver> {
+        if self.resolver.is_none() {
+            self.failed()?;
+            self.parse()?;
+            debug!("Resolving imports of {}", self.file_name);
+            let mut resolver = SymbolResolver::new();
+            resolver.set_prelude(self.prelude);
+            resolver.set_current_module(match self.path() {
+                Some(path) => path.to_string_lossy().to_string(),
+                None => self.file_name.clone(),
+            });
+            let modules = resolver.module_resolver_mut();
+            if let Some(parent_dir) = self.path().and_then(Path::parent) {
+                modules.set_current_dir(parent_dir.to_path_buf());
+            }
+            for path in &self.search_paths {
+                modules.add_search_path(path.clone());
+            }
+            for (path, source) in &self.modules {
+                modules.add_memory_module(path.clone(), source.clone());
+            }
+
+            let program = self.parsed.as_mut().expect("program was parsed");
+            match resolver.resolve_program(program) {
+                Ok(()) => self.resolver = Some(resolver),
+                Err(error) => return self.fail(Stage::Resolve, error),
+            }
+        }
+        Ok(self.resolver.as_ref().expect("imports were just resolved"))
+    }
+
+    /// Evaluate constants and type check the program
+    pub fn check(&mut self) -> Result<&Program> {
+        if self.checked.is_none() {
+            self.failed()?;
+            self.resolve()?;
+            debug!("Type checking {}", self.file_name);
+            let program = self.parsed.clone().expect("program was parsed");
+            let resolver = self.resolver.as_ref().expect("imports were resolved");
+
+            let mut optimizer = Optimizer::new();
+            optimizer.set_file_path(Some(self.file_name.clone()));
+            let checked = optimizer.optimize(program).and_then(|program| {
+                let mut type_checker = TypeChecker::new();
+                type_checker.set_file_path(Some(self.file_name.clone()));
+                type_checker.set_prelude(self.prelude);
+                type_checker.import_symbols_from_resolver(resolver);
+                // Imports may shadow builtins, so add them back afterwards
+                type_checker.add_builtin_functions_after_import();
+                type_checker.add_std_types();
+                type_checker.check(&program).map(|()| program)
+            });
+            match checked {
+                Ok(program) => self.checked = Some(program),
+                Err(error) => return self.fail(Stage::Type, error),
+            }
+        }
+        Ok(self.checked.as_ref().expect("program was just checked"))
+    }
+
+    /// Generate the IR of the program and the modules it imports, optimized
+    /// for the session's level
+    pub fn ir(&mut self) -> Result<&IrProgram> {
+        if self.ir.is_none() {
+            self.failed()?;
+            self.check()?;
+            debug!("Generating IR for {}", self.file_name);
+            let program = self.checked.as_ref().expect("program was checked");
+            let resolver = self.resolver.as_ref().expect("imports were resolved");
+            let opt_level = self.opt_level;
+
+            let ir = SemanticAnalyzer::new()
+                .analyze(&mut program.clone())
+                .and_then(|()| IrGenerator::new().generate(&combine_with_imports(program, resolver)))
+                .and_then(|ir| {
+                    if matches!(opt_level, OptLevel::O0) {
+                        return Ok(ir);
+                    }
+                    let mut optimizer = IrOptimizer::new();
+                    optimizer.set_level(opt_level);
+                    optimizer.optimize(ir)
+                });
+            match ir {
+                Ok(ir) => self.ir = Some(ir),
+                Err(error) => return self.fail(Stage::Ir, error),
+            }
+        }
+        Ok(self.ir.as_ref().expect("IR was just generated"))
+    }
+
+    fn code_generator(&self) -> CodeGenerator {
+        let mut generator = CodeGenerator::new();
+        let target = if self.target == "native" {
+            crate::build::host_target()
+        } else {
+            self.target.clone()
+        };
+        generator.set_target(&target);
+        generator.set_debug(self.debug);
+        generator.set_package(&self.package);
+        generator
+    }
+
+    /// Generate assembly for the session's target
+    pub fn assembly(&mut self) -> Result<String> {
+        self.ir()?;
+        let result = self.code_generator().generate_assembly(self.ir.as_ref().expect("IR was generated"));
+        result.or_else(|error| self.fail(Stage::Codegen, error))
+    }
+
+    /// Generate an executable for the session's target
+    pub fn executable(&mut self) -> Result<Vec<u8>> {
+        self.ir()?;
+        let result = self.code_generator().generate_executable(self.ir.as_ref().expect("IR was generated"));
+        result.or_else(|error| self.fail(Stage::Codegen, error))
+    }
+}
+
+/// The program with the declarations of every module it imports in front,
+/// leaving out imports and re-exports
+fn combine_with_imports(program: &Program, resolver: &SymbolResolver) -> Program {
+    let mut seen = HashSet::new();
+    let mut statements = Vec::new();
+    for module in resolver.get_loaded_modules() {
+        if !seen.insert(module.path.clone()) {
+            continue;
+        }
+        for statement in &module.ast.statements {
+            match statement {
+                Statement::Import(_) => {}
+                Statement::Export(export) => match export.item.as_ref() {
+                    Statement::Import(_) => {}
+                    item => statements.push(item.clone()),
+                },
+                _ => statements.push(statement.clone()),
+            }
+        }
+    }
+    statements.extend(
+        program
+            .statements
+            .iter()
+            .filter(|statement| !matches!(statement, Statement::Import(_)))
+            .cloned(),
+    );
+
+    Program {
+        statements,
+        attributes: program.attributes.clone(),
+        position: program.position,
+    }
+}
@@ -86,6 +86,8 @@ pub struct SymbolResolver {
     current_module_path: Option<String>,
     /// Stack of local scopes for tracking variables in functions/blocks
     scope_stack: Vec<HashMap<String, SymbolInfo>>,
+    /// Whether builtins are in scope without importing them from std/builtin
+    prelude: bool,
 }
 
 impl SymbolResolver {
@@ -100,9 +102,16 @@ impl SymbolResolver {
             },
             current_module_path: None,
             scope_stack: Vec::new(),
+            prelude: true,
         }
     }
 
+    /// Disable the prelude, as `prelude = false` in lang.toml does for a package;
+    /// files starting with `@no_prelude` disable it themselves
+    pub fn set_prelude(&mut self, prelude: bool) {
+        self.prelude = prelude;
+    }
+
     /// Set the current module path for resolution context
     pub fn set_current_module(&mut self, path: String) {
         self.current_module_path = Some(path.clone());
@@ -143,6 +152,10 @@ impl SymbolResolver {
 
     /// Resolve all imports and exports in a program
     pub fn resolve_program(&mut self, program: &mut Program) -> Result<()> {
+        if program.has_attribute(crate::std::builtin::NO_PRELUDE) {
+            self.prelude = false;
+        }
+
         // First pass: collect all local declarations
         self.collect_local_symbols(program)?;
 
@@ -570,11 +583,32 @@ impl SymbolResolver {
     }
 
     /// Resolve a single import statement
-    fn resolve_import_statement(&mut self, import_stmt: &ImportStmt) -> Result<()> {
+    pub fn resolve_import_statement(&mut self, import_stmt: &ImportStmt) -> Result<()> {
         // Load the module, passing the current file for relative path resolution
         let current_file = self.current_module_path.as_ref().map(|s| Path::new(s.as_str()));
         let module = self.module_resolver.load_module_from(&import_stmt.path, current_file)?;
 
+        if crate::std::builtin::is_module_path(&import_stmt.path) {
+            // The checker and interpreter know builtins by name only
+            let renamed = match &import_stmt.items {
+                Some(items) => items.iter().find(|item| item.alias.is_some()).map(|item| {
+                    (format!("Builtin '{}' cannot be imported under another name", item.name), item.position)
+                }),
+                None => import_stmt.alias.as_ref().map(|_| {
+                    ("Builtins cannot be imported as a module; import them by name".to_string(), import_stmt.position)
+                }),
+            };
+            if let Some((message, position)) = renamed {
+                return Err(BuluError::TypeError {
+                    stack: Vec::new(),
+                    message,
+                    line: position.line,
+                    column: position.column,
+                    file: self.current_module_path.clone(),
+                });
+            }
+        }
+
         if let Some(items) = &import_stmt.items {
             // Import specific items: import { item1, item2 } from "path"
             for item in items {
@@ -1052,9 +1086,14 @@ impl SymbolResolver {
         }
 
         // Symbol not found
+        let message = if crate::std::builtin::is_prelude_name(name) {
+            crate::std::builtin::not_imported_message(name)
+        } else {
+            format!("Undefined symbol '{}'", name)
+        };
         Err(BuluError::TypeError {
             stack: Vec::new(),
-            message: format!("Undefined symbol '{}'", name),
+            message,
             line: position.line,
             column: position.column,
             file: self.current_module_path.clone(),
@@ -1063,30 +1102,16 @@ impl SymbolResolver {
 
     /// Check if a name is a built-in function or keyword
     fn is_builtin(&self, name: &str) -> bool {
+        // Builtin functions, unless the prelude is disabled
+        if self.prelude && crate::std::builtin::is_prelude_name(name) {
+            return true;
+        }
+
         // Check exact matches first
         if matches!(
             name,
-            // I/O functions
-            "print" | "println" | "printf" | "input"
-            // Type conversion functions
-            | "int8" | "int16" | "int32" | "int64"
-            | "uint8" | "uint16" | "uint32" | "uint64"
-            | "float32" | "float64" | "bool" | "char" | "string"
-            // Memory functions
-            | "len" | "cap" | "clone" | "sizeof"
-            // String functions
-            | "ord" | "chr"
-            // Collection functions
-            | "make" | "append" | "copy" | "delete"
-            // Utility functions
-            | "typeof" | "instanceof" | "panic" | "assert" | "recover"
-            // Channel functions
-            | "close"
-            // Synchronization functions
-            | "lock" | "sleep" | "yield" | "timer"
-            | "atomic_load" | "atomic_store" | "atomic_add" | "atomic_sub"
             // Additional utility functions
-            | "toString"
+            "toString"
             // Type identifiers
             | "any" | "unknown" | "chan"
         ) {
@@ -1188,6 +1213,15 @@ impl SymbolResolver {
                     self.collect_pattern_variables(alternative, variables);
                 }
             }
+            Pattern::Binding(binding) => {
+                variables.push(binding.name.clone());
+                self.collect_pattern_variables(&binding.pattern, variables);
+            }
+            Pattern::Type(type_pattern) => {
+                if let Some(name) = &type_pattern.name {
+                    variables.push(name.clone());
+                }
+            }
             Pattern::Wildcard(_) | Pattern::Literal(_, _) | Pattern::Range(_) => {
                 // These patterns don't bind variables
             }
@@ -0,0 +1,370 @@
+//! Layered configuration of the formatter, linter, package manager and build
+//!
+//! Settings come from several layers, each overriding the ones before it:
+//!
+//! 1. built-in defaults
+//! 2. the user configuration, `~/.bulu/config.toml` (or the file named by `BULU_CONFIG`)
+//! 3. the project: `.langfmt.toml`, `.langlint.toml`, the `[lint]` and `[build]`
+//!    sections of `lang.toml`, then `.bulu/config.toml`
+//! 4. environment variables named `BULU_<SECTION>_<KEY>`, e.g. `BULU_FMT_INDENT_SIZE=2`
+//! 5. `--config <section>.<key>=<value>` flags on the command line
+//!
+//! Configuration files hold one table per section:
+//!
+//! ```toml
+//! [fmt]
+//! indent_size = 2
+//!
+//! [lint.rules]
+//! unused-variable = "error"
+//!
+//! [package]
+//! vendor_dir = "third_party"
+//! ```
+//!
+//! Tables merge key by key, and every value remembers the layer it came from
+//! so `lang config show` can say why a setting has the value it has.
+
+use crate::build::BuildOptions;
+use crate::formatter::FormatConfig;
+use crate::linter::{LintPolicy, LintRules};
+use crate::package::registries::UserConfig;
+use crate::package::PackageConfig;
+use crate::project::BuildConfig;
+use crate::{BuluError, Result};
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use toml::value::Table;
+
+/// Sections of the configuration, one per subsystem
+pub const SECTIONS: &[&str] = &["fmt", "lint", "package", "build"];
+
+/// Project configuration file, relative to the project root
+pub const PROJECT_CONFIG_FILE: &str = ".bulu/config.toml";
+
+/// The layer a setting came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    User(PathBuf),
+    Project(PathBuf),
+    /// Name of the environment variable
+    Env(String),
+    CommandLine,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::User(path) => write!(f, "user config {}", path.display()),
+            Source::Project(path) => write!(f, "project {}", path.display()),
+            Source::Env(name) => write!(f, "environment {}", name),
+            Source::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+static CLI_OVERRIDES: Mutex<Vec<(String, toml::Value)>> = Mutex::new(Vec::new());
+
+/// Settings given with `--config`, applied by every later `Config::load`
+pub fn set_cli_overrides(overrides: Vec<(String, toml::Value)>) {
+    *CLI_OVERRIDES.lock().unwrap() = overrides;
+}
+
+/// Parse a `section.key=value` override. Values are TOML (`2`, `true`,
+/// `"text"`, `["a"]`); anything else is taken as a bare string.
+pub fn parse_override(text: &str) -> Result<(String, toml::Value)> {
+    let (key, value) = text
+        .split_once('=')
+        .ok_or_else(|| BuluError::Other(format!("Expected <section>.<key>=<value>, got '{}'", text)))?;
+    Ok((key.trim().to_string(), parse_value(value.trim())))
+}
+
+fn parse_value(text: &str) -> toml::Value {
+    toml::from_str::<Table>(&format!("value = {}", text))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(text.to_string()))
+}
+
+/// Merged settings of every layer, with the source of each value
+#[derive(Debug, Clone)]
+pub struct Config {
+    values: Table,
+    /// Source of each leaf value, keyed by dotted path
+    sources: BTreeMap<String, Source>,
+}
+
+impl Config {
+    /// The built-in defaults
+    pub fn defaults() -> Self {
+        let package = PackageConfig::default();
+        let mut package_table = Table::new();
+        package_table.insert(
+            "cache_dir".to_string(),
+            toml::Value::String(package.cache_dir.display().to_string()),
+        );
+        package_table.insert(
+            "vendor_dir".to_string(),
+            toml::Value::String(package.vendor_dir.display().to_string()),
+        );
+
+        let mut defaults = Table::new();
+        defaults.insert("fmt".to_string(), default_table(&FormatConfig::default()));
+        defaults.insert("lint".to_string(), default_table(&LintRules::default()));
+        defaults.insert("package".to_string(), toml::Value::Table(package_table));
+        defaults.insert("build".to_string(), default_table(&BuildConfig::default()));
+
+        let mut config = Self {
+            values: Table::new(),
+            sources: BTreeMap::new(),
+        };
+        config.merge(defaults, &Source::Default);
+        config
+    }
+
+    /// Load every layer: defaults, the user configuration, the project at
+    /// `project_root` if any, the environment and the `--config` flags
+    pub fn load(project_root: Option<&Path>) -> Result<Self> {
+        let mut config = Self::defaults();
+        if let Some(path) = UserConfig::path().filter(|path| path.exists()) {
+            config.load_user(&path)?;
+        }
+        if let Some(root) = project_root {
+            config.load_project(root)?;
+        }
+        config.apply_env(std::env::vars());
+        let overrides = CLI_OVERRIDES.lock().unwrap().clone();
+        config.apply_overrides(&overrides)?;
+        Ok(config)
+    }
+
+    /// Layer a user configuration file. Its other tables hold registry
+    /// settings, which `RegistrySettings` reads.
+    pub fn load_user(&mut self, path: &Path) -> Result<()> {
+        let table = read_table(path)?;
+        self.layer_sections(&table, SECTIONS, path, Source::User(path.to_path_buf()))
+    }
+
+    /// Layer the configuration files of the project at `root`
+    pub fn load_project(&mut self, root: &Path) -> Result<()> {
+        // Files configuring a single section hold that section's keys at top level
+        for (file, section) in [(".langfmt.toml", "fmt"), (".langlint.toml", "lint")] {
+            let path = root.join(file);
+            if path.exists() {
+                let mut table = Table::new();
+                table.insert(section.to_string(), toml::Value::Table(read_table(&path)?));
+                self.merge(table, &Source::Project(path));
+            }
+        }
+        for (file, sections) in [("lang.toml", &["lint", "build"][..]), (PROJECT_CONFIG_FILE, SECTIONS)] {
+            let path = root.join(file);
+            if path.exists() {
+                let table = read_table(&path)?;
+                self.layer_sections(&table, sections, &path, Source::Project(path.clone()))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn layer_sections(&mut self, table: &Table, sections: &[&str], path: &Path, source: Source) -> Result<()> {
+        let mut layer = Table::new();
+        for section in sections {
+            match table.get(*section) {
+                Some(value @ toml::Value::Table(_)) => {
+                    layer.insert(section.to_string(), value.clone());
+                }
+                Some(_) => {
+                    return Err(BuluError::Other(format!(
+                        "The [{}] section of {} must be a table",
+                        section,
+                        path.display()
+                    )))
+                }
+                None => {}
+            }
+        }
+        self.merge(layer, &source);
+        Ok(())
+    }
+
+    /// Layer `BULU_<SECTION>_<KEY>` variables. Only keys with a default can be
+    /// set this way, so unrelated `BULU_` variables are ignored.
+    pub fn apply_env(&mut self, vars: impl IntoIterator<Item = (String, String)>) {
+        let mut vars: Vec<(String, String)> = vars.into_iter().collect();
+        vars.sort();
+        for (name, value) in vars {
+            let Some(rest) = name.strip_prefix("BULU_") else {
+                continue;
+            };
+            for section in SECTIONS {
+                let Some(key) = rest.strip_prefix(&format!("{}_", section.to_uppercase())) else {
+                    continue;
+                };
+                let key = format!("{}.{}", section, key.to_lowercase());
+                if self.sources.contains_key(&key) {
+                    self.set(&key, parse_value(&value), Source::Env(name.clone()));
+                }
+            }
+        }
+    }
+
+    /// Layer `--config` settings; keys must exist, except lint rule severities
+    pub fn apply_overrides(&mut self, overrides: &[(String, toml::Value)]) -> Result<()> {
+        for (key, value) in overrides {
+            let known = self.sources.contains_key(key)
+                || key.strip_prefix("lint.rules.").is_some_and(|rule| !rule.is_empty());
+            if !known {
+                return Err(BuluError::Other(format!("Unknown configuration key '{}'", key)));
+            }
+            self.set(key, value.clone(), Source::CommandLine);
+        }
+        Ok(())
+    }
+
+    /// Set the value at a dotted path
+    pub fn set(&mut self, key: &str, value: toml::Value, source: Source) {
+        let mut layer = value;
+        for part in key.rsplit('.') {
+            let mut table = Table::new();
+            table.insert(part.to_string(), layer);
+            layer = toml::Value::Table(table);
+        }
+        if let toml::Value::Table(table) = layer {
+            self.merge(table, &source);
+        }
+    }
+
+    /// The value at a dotted path, e.g. `fmt.indent_size`
+    pub fn get(&self, key: &str) -> Option<&toml::Value> {
+        let mut parts = key.split('.');
+        let mut value = self.values.get(parts.next()?)?;
+        for part in parts {
+            value = value.as_table()?.get(part)?;
+        }
+        Some(value)
+    }
+
+    /// The layer the value at a dotted path came from
+    pub fn source(&self, key: &str) -> Option<&Source> {
+        self.sources.get(key)
+    }
+
+    /// Every value with its dotted path and source, in path order
+    pub fn entries(&self) -> Vec<(&str, &toml::Value, &Source)> {
+        self.sources
+            .iter()
+            .filter_map(|(key, source)| Some((key.as_str(), self.get(key)?, source)))
+            .collect()
+    }
+
+    /// The listing printed by `lang config show`
+    pub fn show(&self) -> String {
+        let mut listing = String::new();
+        for (key, value, source) in self.entries() {
+            listing.push_str(&format!("{} = {}  # {}\n", key, value, source));
+        }
+        listing
+    }
+
+    /// A section deserialized into its settings type
+    pub fn section<T: DeserializeOwned>(&self, name: &str) -> Result<T> {
+        let table = self.values.get(name).cloned().unwrap_or_else(|| toml::Value::Table(Table::new()));
+        table
+            .try_into()
+            .map_err(|e| BuluError::Other(format!("Invalid [{}] configuration: {}", name, e)))
+    }
+
+    pub fn format_config(&self) -> Result<FormatConfig> {
+        self.section("fmt")
+    }
+
+    pub fn lint_rules(&self) -> Result<LintRules> {
+        self.section("lint")
+    }
+
+    pub fn build_config(&self) -> Result<BuildConfig> {
+        self.section("build")
+    }
+
+    /// Build options with the configured `parallel`, `incremental` and `deny` settings
+    pub fn build_options(&self) -> Result<BuildOptions> {
+        let build = self.build_config()?;
+        Ok(BuildOptions {
+            parallel: build.parallel,
+            incremental: build.incremental,
+            lint_policy: LintPolicy::from_deny(&build.deny),
+            ..BuildOptions::default()
+        })
+    }
+
+    /// Package manager settings with the configured cache and vendor directories
+    pub fn package_config(&self) -> Result<PackageConfig> {
+        let path = |key: &str| match self.get(key) {
+            Some(toml::Value::String(path)) => Ok(PathBuf::from(path)),
+            other => Err(BuluError::Other(format!("Invalid [package] configuration: {} must be a path, got {:?}", key, other))),
+        };
+        Ok(PackageConfig {
+            cache_dir: path("package.cache_dir")?,
+            vendor_dir: path("package.vendor_dir")?,
+            ..PackageConfig::default()
+        })
+    }
+
+    /// Overlay a layer, merging tables key by key
+    fn merge(&mut self, layer: Table, source: &Source) {
+        merge_table(&mut self.values, layer, "", source, &mut self.sources);
+    }
+}
+
+fn merge_table(
+    base: &mut Table,
+    layer: Table,
+    prefix: &str,
+    source: &Source,
+    sources: &mut BTreeMap<String, Source>,
+) {
+    for (key, value) in layer {
+        let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(table)) => {
+                merge_table(existing, table, &path, source, sources);
+            }
+            (_, value) => {
+                // A replaced table's values no longer come from their old layers
+                let nested = format!("{}.", path);
+                sources.retain(|key, _| key != &path && !key.starts_with(&nested));
+                record_sources(&path, &value, source, sources);
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn record_sources(path: &str, value: &toml::Value, source: &Source, sources: &mut BTreeMap<String, Source>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                record_sources(&format!("{}.{}", path, key), value, source, sources);
+            }
+        }
+        _ => {
+            sources.insert(path.to_string(), source.clone());
+        }
+    }
+}
+
+fn default_table<T: serde::Serialize>(settings: &T) -> toml::Value {
+    toml::Value::try_from(settings).expect("default settings serialize to TOML")
+}
+
+fn read_table(path: &Path) -> Result<Table> {
+    let name = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+    let content = fs::read_to_string(path).map_err(|e| BuluError::Other(format!("Failed to read {}: {}", name, e)))?;
+    toml::from_str(&content).map_err(|e| BuluError::Other(format!("Failed to parse {}: {}", name, e)))
+}
@@ -0,0 +1,109 @@
+//! Running the examples of documentation comments
+
+use crate::Result;
+use crate::compiler::symbol_resolver::SymbolResolver;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::runtime::pool::InterpreterPool;
+use crate::types::checker::TypeChecker;
+use super::extractor::DocExtractor;
+use std::path::Path;
+
+/// The function each example is wrapped in
+const DOCTEST_FUNCTION: &str = "__doctest";
+
+/// An example that did not pass
+#[derive(Debug, Clone, PartialEq)]
+pub struct DoctestFailure {
+    /// The documented item
+    pub item: String,
+    /// Line of the item's declaration
+    pub line: usize,
+    pub message: String,
+}
+
+/// Runs `@example` blocks as doctests. An example runs in the scope of the
+/// file that documents it, so it can call the item it documents, and passes
+/// when it type checks and runs without an error or failed assertion.
+/// Examples run on interpreters from a pool, so running many of them, as the
+/// documentation generator and the language server do, doesn't build an
+/// interpreter for each.
+pub struct DoctestRunner {
+    pool: InterpreterPool,
+}
+
+impl DoctestRunner {
+    pub fn new() -> Self {
+        Self {
+            pool: InterpreterPool::default(),
+        }
+    }
+
+    pub fn pool(&self) -> &InterpreterPool {
+        &self.pool
+    }
+
+    /// Run the examples of every item documented in `source`, the contents
+    /// of the file at `file_path`, and return those that failed
+    pub fn run_file(&self, source: &str, file_path: &Path) -> Result<Vec<DoctestFailure>> {
+        let items = DocExtractor::new().extract_from_file(source, &file_path.to_path_buf())?;
+        let mut failures = Vec::new();
+        for item in items {
+            let examples = item.doc_comment.map(|doc| doc.examples).unwrap_or_default();
+            for example in examples {
+                if let Err(e) = self.run(source, file_path, &example) {
+                    failures.push(DoctestFailure {
+                        item: item.name.clone(),
+                        line: item.line_number,
+                        message: e.to_string(),
+                    });
+                }
+            }
+        }
+        Ok(failures)
+    }
+
+    /// Run `example` from the file at `file_path`, whose contents are `source`
+    pub fn run(&self, source: &str, file_path: &Path, example: &str) -> Result<()> {
+        let source = format!("{}\nfunc {}() {{\n{}\n}}\n", source, DOCTEST_FUNCTION, example);
+        let file = file_path.to_string_lossy().to_string();
+
+        let mut lexer = Lexer::new(&source);
+        let tokens = lexer.tokenize()?;
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse()?;
+
+        let mut symbol_resolver = SymbolResolver::new();
+        symbol_resolver.set_current_module(file.clone());
+        if let Some(parent_dir) = file_path.parent() {
+            symbol_resolver
+                .module_resolver_mut()
+                .set_current_dir(parent_dir.to_path_buf());
+        }
+        symbol_resolver.resolve_program(&mut program)?;
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.set_file_path(Some(file.clone()));
+        type_checker.import_symbols_from_resolver(&symbol_resolver);
+        type_checker.add_builtin_functions_after_import();
+        type_checker.add_std_types();
+        type_checker.check(&program)?;
+
+        // Examples print to show usage; keep that out of the generator's output
+        let mut interpreter = self.pool.acquire();
+        interpreter.set_current_file(file);
+        let _stdout = interpreter.capture_stdout();
+        interpreter.execute_program(&program)?;
+        let doctest = interpreter
+            .get_function_definition(DOCTEST_FUNCTION)
+            .expect("the doctest function was just declared");
+        interpreter.call_user_function(&doctest, &[])?;
+        Ok(())
+    }
+}
+
+impl Default for DoctestRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
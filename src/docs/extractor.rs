@@ -5,7 +5,7 @@ use crate::lexer::{Lexer, Token, TokenType};
 use crate::parser::Parser;
 use crate::ast::nodes::*;
 use super::{DocumentedItem, DocComment, ItemKind, Visibility};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Extracts documentation from source code
 pub struct DocExtractor;
@@ -29,6 +29,13 @@ impl DocExtractor {
         Ok(items)
     }
 
+    /// Extract documentation from a parsed source file
+    pub fn extract_from_program(&self, program: &Program, file_path: &Path) -> Vec<DocumentedItem> {
+        let mut items = Vec::new();
+        self.extract_from_ast(program, &file_path.to_path_buf(), &mut items);
+        items
+    }
+
     fn extract_from_ast(&self, ast: &Program, file_path: &PathBuf, items: &mut Vec<DocumentedItem>) {
         for stmt in &ast.statements {
             self.extract_from_statement(stmt, file_path, items);
@@ -231,6 +238,9 @@ impl DocExtractor {
                 signature.push_str(": ");
                 signature.push_str(&self.type_to_string(return_type));
             }
+            if method.default_body.is_some() {
+                signature.push_str(" { ... }");
+            }
             signature.push('\n');
         }
         signature.push('}');
@@ -277,6 +287,8 @@ impl DocExtractor {
             Type::Array(array_type) => {
                 if let Some(size) = &array_type.size {
                     format!("[{}]{}", size, self.type_to_string(&array_type.element_type))
+                } else if let Some(Expression::Identifier(size)) = array_type.size_expr.as_deref() {
+                    format!("[{}]{}", size.name, self.type_to_string(&array_type.element_type))
                 } else {
                     format!("[]{}", self.type_to_string(&array_type.element_type))
                 }
@@ -287,6 +299,9 @@ impl DocExtractor {
             Type::Map(map_type) => {
                 format!("map[{}]{}", self.type_to_string(&map_type.key_type), self.type_to_string(&map_type.value_type))
             }
+            Type::Set(set_type) => {
+                format!("set<{}>", self.type_to_string(&set_type.element_type))
+            }
             Type::Function(func_type) => {
                 let mut sig = String::from("func(");
                 for (i, param) in func_type.param_types.iter().enumerate() {
@@ -345,6 +360,13 @@ impl DocExtractor {
             Type::Promise(promise_type) => {
                 format!("Promise<{}>", self.type_to_string(&promise_type.result_type))
             }
+            Type::Union(union_type) => union_type
+                .types
+                .iter()
+                .map(|member| self.type_to_string(member))
+                .collect::<Vec<_>>()
+                .join(" | "),
+            Type::Null => "null".to_string(),
             Type::Named(name) => name.clone(),
         }
     }
@@ -2,16 +2,76 @@
 
 use crate::Result;
 use crate::project::Project;
-use super::{DocumentedItem, ItemKind, Visibility};
+use super::{markdown, DocCoverage, DocumentedItem, ItemKind};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::fs;
-// HashMap not needed currently
 
 /// Generates HTML documentation
 pub struct HtmlGenerator {
     output_dir: PathBuf,
 }
 
+/// Where each item is documented, and which items type names link to
+struct Catalog {
+    /// Module of each item: its file's path under `src`, without the extension
+    modules: Vec<String>,
+    /// Anchor of each item on the index page
+    anchors: Vec<String>,
+    /// Structs and interfaces by name
+    types: HashMap<String, Vec<usize>>,
+}
+
+impl Catalog {
+    fn new(generator: &HtmlGenerator, items: &[DocumentedItem], source_dir: &Path) -> Self {
+        let modules: Vec<String> = items.iter().map(|item| item.module(source_dir)).collect();
+        let anchors = items
+            .iter()
+            .zip(&modules)
+            .map(|(item, module)| generator.sanitize_id(&format!("{}.{}", module, item.name)))
+            .collect();
+        let mut types: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, item) in items.iter().enumerate() {
+            if matches!(item.kind, ItemKind::Struct | ItemKind::Interface) {
+                types.entry(item.name.clone()).or_default().push(index);
+            }
+        }
+        Self { modules, anchors, types }
+    }
+
+    /// The item a type name in the signature of item `from` refers to,
+    /// preferring one in the same module
+    fn resolve(&self, name: &str, from: usize) -> Option<usize> {
+        let candidates = self.types.get(name)?;
+        candidates
+            .iter()
+            .copied()
+            .find(|&index| self.modules[index] == self.modules[from])
+            .or_else(|| candidates.first().copied())
+            .filter(|&index| index != from)
+    }
+}
+
+/// A module directory or file in the sidebar
+#[derive(Default)]
+struct ModuleNode<'a> {
+    items: Vec<usize>,
+    children: BTreeMap<&'a str, ModuleNode<'a>>,
+}
+
+/// An entry of `search-index.json`
+#[derive(Serialize)]
+struct SearchEntry<'a> {
+    name: &'a str,
+    kind: String,
+    module: &'a str,
+    signature: &'a str,
+    /// First line of the doc comment
+    summary: &'a str,
+    url: String,
+}
+
 impl HtmlGenerator {
     pub fn new(output_dir: &Path) -> Self {
         Self {
@@ -19,8 +79,9 @@ impl HtmlGenerator {
         }
     }
 
-    /// Generate HTML documentation
-    pub fn generate(&self, items: &[DocumentedItem], project: &Project) -> Result<()> {
+    /// Generate HTML documentation for `items`, which the caller has already
+    /// filtered by visibility
+    pub fn generate(&self, items: &[DocumentedItem], project: &Project, coverage: &DocCoverage) -> Result<()> {
         // Create necessary directories
         fs::create_dir_all(&self.output_dir)?;
         fs::create_dir_all(self.output_dir.join("static"))?;
@@ -31,8 +92,13 @@ impl HtmlGenerator {
         // Generate JavaScript
         self.generate_js()?;
 
+        let catalog = Catalog::new(self, items, &project.root.join("src"));
+
         // Generate index page
-        self.generate_index(items, project)?;
+        self.generate_index(items, project, coverage, &catalog)?;
+
+        // Generate the index the search box queries
+        self.generate_search_index(items, &catalog)?;
 
         // Generate individual pages for each item
         self.generate_item_pages(items, project)?;
@@ -184,6 +250,27 @@ body {
     background-color: var(--background-color);
 }
 
+/* Module tree */
+.sidebar .modules .modules {
+    padding-left: 0.75rem;
+}
+
+.sidebar summary {
+    cursor: pointer;
+    font-size: 0.9rem;
+    font-weight: 600;
+    padding: 0.25rem 0;
+}
+
+.type-link {
+    color: var(--primary-color);
+    text-decoration: none;
+}
+
+.type-link:hover {
+    text-decoration: underline;
+}
+
 /* Item documentation */
 .item {
     margin-bottom: 3rem;
@@ -245,6 +332,65 @@ body {
     line-height: 1.7;
 }
 
+.item-description p,
+.item-description ul,
+.item-description ol {
+    margin: 0.5rem 0;
+}
+
+.item-description ul,
+.item-description ol {
+    padding-left: 1.5rem;
+}
+
+.item-description code,
+.item-section code {
+    background-color: var(--code-background);
+    border-radius: 0.25rem;
+    padding: 0.1rem 0.3rem;
+    font-family: 'Monaco', 'Menlo', 'Ubuntu Mono', monospace;
+    font-size: 0.9em;
+}
+
+.item-description pre {
+    background-color: var(--code-background);
+    border: 1px solid var(--border-color);
+    border-radius: 0.375rem;
+    padding: 1rem;
+    margin: 1rem 0;
+    overflow-x: auto;
+}
+
+.item-description pre code,
+.example code {
+    background: none;
+    padding: 0;
+}
+
+.item-description a {
+    color: var(--primary-color);
+}
+
+/* Highlighted Bulu code */
+.tok-keyword {
+    color: var(--primary-color);
+    font-weight: 600;
+}
+
+.tok-string {
+    color: var(--success-color);
+}
+
+.tok-number,
+.tok-literal {
+    color: var(--warning-color);
+}
+
+.tok-comment {
+    color: var(--text-muted);
+    font-style: italic;
+}
+
 .item-section {
     margin: 1.5rem 0;
 }
@@ -302,6 +448,16 @@ body {
     box-shadow: 0 0 0 3px rgba(37, 99, 235, 0.1);
 }
 
+#search-results {
+    list-style: none;
+    margin-top: 0.5rem;
+}
+
+#search-results .search-module {
+    color: var(--text-muted);
+    font-size: 0.8rem;
+}
+
 /* Responsive */
 @media (max-width: 768px) {
     .sidebar {
@@ -355,11 +511,43 @@ body {
 // Bulu Documentation JavaScript
 
 document.addEventListener('DOMContentLoaded', function() {
-    // Search functionality
+    // Search functionality. Results come from search-index.json; browsers that
+    // refuse to fetch it (pages opened from disk) still filter the page.
     const searchInput = document.getElementById('search');
+    const searchResults = document.getElementById('search-results');
+    let searchIndex = [];
+    fetch('search-index.json')
+        .then(response => response.json())
+        .then(index => { searchIndex = index; })
+        .catch(() => {});
+
+    function showResults(query) {
+        searchResults.innerHTML = '';
+        if (!query) {
+            return;
+        }
+        const matches = searchIndex
+            .filter(entry => [entry.name, entry.module, entry.summary].some(text => text.toLowerCase().includes(query)))
+            .sort((a, b) => b.name.toLowerCase().startsWith(query) - a.name.toLowerCase().startsWith(query))
+            .slice(0, 10);
+        matches.forEach(entry => {
+            const link = document.createElement('a');
+            link.href = entry.url;
+            link.textContent = entry.name + ' ';
+            const module = document.createElement('span');
+            module.className = 'search-module';
+            module.textContent = entry.module;
+            link.appendChild(module);
+            const result = document.createElement('li');
+            result.appendChild(link);
+            searchResults.appendChild(result);
+        });
+    }
+
     if (searchInput) {
         searchInput.addEventListener('input', function() {
             const query = this.value.toLowerCase();
+            showResults(query);
             const items = document.querySelectorAll('.item');
             
             items.forEach(item => {
@@ -419,7 +607,7 @@ document.addEventListener('DOMContentLoaded', function() {
         Ok(())
     }
 
-    fn generate_index(&self, items: &[DocumentedItem], project: &Project) -> Result<()> {
+    fn generate_index(&self, items: &[DocumentedItem], project: &Project, coverage: &DocCoverage, catalog: &Catalog) -> Result<()> {
         let mut html = String::new();
         
         // HTML header
@@ -434,9 +622,10 @@ document.addEventListener('DOMContentLoaded', function() {
     <div class=\"container\">
         <h1>{}</h1>
         <p>API Documentation</p>
+        <p class=\"coverage\">{}</p>
     </div>
 </div>
-", project.config.package.name));
+", project.config.package.name, self.escape_html(&coverage.summary())));
 
         // Navigation
         html.push_str("
@@ -459,11 +648,12 @@ document.addEventListener('DOMContentLoaded', function() {
         <div class=\"sidebar\">
             <div class=\"search\">
                 <input type=\"text\" id=\"search\" placeholder=\"Search documentation...\">
+                <ul id=\"search-results\"></ul>
             </div>
 ");
 
         // Generate sidebar
-        self.generate_sidebar(&mut html, items);
+        self.generate_sidebar(&mut html, items, catalog);
         
         html.push_str("
         </div>
@@ -471,7 +661,7 @@ document.addEventListener('DOMContentLoaded', function() {
 ");
 
         // Generate content sections
-        self.generate_content_sections(&mut html, items);
+        self.generate_content_sections(&mut html, items, catalog);
         
         html.push_str("
         </div>
@@ -486,85 +676,64 @@ document.addEventListener('DOMContentLoaded', function() {
         Ok(())
     }
 
-    fn generate_sidebar(&self, html: &mut String, items: &[DocumentedItem]) {
-        // Group items by kind
-        let mut functions = Vec::new();
-        let mut structs = Vec::new();
-        let mut interfaces = Vec::new();
-        let mut constants = Vec::new();
-
-        for item in items {
-            if matches!(item.visibility, Visibility::Public) {
-                match item.kind {
-                    ItemKind::Function => functions.push(item),
-                    ItemKind::Struct => structs.push(item),
-                    ItemKind::Interface => interfaces.push(item),
-                    ItemKind::Constant => constants.push(item),
-                    _ => {}
-                }
-            }
+    /// Sidebar of modules, nested the way the source directories are
+    fn generate_sidebar(&self, html: &mut String, items: &[DocumentedItem], catalog: &Catalog) {
+        let mut root = ModuleNode::default();
+        for (index, module) in catalog.modules.iter().enumerate() {
+            let node = module
+                .split('/')
+                .fold(&mut root, |node, segment| node.children.entry(segment).or_default());
+            node.items.push(index);
         }
 
-        if !functions.is_empty() {
-            html.push_str("<h3>Functions</h3>\n<ul>\n");
-            for func in functions {
-                html.push_str(&format!("<li><a href=\"#{}\">{}</a></li>", 
-                    self.sanitize_id(&func.name), func.name));
-            }
-            html.push_str("</ul>\n");
-        }
-
-        if !structs.is_empty() {
-            html.push_str("<h3>Structs</h3>\n<ul>\n");
-            for struct_item in structs {
-                html.push_str(&format!("<li><a href=\"#{}\">{}</a></li>", 
-                    self.sanitize_id(&struct_item.name), struct_item.name));
-            }
-            html.push_str("</ul>\n");
-        }
+        html.push_str("<h3>Modules</h3>\n");
+        self.generate_module_tree(html, &root, items, catalog);
+    }
 
-        if !interfaces.is_empty() {
-            html.push_str("<h3>Interfaces</h3>\n<ul>\n");
-            for interface in interfaces {
-                html.push_str(&format!("<li><a href=\"#{}\">{}</a></li>", 
-                    self.sanitize_id(&interface.name), interface.name));
+    fn generate_module_tree(&self, html: &mut String, node: &ModuleNode, items: &[DocumentedItem], catalog: &Catalog) {
+        html.push_str("<ul class=\"modules\">\n");
+        for (name, child) in &node.children {
+            html.push_str(&format!("<li><details open><summary>{}</summary>\n", self.escape_html(name)));
+            if !child.items.is_empty() {
+                html.push_str("<ul>\n");
+                for &index in &child.items {
+                    html.push_str(&format!(
+                        "<li><a href=\"#{}\">{}</a></li>",
+                        catalog.anchors[index],
+                        self.escape_html(&items[index].name)
+                    ));
+                }
+                html.push_str("</ul>\n");
             }
-            html.push_str("</ul>\n");
-        }
-
-        if !constants.is_empty() {
-            html.push_str("<h3>Constants</h3>\n<ul>\n");
-            for constant in constants {
-                html.push_str(&format!("<li><a href=\"#{}\">{}</a></li>", 
-                    self.sanitize_id(&constant.name), constant.name));
+            if !child.children.is_empty() {
+                self.generate_module_tree(html, child, items, catalog);
             }
-            html.push_str("</ul>\n");
+            html.push_str("</details></li>\n");
         }
+        html.push_str("</ul>\n");
     }
 
-    fn generate_content_sections(&self, html: &mut String, items: &[DocumentedItem]) {
+    fn generate_content_sections(&self, html: &mut String, items: &[DocumentedItem], catalog: &Catalog) {
         // Group items by kind
         let mut functions = Vec::new();
         let mut structs = Vec::new();
         let mut interfaces = Vec::new();
         let mut constants = Vec::new();
 
-        for item in items {
-            if matches!(item.visibility, Visibility::Public) {
-                match item.kind {
-                    ItemKind::Function => functions.push(item),
-                    ItemKind::Struct => structs.push(item),
-                    ItemKind::Interface => interfaces.push(item),
-                    ItemKind::Constant => constants.push(item),
-                    _ => {}
-                }
+        for (index, item) in items.iter().enumerate() {
+            match item.kind {
+                ItemKind::Function => functions.push(index),
+                ItemKind::Struct => structs.push(index),
+                ItemKind::Interface => interfaces.push(index),
+                ItemKind::Constant => constants.push(index),
+                _ => {}
             }
         }
 
         if !functions.is_empty() {
             html.push_str("<section id=\"functions\"><h2>Functions</h2>");
             for func in functions {
-                self.generate_item_html(html, func);
+                self.generate_item_html(html, items, catalog, func);
             }
             html.push_str("</section>\n");
         }
@@ -572,7 +741,7 @@ document.addEventListener('DOMContentLoaded', function() {
         if !structs.is_empty() {
             html.push_str("<section id=\"structs\"><h2>Structs</h2>");
             for struct_item in structs {
-                self.generate_item_html(html, struct_item);
+                self.generate_item_html(html, items, catalog, struct_item);
             }
             html.push_str("</section>\n");
         }
@@ -580,7 +749,7 @@ document.addEventListener('DOMContentLoaded', function() {
         if !interfaces.is_empty() {
             html.push_str("<section id=\"interfaces\"><h2>Interfaces</h2>");
             for interface in interfaces {
-                self.generate_item_html(html, interface);
+                self.generate_item_html(html, items, catalog, interface);
             }
             html.push_str("</section>\n");
         }
@@ -588,13 +757,14 @@ document.addEventListener('DOMContentLoaded', function() {
         if !constants.is_empty() {
             html.push_str("<section id=\"constants\"><h2>Constants</h2>");
             for constant in constants {
-                self.generate_item_html(html, constant);
+                self.generate_item_html(html, items, catalog, constant);
             }
             html.push_str("</section>\n");
         }
     }
 
-    fn generate_item_html(&self, html: &mut String, item: &DocumentedItem) {
+    fn generate_item_html(&self, html: &mut String, items: &[DocumentedItem], catalog: &Catalog, index: usize) {
+        let item = &items[index];
         let deprecated_class = if item.doc_comment.as_ref()
             .map(|doc| doc.deprecated.is_some())
             .unwrap_or(false) { " deprecated" } else { "" };
@@ -609,18 +779,18 @@ document.addEventListener('DOMContentLoaded', function() {
     <div class=\"item-signature\">{}</div>
 ", 
             deprecated_class,
-            self.sanitize_id(&item.name),
+            catalog.anchors[index],
             self.escape_html(&item.name),
             item.kind,
             item.visibility,
-            self.escape_html(&item.signature)
+            self.link_signature(catalog, index, &item.signature)
         ));
 
         if let Some(doc) = &item.doc_comment {
             if !doc.content.is_empty() {
                 html.push_str(&format!("
     <div class=\"item-description\">{}</div>
-", self.markdown_to_html(&doc.content)));
+", markdown::render(&doc.content)));
             }
 
             if !doc.params.is_empty() {
@@ -632,7 +802,7 @@ document.addEventListener('DOMContentLoaded', function() {
                 for (param, desc) in &doc.params {
                     html.push_str(&format!("
             <li><span class=\"param-name\">{}</span>: {}</li>
-", self.escape_html(param), self.escape_html(desc)));
+", self.escape_html(param), markdown::render_inline(desc)));
                 }
                 html.push_str("        </ul>\n    </div>\n");
             }
@@ -643,7 +813,7 @@ document.addEventListener('DOMContentLoaded', function() {
         <h4>Returns</h4>
         <p>{}</p>
     </div>
-", self.escape_html(returns)));
+", markdown::render_inline(returns)));
             }
 
             if !doc.examples.is_empty() {
@@ -653,8 +823,8 @@ document.addEventListener('DOMContentLoaded', function() {
 ");
                 for example in &doc.examples {
                     html.push_str(&format!("
-        <div class=\"example\">{}</div>
-", self.escape_html(example)));
+        <pre class=\"example\"><code class=\"language-bulu\">{}</code></pre>
+", markdown::highlight_bulu(example)));
                 }
                 html.push_str("    </div>\n");
             }
@@ -665,13 +835,59 @@ document.addEventListener('DOMContentLoaded', function() {
         <h4 style=\"color: var(--warning-color);\">Deprecated</h4>
         <p>{}</p>
     </div>
-", self.escape_html(deprecated)));
+", markdown::render_inline(deprecated)));
             }
         }
 
         html.push_str("</div>\n");
     }
 
+    /// Escape a signature, linking the documented types it names
+    fn link_signature(&self, catalog: &Catalog, index: usize, signature: &str) -> String {
+        let mut html = String::new();
+        let mut rest = signature;
+        while let Some(start) = rest.find(|c: char| c.is_alphabetic() || c == '_') {
+            html.push_str(&self.escape_html(&rest[..start]));
+            rest = &rest[start..];
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            match catalog.resolve(word, index) {
+                Some(target) => html.push_str(&format!(
+                    "<a class=\"type-link\" href=\"#{}\">{}</a>",
+                    catalog.anchors[target],
+                    self.escape_html(word)
+                )),
+                None => html.push_str(&self.escape_html(word)),
+            }
+            rest = &rest[end..];
+        }
+        html.push_str(&self.escape_html(rest));
+        html
+    }
+
+    fn generate_search_index(&self, items: &[DocumentedItem], catalog: &Catalog) -> Result<()> {
+        let entries: Vec<SearchEntry> = items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| SearchEntry {
+                name: &item.name,
+                kind: format!("{:?}", item.kind),
+                module: &catalog.modules[index],
+                signature: &item.signature,
+                summary: item
+                    .doc_comment
+                    .as_ref()
+                    .and_then(|doc| doc.content.lines().next())
+                    .unwrap_or(""),
+                url: format!("index.html#{}", catalog.anchors[index]),
+            })
+            .collect();
+        fs::write(self.output_dir.join("search-index.json"), serde_json::to_string(&entries)?)?;
+        Ok(())
+    }
+
     fn generate_item_pages(&self, items: &[DocumentedItem], project: &Project) -> Result<()> {
         // For now, we'll just generate the main index page
         // Individual item pages can be added later if needed
@@ -712,24 +928,4 @@ document.addEventListener('DOMContentLoaded', function() {
             .collect::<String>()
             .to_lowercase()
     }
-
-    fn markdown_to_html(&self, text: &str) -> String {
-        // Simple markdown to HTML conversion
-        // This is a basic implementation - a full markdown parser would be better
-        let mut html = self.escape_html(text);
-        
-        // Convert **bold** to <strong>
-        html = html.replace("**", "<strong>").replace("</strong>", "</strong>");
-        
-        // Convert *italic* to <em>
-        html = html.replace("*", "<em>").replace("</em>", "</em>");
-        
-        // Convert `code` to <code>
-        html = html.replace("`", "<code>").replace("</code>", "</code>");
-        
-        // Convert newlines to <br>
-        html = html.replace('\n', "<br>");
-        
-        html
-    }
 }
\ No newline at end of file
@@ -0,0 +1,329 @@
+//! Markdown in doc comments, rendered for the HTML documentation
+//!
+//! Doc comments use the common subset of Markdown: headings, lists, fenced
+//! code blocks, paragraphs, and inline code, bold, italics and links. Fenced
+//! blocks without a language or marked `bulu` are highlighted with the Bulu
+//! lexer. Documentation is often hosted, so nothing from a comment reaches
+//! the page unescaped: raw HTML shows as text and links may only point to
+//! web pages, mail addresses or other pages of the documentation.
+
+use crate::lexer::{Lexer, TokenType};
+
+/// How many levels below `#` a heading is rendered, so headings in a comment
+/// sit below the item title (`h3`) and its sections (`h4`)
+const HEADING_OFFSET: usize = 3;
+
+/// Render the Markdown of a doc comment as HTML
+pub fn render(text: &str) -> String {
+    let mut html = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut list: Option<&str> = None;
+    // Language and lines of the fenced block being read
+    let mut code: Option<(&str, Vec<&str>)> = None;
+
+    fn flush_paragraph(html: &mut String, paragraph: &mut Vec<&str>) {
+        if !paragraph.is_empty() {
+            html.push_str(&format!("<p>{}</p>\n", render_inline(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+    }
+    fn close_list(html: &mut String, list: &mut Option<&str>) {
+        if let Some(tag) = list.take() {
+            html.push_str(&format!("</{}>\n", tag));
+        }
+    }
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+
+        if let Some((language, lines)) = code.as_mut() {
+            if trimmed.starts_with("```") {
+                html.push_str(&code_block(language, &lines.join("\n")));
+                code = None;
+            } else {
+                lines.push(line);
+            }
+            continue;
+        }
+
+        if let Some(language) = trimmed.strip_prefix("```") {
+            flush_paragraph(&mut html, &mut paragraph);
+            close_list(&mut html, &mut list);
+            code = Some((language.trim(), Vec::new()));
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            flush_paragraph(&mut html, &mut paragraph);
+            close_list(&mut html, &mut list);
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|&c| c == '#').count();
+        if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            flush_paragraph(&mut html, &mut paragraph);
+            close_list(&mut html, &mut list);
+            let tag_level = (level + HEADING_OFFSET).min(6);
+            html.push_str(&format!(
+                "<h{}>{}</h{}>\n",
+                tag_level,
+                render_inline(trimmed[level..].trim()),
+                tag_level
+            ));
+            continue;
+        }
+
+        let item = if let Some(rest) = ["- ", "* ", "+ "].iter().find_map(|marker| trimmed.strip_prefix(marker)) {
+            Some(("ul", rest))
+        } else {
+            let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+            match trimmed[digits..].strip_prefix(". ") {
+                Some(rest) if digits > 0 => Some(("ol", rest)),
+                _ => None,
+            }
+        };
+        if let Some((tag, rest)) = item {
+            flush_paragraph(&mut html, &mut paragraph);
+            if list != Some(tag) {
+                close_list(&mut html, &mut list);
+                html.push_str(&format!("<{}>\n", tag));
+                list = Some(tag);
+            }
+            html.push_str(&format!("<li>{}</li>\n", render_inline(rest)));
+            continue;
+        }
+
+        close_list(&mut html, &mut list);
+        paragraph.push(trimmed);
+    }
+
+    // An unclosed block runs to the end of the comment
+    if let Some((language, lines)) = code {
+        html.push_str(&code_block(language, &lines.join("\n")));
+    }
+    flush_paragraph(&mut html, &mut paragraph);
+    close_list(&mut html, &mut list);
+    html
+}
+
+fn code_block(language: &str, code: &str) -> String {
+    if language.is_empty() || language == "bulu" {
+        return format!("<pre><code class=\"language-bulu\">{}</code></pre>\n", highlight_bulu(code));
+    }
+    let language: String = language
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    format!(
+        "<pre><code class=\"language-{}\">{}</code></pre>\n",
+        language,
+        escape_html(code)
+    )
+}
+
+/// Inline Markdown: `code` spans are kept verbatim, the rest gets links and emphasis
+pub fn render_inline(text: &str) -> String {
+    let mut html = String::new();
+    let parts: Vec<&str> = text.split('`').collect();
+    for (i, part) in parts.iter().enumerate() {
+        if i % 2 == 1 && i < parts.len() - 1 {
+            html.push_str(&format!("<code>{}</code>", escape_html(part)));
+        } else {
+            if i % 2 == 1 {
+                // An unmatched backtick is text
+                html.push('`');
+            }
+            html.push_str(&render_emphasis(&render_links(part)));
+        }
+    }
+    html
+}
+
+/// Escape text and turn `[label](url)` into links
+fn render_links(text: &str) -> String {
+    let mut html = String::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find("](").map(|i| open + i) else {
+            break;
+        };
+        // Parentheses inside the URL are balanced, as in `f(x)`
+        let mut depth = 0;
+        let end = rest[close + 2..].find(|c| {
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 0 => return true,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            false
+        });
+        let Some(end) = end.map(|i| close + 2 + i) else {
+            break;
+        };
+        html.push_str(&escape_html(&rest[..open]));
+        html.push_str(&format!(
+            "<a href=\"{}\">{}</a>",
+            escape_html(&safe_url(&rest[close + 2..end])),
+            escape_html(&rest[open + 1..close])
+        ));
+        rest = &rest[end + 1..];
+    }
+    html.push_str(&escape_html(rest));
+    html
+}
+
+/// `**bold**` and `*italic*`, applied to already escaped text
+fn render_emphasis(html: &str) -> String {
+    fn wrap(text: &str, marker: &str, tag: &str) -> String {
+        let parts: Vec<&str> = text.split(marker).collect();
+        // An unmatched marker is left as it is
+        if parts.len() < 3 {
+            return text.to_string();
+        }
+        let mut out = String::new();
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                let closing = i % 2 == 0;
+                if !closing && i == parts.len() - 1 {
+                    out.push_str(marker);
+                } else {
+                    out.push_str(&format!("<{}{}>", if closing { "/" } else { "" }, tag));
+                }
+            }
+            out.push_str(part);
+        }
+        out
+    }
+    wrap(&wrap(html, "**", "strong"), "*", "em")
+}
+
+/// Links may use http(s) or mailto, or be relative to the documentation;
+/// anything else, such as `javascript:`, links nowhere
+fn safe_url(url: &str) -> String {
+    let url = url.trim();
+    let lower = url.to_ascii_lowercase();
+    let scheme_end = lower.find(':');
+    let path_start = lower.find(['/', '?', '#']);
+    let relative = match (scheme_end, path_start) {
+        (None, _) => true,
+        (Some(colon), Some(path)) => path < colon,
+        (Some(_), None) => false,
+    };
+    let allowed = ["http://", "https://", "mailto:"];
+    if relative || allowed.iter().any(|scheme| lower.starts_with(scheme)) {
+        url.to_string()
+    } else {
+        "#".to_string()
+    }
+}
+
+/// Bulu source as HTML with `tok-*` classes on keywords, literals and
+/// comments. Code the lexer rejects is escaped without highlighting.
+pub fn highlight_bulu(code: &str) -> String {
+    let Ok(tokens) = Lexer::new(code).tokenize() else {
+        return escape_html(code);
+    };
+    let chars: Vec<char> = code.chars().collect();
+    let text = |start: usize, end: usize| -> String { chars[start..end].iter().collect() };
+
+    let mut html = String::new();
+    let mut position = 0;
+    for token in &tokens {
+        if token.token_type == TokenType::Eof {
+            break;
+        }
+        let start = token.position.offset.clamp(position, chars.len());
+        let end = (start + token.lexeme.chars().count()).min(chars.len());
+
+        // Between tokens there is only whitespace and comments
+        push_gap(&mut html, &text(position, start));
+
+        let class = match token.token_type {
+            TokenType::StringLiteral | TokenType::ByteStringLiteral | TokenType::CharLiteral => Some("string"),
+            TokenType::IntegerLiteral | TokenType::FloatLiteral => Some("number"),
+            TokenType::True | TokenType::False | TokenType::Null => Some("literal"),
+            TokenType::DocComment => Some("comment"),
+            TokenType::Identifier | TokenType::Newline => None,
+            _ if token.lexeme.chars().all(|c| c.is_ascii_alphabetic()) && !token.lexeme.is_empty() => {
+                Some("keyword")
+            }
+            _ => None,
+        };
+        let lexeme = escape_html(&text(start, end));
+        match class {
+            Some(class) => html.push_str(&format!("<span class=\"tok-{}\">{}</span>", class, lexeme)),
+            None => html.push_str(&lexeme),
+        }
+        position = end;
+    }
+    push_gap(&mut html, &text(position, chars.len()));
+    html
+}
+
+fn push_gap(html: &mut String, gap: &str) {
+    let content = gap.trim();
+    if content.is_empty() {
+        html.push_str(gap);
+        return;
+    }
+    let start = gap.len() - gap.trim_start().len();
+    let end = start + content.len();
+    html.push_str(&gap[..start]);
+    html.push_str(&format!("<span class=\"tok-comment\">{}</span>", escape_html(content)));
+    html.push_str(&gap[end..]);
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#x27;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_blocks() {
+        let html = render(
+            "Sums values.\nSee [the guide](https://example.com/guide).\n\n# Notes\n\n- uses `fold`\n- **fast**\n1. first\n\n```text\n<raw>\n```",
+        );
+        assert_eq!(
+            html,
+            "<p>Sums values. See <a href=\"https://example.com/guide\">the guide</a>.</p>\n\
+             <h4>Notes</h4>\n\
+             <ul>\n<li>uses <code>fold</code></li>\n<li><strong>fast</strong></li>\n</ul>\n\
+             <ol>\n<li>first</li>\n</ol>\n\
+             <pre><code class=\"language-text\">&lt;raw&gt;</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_raw_html_and_unsafe_links_are_neutralized() {
+        let html = render("<script>alert(1)</script> [x](javascript:alert(1)) [y](JavaScript:alert(1)) [z](#add)");
+        assert!(!html.contains("<script"), "{}", html);
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"), "{}", html);
+        assert!(html.contains("<a href=\"#\">x</a>"), "{}", html);
+        assert!(html.contains("<a href=\"#\">y</a>"), "{}", html);
+        assert!(html.contains("<a href=\"#add\">z</a>"), "{}", html);
+        assert_eq!(safe_url("mailto:a@b.c"), "mailto:a@b.c");
+        assert_eq!(safe_url("../other.html?q=a:b"), "../other.html?q=a:b");
+        assert_eq!(safe_url("data:text/html,x"), "#");
+        assert!(!render("[q](\"onmouseover=\"x)").contains("\"onmouseover"));
+    }
+
+    #[test]
+    fn test_highlight_bulu() {
+        assert_eq!(
+            highlight_bulu("let s = \"a<b\" // note\nreturn 42"),
+            "<span class=\"tok-keyword\">let</span> s = <span class=\"tok-string\">&quot;a&lt;b&quot;</span> \
+             <span class=\"tok-comment\">// note</span>\n<span class=\"tok-keyword\">return</span> \
+             <span class=\"tok-number\">42</span>"
+        );
+        // Code the lexer rejects is only escaped
+        assert_eq!(highlight_bulu("let s = \"<open"), "let s = &quot;&lt;open");
+    }
+}
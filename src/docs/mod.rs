@@ -8,11 +8,15 @@ use std::fs;
 use std::collections::HashMap;
 use colored::*;
 use serde::{Serialize, Deserialize};
+use tracing::{debug, info};
 
+pub mod doctest;
 pub mod extractor;
 pub mod html_generator;
+pub mod markdown;
 pub mod server;
 
+use doctest::DoctestRunner;
 use extractor::DocExtractor;
 use html_generator::HtmlGenerator;
 use server::DocServer;
@@ -32,7 +36,8 @@ pub struct DocOptions {
     pub format: DocFormat,
     pub serve: bool,
     pub port: u16,
-    pub verbose: bool,
+    /// Document items that are not exported as well
+    pub document_private_items: bool,
 }
 
 impl Default for DocOptions {
@@ -42,7 +47,7 @@ impl Default for DocOptions {
             format: DocFormat::Html,
             serve: false,
             port: 8080,
-            verbose: false,
+            document_private_items: false,
         }
     }
 }
@@ -78,34 +83,43 @@ impl DocComment {
         let mut example_content = String::new();
 
         for line in text.lines() {
-            let line = line.trim().trim_start_matches("*").trim();
-            
-            if line.starts_with("@param") {
-                if let Some(rest) = line.strip_prefix("@param") {
+            // Only the ` * ` gutter goes, so code in the comment keeps its
+            // indentation and Markdown such as `**bold**` survives
+            let line = line.trim_start();
+            let line = match line.strip_prefix("* ") {
+                Some(rest) => rest,
+                None if line == "*" => "",
+                None => line,
+            }
+            .trim_end();
+            let tag = line.trim_start();
+
+            if tag.starts_with("@param") {
+                if let Some(rest) = tag.strip_prefix("@param") {
                     let rest = rest.trim();
                     if let Some((param_name, param_desc)) = rest.split_once(" - ") {
                         doc.params.insert(param_name.trim().to_string(), param_desc.trim().to_string());
                     }
                 }
-            } else if line.starts_with("@return") {
-                if let Some(rest) = line.strip_prefix("@return") {
+            } else if tag.starts_with("@return") {
+                if let Some(rest) = tag.strip_prefix("@return") {
                     doc.returns = Some(rest.trim().to_string());
                 }
-            } else if line.starts_with("@example") {
+            } else if tag.starts_with("@example") {
                 if in_example && !example_content.is_empty() {
                     doc.examples.push(example_content.trim().to_string());
                 }
                 in_example = true;
                 example_content.clear();
-            } else if line.starts_with("@since") {
-                if let Some(rest) = line.strip_prefix("@since") {
+            } else if tag.starts_with("@since") {
+                if let Some(rest) = tag.strip_prefix("@since") {
                     doc.since = Some(rest.trim().to_string());
                 }
-            } else if line.starts_with("@deprecated") {
-                if let Some(rest) = line.strip_prefix("@deprecated") {
+            } else if tag.starts_with("@deprecated") {
+                if let Some(rest) = tag.strip_prefix("@deprecated") {
                     doc.deprecated = Some(rest.trim().to_string());
                 }
-            } else if line.starts_with("@") {
+            } else if tag.starts_with("@") {
                 // End of example or other section
                 if in_example && !example_content.is_empty() {
                     doc.examples.push(example_content.trim().to_string());
@@ -149,6 +163,24 @@ pub struct DocumentedItem {
     pub line_number: usize,
 }
 
+impl DocumentedItem {
+    /// Whether the item has a doc comment that describes it
+    pub fn is_documented(&self) -> bool {
+        self.doc_comment.as_ref().is_some_and(|doc| !doc.content.is_empty())
+    }
+
+    /// The module the item is declared in: its file's path under
+    /// `source_dir`, without the extension, e.g. `geometry/shapes`
+    pub fn module(&self, source_dir: &Path) -> String {
+        let path = self.file_path.strip_prefix(source_dir).unwrap_or(&self.file_path);
+        path.with_extension("")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ItemKind {
     Function,
@@ -165,6 +197,173 @@ pub enum Visibility {
     Private,
 }
 
+/// How much of the public API is documented, and how many of the examples
+/// in the documentation pass as doctests
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocCoverage {
+    pub public_items: usize,
+    /// Public items with a doc comment that describes them
+    pub documented_public_items: usize,
+    /// Examples of the documented items
+    pub examples: usize,
+    pub verified_examples: usize,
+}
+
+impl DocCoverage {
+    /// Measure coverage of `items`, running each of their examples
+    pub fn measure(items: &[DocumentedItem]) -> Self {
+        let runner = DoctestRunner::new();
+        let mut sources: HashMap<&Path, Option<String>> = HashMap::new();
+        let mut coverage = DocCoverage::default();
+
+        for item in items {
+            let doc = item.doc_comment.as_ref();
+            if matches!(item.visibility, Visibility::Public) {
+                coverage.public_items += 1;
+                if item.is_documented() {
+                    coverage.documented_public_items += 1;
+                }
+            }
+
+            let examples = doc.map(|doc| doc.examples.as_slice()).unwrap_or_default();
+            if examples.is_empty() {
+                continue;
+            }
+            coverage.examples += examples.len();
+            let source = sources
+                .entry(&item.file_path)
+                .or_insert_with(|| fs::read_to_string(&item.file_path).ok());
+            let Some(source) = source else { continue };
+            for example in examples {
+                match runner.run(source, &item.file_path, example) {
+                    Ok(()) => coverage.verified_examples += 1,
+                    Err(e) => debug!("Example of {} failed: {}", item.name, e),
+                }
+            }
+        }
+
+        coverage
+    }
+
+    /// A one-line summary for index pages
+    pub fn summary(&self) -> String {
+        format!(
+            "{} of {} public items documented, {} of {} examples verified by doctests",
+            self.documented_public_items, self.public_items, self.verified_examples, self.examples
+        )
+    }
+}
+
+/// How many of a module's exported items are documented
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleCoverage {
+    pub module: String,
+    pub public_items: usize,
+    pub documented_public_items: usize,
+    /// Names of the exported items without documentation
+    pub undocumented: Vec<String>,
+}
+
+impl ModuleCoverage {
+    /// Percentage of documented exported items; 100 without any
+    pub fn percentage(&self) -> f64 {
+        percentage(self.documented_public_items, self.public_items)
+    }
+}
+
+fn percentage(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+/// Documentation coverage of the exported items of each module, for
+/// `lang doc --coverage`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoverageReport {
+    /// Modules sorted by path
+    pub modules: Vec<ModuleCoverage>,
+    pub public_items: usize,
+    pub documented_public_items: usize,
+    pub percentage: f64,
+}
+
+impl CoverageReport {
+    /// The coverage of the exported `items` of the files under `source_dir`
+    pub fn new(items: &[DocumentedItem], source_dir: &Path) -> Self {
+        let mut modules: std::collections::BTreeMap<String, ModuleCoverage> = std::collections::BTreeMap::new();
+        for item in items {
+            let module = item.module(source_dir);
+            let coverage = modules.entry(module.clone()).or_insert_with(|| ModuleCoverage {
+                module,
+                public_items: 0,
+                documented_public_items: 0,
+                undocumented: Vec::new(),
+            });
+            if !matches!(item.visibility, Visibility::Public) {
+                continue;
+            }
+            coverage.public_items += 1;
+            if item.is_documented() {
+                coverage.documented_public_items += 1;
+            } else {
+                coverage.undocumented.push(item.name.clone());
+            }
+        }
+
+        // Modules without exported items have nothing to document
+        let modules: Vec<ModuleCoverage> = modules
+            .into_values()
+            .filter(|module| module.public_items > 0)
+            .collect();
+        let public_items = modules.iter().map(|module| module.public_items).sum();
+        let documented_public_items = modules.iter().map(|module| module.documented_public_items).sum();
+        Self {
+            modules,
+            public_items,
+            documented_public_items,
+            percentage: percentage(documented_public_items, public_items),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("coverage reports serialize")
+    }
+
+    /// A table with a row per module, its undocumented items, and the total
+    pub fn to_text(&self) -> String {
+        let width = self
+            .modules
+            .iter()
+            .map(|module| module.module.len())
+            .chain(std::iter::once("Module".len()))
+            .max()
+            .unwrap_or_default();
+
+        let mut text = format!("{:<width$}  {:>10}  {:>8}\n", "Module", "Documented", "Coverage");
+        for module in &self.modules {
+            text.push_str(&format!(
+                "{:<width$}  {:>10}  {:>7.1}%\n",
+                module.module,
+                format!("{}/{}", module.documented_public_items, module.public_items),
+                module.percentage()
+            ));
+            if !module.undocumented.is_empty() {
+                text.push_str(&format!("{:<width$}    missing: {}\n", "", module.undocumented.join(", ")));
+            }
+        }
+        text.push_str(&format!(
+            "{:<width$}  {:>10}  {:>7.1}%\n",
+            "Total",
+            format!("{}/{}", self.documented_public_items, self.public_items),
+            self.percentage
+        ));
+        text
+    }
+}
+
 /// Documentation generator
 pub struct DocGenerator {
     project: Project,
@@ -178,34 +377,35 @@ impl DocGenerator {
 
     /// Generate documentation
     pub fn generate(&self) -> Result<()> {
-        if self.options.verbose {
-            println!("{} Generating documentation for '{}'...", "Documenting".green().bold(), self.project.config.package.name);
-        }
+        info!("{} Generating documentation for '{}'...", "Documenting".green().bold(), self.project.config.package.name);
 
         // Create output directory
         fs::create_dir_all(&self.options.output_dir)?;
 
         // Extract documentation from source files
         let extractor = DocExtractor::new();
-        let documented_items = self.extract_documentation(&extractor)?;
+        let mut documented_items = self.extract_documentation(&extractor)?;
+        if !self.options.document_private_items {
+            documented_items.retain(|item| matches!(item.visibility, Visibility::Public));
+        }
+        let coverage = DocCoverage::measure(&documented_items);
+        info!("{} {}", "Coverage".cyan(), coverage.summary());
 
         // Generate documentation based on format
         match self.options.format {
             DocFormat::Html => {
                 let generator = HtmlGenerator::new(&self.options.output_dir);
-                generator.generate(&documented_items, &self.project)?;
+                generator.generate(&documented_items, &self.project, &coverage)?;
             }
             DocFormat::Markdown => {
-                self.generate_markdown(&documented_items)?;
+                self.generate_markdown(&documented_items, &coverage)?;
             }
             DocFormat::Json => {
                 self.generate_json(&documented_items)?;
             }
         }
 
-        if self.options.verbose {
-            println!("{} Documentation generated in '{}'", "Success".green().bold(), self.options.output_dir.display());
-        }
+        info!("{} Documentation generated in '{}'", "Success".green().bold(), self.options.output_dir.display());
 
         // Start local server if requested
         if self.options.serve {
@@ -216,6 +416,13 @@ impl DocGenerator {
         Ok(())
     }
 
+    /// The documentation coverage of the project's exported items, without
+    /// generating documentation
+    pub fn coverage_report(&self) -> Result<CoverageReport> {
+        let items = self.extract_documentation(&DocExtractor::new())?;
+        Ok(CoverageReport::new(&items, &self.project.root.join("src")))
+    }
+
     fn extract_documentation(&self, extractor: &DocExtractor) -> Result<Vec<DocumentedItem>> {
         let mut documented_items = Vec::new();
         
@@ -223,9 +430,7 @@ impl DocGenerator {
         let source_files = self.find_source_files()?;
         
         for file_path in source_files {
-            if self.options.verbose {
-                println!("Processing {}", file_path.display());
-            }
+            debug!("Processing {}", file_path.display());
             
             let content = fs::read_to_string(&file_path)?;
             let items = extractor.extract_from_file(&content, &file_path)?;
@@ -256,9 +461,10 @@ impl DocGenerator {
         Ok(())
     }
 
-    fn generate_markdown(&self, items: &[DocumentedItem]) -> Result<()> {
+    fn generate_markdown(&self, items: &[DocumentedItem], coverage: &DocCoverage) -> Result<()> {
         let mut content = String::new();
         content.push_str(&format!("# {} API Documentation\n\n", self.project.config.package.name));
+        content.push_str(&format!("_{}_\n\n", coverage.summary()));
 
         // Group items by kind
         let mut functions = Vec::new();
@@ -313,6 +519,9 @@ impl DocGenerator {
     fn generate_markdown_item(&self, content: &mut String, item: &DocumentedItem) {
         content.push_str(&format!("### {}\n\n", item.name));
         content.push_str(&format!("```bulu\n{}\n```\n\n", item.signature));
+        if matches!(item.visibility, Visibility::Private) {
+            content.push_str("_Private_\n\n");
+        }
         
         if let Some(doc) = &item.doc_comment {
             if !doc.content.is_empty() {
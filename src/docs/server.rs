@@ -7,6 +7,7 @@ use std::io::prelude::*;
 use std::net::{TcpListener, TcpStream};
 use std::thread;
 use colored::*;
+use tracing::{info, warn};
 
 /// Local documentation server
 pub struct DocServer {
@@ -24,9 +25,9 @@ impl DocServer {
         let address = format!("127.0.0.1:{}", self.port);
         let listener = TcpListener::bind(&address)?;
         
-        println!("{} Documentation server running at http://{}", 
+        info!("{} Documentation server running at http://{}", 
                 "Server".green().bold(), address);
-        println!("Press Ctrl+C to stop the server");
+        info!("Press Ctrl+C to stop the server");
 
         for stream in listener.incoming() {
             match stream {
@@ -34,12 +35,12 @@ impl DocServer {
                     let doc_dir = self.doc_dir.clone();
                     thread::spawn(move || {
                         if let Err(e) = handle_request(stream, &doc_dir) {
-                            eprintln!("Error handling request: {}", e);
+                            warn!("Error handling request: {}", e);
                         }
                     });
                 }
                 Err(e) => {
-                    eprintln!("Error accepting connection: {}", e);
+                    warn!("Error accepting connection: {}", e);
                 }
             }
         }
@@ -61,6 +61,9 @@ pub enum BuluError {
     Continue,
     /// Return statement (control flow)
     Return(crate::types::primitive::RuntimeValue),
+    /// `exit(code)` (control flow): unwinds to whoever is running the program,
+    /// which decides whether to end the process
+    ExitRequested(i32),
     /// Generic error
     Other(String),
 }
@@ -138,6 +141,9 @@ impl fmt::Display for BuluError {
             BuluError::Return(_) => {
                 write!(f, "Return statement outside of function")
             }
+            BuluError::ExitRequested(code) => {
+                write!(f, "Program exited with code {}", code)
+            }
             BuluError::Other(message) => {
                 write!(f, "Error: {}", message)
             }
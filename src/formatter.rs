@@ -6,6 +6,7 @@ use colored::*;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
+use tracing::{debug, error, info, warn};
 
 /// Formatting configuration that can be loaded from .langfmt.toml
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,7 +109,6 @@ impl Default for FormatConfig {
 #[derive(Debug, Clone)]
 pub struct FormatOptions {
     pub check_only: bool,
-    pub verbose: bool,
     pub config: FormatConfig,
 }
 
@@ -116,7 +116,6 @@ impl Default for FormatOptions {
     fn default() -> Self {
         Self {
             check_only: false,
-            verbose: false,
             config: FormatConfig::default(),
         }
     }
@@ -127,7 +126,6 @@ impl FormatOptions {
     pub fn from_config(config: FormatConfig) -> Self {
         Self {
             check_only: false,
-            verbose: false,
             config,
         }
     }
@@ -156,33 +154,49 @@ pub struct FormatResult {
     pub original_lines: usize,
     pub formatted_lines: usize,
     pub errors: Vec<String>,
+    /// Unified diff of the pending changes (populated in check-only mode)
+    pub diff: Option<String>,
 }
 
 /// Code formatter for Bulu projects
 pub struct Formatter {
-    project: Project,
+    project: Option<Project>,
     options: FormatOptions,
 }
 
 impl Formatter {
     pub fn new(project: Project, options: FormatOptions) -> Self {
-        Self { project, options }
+        Self {
+            project: Some(project),
+            options,
+        }
+    }
+
+    /// Create a formatter for standalone sources (e.g. editor buffers) outside a project
+    pub fn for_source(options: FormatOptions) -> Self {
+        Self {
+            project: None,
+            options,
+        }
     }
 
     /// Format all source files in the project
     pub fn format_project(&self) -> Result<Vec<FormatResult>> {
-        if self.options.verbose {
-            println!(
-                "{} Formatting project '{}'...",
-                "Formatting".green().bold(),
-                self.project.config.package.name
-            );
-        }
+        let project = self
+            .project
+            .as_ref()
+            .ok_or_else(|| BuluError::Other("No project to format".to_string()))?;
 
-        let source_files = self.project.source_files()?;
+        info!(
+            "{} Formatting project '{}'...",
+            "Formatting".green().bold(),
+            project.config.package.name
+        );
+
+        let source_files = project.source_files()?;
 
         if source_files.is_empty() {
-            println!("{} No source files found", "Warning".yellow().bold());
+            warn!("{} No source files found", "Warning".yellow().bold());
             return Ok(Vec::new());
         }
 
@@ -190,30 +204,31 @@ impl Formatter {
         let mut total_changed = 0;
 
         for source_file in &source_files {
-            if self.options.verbose {
-                println!("{} {}", "Formatting".cyan().bold(), source_file.display());
-            }
+            debug!("{} {}", "Formatting".cyan().bold(), source_file.display());
 
             match self.format_file(source_file) {
                 Ok(result) => {
                     if result.changed {
                         total_changed += 1;
                         if !self.options.check_only {
-                            println!("  {} {}", "Formatted".green(), source_file.display());
+                            info!("  {} {}", "Formatted".green(), source_file.display());
                         } else {
                             println!(
                                 "  {} {} (would be formatted)",
                                 "Check".yellow(),
                                 source_file.display()
                             );
+                            if let Some(diff) = &result.diff {
+                                print!("{}", diff);
+                            }
                         }
-                    } else if self.options.verbose {
-                        println!("  {} {} (no changes)", "OK".green(), source_file.display());
+                    } else {
+                        debug!("  {} {} (no changes)", "OK".green(), source_file.display());
                     }
                     results.push(result);
                 }
                 Err(e) => {
-                    println!(
+                    error!(
                         "  {} {} - {}",
                         "Error".red().bold(),
                         source_file.display(),
@@ -225,6 +240,7 @@ impl Formatter {
                         original_lines: 0,
                         formatted_lines: 0,
                         errors: vec![e.to_string()],
+                        diff: None,
                     });
                 }
             }
@@ -246,13 +262,13 @@ impl Formatter {
             }
         } else {
             if total_changed > 0 {
-                println!(
+                info!(
                     "{} Formatted {} files",
                     "Finished".green().bold(),
                     total_changed
                 );
             } else {
-                println!(
+                info!(
                     "{} All files were already formatted",
                     "Finished".green().bold()
                 );
@@ -281,15 +297,43 @@ impl Formatter {
                 .map_err(|e| BuluError::Other(format!("Failed to write formatted file: {}", e)))?;
         }
 
+        let diff = if changed && self.options.check_only {
+            let name = file_path.display().to_string();
+            Some(unified_diff(&original_content, &formatted_content, &name))
+        } else {
+            None
+        };
+
         Ok(FormatResult {
             file: file_path.to_path_buf(),
             changed,
             original_lines,
             formatted_lines,
             errors: Vec::new(),
+            diff,
         })
     }
 
+    /// Format the lines `start..=end` (0-based) of `source` and return the replacement text
+    /// for exactly those lines. Indentation is derived from the code preceding the range.
+    pub fn format_range(&self, source: &str, start: usize, end: usize) -> Result<String> {
+        let lines: Vec<&str> = source.lines().collect();
+        if start > end || start >= lines.len() {
+            return Err(BuluError::Other(format!(
+                "Invalid format range {}..={} for a source of {} lines",
+                start,
+                end,
+                lines.len()
+            )));
+        }
+        let end = end.min(lines.len() - 1);
+
+        let (_, indent_level) = self.format_lines(&lines[..start], 0);
+        let (formatted, _) = self.format_lines(&lines[start..=end], indent_level);
+
+        Ok(formatted.join("\n"))
+    }
+
     /// Format the content of a source file
     pub fn format_content(&self, content: &str) -> Result<String> {
         // Handle simple single-line cases first
@@ -297,11 +341,19 @@ impl Formatter {
             return Ok(self.format_single_line(content));
         }
 
+        let lines: Vec<&str> = content.lines().collect();
+        let (formatted_lines, _) = self.format_lines(&lines, 0);
+
+        Ok(formatted_lines.join("\n"))
+    }
+
+    /// Format a sequence of lines starting at `indent_level`.
+    /// Returns the formatted lines and the indentation level after the last line.
+    fn format_lines(&self, lines: &[&str], mut indent_level: usize) -> (Vec<String>, usize) {
         let mut formatted_lines = Vec::new();
-        let mut indent_level = 0;
         let mut in_multiline_comment = false;
 
-        for line in content.lines() {
+        for line in lines {
             let trimmed = line.trim();
 
             // Handle multiline comments
@@ -363,7 +415,7 @@ impl Formatter {
             }
         }
 
-        Ok(formatted_lines.join("\n"))
+        (formatted_lines, indent_level)
     }
 
     /// Format a line that contains braces and may need to be split into multiple lines
@@ -805,29 +857,21 @@ impl Formatter {
         let mut options = self.options.clone();
         options.check_only = true;
 
-        let formatter = Formatter::new(self.project.clone(), options);
+        let formatter = Formatter {
+            project: self.project.clone(),
+            options,
+        };
         let results = formatter.format_project()?;
 
         Ok(results.iter().any(|r| r.changed))
     }
 }
 
-/// Load formatting configuration from .langfmt.toml
+/// Load formatting configuration from the `[fmt]` section of the layered
+/// configuration, which includes .langfmt.toml
 pub fn load_format_config(project_root: &Path) -> Result<FormatOptions> {
-    let config_path = project_root.join(".langfmt.toml");
-
-    if !config_path.exists() {
-        return Ok(FormatOptions::default());
-    }
-
-    let _config_content = fs::read_to_string(&config_path)
-        .map_err(|e| BuluError::Other(format!("Failed to read .langfmt.toml: {}", e)))?;
-
-    // Parse TOML configuration
-    let format_config: FormatConfig = toml::from_str(&_config_content)
-        .map_err(|e| BuluError::Other(format!("Failed to parse .langfmt.toml: {}", e)))?;
-
-    Ok(FormatOptions::from_config(format_config))
+    let config = crate::config::Config::load(Some(project_root))?;
+    Ok(FormatOptions::from_config(config.format_config()?))
 }
 
 /// Create a default .langfmt.toml configuration file
@@ -911,7 +955,7 @@ indent_style = "{}"
     fs::write(&config_path, commented_config)
         .map_err(|e| BuluError::Other(format!("Failed to write .langfmt.toml: {}", e)))?;
 
-    println!("Created default .langfmt.toml configuration file");
+    info!("Created default .langfmt.toml configuration file");
     Ok(())
 }
 
@@ -943,3 +987,158 @@ pub fn validate_format_config(config: &FormatConfig) -> Result<()> {
 
     Ok(())
 }
+
+/// Produce a unified diff (3 lines of context) turning `original` into `formatted`.
+/// Returns an empty string when the inputs are identical.
+pub fn unified_diff(original: &str, formatted: &str, file_name: &str) -> String {
+    const CONTEXT: usize = 3;
+
+    let old: Vec<&str> = original.lines().collect();
+    let new: Vec<&str> = formatted.lines().collect();
+
+    let mut ops = Vec::with_capacity(old.len().max(new.len()));
+    edit_script(&old, 0..old.len(), &new, 0..new.len(), &mut ops);
+
+    let mut output = String::new();
+    let mut index = 0;
+    while index < ops.len() {
+        if ops[index].0 == ' ' {
+            index += 1;
+            continue;
+        }
+
+        // Grow the hunk until a run of unchanged lines is long enough to split on
+        let hunk_start = index.saturating_sub(CONTEXT);
+        let mut hunk_end = index;
+        let mut unchanged = 0;
+        while hunk_end < ops.len() && unchanged <= 2 * CONTEXT {
+            if ops[hunk_end].0 == ' ' {
+                unchanged += 1;
+            } else {
+                unchanged = 0;
+            }
+            hunk_end += 1;
+        }
+        if unchanged > CONTEXT {
+            hunk_end -= unchanged - CONTEXT;
+        }
+
+        if output.is_empty() {
+            output.push_str(&format!("--- {}\n+++ {} (formatted)\n", file_name, file_name));
+        }
+
+        let hunk = &ops[hunk_start..hunk_end];
+        let old_count = hunk.iter().filter(|op| op.0 != '+').count();
+        let new_count = hunk.iter().filter(|op| op.0 != '-').count();
+        let old_start = if old_count == 0 { hunk[0].1 } else { hunk[0].1 + 1 };
+        let new_start = if new_count == 0 { hunk[0].2 } else { hunk[0].2 + 1 };
+        output.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start, old_count, new_start, new_count
+        ));
+
+        for &(tag, old_index, new_index) in hunk {
+            let line = if tag == '+' { new[new_index] } else { old[old_index] };
+            output.push(tag);
+            output.push_str(line);
+            output.push('\n');
+        }
+
+        index = hunk_end;
+    }
+
+    output
+}
+
+/// Append the edit script turning `old[old_range]` into `new[new_range]` to
+/// `ops`, as (tag, old index, new index) per line.
+///
+/// This is Myers' diff in linear space: the middle of a shortest edit script
+/// splits the ranges in two, which are diffed the same way. Memory stays
+/// proportional to the length of the files instead of their product.
+fn edit_script(
+    old: &[&str],
+    old_range: std::ops::Range<usize>,
+    new: &[&str],
+    new_range: std::ops::Range<usize>,
+    ops: &mut Vec<(char, usize, usize)>,
+) {
+    let (mut old_start, mut old_end) = (old_range.start, old_range.end);
+    let (mut new_start, mut new_end) = (new_range.start, new_range.end);
+    while old_start < old_end && new_start < new_end && old[old_start] == new[new_start] {
+        ops.push((' ', old_start, new_start));
+        old_start += 1;
+        new_start += 1;
+    }
+    let mut suffix = 0;
+    while old_start < old_end && new_start < new_end && old[old_end - 1] == new[new_end - 1] {
+        old_end -= 1;
+        new_end -= 1;
+        suffix += 1;
+    }
+
+    if old_start == old_end {
+        ops.extend((new_start..new_end).map(|j| ('+', old_start, j)));
+    } else if new_start == new_end {
+        ops.extend((old_start..old_end).map(|i| ('-', i, new_start)));
+    } else {
+        let (x, y) = middle_snake(&old[old_start..old_end], &new[new_start..new_end]);
+        edit_script(old, old_start..old_start + x, new, new_start..new_start + y, ops);
+        edit_script(old, old_start + x..old_end, new, new_start + y..new_end, ops);
+    }
+
+    ops.extend((0..suffix).map(|k| (' ', old_end + k, new_end + k)));
+}
+
+/// A point on a shortest edit script of `old` into `new` with about half
+/// the edits on each side. Both must be non-empty and differ at either end,
+/// so the script has at least two edits and neither side is the whole.
+fn middle_snake(old: &[&str], new: &[&str]) -> (usize, usize) {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2;
+    // Furthest x reached on each diagonal k = x - y, forward from the start
+    // and backward from the end, offset so that k = -max - 1 is index 0
+    let offset = max + 1;
+    let mut forward = vec![0isize; 2 * max as usize + 3];
+    let mut backward = vec![0isize; 2 * max as usize + 3];
+    let at = |k: isize| (k + offset) as usize;
+
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[at(k)] = x;
+            // Backward paths of d - 1 edits end on diagonal delta - k
+            if odd && (delta - k).abs() < d && x + backward[at(delta - k)] >= n {
+                return (x as usize, y as usize);
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[(n - x - 1) as usize] == new[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[at(k)] = x;
+            if !odd && (delta - k).abs() <= d && x + forward[at(delta - k)] >= n {
+                return ((n - x) as usize, (m - y) as usize);
+            }
+        }
+    }
+    unreachable!("the paths meet within (n + m) / 2 edits each")
+}
@@ -0,0 +1,132 @@
+//! String interning for token lexemes
+//!
+//! Identifiers, keywords and operators repeat constantly in a source file. The
+//! lexer interns them into a [`StringTable`] so every occurrence of a name shares
+//! one allocation, and a token only holds a reference-counted [`Lexeme`].
+
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// The text of a token, shared with every other token spelled the same way
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Lexeme(Arc<str>);
+
+impl Lexeme {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Lexeme {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Lexeme {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Lexeme {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Lexeme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Lexeme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl From<&str> for Lexeme {
+    fn from(text: &str) -> Self {
+        Self(Arc::from(text))
+    }
+}
+
+impl From<String> for Lexeme {
+    fn from(text: String) -> Self {
+        Self(Arc::from(text))
+    }
+}
+
+impl From<Lexeme> for String {
+    fn from(lexeme: Lexeme) -> Self {
+        lexeme.0.to_string()
+    }
+}
+
+impl PartialEq<str> for Lexeme {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Lexeme {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Lexeme {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl PartialEq<Lexeme> for &str {
+    fn eq(&self, other: &Lexeme) -> bool {
+        *self == &*other.0
+    }
+}
+
+impl PartialEq<Lexeme> for String {
+    fn eq(&self, other: &Lexeme) -> bool {
+        **self == *other.0
+    }
+}
+
+/// Deduplicating store of lexemes
+#[derive(Debug, Default)]
+pub struct StringTable {
+    strings: HashSet<Lexeme>,
+}
+
+impl StringTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The shared lexeme for `text`, added to the table on first use
+    pub fn intern(&mut self, text: &str) -> Lexeme {
+        if let Some(lexeme) = self.strings.get(text) {
+            return lexeme.clone();
+        }
+        let lexeme = Lexeme::from(text);
+        self.strings.insert(lexeme.clone());
+        lexeme
+    }
+
+    /// Number of distinct strings interned so far
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
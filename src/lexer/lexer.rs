@@ -1,6 +1,7 @@
 //! Lexer implementation for the Bulu language
 
 use crate::error::{BuluError, Result};
+use super::interner::StringTable;
 use super::token::{Token, TokenType, Literal, Position};
 use std::collections::HashMap;
 
@@ -12,6 +13,12 @@ pub struct Lexer {
     column: usize,
     keywords: HashMap<String, TokenType>,
     file_path: Option<String>,
+    /// Identifier, keyword and operator lexemes shared between tokens
+    strings: StringTable,
+    /// Set once the stream has yielded `Eof` or an error
+    finished: bool,
+    /// Where each `//` comment starts, with its text after the `//`
+    line_comments: Vec<(Position, String)>,
 }
 
 impl Lexer {
@@ -84,6 +91,9 @@ impl Lexer {
             column: 1,
             keywords,
             file_path: None,
+            strings: StringTable::new(),
+            finished: false,
+            line_comments: Vec::new(),
         }
     }
 
@@ -96,22 +106,17 @@ impl Lexer {
 
     /// Tokenize the entire input and return a vector of tokens
     pub fn tokenize(&mut self) -> Result<Vec<Token>> {
-        let mut tokens = Vec::new();
-        
-        while !self.is_at_end() {
-            if let Some(token) = self.next_token()? {
-                tokens.push(token);
-            }
-        }
-        
-        tokens.push(Token::new(
-            TokenType::Eof,
-            "".to_string(),
-            None,
-            self.current_position(),
-        ));
-        
-        Ok(tokens)
+        self.collect()
+    }
+
+    /// Lexemes interned so far
+    pub fn string_table(&self) -> &StringTable {
+        &self.strings
+    }
+
+    /// The `//` comments lexed so far, with the position of their `//`
+    pub fn line_comments(&self) -> &[(Position, String)] {
+        &self.line_comments
     }
 
     /// Get the next token from the input
@@ -137,6 +142,7 @@ impl Lexer {
             ';' => self.make_token(TokenType::Semicolon, start_pos),
             ':' => self.make_token(TokenType::Colon, start_pos),
             '?' => self.make_token(TokenType::Question, start_pos),
+            '@' => self.make_token(TokenType::At, start_pos),
             '~' => self.make_token(TokenType::Tilde, start_pos),
             '^' => self.make_token(TokenType::Caret, start_pos),
             '&' => {
@@ -189,7 +195,7 @@ impl Lexer {
                 if self.match_char('=') {
                     self.make_token(TokenType::SlashAssign, start_pos)
                 } else if self.match_char('/') {
-                    self.line_comment()?;
+                    self.line_comment(start_pos)?;
                     return self.next_token();
                 } else if self.match_char('*') {
                     // Check if it's a documentation comment (/**)
@@ -262,6 +268,14 @@ impl Lexer {
             _ => {
                 if ch.is_ascii_digit() {
                     self.number_literal(start_pos)?
+                } else if ch == 'r' && self.raw_string_hashes(0).is_some() {
+                    self.raw_string_literal(start_pos, false)?
+                } else if ch == 'b' && self.peek() == '"' {
+                    self.advance(); // consume opening quote
+                    self.byte_string_literal(start_pos)?
+                } else if ch == 'b' && self.peek() == 'r' && self.raw_string_hashes(1).is_some() {
+                    self.advance(); // consume 'r'
+                    self.raw_string_literal(start_pos, true)?
                 } else if ch.is_alphabetic() || ch == '_' {
                     self.identifier_or_keyword(start_pos)
                 } else {
@@ -330,19 +344,21 @@ impl Lexer {
         }
     }
 
-    fn make_token(&self, token_type: TokenType, position: Position) -> Token {
+    fn make_token(&mut self, token_type: TokenType, position: Position) -> Token {
         let lexeme = match token_type {
-            TokenType::Newline => "\n".to_string(),
-            _ => token_type.to_string(),
+            TokenType::Newline => self.strings.intern("\n"),
+            _ => self.strings.intern(&token_type.to_string()),
         };
         
         Token::new(token_type, lexeme, None, position)
     }
 
-    fn line_comment(&mut self) -> Result<()> {
+    fn line_comment(&mut self, start_pos: Position) -> Result<()> {
+        let mut text = String::new();
         while self.peek() != '\n' && !self.is_at_end() {
-            self.advance();
+            text.push(self.advance());
         }
+        self.line_comments.push((start_pos, text));
         Ok(())
     }
 
@@ -460,6 +476,167 @@ impl Lexer {
         ))
     }
 
+    /// If a raw string opens `offset` characters ahead (`"`, `#"`, `##"`...),
+    /// the number of `#` delimiting it
+    fn raw_string_hashes(&self, offset: usize) -> Option<usize> {
+        let start = self.position + offset;
+        let hashes = self.input[start.min(self.input.len())..]
+            .iter()
+            .take_while(|&&c| c == '#')
+            .count();
+        (self.input.get(start + hashes) == Some(&'"')).then_some(hashes)
+    }
+
+    /// Raw string `r"..."` or `r#"..."#` (the `r`, and any `b`, already consumed):
+    /// no escapes, and as many `#` as needed to embed `"` in the contents
+    fn raw_string_literal(&mut self, start_pos: Position, is_bytes: bool) -> Result<Token> {
+        let hashes = self.raw_string_hashes(0).unwrap_or(0);
+        for _ in 0..=hashes {
+            self.advance(); // consume delimiters and opening quote
+        }
+
+        let mut value = String::new();
+        loop {
+            if self.is_at_end() {
+                return Err(BuluError::LexError { token: None, stack: Vec::new(),
+                    message: "Unterminated raw string".to_string(),
+                    file: None,
+                    line: start_pos.line,
+                    column: start_pos.column,
+                });
+            }
+
+            if self.peek() == '"'
+                && (1..=hashes).all(|i| self.input.get(self.position + i) == Some(&'#'))
+            {
+                for _ in 0..=hashes {
+                    self.advance(); // consume closing quote and delimiters
+                }
+                break;
+            }
+
+            if self.peek() == '\n' {
+                self.line += 1;
+                self.column = 1;
+            }
+            value.push(self.advance());
+        }
+
+        let lexeme: String = self.input[start_pos.offset..self.position].iter().collect();
+        if !is_bytes {
+            return Ok(Token::new(
+                TokenType::StringLiteral,
+                lexeme,
+                Some(Literal::String(value)),
+                start_pos,
+            ));
+        }
+
+        if let Some(c) = value.chars().find(|c| !c.is_ascii()) {
+            return Err(BuluError::LexError { token: None, stack: Vec::new(),
+                message: format!("Non-ASCII character '{}' in raw byte string", c),
+                file: None,
+                line: start_pos.line,
+                column: start_pos.column,
+            });
+        }
+        Ok(Token::new(
+            TokenType::ByteStringLiteral,
+            lexeme,
+            Some(Literal::ByteString(value.into_bytes())),
+            start_pos,
+        ))
+    }
+
+    /// Byte string `b"..."` (the opening quote already consumed): ASCII text plus
+    /// the string escapes and `\xNN` for arbitrary bytes
+    fn byte_string_literal(&mut self, start_pos: Position) -> Result<Token> {
+        let mut bytes = Vec::new();
+
+        while self.peek() != '"' && !self.is_at_end() {
+            if self.peek() == '\n' {
+                self.line += 1;
+                self.column = 1;
+            }
+
+            if self.peek() == '\\' {
+                self.advance(); // consume backslash
+                let byte = match self.peek() {
+                    'n' => b'\n',
+                    't' => b'\t',
+                    'r' => b'\r',
+                    '\\' => b'\\',
+                    '"' => b'"',
+                    '\'' => b'\'',
+                    '0' => b'\0',
+                    'x' => {
+                        let digits: String = self.input[self.position + 1..]
+                            .iter()
+                            .take(2)
+                            .collect();
+                        match u8::from_str_radix(&digits, 16) {
+                            Ok(byte) if digits.len() == 2 => {
+                                // Consume 'x' and the first digit; the second is consumed below
+                                self.advance();
+                                self.advance();
+                                byte
+                            }
+                            _ => {
+                                return Err(BuluError::LexError { token: None, stack: Vec::new(),
+                                    message: "Invalid byte escape: expected '\\x' followed by two hex digits".to_string(),
+                                    file: None,
+                                    line: self.line,
+                                    column: self.column,
+                                });
+                            }
+                        }
+                    }
+                    _ => {
+                        return Err(BuluError::LexError { token: None, stack: Vec::new(),
+                            message: format!("Invalid escape sequence '\\{}'", self.peek()),
+                            file: None,
+                            line: self.line,
+                            column: self.column,
+                        });
+                    }
+                };
+                self.advance();
+                bytes.push(byte);
+            } else if self.peek().is_ascii() {
+                bytes.push(self.advance() as u8);
+            } else {
+                return Err(BuluError::LexError { token: None, stack: Vec::new(),
+                    message: format!(
+                        "Non-ASCII character '{}' in byte string; use '\\x' escapes",
+                        self.peek()
+                    ),
+                    file: None,
+                    line: self.line,
+                    column: self.column,
+                });
+            }
+        }
+
+        if self.is_at_end() {
+            return Err(BuluError::LexError { token: None, stack: Vec::new(),
+                message: "Unterminated byte string".to_string(),
+                file: None,
+                line: start_pos.line,
+                column: start_pos.column,
+            });
+        }
+
+        self.advance(); // consume closing quote
+
+        let lexeme: String = self.input[start_pos.offset..self.position].iter().collect();
+        Ok(Token::new(
+            TokenType::ByteStringLiteral,
+            lexeme,
+            Some(Literal::ByteString(bytes)),
+            start_pos,
+        ))
+    }
+
     fn char_literal(&mut self, start_pos: Position) -> Result<Token> {
         if self.is_at_end() {
             return Err(BuluError::LexError { token: None, stack: Vec::new(),
@@ -726,6 +903,31 @@ impl Lexer {
             _ => None,
         };
         
-        Token::new(token_type, value, literal, start_pos)
+        Token::new(token_type, self.strings.intern(&value), literal, start_pos)
+    }
+}
+
+/// Tokens are produced on demand, so a caller can consume the source without
+/// materializing every token at once. The stream ends with `Eof`, or stops
+/// after the first error.
+impl Iterator for Lexer {
+    type Item = Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        while !self.is_at_end() {
+            match self.next_token() {
+                Ok(Some(token)) => return Some(Ok(token)),
+                Ok(None) => {}
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        self.finished = true;
+        Some(Ok(Token::new(TokenType::Eof, self.strings.intern(""), None, self.current_position())))
     }
 }
\ No newline at end of file
@@ -5,6 +5,8 @@
 
 pub mod token;
 pub mod lexer;
+pub mod interner;
 
 pub use token::{Token, TokenType, Literal};
-pub use lexer::Lexer;
\ No newline at end of file
+pub use lexer::Lexer;
+pub use interner::{Lexeme, StringTable};
\ No newline at end of file
@@ -1,5 +1,6 @@
 //! Token definitions for the Bulu language
 
+use super::interner::Lexeme;
 use std::fmt;
 
 /// Position information for tokens
@@ -24,7 +25,7 @@ impl Position {
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub token_type: TokenType,
-    pub lexeme: String,
+    pub lexeme: Lexeme,
     pub literal: Option<Literal>,
     pub position: Position,
 }
@@ -32,13 +33,13 @@ pub struct Token {
 impl Token {
     pub fn new(
         token_type: TokenType,
-        lexeme: String,
+        lexeme: impl Into<Lexeme>,
         literal: Option<Literal>,
         position: Position,
     ) -> Self {
         Self {
             token_type,
-            lexeme,
+            lexeme: lexeme.into(),
             literal,
             position,
         }
@@ -51,6 +52,7 @@ pub enum Literal {
     Integer(i64),
     Float(f64),
     String(String),
+    ByteString(Vec<u8>),
     Char(char),
     Boolean(bool),
 }
@@ -117,6 +119,7 @@ pub enum TokenType {
     IntegerLiteral,
     FloatLiteral,
     StringLiteral,
+    ByteStringLiteral,
     CharLiteral,
 
     // Operators
@@ -163,6 +166,7 @@ pub enum TokenType {
     DotDotLess,   // ..<
     DotDotDot,    // ...
     Question,     // ?
+    At,           // @
 
     // Special
     Newline,
@@ -219,6 +223,7 @@ impl fmt::Display for TokenType {
             TokenType::IntegerLiteral => "integer",
             TokenType::FloatLiteral => "float",
             TokenType::StringLiteral => "string",
+            TokenType::ByteStringLiteral => "byte string",
             TokenType::CharLiteral => "char",
             TokenType::Plus => "+",
             TokenType::Minus => "-",
@@ -261,6 +266,7 @@ impl fmt::Display for TokenType {
             TokenType::DotDotLess => "..<",
             TokenType::DotDotDot => "...",
             TokenType::Question => "?",
+            TokenType::At => "@",
             TokenType::Newline => "newline",
             TokenType::Eof => "EOF",
             TokenType::Comment => "comment",
@@ -15,11 +15,16 @@ pub mod types;
 
 pub mod std;
 pub mod project;
+pub mod config;
+pub mod terminal;
+pub mod logging;
 pub mod build;
 pub mod testing;
 pub mod formatter;
 pub mod linter;
 pub mod docs;
+pub mod todo;
+pub mod changelog;
 pub mod package;
 pub mod lsp;
 
@@ -0,0 +1,635 @@
+//! Code linter for Bulu source files
+
+pub mod rule;
+pub mod rules;
+pub mod usage;
+
+pub use rule::{LintContext, LintRun, Rule, RuleRegistry, Suppressions};
+
+use crate::project::Project;
+use crate::{BuluError, Result};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Lint severity levels
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Error,
+}
+
+/// A single lint issue
+#[derive(Debug, Clone)]
+pub struct LintIssue {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub level: LintLevel,
+    pub rule: String,
+    pub message: String,
+    pub suggestion: Option<String>,
+    /// Edits that resolve the issue automatically
+    pub fix: Option<LintFix>,
+}
+
+/// An automatic fix for a lint issue
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintFix {
+    pub description: String,
+    pub edits: Vec<LintEdit>,
+}
+
+/// Replacement of a source range. Lines and columns are 1-based and count
+/// characters; the end position is exclusive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintEdit {
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub new_text: String,
+}
+
+/// Linting options
+#[derive(Debug, Clone, Default)]
+pub struct LintOptions {
+    pub fix: bool,
+    pub max_warnings: Option<usize>,
+    pub rules: LintRules,
+    /// Which issues are promoted to errors, see [`LintPolicy`]
+    pub policy: LintPolicy,
+}
+
+/// Promotion of warnings to errors, e.g. from `--deny warnings` or
+/// `--deny unused-import`. A denied rule reports errors whatever its
+/// configured level, including `allow`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LintPolicy {
+    /// Every warning is an error
+    pub deny_warnings: bool,
+    /// IDs of the rules whose issues are errors
+    pub deny: Vec<String>,
+}
+
+impl LintPolicy {
+    /// Policy denying each of `lints`, see [`LintPolicy::deny`]
+    pub fn from_deny<I, S>(lints: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut policy = Self::default();
+        for lint in lints {
+            policy.deny(lint.as_ref());
+        }
+        policy
+    }
+
+    /// Deny `warnings`, or a rule by ID. Rule IDs may be written with
+    /// underscores, as in `unused_import`.
+    pub fn deny(&mut self, lint: &str) {
+        if lint == "warnings" {
+            self.deny_warnings = true;
+            return;
+        }
+        let id = lint.replace('_', "-");
+        if !self.deny.contains(&id) {
+            self.deny.push(id);
+        }
+    }
+
+    /// Whether the policy changes no severity
+    pub fn is_empty(&self) -> bool {
+        !self.deny_warnings && self.deny.is_empty()
+    }
+
+    /// Check that every denied rule exists
+    pub fn validate(&self, registry: &RuleRegistry) -> Result<()> {
+        match self.deny.iter().find(|id| registry.get(id).is_none()) {
+            Some(id) => Err(BuluError::Other(format!(
+                "Unknown lint '{}', expected 'warnings' or one of: {}",
+                id,
+                registry.rules().map(|rule| rule.id()).collect::<Vec<_>>().join(", ")
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Severity of the rule `rule` when it is configured at `level`
+    pub fn level(&self, rule: &str, level: LintLevel) -> LintLevel {
+        if self.deny.iter().any(|id| id == rule) || (self.deny_warnings && level == LintLevel::Warn) {
+            LintLevel::Error
+        } else {
+            level
+        }
+    }
+}
+
+/// Configurable lint rules that can be loaded from .langlint.toml
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintRules {
+    #[serde(default = "default_unused_variables")]
+    pub unused_variables: LintLevel,
+    #[serde(default = "default_unused_imports")]
+    pub unused_imports: LintLevel,
+    #[serde(default = "default_unused_functions")]
+    pub unused_functions: LintLevel,
+    #[serde(default = "default_unreachable_code")]
+    pub unreachable_code: LintLevel,
+    #[serde(default = "default_missing_docs")]
+    pub missing_docs: LintLevel,
+    #[serde(default = "default_long_lines")]
+    pub long_lines: LintLevel,
+    #[serde(default = "default_naming_convention")]
+    pub naming_convention: LintLevel,
+    #[serde(default = "default_complexity")]
+    pub complexity: LintLevel,
+    #[serde(default = "default_performance")]
+    pub performance: LintLevel,
+    #[serde(default = "default_security")]
+    pub security: LintLevel,
+    #[serde(default = "default_max_line_length")]
+    pub max_line_length: usize,
+    #[serde(default = "default_max_complexity")]
+    pub max_complexity: usize,
+    /// Per-rule severities keyed by rule ID, from the `[rules]` table.
+    /// These take precedence over the category levels above.
+    #[serde(default, rename = "rules", skip_serializing_if = "HashMap::is_empty")]
+    pub overrides: HashMap<String, LintLevel>,
+}
+
+// Default value functions for serde
+fn default_unused_variables() -> LintLevel {
+    LintLevel::Warn
+}
+fn default_unused_imports() -> LintLevel {
+    LintLevel::Warn
+}
+fn default_unused_functions() -> LintLevel {
+    LintLevel::Warn
+}
+fn default_unreachable_code() -> LintLevel {
+    LintLevel::Warn
+}
+fn default_missing_docs() -> LintLevel {
+    LintLevel::Allow
+}
+fn default_long_lines() -> LintLevel {
+    LintLevel::Warn
+}
+fn default_naming_convention() -> LintLevel {
+    LintLevel::Warn
+}
+fn default_complexity() -> LintLevel {
+    LintLevel::Warn
+}
+fn default_performance() -> LintLevel {
+    LintLevel::Warn
+}
+fn default_security() -> LintLevel {
+    LintLevel::Error
+}
+fn default_max_line_length() -> usize {
+    100
+}
+fn default_max_complexity() -> usize {
+    4
+}
+
+impl Default for LintRules {
+    fn default() -> Self {
+        Self {
+            unused_variables: default_unused_variables(),
+            unused_imports: default_unused_imports(),
+            unused_functions: default_unused_functions(),
+            unreachable_code: default_unreachable_code(),
+            missing_docs: default_missing_docs(),
+            long_lines: default_long_lines(),
+            naming_convention: default_naming_convention(),
+            complexity: default_complexity(),
+            performance: default_performance(),
+            security: default_security(),
+            max_line_length: default_max_line_length(),
+            max_complexity: default_max_complexity(),
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+/// Lint results for the entire project
+#[derive(Debug)]
+pub struct LintResult {
+    pub files_checked: usize,
+    pub issues: Vec<LintIssue>,
+    pub errors: usize,
+    pub warnings: usize,
+    pub fixed: usize,
+    /// Errors that are only errors because the policy denies them
+    pub denied: usize,
+    /// Issues of rules at the `allow` level
+    pub allowed: usize,
+    /// Issues silenced by `// bulu-lint: allow(...)` comments
+    pub suppressed: usize,
+}
+
+/// Code linter for Bulu projects
+pub struct Linter {
+    project: Project,
+    options: LintOptions,
+    registry: RuleRegistry,
+}
+
+impl Linter {
+    pub fn new(project: Project, options: LintOptions) -> Self {
+        Self {
+            project,
+            options,
+            registry: RuleRegistry::with_builtin_rules(),
+        }
+    }
+
+    /// Add a custom rule, replacing any built-in rule with the same ID
+    pub fn register_rule(&mut self, rule: Box<dyn Rule>) {
+        self.registry.register(rule);
+    }
+
+    /// The rules this linter runs
+    pub fn registry(&self) -> &RuleRegistry {
+        &self.registry
+    }
+
+    /// Lint all source files in the project
+    pub fn lint_project(&self) -> Result<LintResult> {
+        self.options.policy.validate(&self.registry)?;
+        info!(
+            "{} Linting project '{}'...",
+            "Linting".green().bold(),
+            self.project.config.package.name
+        );
+
+        let source_files = self.project.source_files()?;
+
+        if source_files.is_empty() {
+            warn!("{} No source files found", "Warning".yellow().bold());
+            return Ok(LintResult {
+                files_checked: 0,
+                issues: Vec::new(),
+                errors: 0,
+                warnings: 0,
+                fixed: 0,
+                denied: 0,
+                allowed: 0,
+                suppressed: 0,
+            });
+        }
+
+        let mut all_issues = Vec::new();
+        let mut fixed_count = 0;
+        let mut counts = LintRun::default();
+
+        for source_file in &source_files {
+            debug!("{} {}", "Checking".cyan().bold(), source_file.display());
+
+            let (run, fixed) = self.lint_source(source_file)?;
+            all_issues.extend(run.issues);
+            fixed_count += fixed;
+            counts.denied += run.denied;
+            counts.allowed += run.allowed;
+            counts.suppressed += run.suppressed;
+        }
+
+        // Sort issues by severity and location
+        all_issues.sort_by(|a, b| {
+            a.level
+                .cmp(&b.level)
+                .then(a.file.cmp(&b.file))
+                .then(a.line.cmp(&b.line))
+                .then(a.column.cmp(&b.column))
+        });
+
+        let errors = all_issues
+            .iter()
+            .filter(|i| i.level == LintLevel::Error)
+            .count();
+        let warnings = all_issues
+            .iter()
+            .filter(|i| i.level == LintLevel::Warn)
+            .count();
+
+        // Print issues
+        for issue in &all_issues {
+            self.print_issue(issue);
+        }
+
+        let result = LintResult {
+            files_checked: source_files.len(),
+            issues: all_issues,
+            errors,
+            warnings,
+            fixed: fixed_count,
+            denied: counts.denied,
+            allowed: counts.allowed,
+            suppressed: counts.suppressed,
+        };
+
+        // Print summary
+        self.print_summary(&result);
+
+        Ok(result)
+    }
+
+    /// Lint a single source file
+    pub fn lint_file(&self, file_path: &Path) -> Result<(Vec<LintIssue>, usize)> {
+        let (run, fixed) = self.lint_source(file_path)?;
+        Ok((run.issues, fixed))
+    }
+
+    /// Lint a single source file under the policy, counting the issues that
+    /// are not reported
+    fn lint_source(&self, file_path: &Path) -> Result<(LintRun, usize)> {
+        let content = fs::read_to_string(file_path)
+            .map_err(|e| BuluError::Other(format!("Failed to read file: {}", e)))?;
+
+        let mut fixed_count = 0;
+
+        let mut run = self
+            .registry
+            .run_with_policy(file_path, &content, &self.options.rules, &self.options.policy);
+        let issues = &mut run.issues;
+
+        // Apply fixes if requested; fixed issues are no longer reported
+        if self.options.fix {
+            let (fixed_source, applied) = apply_fixes(&content, issues);
+            if !applied.is_empty() {
+                fs::write(file_path, fixed_source)
+                    .map_err(|e| BuluError::Other(format!("Failed to write file: {}", e)))?;
+                fixed_count = applied.len();
+                let mut index = 0;
+                issues.retain(|_| {
+                    index += 1;
+                    !applied.contains(&(index - 1))
+                });
+            }
+        }
+
+        Ok((run, fixed_count))
+    }
+
+    /// Print a single lint issue
+    fn print_issue(&self, issue: &LintIssue) {
+        let level_str = match issue.level {
+            LintLevel::Error => "error".red().bold(),
+            LintLevel::Warn => "warning".yellow().bold(),
+            LintLevel::Allow => return, // Don't print allowed issues
+        };
+
+        println!(
+            "{}:{}:{}: {}: {} [{}]",
+            issue.file.display(),
+            issue.line,
+            issue.column,
+            level_str,
+            issue.message,
+            issue.rule.cyan()
+        );
+
+        if let Some(suggestion) = &issue.suggestion {
+            println!("  {} {}", "help:".cyan().bold(), suggestion);
+        }
+    }
+
+    /// Print summary of lint results
+    fn print_summary(&self, result: &LintResult) {
+        println!();
+
+        let LintResult { files_checked, errors, warnings, fixed, denied, allowed, suppressed, .. } = *result;
+        let mut summary = format!("Checked {} files", files_checked);
+        if errors == 0 && warnings == 0 {
+            summary.push_str(", no issues found");
+        }
+
+        if errors > 0 {
+            summary.push_str(&format!(", {} errors", errors));
+            if denied > 0 {
+                summary.push_str(&format!(" ({} denied)", denied));
+            }
+        }
+
+        if warnings > 0 {
+            summary.push_str(&format!(", {} warnings", warnings));
+        }
+
+        if fixed > 0 {
+            summary.push_str(&format!(", {} fixed", fixed));
+        }
+
+        if allowed > 0 {
+            summary.push_str(&format!(", {} allowed", allowed));
+        }
+
+        if suppressed > 0 {
+            summary.push_str(&format!(", {} suppressed", suppressed));
+        }
+
+        if errors > 0 {
+            println!("{} {}", "Failed".red().bold(), summary);
+        } else if warnings > 0 {
+            println!("{} {}", "Finished".yellow().bold(), summary);
+        } else {
+            println!("{} {}", "Finished".green().bold(), summary);
+        }
+    }
+}
+
+/// Load linting configuration from the `[lint]` section of the layered
+/// configuration, which includes .langlint.toml and the `[lint]` section of
+/// lang.toml. Settings in lang.toml take precedence over .langlint.toml.
+pub fn load_lint_config(project_root: &Path) -> Result<LintOptions> {
+    let config = crate::config::Config::load(Some(project_root))?;
+    Ok(LintOptions {
+        rules: config.lint_rules()?,
+        ..LintOptions::default()
+    })
+}
+
+/// Create a default .langlint.toml configuration file
+pub fn create_default_lint_config(project_root: &Path) -> Result<()> {
+    let config_path = project_root.join(".langlint.toml");
+
+    if config_path.exists() {
+        return Err(BuluError::Other(
+            ".langlint.toml already exists. Remove it first if you want to recreate it."
+                .to_string(),
+        ));
+    }
+
+    let default_rules = LintRules::default();
+    let _config_content = toml::to_string_pretty(&default_rules)
+        .map_err(|e| BuluError::Other(format!("Failed to serialize default config: {}", e)))?;
+
+    // Add comments to make the config file more user-friendly
+    let commented_config = format!(
+        r#"# Bulu Language Linter Configuration
+# This file configures how the 'lang lint' command checks your code.
+
+# Unused variable detection: "allow", "warn", or "error"
+unused_variables = "{}"
+
+# Unused import detection: "allow", "warn", or "error"
+unused_imports = "{}"
+
+# Unused private function and struct detection (dead-code): "allow", "warn", or "error"
+unused_functions = "{}"
+
+# Unreachable code detection: "allow", "warn", or "error"
+unreachable_code = "{}"
+
+# Missing documentation detection: "allow", "warn", or "error"
+missing_docs = "{}"
+
+# Long line detection: "allow", "warn", or "error"
+long_lines = "{}"
+
+# Naming convention checking: "allow", "warn", or "error"
+naming_convention = "{}"
+
+# Code complexity checking: "allow", "warn", or "error"
+complexity = "{}"
+
+# Performance issue detection: "allow", "warn", or "error"
+performance = "{}"
+
+# Security issue detection: "allow", "warn", or "error"
+security = "{}"
+
+# Maximum line length before warning
+max_line_length = {}
+
+# Maximum nesting level before warning
+max_complexity = {}
+
+# Per-rule severities keyed by rule ID, overriding the categories above.
+# Rule IDs are shown in brackets after each lint message, and a single
+# occurrence can be silenced with a `// bulu-lint: allow(rule-id)` comment.
+# The same settings can live in a [lint] section of lang.toml instead.
+[rules]
+# long-line = "error"
+# security-hardcoded-secret = "allow"
+"#,
+        format!("{:?}", default_rules.unused_variables).to_lowercase(),
+        format!("{:?}", default_rules.unused_imports).to_lowercase(),
+        format!("{:?}", default_rules.unused_functions).to_lowercase(),
+        format!("{:?}", default_rules.unreachable_code).to_lowercase(),
+        format!("{:?}", default_rules.missing_docs).to_lowercase(),
+        format!("{:?}", default_rules.long_lines).to_lowercase(),
+        format!("{:?}", default_rules.naming_convention).to_lowercase(),
+        format!("{:?}", default_rules.complexity).to_lowercase(),
+        format!("{:?}", default_rules.performance).to_lowercase(),
+        format!("{:?}", default_rules.security).to_lowercase(),
+        default_rules.max_line_length,
+        default_rules.max_complexity,
+    );
+
+    fs::write(&config_path, commented_config)
+        .map_err(|e| BuluError::Other(format!("Failed to write .langlint.toml: {}", e)))?;
+
+    info!("Created default .langlint.toml configuration file");
+    Ok(())
+}
+
+/// Validate a lint configuration
+pub fn validate_lint_config(rules: &LintRules) -> Result<()> {
+    if rules.max_line_length < 40 {
+        return Err(BuluError::Other(
+            "max_line_length should be at least 40 characters".to_string(),
+        ));
+    }
+
+    if rules.max_line_length > 500 {
+        return Err(BuluError::Other(
+            "max_line_length should not exceed 500 characters".to_string(),
+        ));
+    }
+
+    if rules.max_complexity == 0 {
+        return Err(BuluError::Other(
+            "max_complexity must be greater than 0".to_string(),
+        ));
+    }
+
+    if rules.max_complexity > 20 {
+        return Err(BuluError::Other(
+            "max_complexity should not exceed 20 levels".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Apply the fixes attached to `issues` to `content`. A fix whose edits
+/// overlap an earlier fix is skipped. Returns the new source and the
+/// indices of the issues whose fixes were applied.
+pub fn apply_fixes(content: &str, issues: &[LintIssue]) -> (String, Vec<usize>) {
+    let chars: Vec<char> = content.chars().collect();
+
+    // Char offset where each 1-based line starts
+    let mut line_starts = vec![0];
+    for (offset, ch) in chars.iter().enumerate() {
+        if *ch == '\n' {
+            line_starts.push(offset + 1);
+        }
+    }
+    let to_offset = |line: usize, column: usize| -> usize {
+        match line_starts.get(line.saturating_sub(1)) {
+            Some(start) => (start + column.saturating_sub(1)).min(chars.len()),
+            None => chars.len(),
+        }
+    };
+
+    let mut applied = Vec::new();
+    let mut ranges: Vec<(usize, usize, &str)> = Vec::new();
+    for (index, issue) in issues.iter().enumerate() {
+        let fix = match &issue.fix {
+            Some(fix) if !fix.edits.is_empty() => fix,
+            _ => continue,
+        };
+        let edits: Vec<(usize, usize, &str)> = fix
+            .edits
+            .iter()
+            .map(|edit| {
+                (
+                    to_offset(edit.start_line, edit.start_column),
+                    to_offset(edit.end_line, edit.end_column),
+                    edit.new_text.as_str(),
+                )
+            })
+            .collect();
+        let overlaps = edits.iter().any(|(start, end, _)| {
+            ranges
+                .iter()
+                .any(|(other_start, other_end, _)| start < other_end && other_start < end || start == other_start)
+        });
+        if !overlaps {
+            ranges.extend(edits);
+            applied.push(index);
+        }
+    }
+
+    ranges.sort_by_key(|(start, _, _)| *start);
+    let mut result = String::with_capacity(content.len());
+    let mut cursor = 0;
+    for (start, end, new_text) in ranges {
+        result.extend(&chars[cursor..start]);
+        result.push_str(new_text);
+        cursor = end.max(start);
+    }
+    result.extend(&chars[cursor..]);
+
+    (result, applied)
+}
@@ -0,0 +1,551 @@
+//! Rule registry and AST walker for the linter
+//!
+//! Each lint is a [`Rule`] with a stable ID. Rules can inspect the raw source
+//! text, and receive hooks for every block, statement and expression of the
+//! parsed program. The registry decides the severity of each rule from the
+//! configuration and drops issues suppressed with
+//! `// bulu-lint: allow(rule_id)` comments.
+
+use super::usage::{self, UsageReport};
+use super::{LintFix, LintIssue, LintLevel, LintPolicy, LintRules};
+use crate::ast::*;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::rc::Rc;
+
+/// A single lint check
+pub trait Rule: Send + Sync {
+    /// Stable identifier used in diagnostics, configuration and suppressions
+    fn id(&self) -> &'static str;
+
+    /// Short description of what the rule checks
+    fn description(&self) -> &'static str;
+
+    /// Severity used when the `[rules]` table does not mention this rule
+    fn default_level(&self, config: &LintRules) -> LintLevel;
+
+    /// Inspect the raw source text
+    fn check_source(&self, _ctx: &mut LintContext) {}
+
+    /// Called once with the whole parsed program, before the per-node hooks
+    fn check_program(&self, _program: &Program, _ctx: &mut LintContext) {}
+
+    /// Called for the top-level statements and every block of statements
+    fn check_block(&self, _statements: &[Statement], _ctx: &mut LintContext) {}
+
+    /// Called for every statement in the program
+    fn check_statement(&self, _statement: &Statement, _ctx: &mut LintContext) {}
+
+    /// Called for every expression in the program
+    fn check_expression(&self, _expression: &Expression, _ctx: &mut LintContext) {}
+}
+
+/// State shared with rules while a file is being linted
+pub struct LintContext<'a> {
+    pub file: &'a Path,
+    pub content: &'a str,
+    pub config: &'a LintRules,
+    rule: &'static str,
+    level: LintLevel,
+    issues: Vec<LintIssue>,
+    /// Usage analysis of the program, shared by the unused-code rules
+    usage: Option<Rc<UsageReport>>,
+}
+
+impl<'a> LintContext<'a> {
+    fn new(file: &'a Path, content: &'a str, config: &'a LintRules) -> Self {
+        Self {
+            file,
+            content,
+            config,
+            rule: "",
+            level: LintLevel::Allow,
+            issues: Vec::new(),
+            usage: None,
+        }
+    }
+
+    /// Usage analysis of `program`, computed on first use and shared by
+    /// every rule that runs on this file
+    pub fn usage(&mut self, program: &Program) -> Rc<UsageReport> {
+        let file = self.file;
+        self.usage
+            .get_or_insert_with(|| Rc::new(usage::analyze(program, file)))
+            .clone()
+    }
+
+    /// Select the rule that subsequent reports are attributed to
+    fn begin(&mut self, rule: &'static str, level: LintLevel) {
+        self.rule = rule;
+        self.level = level;
+    }
+
+    /// Report an issue at a 1-based line and column for the active rule
+    pub fn report(
+        &mut self,
+        line: usize,
+        column: usize,
+        message: impl Into<String>,
+        suggestion: Option<String>,
+    ) {
+        self.push(line, column, message.into(), suggestion, None);
+    }
+
+    /// Report an issue together with the edits that resolve it
+    pub fn report_with_fix(
+        &mut self,
+        line: usize,
+        column: usize,
+        message: impl Into<String>,
+        fix: LintFix,
+    ) {
+        let suggestion = Some(fix.description.clone());
+        self.push(line, column, message.into(), suggestion, Some(fix));
+    }
+
+    fn push(
+        &mut self,
+        line: usize,
+        column: usize,
+        message: String,
+        suggestion: Option<String>,
+        fix: Option<LintFix>,
+    ) {
+        self.issues.push(LintIssue {
+            file: self.file.to_path_buf(),
+            line,
+            column,
+            level: self.level.clone(),
+            rule: self.rule.to_string(),
+            message,
+            suggestion,
+            fix,
+        });
+    }
+}
+
+/// The set of rules the linter runs
+pub struct RuleRegistry {
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl RuleRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Create a registry with all built-in rules
+    pub fn with_builtin_rules() -> Self {
+        let mut registry = Self::new();
+        for rule in super::rules::builtin_rules() {
+            registry.register(rule);
+        }
+        registry
+    }
+
+    /// Add a rule, replacing any rule with the same ID
+    pub fn register(&mut self, rule: Box<dyn Rule>) {
+        self.rules.retain(|existing| existing.id() != rule.id());
+        self.rules.push(rule);
+    }
+
+    /// All registered rules, in registration order
+    pub fn rules(&self) -> impl Iterator<Item = &dyn Rule> {
+        self.rules.iter().map(|rule| rule.as_ref())
+    }
+
+    /// Look up a rule by ID
+    pub fn get(&self, id: &str) -> Option<&dyn Rule> {
+        self.rules().find(|rule| rule.id() == id)
+    }
+
+    /// Effective severity of a rule under the given configuration
+    pub fn level_for(&self, rule: &dyn Rule, config: &LintRules) -> LintLevel {
+        config
+            .overrides
+            .get(rule.id())
+            .cloned()
+            .unwrap_or_else(|| rule.default_level(config))
+    }
+
+    /// Run every enabled rule over a file's contents
+    pub fn run(&self, file: &Path, content: &str, config: &LintRules) -> Vec<LintIssue> {
+        self.run_rules(file, content, config, &LintPolicy::default(), false).issues
+    }
+
+    /// Run every rule over a file's contents, with severities adjusted by
+    /// `policy`. Rules at the `allow` level run too, so that the issues they
+    /// would have reported can be counted.
+    pub fn run_with_policy(&self, file: &Path, content: &str, config: &LintRules, policy: &LintPolicy) -> LintRun {
+        self.run_rules(file, content, config, policy, true)
+    }
+
+    fn run_rules(
+        &self,
+        file: &Path,
+        content: &str,
+        config: &LintRules,
+        policy: &LintPolicy,
+        count_allowed: bool,
+    ) -> LintRun {
+        let mut denied_rules = HashSet::new();
+        let enabled: Vec<(&dyn Rule, LintLevel)> = self
+            .rules()
+            .map(|rule| {
+                let configured = self.level_for(rule, config);
+                let level = policy.level(rule.id(), configured.clone());
+                if level == LintLevel::Error && configured != LintLevel::Error {
+                    denied_rules.insert(rule.id());
+                }
+                (rule, level)
+            })
+            .filter(|(_, level)| count_allowed || *level != LintLevel::Allow)
+            .collect();
+
+        if enabled.is_empty() {
+            return LintRun::default();
+        }
+
+        let mut ctx = LintContext::new(file, content, config);
+
+        for (rule, level) in &enabled {
+            ctx.begin(rule.id(), level.clone());
+            rule.check_source(&mut ctx);
+        }
+
+        // AST hooks only run for files that parse
+        if let Some(program) = parse_program(content) {
+            for (rule, level) in &enabled {
+                ctx.begin(rule.id(), level.clone());
+                rule.check_program(&program, &mut ctx);
+            }
+            let mut walker = Walker {
+                rules: &enabled,
+                ctx: &mut ctx,
+            };
+            walker.walk_block(&program.statements);
+        }
+
+        let suppressions = Suppressions::parse(content);
+        let mut run = LintRun::default();
+        for issue in ctx.issues {
+            if suppressions.is_suppressed(issue.line, &issue.rule) {
+                run.suppressed += 1;
+            } else if issue.level == LintLevel::Allow {
+                run.allowed += 1;
+            } else {
+                if denied_rules.contains(issue.rule.as_str()) {
+                    run.denied += 1;
+                }
+                run.issues.push(issue);
+            }
+        }
+        run
+    }
+}
+
+/// The issues reported for a file, and counts of the issues that were not
+/// reported or were promoted to errors
+#[derive(Debug, Default)]
+pub struct LintRun {
+    pub issues: Vec<LintIssue>,
+    /// Issues of rules at the `allow` level
+    pub allowed: usize,
+    /// Issues silenced by `// bulu-lint: allow(...)` comments
+    pub suppressed: usize,
+    /// Reported issues that are errors only because the policy denies them
+    pub denied: usize,
+}
+
+impl Default for RuleRegistry {
+    fn default() -> Self {
+        Self::with_builtin_rules()
+    }
+}
+
+fn parse_program(content: &str) -> Option<Program> {
+    let tokens = Lexer::new(content).tokenize().ok()?;
+    Parser::new(tokens).parse().ok()
+}
+
+/// Rule IDs allowed per line by `// bulu-lint: allow(...)` comments.
+/// A trailing comment applies to its own line, a comment on a line of its
+/// own applies to the next line. Comments are found by the lexer, so `//`
+/// inside a string is not one; in a file that does not lex, the comments
+/// after the error are not seen.
+#[derive(Debug, Default)]
+pub struct Suppressions {
+    lines: HashMap<usize, HashSet<String>>,
+}
+
+impl Suppressions {
+    pub fn parse(content: &str) -> Self {
+        let mut suppressions = Self::default();
+        let mut lexer = Lexer::new(content);
+        for token in lexer.by_ref() {
+            if token.is_err() {
+                break;
+            }
+        }
+
+        let lines: Vec<&str> = content.lines().collect();
+        for (position, comment) in lexer.line_comments() {
+            let rule_ids = match Self::parse_comment(comment) {
+                Some(rule_ids) => rule_ids,
+                None => continue,
+            };
+
+            let before = lines.get(position.line - 1).map_or("", |line| {
+                let end = line.char_indices().nth(position.column - 1).map_or(line.len(), |(i, _)| i);
+                &line[..end]
+            });
+            let own_line = before.trim().is_empty();
+            let target = if own_line { position.line + 1 } else { position.line };
+            suppressions.lines.entry(target).or_default().extend(rule_ids);
+        }
+
+        suppressions
+    }
+
+    /// Parse the text after `//`, e.g. ` bulu-lint: allow(long-line, unused-variable)`
+    fn parse_comment(comment: &str) -> Option<Vec<String>> {
+        let rest = comment.trim_start().strip_prefix("bulu-lint:")?;
+        let rest = rest.trim_start().strip_prefix("allow")?;
+        let rest = rest.trim_start().strip_prefix('(')?;
+        let end = rest.find(')')?;
+        Some(
+            rest[..end]
+                .split(',')
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty())
+                .collect(),
+        )
+    }
+
+    /// Whether issues of `rule` are allowed on the 1-based `line`
+    pub fn is_suppressed(&self, line: usize, rule: &str) -> bool {
+        self.lines
+            .get(&line)
+            .is_some_and(|rules| rules.contains(rule))
+    }
+}
+
+/// Depth-first traversal that calls each enabled rule's hooks
+struct Walker<'r, 'c, 'a> {
+    rules: &'r [(&'r dyn Rule, LintLevel)],
+    ctx: &'c mut LintContext<'a>,
+}
+
+impl Walker<'_, '_, '_> {
+    fn walk_block(&mut self, statements: &[Statement]) {
+        for (rule, level) in self.rules {
+            self.ctx.begin(rule.id(), level.clone());
+            rule.check_block(statements, self.ctx);
+        }
+        for statement in statements {
+            self.walk_statement(statement);
+        }
+    }
+
+    fn walk_statement(&mut self, statement: &Statement) {
+        for (rule, level) in self.rules {
+            self.ctx.begin(rule.id(), level.clone());
+            rule.check_statement(statement, self.ctx);
+        }
+
+        match statement {
+            Statement::VariableDecl(decl) => self.walk_optional(decl.initializer.as_ref()),
+            Statement::DestructuringDecl(decl) => self.walk_expression(&decl.initializer),
+            Statement::MultipleVariableDecl(decl) => {
+                for single in &decl.declarations {
+                    self.walk_optional(single.initializer.as_ref());
+                }
+            }
+            Statement::MultipleAssignment(stmt) => {
+                for expr in stmt.targets.iter().chain(&stmt.values) {
+                    self.walk_expression(expr);
+                }
+            }
+            Statement::FunctionDecl(decl) => self.walk_function(decl),
+            Statement::StructDecl(decl) => {
+                for method in &decl.methods {
+                    self.walk_statement(&Statement::FunctionDecl(method.clone()));
+                }
+            }
+            Statement::InterfaceDecl(_) | Statement::TypeAlias(_) => {}
+            Statement::If(stmt) => {
+                self.walk_expression(&stmt.condition);
+                self.walk_block(&stmt.then_branch.statements);
+                if let Some(else_branch) = &stmt.else_branch {
+                    self.walk_statement(else_branch);
+                }
+            }
+            Statement::While(stmt) => {
+                self.walk_expression(&stmt.condition);
+                self.walk_block(&stmt.body.statements);
+            }
+            Statement::For(stmt) => {
+                self.walk_expression(&stmt.iterable);
+                self.walk_block(&stmt.body.statements);
+            }
+            Statement::Match(stmt) => {
+                self.walk_expression(&stmt.expr);
+                for arm in &stmt.arms {
+                    self.walk_optional(arm.guard.as_ref());
+                    self.walk_statement(&arm.body);
+                }
+            }
+            Statement::Select(stmt) => {
+                for arm in &stmt.arms {
+                    if let Some(op) = &arm.channel_op {
+                        self.walk_channel_operation(op);
+                    }
+                    self.walk_statement(&arm.body);
+                }
+            }
+            Statement::Return(stmt) => self.walk_optional(stmt.value.as_ref()),
+            Statement::Break(_) | Statement::Continue(_) | Statement::Import(_) => {}
+            Statement::Defer(stmt) => self.walk_statement(&stmt.stmt),
+            Statement::Try(stmt) => {
+                self.walk_block(&stmt.body.statements);
+                if let Some(catch_clause) = &stmt.catch_clause {
+                    self.walk_block(&catch_clause.body.statements);
+                }
+            }
+            Statement::Fail(stmt) => self.walk_expression(&stmt.message),
+            Statement::Export(stmt) => self.walk_statement(&stmt.item),
+            Statement::Expression(stmt) => self.walk_expression(&stmt.expr),
+            Statement::Block(stmt) => self.walk_block(&stmt.statements),
+        }
+    }
+
+    fn walk_function(&mut self, decl: &FunctionDecl) {
+        for param in &decl.params {
+            self.walk_optional(param.default_value.as_ref());
+        }
+        self.walk_block(&decl.body.statements);
+    }
+
+    fn walk_channel_operation(&mut self, op: &ChannelOperation) {
+        self.walk_expression(&op.channel);
+        self.walk_optional(op.value.as_ref());
+    }
+
+    fn walk_optional(&mut self, expression: Option<&Expression>) {
+        if let Some(expression) = expression {
+            self.walk_expression(expression);
+        }
+    }
+
+    fn walk_expression(&mut self, expression: &Expression) {
+        for (rule, level) in self.rules {
+            self.ctx.begin(rule.id(), level.clone());
+            rule.check_expression(expression, self.ctx);
+        }
+
+        match expression {
+            Expression::Literal(_) | Expression::Identifier(_) => {}
+            Expression::Binary(expr) => {
+                self.walk_expression(&expr.left);
+                self.walk_expression(&expr.right);
+            }
+            Expression::Unary(expr) => self.walk_expression(&expr.operand),
+            Expression::Call(expr) => {
+                self.walk_expression(&expr.callee);
+                for arg in &expr.args {
+                    self.walk_expression(arg);
+                }
+            }
+            Expression::MemberAccess(expr) => self.walk_expression(&expr.object),
+            Expression::Index(expr) => {
+                self.walk_expression(&expr.object);
+                self.walk_expression(&expr.index);
+            }
+            Expression::Assignment(expr) => {
+                self.walk_expression(&expr.target);
+                self.walk_expression(&expr.value);
+            }
+            Expression::If(expr) => {
+                self.walk_expression(&expr.condition);
+                self.walk_expression(&expr.then_expr);
+                self.walk_expression(&expr.else_expr);
+            }
+            Expression::Match(expr) => {
+                self.walk_expression(&expr.expr);
+                for arm in &expr.arms {
+                    self.walk_optional(arm.guard.as_ref());
+                    self.walk_expression(&arm.expr);
+                }
+            }
+            Expression::Array(expr) => {
+                for element in &expr.elements {
+                    self.walk_expression(element);
+                }
+            }
+            Expression::Set(expr) => {
+                for element in &expr.elements {
+                    self.walk_expression(element);
+                }
+            }
+            Expression::Map(expr) => {
+                for entry in &expr.entries {
+                    self.walk_expression(&entry.key);
+                    self.walk_expression(&entry.value);
+                }
+            }
+            Expression::StructLiteral(expr) => {
+                for field in &expr.fields {
+                    self.walk_expression(&field.value);
+                }
+            }
+            Expression::Lambda(expr) => {
+                for param in &expr.params {
+                    self.walk_optional(param.default_value.as_ref());
+                }
+                self.walk_expression(&expr.body);
+            }
+            Expression::Async(expr) => self.walk_expression(&expr.expr),
+            Expression::Await(expr) => self.walk_expression(&expr.expr),
+            Expression::Run(expr) => self.walk_expression(&expr.expr),
+            Expression::Channel(expr) => {
+                self.walk_expression(&expr.channel);
+                if let Some(value) = &expr.value {
+                    self.walk_expression(value);
+                }
+            }
+            Expression::Select(expr) => {
+                for arm in &expr.arms {
+                    if let Some(op) = &arm.channel_op {
+                        self.walk_channel_operation(op);
+                    }
+                    self.walk_expression(&arm.expr);
+                }
+            }
+            Expression::Cast(expr) => self.walk_expression(&expr.expr),
+            Expression::TypeOf(expr) => self.walk_expression(&expr.expr),
+            Expression::Propagate(expr) => self.walk_expression(&expr.expr),
+            Expression::Range(expr) => {
+                self.walk_expression(&expr.start);
+                self.walk_expression(&expr.end);
+                if let Some(step) = &expr.step {
+                    self.walk_expression(step);
+                }
+            }
+            Expression::Yield(expr) => {
+                if let Some(value) = &expr.value {
+                    self.walk_expression(value);
+                }
+            }
+            Expression::Parenthesized(expr) => self.walk_expression(&expr.expr),
+            Expression::Block(expr) => self.walk_block(&expr.statements),
+            Expression::Tuple(expr) => {
+                for element in &expr.elements {
+                    self.walk_expression(element);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,666 @@
+//! Built-in lint rules
+
+use super::rule::{LintContext, Rule};
+use super::usage::DeclarationKind;
+use super::{LintEdit, LintFix, LintLevel, LintRules};
+use crate::ast::*;
+use crate::docs::extractor::DocExtractor;
+use crate::docs::{ItemKind, Visibility};
+use crate::lexer::token::Position;
+
+/// All rules registered by default, in reporting order
+pub fn builtin_rules() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(UnusedVariableRule),
+        Box::new(UnusedImportRule),
+        Box::new(DeadCodeRule),
+        Box::new(UnreachableCodeRule),
+        Box::new(LongLineRule),
+        Box::new(NamingConventionRule),
+        Box::new(MissingDocsRule),
+        Box::new(ComplexityRule),
+        Box::new(StringConcatRule),
+        Box::new(SqlInjectionRule),
+        Box::new(HardcodedSecretRule),
+    ]
+}
+
+/// Local variables that are never read
+pub struct UnusedVariableRule;
+
+impl Rule for UnusedVariableRule {
+    fn id(&self) -> &'static str {
+        "unused-variable"
+    }
+
+    fn description(&self) -> &'static str {
+        "Variables that are declared or assigned but never read"
+    }
+
+    fn default_level(&self, config: &LintRules) -> LintLevel {
+        config.unused_variables.clone()
+    }
+
+    fn check_program(&self, program: &Program, ctx: &mut LintContext) {
+        for variable in &ctx.usage(program).unused_variables {
+            let message = if variable.assigned {
+                format!("Variable '{}' is assigned but never read", variable.name)
+            } else {
+                format!("Variable '{}' is declared but never used", variable.name)
+            };
+            let (line, column) = find_name(ctx.content, variable.position, &variable.name);
+            let fix = LintFix {
+                description: format!("Rename to '_{}'", variable.name),
+                edits: vec![LintEdit {
+                    start_line: line,
+                    start_column: column,
+                    end_line: line,
+                    end_column: column,
+                    new_text: "_".to_string(),
+                }],
+            };
+            ctx.report_with_fix(line, column, message, fix);
+        }
+    }
+}
+
+/// Imports, or imported names, that are never referenced
+pub struct UnusedImportRule;
+
+impl Rule for UnusedImportRule {
+    fn id(&self) -> &'static str {
+        "unused-import"
+    }
+
+    fn description(&self) -> &'static str {
+        "Imports that are never referenced"
+    }
+
+    fn default_level(&self, config: &LintRules) -> LintLevel {
+        config.unused_imports.clone()
+    }
+
+    fn check_program(&self, program: &Program, ctx: &mut LintContext) {
+        for unused in &ctx.usage(program).unused_imports {
+            let import = &unused.import;
+            let line = import.position.line;
+            let column = import.position.column;
+            let (statement_end, end_line) = import_end(ctx.content, import);
+
+            if unused.fully_unused {
+                let fix = LintFix {
+                    description: "Remove the unused import".to_string(),
+                    edits: vec![delete_lines(line, end_line)],
+                };
+                ctx.report_with_fix(line, column, format!("Import '{}' is not used", import_label(import)), fix);
+                continue;
+            }
+
+            let items = import.items.as_deref().unwrap_or_default();
+            let kept: Vec<String> = items
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !unused.unused_items.contains(index))
+                .map(|(_, item)| match &item.alias {
+                    Some(alias) => format!("{} as {}", item.name, alias),
+                    None => item.name.clone(),
+                })
+                .collect();
+
+            for index in &unused.unused_items {
+                let item = &items[*index];
+                let name = item.alias.as_ref().unwrap_or(&item.name);
+                let message = format!("Import '{}' is not used", name);
+                // Only single-line imports are rewritten in place
+                if end_line == line {
+                    let fix = LintFix {
+                        description: "Remove the unused imported names".to_string(),
+                        edits: vec![LintEdit {
+                            start_line: line,
+                            start_column: column,
+                            end_line: line,
+                            end_column: statement_end,
+                            new_text: format!("import {{ {} }} from \"{}\"", kept.join(", "), import.path),
+                        }],
+                    };
+                    ctx.report_with_fix(line, column, message, fix);
+                } else {
+                    ctx.report(line, column, message, Some("Consider removing this name from the import".to_string()));
+                }
+            }
+        }
+    }
+}
+
+/// Private top-level functions and structs that are never referenced
+pub struct DeadCodeRule;
+
+impl Rule for DeadCodeRule {
+    fn id(&self) -> &'static str {
+        "dead-code"
+    }
+
+    fn description(&self) -> &'static str {
+        "Private functions and structs that are never used"
+    }
+
+    fn default_level(&self, config: &LintRules) -> LintLevel {
+        config.unused_functions.clone()
+    }
+
+    fn check_program(&self, program: &Program, ctx: &mut LintContext) {
+        for declaration in &ctx.usage(program).unused_declarations {
+            let kind = match declaration.kind {
+                DeclarationKind::Function => "Function",
+                DeclarationKind::Struct => "Struct",
+            };
+            let message = format!("{} '{}' is never used", kind, declaration.name);
+            let line = declaration.position.line;
+            let column = declaration.position.column;
+            match declaration_end_line(ctx.content, declaration.position) {
+                Some(mut end_line) => {
+                    // Take the blank separator line along with the declaration
+                    if ctx.content.lines().nth(end_line).is_some_and(|next| next.trim().is_empty()) {
+                        end_line += 1;
+                    }
+                    let fix = LintFix {
+                        description: format!("Remove {} '{}'", kind.to_lowercase(), declaration.name),
+                        edits: vec![delete_lines(line, end_line)],
+                    };
+                    ctx.report_with_fix(line, column, message, fix);
+                }
+                None => ctx.report(line, column, message, None),
+            }
+        }
+    }
+}
+
+/// Statements following a `return`, `break`, `continue` or `fail` in the same block
+pub struct UnreachableCodeRule;
+
+impl Rule for UnreachableCodeRule {
+    fn id(&self) -> &'static str {
+        "unreachable-code"
+    }
+
+    fn description(&self) -> &'static str {
+        "Statements that can never execute"
+    }
+
+    fn default_level(&self, config: &LintRules) -> LintLevel {
+        config.unreachable_code.clone()
+    }
+
+    fn check_block(&self, statements: &[Statement], ctx: &mut LintContext) {
+        let exit = statements.iter().position(|statement| {
+            matches!(
+                statement,
+                Statement::Return(_) | Statement::Break(_) | Statement::Continue(_) | Statement::Fail(_)
+            )
+        });
+        let index = match exit {
+            Some(index) => index,
+            None => return,
+        };
+
+        // Only report the first unreachable statement of the block
+        if let Some(next) = statements.get(index + 1) {
+            let keyword = match &statements[index] {
+                Statement::Return(_) => "return",
+                Statement::Break(_) => "break",
+                Statement::Continue(_) => "continue",
+                _ => "fail",
+            };
+            let position = next.position();
+            ctx.report(
+                position.line,
+                position.column,
+                format!("Code after {} statement is unreachable", keyword),
+                Some("Remove unreachable code".to_string()),
+            );
+        }
+    }
+}
+
+/// Lines longer than `max_line_length`
+pub struct LongLineRule;
+
+impl Rule for LongLineRule {
+    fn id(&self) -> &'static str {
+        "long-line"
+    }
+
+    fn description(&self) -> &'static str {
+        "Lines longer than the configured maximum"
+    }
+
+    fn default_level(&self, config: &LintRules) -> LintLevel {
+        config.long_lines.clone()
+    }
+
+    fn check_source(&self, ctx: &mut LintContext) {
+        let max_line_length = ctx.config.max_line_length;
+
+        for (line_num, line) in ctx.content.lines().enumerate() {
+            if line.len() > max_line_length {
+                ctx.report(
+                    line_num + 1,
+                    max_line_length + 1,
+                    format!(
+                        "Line is {} characters long, exceeds maximum of {}",
+                        line.len(),
+                        max_line_length
+                    ),
+                    Some("Consider breaking this line into multiple lines".to_string()),
+                );
+            }
+        }
+    }
+}
+
+/// camelCase functions and PascalCase structs
+pub struct NamingConventionRule;
+
+impl Rule for NamingConventionRule {
+    fn id(&self) -> &'static str {
+        "naming-convention"
+    }
+
+    fn description(&self) -> &'static str {
+        "Functions should be camelCase and structs PascalCase"
+    }
+
+    fn default_level(&self, config: &LintRules) -> LintLevel {
+        config.naming_convention.clone()
+    }
+
+    fn check_statement(&self, statement: &Statement, ctx: &mut LintContext) {
+        match statement {
+            Statement::FunctionDecl(decl)
+                if !is_camel_case(&decl.name) && !decl.name.starts_with("Test") =>
+            {
+                ctx.report(
+                    decl.position.line,
+                    decl.position.column,
+                    format!("Function '{}' should use camelCase naming", decl.name),
+                    Some(format!("Consider renaming to '{}'", to_camel_case(&decl.name))),
+                );
+            }
+            Statement::StructDecl(decl) if !is_pascal_case(&decl.name) => {
+                ctx.report(
+                    decl.position.line,
+                    decl.position.column,
+                    format!("Struct '{}' should use PascalCase naming", decl.name),
+                    Some(format!("Consider renaming to '{}'", to_pascal_case(&decl.name))),
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Exported functions, structs, interfaces and constants without a
+/// documentation comment. An item this rule accepts counts as documented in
+/// `lang doc --coverage`, so a project that passes it has full coverage.
+pub struct MissingDocsRule;
+
+impl Rule for MissingDocsRule {
+    fn id(&self) -> &'static str {
+        "missing-docs"
+    }
+
+    fn description(&self) -> &'static str {
+        "Exported items without a documentation comment"
+    }
+
+    fn default_level(&self, config: &LintRules) -> LintLevel {
+        config.missing_docs.clone()
+    }
+
+    fn check_program(&self, program: &Program, ctx: &mut LintContext) {
+        let items = DocExtractor::new().extract_from_program(program, ctx.file);
+        for item in items {
+            if !matches!(item.visibility, Visibility::Public) || item.is_documented() {
+                continue;
+            }
+            let kind = match item.kind {
+                ItemKind::Function => "Function",
+                ItemKind::Struct => "Struct",
+                ItemKind::Interface => "Interface",
+                ItemKind::Constant => "Constant",
+                ItemKind::Variable => "Variable",
+                ItemKind::Module => "Module",
+            };
+            ctx.report(
+                item.line_number,
+                1,
+                format!("{} '{}' is exported but has no documentation", kind, item.name),
+                Some("Add a /** ... */ comment describing it above the declaration".to_string()),
+            );
+        }
+    }
+}
+
+/// Blocks nested deeper than `max_complexity`
+pub struct ComplexityRule;
+
+impl Rule for ComplexityRule {
+    fn id(&self) -> &'static str {
+        "high-complexity"
+    }
+
+    fn description(&self) -> &'static str {
+        "Code nested deeper than the configured maximum"
+    }
+
+    fn default_level(&self, config: &LintRules) -> LintLevel {
+        config.complexity.clone()
+    }
+
+    fn check_source(&self, ctx: &mut LintContext) {
+        let mut nesting_level = 0;
+        let max_nesting = ctx.config.max_complexity;
+
+        for (line_num, line) in ctx.content.lines().enumerate() {
+            let trimmed = line.trim();
+
+            // Count nesting level
+            if trimmed.contains('{') {
+                nesting_level += 1;
+                if nesting_level > max_nesting {
+                    ctx.report(
+                        line_num + 1,
+                        1,
+                        format!(
+                            "Code nesting level {} exceeds maximum of {}",
+                            nesting_level, max_nesting
+                        ),
+                        Some("Consider extracting nested code into separate functions".to_string()),
+                    );
+                }
+            }
+
+            if trimmed.contains('}') {
+                nesting_level = nesting_level.saturating_sub(1);
+            }
+        }
+    }
+}
+
+/// String concatenation inside loops
+pub struct StringConcatRule;
+
+impl Rule for StringConcatRule {
+    fn id(&self) -> &'static str {
+        "performance-string-concat"
+    }
+
+    fn description(&self) -> &'static str {
+        "String concatenation inside loops"
+    }
+
+    fn default_level(&self, config: &LintRules) -> LintLevel {
+        config.performance.clone()
+    }
+
+    fn check_source(&self, ctx: &mut LintContext) {
+        let content = ctx.content;
+
+        for (line_num, line) in content.lines().enumerate() {
+            let trimmed = line.trim();
+
+            // Check for string concatenation in loops
+            if (trimmed.contains("for ") || trimmed.contains("while "))
+                && content
+                    .lines()
+                    .skip(line_num)
+                    .take(10)
+                    .any(|l| l.contains(" + ") && l.contains("string"))
+            {
+                ctx.report(
+                    line_num + 1,
+                    1,
+                    "String concatenation in loop may cause performance issues",
+                    Some("Consider using a string builder or collecting into an array".to_string()),
+                );
+            }
+        }
+    }
+}
+
+/// Queries built by concatenating strings
+pub struct SqlInjectionRule;
+
+impl Rule for SqlInjectionRule {
+    fn id(&self) -> &'static str {
+        "security-sql-injection"
+    }
+
+    fn description(&self) -> &'static str {
+        "Queries built with string concatenation"
+    }
+
+    fn default_level(&self, config: &LintRules) -> LintLevel {
+        config.security.clone()
+    }
+
+    fn check_source(&self, ctx: &mut LintContext) {
+        for (line_num, line) in ctx.content.lines().enumerate() {
+            let trimmed = line.trim();
+
+            // Check for potential SQL injection
+            if trimmed.contains("query") && trimmed.contains('+') && trimmed.contains('"') {
+                ctx.report(
+                    line_num + 1,
+                    1,
+                    "Potential SQL injection vulnerability detected",
+                    Some("Use parameterized queries instead of string concatenation".to_string()),
+                );
+            }
+        }
+    }
+}
+
+/// Passwords, secrets and keys assigned from string literals
+pub struct HardcodedSecretRule;
+
+impl Rule for HardcodedSecretRule {
+    fn id(&self) -> &'static str {
+        "security-hardcoded-secret"
+    }
+
+    fn description(&self) -> &'static str {
+        "Secrets hardcoded in string literals"
+    }
+
+    fn default_level(&self, config: &LintRules) -> LintLevel {
+        config.security.clone()
+    }
+
+    fn check_source(&self, ctx: &mut LintContext) {
+        for (line_num, line) in ctx.content.lines().enumerate() {
+            let trimmed = line.trim();
+
+            // Check for hardcoded secrets
+            let mentions_secret = trimmed.contains("password")
+                || trimmed.contains("secret")
+                || trimmed.contains("key");
+            if mentions_secret && trimmed.contains('=') && trimmed.contains('"') {
+                ctx.report(
+                    line_num + 1,
+                    1,
+                    "Potential hardcoded secret detected",
+                    Some("Use environment variables or secure configuration for secrets".to_string()),
+                );
+            }
+        }
+    }
+}
+
+// Helper functions for parsing and checking
+fn is_camel_case(name: &str) -> bool {
+    match name.chars().next() {
+        Some(first_char) => first_char.is_lowercase() && !name.contains('_'),
+        None => false,
+    }
+}
+
+fn is_pascal_case(name: &str) -> bool {
+    match name.chars().next() {
+        Some(first_char) => first_char.is_uppercase() && !name.contains('_'),
+        None => false,
+    }
+}
+
+fn to_camel_case(name: &str) -> String {
+    let mut result = String::new();
+    let mut capitalize_next = false;
+
+    for ch in name.chars() {
+        if ch == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            result.push(ch.to_uppercase().next().unwrap_or(ch));
+            capitalize_next = false;
+        } else {
+            result.push(ch.to_lowercase().next().unwrap_or(ch));
+        }
+    }
+
+    result
+}
+
+fn to_pascal_case(name: &str) -> String {
+    let camel = to_camel_case(name);
+    if let Some(first_char) = camel.chars().next() {
+        first_char.to_uppercase().collect::<String>() + &camel[first_char.len_utf8()..]
+    } else {
+        camel
+    }
+}
+
+/// Edit deleting the whole lines `first..=last`
+fn delete_lines(first: usize, last: usize) -> LintEdit {
+    LintEdit {
+        start_line: first,
+        start_column: 1,
+        end_line: last + 1,
+        end_column: 1,
+        new_text: String::new(),
+    }
+}
+
+/// Line and column of `name` at or after `position` on the same line
+fn find_name(content: &str, position: Position, name: &str) -> (usize, usize) {
+    let line: Vec<char> = content
+        .lines()
+        .nth(position.line.saturating_sub(1))
+        .unwrap_or("")
+        .chars()
+        .collect();
+    let name: Vec<char> = name.chars().collect();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+    let from = position.column.saturating_sub(1);
+    let found = (from..line.len()).find(|&start| {
+        line[start..].starts_with(&name)
+            && (start == 0 || !is_word(line[start - 1]))
+            && line.get(start + name.len()).is_none_or(|c| !is_word(*c))
+    });
+
+    (position.line, found.map_or(position.column, |start| start + 1))
+}
+
+/// Name shown for an import in diagnostics
+fn import_label(import: &ImportStmt) -> String {
+    match (&import.alias, &import.items) {
+        (Some(alias), _) => alias.clone(),
+        (None, Some(items)) => items
+            .iter()
+            .map(|item| item.alias.as_ref().unwrap_or(&item.name).clone())
+            .collect::<Vec<_>>()
+            .join(", "),
+        (None, None) => import.path.clone(),
+    }
+}
+
+/// Column just past the import statement and the line it ends on
+fn import_end(content: &str, import: &ImportStmt) -> (usize, usize) {
+    let chars: Vec<char> = content.chars().collect();
+    let quoted: Vec<char> = format!("\"{}\"", import.path).chars().collect();
+    let bare: Vec<char> = import.path.chars().collect();
+
+    let start = import.position.offset.min(chars.len());
+    let mut end = (start..chars.len())
+        .find(|&i| chars[i..].starts_with(&quoted))
+        .map(|i| i + quoted.len())
+        .or_else(|| {
+            (start..chars.len())
+                .find(|&i| chars[i..].starts_with(&bare))
+                .map(|i| i + bare.len())
+        })
+        .unwrap_or(start);
+
+    if let Some(alias) = &import.alias {
+        let alias: Vec<char> = alias.chars().collect();
+        if let Some(i) = (end..chars.len()).find(|&i| chars[i..].starts_with(&alias)) {
+            end = i + alias.len();
+        }
+    }
+
+    let end_line = import.position.line + chars[start..end].iter().filter(|c| **c == '\n').count();
+    let line_start = chars[..end].iter().rposition(|c| *c == '\n').map_or(0, |i| i + 1);
+    (end - line_start + 1, end_line)
+}
+
+/// Line of the brace closing the declaration that starts at `position`
+fn declaration_end_line(content: &str, position: Position) -> Option<usize> {
+    let chars: Vec<char> = content.chars().collect();
+    let mut line = position.line;
+    let mut depth = 0;
+    let mut i = position.offset;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\n' => line += 1,
+            '"' | '\'' => {
+                let quote = chars[i];
+                i += 1;
+                while i < chars.len() && chars[i] != quote {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    } else if chars[i] == '\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i + 1 < chars.len() && chars[i + 1] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i + 1 < chars.len() && !(chars[i] == '*' && chars[i + 1] == '/') {
+                    if chars[i] == '\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(line);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    None
+}
@@ -0,0 +1,700 @@
+//! Scope-aware usage analysis shared by the unused-code lints
+//!
+//! Every identifier is resolved lexically to the binding it refers to, so a
+//! local that shadows an import does not count as a use of the import.
+//! Assignments count as writes, not reads. Names brought in by a bare
+//! `import "path"` come from the symbol resolver's table.
+
+use crate::ast::*;
+use crate::compiler::symbol_resolver::SymbolResolver;
+use crate::lexer::token::Position;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// An import statement with names that are never referenced
+#[derive(Debug, Clone)]
+pub struct UnusedImport {
+    pub import: ImportStmt,
+    /// Indices into `import.items` of the unused items, or empty when the
+    /// import has no item list
+    pub unused_items: Vec<usize>,
+    /// Nothing brought in by the statement is used
+    pub fully_unused: bool,
+}
+
+/// A local variable that is never read
+#[derive(Debug, Clone)]
+pub struct UnusedVariable {
+    pub name: String,
+    pub position: Position,
+    /// The variable is assigned after its declaration
+    pub assigned: bool,
+}
+
+/// Kind of a private top-level declaration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeclarationKind {
+    Function,
+    Struct,
+}
+
+/// A private top-level function or struct that is never referenced
+#[derive(Debug, Clone)]
+pub struct UnusedDeclaration {
+    pub name: String,
+    pub kind: DeclarationKind,
+    pub position: Position,
+}
+
+/// Unused code found in a program
+#[derive(Debug, Clone, Default)]
+pub struct UsageReport {
+    pub unused_imports: Vec<UnusedImport>,
+    pub unused_variables: Vec<UnusedVariable>,
+    pub unused_declarations: Vec<UnusedDeclaration>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum BindingKind {
+    /// Name brought in by the import at the given index, optionally by one of its items
+    Import { import: usize, item: Option<usize> },
+    Variable { exported: bool },
+    Declaration { kind: DeclarationKind, exported: bool },
+    /// Parameters, loop variables, pattern bindings and other names that are never reported
+    Other,
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    name: String,
+    kind: BindingKind,
+    position: Position,
+    reads: usize,
+    writes: usize,
+}
+
+/// Analyze a parsed program. `file` locates relative imports.
+pub fn analyze(program: &Program, file: &Path) -> UsageReport {
+    let mut analyzer = UsageAnalyzer::new();
+    analyzer.declare_imports(program, file);
+    analyzer.hoist(&program.statements, true);
+    for statement in &program.statements {
+        analyzer.walk_statement(statement);
+    }
+    analyzer.report()
+}
+
+struct UsageAnalyzer {
+    bindings: Vec<Binding>,
+    scopes: Vec<HashMap<String, usize>>,
+    imports: Vec<ImportStmt>,
+    /// Bindings of the functions currently being walked, so recursion is not a use
+    enclosing_functions: Vec<usize>,
+}
+
+impl UsageAnalyzer {
+    fn new() -> Self {
+        Self {
+            bindings: Vec::new(),
+            scopes: vec![HashMap::new()],
+            imports: Vec::new(),
+            enclosing_functions: Vec::new(),
+        }
+    }
+
+    fn declare(&mut self, name: &str, kind: BindingKind, position: Position) -> usize {
+        let index = self.bindings.len();
+        self.bindings.push(Binding {
+            name: name.to_string(),
+            kind,
+            position,
+            reads: 0,
+            writes: 0,
+        });
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), index);
+        }
+        index
+    }
+
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    fn read(&mut self, name: &str) {
+        if let Some(index) = self.lookup(name) {
+            if !self.enclosing_functions.contains(&index) {
+                self.bindings[index].reads += 1;
+            }
+        }
+    }
+
+    fn write(&mut self, name: &str) {
+        if let Some(index) = self.lookup(name) {
+            self.bindings[index].writes += 1;
+        }
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Bind the names of every import, asking the symbol resolver for the
+    /// exports of bare `import "path"` statements
+    fn declare_imports(&mut self, program: &Program, file: &Path) {
+        let mut resolver = SymbolResolver::new();
+        resolver.set_current_module(file.to_string_lossy().to_string());
+
+        for statement in &program.statements {
+            let import = match statement {
+                Statement::Import(import) => import,
+                _ => continue,
+            };
+            let import_index = self.imports.len();
+            self.imports.push(import.clone());
+
+            if let Some(items) = &import.items {
+                for (item_index, item) in items.iter().enumerate() {
+                    let name = item.alias.as_ref().unwrap_or(&item.name);
+                    self.declare(
+                        name,
+                        BindingKind::Import { import: import_index, item: Some(item_index) },
+                        import.position,
+                    );
+                }
+                continue;
+            }
+
+            let kind = BindingKind::Import { import: import_index, item: None };
+            if let Some(alias) = &import.alias {
+                self.declare(alias, kind, import.position);
+                continue;
+            }
+
+            // The module name itself, e.g. `io` for "std/io"
+            let module_name = import
+                .path
+                .rsplit('/')
+                .next()
+                .unwrap_or(&import.path)
+                .trim_end_matches(".bu")
+                .to_string();
+            self.declare(&module_name, kind.clone(), import.position);
+
+            if resolver.resolve_import_statement(import).is_ok() {
+                let exported: Vec<String> = resolver
+                    .symbol_table()
+                    .imported_symbols
+                    .values()
+                    .filter(|symbol| symbol.module_path == import.path)
+                    .map(|symbol| symbol.name.clone())
+                    .collect();
+                for name in exported {
+                    self.declare(&name, kind.clone(), import.position);
+                }
+            }
+        }
+    }
+
+    /// Declare functions and types before walking a block so they can be used before their definition
+    fn hoist(&mut self, statements: &[Statement], top_level: bool) {
+        for statement in statements {
+            let (statement, exported) = match statement {
+                Statement::Export(export) => (export.item.as_ref(), true),
+                other => (other, false),
+            };
+
+            match statement {
+                Statement::FunctionDecl(decl) => {
+                    let kind = if top_level {
+                        BindingKind::Declaration {
+                            kind: DeclarationKind::Function,
+                            exported: exported || decl.is_exported,
+                        }
+                    } else {
+                        BindingKind::Other
+                    };
+                    self.declare(&decl.name, kind, decl.position);
+                }
+                Statement::StructDecl(decl) => {
+                    let kind = if top_level {
+                        BindingKind::Declaration {
+                            kind: DeclarationKind::Struct,
+                            exported: exported || decl.is_exported,
+                        }
+                    } else {
+                        BindingKind::Other
+                    };
+                    self.declare(&decl.name, kind, decl.position);
+                }
+                Statement::InterfaceDecl(decl) => {
+                    self.declare(&decl.name, BindingKind::Other, decl.position);
+                }
+                Statement::TypeAlias(decl) => {
+                    self.declare(&decl.name, BindingKind::Other, decl.position);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn walk_block(&mut self, statements: &[Statement]) {
+        self.push_scope();
+        self.hoist(statements, false);
+        for statement in statements {
+            self.walk_statement(statement);
+        }
+        self.pop_scope();
+    }
+
+    fn walk_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::VariableDecl(decl) => {
+                if let Some(type_annotation) = &decl.type_annotation {
+                    self.walk_type(type_annotation);
+                }
+                self.walk_optional(decl.initializer.as_ref());
+                self.declare(
+                    &decl.name,
+                    BindingKind::Variable { exported: decl.is_exported },
+                    decl.position,
+                );
+            }
+            Statement::DestructuringDecl(decl) => {
+                self.walk_expression(&decl.initializer);
+                self.declare_pattern(&decl.pattern, BindingKind::Variable { exported: decl.is_exported });
+            }
+            Statement::MultipleVariableDecl(decl) => {
+                for single in &decl.declarations {
+                    if let Some(type_annotation) = &single.type_annotation {
+                        self.walk_type(type_annotation);
+                    }
+                    self.walk_optional(single.initializer.as_ref());
+                }
+                for single in &decl.declarations {
+                    self.declare(
+                        &single.name,
+                        BindingKind::Variable { exported: decl.is_exported },
+                        decl.position,
+                    );
+                }
+            }
+            Statement::MultipleAssignment(stmt) => {
+                for value in &stmt.values {
+                    self.walk_expression(value);
+                }
+                for target in &stmt.targets {
+                    self.walk_assignment_target(target);
+                }
+            }
+            Statement::FunctionDecl(decl) => self.walk_function(decl),
+            Statement::StructDecl(decl) => {
+                for field in &decl.fields {
+                    self.walk_type(&field.field_type);
+                }
+                for method in &decl.methods {
+                    self.walk_function_body(method);
+                }
+            }
+            Statement::InterfaceDecl(decl) => {
+                for method in &decl.methods {
+                    if let Some(default_method) = method.default_method() {
+                        self.walk_function_body(&default_method);
+                        continue;
+                    }
+                    for param in &method.params {
+                        self.walk_type(&param.param_type);
+                    }
+                    if let Some(return_type) = &method.return_type {
+                        self.walk_type(return_type);
+                    }
+                }
+            }
+            Statement::TypeAlias(decl) => self.walk_type(&decl.target_type),
+            Statement::If(stmt) => {
+                self.walk_expression(&stmt.condition);
+                self.walk_block(&stmt.then_branch.statements);
+                if let Some(else_branch) = &stmt.else_branch {
+                    self.walk_statement(else_branch);
+                }
+            }
+            Statement::While(stmt) => {
+                self.walk_expression(&stmt.condition);
+                self.walk_block(&stmt.body.statements);
+            }
+            Statement::For(stmt) => {
+                self.walk_expression(&stmt.iterable);
+                self.push_scope();
+                if let Some(index_variable) = &stmt.index_variable {
+                    self.declare(index_variable, BindingKind::Other, stmt.position);
+                }
+                self.declare(&stmt.variable, BindingKind::Other, stmt.position);
+                self.walk_block(&stmt.body.statements);
+                self.pop_scope();
+            }
+            Statement::Match(stmt) => {
+                self.walk_expression(&stmt.expr);
+                for arm in &stmt.arms {
+                    self.push_scope();
+                    self.declare_pattern(&arm.pattern, BindingKind::Other);
+                    self.walk_optional(arm.guard.as_ref());
+                    self.walk_statement(&arm.body);
+                    self.pop_scope();
+                }
+            }
+            Statement::Select(stmt) => {
+                for arm in &stmt.arms {
+                    self.push_scope();
+                    if let Some(op) = &arm.channel_op {
+                        self.walk_channel_operation(op);
+                    }
+                    self.walk_statement(&arm.body);
+                    self.pop_scope();
+                }
+            }
+            Statement::Return(stmt) => self.walk_optional(stmt.value.as_ref()),
+            Statement::Break(_) | Statement::Continue(_) | Statement::Import(_) => {}
+            Statement::Defer(stmt) => self.walk_statement(&stmt.stmt),
+            Statement::Try(stmt) => {
+                self.walk_block(&stmt.body.statements);
+                if let Some(catch_clause) = &stmt.catch_clause {
+                    self.push_scope();
+                    if let Some(error_var) = &catch_clause.error_var {
+                        self.declare(error_var, BindingKind::Other, catch_clause.position);
+                    }
+                    self.walk_block(&catch_clause.body.statements);
+                    self.pop_scope();
+                }
+            }
+            Statement::Fail(stmt) => self.walk_expression(&stmt.message),
+            Statement::Export(stmt) => self.walk_statement(&stmt.item),
+            Statement::Expression(stmt) => self.walk_expression(&stmt.expr),
+            Statement::Block(stmt) => self.walk_block(&stmt.statements),
+        }
+    }
+
+    fn walk_function(&mut self, decl: &FunctionDecl) {
+        let binding = self.lookup(&decl.name);
+        if let Some(binding) = binding {
+            self.enclosing_functions.push(binding);
+        }
+        self.walk_function_body(decl);
+        if binding.is_some() {
+            self.enclosing_functions.pop();
+        }
+    }
+
+    fn walk_function_body(&mut self, decl: &FunctionDecl) {
+        self.push_scope();
+        for param in &decl.params {
+            self.walk_type(&param.param_type);
+            self.walk_optional(param.default_value.as_ref());
+            self.declare(&param.name, BindingKind::Other, param.position);
+        }
+        if let Some(return_type) = &decl.return_type {
+            self.walk_type(return_type);
+        }
+        self.walk_block(&decl.body.statements);
+        self.pop_scope();
+    }
+
+    fn walk_channel_operation(&mut self, op: &ChannelOperation) {
+        self.walk_expression(&op.channel);
+        self.walk_optional(op.value.as_ref());
+        if let Some(variable) = &op.variable {
+            self.declare(variable, BindingKind::Other, op.position);
+        }
+    }
+
+    fn declare_pattern(&mut self, pattern: &Pattern, kind: BindingKind) {
+        match pattern {
+            Pattern::Identifier(name, position) => {
+                self.declare(name, kind, *position);
+            }
+            Pattern::Binding(binding) => {
+                self.declare(&binding.name, kind.clone(), binding.position);
+                self.declare_pattern(&binding.pattern, kind);
+            }
+            Pattern::Struct(struct_pattern) => {
+                self.read(&struct_pattern.name);
+                for field in &struct_pattern.fields {
+                    self.declare_pattern(&field.pattern, kind.clone());
+                }
+            }
+            Pattern::Array(array_pattern) => {
+                for element in &array_pattern.elements {
+                    self.declare_pattern(element, kind.clone());
+                }
+            }
+            Pattern::Tuple(tuple_pattern) => {
+                for element in &tuple_pattern.elements {
+                    self.declare_pattern(element, kind.clone());
+                }
+            }
+            Pattern::Or(or_pattern) => {
+                for alternative in &or_pattern.patterns {
+                    self.declare_pattern(alternative, kind.clone());
+                }
+            }
+            Pattern::Type(type_pattern) => {
+                self.walk_type(&type_pattern.pattern_type);
+                if let Some(name) = &type_pattern.name {
+                    self.declare(name, kind, type_pattern.position);
+                }
+            }
+            Pattern::Wildcard(_) | Pattern::Literal(_, _) | Pattern::Range(_) => {}
+        }
+    }
+
+    fn walk_type(&mut self, type_node: &Type) {
+        match type_node {
+            Type::Named(name) => self.read(name),
+            Type::Struct(struct_type) => {
+                self.read(&struct_type.name);
+                for arg in &struct_type.type_args {
+                    self.walk_type(arg);
+                }
+            }
+            Type::Interface(interface_type) => {
+                self.read(&interface_type.name);
+                for arg in &interface_type.type_args {
+                    self.walk_type(arg);
+                }
+            }
+            Type::Generic(generic) => {
+                for constraint in &generic.constraints {
+                    self.walk_type(constraint);
+                }
+            }
+            Type::Array(array) => self.walk_type(&array.element_type),
+            Type::Slice(slice) => self.walk_type(&slice.element_type),
+            Type::Map(map) => {
+                self.walk_type(&map.key_type);
+                self.walk_type(&map.value_type);
+            }
+            Type::Tuple(tuple) => {
+                for element in &tuple.element_types {
+                    self.walk_type(element);
+                }
+            }
+            Type::Function(function) => {
+                for param in &function.param_types {
+                    self.walk_type(param);
+                }
+                if let Some(return_type) = &function.return_type {
+                    self.walk_type(return_type);
+                }
+            }
+            Type::Channel(channel) => self.walk_type(&channel.element_type),
+            Type::Promise(promise) => self.walk_type(&promise.result_type),
+            Type::Union(union) => {
+                for member in &union.types {
+                    self.walk_type(member);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn walk_optional(&mut self, expression: Option<&Expression>) {
+        if let Some(expression) = expression {
+            self.walk_expression(expression);
+        }
+    }
+
+    /// Assigning to a plain name is a write; other targets read their object
+    fn walk_assignment_target(&mut self, target: &Expression) {
+        match target {
+            Expression::Identifier(ident) => self.write(&ident.name),
+            other => self.walk_expression(other),
+        }
+    }
+
+    fn walk_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Literal(_) => {}
+            Expression::Identifier(ident) => self.read(&ident.name),
+            Expression::Binary(expr) => {
+                self.walk_expression(&expr.left);
+                self.walk_expression(&expr.right);
+            }
+            Expression::Unary(expr) => self.walk_expression(&expr.operand),
+            Expression::Call(expr) => {
+                self.walk_expression(&expr.callee);
+                for type_arg in &expr.type_args {
+                    self.walk_type(type_arg);
+                }
+                for arg in &expr.args {
+                    self.walk_expression(arg);
+                }
+            }
+            Expression::MemberAccess(expr) => self.walk_expression(&expr.object),
+            Expression::Index(expr) => {
+                self.walk_expression(&expr.object);
+                self.walk_expression(&expr.index);
+            }
+            Expression::Assignment(expr) => {
+                self.walk_expression(&expr.value);
+                self.walk_assignment_target(&expr.target);
+            }
+            Expression::If(expr) => {
+                self.walk_expression(&expr.condition);
+                self.walk_expression(&expr.then_expr);
+                self.walk_expression(&expr.else_expr);
+            }
+            Expression::Match(expr) => {
+                self.walk_expression(&expr.expr);
+                for arm in &expr.arms {
+                    self.push_scope();
+                    self.declare_pattern(&arm.pattern, BindingKind::Other);
+                    self.walk_optional(arm.guard.as_ref());
+                    self.walk_expression(&arm.expr);
+                    self.pop_scope();
+                }
+            }
+            Expression::Array(expr) => {
+                for element in &expr.elements {
+                    self.walk_expression(element);
+                }
+            }
+            Expression::Set(expr) => {
+                for element in &expr.elements {
+                    self.walk_expression(element);
+                }
+            }
+            Expression::Map(expr) => {
+                for entry in &expr.entries {
+                    self.walk_expression(&entry.key);
+                    self.walk_expression(&entry.value);
+                }
+            }
+            Expression::StructLiteral(expr) => {
+                self.read(&expr.type_name);
+                for field in &expr.fields {
+                    self.walk_expression(&field.value);
+                }
+            }
+            Expression::Lambda(expr) => {
+                self.push_scope();
+                for param in &expr.params {
+                    self.walk_type(&param.param_type);
+                    self.walk_optional(param.default_value.as_ref());
+                    self.declare(&param.name, BindingKind::Other, param.position);
+                }
+                if let Some(return_type) = &expr.return_type {
+                    self.walk_type(return_type);
+                }
+                self.walk_expression(&expr.body);
+                self.pop_scope();
+            }
+            Expression::Async(expr) => self.walk_expression(&expr.expr),
+            Expression::Await(expr) => self.walk_expression(&expr.expr),
+            Expression::Run(expr) => self.walk_expression(&expr.expr),
+            Expression::Channel(expr) => {
+                self.walk_expression(&expr.channel);
+                if let Some(value) = &expr.value {
+                    self.walk_expression(value);
+                }
+            }
+            Expression::Select(expr) => {
+                for arm in &expr.arms {
+                    self.push_scope();
+                    if let Some(op) = &arm.channel_op {
+                        self.walk_channel_operation(op);
+                    }
+                    self.walk_expression(&arm.expr);
+                    self.pop_scope();
+                }
+            }
+            Expression::Cast(expr) => {
+                self.walk_expression(&expr.expr);
+                self.walk_type(&expr.target_type);
+            }
+            Expression::TypeOf(expr) => self.walk_expression(&expr.expr),
+            Expression::Propagate(expr) => self.walk_expression(&expr.expr),
+            Expression::Range(expr) => {
+                self.walk_expression(&expr.start);
+                self.walk_expression(&expr.end);
+                if let Some(step) = &expr.step {
+                    self.walk_expression(step);
+                }
+            }
+            Expression::Yield(expr) => {
+                if let Some(value) = &expr.value {
+                    self.walk_expression(value);
+                }
+            }
+            Expression::Parenthesized(expr) => self.walk_expression(&expr.expr),
+            Expression::Block(expr) => self.walk_block(&expr.statements),
+            Expression::Tuple(expr) => {
+                for element in &expr.elements {
+                    self.walk_expression(element);
+                }
+            }
+        }
+    }
+
+    fn report(self) -> UsageReport {
+        let mut report = UsageReport::default();
+
+        // Group import bindings by statement
+        let mut import_usage: Vec<(bool, Vec<usize>)> = vec![(false, Vec::new()); self.imports.len()];
+        for binding in &self.bindings {
+            if let BindingKind::Import { import, item } = binding.kind {
+                let (any_used, unused_items) = &mut import_usage[import];
+                if binding.reads > 0 {
+                    *any_used = true;
+                } else if let Some(item) = item {
+                    unused_items.push(item);
+                }
+            }
+        }
+        for (import, (any_used, unused_items)) in self.imports.into_iter().zip(import_usage) {
+            let item_count = import.items.as_ref().map_or(0, |items| items.len());
+            let fully_unused = !any_used;
+            if fully_unused || !unused_items.is_empty() {
+                report.unused_imports.push(UnusedImport {
+                    fully_unused: fully_unused || (item_count > 0 && unused_items.len() == item_count),
+                    unused_items,
+                    import,
+                });
+            }
+        }
+
+        for binding in self.bindings {
+            if binding.reads > 0 || binding.name.starts_with('_') {
+                continue;
+            }
+            match binding.kind {
+                BindingKind::Variable { exported: false } => {
+                    report.unused_variables.push(UnusedVariable {
+                        name: binding.name,
+                        position: binding.position,
+                        assigned: binding.writes > 0,
+                    });
+                }
+                BindingKind::Declaration { kind, exported: false } => {
+                    let lowercase = binding.name.to_lowercase();
+                    if binding.name == "main" || lowercase.starts_with("test") {
+                        continue;
+                    }
+                    report.unused_declarations.push(UnusedDeclaration {
+                        name: binding.name,
+                        kind,
+                        position: binding.position,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        report
+    }
+}
@@ -0,0 +1,129 @@
+//! Log levels and output for the command-line tools
+//!
+//! The build system, test runner, formatter, linter, documentation generator
+//! and package manager report progress as `tracing` events. `init` installs
+//! the subscriber that writes them to stderr, filtered by the verbosity the
+//! user asked for:
+//!
+//! - `-q`, `--quiet`: warnings and errors only
+//! - no flag: progress messages
+//! - `-v`, `--verbose`: details of each step
+//! - `-vv`: everything, prefixed with the level and the module it came from
+//!
+//! `BULU_LOG` adds `tracing` filter directives on top of the flags, e.g.
+//! `BULU_LOG=bulu::package=trace` or `BULU_LOG=error`.
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::EnvFilter;
+
+/// Environment variable holding extra filter directives
+pub const LOG_ENV: &str = "BULU_LOG";
+
+/// How much the tools say
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Verbosity {
+    Quiet,
+    #[default]
+    Normal,
+    Verbose,
+    Trace,
+}
+
+impl Verbosity {
+    /// The verbosity of `-q` and the number of `-v` flags; quiet wins
+    pub fn from_flags(quiet: bool, verbose: u8) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            (false, _) => Verbosity::Trace,
+        }
+    }
+
+    /// The most detailed level shown
+    pub fn level(self) -> LevelFilter {
+        match self {
+            Verbosity::Quiet => LevelFilter::WARN,
+            Verbosity::Normal => LevelFilter::INFO,
+            Verbosity::Verbose => LevelFilter::DEBUG,
+            Verbosity::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// The filter for a verbosity and the value of `BULU_LOG`, if any. Module
+/// directives apply on top of the verbosity; a bare level replaces it.
+pub fn filter(verbosity: Verbosity, directives: Option<&str>) -> Result<EnvFilter, String> {
+    let directives = directives.unwrap_or("").trim();
+    let filter = EnvFilter::builder()
+        .parse(directives)
+        .map_err(|e| format!("Invalid {} value '{}': {}", LOG_ENV, directives, e))?;
+    let has_level = directives
+        .split(',')
+        .map(str::trim)
+        .any(|directive| !directive.is_empty() && directive.parse::<LevelFilter>().is_ok());
+    Ok(if has_level {
+        filter
+    } else {
+        filter.add_directive(verbosity.level().into())
+    })
+}
+
+/// Write log events to stderr for the rest of the process
+pub fn init(verbosity: Verbosity) -> Result<(), String> {
+    let directives = std::env::var(LOG_ENV).ok();
+    let filter = filter(verbosity, directives.as_deref())?;
+    let detailed = verbosity == Verbosity::Trace || directives.is_some();
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .with_ansi(crate::terminal::colors_enabled())
+        .without_time()
+        .with_level(detailed)
+        .with_target(detailed)
+        .try_init()
+        .map_err(|e| format!("Failed to initialize logging: {}", e))
+}
+
+/// Run `f` with log events at `verbosity` collected instead of printed,
+/// returning its result and what was logged
+pub fn capture<R>(verbosity: Verbosity, f: impl FnOnce() -> R) -> (R, String) {
+    let buffer = CaptureBuffer::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(verbosity.level())
+        .with_writer(buffer.clone())
+        .with_ansi(false)
+        .without_time()
+        .with_level(false)
+        .with_target(false)
+        .finish();
+    let result = tracing::subscriber::with_default(subscriber, f);
+    let output = String::from_utf8_lossy(&buffer.0.lock().unwrap()).into_owned();
+    (result, output)
+}
+
+/// Where `capture` writes events
+#[derive(Clone, Default)]
+struct CaptureBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for CaptureBuffer {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for CaptureBuffer {
+    type Writer = CaptureBuffer;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
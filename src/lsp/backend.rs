@@ -4,6 +4,7 @@ use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer};
 
+use crate::docs::doctest::DoctestRunner;
 use crate::lexer::Lexer;
 use crate::parser::Parser;
 use crate::linter::Linter;
@@ -11,6 +12,7 @@ use crate::types::checker::TypeChecker;
 
 use super::completion::CompletionProvider;
 use super::diagnostics::DiagnosticsProvider;
+use super::formatting::FormattingProvider;
 use super::hover::HoverProvider;
 use super::navigation::NavigationProvider;
 use super::refactor::RefactorProvider;
@@ -29,9 +31,12 @@ pub struct BuluLanguageServer {
     documents: Arc<DashMap<String, DocumentState>>,
     completion_provider: CompletionProvider,
     diagnostics_provider: DiagnosticsProvider,
+    formatting_provider: FormattingProvider,
     hover_provider: HoverProvider,
     navigation_provider: NavigationProvider,
     refactor_provider: RefactorProvider,
+    /// Runs the examples of saved documents on pooled interpreters
+    doctest_runner: Arc<DoctestRunner>,
 }
 
 impl BuluLanguageServer {
@@ -43,9 +48,11 @@ impl BuluLanguageServer {
             documents: documents.clone(),
             completion_provider: CompletionProvider::new(documents.clone()),
             diagnostics_provider: DiagnosticsProvider::new(documents.clone()),
+            formatting_provider: FormattingProvider::new(documents.clone()),
             hover_provider: HoverProvider::new(documents.clone()),
             navigation_provider: NavigationProvider::new(documents.clone()),
             refactor_provider: RefactorProvider::new(documents.clone()),
+            doctest_runner: Arc::new(DoctestRunner::new()),
         }
     }
 
@@ -60,8 +67,13 @@ impl LanguageServer for BuluLanguageServer {
     async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
-                text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                    TextDocumentSyncKind::FULL,
+                text_document_sync: Some(TextDocumentSyncCapability::Options(
+                    TextDocumentSyncOptions {
+                        open_close: Some(true),
+                        change: Some(TextDocumentSyncKind::FULL),
+                        save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                        ..Default::default()
+                    },
                 )),
                 completion_provider: Some(CompletionOptions {
                     resolve_provider: Some(true),
@@ -79,6 +91,7 @@ impl LanguageServer for BuluLanguageServer {
                 code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
                 document_symbol_provider: Some(OneOf::Left(true)),
                 workspace_symbol_provider: Some(OneOf::Left(true)),
+                document_range_formatting_provider: Some(OneOf::Left(true)),
                 signature_help_provider: Some(SignatureHelpOptions {
                     trigger_characters: Some(vec!["(".to_string(), ",".to_string()]),
                     retrigger_characters: None,
@@ -108,6 +121,9 @@ impl LanguageServer for BuluLanguageServer {
         let text = params.text_document.text.clone();
         let version = params.text_document.version;
 
+        // Version numbers start over for a reopened document
+        self.diagnostics_provider.invalidate_exports(&params.text_document.uri);
+
         // Store document
         self.documents.insert(
             uri.clone(),
@@ -150,7 +166,35 @@ impl LanguageServer for BuluLanguageServer {
         }
     }
 
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        let uri = params.text_document.uri;
+        let Some((text, version)) = self
+            .documents
+            .get(&uri.to_string())
+            .map(|doc| (doc.text.clone(), doc.version))
+        else {
+            return;
+        };
+
+        // Examples run on saves rather than edits, as running code is slower
+        // than checking it
+        let mut diagnostics = self.analyze_document(&uri, &text).await;
+        let runner = self.doctest_runner.clone();
+        let document = uri.clone();
+        if let Ok(failures) = tokio::task::spawn_blocking(move || {
+            DiagnosticsProvider::doctest_diagnostics(&runner, &document, &text)
+        })
+        .await
+        {
+            diagnostics.extend(failures);
+        }
+        self.client
+            .publish_diagnostics(uri, diagnostics, Some(version))
+            .await;
+    }
+
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.diagnostics_provider.invalidate_exports(&params.text_document.uri);
         let uri = params.text_document.uri.to_string();
         self.documents.remove(&uri);
     }
@@ -179,7 +223,38 @@ impl LanguageServer for BuluLanguageServer {
     }
 
     async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
-        self.refactor_provider.code_actions(params).await
+        let uri = params.text_document.uri.clone();
+        let mut actions: CodeActionResponse = match self.documents.get(&uri.to_string()) {
+            Some(doc) => {
+                let diagnostics = &params.context.diagnostics;
+                let mut actions = self
+                    .diagnostics_provider
+                    .import_code_actions(&uri, &doc.text, diagnostics);
+                actions.extend(
+                    self.diagnostics_provider
+                        .lint_code_actions(&uri, &doc.text, diagnostics),
+                );
+                actions.into_iter().map(CodeActionOrCommand::CodeAction).collect()
+            }
+            None => Vec::new(),
+        };
+
+        if let Some(refactorings) = self.refactor_provider.code_actions(params).await? {
+            actions.extend(refactorings);
+        }
+
+        if actions.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(actions))
+        }
+    }
+
+    async fn range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        self.formatting_provider.range_formatting(params).await
     }
 
     async fn signature_help(&self, params: SignatureHelpParams) -> Result<Option<SignatureHelp>> {
@@ -112,6 +112,8 @@ impl CompletionProvider {
             ("recover", "func(): any", "Recover from panic"),
             ("assert", "func(condition: bool, message: string)", "Assert condition"),
             ("typeof", "func(x: any): string", "Get type name"),
+            ("hash", "func(x: any, seed: int64): int64", "Stable hash of a value"),
+            ("intrinsic", "func(name: string, args: ...int64): int64", "Machine instruction, native builds only"),
             ("instanceof", "func(x: any, T: Type): bool", "Check type"),
             ("sizeof", "func(T: Type): int32", "Get type size"),
         ];
@@ -217,13 +219,16 @@ impl CompletionProvider {
             ("std.strings", "String manipulation"),
             ("std.arrays", "Array operations"),
             ("std.math", "Mathematical functions"),
-            ("std.time", "Time and duration"),
+            ("std.time", "Durations, instants, dates and timers"),
+            ("std.log", "Leveled, structured logging"),
+            ("std.schedule", "Cron and interval schedules"),
             ("std.sync", "Synchronization primitives"),
             ("std.os", "Operating system interface"),
             ("std.http", "HTTP client and server"),
             ("std.net", "TCP/UDP networking"),
             ("std.json", "JSON encoding/decoding"),
             ("std.crypto", "Cryptographic operations"),
+            ("std.encoding", "Base64, hex and URL encoding"),
         ];
 
         modules
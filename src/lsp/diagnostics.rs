@@ -1,25 +1,74 @@
 use dashmap::DashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
+use std::time::SystemTime;
 use tower_lsp::lsp_types::*;
 
 use crate::lexer::Lexer;
 use crate::parser::Parser;
 use crate::error::BuluError;
+use crate::compiler::symbol_resolver::SymbolResolver;
+use crate::docs::doctest::DoctestRunner;
+use crate::linter::{load_lint_config, LintIssue, LintLevel, RuleRegistry};
+use crate::project::Project;
+use crate::resolver::{Module, ModuleResolver};
+use crate::types::checker::TypeChecker;
 
 use super::backend::DocumentState;
 
+/// An exported symbol that can satisfy an unresolved identifier
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportCandidate {
+    /// Name of the exported symbol
+    pub name: String,
+    /// Path to use in the `from "..."` clause of the import
+    pub module_path: String,
+}
+
+/// Which revision of a module's source a set of exports was read from
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SourceStamp {
+    /// Version of an open document
+    Document(i32),
+    /// Modification time of a file on disk
+    Disk(Option<SystemTime>),
+    /// Standard library modules never change while the server runs
+    Std,
+}
+
+/// Exported names of a module, kept until its source changes
+#[derive(Debug, Clone)]
+struct CachedExports {
+    stamp: SourceStamp,
+    names: Arc<HashSet<String>>,
+}
+
 /// Provides real-time diagnostics for Bulu code
 pub struct DiagnosticsProvider {
     documents: Arc<DashMap<String, DocumentState>>,
+    /// Exports of the modules searched for import candidates, by path
+    exports: DashMap<PathBuf, CachedExports>,
 }
 
 impl DiagnosticsProvider {
     pub fn new(documents: Arc<DashMap<String, DocumentState>>) -> Self {
-        Self { documents }
+        Self {
+            documents,
+            exports: DashMap::new(),
+        }
+    }
+
+    /// Drop the cached exports of a document, e.g. when it is opened or
+    /// closed and its version numbers start over
+    pub fn invalidate_exports(&self, uri: &Url) {
+        if let Ok(path) = uri.to_file_path() {
+            self.exports.remove(&path.canonicalize().unwrap_or(path));
+        }
     }
 
     /// Analyze document and return diagnostics
-    pub async fn analyze(&self, _uri: &Url, text: &str) -> Vec<Diagnostic> {
+    pub async fn analyze(&self, uri: &Url, text: &str) -> Vec<Diagnostic> {
         let mut diagnostics = Vec::new();
 
         // Lexical analysis
@@ -29,9 +78,11 @@ impl DiagnosticsProvider {
                 // Syntax analysis
                 let mut parser = Parser::new(tokens);
                 match parser.parse() {
-                    Ok(_ast) => {
-                        // Successfully parsed - no errors
-                        // In a full implementation, we would run type checking and linting here
+                    Ok(mut ast) => {
+                        // Semantic analysis
+                        if let Err(error) = self.check_program(uri, &mut ast) {
+                            diagnostics.push(self.error_to_diagnostic(&error, DiagnosticSeverity::ERROR));
+                        }
                     }
                     Err(parse_error) => {
                         diagnostics.push(self.error_to_diagnostic(&parse_error, DiagnosticSeverity::ERROR));
@@ -43,9 +94,466 @@ impl DiagnosticsProvider {
             }
         }
 
+        diagnostics.extend(self.lint_diagnostics(uri, text));
+
         diagnostics
     }
 
+    /// Run the examples in a saved document's doc comments, reporting each
+    /// failing example on the declaration it documents. Documents that don't
+    /// parse have no results here; `analyze` reports why.
+    pub fn doctest_diagnostics(runner: &DoctestRunner, uri: &Url, text: &str) -> Vec<Diagnostic> {
+        let Ok(path) = uri.to_file_path() else {
+            return Vec::new();
+        };
+        let Ok(failures) = runner.run_file(text, &path) else {
+            return Vec::new();
+        };
+
+        let lines: Vec<&str> = text.lines().collect();
+        failures
+            .into_iter()
+            .map(|failure| {
+                let line = failure.line.saturating_sub(1);
+                let line_length = lines
+                    .get(line)
+                    .map(|content| content.encode_utf16().count())
+                    .unwrap_or(0);
+                Diagnostic {
+                    range: Range {
+                        start: Position {
+                            line: line as u32,
+                            character: 0,
+                        },
+                        end: Position {
+                            line: line as u32,
+                            character: line_length as u32,
+                        },
+                    },
+                    severity: Some(DiagnosticSeverity::WARNING),
+                    source: Some("bulu-doctest".to_string()),
+                    message: format!("Example of {} failed: {}", failure.item, failure.message),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    /// Run the lint rules over a document, using the enclosing project's lint configuration.
+    /// The rule ID is reported as the diagnostic code.
+    pub fn lint_diagnostics(&self, uri: &Url, text: &str) -> Vec<Diagnostic> {
+        let lines: Vec<&str> = text.lines().collect();
+        Self::run_lint(uri, text)
+            .into_iter()
+            .map(|issue| {
+                let line = issue.line.saturating_sub(1);
+                let line_length = lines
+                    .get(line)
+                    .map(|content| content.encode_utf16().count())
+                    .unwrap_or(0);
+                let start_char = issue.column.saturating_sub(1).min(line_length);
+
+                Diagnostic {
+                    range: Range {
+                        start: Position {
+                            line: line as u32,
+                            character: start_char as u32,
+                        },
+                        end: Position {
+                            line: line as u32,
+                            character: line_length.max(start_char) as u32,
+                        },
+                    },
+                    severity: Some(match issue.level {
+                        LintLevel::Error => DiagnosticSeverity::ERROR,
+                        _ => DiagnosticSeverity::WARNING,
+                    }),
+                    code: Some(NumberOrString::String(issue.rule)),
+                    code_description: None,
+                    source: Some("bulu-lint".to_string()),
+                    message: issue.message,
+                    related_information: None,
+                    tags: None,
+                    data: None,
+                }
+            })
+            .collect()
+    }
+
+    /// Quick fixes for the lint diagnostics among `diagnostics` that carry automatic edits
+    pub fn lint_code_actions(&self, uri: &Url, text: &str, diagnostics: &[Diagnostic]) -> Vec<CodeAction> {
+        let requested: Vec<&Diagnostic> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.source.as_deref() == Some("bulu-lint"))
+            .collect();
+        if requested.is_empty() {
+            return Vec::new();
+        }
+
+        let lines: Vec<&str> = text.lines().collect();
+        let mut actions = Vec::new();
+
+        for issue in Self::run_lint(uri, text) {
+            let fix = match issue.fix {
+                Some(fix) => fix,
+                None => continue,
+            };
+            let diagnostic = match requested.iter().find(|diagnostic| {
+                diagnostic.range.start.line as usize + 1 == issue.line
+                    && diagnostic.code == Some(NumberOrString::String(issue.rule.clone()))
+            }) {
+                Some(diagnostic) => (*diagnostic).clone(),
+                None => continue,
+            };
+
+            let edits = fix
+                .edits
+                .iter()
+                .map(|edit| TextEdit {
+                    range: Range {
+                        start: Self::lint_position(&lines, edit.start_line, edit.start_column),
+                        end: Self::lint_position(&lines, edit.end_line, edit.end_column),
+                    },
+                    new_text: edit.new_text.clone(),
+                })
+                .collect();
+            let mut changes = HashMap::new();
+            changes.insert(uri.clone(), edits);
+
+            actions.push(CodeAction {
+                title: fix.description,
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(changes),
+                    document_changes: None,
+                    change_annotations: None,
+                }),
+                command: None,
+                is_preferred: Some(true),
+                disabled: None,
+                data: None,
+            });
+        }
+
+        actions
+    }
+
+    /// Lint a document with the enclosing project's configuration
+    fn run_lint(uri: &Url, text: &str) -> Vec<LintIssue> {
+        let path = uri
+            .to_file_path()
+            .unwrap_or_else(|_| PathBuf::from(uri.path()));
+        let options = Self::find_project(&path)
+            .and_then(|project| load_lint_config(&project.root).ok())
+            .unwrap_or_default();
+
+        RuleRegistry::with_builtin_rules().run(&path, text, &options.rules)
+    }
+
+    /// Convert a 1-based line and character column from the linter to an LSP position
+    fn lint_position(lines: &[&str], line: usize, column: usize) -> Position {
+        let line = line.saturating_sub(1);
+        let character = lines
+            .get(line)
+            .map(|content| {
+                content
+                    .chars()
+                    .take(column.saturating_sub(1))
+                    .map(char::len_utf16)
+                    .sum::<usize>()
+            })
+            .unwrap_or(0);
+        Position {
+            line: line as u32,
+            character: character as u32,
+        }
+    }
+
+    /// Resolve imports and type check a parsed program, the same way `lang run` does
+    fn check_program(&self, uri: &Url, ast: &mut crate::ast::nodes::Program) -> crate::error::Result<()> {
+        let mut symbol_resolver = SymbolResolver::new();
+        if let Ok(path) = uri.to_file_path() {
+            symbol_resolver.set_current_module(path.to_string_lossy().to_string());
+            if let Some(parent_dir) = path.parent() {
+                symbol_resolver
+                    .module_resolver_mut()
+                    .set_current_dir(parent_dir.to_path_buf());
+            }
+        }
+        symbol_resolver.resolve_program(ast)?;
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.import_symbols_from_resolver(&symbol_resolver);
+        type_checker.add_builtin_functions_after_import();
+        type_checker.add_std_types();
+        type_checker.check(ast)
+    }
+
+    /// Build "add import" quick fixes for every unresolved identifier diagnostic
+    pub fn import_code_actions(&self, uri: &Url, text: &str, diagnostics: &[Diagnostic]) -> Vec<CodeAction> {
+        let mut actions = Vec::new();
+
+        for diagnostic in diagnostics {
+            let name = match Self::undefined_symbol_name(&diagnostic.message) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            let mut candidates = self.find_import_candidates(uri, name);
+            // Builtins are only missing from files without the prelude
+            if diagnostic.message == crate::std::builtin::not_imported_message(name) {
+                candidates.insert(
+                    0,
+                    ImportCandidate {
+                        name: name.to_string(),
+                        module_path: "std/builtin".to_string(),
+                    },
+                );
+            }
+
+            for (index, candidate) in candidates.into_iter().enumerate() {
+                // Add the name to an import from the same module, if there is one
+                let (position, new_text) = match Self::existing_import_end(text, &candidate.module_path) {
+                    Some(position) => (position, format!(", {}", candidate.name)),
+                    None => (
+                        Position { line: Self::import_insertion_line(text), character: 0 },
+                        format!(
+                            "import {{ {} }} from \"{}\"\n",
+                            candidate.name, candidate.module_path
+                        ),
+                    ),
+                };
+                let mut changes = HashMap::new();
+                changes.insert(
+                    uri.clone(),
+                    vec![TextEdit {
+                        range: Range { start: position, end: position },
+                        new_text,
+                    }],
+                );
+
+                actions.push(CodeAction {
+                    title: format!(
+                        "Import '{}' from \"{}\"",
+                        candidate.name, candidate.module_path
+                    ),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![diagnostic.clone()]),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(changes),
+                        document_changes: None,
+                        change_annotations: None,
+                    }),
+                    command: None,
+                    is_preferred: Some(index == 0),
+                    disabled: None,
+                    data: None,
+                });
+            }
+        }
+
+        actions
+    }
+
+    /// Extract the symbol name from an "Undefined identifier 'x'" style message,
+    /// or from the error for a builtin that was not imported
+    pub fn undefined_symbol_name(message: &str) -> Option<&str> {
+        if let Some((name, _)) = message.strip_prefix('\'').and_then(|rest| rest.split_once('\'')) {
+            if message == crate::std::builtin::not_imported_message(name) {
+                return Some(name);
+            }
+        }
+        let rest = ["Undefined identifier '", "Undefined variable '", "Undefined function '"]
+            .iter()
+            .find_map(|prefix| message.strip_prefix(prefix))?;
+        let end = rest.find('\'')?;
+        if end == 0 {
+            return None;
+        }
+        Some(&rest[..end])
+    }
+
+    /// Search workspace modules and the standard library for modules exporting `name`.
+    /// Workspace modules are listed first, sorted by import path.
+    pub fn find_import_candidates(&self, uri: &Url, name: &str) -> Vec<ImportCandidate> {
+        let resolver = ModuleResolver::new();
+        let mut candidates = Vec::new();
+
+        if let Ok(current_file) = uri.to_file_path() {
+            let mut sources: HashMap<PathBuf, Option<(i32, String)>> = HashMap::new();
+
+            // Files on disk in the enclosing project
+            if let Some(project) = Self::find_project(&current_file) {
+                if let Ok(files) = project.source_files() {
+                    for file in files {
+                        sources.insert(file.canonicalize().unwrap_or(file), None);
+                    }
+                }
+            }
+
+            // Open documents take precedence over their on-disk contents
+            for entry in self.documents.iter() {
+                if let Ok(path) = entry.value().uri.to_file_path() {
+                    sources.insert(
+                        path.canonicalize().unwrap_or(path),
+                        Some((entry.value().version, entry.value().text.clone())),
+                    );
+                }
+            }
+
+            let current_file = current_file.canonicalize().unwrap_or(current_file);
+            let mut workspace = Vec::new();
+            for (path, document) in sources {
+                if path == current_file || path.extension().and_then(|ext| ext.to_str()) != Some("bu") {
+                    continue;
+                }
+
+                let exports = match document {
+                    Some((version, text)) => self.cached_exports(&path, SourceStamp::Document(version), || {
+                        resolver.load_module_from_source(&path, &text)
+                    }),
+                    None => {
+                        let modified = path.metadata().and_then(|meta| meta.modified()).ok();
+                        self.cached_exports(&path, SourceStamp::Disk(modified), || {
+                            resolver.load_module(&path.to_string_lossy())
+                        })
+                    }
+                };
+
+                if exports.contains(name) {
+                    if let Some(module_path) = Self::relative_import_path(&current_file, &path) {
+                        workspace.push(ImportCandidate {
+                            name: name.to_string(),
+                            module_path,
+                        });
+                    }
+                }
+            }
+            workspace.sort_by(|a, b| a.module_path.cmp(&b.module_path));
+            candidates.extend(workspace);
+        }
+
+        for std_module in ModuleResolver::std_module_names() {
+            // Offered only for the builtins a file without the prelude uses
+            if std_module == "builtin" {
+                continue;
+            }
+            let module_path = format!("std/{}", std_module);
+            let exports = self.cached_exports(Path::new(&module_path), SourceStamp::Std, || {
+                resolver.load_module(&module_path)
+            });
+            if exports.contains(name) {
+                candidates.push(ImportCandidate {
+                    name: name.to_string(),
+                    module_path,
+                });
+            }
+        }
+
+        candidates
+    }
+
+    /// Exported names of the module at `path`, loading it only when the
+    /// cached names were read from a different revision of its source.
+    /// Modules that fail to load export nothing.
+    fn cached_exports(
+        &self,
+        path: &Path,
+        stamp: SourceStamp,
+        load: impl FnOnce() -> crate::error::Result<Module>,
+    ) -> Arc<HashSet<String>> {
+        if let Some(cached) = self.exports.get(path) {
+            if cached.stamp == stamp {
+                return cached.names.clone();
+            }
+        }
+
+        let names: Arc<HashSet<String>> = Arc::new(
+            load()
+                .map(|module| module.exports.into_keys().collect())
+                .unwrap_or_default(),
+        );
+        self.exports.insert(
+            path.to_path_buf(),
+            CachedExports {
+                stamp,
+                names: names.clone(),
+            },
+        );
+        names
+    }
+
+    /// Find the project (directory with a lang.toml) containing `file`
+    fn find_project(file: &Path) -> Option<Project> {
+        file.ancestors()
+            .skip(1)
+            .find(|dir| dir.join("lang.toml").exists())
+            .and_then(|dir| Project::load_from_path(dir).ok())
+    }
+
+    /// Compute a `./` or `../` relative import path from `from_file` to `to_file`, without extension
+    fn relative_import_path(from_file: &Path, to_file: &Path) -> Option<String> {
+        let from_dir: Vec<Component> = from_file.parent()?.components().collect();
+        let target = to_file.with_extension("");
+        let to: Vec<Component> = target.components().collect();
+
+        let common = from_dir
+            .iter()
+            .zip(to.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let mut parts: Vec<String> = Vec::new();
+        for _ in common..from_dir.len() {
+            parts.push("..".to_string());
+        }
+        for component in &to[common..] {
+            parts.push(component.as_os_str().to_string_lossy().to_string());
+        }
+
+        if parts.first().is_some_and(|p| p == "..") {
+            Some(parts.join("/"))
+        } else {
+            Some(format!("./{}", parts.join("/")))
+        }
+    }
+
+    /// Position just after the last name of a single-line
+    /// `import { ... } from "module_path"`, where another name can be added
+    fn existing_import_end(text: &str, module_path: &str) -> Option<Position> {
+        let from_clause = format!("}} from \"{}\"", module_path);
+        text.lines().enumerate().find_map(|(index, content)| {
+            let rest = content.trim_start().strip_prefix("import")?;
+            if !rest.trim_start().starts_with('{') {
+                return None;
+            }
+            let close = content.find(&from_clause)?;
+            let names_end = content[..close].trim_end().len();
+            if content[..names_end].ends_with('{') {
+                return None;
+            }
+            Some(Position {
+                line: index as u32,
+                character: content[..names_end].encode_utf16().count() as u32,
+            })
+        })
+    }
+
+    /// Line where a new import should go: after any leading imports, otherwise the top of the file
+    fn import_insertion_line(text: &str) -> u32 {
+        let mut line = 0;
+        for (index, content) in text.lines().enumerate() {
+            let trimmed = content.trim_start();
+            if trimmed.starts_with("import ") {
+                line = index as u32 + 1;
+            } else if !trimmed.is_empty() && !trimmed.starts_with("//") {
+                break;
+            }
+        }
+        line
+    }
+
     /// Convert BuluError to LSP Diagnostic
     fn error_to_diagnostic(&self, error: &BuluError, severity: DiagnosticSeverity) -> Diagnostic {
         let (line, column, message) = match error {
@@ -0,0 +1,83 @@
+use dashmap::DashMap;
+use std::sync::Arc;
+use tower_lsp::jsonrpc::Result;
+use tower_lsp::lsp_types::*;
+
+use crate::formatter::{FormatOptions, Formatter, IndentStyle};
+
+use super::backend::DocumentState;
+
+/// Provides document range formatting
+pub struct FormattingProvider {
+    documents: Arc<DashMap<String, DocumentState>>,
+}
+
+impl FormattingProvider {
+    pub fn new(documents: Arc<DashMap<String, DocumentState>>) -> Self {
+        Self { documents }
+    }
+
+    pub async fn range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri.to_string();
+
+        let doc = match self.documents.get(&uri) {
+            Some(doc) => doc.clone(),
+            None => return Ok(None),
+        };
+
+        Ok(Self::range_edits(&doc.text, params.range, &params.options))
+    }
+
+    /// Compute the edit that reformats the lines covered by `range`
+    pub fn range_edits(text: &str, range: Range, options: &FormattingOptions) -> Option<Vec<TextEdit>> {
+        let line_count = text.lines().count();
+        if line_count == 0 {
+            return None;
+        }
+
+        let start = range.start.line as usize;
+        // A selection ending at column 0 does not include that line
+        let mut end = range.end.line as usize;
+        if range.end.character == 0 && end > start {
+            end -= 1;
+        }
+        if start >= line_count {
+            return None;
+        }
+        let end = end.min(line_count - 1);
+
+        let mut format_options = FormatOptions::default();
+        format_options.config.indent_size = options.tab_size as usize;
+        format_options.config.indent_style = if options.insert_spaces {
+            IndentStyle::Spaces
+        } else {
+            IndentStyle::Tabs
+        };
+
+        let formatter = Formatter::for_source(format_options);
+        let formatted = formatter.format_range(text, start, end).ok()?;
+
+        let last_line = text.lines().nth(end).unwrap_or("");
+        let original: Vec<&str> = text.lines().skip(start).take(end - start + 1).collect();
+        if original.join("\n") == formatted {
+            return Some(Vec::new());
+        }
+
+        Some(vec![TextEdit {
+            range: Range {
+                start: Position {
+                    line: start as u32,
+                    character: 0,
+                },
+                end: Position {
+                    line: end as u32,
+                    character: last_line.encode_utf16().count() as u32,
+                },
+            },
+            new_text: formatted,
+        }])
+    }
+}
@@ -148,6 +148,8 @@ impl HoverProvider {
             "close" => Some("```bulu\nfunc close(ch: chan T)\n```\nClose a channel".to_string()),
             "panic" => Some("```bulu\nfunc panic(message: string)\n```\nTrigger a panic with message".to_string()),
             "typeof" => Some("```bulu\nfunc typeof(x: any): string\n```\nGet type name as string".to_string()),
+            "intrinsic" => Some("```bulu\nfunc intrinsic(name: string, args: ...int64): int64\n```\nEmit the machine instruction `name` inline, e.g. `intrinsic(\"popcount\", x)`; native builds only".to_string()),
+            "hash" => Some("```bulu\nfunc hash(x: any, seed: int64): int64\n```\nNon-negative hash of a value; the same in every run when seeded".to_string()),
             
             // Types
             "int32" => Some("```bulu\nint32\n```\n32-bit signed integer (-2,147,483,648 to 2,147,483,647)".to_string()),
@@ -2,6 +2,7 @@
 pub mod backend;
 pub mod completion;
 pub mod diagnostics;
+pub mod formatting;
 pub mod hover;
 pub mod navigation;
 pub mod refactor;
@@ -197,43 +197,6 @@ impl RefactorProvider {
     }
 
     fn create_quick_fix(&self, doc: &DocumentState, diagnostic: &Diagnostic) -> Option<CodeAction> {
-        // Example: Add missing import
-        if diagnostic.message.contains("undefined") || diagnostic.message.contains("not found") {
-            return Some(CodeAction {
-                title: "Add import statement".to_string(),
-                kind: Some(CodeActionKind::QUICKFIX),
-                diagnostics: Some(vec![diagnostic.clone()]),
-                edit: Some(WorkspaceEdit {
-                    changes: Some({
-                        let mut changes = HashMap::new();
-                        changes.insert(
-                            doc.uri.clone(),
-                            vec![TextEdit {
-                                range: Range {
-                                    start: Position {
-                                        line: 0,
-                                        character: 0,
-                                    },
-                                    end: Position {
-                                        line: 0,
-                                        character: 0,
-                                    },
-                                },
-                                new_text: "import std.io\n".to_string(),
-                            }],
-                        );
-                        changes
-                    }),
-                    document_changes: None,
-                    change_annotations: None,
-                }),
-                command: None,
-                is_preferred: Some(true),
-                disabled: None,
-                data: None,
-            });
-        }
-
         // Example: Fix unused variable
         if diagnostic.message.contains("unused") {
             return Some(CodeAction {
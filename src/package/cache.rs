@@ -0,0 +1,299 @@
+//! Content-addressed cache of downloaded package tarballs
+//!
+//! Tarballs are stored by their sha256 under `store/sha256/<ab>/<sha256>`, so
+//! a package is found by the checksum `lang.lock` records for it, and every
+//! read checks the bytes against the name they are stored under. The index
+//! under `index/<name>@<version>` remembers which checksum each downloaded
+//! version had, for listing the cache.
+//!
+//! Entries whose bytes no longer match their checksum are moved to
+//! `quarantine/` instead of being served or silently deleted; `lang cache
+//! verify` checks the whole store and `lang cache clean` empties it.
+
+use super::{validate_version, PackageName};
+use crate::{BuluError, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A version recorded in the cache index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    pub name: String,
+    pub version: String,
+    pub checksum: String,
+    /// Size of the stored tarball, `None` when it is no longer in the store
+    pub size: Option<u64>,
+}
+
+/// Outcome of `PackageCache::verify`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Tarballs whose bytes match their checksum
+    pub verified: usize,
+    /// Checksums of the tarballs moved to quarantine
+    pub quarantined: Vec<String>,
+}
+
+/// The package cache rooted at a directory, usually `package.cache_dir`
+#[derive(Debug, Clone)]
+pub struct PackageCache {
+    root: PathBuf,
+}
+
+impl PackageCache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn store_dir(&self) -> PathBuf {
+        self.root.join("store").join("sha256")
+    }
+
+    fn index_dir(&self) -> PathBuf {
+        self.root.join("index")
+    }
+
+    fn quarantine_dir(&self) -> PathBuf {
+        self.root.join("quarantine")
+    }
+
+    /// Where the tarball with `checksum` is stored
+    fn blob_path(&self, checksum: &str) -> Result<PathBuf> {
+        if checksum.len() != 64 || !checksum.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(BuluError::Other(format!("Invalid sha256 checksum '{}'", checksum)));
+        }
+        let checksum = checksum.to_ascii_lowercase();
+        Ok(self.store_dir().join(&checksum[..2]).join(checksum))
+    }
+
+    /// Scoped packages are indexed under a directory named after their scope.
+    /// The name and version are validated first, so neither can lead out of
+    /// the index directory.
+    fn index_path(&self, name: &str, version: &str) -> Result<PathBuf> {
+        let name = PackageName::parse(name).map_err(BuluError::Other)?;
+        validate_version(version).map_err(BuluError::Other)?;
+        Ok(self.index_dir().join(format!("{}@{}", name, version)))
+    }
+
+    /// The tarball with `checksum`, if it is cached and intact. A corrupted
+    /// tarball is quarantined and reported as missing.
+    pub fn get(&self, checksum: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.blob_path(checksum)?;
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(BuluError::Other(format!("Failed to read {}: {}", path.display(), e))),
+        };
+        if !sha256::digest(bytes.as_slice()).eq_ignore_ascii_case(checksum) {
+            self.quarantine(&path)?;
+            return Ok(None);
+        }
+        Ok(Some(bytes))
+    }
+
+    /// Checksum the index records for a version
+    pub fn checksum_of(&self, name: &str, version: &str) -> Option<String> {
+        let checksum = fs::read_to_string(self.index_path(name, version).ok()?).ok()?;
+        Some(checksum.trim().to_string())
+    }
+
+    /// Store the tarball of a version and return its checksum
+    pub fn insert(&self, name: &str, version: &str, bytes: &[u8]) -> Result<String> {
+        let index_path = self.index_path(name, version)?;
+        let checksum = sha256::digest(bytes);
+        let path = self.blob_path(&checksum)?;
+        if !path.exists() {
+            let dir = path.parent().expect("blob path has a parent");
+            fs::create_dir_all(dir)
+                .map_err(|e| BuluError::Other(format!("Failed to create cache directory: {}", e)))?;
+            // Written aside and renamed, so that a reader never sees half a tarball
+            let partial = dir.join(format!("{}.partial-{}", checksum, std::process::id()));
+            fs::write(&partial, bytes)
+                .and_then(|()| fs::rename(&partial, &path))
+                .map_err(|e| BuluError::Other(format!("Failed to write cached tarball: {}", e)))?;
+        }
+
+        fs::create_dir_all(index_path.parent().expect("index path has a parent"))
+            .map_err(|e| BuluError::Other(format!("Failed to create cache directory: {}", e)))?;
+        fs::write(&index_path, format!("{}\n", checksum))
+            .map_err(|e| BuluError::Other(format!("Failed to write cache index: {}", e)))?;
+        Ok(checksum)
+    }
+
+    /// The versions in the index, sorted by name and version
+    pub fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+        for path in files_under(&self.index_dir())? {
+            let relative = path.strip_prefix(self.index_dir()).expect("indexed under the index");
+            let relative = relative.to_string_lossy().replace(std::path::MAIN_SEPARATOR, "/");
+            let Some((name, version)) = relative.rsplit_once('@').filter(|(name, _)| !name.is_empty()) else {
+                continue;
+            };
+            let Ok(checksum) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let checksum = checksum.trim().to_string();
+            let size = self
+                .blob_path(&checksum)
+                .ok()
+                .and_then(|blob| fs::metadata(blob).ok())
+                .map(|metadata| metadata.len());
+            entries.push(CacheEntry {
+                name: name.to_string(),
+                version: version.to_string(),
+                checksum,
+                size,
+            });
+        }
+        entries.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+        Ok(entries)
+    }
+
+    /// Check every stored tarball against its checksum, quarantining those
+    /// that do not match
+    pub fn verify(&self) -> Result<VerifyReport> {
+        let mut report = VerifyReport::default();
+        for path in files_under(&self.store_dir())? {
+            let checksum = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            // Left behind by an interrupted insert
+            if checksum.contains(".partial-") {
+                let _ = fs::remove_file(&path);
+                continue;
+            }
+            let bytes = fs::read(&path)
+                .map_err(|e| BuluError::Other(format!("Failed to read {}: {}", path.display(), e)))?;
+            if sha256::digest(bytes.as_slice()) == checksum {
+                report.verified += 1;
+            } else {
+                self.quarantine(&path)?;
+                report.quarantined.push(checksum);
+            }
+        }
+        report.quarantined.sort();
+        Ok(report)
+    }
+
+    /// Tarballs moved to quarantine
+    pub fn quarantined(&self) -> Result<Vec<PathBuf>> {
+        let mut paths = files_under(&self.quarantine_dir())?;
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Remove everything from the cache and return the bytes freed
+    pub fn clean(&self) -> Result<u64> {
+        let mut freed = 0;
+        for dir in [self.root.join("store"), self.index_dir(), self.quarantine_dir()] {
+            for path in files_under(&dir)? {
+                freed += fs::metadata(&path).map_or(0, |metadata| metadata.len());
+            }
+            if dir.exists() {
+                fs::remove_dir_all(&dir)
+                    .map_err(|e| BuluError::Other(format!("Failed to clear cache: {}", e)))?;
+            }
+        }
+        Ok(freed)
+    }
+
+    /// Move a corrupted tarball out of the store
+    fn quarantine(&self, path: &Path) -> Result<()> {
+        let dir = self.quarantine_dir();
+        fs::create_dir_all(&dir)
+            .map_err(|e| BuluError::Other(format!("Failed to create quarantine directory: {}", e)))?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let stamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis());
+        fs::rename(path, dir.join(format!("{}.{}", name, stamp)))
+            .map_err(|e| BuluError::Other(format!("Failed to quarantine {}: {}", path.display(), e)))
+    }
+}
+
+/// Files under `dir`, recursively; a missing directory has none
+fn files_under(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(BuluError::Other(format!("Failed to read {}: {}", dir.display(), e))),
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_by_checksum_and_quarantine_corruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = PackageCache::new(dir.path());
+
+        let checksum = cache.insert("@acme/http", "1.2.0", b"tarball").unwrap();
+        assert_eq!(checksum, sha256::digest(b"tarball".as_slice()));
+        assert_eq!(cache.get(&checksum).unwrap(), Some(b"tarball".to_vec()));
+        assert_eq!(cache.checksum_of("@acme/http", "1.2.0"), Some(checksum.clone()));
+        assert_eq!(
+            cache.entries().unwrap(),
+            vec![CacheEntry {
+                name: "@acme/http".to_string(),
+                version: "1.2.0".to_string(),
+                checksum: checksum.clone(),
+                size: Some(7),
+            }]
+        );
+
+        // A flipped byte is caught on read and the tarball set aside
+        let other = cache.insert("json", "1.0.0", b"other").unwrap();
+        fs::write(cache.blob_path(&checksum).unwrap(), b"tarbalL").unwrap();
+        assert_eq!(cache.get(&checksum).unwrap(), None);
+        assert_eq!(cache.quarantined().unwrap().len(), 1);
+        assert_eq!(cache.entries().unwrap()[0].size, None);
+
+        fs::write(cache.blob_path(&other).unwrap(), b"").unwrap();
+        let report = cache.verify().unwrap();
+        assert_eq!(report, VerifyReport { verified: 0, quarantined: vec![other] });
+
+        assert!(cache.get("not-a-checksum").is_err());
+        assert!(cache.clean().unwrap() > 0);
+        assert!(cache.entries().unwrap().is_empty());
+        assert!(cache.quarantined().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_names_and_versions_stay_inside_the_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = PackageCache::new(dir.path().join("cache"));
+
+        let names = ["../../x", "@acme/../x", "a/b", "a\\b", "/etc/x", ".."];
+        for name in names {
+            assert!(cache.insert(name, "1.0.0", b"tarball").is_err(), "{}", name);
+            assert_eq!(cache.checksum_of(name, "1.0.0"), None);
+        }
+        let versions = ["1.0.0/../../x", "../1.0.0", "1.0.0-a/b", "1.0.0\\x", "/1.0.0", ""];
+        for version in versions {
+            assert!(cache.insert("json", version, b"tarball").is_err(), "{}", version);
+        }
+        // Nothing was written, not even to the store
+        assert!(!dir.path().join("cache").exists());
+        assert!(!dir.path().join("x").exists());
+
+        cache.insert("json", "1.0.0-beta.1+build-5", b"tarball").unwrap();
+        assert_eq!(cache.entries().unwrap()[0].version, "1.0.0-beta.1+build-5");
+    }
+}
@@ -2,13 +2,14 @@
 
 use super::lockfile::{LockFile, LockFileManager, RootPackageInfo};
 use super::registry::RegistryClient;
-use super::resolver::{ConflictStrategy, DependencyResolver};
+use super::resolver::{DependencyResolver, ResolutionMode};
 use super::vendor::{VendorManager, VendorOptions};
 use super::{PackageConfig, PackageMetadata, VersionConstraint};
 use crate::project::{DependencySpec, Project, ProjectConfig};
 use crate::{BuluError, Result};
 use colored::*;
 use std::fs;
+use tracing::{error, info, warn, Level};
 
 /// Package manager for handling all package operations
 pub struct PackageManager {
@@ -21,17 +22,18 @@ pub struct PackageManager {
 /// Options for package operations
 #[derive(Debug, Clone)]
 pub struct PackageOptions {
-    pub verbose: bool,
     pub dry_run: bool,
     pub force: bool,
+    /// `--locked`, `--frozen` and `--minimal-versions`, on top of lang.toml's `[resolver]`
+    pub resolution: ResolutionMode,
 }
 
 impl Default for PackageOptions {
     fn default() -> Self {
         Self {
-            verbose: false,
             dry_run: false,
             force: false,
+            resolution: ResolutionMode::default(),
         }
     }
 }
@@ -39,7 +41,7 @@ impl Default for PackageOptions {
 impl PackageManager {
     /// Create a new package manager
     pub fn new(project: Project) -> Result<Self> {
-        let config = PackageConfig::default();
+        let config = crate::config::Config::load(Some(&project.root))?.package_config()?;
         let registry = RegistryClient::new(config.clone());
         let lock_manager = LockFileManager::new(&project.root);
 
@@ -58,9 +60,9 @@ impl PackageManager {
         version_spec: Option<&str>,
         options: &PackageOptions,
     ) -> Result<()> {
-        if options.verbose {
-            println!("{} Adding dependency: {}", "Adding".green().bold(), name);
-        }
+        info!("{} Adding dependency: {}", "Adding".green().bold(), name);
+
+        self.resolution_mode(options).check_online()?;
 
         // Parse version specification
         let dependency_spec = if let Some(version) = version_spec {
@@ -82,33 +84,21 @@ impl PackageManager {
         let mut config = self.project.config.clone();
         config.dependencies.insert(name.to_string(), dependency_spec);
 
-        // Resolve dependencies
-        let mut resolver = DependencyResolver::new(self.registry.clone());
-        let resolved = resolver.resolve_dependencies(&config.dependencies, ConflictStrategy::HighestCompatible).await?;
-
-        // Update lock file
-        let root_package = RootPackageInfo {
-            name: config.package.name.clone(),
-            version: config.package.version.clone(),
-        };
-        let lock_file = LockFile::from_resolved_dependencies(&resolved, Some(root_package));
+        // Resolve dependencies and update lock file
+        let lock_file = self.resolve_lock_file(&config, options).await?;
         self.lock_manager.save(&lock_file)?;
 
         // Save updated project configuration
         self.save_project_config(&config)?;
 
-        if options.verbose {
-            println!("{} Added dependency: {}", "Success".green().bold(), name);
-        }
+        info!("{} Added dependency: {}", "Success".green().bold(), name);
 
         Ok(())
     }
 
     /// Remove a dependency from the project
     pub async fn remove_dependency(&mut self, name: &str, options: &PackageOptions) -> Result<()> {
-        if options.verbose {
-            println!("{} Removing dependency: {}", "Removing".red().bold(), name);
-        }
+        info!("{} Removing dependency: {}", "Removing".red().bold(), name);
 
         if !self.project.config.dependencies.contains_key(name) {
             return Err(BuluError::Other(format!("Dependency {} not found", name)));
@@ -123,33 +113,21 @@ impl PackageManager {
         let mut config = self.project.config.clone();
         config.dependencies.remove(name);
 
-        // Re-resolve remaining dependencies
-        let mut resolver = DependencyResolver::new(self.registry.clone());
-        let resolved = resolver.resolve_dependencies(&config.dependencies, ConflictStrategy::HighestCompatible).await?;
-
-        // Update lock file
-        let root_package = RootPackageInfo {
-            name: config.package.name.clone(),
-            version: config.package.version.clone(),
-        };
-        let lock_file = LockFile::from_resolved_dependencies(&resolved, Some(root_package));
+        // Re-resolve remaining dependencies and update lock file
+        let lock_file = self.resolve_lock_file(&config, options).await?;
         self.lock_manager.save(&lock_file)?;
 
         // Save updated project configuration
         self.save_project_config(&config)?;
 
-        if options.verbose {
-            println!("{} Removed dependency: {}", "Success".green().bold(), name);
-        }
+        info!("{} Removed dependency: {}", "Success".green().bold(), name);
 
         Ok(())
     }
 
     /// Update all dependencies to their latest compatible versions
     pub async fn update_dependencies(&mut self, options: &PackageOptions) -> Result<()> {
-        if options.verbose {
-            println!("{} Updating dependencies...", "Updating".blue().bold());
-        }
+        info!("{} Updating dependencies...", "Updating".blue().bold());
 
         if options.dry_run {
             println!("Would update all dependencies");
@@ -157,58 +135,34 @@ impl PackageManager {
         }
 
         // Re-resolve all dependencies with latest versions
-        let mut resolver = DependencyResolver::new(self.registry.clone());
-        let resolved = resolver.resolve_dependencies(&self.project.config.dependencies, ConflictStrategy::HighestCompatible).await?;
-
-        // Update lock file
-        let root_package = RootPackageInfo {
-            name: self.project.config.package.name.clone(),
-            version: self.project.config.package.version.clone(),
-        };
-        let lock_file = LockFile::from_resolved_dependencies(&resolved, Some(root_package));
+        let lock_file = self.resolve_lock_file(&self.project.config, options).await?;
         self.lock_manager.save(&lock_file)?;
 
-        if options.verbose {
-            println!("{} Updated {} dependencies", "Success".green().bold(), resolved.len());
-        }
+        info!("{} Updated {} dependencies", "Success".green().bold(), lock_file.dependencies.len());
 
         Ok(())
     }
 
     /// Install dependencies from lang.toml
     pub async fn install_dependencies(&mut self, options: &PackageOptions) -> Result<()> {
-        if options.verbose {
-            println!("{} Installing dependencies...", "Installing".blue().bold());
-        }
-
-        // Check if lock file exists and is up to date
-        let lock_file = if self.lock_manager.exists() {
-            let existing_lock = self.lock_manager.load_or_create()?;
-            if existing_lock.is_up_to_date(&self.project.config.dependencies) {
-                existing_lock
-            } else {
-                // Re-resolve dependencies
-                let mut resolver = DependencyResolver::new(self.registry.clone());
-                let resolved = resolver.resolve_dependencies(&self.project.config.dependencies, ConflictStrategy::HighestCompatible).await?;
-                
-                let root_package = RootPackageInfo {
-                    name: self.project.config.package.name.clone(),
-                    version: self.project.config.package.version.clone(),
-                };
-                LockFile::from_resolved_dependencies(&resolved, Some(root_package))
-            }
-        } else {
-            // Create new lock file
-            let mut resolver = DependencyResolver::new(self.registry.clone());
-            let resolved = resolver.resolve_dependencies(&self.project.config.dependencies, ConflictStrategy::HighestCompatible).await?;
-            
-            let root_package = RootPackageInfo {
-                name: self.project.config.package.name.clone(),
-                version: self.project.config.package.version.clone(),
-            };
-            LockFile::from_resolved_dependencies(&resolved, Some(root_package))
+        info!("{} Installing dependencies...", "Installing".blue().bold());
+
+        // Use the lock file when it is up to date, otherwise re-resolve
+        let mode = self.resolution_mode(options);
+        let current_lock = self
+            .existing_lock_file()?
+            .filter(|lock| lock.is_up_to_date(&self.project.config.dependencies))
+            .filter(|lock| lock.patches_match(&self.project.config.patch));
+        let lock_file = match current_lock {
+            // Minimal versions are only checked when the registry can be reached
+            Some(lock) if mode.offline || !mode.minimal_versions => lock,
+            _ => self.resolve_lock_file(&self.project.config, options).await?,
         };
 
+        for warning in self.project.config.patch_warnings() {
+            warn!("{} {}", "Warning".yellow().bold(), warning);
+        }
+
         if options.dry_run {
             println!("Would install {} dependencies", lock_file.dependencies.len());
             return Ok(());
@@ -217,15 +171,13 @@ impl PackageManager {
         // Save lock file
         self.lock_manager.save(&lock_file)?;
 
-        if options.verbose {
-            println!("{} Installed {} dependencies", "Success".green().bold(), lock_file.dependencies.len());
-        }
+        info!("{} Installed {} dependencies", "Success".green().bold(), lock_file.dependencies.len());
 
         Ok(())
     }
 
     /// List installed dependencies
-    pub async fn list_dependencies(&self, options: &PackageOptions) -> Result<()> {
+    pub async fn list_dependencies(&self, _options: &PackageOptions) -> Result<()> {
         let lock_file = if self.lock_manager.exists() {
             self.lock_manager.load_or_create()?
         } else {
@@ -233,6 +185,8 @@ impl PackageManager {
             return Ok(());
         };
 
+        // `-v` adds where each dependency comes from and what it pulls in
+        let details = tracing::enabled!(Level::DEBUG);
         println!("{}", "Dependencies:".bold());
         
         for (name, spec) in &self.project.config.dependencies {
@@ -243,7 +197,7 @@ impl PackageManager {
                     self.spec_to_string(spec).dimmed()
                 );
                 
-                if options.verbose {
+                if details {
                     println!("    Source: {:?}", locked_dep.source);
                     if !locked_dep.dependencies.is_empty() {
                         println!("    Dependencies: {}", locked_dep.dependencies.join(", "));
@@ -258,7 +212,7 @@ impl PackageManager {
             }
         }
 
-        if options.verbose {
+        if details {
             println!("\nTransitive dependencies:");
             for (name, locked_dep) in &lock_file.dependencies {
                 if !self.project.config.dependencies.contains_key(name) {
@@ -272,7 +226,7 @@ impl PackageManager {
 
     /// Search for packages in the registry
     pub async fn search_packages(&self, query: &str, limit: Option<usize>) -> Result<()> {
-        println!("{} Searching for: {}", "Searching".blue().bold(), query);
+        info!("{} Searching for: {}", "Searching".blue().bold(), query);
         
         let results = self.registry.search(query, limit).await?;
         
@@ -304,9 +258,7 @@ impl PackageManager {
 
     /// Publish a package to the registry
     pub async fn publish_package(&self, options: &PackageOptions) -> Result<()> {
-        if options.verbose {
-            println!("{} Publishing package: {}", "Publishing".blue().bold(), self.project.config.package.name);
-        }
+        info!("{} Publishing package: {}", "Publishing".blue().bold(), self.project.config.package.name);
 
         // Create package tarball
         let tarball = self.create_package_tarball()?;
@@ -342,27 +294,23 @@ impl PackageManager {
         // Publish to registry
         self.registry.publish_package(&metadata, tarball).await?;
 
-        if options.verbose {
-            println!("{} Published: {} v{}", "Success".green().bold(), metadata.name, metadata.version);
-        }
+        info!("{} Published: {} v{}", "Success".green().bold(), metadata.name, metadata.version);
 
         Ok(())
     }
 
     /// Vendor dependencies
     pub async fn vendor_dependencies(&self, options: &PackageOptions) -> Result<()> {
-        if options.verbose {
-            println!("{} Vendoring dependencies...", "Vendoring".blue().bold());
-        }
+        info!("{} Vendoring dependencies...", "Vendoring".blue().bold());
 
         let lock_file = self.lock_manager.load_or_create()?;
-        let vendor_manager = VendorManager::new(&self.project.root, self.registry.clone());
+        let vendor_manager = VendorManager::new(&self.project.root, self.registry.clone())
+            .with_vendor_dir(self.project.root.join(&self.config.vendor_dir));
         
         let vendor_options = VendorOptions {
             update_existing: options.force,
             verify_checksums: true,
             include_dev_deps: false,
-            verbose: options.verbose,
         };
 
         if options.dry_run {
@@ -375,22 +323,18 @@ impl PackageManager {
 
         if !result.errors.is_empty() {
             for error in &result.errors {
-                eprintln!("{} {}", "Error:".red().bold(), error);
+                error!("{} {}", "Error:".red().bold(), error);
             }
         }
 
-        if options.verbose {
-            println!("{} Vendored {} dependencies", "Success".green().bold(), result.vendored.len());
-        }
+        info!("{} Vendored {} dependencies", "Success".green().bold(), result.vendored.len());
 
         Ok(())
     }
 
     /// Clean build artifacts and caches
     pub fn clean(&self, options: &PackageOptions) -> Result<()> {
-        if options.verbose {
-            println!("{} Cleaning project...", "Cleaning".blue().bold());
-        }
+        info!("{} Cleaning project...", "Cleaning".blue().bold());
 
         let mut cleaned_items = Vec::new();
 
@@ -422,13 +366,45 @@ impl PackageManager {
             cleaned_items.push("package cache");
         }
 
-        if options.verbose && !options.dry_run {
-            println!("{} Cleaned: {}", "Success".green().bold(), cleaned_items.join(", "));
+        if !options.dry_run {
+            info!("{} Cleaned: {}", "Success".green().bold(), cleaned_items.join(", "));
         }
 
         Ok(())
     }
 
+    /// Helper: Resolution mode from the options and the project's `[resolver]` section
+    fn resolution_mode(&self, options: &PackageOptions) -> ResolutionMode {
+        options.resolution.with_config(&self.project.config.resolver)
+    }
+
+    /// Helper: Load lang.lock if it exists
+    fn existing_lock_file(&self) -> Result<Option<LockFile>> {
+        if self.lock_manager.exists() {
+            self.lock_manager.load_or_create().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Helper: Resolve the dependencies of `config` into a new lock file,
+    /// honoring the project's `[patch]` section and the resolution mode
+    async fn resolve_lock_file(&self, config: &ProjectConfig, options: &PackageOptions) -> Result<LockFile> {
+        let mode = self.resolution_mode(options);
+        mode.check_online()?;
+
+        let mut resolver = DependencyResolver::new(self.registry.clone()).with_patches(config.patch.clone());
+        let resolved = resolver.resolve_dependencies(&config.dependencies, mode.strategy()).await?;
+
+        let root_package = RootPackageInfo {
+            name: config.package.name.clone(),
+            version: config.package.version.clone(),
+        };
+        let lock_file = LockFile::from_resolved_dependencies(&resolved, Some(root_package));
+        mode.check_lock(self.existing_lock_file()?.as_ref(), &lock_file)?;
+        Ok(lock_file)
+    }
+
     /// Helper: Convert DependencySpec to string
     fn spec_to_string(&self, spec: &DependencySpec) -> String {
         match spec {
@@ -529,6 +505,7 @@ mod tests {
             tag: None,
             features: None,
             optional: None,
+            registry: None,
         };
         let detailed_path = DependencySpec::Detailed {
             version: None,
@@ -538,6 +515,7 @@ mod tests {
             tag: None,
             features: None,
             optional: None,
+            registry: None,
         };
 
         // Create a temporary project for testing
@@ -552,10 +530,15 @@ mod tests {
                 repository: None,
                 keywords: None,
                 categories: None,
+                prelude: true,
             },
             dependencies: std::collections::HashMap::new(),
             build: crate::project::BuildConfig::default(),
             test: crate::project::TestConfig::default(),
+            registries: std::collections::BTreeMap::new(),
+            scopes: std::collections::BTreeMap::new(),
+            patch: std::collections::BTreeMap::new(),
+            resolver: crate::project::ResolverConfig::default(),
         };
 
         // This test would need a proper project setup to work fully
@@ -1,5 +1,6 @@
 //! HTTP client for communicating with the Bulu package registry
 
+use super::name::encode_for_url;
 use super::{PackageMetadata, VersionConstraint};
 use crate::{BuluError, Result};
 use serde::{Deserialize, Serialize};
@@ -10,6 +11,11 @@ use std::collections::HashMap;
 pub struct RegistryHttpClient {
     base_url: String,
     client: reqwest::Client,
+    /// Sent as a bearer token with every request; private registries and
+    /// publishing scoped packages require it
+    token: Option<String>,
+    /// Tried in order when `base_url` is unreachable; publishing never falls back
+    mirrors: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,6 +29,9 @@ pub struct PublishRequest {
     pub keywords: Vec<String>,
     pub dependencies: HashMap<String, String>,
     pub tarball: Vec<u8>, // Raw bytes
+    /// The version's section of CHANGELOG.md
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_notes: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -85,9 +94,31 @@ pub struct SearchPackage {
     pub version: String,
     pub description: Option<String>,
     pub downloads: u64,
+    /// Downloads over the last week, from registries that count them per day
+    #[serde(default)]
+    pub recent_downloads: Option<u64>,
     pub updated_at: String,
 }
 
+/// What the registry stored of a version when it was published
+#[derive(Debug, Deserialize)]
+pub struct PackageReadme {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub license: Option<String>,
+    pub readme: Option<String>,
+    /// The version's `lang.toml`
+    pub manifest: Option<String>,
+}
+
+impl PackageReadme {
+    /// The version's `lang.toml`, if it has one that parses
+    pub fn project_config(&self) -> Option<crate::project::ProjectConfig> {
+        toml::from_str(self.manifest.as_deref()?).ok()
+    }
+}
+
 impl RegistryHttpClient {
     /// Create a new HTTP client
     pub fn new(base_url: String) -> Self {
@@ -96,16 +127,67 @@ impl RegistryHttpClient {
             .build()
             .unwrap();
 
-        Self { base_url, client }
+        Self { base_url, client, token: None, mirrors: Vec::new() }
+    }
+
+    /// Authenticate requests with `token`
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Fall back to `mirrors`, in order, when the registry is unreachable
+    pub fn with_mirrors(mut self, mirrors: Vec<String>) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
+    /// The registry's URL, then those of its mirrors
+    fn base_urls(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.base_url.as_str()).chain(self.mirrors.iter().map(String::as_str))
+    }
+
+    /// A client for `base_url` alone, with this one's token
+    fn without_mirrors(&self, base_url: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            client: self.client.clone(),
+            token: self.token.clone(),
+            mirrors: Vec::new(),
+        }
+    }
+
+    /// Send the request `request` builds for a base URL to the registry,
+    /// moving on to the next mirror while connections fail or servers answer
+    /// with a 5xx status. The last one's answer is returned whatever it is.
+    async fn send(&self, request: impl Fn(&str) -> reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+        let last = self.mirrors.len();
+        for (index, base_url) in self.base_urls().enumerate() {
+            match self.authorize(request(base_url)).send().await {
+                Ok(response) if index < last && response.status().is_server_error() => {
+                    tracing::warn!("Registry {} answered {}, trying the next mirror", base_url, response.status());
+                }
+                Err(e) if index < last && (e.is_connect() || e.is_timeout()) => {
+                    tracing::warn!("Registry {} is unreachable, trying the next mirror: {}", base_url, e);
+                }
+                result => return result,
+            }
+        }
+        unreachable!("the registry itself is always tried")
+    }
+
+    /// Add the bearer token, if any, to a request
+    fn authorize(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.token {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        }
     }
 
     /// List all packages
     pub async fn list_packages(&self) -> Result<PackageListResponse> {
-        let url = format!("{}/api/packages", self.base_url);
-        
-        let response = self.client
-            .get(&url)
-            .send()
+        let response = self
+            .send(|base_url| self.client.get(format!("{}/api/packages", base_url)))
             .await
             .map_err(|e| BuluError::Other(format!("Failed to list packages: {}", e)))?;
 
@@ -121,11 +203,8 @@ impl RegistryHttpClient {
 
     /// Get package information
     pub async fn get_package(&self, name: &str) -> Result<PackageInfo> {
-        let url = format!("{}/api/packages/{}", self.base_url, name);
-        
-        let response = self.client
-            .get(&url)
-            .send()
+        let response = self
+            .send(|base_url| self.client.get(format!("{}/api/packages/{}", base_url, encode_for_url(name))))
             .await
             .map_err(|e| BuluError::Other(format!("Failed to get package: {}", e)))?;
 
@@ -147,11 +226,8 @@ impl RegistryHttpClient {
 
     /// Get specific package version info
     pub async fn get_package_version(&self, name: &str, version: &str) -> Result<PackageVersionInfo> {
-        let url = format!("{}/api/packages/{}/{}", self.base_url, name, version);
-        
-        let response = self.client
-            .get(&url)
-            .send()
+        let response = self
+            .send(|base_url| self.client.get(format!("{}/api/packages/{}/{}", base_url, encode_for_url(name), version)))
             .await
             .map_err(|e| BuluError::Other(format!("Failed to get package version: {}", e)))?;
 
@@ -165,13 +241,36 @@ impl RegistryHttpClient {
             .map_err(|e| BuluError::Other(format!("Failed to parse response: {}", e)))
     }
 
+    /// Get the README and manifest of a version, the latest one by default
+    pub async fn get_readme(&self, name: &str, version: Option<&str>) -> Result<PackageReadme> {
+        let response = self
+            .send(|base_url| {
+                let request = self.client.get(format!("{}/api/packages/{}/readme", base_url, encode_for_url(name)));
+                match version {
+                    Some(version) => request.query(&[("version", version)]),
+                    None => request,
+                }
+            })
+            .await
+            .map_err(|e| BuluError::Other(format!("Failed to get package README: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(BuluError::Other(match version {
+                Some(version) => format!("Version not found: {} v{}", name, version),
+                None => format!("Package not found: {}", name),
+            }));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| BuluError::Other(format!("Failed to parse response: {}", e)))
+    }
+
     /// Download package tarball
     pub async fn download_package(&self, name: &str, version: &str) -> Result<Vec<u8>> {
-        let url = format!("{}/api/download/{}/{}", self.base_url, name, version);
-        
-        let response = self.client
-            .get(&url)
-            .send()
+        let response = self
+            .send(|base_url| self.client.get(format!("{}/api/download/{}/{}", base_url, encode_for_url(name), version)))
             .await
             .map_err(|e| BuluError::Other(format!("Failed to download package: {}", e)))?;
 
@@ -186,14 +285,60 @@ impl RegistryHttpClient {
             .map_err(|e| BuluError::Other(format!("Failed to read package data: {}", e)))
     }
 
+    /// Checksum of a version, which the registry and every mirror that answers
+    /// must agree on. Unreachable mirrors are skipped; at least one URL has to answer.
+    pub async fn verified_checksum(&self, name: &str, version: &str) -> Result<String> {
+        let mut agreed: Option<(&str, String)> = None;
+        let mut last_error = None;
+        for base_url in self.base_urls() {
+            let checksum = match self.without_mirrors(base_url).get_package_version(name, version).await {
+                Ok(info) => info.checksum,
+                Err(e) => {
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+            match &agreed {
+                Some((first_url, first)) if *first != checksum => {
+                    return Err(BuluError::Other(format!(
+                        "Checksum mismatch for {} v{}: {} has {}, {} has {}",
+                        name, version, first_url, first, base_url, checksum
+                    )));
+                }
+                Some(_) => {}
+                None => agreed = Some((base_url, checksum)),
+            }
+        }
+        match (agreed, last_error) {
+            (Some((_, checksum)), _) => Ok(checksum),
+            (None, Some(e)) => Err(e),
+            (None, None) => unreachable!("the registry itself is always tried"),
+        }
+    }
+
+    /// Download a version's tarball from the first URL that answers and check
+    /// it against the checksum the registry and its mirrors agree on
+    pub async fn download_verified(&self, name: &str, version: &str) -> Result<Vec<u8>> {
+        use sha2::{Digest, Sha256};
+
+        let expected = self.verified_checksum(name, version).await?;
+        let tarball = self.download_package(name, version).await?;
+        let actual = format!("{:x}", Sha256::digest(&tarball));
+        // Registries that never recorded a checksum publish an empty one
+        if !expected.is_empty() && !expected.eq_ignore_ascii_case(&actual) {
+            return Err(BuluError::Other(format!(
+                "Checksum mismatch for {} v{}: expected {}, downloaded {}",
+                name, version, expected, actual
+            )));
+        }
+        Ok(tarball)
+    }
+
     /// Search for packages
     pub async fn search(&self, query: &str, limit: Option<usize>) -> Result<SearchResponse> {
         let limit = limit.unwrap_or(20);
-        let url = format!("{}/api/search?q={}&limit={}", self.base_url, query, limit);
-        
-        let response = self.client
-            .get(&url)
-            .send()
+        let response = self
+            .send(|base_url| self.client.get(format!("{}/api/search?q={}&limit={}", base_url, query, limit)))
             .await
             .map_err(|e| BuluError::Other(format!("Failed to search: {}", e)))?;
 
@@ -209,11 +354,10 @@ impl RegistryHttpClient {
 
     /// Publish a package
     pub async fn publish(&self, request: PublishRequest) -> Result<()> {
-        let url = format!("{}/api/packages/{}/{}", self.base_url, request.name, request.version);
+        let url = format!("{}/api/packages/{}/{}", self.base_url, encode_for_url(&request.name), request.version);
         
-        let response = self.client
-            .post(&url)
-            .json(&request)
+        let response = self
+            .authorize(self.client.post(&url).json(&request))
             .send()
             .await
             .map_err(|e| BuluError::Other(format!("Network error while publishing to {}: {}", self.base_url, e)))?;
@@ -261,6 +405,96 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_readme_manifest_is_parsed() {
+        let readme: PackageReadme = serde_json::from_str(
+            r##"{"name": "json", "version": "1.1.0", "description": null, "license": "MIT", "readme": "# json",
+                "manifest": "[package]\nname = \"json\"\nversion = \"1.1.0\"\nauthors = []\n\n[build]\nfeatures = [\"simd\"]\n"}"##,
+        )
+        .unwrap();
+        let config = readme.project_config().unwrap();
+        assert_eq!(config.package.name, "json");
+        assert_eq!(config.build.features, vec!["simd"]);
+
+        let without: PackageReadme = serde_json::from_str(
+            r#"{"name": "json", "version": "1.0.0", "description": null, "license": null, "readme": null, "manifest": null}"#,
+        )
+        .unwrap();
+        assert!(without.project_config().is_none());
+    }
+
+    /// Serve `checksum` as the checksum of every version, and the tarball
+    /// `data`, on a local port
+    fn serve(checksum: &str, data: &str) -> String {
+        use std::io::{Read, Write};
+
+        let (checksum, data) = (checksum.to_string(), data.to_string());
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = [0u8; 4096];
+                let read = stream.read(&mut request).unwrap_or(0);
+                let request = String::from_utf8_lossy(&request[..read]);
+                let body = if request.starts_with("GET /api/download/") {
+                    data.clone()
+                } else {
+                    format!(
+                        r#"{{"name": "json", "version": "1.0.0", "description": null, "authors": [], "license": null,
+                            "dependencies": {{}}, "checksum": "{}", "published_at": "2026-01-01T00:00:00Z"}}"#,
+                        checksum
+                    )
+                };
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+            }
+        });
+        url
+    }
+
+    /// A URL nothing listens on
+    fn unreachable_url() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_fall_back_to_mirrors() {
+        use sha2::{Digest, Sha256};
+
+        let checksum = format!("{:x}", Sha256::digest(b"tarball"));
+        let client = RegistryHttpClient::new(unreachable_url())
+            .with_mirrors(vec![unreachable_url(), serve(&checksum, "tarball")]);
+
+        let info = client.get_package_version("json", "1.0.0").await.unwrap();
+        assert_eq!(info.checksum, checksum);
+        assert_eq!(client.download_verified("json", "1.0.0").await.unwrap(), b"tarball");
+
+        // A mirror serving other bytes than the registry recorded is caught
+        let client = RegistryHttpClient::new(serve(&checksum, "tampered"));
+        let err = client.download_verified("json", "1.0.0").await.unwrap_err();
+        assert!(err.to_string().contains("expected"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_mirrors_must_agree_on_checksums() {
+        let client = RegistryHttpClient::new(serve("aaaa", "tarball")).with_mirrors(vec![
+            unreachable_url(),
+            serve("aaaa", "tarball"),
+            serve("bbbb", "tarball"),
+        ]);
+        let err = client.verified_checksum("json", "1.0.0").await.unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch for json v1.0.0"), "{}", err);
+        assert!(err.to_string().contains("has bbbb"), "{}", err);
+
+        let client = RegistryHttpClient::new(unreachable_url()).with_mirrors(vec![serve("aaaa", "tarball")]);
+        assert_eq!(client.verified_checksum("json", "1.0.0").await.unwrap(), "aaaa");
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_search() {
@@ -81,20 +81,40 @@ impl LocalRegistry {
             let entry = entry.map_err(|e| BuluError::Other(format!("Failed to read entry: {}", e)))?;
             let name = entry.file_name().to_string_lossy().to_string();
 
-            if entry.path().is_dir() {
-                for version_entry in fs::read_dir(entry.path())
-                    .map_err(|e| BuluError::Other(format!("Failed to read versions: {}", e)))? 
+            if !entry.path().is_dir() {
+                continue;
+            }
+
+            // Scoped packages live one level deeper, under @scope/name
+            if name.starts_with('@') {
+                for scoped_entry in fs::read_dir(entry.path())
+                    .map_err(|e| BuluError::Other(format!("Failed to read scope: {}", e)))?
                 {
-                    let version_entry = version_entry.map_err(|e| BuluError::Other(format!("Failed to read version: {}", e)))?;
-                    let version = version_entry.file_name().to_string_lossy().to_string();
-                    packages.push((name.clone(), version));
+                    let scoped_entry = scoped_entry.map_err(|e| BuluError::Other(format!("Failed to read entry: {}", e)))?;
+                    if scoped_entry.path().is_dir() {
+                        let scoped_name = format!("{}/{}", name, scoped_entry.file_name().to_string_lossy());
+                        Self::list_versions(&scoped_entry.path(), &scoped_name, &mut packages)?;
+                    }
                 }
+            } else {
+                Self::list_versions(&entry.path(), &name, &mut packages)?;
             }
         }
 
         Ok(packages)
     }
 
+    fn list_versions(package_dir: &Path, name: &str, packages: &mut Vec<(String, String)>) -> Result<()> {
+        for version_entry in fs::read_dir(package_dir)
+            .map_err(|e| BuluError::Other(format!("Failed to read versions: {}", e)))? 
+        {
+            let version_entry = version_entry.map_err(|e| BuluError::Other(format!("Failed to read version: {}", e)))?;
+            let version = version_entry.file_name().to_string_lossy().to_string();
+            packages.push((name.to_string(), version));
+        }
+        Ok(())
+    }
+
     /// Helper: Copy directory recursively
     fn copy_dir_recursive(&self, src: &Path, dst: &Path) -> Result<()> {
         fs::create_dir_all(dst)
@@ -1,9 +1,9 @@
 //! Lock file generation and management for reproducible builds
 
-use super::{ResolvedDependency, DependencySource};
+use super::{PackageName, ResolvedDependency, DependencySource};
 use crate::{BuluError, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::Path;
 
@@ -31,10 +31,13 @@ pub struct LockedDependency {
     pub checksum: Option<String>,
     /// Direct dependencies of this package
     pub dependencies: Vec<String>,
+    /// Source overridden by the project's `[patch]` section
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub patched: bool,
 }
 
 /// Locked source information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum LockedSource {
     #[serde(rename = "registry")]
@@ -104,6 +107,7 @@ impl LockFile {
                     source: locked_source,
                     checksum: resolved.checksum.clone(),
                     dependencies: resolved.dependencies.keys().cloned().collect(),
+                    patched: resolved.patched,
                 };
 
                 (name.clone(), locked_dep)
@@ -139,6 +143,11 @@ impl LockFile {
             )));
         }
 
+        for name in lock_file.dependencies.keys() {
+            PackageName::parse(name)
+                .map_err(|e| BuluError::Other(format!("Invalid lock file: {}", e)))?;
+        }
+
         Ok(lock_file)
     }
 
@@ -171,6 +180,41 @@ impl LockFile {
         project_dep_names.is_subset(&lock_dep_names)
     }
 
+    /// Describe how this lock file differs from `previous`, ignoring metadata
+    pub fn changes_from(&self, previous: &LockFile) -> Vec<String> {
+        let mut names: Vec<&String> = self.dependencies.keys().chain(previous.dependencies.keys()).collect();
+        names.sort();
+        names.dedup();
+
+        names
+            .into_iter()
+            .filter_map(|name| match (previous.dependencies.get(name), self.dependencies.get(name)) {
+                (None, Some(new)) => Some(format!("add {} {}", name, new.version)),
+                (Some(old), None) => Some(format!("remove {} {}", name, old.version)),
+                (Some(old), Some(new)) if old.version != new.version => {
+                    Some(format!("update {} {} -> {}", name, old.version, new.version))
+                }
+                (Some(old), Some(new)) if old.source != new.source || old.patched != new.patched => {
+                    Some(format!("change source of {} {}", name, new.version))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Check that the locked patches are exactly the project's `[patch]` entries
+    /// that appear in the dependency graph
+    pub fn patches_match(&self, patches: &BTreeMap<String, crate::project::DependencySpec>) -> bool {
+        self.dependencies
+            .iter()
+            .all(|(name, locked_dep)| locked_dep.patched == patches.contains_key(name))
+    }
+
+    /// Get patched dependencies
+    pub fn get_patched_dependencies(&self) -> Vec<&LockedDependency> {
+        self.dependencies.values().filter(|dep| dep.patched).collect()
+    }
+
     /// Get dependency resolution order (topological sort)
     pub fn get_resolution_order(&self) -> Result<Vec<String>> {
         let mut visited = std::collections::HashSet::new();
@@ -360,6 +404,7 @@ mod tests {
             },
             dependencies: HashMap::new(),
             checksum: Some("abc123".to_string()),
+            patched: false,
         };
         
         dependencies.insert("test-lib".to_string(), resolved_dep);
@@ -414,6 +459,7 @@ mod tests {
             },
             checksum: Some("c123".to_string()),
             dependencies: vec![],
+            patched: false,
         };
         
         let dep_b = LockedDependency {
@@ -425,6 +471,7 @@ mod tests {
             },
             checksum: Some("b123".to_string()),
             dependencies: vec!["c".to_string()],
+            patched: false,
         };
         
         let dep_a = LockedDependency {
@@ -436,6 +483,7 @@ mod tests {
             },
             checksum: Some("a123".to_string()),
             dependencies: vec!["b".to_string()],
+            patched: false,
         };
 
         dependencies.insert("a".to_string(), dep_a);
@@ -462,4 +510,72 @@ mod tests {
         assert!(c_pos < b_pos);
         assert!(b_pos < a_pos);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_changes_from() {
+        let lock_file = |deps: &[(&str, &str)]| {
+            let dependencies = deps
+                .iter()
+                .map(|(name, version)| {
+                    let resolved = ResolvedDependency {
+                        name: name.to_string(),
+                        version: version.to_string(),
+                        source: DependencySource::Registry {
+                            url: "https://example.com".to_string(),
+                        },
+                        dependencies: HashMap::new(),
+                        checksum: None,
+                        patched: false,
+                    };
+                    (name.to_string(), resolved)
+                })
+                .collect();
+            LockFile::from_resolved_dependencies(&dependencies, None)
+        };
+
+        let old = lock_file(&[("http", "1.2.0"), ("json", "1.0.0")]);
+        assert!(lock_file(&[("json", "1.0.0"), ("http", "1.2.0")]).changes_from(&old).is_empty());
+
+        let new = lock_file(&[("http", "1.3.0"), ("log", "0.4.0")]);
+        assert_eq!(
+            new.changes_from(&old),
+            vec!["update http 1.2.0 -> 1.3.0", "remove json 1.0.0", "add log 0.4.0"]
+        );
+    }
+
+    #[test]
+    fn test_scoped_names_round_trip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("lang.lock");
+
+        let mut dependencies = HashMap::new();
+        dependencies.insert("@acme/http-utils".to_string(), LockedDependency {
+            name: "@acme/http-utils".to_string(),
+            version: "2.1.0".to_string(),
+            source: LockedSource::Registry {
+                url: "https://pkg.lang-lang.org/@acme%2Fhttp-utils/2.1.0".to_string(),
+                checksum: "d00d".to_string(),
+            },
+            checksum: Some("d00d".to_string()),
+            dependencies: vec!["json".to_string()],
+            patched: false,
+        });
+        let lock_file = LockFile {
+            version: "1".to_string(),
+            dependencies,
+            metadata: LockFileMetadata {
+                generated_at: "2023-01-01T00:00:00Z".to_string(),
+                generator: "bulu-lang/1.0.0".to_string(),
+                root_package: None,
+            },
+        };
+
+        let serialized = toml::to_string_pretty(&lock_file).unwrap();
+        fs::write(&path, &serialized).unwrap();
+        let loaded = LockFile::load(&path).unwrap();
+        assert_eq!(loaded.dependencies["@acme/http-utils"].version, "2.1.0");
+
+        fs::write(&path, serialized.replace("@acme/http-utils", "@acme/../escape")).unwrap();
+        assert!(LockFile::load(&path).is_err());
+    }
+}
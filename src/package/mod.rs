@@ -10,6 +10,13 @@ pub mod lockfile;
 pub mod vendor;
 pub mod local_registry;
 pub mod http_client;
+pub mod name;
+pub mod registries;
+pub mod cache;
+
+pub use name::PackageName;
+pub use registries::RegistrySettings;
+pub use cache::PackageCache;
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -116,6 +123,9 @@ pub struct ResolvedDependency {
     pub source: DependencySource,
     pub dependencies: HashMap<String, VersionConstraint>,
     pub checksum: Option<String>,
+    /// Source taken from the project's `[patch]` section
+    #[serde(default)]
+    pub patched: bool,
 }
 
 /// Source of a dependency
@@ -143,6 +153,9 @@ pub enum DependencySource {
 pub struct PackageConfig {
     /// Registry URL
     pub registry_url: String,
+    /// Mirrors of the registry, tried in order when it is unreachable
+    #[serde(default)]
+    pub registry_mirrors: Vec<String>,
     /// Cache directory
     pub cache_dir: PathBuf,
     /// Vendor directory
@@ -155,6 +168,7 @@ impl Default for PackageConfig {
     fn default() -> Self {
         Self {
             registry_url: "https://pkg.lang-lang.org".to_string(),
+            registry_mirrors: Vec::new(),
             cache_dir: dirs::cache_dir()
                 .unwrap_or_else(|| PathBuf::from(".cache"))
                 .join("bulu"),
@@ -176,6 +190,23 @@ fn parse_version(version: &str) -> Result<Vec<u32>, String> {
         .collect()
 }
 
+/// Check that `version` is a dotted numeric version, optionally followed by a
+/// `-prerelease` or `+build` suffix of letters, digits, `.` and `-`
+pub(crate) fn validate_version(version: &str) -> Result<(), String> {
+    let (core, suffix) = match version.find(['-', '+']) {
+        Some(index) => (&version[..index], &version[index + 1..]),
+        None => (version, ""),
+    };
+    parse_version(core).map_err(|e| format!("Invalid version '{}': {}", version, e))?;
+    if let Some(c) = suffix
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+')))
+    {
+        return Err(format!("Invalid version '{}': '{}' is not allowed", version, c));
+    }
+    Ok(())
+}
+
 fn compare_versions(a: &str, b: &str) -> i32 {
     let a_parts = parse_version(a).unwrap_or_default();
     let b_parts = parse_version(b).unwrap_or_default();
@@ -0,0 +1,148 @@
+//! Package names, plain (`http-utils`) or scoped to an organisation (`@acme/http-utils`)
+//!
+//! A scoped name keeps its `/` everywhere on disk, so `@acme/http-utils` is vendored
+//! under `vendor/@acme/http-utils`. In registry URLs the whole name is a single path
+//! segment, with the separator percent-encoded.
+
+use std::fmt;
+
+/// Longest accepted scope or package segment
+pub const MAX_SEGMENT_LEN: usize = 64;
+
+/// A validated package name
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PackageName {
+    /// Organisation the package belongs to, without the leading `@`
+    pub scope: Option<String>,
+    pub name: String,
+}
+
+impl PackageName {
+    /// Parse and validate `name` or `@scope/name`
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let (scope, bare) = match name.strip_prefix('@') {
+            Some(scoped) => {
+                let (scope, bare) = scoped.split_once('/').ok_or_else(|| {
+                    format!("Invalid package name '{}': scoped names look like @scope/name", name)
+                })?;
+                validate_segment(name, "scope", scope)?;
+                if scope.chars().any(|c| c.is_ascii_uppercase()) {
+                    return Err(format!("Invalid package name '{}': scopes must be lowercase", name));
+                }
+                (Some(scope.to_string()), bare)
+            }
+            None => (None, name),
+        };
+        validate_segment(name, "name", bare)?;
+
+        Ok(Self {
+            scope,
+            name: bare.to_string(),
+        })
+    }
+
+    pub fn is_scoped(&self) -> bool {
+        self.scope.is_some()
+    }
+
+    /// The name as a single URL path segment
+    pub fn url_segment(&self) -> String {
+        encode_for_url(&self.to_string())
+    }
+}
+
+impl fmt::Display for PackageName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.scope {
+            Some(scope) => write!(f, "@{}/{}", scope, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Percent-encode the scope separator of a package name so that it stays a single
+/// URL path segment. Plain names are returned unchanged.
+pub fn encode_for_url(name: &str) -> String {
+    name.replace('/', "%2F")
+}
+
+/// Split an import path such as `@acme/http-utils/client` into the package name
+/// and the path inside the package
+pub fn split_module_path(module_path: &str) -> (&str, Option<&str>) {
+    let package_end = if module_path.starts_with('@') {
+        module_path
+            .match_indices('/')
+            .nth(1)
+            .map(|(index, _)| index)
+    } else {
+        module_path.find('/')
+    };
+
+    match package_end {
+        Some(index) => (&module_path[..index], Some(&module_path[index + 1..])),
+        None => (module_path, None),
+    }
+}
+
+fn validate_segment(full: &str, what: &str, segment: &str) -> Result<(), String> {
+    if segment.is_empty() {
+        return Err(format!("Invalid package name '{}': the {} is empty", full, what));
+    }
+    if segment.len() > MAX_SEGMENT_LEN {
+        return Err(format!(
+            "Invalid package name '{}': the {} is longer than {} characters",
+            full, what, MAX_SEGMENT_LEN
+        ));
+    }
+    if !segment.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        return Err(format!(
+            "Invalid package name '{}': the {} must start with a letter or digit",
+            full, what
+        ));
+    }
+    if let Some(c) = segment
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || *c == '-' || *c == '_'))
+    {
+        return Err(format!(
+            "Invalid package name '{}': '{}' is not allowed in a package {}",
+            full, c, what
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plain_and_scoped_names() {
+        let plain = PackageName::parse("http-utils").unwrap();
+        assert_eq!(plain.scope, None);
+        assert_eq!(plain.to_string(), "http-utils");
+        assert_eq!(plain.url_segment(), "http-utils");
+
+        let scoped = PackageName::parse("@acme/http-utils").unwrap();
+        assert_eq!(scoped.scope.as_deref(), Some("acme"));
+        assert_eq!(scoped.name, "http-utils");
+        assert!(scoped.is_scoped());
+        assert_eq!(scoped.to_string(), "@acme/http-utils");
+        assert_eq!(scoped.url_segment(), "@acme%2Fhttp-utils");
+    }
+
+    #[test]
+    fn test_reject_malformed_names() {
+        for name in ["", "@acme", "@acme/", "@/http", "@Acme/http", "@acme/http/extra", "../escape", "a b", "-lead"] {
+            assert!(PackageName::parse(name).is_err(), "{:?} should be rejected", name);
+        }
+    }
+
+    #[test]
+    fn test_split_module_path() {
+        assert_eq!(split_module_path("math"), ("math", None));
+        assert_eq!(split_module_path("math/geometry"), ("math", Some("geometry")));
+        assert_eq!(split_module_path("@acme/http"), ("@acme/http", None));
+        assert_eq!(split_module_path("@acme/http/client/tls"), ("@acme/http", Some("client/tls")));
+    }
+}
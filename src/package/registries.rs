@@ -0,0 +1,442 @@
+//! Registry selection for public and private packages
+//!
+//! Registries are declared in the user configuration (`~/.bulu/config.toml`, or the
+//! file named by `BULU_CONFIG`) and may be added to or overridden by a project's
+//! `lang.toml`. Scoped packages are routed to a registry by their scope; everything
+//! else goes to the default registry:
+//!
+//! ```toml
+//! default = "public"
+//!
+//! [registries.public]
+//! url = "https://bulu-language.onrender.com"
+//! mirrors = ["https://mirror-eu.example.com", "https://mirror-us.example.com"]
+//!
+//! [registries.internal]
+//! url = "https://registry.acme.corp"
+//! token = "..."
+//!
+//! [scopes]
+//! "@acme" = "internal"
+//! ```
+//!
+//! Tokens belong in the user configuration; a project only needs to name the
+//! registry, and its token is taken from the user's entry of the same name.
+//!
+//! Requests go to a registry's `url` first and fall back to its mirrors, in
+//! order, when it is unreachable. A single dependency can name its registry in
+//! `lang.toml`, whatever its scope:
+//!
+//! ```toml
+//! [dependencies]
+//! auth = { version = "^2.0", registry = "internal" }
+//! ```
+
+use super::http_client::RegistryHttpClient;
+use super::PackageName;
+use crate::project::{DependencySpec, ProjectConfig};
+use crate::{BuluError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the built-in public registry
+pub const DEFAULT_REGISTRY_NAME: &str = "bulu";
+
+/// URL of the built-in public registry
+pub const DEFAULT_REGISTRY_URL: &str = "https://bulu-language.onrender.com";
+
+/// A registry entry in `~/.bulu/config.toml` or `lang.toml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RegistryEntry {
+    /// Base URL; may be omitted in `lang.toml` to only route scopes to a user registry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Bearer token sent with every request to this registry
+    #[serde(default, alias = "auth_token", skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Fallback URLs serving the same packages, tried in order when `url` is unreachable
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+}
+
+/// The registry settings of `~/.bulu/config.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserConfig {
+    /// Registry used for unscoped packages and unrouted scopes
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub registries: BTreeMap<String, RegistryEntry>,
+    /// Scope to registry name, e.g. `"@acme" = "internal"`
+    #[serde(default)]
+    pub scopes: BTreeMap<String, String>,
+    /// Single-registry form (`[registry] url = ..., auth_token = ...`), which
+    /// overrides the default registry
+    #[serde(default)]
+    pub registry: Option<RegistryEntry>,
+}
+
+impl UserConfig {
+    /// Location of the user configuration
+    pub fn path() -> Option<PathBuf> {
+        match std::env::var_os("BULU_CONFIG") {
+            Some(path) => Some(PathBuf::from(path)),
+            None => dirs::home_dir().map(|home| home.join(".bulu").join("config.toml")),
+        }
+    }
+
+    /// Load the user configuration; a missing file is an empty configuration
+    pub fn load() -> Result<Self> {
+        match Self::path() {
+            Some(path) if path.exists() => Self::load_from_path(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from_path(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| BuluError::Other(format!("Failed to read {}: {}", path.display(), e)))?;
+        toml::from_str(&content)
+            .map_err(|e| BuluError::Other(format!("Failed to parse {}: {}", path.display(), e)))
+    }
+}
+
+/// A registry selected for a package
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedRegistry {
+    pub name: String,
+    pub url: String,
+    pub token: Option<String>,
+    /// Fallbacks for `url`, highest priority first
+    pub mirrors: Vec<String>,
+}
+
+impl ResolvedRegistry {
+    /// HTTP client for this registry and its mirrors, authenticated when a
+    /// token is configured
+    pub fn client(&self) -> RegistryHttpClient {
+        let client = RegistryHttpClient::new(self.url.clone()).with_mirrors(self.mirrors.clone());
+        match &self.token {
+            Some(token) => client.with_token(token.clone()),
+            None => client,
+        }
+    }
+}
+
+/// User and project registry configuration merged together
+#[derive(Debug, Clone)]
+pub struct RegistrySettings {
+    default: String,
+    registries: BTreeMap<String, RegistryEntry>,
+    /// Scope names without the leading `@`
+    scopes: BTreeMap<String, String>,
+    /// Dependencies of the project that name their registry
+    dependencies: BTreeMap<String, String>,
+}
+
+impl RegistrySettings {
+    /// Load the user configuration, merge `project` over it and apply the
+    /// `BULU_REGISTRY`, `BULU_REGISTRY_TOKEN` and `BULU_REGISTRY_MIRRORS`
+    /// (comma-separated) overrides of the default registry
+    pub fn load(project: Option<&ProjectConfig>) -> Result<Self> {
+        let mut settings = Self::merge(UserConfig::load()?, project)?;
+        let default = settings.registries.entry(settings.default.clone()).or_default();
+        if let Ok(url) = std::env::var("BULU_REGISTRY") {
+            default.url = Some(url);
+        }
+        if let Ok(token) = std::env::var("BULU_REGISTRY_TOKEN") {
+            default.token = Some(token);
+        }
+        if let Ok(mirrors) = std::env::var("BULU_REGISTRY_MIRRORS") {
+            default.mirrors = mirrors
+                .split(',')
+                .map(str::trim)
+                .filter(|mirror| !mirror.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        Ok(settings)
+    }
+
+    /// Merge the user configuration with a project's registries and scopes.
+    /// Project entries win, except that a token missing from the project is
+    /// taken from the user's registry of the same name.
+    pub fn merge(user: UserConfig, project: Option<&ProjectConfig>) -> Result<Self> {
+        let mut registries = BTreeMap::new();
+        registries.insert(
+            DEFAULT_REGISTRY_NAME.to_string(),
+            RegistryEntry {
+                url: Some(DEFAULT_REGISTRY_URL.to_string()),
+                ..RegistryEntry::default()
+            },
+        );
+
+        let default = user.default.unwrap_or_else(|| DEFAULT_REGISTRY_NAME.to_string());
+        let mut scopes = BTreeMap::new();
+        let project_registries = project.map(|p| &p.registries).into_iter().flatten();
+        let project_scopes = project.map(|p| &p.scopes).into_iter().flatten();
+
+        for (name, entry) in user.registries.iter().chain(project_registries) {
+            merge_entry(registries.entry(name.clone()).or_default(), entry);
+        }
+        if let Some(entry) = &user.registry {
+            merge_entry(registries.entry(default.clone()).or_default(), entry);
+        }
+        for (scope, registry) in user.scopes.iter().chain(project_scopes) {
+            scopes.insert(normalize_scope(scope)?, registry.clone());
+        }
+        let dependencies = project
+            .map(|p| &p.dependencies)
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, spec)| match spec {
+                DependencySpec::Detailed { registry: Some(registry), .. } => Some((name.clone(), registry.clone())),
+                _ => None,
+            })
+            .collect();
+
+        let settings = Self {
+            default,
+            registries,
+            scopes,
+            dependencies,
+        };
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Registry serving `package`: the one its dependency entry names, else
+    /// the one its scope routes to
+    pub fn registry_for(&self, package: &str) -> Result<ResolvedRegistry> {
+        let name = PackageName::parse(package).map_err(BuluError::Other)?;
+        let registry = self.dependencies.get(package).or_else(|| {
+            name.scope.as_ref().and_then(|scope| self.scopes.get(scope))
+        });
+        let registry = registry.unwrap_or(&self.default);
+        self.resolve(registry)
+    }
+
+    /// Registry used for unscoped packages and searches
+    pub fn default_registry(&self) -> Result<ResolvedRegistry> {
+        self.resolve(&self.default)
+    }
+
+    /// HTTP client for the registry serving `package`
+    pub fn client_for(&self, package: &str) -> Result<RegistryHttpClient> {
+        Ok(self.registry_for(package)?.client())
+    }
+
+    fn resolve(&self, name: &str) -> Result<ResolvedRegistry> {
+        let entry = self
+            .registries
+            .get(name)
+            .ok_or_else(|| BuluError::Other(format!("Unknown registry '{}'", name)))?;
+        let url = entry.url.as_ref().ok_or_else(|| {
+            BuluError::Other(format!(
+                "Registry '{}' has no url; add it to {}",
+                name,
+                UserConfig::path().map_or_else(|| "~/.bulu/config.toml".to_string(), |p| p.display().to_string())
+            ))
+        })?;
+
+        Ok(ResolvedRegistry {
+            name: name.to_string(),
+            url: url.trim_end_matches('/').to_string(),
+            token: entry.token.clone(),
+            mirrors: entry.mirrors.iter().map(|mirror| mirror.trim_end_matches('/').to_string()).collect(),
+        })
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !self.registries.contains_key(&self.default) {
+            return Err(BuluError::Other(format!(
+                "Default registry '{}' is not declared under [registries]",
+                self.default
+            )));
+        }
+        for (scope, registry) in &self.scopes {
+            if !self.registries.contains_key(registry) {
+                return Err(BuluError::Other(format!(
+                    "Scope '@{}' routes to unknown registry '{}'",
+                    scope, registry
+                )));
+            }
+        }
+        for (package, registry) in &self.dependencies {
+            if !self.registries.contains_key(registry) {
+                return Err(BuluError::Other(format!(
+                    "Dependency '{}' names unknown registry '{}'",
+                    package, registry
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn merge_entry(target: &mut RegistryEntry, entry: &RegistryEntry) {
+    if entry.url.is_some() {
+        target.url = entry.url.clone();
+    }
+    if entry.token.is_some() {
+        target.token = entry.token.clone();
+    }
+    if !entry.mirrors.is_empty() {
+        target.mirrors = entry.mirrors.clone();
+    }
+}
+
+/// `@acme` or `acme` to `acme`
+fn normalize_scope(scope: &str) -> Result<String> {
+    let bare = scope.strip_prefix('@').unwrap_or(scope);
+    let name = PackageName::parse(&format!("@{}/package", bare))
+        .map_err(|_| BuluError::Other(format!("Invalid scope '{}' in [scopes]", scope)))?;
+    Ok(name.scope.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_config(content: &str) -> UserConfig {
+        toml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn test_route_scopes_to_registries() {
+        let user = user_config(
+            r#"
+            [registries.internal]
+            url = "https://registry.acme.corp/"
+            token = "secret"
+
+            [scopes]
+            "@acme" = "internal"
+            "#,
+        );
+        let settings = RegistrySettings::merge(user, None).unwrap();
+
+        let internal = settings.registry_for("@acme/http").unwrap();
+        assert_eq!(internal.name, "internal");
+        assert_eq!(internal.url, "https://registry.acme.corp");
+        assert_eq!(internal.token.as_deref(), Some("secret"));
+
+        for package in ["http", "@other/http"] {
+            let public = settings.registry_for(package).unwrap();
+            assert_eq!(public.name, DEFAULT_REGISTRY_NAME);
+            assert_eq!(public.url, DEFAULT_REGISTRY_URL);
+            assert_eq!(public.token, None);
+        }
+    }
+
+    #[test]
+    fn test_project_overrides_user_but_keeps_tokens() {
+        let user = user_config(
+            r#"
+            default = "mirror"
+
+            [registries.mirror]
+            url = "https://mirror.example.com"
+
+            [registries.internal]
+            url = "https://old.acme.corp"
+            token = "secret"
+            "#,
+        );
+        let project: ProjectConfig = toml::from_str(
+            r#"
+            [package]
+            name = "app"
+            version = "0.1.0"
+            authors = []
+
+            [registries.internal]
+            url = "https://registry.acme.corp"
+
+            [scopes]
+            acme = "internal"
+            "#,
+        )
+        .unwrap();
+        let settings = RegistrySettings::merge(user, Some(&project)).unwrap();
+
+        let internal = settings.registry_for("@acme/http").unwrap();
+        assert_eq!(internal.url, "https://registry.acme.corp");
+        assert_eq!(internal.token.as_deref(), Some("secret"));
+        assert_eq!(settings.default_registry().unwrap().url, "https://mirror.example.com");
+    }
+
+    #[test]
+    fn test_single_registry_form() {
+        let user = user_config(
+            r#"
+            [registry]
+            url = "https://my-private-registry.com"
+            auth_token = "token"
+            "#,
+        );
+        let settings = RegistrySettings::merge(user, None).unwrap();
+        let default = settings.default_registry().unwrap();
+        assert_eq!(default.url, "https://my-private-registry.com");
+        assert_eq!(default.token.as_deref(), Some("token"));
+    }
+
+    #[test]
+    fn test_mirrors_and_dependency_registries() {
+        let user = user_config(
+            r#"
+            [registries.bulu]
+            mirrors = ["https://mirror-eu.example.com/", "https://mirror-us.example.com"]
+
+            [registries.internal]
+            url = "https://registry.acme.corp"
+            token = "secret"
+            "#,
+        );
+        let project: ProjectConfig = toml::from_str(
+            r#"
+            [package]
+            name = "app"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies]
+            json = "1.0.0"
+            auth = { version = "^2.0", registry = "internal" }
+            "#,
+        )
+        .unwrap();
+        let settings = RegistrySettings::merge(user, Some(&project)).unwrap();
+
+        let public = settings.registry_for("json").unwrap();
+        assert_eq!(public.url, DEFAULT_REGISTRY_URL);
+        assert_eq!(public.mirrors, vec!["https://mirror-eu.example.com", "https://mirror-us.example.com"]);
+
+        let auth = settings.registry_for("auth").unwrap();
+        assert_eq!(auth.name, "internal");
+        assert_eq!(auth.token.as_deref(), Some("secret"));
+        assert!(auth.mirrors.is_empty());
+
+        let project: ProjectConfig = toml::from_str(
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nauthors = []\n\n[dependencies]\nauth = { version = \"2.0\", registry = \"missing\" }\n",
+        )
+        .unwrap();
+        let err = RegistrySettings::merge(UserConfig::default(), Some(&project)).unwrap_err();
+        assert!(err.to_string().contains("Dependency 'auth' names unknown registry 'missing'"));
+    }
+
+    #[test]
+    fn test_reject_unknown_registries() {
+        let user = user_config("[scopes]\n\"@acme\" = \"missing\"\n");
+        let err = RegistrySettings::merge(user, None).unwrap_err();
+        assert!(err.to_string().contains("unknown registry 'missing'"));
+
+        let user = user_config("default = \"missing\"\n");
+        assert!(RegistrySettings::merge(user, None).is_err());
+
+        let user = user_config("[scopes]\n\"@Acme\" = \"bulu\"\n");
+        assert!(RegistrySettings::merge(user, None).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 //! Package registry client for interacting with pkg.lang-lang.org
 
+use super::cache::PackageCache;
+use super::name::encode_for_url;
 use super::{PackageConfig, PackageMetadata, VersionConstraint};
 use crate::{BuluError, Result};
 use serde::{Deserialize, Serialize};
@@ -75,16 +77,61 @@ impl RegistryClient {
         }
     }
 
+    /// The registry's URL, then those of its mirrors
+    fn base_urls(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.config.registry_url.as_str())
+            .chain(self.config.registry_mirrors.iter().map(String::as_str))
+    }
+
+    /// Send the request `request` builds for a base URL, moving on to the next
+    /// mirror while connections fail or servers answer with a 5xx status
+    async fn send(&self, request: impl Fn(&str) -> reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+        let last = self.config.registry_mirrors.len();
+        for (index, base_url) in self.base_urls().enumerate() {
+            match request(base_url).send().await {
+                Ok(response) if index < last && response.status().is_server_error() => {
+                    tracing::warn!("Registry {} answered {}, trying the next mirror", base_url, response.status());
+                }
+                Err(e) if index < last && (e.is_connect() || e.is_timeout()) => {
+                    tracing::warn!("Registry {} is unreachable, trying the next mirror: {}", base_url, e);
+                }
+                result => return result,
+            }
+        }
+        unreachable!("the registry itself is always tried")
+    }
+
+    /// Check that every mirror that answers has the checksum of `package`
+    async fn check_mirror_checksums(&self, package: &PackageMetadata) -> Result<()> {
+        for base_url in self.base_urls() {
+            let url = format!("{}/api/v1/packages/{}/{}", base_url, encode_for_url(&package.name), package.version);
+            let response = match self.http_client.get(&url).send().await {
+                Ok(response) if response.status().is_success() => response,
+                _ => continue,
+            };
+            let Ok(mirrored) = response.json::<RegistryPackageResponse>().await else {
+                continue;
+            };
+            if mirrored.package.checksum != package.checksum {
+                return Err(BuluError::Other(format!(
+                    "Checksum mismatch for package {} v{}: {} has {}, expected {}",
+                    package.name, package.version, base_url, mirrored.package.checksum, package.checksum
+                )));
+            }
+        }
+        Ok(())
+    }
+
     /// Search for packages in the registry
     pub async fn search(&self, query: &str, limit: Option<usize>) -> Result<SearchResult> {
-        let url = format!("{}/api/v1/search", self.config.registry_url);
         let limit = limit.unwrap_or(20);
 
         let response = self
-            .http_client
-            .get(&url)
-            .query(&[("q", query), ("limit", &limit.to_string())])
-            .send()
+            .send(|base_url| {
+                self.http_client
+                    .get(format!("{}/api/v1/search", base_url))
+                    .query(&[("q", query), ("limit", &limit.to_string())])
+            })
             .await
             .map_err(|e| BuluError::Other(format!("Failed to search packages: {}", e)))?;
 
@@ -108,10 +155,9 @@ impl RegistryClient {
 
     /// Get package metadata from registry
     pub async fn get_package(&self, name: &str, version: Option<&str>) -> Result<PackageMetadata> {
-        let url = if let Some(version) = version {
-            format!("{}/api/v1/packages/{}/{}", self.config.registry_url, name, version)
-        } else {
-            format!("{}/api/v1/packages/{}", self.config.registry_url, name)
+        let url = |base_url: &str| match version {
+            Some(version) => format!("{}/api/v1/packages/{}/{}", base_url, encode_for_url(name), version),
+            None => format!("{}/api/v1/packages/{}", base_url, encode_for_url(name)),
         };
 
         // Check cache first
@@ -120,9 +166,7 @@ impl RegistryClient {
         }
 
         let response = self
-            .http_client
-            .get(&url)
-            .send()
+            .send(|base_url| self.http_client.get(url(base_url)))
             .await
             .map_err(|e| BuluError::Other(format!("Failed to fetch package {}: {}", name, e)))?;
 
@@ -147,12 +191,11 @@ impl RegistryClient {
 
     /// Get all available versions for a package
     pub async fn get_package_versions(&self, name: &str) -> Result<Vec<String>> {
-        let url = format!("{}/api/v1/packages/{}/versions", self.config.registry_url, name);
-
         let response = self
-            .http_client
-            .get(&url)
-            .send()
+            .send(|base_url| {
+                self.http_client
+                    .get(format!("{}/api/v1/packages/{}/versions", base_url, encode_for_url(name)))
+            })
             .await
             .map_err(|e| BuluError::Other(format!("Failed to fetch versions for {}: {}", name, e)))?;
 
@@ -172,10 +215,18 @@ impl RegistryClient {
         Ok(versions)
     }
 
-    /// Download a package tarball
+    /// Download a package tarball, reusing a verified copy from the cache when
+    /// there is one. With mirrors, those that answer must agree on its checksum.
     pub async fn download_package(&self, name: &str, version: &str) -> Result<Vec<u8>> {
         let package = self.get_package(name, Some(version)).await?;
-        
+
+        if let Some(bytes) = self.get_cached_tarball(&package) {
+            return Ok(bytes);
+        }
+        if !self.config.registry_mirrors.is_empty() {
+            self.check_mirror_checksums(&package).await?;
+        }
+
         let response = self
             .http_client
             .get(&package.download_url)
@@ -205,6 +256,9 @@ impl RegistryClient {
             )));
         }
 
+        // A failure to cache only costs a download next time
+        let _ = self.cache_tarball(&package, &bytes);
+
         Ok(bytes.to_vec())
     }
 
@@ -299,10 +353,11 @@ impl RegistryClient {
     /// Cache package metadata
     fn cache_package(&self, package: &PackageMetadata) -> Result<()> {
         let cache_key = format!("{}@{}", package.name, package.version);
-        let cache_dir = self.config.cache_dir.join("packages");
-        let cache_path = cache_dir.join(format!("{}.json", cache_key));
+        let cache_path = self.config.cache_dir.join("packages").join(format!("{}.json", cache_key));
 
-        fs::create_dir_all(&cache_dir)
+        // Scoped packages are cached under a directory named after their scope
+        let cache_dir = cache_path.parent().expect("cache path has a parent");
+        fs::create_dir_all(cache_dir)
             .map_err(|e| BuluError::Other(format!("Failed to create cache directory: {}", e)))?;
 
         let content = serde_json::to_string_pretty(package)
@@ -314,16 +369,30 @@ impl RegistryClient {
         Ok(())
     }
 
+    /// The content-addressed store of tarballs under the cache directory
+    fn tarball_cache(&self) -> PackageCache {
+        PackageCache::new(&self.config.cache_dir)
+    }
+
+    /// Get a cached tarball by its checksum; corrupted entries are quarantined
+    fn get_cached_tarball(&self, package: &PackageMetadata) -> Option<Vec<u8>> {
+        self.tarball_cache().get(&package.checksum).ok().flatten()
+    }
+
+    /// Cache a verified tarball
+    fn cache_tarball(&self, package: &PackageMetadata, bytes: &[u8]) -> Result<()> {
+        self.tarball_cache().insert(&package.name, &package.version, bytes).map(drop)
+    }
+
     /// Clear package cache
     pub fn clear_cache(&self) -> Result<()> {
         let cache_dir = self.config.cache_dir.join("packages");
-        
         if cache_dir.exists() {
             fs::remove_dir_all(&cache_dir)
                 .map_err(|e| BuluError::Other(format!("Failed to clear cache: {}", e)))?;
         }
 
-        Ok(())
+        self.tarball_cache().clean().map(drop)
     }
 }
 
@@ -406,4 +475,42 @@ mod tests {
         assert_eq!(retrieved.name, "test-package");
         assert_eq!(retrieved.version, "1.0.0");
     }
+
+    #[test]
+    fn test_tarball_cache_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let client = RegistryClient::new(PackageConfig {
+            cache_dir: dir.path().to_path_buf(),
+            ..PackageConfig::default()
+        });
+        let tarball = b"tarball contents".to_vec();
+        let package = PackageMetadata {
+            name: "@scope/pkg".to_string(),
+            version: "1.2.0".to_string(),
+            description: None,
+            authors: vec![],
+            license: None,
+            repository: None,
+            keywords: vec![],
+            categories: vec![],
+            dependencies: HashMap::new(),
+            checksum: sha256::digest(tarball.as_slice()),
+            download_url: "https://example.com/pkg.tar.gz".to_string(),
+        };
+
+        assert!(client.get_cached_tarball(&package).is_none());
+        client.cache_tarball(&package, &tarball).unwrap();
+        assert_eq!(client.get_cached_tarball(&package), Some(tarball));
+
+        // A corrupted entry fails its checksum and is quarantined
+        let cache_path = dir.path().join("store").join("sha256").join(&package.checksum[..2]).join(&package.checksum);
+        fs::write(&cache_path, b"tarball c0ntents").unwrap();
+        assert!(client.get_cached_tarball(&package).is_none());
+        assert!(!cache_path.exists());
+        assert_eq!(client.tarball_cache().quarantined().unwrap().len(), 1);
+
+        client.clear_cache().unwrap();
+        assert!(!dir.path().join("store").exists());
+        assert!(!dir.path().join("quarantine").exists());
+    }
 }
\ No newline at end of file
@@ -1,10 +1,11 @@
 //! Dependency resolution for package management
 
 use super::{PackageMetadata, ResolvedDependency, VersionConstraint, DependencySource};
+use super::lockfile::LockFile;
 use super::registry::RegistryClient;
-use crate::project::DependencySpec;
+use crate::project::{DependencySpec, ResolverConfig};
 use crate::{BuluError, Result};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
 
 /// Dependency resolver for handling transitive dependencies
@@ -12,6 +13,8 @@ pub struct DependencyResolver {
     registry: RegistryClient,
     resolved: HashMap<String, ResolvedDependency>,
     visited: HashSet<String>,
+    /// Source overrides from the project's `[patch]` section
+    patches: BTreeMap<String, DependencySpec>,
 }
 
 /// Resolution context for tracking dependency resolution
@@ -34,6 +37,93 @@ pub enum ConflictStrategy {
     LowestCompatible,
 }
 
+/// How strictly dependency resolution must follow `lang.lock`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResolutionMode {
+    /// Fail instead of writing a `lang.lock` that differs from the current one
+    pub locked: bool,
+    /// Never contact a registry, so `lang.lock` has to be current already
+    pub offline: bool,
+    /// Choose the lowest version satisfying each requirement, to catch
+    /// requirements whose lower bound is never actually tested
+    pub minimal_versions: bool,
+}
+
+impl ResolutionMode {
+    /// `--frozen`: locked and offline
+    pub fn frozen() -> Self {
+        Self {
+            locked: true,
+            offline: true,
+            minimal_versions: false,
+        }
+    }
+
+    /// Enable the modes turned on by the `[resolver]` section of lang.toml
+    pub fn with_config(self, config: &ResolverConfig) -> Self {
+        Self {
+            locked: self.locked || config.locked || config.frozen,
+            offline: self.offline || config.frozen,
+            minimal_versions: self.minimal_versions || config.minimal_versions,
+        }
+    }
+
+    /// Version selection strategy for this mode
+    pub fn strategy(&self) -> ConflictStrategy {
+        if self.minimal_versions {
+            ConflictStrategy::LowestCompatible
+        } else {
+            ConflictStrategy::HighestCompatible
+        }
+    }
+
+    /// Fail when offline, since resolving needs the registry
+    pub fn check_online(&self) -> Result<()> {
+        if self.offline {
+            return Err(BuluError::Other(
+                "lang.lock is missing or out of date, and --frozen prevents resolving dependencies".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fail when locked and `resolved` would change the `existing` lock file
+    pub fn check_lock(&self, existing: Option<&LockFile>, resolved: &LockFile) -> Result<()> {
+        if !self.locked {
+            return Ok(());
+        }
+        let changes = match existing {
+            Some(existing) => resolved.changes_from(existing),
+            None => return Err(BuluError::Other("lang.lock does not exist, and --locked prevents creating it".to_string())),
+        };
+        if changes.is_empty() {
+            return Ok(());
+        }
+        Err(BuluError::Other(format!(
+            "lang.lock needs to be updated, but --locked was passed:\n  {}",
+            changes.join("\n  ")
+        )))
+    }
+}
+
+/// Pick the version satisfying `constraint` according to `strategy`;
+/// `Strict` only succeeds when a single version matches
+pub fn select_version<'a>(
+    versions: &'a [String],
+    constraint: &VersionConstraint,
+    strategy: &ConflictStrategy,
+) -> Option<&'a String> {
+    let mut compatible = versions.iter().filter(|version| constraint.satisfies(version));
+    match strategy {
+        ConflictStrategy::Strict => {
+            let version = compatible.next()?;
+            compatible.next().is_none().then_some(version)
+        }
+        ConflictStrategy::HighestCompatible => compatible.max_by(|a, b| super::compare_versions(a, b).cmp(&0)),
+        ConflictStrategy::LowestCompatible => compatible.min_by(|a, b| super::compare_versions(a, b).cmp(&0)),
+    }
+}
+
 impl DependencyResolver {
     /// Create a new dependency resolver
     pub fn new(registry: RegistryClient) -> Self {
@@ -41,9 +131,16 @@ impl DependencyResolver {
             registry,
             resolved: HashMap::new(),
             visited: HashSet::new(),
+            patches: BTreeMap::new(),
         }
     }
 
+    /// Replace the source of the named packages wherever they appear in the graph
+    pub fn with_patches(mut self, patches: BTreeMap<String, DependencySpec>) -> Self {
+        self.patches = patches;
+        self
+    }
+
     /// Resolve all dependencies for a project
     pub async fn resolve_dependencies(
         &mut self,
@@ -92,10 +189,13 @@ impl DependencyResolver {
 
         context.chain.push(name.to_string());
 
+        let patch = self.patches.get(name).cloned();
+        let spec = patch.as_ref().unwrap_or(spec);
         let constraint = self.spec_to_constraint(spec)?;
-        let source_name = context.chain.get(context.chain.len() - 2)
-            .unwrap_or(&"root".to_string())
-            .clone();
+        let source_name = context.chain.len().checked_sub(2)
+            .and_then(|parent| context.chain.get(parent))
+            .cloned()
+            .unwrap_or_else(|| "root".to_string());
 
         context.constraints
             .entry(name.to_string())
@@ -139,9 +239,16 @@ impl DependencyResolver {
 
         self.visited.insert(name.to_string());
 
+        let patch = self.patches.get(name).cloned();
+        let spec = patch.as_ref().unwrap_or(spec);
         let source = self.spec_to_source(spec)?;
-        let resolved_dep = match &source {
+        let mut resolved_dep = match &source {
             DependencySource::Registry { .. } => {
+                // Dependencies of path and git packages were not visited while collecting
+                if !context.constraints.contains_key(name) {
+                    let constraint = self.spec_to_constraint(spec)?;
+                    context.constraints.insert(name.to_string(), vec![("root".to_string(), constraint)]);
+                }
                 self.resolve_registry_dependency(name, context, strategy).await?
             }
             DependencySource::Path { path } => {
@@ -151,6 +258,7 @@ impl DependencyResolver {
                 self.resolve_git_dependency(name, &source).await?
             }
         };
+        resolved_dep.patched = patch.is_some();
 
         // Resolve transitive dependencies
         for (dep_name, dep_constraint) in &resolved_dep.dependencies {
@@ -187,6 +295,7 @@ impl DependencyResolver {
             },
             dependencies: package.dependencies.clone(),
             checksum: Some(package.checksum.clone()),
+            patched: false,
         })
     }
 
@@ -226,6 +335,7 @@ impl DependencyResolver {
             },
             dependencies,
             checksum: None,
+            patched: false,
         })
     }
 
@@ -243,6 +353,7 @@ impl DependencyResolver {
                 source: source.clone(),
                 dependencies: HashMap::new(),
                 checksum: None,
+                patched: false,
             })
         } else {
             Err(BuluError::Other("Invalid git source".to_string()))
@@ -438,6 +549,31 @@ mod tests {
         // For now, this is a conceptual test structure
     }
 
+    #[test]
+    fn test_select_version() {
+        let versions: Vec<String> = ["1.0.0", "1.2.0", "1.10.1", "2.0.0"].iter().map(|v| v.to_string()).collect();
+        let constraint = VersionConstraint::Compatible("1.1.0".to_string());
+
+        let highest = select_version(&versions, &constraint, &ConflictStrategy::HighestCompatible);
+        assert_eq!(highest.map(String::as_str), Some("1.10.1"));
+        let lowest = select_version(&versions, &constraint, &ResolutionMode { minimal_versions: true, ..Default::default() }.strategy());
+        assert_eq!(lowest.map(String::as_str), Some("1.2.0"));
+        assert_eq!(select_version(&versions, &constraint, &ConflictStrategy::Strict), None);
+        assert_eq!(select_version(&versions, &VersionConstraint::Exact("3.0.0".to_string()), &ConflictStrategy::HighestCompatible), None);
+    }
+
+    #[test]
+    fn test_resolution_mode_from_config() {
+        let config = ResolverConfig { frozen: true, ..Default::default() };
+        assert_eq!(ResolutionMode::default().with_config(&config), ResolutionMode::frozen());
+
+        let config = ResolverConfig { minimal_versions: true, ..Default::default() };
+        let mode = ResolutionMode { locked: true, ..Default::default() }.with_config(&config);
+        assert!(mode.locked && mode.minimal_versions && !mode.offline);
+        assert!(mode.check_online().is_ok());
+        assert!(ResolutionMode::frozen().check_online().is_err());
+    }
+
     #[test]
     fn test_version_constraint_to_string() {
         assert_eq!(VersionConstraint::Any.to_string(), "*");
@@ -5,6 +5,7 @@ use super::registry::RegistryClient;
 use crate::{BuluError, Result};
 use std::fs;
 use std::path::{Path, PathBuf};
+use tracing::debug;
 
 /// Vendor manager for handling local dependency copies
 pub struct VendorManager {
@@ -21,8 +22,6 @@ pub struct VendorOptions {
     pub verify_checksums: bool,
     /// Whether to include dev dependencies
     pub include_dev_deps: bool,
-    /// Verbose output
-    pub verbose: bool,
 }
 
 impl Default for VendorOptions {
@@ -31,7 +30,6 @@ impl Default for VendorOptions {
             update_existing: false,
             verify_checksums: true,
             include_dev_deps: false,
-            verbose: false,
         }
     }
 }
@@ -45,6 +43,12 @@ impl VendorManager {
         }
     }
 
+    /// Vendor into `vendor_dir` instead of the project's `vendor/`
+    pub fn with_vendor_dir(mut self, vendor_dir: PathBuf) -> Self {
+        self.vendor_dir = vendor_dir;
+        self
+    }
+
     /// Vendor all dependencies from lock file
     pub async fn vendor_dependencies(
         &self,
@@ -65,22 +69,18 @@ impl VendorManager {
                 match self.vendor_single_dependency(locked_dep, options).await {
                     Ok(vendor_info) => {
                         result.vendored.push(vendor_info);
-                        if options.verbose {
-                            println!("Vendored: {} v{}", locked_dep.name, locked_dep.version);
-                        }
+                        debug!("Vendored: {} v{}", locked_dep.name, locked_dep.version);
                     }
                     Err(e) => {
                         result.errors.push(format!("Failed to vendor {}: {}", dep_name, e));
-                        if options.verbose {
-                            eprintln!("Error vendoring {}: {}", dep_name, e);
-                        }
+                        debug!("Error vendoring {}: {}", dep_name, e);
                     }
                 }
             }
         }
 
         // Clean up unused vendored dependencies
-        self.cleanup_unused_dependencies(lock_file, options)?;
+        self.cleanup_unused_dependencies(lock_file)?;
 
         Ok(result)
     }
@@ -292,7 +292,7 @@ impl VendorManager {
     }
 
     /// Clean up unused vendored dependencies
-    fn cleanup_unused_dependencies(&self, lock_file: &LockFile, options: &VendorOptions) -> Result<()> {
+    fn cleanup_unused_dependencies(&self, lock_file: &LockFile) -> Result<()> {
         if !self.vendor_dir.exists() {
             return Ok(());
         }
@@ -309,9 +309,7 @@ impl VendorManager {
                 let dir_name = entry.file_name().to_string_lossy().to_string();
                 
                 if !lock_dep_names.contains(&dir_name) {
-                    if options.verbose {
-                        println!("Removing unused vendored dependency: {}", dir_name);
-                    }
+                    debug!("Removing unused vendored dependency: {}", dir_name);
                     
                     fs::remove_dir_all(entry.path())
                         .map_err(|e| BuluError::Other(format!("Failed to remove unused dependency: {}", e)))?;
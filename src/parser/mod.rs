@@ -5,5 +5,6 @@
 
 pub mod parser;
 pub mod precedence;
+mod stream;
 
 pub use parser::Parser;
\ No newline at end of file
@@ -4,11 +4,14 @@ use crate::ast::*;
 use crate::error::{BuluError, Result};
 use crate::lexer::token::Position;
 use crate::lexer::{Literal, Token, TokenType};
+use super::stream::TopLevelChunks;
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
     file_path: Option<String>,
+    /// Set while parsing a match scrutinee, where `x {` opens the arms rather than a struct literal
+    no_struct_literal: bool,
 }
 
 impl Parser {
@@ -17,6 +20,7 @@ impl Parser {
             tokens,
             current: 0,
             file_path: None,
+            no_struct_literal: false,
         }
     }
 
@@ -25,6 +29,7 @@ impl Parser {
             tokens,
             current: 0,
             file_path: Some(file_path),
+            no_struct_literal: false,
         }
     }
 
@@ -32,7 +37,44 @@ impl Parser {
     pub fn parse(&mut self) -> Result<Program> {
         let start_pos = self.current_position();
         let mut statements = Vec::new();
+        let mut attributes = Vec::new();
+        self.parse_statements(&mut statements, &mut attributes)?;
 
+        Ok(Program {
+            statements,
+            attributes,
+            position: start_pos,
+        })
+    }
+
+    /// Parse a program from a token stream such as a [`Lexer`](crate::lexer::Lexer),
+    /// buffering one top-level declaration at a time instead of every token of the file
+    pub fn parse_stream<I>(&mut self, tokens: I) -> Result<Program>
+    where
+        I: IntoIterator<Item = Result<Token>>,
+    {
+        let mut chunks = TopLevelChunks::new(tokens.into_iter());
+        let mut statements = Vec::new();
+        let mut attributes = Vec::new();
+        let mut start_pos = None;
+
+        while let Some(chunk) = chunks.next_chunk()? {
+            self.tokens = chunk;
+            self.current = 0;
+            start_pos.get_or_insert(self.current_position());
+            self.parse_statements(&mut statements, &mut attributes)?;
+        }
+
+        Ok(Program {
+            statements,
+            attributes,
+            position: start_pos.unwrap_or(Position::new(1, 1, 0)),
+        })
+    }
+
+    /// Parse statements until the end of the token buffer, and the file
+    /// attributes before them
+    fn parse_statements(&mut self, statements: &mut Vec<Statement>, attributes: &mut Vec<Attribute>) -> Result<()> {
         while !self.is_at_end() {
             // Skip newlines at the top level
             if self.check(&TokenType::Newline) {
@@ -40,6 +82,14 @@ impl Parser {
                 continue;
             }
 
+            if self.check(&TokenType::At) {
+                if !statements.is_empty() {
+                    return Err(self.error("File attributes must come before any statement"));
+                }
+                attributes.extend(self.parse_attributes()?);
+                continue;
+            }
+
             match self.parse_statement() {
                 Ok(stmt) => statements.push(stmt),
                 Err(e) => {
@@ -50,10 +100,7 @@ impl Parser {
             }
         }
 
-        Ok(Program {
-            statements,
-            position: start_pos,
-        })
+        Ok(())
     }
 
     // ============================================================================
@@ -130,6 +177,8 @@ impl Parser {
 
         while self.check(&TokenType::DocComment) {
             doc_comments.push(self.advance().clone());
+            // The declaration usually starts on the next line
+            while self.match_token(&TokenType::Newline) {}
         }
 
         if doc_comments.is_empty() {
@@ -402,19 +451,22 @@ impl Parser {
         if let Some(token) = self.peek_ahead(i) {
             if token.token_type == TokenType::Colon {
                 i += 1; // skip ':'
-                        // Skip the type (simplified - we just skip until we find comma or other tokens)
-                while i < 10 {
+                        // Skip the type, up to a comma that is not inside type arguments
+                let mut depth = 0;
+                while i < 64 {
                     // safety limit
                     if let Some(token) = self.peek_ahead(i) {
-                        if matches!(
-                            token.token_type,
+                        match token.token_type {
+                            TokenType::Less | TokenType::LeftParen | TokenType::LeftBracket => depth += 1,
+                            TokenType::Greater | TokenType::RightParen | TokenType::RightBracket => depth -= 1,
+                            TokenType::RightShift => depth -= 2,
+                            TokenType::Comma if depth > 0 => {}
                             TokenType::Comma
-                                | TokenType::Assign
-                                | TokenType::Semicolon
-                                | TokenType::Newline
-                                | TokenType::Eof
-                        ) {
-                            break;
+                            | TokenType::Assign
+                            | TokenType::Semicolon
+                            | TokenType::Newline
+                            | TokenType::Eof => break,
+                            _ => {}
                         }
                         i += 1;
                     } else {
@@ -702,42 +754,7 @@ impl Parser {
 
         self.consume(&TokenType::LeftBrace, "Expected '{'")?;
 
-        let mut fields = Vec::new();
-        let mut methods = Vec::new();
-
-        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
-            // Skip newlines
-            if self.check(&TokenType::Newline) {
-                self.advance();
-                continue;
-            }
-
-            // Check for visibility modifiers and what follows
-            if self.check(&TokenType::Pub) {
-                self.advance(); // consume 'pub'
-                if self.check(&TokenType::Func) {
-                    methods.push(self.parse_method_declaration_with_visibility(false)?);
-                } else {
-                    // Public field
-                    fields.push(self.parse_struct_field_with_visibility(false)?);
-                }
-            } else if self.check(&TokenType::Priv) {
-                self.advance(); // consume 'priv'
-                if self.check(&TokenType::Func) {
-                    methods.push(self.parse_method_declaration_with_visibility(true)?);
-                } else {
-                    // Private field
-                    fields.push(self.parse_struct_field_with_visibility(true)?);
-                }
-            } else if self.check(&TokenType::Func) {
-                methods.push(self.parse_method_declaration()?);
-            } else {
-                // Parse field (public by default)
-                fields.push(self.parse_struct_field()?);
-            }
-        }
-
-        self.consume(&TokenType::RightBrace, "Expected '}'")?;
+        let (fields, methods) = self.parse_struct_body()?;
 
         Ok(Statement::StructDecl(StructDecl {
             name,
@@ -775,6 +792,21 @@ impl Parser {
 
         self.consume(&TokenType::LeftBrace, "Expected '{'")?;
 
+        let (fields, methods) = self.parse_struct_body()?;
+
+        Ok(Statement::StructDecl(StructDecl {
+            name,
+            type_params,
+            fields,
+            methods,
+            doc_comment: doc_comments,
+            is_exported,
+            position: pos,
+        }))
+    }
+
+    /// Parse the fields and methods of a struct up to and including its `}`
+    fn parse_struct_body(&mut self) -> Result<(Vec<StructField>, Vec<FunctionDecl>)> {
         let mut fields = Vec::new();
         let mut methods = Vec::new();
 
@@ -785,47 +817,56 @@ impl Parser {
                 continue;
             }
 
+            // Attributes apply to the field after them, on the same or the next line
+            let attributes = self.parse_attributes()?;
+
             // Check for visibility modifiers and what follows
-            if self.check(&TokenType::Pub) {
-                self.advance(); // consume 'pub'
-                if self.check(&TokenType::Func) {
-                    methods.push(self.parse_method_declaration_with_visibility(false)?);
-                } else {
-                    // Public field
-                    fields.push(self.parse_struct_field_with_visibility(false)?);
-                }
-            } else if self.check(&TokenType::Priv) {
-                self.advance(); // consume 'priv'
-                if self.check(&TokenType::Func) {
-                    methods.push(self.parse_method_declaration_with_visibility(true)?);
-                } else {
-                    // Private field
-                    fields.push(self.parse_struct_field_with_visibility(true)?);
+            let is_private = if self.match_token(&TokenType::Priv) {
+                true
+            } else {
+                // Members are public by default
+                self.match_token(&TokenType::Pub);
+                false
+            };
+            if self.check(&TokenType::Func) {
+                if let Some(attribute) = attributes.first() {
+                    return Err(self.error(&format!("Attribute '@{}' must come before a field", attribute.name)));
                 }
-            } else if self.check(&TokenType::Func) {
-                methods.push(self.parse_method_declaration()?);
+                methods.push(self.parse_method_declaration_with_visibility(is_private)?);
             } else {
-                // Parse field (public by default)
-                fields.push(self.parse_struct_field()?);
+                let mut field = self.parse_struct_field_with_visibility(is_private)?;
+                field.attributes = attributes;
+                fields.push(field);
             }
         }
 
         self.consume(&TokenType::RightBrace, "Expected '}'")?;
-
-        Ok(Statement::StructDecl(StructDecl {
-            name,
-            type_params,
-            fields,
-            methods,
-            doc_comment: doc_comments,
-            is_exported,
-            position: pos,
-        }))
+        Ok((fields, methods))
     }
 
-    /// Parse struct field
-    fn parse_struct_field(&mut self) -> Result<StructField> {
-        self.parse_struct_field_with_visibility(false)
+    /// Parse attributes such as `@json("user_id")`, each followed by optional newlines
+    fn parse_attributes(&mut self) -> Result<Vec<Attribute>> {
+        let mut attributes = Vec::new();
+        while self.check(&TokenType::At) {
+            let position = self.current_position();
+            self.advance(); // consume '@'
+            let name = self.consume_identifier("Expected attribute name after '@'")?;
+            let mut args = Vec::new();
+            if self.match_token(&TokenType::LeftParen) {
+                if !self.check(&TokenType::RightParen) {
+                    loop {
+                        args.push(self.parse_expression()?);
+                        if !self.match_token(&TokenType::Comma) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(&TokenType::RightParen, "Expected ')' after attribute arguments")?;
+            }
+            attributes.push(Attribute { name, args, position });
+            while self.match_token(&TokenType::Newline) {}
+        }
+        Ok(attributes)
     }
 
     /// Parse struct field with visibility
@@ -844,15 +885,11 @@ impl Parser {
             name,
             field_type,
             is_private,
+            attributes: Vec::new(),
             position: pos,
         })
     }
 
-    /// Parse method declaration (similar to function but inside struct)
-    fn parse_method_declaration(&mut self) -> Result<FunctionDecl> {
-        self.parse_method_declaration_with_visibility(false)
-    }
-
     /// Parse method declaration with visibility
     fn parse_method_declaration_with_visibility(
         &mut self,
@@ -1071,6 +1108,13 @@ impl Parser {
             None
         };
 
+        // A body makes this a default implementation
+        let default_body = if self.check(&TokenType::LeftBrace) {
+            Some(self.parse_block_statement_body()?)
+        } else {
+            None
+        };
+
         // Optional newline or semicolon
         if self.check(&TokenType::Newline) || self.check(&TokenType::Semicolon) {
             self.advance();
@@ -1080,6 +1124,7 @@ impl Parser {
             name,
             params,
             return_type,
+            default_body,
             is_private,
             position: pos,
         })
@@ -1165,7 +1210,7 @@ impl Parser {
     fn parse_match_statement(&mut self) -> Result<Statement> {
         let pos = self.current_position();
         self.advance(); // consume 'match'
-        let expr = self.parse_expression()?;
+        let expr = self.parse_match_scrutinee()?;
 
         self.consume(&TokenType::LeftBrace, "Expected '{'")?;
 
@@ -1425,6 +1470,15 @@ impl Parser {
             // Wildcard pattern
             TokenType::Identifier if self.peek().lexeme == "_" => {
                 self.advance();
+                if self.match_token(&TokenType::Colon) {
+                    // Type pattern without a binding: _: Type
+                    let pattern_type = self.parse_optional_type()?;
+                    return Ok(Pattern::Type(TypePattern {
+                        name: None,
+                        pattern_type,
+                        position: pos,
+                    }));
+                }
                 Ok(Pattern::Wildcard(pos))
             }
 
@@ -1477,6 +1531,16 @@ impl Parser {
                 }
             }
 
+            TokenType::ByteStringLiteral => {
+                if let Some(crate::lexer::Literal::ByteString(bytes)) = &self.peek().literal {
+                    let bytes = bytes.clone();
+                    self.advance();
+                    Ok(Pattern::Literal(LiteralValue::ByteString(bytes), pos))
+                } else {
+                    Err(self.error("Invalid byte string literal"))
+                }
+            }
+
             TokenType::CharLiteral => {
                 if let Some(crate::lexer::Literal::Char(value)) = &self.peek().literal {
                     let value = *value;
@@ -1508,7 +1572,23 @@ impl Parser {
 
                 // Check if this is a struct pattern
                 if self.check(&TokenType::LeftBrace) {
-                    self.parse_struct_pattern(name)
+                    self.parse_struct_pattern(name, pos)
+                } else if self.match_token(&TokenType::Colon) {
+                    // Type pattern: name: Type
+                    let pattern_type = self.parse_optional_type()?;
+                    Ok(Pattern::Type(TypePattern {
+                        name: Some(name),
+                        pattern_type,
+                        position: pos,
+                    }))
+                } else if self.match_token(&TokenType::At) {
+                    // Binding pattern: name @ pattern
+                    let pattern = self.parse_primary_pattern()?;
+                    Ok(Pattern::Binding(BindingPattern {
+                        name,
+                        pattern: Box::new(pattern),
+                        position: pos,
+                    }))
                 } else {
                     // Variable binding pattern
                     Ok(Pattern::Identifier(name, pos))
@@ -1632,10 +1712,8 @@ impl Parser {
         }))
     }
 
-    /// Parse struct pattern (e.g., Point{x: 1, y: 2})
-    fn parse_struct_pattern(&mut self, name: String) -> Result<Pattern> {
-        let pos = self.current_position();
-
+    /// Parse struct pattern (e.g., Point{x: 1, y: 2} or the shorthand Point{x, y})
+    fn parse_struct_pattern(&mut self, name: String, pos: Position) -> Result<Pattern> {
         self.consume(&TokenType::LeftBrace, "Expected '{'")?;
 
         let mut fields = Vec::new();
@@ -1649,8 +1727,17 @@ impl Parser {
 
             let field_pos = self.current_position();
             let field_name = self.consume_identifier("Expected field name")?;
-            self.consume(&TokenType::Colon, "Expected ':' after field name")?;
-            let pattern = self.parse_pattern()?;
+            // `Point { x, y }` is shorthand for `Point { x: x, y: y }`
+            let pattern = if self.match_token(&TokenType::Colon) {
+                self.parse_pattern()?
+            } else if self.check(&TokenType::Comma)
+                || self.check(&TokenType::RightBrace)
+                || self.check(&TokenType::Newline)
+            {
+                Pattern::Identifier(field_name.clone(), field_pos)
+            } else {
+                return Err(self.error("Expected ':' after field name"));
+            };
 
             fields.push(FieldPattern {
                 name: field_name,
@@ -1702,11 +1789,20 @@ impl Parser {
         }))
     }
 
+    /// Parse the value being matched, which cannot be a bare struct literal: in
+    /// `match value { n: int32 -> ... }` the brace opens the arms
+    fn parse_match_scrutinee(&mut self) -> Result<Expression> {
+        let previous = std::mem::replace(&mut self.no_struct_literal, true);
+        let expr = self.parse_expression();
+        self.no_struct_literal = previous;
+        expr
+    }
+
     /// Parse match expression
     fn parse_match_expression(&mut self) -> Result<Expression> {
         let pos = self.current_position();
         self.advance(); // consume 'match'
-        let expr = Box::new(self.parse_expression()?);
+        let expr = Box::new(self.parse_match_scrutinee()?);
 
         self.consume(&TokenType::LeftBrace, "Expected '{'")?;
 
@@ -2574,7 +2670,7 @@ impl Parser {
             if self.match_token(&TokenType::LeftParen) {
                 expr = self.finish_call(expr)?;
             } else if self.match_token(&TokenType::Dot) {
-                let name = self.consume_identifier("Expected property name after '.'")?;
+                let name = self.consume_member_name("Expected property name after '.'")?;
                 let pos = expr.position();
                 expr = Expression::MemberAccess(MemberAccessExpr {
                     object: Box::new(expr),
@@ -2653,12 +2749,37 @@ impl Parser {
                         });
                     }
                 }
+            } else if self.check(&TokenType::Less)
+                && matches!(expr, Expression::Identifier(_) | Expression::MemberAccess(_))
+                && self.looks_like_type_arguments()
+            {
+                // Explicit type arguments: identity<int32>(5), box.map<string>(f)
+                // or Box<string>{value: "x"}
+                let type_args = self.parse_type_arguments()?;
+                if self.match_token(&TokenType::LeftParen) {
+                    expr = self.finish_call(expr)?;
+                    if let Expression::Call(call) = &mut expr {
+                        call.type_args = type_args;
+                    }
+                } else {
+                    expr = self.finish_struct_literal(expr)?;
+                    if let Expression::StructLiteral(struct_lit) = &mut expr {
+                        struct_lit.type_args = type_args;
+                    }
+                }
+            } else if self.check(&TokenType::Question) {
+                // Error propagation: fetchUser(id)?
+                let pos = self.advance().position;
+                expr = Expression::Propagate(PropagateExpr {
+                    expr: Box::new(expr),
+                    position: pos,
+                });
             } else if self.check(&TokenType::LeftBrace) {
                 // Check if this is a struct literal (TypeName{...})
                 if let Expression::Identifier(_) = expr {
                     // Look ahead to see if this looks like a struct literal
                     // A struct literal should have: { identifier : ... } or be empty { }
-                    if self.looks_like_struct_literal() {
+                    if !self.no_struct_literal && self.looks_like_struct_literal() {
                         expr = self.finish_struct_literal(expr)?;
                     } else {
                         break;
@@ -2693,17 +2814,22 @@ impl Parser {
 
     /// Check if the upcoming tokens look like a struct literal
     fn looks_like_struct_literal(&self) -> bool {
+        self.looks_like_struct_literal_at(0)
+    }
+
+    /// Check if the tokens after the '{' at `brace_offset` look like a struct literal body
+    fn looks_like_struct_literal_at(&self, brace_offset: usize) -> bool {
         // Look ahead to see what's after the '{'
-        if let Some(token1) = self.peek_ahead(1) {
+        if let Some(token1) = self.peek_ahead(brace_offset + 1) {
             match &token1.token_type {
                 // Empty struct literal: {}
                 TokenType::RightBrace => true,
                 // Field in struct literal: { identifier : ... }
                 TokenType::Identifier => {
-                    if let Some(token2) = self.peek_ahead(2) {
+                    if let Some(token2) = self.peek_ahead(brace_offset + 2) {
                         // Skip potential newlines
                         if token2.token_type == TokenType::Newline {
-                            if let Some(token3) = self.peek_ahead(3) {
+                            if let Some(token3) = self.peek_ahead(brace_offset + 3) {
                                 token3.token_type == TokenType::Colon
                             } else {
                                 false
@@ -2717,9 +2843,9 @@ impl Parser {
                 }
                 // Newlines followed by identifier:colon
                 TokenType::Newline => {
-                    if let Some(token2) = self.peek_ahead(2) {
+                    if let Some(token2) = self.peek_ahead(brace_offset + 2) {
                         if token2.token_type == TokenType::Identifier {
-                            if let Some(token3) = self.peek_ahead(3) {
+                            if let Some(token3) = self.peek_ahead(brace_offset + 3) {
                                 token3.token_type == TokenType::Colon
                             } else {
                                 false
@@ -2739,6 +2865,38 @@ impl Parser {
         }
     }
 
+    /// Check if the upcoming `<...>` is a type argument list followed by a call or a struct literal
+    fn looks_like_type_arguments(&self) -> bool {
+        let mut depth: i32 = 0;
+        let mut offset = 0;
+        while let Some(token) = self.peek_ahead(offset) {
+            match token.token_type {
+                TokenType::Less => depth += 1,
+                TokenType::Greater => depth -= 1,
+                TokenType::RightShift => depth -= 2,
+                TokenType::Identifier
+                | TokenType::Comma
+                | TokenType::LeftBracket
+                | TokenType::RightBracket
+                | TokenType::IntegerLiteral => {}
+                _ => return false,
+            }
+            offset += 1;
+
+            if depth == 0 {
+                return match self.peek_ahead(offset).map(|token| token.token_type) {
+                    Some(TokenType::LeftParen) => true,
+                    Some(TokenType::LeftBrace) => self.looks_like_struct_literal_at(offset),
+                    _ => false,
+                };
+            }
+            if depth < 0 {
+                return false;
+            }
+        }
+        false
+    }
+
     /// Finish parsing a struct literal (TypeName{field: value, ...})
     fn finish_struct_literal(&mut self, type_expr: Expression) -> Result<Expression> {
         let pos = type_expr.position();
@@ -2803,6 +2961,7 @@ impl Parser {
 
         Ok(Expression::StructLiteral(StructLiteralExpr {
             type_name,
+            type_args: Vec::new(),
             fields,
             position: pos,
         }))
@@ -2875,7 +3034,7 @@ impl Parser {
             } else if self.check(&TokenType::Identifier) {
                 // Check if this is a primitive type identifier
                 let current_token = self.peek();
-                let type_name = current_token.lexeme.clone();
+                let type_name = current_token.lexeme.to_string();
                 let position = current_token.position;
 
                 // Check if it's a primitive type
@@ -3072,6 +3231,18 @@ impl Parser {
                     Err(self.error("Invalid string literal"))
                 }
             }
+            TokenType::ByteStringLiteral => {
+                if let Some(Literal::ByteString(bytes)) = &token.literal {
+                    let bytes = bytes.clone();
+                    self.advance();
+                    Ok(Expression::Literal(LiteralExpr {
+                        value: LiteralValue::ByteString(bytes),
+                        position: pos,
+                    }))
+                } else {
+                    Err(self.error("Invalid byte string literal"))
+                }
+            }
             TokenType::CharLiteral => {
                 if let Some(Literal::Char(value)) = &token.literal {
                     let value = *value;
@@ -3085,7 +3256,7 @@ impl Parser {
                 }
             }
             TokenType::Identifier => {
-                let name = token.lexeme.clone();
+                let name = token.lexeme.to_string();
 
                 // Check if this is a single-parameter arrow function: param => expr
                 if self.peek_ahead(1).map(|t| &t.token_type) == Some(&TokenType::FatArrow) {
@@ -3119,6 +3290,8 @@ impl Parser {
                         captures: Vec::new(), // Will be filled by semantic analysis
                         position: pos,
                     }))
+                } else if name == "set" && self.at_set_literal() {
+                    self.parse_set_literal()
                 } else {
                     self.advance();
                     Ok(Expression::Identifier(IdentifierExpr {
@@ -3255,6 +3428,70 @@ impl Parser {
         self.parse_map_literal()
     }
 
+    /// Check whether the `set` identifier under the cursor starts a set literal:
+    /// `set{...}` with the brace right after the keyword, or `set<T>{...}`.
+    /// Anything else (`for x in set {`, `set < limit`) is a plain identifier.
+    fn at_set_literal(&mut self) -> bool {
+        if self.no_struct_literal {
+            return false;
+        }
+        let set_token = self.peek().clone();
+        match self.peek_ahead(1) {
+            Some(next) if next.token_type == TokenType::LeftBrace => {
+                next.position.line == set_token.position.line
+                    && next.position.offset == set_token.position.offset + set_token.lexeme.len()
+            }
+            Some(next) if next.token_type == TokenType::Less => {
+                // Type arguments may split a '>>' token, so restore the tokens as well
+                let start = self.current;
+                let tokens = self.tokens.clone();
+                self.advance();
+                let is_literal =
+                    self.parse_type_arguments().is_ok() && self.check(&TokenType::LeftBrace);
+                self.current = start;
+                self.tokens = tokens;
+                is_literal
+            }
+            _ => false,
+        }
+    }
+
+    /// Parse set literal: set{a, b} or set<T>{a, b}
+    fn parse_set_literal(&mut self) -> Result<Expression> {
+        let pos = self.advance().position; // consume 'set'
+
+        let element_type = if self.check(&TokenType::Less) {
+            let mut type_args = self.parse_type_arguments()?;
+            if type_args.len() != 1 {
+                return Err(self.error("Set type takes exactly one type argument"));
+            }
+            type_args.pop()
+        } else {
+            None
+        };
+
+        self.consume(&TokenType::LeftBrace, "Expected '{' to start set literal")?;
+        let mut elements = Vec::new();
+        loop {
+            while self.match_token(&TokenType::Newline) {}
+            if self.check(&TokenType::RightBrace) {
+                break;
+            }
+            elements.push(self.parse_expression()?);
+            while self.match_token(&TokenType::Newline) {}
+            if !self.match_token(&TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(&TokenType::RightBrace, "Expected '}' after set elements")?;
+
+        Ok(Expression::Set(SetExpr {
+            element_type,
+            elements,
+            position: pos,
+        }))
+    }
+
     /// Parse map literal
     fn parse_map_literal(&mut self) -> Result<Expression> {
         let pos = self.current_position();
@@ -3276,7 +3513,7 @@ impl Parser {
                 let key = if self.check(&TokenType::Identifier) {
                     let ident = self.advance().clone();
                     Expression::Literal(LiteralExpr {
-                        value: LiteralValue::String(ident.lexeme),
+                        value: LiteralValue::String(ident.lexeme.into()),
                         position: ident.position,
                     })
                 } else {
@@ -3362,9 +3599,48 @@ impl Parser {
         }
     }
 
-    /// Parse type annotation
+    /// Parse type annotation, including unions (T | U) and optionals (T?)
     fn parse_type(&mut self) -> Result<Type> {
+        let first = self.parse_optional_type()?;
+        if !self.check(&TokenType::Pipe) {
+            return Ok(first);
+        }
+
+        let mut types = Vec::new();
+        let mut member = first;
+        loop {
+            // Nested unions such as `int32? | string` are flattened
+            match member {
+                Type::Union(union) => types.extend(union.types),
+                other => types.push(other),
+            }
+            if !self.match_token(&TokenType::Pipe) {
+                break;
+            }
+            member = self.parse_optional_type()?;
+        }
+        Ok(Type::Union(UnionType { types }))
+    }
+
+    /// Parse a single type with an optional `?` suffix, which is sugar for `T | null`
+    fn parse_optional_type(&mut self) -> Result<Type> {
+        let base = self.parse_single_type()?;
+        if self.match_token(&TokenType::Question) {
+            Ok(Type::Union(UnionType {
+                types: vec![base, Type::Null],
+            }))
+        } else {
+            Ok(base)
+        }
+    }
+
+    /// Parse a single type without union or optional suffixes
+    fn parse_single_type(&mut self) -> Result<Type> {
         match self.peek().token_type {
+            TokenType::Null => {
+                self.advance();
+                Ok(Type::Null)
+            }
             TokenType::Func => {
                 // Function type: func(T1, T2): R
                 self.advance(); // consume 'func'
@@ -3402,7 +3678,7 @@ impl Parser {
                 }))
             }
             TokenType::Identifier => {
-                let name = self.advance().lexeme.clone();
+                let name = self.advance().lexeme.to_string();
                 if name == "map" {
                     // Map type: map[K]V
                     self.consume(&TokenType::LeftBracket, "Expected '[' after 'map'")?;
@@ -3413,6 +3689,15 @@ impl Parser {
                         key_type,
                         value_type,
                     }))
+                } else if name == "set" && self.check(&TokenType::Less) {
+                    // Set type: set<T>
+                    let mut type_args = self.parse_type_arguments()?;
+                    if type_args.len() != 1 {
+                        return Err(self.error("Set type takes exactly one type argument"));
+                    }
+                    Ok(Type::Set(SetType {
+                        element_type: Box::new(type_args.remove(0)),
+                    }))
                 } else {
                     // Handle other identifier types
                     match name.as_str() {
@@ -3422,7 +3707,7 @@ impl Parser {
                         "int32" => Ok(Type::Int32),
                         "int64" => Ok(Type::Int64),
                         "uint" => Ok(Type::UInt64), // Default uint is uint64
-                        "uint8" => Ok(Type::UInt8),
+                        "uint8" | "byte" => Ok(Type::UInt8),
                         "uint16" => Ok(Type::UInt16),
                         "uint32" => Ok(Type::UInt32),
                         "uint64" => Ok(Type::UInt64),
@@ -3433,6 +3718,11 @@ impl Parser {
                         "char" => Ok(Type::Char),
                         "string" => Ok(Type::String),
                         "any" => Ok(Type::Any),
+                        _ if self.check(&TokenType::Less) => {
+                            // Instantiated generic type: Box<string>
+                            let type_args = self.parse_type_arguments()?;
+                            Ok(Type::Struct(StructType { name, type_args }))
+                        }
                         _ => Ok(Type::Named(name)),
                     }
                 }
@@ -3444,15 +3734,22 @@ impl Parser {
                     let element_type = Box::new(self.parse_type()?);
                     Ok(Type::Slice(SliceType { element_type }))
                 } else {
-                    // Array type [N]T - for now, just parse as slice
-                    while !self.check(&TokenType::RightBracket) && !self.is_at_end() {
-                        self.advance();
-                    }
-                    self.consume(&TokenType::RightBracket, "Expected ']'")?;
+                    // Array type [N]T; a size that is not a literal is resolved by
+                    // constant evaluation
+                    let size_expr = self.parse_expression()?;
+                    self.consume(&TokenType::RightBracket, "Expected ']' after array size")?;
                     let element_type = Box::new(self.parse_type()?);
+                    let (size, size_expr) = match size_expr {
+                        Expression::Literal(LiteralExpr {
+                            value: LiteralValue::Integer(size),
+                            ..
+                        }) if size >= 0 => (Some(size as usize), None),
+                        size_expr => (None, Some(Box::new(size_expr))),
+                    };
                     Ok(Type::Array(ArrayType {
                         element_type,
-                        size: None,
+                        size,
+                        size_expr,
                     }))
                 }
             }
@@ -3570,6 +3867,29 @@ impl Parser {
         Ok(params)
     }
 
+    /// Parse type arguments: <Type1, Type2>
+    fn parse_type_arguments(&mut self) -> Result<Vec<Type>> {
+        self.consume(&TokenType::Less, "Expected '<' before type arguments")?;
+
+        let mut type_args = Vec::new();
+        loop {
+            type_args.push(self.parse_type()?);
+            if !self.match_token(&TokenType::Comma) {
+                break;
+            }
+        }
+
+        if self.check(&TokenType::RightShift) {
+            // Nested type arguments (Box<Box<int32>>): take one '>' and leave the other
+            let token = &mut self.tokens[self.current];
+            token.token_type = TokenType::Greater;
+            token.lexeme = ">".into();
+        } else {
+            self.consume(&TokenType::Greater, "Expected '>' after type arguments")?;
+        }
+        Ok(type_args)
+    }
+
     /// Parse where clause for complex generic constraints
     fn parse_where_clause(&mut self) -> Result<Option<Vec<TypeParam>>> {
         if !self.match_token(&TokenType::Where) {
@@ -3842,13 +4162,28 @@ impl Parser {
     /// Consume identifier token
     fn consume_identifier(&mut self, message: &str) -> Result<String> {
         if self.check(&TokenType::Identifier) {
-            Ok(self.advance().lexeme.clone())
+            Ok(self.advance().lexeme.to_string())
         } else {
             Err(self.error(message))
         }
     }
 
-    /// Consume statement terminator (newline or semicolon)
+    /// Consume the name after a '.', which may be a keyword, as in `pattern.match(text)`
+    fn consume_member_name(&mut self, message: &str) -> Result<String> {
+        let token = self.peek();
+        let is_keyword = !matches!(
+            token.token_type,
+            TokenType::StringLiteral | TokenType::ByteStringLiteral | TokenType::CharLiteral
+        ) && token.lexeme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && token.token_type.to_string() == token.lexeme.as_str();
+        if is_keyword {
+            Ok(self.advance().lexeme.to_string())
+        } else {
+            self.consume_identifier(message)
+        }
+    }
+
+        /// Consume statement terminator (newline or semicolon)
     fn consume_statement_terminator(&mut self) -> Result<()> {
         if self.match_token(&TokenType::Semicolon)
             || self.match_token(&TokenType::Newline)
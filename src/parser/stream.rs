@@ -0,0 +1,125 @@
+//! Splitting a token stream into top-level declarations
+//!
+//! The parser backtracks and looks ahead freely, so it works on a buffer of
+//! tokens. When parsing from a stream that buffer only has to hold one top-level
+//! declaration: a new chunk starts at a declaration keyword at the beginning of
+//! a line, outside any brackets, after a line that ended a complete statement.
+
+use crate::error::Result;
+use crate::lexer::token::Position;
+use crate::lexer::{Token, TokenType};
+
+pub(crate) struct TopLevelChunks<I> {
+    tokens: I,
+    /// First token of the next chunk, read while finding the end of this one
+    pending: Option<Token>,
+    last_position: Position,
+    done: bool,
+}
+
+impl<I> TopLevelChunks<I>
+where
+    I: Iterator<Item = Result<Token>>,
+{
+    pub(crate) fn new(tokens: I) -> Self {
+        Self {
+            tokens,
+            pending: None,
+            last_position: Position::new(1, 1, 0),
+            done: false,
+        }
+    }
+
+    /// The tokens of the next top-level declarations, terminated by `Eof`
+    pub(crate) fn next_chunk(&mut self) -> Result<Option<Vec<Token>>> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let mut chunk = Vec::new();
+        let mut depth = 0usize;
+        let mut last_significant: Option<TokenType> = None;
+        let mut at_line_start = false;
+
+        loop {
+            let token = match self.pending.take() {
+                Some(token) => token,
+                None => match self.tokens.next() {
+                    Some(token) => token?,
+                    None => Token::new(TokenType::Eof, "", None, self.last_position),
+                },
+            };
+            self.last_position = token.position;
+
+            if token.token_type == TokenType::Eof {
+                chunk.push(token);
+                self.done = true;
+                return Ok(Some(chunk));
+            }
+
+            if depth == 0
+                && at_line_start
+                && last_significant.is_some_and(ends_statement)
+                && starts_declaration(token.token_type)
+            {
+                chunk.push(Token::new(TokenType::Eof, "", None, token.position));
+                self.pending = Some(token);
+                return Ok(Some(chunk));
+            }
+
+            match token.token_type {
+                TokenType::LeftParen | TokenType::LeftBracket | TokenType::LeftBrace => depth += 1,
+                TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace => {
+                    depth = depth.saturating_sub(1)
+                }
+                _ => {}
+            }
+            if token.token_type == TokenType::Newline {
+                at_line_start = true;
+            } else {
+                at_line_start = false;
+                last_significant = Some(token.token_type);
+            }
+            chunk.push(token);
+        }
+    }
+}
+
+/// Tokens that begin a top-level declaration
+fn starts_declaration(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Func
+            | TokenType::Struct
+            | TokenType::Interface
+            | TokenType::Type
+            | TokenType::Import
+            | TokenType::Export
+            | TokenType::Let
+            | TokenType::Const
+            | TokenType::Async
+            | TokenType::DocComment
+    )
+}
+
+/// Tokens after which a line is a complete statement. Anything else (an
+/// operator, a comma, a doc comment) may continue on the next line.
+fn ends_statement(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Identifier
+            | TokenType::IntegerLiteral
+            | TokenType::FloatLiteral
+            | TokenType::StringLiteral
+            | TokenType::ByteStringLiteral
+            | TokenType::CharLiteral
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Null
+            | TokenType::RightParen
+            | TokenType::RightBracket
+            | TokenType::RightBrace
+            | TokenType::Greater
+            | TokenType::Semicolon
+    )
+}
@@ -1,9 +1,11 @@
 //! Project configuration and management for Bulu projects
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
+use crate::package::registries::RegistryEntry;
+use crate::package::PackageName;
 use crate::{BuluError, Result};
 
 /// Project configuration loaded from lang.toml
@@ -16,6 +18,18 @@ pub struct ProjectConfig {
     pub build: BuildConfig,
     #[serde(default)]
     pub test: TestConfig,
+    /// Registries added to or overriding those of `~/.bulu/config.toml`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub registries: BTreeMap<String, RegistryEntry>,
+    /// Scope to registry name routing, e.g. `"@acme" = "internal"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scopes: BTreeMap<String, String>,
+    /// Packages whose source is replaced by a local path or git repository,
+    /// wherever they appear in the dependency graph
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub patch: BTreeMap<String, DependencySpec>,
+    #[serde(default, skip_serializing_if = "ResolverConfig::is_default")]
+    pub resolver: ResolverConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +42,10 @@ pub struct PackageConfig {
     pub repository: Option<String>,
     pub keywords: Option<Vec<String>>,
     pub categories: Option<Vec<String>>,
+    /// Whether builtins are in scope without `import "std/builtin"`; a file
+    /// can also disable them for itself with `@no_prelude`
+    #[serde(default = "default_prelude", skip_serializing_if = "is_true")]
+    pub prelude: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +60,10 @@ pub enum DependencySpec {
         tag: Option<String>,
         features: Option<Vec<String>>,
         optional: Option<bool>,
+        /// Registry, by its name under `[registries]`, serving this dependency
+        /// instead of the one its scope routes to
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        registry: Option<String>,
     },
 }
 
@@ -57,6 +79,9 @@ pub struct BuildConfig {
     pub incremental: bool,
     #[serde(default)]
     pub parallel: bool,
+    /// Lints that fail the build, `warnings` or rule IDs, as with `--deny`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,6 +94,78 @@ pub struct TestConfig {
     pub coverage: bool,
 }
 
+/// Resolution modes applied to every install, e.g. `frozen = true` in a CI checkout
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ResolverConfig {
+    /// Fail instead of changing lang.lock
+    #[serde(default)]
+    pub locked: bool,
+    /// Locked, and never contact a registry
+    #[serde(default)]
+    pub frozen: bool,
+    /// Choose the lowest version satisfying each requirement
+    #[serde(default)]
+    pub minimal_versions: bool,
+}
+
+impl ResolverConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl ProjectConfig {
+    /// Check that the package and every dependency have valid, possibly scoped, names
+    pub fn validate_names(&self) -> Result<()> {
+        let names = std::iter::once(&self.package.name)
+            .chain(self.dependencies.keys())
+            .chain(self.patch.keys());
+        for name in names {
+            PackageName::parse(name)
+                .map_err(|e| BuluError::Other(format!("Invalid lang.toml: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    /// Check that every `[patch]` entry replaces the source with a path or git repository
+    pub fn validate_patches(&self) -> Result<()> {
+        for (name, spec) in &self.patch {
+            if patch_source(spec).is_none() {
+                return Err(BuluError::Other(format!(
+                    "Invalid lang.toml: patch for '{}' must set `path` or `git`",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Warnings describing the active `[patch]` overrides
+    pub fn patch_warnings(&self) -> Vec<String> {
+        self.patch
+            .iter()
+            .filter_map(|(name, spec)| {
+                patch_source(spec).map(|source| {
+                    format!("dependency '{}' is patched to {}", name, source)
+                })
+            })
+            .collect()
+    }
+}
+
+/// `path:<path>` or `git:<url>[#<branch or tag>]` for a patch entry
+fn patch_source(spec: &DependencySpec) -> Option<String> {
+    match spec {
+        DependencySpec::Detailed { path: Some(path), .. } => Some(format!("path:{}", path)),
+        DependencySpec::Detailed { git: Some(git), branch, tag, .. } => match branch.as_ref().or(tag.as_ref()) {
+            Some(reference) => Some(format!("git:{}#{}", git, reference)),
+            None => Some(format!("git:{}", git)),
+        },
+        _ => None,
+    }
+}
+
 impl Default for BuildConfig {
     fn default() -> Self {
         Self {
@@ -77,6 +174,7 @@ impl Default for BuildConfig {
             features: Vec::new(),
             incremental: true,
             parallel: true,
+            deny: Vec::new(),
         }
     }
 }
@@ -99,6 +197,14 @@ fn default_target() -> String {
     "native".to_string()
 }
 
+fn default_prelude() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
 /// Represents a Bulu project
 #[derive(Debug, Clone)]
 pub struct Project {
@@ -132,6 +238,8 @@ impl Project {
         
         let config: ProjectConfig = toml::from_str(&config_content)
             .map_err(|e| BuluError::Other(format!("Failed to parse lang.toml: {}", e)))?;
+        config.validate_names()?;
+        config.validate_patches()?;
 
         let src_dir = root.join("src");
         let build_dir = root.join("build");
@@ -213,10 +321,15 @@ impl Project {
         Ok(())
     }
 
-    /// Get the output executable path
-    pub fn executable_path(&self, release: bool) -> PathBuf {
+    /// Output directory of a build: `target/<profile>/<target>`
+    pub fn profile_dir(&self, release: bool, target: &str) -> PathBuf {
         let profile = if release { "release" } else { "debug" };
-        self.target_dir.join(profile).join(&self.config.package.name)
+        self.target_dir.join(profile).join(target)
+    }
+
+    /// Get the output executable path for the host target
+    pub fn executable_path(&self, release: bool) -> PathBuf {
+        self.profile_dir(release, &crate::build::host_target()).join(&self.config.package.name)
     }
 
     /// Check if the project needs rebuilding
@@ -289,10 +402,15 @@ pub fn create_project(name: &str, path: Option<&Path>) -> Result<()> {
             repository: None,
             keywords: None,
             categories: None,
+            prelude: true,
         },
         dependencies: HashMap::new(),
         build: BuildConfig::default(),
         test: TestConfig::default(),
+        registries: BTreeMap::new(),
+        scopes: BTreeMap::new(),
+        patch: BTreeMap::new(),
+        resolver: ResolverConfig::default(),
     };
 
     let config_content = toml::to_string_pretty(&config)
@@ -4,6 +4,7 @@ use crate::error::{BuluError, Result};
 use crate::ast::*;
 use crate::lexer::{Lexer, token::Position};
 use crate::parser::Parser;
+use crate::package::name::split_module_path;
 use super::{Module, Symbol, SymbolKind, Visibility};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -122,16 +123,11 @@ impl ModuleResolver {
     }
 
     /// Try to resolve a vendor package from a specific directory, searching upwards
-    /// Supports both "package-name" and "package-name/submodule" formats
+    /// Supports "package-name", "package-name/submodule" and the scoped
+    /// "@scope/package-name/submodule" formats
     fn try_resolve_vendor_from_dir(&self, start_dir: &Path, module_path: &str) -> Result<PathBuf> {
         // Split the module path to get package name and subpath
-        let parts: Vec<&str> = module_path.split('/').collect();
-        let package_name = parts[0];
-        let subpath = if parts.len() > 1 {
-            Some(parts[1..].join("/"))
-        } else {
-            None
-        };
+        let (package_name, subpath) = split_module_path(module_path);
         
         let mut current_dir = start_dir.to_path_buf();
         
@@ -277,7 +273,12 @@ impl ModuleResolver {
         let source = fs::read_to_string(&file_path)
             .map_err(|e| BuluError::IoError(format!("Failed to read {}: {}", file_path.display(), e)))?;
 
-        let mut lexer = Lexer::with_file(&source, file_path.to_string_lossy().to_string());
+        self.load_module_from_source(&file_path, &source)
+    }
+
+    /// Build a module from in-memory source, e.g. an unsaved editor buffer
+    pub fn load_module_from_source(&self, file_path: &Path, source: &str) -> Result<Module> {
+        let mut lexer = Lexer::with_file(source, file_path.to_string_lossy().to_string());
         let tokens = lexer.tokenize()?;
 
         let mut parser = Parser::with_file(tokens, file_path.to_string_lossy().to_string());
@@ -523,6 +524,14 @@ impl ModuleResolver {
         Ok(())
     }
 
+    /// Names of the virtual standard library modules (importable as `std/<name>`)
+    pub fn std_module_names() -> Vec<&'static str> {
+        let mut names = vec!["net", "io", "os", "flag"];
+        names.extend(crate::std::FUNCTION_MODULES.iter().map(|(name, _)| *name));
+        names.push("builtin");
+        names
+    }
+
     /// Create a virtual standard library module
     fn create_std_module(&self, module_path: &str) -> Result<Module> {
         let module_name = if module_path.starts_with("std/") {
@@ -535,10 +544,13 @@ impl ModuleResolver {
             "net" => self.create_net_module(),
             "time" => self.create_time_module(),
             "io" => self.create_io_module(),
-            "math" => self.create_math_module(),
             "os" => self.create_os_module(),
             "flag" => self.create_flag_module(),
-            _ => Err(BuluError::Other(format!("Unknown standard library module: {}", module_path)))
+            "builtin" => self.create_builtin_module(),
+            name => match crate::std::module_functions(name) {
+                Some(functions) => Self::exported_functions_module(name, functions),
+                None => Err(BuluError::Other(format!("Unknown standard library module: {}", module_path))),
+            },
         }
     }
 
@@ -566,7 +578,7 @@ impl ModuleResolver {
 
     /// Create the std/time module
     fn create_time_module(&self) -> Result<Module> {
-        let mut module = Module::new(PathBuf::from("std/time"), "time".to_string());
+        let mut module = Self::exported_functions_module("time", crate::std::time::EXPORTED_FUNCTIONS)?;
         
         // Add exports for time functions
         let position = Position::new(0, 0, 0);
@@ -593,47 +605,38 @@ impl ModuleResolver {
         Ok(module)
     }
 
-    /// Create the std/math module
-    fn create_math_module(&self) -> Result<Module> {
-        let mut module = Module::new(PathBuf::from("std/math"), "math".to_string());
+    /// Create the std/builtin module
+    fn create_builtin_module(&self) -> Result<Module> {
+        let mut module = Self::exported_functions_module("builtin", crate::std::builtin::EXPORTED_FUNCTIONS)?;
         
-        // Add exports for math functions
+        // Add exports for the values of the prelude
         let position = Position::new(0, 0, 0);
         
-        let abs_symbol = Symbol::new("abs".to_string(), SymbolKind::Function, Visibility::Public, position);
-        module.add_export("abs".to_string(), abs_symbol);
-        
-        let sqrt_symbol = Symbol::new("sqrt".to_string(), SymbolKind::Function, Visibility::Public, position);
-        module.add_export("sqrt".to_string(), sqrt_symbol);
-        
-        let pow_symbol = Symbol::new("pow".to_string(), SymbolKind::Function, Visibility::Public, position);
-        module.add_export("pow".to_string(), pow_symbol);
+        for name in crate::std::builtin::EXPORTED_VALUES {
+            let symbol = Symbol::new(name.to_string(), SymbolKind::Constant, Visibility::Public, position);
+            module.symbols.define(symbol.clone()).map_err(BuluError::Other)?;
+            module.add_export(name.to_string(), symbol);
+        }
 
         Ok(module)
     }
 
     /// Create the std/os module
     fn create_os_module(&self) -> Result<Module> {
-        let mut module = Module::new(PathBuf::from("std/os"), "os".to_string());
-        
-        // Add exports for OS functions
+        let functions: Vec<&str> = crate::std::os::exported_functions().collect();
+        Self::exported_functions_module("os", &functions)
+    }
+
+    /// Create a std module exporting the functions `exports`
+    fn exported_functions_module(name: &str, exports: &[&str]) -> Result<Module> {
+        let mut module = Module::new(PathBuf::from(format!("std/{}", name)), name.to_string());
         let position = Position::new(0, 0, 0);
-        
-        let args_symbol = Symbol::new("args".to_string(), SymbolKind::Function, Visibility::Public, position);
-        module.symbols.define(args_symbol.clone()).map_err(|e| BuluError::Other(e))?;
-        module.add_export("args".to_string(), args_symbol);
-        
-        let get_env_symbol = Symbol::new("getEnv".to_string(), SymbolKind::Function, Visibility::Public, position);
-        module.symbols.define(get_env_symbol.clone()).map_err(|e| BuluError::Other(e))?;
-        module.add_export("getEnv".to_string(), get_env_symbol);
-        
-        let cwd_symbol = Symbol::new("cwd".to_string(), SymbolKind::Function, Visibility::Public, position);
-        module.symbols.define(cwd_symbol.clone()).map_err(|e| BuluError::Other(e))?;
-        module.add_export("cwd".to_string(), cwd_symbol);
-        
-        let exit_symbol = Symbol::new("exit".to_string(), SymbolKind::Function, Visibility::Public, position);
-        module.symbols.define(exit_symbol.clone()).map_err(|e| BuluError::Other(e))?;
-        module.add_export("exit".to_string(), exit_symbol);
+
+        for export in exports {
+            let symbol = Symbol::new(export.to_string(), SymbolKind::Function, Visibility::Public, position);
+            module.symbols.define(symbol.clone()).map_err(BuluError::Other)?;
+            module.add_export(export.to_string(), symbol);
+        }
 
         Ok(module)
     }
@@ -679,4 +682,5 @@ impl ModuleResolver {
 
         Ok(module)
     }
+
 }
\ No newline at end of file
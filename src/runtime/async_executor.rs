@@ -225,3 +225,367 @@ impl Future for TcpAcceptFuture {
         }
     }
 }
+
+/// How long the reactor sleeps in the netpoller before retrying every pending operation
+const REACTOR_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// A network operation the reactor drives with non-blocking sockets
+#[derive(Debug)]
+pub enum NetOp {
+    Accept(Arc<std::net::TcpListener>),
+    Read {
+        stream: Arc<Mutex<std::net::TcpStream>>,
+        buffer_size: usize,
+    },
+    Write {
+        stream: Arc<Mutex<std::net::TcpStream>>,
+        data: Vec<u8>,
+        written: usize,
+    },
+    RecvFrom {
+        socket: Arc<std::net::UdpSocket>,
+        buffer_size: usize,
+    },
+    SendTo {
+        socket: Arc<std::net::UdpSocket>,
+        data: Vec<u8>,
+        addr: String,
+    },
+}
+
+/// What a completed network operation produced
+#[derive(Debug)]
+pub enum NetOutput {
+    Accepted(std::net::TcpStream, std::net::SocketAddr),
+    Read(Vec<u8>),
+    Written(usize),
+    Received(Vec<u8>, std::net::SocketAddr),
+    Sent(usize),
+}
+
+/// A network operation on the reactor's own duplicates of the runtime's sockets.
+///
+/// The duplicates share the socket with the blocking builtins, so the reactor
+/// never changes its blocking mode: TCP reads and writes pass `MSG_DONTWAIT`,
+/// and the operations without such a flag only run once `poll` reports the
+/// socket ready. Owning the duplicates also keeps the reactor from locking
+/// a stream another thread is reading.
+enum ReactorOp {
+    Accept(std::net::TcpListener),
+    Read {
+        stream: std::net::TcpStream,
+        buffer_size: usize,
+    },
+    Write {
+        stream: std::net::TcpStream,
+        data: Vec<u8>,
+        written: usize,
+    },
+    RecvFrom {
+        socket: std::net::UdpSocket,
+        buffer_size: usize,
+    },
+    SendTo {
+        socket: std::net::UdpSocket,
+        data: Vec<u8>,
+        addr: String,
+    },
+}
+
+impl NetOp {
+    /// Duplicate the sockets of the operation for the reactor
+    fn into_reactor_op(self) -> std::io::Result<ReactorOp> {
+        Ok(match self {
+            NetOp::Accept(listener) => ReactorOp::Accept(listener.try_clone()?),
+            NetOp::Read { stream, buffer_size } => ReactorOp::Read {
+                stream: stream.lock().unwrap().try_clone()?,
+                buffer_size,
+            },
+            NetOp::Write { stream, data, written } => ReactorOp::Write {
+                stream: stream.lock().unwrap().try_clone()?,
+                data,
+                written,
+            },
+            NetOp::RecvFrom { socket, buffer_size } => ReactorOp::RecvFrom {
+                socket: socket.try_clone()?,
+                buffer_size,
+            },
+            NetOp::SendTo { socket, data, addr } => ReactorOp::SendTo {
+                socket: socket.try_clone()?,
+                data,
+                addr,
+            },
+        })
+    }
+}
+
+/// Whether `fd` is ready for `event`, without waiting
+fn poll_ready(fd: std::os::unix::io::RawFd, event: PollEvent) -> std::io::Result<()> {
+    let events = match event {
+        PollEvent::Read => libc::POLLIN,
+        PollEvent::Write => libc::POLLOUT,
+        PollEvent::ReadWrite => libc::POLLIN | libc::POLLOUT,
+    };
+    let mut pollfd = libc::pollfd { fd, events, revents: 0 };
+    match unsafe { libc::poll(&mut pollfd, 1, 0) } {
+        -1 => Err(std::io::Error::last_os_error()),
+        0 => Err(std::io::ErrorKind::WouldBlock.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Turn the return value of a libc call into a byte count
+fn io_count(n: isize) -> std::io::Result<usize> {
+    if n < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(n as usize)
+    }
+}
+
+impl ReactorOp {
+    fn fd(&self) -> std::os::unix::io::RawFd {
+        use std::os::unix::io::AsRawFd;
+
+        match self {
+            ReactorOp::Accept(listener) => listener.as_raw_fd(),
+            ReactorOp::Read { stream, .. } | ReactorOp::Write { stream, .. } => stream.as_raw_fd(),
+            ReactorOp::RecvFrom { socket, .. } | ReactorOp::SendTo { socket, .. } => socket.as_raw_fd(),
+        }
+    }
+
+    fn event(&self) -> PollEvent {
+        match self {
+            ReactorOp::Accept(_) | ReactorOp::Read { .. } | ReactorOp::RecvFrom { .. } => PollEvent::Read,
+            ReactorOp::Write { .. } | ReactorOp::SendTo { .. } => PollEvent::Write,
+        }
+    }
+
+    /// Make progress without blocking. Returns `WouldBlock` while the socket is not ready.
+    fn attempt(&mut self) -> std::io::Result<NetOutput> {
+        let fd = self.fd();
+        match self {
+            ReactorOp::Accept(listener) => {
+                poll_ready(fd, PollEvent::Read)?;
+                let (stream, addr) = listener.accept()?;
+                stream.set_nonblocking(false)?;
+                Ok(NetOutput::Accepted(stream, addr))
+            }
+            ReactorOp::Read { buffer_size, .. } => {
+                let mut buffer = vec![0u8; *buffer_size];
+                let n = io_count(unsafe {
+                    libc::recv(fd, buffer.as_mut_ptr().cast(), buffer.len(), libc::MSG_DONTWAIT)
+                })?;
+                buffer.truncate(n);
+                Ok(NetOutput::Read(buffer))
+            }
+            ReactorOp::Write { data, written, .. } => {
+                while *written < data.len() {
+                    let rest = &data[*written..];
+                    let n = io_count(unsafe {
+                        libc::send(
+                            fd,
+                            rest.as_ptr().cast(),
+                            rest.len(),
+                            libc::MSG_DONTWAIT | libc::MSG_NOSIGNAL,
+                        )
+                    })?;
+                    if n == 0 {
+                        return Err(std::io::ErrorKind::WriteZero.into());
+                    }
+                    *written += n;
+                }
+                Ok(NetOutput::Written(*written))
+            }
+            ReactorOp::RecvFrom { socket, buffer_size } => {
+                poll_ready(fd, PollEvent::Read)?;
+                let mut buffer = vec![0u8; *buffer_size];
+                let (n, addr) = socket.recv_from(&mut buffer)?;
+                buffer.truncate(n);
+                Ok(NetOutput::Received(buffer, addr))
+            }
+            ReactorOp::SendTo { socket, data, addr } => {
+                poll_ready(fd, PollEvent::Write)?;
+                socket.send_to(data, addr.as_str()).map(NetOutput::Sent)
+            }
+        }
+    }
+}
+
+/// Called with the outcome of a network operation, on the reactor thread
+pub type NetCallback = Box<dyn FnOnce(std::io::Result<NetOutput>) + Send>;
+
+struct PendingNetOp {
+    op: ReactorOp,
+    callback: NetCallback,
+    fd: std::os::unix::io::RawFd,
+    registered: bool,
+}
+
+/// Drives network operations to completion on one background thread.
+///
+/// Operations are attempted with non-blocking sockets; the ones that are not
+/// ready are registered with the reactor's own netpoller, which the scheduler
+/// never polls, and the reactor sleeps in it until one of them is ready. Every
+/// pending operation is retried at least every `REACTOR_POLL_INTERVAL`, so a
+/// missed readiness event only delays an operation.
+pub struct NetReactor {
+    pending: Mutex<HashMap<u64, PendingNetOp>>,
+    submitted: std::sync::Condvar,
+    next_id: std::sync::atomic::AtomicU64,
+    poller: Option<NetPoller>,
+}
+
+static NET_REACTOR: std::sync::OnceLock<Arc<NetReactor>> = std::sync::OnceLock::new();
+
+/// The shared reactor, started on first use
+pub fn net_reactor() -> Arc<NetReactor> {
+    NET_REACTOR
+        .get_or_init(|| {
+            let reactor = Arc::new(NetReactor::new());
+            let driver = Arc::clone(&reactor);
+            std::thread::Builder::new()
+                .name("net-reactor".to_string())
+                .spawn(move || driver.run())
+                .expect("Failed to spawn net reactor thread");
+            reactor
+        })
+        .clone()
+}
+
+/// Run a network operation on the reactor and block the calling thread
+/// until it finishes. Gives up with `Interrupted` when the current context
+/// is cancelled.
+pub fn wait_net_op(op: NetOp) -> std::io::Result<NetOutput> {
+    use crate::runtime::context::{self, CANCEL_POLL_INTERVAL};
+    use std::sync::mpsc::RecvTimeoutError;
+
+    let reactor = net_reactor();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let id = reactor.submit(op, Box::new(move |result| {
+        let _ = sender.send(result);
+    }));
+    loop {
+        match receiver.recv_timeout(CANCEL_POLL_INTERVAL) {
+            Ok(result) => return result,
+            Err(RecvTimeoutError::Timeout) => {
+                if let Some(error) = context::current().and_then(|ctx| ctx.err()) {
+                    if reactor.cancel(id) {
+                        return Err(std::io::Error::new(std::io::ErrorKind::Interrupted, error.message()));
+                    }
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(std::io::Error::other("network operation was dropped"));
+            }
+        }
+    }
+}
+
+impl NetReactor {
+    fn new() -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            submitted: std::sync::Condvar::new(),
+            next_id: std::sync::atomic::AtomicU64::new(1),
+            poller: NetPoller::new().ok(),
+        }
+    }
+
+    /// Start an operation; `callback` runs once it completes or fails
+    pub fn submit(&self, op: NetOp, callback: NetCallback) -> u64 {
+        let id = self.next_id.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let op = match op.into_reactor_op() {
+            Ok(op) => op,
+            Err(e) => {
+                callback(Err(e));
+                return id;
+            }
+        };
+        let fd = op.fd();
+        self.pending.lock().unwrap().insert(
+            id,
+            PendingNetOp {
+                op,
+                callback,
+                fd,
+                registered: false,
+            },
+        );
+        self.submitted.notify_one();
+        id
+    }
+
+    /// Drop a pending operation without running its callback. Returns false
+    /// if it already completed.
+    pub fn cancel(&self, id: u64) -> bool {
+        match self.pending.lock().unwrap().remove(&id) {
+            Some(pending) => {
+                self.unregister(id, &pending);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of operations still waiting for their socket
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    fn unregister(&self, id: u64, pending: &PendingNetOp) {
+        if let (true, Some(poller)) = (pending.registered, &self.poller) {
+            let _ = poller.unregister(pending.fd, id);
+        }
+    }
+
+    /// Attempt every pending operation once and run the callbacks of the finished ones
+    fn turn(&self) {
+        let mut finished = Vec::new();
+        {
+            let mut pending = self.pending.lock().unwrap();
+            let mut results = Vec::new();
+            for (id, entry) in pending.iter_mut() {
+                match entry.op.attempt() {
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        if let (false, Some(poller)) = (entry.registered, &self.poller) {
+                            entry.registered = poller.register(entry.fd, *id, entry.op.event()).is_ok();
+                        }
+                    }
+                    result => results.push((*id, result)),
+                }
+            }
+            for (id, result) in results {
+                if let Some(entry) = pending.remove(&id) {
+                    self.unregister(id, &entry);
+                    finished.push((entry.callback, result));
+                }
+            }
+        }
+        // Callbacks run without the pending lock so they may submit new operations
+        for (callback, result) in finished {
+            callback(result);
+        }
+    }
+
+    fn run(&self) {
+        loop {
+            {
+                let mut pending = self.pending.lock().unwrap();
+                while pending.is_empty() {
+                    pending = self.submitted.wait(pending).unwrap();
+                }
+            }
+            self.turn();
+            if self.pending_count() == 0 {
+                continue;
+            }
+            match &self.poller {
+                Some(poller) => {
+                    let _ = poller.poll(REACTOR_POLL_INTERVAL);
+                }
+                None => std::thread::sleep(std::time::Duration::from_millis(1)),
+            }
+        }
+    }
+}
@@ -8,11 +8,17 @@
 //! - I/O functions (print(), println(), printf(), input())
 
 use crate::error::{BuluError, Result};
-use crate::types::primitive::{PrimitiveType, RuntimeValue, TypeId};
+use crate::types::primitive::{
+    format_float32, format_float64, sorted_map_entries, widen_float32, PrimitiveType, RuntimeValue, TypeId,
+};
 
 use crate::runtime::channels::{Channel, ChannelRegistry};
 use crate::runtime::promises::PromiseRegistry;
-use crate::runtime::sync::{sleep, timer, yield_now, AtomicOperations, LockRegistry};
+use crate::runtime::async_executor::{wait_net_op, NetOp, NetOutput};
+use crate::runtime::context;
+use crate::runtime::output::{self, Stream};
+use crate::runtime::sets;
+use crate::runtime::sync::{timer, yield_now, AtomicOperations, LockRegistry};
 use std::collections::HashMap;
 use std::io::{self, Read, Write};
 use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
@@ -206,6 +212,8 @@ impl BuiltinRegistry {
     /// Register utility functions
     fn register_utility_functions(&mut self) {
         self.register("typeof", builtin_typeof);
+        self.register("hash", builtin_hash);
+        self.register("intrinsic", builtin_intrinsic);
         self.register("instanceof", builtin_instanceof);
         self.register("panic", builtin_panic);
         self.register("assert", builtin_assert);
@@ -492,7 +500,7 @@ pub fn builtin_string(args: &[RuntimeValue]) -> Result<RuntimeValue> {
                     })
                     .collect();
                 return Ok(RuntimeValue::String(
-                    String::from_utf8_lossy(&bytes).to_string(),
+                    crate::runtime::bytes::string_from_utf8_lossy(bytes),
                 ));
             }
         }
@@ -518,7 +526,7 @@ pub fn builtin_get_read_data(_args: &[RuntimeValue]) -> Result<RuntimeValue> {
 // MEMORY FUNCTIONS
 // ============================================================================
 
-/// Get length of array, slice, string, or map
+/// Get length of array, slice, string, map, or set
 pub fn builtin_len(args: &[RuntimeValue]) -> Result<RuntimeValue> {
     if args.len() != 1 {
         return Err(BuluError::RuntimeError {
@@ -532,6 +540,7 @@ pub fn builtin_len(args: &[RuntimeValue]) -> Result<RuntimeValue> {
         RuntimeValue::Array(arr) => Ok(RuntimeValue::Int32(arr.len() as i32)),
         RuntimeValue::Slice(slice) => Ok(RuntimeValue::Int32(slice.len() as i32)),
         RuntimeValue::Map(map) => Ok(RuntimeValue::Int32(map.len() as i32)),
+        RuntimeValue::Set(set) => Ok(RuntimeValue::Int32(set.len() as i32)),
         RuntimeValue::Channel(channel_id) => {
             // Get channel length from global registry
             let registry = crate::runtime::interpreter::get_global_channel_registry()
@@ -697,6 +706,7 @@ pub fn builtin_sizeof(args: &[RuntimeValue]) -> Result<RuntimeValue> {
         RuntimeValue::Map(map) => {
             map.len() * (std::mem::size_of::<String>() + std::mem::size_of::<RuntimeValue>())
         } // Map size
+        RuntimeValue::Set(set) => set.len() * std::mem::size_of::<RuntimeValue>(), // Set size
         RuntimeValue::Integer(_) => 8, // Generic integer is 64-bit
         RuntimeValue::Byte(_) => 1,    // Byte is 1 byte
         RuntimeValue::Null => 0,
@@ -900,7 +910,7 @@ pub fn builtin_make(args: &[RuntimeValue]) -> Result<RuntimeValue> {
         1 => {
             // Single argument - assume it's a map
             let map = std::collections::HashMap::new();
-            Ok(RuntimeValue::Map(map))
+            Ok(RuntimeValue::Map(map.into()))
         }
         2 => {
             // Two arguments - assume it's a slice with length
@@ -1089,9 +1099,14 @@ pub fn builtin_delete(args: &[RuntimeValue]) -> Result<RuntimeValue> {
             new_map.remove(&key);
             Ok(RuntimeValue::Map(new_map))
         }
+        RuntimeValue::Set(set) => {
+            let mut new_set = set.clone();
+            sets::remove(&mut new_set, &args[1]);
+            Ok(RuntimeValue::Set(new_set))
+        }
         _ => Err(BuluError::RuntimeError {
             file: None,
-            message: "delete() first argument must be a map".to_string(),
+            message: "delete() first argument must be a map or set".to_string(),
         }),
     }
 }
@@ -1203,29 +1218,17 @@ pub fn builtin_create_range(args: &[RuntimeValue]) -> Result<RuntimeValue> {
 
 /// Print values to stdout
 pub fn builtin_print(args: &[RuntimeValue]) -> Result<RuntimeValue> {
-    for (i, arg) in args.iter().enumerate() {
-        if i > 0 {
-            print!(" ");
-        }
-        print!("{}", format_runtime_value(arg));
-    }
-    io::stdout().flush().map_err(|e| BuluError::RuntimeError {
-        file: None,
-        message: format!("Failed to flush stdout: {}", e),
-    })?;
+    let text: Vec<String> = args.iter().map(format_runtime_value).collect();
+    output::write(Stream::Stdout, &text.join(" ")).map_err(|e| output::write_error(Stream::Stdout, e))?;
 
     Ok(RuntimeValue::Null)
 }
 
 /// Print values to stdout with newline
 pub fn builtin_println(args: &[RuntimeValue]) -> Result<RuntimeValue> {
-    for (i, arg) in args.iter().enumerate() {
-        if i > 0 {
-            print!(" ");
-        }
-        print!("{}", format_runtime_value(arg));
-    }
-    println!();
+    let text: Vec<String> = args.iter().map(format_runtime_value).collect();
+    output::write(Stream::Stdout, &format!("{}\n", text.join(" ")))
+        .map_err(|e| output::write_error(Stream::Stdout, e))?;
 
     Ok(RuntimeValue::Null)
 }
@@ -1241,11 +1244,7 @@ pub fn builtin_printf(args: &[RuntimeValue]) -> Result<RuntimeValue> {
 
     if let RuntimeValue::String(format_str) = &args[0] {
         let formatted = format_string_with_args(format_str, &args[1..])?;
-        print!("{}", formatted);
-        io::stdout().flush().map_err(|e| BuluError::RuntimeError {
-            file: None,
-            message: format!("Failed to flush stdout: {}", e),
-        })?;
+        output::write(Stream::Stdout, &formatted).map_err(|e| output::write_error(Stream::Stdout, e))?;
     } else {
         return Err(BuluError::RuntimeError {
             file: None,
@@ -1261,11 +1260,7 @@ pub fn builtin_input(args: &[RuntimeValue]) -> Result<RuntimeValue> {
     // Print prompt if provided
     if !args.is_empty() {
         if let RuntimeValue::String(prompt) = &args[0] {
-            print!("{}", prompt);
-            io::stdout().flush().map_err(|e| BuluError::RuntimeError {
-                file: None,
-                message: format!("Failed to flush stdout: {}", e),
-            })?;
+            output::write(Stream::Stdout, prompt).map_err(|e| output::write_error(Stream::Stdout, e))?;
         }
     }
 
@@ -1329,11 +1324,7 @@ pub fn builtin_eprint(args: &[RuntimeValue]) -> Result<RuntimeValue> {
     }
 
     if let RuntimeValue::String(text) = &args[0] {
-        eprint!("{}", text);
-        io::stderr().flush().map_err(|e| BuluError::RuntimeError {
-            file: None,
-            message: format!("Failed to flush stderr: {}", e),
-        })?;
+        output::write(Stream::Stderr, text).map_err(|e| output::write_error(Stream::Stderr, e))?;
     }
 
     Ok(RuntimeValue::Null)
@@ -1341,14 +1332,12 @@ pub fn builtin_eprint(args: &[RuntimeValue]) -> Result<RuntimeValue> {
 
 /// Print to stderr with newline
 pub fn builtin_eprintln(args: &[RuntimeValue]) -> Result<RuntimeValue> {
-    if args.is_empty() {
-        eprintln!();
-        return Ok(RuntimeValue::Null);
-    }
-
-    if let RuntimeValue::String(text) = &args[0] {
-        eprintln!("{}", text);
-    }
+    let text = match args.first() {
+        Some(RuntimeValue::String(text)) => text.as_str(),
+        Some(_) => return Ok(RuntimeValue::Null),
+        None => "",
+    };
+    output::write(Stream::Stderr, &format!("{}\n", text)).map_err(|e| output::write_error(Stream::Stderr, e))?;
 
     Ok(RuntimeValue::Null)
 }
@@ -1366,7 +1355,24 @@ pub fn builtin_typeof(args: &[RuntimeValue]) -> Result<RuntimeValue> {
         });
     }
 
-    let type_name = match &args[0] {
+    Ok(RuntimeValue::String(runtime_type_name(&args[0]).to_string()))
+}
+
+/// Intrinsics are machine instructions, which only the native backend emits
+pub fn builtin_intrinsic(args: &[RuntimeValue]) -> Result<RuntimeValue> {
+    let name = match args.first() {
+        Some(RuntimeValue::String(name)) => name.as_str(),
+        _ => "?",
+    };
+    Err(BuluError::RuntimeError {
+        file: None,
+        message: crate::compiler::intrinsics::interpreted_message(name),
+    })
+}
+
+/// The type name `typeof` reports for a runtime value
+pub fn runtime_type_name(value: &RuntimeValue) -> &str {
+    match value {
         RuntimeValue::Int8(_) => "int8",
         RuntimeValue::Int16(_) => "int16",
         RuntimeValue::Int32(_) => "int32",
@@ -1388,6 +1394,7 @@ pub fn builtin_typeof(args: &[RuntimeValue]) -> Result<RuntimeValue> {
         RuntimeValue::Slice(_) => "slice",
         RuntimeValue::Tuple(_) => "tuple",
         RuntimeValue::Map(_) => "map",
+        RuntimeValue::Set(_) => "set",
         RuntimeValue::Integer(_) => "integer",
         RuntimeValue::Byte(_) => "byte",
         RuntimeValue::Function(_) => "function",
@@ -1397,9 +1404,210 @@ pub fn builtin_typeof(args: &[RuntimeValue]) -> Result<RuntimeValue> {
         RuntimeValue::Global(_) => "global",
         RuntimeValue::Range(_, _, _) => "range",
         RuntimeValue::Null => "null",
+    }
+}
+
+/// hash(value) or hash(value, seed) - a non-negative int64 hash of a value.
+///
+/// Equal values hash equally: integers by numeric value whatever their width,
+/// arrays and slices element by element, maps and structs independent of field
+/// order. A seeded hash is the same in every run; an unseeded hash uses a seed
+/// chosen once per process.
+pub fn builtin_hash(args: &[RuntimeValue]) -> Result<RuntimeValue> {
+    let seed = match args {
+        [_] => process_hash_seed(),
+        [_, seed] => match seed {
+            RuntimeValue::Int8(i) => *i as u64,
+            RuntimeValue::Int16(i) => *i as u64,
+            RuntimeValue::Int32(i) => *i as u64,
+            RuntimeValue::Int64(i) | RuntimeValue::Integer(i) => *i as u64,
+            RuntimeValue::UInt8(i) => *i as u64,
+            RuntimeValue::UInt16(i) => *i as u64,
+            RuntimeValue::UInt32(i) => *i as u64,
+            RuntimeValue::UInt64(i) => *i,
+            other => {
+                return Err(BuluError::RuntimeError {
+                    file: None,
+                    message: format!("hash() seed must be an integer, got {}", runtime_type_name(other)),
+                })
+            }
+        },
+        _ => {
+            return Err(BuluError::RuntimeError {
+                file: None,
+                message: "hash() expects 1 or 2 arguments (value, seed)".to_string(),
+            })
+        }
     };
 
-    Ok(RuntimeValue::String(type_name.to_string()))
+    let hash = stable_hash(&args[0], seed)?;
+    Ok(RuntimeValue::Int64((hash >> 1) as i64))
+}
+
+/// Seed for unseeded `hash` calls, fixed for the life of the process
+fn process_hash_seed() -> u64 {
+    static SEED: OnceLock<u64> = OnceLock::new();
+    *SEED.get_or_init(|| {
+        use std::hash::{BuildHasher, Hasher};
+        std::collections::hash_map::RandomState::new().build_hasher().finish()
+    })
+}
+
+/// Seeded 64-bit hash of a runtime value that does not depend on the Rust
+/// version, the platform or map iteration order. Handles, functions and NaN
+/// have no stable identity and are rejected.
+pub fn stable_hash(value: &RuntimeValue, seed: u64) -> Result<u64> {
+    let mut hasher = StableHasher::new(seed);
+    hasher.value(value)?;
+    Ok(hasher.finish())
+}
+
+/// FNV-1a over a tagged encoding of the value, finished with a 64-bit mixer
+struct StableHasher {
+    state: u64,
+}
+
+impl StableHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn new(seed: u64) -> Self {
+        let mut hasher = Self { state: Self::OFFSET_BASIS };
+        hasher.bytes(&seed.to_le_bytes());
+        hasher
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= *byte as u64;
+            self.state = self.state.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn tag(&mut self, tag: u8) {
+        self.bytes(&[tag]);
+    }
+
+    fn len(&mut self, len: usize) {
+        self.bytes(&(len as u64).to_le_bytes());
+    }
+
+    fn str(&mut self, s: &str) {
+        self.len(s.len());
+        self.bytes(s.as_bytes());
+    }
+
+    fn int(&mut self, value: i128) {
+        self.tag(1);
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn value(&mut self, value: &RuntimeValue) -> Result<()> {
+        match value {
+            RuntimeValue::Int8(i) => self.int(*i as i128),
+            RuntimeValue::Int16(i) => self.int(*i as i128),
+            RuntimeValue::Int32(i) => self.int(*i as i128),
+            RuntimeValue::Int64(i) | RuntimeValue::Integer(i) => self.int(*i as i128),
+            RuntimeValue::UInt8(i) | RuntimeValue::Byte(i) => self.int(*i as i128),
+            RuntimeValue::UInt16(i) => self.int(*i as i128),
+            RuntimeValue::UInt32(i) => self.int(*i as i128),
+            RuntimeValue::UInt64(i) => self.int(*i as i128),
+            RuntimeValue::Float32(f) => self.float(widen_float32(*f))?,
+            RuntimeValue::Float64(f) => self.float(*f)?,
+            RuntimeValue::Bool(b) => {
+                self.tag(3);
+                self.bytes(&[*b as u8]);
+            }
+            RuntimeValue::Char(c) => {
+                self.tag(4);
+                self.bytes(&(*c as u32).to_le_bytes());
+            }
+            RuntimeValue::String(s) => {
+                self.tag(5);
+                self.str(s);
+            }
+            RuntimeValue::Array(items) | RuntimeValue::Slice(items) => {
+                self.tag(6);
+                self.sequence(items)?;
+            }
+            RuntimeValue::Tuple(items) => {
+                self.tag(7);
+                self.sequence(items)?;
+            }
+            RuntimeValue::Map(map) => {
+                self.tag(8);
+                self.len(map.len());
+                for (key, value) in sorted_map_entries(map) {
+                    self.str(key);
+                    self.value(value)?;
+                }
+            }
+            RuntimeValue::Struct { name, fields } => {
+                self.tag(9);
+                self.str(name);
+                let mut fields: Vec<_> = fields.iter().collect();
+                fields.sort_by_key(|(field, _)| *field);
+                self.len(fields.len());
+                for (field, value) in fields {
+                    self.str(field);
+                    self.value(value)?;
+                }
+            }
+            RuntimeValue::Set(items) => {
+                // Elements are kept in a canonical order, so equal sets hash equally
+                self.tag(11);
+                self.sequence(items)?;
+            }
+            RuntimeValue::Range(start, end, step) => {
+                self.tag(10);
+                self.bytes(&start.to_le_bytes());
+                self.bytes(&end.to_le_bytes());
+                self.bytes(&step.unwrap_or(1).to_le_bytes());
+            }
+            RuntimeValue::Null => self.tag(0),
+            RuntimeValue::Lock(_)
+            | RuntimeValue::Channel(_)
+            | RuntimeValue::Goroutine(_)
+            | RuntimeValue::Promise(_)
+            | RuntimeValue::Function(_)
+            | RuntimeValue::ModuleFunction { .. }
+            | RuntimeValue::MethodRef { .. }
+            | RuntimeValue::Global(_) => {
+                return Err(BuluError::RuntimeError {
+                    file: None,
+                    message: format!("hash() of unhashable type {}", runtime_type_name(value)),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    fn float(&mut self, value: f64) -> Result<()> {
+        if value.is_nan() {
+            return Err(BuluError::RuntimeError {
+                file: None,
+                message: "hash() of NaN: NaN is not equal to itself".to_string(),
+            });
+        }
+        // 0.0 and -0.0 compare equal
+        let value = if value == 0.0 { 0.0 } else { value };
+        self.tag(2);
+        self.bytes(&value.to_bits().to_le_bytes());
+        Ok(())
+    }
+
+    fn sequence(&mut self, items: &[RuntimeValue]) -> Result<()> {
+        self.len(items.len());
+        items.iter().try_for_each(|item| self.value(item))
+    }
+
+    fn finish(&self) -> u64 {
+        // splitmix64 finalizer, so nearby inputs land far apart
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
 }
 
 /// Check if a value is an instance of a specific type
@@ -1444,6 +1652,7 @@ pub fn builtin_instanceof(args: &[RuntimeValue]) -> Result<RuntimeValue> {
         RuntimeValue::Slice(_) => "slice",
         RuntimeValue::Tuple(_) => "tuple",
         RuntimeValue::Map(_) => "map",
+        RuntimeValue::Set(_) => "set",
         RuntimeValue::Integer(_) => "integer",
         RuntimeValue::Byte(_) => "byte",
         RuntimeValue::Function(_) => "function",
@@ -1627,7 +1836,7 @@ pub fn builtin_sleep(args: &[RuntimeValue]) -> Result<RuntimeValue> {
 
     let milliseconds = match &args[0] {
         RuntimeValue::Int32(ms) => *ms as u64,
-        RuntimeValue::Int64(ms) => *ms as u64,
+        RuntimeValue::Int64(ms) | RuntimeValue::Integer(ms) => *ms as u64,
         RuntimeValue::UInt32(ms) => *ms as u64,
         RuntimeValue::UInt64(ms) => *ms,
         _ => {
@@ -1638,7 +1847,8 @@ pub fn builtin_sleep(args: &[RuntimeValue]) -> Result<RuntimeValue> {
         }
     };
 
-    sleep(milliseconds);
+    // Returns early with an error once the current context is cancelled
+    context::sleep(Duration::from_millis(milliseconds))?;
     Ok(RuntimeValue::Null)
 }
 
@@ -1928,8 +2138,8 @@ pub fn format_runtime_value(value: &RuntimeValue) -> String {
         RuntimeValue::UInt16(i) => i.to_string(),
         RuntimeValue::UInt32(i) => i.to_string(),
         RuntimeValue::UInt64(i) => i.to_string(),
-        RuntimeValue::Float32(f) => f.to_string(),
-        RuntimeValue::Float64(f) => f.to_string(),
+        RuntimeValue::Float32(f) => format_float32(*f),
+        RuntimeValue::Float64(f) => format_float64(*f),
         RuntimeValue::Bool(b) => b.to_string(),
         RuntimeValue::Char(c) => c.to_string(),
         RuntimeValue::String(s) => s.clone(),
@@ -1950,12 +2160,16 @@ pub fn format_runtime_value(value: &RuntimeValue) -> String {
             format!("({})", elements.join(", "))
         }
         RuntimeValue::Map(map) => {
-            let pairs: Vec<String> = map
-                .iter()
+            let pairs: Vec<String> = sorted_map_entries(map)
+                .into_iter()
                 .map(|(k, v)| format!("{}: {}", k, format_runtime_value(v)))
                 .collect();
             format!("{{{}}}", pairs.join(", "))
         }
+        RuntimeValue::Set(set) => {
+            let elements: Vec<String> = set.iter().map(format_runtime_value).collect();
+            format!("set{{{}}}", elements.join(", "))
+        }
         RuntimeValue::Integer(i) => i.to_string(),
         RuntimeValue::Byte(b) => b.to_string(),
         RuntimeValue::Function(name) => format!("function({})", name),
@@ -1980,142 +2194,89 @@ pub fn format_runtime_value(value: &RuntimeValue) -> String {
     }
 }
 
-/// Format a string with arguments using basic format specifiers
-fn format_string_with_args(format_str: &str, args: &[RuntimeValue]) -> Result<String> {
-    let mut result = String::new();
-    let mut chars = format_str.chars().peekable();
-    let mut arg_index = 0;
-
-    while let Some(ch) = chars.next() {
-        if ch == '%' {
-            if let Some(&next_ch) = chars.peek() {
-                if next_ch == '%' {
-                    // Escaped %
-                    chars.next();
-                    result.push('%');
-                } else {
-                    // Format specifier
-                    chars.next(); // consume the format character
+/// Format a string with printf-style conversions (`%d`, `%-8.2f`, `%v`, ...)
+pub fn format_string_with_args(format_str: &str, args: &[RuntimeValue]) -> Result<String> {
+    use crate::std::fmt::{PrintfArg, PrintfPiece};
 
-                    if arg_index >= args.len() {
-                        return Err(BuluError::RuntimeError {
-                            file: None,
-                            message: format!("printf: not enough arguments for format string"),
-                        });
-                    }
+    let error = |message: String| BuluError::RuntimeError { file: None, message };
+    let compiled = crate::runtime::pattern_cache::compile_printf(format_str);
+    let pieces = compiled.as_ref().as_ref().map_err(|e| error(format!("printf: {}", e)))?;
 
-                    let formatted = match next_ch {
-                        'd' | 'i' => {
-                            // Integer format
-                            match &args[arg_index] {
-                                RuntimeValue::Int8(i) => i.to_string(),
-                                RuntimeValue::Int16(i) => i.to_string(),
-                                RuntimeValue::Int32(i) => i.to_string(),
-                                RuntimeValue::Int64(i) => i.to_string(),
-                                RuntimeValue::UInt8(i) => i.to_string(),
-                                RuntimeValue::UInt16(i) => i.to_string(),
-                                RuntimeValue::UInt32(i) => i.to_string(),
-                                RuntimeValue::UInt64(i) => i.to_string(),
-                                _ => format_runtime_value(&args[arg_index]),
-                            }
-                        }
-                        'f' => {
-                            // Float format
-                            match &args[arg_index] {
-                                RuntimeValue::Float32(f) => format!("{:.6}", f),
-                                RuntimeValue::Float64(f) => format!("{:.6}", f),
-                                _ => format_runtime_value(&args[arg_index]),
-                            }
-                        }
-                        'g' => {
-                            // General float format
-                            match &args[arg_index] {
-                                RuntimeValue::Float32(f) => f.to_string(),
-                                RuntimeValue::Float64(f) => f.to_string(),
-                                _ => format_runtime_value(&args[arg_index]),
-                            }
-                        }
-                        's' => {
-                            // String format
-                            match &args[arg_index] {
-                                RuntimeValue::String(s) => s.clone(),
-                                _ => format_runtime_value(&args[arg_index]),
-                            }
-                        }
-                        'c' => {
-                            // Character format
-                            match &args[arg_index] {
-                                RuntimeValue::Char(c) => c.to_string(),
-                                RuntimeValue::Int32(i) => {
-                                    if let Some(c) = char::from_u32(*i as u32) {
-                                        c.to_string()
-                                    } else {
-                                        "?".to_string()
-                                    }
-                                }
-                                _ => format_runtime_value(&args[arg_index]),
-                            }
-                        }
-                        'b' => {
-                            // Boolean format
-                            match &args[arg_index] {
-                                RuntimeValue::Bool(b) => b.to_string(),
-                                _ => args[arg_index].is_truthy().to_string(),
-                            }
-                        }
-                        'x' => {
-                            // Hexadecimal format
-                            match &args[arg_index] {
-                                RuntimeValue::Int8(i) => format!("{:x}", i),
-                                RuntimeValue::Int16(i) => format!("{:x}", i),
-                                RuntimeValue::Int32(i) => format!("{:x}", i),
-                                RuntimeValue::Int64(i) => format!("{:x}", i),
-                                RuntimeValue::UInt8(i) => format!("{:x}", i),
-                                RuntimeValue::UInt16(i) => format!("{:x}", i),
-                                RuntimeValue::UInt32(i) => format!("{:x}", i),
-                                RuntimeValue::UInt64(i) => format!("{:x}", i),
-                                _ => format_runtime_value(&args[arg_index]),
-                            }
-                        }
-                        'o' => {
-                            // Octal format
-                            match &args[arg_index] {
-                                RuntimeValue::Int8(i) => format!("{:o}", i),
-                                RuntimeValue::Int16(i) => format!("{:o}", i),
-                                RuntimeValue::Int32(i) => format!("{:o}", i),
-                                RuntimeValue::Int64(i) => format!("{:o}", i),
-                                RuntimeValue::UInt8(i) => format!("{:o}", i),
-                                RuntimeValue::UInt16(i) => format!("{:o}", i),
-                                RuntimeValue::UInt32(i) => format!("{:o}", i),
-                                RuntimeValue::UInt64(i) => format!("{:o}", i),
-                                _ => format_runtime_value(&args[arg_index]),
-                            }
-                        }
-                        'v' => {
-                            // Default format (like %s but for any type)
-                            format_runtime_value(&args[arg_index])
-                        }
-                        _ => {
-                            // Unknown format specifier, just use the character
-                            format!("%{}", next_ch)
-                        }
-                    };
+    let expected = pieces
+        .iter()
+        .filter(|piece| matches!(piece, PrintfPiece::Spec(_)))
+        .count();
+    if args.len() < expected {
+        return Err(error("printf: not enough arguments for format string".to_string()));
+    }
+    if args.len() > expected {
+        return Err(error(format!(
+            "printf: too many arguments for format string (expected {}, got {})",
+            expected,
+            args.len()
+        )));
+    }
 
-                    result.push_str(&formatted);
-                    arg_index += 1;
-                }
-            } else {
-                // % at end of string
-                result.push('%');
+    let mut result = String::new();
+    let mut args = args.iter();
+    for piece in pieces {
+        let spec = match piece {
+            PrintfPiece::Literal(text) => {
+                result.push_str(text);
+                continue;
+            }
+            PrintfPiece::Spec(spec) => spec,
+        };
+
+        let arg = args.next().expect("argument count was checked");
+        let formatted = match (spec.arg_kind(), arg) {
+            (PrintfArg::Float, RuntimeValue::Float32(f)) => Some(spec.format_float(widen_float32(*f))),
+            (PrintfArg::Float, RuntimeValue::Float64(f)) => Some(spec.format_float(*f)),
+            (PrintfArg::Integer | PrintfArg::Float, value) => printf_integer(value).map(|i| spec.format_integer(i)),
+            (PrintfArg::String, RuntimeValue::String(s)) => Some(spec.format_str(s)),
+            (PrintfArg::Char, RuntimeValue::Char(c)) => Some(spec.format_str(&c.to_string())),
+            (PrintfArg::Char, value) => printf_integer(value)
+                .and_then(|i| u32::try_from(i).ok())
+                .and_then(char::from_u32)
+                .map(|c| spec.format_str(&c.to_string())),
+            (PrintfArg::Bool, RuntimeValue::Bool(b)) => Some(spec.format_str(&b.to_string())),
+            (PrintfArg::Any, value) => Some(spec.format_str(&format_runtime_value(value))),
+            _ => None,
+        };
+
+        match formatted {
+            Some(text) => result.push_str(&text),
+            None => {
+                return Err(error(format!(
+                    "printf: %{} expects {}, got {}",
+                    spec.verb,
+                    spec.arg_kind().description(),
+                    runtime_type_name(arg)
+                )))
             }
-        } else {
-            result.push(ch);
         }
     }
 
     Ok(result)
 }
 
+/// Integer payload of a runtime value, for printf's integer conversions
+fn printf_integer(value: &RuntimeValue) -> Option<i128> {
+    match value {
+        RuntimeValue::Integer(i) => Some(*i as i128),
+        RuntimeValue::Int8(i) => Some(*i as i128),
+        RuntimeValue::Int16(i) => Some(*i as i128),
+        RuntimeValue::Int32(i) => Some(*i as i128),
+        RuntimeValue::Int64(i) => Some(*i as i128),
+        RuntimeValue::UInt8(i) => Some(*i as i128),
+        RuntimeValue::UInt16(i) => Some(*i as i128),
+        RuntimeValue::UInt32(i) => Some(*i as i128),
+        RuntimeValue::UInt64(i) => Some(*i as i128),
+        RuntimeValue::Byte(b) => Some(*b as i128),
+        _ => None,
+    }
+}
+
 // ============================================================================
 // CHANNEL FUNCTIONS
 // ============================================================================
@@ -2351,7 +2512,7 @@ pub fn builtin_make_map(
     // In a full implementation, we'd support different key types
     let _ = (key_type, value_type);
     let map = std::collections::HashMap::new();
-    Ok(RuntimeValue::Map(map))
+    Ok(RuntimeValue::Map(map.into()))
 }
 
 /// Make channel: make(chan T) or make(chan T, capacity)
@@ -2630,6 +2791,138 @@ pub fn builtin_udpconnection_bind(args: &[RuntimeValue]) -> Result<RuntimeValue>
     }
 }
 
+/// Start a network method on the reactor instead of blocking: `TcpServer.accept`,
+/// `TcpConnection.read`/`write` and `UdpConnection.recv_from`/`send_to`. `args`
+/// starts with the receiver. Returns `None` for any other call.
+pub fn async_net_op(method: &str, args: &[RuntimeValue]) -> Result<Option<NetOp>> {
+    let Some(RuntimeValue::Struct { name, fields }) = args.first() else {
+        return Ok(None);
+    };
+    let id = |field: &str| match fields.get(field) {
+        Some(RuntimeValue::String(id)) => Ok(id.clone()),
+        _ => Err(BuluError::RuntimeError {
+            message: format!("Invalid {}: missing {}", name, field),
+            file: None,
+        }),
+    };
+    let data = |value: Option<&RuntimeValue>| match value {
+        Some(RuntimeValue::Array(bytes)) => Ok(bytes
+            .iter()
+            .filter_map(|v| match v {
+                RuntimeValue::Int32(i) => Some(*i as u8),
+                RuntimeValue::UInt8(b) => Some(*b),
+                _ => None,
+            })
+            .collect()),
+        Some(RuntimeValue::String(s)) => Ok(s.as_bytes().to_vec()),
+        _ => Err(BuluError::RuntimeError {
+            message: format!("{}.{}() data must be a byte array or string", name, method),
+            file: None,
+        }),
+    };
+    let missing = |what: &str| BuluError::RuntimeError {
+        message: format!("{} not found", what),
+        file: None,
+    };
+
+    let op = match (name.as_str(), method, args.len()) {
+        ("TcpServer", "accept", 1) => {
+            let listener = get_tcp_servers().lock().unwrap().get(&id("server_id")?).cloned();
+            NetOp::Accept(listener.ok_or_else(|| missing("Server"))?)
+        }
+        ("TcpConnection", "read", 2) | ("TcpConnection", "write", 2) => {
+            let connection = get_tcp_connections().lock().unwrap().get(&id("connection_id")?).cloned();
+            let stream = connection.ok_or_else(|| missing("Connection"))?;
+            if method == "read" {
+                NetOp::Read { stream, buffer_size: 1024 }
+            } else {
+                NetOp::Write { stream, data: data(args.get(1))?, written: 0 }
+            }
+        }
+        ("UdpConnection", "recv_from", 2) | ("UdpConnection", "send_to", 3) => {
+            let socket = get_udp_sockets().lock().unwrap().get(&id("connection_id")?).cloned();
+            let socket = socket.ok_or_else(|| missing("Socket"))?;
+            if method == "recv_from" {
+                NetOp::RecvFrom { socket, buffer_size: 1024 }
+            } else {
+                let addr = match args.get(2) {
+                    Some(RuntimeValue::String(addr)) => addr.clone(),
+                    _ => {
+                        return Err(BuluError::RuntimeError {
+                            message: "UdpConnection.send_to() address must be a string".to_string(),
+                            file: None,
+                        })
+                    }
+                };
+                NetOp::SendTo { socket, data: data(args.get(1))?, addr }
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(op))
+}
+
+/// The `Result` a network builtin returns for a finished reactor operation
+pub fn net_output_to_result(output: io::Result<NetOutput>) -> RuntimeValue {
+    let remember_read = |bytes: &[u8]| {
+        if let Ok(mut last) = get_last_read_data().lock() {
+            *last = String::from_utf8_lossy(bytes).to_string();
+        }
+    };
+    let (is_ok, value, error_msg) = match output {
+        Ok(NetOutput::Accepted(stream, peer_addr)) => {
+            let connection_id = get_next_connection_id();
+            if let Ok(mut connections) = get_tcp_connections().lock() {
+                connections.insert(connection_id.clone(), Arc::new(Mutex::new(stream)));
+            }
+            let mut connection_fields = HashMap::new();
+            connection_fields.insert("peer_addr".to_string(), RuntimeValue::String(peer_addr.to_string()));
+            connection_fields.insert("connection_id".to_string(), RuntimeValue::String(connection_id));
+            let connection = RuntimeValue::Struct {
+                name: "TcpConnection".to_string(),
+                fields: connection_fields,
+            };
+            (true, connection, String::new())
+        }
+        Ok(NetOutput::Read(bytes)) => {
+            remember_read(&bytes);
+            (true, RuntimeValue::Int64(bytes.len() as i64), String::new())
+        }
+        Ok(NetOutput::Written(n)) => (true, RuntimeValue::Int64(n as i64), String::new()),
+        Ok(NetOutput::Received(bytes, from_addr)) => {
+            remember_read(&bytes);
+            let received = RuntimeValue::Tuple(vec![
+                RuntimeValue::Int32(bytes.len() as i32),
+                RuntimeValue::String(from_addr.to_string()),
+            ]);
+            (true, received, String::new())
+        }
+        Ok(NetOutput::Sent(n)) => (true, RuntimeValue::Int32(n as i32), String::new()),
+        Err(e) => (false, RuntimeValue::Null, e.to_string()),
+    };
+
+    let mut result_fields = HashMap::new();
+    result_fields.insert("is_ok".to_string(), RuntimeValue::Bool(is_ok));
+    result_fields.insert("value".to_string(), value);
+    result_fields.insert("error_msg".to_string(), RuntimeValue::String(error_msg));
+    RuntimeValue::Struct {
+        name: "Result".to_string(),
+        fields: result_fields,
+    }
+}
+
+/// How long a socket read waits before giving up
+const IO_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Read timeout for a single socket read: the full timeout, or a short slice
+/// when the current context can be cancelled
+fn io_wait_slice() -> Duration {
+    match context::current() {
+        Some(ctx) if ctx.can_finish() => context::CANCEL_POLL_INTERVAL,
+        _ => IO_TIMEOUT,
+    }
+}
+
 /// UdpConnection.recv_from(buffer) - receive data from UDP socket
 pub fn builtin_udpconnection_recv_from(args: &[RuntimeValue]) -> Result<RuntimeValue> {
     if args.len() != 2 {
@@ -2671,9 +2964,17 @@ pub fn builtin_udpconnection_recv_from(args: &[RuntimeValue]) -> Result<RuntimeV
     };
 
     match socket {
+        Some(socket) if is_in_goroutine_context() => {
+            // Wait on the netpoller instead of blocking a worker in recv_from
+            Ok(net_output_to_result(wait_net_op(NetOp::RecvFrom {
+                socket,
+                buffer_size: 1024,
+            })))
+        }
         Some(socket) => {
-            // Set a timeout for recv_from
-            if let Err(e) = socket.set_read_timeout(Some(Duration::from_millis(1000))) {
+            // Set a timeout for recv_from; with a cancellable context, wait in
+            // short slices so cancellation is noticed
+            if let Err(e) = socket.set_read_timeout(Some(io_wait_slice())) {
                 let mut result_fields = std::collections::HashMap::new();
                 result_fields.insert("is_ok".to_string(), RuntimeValue::Bool(false));
                 result_fields.insert("value".to_string(), RuntimeValue::Null);
@@ -2686,7 +2987,7 @@ pub fn builtin_udpconnection_recv_from(args: &[RuntimeValue]) -> Result<RuntimeV
             }
 
             let mut buffer = [0u8; 1024];
-            match socket.recv_from(&mut buffer) {
+            match context::retry_io(Some(IO_TIMEOUT), || socket.recv_from(&mut buffer)) {
                 Ok((bytes_read, from_addr)) => {
                     // Store the read data globally for string conversion
                     let data = String::from_utf8_lossy(&buffer[..bytes_read]).to_string();
@@ -2820,6 +3121,14 @@ pub fn builtin_udpconnection_send_to(args: &[RuntimeValue]) -> Result<RuntimeVal
     };
 
     match socket {
+        Some(socket) if is_in_goroutine_context() => {
+            // Wait on the netpoller instead of blocking a worker in send_to
+            Ok(net_output_to_result(wait_net_op(NetOp::SendTo {
+                socket,
+                data: data_bytes,
+                addr: target_addr.clone(),
+            })))
+        }
         Some(socket) => match socket.send_to(&data_bytes, target_addr) {
             Ok(bytes_sent) => {
                 let mut result_fields = std::collections::HashMap::new();
@@ -2920,8 +3229,10 @@ pub fn builtin_tcpserver_accept(args: &[RuntimeValue]) -> Result<RuntimeValue> {
             //     );
             // }
 
-            // Set blocking mode based on context
-            if let Err(e) = listener.set_nonblocking(is_goroutine) {
+            // Set blocking mode based on context; a cancellable context polls
+            // so that accept can give up when it is cancelled
+            let cancellable = context::current().is_some_and(|ctx| ctx.can_finish());
+            if let Err(e) = listener.set_nonblocking(is_goroutine || cancellable) {
                 let mut result_fields = std::collections::HashMap::new();
                 result_fields.insert("is_ok".to_string(), RuntimeValue::Bool(false));
                 result_fields.insert("value".to_string(), RuntimeValue::Null);
@@ -2933,49 +3244,14 @@ pub fn builtin_tcpserver_accept(args: &[RuntimeValue]) -> Result<RuntimeValue> {
                 });
             }
 
-            // In goroutine context, use syscall thread pool for blocking operation
+            // In goroutine context, wait on the netpoller instead of blocking a worker in accept
             if is_goroutine {
-                // println!("🌐 TCP_ACCEPT: In goroutine context, submitting to syscall pool and waiting");
-
-                // Get current goroutine ID
-                if let Some(goroutine_id) = crate::runtime::goroutine::get_current_goroutine_id() {
-                    // Clone the listener and wrap in Mutex for syscall pool
-                    let listener_clone = listener.try_clone().unwrap();
-                    let listener_mutex = Arc::new(Mutex::new(listener_clone));
-                    
-                    // Submit the blocking operation and wait for result synchronously
-                    match crate::runtime::syscall_thread::submit_blocking_op_sync(
-                        goroutine_id,
-                        crate::runtime::syscall_thread::BlockingOp::TcpAccept {
-                            listener: listener_mutex,
-                        },
-                    ) {
-                        Ok(result) => {
-                            // println!("🌐 TCP_ACCEPT: Received result from syscall pool");
-                            return Ok(result);
-                        }
-                        Err(e) => {
-                            println!("❌ TCP_ACCEPT: Syscall failed: {:?}", e);
-                            let mut result_fields = std::collections::HashMap::new();
-                            result_fields.insert("is_ok".to_string(), RuntimeValue::Bool(false));
-                            result_fields.insert("value".to_string(), RuntimeValue::Null);
-                            result_fields.insert(
-                                "error_msg".to_string(),
-                                RuntimeValue::String(format!("{:?}", e)),
-                            );
-
-                            return Ok(RuntimeValue::Struct {
-                                name: "Result".to_string(),
-                                fields: result_fields,
-                            });
-                        }
-                    }
-                }
+                return Ok(net_output_to_result(wait_net_op(NetOp::Accept(listener))));
             }
             
             // Fallback: blocking accept (not in goroutine context)
             // println!("⚠️  TCP_ACCEPT: No goroutine context, using blocking accept");
-            match listener.accept() {
+            match context::retry_io(None, || listener.accept()) {
                     Ok((stream, peer_addr)) => {
                         let connection_id = get_next_connection_id();
 
@@ -3098,61 +3374,12 @@ pub fn builtin_tcpconnection_read(args: &[RuntimeValue]) -> Result<RuntimeValue>
 
     match connection {
         Some(connection) => {
-            // Check if we're in a goroutine context
+            // In goroutine context, wait on the netpoller instead of blocking a worker in read
             if is_in_goroutine_context() {
-                if let Some(goroutine_id) = crate::runtime::goroutine::get_current_goroutine_id() {
-                    // println!("🌐 TCP_READ: Called from goroutine context, submitting to syscall pool and waiting");
-                    
-                    // Submit the blocking operation and wait for result synchronously
-                    match crate::runtime::syscall_thread::submit_blocking_op_sync(
-                        goroutine_id,
-                        crate::runtime::syscall_thread::BlockingOp::TcpRead {
-                            stream: connection,
-                            buffer_size: 1024,
-                        },
-                    ) {
-                        Ok(result) => {
-                            // println!("🌐 TCP_READ: Received result from syscall pool");
-                            
-                            // Extract the data from the result and copy it to the user's buffer
-                            if let RuntimeValue::Struct { fields, .. } = &result {
-                                if let Some(RuntimeValue::Array(data)) = fields.get("data") {
-                                    // Copy data to the user's buffer (args[1])
-                                    // Note: In Bulu, arrays are passed by value, so we can't modify the original
-                                    // Instead, we store the data globally for later retrieval
-                                    static READ_BUFFER_DATA: OnceLock<Arc<Mutex<Vec<u8>>>> = OnceLock::new();
-                                    let buffer_data = READ_BUFFER_DATA.get_or_init(|| Arc::new(Mutex::new(Vec::new())));
-                                    
-                                    if let Ok(mut buf) = buffer_data.lock() {
-                                        buf.clear();
-                                        for val in data {
-                                            if let RuntimeValue::UInt8(b) = val {
-                                                buf.push(*b);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            
-                            return Ok(result);
-                        }
-                        Err(e) => {
-                            println!("❌ TCP_READ: Syscall failed: {:?}", e);
-                            let mut result_fields = std::collections::HashMap::new();
-                            result_fields.insert("is_ok".to_string(), RuntimeValue::Bool(false));
-                            result_fields.insert("value".to_string(), RuntimeValue::Null);
-                            result_fields.insert(
-                                "error_msg".to_string(),
-                                RuntimeValue::String(format!("{:?}", e)),
-                            );
-
-                            return Ok(RuntimeValue::Struct {
-                                name: "Result".to_string(),
-                                fields: result_fields,
-                            });
-                        }
-                    }
-                }
+                return Ok(net_output_to_result(wait_net_op(NetOp::Read {
+                    stream: connection,
+                    buffer_size: 1024,
+                })));
             }
             
             // Fallback: blocking read (not in goroutine context)
@@ -3161,7 +3388,7 @@ pub fn builtin_tcpconnection_read(args: &[RuntimeValue]) -> Result<RuntimeValue>
             // Try to read from the connection
             if let Ok(mut stream) = connection.lock() {
                 // Set blocking mode with timeout
-                if let Err(e) = stream.set_read_timeout(Some(Duration::from_millis(1000))) {
+                if let Err(e) = stream.set_read_timeout(Some(io_wait_slice())) {
                     let mut result_fields = std::collections::HashMap::new();
                     result_fields.insert("is_ok".to_string(), RuntimeValue::Bool(false));
                     result_fields.insert("value".to_string(), RuntimeValue::Int64(0));
@@ -3175,7 +3402,7 @@ pub fn builtin_tcpconnection_read(args: &[RuntimeValue]) -> Result<RuntimeValue>
                 }
 
                 let mut buffer = [0u8; 1024];
-                match stream.read(&mut buffer) {
+                match context::retry_io(Some(IO_TIMEOUT), || stream.read(&mut buffer)) {
                     Ok(bytes_read) => {
                         // Store the read data globally for string conversion
                         let data = String::from_utf8_lossy(&buffer[..bytes_read]).to_string();
@@ -3319,40 +3546,13 @@ pub fn builtin_tcpconnection_write(args: &[RuntimeValue]) -> Result<RuntimeValue
 
     match connection {
         Some(connection) => {
-            // Check if we're in a goroutine context
+            // In goroutine context, wait on the netpoller instead of blocking a worker in write
             if is_in_goroutine_context() {
-                if let Some(goroutine_id) = crate::runtime::goroutine::get_current_goroutine_id() {
-                    // println!("🌐 TCP_WRITE: Called from goroutine context, submitting to syscall pool and waiting");
-                    
-                    // Submit the blocking operation and wait for result synchronously
-                    match crate::runtime::syscall_thread::submit_blocking_op_sync(
-                        goroutine_id,
-                        crate::runtime::syscall_thread::BlockingOp::TcpWrite {
-                            stream: connection,
-                            data: data_bytes,
-                        },
-                    ) {
-                        Ok(result) => {
-                            // println!("🌐 TCP_WRITE: Received result from syscall pool");
-                            return Ok(result);
-                        }
-                        Err(e) => {
-                            println!("❌ TCP_WRITE: Syscall failed: {:?}", e);
-                            let mut result_fields = std::collections::HashMap::new();
-                            result_fields.insert("is_ok".to_string(), RuntimeValue::Bool(false));
-                            result_fields.insert("value".to_string(), RuntimeValue::Null);
-                            result_fields.insert(
-                                "error_msg".to_string(),
-                                RuntimeValue::String(format!("{:?}", e)),
-                            );
-
-                            return Ok(RuntimeValue::Struct {
-                                name: "Result".to_string(),
-                                fields: result_fields,
-                            });
-                        }
-                    }
-                }
+                return Ok(net_output_to_result(wait_net_op(NetOp::Write {
+                    stream: connection,
+                    data: data_bytes,
+                    written: 0,
+                })));
             }
             
             // Fallback: blocking write (not in goroutine context)
@@ -0,0 +1,90 @@
+//! Vectorized primitives for strings and byte slices
+//!
+//! Substring search goes through `memchr::memmem`, which scans with SSE2/AVX2
+//! (or NEON) instead of comparing one byte at a time, and UTF-8 validation
+//! through `simdutf8`, which checks 32 or 64 bytes per step. Both fall back to
+//! portable code on other targets, so results never depend on the CPU.
+//!
+//! Positions returned to Bulu programs count characters, like string indexing
+//! and slicing; the byte offsets found by the search are converted after the
+//! fact, which costs one pass over the text before the match.
+
+use memchr::memmem;
+
+/// Character index of the first occurrence of `needle` in `haystack`
+pub fn index_of(haystack: &str, needle: &str) -> Option<usize> {
+    let offset = memmem::find(haystack.as_bytes(), needle.as_bytes())?;
+    Some(char_index(haystack, offset))
+}
+
+/// Character index of the last occurrence of `needle` in `haystack`
+pub fn last_index_of(haystack: &str, needle: &str) -> Option<usize> {
+    let offset = memmem::rfind(haystack.as_bytes(), needle.as_bytes())?;
+    Some(char_index(haystack, offset))
+}
+
+/// Whether `needle` occurs in `haystack`
+pub fn contains(haystack: &str, needle: &str) -> bool {
+    memmem::find(haystack.as_bytes(), needle.as_bytes()).is_some()
+}
+
+/// Number of non-overlapping occurrences of `needle` in `haystack`; 0 for an
+/// empty needle
+pub fn count(haystack: &str, needle: &str) -> usize {
+    if needle.is_empty() {
+        return 0;
+    }
+    memmem::find_iter(haystack.as_bytes(), needle.as_bytes()).count()
+}
+
+fn char_index(text: &str, offset: usize) -> usize {
+    let before = &text[..offset];
+    if before.is_ascii() {
+        offset
+    } else {
+        before.chars().count()
+    }
+}
+
+/// `bytes` as a string, or where the first invalid UTF-8 sequence starts
+pub fn validate_utf8(bytes: &[u8]) -> Result<&str, String> {
+    simdutf8::compat::from_utf8(bytes)
+        .map_err(|error| format!("invalid UTF-8 at byte {}", error.valid_up_to()))
+}
+
+/// `bytes` as a string, with invalid sequences replaced by U+FFFD; valid
+/// input, the common case, is taken over without copying
+pub fn string_from_utf8_lossy(bytes: Vec<u8>) -> String {
+    if simdutf8::basic::from_utf8(&bytes).is_ok() {
+        // SAFETY: validated just above
+        unsafe { String::from_utf8_unchecked(bytes) }
+    } else {
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_counts_characters() {
+        assert_eq!(index_of("hello world", "o"), Some(4));
+        assert_eq!(last_index_of("hello world", "o"), Some(7));
+        assert_eq!(index_of("héllo wörld", "wö"), Some(6));
+        assert_eq!(last_index_of("ßßß", "ß"), Some(2));
+        assert_eq!(index_of("abc", ""), Some(0));
+        assert_eq!(index_of("abc", "abcd"), None);
+        assert!(contains(&"ab".repeat(1000), "ba"));
+        assert_eq!(count("aaaa", "aa"), 2);
+        assert_eq!(count("abc", ""), 0);
+    }
+
+    #[test]
+    fn test_utf8() {
+        assert_eq!(validate_utf8("añb".as_bytes()), Ok("añb"));
+        assert_eq!(validate_utf8(b"ab\xffcd").unwrap_err(), "invalid UTF-8 at byte 2");
+        assert_eq!(string_from_utf8_lossy(b"ok".to_vec()), "ok");
+        assert_eq!(string_from_utf8_lossy(b"a\xffb".to_vec()), "a\u{fffd}b");
+    }
+}
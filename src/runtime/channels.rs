@@ -8,10 +8,11 @@
 //! - Select statement support
 
 use crate::error::{BuluError, Result};
+use crate::runtime::context::{self, CANCEL_POLL_INTERVAL};
 use crate::types::composite::ChannelDirection;
-use crate::types::primitive::{RuntimeValue, TypeId};
+use crate::types::primitive::{PrimitiveType, RuntimeValue, TypeId};
 use std::collections::VecDeque;
-use std::sync::{Arc, Condvar, Mutex};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
 use std::time::{Duration, Instant};
 
 /// Channel runtime representation
@@ -31,6 +32,9 @@ struct ChannelInner {
     closed: bool,
     waiting_senders: usize,
     waiting_receivers: usize,
+    /// Values taken by receivers so far, so an unbuffered sender knows when its
+    /// value has been handed over
+    received: u64,
 }
 
 /// Channel operation result
@@ -59,6 +63,7 @@ impl Channel {
                 closed: false,
                 waiting_senders: 0,
                 waiting_receivers: 0,
+                received: 0,
             })),
             send_notify: Arc::new(Condvar::new()),
             recv_notify: Arc::new(Condvar::new()),
@@ -76,6 +81,7 @@ impl Channel {
                 closed: false,
                 waiting_senders: 0,
                 waiting_receivers: 0,
+                received: 0,
             })),
             send_notify: Arc::new(Condvar::new()),
             recv_notify: Arc::new(Condvar::new()),
@@ -147,56 +153,17 @@ impl Channel {
     }
 
     /// Send a value to the channel (blocking)
+    ///
+    /// On a buffered channel this waits only while the buffer is full. On an
+    /// unbuffered channel it waits until a receiver has taken the value; if the
+    /// channel is closed first, the value is taken back and `Closed` returned.
     pub fn send(&self, value: RuntimeValue) -> Result<SendResult> {
-        if self.direction == ChannelDirection::ReceiveOnly {
-            return Err(BuluError::RuntimeError {
-            file: None,
-                message: "Cannot send on receive-only channel".to_string(),
-            });
-        }
-
-        let mut inner = self.inner.lock().unwrap();
-
-        // Check if channel is closed
-        if inner.closed {
-            return Ok(SendResult::Closed);
-        }
-
-        // For unbuffered channels or when buffer is full, wait for receiver
-        if inner.capacity == 0 || inner.buffer.len() >= inner.capacity {
-            inner.waiting_senders += 1;
-
-            // Wait for space or receiver
-            while !inner.closed && (inner.capacity == 0 || inner.buffer.len() >= inner.capacity) {
-                inner = self.send_notify.wait(inner).unwrap();
-            }
-
-            inner.waiting_senders -= 1;
-
-            // Check if channel was closed while waiting
-            if inner.closed {
-                return Ok(SendResult::Closed);
-            }
-        }
-
-        // Add value to buffer
-        inner.buffer.push_back(value);
-
-        // Notify waiting receivers
-        drop(inner);
-        self.recv_notify.notify_one();
-
-        Ok(SendResult::Ok)
+        self.send_until(value, None)
     }
 
     /// Try to send a value to the channel (non-blocking)
     pub fn try_send(&self, value: RuntimeValue) -> Result<SendResult> {
-        if self.direction == ChannelDirection::ReceiveOnly {
-            return Err(BuluError::RuntimeError {
-            file: None,
-                message: "Cannot send on receive-only channel".to_string(),
-            });
-        }
+        self.check_can_send()?;
 
         let mut inner = self.inner.lock().unwrap();
 
@@ -206,39 +173,31 @@ impl Channel {
         }
 
         // Check if we can send immediately
-        if inner.capacity == 0 {
-            // Unbuffered channel - need a waiting receiver
-            if inner.waiting_receivers > 0 {
-                inner.buffer.push_back(value);
-                drop(inner);
-                self.recv_notify.notify_one();
-                Ok(SendResult::Ok)
-            } else {
-                Ok(SendResult::WouldBlock)
-            }
+        let ready = if inner.capacity == 0 {
+            // Unbuffered channel - need a waiting receiver and no value in flight
+            inner.waiting_receivers > 0 && inner.buffer.is_empty()
         } else {
             // Buffered channel - check if there's space
-            if inner.buffer.len() < inner.capacity {
-                inner.buffer.push_back(value);
-                drop(inner);
-                self.recv_notify.notify_one();
-                Ok(SendResult::Ok)
-            } else {
-                Ok(SendResult::WouldBlock)
-            }
+            inner.buffer.len() < inner.capacity
+        };
+        if !ready {
+            return Ok(SendResult::WouldBlock);
         }
+
+        inner.buffer.push_back(value);
+        drop(inner);
+        self.recv_notify.notify_one();
+        Ok(SendResult::Ok)
     }
 
     /// Send a value with timeout
     pub fn send_timeout(&self, value: RuntimeValue, timeout: Duration) -> Result<SendResult> {
-        if self.direction == ChannelDirection::ReceiveOnly {
-            return Err(BuluError::RuntimeError {
-            file: None,
-                message: "Cannot send on receive-only channel".to_string(),
-            });
-        }
+        self.send_until(value, Some(Instant::now() + timeout))
+    }
+
+    fn send_until(&self, value: RuntimeValue, deadline: Option<Instant>) -> Result<SendResult> {
+        self.check_can_send()?;
 
-        let start = Instant::now();
         let mut inner = self.inner.lock().unwrap();
 
         // Check if channel is closed
@@ -246,98 +205,81 @@ impl Channel {
             return Ok(SendResult::Closed);
         }
 
-        // For unbuffered channels or when buffer is full, wait for receiver
-        if inner.capacity == 0 || inner.buffer.len() >= inner.capacity {
-            inner.waiting_senders += 1;
-
-            // Wait for space or receiver with timeout
-            while !inner.closed && (inner.capacity == 0 || inner.buffer.len() >= inner.capacity) {
-                let remaining = timeout.saturating_sub(start.elapsed());
-                if remaining.is_zero() {
-                    inner.waiting_senders -= 1;
-                    return Ok(SendResult::WouldBlock);
-                }
-
-                let (new_inner, timeout_result) = self.send_notify.wait_timeout(inner, remaining).unwrap();
-                inner = new_inner;
-
-                if timeout_result.timed_out() {
+        // Wait for space in the buffer, or for the value in flight on an
+        // unbuffered channel to be taken
+        inner.waiting_senders += 1;
+        while !inner.closed && inner.buffer.len() >= inner.capacity.max(1) {
+            match self.wait(&self.send_notify, inner, deadline) {
+                Some(guard) => inner = guard,
+                None => {
+                    let mut inner = self.inner.lock().unwrap();
                     inner.waiting_senders -= 1;
+                    drop(inner);
+                    context::check_current()?;
                     return Ok(SendResult::WouldBlock);
                 }
             }
+        }
 
+        // Check if channel was closed while waiting
+        if inner.closed {
             inner.waiting_senders -= 1;
-
-            // Check if channel was closed while waiting
-            if inner.closed {
-                return Ok(SendResult::Closed);
-            }
+            return Ok(SendResult::Closed);
         }
 
         // Add value to buffer
         inner.buffer.push_back(value);
-
-        // Notify waiting receivers
-        drop(inner);
         self.recv_notify.notify_one();
 
-        Ok(SendResult::Ok)
-    }
-
-    /// Receive a value from the channel (blocking)
-    pub fn receive(&self) -> Result<ChannelResult> {
-        if self.direction == ChannelDirection::SendOnly {
-            return Err(BuluError::RuntimeError {
-            file: None,
-                message: "Cannot receive from send-only channel".to_string(),
-            });
+        if inner.capacity > 0 {
+            inner.waiting_senders -= 1;
+            return Ok(SendResult::Ok);
         }
 
-        let mut inner = self.inner.lock().unwrap();
-
-        // Wait for data or channel close
-        inner.waiting_receivers += 1;
-
-        while inner.buffer.is_empty() && !inner.closed {
-            inner = self.recv_notify.wait(inner).unwrap();
+        // Unbuffered: hand the value over to a receiver
+        let ticket = inner.received;
+        while inner.received == ticket && !inner.closed {
+            match self.wait(&self.send_notify, inner, deadline) {
+                Some(guard) => inner = guard,
+                None => {
+                    inner = self.inner.lock().unwrap();
+                    break;
+                }
+            }
         }
+        inner.waiting_senders -= 1;
 
-        inner.waiting_receivers -= 1;
-
-        // Check if we have data
-        if let Some(value) = inner.buffer.pop_front() {
-            // Notify waiting senders if there's space
-            if inner.capacity == 0 || inner.buffer.len() < inner.capacity {
-                drop(inner);
-                self.send_notify.notify_one();
+        if inner.received == ticket {
+            // Nobody took the value: the channel was closed, the wait timed out
+            // or the current context was cancelled
+            inner.buffer.pop_back();
+            let closed = inner.closed;
+            drop(inner);
+            self.send_notify.notify_all();
+            if closed {
+                return Ok(SendResult::Closed);
             }
-            Ok(ChannelResult::Ok(value))
-        } else if inner.closed {
-            Ok(ChannelResult::Closed)
-        } else {
-            // This shouldn't happen, but handle it gracefully
-            Ok(ChannelResult::Closed)
+            context::check_current()?;
+            return Ok(SendResult::WouldBlock);
         }
+        Ok(SendResult::Ok)
+    }
+
+    /// Receive a value from the channel (blocking)
+    ///
+    /// Values still buffered when the channel is closed are received first;
+    /// `Closed` is only returned once the buffer is drained.
+    pub fn receive(&self) -> Result<ChannelResult> {
+        self.receive_until(None)
     }
 
     /// Try to receive a value from the channel (non-blocking)
     pub fn try_receive(&self) -> Result<ChannelResult> {
-        if self.direction == ChannelDirection::SendOnly {
-            return Err(BuluError::RuntimeError {
-            file: None,
-                message: "Cannot receive from send-only channel".to_string(),
-            });
-        }
+        self.check_can_receive()?;
 
         let mut inner = self.inner.lock().unwrap();
 
-        if let Some(value) = inner.buffer.pop_front() {
-            // Notify waiting senders if there's space
-            if inner.capacity == 0 || inner.buffer.len() < inner.capacity {
-                drop(inner);
-                self.send_notify.notify_one();
-            }
+        if let Some(value) = self.take(&mut inner) {
             Ok(ChannelResult::Ok(value))
         } else if inner.closed {
             Ok(ChannelResult::Closed)
@@ -348,52 +290,133 @@ impl Channel {
 
     /// Receive a value with timeout
     pub fn receive_timeout(&self, timeout: Duration) -> Result<ChannelResult> {
-        if self.direction == ChannelDirection::SendOnly {
-            return Err(BuluError::RuntimeError {
-            file: None,
-                message: "Cannot receive from send-only channel".to_string(),
-            });
-        }
+        self.receive_until(Some(Instant::now() + timeout))
+    }
+
+    fn receive_until(&self, deadline: Option<Instant>) -> Result<ChannelResult> {
+        self.check_can_receive()?;
 
-        let start = Instant::now();
         let mut inner = self.inner.lock().unwrap();
 
-        // Wait for data or channel close with timeout
+        // Wait for data or channel close
         inner.waiting_receivers += 1;
 
         while inner.buffer.is_empty() && !inner.closed {
-            let remaining = timeout.saturating_sub(start.elapsed());
-            if remaining.is_zero() {
-                inner.waiting_receivers -= 1;
-                return Ok(ChannelResult::WouldBlock);
-            }
-
-            let (new_inner, timeout_result) = self.recv_notify.wait_timeout(inner, remaining).unwrap();
-            inner = new_inner;
-
-            if timeout_result.timed_out() {
-                inner.waiting_receivers -= 1;
-                return Ok(ChannelResult::WouldBlock);
+            match self.wait(&self.recv_notify, inner, deadline) {
+                Some(guard) => inner = guard,
+                None => {
+                    inner = self.inner.lock().unwrap();
+                    break;
+                }
             }
         }
 
         inner.waiting_receivers -= 1;
 
-        // Check if we have data
-        if let Some(value) = inner.buffer.pop_front() {
-            // Notify waiting senders if there's space
-            if inner.capacity == 0 || inner.buffer.len() < inner.capacity {
-                drop(inner);
-                self.send_notify.notify_one();
-            }
+        // A value that arrived as the wait timed out is still received
+        if let Some(value) = self.take(&mut inner) {
             Ok(ChannelResult::Ok(value))
         } else if inner.closed {
             Ok(ChannelResult::Closed)
         } else {
+            drop(inner);
+            context::check_current()?;
             Ok(ChannelResult::WouldBlock)
         }
     }
 
+    /// Pop the next value and wake the senders waiting for it to be taken or
+    /// for space in the buffer
+    fn take(&self, inner: &mut ChannelInner) -> Option<RuntimeValue> {
+        let value = inner.buffer.pop_front()?;
+        inner.received += 1;
+        if inner.capacity == 0 {
+            self.send_notify.notify_all();
+        } else {
+            self.send_notify.notify_one();
+        }
+        Some(value)
+    }
+
+    /// Wait on `condvar` until notified, or give up at `deadline`. Returns `None`
+    /// with the lock released when the deadline has passed or the current
+    /// context is done.
+    fn wait<'a>(
+        &self,
+        condvar: &Condvar,
+        inner: MutexGuard<'a, ChannelInner>,
+        deadline: Option<Instant>,
+    ) -> Option<MutexGuard<'a, ChannelInner>> {
+        let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        if remaining.is_some_and(|remaining| remaining.is_zero()) {
+            return None;
+        }
+
+        let Some(ctx) = context::current().filter(|ctx| ctx.can_finish()) else {
+            let Some(remaining) = remaining else {
+                return Some(condvar.wait(inner).unwrap());
+            };
+            let (inner, timeout_result) = condvar.wait_timeout(inner, remaining).unwrap();
+            return if timeout_result.timed_out() { None } else { Some(inner) };
+        };
+
+        // Wake up regularly to notice cancellation; the caller re-checks its
+        // condition after every wake-up anyway
+        if ctx.is_done() {
+            return None;
+        }
+        let slice = remaining.map_or(CANCEL_POLL_INTERVAL, |remaining| remaining.min(CANCEL_POLL_INTERVAL));
+        let (inner, _) = condvar.wait_timeout(inner, slice).unwrap();
+        let expired = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        if expired || ctx.is_done() {
+            None
+        } else {
+            Some(inner)
+        }
+    }
+
+    fn check_can_send(&self) -> Result<()> {
+        if self.direction == ChannelDirection::ReceiveOnly {
+            return Err(BuluError::RuntimeError {
+            file: None,
+                message: "Cannot send on receive-only channel".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn check_can_receive(&self) -> Result<()> {
+        if self.direction == ChannelDirection::SendOnly {
+            return Err(BuluError::RuntimeError {
+            file: None,
+                message: "Cannot receive from send-only channel".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The value a receive yields once the channel is closed and drained: the
+    /// zero value of the element type, or null when it has none
+    pub fn zero_value(&self) -> RuntimeValue {
+        let primitive = match self.element_type {
+            TypeId::Int8 => PrimitiveType::Int8,
+            TypeId::Int16 => PrimitiveType::Int16,
+            TypeId::Int32 => PrimitiveType::Int32,
+            TypeId::Int64 => PrimitiveType::Int64,
+            TypeId::UInt8 => PrimitiveType::UInt8,
+            TypeId::UInt16 => PrimitiveType::UInt16,
+            TypeId::UInt32 => PrimitiveType::UInt32,
+            TypeId::UInt64 => PrimitiveType::UInt64,
+            TypeId::Float32 => PrimitiveType::Float32,
+            TypeId::Float64 => PrimitiveType::Float64,
+            TypeId::Bool => PrimitiveType::Bool,
+            TypeId::Char => PrimitiveType::Char,
+            TypeId::String => PrimitiveType::String,
+            _ => return RuntimeValue::Null,
+        };
+        primitive.default_value()
+    }
+
     /// Close the channel
     pub fn close(&self) -> Result<()> {
         let mut inner = self.inner.lock().unwrap();
@@ -720,4 +743,87 @@ mod tests {
         assert!(removed.is_some());
         assert!(registry.get(id1).is_none());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_buffered_send_blocks_when_full() {
+        let channel = Channel::new_buffered(TypeId::Int32, 2);
+        assert_eq!(channel.send(RuntimeValue::Int32(1)).unwrap(), SendResult::Ok);
+        assert_eq!(channel.send(RuntimeValue::Int32(2)).unwrap(), SendResult::Ok);
+        assert!(channel.is_full());
+        assert_eq!(
+            channel.send_timeout(RuntimeValue::Int32(3), Duration::from_millis(20)).unwrap(),
+            SendResult::WouldBlock
+        );
+
+        let sender = channel.clone();
+        let handle = std::thread::spawn(move || sender.send(RuntimeValue::Int32(3)).unwrap());
+        assert_eq!(channel.receive().unwrap(), ChannelResult::Ok(RuntimeValue::Int32(1)));
+        assert_eq!(handle.join().unwrap(), SendResult::Ok);
+        assert_eq!(channel.len(), 2);
+    }
+
+    #[test]
+    fn test_unbuffered_send_waits_for_receiver() {
+        let channel = Channel::new_unbuffered(TypeId::Int32);
+        assert_eq!(
+            channel.send_timeout(RuntimeValue::Int32(1), Duration::from_millis(20)).unwrap(),
+            SendResult::WouldBlock
+        );
+        assert!(channel.is_empty());
+
+        let sender = channel.clone();
+        let handle = std::thread::spawn(move || {
+            (0..3)
+                .map(|i| sender.send(RuntimeValue::Int32(i)).unwrap())
+                .collect::<Vec<_>>()
+        });
+        for i in 0..3 {
+            assert_eq!(channel.receive().unwrap(), ChannelResult::Ok(RuntimeValue::Int32(i)));
+        }
+        assert_eq!(handle.join().unwrap(), vec![SendResult::Ok; 3]);
+
+        // A value nobody took is not left behind when the channel closes
+        let sender = channel.clone();
+        let handle = std::thread::spawn(move || sender.send(RuntimeValue::Int32(9)).unwrap());
+        while channel.is_empty() {
+            std::thread::yield_now();
+        }
+        channel.close().unwrap();
+        assert_eq!(handle.join().unwrap(), SendResult::Closed);
+        assert_eq!(channel.receive().unwrap(), ChannelResult::Closed);
+    }
+
+    #[test]
+    fn test_close_drains_buffer_then_zero_value() {
+        let channel = Channel::new_buffered(TypeId::Int32, 3);
+        channel.send(RuntimeValue::Int32(1)).unwrap();
+        channel.send(RuntimeValue::Int32(2)).unwrap();
+        channel.close().unwrap();
+
+        assert_eq!(channel.receive().unwrap(), ChannelResult::Ok(RuntimeValue::Int32(1)));
+        assert_eq!(
+            channel.receive_timeout(Duration::from_millis(20)).unwrap(),
+            ChannelResult::Ok(RuntimeValue::Int32(2))
+        );
+        assert_eq!(channel.receive().unwrap(), ChannelResult::Closed);
+        assert_eq!(channel.zero_value(), RuntimeValue::Int32(0));
+        assert_eq!(Channel::new_unbuffered(TypeId::String).zero_value(), RuntimeValue::String(String::new()));
+        assert_eq!(Channel::new_unbuffered(TypeId::Any).zero_value(), RuntimeValue::Null);
+    }
+
+    #[test]
+    fn test_blocking_operations_stop_when_context_is_cancelled() {
+        use crate::runtime::context::Context;
+
+        let ctx = Context::background().with_timeout(Duration::from_millis(30));
+        let _scope = context::enter(Some(ctx));
+
+        let channel = Channel::new_unbuffered(TypeId::Int32);
+        let error = channel.receive().unwrap_err();
+        assert!(error.to_string().contains("context deadline exceeded"), "{}", error);
+        let error = channel.send(RuntimeValue::Int32(1)).unwrap_err();
+        assert!(error.to_string().contains("context deadline exceeded"), "{}", error);
+        // The value that was never taken is not left in the channel
+        assert!(channel.is_empty());
+    }
+}
@@ -0,0 +1,525 @@
+//! Runtime support for std/collections: deques, priority queues, ordered maps
+//! and insertion-ordered maps and sets
+//!
+//! Bulu values are small handles carrying a registry ID (see `std::collections`);
+//! the collections themselves live in a `CollectionRegistry` shared with
+//! goroutines. Each collection has its own lock, so the registry is only held
+//! long enough to look one up.
+
+use crate::error::{BuluError, Result};
+use crate::runtime::sets;
+use crate::types::primitive::RuntimeValue;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Unique identifier for collections
+pub type CollectionId = usize;
+
+/// The "comes before" relation of a priority queue: `before(a, b)` is true when
+/// `a` should be popped before `b`. Comparators are Bulu functions, so it can fail.
+pub type Before<'a> = dyn FnMut(&RuntimeValue, &RuntimeValue) -> Result<bool> + 'a;
+
+/// Default priority queue order: smallest first, in the order sets use
+pub fn natural_order(a: &RuntimeValue, b: &RuntimeValue) -> Result<bool> {
+    Ok(sets::compare(a, b) == Ordering::Less)
+}
+
+/// A collection created through std/collections
+#[derive(Debug, Clone)]
+pub enum Collection {
+    Deque(VecDeque<RuntimeValue>),
+    PriorityQueue(PriorityQueue),
+    OrderedMap(OrderedMap),
+    LinkedMap(LinkedMap),
+    LinkedSet(LinkedSet),
+}
+
+/// Binary heap whose root is the element that nothing else comes before
+#[derive(Debug, Clone, Default)]
+pub struct PriorityQueue {
+    heap: Vec<RuntimeValue>,
+    comparator: Option<RuntimeValue>,
+}
+
+impl PriorityQueue {
+    /// Create an empty queue; without a comparator the smallest element comes out first
+    pub fn new(comparator: Option<RuntimeValue>) -> Self {
+        Self {
+            heap: Vec::new(),
+            comparator,
+        }
+    }
+
+    /// The Bulu function ordering this queue, if any
+    pub fn comparator(&self) -> Option<&RuntimeValue> {
+        self.comparator.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+
+    /// The element `pop` would return
+    pub fn peek(&self) -> Option<&RuntimeValue> {
+        self.heap.first()
+    }
+
+    /// Add an element
+    pub fn push(&mut self, value: RuntimeValue, before: &mut Before) -> Result<()> {
+        self.heap.push(value);
+        let mut child = self.heap.len() - 1;
+        while child > 0 {
+            let parent = (child - 1) / 2;
+            if !before(&self.heap[child], &self.heap[parent])? {
+                break;
+            }
+            self.heap.swap(child, parent);
+            child = parent;
+        }
+        Ok(())
+    }
+
+    /// Remove and return the first element
+    pub fn pop(&mut self, before: &mut Before) -> Result<Option<RuntimeValue>> {
+        if self.heap.is_empty() {
+            return Ok(None);
+        }
+        let top = self.heap.swap_remove(0);
+        let mut parent = 0;
+        loop {
+            let mut first = parent;
+            for child in [2 * parent + 1, 2 * parent + 2] {
+                if child < self.heap.len() && before(&self.heap[child], &self.heap[first])? {
+                    first = child;
+                }
+            }
+            if first == parent {
+                break;
+            }
+            self.heap.swap(parent, first);
+            parent = first;
+        }
+        Ok(Some(top))
+    }
+
+    /// All elements in the order `pop` would return them, leaving the queue as it is
+    pub fn to_sorted_vec(&self, before: &mut Before) -> Result<Vec<RuntimeValue>> {
+        let mut queue = self.clone();
+        let mut sorted = Vec::with_capacity(queue.len());
+        while let Some(value) = queue.pop(before)? {
+            sorted.push(value);
+        }
+        Ok(sorted)
+    }
+}
+
+/// Map with keys kept in ascending order (the order sets use). Keys may be any
+/// value a set can hold, and integers of different widths are the same key.
+#[derive(Debug, Clone, Default)]
+pub struct OrderedMap {
+    entries: Vec<(RuntimeValue, RuntimeValue)>,
+}
+
+impl OrderedMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn search(&self, key: &RuntimeValue) -> std::result::Result<usize, usize> {
+        self.entries.binary_search_by(|(k, _)| sets::compare(k, key))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Set the value for `key`, returning the previous one
+    pub fn insert(&mut self, key: RuntimeValue, value: RuntimeValue) -> Result<Option<RuntimeValue>> {
+        sets::check_element(&key).map_err(|error| match error {
+            BuluError::RuntimeError { message, file } => BuluError::RuntimeError {
+                message: message.replacen("set element", "map key", 1),
+                file,
+            },
+            other => other,
+        })?;
+        match self.search(&key) {
+            Ok(index) => Ok(Some(std::mem::replace(&mut self.entries[index].1, value))),
+            Err(index) => {
+                self.entries.insert(index, (key, value));
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn get(&self, key: &RuntimeValue) -> Option<&RuntimeValue> {
+        self.search(key).ok().map(|index| &self.entries[index].1)
+    }
+
+    pub fn contains_key(&self, key: &RuntimeValue) -> bool {
+        self.search(key).is_ok()
+    }
+
+    /// Remove `key`, returning its value
+    pub fn remove(&mut self, key: &RuntimeValue) -> Option<RuntimeValue> {
+        self.search(key).ok().map(|index| self.entries.remove(index).1)
+    }
+
+    pub fn first_key(&self) -> Option<&RuntimeValue> {
+        self.entries.first().map(|(key, _)| key)
+    }
+
+    pub fn last_key(&self) -> Option<&RuntimeValue> {
+        self.entries.last().map(|(key, _)| key)
+    }
+
+    /// The largest key less than or equal to `key`
+    pub fn floor_key(&self, key: &RuntimeValue) -> Option<&RuntimeValue> {
+        match self.search(key) {
+            Ok(index) => Some(&self.entries[index].0),
+            Err(0) => None,
+            Err(index) => Some(&self.entries[index - 1].0),
+        }
+    }
+
+    /// The smallest key greater than or equal to `key`
+    pub fn ceiling_key(&self, key: &RuntimeValue) -> Option<&RuntimeValue> {
+        let index = self.search(key).unwrap_or_else(|index| index);
+        self.entries.get(index).map(|(key, _)| key)
+    }
+
+    /// Entries in ascending key order
+    pub fn entries(&self) -> &[(RuntimeValue, RuntimeValue)] {
+        &self.entries
+    }
+}
+
+/// Map that iterates in the order keys were first set. Keys are the values a
+/// set can hold, found through an index sorted the way sets are.
+#[derive(Debug, Clone, Default)]
+pub struct LinkedMap {
+    entries: Vec<(RuntimeValue, RuntimeValue)>,
+    /// Positions in `entries`, in ascending key order
+    index: Vec<usize>,
+}
+
+impl LinkedMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn search(&self, key: &RuntimeValue) -> std::result::Result<usize, usize> {
+        self.index.binary_search_by(|&position| sets::compare(&self.entries[position].0, key))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear();
+    }
+
+    /// Set the value for `key`, returning the previous one. A key that is
+    /// already there keeps its place.
+    pub fn insert(&mut self, key: RuntimeValue, value: RuntimeValue) -> Result<Option<RuntimeValue>> {
+        sets::check_element(&key).map_err(|error| match error {
+            BuluError::RuntimeError { message, file } => BuluError::RuntimeError {
+                message: message.replacen("set element", "map key", 1),
+                file,
+            },
+            other => other,
+        })?;
+        match self.search(&key) {
+            Ok(slot) => Ok(Some(std::mem::replace(&mut self.entries[self.index[slot]].1, value))),
+            Err(slot) => {
+                self.index.insert(slot, self.entries.len());
+                self.entries.push((key, value));
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn get(&self, key: &RuntimeValue) -> Option<&RuntimeValue> {
+        self.search(key).ok().map(|slot| &self.entries[self.index[slot]].1)
+    }
+
+    pub fn contains_key(&self, key: &RuntimeValue) -> bool {
+        self.search(key).is_ok()
+    }
+
+    /// Remove `key`, returning its value
+    pub fn remove(&mut self, key: &RuntimeValue) -> Option<RuntimeValue> {
+        let slot = self.search(key).ok()?;
+        let position = self.index.remove(slot);
+        for later in self.index.iter_mut().filter(|later| **later > position) {
+            *later -= 1;
+        }
+        Some(self.entries.remove(position).1)
+    }
+
+    /// The key set first
+    pub fn first_key(&self) -> Option<&RuntimeValue> {
+        self.entries.first().map(|(key, _)| key)
+    }
+
+    /// The key set last
+    pub fn last_key(&self) -> Option<&RuntimeValue> {
+        self.entries.last().map(|(key, _)| key)
+    }
+
+    /// Entries in the order their keys were first set
+    pub fn entries(&self) -> &[(RuntimeValue, RuntimeValue)] {
+        &self.entries
+    }
+}
+
+/// Set that iterates in the order elements were first added
+#[derive(Debug, Clone, Default)]
+pub struct LinkedSet {
+    elements: LinkedMap,
+}
+
+impl LinkedSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a set from `elements`, keeping the first of any duplicates
+    pub fn from_elements(elements: Vec<RuntimeValue>) -> Result<Self> {
+        let mut set = Self::new();
+        for element in elements {
+            set.insert(element)?;
+        }
+        Ok(set)
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.elements.clear();
+    }
+
+    /// Add `element`. Returns false if it was already there.
+    pub fn insert(&mut self, element: RuntimeValue) -> Result<bool> {
+        sets::check_element(&element)?;
+        Ok(self.elements.insert(element, RuntimeValue::Null)?.is_none())
+    }
+
+    pub fn contains(&self, element: &RuntimeValue) -> bool {
+        self.elements.contains_key(element)
+    }
+
+    /// Remove `element`. Returns false if it was not there.
+    pub fn remove(&mut self, element: &RuntimeValue) -> bool {
+        self.elements.remove(element).is_some()
+    }
+
+    /// Elements in the order they were first added
+    pub fn iter(&self) -> impl Iterator<Item = &RuntimeValue> {
+        self.elements.entries().iter().map(|(element, _)| element)
+    }
+
+    /// Elements of this set followed by those only in `other`
+    pub fn union(&self, other: &LinkedSet) -> LinkedSet {
+        let mut union = self.clone();
+        for element in other.iter() {
+            // Elements of `other` were already checked
+            let _ = union.insert(element.clone());
+        }
+        union
+    }
+
+    /// Elements of this set that are also in `other`, in this set's order
+    pub fn intersection(&self, other: &LinkedSet) -> LinkedSet {
+        self.filtered(|element| other.contains(element))
+    }
+
+    /// Elements of this set that are not in `other`, in this set's order
+    pub fn difference(&self, other: &LinkedSet) -> LinkedSet {
+        self.filtered(|element| !other.contains(element))
+    }
+
+    /// Check whether every element of this set is in `other`
+    pub fn is_subset(&self, other: &LinkedSet) -> bool {
+        self.iter().all(|element| other.contains(element))
+    }
+
+    fn filtered(&self, mut keep: impl FnMut(&RuntimeValue) -> bool) -> LinkedSet {
+        let mut filtered = LinkedSet::new();
+        for element in self.iter().filter(|element| keep(element)) {
+            let _ = filtered.insert(element.clone());
+        }
+        filtered
+    }
+}
+
+/// Registry for the collections created through std/collections
+#[derive(Debug)]
+pub struct CollectionRegistry {
+    collections: HashMap<CollectionId, Arc<Mutex<Collection>>>,
+    next_id: CollectionId,
+}
+
+impl CollectionRegistry {
+    /// Create a new collection registry
+    pub fn new() -> Self {
+        Self {
+            collections: HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Add a collection and return its ID
+    pub fn create(&mut self, collection: Collection) -> CollectionId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.collections.insert(id, Arc::new(Mutex::new(collection)));
+        id
+    }
+
+    /// Get a collection by ID
+    pub fn get(&self, id: CollectionId) -> Option<Arc<Mutex<Collection>>> {
+        self.collections.get(&id).cloned()
+    }
+
+    /// Drop a collection, returning false if the ID was not registered. A call
+    /// already holding the collection keeps it until the call returns.
+    pub fn free(&mut self, id: CollectionId) -> bool {
+        self.collections.remove(&id).is_some()
+    }
+
+    /// Number of live collections
+    pub fn len(&self) -> usize {
+        self.collections.len()
+    }
+
+    /// Check whether no collections are live
+    pub fn is_empty(&self) -> bool {
+        self.collections.is_empty()
+    }
+}
+
+impl Default for CollectionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ints(values: &[i32]) -> Vec<RuntimeValue> {
+        values.iter().map(|v| RuntimeValue::Int32(*v)).collect()
+    }
+
+    #[test]
+    fn test_priority_queue_orders() {
+        let mut queue = PriorityQueue::new(None);
+        for value in ints(&[5, 1, 4, 1, 3]) {
+            queue.push(value, &mut natural_order).unwrap();
+        }
+        assert_eq!(queue.peek(), Some(&RuntimeValue::Int32(1)));
+        assert_eq!(queue.to_sorted_vec(&mut natural_order).unwrap(), ints(&[1, 1, 3, 4, 5]));
+        assert_eq!(queue.len(), 5);
+
+        // Largest first
+        let mut largest_first = |a: &RuntimeValue, b: &RuntimeValue| natural_order(b, a);
+        let mut queue = PriorityQueue::new(None);
+        for value in ints(&[2, 7, 3]) {
+            queue.push(value, &mut largest_first).unwrap();
+        }
+        assert_eq!(queue.pop(&mut largest_first).unwrap(), Some(RuntimeValue::Int32(7)));
+        assert_eq!(queue.pop(&mut largest_first).unwrap(), Some(RuntimeValue::Int32(3)));
+    }
+
+    #[test]
+    fn test_ordered_map_keys() {
+        let mut map = OrderedMap::new();
+        for key in [30, 10, 20] {
+            map.insert(RuntimeValue::Int32(key), RuntimeValue::Int32(key * 2)).unwrap();
+        }
+        assert_eq!(map.insert(RuntimeValue::Int64(10), RuntimeValue::Null).unwrap(), Some(RuntimeValue::Int32(20)));
+        assert_eq!(map.first_key(), Some(&RuntimeValue::Int32(10)));
+        assert_eq!(map.floor_key(&RuntimeValue::Int32(25)), Some(&RuntimeValue::Int32(20)));
+        assert_eq!(map.ceiling_key(&RuntimeValue::Int32(25)), Some(&RuntimeValue::Int32(30)));
+        assert_eq!(map.floor_key(&RuntimeValue::Int32(5)), None);
+        assert!(map.insert(RuntimeValue::Channel(1), RuntimeValue::Null).is_err());
+    }
+
+    #[test]
+    fn test_linked_map_keeps_insertion_order() {
+        let mut map = LinkedMap::new();
+        for key in [30, 10, 20] {
+            map.insert(RuntimeValue::Int32(key), RuntimeValue::Int32(key * 2)).unwrap();
+        }
+        // Setting a key again keeps its place, and removal closes the gap
+        assert_eq!(map.insert(RuntimeValue::Int64(30), RuntimeValue::Null).unwrap(), Some(RuntimeValue::Int32(60)));
+        assert_eq!(map.remove(&RuntimeValue::Int32(10)), Some(RuntimeValue::Int32(20)));
+        map.insert(RuntimeValue::Int32(5), RuntimeValue::Int32(10)).unwrap();
+        let keys: Vec<_> = map.entries().iter().map(|(key, _)| key.clone()).collect();
+        assert_eq!(keys, ints(&[30, 20, 5]));
+        assert_eq!(map.get(&RuntimeValue::Int32(20)), Some(&RuntimeValue::Int32(40)));
+        assert_eq!(map.get(&RuntimeValue::Int32(5)), Some(&RuntimeValue::Int32(10)));
+        assert_eq!(map.last_key(), Some(&RuntimeValue::Int32(5)));
+    }
+
+    #[test]
+    fn test_linked_set_operations() {
+        let a = LinkedSet::from_elements(ints(&[3, 1, 2, 1])).unwrap();
+        let b = LinkedSet::from_elements(ints(&[2, 4, 3])).unwrap();
+        let elements = |set: LinkedSet| set.iter().cloned().collect::<Vec<_>>();
+        assert_eq!(elements(a.union(&b)), ints(&[3, 1, 2, 4]));
+        assert_eq!(elements(a.intersection(&b)), ints(&[3, 2]));
+        assert_eq!(elements(a.difference(&b)), ints(&[1]));
+        assert!(a.intersection(&b).is_subset(&b));
+        assert!(!a.is_subset(&b));
+        assert!(LinkedSet::new().insert(RuntimeValue::Channel(1)).is_err());
+    }
+
+    #[test]
+    fn test_registry_frees_collections() {
+        let mut registry = CollectionRegistry::new();
+        let deque = registry.create(Collection::Deque(VecDeque::new()));
+        let map = registry.create(Collection::OrderedMap(OrderedMap::new()));
+        let held = registry.get(deque).unwrap();
+        assert_eq!(registry.len(), 2);
+
+        assert!(registry.free(deque));
+        assert!(!registry.free(deque));
+        assert!(registry.get(deque).is_none());
+        assert!(registry.get(map).is_some());
+        assert_eq!(registry.len(), 1);
+        // IDs are not reused, so a stale handle cannot reach a newer collection
+        assert_ne!(registry.create(Collection::Deque(VecDeque::new())), deque);
+        assert!(matches!(&*held.lock().unwrap(), Collection::Deque(_)));
+    }
+}
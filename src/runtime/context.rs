@@ -0,0 +1,510 @@
+//! Cancellation contexts for goroutines
+//!
+//! A context carries a cancellation signal, an optional deadline and
+//! key/value pairs down a tree of work. Cancelling a context cancels every
+//! context derived from it, and a derived context never outlives its
+//! parent's deadline.
+//!
+//! Each thread has a current context. Goroutines inherit the context of the
+//! code that spawned them, and blocking operations (channel sends and
+//! receives, `sleep`, network reads and accepts) wait in short slices so
+//! they can give up as soon as the current context is done.
+
+use crate::error::{BuluError, Result};
+use crate::types::primitive::RuntimeValue;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+/// Unique identifier for contexts
+pub type ContextId = usize;
+
+/// How long a blocking operation waits before looking at its context again
+pub const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Wakes whoever is waiting for a context to be done
+pub type DoneWaker = Box<dyn FnOnce() + Send>;
+
+/// Identifies a registered `DoneWaker`, for removing it again
+pub type DoneWakerId = u64;
+
+/// Why a context is done
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextError {
+    Canceled,
+    DeadlineExceeded,
+}
+
+impl ContextError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            ContextError::Canceled => "context canceled",
+            ContextError::DeadlineExceeded => "context deadline exceeded",
+        }
+    }
+
+    /// The runtime error a blocking operation returns when it gives up
+    pub fn to_error(&self) -> BuluError {
+        BuluError::RuntimeError {
+            file: None,
+            message: self.message().to_string(),
+        }
+    }
+}
+
+struct ContextInner {
+    parent: Option<Context>,
+    deadline: Option<Instant>,
+    value: Option<(String, RuntimeValue)>,
+    /// Background contexts are never done
+    cancelable: bool,
+    done: Mutex<Option<ContextError>>,
+    changed: Condvar,
+    children: Mutex<Vec<Weak<ContextInner>>>,
+    wakers: Mutex<Wakers>,
+}
+
+/// The wakers waiting for a context, and whether its deadline timer is armed
+#[derive(Default)]
+struct Wakers {
+    waiting: Vec<(DoneWakerId, DoneWaker)>,
+    next_id: DoneWakerId,
+    deadline_armed: bool,
+}
+
+impl std::fmt::Debug for ContextInner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContextInner")
+            .field("parent", &self.parent)
+            .field("deadline", &self.deadline)
+            .field("value", &self.value)
+            .field("cancelable", &self.cancelable)
+            .field("done", &self.done)
+            .field("wakers", &format!("{} wakers", self.wakers.lock().unwrap().waiting.len()))
+            .finish()
+    }
+}
+
+/// A cancellation context; clones share the same state
+#[derive(Debug, Clone)]
+pub struct Context {
+    inner: Arc<ContextInner>,
+}
+
+impl Context {
+    /// The root context: never cancelled, no deadline, no values
+    pub fn background() -> Self {
+        Self::new(None, None, None, false)
+    }
+
+    /// A child context that is cancelled by `cancel` or with its parent
+    pub fn with_cancel(&self) -> Self {
+        self.child(self.deadline(), None)
+    }
+
+    /// A child context that is done at `deadline`, or earlier with its parent
+    pub fn with_deadline(&self, deadline: Instant) -> Self {
+        let deadline = match self.deadline() {
+            Some(parent) => parent.min(deadline),
+            None => deadline,
+        };
+        self.child(Some(deadline), None)
+    }
+
+    /// A child context that is done after `timeout`
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// A child context that carries `key` (shadowing the same key further up)
+    pub fn with_value(&self, key: &str, value: RuntimeValue) -> Self {
+        self.child(self.deadline(), Some((key.to_string(), value)))
+    }
+
+    fn new(
+        parent: Option<Context>,
+        deadline: Option<Instant>,
+        value: Option<(String, RuntimeValue)>,
+        cancelable: bool,
+    ) -> Self {
+        Self {
+            inner: Arc::new(ContextInner {
+                parent,
+                deadline,
+                value,
+                cancelable,
+                done: Mutex::new(None),
+                changed: Condvar::new(),
+                children: Mutex::new(Vec::new()),
+                wakers: Mutex::new(Wakers::default()),
+            }),
+        }
+    }
+
+    fn child(&self, deadline: Option<Instant>, value: Option<(String, RuntimeValue)>) -> Self {
+        let child = Self::new(Some(self.clone()), deadline, value, true);
+        let mut children = self.inner.children.lock().unwrap();
+        children.retain(|child| child.strong_count() > 0);
+        children.push(Arc::downgrade(&child.inner));
+        drop(children);
+
+        // A child of a context that is already done starts out done
+        if let Some(error) = self.err() {
+            child.finish(error);
+        }
+        child
+    }
+
+    /// Cancel this context and every context derived from it
+    pub fn cancel(&self) {
+        self.finish(ContextError::Canceled);
+    }
+
+    fn finish(&self, error: ContextError) {
+        if !self.inner.cancelable {
+            return;
+        }
+        {
+            let mut done = self.inner.done.lock().unwrap();
+            if done.is_some() {
+                return;
+            }
+            *done = Some(error);
+        }
+        self.inner.changed.notify_all();
+        let wakers = std::mem::take(&mut self.inner.wakers.lock().unwrap().waiting);
+        for (_, waker) in wakers {
+            waker();
+        }
+
+        let children: Vec<_> = self.inner.children.lock().unwrap().drain(..).collect();
+        for child in children.into_iter().filter_map(|child| child.upgrade()) {
+            Context { inner: child }.finish(error);
+        }
+    }
+
+    /// Why the context is done, or `None` while it is still live
+    pub fn err(&self) -> Option<ContextError> {
+        if let Some(error) = *self.inner.done.lock().unwrap() {
+            return Some(error);
+        }
+        match self.inner.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                self.finish(ContextError::DeadlineExceeded);
+                Some(ContextError::DeadlineExceeded)
+            }
+            _ => None,
+        }
+    }
+
+    /// Check whether the context is done
+    pub fn is_done(&self) -> bool {
+        self.err().is_some()
+    }
+
+    /// Return the context's error as a runtime error once it is done
+    pub fn check(&self) -> Result<()> {
+        match self.err() {
+            Some(error) => Err(error.to_error()),
+            None => Ok(()),
+        }
+    }
+
+    /// The deadline, if this context or one of its parents has one
+    pub fn deadline(&self) -> Option<Instant> {
+        self.inner.deadline
+    }
+
+    /// Look a value up in this context and then its parents
+    pub fn value(&self, key: &str) -> Option<RuntimeValue> {
+        let mut context = Some(self);
+        while let Some(current) = context {
+            if let Some((name, value)) = &current.inner.value {
+                if name == key {
+                    return Some(value.clone());
+                }
+            }
+            context = current.inner.parent.as_ref();
+        }
+        None
+    }
+
+    /// Wait until the context is done or `timeout` has passed. Returns the
+    /// context's error if it finished first.
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<ContextError> {
+        let until = Instant::now() + timeout;
+        let until = match self.inner.deadline {
+            Some(deadline) => deadline.min(until),
+            None => until,
+        };
+
+        let mut done = self.inner.done.lock().unwrap();
+        while done.is_none() {
+            let remaining = until.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            done = self.inner.changed.wait_timeout(done, remaining).unwrap().0;
+        }
+        drop(done);
+        self.err()
+    }
+
+    /// Block until the context is done. Returns immediately with `None` for
+    /// contexts that can never be done.
+    pub fn wait(&self) -> Option<ContextError> {
+        if !self.inner.cancelable {
+            return None;
+        }
+        loop {
+            let timeout = match self.inner.deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => Duration::from_secs(3600),
+            };
+            if let Some(error) = self.wait_timeout(timeout) {
+                return Some(error);
+            }
+        }
+    }
+
+    /// Run `waker` once the context is done, right away if it already is.
+    /// Returns the ID to remove it with while it is still waiting, or `None`
+    /// when it ran or the context can never be done (and it was dropped).
+    pub fn on_done(&self, waker: DoneWaker) -> Option<DoneWakerId> {
+        if !self.inner.cancelable {
+            return None;
+        }
+        if self.is_done() {
+            waker();
+            return None;
+        }
+        let mut wakers = self.inner.wakers.lock().unwrap();
+        // Cancelled between the check and taking the lock
+        if self.inner.done.lock().unwrap().is_some() {
+            drop(wakers);
+            waker();
+            return None;
+        }
+        let id = wakers.next_id;
+        wakers.next_id += 1;
+        wakers.waiting.push((id, waker));
+
+        // A deadline is only noticed when someone looks, so a timer looks at it
+        if let (Some(deadline), false) = (self.inner.deadline, wakers.deadline_armed) {
+            wakers.deadline_armed = true;
+            let context = Arc::downgrade(&self.inner);
+            crate::runtime::timers::TimerWheel::global().schedule(deadline, move |_| {
+                let inner = context.upgrade()?;
+                match (Context { inner }).err() {
+                    Some(_) => None,
+                    None => Some(Instant::now() + CANCEL_POLL_INTERVAL),
+                }
+            });
+        }
+        Some(id)
+    }
+
+    /// Remove a waker registered with `on_done` that is no longer needed
+    pub fn remove_waker(&self, id: DoneWakerId) {
+        self.inner.wakers.lock().unwrap().waiting.retain(|(waiting, _)| *waiting != id);
+    }
+
+    /// Whether the context can ever be done (background contexts cannot)
+    pub fn can_finish(&self) -> bool {
+        self.inner.cancelable
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Context>> = const { RefCell::new(None) };
+}
+
+/// The context of the code running on this thread, if any
+pub fn current() -> Option<Context> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Restores the previous current context when dropped
+pub struct ContextScope {
+    previous: Option<Context>,
+}
+
+impl Drop for ContextScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Make `context` the current context until the returned scope is dropped
+pub fn enter(context: Option<Context>) -> ContextScope {
+    let previous = CURRENT.with(|current| std::mem::replace(&mut *current.borrow_mut(), context));
+    ContextScope { previous }
+}
+
+/// Fail with the current context's error once it is done
+pub fn check_current() -> Result<()> {
+    match current() {
+        Some(context) => context.check(),
+        None => Ok(()),
+    }
+}
+
+/// Sleep for `duration`, returning early with an error if the current context is done
+pub fn sleep(duration: Duration) -> Result<()> {
+    match current() {
+        Some(context) => match context.wait_timeout(duration) {
+            Some(error) => Err(error.to_error()),
+            None => Ok(()),
+        },
+        None => {
+            std::thread::sleep(duration);
+            Ok(())
+        }
+    }
+}
+
+/// Retry a bounded I/O attempt until it stops reporting "not ready yet", the
+/// overall `timeout` passes, or the current context is done. The attempt must
+/// not block for long (a short read timeout or non-blocking socket).
+pub fn retry_io<T>(
+    timeout: Option<Duration>,
+    mut attempt: impl FnMut() -> std::io::Result<T>,
+) -> std::io::Result<T> {
+    use std::io::{Error, ErrorKind};
+
+    let started = Instant::now();
+    loop {
+        if let Some(error) = current().and_then(|context| context.err()) {
+            return Err(Error::new(ErrorKind::Interrupted, error.message()));
+        }
+        match attempt() {
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                if timeout.is_some_and(|timeout| started.elapsed() >= timeout) {
+                    return Err(e);
+                }
+                if let Some(error) = sleep(CANCEL_POLL_INTERVAL).err() {
+                    return Err(Error::new(ErrorKind::Interrupted, error.to_string()));
+                }
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Registry of the contexts handed out to Bulu code
+#[derive(Debug, Default)]
+pub struct ContextRegistry {
+    contexts: HashMap<ContextId, Context>,
+    next_id: ContextId,
+}
+
+impl ContextRegistry {
+    pub fn new() -> Self {
+        Self {
+            contexts: HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Register a context and return its ID
+    pub fn register(&mut self, context: Context) -> ContextId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.contexts.insert(id, context);
+        id
+    }
+
+    /// Get a context by ID
+    pub fn get(&self, id: ContextId) -> Option<&Context> {
+        self.contexts.get(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_propagates_to_children() {
+        let root = Context::background().with_cancel();
+        let child = root.with_value("user", RuntimeValue::Int32(7));
+        let grandchild = child.with_cancel();
+
+        grandchild.cancel();
+        assert!(root.err().is_none());
+        root.cancel();
+        assert_eq!(child.err(), Some(ContextError::Canceled));
+        assert_eq!(grandchild.value("user"), Some(RuntimeValue::Int32(7)));
+
+        // Created after the parent was cancelled
+        assert!(root.with_cancel().is_done());
+        // The background context is never done
+        let background = Context::background();
+        background.cancel();
+        assert!(!background.is_done());
+    }
+
+    #[test]
+    fn test_deadline_is_inherited() {
+        let parent = Context::background().with_timeout(Duration::from_millis(20));
+        let child = parent.with_timeout(Duration::from_secs(60));
+        assert_eq!(child.deadline(), parent.deadline());
+
+        let start = Instant::now();
+        assert_eq!(child.wait(), Some(ContextError::DeadlineExceeded));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!(parent.err(), Some(ContextError::DeadlineExceeded));
+    }
+
+    #[test]
+    fn test_done_wakers_run_on_cancel_and_deadline() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let woken = Arc::new(AtomicUsize::new(0));
+        let waker = || -> DoneWaker {
+            let woken = woken.clone();
+            Box::new(move || {
+                woken.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+
+        let parent = Context::background().with_cancel();
+        let child = parent.with_cancel();
+        assert!(child.on_done(waker()).is_some());
+        let removed = child.on_done(waker()).unwrap();
+        child.remove_waker(removed);
+        parent.cancel();
+        assert_eq!(woken.load(Ordering::SeqCst), 1);
+
+        // Already done: the waker runs straight away
+        assert!(child.on_done(waker()).is_none());
+        assert_eq!(woken.load(Ordering::SeqCst), 2);
+
+        // Nobody looks at the deadline, the timer does
+        let timed = Context::background().with_timeout(Duration::from_millis(20));
+        timed.on_done(waker());
+        let start = Instant::now();
+        while woken.load(Ordering::SeqCst) < 3 {
+            assert!(start.elapsed() < Duration::from_secs(2));
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_sleep_returns_early_when_cancelled() {
+        let context = Context::background().with_cancel();
+        let canceller = context.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            canceller.cancel();
+        });
+
+        let _scope = enter(Some(context));
+        let start = Instant::now();
+        let error = sleep(Duration::from_secs(5)).unwrap_err();
+        assert!(error.to_string().contains("context canceled"));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -2,10 +2,17 @@
 //!
 //! This module implements the try-fail error handling mechanism,
 //! error propagation, and error formatting.
+//!
+//! The error handler also keeps the call stack of the Bulu functions being
+//! run: each frame has the function's name, the position of the call and,
+//! when argument capture is on, a short summary of every argument. When a
+//! call fails, the stack at the innermost failing frame is kept so the error
+//! can be reported with its trace once it has unwound to the top.
 
 use crate::ast::*;
 use crate::error::{BuluError, Result};
 use crate::lexer::token::Position;
+use crate::types::primitive::{sorted_map_entries, RuntimeValue};
 
 use std::fmt;
 
@@ -41,9 +48,22 @@ pub enum ErrorType {
 #[derive(Debug, Clone, PartialEq)]
 pub struct StackFrame {
     pub function_name: String,
+    /// Where the function was called
     pub position: Position,
+    /// Parameter names and summaries of their arguments, empty unless
+    /// argument capture is on
+    pub arguments: Vec<(String, String)>,
 }
 
+/// Longest argument summary, in characters, before it is cut short with `...`
+pub const MAX_ARGUMENT_SUMMARY: usize = 40;
+
+/// Elements or fields shown per collection in an argument summary
+const MAX_SUMMARY_ITEMS: usize = 4;
+
+/// Collections nested deeper than this are summarised as `[...]`
+const MAX_SUMMARY_DEPTH: usize = 2;
+
 /// Error handler manages try-fail blocks and error propagation
 pub struct ErrorHandler {
     /// Stack of active try blocks
@@ -52,6 +72,13 @@ pub struct ErrorHandler {
     current_error: Option<RuntimeError>,
     /// Defer stack for cleanup
     pub defer_stack: Vec<DeferredAction>,
+    /// Frames of the functions being called, outermost first
+    call_stack: Vec<StackFrame>,
+    /// Whether new frames record summaries of their arguments
+    capture_arguments: bool,
+    /// Text of the error the last failing call raised, with the call stack
+    /// at its innermost frame
+    failure: Option<(String, Vec<StackFrame>)>,
 }
 
 /// Active try block information
@@ -90,6 +117,7 @@ impl RuntimeError {
         self.stack_trace.push(StackFrame {
             function_name,
             position,
+            arguments: Vec::new(),
         });
     }
 
@@ -104,10 +132,7 @@ impl RuntimeError {
         if !self.stack_trace.is_empty() {
             result.push_str("\nStack trace:");
             for frame in &self.stack_trace {
-                result.push_str(&format!(
-                    "\n  at {} (line {}, column {})",
-                    frame.function_name, frame.position.line, frame.position.column
-                ));
+                result.push_str(&format!("\n  at {}", frame));
             }
         }
 
@@ -121,6 +146,97 @@ impl fmt::Display for RuntimeError {
     }
 }
 
+impl fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.function_name)?;
+        if !self.arguments.is_empty() {
+            let arguments: Vec<String> = self
+                .arguments
+                .iter()
+                .map(|(name, summary)| format!("{}={}", name, summary))
+                .collect();
+            write!(f, "({})", arguments.join(", "))?;
+        }
+        write!(f, " (line {}, column {})", self.position.line, self.position.column)
+    }
+}
+
+/// A short rendering of `value` for stack traces: strings are quoted,
+/// collections show their first few elements, nesting is cut off at a fixed
+/// depth and the whole is at most `MAX_ARGUMENT_SUMMARY` characters. Values
+/// only refer to other values through handles, which are shown as ids, so
+/// the summary of any value takes bounded time.
+pub fn summarize_value(value: &RuntimeValue) -> String {
+    let mut summary = String::new();
+    write_summary(value, 0, &mut summary);
+    if summary.chars().count() > MAX_ARGUMENT_SUMMARY {
+        summary = summary.chars().take(MAX_ARGUMENT_SUMMARY - 3).collect();
+        summary.push_str("...");
+    }
+    summary
+}
+
+fn write_summary(value: &RuntimeValue, depth: usize, out: &mut String) {
+    match value {
+        RuntimeValue::String(s) => {
+            // Long strings are cut before escaping, so the summary stays bounded
+            let shown: String = s.chars().take(MAX_ARGUMENT_SUMMARY).collect();
+            out.push_str(&format!("{:?}", shown));
+        }
+        RuntimeValue::Char(c) => out.push_str(&format!("{:?}", c)),
+        RuntimeValue::Array(items) | RuntimeValue::Slice(items) => {
+            write_items(items.iter().map(|item| (None, item)), items.len(), "[", "]", depth, out)
+        }
+        RuntimeValue::Tuple(items) => {
+            write_items(items.iter().map(|item| (None, item)), items.len(), "(", ")", depth, out)
+        }
+        RuntimeValue::Set(items) => {
+            write_items(items.iter().map(|item| (None, item)), items.len(), "set{", "}", depth, out)
+        }
+        RuntimeValue::Map(map) => {
+            let entries = sorted_map_entries(map).into_iter().map(|(key, item)| (Some(key.as_str()), item));
+            write_items(entries, map.len(), "{", "}", depth, out)
+        }
+        RuntimeValue::Struct { name, fields } => {
+            let mut names: Vec<&String> = fields.keys().collect();
+            names.sort();
+            let entries = names.into_iter().map(|key| (Some(key.as_str()), &fields[key]));
+            write_items(entries, fields.len(), &format!("{}{{", name), "}", depth, out)
+        }
+        RuntimeValue::MethodRef { method_name, .. } => out.push_str(&format!("method({})", method_name)),
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+fn write_items<'a>(
+    items: impl Iterator<Item = (Option<&'a str>, &'a RuntimeValue)>,
+    len: usize,
+    open: &str,
+    close: &str,
+    depth: usize,
+    out: &mut String,
+) {
+    out.push_str(open);
+    if depth >= MAX_SUMMARY_DEPTH && len > 0 {
+        out.push_str("...");
+    } else {
+        for (i, (key, item)) in items.take(MAX_SUMMARY_ITEMS).enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            if let Some(key) = key {
+                out.push_str(key);
+                out.push_str(": ");
+            }
+            write_summary(item, depth + 1, out);
+        }
+        if len > MAX_SUMMARY_ITEMS {
+            out.push_str(&format!(", ... {} more", len - MAX_SUMMARY_ITEMS));
+        }
+    }
+    out.push_str(close);
+}
+
 impl ErrorHandler {
     /// Create a new error handler
     pub fn new() -> Self {
@@ -128,6 +244,91 @@ impl ErrorHandler {
             try_stack: Vec::new(),
             current_error: None,
             defer_stack: Vec::new(),
+            call_stack: Vec::new(),
+            capture_arguments: false,
+            failure: None,
+        }
+    }
+
+    /// Record summaries of the arguments in the frames entered from now on
+    pub fn set_capture_arguments(&mut self, capture: bool) {
+        self.capture_arguments = capture;
+    }
+
+    /// Whether frames record summaries of their arguments
+    pub fn captures_arguments(&self) -> bool {
+        self.capture_arguments
+    }
+
+    /// Push the frame of a call to `function_name` made at `call_site`
+    pub fn enter_function(
+        &mut self,
+        function_name: String,
+        call_site: Position,
+        params: &[Parameter],
+        args: &[RuntimeValue],
+    ) {
+        let arguments = if self.capture_arguments {
+            params
+                .iter()
+                .zip(args)
+                .map(|(param, arg)| (param.name.clone(), summarize_value(arg)))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self.call_stack.push(StackFrame {
+            function_name,
+            position: call_site,
+            arguments,
+        });
+    }
+
+    /// Pop the innermost frame. `failure` is the text of the error the call
+    /// failed with: the first frame to fail with an error keeps the stack,
+    /// and a call that returns normally forgets it, since whatever failed
+    /// below it was handled.
+    pub fn exit_function(&mut self, failure: Option<&str>) {
+        match failure {
+            Some(error) => {
+                if self.failure.as_ref().map(|(text, _)| text.as_str()) != Some(error) {
+                    self.failure = Some((error.to_string(), self.call_stack.clone()));
+                }
+            }
+            None => self.failure = None,
+        }
+        self.call_stack.pop();
+    }
+
+    /// Frames of the functions being called, outermost first
+    pub fn call_stack(&self) -> &[StackFrame] {
+        &self.call_stack
+    }
+
+    /// The call stack where `error` was raised, outermost first, if it is
+    /// the error the last failing call raised
+    pub fn failure_stack(&self, error: &str) -> Option<&[StackFrame]> {
+        match &self.failure {
+            Some((text, frames)) if text == error => Some(frames),
+            _ => None,
+        }
+    }
+
+    /// `error` with the stack it was raised at appended to its message,
+    /// innermost frame first; errors without a recorded stack are unchanged
+    pub fn with_stack_trace(&self, error: BuluError) -> BuluError {
+        let Some(frames) = self.failure_stack(&error.to_string()) else {
+            return error;
+        };
+        match error {
+            BuluError::RuntimeError { mut message, file } => {
+                message.push_str("\nStack trace:");
+                for frame in frames.iter().rev() {
+                    message.push_str(&format!("\n  at {}", frame));
+                }
+                BuluError::RuntimeError { message, file }
+            }
+            other => other,
         }
     }
 
@@ -291,10 +492,7 @@ impl ErrorFormatter {
         if !error.stack_trace.is_empty() {
             report.push_str("\nStack trace:\n");
             for frame in &error.stack_trace {
-                report.push_str(&format!(
-                    "  at {} (line {}, column {})\n",
-                    frame.function_name, frame.position.line, frame.position.column
-                ));
+                report.push_str(&format!("  at {}\n", frame));
             }
         }
         
@@ -381,4 +579,62 @@ mod tests {
         assert!(report.contains("Source context"));
         assert!(report.contains("let z = x / 0"));
     }
+
+    #[test]
+    fn test_summaries_are_bounded() {
+        let ints = |n: i64| RuntimeValue::Array((0..n).map(RuntimeValue::Int64).collect());
+        assert_eq!(summarize_value(&RuntimeValue::String("hi \"x\"".to_string())), "\"hi \\\"x\\\"\"");
+        assert_eq!(summarize_value(&ints(3)), "[0, 1, 2]");
+        assert_eq!(summarize_value(&ints(100)), "[0, 1, 2, 3, ... 96 more]");
+        assert_eq!(
+            summarize_value(&RuntimeValue::Tuple(vec![RuntimeValue::Tuple(vec![ints(2)])])),
+            "(([...]))"
+        );
+        let point = RuntimeValue::Struct {
+            name: "Point".to_string(),
+            fields: [("y", 2), ("x", 1)]
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), RuntimeValue::Int32(value)))
+                .collect(),
+        };
+        assert_eq!(summarize_value(&point), "Point{x: 1, y: 2}");
+        let long = summarize_value(&RuntimeValue::String("a".repeat(1000)));
+        assert_eq!(long.chars().count(), MAX_ARGUMENT_SUMMARY);
+        assert!(long.ends_with("..."));
+    }
+
+    #[test]
+    fn test_call_stack_keeps_failing_frames() {
+        let mut handler = ErrorHandler::new();
+        handler.set_capture_arguments(true);
+        let param = |name: &str| Parameter {
+            name: name.to_string(),
+            param_type: Type::Int32,
+            default_value: None,
+            is_variadic: false,
+            position: Position::new(1, 1, 0),
+        };
+
+        handler.enter_function("main".to_string(), Position::new(1, 1, 0), &[], &[]);
+        handler.enter_function(
+            "divide".to_string(),
+            Position::new(3, 12, 30),
+            &[param("a"), param("b")],
+            &[RuntimeValue::Int32(1), RuntimeValue::Int32(0)],
+        );
+        assert_eq!(handler.call_stack().len(), 2);
+        assert_eq!(handler.call_stack()[1].to_string(), "divide(a=1, b=0) (line 3, column 12)");
+
+        // Unwinding keeps the stack of the innermost frame
+        handler.exit_function(Some("Runtime Error: Division by zero"));
+        handler.exit_function(Some("Runtime Error: Division by zero"));
+        assert!(handler.call_stack().is_empty());
+        assert_eq!(handler.failure_stack("Runtime Error: Division by zero").unwrap().len(), 2);
+        assert!(handler.failure_stack("Runtime Error: other").is_none());
+
+        // A call that returns normally handled any earlier failure
+        handler.enter_function("main".to_string(), Position::new(1, 1, 0), &[], &[]);
+        handler.exit_function(None);
+        assert!(handler.failure_stack("Runtime Error: Division by zero").is_none());
+    }
 }
\ No newline at end of file
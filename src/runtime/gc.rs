@@ -6,10 +6,11 @@
 //! - Concurrent collection with minimal pause times
 //! - Escape analysis integration
 //! - GC tuning parameters and monitoring
+//! - Allocation accounting per goroutine, with optional soft limits
 
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
 use std::time::{Duration, Instant};
 
@@ -32,6 +33,8 @@ pub struct GcConfig {
     pub concurrent_gc: bool,
     /// Maximum GC pause time target in milliseconds
     pub max_pause_time_ms: u64,
+    /// Soft limit on the bytes each spawned goroutine allocates
+    pub goroutine_memory_limit: Option<usize>,
 }
 
 impl Default for GcConfig {
@@ -45,6 +48,7 @@ impl Default for GcConfig {
             promotion_threshold: 2,
             concurrent_gc: true,
             max_pause_time_ms: 10, // 10ms target
+            goroutine_memory_limit: None,
         }
     }
 }
@@ -697,6 +701,129 @@ impl RootSet for EmptyRootSet {
     }
 }
 
+/// Bytes allocated by one goroutine and its soft memory limit
+#[derive(Debug)]
+pub struct GoroutineAllocations {
+    id: u32,
+    bytes: AtomicU64,
+    allocations: AtomicU64,
+    /// Soft limit in bytes, 0 when there is none
+    limit: AtomicU64,
+    /// Set once the goroutine has been told it went over its limit
+    exceeded: AtomicBool,
+}
+
+impl GoroutineAllocations {
+    fn new(id: u32, limit: Option<u64>) -> Self {
+        Self {
+            id,
+            bytes: AtomicU64::new(0),
+            allocations: AtomicU64::new(0),
+            limit: AtomicU64::new(limit.unwrap_or(0)),
+            exceeded: AtomicBool::new(false),
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Count an allocation of `size` bytes. Returns the limit the first time
+    /// the goroutine's bytes go over it, so the caller can raise an error;
+    /// the goroutine may then carry on, the limit being soft.
+    pub fn record(&self, size: usize) -> Option<u64> {
+        let bytes = self.bytes.fetch_add(size as u64, Ordering::Relaxed) + size as u64;
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        let limit = self.limit.load(Ordering::Relaxed);
+        if limit > 0 && bytes > limit && !self.exceeded.swap(true, Ordering::Relaxed) {
+            Some(limit)
+        } else {
+            None
+        }
+    }
+
+    /// Replace the limit; `None` removes it. A goroutine already over the
+    /// new limit is told so at its next allocation.
+    pub fn set_limit(&self, limit: Option<u64>) {
+        self.limit.store(limit.unwrap_or(0), Ordering::Relaxed);
+        self.exceeded.store(false, Ordering::Relaxed);
+    }
+
+    pub fn usage(&self) -> GoroutineUsage {
+        let limit = self.limit.load(Ordering::Relaxed);
+        GoroutineUsage {
+            goroutine: self.id,
+            bytes: self.bytes.load(Ordering::Relaxed),
+            allocations: self.allocations.load(Ordering::Relaxed),
+            limit: (limit > 0).then_some(limit),
+        }
+    }
+}
+
+/// What a goroutine has allocated so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GoroutineUsage {
+    pub goroutine: u32,
+    pub bytes: u64,
+    pub allocations: u64,
+    pub limit: Option<u64>,
+}
+
+/// Allocations of the running goroutines of a program. The main goroutine is
+/// number 0; goroutines are removed when they finish.
+#[derive(Debug, Default)]
+pub struct AllocationAccounting {
+    goroutines: Mutex<HashMap<u32, Arc<GoroutineAllocations>>>,
+    /// Limit given to goroutines when they start, 0 when there is none
+    default_limit: AtomicU64,
+}
+
+impl AllocationAccounting {
+    /// Accounting whose spawned goroutines get the limit of `LANG_GC_GOROUTINE_LIMIT`
+    pub fn from_env() -> Self {
+        let accounting = Self::default();
+        let limit = parse_gc_config_from_env().goroutine_memory_limit;
+        accounting.set_default_limit(limit.map(|limit| limit as u64));
+        accounting
+    }
+
+    /// Start counting for the main goroutine, which has no limit until it sets one
+    pub fn main_goroutine(&self) -> Arc<GoroutineAllocations> {
+        self.insert(GoroutineAllocations::new(0, None))
+    }
+
+    /// Start counting for a spawned goroutine, under the default limit
+    pub fn spawned(&self, id: u32) -> Arc<GoroutineAllocations> {
+        let limit = self.default_limit.load(Ordering::Relaxed);
+        self.insert(GoroutineAllocations::new(id, (limit > 0).then_some(limit)))
+    }
+
+    fn insert(&self, goroutine: GoroutineAllocations) -> Arc<GoroutineAllocations> {
+        let goroutine = Arc::new(goroutine);
+        self.goroutines.lock().unwrap().insert(goroutine.id, goroutine.clone());
+        goroutine
+    }
+
+    /// Stop counting for a goroutine that finished
+    pub fn finished(&self, id: u32) {
+        self.goroutines.lock().unwrap().remove(&id);
+    }
+
+    /// The limit of goroutines started from now on; `None` removes it
+    pub fn set_default_limit(&self, limit: Option<u64>) {
+        self.default_limit.store(limit.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    /// The `count` running goroutines that allocated the most bytes, most first
+    pub fn top(&self, count: usize) -> Vec<GoroutineUsage> {
+        let mut usages: Vec<GoroutineUsage> =
+            self.goroutines.lock().unwrap().values().map(|goroutine| goroutine.usage()).collect();
+        usages.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.goroutine.cmp(&b.goroutine)));
+        usages.truncate(count);
+        usages
+    }
+}
+
 /// Parse GC configuration from environment variables
 pub fn parse_gc_config_from_env() -> GcConfig {
     let mut config = GcConfig::default();
@@ -721,6 +848,12 @@ pub fn parse_gc_config_from_env() -> GcConfig {
         }
     }
 
+    if let Ok(limit) = std::env::var("LANG_GC_GOROUTINE_LIMIT") {
+        if let Ok(size) = parse_size(&limit) {
+            config.goroutine_memory_limit = (size > 0).then_some(size);
+        }
+    }
+
     if let Ok(debug) = std::env::var("LANG_GC_DEBUG") {
         config.debug = debug.to_lowercase() == "true";
     }
@@ -729,7 +862,7 @@ pub fn parse_gc_config_from_env() -> GcConfig {
 }
 
 /// Parse size string (e.g., "1024M", "2G")
-fn parse_size(size_str: &str) -> Result<usize, String> {
+pub(crate) fn parse_size(size_str: &str) -> Result<usize, String> {
     let size_str = size_str.trim().to_uppercase();
 
     if let Some(num_str) = size_str.strip_suffix('G') {
@@ -768,6 +901,31 @@ mod tests {
         assert_eq!(parse_size("1G").unwrap(), 1024 * 1024 * 1024);
     }
 
+    #[test]
+    fn test_goroutine_limits_are_reported_once() {
+        let accounting = AllocationAccounting::default();
+        accounting.set_default_limit(Some(100));
+        let main = accounting.main_goroutine();
+        let worker = accounting.spawned(7);
+
+        assert_eq!(worker.record(60), None);
+        assert_eq!(worker.record(60), Some(100));
+        assert_eq!(worker.record(60), None);
+        assert_eq!(main.record(500), None);
+
+        // A new limit the goroutine is already over is reported again
+        worker.set_limit(Some(150));
+        assert_eq!(worker.record(1), Some(150));
+
+        let top = accounting.top(1);
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0], GoroutineUsage { goroutine: 0, bytes: 500, allocations: 1, limit: None });
+        assert_eq!(accounting.top(5)[1].bytes, 181);
+
+        accounting.finished(7);
+        assert_eq!(accounting.top(5).len(), 1);
+    }
+
     #[test]
     fn test_generation_allocation() {
         let mut gen = Generation::new(1024);
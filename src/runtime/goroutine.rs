@@ -1,6 +1,7 @@
 // Goroutine runtime implementation based on M:N threading model
 // Inspired by Go's runtime and Tokio's architecture
 
+use std::cell::Cell;
 use std::collections::{HashMap, VecDeque};
 use std::sync::{
     atomic::{AtomicBool, AtomicU64, Ordering},
@@ -9,13 +10,67 @@ use std::sync::{
 use std::thread::{self, JoinHandle};
 use std::time::Duration;
 
+use corosensei::stack::DefaultStack;
+use corosensei::{Coroutine, CoroutineResult, Yielder};
+
 use super::super::compiler::ir::{IrFunction, IrProgram};
 use super::super::error::Result;
 use super::super::types::primitive::RuntimeValue;
+use super::context::{Context, DoneWakerId};
+use super::promises::PromiseRegistry;
+
+/// Size of the stack each goroutine runs on; pages are only committed once touched
+const GOROUTINE_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// What a suspended goroutine is waiting for
+pub(crate) struct Suspension {
+    promise_id: usize,
+    registry: Arc<Mutex<PromiseRegistry>>,
+    context: Option<Context>,
+}
+
+/// A goroutine's own stack, which it leaves whenever it suspends
+type GoroutineCoroutine = Coroutine<(), Suspension, Result<RuntimeValue>>;
+
+/// A suspended goroutine, parked on the worker that owns its stack
+struct SuspendedGoroutine {
+    goroutine: Goroutine,
+    coroutine: GoroutineCoroutine,
+    /// The waker that resumes it once its context is done
+    done_waker: Option<(Context, DoneWakerId)>,
+}
 
 // Thread-local storage for current goroutine context
 thread_local! {
     static CURRENT_GOROUTINE_ID: std::cell::RefCell<Option<GoroutineId>> = std::cell::RefCell::new(None);
+    static CURRENT_YIELDER: Cell<Option<*const Yielder<(), Suspension>>> = const { Cell::new(None) };
+}
+
+/// Whether the code on this thread runs on a goroutine stack that can be suspended
+pub fn can_suspend() -> bool {
+    CURRENT_YIELDER.with(|cell| cell.get().is_some())
+}
+
+/// Suspend the running goroutine until promise `promise_id` settles or its
+/// context is done, so its worker can run other goroutines meanwhile.
+/// Returns false without waiting when the caller is not a goroutine.
+pub fn suspend_until_settled(promise_id: usize, registry: &Arc<Mutex<PromiseRegistry>>) -> bool {
+    let Some(yielder) = CURRENT_YIELDER.with(|cell| cell.take()) else {
+        return false;
+    };
+    let context = super::context::current();
+    // Other goroutines run on this thread until the worker resumes this one
+    let scope = super::context::enter(None);
+    // SAFETY: the yielder belongs to the coroutine this code is running on,
+    // which outlives the call
+    unsafe { &*yielder }.suspend(Suspension {
+        promise_id,
+        registry: Arc::clone(registry),
+        context,
+    });
+    drop(scope);
+    CURRENT_YIELDER.with(|cell| cell.set(Some(yielder)));
+    true
 }
 
 /// Set the current goroutine ID for this thread
@@ -105,6 +160,8 @@ pub struct Goroutine {
     pub task: GoroutineTask,
     pub result: Option<RuntimeValue>,
     pub error: Option<String>,
+    /// The context of the code that spawned it
+    pub context: Option<Context>,
 }
 
 /// Task to be executed by a goroutine
@@ -137,6 +194,7 @@ impl Goroutine {
             task,
             result: None,
             error: None,
+            context: super::context::current(),
         }
     }
 }
@@ -491,7 +549,29 @@ impl GoroutineRuntime {
         //     std::thread::current().id()
         // );
 
+        // Goroutines suspended on this worker, and the ones whose promise has settled
+        let mut suspended: HashMap<GoroutineId, SuspendedGoroutine> = HashMap::new();
+        let woken: Arc<Mutex<Vec<GoroutineId>>> = Arc::new(Mutex::new(Vec::new()));
+
         while !shutdown.load(Ordering::Relaxed) {
+            // Resume the suspended goroutines that can make progress: their
+            // promise settled or their context is done
+            let ready = std::mem::take(&mut *woken.lock().unwrap());
+            for id in ready {
+                // Woken twice when both happened; the second wake finds it gone
+                if let Some(SuspendedGoroutine {
+                    goroutine,
+                    coroutine,
+                    done_waker,
+                }) = suspended.remove(&id)
+                {
+                    if let Some((context, waker)) = done_waker {
+                        context.remove_waker(waker);
+                    }
+                    Self::drive_goroutine(goroutine, coroutine, &mut suspended, &woken, &parked_queue, &condvar, &stats);
+                }
+            }
+
             // Try to get work in order of preference:
             // 1. Local queue
             // 2. Global queue
@@ -522,50 +602,34 @@ impl GoroutineRuntime {
                 }
             }
 
-            if let Some(mut g) = goroutine {
+            if let Some(g) = goroutine {
                 // println!(
                 //     "⚙️  WORKER {}: Found goroutine {} to execute",
                 //     worker_id, g.id
                 // );
 
-                // Execute the goroutine
-                g.state = GoroutineState::Running;
-
-                match Self::execute_goroutine(&mut g) {
-                    Ok(result) => {
-                        // Check if the goroutine should be parked (syscall in progress)
-                        if should_park_goroutine(&result) {
-                            // println!("🅿️  WORKER {}: Parking goroutine {} for I/O", worker_id, g.id);
-                            
-                            // Park the goroutine
-                            g.state = GoroutineState::Parked;
-                            let mut parked = parked_queue.lock().unwrap();
-                            parked.insert(g.id, g);
-                            
-                            // Update stats
-                            let mut stats = stats.lock().unwrap();
-                            stats.active_goroutines -= 1;
-                        } else {
-                            // Normal completion
-                            g.state = GoroutineState::Completed;
-                            g.result = Some(result);
-
-                            // Update stats
-                            let mut stats = stats.lock().unwrap();
-                            stats.active_goroutines -= 1;
-                            stats.completed_goroutines += 1;
-                        }
+                // Run the goroutine on its own stack so it can suspend on await
+                let id = g.id;
+                let task = g.task.clone();
+                let context = g.context.clone();
+                let coroutine = DefaultStack::new(GOROUTINE_STACK_SIZE).map(|stack| {
+                    Coroutine::with_stack(stack, move |yielder: &Yielder<(), Suspension>, ()| {
+                        let _context = super::context::enter(context);
+                        CURRENT_YIELDER.with(|cell| cell.set(Some(yielder as *const _)));
+                        let result = Self::execute_goroutine(id, &task);
+                        CURRENT_YIELDER.with(|cell| cell.set(None));
+                        result
+                    })
+                });
+                match coroutine {
+                    Ok(coroutine) => {
+                        Self::drive_goroutine(g, coroutine, &mut suspended, &woken, &parked_queue, &condvar, &stats)
                     }
-                    Err(e) => {
-                        g.state = GoroutineState::Panicked;
-                        g.error = Some(format!("{:?}", e));
-
-                        // Update stats
-                        let mut stats = stats.lock().unwrap();
-                        stats.active_goroutines -= 1;
-                        stats.panicked_goroutines += 1;
-
-                        eprintln!("Goroutine {} panicked: {:?}", g.id, e);
+                    // Without a stack of its own the goroutine blocks its worker on await
+                    Err(_) => {
+                        let _context = super::context::enter(g.context.clone());
+                        let result = Self::execute_goroutine(g.id, &g.task);
+                        Self::finish_goroutine(g, result, &parked_queue, &stats);
                     }
                 }
             } else {
@@ -579,19 +643,112 @@ impl GoroutineRuntime {
         println!("Goroutine worker {} shutting down", worker_id);
     }
 
+    /// Resume a goroutine until it finishes, or until it suspends on a promise.
+    /// A suspended goroutine is woken on this worker once the promise settles
+    /// or its context is done, whichever comes first.
+    fn drive_goroutine(
+        mut g: Goroutine,
+        mut coroutine: GoroutineCoroutine,
+        suspended: &mut HashMap<GoroutineId, SuspendedGoroutine>,
+        woken: &Arc<Mutex<Vec<GoroutineId>>>,
+        parked_queue: &Arc<Mutex<HashMap<GoroutineId, Goroutine>>>,
+        condvar: &Arc<Condvar>,
+        stats: &Arc<Mutex<RuntimeStats>>,
+    ) {
+        g.state = GoroutineState::Running;
+        set_current_goroutine_id(g.id);
+        match coroutine.resume(()) {
+            CoroutineResult::Yield(Suspension {
+                promise_id,
+                registry,
+                context,
+            }) => {
+                let id = g.id;
+                g.state = GoroutineState::Blocked;
+                let wake = || {
+                    let woken = Arc::clone(woken);
+                    let condvar = Arc::clone(condvar);
+                    move || {
+                        woken.lock().unwrap().push(id);
+                        condvar.notify_all();
+                    }
+                };
+                // Cancellation resumes it too; the awaiting code then fails with the context's error
+                let done_waker = context.and_then(|context| {
+                    let waker = context.on_done(Box::new(wake()))?;
+                    Some((context, waker))
+                });
+                suspended.insert(
+                    id,
+                    SuspendedGoroutine {
+                        goroutine: g,
+                        coroutine,
+                        done_waker,
+                    },
+                );
+                registry.lock().unwrap().on_settle(promise_id, Box::new(wake()));
+            }
+            CoroutineResult::Return(result) => Self::finish_goroutine(g, result, parked_queue, stats),
+        }
+    }
+
+    /// Record how a goroutine ended
+    fn finish_goroutine(
+        mut g: Goroutine,
+        result: Result<RuntimeValue>,
+        parked_queue: &Arc<Mutex<HashMap<GoroutineId, Goroutine>>>,
+        stats: &Arc<Mutex<RuntimeStats>>,
+    ) {
+        match result {
+            Ok(result) => {
+                // Check if the goroutine should be parked (syscall in progress)
+                if should_park_goroutine(&result) {
+                    // Park the goroutine
+                    g.state = GoroutineState::Parked;
+                    let mut parked = parked_queue.lock().unwrap();
+                    parked.insert(g.id, g);
+
+                    // Update stats
+                    let mut stats = stats.lock().unwrap();
+                    stats.active_goroutines -= 1;
+                } else {
+                    // Normal completion
+                    g.state = GoroutineState::Completed;
+                    g.result = Some(result);
+
+                    // Update stats
+                    let mut stats = stats.lock().unwrap();
+                    stats.active_goroutines -= 1;
+                    stats.completed_goroutines += 1;
+                }
+            }
+            Err(e) => {
+                g.state = GoroutineState::Panicked;
+                g.error = Some(format!("{:?}", e));
+
+                // Update stats
+                let mut stats = stats.lock().unwrap();
+                stats.active_goroutines -= 1;
+                stats.panicked_goroutines += 1;
+
+                eprintln!("Goroutine {} panicked: {:?}", g.id, e);
+            }
+        }
+    }
+
     /// Execute a single goroutine with better error handling
-    fn execute_goroutine(goroutine: &mut Goroutine) -> Result<RuntimeValue> {
+    fn execute_goroutine(id: GoroutineId, task: &GoroutineTask) -> Result<RuntimeValue> {
         // println!(
         //     "🔄 GOROUTINE {}: Starting execution on thread {:?}",
-        //     goroutine.id,
+        //     id,
         //     std::thread::current().id()
         // );
 
         // Set the goroutine context for this thread
-        set_current_goroutine_id(goroutine.id);
+        set_current_goroutine_id(id);
         crate::runtime::builtins::set_goroutine_context(true);
 
-        match &goroutine.task {
+        match task {
             GoroutineTask::Function {
                 name,
                 args,
@@ -601,7 +758,7 @@ impl GoroutineRuntime {
             } => {
                 // println!(
                 //     "🔄 GOROUTINE {}: Executing function '{}' with {} args",
-                //     goroutine.id,
+                //     id,
                 //     name,
                 //     args.len()
                 // );
@@ -618,14 +775,14 @@ impl GoroutineRuntime {
                 if let Some(function) = program.functions.iter().find(|f| f.name == *name) {
                     // println!(
                     //     "🔄 GOROUTINE {}: Found function '{}', executing...",
-                    //     goroutine.id, name
+                    //     id, name
                     // );
                     // Use the normal IR execution method
                     match interpreter.call_function(function, args.clone()) {
                         Ok(result) => {
                             // println!(
                             //     "🔄 GOROUTINE {}: Function '{}' completed successfully",
-                            //     goroutine.id, name
+                            //     id, name
                             // );
                             Ok(result)
                         }
@@ -633,7 +790,7 @@ impl GoroutineRuntime {
                             // Log the error but don't panic the goroutine
                             eprintln!(
                                 "❌ Goroutine {} function execution error: {:?}",
-                                goroutine.id, e
+                                id, e
                             );
                             Ok(RuntimeValue::Null)
                         }
@@ -641,7 +798,7 @@ impl GoroutineRuntime {
                 } else {
                     println!(
                         "🔄 GOROUTINE {}: Function '{}' not found, trying builtins",
-                        goroutine.id, name
+                        id, name
                     );
                     // Try built-in functions
                     match interpreter.call_builtin_function(name, args) {
@@ -649,7 +806,7 @@ impl GoroutineRuntime {
                         Err(e) => {
                             eprintln!(
                                 "❌ Goroutine {} builtin function error: {:?}",
-                                goroutine.id, e
+                                id, e
                             );
                             Ok(RuntimeValue::Null)
                         }
@@ -675,7 +832,7 @@ impl GoroutineRuntime {
                     Err(e) => {
                         eprintln!(
                             "Goroutine {} closure execution error: {:?}",
-                            goroutine.id, e
+                            id, e
                         );
                         Ok(RuntimeValue::Null)
                     }
@@ -690,7 +847,7 @@ impl GoroutineRuntime {
                         match interpreter.call_builtin_function(func_name, &[]) {
                             Ok(result) => Ok(result),
                             Err(e) => {
-                                eprintln!("Goroutine {} expression error: {:?}", goroutine.id, e);
+                                eprintln!("Goroutine {} expression error: {:?}", id, e);
                                 Ok(RuntimeValue::Null)
                             }
                         }
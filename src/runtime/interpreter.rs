@@ -8,7 +8,7 @@ use crate::compiler::ir::{
 };
 use crate::lexer::token::Position;
 use crate::runtime::builtins::BuiltinRegistry;
-use crate::types::primitive::RuntimeValue;
+use crate::types::primitive::{format_float32, format_float64, RuntimeValue};
 use crate::{BuluError, Result};
 use std::collections::HashMap;
 use std::fs;
@@ -161,6 +161,7 @@ pub struct Interpreter {
     pub environment: Environment,
     pub error_handler: MockErrorHandler,
     promise_registry: std::sync::Arc<std::sync::Mutex<crate::runtime::promises::PromiseRegistry>>,
+    promise_settled: std::sync::Arc<std::sync::Condvar>, // Signalled when another thread settles a promise
     async_context_stack: Vec<bool>, // Stack to track async contexts
     struct_definitions: HashMap<String, StructDefinition>,
     method_call_stack: Vec<String>, // Track method calls to prevent infinite recursion
@@ -258,6 +259,7 @@ impl Interpreter {
             promise_registry: std::sync::Arc::new(std::sync::Mutex::new(
                 crate::runtime::promises::PromiseRegistry::new(),
             )),
+            promise_settled: std::sync::Arc::new(std::sync::Condvar::new()),
             async_context_stack: Vec::new(),
             struct_definitions,
             method_call_stack: Vec::new(),
@@ -287,6 +289,7 @@ impl Interpreter {
             promise_registry: std::sync::Arc::new(std::sync::Mutex::new(
                 crate::runtime::promises::PromiseRegistry::new(),
             )),
+            promise_settled: std::sync::Arc::new(std::sync::Condvar::new()),
             async_context_stack: Vec::new(),
             struct_definitions,
             method_call_stack: Vec::new(),
@@ -311,6 +314,7 @@ impl Interpreter {
             promise_registry: std::sync::Arc::new(std::sync::Mutex::new(
                 crate::runtime::promises::PromiseRegistry::new(),
             )),
+            promise_settled: std::sync::Arc::new(std::sync::Condvar::new()),
             async_context_stack: Vec::new(),
             struct_definitions,
             method_call_stack: Vec::new(),
@@ -661,8 +665,8 @@ impl Interpreter {
             "toString" => {
                 // Convert the object to string - this is a built-in method
                 match object {
-                    RuntimeValue::Float64(f) => Ok(RuntimeValue::String(f.to_string())),
-                    RuntimeValue::Float32(f) => Ok(RuntimeValue::String(f.to_string())),
+                    RuntimeValue::Float64(f) => Ok(RuntimeValue::String(format_float64(f))),
+                    RuntimeValue::Float32(f) => Ok(RuntimeValue::String(format_float32(f))),
                     RuntimeValue::Int64(i) => Ok(RuntimeValue::String(i.to_string())),
                     RuntimeValue::Int32(i) => Ok(RuntimeValue::String(i.to_string())),
                     RuntimeValue::Bool(b) => Ok(RuntimeValue::String(b.to_string())),
@@ -671,8 +675,8 @@ impl Interpreter {
                         // For structs, try to convert the "value" field if it exists
                         if let Some(value_field) = fields.get("value") {
                             match value_field {
-                                RuntimeValue::Float64(f) => Ok(RuntimeValue::String(f.to_string())),
-                                RuntimeValue::Float32(f) => Ok(RuntimeValue::String(f.to_string())),
+                                RuntimeValue::Float64(f) => Ok(RuntimeValue::String(format_float64(*f))),
+                                RuntimeValue::Float32(f) => Ok(RuntimeValue::String(format_float32(*f))),
                                 RuntimeValue::Int64(i) => Ok(RuntimeValue::String(i.to_string())),
                                 RuntimeValue::Int32(i) => Ok(RuntimeValue::String(i.to_string())),
                                 RuntimeValue::Bool(b) => Ok(RuntimeValue::String(b.to_string())),
@@ -832,7 +836,7 @@ impl Interpreter {
                     "map" => {
                         // make(map, ...)
                         let map = std::collections::HashMap::new();
-                        Ok(RuntimeValue::Map(map))
+                        Ok(RuntimeValue::Map(map.into()))
                     }
                     "slice" => {
                         // make(slice, len, ...)
@@ -934,7 +938,7 @@ impl Interpreter {
             1 => {
                 // make(something) - assume it's a map
                 let map = std::collections::HashMap::new();
-                Ok(RuntimeValue::Map(map))
+                Ok(RuntimeValue::Map(map.into()))
             }
             2 => {
                 // make(type, size) - assume it's a slice
@@ -1205,6 +1209,7 @@ impl Interpreter {
             promise_registry: std::sync::Arc::new(std::sync::Mutex::new(
                 crate::runtime::promises::PromiseRegistry::new(),
             )),
+            promise_settled: std::sync::Arc::new(std::sync::Condvar::new()),
             async_context_stack: Vec::new(),
             struct_definitions: HashMap::new(),
             method_call_stack: Vec::new(),
@@ -1550,6 +1555,9 @@ impl Interpreter {
                 crate::ast::LiteralValue::Boolean(b) => Ok(RuntimeValue::Bool(*b)),
                 crate::ast::LiteralValue::Null => Ok(RuntimeValue::Null),
                 crate::ast::LiteralValue::Char(c) => Ok(RuntimeValue::String(c.to_string())),
+                crate::ast::LiteralValue::ByteString(bytes) => Ok(RuntimeValue::Slice(
+                    bytes.iter().map(|b| RuntimeValue::UInt8(*b)).collect(),
+                )),
             },
             crate::ast::Expression::Array(array_expr) => {
                 let mut elements = Vec::new();
@@ -1567,7 +1575,7 @@ impl Interpreter {
                     let key_str = key.to_string();
                     map.insert(key_str, value);
                 }
-                Ok(RuntimeValue::Map(map))
+                Ok(RuntimeValue::Map(map.into()))
             }
             crate::ast::Expression::Channel(channel_expr) => {
                 self.evaluate_channel_expression(channel_expr)
@@ -1633,7 +1641,7 @@ impl Interpreter {
             Type::Void => RuntimeValue::Null,
             Type::Array(_) => RuntimeValue::Array(Vec::new()),
             Type::Slice(_) => RuntimeValue::Slice(Vec::new()),
-            Type::Map(_) => RuntimeValue::Map(std::collections::HashMap::new()),
+            Type::Map(_) => RuntimeValue::Map(std::collections::HashMap::new().into()),
             _ => RuntimeValue::Null, // For complex types, default to null
         }
     }
@@ -1659,7 +1667,7 @@ impl Interpreter {
             IrType::Void => RuntimeValue::Null,
             IrType::Array(_, _) => RuntimeValue::Array(Vec::new()),
             IrType::Slice(_) => RuntimeValue::Slice(Vec::new()),
-            IrType::Map(_, _) => RuntimeValue::Map(std::collections::HashMap::new()),
+            IrType::Map(_, _) => RuntimeValue::Map(std::collections::HashMap::new().into()),
             _ => RuntimeValue::Null, // For complex types, default to null
         }
     }
@@ -1684,6 +1692,9 @@ impl Interpreter {
                         crate::ast::LiteralValue::Boolean(b) => RuntimeValue::Bool(*b),
                         crate::ast::LiteralValue::Null => RuntimeValue::Null,
                         crate::ast::LiteralValue::Char(c) => RuntimeValue::String(c.to_string()),
+                        crate::ast::LiteralValue::ByteString(bytes) => RuntimeValue::Slice(
+                            bytes.iter().map(|b| RuntimeValue::UInt8(*b)).collect(),
+                        ),
                     },
                     _ => {
                         // For complex expressions, store a string representation
@@ -2011,8 +2022,8 @@ impl Interpreter {
         match value {
             RuntimeValue::Int32(i) => i.to_string(),
             RuntimeValue::Int64(i) => i.to_string(),
-            RuntimeValue::Float32(f) => f.to_string(),
-            RuntimeValue::Float64(f) => f.to_string(),
+            RuntimeValue::Float32(f) => format_float32(*f),
+            RuntimeValue::Float64(f) => format_float64(*f),
             RuntimeValue::String(s) => s.clone(),
             RuntimeValue::Bool(b) => b.to_string(),
             RuntimeValue::Channel(id) => format!("chan#{}", id),
@@ -2056,6 +2067,11 @@ impl Interpreter {
                 }
             }
             RuntimeValue::Map(map) => format!("map[{}]", map.len()),
+            RuntimeValue::Set(set) => {
+                let elements: Vec<String> =
+                    set.iter().map(|v| self.runtime_value_to_string(v)).collect();
+                format!("set{{{}}}", elements.join(", "))
+            }
             _ => "null".to_string(),
         }
     }
@@ -2088,6 +2104,16 @@ impl Interpreter {
             }
         }
 
+        // Handle set methods; add and remove hand back the updated set
+        if let RuntimeValue::Set(set) = object {
+            if !crate::runtime::sets::is_mutating(method_name) {
+                return Ok((crate::runtime::sets::call_method(set, method_name, &args)?, None));
+            }
+            let mut set = set.clone();
+            let result = crate::runtime::sets::call_method_mut(&mut set, method_name, &args)?;
+            return Ok((result, Some(RuntimeValue::Set(set))));
+        }
+
         // Handle Result methods
         if let RuntimeValue::Struct { name, fields } = object {
             if name == "Result" {
@@ -2547,6 +2573,23 @@ impl Interpreter {
 
                             if let Some(user_function) = user_function {
                                 self.call_function(&user_function, args)?
+                            } else if let (Some(RuntimeValue::Set(set)), Some((_, method))) =
+                                (args.first(), function_name.rsplit_once('.'))
+                            {
+                                // Method call on a set: the receiver is the first argument
+                                if crate::runtime::sets::is_mutating(method) {
+                                    let mut set = set.clone();
+                                    let result =
+                                        crate::runtime::sets::call_method_mut(&mut set, method, &args[1..])?;
+                                    if let (IrValue::Register(reg), Some(frame)) =
+                                        (&instruction.operands[1], self.call_stack.last_mut())
+                                    {
+                                        frame.registers.insert(reg.id, RuntimeValue::Set(set));
+                                    }
+                                    result
+                                } else {
+                                    crate::runtime::sets::call_method(set, method, &args[1..])?
+                                }
                             } else {
                                 return Err(BuluError::Other(format!(
                                     "Unknown function: {}",
@@ -3179,6 +3222,10 @@ impl Interpreter {
                     (RuntimeValue::Bool(b1), RuntimeValue::Bool(b2)) => {
                         RuntimeValue::Bool(b1 == b2)
                     }
+                    (
+                        RuntimeValue::Array(a1) | RuntimeValue::Slice(a1),
+                        RuntimeValue::Array(a2) | RuntimeValue::Slice(a2),
+                    ) => RuntimeValue::Bool(a1 == a2),
                     _ => RuntimeValue::Bool(false),
                 };
 
@@ -3217,6 +3264,10 @@ impl Interpreter {
                     (RuntimeValue::Bool(b1), RuntimeValue::Bool(b2)) => {
                         RuntimeValue::Bool(b1 != b2)
                     }
+                    (
+                        RuntimeValue::Array(a1) | RuntimeValue::Slice(a1),
+                        RuntimeValue::Array(a2) | RuntimeValue::Slice(a2),
+                    ) => RuntimeValue::Bool(a1 != a2),
                     _ => RuntimeValue::Bool(true),
                 };
 
@@ -3598,6 +3649,20 @@ impl Interpreter {
                 }
             }
 
+            IrOpcode::SetConstruct => {
+                let mut elements = Vec::new();
+                for operand in &instruction.operands {
+                    elements.push(self.evaluate_value(operand)?);
+                }
+                let set_instance = RuntimeValue::Set(crate::runtime::sets::from_elements(elements)?);
+
+                if let Some(result_reg) = &instruction.result {
+                    if let Some(frame) = self.call_stack.last_mut() {
+                        frame.registers.insert(result_reg.id, set_instance);
+                    }
+                }
+            }
+
             IrOpcode::TupleAccess => {
                 if instruction.operands.len() != 2 {
                     return Err(BuluError::Other(
@@ -3784,6 +3849,17 @@ impl Interpreter {
                             }
                         }
                     }
+                    RuntimeValue::Set(_) => {
+                        let source_reg = match &instruction.operands[0] {
+                            IrValue::Register(reg) => Some(reg.id),
+                            _ => None,
+                        };
+                        RuntimeValue::MethodRef {
+                            object: Box::new(object),
+                            method_name: member_name.clone(),
+                            source_register: source_reg,
+                        }
+                    }
                     RuntimeValue::Map(map) => {
                         // Handle module access (e.g., os.args)
                         if let Some(value) = map.get(member_name) {
@@ -3881,6 +3957,7 @@ impl Interpreter {
                 let length = match array {
                     RuntimeValue::Array(ref arr) => arr.len() as i64,
                     RuntimeValue::Slice(ref slice) => slice.len() as i64,
+                    RuntimeValue::Set(ref set) => set.len() as i64,
                     RuntimeValue::String(ref s) => s.len() as i64,
                     RuntimeValue::Range(start, end, step) => {
                         // Calculate the length of the range
@@ -4049,7 +4126,7 @@ impl Interpreter {
                     RuntimeValue::Integer(i) => i.to_string(),
                     RuntimeValue::Int32(i) => i.to_string(),
                     RuntimeValue::Int64(i) => i.to_string(),
-                    RuntimeValue::Float64(f) => f.to_string(),
+                    RuntimeValue::Float64(f) => format_float64(f),
                     RuntimeValue::Bool(b) => b.to_string(),
                     _ => {
                         return Err(BuluError::Other(
@@ -4080,7 +4157,11 @@ impl Interpreter {
                     ));
                 }
 
-                let array = self.evaluate_value(&instruction.operands[0])?;
+                let array = match self.evaluate_value(&instruction.operands[0])? {
+                    // for-in over a set walks its elements in ascending order
+                    RuntimeValue::Set(elements) => RuntimeValue::Array(elements),
+                    other => other,
+                };
                 let index = self.evaluate_value(&instruction.operands[1])?;
 
                 // Special case: if array is a channel, receive from it instead of indexing
@@ -4491,6 +4572,63 @@ impl Interpreter {
                     }
                 }
             }
+            IrOpcode::CallAsync => {
+                // An awaited network method is handed to the netpoller reactor and
+                // yields a promise; every other call runs as a plain call. The
+                // method is either a `Type.method` global called with the receiver
+                // first, or a method reference bound to its receiver
+                let method_call = match instruction.operands.first() {
+                    Some(IrValue::Global(function_name)) if instruction.operands.len() > 1 => function_name
+                        .rsplit_once('.')
+                        .map(|(_, method_name)| (method_name.to_string(), Vec::new())),
+                    Some(callee @ IrValue::Register(_)) => match self.evaluate_value(callee)? {
+                        RuntimeValue::MethodRef {
+                            object,
+                            method_name,
+                            ..
+                        } => Some((method_name, vec![*object])),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                if let Some((method_name, mut args)) = method_call {
+                    for operand in &instruction.operands[1..] {
+                        args.push(self.evaluate_value(operand)?);
+                    }
+
+                    if let Some(op) = crate::runtime::builtins::async_net_op(&method_name, &args)? {
+                        let promise_id = self.promise_registry.lock().unwrap().create_promise();
+                        let promise_registry = self.promise_registry.clone();
+                        let promise_settled = self.promise_settled.clone();
+                        crate::runtime::async_executor::net_reactor().submit(
+                            op,
+                            Box::new(move |output| {
+                                let result = crate::runtime::builtins::net_output_to_result(output);
+                                if let Ok(mut registry) = promise_registry.lock() {
+                                    // Runs on the reactor thread, so there is no caller to report to
+                                    if let Err(error) = registry.resolve_promise(promise_id, result) {
+                                        tracing::warn!("Failed to resolve promise {}: {}", promise_id, error);
+                                    }
+                                }
+                                promise_settled.notify_all();
+                            }),
+                        );
+
+                        if let Some(result_reg) = &instruction.result {
+                            if let Some(frame) = self.call_stack.last_mut() {
+                                frame
+                                    .registers
+                                    .insert(result_reg.id, RuntimeValue::Promise(promise_id as u32));
+                            }
+                        }
+                        return Ok(());
+                    }
+                }
+
+                let mut call = instruction.clone();
+                call.opcode = IrOpcode::Call;
+                self.execute_instruction(&call)?;
+            }
             IrOpcode::Await => {
                 // await promise_expr
                 if instruction.operands.len() != 1 {
@@ -4528,9 +4666,24 @@ impl Interpreter {
                                         });
                                     }
                                     PromiseState::Pending => {
-                                        // Still pending, continue polling
-                                        drop(registry); // Release lock before sleeping
-                                        std::thread::sleep(std::time::Duration::from_millis(1));
+                                        // Still pending; a goroutine is suspended so its worker can
+                                        // run others, any other caller waits for the promise to be
+                                        // settled. Both wake up to notice a cancelled context
+                                        if crate::runtime::goroutine::can_suspend() {
+                                            drop(registry);
+                                            crate::runtime::goroutine::suspend_until_settled(
+                                                promise_id as usize,
+                                                &self.promise_registry,
+                                            );
+                                        } else {
+                                            let _ = self
+                                                .promise_settled
+                                                .wait_timeout(registry, crate::runtime::context::CANCEL_POLL_INTERVAL)
+                                                .unwrap();
+                                        }
+                                        if let Some(error) = crate::runtime::context::current().and_then(|ctx| ctx.err()) {
+                                            return Err(error.to_error());
+                                        }
                                         continue;
                                     }
                                 }
@@ -0,0 +1,375 @@
+//! Slot resolution for the local variables of a function
+//!
+//! Before a function first runs, its body is walked with the same scopes the
+//! AST interpreter creates: one for the parameters, one for each block, loop
+//! iteration and match arm. Every identifier bound inside the function is given
+//! the number of scopes between its use and its declaration, and its slot in
+//! that scope, so the interpreter reads it by index instead of hashing its name
+//! through every scope. Names stay on the slots for diagnostics and to check
+//! each indexed access.
+//!
+//! Closures are resolved the same way when they run, with the variables they
+//! captured bound ahead of their parameters. Lambda bodies are left out of the
+//! function that defines them, since they run as functions of their own.
+//!
+//! Uses the walk cannot pin down are left out and looked up by name: globals,
+//! captures a closure could not find when it was created, and any scope whose
+//! variables depend on which way the program runs (destructuring, `select`,
+//! loops that declare variables in the enclosing scope).
+
+use crate::ast::nodes::*;
+use crate::lexer::token::Position;
+use std::collections::{HashMap, HashSet};
+
+/// Scope distance and slot of the variable an identifier refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalSlot {
+    pub depth: usize,
+    pub slot: usize,
+}
+
+/// Resolved identifiers of one function, keyed by source position
+#[derive(Debug, Clone, Default)]
+pub struct LocalSlots {
+    slots: HashMap<(usize, usize), LocalSlot>,
+}
+
+impl LocalSlots {
+    /// The variable read or assigned by the identifier at `position`
+    pub fn get(&self, position: Position) -> Option<LocalSlot> {
+        self.slots.get(&(position.line, position.column)).copied()
+    }
+
+    /// Number of resolved identifiers
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+}
+
+/// Resolve the locals of a function called with all of its parameters bound
+pub fn resolve_locals(function: &FunctionDecl) -> LocalSlots {
+    resolve_closure_locals(function, &[])
+}
+
+/// Resolve the locals of a closure whose captured variables are bound, in
+/// order, before its parameters
+pub fn resolve_closure_locals(function: &FunctionDecl, captures: &[String]) -> LocalSlots {
+    let mut resolver = LocalResolver::default();
+    resolver.push();
+    for capture in captures {
+        resolver.define(capture);
+    }
+    for param in &function.params {
+        resolver.define(&param.name);
+    }
+    resolver.block(&function.body.statements);
+    resolver.slots
+}
+
+#[derive(Debug, Default)]
+struct Scope {
+    names: HashMap<String, usize>,
+    /// Names a loop body may already have declared here in an earlier iteration
+    pending: HashSet<String>,
+    /// Whether the slots of this scope are known; when they are not, nothing
+    /// in or beyond it is resolved
+    exact: bool,
+}
+
+#[derive(Debug, Default)]
+struct LocalResolver {
+    scopes: Vec<Scope>,
+    slots: LocalSlots,
+}
+
+impl LocalResolver {
+    fn push(&mut self) {
+        self.scopes.push(Scope {
+            exact: true,
+            ..Scope::default()
+        });
+    }
+
+    fn pop(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn current(&mut self) -> &mut Scope {
+        self.scopes.last_mut().expect("function scope")
+    }
+
+    fn define(&mut self, name: &str) {
+        let scope = self.current();
+        let next = scope.names.len();
+        scope.names.entry(name.to_string()).or_insert(next);
+    }
+
+    /// Forget the slot layout of the current scope
+    fn invalidate(&mut self) {
+        self.current().exact = false;
+    }
+
+    fn resolve(&mut self, name: &str, position: Position) {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if !scope.exact {
+                return;
+            }
+            if let Some(&slot) = scope.names.get(name) {
+                self.slots
+                    .slots
+                    .insert((position.line, position.column), LocalSlot { depth, slot });
+                return;
+            }
+            if scope.pending.contains(name) {
+                return;
+            }
+        }
+    }
+
+    /// Statements run in a new scope
+    fn block(&mut self, statements: &[Statement]) {
+        self.push();
+        self.statements(statements);
+        self.pop();
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::VariableDecl(decl) => {
+                if let Some(initializer) = &decl.initializer {
+                    self.expression(initializer);
+                }
+                self.define(&decl.name);
+            }
+            Statement::MultipleVariableDecl(decl) => {
+                for declaration in &decl.declarations {
+                    if let Some(initializer) = &declaration.initializer {
+                        self.expression(initializer);
+                    }
+                    self.define(&declaration.name);
+                }
+            }
+            Statement::DestructuringDecl(decl) => {
+                self.expression(&decl.initializer);
+                self.invalidate();
+            }
+            Statement::FunctionDecl(decl) => self.define(&decl.name),
+            Statement::StructDecl(decl) => self.define(&decl.name),
+            Statement::InterfaceDecl(decl) => self.define(&decl.name),
+            Statement::TypeAlias(decl) => self.define(&decl.name),
+            Statement::While(stmt) => self.while_loop(stmt),
+            Statement::For(stmt) => {
+                self.expression(&stmt.iterable);
+                self.push();
+                if let Some(index) = &stmt.index_variable {
+                    self.define(index);
+                }
+                self.define(&stmt.variable);
+                self.block(&stmt.body.statements);
+                self.pop();
+            }
+            Statement::Match(stmt) => {
+                self.expression(&stmt.expr);
+                for arm in &stmt.arms {
+                    self.push();
+                    self.pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.expression(guard);
+                    }
+                    self.statement(&arm.body);
+                    self.pop();
+                }
+            }
+            Statement::Return(stmt) => {
+                if let Some(value) = &stmt.value {
+                    self.expression(value);
+                }
+            }
+            Statement::Fail(stmt) => self.expression(&stmt.message),
+            Statement::Expression(stmt) => self.expression(&stmt.expr),
+            Statement::Block(stmt) => self.block(&stmt.statements),
+            Statement::MultipleAssignment(stmt) => {
+                for value in &stmt.values {
+                    self.expression(value);
+                }
+                for target in &stmt.targets {
+                    self.expression(target);
+                }
+            }
+            // Imports, exports and `select` may bind names the walk cannot predict
+            Statement::Import(_) | Statement::Export(_) | Statement::Select(_) => self.invalidate(),
+            Statement::If(_)
+            | Statement::Break(_)
+            | Statement::Continue(_)
+            | Statement::Defer(_)
+            | Statement::Try(_) => {}
+        }
+    }
+
+    /// A `while` body runs in the enclosing scope, so a variable it declares may
+    /// already exist when an earlier statement of the body runs again
+    fn while_loop(&mut self, stmt: &WhileStmt) {
+        let mut declared = Vec::new();
+        if !loop_declarations(&stmt.body.statements, &mut declared) {
+            self.invalidate();
+        }
+        let declares = !declared.is_empty();
+        self.current().pending.extend(declared);
+
+        self.expression(&stmt.condition);
+        self.statements(&stmt.body.statements);
+
+        // Whether the body ran, and how far, decides which slots exist afterwards
+        if declares {
+            self.invalidate();
+        }
+    }
+
+    /// Bind the variables of a match arm pattern
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Wildcard(_) | Pattern::Literal(..) | Pattern::Range(_) => {}
+            Pattern::Identifier(name, _) => self.define(name),
+            Pattern::Binding(binding) => {
+                self.define(&binding.name);
+                self.pattern(&binding.pattern);
+            }
+            Pattern::Type(type_pattern) => {
+                if let Some(name) = &type_pattern.name {
+                    self.define(name);
+                }
+            }
+            Pattern::Struct(_) | Pattern::Array(_) | Pattern::Tuple(_) | Pattern::Or(_) => self.invalidate(),
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Identifier(ident) => self.resolve(&ident.name, ident.position),
+            Expression::Literal(_) => {}
+            Expression::Binary(binary) => {
+                self.expression(&binary.left);
+                self.expression(&binary.right);
+            }
+            Expression::Unary(unary) => self.expression(&unary.operand),
+            Expression::Call(call) => {
+                self.expression(&call.callee);
+                for arg in &call.args {
+                    self.expression(arg);
+                }
+            }
+            Expression::MemberAccess(access) => self.expression(&access.object),
+            Expression::Index(index) => {
+                self.expression(&index.object);
+                self.expression(&index.index);
+            }
+            Expression::Assignment(assignment) => {
+                self.expression(&assignment.value);
+                self.expression(&assignment.target);
+            }
+            Expression::Match(expr) => {
+                self.expression(&expr.expr);
+                for arm in &expr.arms {
+                    self.push();
+                    self.pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.expression(guard);
+                    }
+                    self.expression(&arm.expr);
+                    self.pop();
+                }
+            }
+            Expression::Array(array) => {
+                for element in &array.elements {
+                    self.expression(element);
+                }
+            }
+            Expression::Set(set) => {
+                for element in &set.elements {
+                    self.expression(element);
+                }
+            }
+            Expression::Tuple(tuple) => {
+                for element in &tuple.elements {
+                    self.expression(element);
+                }
+            }
+            Expression::Map(map) => {
+                for entry in &map.entries {
+                    self.expression(&entry.key);
+                    self.expression(&entry.value);
+                }
+            }
+            Expression::StructLiteral(literal) => {
+                for field in &literal.fields {
+                    self.expression(&field.value);
+                }
+            }
+            Expression::Async(expr) => self.expression(&expr.expr),
+            Expression::Await(expr) => self.expression(&expr.expr),
+            Expression::Propagate(expr) => self.expression(&expr.expr),
+            Expression::Cast(expr) => self.expression(&expr.expr),
+            Expression::TypeOf(expr) => self.expression(&expr.expr),
+            Expression::Parenthesized(expr) => self.expression(&expr.expr),
+            Expression::Channel(expr) => {
+                self.expression(&expr.channel);
+                if let Some(value) = &expr.value {
+                    self.expression(value);
+                }
+            }
+            Expression::Range(range) => {
+                self.expression(&range.start);
+                self.expression(&range.end);
+                if let Some(step) = &range.step {
+                    self.expression(step);
+                }
+            }
+            Expression::Yield(expr) => {
+                if let Some(value) = &expr.value {
+                    self.expression(value);
+                }
+            }
+            // Goroutines run on a copy of the environment; lambda bodies run as
+            // functions of their own
+            Expression::Run(_) | Expression::Lambda(_) => {}
+            Expression::Select(_) => self.invalidate(),
+            Expression::If(_) | Expression::Block(_) => {}
+        }
+    }
+}
+
+/// Collect the names a loop body declares in the scope it runs in, including
+/// the bodies of nested `while` loops. Returns false when the body may also
+/// declare names the walk cannot predict.
+fn loop_declarations(statements: &[Statement], names: &mut Vec<String>) -> bool {
+    let mut predictable = true;
+    for statement in statements {
+        match statement {
+            Statement::VariableDecl(decl) => names.push(decl.name.clone()),
+            Statement::MultipleVariableDecl(decl) => {
+                names.extend(decl.declarations.iter().map(|declaration| declaration.name.clone()))
+            }
+            Statement::FunctionDecl(decl) => names.push(decl.name.clone()),
+            Statement::StructDecl(decl) => names.push(decl.name.clone()),
+            Statement::InterfaceDecl(decl) => names.push(decl.name.clone()),
+            Statement::TypeAlias(decl) => names.push(decl.name.clone()),
+            Statement::While(stmt) => predictable &= loop_declarations(&stmt.body.statements, names),
+            Statement::DestructuringDecl(_)
+            | Statement::Import(_)
+            | Statement::Export(_)
+            | Statement::Select(_) => predictable = false,
+            _ => {}
+        }
+    }
+    predictable
+}
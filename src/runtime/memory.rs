@@ -5,9 +5,11 @@
 //! - Escape analysis integration
 //! - Memory layout optimization
 //! - Integration with garbage collector
+//! - Allocation profiling per Bulu call stack
 
 use crate::runtime::gc::{GarbageCollector, ObjectId};
 use crate::runtime::safety::{SafetyChecker, SafetyResult};
+use crate::types::primitive::RuntimeValue;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
@@ -378,6 +380,114 @@ pub struct MemoryStats {
     pub gc_stats: crate::runtime::gc::GcStats,
 }
 
+/// Allocation totals for one call site
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocationSite {
+    /// Number of allocations
+    pub count: u64,
+    /// Estimated bytes allocated
+    pub bytes: u64,
+}
+
+impl AllocationSite {
+    fn add(&mut self, other: AllocationSite) {
+        self.count += other.count;
+        self.bytes += other.bytes;
+    }
+}
+
+/// Heap profile of a program run: allocations per call site, where a call
+/// site is the stack of Bulu functions plus the source line that allocated
+#[derive(Debug, Clone, Default)]
+pub struct HeapProfile {
+    sites: HashMap<(Vec<String>, usize), AllocationSite>,
+}
+
+impl HeapProfile {
+    /// Create an empty heap profile
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an allocation of `bytes` at `line`, made with `frames` on the call stack
+    /// (outermost first)
+    pub fn record(&mut self, frames: &[String], line: usize, bytes: usize) {
+        let site = AllocationSite {
+            count: 1,
+            bytes: bytes as u64,
+        };
+        self.sites
+            .entry((frames.to_vec(), line))
+            .or_default()
+            .add(site);
+    }
+
+    /// Totals over all call sites
+    pub fn total(&self) -> AllocationSite {
+        let mut total = AllocationSite::default();
+        for site in self.sites.values() {
+            total.add(*site);
+        }
+        total
+    }
+
+    /// Totals per allocating function (the innermost frame), most bytes first
+    pub fn by_function(&self) -> Vec<(String, AllocationSite)> {
+        let mut functions: HashMap<String, AllocationSite> = HashMap::new();
+        for ((frames, _), site) in &self.sites {
+            let function = frames.last().cloned().unwrap_or_else(|| "<top level>".to_string());
+            functions.entry(function).or_default().add(*site);
+        }
+        let mut functions: Vec<_> = functions.into_iter().collect();
+        functions.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then_with(|| a.0.cmp(&b.0)));
+        functions
+    }
+
+    /// The profile in the folded stack format flamegraph tools read: one
+    /// `main;build;build:12 480` line per call site, weighted by bytes
+    pub fn folded(&self) -> String {
+        let mut lines: Vec<String> = self
+            .sites
+            .iter()
+            .map(|((frames, line), site)| {
+                let mut stack = if frames.is_empty() {
+                    vec!["<top level>".to_string()]
+                } else {
+                    frames.clone()
+                };
+                let leaf = format!("{}:{}", stack[stack.len() - 1], line);
+                stack.push(leaf);
+                format!("{} {}", stack.join(";"), site.bytes)
+            })
+            .collect();
+        lines.sort();
+        let mut report = lines.join("\n");
+        report.push('\n');
+        report
+    }
+}
+
+/// Estimated bytes a new value allocates itself. Elements of containers are
+/// counted as slots only, since nested values are allocated (and recorded)
+/// by their own expressions.
+pub fn estimated_size(value: &RuntimeValue) -> usize {
+    let slot = std::mem::size_of::<RuntimeValue>();
+    match value {
+        RuntimeValue::String(text) => slot + text.len(),
+        RuntimeValue::Array(items) | RuntimeValue::Slice(items) | RuntimeValue::Tuple(items) => {
+            slot + items.len() * slot
+        }
+        RuntimeValue::Set(items) => slot + items.len() * slot,
+        RuntimeValue::Map(entries) => {
+            slot + entries.keys().map(|key| key.len() + 2 * slot).sum::<usize>()
+        }
+        RuntimeValue::Struct { name, fields } => {
+            slot + name.len() + fields.keys().map(|field| field.len() + 2 * slot).sum::<usize>()
+        }
+        _ => slot,
+    }
+}
+
 /// Initialize default type layouts
 pub fn init_default_type_layouts(memory_manager: &mut MemoryManager) {
     use crate::types::primitive::PrimitiveType;
@@ -501,4 +611,18 @@ mod tests {
         assert_eq!(layout.strategy, AllocStrategy::Heap);
         assert!(layout.contains_references);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_heap_profile_folds_call_sites() {
+        let mut profile = HeapProfile::new();
+        let stack = vec!["main".to_string(), "build".to_string()];
+        profile.record(&stack, 12, 100);
+        profile.record(&stack, 12, 50);
+        profile.record(&stack[..1], 3, 10);
+
+        assert_eq!(profile.total(), AllocationSite { count: 3, bytes: 160 });
+        assert_eq!(profile.folded(), "main;build;build:12 150\nmain;main:3 10\n");
+        let functions = profile.by_function();
+        assert_eq!(functions[0], ("build".to_string(), AllocationSite { count: 2, bytes: 150 }));
+    }
+}
@@ -13,6 +13,12 @@ pub mod async_executor;
 pub mod syscall_thread;
 pub mod builtins;
 pub mod memory;
+pub mod profiler;
+pub mod race;
+pub mod stack;
+pub mod shutdown;
+pub mod timers;
+pub mod output;
 pub mod error_handler;
 pub mod channels;
 pub mod sync;
@@ -22,6 +28,15 @@ pub mod safe_collections;
 pub mod interpreter;
 pub mod module;
 pub mod ast_interpreter;
+pub mod pool;
+pub mod locals;
+pub mod vtable;
+pub mod simplify;
+pub mod pattern_cache;
+pub mod context;
+pub mod sets;
+pub mod bytes;
+pub mod collections;
 
 #[cfg(test)]
 mod test_import_export;
@@ -38,4 +53,5 @@ pub use safe_collections::{SafeArray, SafeSlice, SafeSliceMut, SafeString};
 pub use interpreter::Interpreter;
 pub use crate::types::primitive::RuntimeValue;
 pub use module::{ModuleResolver, Module};
-pub use ast_interpreter::{AstInterpreter, Environment};
\ No newline at end of file
+pub use ast_interpreter::{AstInterpreter, Environment};
+pub use pool::{InterpreterPool, PooledInterpreter, PoolStats};
\ No newline at end of file
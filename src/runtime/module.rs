@@ -11,6 +11,7 @@ use crate::types::primitive::RuntimeValue;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use tracing::debug;
 
 /// Source information for error reporting
 #[derive(Debug, Clone)]
@@ -59,6 +60,8 @@ pub struct ModuleResolver {
     memory_modules: HashMap<String, String>,
     /// Current working directory for relative imports
     current_dir: PathBuf,
+    /// Directories searched after the current one
+    search_paths: Vec<PathBuf>,
 }
 
 impl ModuleResolver {
@@ -69,6 +72,7 @@ impl ModuleResolver {
             std_modules: HashMap::new(),
             memory_modules: HashMap::new(),
             current_dir: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            search_paths: Vec::new(),
         };
 
         // Initialize standard library modules
@@ -79,14 +83,23 @@ impl ModuleResolver {
     /// Initialize standard library modules
     fn init_std_modules(&mut self) {
         // Create mock standard library modules for now
-        let std_modules = vec![
-            "io", "fmt", "strings", "arrays", "math", "time", "sync", "os", "path", "http", "net",
-            "json", "xml", "csv", "crypto", "db", "test", "random", "flag",
+        let mut std_modules = vec![
+            "io", "os", "path", "net", "xml", "csv", "test", "random", "flag", "builtin",
         ];
+        std_modules.extend(crate::std::FUNCTION_MODULES.iter().map(|(name, _)| *name));
 
         for module_name in std_modules {
             let mut exports = HashMap::new();
 
+            // Modules of the standard library export their functions
+            let functions = crate::std::module_functions(module_name);
+            for name in functions.into_iter().flatten() {
+                exports.insert(
+                    name.to_string(),
+                    RuntimeValue::String(format!("function:{}.{}", module_name, name)),
+                );
+            }
+
             // Add some basic exports based on module name
             match module_name {
                 "io" => {
@@ -109,12 +122,15 @@ impl ModuleResolver {
                     exports.insert("len".to_string(), RuntimeValue::Null);
                     exports.insert("copy".to_string(), RuntimeValue::Null);
                 }
-                "math" => {
-                    exports.insert("abs".to_string(), RuntimeValue::Null);
-                    exports.insert("sqrt".to_string(), RuntimeValue::Null);
-                    exports.insert("pow".to_string(), RuntimeValue::Null);
-                    exports.insert("sin".to_string(), RuntimeValue::Null);
-                    exports.insert("cos".to_string(), RuntimeValue::Null);
+                "builtin" => {
+                    // The interpreter provides builtins itself, so these only tell
+                    // the resolver and checker which names the module has
+                    for name in crate::std::builtin::EXPORTED_FUNCTIONS {
+                        exports.insert(name.to_string(), RuntimeValue::String(format!("function:{}", name)));
+                    }
+                    for name in crate::std::builtin::EXPORTED_VALUES {
+                        exports.insert(name.to_string(), RuntimeValue::Null);
+                    }
                 }
                 "net" => {
                     exports.insert(
@@ -138,10 +154,12 @@ impl ModuleResolver {
                     exports.insert("sleep".to_string(), RuntimeValue::Null);
                 }
                 "os" => {
-                    exports.insert("args".to_string(), RuntimeValue::Null);
-                    exports.insert("getEnv".to_string(), RuntimeValue::Null);
-                    exports.insert("cwd".to_string(), RuntimeValue::Null);
-                    exports.insert("exit".to_string(), RuntimeValue::Null);
+                    for name in crate::std::os::exported_functions() {
+                        exports.insert(
+                            name.to_string(),
+                            RuntimeValue::String(format!("function:os.{}", name)),
+                        );
+                    }
                 }
                 "flag" => {
                     exports.insert(
@@ -217,15 +235,17 @@ impl ModuleResolver {
                         RuntimeValue::String("function:Usage".to_string()),
                     );
                 }
-                _ => {
+                _ if functions.is_none() => {
                     // Add a generic export for other modules
                     exports.insert("default".to_string(), RuntimeValue::Null);
                 }
+                _ => {}
             }
 
             // Create a dummy AST for std modules
             let ast = Program {
                 statements: vec![],
+                attributes: vec![],
                 position: crate::lexer::token::Position::new(0, 0, 0),
             };
 
@@ -290,7 +310,7 @@ impl ModuleResolver {
             }
         } else if let Some(alias) = &import.alias {
             // Import entire module with alias: import "path" as alias
-            let module_object = RuntimeValue::Map(module.exports.clone());
+            let module_object = RuntimeValue::Map(module.exports.clone().into());
             imported_symbols.insert(alias.clone(), module_object);
             // For aliased imports, we don't copy function definitions directly
         } else {
@@ -332,9 +352,16 @@ impl ModuleResolver {
         }
     }
 
+    /// File a loaded module was read from, or its import path for modules
+    /// held in memory
+    pub fn module_file(&self, path: &str) -> Option<String> {
+        let module = self.modules.get(path)?;
+        Some(module.source_info.file_path.clone().unwrap_or_else(|| path.to_string()))
+    }
+
     /// Load a module from the given path
     pub fn load_module(&mut self, path: &str) -> Result<Module> {
-        eprintln!("🔄 Loading module: {}", path);
+        debug!("Loading module: {}", path);
         // Check if module is already loaded
         if let Some(module) = self.modules.get(path) {
             eprintln!("  ✓ Already loaded");
@@ -423,14 +450,14 @@ impl ModuleResolver {
             interpreter: Some(interpreter_wrapper),
         };
 
-        eprintln!("  ✓ Loaded and cached as: {}", path);
+        debug!("Loaded and cached as: {}", path);
         self.modules.insert(path.to_string(), module.clone());
         Ok(module)
     }
 
     /// Load a module from the given path with context of the current file
     pub fn load_module_from(&mut self, path: &str, current_file: Option<&Path>) -> Result<Module> {
-        eprintln!("🔄 Loading module: {}", path);
+        debug!("Loading module: {}", path);
         // Check if module is already loaded
         if let Some(module) = self.modules.get(path) {
             eprintln!("  ✓ Already loaded");
@@ -521,7 +548,7 @@ impl ModuleResolver {
             interpreter: Some(interpreter_wrapper),
         };
 
-        eprintln!("  ✓ Loaded and cached as: {}", path);
+        debug!("Loaded and cached as: {}", path);
         self.modules.insert(path.to_string(), module.clone());
         Ok(module)
     }
@@ -533,6 +560,9 @@ impl ModuleResolver {
 
         let mut resolver = ResolverModuleResolver::new();
         resolver.add_search_path(self.current_dir.clone());
+        for search_path in &self.search_paths {
+            resolver.add_search_path(search_path.clone());
+        }
 
         // Try to resolve using the proper module resolver with current_file context
         resolver.resolve_module_path(path, current_file)
@@ -764,6 +794,9 @@ impl ModuleResolver {
                 crate::ast::LiteralValue::String(s) => RuntimeValue::String(s.clone()),
                 crate::ast::LiteralValue::Boolean(b) => RuntimeValue::Bool(*b),
                 crate::ast::LiteralValue::Char(c) => RuntimeValue::Char(*c),
+                crate::ast::LiteralValue::ByteString(bytes) => RuntimeValue::Slice(
+                    bytes.iter().map(|b| RuntimeValue::UInt8(*b)).collect(),
+                ),
                 crate::ast::LiteralValue::Null => RuntimeValue::Null,
             },
             _ => RuntimeValue::Null, // For non-literal expressions, return null for now
@@ -797,6 +830,20 @@ impl ModuleResolver {
         self.current_dir = dir;
     }
 
+    /// Also look for modules in `dir`, after the current directory
+    pub fn add_search_path(&mut self, dir: PathBuf) {
+        self.search_paths.push(dir);
+    }
+
+    /// Forget the loaded modules, in-memory modules and directories, keeping
+    /// the standard library modules
+    pub fn reset(&mut self) {
+        self.modules.clear();
+        self.memory_modules.clear();
+        self.current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        self.search_paths.clear();
+    }
+
     /// Add an in-memory module for testing
     pub fn add_memory_module(&mut self, path: String, source: String) {
         self.memory_modules.insert(path, source);
@@ -804,9 +851,9 @@ impl ModuleResolver {
 
     /// Get all loaded modules for compilation
     pub fn get_loaded_modules(&self) -> Vec<&Module> {
-        eprintln!("🔍 ModuleResolver has {} modules in cache", self.modules.len());
+        debug!("ModuleResolver has {} modules in cache", self.modules.len());
         for (path, _) in &self.modules {
-            eprintln!("    - {}", path);
+            debug!("    - {}", path);
         }
         self.modules.values().collect()
     }
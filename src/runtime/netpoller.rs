@@ -64,11 +64,6 @@ impl NetPoller {
 
     /// Register a file descriptor for polling
     pub fn register(&self, fd: RawFd, goroutine_id: u64, event: PollEvent) -> std::io::Result<()> {
-        println!(
-            "🔌 NETPOLLER: Registering fd {} for goroutine {} with event {:?}",
-            fd, goroutine_id, event
-        );
-
         let mut waiting = self.waiting.lock().unwrap();
         let entry = waiting.entry(fd).or_insert_with(Vec::new);
         entry.push(WaitingGoroutine {
@@ -123,11 +118,6 @@ impl NetPoller {
 
     /// Unregister a file descriptor
     pub fn unregister(&self, fd: RawFd, goroutine_id: u64) -> std::io::Result<()> {
-        println!(
-            "🔌 NETPOLLER: Unregistering fd {} for goroutine {}",
-            fd, goroutine_id
-        );
-
         let mut waiting = self.waiting.lock().unwrap();
         if let Some(goroutines) = waiting.get_mut(&fd) {
             goroutines.retain(|g| g.goroutine_id != goroutine_id);
@@ -179,10 +169,6 @@ impl NetPoller {
                 let fd = events[i].u64 as RawFd;
                 if let Some(goroutines) = waiting.get(&fd) {
                     for g in goroutines {
-                        println!(
-                            "🔌 NETPOLLER: fd {} is ready, waking goroutine {}",
-                            fd, g.goroutine_id
-                        );
                         ready_goroutines.push(g.goroutine_id);
                     }
                 }
@@ -237,10 +223,6 @@ impl NetPoller {
                 if pfd.revents != 0 {
                     if let Some(goroutines) = waiting.get(&pfd.fd) {
                         for g in goroutines {
-                            println!(
-                                "🔌 NETPOLLER: fd {} is ready, waking goroutine {}",
-                                pfd.fd, g.goroutine_id
-                            );
                             ready_goroutines.push(g.goroutine_id);
                         }
                     }
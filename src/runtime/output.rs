@@ -0,0 +1,248 @@
+//! Destinations of program output
+//!
+//! `print`, `println`, `printf` and the std/io functions write through here.
+//! Output goes to the sinks of the interpreter running on the current thread,
+//! then to the process-wide sinks, and otherwise to the host's stdout and
+//! stderr. Embedders use this to capture what a program prints.
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+/// A shared writer program output can be sent to
+pub type Sink = Arc<Mutex<Box<dyn Write + Send>>>;
+
+/// Make a sink of a writer
+pub fn sink(writer: Box<dyn Write + Send>) -> Sink {
+    Arc::new(Mutex::new(writer))
+}
+
+/// An output stream of a program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl Stream {
+    fn name(self) -> &'static str {
+        match self {
+            Stream::Stdout => "stdout",
+            Stream::Stderr => "stderr",
+        }
+    }
+}
+
+/// Where stdout and stderr go; a stream without a sink falls through to the
+/// next level
+#[derive(Clone, Default)]
+pub struct OutputSinks {
+    pub stdout: Option<Sink>,
+    pub stderr: Option<Sink>,
+}
+
+impl OutputSinks {
+    /// The sink of a stream
+    pub fn get(&self, stream: Stream) -> Option<&Sink> {
+        match stream {
+            Stream::Stdout => self.stdout.as_ref(),
+            Stream::Stderr => self.stderr.as_ref(),
+        }
+    }
+
+    /// Whether neither stream is redirected
+    pub fn is_empty(&self) -> bool {
+        self.stdout.is_none() && self.stderr.is_none()
+    }
+}
+
+impl std::fmt::Debug for OutputSinks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutputSinks")
+            .field("stdout", &self.stdout.is_some())
+            .field("stderr", &self.stderr.is_some())
+            .finish()
+    }
+}
+
+static PROCESS: Mutex<OutputSinks> = Mutex::new(OutputSinks {
+    stdout: None,
+    stderr: None,
+});
+
+thread_local! {
+    static CURRENT: RefCell<OutputSinks> = RefCell::new(OutputSinks::default());
+}
+
+/// Send the stdout of every program in the process to `writer`
+pub fn set_process_stdout(writer: Box<dyn Write + Send>) {
+    PROCESS.lock().unwrap().stdout = Some(sink(writer));
+}
+
+/// Send the stderr of every program in the process to `writer`
+pub fn set_process_stderr(writer: Box<dyn Write + Send>) {
+    PROCESS.lock().unwrap().stderr = Some(sink(writer));
+}
+
+/// Send program output back to the host's stdout and stderr
+pub fn reset_process_output() {
+    *PROCESS.lock().unwrap() = OutputSinks::default();
+}
+
+/// Restores the sinks of the current thread when dropped
+pub struct OutputGuard {
+    previous: Option<OutputSinks>,
+}
+
+impl Drop for OutputGuard {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            CURRENT.with(|current| *current.borrow_mut() = previous);
+        }
+    }
+}
+
+/// Use `sinks` for output on this thread until the guard is dropped. Streams
+/// `sinks` does not redirect keep their current destination.
+pub fn enter(sinks: &OutputSinks) -> OutputGuard {
+    if sinks.is_empty() {
+        return OutputGuard { previous: None };
+    }
+    CURRENT.with(|current| {
+        let mut current = current.borrow_mut();
+        let previous = current.clone();
+        if let Some(stdout) = &sinks.stdout {
+            current.stdout = Some(stdout.clone());
+        }
+        if let Some(stderr) = &sinks.stderr {
+            current.stderr = Some(stderr.clone());
+        }
+        OutputGuard { previous: Some(previous) }
+    })
+}
+
+/// The sinks entered on this thread, for threads it starts to enter in turn
+pub fn current() -> OutputSinks {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Write program output to the sink `stream` currently goes to
+pub fn write(stream: Stream, text: &str) -> io::Result<()> {
+    let sink = CURRENT
+        .with(|current| current.borrow().get(stream).cloned())
+        .or_else(|| PROCESS.lock().unwrap().get(stream).cloned());
+    match sink {
+        Some(sink) => write_to(&sink, text),
+        // The print macros keep output visible to the test harness's capture
+        None => match stream {
+            Stream::Stdout => {
+                print!("{}", text);
+                io::stdout().flush()
+            }
+            Stream::Stderr => {
+                eprint!("{}", text);
+                io::stderr().flush()
+            }
+        },
+    }
+}
+
+/// Write text to a sink and flush it
+pub fn write_to(sink: &Sink, text: &str) -> io::Result<()> {
+    let mut writer = sink.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    writer.write_all(text.as_bytes())?;
+    writer.flush()
+}
+
+/// The runtime error for a failed write to `stream`
+pub fn write_error(stream: Stream, error: io::Error) -> crate::error::BuluError {
+    crate::error::BuluError::RuntimeError {
+        file: None,
+        message: format!("Failed to write to {}: {}", stream.name(), error),
+    }
+}
+
+/// A writer collecting output in memory, read back with `contents`
+#[derive(Debug, Clone, Default)]
+pub struct Capture {
+    buffer: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Capture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything written so far
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.lock().unwrap()).into_owned()
+    }
+
+    /// Everything written so far, clearing the buffer
+    pub fn take(&self) -> String {
+        let bytes = std::mem::take(&mut *self.buffer.lock().unwrap());
+        crate::runtime::bytes::string_from_utf8_lossy(bytes)
+    }
+}
+
+impl Write for Capture {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.buffer.lock().unwrap().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thread_sinks_nest_and_restore() {
+        let outer = Capture::new();
+        let inner = Capture::new();
+        let errors = Capture::new();
+        {
+            let _outer = enter(&OutputSinks {
+                stdout: Some(sink(Box::new(outer.clone()))),
+                stderr: Some(sink(Box::new(errors.clone()))),
+            });
+            write(Stream::Stdout, "a").unwrap();
+            {
+                // Only stdout is redirected again; stderr keeps the outer sink
+                let _inner = enter(&OutputSinks {
+                    stdout: Some(sink(Box::new(inner.clone()))),
+                    stderr: None,
+                });
+                write(Stream::Stdout, "b").unwrap();
+                write(Stream::Stderr, "e").unwrap();
+            }
+            write(Stream::Stdout, "c").unwrap();
+        }
+        assert_eq!(outer.take(), "ac");
+        assert_eq!(outer.contents(), "");
+        assert_eq!(inner.contents(), "b");
+        assert_eq!(errors.contents(), "e");
+        assert!(CURRENT.with(|current| current.borrow().is_empty()));
+    }
+
+    #[test]
+    fn test_threads_can_enter_the_current_sinks() {
+        let errors = Capture::new();
+        let _entered = enter(&OutputSinks {
+            stdout: None,
+            stderr: Some(sink(Box::new(errors.clone()))),
+        });
+        let sinks = current();
+        std::thread::spawn(move || {
+            let _output = enter(&sinks);
+            write(Stream::Stderr, "from a thread").unwrap();
+        })
+        .join()
+        .unwrap();
+        assert_eq!(errors.contents(), "from a thread");
+    }
+}
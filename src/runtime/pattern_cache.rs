@@ -0,0 +1,194 @@
+//! Bounded caches for compiled patterns and format strings
+//!
+//! Calls like `printf` and `regex.compile` are usually made with the same
+//! literal format string or pattern over and over, so the parsed or compiled
+//! form is kept per pattern instead of being rebuilt on every call. Entries are evicted least-recently-used once the
+//! cache is full.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::std::fmt::{parse_printf, PrintfPiece};
+use crate::std::regex::{compile_uncached, RegexError};
+use ::regex::Regex;
+
+/// Default number of patterns kept by the runtime caches
+pub const DEFAULT_PATTERN_CACHE_CAPACITY: usize = 256;
+
+/// A parsed printf format string, or the reason it could not be parsed
+pub type CompiledFormat = std::result::Result<Vec<PrintfPiece>, String>;
+
+/// A compiled regular expression, or the reason it could not be compiled
+pub type CompiledRegex = std::result::Result<Arc<Regex>, RegexError>;
+
+/// Hit/miss counters for a pattern cache
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PatternCacheStats {
+    /// Lookups served from the cache
+    pub hits: u64,
+    /// Lookups that had to compile the pattern
+    pub misses: u64,
+    /// Entries dropped to stay within capacity
+    pub evictions: u64,
+    /// Patterns currently cached
+    pub entries: usize,
+    /// Maximum number of cached patterns
+    pub capacity: usize,
+}
+
+struct CacheEntry<T> {
+    value: Arc<T>,
+    last_used: u64,
+}
+
+/// Thread-safe, bounded cache keyed by pattern string
+pub struct PatternCache<T> {
+    capacity: usize,
+    entries: Mutex<HashMap<String, CacheEntry<T>>>,
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl<T> PatternCache<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: Mutex::new(HashMap::new()),
+            clock: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// Get the compiled form of `pattern`, compiling it on a miss
+    pub fn get_or_compile<F>(&self, pattern: &str, compile: F) -> Arc<T>
+    where
+        F: FnOnce(&str) -> T,
+    {
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(pattern) {
+            entry.last_used = tick;
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return entry.value.clone();
+        }
+
+        // Compile without holding the lock; a racing miss just compiles twice
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let value = Arc::new(compile(pattern));
+
+        let mut entries = self.entries.lock().unwrap();
+        if !entries.contains_key(pattern) && entries.len() >= self.capacity {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        entries.insert(
+            pattern.to_string(),
+            CacheEntry {
+                value: value.clone(),
+                last_used: tick,
+            },
+        );
+        value
+    }
+
+    /// Snapshot of the cache counters
+    pub fn stats(&self) -> PatternCacheStats {
+        PatternCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap().len(),
+            capacity: self.capacity,
+        }
+    }
+
+    /// Drop all cached patterns; counters are kept
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+static PRINTF_FORMATS: OnceLock<PatternCache<CompiledFormat>> = OnceLock::new();
+
+/// Cache of parsed printf format strings shared by the runtime
+pub fn printf_formats() -> &'static PatternCache<CompiledFormat> {
+    PRINTF_FORMATS.get_or_init(|| PatternCache::new(DEFAULT_PATTERN_CACHE_CAPACITY))
+}
+
+/// Parse a printf format string, reusing an earlier parse of the same string
+pub fn compile_printf(format: &str) -> Arc<CompiledFormat> {
+    printf_formats().get_or_compile(format, parse_printf)
+}
+
+static REGEXES: OnceLock<PatternCache<CompiledRegex>> = OnceLock::new();
+
+/// Cache of compiled regular expressions shared by the runtime
+pub fn regexes() -> &'static PatternCache<CompiledRegex> {
+    REGEXES.get_or_init(|| PatternCache::new(DEFAULT_PATTERN_CACHE_CAPACITY))
+}
+
+/// Compile a regular expression, reusing an earlier compilation of the same pattern
+pub fn compile_regex(pattern: &str) -> Arc<CompiledRegex> {
+    regexes().get_or_compile(pattern, |pattern| compile_uncached(pattern).map(Arc::new))
+}
+
+/// Cache metrics reported by the runtime
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PatternCacheMetrics {
+    pub printf_formats: PatternCacheStats,
+    pub regexes: PatternCacheStats,
+}
+
+/// Current hit/miss statistics of the runtime pattern caches
+pub fn pattern_cache_metrics() -> PatternCacheMetrics {
+    PatternCacheMetrics {
+        printf_formats: printf_formats().stats(),
+        regexes: regexes().stats(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hits_and_misses() {
+        let cache = PatternCache::new(4);
+        let mut compiled = 0;
+        for _ in 0..3 {
+            cache.get_or_compile("%d", |p| {
+                compiled += 1;
+                p.len()
+            });
+        }
+        assert_eq!(compiled, 1);
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (2, 1, 1));
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = PatternCache::new(2);
+        cache.get_or_compile("a", |p| p.to_string());
+        cache.get_or_compile("b", |p| p.to_string());
+        cache.get_or_compile("a", |p| p.to_string());
+        cache.get_or_compile("c", |p| p.to_string());
+
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.evictions), (2, 1));
+        // "b" was the least recently used and had to be compiled again
+        cache.get_or_compile("a", |p| p.to_string());
+        cache.get_or_compile("b", |p| p.to_string());
+        assert_eq!(cache.stats().misses, 4);
+    }
+}
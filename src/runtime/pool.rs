@@ -0,0 +1,161 @@
+//! A pool of warm AST interpreters
+//!
+//! Tools that run many short programs, such as doctests and the language
+//! server's checks on save, take interpreters from a pool instead of
+//! building one per program. An interpreter is reset when it goes back to
+//! the pool: whatever the program defined or created is dropped, and the
+//! standard library modules stay registered for the next program.
+
+use crate::runtime::ast_interpreter::AstInterpreter;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// How many interpreters a pool keeps by default
+pub const DEFAULT_POOL_SIZE: usize = 4;
+
+/// How often a pool built an interpreter or handed out a warm one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    pub created: usize,
+    pub reused: usize,
+}
+
+/// Idle interpreters, at most `capacity` of them. More interpreters can be
+/// in use at once; those returned to a full pool are dropped.
+pub struct InterpreterPool {
+    idle: Mutex<Vec<AstInterpreter>>,
+    capacity: usize,
+    created: AtomicUsize,
+    reused: AtomicUsize,
+}
+
+impl InterpreterPool {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            idle: Mutex::new(Vec::new()),
+            capacity,
+            created: AtomicUsize::new(0),
+            reused: AtomicUsize::new(0),
+        }
+    }
+
+    /// A warm interpreter if one is idle, or else a new one
+    pub fn acquire(&self) -> PooledInterpreter<'_> {
+        let warm = self.idle.lock().unwrap().pop();
+        let interpreter = match warm {
+            Some(interpreter) => {
+                self.reused.fetch_add(1, Ordering::Relaxed);
+                interpreter
+            }
+            None => {
+                self.created.fetch_add(1, Ordering::Relaxed);
+                AstInterpreter::new()
+            }
+        };
+        PooledInterpreter {
+            pool: self,
+            interpreter: Some(interpreter),
+        }
+    }
+
+    /// The interpreters waiting to be reused
+    pub fn idle(&self) -> usize {
+        self.idle.lock().unwrap().len()
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            created: self.created.load(Ordering::Relaxed),
+            reused: self.reused.load(Ordering::Relaxed),
+        }
+    }
+
+    fn release(&self, mut interpreter: AstInterpreter) {
+        // Reset before waiting, so idle interpreters don't hold on to the
+        // values of the last program
+        interpreter.reset();
+        let mut idle = self.idle.lock().unwrap();
+        if idle.len() < self.capacity {
+            idle.push(interpreter);
+        }
+    }
+}
+
+impl Default for InterpreterPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_POOL_SIZE)
+    }
+}
+
+/// An interpreter taken from a pool, returned to it when dropped
+pub struct PooledInterpreter<'a> {
+    pool: &'a InterpreterPool,
+    interpreter: Option<AstInterpreter>,
+}
+
+impl Deref for PooledInterpreter<'_> {
+    type Target = AstInterpreter;
+
+    fn deref(&self) -> &AstInterpreter {
+        self.interpreter.as_ref().expect("the interpreter is only taken on drop")
+    }
+}
+
+impl DerefMut for PooledInterpreter<'_> {
+    fn deref_mut(&mut self) -> &mut AstInterpreter {
+        self.interpreter.as_mut().expect("the interpreter is only taken on drop")
+    }
+}
+
+impl Drop for PooledInterpreter<'_> {
+    fn drop(&mut self) {
+        if let Some(interpreter) = self.interpreter.take() {
+            self.pool.release(interpreter);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn run(interpreter: &mut AstInterpreter, source: &str) {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let program = Parser::new(tokens).parse().unwrap();
+        interpreter.execute_program(&program).unwrap();
+    }
+
+    #[test]
+    fn test_released_interpreters_are_reset_and_reused() {
+        let pool = InterpreterPool::new(1);
+        {
+            let mut interpreter = pool.acquire();
+            run(&mut interpreter, "let answer = 42\nfunc helper() {}\n");
+            assert!(interpreter.get_variable("answer").is_some());
+            assert!(interpreter.get_function_definition("helper").is_some());
+        }
+        assert_eq!(pool.idle(), 1);
+
+        let mut interpreter = pool.acquire();
+        assert_eq!(pool.stats(), PoolStats { created: 1, reused: 1 });
+        assert!(interpreter.get_variable("answer").is_none());
+        assert!(interpreter.get_function_definition("helper").is_none());
+        // Built-in identifiers survive the reset
+        assert!(interpreter.get_variable("int32").is_some());
+        run(&mut interpreter, "let answer = \"again\"\n");
+    }
+
+    #[test]
+    fn test_pool_keeps_at_most_capacity_idle() {
+        let pool = InterpreterPool::new(1);
+        let first = pool.acquire();
+        let second = pool.acquire();
+        drop(first);
+        drop(second);
+        assert_eq!(pool.idle(), 1);
+        assert_eq!(pool.stats(), PoolStats { created: 2, reused: 0 });
+    }
+}
@@ -0,0 +1,258 @@
+//! CPU sampling profiler for interpreted programs
+//!
+//! While a profile is running, every AST interpreter registers the stack of
+//! Bulu functions it is executing, and a sampler thread records all of those
+//! stacks at a fixed interval. The samples are Bulu-level: a sample says which
+//! Bulu functions were on the stack, not where the interpreter itself was.
+//!
+//! There is at most one profile per process, so `bulu run` and `bulu test` can
+//! profile everything they execute, goroutines included.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// The Bulu functions an interpreter is executing, outermost first
+pub type CallStack = Arc<Mutex<Vec<String>>>;
+
+/// Default time between samples
+pub const DEFAULT_SAMPLE_INTERVAL: Duration = Duration::from_millis(1);
+
+/// State shared between the profiled interpreters and the sampler thread
+struct Sampler {
+    interval: Duration,
+    running: AtomicBool,
+    stacks: Mutex<Vec<Weak<Mutex<Vec<String>>>>>,
+    samples: Mutex<HashMap<Vec<String>, u64>>,
+}
+
+impl Sampler {
+    /// Record the current stack of every live interpreter; idle ones are skipped
+    fn sample(&self) {
+        let mut stacks = self.stacks.lock().unwrap();
+        stacks.retain(|stack| stack.strong_count() > 0);
+        let mut samples = self.samples.lock().unwrap();
+        for stack in stacks.iter().filter_map(Weak::upgrade) {
+            let frames = stack.lock().unwrap();
+            if !frames.is_empty() {
+                *samples.entry(frames.clone()).or_insert(0) += 1;
+            }
+        }
+    }
+}
+
+struct ActiveProfile {
+    sampler: Arc<Sampler>,
+    thread: JoinHandle<()>,
+    started: Instant,
+}
+
+static ACTIVE: Mutex<Option<ActiveProfile>> = Mutex::new(None);
+
+/// Start sampling every `interval`
+pub fn start_cpu_profile(interval: Duration) -> Result<(), String> {
+    let mut active = ACTIVE.lock().unwrap();
+    if active.is_some() {
+        return Err("a CPU profile is already running".to_string());
+    }
+    let sampler = Arc::new(Sampler {
+        interval,
+        running: AtomicBool::new(true),
+        stacks: Mutex::new(Vec::new()),
+        samples: Mutex::new(HashMap::new()),
+    });
+    let thread_sampler = sampler.clone();
+    let thread = std::thread::spawn(move || {
+        while thread_sampler.running.load(Ordering::Relaxed) {
+            std::thread::sleep(thread_sampler.interval);
+            thread_sampler.sample();
+        }
+    });
+    *active = Some(ActiveProfile {
+        sampler,
+        thread,
+        started: Instant::now(),
+    });
+    Ok(())
+}
+
+/// Stop sampling and return the profile, if one was running
+pub fn stop_cpu_profile() -> Option<CpuProfile> {
+    let active = ACTIVE.lock().unwrap().take()?;
+    active.sampler.running.store(false, Ordering::Relaxed);
+    let _ = active.thread.join();
+    let samples = std::mem::take(&mut *active.sampler.samples.lock().unwrap());
+    Some(CpuProfile {
+        samples,
+        interval: active.sampler.interval,
+        duration: active.started.elapsed(),
+    })
+}
+
+/// Whether a CPU profile is running
+pub fn cpu_profile_running() -> bool {
+    ACTIVE.lock().unwrap().is_some()
+}
+
+/// Have the running profile sample `stack`. Does nothing when no profile is running.
+pub fn register_call_stack(stack: &CallStack) {
+    if let Some(active) = ACTIVE.lock().unwrap().as_ref() {
+        active.sampler.stacks.lock().unwrap().push(Arc::downgrade(stack));
+    }
+}
+
+/// Samples of the Bulu call stacks of a program run
+#[derive(Debug, Clone, Default)]
+pub struct CpuProfile {
+    samples: HashMap<Vec<String>, u64>,
+    interval: Duration,
+    duration: Duration,
+}
+
+impl CpuProfile {
+    /// A profile from already aggregated samples
+    pub fn from_samples(samples: HashMap<Vec<String>, u64>, interval: Duration) -> Self {
+        Self {
+            samples,
+            interval,
+            duration: Duration::ZERO,
+        }
+    }
+
+    /// Time between samples
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Wall-clock time the profile ran for
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Number of samples taken
+    pub fn total_samples(&self) -> u64 {
+        self.samples.values().sum()
+    }
+
+    /// Samples per function: (name, self samples, total samples), most self samples first.
+    /// Self samples have the function innermost; total samples have it anywhere on the stack.
+    pub fn by_function(&self) -> Vec<(String, u64, u64)> {
+        let mut functions: HashMap<&str, (u64, u64)> = HashMap::new();
+        for (stack, count) in &self.samples {
+            if let Some(leaf) = stack.last() {
+                functions.entry(leaf).or_default().0 += count;
+            }
+            let mut seen = Vec::new();
+            for frame in stack {
+                // Recursive functions count once per sample
+                if !seen.contains(&frame) {
+                    seen.push(frame);
+                    functions.entry(frame).or_default().1 += count;
+                }
+            }
+        }
+        let mut functions: Vec<_> = functions
+            .into_iter()
+            .map(|(name, (own, total))| (name.to_string(), own, total))
+            .collect();
+        functions.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.cmp(&a.2)).then_with(|| a.0.cmp(&b.0)));
+        functions
+    }
+
+    /// The samples in the folded stack format: one `main;parse;token 12` line per stack
+    pub fn folded(&self) -> String {
+        let mut lines: Vec<String> = self
+            .samples
+            .iter()
+            .map(|(stack, count)| format!("{} {}", stack.join(";"), count))
+            .collect();
+        lines.sort();
+        let mut report = lines.join("\n");
+        report.push('\n');
+        report
+    }
+
+    /// The profile in speedscope's sampled file format, weighted in milliseconds
+    pub fn speedscope_json(&self, name: &str) -> String {
+        let mut stacks: Vec<(&Vec<String>, &u64)> = self.samples.iter().collect();
+        stacks.sort();
+
+        let mut frames: Vec<&str> = Vec::new();
+        let mut frame_index: HashMap<&str, usize> = HashMap::new();
+        let mut samples = Vec::with_capacity(stacks.len());
+        let mut weights = Vec::with_capacity(stacks.len());
+        let interval_ms = self.interval.as_secs_f64() * 1000.0;
+        for (stack, count) in stacks {
+            let indices: Vec<usize> = stack
+                .iter()
+                .map(|frame| {
+                    *frame_index.entry(frame.as_str()).or_insert_with(|| {
+                        frames.push(frame);
+                        frames.len() - 1
+                    })
+                })
+                .collect();
+            samples.push(indices);
+            weights.push(*count as f64 * interval_ms);
+        }
+        let end_value: f64 = weights.iter().sum();
+
+        let profile = serde_json::json!({
+            "$schema": "https://www.speedscope.app/file-format-schema.json",
+            "name": name,
+            "exporter": "bulu",
+            "activeProfileIndex": 0,
+            "shared": {
+                "frames": frames.iter().map(|frame| serde_json::json!({ "name": frame })).collect::<Vec<_>>(),
+            },
+            "profiles": [{
+                "type": "sampled",
+                "name": name,
+                "unit": "milliseconds",
+                "startValue": 0,
+                "endValue": end_value,
+                "samples": samples,
+                "weights": weights,
+            }],
+        });
+        profile.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile() -> CpuProfile {
+        let stack = |frames: &[&str]| frames.iter().map(|f| f.to_string()).collect::<Vec<_>>();
+        let mut samples = HashMap::new();
+        samples.insert(stack(&["main", "parse"]), 3);
+        samples.insert(stack(&["main", "parse", "parse"]), 1);
+        samples.insert(stack(&["main"]), 2);
+        CpuProfile::from_samples(samples, Duration::from_millis(2))
+    }
+
+    #[test]
+    fn test_function_totals() {
+        let profile = profile();
+        assert_eq!(profile.total_samples(), 6);
+        assert_eq!(
+            profile.by_function(),
+            vec![("parse".to_string(), 4, 4), ("main".to_string(), 2, 6)]
+        );
+        assert_eq!(profile.folded(), "main 2\nmain;parse 3\nmain;parse;parse 1\n");
+    }
+
+    #[test]
+    fn test_speedscope_output() {
+        let json: serde_json::Value = serde_json::from_str(&profile().speedscope_json("demo")).unwrap();
+        assert_eq!(json["shared"]["frames"][1]["name"], "parse");
+        let sampled = &json["profiles"][0];
+        assert_eq!(sampled["type"], "sampled");
+        assert_eq!(sampled["samples"][1], serde_json::json!([0, 1]));
+        assert_eq!(sampled["weights"][1], 6.0);
+        assert_eq!(sampled["endValue"], 12.0);
+    }
+}
@@ -26,8 +26,13 @@ pub struct RuntimePromise {
     pub then_callbacks: Vec<Box<dyn Fn(Value) -> Value + Send + Sync>>,
     /// Callbacks to execute when promise rejects
     pub catch_callbacks: Vec<Box<dyn Fn(String) -> Value + Send + Sync>>,
+    /// Run once when the promise settles either way
+    pub settle_wakers: Vec<SettleWaker>,
 }
 
+/// Wakes whoever is waiting for a promise to settle
+pub type SettleWaker = Box<dyn FnOnce() + Send + Sync>;
+
 impl std::fmt::Debug for RuntimePromise {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RuntimePromise")
@@ -41,6 +46,10 @@ impl std::fmt::Debug for RuntimePromise {
                 "catch_callbacks",
                 &format!("{} callbacks", self.catch_callbacks.len()),
             )
+            .field(
+                "settle_wakers",
+                &format!("{} wakers", self.settle_wakers.len()),
+            )
             .finish()
     }
 }
@@ -52,6 +61,7 @@ impl Clone for RuntimePromise {
             state: self.state.clone(),
             then_callbacks: Vec::new(),  // Don't clone callbacks
             catch_callbacks: Vec::new(), // Don't clone callbacks
+            settle_wakers: Vec::new(),
         }
     }
 }
@@ -64,6 +74,7 @@ impl RuntimePromise {
             state: PromiseState::Pending,
             then_callbacks: Vec::new(),
             catch_callbacks: Vec::new(),
+            settle_wakers: Vec::new(),
         }
     }
 
@@ -74,6 +85,7 @@ impl RuntimePromise {
             state: PromiseState::Resolved(value),
             then_callbacks: Vec::new(),
             catch_callbacks: Vec::new(),
+            settle_wakers: Vec::new(),
         }
     }
 
@@ -84,6 +96,7 @@ impl RuntimePromise {
             state: PromiseState::Rejected(error),
             then_callbacks: Vec::new(),
             catch_callbacks: Vec::new(),
+            settle_wakers: Vec::new(),
         }
     }
 
@@ -111,6 +124,7 @@ impl RuntimePromise {
             for callback in &self.then_callbacks {
                 callback(value.clone());
             }
+            self.wake();
         }
     }
 
@@ -123,6 +137,22 @@ impl RuntimePromise {
             for callback in &self.catch_callbacks {
                 callback(error.clone());
             }
+            self.wake();
+        }
+    }
+
+    /// Run `waker` once the promise settles, right away if it already has
+    pub fn on_settle(&mut self, waker: SettleWaker) {
+        if self.is_pending() {
+            self.settle_wakers.push(waker);
+        } else {
+            waker();
+        }
+    }
+
+    fn wake(&mut self) {
+        for waker in self.settle_wakers.drain(..) {
+            waker();
         }
     }
 
@@ -224,6 +254,15 @@ impl PromiseRegistry {
         }
     }
 
+    /// Run `waker` once the promise settles. Runs it right away if the promise
+    /// has already settled or does not exist, so the waiter finds out either way.
+    pub fn on_settle(&mut self, id: usize, waker: SettleWaker) {
+        match self.promises.get_mut(&id) {
+            Some(promise) => promise.on_settle(waker),
+            None => waker(),
+        }
+    }
+
     /// Check if a promise exists
     pub fn has_promise(&self, id: usize) -> bool {
         self.promises.contains_key(&id)
@@ -389,4 +428,31 @@ mod tests {
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), "Error");
     }
+
+    #[test]
+    fn test_settle_wakers_run_once_the_promise_settles() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let mut registry = PromiseRegistry::new();
+        let woken = Arc::new(AtomicUsize::new(0));
+        let waker = |woken: &Arc<AtomicUsize>| -> SettleWaker {
+            let woken = Arc::clone(woken);
+            Box::new(move || {
+                woken.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+
+        let pending = registry.create_promise();
+        registry.on_settle(pending, waker(&woken));
+        assert_eq!(woken.load(Ordering::SeqCst), 0);
+        registry.reject_promise(pending, "Error".to_string()).unwrap();
+        assert_eq!(woken.load(Ordering::SeqCst), 1);
+
+        // Settled and unknown promises wake their waiter straight away
+        let resolved = registry.create_resolved_promise(Value::Int64(1));
+        registry.on_settle(resolved, waker(&woken));
+        registry.on_settle(999, waker(&woken));
+        assert_eq!(woken.load(Ordering::SeqCst), 3);
+    }
 }
@@ -0,0 +1,402 @@
+//! Data race detector for interpreted programs (`bulu run --race`)
+//!
+//! While the detector runs, every goroutine carries a vector clock: one
+//! counter per goroutine, saying how much of that goroutine's execution it
+//! has synchronized with. Spawning a goroutine, sending on a channel,
+//! releasing a lock, `WaitGroup.done()` and finishing a `Once` publish the
+//! clock of the goroutine doing it; receiving, acquiring, waiting and later
+//! `Once` calls take it in. Two accesses to the same variable, at least one
+//! of them a write, race when neither goroutine had synchronized with the
+//! other in between.
+//!
+//! Only variables shared between goroutines can race: those captured by
+//! reference, which live in a `SharedValue` cell. Each cell's last write and
+//! latest read per goroutine are kept along with the stack they were made
+//! at, so a report shows both sides of the race.
+//!
+//! There is at most one detector per process, like the CPU profiler.
+
+use crate::runtime::ast_interpreter::SharedValue;
+use crate::runtime::output::{self, Stream};
+use crate::runtime::sync::LockId;
+use crate::types::primitive::RuntimeValue;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+/// Exit status of `bulu run --race` when races were found, as with Go
+pub const RACE_EXIT_CODE: i32 = 66;
+
+/// What a goroutine synchronizes through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyncObject {
+    Channel(u32),
+    Mutex(LockId),
+    RwLock(LockId),
+    WaitGroup(LockId),
+    Once(LockId),
+}
+
+/// One counter per goroutine, indexed by goroutine id
+#[derive(Debug, Clone, Default, PartialEq)]
+struct VectorClock(Vec<u64>);
+
+impl VectorClock {
+    fn get(&self, goroutine: usize) -> u64 {
+        self.0.get(goroutine).copied().unwrap_or(0)
+    }
+
+    fn tick(&mut self, goroutine: usize) {
+        if self.0.len() <= goroutine {
+            self.0.resize(goroutine + 1, 0);
+        }
+        self.0[goroutine] += 1;
+    }
+
+    fn join(&mut self, other: &VectorClock) {
+        if self.0.len() < other.0.len() {
+            self.0.resize(other.0.len(), 0);
+        }
+        for (mine, theirs) in self.0.iter_mut().zip(&other.0) {
+            *mine = (*mine).max(*theirs);
+        }
+    }
+}
+
+/// A read or write of a shared variable
+#[derive(Debug, Clone, PartialEq)]
+pub struct Access {
+    pub goroutine: usize,
+    pub write: bool,
+    /// Where the access was made, then the calls leading to it, innermost first
+    pub stack: Vec<String>,
+    /// The goroutine's own clock counter when it made the access
+    epoch: u64,
+}
+
+impl Access {
+    /// Whether a goroutine at `clock` has synchronized with this access
+    fn happened_before(&self, clock: &VectorClock) -> bool {
+        self.epoch <= clock.get(self.goroutine)
+    }
+}
+
+/// Two unsynchronized accesses to a variable, at least one of them a write
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaceReport {
+    pub variable: String,
+    pub current: Access,
+    pub previous: Access,
+    /// Where the goroutines involved were spawned, by goroutine id
+    pub created_at: Vec<(usize, Vec<String>)>,
+}
+
+impl fmt::Display for RaceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = |access: &Access| if access.write { "write" } else { "read" };
+        let write_stack = |f: &mut fmt::Formatter<'_>, stack: &[String]| -> fmt::Result {
+            for line in stack {
+                writeln!(f, "  {}", line)?;
+            }
+            Ok(())
+        };
+
+        writeln!(f, "==================")?;
+        writeln!(f, "WARNING: DATA RACE")?;
+        writeln!(
+            f,
+            "{} of '{}' by goroutine {}:",
+            if self.current.write { "Write" } else { "Read" },
+            self.variable,
+            self.current.goroutine
+        )?;
+        write_stack(f, &self.current.stack)?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Previous {} of '{}' by goroutine {}:",
+            kind(&self.previous),
+            self.variable,
+            self.previous.goroutine
+        )?;
+        write_stack(f, &self.previous.stack)?;
+        for (goroutine, stack) in &self.created_at {
+            writeln!(f)?;
+            writeln!(f, "Goroutine {} created at:", goroutine)?;
+            write_stack(f, stack)?;
+        }
+        write!(f, "==================")
+    }
+}
+
+/// What the detector knows about one shared variable
+struct Shadow {
+    /// Keeps the cell's allocation, so its address is not reused for
+    /// another variable while the detector remembers this one
+    _cell: Weak<Mutex<RuntimeValue>>,
+    last_write: Option<Access>,
+    /// Latest read of each goroutine since the last write
+    reads: Vec<Access>,
+}
+
+struct Detector {
+    session: u64,
+    next_goroutine: usize,
+    sync: HashMap<SyncObject, VectorClock>,
+    shadows: HashMap<usize, Shadow>,
+    created_at: HashMap<usize, Vec<String>>,
+    reports: Vec<RaceReport>,
+    /// Where both accesses of each reported race were made, so a race in a
+    /// loop is reported once
+    reported: HashSet<(String, String)>,
+}
+
+impl Detector {
+    /// A new goroutine, whose clock has only its own first tick
+    fn new_goroutine(&mut self) -> Goroutine {
+        let id = self.next_goroutine;
+        self.next_goroutine += 1;
+        let mut clock = VectorClock::default();
+        clock.tick(id);
+        Goroutine { session: self.session, id, clock }
+    }
+}
+
+/// The detector's view of the goroutine running on this thread
+struct Goroutine {
+    session: u64,
+    id: usize,
+    clock: VectorClock,
+}
+
+/// The clock a spawned goroutine starts with, taken on the spawning thread
+pub struct Fork {
+    goroutine: Goroutine,
+}
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+static SESSIONS: AtomicU64 = AtomicU64::new(0);
+static DETECTOR: Mutex<Option<Detector>> = Mutex::new(None);
+
+thread_local! {
+    static CURRENT: RefCell<Option<Goroutine>> = const { RefCell::new(None) };
+}
+
+/// Start detecting races in everything interpreted from now on
+pub fn start_race_detector() -> Result<(), String> {
+    let mut detector = DETECTOR.lock().unwrap();
+    if detector.is_some() {
+        return Err("the race detector is already running".to_string());
+    }
+    *detector = Some(Detector {
+        session: SESSIONS.fetch_add(1, Ordering::Relaxed) + 1,
+        next_goroutine: 0,
+        sync: HashMap::new(),
+        shadows: HashMap::new(),
+        created_at: HashMap::new(),
+        reports: Vec::new(),
+        reported: HashSet::new(),
+    });
+    RUNNING.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Stop detecting and return the races found, if the detector was running
+pub fn stop_race_detector() -> Option<Vec<RaceReport>> {
+    let detector = DETECTOR.lock().unwrap().take()?;
+    RUNNING.store(false, Ordering::Relaxed);
+    Some(detector.reports)
+}
+
+/// Whether the race detector is running; checked before any bookkeeping
+pub fn race_detector_running() -> bool {
+    RUNNING.load(Ordering::Relaxed)
+}
+
+/// Run `f` with the detector and this thread's goroutine, which joins the
+/// detector the first time it does anything
+fn with_goroutine<T>(f: impl FnOnce(&mut Detector, &mut Goroutine) -> T) -> Option<T> {
+    let mut detector = DETECTOR.lock().unwrap();
+    let detector = detector.as_mut()?;
+    CURRENT.with(|current| {
+        let mut current = current.borrow_mut();
+        if current.as_ref().is_none_or(|goroutine| goroutine.session != detector.session) {
+            *current = Some(detector.new_goroutine());
+        }
+        Some(f(detector, current.as_mut().expect("goroutine was just set")))
+    })
+}
+
+/// The clock for a goroutine about to be spawned from this one at `stack`.
+/// Everything this goroutine did so far happens before the new one starts.
+pub fn fork(stack: Vec<String>) -> Option<Fork> {
+    if !race_detector_running() {
+        return None;
+    }
+    with_goroutine(|detector, parent| {
+        let mut child = detector.new_goroutine();
+        child.clock.join(&parent.clock);
+        parent.clock.tick(parent.id);
+        detector.created_at.insert(child.id, stack);
+        Fork { goroutine: child }
+    })
+}
+
+/// Make the spawned goroutine of `fork` the one running on this thread
+pub fn enter(fork: Option<Fork>) {
+    if let Some(fork) = fork {
+        CURRENT.with(|current| *current.borrow_mut() = Some(fork.goroutine));
+    }
+}
+
+/// Publish this goroutine's clock through `object`, for the next goroutine
+/// that acquires it
+pub fn release(object: SyncObject) {
+    if !race_detector_running() {
+        return;
+    }
+    with_goroutine(|detector, goroutine| {
+        detector.sync.entry(object).or_default().join(&goroutine.clock);
+        goroutine.clock.tick(goroutine.id);
+    });
+}
+
+/// Synchronize with every goroutine that released `object` before
+pub fn acquire(object: SyncObject) {
+    if !race_detector_running() {
+        return;
+    }
+    with_goroutine(|detector, goroutine| {
+        if let Some(clock) = detector.sync.get(&object) {
+            goroutine.clock.join(clock);
+        }
+    });
+}
+
+/// Record a read of the variable `name` held in `cell`, made at `stack`
+pub fn read(cell: &SharedValue, name: &str, stack: Vec<String>) {
+    access(cell, name, false, stack);
+}
+
+/// Record a write of the variable `name` held in `cell`, made at `stack`
+pub fn write(cell: &SharedValue, name: &str, stack: Vec<String>) {
+    access(cell, name, true, stack);
+}
+
+fn access(cell: &SharedValue, name: &str, write: bool, stack: Vec<String>) {
+    if !race_detector_running() {
+        return;
+    }
+    let races = with_goroutine(|detector, goroutine| {
+        let current = Access {
+            goroutine: goroutine.id,
+            write,
+            stack,
+            epoch: goroutine.clock.get(goroutine.id),
+        };
+        let shadow = detector
+            .shadows
+            .entry(Arc::as_ptr(cell) as usize)
+            .or_insert_with(|| Shadow {
+                _cell: Arc::downgrade(cell),
+                last_write: None,
+                reads: Vec::new(),
+            });
+
+        // Reads only conflict with writes; writes conflict with everything
+        let unsynchronized = |previous: &&Access| {
+            previous.goroutine != current.goroutine && !previous.happened_before(&goroutine.clock)
+        };
+        let mut conflicts: Vec<Access> = shadow.last_write.iter().filter(unsynchronized).cloned().collect();
+        if write {
+            conflicts.extend(shadow.reads.iter().filter(unsynchronized).cloned());
+        }
+
+        if write {
+            shadow.last_write = Some(current.clone());
+            shadow.reads.clear();
+        } else {
+            shadow.reads.retain(|read| read.goroutine != current.goroutine);
+            shadow.reads.push(current.clone());
+        }
+
+        let mut races = Vec::new();
+        for previous in conflicts {
+            let key = (
+                current.stack.first().cloned().unwrap_or_default(),
+                previous.stack.first().cloned().unwrap_or_default(),
+            );
+            let flipped = (key.1.clone(), key.0.clone());
+            if detector.reported.contains(&flipped) || !detector.reported.insert(key) {
+                continue;
+            }
+            let created_at = [current.goroutine, previous.goroutine]
+                .iter()
+                .filter_map(|id| detector.created_at.get(id).map(|stack| (*id, stack.clone())))
+                .collect();
+            let report = RaceReport {
+                variable: name.to_string(),
+                current: current.clone(),
+                previous,
+                created_at,
+            };
+            detector.reports.push(report.clone());
+            races.push(report);
+        }
+        races
+    });
+
+    // Reported as they are found, as a racy program may never finish
+    for race in races.into_iter().flatten() {
+        let _ = output::write(Stream::Stderr, &format!("{}\n", race));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector_clocks() {
+        let mut a = VectorClock::default();
+        a.tick(0);
+        a.tick(0);
+        let mut b = VectorClock::default();
+        b.tick(2);
+        b.join(&a);
+        assert_eq!(b, VectorClock(vec![2, 0, 1]));
+
+        let access = Access { goroutine: 0, write: true, stack: Vec::new(), epoch: 2 };
+        assert!(access.happened_before(&b));
+        assert!(!access.happened_before(&VectorClock(vec![1])));
+    }
+
+    #[test]
+    fn test_report_format() {
+        let report = RaceReport {
+            variable: "count".to_string(),
+            current: Access {
+                goroutine: 1,
+                write: true,
+                stack: vec!["at line 3, column 9".to_string()],
+                epoch: 1,
+            },
+            previous: Access {
+                goroutine: 0,
+                write: false,
+                stack: vec!["at line 5, column 5".to_string(), "at main (line 1, column 1)".to_string()],
+                epoch: 1,
+            },
+            created_at: vec![(1, vec!["at line 2, column 5".to_string()])],
+        };
+        assert_eq!(
+            report.to_string(),
+            "==================\nWARNING: DATA RACE\n\
+             Write of 'count' by goroutine 1:\n  at line 3, column 9\n\n\
+             Previous read of 'count' by goroutine 0:\n  at line 5, column 5\n  at main (line 1, column 1)\n\n\
+             Goroutine 1 created at:\n  at line 2, column 5\n=================="
+        );
+    }
+}
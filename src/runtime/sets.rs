@@ -0,0 +1,359 @@
+//! Runtime support for `set<T>` values
+//!
+//! A set is stored as a vector of distinct elements in ascending order (see
+//! `compare`), so equal sets have equal representations, membership is a
+//! binary search and union, intersection and difference are linear merges.
+//! Elements must be hashable in the sense of the `hash` builtin, and integers
+//! of different widths with the same value are the same element.
+
+use crate::error::{BuluError, Result};
+use crate::runtime::builtins::{runtime_type_name, stable_hash};
+use crate::types::primitive::{sorted_map_entries, RuntimeValue};
+use std::cmp::Ordering;
+
+/// Build a set from arbitrary elements, dropping duplicates
+pub fn from_elements(elements: Vec<RuntimeValue>) -> Result<Vec<RuntimeValue>> {
+    for element in &elements {
+        check_element(element)?;
+    }
+    let mut set = elements;
+    set.sort_by(compare);
+    set.dedup_by(|a, b| compare(a, b) == Ordering::Equal);
+    Ok(set)
+}
+
+/// Fail for values that cannot be set elements (functions, channels, NaN, ...)
+pub fn check_element(value: &RuntimeValue) -> Result<()> {
+    stable_hash(value, 0).map(|_| ()).map_err(|error| match error {
+        BuluError::RuntimeError { message, file } => BuluError::RuntimeError {
+            message: message.replacen("hash() of", "set element of", 1),
+            file,
+        },
+        other => other,
+    })
+}
+
+/// Whether a set method changes the set it is called on
+pub fn is_mutating(method: &str) -> bool {
+    matches!(method, "add" | "remove")
+}
+
+/// Call a set method. `add` and `remove` change `set` in place, finding the
+/// element by binary search, and return whether they changed it.
+pub fn call_method_mut(
+    set: &mut Vec<RuntimeValue>,
+    method: &str,
+    args: &[RuntimeValue],
+) -> Result<RuntimeValue> {
+    match (method, args) {
+        ("add", [value]) => Ok(RuntimeValue::Bool(insert(set, value.clone())?)),
+        ("remove", [value]) => Ok(RuntimeValue::Bool(remove(set, value))),
+        _ => call_method(set, method, args),
+    }
+}
+
+/// Call a set method on a set that is not stored anywhere; `add` and `remove`
+/// change a copy of it
+pub fn call_method(set: &[RuntimeValue], method: &str, args: &[RuntimeValue]) -> Result<RuntimeValue> {
+    let error = |message: String| BuluError::RuntimeError { message, file: None };
+
+    let expected = match method {
+        "add" | "remove" | "contains" | "union" | "intersection" | "difference" | "isSubset" => 1,
+        "len" | "toArray" => 0,
+        _ => return Err(error(format!("Method '{}' not found on set", method))),
+    };
+    if args.len() != expected {
+        return Err(error(format!(
+            "set.{}() expects {} argument(s), got {}",
+            method,
+            expected,
+            args.len()
+        )));
+    }
+    let other = || match &args[0] {
+        RuntimeValue::Set(other) => Ok(other.as_slice()),
+        other => Err(error(format!(
+            "set.{}() expects a set, got {}",
+            method,
+            runtime_type_name(other)
+        ))),
+    };
+
+    Ok(match method {
+        "add" | "remove" => return call_method_mut(&mut set.to_vec(), method, args),
+        "contains" => RuntimeValue::Bool(contains(set, &args[0])),
+        "union" => RuntimeValue::Set(union(set, other()?)),
+        "intersection" => RuntimeValue::Set(intersection(set, other()?)),
+        "difference" => RuntimeValue::Set(difference(set, other()?)),
+        "isSubset" => RuntimeValue::Bool(is_subset(set, other()?)),
+        "len" => RuntimeValue::Int32(set.len() as i32),
+        _ => RuntimeValue::Array(set.to_vec()),
+    })
+}
+
+/// Check whether `value` is an element of `set`
+pub fn contains(set: &[RuntimeValue], value: &RuntimeValue) -> bool {
+    set.binary_search_by(|element| compare(element, value)).is_ok()
+}
+
+/// Add `value` to `set`. Returns false if it was already there.
+pub fn insert(set: &mut Vec<RuntimeValue>, value: RuntimeValue) -> Result<bool> {
+    check_element(&value)?;
+    match set.binary_search_by(|element| compare(element, &value)) {
+        Ok(_) => Ok(false),
+        Err(index) => {
+            set.insert(index, value);
+            Ok(true)
+        }
+    }
+}
+
+/// Remove `value` from `set`. Returns false if it was not there.
+pub fn remove(set: &mut Vec<RuntimeValue>, value: &RuntimeValue) -> bool {
+    match set.binary_search_by(|element| compare(element, value)) {
+        Ok(index) => {
+            set.remove(index);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Elements in either set
+pub fn union(a: &[RuntimeValue], b: &[RuntimeValue]) -> Vec<RuntimeValue> {
+    merge(a, b, true, true, true)
+}
+
+/// Elements in both sets
+pub fn intersection(a: &[RuntimeValue], b: &[RuntimeValue]) -> Vec<RuntimeValue> {
+    merge(a, b, false, true, false)
+}
+
+/// Elements of `a` that are not in `b`
+pub fn difference(a: &[RuntimeValue], b: &[RuntimeValue]) -> Vec<RuntimeValue> {
+    merge(a, b, true, false, false)
+}
+
+/// Check whether every element of `a` is in `b`
+pub fn is_subset(a: &[RuntimeValue], b: &[RuntimeValue]) -> bool {
+    difference(a, b).is_empty()
+}
+
+/// Walk two sorted sets together, keeping elements only in `a`, in both, or only in `b`
+fn merge(
+    a: &[RuntimeValue],
+    b: &[RuntimeValue],
+    keep_a: bool,
+    keep_both: bool,
+    keep_b: bool,
+) -> Vec<RuntimeValue> {
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match compare(&a[i], &b[j]) {
+            Ordering::Less => {
+                if keep_a {
+                    result.push(a[i].clone());
+                }
+                i += 1;
+            }
+            Ordering::Greater => {
+                if keep_b {
+                    result.push(b[j].clone());
+                }
+                j += 1;
+            }
+            Ordering::Equal => {
+                if keep_both {
+                    result.push(a[i].clone());
+                }
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    if keep_a {
+        result.extend_from_slice(&a[i..]);
+    }
+    if keep_b {
+        result.extend_from_slice(&b[j..]);
+    }
+    result
+}
+
+/// Total order over set elements: by kind first (null, bool, integer, float,
+/// char, string, sequence, tuple, map, struct, range, set), then by value.
+/// Integers compare numerically whatever their width.
+pub fn compare(a: &RuntimeValue, b: &RuntimeValue) -> Ordering {
+    match (a, b) {
+        (RuntimeValue::Bool(a), RuntimeValue::Bool(b)) => a.cmp(b),
+        (RuntimeValue::Char(a), RuntimeValue::Char(b)) => a.cmp(b),
+        (RuntimeValue::String(a), RuntimeValue::String(b)) => a.cmp(b),
+        (RuntimeValue::Array(a) | RuntimeValue::Slice(a), RuntimeValue::Array(b) | RuntimeValue::Slice(b))
+        | (RuntimeValue::Tuple(a), RuntimeValue::Tuple(b))
+        | (RuntimeValue::Set(a), RuntimeValue::Set(b)) => compare_sequences(a, b),
+        (RuntimeValue::Map(a), RuntimeValue::Map(b)) => {
+            let a = sorted_map_entries(a);
+            let b = sorted_map_entries(b);
+            compare_entries(a.into_iter(), b.into_iter())
+        }
+        (
+            RuntimeValue::Struct { name: a_name, fields: a },
+            RuntimeValue::Struct { name: b_name, fields: b },
+        ) => a_name.cmp(b_name).then_with(|| {
+            let mut a: Vec<_> = a.iter().collect();
+            let mut b: Vec<_> = b.iter().collect();
+            a.sort_by(|x, y| x.0.cmp(y.0));
+            b.sort_by(|x, y| x.0.cmp(y.0));
+            compare_entries(a.into_iter(), b.into_iter())
+        }),
+        (RuntimeValue::Range(a_start, a_end, a_step), RuntimeValue::Range(b_start, b_end, b_step)) => {
+            (a_start, a_end, a_step.unwrap_or(1)).cmp(&(b_start, b_end, b_step.unwrap_or(1)))
+        }
+        _ => match (integer_value(a), integer_value(b)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => match (float_value(a), float_value(b)) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                _ => kind_rank(a).cmp(&kind_rank(b)),
+            },
+        },
+    }
+}
+
+fn compare_sequences(a: &[RuntimeValue], b: &[RuntimeValue]) -> Ordering {
+    for (a, b) in a.iter().zip(b) {
+        let ordering = compare(a, b);
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+fn compare_entries<'a>(
+    mut a: impl Iterator<Item = (&'a String, &'a RuntimeValue)>,
+    mut b: impl Iterator<Item = (&'a String, &'a RuntimeValue)>,
+) -> Ordering {
+    loop {
+        match (a.next(), b.next()) {
+            (Some((a_key, a_value)), Some((b_key, b_value))) => {
+                let ordering = a_key.cmp(b_key).then_with(|| compare(a_value, b_value));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(_), None) => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (None, None) => return Ordering::Equal,
+        }
+    }
+}
+
+fn integer_value(value: &RuntimeValue) -> Option<i128> {
+    match value {
+        RuntimeValue::Int8(i) => Some(*i as i128),
+        RuntimeValue::Int16(i) => Some(*i as i128),
+        RuntimeValue::Int32(i) => Some(*i as i128),
+        RuntimeValue::Int64(i) | RuntimeValue::Integer(i) => Some(*i as i128),
+        RuntimeValue::UInt8(i) | RuntimeValue::Byte(i) => Some(*i as i128),
+        RuntimeValue::UInt16(i) => Some(*i as i128),
+        RuntimeValue::UInt32(i) => Some(*i as i128),
+        RuntimeValue::UInt64(i) => Some(*i as i128),
+        _ => None,
+    }
+}
+
+fn float_value(value: &RuntimeValue) -> Option<f64> {
+    // 0.0 and -0.0 are the same element
+    match value {
+        RuntimeValue::Float32(f) => Some(*f as f64 + 0.0),
+        RuntimeValue::Float64(f) => Some(*f + 0.0),
+        _ => None,
+    }
+}
+
+fn kind_rank(value: &RuntimeValue) -> u8 {
+    match value {
+        RuntimeValue::Null => 0,
+        RuntimeValue::Bool(_) => 1,
+        RuntimeValue::Int8(_)
+        | RuntimeValue::Int16(_)
+        | RuntimeValue::Int32(_)
+        | RuntimeValue::Int64(_)
+        | RuntimeValue::Integer(_)
+        | RuntimeValue::UInt8(_)
+        | RuntimeValue::Byte(_)
+        | RuntimeValue::UInt16(_)
+        | RuntimeValue::UInt32(_)
+        | RuntimeValue::UInt64(_) => 2,
+        RuntimeValue::Float32(_) | RuntimeValue::Float64(_) => 3,
+        RuntimeValue::Char(_) => 4,
+        RuntimeValue::String(_) => 5,
+        RuntimeValue::Array(_) | RuntimeValue::Slice(_) => 6,
+        RuntimeValue::Tuple(_) => 7,
+        RuntimeValue::Map(_) => 8,
+        RuntimeValue::Struct { .. } => 9,
+        RuntimeValue::Range(_, _, _) => 10,
+        RuntimeValue::Set(_) => 11,
+        // Not hashable, so never stored in a set
+        _ => 12,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ints(values: &[i32]) -> Vec<RuntimeValue> {
+        values.iter().map(|v| RuntimeValue::Int32(*v)).collect()
+    }
+
+    #[test]
+    fn test_elements_are_sorted_and_distinct() {
+        let set = from_elements(vec![
+            RuntimeValue::Int32(3),
+            RuntimeValue::Integer(1),
+            RuntimeValue::Int64(3),
+            RuntimeValue::String("a".to_string()),
+        ])
+        .unwrap();
+        assert_eq!(set.len(), 3);
+        assert!(contains(&set, &RuntimeValue::UInt8(1)));
+        assert_eq!(set[2], RuntimeValue::String("a".to_string()));
+
+        assert!(from_elements(vec![RuntimeValue::Channel(1)]).is_err());
+    }
+
+    #[test]
+    fn test_set_algebra() {
+        let a = from_elements(ints(&[1, 2, 3, 4])).unwrap();
+        let b = from_elements(ints(&[3, 4, 5])).unwrap();
+        assert_eq!(union(&a, &b), ints(&[1, 2, 3, 4, 5]));
+        assert_eq!(intersection(&a, &b), ints(&[3, 4]));
+        assert_eq!(difference(&a, &b), ints(&[1, 2]));
+        assert!(is_subset(&intersection(&a, &b), &b));
+
+        let mut c = b.clone();
+        assert!(insert(&mut c, RuntimeValue::Int32(0)).unwrap());
+        assert!(!insert(&mut c, RuntimeValue::Int64(5)).unwrap());
+        assert!(remove(&mut c, &RuntimeValue::Int32(4)));
+        assert_eq!(c, ints(&[0, 3, 5]));
+    }
+
+    #[test]
+    fn test_add_and_remove_change_the_set_in_place() {
+        let mut set = from_elements(ints(&[1, 3])).unwrap();
+        set.reserve(8);
+        let storage = set.as_ptr();
+        let added = call_method_mut(&mut set, "add", &[RuntimeValue::Int32(2)]).unwrap();
+        let removed = call_method_mut(&mut set, "remove", &[RuntimeValue::Int32(1)]).unwrap();
+        assert_eq!((added, removed), (RuntimeValue::Bool(true), RuntimeValue::Bool(true)));
+        assert_eq!(set, ints(&[2, 3]));
+        assert_eq!(set.as_ptr(), storage);
+
+        // A set that is not stored anywhere is left as it was
+        let result = call_method(&set, "add", &[RuntimeValue::Int32(9)]).unwrap();
+        assert_eq!(result, RuntimeValue::Bool(true));
+        assert_eq!(set, ints(&[2, 3]));
+    }
+}
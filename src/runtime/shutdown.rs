@@ -0,0 +1,211 @@
+//! Cooperative shutdown of a program's goroutines
+//!
+//! Every goroutine of a program runs under the program's root context, or a
+//! context derived from it. Shutting down cancels that context, so blocking
+//! operations fail with "context canceled", closes every channel the program
+//! made, so receiving loops end, and waits up to a grace period for the
+//! goroutines to return. Those still running afterwards are reported; threads
+//! cannot be stopped from the outside, so they are left to finish on their own.
+//!
+//! The grace period defaults to `LANG_SHUTDOWN_GRACE` milliseconds, 5 seconds
+//! when unset. Bulu code shuts down with `shutdown()` from `std/os`;
+//! embedders call `AstInterpreter::shutdown`, or `Shutdown::shutdown` on the
+//! handle `AstInterpreter::shutdown_handle` returns from another thread.
+
+use crate::runtime::channels::Channel;
+use crate::runtime::context::Context;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+/// Grace period when `LANG_SHUTDOWN_GRACE` is unset
+pub const DEFAULT_GRACE: Duration = Duration::from_secs(5);
+
+/// The grace period set by `LANG_SHUTDOWN_GRACE`, in milliseconds
+pub fn default_grace() -> Duration {
+    std::env::var("LANG_SHUTDOWN_GRACE")
+        .ok()
+        .and_then(|millis| millis.trim().parse().ok())
+        .map_or(DEFAULT_GRACE, Duration::from_millis)
+}
+
+/// A goroutine still running when the grace period ran out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Straggler {
+    pub goroutine: u32,
+    /// How long it had been running
+    pub running: Duration,
+}
+
+/// What a shutdown achieved
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Goroutines that returned during the grace period
+    pub drained: usize,
+    /// Goroutines that did not, by goroutine ID
+    pub stragglers: Vec<Straggler>,
+    /// Channels closed
+    pub closed_channels: usize,
+    /// Time spent waiting for the goroutines
+    pub waited: Duration,
+}
+
+impl ShutdownReport {
+    /// Every goroutine returned in time
+    pub fn is_clean(&self) -> bool {
+        self.stragglers.is_empty()
+    }
+}
+
+/// Shutdown state shared by a program's interpreters
+#[derive(Debug)]
+pub struct Shutdown {
+    /// Root of the contexts the program's goroutines run under
+    context: Context,
+    /// Running goroutines and when they started
+    running: Mutex<HashMap<u32, Instant>>,
+    finished: Condvar,
+    channels: Mutex<Vec<Weak<Channel>>>,
+    started: AtomicBool,
+}
+
+impl Shutdown {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            context: Context::background().with_cancel(),
+            running: Mutex::new(HashMap::new()),
+            finished: Condvar::new(),
+            channels: Mutex::new(Vec::new()),
+            started: AtomicBool::new(false),
+        })
+    }
+
+    /// The program's root context, cancelled when it shuts down
+    pub fn context(&self) -> Context {
+        self.context.clone()
+    }
+
+    /// Whether a shutdown began; goroutine errors are expected from then on
+    pub fn is_shutting_down(&self) -> bool {
+        self.started.load(Ordering::SeqCst)
+    }
+
+    /// Count goroutine `goroutine` as running until the guard is dropped
+    pub fn goroutine(self: &Arc<Self>, goroutine: u32) -> GoroutineGuard {
+        self.running.lock().unwrap().insert(goroutine, Instant::now());
+        GoroutineGuard {
+            shutdown: self.clone(),
+            goroutine,
+        }
+    }
+
+    /// IDs of the running goroutines, in order
+    pub fn running(&self) -> Vec<u32> {
+        let mut running: Vec<u32> = self.running.lock().unwrap().keys().copied().collect();
+        running.sort_unstable();
+        running
+    }
+
+    /// Close `channel` when the program shuts down
+    pub fn track_channel(&self, channel: &Arc<Channel>) {
+        let mut channels = self.channels.lock().unwrap();
+        // Channels nobody holds any more need no closing
+        if channels.len() >= 64 && channels.len().is_power_of_two() {
+            channels.retain(|channel| channel.strong_count() > 0);
+        }
+        channels.push(Arc::downgrade(channel));
+    }
+
+    /// Cancel the root context, close the program's channels and wait up to
+    /// `grace` for its goroutines to return. `caller`, the goroutine shutting
+    /// down, is not waited for.
+    pub fn shutdown(&self, grace: Duration, caller: u32) -> ShutdownReport {
+        self.started.store(true, Ordering::SeqCst);
+        let waiting = self.running.lock().unwrap().keys().filter(|&&id| id != caller).count();
+
+        self.context.cancel();
+        let channels = std::mem::take(&mut *self.channels.lock().unwrap());
+        let closed_channels = channels
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|channel| channel.close().is_ok())
+            .count();
+
+        let started = Instant::now();
+        let deadline = started + grace;
+        let mut running = self.running.lock().unwrap();
+        while running.keys().any(|&id| id != caller) {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            running = self.finished.wait_timeout(running, deadline - now).unwrap().0;
+        }
+
+        let now = Instant::now();
+        let mut stragglers: Vec<Straggler> = running
+            .iter()
+            .filter(|(&id, _)| id != caller)
+            .map(|(&goroutine, &since)| Straggler {
+                goroutine,
+                running: now.duration_since(since),
+            })
+            .collect();
+        stragglers.sort_by_key(|straggler| straggler.goroutine);
+        ShutdownReport {
+            drained: waiting.saturating_sub(stragglers.len()),
+            stragglers,
+            closed_channels,
+            waited: now.duration_since(started),
+        }
+    }
+}
+
+/// Marks a goroutine as running, see `Shutdown::goroutine`
+pub struct GoroutineGuard {
+    shutdown: Arc<Shutdown>,
+    goroutine: u32,
+}
+
+impl Drop for GoroutineGuard {
+    fn drop(&mut self) {
+        self.shutdown.running.lock().unwrap().remove(&self.goroutine);
+        self.shutdown.finished.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shutdown_drains_and_reports_stragglers() {
+        let shutdown = Shutdown::new();
+        let channel = Arc::new(Channel::new_unbuffered(crate::types::primitive::TypeId::Any));
+        shutdown.track_channel(&channel);
+
+        let cooperative = {
+            let guard = shutdown.goroutine(1);
+            let context = shutdown.context();
+            std::thread::spawn(move || {
+                let _guard = guard;
+                context.wait();
+            })
+        };
+        let stuck = shutdown.goroutine(2);
+
+        let report = shutdown.shutdown(Duration::from_millis(200), 0);
+        cooperative.join().unwrap();
+        assert!(shutdown.is_shutting_down());
+        assert_eq!(report.drained, 1);
+        assert_eq!(report.closed_channels, 1);
+        assert_eq!(report.stragglers.iter().map(|s| s.goroutine).collect::<Vec<_>>(), vec![2]);
+        assert!(report.waited >= Duration::from_millis(200));
+        assert!(channel.is_closed());
+
+        drop(stuck);
+        assert!(shutdown.running().is_empty());
+        assert!(shutdown.shutdown(Duration::from_secs(5), 0).is_clean());
+    }
+}
@@ -0,0 +1,485 @@
+//! Simplification of a checked program before it is interpreted
+//!
+//! The AST interpreter evaluates every node each time it is reached, so work
+//! that does not depend on the running program is done once beforehand:
+//!
+//! - operators applied to literals are folded, including those only exposed
+//!   once a branch around them is pruned, and redundant parentheses are dropped
+//! - `if` statements and expressions with a constant condition are replaced by
+//!   the branch taken, and `while false` loops and empty blocks are removed
+//! - array, tuple and map literals made only of literals are built once, before
+//!   the outermost loop that uses them, instead of on every iteration
+//!
+//! The pass runs after type checking, so pruned branches are still checked.
+//! Operations with no valid constant value, such as a division by zero, are left
+//! to fail at run time.
+
+use crate::ast::nodes::*;
+use crate::compiler::optimizer::{literal_value, ConstValue};
+use crate::lexer::token::Position;
+use std::mem;
+
+/// Simplify `program` in place
+pub fn simplify(program: &mut Program) {
+    Simplifier::default().statements(&mut program.statements);
+}
+
+#[derive(Debug, Default)]
+struct Simplifier {
+    /// Literals hoisted so far, numbering the variables that hold them
+    hoisted: usize,
+    /// Loops around the code being simplified, within the current function
+    loop_depth: usize,
+}
+
+impl Simplifier {
+    fn statements(&mut self, statements: &mut Vec<Statement>) {
+        let mut simplified = Vec::with_capacity(statements.len());
+        for mut statement in statements.drain(..) {
+            if !self.statement(&mut statement) {
+                continue;
+            }
+            if self.loop_depth == 0 && matches!(statement, Statement::While(_) | Statement::For(_)) {
+                self.hoist_loop(&mut statement, &mut simplified);
+            }
+            simplified.push(statement);
+        }
+        *statements = simplified;
+    }
+
+    /// Simplify `statement` in place; false when it does nothing and can be dropped
+    fn statement(&mut self, statement: &mut Statement) -> bool {
+        match statement {
+            Statement::VariableDecl(decl) => {
+                if let Some(initializer) = &mut decl.initializer {
+                    self.expression(initializer);
+                }
+            }
+            Statement::MultipleVariableDecl(decl) => {
+                for declaration in &mut decl.declarations {
+                    if let Some(initializer) = &mut declaration.initializer {
+                        self.expression(initializer);
+                    }
+                }
+            }
+            Statement::DestructuringDecl(decl) => self.expression(&mut decl.initializer),
+            Statement::MultipleAssignment(stmt) => {
+                for value in &mut stmt.values {
+                    self.expression(value);
+                }
+            }
+            Statement::FunctionDecl(decl) => self.function(decl),
+            Statement::StructDecl(decl) => {
+                for method in &mut decl.methods {
+                    self.function(method);
+                }
+            }
+            Statement::InterfaceDecl(decl) => {
+                for method in &mut decl.methods {
+                    if let Some(body) = &mut method.default_body {
+                        let outer = mem::take(&mut self.loop_depth);
+                        self.statements(&mut body.statements);
+                        self.loop_depth = outer;
+                    }
+                }
+            }
+            Statement::If(stmt) => {
+                self.expression(&mut stmt.condition);
+                let Some(condition) = constant_condition(&stmt.condition) else {
+                    self.statements(&mut stmt.then_branch.statements);
+                    if let Some(else_branch) = &mut stmt.else_branch {
+                        self.nested(else_branch);
+                    }
+                    return true;
+                };
+                let taken = if condition {
+                    let then_branch = mem::replace(&mut stmt.then_branch, empty_block(stmt.position));
+                    Some(Statement::Block(then_branch))
+                } else {
+                    stmt.else_branch.take().map(|else_branch| *else_branch)
+                };
+                return match taken {
+                    Some(taken) => {
+                        *statement = taken;
+                        self.statement(statement)
+                    }
+                    None => false,
+                };
+            }
+            Statement::While(stmt) => {
+                self.expression(&mut stmt.condition);
+                if constant_condition(&stmt.condition) == Some(false) {
+                    return false;
+                }
+                self.loop_depth += 1;
+                self.statements(&mut stmt.body.statements);
+                self.loop_depth -= 1;
+            }
+            Statement::For(stmt) => {
+                self.expression(&mut stmt.iterable);
+                self.loop_depth += 1;
+                self.statements(&mut stmt.body.statements);
+                self.loop_depth -= 1;
+            }
+            Statement::Match(stmt) => {
+                self.expression(&mut stmt.expr);
+                for arm in &mut stmt.arms {
+                    if let Some(guard) = &mut arm.guard {
+                        self.expression(guard);
+                    }
+                    self.nested(&mut arm.body);
+                }
+            }
+            Statement::Select(stmt) => {
+                for arm in &mut stmt.arms {
+                    if let Some(op) = &mut arm.channel_op {
+                        self.expression(&mut op.channel);
+                        if let Some(value) = &mut op.value {
+                            self.expression(value);
+                        }
+                    }
+                    self.nested(&mut arm.body);
+                }
+            }
+            Statement::Return(stmt) => {
+                if let Some(value) = &mut stmt.value {
+                    self.expression(value);
+                }
+            }
+            Statement::Defer(stmt) => self.nested(&mut stmt.stmt),
+            Statement::Try(stmt) => {
+                self.statements(&mut stmt.body.statements);
+                if let Some(catch) = &mut stmt.catch_clause {
+                    self.statements(&mut catch.body.statements);
+                }
+            }
+            Statement::Fail(stmt) => self.expression(&mut stmt.message),
+            Statement::Export(stmt) => self.nested(&mut stmt.item),
+            Statement::Expression(stmt) => self.expression(&mut stmt.expr),
+            Statement::Block(stmt) => {
+                self.statements(&mut stmt.statements);
+                return !stmt.statements.is_empty();
+            }
+            Statement::TypeAlias(_) | Statement::Break(_) | Statement::Continue(_) | Statement::Import(_) => {}
+        }
+        true
+    }
+
+    /// Simplify a statement that has to stay in place, emptying it when it does nothing
+    fn nested(&mut self, statement: &mut Statement) {
+        if !self.statement(statement) {
+            *statement = Statement::Block(empty_block(statement.position()));
+        }
+    }
+
+    /// A function body runs on its own, outside the loops around its declaration
+    fn function(&mut self, decl: &mut FunctionDecl) {
+        for param in &mut decl.params {
+            if let Some(default) = &mut param.default_value {
+                self.expression(default);
+            }
+        }
+        let outer = mem::take(&mut self.loop_depth);
+        self.statements(&mut decl.body.statements);
+        self.loop_depth = outer;
+    }
+
+    fn expression(&mut self, expr: &mut Expression) {
+        match expr {
+            Expression::Lambda(lambda) => {
+                let outer = mem::take(&mut self.loop_depth);
+                self.expression(&mut lambda.body);
+                self.loop_depth = outer;
+            }
+            Expression::Block(block) => self.statements(&mut block.statements),
+            _ => for_each_operand(expr, &mut |operand| self.expression(operand)),
+        }
+
+        match expr {
+            Expression::If(if_expr) => {
+                if let Some(condition) = constant_condition(&if_expr.condition) {
+                    let taken = if condition { &mut if_expr.then_expr } else { &mut if_expr.else_expr };
+                    *expr = take_expression(taken);
+                }
+            }
+            Expression::Binary(_) | Expression::Unary(_) => {
+                if let Some(value) = literal_value(expr) {
+                    if !matches!(value, ConstValue::Array(_)) {
+                        *expr = value.to_expression(expr.position());
+                    }
+                }
+            }
+            Expression::Parenthesized(inner) => *expr = take_expression(&mut inner.expr),
+            _ => {}
+        }
+    }
+
+    /// Move the constant literals evaluated on each iteration of a loop into
+    /// variables declared before it, appended to `declarations`
+    fn hoist_loop(&mut self, statement: &mut Statement, declarations: &mut Vec<Statement>) {
+        match statement {
+            Statement::While(stmt) => {
+                self.hoist(&mut stmt.condition, declarations);
+                self.hoist_statements(&mut stmt.body.statements, declarations);
+            }
+            // The iterable of the loop itself is only evaluated once
+            Statement::For(stmt) => self.hoist_statements(&mut stmt.body.statements, declarations),
+            _ => {}
+        }
+    }
+
+    fn hoist_statements(&mut self, statements: &mut [Statement], declarations: &mut Vec<Statement>) {
+        for statement in statements {
+            self.hoist_statement(statement, declarations);
+        }
+    }
+
+    fn hoist_statement(&mut self, statement: &mut Statement, declarations: &mut Vec<Statement>) {
+        match statement {
+            Statement::VariableDecl(decl) => {
+                if let Some(initializer) = &mut decl.initializer {
+                    self.hoist(initializer, declarations);
+                }
+            }
+            Statement::MultipleVariableDecl(decl) => {
+                for declaration in &mut decl.declarations {
+                    if let Some(initializer) = &mut declaration.initializer {
+                        self.hoist(initializer, declarations);
+                    }
+                }
+            }
+            Statement::DestructuringDecl(decl) => self.hoist(&mut decl.initializer, declarations),
+            Statement::MultipleAssignment(stmt) => {
+                for value in &mut stmt.values {
+                    self.hoist(value, declarations);
+                }
+            }
+            Statement::If(stmt) => {
+                self.hoist(&mut stmt.condition, declarations);
+                self.hoist_statements(&mut stmt.then_branch.statements, declarations);
+                if let Some(else_branch) = &mut stmt.else_branch {
+                    self.hoist_statement(else_branch, declarations);
+                }
+            }
+            Statement::While(stmt) => {
+                self.hoist(&mut stmt.condition, declarations);
+                self.hoist_statements(&mut stmt.body.statements, declarations);
+            }
+            Statement::For(stmt) => {
+                self.hoist(&mut stmt.iterable, declarations);
+                self.hoist_statements(&mut stmt.body.statements, declarations);
+            }
+            Statement::Match(stmt) => {
+                self.hoist(&mut stmt.expr, declarations);
+                for arm in &mut stmt.arms {
+                    if let Some(guard) = &mut arm.guard {
+                        self.hoist(guard, declarations);
+                    }
+                    self.hoist_statement(&mut arm.body, declarations);
+                }
+            }
+            Statement::Return(stmt) => {
+                if let Some(value) = &mut stmt.value {
+                    self.hoist(value, declarations);
+                }
+            }
+            Statement::Try(stmt) => {
+                self.hoist_statements(&mut stmt.body.statements, declarations);
+                if let Some(catch) = &mut stmt.catch_clause {
+                    self.hoist_statements(&mut catch.body.statements, declarations);
+                }
+            }
+            Statement::Fail(stmt) => self.hoist(&mut stmt.message, declarations),
+            Statement::Expression(stmt) => self.hoist(&mut stmt.expr, declarations),
+            Statement::Block(stmt) => self.hoist_statements(&mut stmt.statements, declarations),
+            // Declarations have bodies of their own, and `select` and `defer`
+            // run their statements apart from the loop's iterations
+            Statement::FunctionDecl(_)
+            | Statement::StructDecl(_)
+            | Statement::InterfaceDecl(_)
+            | Statement::TypeAlias(_)
+            | Statement::Select(_)
+            | Statement::Defer(_)
+            | Statement::Import(_)
+            | Statement::Export(_)
+            | Statement::Break(_)
+            | Statement::Continue(_) => {}
+        }
+    }
+
+    fn hoist(&mut self, expr: &mut Expression, declarations: &mut Vec<Statement>) {
+        match expr {
+            // Lambda bodies and goroutines do not run as part of the iteration
+            Expression::Lambda(_) | Expression::Run(_) | Expression::Block(_) => {}
+            _ if is_constant_aggregate(expr) => {
+                let position = expr.position();
+                let name = format!("$literal{}", self.hoisted);
+                self.hoisted += 1;
+                let value = mem::replace(
+                    expr,
+                    Expression::Identifier(IdentifierExpr {
+                        name: name.clone(),
+                        position,
+                    }),
+                );
+                declarations.push(Statement::VariableDecl(VariableDecl {
+                    is_const: false,
+                    name,
+                    type_annotation: None,
+                    initializer: Some(value),
+                    doc_comment: None,
+                    is_exported: false,
+                    position,
+                }));
+            }
+            _ => for_each_operand(expr, &mut |operand| self.hoist(operand, declarations)),
+        }
+    }
+}
+
+/// Call `f` on the subexpressions evaluated with `expr`, except for the bodies
+/// of lambdas and block expressions
+fn for_each_operand(expr: &mut Expression, f: &mut dyn FnMut(&mut Expression)) {
+    match expr {
+        Expression::Literal(_) | Expression::Identifier(_) | Expression::Lambda(_) | Expression::Block(_) => {}
+        Expression::Binary(binary) => {
+            f(&mut binary.left);
+            f(&mut binary.right);
+        }
+        Expression::Unary(unary) => f(&mut unary.operand),
+        Expression::Call(call) => {
+            f(&mut call.callee);
+            for arg in &mut call.args {
+                f(arg);
+            }
+        }
+        Expression::MemberAccess(access) => f(&mut access.object),
+        Expression::Index(index) => {
+            f(&mut index.object);
+            f(&mut index.index);
+        }
+        Expression::Assignment(assignment) => f(&mut assignment.value),
+        Expression::If(if_expr) => {
+            f(&mut if_expr.condition);
+            f(&mut if_expr.then_expr);
+            f(&mut if_expr.else_expr);
+        }
+        Expression::Match(match_expr) => {
+            f(&mut match_expr.expr);
+            for arm in &mut match_expr.arms {
+                if let Some(guard) = &mut arm.guard {
+                    f(guard);
+                }
+                f(&mut arm.expr);
+            }
+        }
+        Expression::Array(array) => {
+            for element in &mut array.elements {
+                f(element);
+            }
+        }
+        Expression::Set(set) => {
+            for element in &mut set.elements {
+                f(element);
+            }
+        }
+        Expression::Tuple(tuple) => {
+            for element in &mut tuple.elements {
+                f(element);
+            }
+        }
+        Expression::Map(map) => {
+            for entry in &mut map.entries {
+                f(&mut entry.key);
+                f(&mut entry.value);
+            }
+        }
+        Expression::StructLiteral(literal) => {
+            for field in &mut literal.fields {
+                f(&mut field.value);
+            }
+        }
+        Expression::Async(inner) => f(&mut inner.expr),
+        Expression::Await(inner) => f(&mut inner.expr),
+        Expression::Run(inner) => f(&mut inner.expr),
+        Expression::Channel(channel) => {
+            f(&mut channel.channel);
+            if let Some(value) = &mut channel.value {
+                f(value);
+            }
+        }
+        Expression::Select(select) => {
+            for arm in &mut select.arms {
+                if let Some(op) = &mut arm.channel_op {
+                    f(&mut op.channel);
+                    if let Some(value) = &mut op.value {
+                        f(value);
+                    }
+                }
+                f(&mut arm.expr);
+            }
+        }
+        Expression::Cast(cast) => f(&mut cast.expr),
+        Expression::TypeOf(inner) => f(&mut inner.expr),
+        Expression::Propagate(inner) => f(&mut inner.expr),
+        Expression::Range(range) => {
+            f(&mut range.start);
+            f(&mut range.end);
+            if let Some(step) = &mut range.step {
+                f(step);
+            }
+        }
+        Expression::Yield(yield_expr) => {
+            if let Some(value) = &mut yield_expr.value {
+                f(value);
+            }
+        }
+        Expression::Parenthesized(inner) => f(&mut inner.expr),
+    }
+}
+
+/// The value of a condition that is a boolean literal
+fn constant_condition(condition: &Expression) -> Option<bool> {
+    match condition {
+        Expression::Literal(LiteralExpr {
+            value: LiteralValue::Boolean(value),
+            ..
+        }) => Some(*value),
+        _ => None,
+    }
+}
+
+/// An array, tuple or map literal, or a byte string, built only from literals
+fn is_constant_aggregate(expr: &Expression) -> bool {
+    let is_constant = |expr: &Expression| matches!(expr, Expression::Literal(_)) || is_constant_aggregate(expr);
+    match expr {
+        Expression::Array(array) => !array.elements.is_empty() && array.elements.iter().all(is_constant),
+        Expression::Tuple(tuple) => !tuple.elements.is_empty() && tuple.elements.iter().all(is_constant),
+        Expression::Map(map) => {
+            !map.entries.is_empty() && map.entries.iter().all(|entry| is_constant(&entry.key) && is_constant(&entry.value))
+        }
+        Expression::Literal(LiteralExpr {
+            value: LiteralValue::ByteString(bytes),
+            ..
+        }) => !bytes.is_empty(),
+        _ => false,
+    }
+}
+
+fn take_expression(expr: &mut Expression) -> Expression {
+    let position = expr.position();
+    mem::replace(
+        expr,
+        Expression::Literal(LiteralExpr {
+            value: LiteralValue::Null,
+            position,
+        }),
+    )
+}
+
+fn empty_block(position: Position) -> BlockStmt {
+    BlockStmt {
+        statements: Vec::new(),
+        position,
+    }
+}
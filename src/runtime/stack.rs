@@ -0,0 +1,301 @@
+//! Growable goroutine stacks for the AST interpreter
+//!
+//! Each Bulu call recurses on the native stack, so a goroutine's thread
+//! stack bounds how deep its calls can go. Goroutines start on a small
+//! thread stack; when a call finds less than `RED_ZONE` bytes left, the rest
+//! of it runs on a new segment allocated on the heap, which is freed when the
+//! call returns. Past the maximum size the call fails with a runtime error
+//! instead of overflowing the process's stack.
+//!
+//! Sizes come from `LANG_STACK_INITIAL`, `LANG_STACK_SEGMENT` and
+//! `LANG_STACK_MAX` (e.g. `256K`, `1M`), or `set_stack_config`. The bytes
+//! each goroutine uses are measured from its first Bulu call, across
+//! segments; the peaks of running goroutines and of the deepest finished
+//! ones are kept for `stack_usage`.
+
+use crate::error::{BuluError, Result};
+use crate::runtime::gc::parse_size;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Bytes a call must find left on its segment to run there
+pub const RED_ZONE: usize = 256 * 1024;
+
+/// How many finished goroutines `stack_usage` remembers, the deepest first
+const FINISHED_KEPT: usize = 64;
+
+/// Sizes of goroutine stacks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackConfig {
+    /// Size of the thread stack a goroutine starts on
+    pub initial_size: usize,
+    /// Size of each segment added when a goroutine runs out
+    pub segment_size: usize,
+    /// Bytes a goroutine may use before its calls fail
+    pub max_size: usize,
+}
+
+impl Default for StackConfig {
+    fn default() -> Self {
+        Self {
+            initial_size: 512 * 1024,
+            segment_size: 1024 * 1024,
+            max_size: 1024 * 1024 * 1024, // 1GB, as with Go
+        }
+    }
+}
+
+impl StackConfig {
+    /// The default sizes, overridden by the `LANG_STACK_*` variables
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        let size = |name: &str| std::env::var(name).ok().and_then(|value| parse_size(&value).ok());
+        if let Some(initial) = size("LANG_STACK_INITIAL") {
+            config.initial_size = initial;
+        }
+        if let Some(segment) = size("LANG_STACK_SEGMENT") {
+            config.segment_size = segment;
+        }
+        if let Some(max) = size("LANG_STACK_MAX") {
+            config.max_size = max;
+        }
+        config.clamped()
+    }
+
+    /// Stacks and segments have room for at least one call past the red zone
+    fn clamped(mut self) -> Self {
+        self.initial_size = self.initial_size.max(2 * RED_ZONE);
+        self.segment_size = self.segment_size.max(2 * RED_ZONE);
+        self
+    }
+}
+
+static CONFIG: RwLock<Option<StackConfig>> = RwLock::new(None);
+
+/// The sizes goroutine stacks get
+pub fn stack_config() -> StackConfig {
+    if let Some(config) = *CONFIG.read().unwrap() {
+        return config;
+    }
+    *CONFIG.write().unwrap().get_or_insert_with(StackConfig::from_env)
+}
+
+/// Change the sizes of goroutine stacks. Goroutines already running keep
+/// their thread stack but grow and stop at the new sizes.
+pub fn set_stack_config(config: StackConfig) {
+    *CONFIG.write().unwrap() = Some(config.clamped());
+}
+
+/// Stack use of one goroutine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GoroutineStackUsage {
+    pub goroutine: u32,
+    /// Bytes in use at its latest call, 0 once it returned to the host or finished
+    pub current: usize,
+    /// Most bytes in use at once
+    pub peak: usize,
+    /// Segments the stack grew by
+    pub segments: usize,
+}
+
+#[derive(Debug)]
+struct GoroutineStack {
+    goroutine: u32,
+    current: AtomicUsize,
+    peak: AtomicUsize,
+    segments: AtomicUsize,
+}
+
+impl GoroutineStack {
+    fn usage(&self) -> GoroutineStackUsage {
+        GoroutineStackUsage {
+            goroutine: self.goroutine,
+            current: self.current.load(Ordering::Relaxed),
+            peak: self.peak.load(Ordering::Relaxed),
+            segments: self.segments.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    /// Running goroutines, keyed by the address of their record
+    running: HashMap<usize, Arc<GoroutineStack>>,
+    /// Deepest finished goroutines, deepest first
+    finished: Vec<GoroutineStackUsage>,
+}
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+fn registry<T>(f: impl FnOnce(&mut Registry) -> T) -> T {
+    f(REGISTRY.lock().unwrap().get_or_insert_with(Registry::default))
+}
+
+/// Where a segment starts and how many bytes earlier segments held then
+struct Segment {
+    base: usize,
+    below: usize,
+}
+
+/// The stack of the goroutine running on this thread
+struct ThreadStack {
+    record: Arc<GoroutineStack>,
+    segments: Vec<Segment>,
+}
+
+impl ThreadStack {
+    fn new(goroutine: u32) -> Self {
+        let record = Arc::new(GoroutineStack {
+            goroutine,
+            current: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            segments: AtomicUsize::new(0),
+        });
+        registry(|registry| registry.running.insert(Arc::as_ptr(&record) as usize, record.clone()));
+        Self {
+            record,
+            segments: Vec::new(),
+        }
+    }
+}
+
+impl Drop for ThreadStack {
+    fn drop(&mut self) {
+        let mut usage = self.record.usage();
+        usage.current = 0;
+        registry(|registry| {
+            registry.running.remove(&(Arc::as_ptr(&self.record) as usize));
+            let at = registry.finished.partition_point(|finished| finished.peak >= usage.peak);
+            registry.finished.insert(at, usage);
+            registry.finished.truncate(FINISHED_KEPT);
+        });
+    }
+}
+
+thread_local! {
+    /// Threads that never entered a goroutine run the main goroutine, 0
+    static STACK: RefCell<Option<ThreadStack>> = const { RefCell::new(None) };
+}
+
+/// Measures the stack of goroutine `goroutine` on this thread until dropped
+pub struct StackGuard(());
+
+impl Drop for StackGuard {
+    fn drop(&mut self) {
+        STACK.with(|stack| stack.borrow_mut().take());
+    }
+}
+
+/// Run goroutine `goroutine` on this thread, see `StackGuard`
+pub fn enter(goroutine: u32) -> StackGuard {
+    STACK.with(|stack| *stack.borrow_mut() = Some(ThreadStack::new(goroutine)));
+    StackGuard(())
+}
+
+/// Roughly where the stack pointer is
+#[inline(never)]
+fn stack_pointer() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+/// Run the body of a Bulu call, on a new segment if this one is nearly full.
+/// Fails when the goroutine would use more than the maximum stack size.
+pub fn grow<T>(call: impl FnOnce() -> Result<T>) -> Result<T> {
+    let config = stack_config();
+    let sp = stack_pointer();
+    let (outermost, used) = STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        let stack = stack.get_or_insert_with(|| ThreadStack::new(0));
+        // The goroutine's stack is measured from its outermost call
+        let outermost = stack.segments.is_empty();
+        if outermost {
+            stack.segments.push(Segment { base: sp, below: 0 });
+        }
+        let segment = stack.segments.last().unwrap();
+        let used = segment.below + segment.base.saturating_sub(sp);
+        stack.record.current.store(used, Ordering::Relaxed);
+        stack.record.peak.fetch_max(used, Ordering::Relaxed);
+        (outermost, used)
+    });
+
+    let result = if used + RED_ZONE > config.max_size {
+        let goroutine = STACK.with(|stack| stack.borrow().as_ref().map_or(0, |stack| stack.record.goroutine));
+        Err(BuluError::RuntimeError {
+            message: format!(
+                "stack overflow: goroutine {} would use more than its maximum stack of {} bytes",
+                goroutine, config.max_size
+            ),
+            file: None,
+        })
+    } else if stacker::remaining_stack().is_some_and(|left| left < RED_ZONE) {
+        stacker::grow(config.segment_size, || {
+            let base = stack_pointer();
+            STACK.with(|stack| {
+                if let Some(stack) = stack.borrow_mut().as_mut() {
+                    stack.segments.push(Segment { base, below: used });
+                    stack.record.segments.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+            let result = call();
+            STACK.with(|stack| stack.borrow_mut().as_mut().map(|stack| stack.segments.pop()));
+            result
+        })
+    } else {
+        call()
+    };
+
+    if outermost {
+        STACK.with(|stack| {
+            if let Some(stack) = stack.borrow_mut().as_mut() {
+                stack.segments.clear();
+                stack.record.current.store(0, Ordering::Relaxed);
+            }
+        });
+    }
+    result
+}
+
+/// Stack use of the running goroutines, then of the deepest finished ones,
+/// each group deepest first
+pub fn stack_usage() -> Vec<GoroutineStackUsage> {
+    registry(|registry| {
+        let mut running: Vec<GoroutineStackUsage> = registry.running.values().map(|stack| stack.usage()).collect();
+        running.sort_by(|a, b| b.peak.cmp(&a.peak).then(a.goroutine.cmp(&b.goroutine)));
+        running.extend(registry.finished.iter().copied());
+        running
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn depth(n: usize) -> Result<usize> {
+        // Enough stack per level that a few thousand levels need segments
+        let padding = std::hint::black_box([0u8; 1024]);
+        if n == 0 {
+            return Ok(padding[0] as usize);
+        }
+        grow(|| depth(n - 1)).map(|deeper| deeper + 1)
+    }
+
+    #[test]
+    fn test_deep_calls_grow_the_stack() {
+        let usage = std::thread::Builder::new()
+            .stack_size(stack_config().initial_size)
+            .spawn(|| {
+                let _stack = enter(4242);
+                assert_eq!(depth(5000).unwrap(), 5000);
+                stack_usage().into_iter().find(|usage| usage.goroutine == 4242).unwrap()
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert!(usage.peak > 5000 * 1024, "{:?}", usage);
+        assert!(usage.segments > 0, "{:?}", usage);
+        assert_eq!(usage.current, 0);
+    }
+}
@@ -2,13 +2,14 @@
 //!
 //! This module provides thread-safe synchronization primitives including:
 //! - Mutex locks with acquire()/release() methods
+//! - Reader/writer locks, wait groups and once for std.sync
 //! - Block syntax for automatic lock management
 //! - Atomic operations for basic types
 //! - Sleep and yield functions
 
 use crate::error::{BuluError, Result};
 use crate::types::primitive::RuntimeValue;
-use std::sync::{Arc, Mutex as StdMutex};
+use std::sync::{Arc, Condvar, Mutex as StdMutex};
 use std::time::{Duration, Instant};
 use std::collections::HashMap;
 
@@ -16,10 +17,13 @@ use std::collections::HashMap;
 pub type LockId = usize;
 
 /// A mutual exclusion lock for the Bulu language
+///
+/// The lock is not tied to a Rust guard's lifetime, so Bulu code can lock in
+/// one call and unlock in a later one (or from another goroutine, as in Go).
 #[derive(Debug, Clone)]
 pub struct Lock {
     id: LockId,
-    inner: Arc<StdMutex<()>>,
+    inner: Arc<(StdMutex<bool>, Condvar)>,
     created_at: Instant,
 }
 
@@ -28,7 +32,7 @@ impl Lock {
     pub fn new(id: LockId) -> Self {
         Self {
             id,
-            inner: Arc::new(StdMutex::new(())),
+            inner: Arc::new((StdMutex::new(false), Condvar::new())),
             created_at: Instant::now(),
         }
     }
@@ -40,33 +44,19 @@ impl Lock {
 
     /// Acquire the lock (blocking)
     pub fn acquire(&self) -> Result<LockGuard<'_>> {
-        match self.inner.lock() {
-            Ok(guard) => Ok(LockGuard {
-                _guard: guard,
-                lock_id: self.id,
-                acquired_at: Instant::now(),
-            }),
-            Err(_) => Err(BuluError::RuntimeError {
-            file: None,
-                message: format!("Failed to acquire lock {} (poisoned)", self.id),
-            }),
-        }
+        self.lock()?;
+        Ok(LockGuard {
+            lock: self,
+            acquired_at: Instant::now(),
+        })
     }
 
     /// Try to acquire the lock (non-blocking)
     pub fn try_acquire(&self) -> Result<Option<LockGuard<'_>>> {
-        match self.inner.try_lock() {
-            Ok(guard) => Ok(Some(LockGuard {
-                _guard: guard,
-                lock_id: self.id,
-                acquired_at: Instant::now(),
-            })),
-            Err(std::sync::TryLockError::WouldBlock) => Ok(None),
-            Err(std::sync::TryLockError::Poisoned(_)) => Err(BuluError::RuntimeError {
-            file: None,
-                message: format!("Failed to acquire lock {} (poisoned)", self.id),
-            }),
-        }
+        Ok(self.try_lock()?.then(|| LockGuard {
+            lock: self,
+            acquired_at: Instant::now(),
+        }))
     }
 
     /// Try to acquire the lock with a timeout
@@ -83,6 +73,47 @@ impl Lock {
         Ok(None)
     }
 
+    /// Lock without a guard; the lock stays held until `unlock` is called
+    pub fn lock(&self) -> Result<()> {
+        let (locked, released) = &*self.inner;
+        let mut locked = locked.lock().map_err(|_| self.poisoned())?;
+        while *locked {
+            locked = released.wait(locked).map_err(|_| self.poisoned())?;
+        }
+        *locked = true;
+        Ok(())
+    }
+
+    /// Lock if the lock is free, returning whether it was acquired
+    pub fn try_lock(&self) -> Result<bool> {
+        let mut locked = self.inner.0.lock().map_err(|_| self.poisoned())?;
+        if *locked {
+            return Ok(false);
+        }
+        *locked = true;
+        Ok(true)
+    }
+
+    /// Unlock a lock taken with `lock`; unlocking a free lock is an error
+    pub fn unlock(&self) -> Result<()> {
+        let (locked, released) = &*self.inner;
+        let mut locked = locked.lock().map_err(|_| self.poisoned())?;
+        if !*locked {
+            return Err(BuluError::RuntimeError {
+                file: None,
+                message: format!("Unlock of unlocked lock {}", self.id),
+            });
+        }
+        *locked = false;
+        released.notify_one();
+        Ok(())
+    }
+
+    /// Check whether the lock is currently held
+    pub fn is_locked(&self) -> bool {
+        self.inner.0.lock().map(|locked| *locked).unwrap_or(false)
+    }
+
     /// Release method for explicit release (used in Bulu code)
     /// Note: This is mainly for API compatibility - the actual release
     /// happens when the LockGuard is dropped
@@ -92,19 +123,25 @@ impl Lock {
         // For now, this is a no-op since Rust's RAII handles the release.
         Ok(())
     }
+
+    fn poisoned(&self) -> BuluError {
+        BuluError::RuntimeError {
+            file: None,
+            message: format!("Failed to acquire lock {} (poisoned)", self.id),
+        }
+    }
 }
 
 /// RAII guard for a lock
 pub struct LockGuard<'a> {
-    _guard: std::sync::MutexGuard<'a, ()>,
-    lock_id: LockId,
+    lock: &'a Lock,
     acquired_at: Instant,
 }
 
 impl<'a> LockGuard<'a> {
     /// Get the lock ID this guard is for
     pub fn lock_id(&self) -> LockId {
-        self.lock_id
+        self.lock.id
     }
 
     /// Get the time when this lock was acquired
@@ -113,10 +150,262 @@ impl<'a> LockGuard<'a> {
     }
 }
 
-/// Registry for managing locks
+impl Drop for LockGuard<'_> {
+    fn drop(&mut self) {
+        let _ = self.lock.unlock();
+    }
+}
+
+#[derive(Debug, Default)]
+struct RwState {
+    readers: usize,
+    writer: bool,
+    waiting_writers: usize,
+}
+
+/// A reader/writer lock: many readers or a single writer
+///
+/// Waiting writers block new readers so a steady stream of readers cannot
+/// starve them.
+#[derive(Debug, Clone)]
+pub struct RwLock {
+    id: LockId,
+    inner: Arc<(StdMutex<RwState>, Condvar)>,
+}
+
+impl RwLock {
+    /// Create a new reader/writer lock with the given ID
+    pub fn new(id: LockId) -> Self {
+        Self {
+            id,
+            inner: Arc::new((StdMutex::new(RwState::default()), Condvar::new())),
+        }
+    }
+
+    /// Get the lock ID
+    pub fn id(&self) -> LockId {
+        self.id
+    }
+
+    /// Take the lock for writing (blocking)
+    pub fn lock(&self) -> Result<()> {
+        let (state, changed) = &*self.inner;
+        let mut state = state.lock().map_err(|_| self.poisoned())?;
+        state.waiting_writers += 1;
+        while state.writer || state.readers > 0 {
+            state = changed.wait(state).map_err(|_| self.poisoned())?;
+        }
+        state.waiting_writers -= 1;
+        state.writer = true;
+        Ok(())
+    }
+
+    /// Take the lock for reading (blocking)
+    pub fn read_lock(&self) -> Result<()> {
+        let (state, changed) = &*self.inner;
+        let mut state = state.lock().map_err(|_| self.poisoned())?;
+        while state.writer || state.waiting_writers > 0 {
+            state = changed.wait(state).map_err(|_| self.poisoned())?;
+        }
+        state.readers += 1;
+        Ok(())
+    }
+
+    /// Take the lock for writing if it is free, returning whether it was acquired
+    pub fn try_lock(&self) -> Result<bool> {
+        let mut state = self.inner.0.lock().map_err(|_| self.poisoned())?;
+        if state.writer || state.readers > 0 {
+            return Ok(false);
+        }
+        state.writer = true;
+        Ok(true)
+    }
+
+    /// Release a write lock
+    pub fn unlock(&self) -> Result<()> {
+        let (state, changed) = &*self.inner;
+        let mut state = state.lock().map_err(|_| self.poisoned())?;
+        if !state.writer {
+            return Err(BuluError::RuntimeError {
+                file: None,
+                message: format!("Unlock of unlocked rwlock {}", self.id),
+            });
+        }
+        state.writer = false;
+        changed.notify_all();
+        Ok(())
+    }
+
+    /// Release a read lock
+    pub fn read_unlock(&self) -> Result<()> {
+        let (state, changed) = &*self.inner;
+        let mut state = state.lock().map_err(|_| self.poisoned())?;
+        if state.readers == 0 {
+            return Err(BuluError::RuntimeError {
+                file: None,
+                message: format!("Read unlock of rwlock {} without readers", self.id),
+            });
+        }
+        state.readers -= 1;
+        if state.readers == 0 {
+            changed.notify_all();
+        }
+        Ok(())
+    }
+
+    fn poisoned(&self) -> BuluError {
+        BuluError::RuntimeError {
+            file: None,
+            message: format!("Failed to acquire rwlock {} (poisoned)", self.id),
+        }
+    }
+}
+
+/// Waits for a collection of goroutines to finish
+#[derive(Debug, Clone)]
+pub struct WaitGroup {
+    id: LockId,
+    inner: Arc<(StdMutex<i64>, Condvar)>,
+}
+
+impl WaitGroup {
+    /// Create a new wait group with the given ID and a zero counter
+    pub fn new(id: LockId) -> Self {
+        Self {
+            id,
+            inner: Arc::new((StdMutex::new(0), Condvar::new())),
+        }
+    }
+
+    /// Get the wait group ID
+    pub fn id(&self) -> LockId {
+        self.id
+    }
+
+    /// Add `delta` (which may be negative) to the counter
+    pub fn add(&self, delta: i64) -> Result<()> {
+        let (counter, zero) = &*self.inner;
+        let mut counter = counter.lock().map_err(|_| self.poisoned())?;
+        if *counter + delta < 0 {
+            return Err(BuluError::RuntimeError {
+                file: None,
+                message: "Negative WaitGroup counter".to_string(),
+            });
+        }
+        *counter += delta;
+        if *counter == 0 {
+            zero.notify_all();
+        }
+        Ok(())
+    }
+
+    /// Decrement the counter by one
+    pub fn done(&self) -> Result<()> {
+        self.add(-1)
+    }
+
+    /// Block until the counter reaches zero
+    pub fn wait(&self) -> Result<()> {
+        let (counter, zero) = &*self.inner;
+        let mut counter = counter.lock().map_err(|_| self.poisoned())?;
+        while *counter > 0 {
+            counter = zero.wait(counter).map_err(|_| self.poisoned())?;
+        }
+        Ok(())
+    }
+
+    /// Current value of the counter
+    pub fn count(&self) -> i64 {
+        self.inner.0.lock().map(|counter| *counter).unwrap_or(0)
+    }
+
+    fn poisoned(&self) -> BuluError {
+        BuluError::RuntimeError {
+            file: None,
+            message: format!("WaitGroup {} is poisoned", self.id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OnceState {
+    New,
+    Running,
+    Done,
+}
+
+/// Runs an action exactly once, however many goroutines ask for it
+#[derive(Debug, Clone)]
+pub struct Once {
+    id: LockId,
+    inner: Arc<(StdMutex<OnceState>, Condvar)>,
+}
+
+impl Once {
+    /// Create a new once with the given ID
+    pub fn new(id: LockId) -> Self {
+        Self {
+            id,
+            inner: Arc::new((StdMutex::new(OnceState::New), Condvar::new())),
+        }
+    }
+
+    /// Get the once ID
+    pub fn id(&self) -> LockId {
+        self.id
+    }
+
+    /// Claim the action. Returns true for the single caller that must run it and
+    /// then call `finish`; every other caller blocks until the action has finished.
+    pub fn begin(&self) -> Result<bool> {
+        let (state, finished) = &*self.inner;
+        let mut state = state.lock().map_err(|_| self.poisoned())?;
+        match *state {
+            OnceState::New => {
+                *state = OnceState::Running;
+                Ok(true)
+            }
+            _ => {
+                while *state == OnceState::Running {
+                    state = finished.wait(state).map_err(|_| self.poisoned())?;
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    /// Mark the action as finished, even if it failed, and wake the waiters
+    pub fn finish(&self) {
+        let (state, finished) = &*self.inner;
+        if let Ok(mut state) = state.lock() {
+            *state = OnceState::Done;
+        }
+        finished.notify_all();
+    }
+
+    /// Check whether the action has finished
+    pub fn is_done(&self) -> bool {
+        self.inner.0.lock().map(|state| *state == OnceState::Done).unwrap_or(false)
+    }
+
+    fn poisoned(&self) -> BuluError {
+        BuluError::RuntimeError {
+            file: None,
+            message: format!("Once {} is poisoned", self.id),
+        }
+    }
+}
+
+/// Registry for managing locks and the other synchronization primitives
+///
+/// Primitives are cheap handles around shared state: clone one out of the
+/// registry and release the registry before blocking on it.
 #[derive(Debug)]
 pub struct LockRegistry {
     locks: HashMap<LockId, Lock>,
+    rw_locks: HashMap<LockId, RwLock>,
+    wait_groups: HashMap<LockId, WaitGroup>,
+    onces: HashMap<LockId, Once>,
     next_id: LockId,
 }
 
@@ -125,18 +414,23 @@ impl LockRegistry {
     pub fn new() -> Self {
         Self {
             locks: HashMap::new(),
+            rw_locks: HashMap::new(),
+            wait_groups: HashMap::new(),
+            onces: HashMap::new(),
             next_id: 1,
         }
     }
 
-    /// Create a new lock and return its ID
-    pub fn create_lock(&mut self) -> LockId {
+    fn allocate_id(&mut self) -> LockId {
         let id = self.next_id;
         self.next_id += 1;
-        
-        let lock = Lock::new(id);
-        self.locks.insert(id, lock);
-        
+        id
+    }
+
+    /// Create a new lock and return its ID
+    pub fn create_lock(&mut self) -> LockId {
+        let id = self.allocate_id();
+        self.locks.insert(id, Lock::new(id));
         id
     }
 
@@ -150,14 +444,50 @@ impl LockRegistry {
         self.locks.remove(&id)
     }
 
-    /// Get the number of locks in the registry
+    /// Create a new reader/writer lock and return its ID
+    pub fn create_rw_lock(&mut self) -> LockId {
+        let id = self.allocate_id();
+        self.rw_locks.insert(id, RwLock::new(id));
+        id
+    }
+
+    /// Get a reader/writer lock by ID
+    pub fn get_rw_lock(&self, id: LockId) -> Option<&RwLock> {
+        self.rw_locks.get(&id)
+    }
+
+    /// Create a new wait group and return its ID
+    pub fn create_wait_group(&mut self) -> LockId {
+        let id = self.allocate_id();
+        self.wait_groups.insert(id, WaitGroup::new(id));
+        id
+    }
+
+    /// Get a wait group by ID
+    pub fn get_wait_group(&self, id: LockId) -> Option<&WaitGroup> {
+        self.wait_groups.get(&id)
+    }
+
+    /// Create a new once and return its ID
+    pub fn create_once(&mut self) -> LockId {
+        let id = self.allocate_id();
+        self.onces.insert(id, Once::new(id));
+        id
+    }
+
+    /// Get a once by ID
+    pub fn get_once(&self, id: LockId) -> Option<&Once> {
+        self.onces.get(&id)
+    }
+
+    /// Get the number of primitives in the registry
     pub fn len(&self) -> usize {
-        self.locks.len()
+        self.locks.len() + self.rw_locks.len() + self.wait_groups.len() + self.onces.len()
     }
 
     /// Check if the registry is empty
     pub fn is_empty(&self) -> bool {
-        self.locks.is_empty()
+        self.len() == 0
     }
 }
 
@@ -511,4 +841,62 @@ mod tests {
         // Should not be able to get the removed lock
         assert!(registry.get_lock(lock_id).is_none());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_lock_unlock_across_threads() {
+        let lock = Lock::new(1);
+        lock.lock().unwrap();
+        assert!(!lock.try_lock().unwrap());
+
+        let other = lock.clone();
+        std::thread::spawn(move || other.unlock().unwrap()).join().unwrap();
+        assert!(lock.try_lock().unwrap());
+        lock.unlock().unwrap();
+        assert!(lock.unlock().is_err());
+    }
+
+    #[test]
+    fn test_rw_lock_readers_and_writer() {
+        let rw = RwLock::new(1);
+        rw.read_lock().unwrap();
+        rw.read_lock().unwrap();
+        assert!(!rw.try_lock().unwrap());
+
+        rw.read_unlock().unwrap();
+        rw.read_unlock().unwrap();
+        assert!(rw.try_lock().unwrap());
+        assert!(rw.read_unlock().is_err());
+        rw.unlock().unwrap();
+    }
+
+    #[test]
+    fn test_wait_group_waits_for_workers() {
+        let wg = WaitGroup::new(1);
+        let finished = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        wg.add(3).unwrap();
+        for _ in 0..3 {
+            let (wg, finished) = (wg.clone(), finished.clone());
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(10));
+                finished.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                wg.done().unwrap();
+            });
+        }
+        wg.wait().unwrap();
+        assert_eq!(finished.load(std::sync::atomic::Ordering::SeqCst), 3);
+        assert!(wg.done().is_err());
+    }
+
+    #[test]
+    fn test_once_runs_a_single_caller() {
+        let once = Once::new(1);
+        assert!(once.begin().unwrap());
+
+        let waiter = once.clone();
+        let handle = std::thread::spawn(move || waiter.begin().unwrap());
+        std::thread::sleep(Duration::from_millis(10));
+        once.finish();
+        assert!(!handle.join().unwrap());
+        assert!(once.is_done());
+    }
+}
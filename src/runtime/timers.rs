@@ -0,0 +1,236 @@
+//! The runtime's timer wheel
+//!
+//! Timers live in a hashed wheel of `SLOTS` slots, each covering one `TICK`.
+//! A timer due further away than one turn of the wheel waits in its slot for
+//! the turns still to come. One thread, started with the first timer, turns
+//! the wheel and sleeps while no timer is armed.
+//!
+//! Callbacks run on the wheel's thread, so they must not block. A callback
+//! is given the instant it was due and re-arms its timer by returning the
+//! next one.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Resolution of the wheel: timers fire up to one tick late, never early
+pub const TICK: Duration = Duration::from_millis(10);
+/// Slots in one turn of the wheel
+const SLOTS: u64 = 512;
+
+type Callback = Box<dyn FnMut(Instant) -> Option<Instant> + Send>;
+
+/// Identifies an armed timer, for cancelling it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerId(u64);
+
+struct Timer {
+    id: TimerId,
+    /// The tick it fires on
+    tick: u64,
+    /// When it asked to fire
+    due: Instant,
+    callback: Callback,
+}
+
+struct Wheel {
+    slots: Vec<Vec<Timer>>,
+    /// Ticks turned since `epoch`
+    turned: u64,
+    epoch: Instant,
+    armed: usize,
+    /// Timers whose callbacks are running
+    firing: HashSet<TimerId>,
+    /// Timers cancelled while firing, not to be re-armed
+    cancelled: HashSet<TimerId>,
+    next_id: u64,
+}
+
+impl Wheel {
+    fn insert(&mut self, timer: Timer) {
+        self.slots[(timer.tick % SLOTS) as usize].push(timer);
+        self.armed += 1;
+    }
+
+    /// The first tick at or after `due` that has not been turned yet
+    fn tick_of(&self, due: Instant) -> u64 {
+        let offset = due.saturating_duration_since(self.epoch).as_nanos();
+        let tick = offset.div_ceil(TICK.as_nanos()) as u64;
+        tick.max(self.turned + 1)
+    }
+}
+
+/// How far tick `tick` is from the epoch
+fn tick_offset(tick: u64) -> Duration {
+    Duration::from_nanos(TICK.as_nanos() as u64 * tick)
+}
+
+/// A hashed timer wheel and the thread turning it
+pub struct TimerWheel {
+    wheel: Mutex<Wheel>,
+    changed: Condvar,
+}
+
+impl TimerWheel {
+    /// The wheel shared by the whole process
+    pub fn global() -> &'static Arc<TimerWheel> {
+        static WHEEL: OnceLock<Arc<TimerWheel>> = OnceLock::new();
+        WHEEL.get_or_init(|| {
+            let wheel = Arc::new(TimerWheel {
+                wheel: Mutex::new(Wheel {
+                    slots: (0..SLOTS).map(|_| Vec::new()).collect(),
+                    turned: 0,
+                    epoch: Instant::now(),
+                    armed: 0,
+                    firing: HashSet::new(),
+                    cancelled: HashSet::new(),
+                    next_id: 1,
+                }),
+                changed: Condvar::new(),
+            });
+            let turning = wheel.clone();
+            std::thread::Builder::new()
+                .name("timer-wheel".to_string())
+                .spawn(move || turning.turn())
+                .expect("failed to start the timer wheel");
+            wheel
+        })
+    }
+
+    /// Call `callback` at `due`, and again at every instant it returns
+    pub fn schedule(
+        &self,
+        due: Instant,
+        callback: impl FnMut(Instant) -> Option<Instant> + Send + 'static,
+    ) -> TimerId {
+        let mut wheel = self.wheel.lock().unwrap();
+        // An idle wheel stopped turning; skip the ticks it slept through
+        if wheel.armed == 0 {
+            let elapsed = wheel.epoch.elapsed().as_nanos() / TICK.as_nanos();
+            wheel.turned = wheel.turned.max(elapsed as u64);
+        }
+        let id = TimerId(wheel.next_id);
+        wheel.next_id += 1;
+        let tick = wheel.tick_of(due);
+        wheel.insert(Timer {
+            id,
+            tick,
+            due,
+            callback: Box::new(callback),
+        });
+        self.changed.notify_one();
+        id
+    }
+
+    /// Disarm a timer; false when it already fired for the last time
+    pub fn cancel(&self, id: TimerId) -> bool {
+        let mut wheel = self.wheel.lock().unwrap();
+        for slot in 0..wheel.slots.len() {
+            if let Some(index) = wheel.slots[slot].iter().position(|timer| timer.id == id) {
+                wheel.slots[slot].swap_remove(index);
+                wheel.armed -= 1;
+                return true;
+            }
+        }
+        if wheel.firing.contains(&id) {
+            wheel.cancelled.insert(id);
+            return true;
+        }
+        false
+    }
+
+    /// Number of armed timers
+    pub fn armed(&self) -> usize {
+        self.wheel.lock().unwrap().armed
+    }
+
+    fn turn(&self) {
+        let mut wheel = self.wheel.lock().unwrap();
+        loop {
+            if wheel.armed == 0 {
+                wheel = self.changed.wait(wheel).unwrap();
+                continue;
+            }
+            let next = wheel.epoch + tick_offset(wheel.turned + 1);
+            let now = Instant::now();
+            if now < next {
+                wheel = self.changed.wait_timeout(wheel, next - now).unwrap().0;
+                continue;
+            }
+
+            wheel.turned += 1;
+            let tick = wheel.turned;
+            let slot = (tick % SLOTS) as usize;
+            let (fired, waiting): (Vec<Timer>, Vec<Timer>) =
+                std::mem::take(&mut wheel.slots[slot]).into_iter().partition(|timer| timer.tick <= tick);
+            wheel.slots[slot] = waiting;
+            if fired.is_empty() {
+                continue;
+            }
+            wheel.armed -= fired.len();
+            wheel.firing.extend(fired.iter().map(|timer| timer.id));
+
+            // Callbacks may arm timers of their own
+            drop(wheel);
+            let rearmed: Vec<Timer> = fired
+                .into_iter()
+                .filter_map(|mut timer| {
+                    let due = (timer.callback)(timer.due)?;
+                    Some(Timer { due, ..timer })
+                })
+                .collect();
+            wheel = self.wheel.lock().unwrap();
+
+            let firing = std::mem::take(&mut wheel.firing);
+            for mut timer in rearmed {
+                if wheel.cancelled.remove(&timer.id) {
+                    continue;
+                }
+                timer.tick = wheel.tick_of(timer.due);
+                wheel.insert(timer);
+            }
+            for id in firing {
+                wheel.cancelled.remove(&id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_fire_rearm_and_cancel() {
+        let wheel = TimerWheel::global();
+        let start = Instant::now();
+
+        // Re-armed twice, then done
+        let (fired, fires) = mpsc::channel();
+        let mut left = 3;
+        wheel.schedule(start + Duration::from_millis(30), move |due| {
+            fired.send((due, Instant::now())).unwrap();
+            left -= 1;
+            (left > 0).then(|| due + Duration::from_millis(20))
+        });
+        for millis in [30, 50, 70] {
+            let (due, at) = fires.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert_eq!(due, start + Duration::from_millis(millis));
+            assert!(at >= due, "fired early");
+        }
+
+        // Further away than one turn of the wheel
+        let (fired, fires) = mpsc::channel();
+        let later = Instant::now() + tick_offset(SLOTS + 3);
+        wheel.schedule(later, move |_| {
+            fired.send(Instant::now()).unwrap();
+            None
+        });
+        assert!(fires.recv_timeout(Duration::from_secs(15)).unwrap() >= later);
+
+        let cancelled = wheel.schedule(Instant::now() + Duration::from_secs(60), |_| None);
+        assert!(wheel.cancel(cancelled));
+        assert!(!wheel.cancel(cancelled));
+    }
+}
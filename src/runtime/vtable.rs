@@ -0,0 +1,359 @@
+//! Method dispatch tables for user-defined structs
+//!
+//! Every method name defined on a struct or as an interface default gets a
+//! selector, a small integer. Every struct gets a table indexed by selector
+//! holding the method it answers to: its own method, or else the default of the
+//! first interface (by name) it inherits one from. A call then costs one
+//! selector lookup and one index instead of a search of the struct's methods
+//! and every interface.
+//!
+//! Tables are built before a program runs, from the structs and interfaces it
+//! declares, and every method call in it is resolved to the selector of the
+//! method it names, keyed by the call's position. Selectors are never
+//! renumbered, so a struct declared while the program runs only rebuilds the
+//! tables.
+
+use crate::ast::nodes::*;
+use crate::lexer::token::Position;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The methods of one struct, indexed by selector
+#[derive(Debug, Clone, Default)]
+pub struct VTable {
+    methods: Vec<Option<Arc<FunctionDecl>>>,
+}
+
+impl VTable {
+    /// The method for `selector`, if the struct has one
+    pub fn get(&self, selector: usize) -> Option<&Arc<FunctionDecl>> {
+        self.methods.get(selector)?.as_ref()
+    }
+
+    fn set(&mut self, selector: usize, method: FunctionDecl) {
+        if self.methods.len() <= selector {
+            self.methods.resize(selector + 1, None);
+        }
+        if self.methods[selector].is_none() {
+            self.methods[selector] = Some(Arc::new(method));
+        }
+    }
+}
+
+/// A method call site as last dispatched: the receiver's struct and table, and
+/// the selector of the method called
+#[derive(Debug, Clone)]
+pub struct MethodSite {
+    receiver: String,
+    table: Arc<VTable>,
+    selector: usize,
+}
+
+impl MethodSite {
+    /// The method a call of `method` on a `struct_name` finds, when the site
+    /// last dispatched on that struct
+    pub fn get(&self, struct_name: &str, method: &str) -> Option<&Arc<FunctionDecl>> {
+        if self.receiver != struct_name {
+            return None;
+        }
+        self.table.get(self.selector).filter(|found| found.name == method)
+    }
+}
+
+/// Selectors, the tables of every struct and the selectors of method call sites
+#[derive(Debug, Clone, Default)]
+pub struct VTables {
+    selectors: HashMap<String, usize>,
+    tables: HashMap<String, Arc<VTable>>,
+    call_sites: HashMap<(usize, usize), usize>,
+}
+
+impl VTables {
+    /// Build the tables of `structs`, with the defaults they inherit from `interfaces`
+    pub fn build(structs: &HashMap<String, StructDecl>, interfaces: &HashMap<String, InterfaceDecl>) -> Self {
+        let mut vtables = VTables::default();
+        vtables.rebuild(structs, interfaces);
+        vtables
+    }
+
+    /// Replace the tables with those of `structs` and `interfaces`, keeping the
+    /// selectors already handed out
+    pub fn rebuild(&mut self, structs: &HashMap<String, StructDecl>, interfaces: &HashMap<String, InterfaceDecl>) {
+        let mut interfaces: Vec<&InterfaceDecl> = interfaces.values().collect();
+        interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+
+        self.tables.clear();
+        for (name, struct_decl) in structs {
+            let mut table = VTable::default();
+            for method in &struct_decl.methods {
+                let selector = self.intern(&method.name);
+                table.set(selector, method.clone());
+            }
+            for interface in &interfaces {
+                for method in interface.inherited_defaults(struct_decl) {
+                    let selector = self.intern(&method.name);
+                    table.set(selector, method);
+                }
+            }
+            self.tables.insert(name.clone(), Arc::new(table));
+        }
+    }
+
+    /// Resolve every method call in `program` to the selector of the method it
+    /// names. Calls of names no struct defines are left unresolved.
+    pub fn resolve_call_sites(&mut self, program: &Program) {
+        let mut resolver = CallSiteResolver { vtables: self };
+        for statement in &program.statements {
+            resolver.walk_statement(statement);
+        }
+    }
+
+    /// The selector of the method called at `position`, if it was resolved
+    pub fn call_site(&self, position: Position) -> Option<usize> {
+        self.call_sites.get(&(position.line, position.column)).copied()
+    }
+
+    fn intern(&mut self, method: &str) -> usize {
+        let next = self.selectors.len();
+        *self.selectors.entry(method.to_string()).or_insert(next)
+    }
+
+    /// The selector of a method name; names no struct defines have none
+    pub fn selector(&self, method: &str) -> Option<usize> {
+        self.selectors.get(method).copied()
+    }
+
+    /// The table of a struct
+    pub fn table(&self, struct_name: &str) -> Option<&Arc<VTable>> {
+        self.tables.get(struct_name)
+    }
+
+    /// Dispatch the call of `method` on a `struct_name` at `position`, through
+    /// the selector resolved for the call site, or the method's own selector
+    /// for calls analysis didn't see
+    pub fn dispatch(&self, position: Position, struct_name: &str, method: &str) -> Option<MethodSite> {
+        let table = self.tables.get(struct_name)?;
+        let selector = self
+            .call_site(position)
+            .filter(|&selector| table.get(selector).is_some_and(|found| found.name == method))
+            .or_else(|| self.selector(method))?;
+        table.get(selector)?;
+        Some(MethodSite {
+            receiver: struct_name.to_string(),
+            table: table.clone(),
+            selector,
+        })
+    }
+
+    /// The method a struct answers `method` with
+    pub fn lookup(&self, struct_name: &str, method: &str) -> Option<&Arc<FunctionDecl>> {
+        self.table(struct_name)?.get(self.selector(method)?)
+    }
+}
+
+/// Walks a program recording the selector of each `object.method(...)` call
+struct CallSiteResolver<'a> {
+    vtables: &'a mut VTables,
+}
+
+impl CallSiteResolver<'_> {
+    fn walk_block(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.walk_statement(statement);
+        }
+    }
+
+    fn walk_optional(&mut self, expression: Option<&Expression>) {
+        if let Some(expression) = expression {
+            self.walk_expression(expression);
+        }
+    }
+
+    fn walk_function(&mut self, decl: &FunctionDecl) {
+        for param in &decl.params {
+            self.walk_optional(param.default_value.as_ref());
+        }
+        self.walk_block(&decl.body.statements);
+    }
+
+    fn walk_channel_operation(&mut self, op: &ChannelOperation) {
+        self.walk_expression(&op.channel);
+        self.walk_optional(op.value.as_ref());
+    }
+
+    fn walk_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::VariableDecl(decl) => self.walk_optional(decl.initializer.as_ref()),
+            Statement::DestructuringDecl(decl) => self.walk_expression(&decl.initializer),
+            Statement::MultipleVariableDecl(decl) => {
+                for single in &decl.declarations {
+                    self.walk_optional(single.initializer.as_ref());
+                }
+            }
+            Statement::MultipleAssignment(stmt) => {
+                for expression in stmt.targets.iter().chain(&stmt.values) {
+                    self.walk_expression(expression);
+                }
+            }
+            Statement::FunctionDecl(decl) => self.walk_function(decl),
+            Statement::StructDecl(decl) => {
+                for method in &decl.methods {
+                    self.walk_function(method);
+                }
+            }
+            Statement::InterfaceDecl(decl) => {
+                for method in &decl.methods {
+                    if let Some(default_method) = method.default_method() {
+                        self.walk_function(&default_method);
+                    }
+                }
+            }
+            Statement::TypeAlias(_) | Statement::Import(_) => {}
+            Statement::If(stmt) => {
+                self.walk_expression(&stmt.condition);
+                self.walk_block(&stmt.then_branch.statements);
+                if let Some(else_branch) = &stmt.else_branch {
+                    self.walk_statement(else_branch);
+                }
+            }
+            Statement::While(stmt) => {
+                self.walk_expression(&stmt.condition);
+                self.walk_block(&stmt.body.statements);
+            }
+            Statement::For(stmt) => {
+                self.walk_expression(&stmt.iterable);
+                self.walk_block(&stmt.body.statements);
+            }
+            Statement::Match(stmt) => {
+                self.walk_expression(&stmt.expr);
+                for arm in &stmt.arms {
+                    self.walk_optional(arm.guard.as_ref());
+                    self.walk_statement(&arm.body);
+                }
+            }
+            Statement::Select(stmt) => {
+                for arm in &stmt.arms {
+                    if let Some(op) = &arm.channel_op {
+                        self.walk_channel_operation(op);
+                    }
+                    self.walk_statement(&arm.body);
+                }
+            }
+            Statement::Return(stmt) => self.walk_optional(stmt.value.as_ref()),
+            Statement::Break(_) | Statement::Continue(_) => {}
+            Statement::Defer(stmt) => self.walk_statement(&stmt.stmt),
+            Statement::Try(stmt) => {
+                self.walk_block(&stmt.body.statements);
+                if let Some(catch_clause) = &stmt.catch_clause {
+                    self.walk_block(&catch_clause.body.statements);
+                }
+            }
+            Statement::Fail(stmt) => self.walk_expression(&stmt.message),
+            Statement::Export(stmt) => self.walk_statement(&stmt.item),
+            Statement::Expression(stmt) => self.walk_expression(&stmt.expr),
+            Statement::Block(stmt) => self.walk_block(&stmt.statements),
+        }
+    }
+
+    fn walk_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Literal(_) | Expression::Identifier(_) => {}
+            Expression::Binary(expr) => {
+                self.walk_expression(&expr.left);
+                self.walk_expression(&expr.right);
+            }
+            Expression::Unary(expr) => self.walk_expression(&expr.operand),
+            Expression::Call(expr) => {
+                if let Expression::MemberAccess(member_access) = expr.callee.as_ref() {
+                    if let Some(selector) = self.vtables.selector(&member_access.member) {
+                        let position = member_access.position;
+                        self.vtables.call_sites.insert((position.line, position.column), selector);
+                    }
+                }
+                self.walk_expression(&expr.callee);
+                for arg in &expr.args {
+                    self.walk_expression(arg);
+                }
+            }
+            Expression::MemberAccess(expr) => self.walk_expression(&expr.object),
+            Expression::Index(expr) => {
+                self.walk_expression(&expr.object);
+                self.walk_expression(&expr.index);
+            }
+            Expression::Assignment(expr) => {
+                self.walk_expression(&expr.target);
+                self.walk_expression(&expr.value);
+            }
+            Expression::If(expr) => {
+                self.walk_expression(&expr.condition);
+                self.walk_expression(&expr.then_expr);
+                self.walk_expression(&expr.else_expr);
+            }
+            Expression::Match(expr) => {
+                self.walk_expression(&expr.expr);
+                for arm in &expr.arms {
+                    self.walk_optional(arm.guard.as_ref());
+                    self.walk_expression(&arm.expr);
+                }
+            }
+            Expression::Array(expr) => {
+                for element in &expr.elements {
+                    self.walk_expression(element);
+                }
+            }
+            Expression::Set(expr) => {
+                for element in &expr.elements {
+                    self.walk_expression(element);
+                }
+            }
+            Expression::Map(expr) => {
+                for entry in &expr.entries {
+                    self.walk_expression(&entry.key);
+                    self.walk_expression(&entry.value);
+                }
+            }
+            Expression::StructLiteral(expr) => {
+                for field in &expr.fields {
+                    self.walk_expression(&field.value);
+                }
+            }
+            Expression::Lambda(expr) => {
+                for param in &expr.params {
+                    self.walk_optional(param.default_value.as_ref());
+                }
+                self.walk_expression(&expr.body);
+            }
+            Expression::Async(expr) => self.walk_expression(&expr.expr),
+            Expression::Await(expr) => self.walk_expression(&expr.expr),
+            Expression::Run(expr) => self.walk_expression(&expr.expr),
+            Expression::Channel(expr) => {
+                self.walk_expression(&expr.channel);
+                self.walk_optional(expr.value.as_deref());
+            }
+            Expression::Select(expr) => {
+                for arm in &expr.arms {
+                    if let Some(op) = &arm.channel_op {
+                        self.walk_channel_operation(op);
+                    }
+                    self.walk_expression(&arm.expr);
+                }
+            }
+            Expression::Cast(expr) => self.walk_expression(&expr.expr),
+            Expression::TypeOf(expr) => self.walk_expression(&expr.expr),
+            Expression::Propagate(expr) => self.walk_expression(&expr.expr),
+            Expression::Range(expr) => {
+                self.walk_expression(&expr.start);
+                self.walk_expression(&expr.end);
+                self.walk_optional(expr.step.as_deref());
+            }
+            Expression::Yield(expr) => self.walk_optional(expr.value.as_deref()),
+            Expression::Parenthesized(expr) => self.walk_expression(&expr.expr),
+            Expression::Block(expr) => self.walk_block(&expr.statements),
+            Expression::Tuple(expr) => {
+                for element in &expr.elements {
+                    self.walk_expression(element);
+                }
+            }
+        }
+    }
+}
@@ -1,9 +1,46 @@
 // std.arrays module - Array operation utilities
 // Requirements: 7.1.4
+//
+//   import { sort, binarySearch, map, filter, reduce, zip } from "std/arrays"
+//
+//   let sorted = sort(scores, (a: int32, b: int32) => b - a)
+//   let names = map(users, (u: User) => u.name)          // []string
+//   let adults = filter(users, (u: User) => u.age >= 18)
+//   let total = reduce(scores, 0, (sum: int32, s: int32) => sum + s)
+//   let pairs = zip(names, scores)                       // [](string, int32), as long as the shorter
+//
+// Results are new arrays, slices when the first argument is a slice. The type
+// checker types them from the element type and the callbacks' return types.
 
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::hash::Hash;
 
+/// Functions the `std/arrays` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &[
+    "sort",
+    "sortStable",
+    "binarySearch",
+    "dedup",
+    "chunk",
+    "flatten",
+    "shuffle",
+    "map",
+    "filter",
+    "reduce",
+    "zip",
+];
+
+/// How many arguments the `std/arrays` function `name` takes
+pub fn arity(name: &str) -> Option<usize> {
+    match name {
+        "dedup" | "flatten" => Some(1),
+        "reduce" => Some(3),
+        _ if EXPORTED_FUNCTIONS.contains(&name) => Some(2),
+        _ => None,
+    }
+}
+
 /// Array manipulation utilities
 pub struct ArrayUtils;
 
@@ -272,11 +309,111 @@ impl ArrayUtils {
         result
     }
     
+    /// Shuffle array deterministically: the same seed always gives the same order
+    pub fn shuffle_seeded<T: Clone>(arr: &[T], seed: u64) -> Vec<T> {
+        let mut result = arr.to_vec();
+        crate::std::random::Random::with_seed(seed).shuffle(&mut result);
+        result
+    }
+    
+    /// Sort stably with a comparator that may fail. The first error aborts the sort.
+    ///
+    /// This is a merge sort of its own rather than `sort_by`, because the
+    /// comparator comes from user code: one that is not a total order gives
+    /// some permutation of the input instead of a panic.
+    pub fn try_sort_by<T: Clone, E, F>(arr: &[T], mut compare: F) -> Result<Vec<T>, E>
+    where
+        F: FnMut(&T, &T) -> Result<Ordering, E>,
+    {
+        // Sort indices so each pass moves integers rather than clones
+        let mut order: Vec<usize> = (0..arr.len()).collect();
+        let mut merged = Vec::with_capacity(arr.len());
+        let mut width = 1;
+        while width < order.len() {
+            merged.clear();
+            for start in (0..order.len()).step_by(2 * width) {
+                let middle = (start + width).min(order.len());
+                let end = (start + 2 * width).min(order.len());
+                let (mut left, mut right) = (start, middle);
+                while left < middle && right < end {
+                    // Ties take the left element, which keeps the sort stable
+                    if compare(&arr[order[left]], &arr[order[right]])? == Ordering::Greater {
+                        merged.push(order[right]);
+                        right += 1;
+                    } else {
+                        merged.push(order[left]);
+                        left += 1;
+                    }
+                }
+                merged.extend_from_slice(&order[left..middle]);
+                merged.extend_from_slice(&order[right..end]);
+            }
+            std::mem::swap(&mut order, &mut merged);
+            width *= 2;
+        }
+        Ok(order.into_iter().map(|index| arr[index].clone()).collect())
+    }
+    
+    /// Map with a fallible mapper, stopping at the first error
+    pub fn try_map<T, U, E, F>(arr: &[T], mapper: F) -> Result<Vec<U>, E>
+    where
+        F: FnMut(&T) -> Result<U, E>,
+    {
+        arr.iter().map(mapper).collect()
+    }
+    
+    /// Filter with a fallible predicate, stopping at the first error
+    pub fn try_filter<T: Clone, E, F>(arr: &[T], mut predicate: F) -> Result<Vec<T>, E>
+    where
+        F: FnMut(&T) -> Result<bool, E>,
+    {
+        let mut kept = Vec::new();
+        for item in arr {
+            if predicate(item)? {
+                kept.push(item.clone());
+            }
+        }
+        Ok(kept)
+    }
+    
+    /// Fold from the left with a fallible reducer, stopping at the first error
+    pub fn try_reduce<T, A, E, F>(arr: &[T], initial: A, reducer: F) -> Result<A, E>
+    where
+        F: FnMut(A, &T) -> Result<A, E>,
+    {
+        arr.iter().try_fold(initial, reducer)
+    }
+    
     /// Binary search in sorted array
     pub fn binary_search<T: Ord>(arr: &[T], item: &T) -> Result<usize, usize> {
         arr.binary_search(item)
     }
     
+    /// Binary search with a comparator that may fail. `compare` orders an
+    /// element relative to the target. Returns `Ok(Some(index))` when found.
+    pub fn try_binary_search_by<T, E, F>(arr: &[T], mut compare: F) -> Result<Option<usize>, E>
+    where
+        F: FnMut(&T) -> Result<Ordering, E>,
+    {
+        let (mut low, mut high) = (0, arr.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match compare(&arr[mid])? {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Ok(Some(mid)),
+            }
+        }
+        Ok(None)
+    }
+    
+    /// Remove consecutive duplicate elements
+    pub fn dedup<T: Clone + PartialEq>(arr: &[T]) -> Vec<T> {
+        let mut result = arr.to_vec();
+        result.dedup();
+        result
+    }
+    
     /// Get minimum element
     pub fn min<T: Ord>(arr: &[T]) -> Option<&T> {
         arr.iter().min()
@@ -450,6 +587,69 @@ mod tests {
         assert_eq!(chunks, vec![vec![1, 2, 3], vec![4, 5, 6], vec![7]]);
     }
     
+    #[test]
+    fn test_sorting_with_comparator() {
+        let arr = vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')];
+        let by_number = |a: &(i32, char), b: &(i32, char)| Ok::<_, ()>(a.0.cmp(&b.0));
+        
+        let stable = ArrayUtils::try_sort_by(&arr, by_number).unwrap();
+        assert_eq!(stable, vec![(1, 'b'), (1, 'd'), (2, 'a'), (2, 'c')]);
+        
+        let failing = ArrayUtils::try_sort_by(&arr, |_, _| Err::<Ordering, _>("boom"));
+        assert_eq!(failing, Err("boom"));
+        
+        // The comparator is not called again after it fails
+        let mut calls = 0;
+        let failing = ArrayUtils::try_sort_by(&(0..50).collect::<Vec<_>>(), |_, _| {
+            calls += 1;
+            if calls == 10 { Err("boom") } else { Ok(Ordering::Less) }
+        });
+        assert_eq!((failing, calls), (Err("boom"), 10));
+        
+        // A comparator that is not a total order still gives a permutation
+        let arr: Vec<i32> = (0..200).map(|i| (i * 37) % 101).collect();
+        let mut state = 17u32;
+        let mut sorted = ArrayUtils::try_sort_by(&arr, |_, _| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            Ok::<_, ()>([Ordering::Less, Ordering::Equal, Ordering::Greater][(state >> 16) as usize % 3])
+        })
+        .unwrap();
+        let mut expected = arr.clone();
+        sorted.sort();
+        expected.sort();
+        assert_eq!(sorted, expected);
+    }
+    
+    #[test]
+    fn test_binary_search_dedup_and_seeded_shuffle() {
+        let arr = vec![1, 3, 5, 7, 9];
+        assert_eq!(ArrayUtils::try_binary_search_by(&arr, |x| Ok::<_, ()>(x.cmp(&7))), Ok(Some(3)));
+        assert_eq!(ArrayUtils::try_binary_search_by(&arr, |x| Ok::<_, ()>(x.cmp(&4))), Ok(None));
+        
+        assert_eq!(ArrayUtils::dedup(&[1, 1, 2, 2, 1, 3, 3]), vec![1, 2, 1, 3]);
+        
+        let items: Vec<i32> = (0..20).collect();
+        let shuffled = ArrayUtils::shuffle_seeded(&items, 42);
+        assert_eq!(shuffled, ArrayUtils::shuffle_seeded(&items, 42));
+        assert_ne!(shuffled, items);
+        let mut sorted = shuffled.clone();
+        sorted.sort();
+        assert_eq!(sorted, items);
+    }
+    
+    #[test]
+    fn test_fallible_functional_operations() {
+        let arr = vec![1, 2, 3, 4];
+        assert_eq!(ArrayUtils::try_map(&arr, |x| Ok::<_, ()>(x * 10)), Ok(vec![10, 20, 30, 40]));
+        assert_eq!(ArrayUtils::try_filter(&arr, |x| Ok::<_, ()>(x % 2 == 1)), Ok(vec![1, 3]));
+        assert_eq!(ArrayUtils::try_reduce(&arr, 0, |sum, x| Ok::<_, ()>(sum + x)), Ok(10));
+
+        let failing = ArrayUtils::try_map(&arr, |&x| if x < 3 { Ok(x) } else { Err(x) });
+        assert_eq!(failing, Err(3));
+        assert_eq!(arity("reduce"), Some(3));
+        assert_eq!(arity("nothing"), None);
+    }
+    
     #[test]
     fn test_rotation() {
         let arr = vec![1, 2, 3, 4, 5];
@@ -0,0 +1,497 @@
+// std.binary module - Fixed-width integers, varints and struct packing over byte slices
+//
+//   import { readUint32BE, writeInt64LE, appendUvarint, pack, unpack } from "std/binary"
+//
+//   let length = readUint32BE(header, 0)
+//   buf = writeInt64LE(buf, 8, timestamp)
+//   let packet = pack(">HHI", 1, 2, 4096)
+//   let fields = unpack(">HHI", packet)
+//
+// Byte slices are immutable values, so writers return an updated copy. Every
+// access is bounds checked.
+//
+// Pack formats start with an optional byte order (`>` or `!` big endian, the
+// default, `<` little endian) followed by fields, each with an optional repeat
+// count:
+//
+//   b B   int8, uint8          h H   int16, uint16
+//   i I   int32, uint32        q Q   int64, uint64
+//   f d   float32, float64     ?     bool
+//   x     padding byte         Ns    string of N bytes, zero padded
+
+use crate::types::primitive::RuntimeValue;
+
+/// Functions the `std/binary` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &[
+    "readInt8", "readUint8", "writeInt8", "writeUint8",
+    "readInt16BE", "readInt16LE", "readUint16BE", "readUint16LE",
+    "readInt32BE", "readInt32LE", "readUint32BE", "readUint32LE",
+    "readInt64BE", "readInt64LE", "readUint64BE", "readUint64LE",
+    "readFloat32BE", "readFloat32LE", "readFloat64BE", "readFloat64LE",
+    "writeInt16BE", "writeInt16LE", "writeUint16BE", "writeUint16LE",
+    "writeInt32BE", "writeInt32LE", "writeUint32BE", "writeUint32LE",
+    "writeInt64BE", "writeInt64LE", "writeUint64BE", "writeUint64LE",
+    "writeFloat32BE", "writeFloat32LE", "writeFloat64BE", "writeFloat64LE",
+    "appendUvarint", "appendVarint", "readUvarint", "readVarint",
+    "pack", "unpack", "packedSize",
+];
+
+/// Byte order of multi-byte values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    Big,
+    Little,
+}
+
+/// A fixed-width value in a byte slice
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Int { size: usize, signed: bool },
+    Float { size: usize },
+    Bool,
+    /// A zero byte, skipped when unpacking
+    Padding,
+    /// A string stored in a fixed number of bytes
+    String(usize),
+}
+
+impl Field {
+    /// Number of bytes the field takes
+    pub fn size(&self) -> usize {
+        match self {
+            Field::Int { size, .. } | Field::Float { size } | Field::String(size) => *size,
+            Field::Bool | Field::Padding => 1,
+        }
+    }
+
+    /// Bulu type name of the field's values
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Field::Int { size: 1, signed: true } => "int8",
+            Field::Int { size: 2, signed: true } => "int16",
+            Field::Int { size: 4, signed: true } => "int32",
+            Field::Int { signed: true, .. } => "int64",
+            Field::Int { size: 1, .. } => "uint8",
+            Field::Int { size: 2, .. } => "uint16",
+            Field::Int { size: 4, .. } => "uint32",
+            Field::Int { .. } => "uint64",
+            Field::Float { size: 4 } => "float32",
+            Field::Float { .. } => "float64",
+            Field::Bool => "bool",
+            Field::Padding => "padding",
+            Field::String(_) => "string",
+        }
+    }
+}
+
+/// What a fixed-width accessor such as `readUint32BE` reads or writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Accessor {
+    pub write: bool,
+    pub field: Field,
+    pub order: ByteOrder,
+}
+
+/// The accessor an exported function name refers to
+pub fn accessor(name: &str) -> Option<Accessor> {
+    let (write, rest) = if let Some(rest) = name.strip_prefix("read") {
+        (false, rest)
+    } else {
+        (true, name.strip_prefix("write")?)
+    };
+    let (type_name, order) = if let Some(type_name) = rest.strip_suffix("BE") {
+        (type_name, ByteOrder::Big)
+    } else if let Some(type_name) = rest.strip_suffix("LE") {
+        (type_name, ByteOrder::Little)
+    } else {
+        (rest, ByteOrder::Big)
+    };
+    let field = match type_name {
+        "Int8" => Field::Int { size: 1, signed: true },
+        "Uint8" => Field::Int { size: 1, signed: false },
+        "Int16" => Field::Int { size: 2, signed: true },
+        "Uint16" => Field::Int { size: 2, signed: false },
+        "Int32" => Field::Int { size: 4, signed: true },
+        "Uint32" => Field::Int { size: 4, signed: false },
+        "Int64" => Field::Int { size: 8, signed: true },
+        "Uint64" => Field::Int { size: 8, signed: false },
+        "Float32" => Field::Float { size: 4 },
+        "Float64" => Field::Float { size: 8 },
+        _ => return None,
+    };
+    // Single bytes have no byte order, wider values always name one
+    if (field.size() == 1) != (rest == type_name) {
+        return None;
+    }
+    Some(Accessor { write, field, order })
+}
+
+/// A parsed pack format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Format {
+    pub order: ByteOrder,
+    pub fields: Vec<Field>,
+}
+
+impl Format {
+    /// Parse a format such as `<2Hq16s`
+    pub fn parse(format: &str) -> Result<Format, String> {
+        let mut chars = format.chars().peekable();
+        let order = match chars.peek() {
+            Some('<') => ByteOrder::Little,
+            _ => ByteOrder::Big,
+        };
+        if matches!(chars.peek(), Some('<' | '>' | '!')) {
+            chars.next();
+        }
+
+        let mut fields = Vec::new();
+        while let Some(c) = chars.next() {
+            if c.is_whitespace() {
+                continue;
+            }
+            let mut count = None;
+            let mut code = c;
+            if c.is_ascii_digit() {
+                let mut digits = c.to_string();
+                while let Some(&digit) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                    digits.push(digit);
+                    chars.next();
+                }
+                count = Some(digits.parse::<usize>().map_err(|_| format!("repeat count {} is too large", digits))?);
+                code = chars
+                    .next()
+                    .ok_or_else(|| format!("repeat count {} is not followed by a field", digits))?;
+            }
+            let field = match code {
+                'b' => Field::Int { size: 1, signed: true },
+                'B' => Field::Int { size: 1, signed: false },
+                'h' => Field::Int { size: 2, signed: true },
+                'H' => Field::Int { size: 2, signed: false },
+                'i' => Field::Int { size: 4, signed: true },
+                'I' => Field::Int { size: 4, signed: false },
+                'q' => Field::Int { size: 8, signed: true },
+                'Q' => Field::Int { size: 8, signed: false },
+                'f' => Field::Float { size: 4 },
+                'd' => Field::Float { size: 8 },
+                '?' => Field::Bool,
+                'x' => Field::Padding,
+                // The count of a string is its length, not a repetition
+                's' => {
+                    fields.push(Field::String(count.unwrap_or(1)));
+                    continue;
+                }
+                '<' | '>' | '!' => return Err(format!("byte order '{}' must come first", code)),
+                other => return Err(format!("unknown field code '{}'", other)),
+            };
+            fields.extend(std::iter::repeat_n(field, count.unwrap_or(1)));
+        }
+        Ok(Format { order, fields })
+    }
+
+    /// Number of bytes a packed value takes
+    pub fn size(&self) -> usize {
+        self.fields.iter().map(Field::size).sum()
+    }
+
+    /// Number of values packed or unpacked; padding takes none
+    pub fn value_count(&self) -> usize {
+        self.fields.iter().filter(|field| **field != Field::Padding).count()
+    }
+}
+
+/// Check that `size` bytes starting at `offset` lie within a slice of `len` bytes
+pub fn check_bounds(offset: i64, size: usize, len: usize) -> Result<usize, String> {
+    if offset < 0 || offset as u64 + size as u64 > len as u64 {
+        return Err(format!(
+            "offset {} out of bounds: need {} byte{}, slice has {}",
+            offset,
+            size,
+            if size == 1 { "" } else { "s" },
+            len
+        ));
+    }
+    Ok(offset as usize)
+}
+
+/// The bytes of a Bulu byte slice or array
+pub fn bytes_of(value: &RuntimeValue) -> Result<Vec<u8>, String> {
+    let items = match value {
+        RuntimeValue::Array(items) | RuntimeValue::Slice(items) => items,
+        RuntimeValue::String(text) => return Ok(text.as_bytes().to_vec()),
+        other => {
+            return Err(format!(
+                "expected a byte slice, got {}",
+                crate::runtime::builtins::runtime_type_name(other)
+            ))
+        }
+    };
+    items
+        .iter()
+        .map(|item| match integer_of(item) {
+            Some(byte) if (0..=255).contains(&byte) => Ok(byte as u8),
+            _ => Err(format!("expected a byte slice, found element {:?}", item)),
+        })
+        .collect()
+}
+
+/// A Bulu `[]byte` holding `bytes`
+pub fn byte_slice(bytes: Vec<u8>) -> RuntimeValue {
+    RuntimeValue::Slice(bytes.into_iter().map(RuntimeValue::UInt8).collect())
+}
+
+/// Any integer runtime value, widened so both int64 and uint64 fit
+pub fn integer_of(value: &RuntimeValue) -> Option<i128> {
+    match value {
+        RuntimeValue::Integer(i) | RuntimeValue::Int64(i) => Some(*i as i128),
+        RuntimeValue::Int8(i) => Some(*i as i128),
+        RuntimeValue::Int16(i) => Some(*i as i128),
+        RuntimeValue::Int32(i) => Some(*i as i128),
+        RuntimeValue::UInt8(i) | RuntimeValue::Byte(i) => Some(*i as i128),
+        RuntimeValue::UInt16(i) => Some(*i as i128),
+        RuntimeValue::UInt32(i) => Some(*i as i128),
+        RuntimeValue::UInt64(i) => Some(*i as i128),
+        _ => None,
+    }
+}
+
+/// Read a field at `offset`; the caller has checked the bounds
+pub fn read_field(bytes: &[u8], offset: usize, field: Field, order: ByteOrder) -> RuntimeValue {
+    let raw = &bytes[offset..offset + field.size()];
+    let unsigned = || {
+        let mut value: u64 = 0;
+        let mut push = |byte: &u8| value = (value << 8) | *byte as u64;
+        match order {
+            ByteOrder::Big => raw.iter().for_each(&mut push),
+            ByteOrder::Little => raw.iter().rev().for_each(&mut push),
+        }
+        value
+    };
+    match field {
+        Field::Int { size: 1, signed: true } => RuntimeValue::Int8(unsigned() as u8 as i8),
+        Field::Int { size: 2, signed: true } => RuntimeValue::Int16(unsigned() as u16 as i16),
+        Field::Int { size: 4, signed: true } => RuntimeValue::Int32(unsigned() as u32 as i32),
+        Field::Int { signed: true, .. } => RuntimeValue::Int64(unsigned() as i64),
+        Field::Int { size: 1, .. } => RuntimeValue::UInt8(unsigned() as u8),
+        Field::Int { size: 2, .. } => RuntimeValue::UInt16(unsigned() as u16),
+        Field::Int { size: 4, .. } => RuntimeValue::UInt32(unsigned() as u32),
+        Field::Int { .. } => RuntimeValue::UInt64(unsigned()),
+        Field::Float { size: 4 } => RuntimeValue::Float32(f32::from_bits(unsigned() as u32)),
+        Field::Float { .. } => RuntimeValue::Float64(f64::from_bits(unsigned())),
+        Field::Bool => RuntimeValue::Bool(raw[0] != 0),
+        Field::Padding => RuntimeValue::Null,
+        Field::String(_) => {
+            let end = raw.iter().position(|byte| *byte == 0).unwrap_or(raw.len());
+            RuntimeValue::String(String::from_utf8_lossy(&raw[..end]).into_owned())
+        }
+    }
+}
+
+/// Write a field at `offset`; the caller has checked the bounds
+pub fn write_field(
+    bytes: &mut [u8],
+    offset: usize,
+    field: Field,
+    order: ByteOrder,
+    value: &RuntimeValue,
+) -> Result<(), String> {
+    let size = field.size();
+    let bits: u64 = match field {
+        Field::Int { size, signed } => {
+            let n = integer_of(value)
+                .ok_or_else(|| format!("expected an integer for {}, got {:?}", field.type_name(), value))?;
+            let bits = size as u32 * 8;
+            let (min, max) = if signed {
+                (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+            } else {
+                (0, (1i128 << bits) - 1)
+            };
+            if n < min || n > max {
+                return Err(format!("value {} out of range for {}", n, field.type_name()));
+            }
+            n as u64
+        }
+        Field::Float { size } => {
+            let f = match value {
+                RuntimeValue::Float32(f) => *f as f64,
+                RuntimeValue::Float64(f) => *f,
+                other => integer_of(other)
+                    .ok_or_else(|| format!("expected a number for {}, got {:?}", field.type_name(), other))?
+                    as f64,
+            };
+            if size == 4 {
+                (f as f32).to_bits() as u64
+            } else {
+                f.to_bits()
+            }
+        }
+        Field::Bool => match value {
+            RuntimeValue::Bool(b) => *b as u64,
+            other => return Err(format!("expected a bool, got {:?}", other)),
+        },
+        Field::Padding => 0,
+        Field::String(size) => {
+            let text = match value {
+                RuntimeValue::String(text) => text,
+                other => return Err(format!("expected a string, got {:?}", other)),
+            };
+            if text.len() > size {
+                return Err(format!("string of {} bytes does not fit in {}s", text.len(), size));
+            }
+            let target = &mut bytes[offset..offset + size];
+            target.fill(0);
+            target[..text.len()].copy_from_slice(text.as_bytes());
+            return Ok(());
+        }
+    };
+    for index in 0..size {
+        let shift = match order {
+            ByteOrder::Big => (size - 1 - index) * 8,
+            ByteOrder::Little => index * 8,
+        };
+        bytes[offset + index] = (bits >> shift) as u8;
+    }
+    Ok(())
+}
+
+/// Pack values according to a format
+pub fn pack(format: &Format, values: &[RuntimeValue]) -> Result<Vec<u8>, String> {
+    if values.len() != format.value_count() {
+        return Err(format!("format packs {} values, got {}", format.value_count(), values.len()));
+    }
+    let mut bytes = vec![0; format.size()];
+    let mut offset = 0;
+    let mut values = values.iter();
+    for &field in &format.fields {
+        if field != Field::Padding {
+            write_field(&mut bytes, offset, field, format.order, values.next().unwrap())?;
+        }
+        offset += field.size();
+    }
+    Ok(bytes)
+}
+
+/// Unpack the values of a format from `bytes`, starting at `offset`
+pub fn unpack(format: &Format, bytes: &[u8], offset: i64) -> Result<Vec<RuntimeValue>, String> {
+    let mut offset = check_bounds(offset, format.size(), bytes.len())?;
+    let mut values = Vec::with_capacity(format.value_count());
+    for &field in &format.fields {
+        if field != Field::Padding {
+            values.push(read_field(bytes, offset, field, format.order));
+        }
+        offset += field.size();
+    }
+    Ok(values)
+}
+
+/// Append an unsigned LEB128 varint
+pub fn append_uvarint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Append a signed varint, zigzag encoded so small negative numbers stay short
+pub fn append_varint(bytes: &mut Vec<u8>, value: i64) {
+    append_uvarint(bytes, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+/// Read an unsigned varint at `offset`, returning it and the number of bytes it took
+pub fn read_uvarint(bytes: &[u8], offset: i64) -> Result<(u64, usize), String> {
+    let start = check_bounds(offset, 1, bytes.len())?;
+    let mut value: u64 = 0;
+    for (index, byte) in bytes[start..].iter().enumerate() {
+        if index == 9 && *byte > 1 {
+            return Err("varint overflows 64 bits".to_string());
+        }
+        value |= ((byte & 0x7f) as u64) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Ok((value, index + 1));
+        }
+    }
+    Err(format!("varint at offset {} runs past the end of the slice", start))
+}
+
+/// Read a zigzag encoded signed varint at `offset`
+pub fn read_varint(bytes: &[u8], offset: i64) -> Result<(i64, usize), String> {
+    let (value, length) = read_uvarint(bytes, offset)?;
+    Ok((((value >> 1) as i64) ^ -((value & 1) as i64), length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accessor_names() {
+        let accessor = accessor("writeInt64LE").unwrap();
+        assert!(accessor.write);
+        assert_eq!(accessor.field, Field::Int { size: 8, signed: true });
+        assert_eq!(accessor.order, ByteOrder::Little);
+        assert_eq!(super::accessor("readUint8").unwrap().field, Field::Int { size: 1, signed: false });
+        assert!(super::accessor("readUint8BE").is_none());
+        assert!(super::accessor("readUint32").is_none());
+        assert!(super::accessor("pack").is_none());
+    }
+
+    #[test]
+    fn test_fields_round_trip() {
+        let mut bytes = vec![0; 8];
+        let field = Field::Int { size: 4, signed: false };
+        write_field(&mut bytes, 2, field, ByteOrder::Big, &RuntimeValue::Integer(0x0102_0304)).unwrap();
+        assert_eq!(bytes, [0, 0, 1, 2, 3, 4, 0, 0]);
+        assert_eq!(read_field(&bytes, 2, field, ByteOrder::Big), RuntimeValue::UInt32(0x0102_0304));
+        assert_eq!(read_field(&bytes, 2, field, ByteOrder::Little), RuntimeValue::UInt32(0x0403_0201));
+
+        let signed = Field::Int { size: 2, signed: true };
+        write_field(&mut bytes, 0, signed, ByteOrder::Little, &RuntimeValue::Integer(-2)).unwrap();
+        assert_eq!(read_field(&bytes, 0, signed, ByteOrder::Little), RuntimeValue::Int16(-2));
+        assert!(write_field(&mut bytes, 0, signed, ByteOrder::Big, &RuntimeValue::Integer(40000)).is_err());
+        assert!(check_bounds(6, 4, bytes.len()).is_err());
+        assert!(check_bounds(-1, 1, bytes.len()).is_err());
+    }
+
+    #[test]
+    fn test_pack_formats() {
+        let format = Format::parse("<2Hx3s?").unwrap();
+        assert_eq!(format.size(), 9);
+        assert_eq!(format.value_count(), 4);
+        let values = [
+            RuntimeValue::Integer(1),
+            RuntimeValue::Integer(258),
+            RuntimeValue::String("ab".to_string()),
+            RuntimeValue::Bool(true),
+        ];
+        let bytes = pack(&format, &values).unwrap();
+        assert_eq!(bytes, [1, 0, 2, 1, 0, b'a', b'b', 0, 1]);
+        assert_eq!(
+            unpack(&format, &bytes, 0).unwrap(),
+            vec![
+                RuntimeValue::UInt16(1),
+                RuntimeValue::UInt16(258),
+                RuntimeValue::String("ab".to_string()),
+                RuntimeValue::Bool(true),
+            ]
+        );
+        assert!(Format::parse("H>").is_err());
+        assert!(Format::parse("3").is_err());
+        assert!(Format::parse("z").is_err());
+    }
+
+    #[test]
+    fn test_varints() {
+        let mut bytes = Vec::new();
+        append_uvarint(&mut bytes, 300);
+        append_varint(&mut bytes, -3);
+        assert_eq!(bytes, [0xac, 0x02, 0x05]);
+        assert_eq!(read_uvarint(&bytes, 0).unwrap(), (300, 2));
+        assert_eq!(read_varint(&bytes, 2).unwrap(), (-3, 1));
+        assert!(read_uvarint(&[0x80], 0).is_err());
+
+        let mut max = Vec::new();
+        append_varint(&mut max, i64::MIN);
+        assert_eq!(read_varint(&max, 0).unwrap(), (i64::MIN, 10));
+    }
+}
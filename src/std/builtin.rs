@@ -0,0 +1,63 @@
+// std.builtin module - The builtin functions of the prelude, for files that disable it
+//
+//   @no_prelude
+//
+//   import { println, len } from "std/builtin"
+//   import "std/builtin"                      // every builtin
+//
+// Builtins are in scope in every file unless the file starts with `@no_prelude`
+// or its package sets `prelude = false` in lang.toml. Such files can declare
+// their own `delete` or `print`, and import the builtins they want from here.
+// Imported builtins keep their names; they cannot be renamed with `as`.
+
+/// Functions the `std/builtin` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &[
+    // I/O functions
+    "print", "println", "printf", "input", "readLine", "readAll", "eprint", "eprintln",
+    // Type conversion functions
+    "int8", "int16", "int32", "int64", "uint8", "uint16", "uint32", "uint64",
+    "float32", "float64", "bool", "char", "string",
+    // Memory functions
+    "len", "cap", "clone", "sizeof",
+    // String functions
+    "ord", "chr",
+    // Collection functions
+    "make", "append", "copy", "delete",
+    // Utility functions
+    "typeof", "hash", "instanceof", "panic", "assert", "recover",
+    // Native backend functions
+    "intrinsic",
+    // Result and Option constructors
+    "Ok", "Err", "Some",
+    // Channel functions
+    "close",
+    // Synchronization functions
+    "lock", "sleep", "yield", "timer",
+    "atomic_load", "atomic_store", "atomic_add", "atomic_sub", "atomic_cas",
+    // OS functions
+    "args", "getEnv", "cwd", "exit", "waitForGoroutines",
+];
+
+/// Values the `std/builtin` module exports to Bulu programs
+pub const EXPORTED_VALUES: &[&str] = &["None"];
+
+/// The file attribute that disables the prelude
+pub const NO_PRELUDE: &str = "no_prelude";
+
+/// Whether `name` is brought into scope by the prelude
+pub fn is_prelude_name(name: &str) -> bool {
+    EXPORTED_FUNCTIONS.contains(&name) || EXPORTED_VALUES.contains(&name)
+}
+
+/// Whether an import path names this module
+pub fn is_module_path(path: &str) -> bool {
+    path == "std/builtin" || path == "std.builtin"
+}
+
+/// The error for using a builtin that a file without the prelude did not import
+pub fn not_imported_message(name: &str) -> String {
+    format!(
+        "'{}' is a builtin, and the prelude is disabled; import it from \"std/builtin\"",
+        name
+    )
+}
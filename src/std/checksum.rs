@@ -0,0 +1,300 @@
+// std.checksum module - Fast non-cryptographic checksums: CRC-32, CRC-32C and xxHash64
+//
+//   import { crc32, crc32c, xxhash64, newHasher } from "std/checksum"
+//
+//   let sum = crc32(payload)
+//   let key = xxhash64("some text", 42)
+//
+//   let hasher = newHasher("xxhash64")
+//   hasher.write(chunk)
+//   let digest = hasher.sum()
+//
+// These detect accidental corruption only; use std/crypto against tampering.
+
+use crate::types::primitive::RuntimeValue;
+use std::collections::HashMap;
+
+/// Functions the `std/checksum` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &["crc32", "crc32c", "xxhash64", "newHasher"];
+
+/// Name of the streaming hasher handle type
+pub const HASHER: &str = "Hasher";
+
+/// Algorithms `newHasher` accepts
+pub const ALGORITHMS: &[&str] = &["crc32", "crc32c", "xxhash64"];
+
+/// Lookup table for a reflected CRC-32 polynomial
+const fn crc_table(polynomial: u32) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ polynomial } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// CRC-32 (IEEE 802.3, as used by zip, gzip and PNG)
+static CRC32_TABLE: [u32; 256] = crc_table(0xEDB8_8320);
+/// CRC-32C (Castagnoli, as used by iSCSI, ext4 and SCTP)
+static CRC32C_TABLE: [u32; 256] = crc_table(0x82F6_3B78);
+
+fn crc_update(table: &[u32; 256], crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for byte in data {
+        crc = table[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// CRC-32 of `data`
+pub fn crc32(data: &[u8]) -> u32 {
+    crc_update(&CRC32_TABLE, 0, data)
+}
+
+/// CRC-32C of `data`
+pub fn crc32c(data: &[u8]) -> u32 {
+    crc_update(&CRC32C_TABLE, 0, data)
+}
+
+const PRIME64_1: u64 = 0x9E37_79B1_85EB_CA87;
+const PRIME64_2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const PRIME64_3: u64 = 0x1656_67B1_9E37_79F9;
+const PRIME64_4: u64 = 0x85EB_CA77_C2B2_AE63;
+const PRIME64_5: u64 = 0x27D4_EB2F_1656_67C5;
+
+fn xxh_round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(PRIME64_2))
+        .rotate_left(31)
+        .wrapping_mul(PRIME64_1)
+}
+
+fn xxh_merge(acc: u64, value: u64) -> u64 {
+    (acc ^ xxh_round(0, value)).wrapping_mul(PRIME64_1).wrapping_add(PRIME64_4)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+/// Incremental xxHash64
+#[derive(Debug, Clone)]
+pub struct Xxh64 {
+    seed: u64,
+    accumulators: [u64; 4],
+    buffer: Vec<u8>,
+    total_len: u64,
+}
+
+impl Xxh64 {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            accumulators: [
+                seed.wrapping_add(PRIME64_1).wrapping_add(PRIME64_2),
+                seed.wrapping_add(PRIME64_2),
+                seed,
+                seed.wrapping_sub(PRIME64_1),
+            ],
+            buffer: Vec::with_capacity(32),
+            total_len: 0,
+        }
+    }
+
+    fn consume_stripe(&mut self, stripe: &[u8]) {
+        for (index, acc) in self.accumulators.iter_mut().enumerate() {
+            *acc = xxh_round(*acc, read_u64(&stripe[index * 8..]));
+        }
+    }
+
+    pub fn write(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+        if !self.buffer.is_empty() {
+            let needed = 32 - self.buffer.len();
+            if data.len() < needed {
+                self.buffer.extend_from_slice(data);
+                return;
+            }
+            let mut stripe = std::mem::take(&mut self.buffer);
+            stripe.extend_from_slice(&data[..needed]);
+            self.consume_stripe(&stripe);
+            stripe.clear();
+            self.buffer = stripe;
+            data = &data[needed..];
+        }
+        let mut stripes = data.chunks_exact(32);
+        for stripe in &mut stripes {
+            self.consume_stripe(stripe);
+        }
+        self.buffer.extend_from_slice(stripes.remainder());
+    }
+
+    /// Hash of everything written so far
+    pub fn finish(&self) -> u64 {
+        let [v1, v2, v3, v4] = self.accumulators;
+        let mut hash = if self.total_len >= 32 {
+            let hash = v1
+                .rotate_left(1)
+                .wrapping_add(v2.rotate_left(7))
+                .wrapping_add(v3.rotate_left(12))
+                .wrapping_add(v4.rotate_left(18));
+            [v1, v2, v3, v4].into_iter().fold(hash, xxh_merge)
+        } else {
+            self.seed.wrapping_add(PRIME64_5)
+        };
+        hash = hash.wrapping_add(self.total_len);
+
+        let mut rest = self.buffer.as_slice();
+        while rest.len() >= 8 {
+            hash ^= xxh_round(0, read_u64(rest));
+            hash = hash.rotate_left(27).wrapping_mul(PRIME64_1).wrapping_add(PRIME64_4);
+            rest = &rest[8..];
+        }
+        if rest.len() >= 4 {
+            let word = u32::from_le_bytes(rest[..4].try_into().unwrap()) as u64;
+            hash ^= word.wrapping_mul(PRIME64_1);
+            hash = hash.rotate_left(23).wrapping_mul(PRIME64_2).wrapping_add(PRIME64_3);
+            rest = &rest[4..];
+        }
+        for byte in rest {
+            hash ^= (*byte as u64).wrapping_mul(PRIME64_5);
+            hash = hash.rotate_left(11).wrapping_mul(PRIME64_1);
+        }
+
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(PRIME64_2);
+        hash ^= hash >> 29;
+        hash = hash.wrapping_mul(PRIME64_3);
+        hash ^ (hash >> 32)
+    }
+}
+
+/// xxHash64 of `data`
+pub fn xxhash64(data: &[u8], seed: u64) -> u64 {
+    let mut hasher = Xxh64::new(seed);
+    hasher.write(data);
+    hasher.finish()
+}
+
+/// A checksum computed over data written in pieces
+#[derive(Debug, Clone)]
+pub enum Hasher {
+    Crc32(u32),
+    Crc32c(u32),
+    Xxhash64(Xxh64),
+}
+
+impl Hasher {
+    /// A hasher for one of `ALGORITHMS`; the seed only applies to xxhash64
+    pub fn new(algorithm: &str, seed: u64) -> Option<Self> {
+        match algorithm {
+            "crc32" => Some(Hasher::Crc32(0)),
+            "crc32c" => Some(Hasher::Crc32c(0)),
+            "xxhash64" => Some(Hasher::Xxhash64(Xxh64::new(seed))),
+            _ => None,
+        }
+    }
+
+    pub fn write(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Crc32(crc) => *crc = crc_update(&CRC32_TABLE, *crc, data),
+            Hasher::Crc32c(crc) => *crc = crc_update(&CRC32C_TABLE, *crc, data),
+            Hasher::Xxhash64(hasher) => hasher.write(data),
+        }
+    }
+
+    /// Checksum of everything written since creation or the last reset
+    pub fn sum(&self) -> u64 {
+        match self {
+            Hasher::Crc32(crc) | Hasher::Crc32c(crc) => *crc as u64,
+            Hasher::Xxhash64(hasher) => hasher.finish(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        match self {
+            Hasher::Crc32(crc) | Hasher::Crc32c(crc) => *crc = 0,
+            Hasher::Xxhash64(hasher) => *hasher = Xxh64::new(hasher.seed),
+        }
+    }
+}
+
+/// Hashers created through `newHasher`, keyed by handle ID
+#[derive(Debug, Default)]
+pub struct HasherRegistry {
+    hashers: HashMap<u64, Hasher>,
+    next_id: u64,
+}
+
+impl HasherRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a hasher and return its handle
+    pub fn create(&mut self, hasher: Hasher) -> RuntimeValue {
+        self.next_id += 1;
+        self.hashers.insert(self.next_id, hasher);
+        let mut fields = HashMap::new();
+        fields.insert("id".to_string(), RuntimeValue::UInt64(self.next_id));
+        RuntimeValue::Struct {
+            name: HASHER.to_string(),
+            fields,
+        }
+    }
+
+    /// The hasher behind a handle
+    pub fn get_mut(&mut self, fields: &HashMap<String, RuntimeValue>) -> Option<&mut Hasher> {
+        match fields.get("id") {
+            Some(RuntimeValue::UInt64(id)) => self.hashers.get_mut(id),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc_check_values() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn test_xxhash64_reference_values() {
+        assert_eq!(xxhash64(b"", 0), 0xEF46_DB37_51D8_E999);
+        assert_eq!(xxhash64(b"abc", 0), 0x44BC_2CF5_AD77_0999);
+        assert_eq!(xxhash64(b"Nobody inspects the spammish repetition", 0), 0xFBCE_A83C_8A37_8BF1);
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        let data: Vec<u8> = (0..200u32).map(|i| (i * 7 % 251) as u8).collect();
+        for algorithm in ALGORITHMS {
+            let mut whole = Hasher::new(algorithm, 9).unwrap();
+            whole.write(&data);
+            let mut pieces = Hasher::new(algorithm, 9).unwrap();
+            for chunk in data.chunks(13) {
+                pieces.write(chunk);
+            }
+            assert_eq!(whole.sum(), pieces.sum(), "{}", algorithm);
+            pieces.reset();
+            pieces.write(b"123456789");
+            let expected = match *algorithm {
+                "crc32" => crc32(b"123456789") as u64,
+                "crc32c" => crc32c(b"123456789") as u64,
+                _ => xxhash64(b"123456789", 9),
+            };
+            assert_eq!(pieces.sum(), expected, "{}", algorithm);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// std.collections module - deques, priority queues, ordered maps and
+// insertion-ordered maps and sets
+//
+//   import { newDeque, newPriorityQueue, newOrderedMap, newLinkedMap, newLinkedSet } from "std/collections"
+//
+//   let queue = newDeque<int32>()
+//   queue.pushBack(1)
+//   let tasks = newPriorityQueue<Task>(func(a: Task, b: Task): bool { return a.priority > b.priority })
+//   let index = newOrderedMap<string, int32>()
+//   for key, value in index { ... }             // ascending key order
+//   let headers = newLinkedMap<string, string>()
+//   for name, value in headers { ... }          // order the keys were first set
+//   let seen = newLinkedSet<string>()
+//   let both = seen.intersection(other)         // in the order of `seen`
+//   both.free()                                 // release it; the handle is invalid after
+//
+// The collections live in the runtime's CollectionRegistry; Bulu values are
+// small handles that carry the registry ID. Handles are plain values, so the
+// registry keeps a collection until `free()` is called on it.
+
+use crate::runtime::collections::CollectionId;
+use crate::types::primitive::RuntimeValue;
+use std::collections::HashMap;
+
+/// Functions the `std/collections` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &["newDeque", "newPriorityQueue", "newOrderedMap", "newLinkedMap", "newLinkedSet"];
+
+pub const DEQUE: &str = "Deque";
+pub const PRIORITY_QUEUE: &str = "PriorityQueue";
+pub const ORDERED_MAP: &str = "OrderedMap";
+pub const LINKED_MAP: &str = "LinkedMap";
+pub const LINKED_SET: &str = "LinkedSet";
+
+/// Names of the handle types, in the order of their type checker IDs
+pub const HANDLE_TYPES: &[&str] = &[DEQUE, PRIORITY_QUEUE, ORDERED_MAP, LINKED_MAP, LINKED_SET];
+
+/// Check whether a struct name is one of the std/collections handle types
+pub fn is_handle_type(name: &str) -> bool {
+    HANDLE_TYPES.contains(&name)
+}
+
+/// Number of type parameters of a handle type
+pub fn type_param_count(name: &str) -> usize {
+    if is_keyed(name) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Check whether a handle type maps keys to values, so `for key, value in m`
+/// iterates its entries
+pub fn is_keyed(name: &str) -> bool {
+    name == ORDERED_MAP || name == LINKED_MAP
+}
+
+/// Handle for the collection with the given registry ID
+pub fn handle(type_name: &str, id: CollectionId) -> RuntimeValue {
+    let mut fields = HashMap::new();
+    fields.insert("id".to_string(), RuntimeValue::UInt64(id as u64));
+    RuntimeValue::Struct {
+        name: type_name.to_string(),
+        fields,
+    }
+}
+
+/// Registry ID carried by a handle
+pub fn handle_id(fields: &HashMap<String, RuntimeValue>) -> Option<CollectionId> {
+    match fields.get("id") {
+        Some(RuntimeValue::UInt64(id)) => Some(*id as CollectionId),
+        _ => None,
+    }
+}
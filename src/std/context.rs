@@ -0,0 +1,43 @@
+// std.context module - cancellation, deadlines and request-scoped values
+//
+//   import { background, withTimeout } from "std/context"
+//
+//   let ctx = withTimeout(background(), 500)
+//   ctx.call(func() {
+//       run worker(jobs)   // inherits ctx
+//       let job = <-jobs   // fails with "context deadline exceeded" after 500ms
+//   })
+//
+// `background()` is the program's root context: it has no deadline and is
+// only cancelled when the program shuts down (`shutdown()` from std/os).
+// Contexts live in the runtime's ContextRegistry; Bulu values are small
+// handles that carry the registry ID.
+
+use crate::runtime::context::ContextId;
+use crate::types::primitive::RuntimeValue;
+use std::collections::HashMap;
+
+/// Functions the `std/context` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] =
+    &["background", "current", "withCancel", "withTimeout", "withDeadline", "withValue"];
+
+/// Name of the handle type
+pub const CONTEXT: &str = "Context";
+
+/// Handle for the context with the given registry ID
+pub fn handle(id: ContextId) -> RuntimeValue {
+    let mut fields = HashMap::new();
+    fields.insert("id".to_string(), RuntimeValue::UInt64(id as u64));
+    RuntimeValue::Struct {
+        name: CONTEXT.to_string(),
+        fields,
+    }
+}
+
+/// Registry ID carried by a handle
+pub fn handle_id(fields: &HashMap<String, RuntimeValue>) -> Option<ContextId> {
+    match fields.get("id") {
+        Some(RuntimeValue::UInt64(id)) => Some(*id as ContextId),
+        _ => None,
+    }
+}
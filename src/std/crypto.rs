@@ -1,10 +1,29 @@
 // Cryptography module for the Bulu programming language
 // Provides hashing functions and cryptographic operations
-
+//
+//   import { hmacSha256, encrypt, decrypt, argon2, randomBytes } from "std/crypto"
+//
+//   let mac = hmacSha256(key, message)
+//   let sealed = encrypt(key, plaintext).unwrap()     // nonce + ciphertext + tag
+//   let opened = decrypt(key, sealed).unwrap()
+//   let salt = randomBytes(16)
+//   let derived = argon2(password, salt, 32).unwrap()
+//
+// Every function takes byte slices, or strings as their UTF-8 bytes, and
+// returns byte slices. `encrypt` is AES-GCM with a 16 or 32 byte key; it
+// draws a fresh random 12 byte nonce for every message and puts it in front
+// of the ciphertext, where `decrypt` reads it back. Random nonces are safe for
+// about 2^32 messages under one key.
+
+use aes_gcm::aead::{Aead, OsRng, Payload};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::{AeadCore, Aes128Gcm, Aes256Gcm, KeyInit};
+use hmac::{Hmac, Mac};
 use md5;
 use sha1::{Digest as Sha1Digest, Sha1};
 use sha2::{Digest, Sha256, Sha512};
 use std::collections::HashMap;
+use subtle::ConstantTimeEq;
 
 /// Cryptographic hash algorithms supported by the language
 #[derive(Debug, Clone, PartialEq)]
@@ -127,6 +146,110 @@ impl CryptoContext {
     }
 }
 
+/// Functions the `std/crypto` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &[
+    "sha256",
+    "sha512",
+    "hmacSha256",
+    "encrypt",
+    "decrypt",
+    "pbkdf2",
+    "argon2",
+    "constantTimeEquals",
+    "randomBytes",
+];
+
+/// Length of the nonce `encrypt` puts in front of each ciphertext
+pub const NONCE_LEN: usize = 12;
+
+/// Length of the authentication tag at the end of each ciphertext
+pub const TAG_LEN: usize = 16;
+
+/// HMAC-SHA256 of `data` under `key`
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Encrypt and authenticate `plaintext` and `aad` with AES-GCM under a fresh
+/// random nonce; the result is the nonce, the ciphertext and the tag
+pub fn encrypt(key: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+    let payload = Payload { msg: plaintext, aad };
+    let (nonce, ciphertext) = match key.len() {
+        16 => {
+            let nonce = Aes128Gcm::generate_nonce(&mut OsRng);
+            (nonce, Aes128Gcm::new_from_slice(key).unwrap().encrypt(&nonce, payload))
+        }
+        32 => {
+            let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+            (nonce, Aes256Gcm::new_from_slice(key).unwrap().encrypt(&nonce, payload))
+        }
+        len => return Err(key_length_error(len)),
+    };
+    let ciphertext = ciphertext.map_err(|_| "plaintext is too long to encrypt".to_string())?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// Check and decrypt the output of `encrypt`; fails if the key or `aad`
+/// differ or a byte of `sealed` was changed
+pub fn decrypt(key: &[u8], sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < NONCE_LEN + TAG_LEN {
+        return Err(format!(
+            "ciphertext is {} bytes, shorter than its {} byte nonce and tag",
+            sealed.len(),
+            NONCE_LEN + TAG_LEN
+        ));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let payload = Payload { msg: ciphertext, aad };
+    let plaintext = match key.len() {
+        16 => Aes128Gcm::new_from_slice(key).unwrap().decrypt(nonce.into(), payload),
+        32 => Aes256Gcm::new_from_slice(key).unwrap().decrypt(nonce.into(), payload),
+        len => return Err(key_length_error(len)),
+    };
+    plaintext.map_err(|_| "message authentication failed".to_string())
+}
+
+fn key_length_error(len: usize) -> String {
+    format!("AES-GCM keys are 16 or 32 bytes, got {}", len)
+}
+
+/// PBKDF2-HMAC-SHA256 key of `length` bytes; `iterations` must be at least 1
+pub fn pbkdf2(password: &[u8], salt: &[u8], iterations: u32, length: usize) -> Vec<u8> {
+    let mut key = vec![0u8; length];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, iterations, &mut key);
+    key
+}
+
+/// Argon2id key of `length` bytes, with the recommended default cost
+/// (19 MiB of memory, 2 passes, 1 lane)
+pub fn argon2(password: &[u8], salt: &[u8], length: usize) -> Result<Vec<u8>, String> {
+    let mut key = vec![0u8; length];
+    argon2::Argon2::default()
+        .hash_password_into(password, salt, &mut key)
+        .map_err(|error| match error {
+            argon2::Error::SaltTooShort => format!("salt is {} bytes, the minimum is 8", salt.len()),
+            argon2::Error::OutputTooShort => format!("key length is {}, the minimum is 4", length),
+            error => error.to_string(),
+        })?;
+    Ok(key)
+}
+
+/// Whether `a` and `b` are equal, in time that depends on their lengths only
+pub fn constant_time_equals(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+/// `count` bytes from the operating system's secure random number generator
+pub fn random_bytes(count: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; count];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
 /// Built-in functions for cryptographic operations
 pub mod builtins {
     use super::*;
@@ -261,4 +384,49 @@ mod tests {
         assert!(algorithms.contains(&"sha256".to_string()));
         assert!(algorithms.contains(&"sha512".to_string()));
     }
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            hex::encode(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        for key in [[7u8; 16].as_slice(), [7u8; 32].as_slice()] {
+            let sealed = encrypt(key, b"attack at dawn", b"header").unwrap();
+            assert_eq!(sealed.len(), NONCE_LEN + 14 + TAG_LEN);
+            assert_eq!(decrypt(key, &sealed, b"header").unwrap(), b"attack at dawn");
+            // Every message gets its own nonce
+            assert_ne!(encrypt(key, b"attack at dawn", b"header").unwrap()[..NONCE_LEN], sealed[..NONCE_LEN]);
+
+            let mut tampered = sealed.clone();
+            tampered[NONCE_LEN] ^= 1;
+            assert_eq!(decrypt(key, &tampered, b"header").unwrap_err(), "message authentication failed");
+            assert_eq!(decrypt(key, &sealed, b"other").unwrap_err(), "message authentication failed");
+        }
+        assert_eq!(encrypt(&[0; 24], b"", b"").unwrap_err(), "AES-GCM keys are 16 or 32 bytes, got 24");
+        assert!(decrypt(&[0; 16], &[0; 20], b"").unwrap_err().starts_with("ciphertext is 20 bytes"));
+    }
+
+    #[test]
+    fn test_key_derivation() {
+        // RFC 7914, section 11
+        assert_eq!(
+            hex::encode(pbkdf2(b"passwd", b"salt", 1, 64)),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+             49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"
+        );
+        let key = argon2(b"password", b"somesalt", 32).unwrap();
+        assert_eq!(key.len(), 32);
+        assert_eq!(argon2(b"password", b"somesalt", 32).unwrap(), key);
+        assert_eq!(argon2(b"password", b"salt", 32).unwrap_err(), "salt is 4 bytes, the minimum is 8");
+
+        assert!(constant_time_equals(&key, &key.clone()));
+        assert!(!constant_time_equals(&key, &key[..31]));
+        assert_eq!(random_bytes(24).len(), 24);
+    }
 }
@@ -1,8 +1,11 @@
 // Database module for the Bulu programming language
 // Provides SQL database operations with connection pooling and transaction support
 
+use crate::types::primitive::RuntimeValue;
+use rusqlite::types::{ToSqlOutput, ValueRef};
+use rusqlite::Connection;
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex};
 use std::time::{Duration, Instant};
 
 /// Database connection configuration
@@ -156,7 +159,7 @@ impl DatabaseConnection {
 }
 
 /// SQL value types
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SqlValue {
     Null,
     Integer(i64),
@@ -382,6 +385,537 @@ pub struct PoolStats {
     pub max_connections: usize,
 }
 
+// SQLite driver for Bulu programs
+//
+//   import { open } from "std/db"
+//
+//   let db = open("app.db").unwrap()
+//   db.exec("CREATE TABLE users (name TEXT, age INTEGER)")
+//   let insert = db.prepare("INSERT INTO users VALUES (?, ?)").unwrap()
+//   insert.exec("ada", 36)
+//
+//   let tx = db.begin().unwrap()
+//   tx.exec("UPDATE users SET age = age + 1 WHERE name = ?", "ada")
+//   tx.commit()
+//
+//   for row in db.query("SELECT name, age FROM users WHERE age > ?", 30).unwrap() {
+//       println(row.getString("name"), row.getInt("age"))
+//   }
+//
+// A DB is a pool of connections that goroutines share: every call takes a
+// connection for as long as it runs, and a transaction keeps its connection
+// until it commits or rolls back. Statements are compiled once per connection
+// and cached there. An in-memory database (":memory:") lives in a single
+// connection, so its pool never grows past one.
+
+/// Functions the `std/db` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &["open"];
+
+/// Name of the database handle type
+pub const DB: &str = "DB";
+/// Name of the prepared statement handle type
+pub const STMT: &str = "Stmt";
+/// Name of the transaction handle type
+pub const TX: &str = "Tx";
+/// Name of the type of the rows a query returns
+pub const ROW: &str = "Row";
+
+/// Types of `std/db` values, in the order of their type IDs
+pub const TYPES: &[&str] = &[DB, STMT, TX, ROW];
+
+/// Whether `name` is one of the types of `std/db` values
+pub fn is_db_type(name: &str) -> bool {
+    TYPES.contains(&name)
+}
+
+/// Connections a DB opens at most, until the program calls `setMaxOpen`
+pub const DEFAULT_MAX_OPEN: usize = 4;
+
+/// How long a call waits for a free connection, or for another connection
+/// to unlock the database
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl rusqlite::ToSql for SqlValue {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::Borrowed(match self {
+            SqlValue::Null => ValueRef::Null,
+            SqlValue::Integer(i) => ValueRef::Integer(*i),
+            SqlValue::Float(f) => ValueRef::Real(*f),
+            SqlValue::Text(text) => ValueRef::Text(text.as_bytes()),
+            SqlValue::Boolean(b) => ValueRef::Integer(*b as i64),
+            SqlValue::Bytes(bytes) => ValueRef::Blob(bytes),
+        }))
+    }
+}
+
+impl From<ValueRef<'_>> for SqlValue {
+    fn from(value: ValueRef<'_>) -> Self {
+        match value {
+            ValueRef::Null => SqlValue::Null,
+            ValueRef::Integer(i) => SqlValue::Integer(i),
+            ValueRef::Real(f) => SqlValue::Float(f),
+            ValueRef::Text(text) => SqlValue::Text(String::from_utf8_lossy(text).into_owned()),
+            ValueRef::Blob(bytes) => SqlValue::Bytes(bytes.to_vec()),
+        }
+    }
+}
+
+/// A pool of connections to one SQLite database
+#[derive(Debug)]
+pub struct SqlitePool {
+    path: String,
+    state: Mutex<PoolState>,
+    released: Condvar,
+}
+
+#[derive(Debug)]
+struct PoolState {
+    idle: Vec<Connection>,
+    open: usize,
+    max_open: usize,
+    closed: bool,
+}
+
+impl SqlitePool {
+    /// Open the database file at `path`, creating it if needed
+    pub fn open(path: &str) -> Result<Arc<Self>, String> {
+        let connection = Self::connect(path)?;
+        Ok(Arc::new(Self {
+            path: path.to_string(),
+            state: Mutex::new(PoolState {
+                idle: vec![connection],
+                open: 1,
+                max_open: if Self::is_in_memory(path) { 1 } else { DEFAULT_MAX_OPEN },
+                closed: false,
+            }),
+            released: Condvar::new(),
+        }))
+    }
+
+    /// Each connection to an in-memory database has a database of its own
+    fn is_in_memory(path: &str) -> bool {
+        path.is_empty() || path == ":memory:"
+    }
+
+    fn connect(path: &str) -> Result<Connection, String> {
+        let connection =
+            Connection::open(path).map_err(|e| format!("cannot open database '{}': {}", path, e))?;
+        connection.busy_timeout(BUSY_TIMEOUT).map_err(|e| e.to_string())?;
+        Ok(connection)
+    }
+
+    /// Take a connection, opening one if none is idle and the pool may grow,
+    /// or else waiting for one to be released
+    pub fn acquire(self: &Arc<Self>) -> Result<PooledConnection, String> {
+        let deadline = Instant::now() + BUSY_TIMEOUT;
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.closed {
+                return Err("database is closed".to_string());
+            }
+            if let Some(connection) = state.idle.pop() {
+                return Ok(PooledConnection::new(self.clone(), connection));
+            }
+            if state.open < state.max_open {
+                state.open += 1;
+                drop(state);
+                return match Self::connect(&self.path) {
+                    Ok(connection) => Ok(PooledConnection::new(self.clone(), connection)),
+                    Err(e) => {
+                        self.state.lock().unwrap().open -= 1;
+                        self.released.notify_one();
+                        Err(e)
+                    }
+                };
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(format!(
+                    "timed out waiting for a connection, all {} are in use",
+                    state.max_open
+                ));
+            }
+            state = self.released.wait_timeout(state, deadline - now).unwrap().0;
+        }
+    }
+
+    /// Give a connection back, closing it if the pool has shrunk or closed
+    fn release(&self, connection: Connection) {
+        if !connection.is_autocommit() {
+            // A transaction that was never finished must not leak into the next user
+            let _ = connection.execute_batch("ROLLBACK");
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.closed || state.open > state.max_open {
+            state.open -= 1;
+        } else {
+            state.idle.push(connection);
+        }
+        self.released.notify_one();
+    }
+
+    /// Limit the connections the pool opens; in-memory databases keep one
+    pub fn set_max_open(&self, max_open: usize) {
+        if Self::is_in_memory(&self.path) {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.max_open = max_open.max(1);
+        while state.open > state.max_open && state.idle.pop().is_some() {
+            state.open -= 1;
+        }
+        self.released.notify_all();
+    }
+
+    /// Get pool statistics
+    pub fn stats(&self) -> PoolStats {
+        let state = self.state.lock().unwrap();
+        PoolStats {
+            total_connections: state.open,
+            active_connections: state.open - state.idle.len(),
+            idle_connections: state.idle.len(),
+            max_connections: state.max_open,
+        }
+    }
+
+    /// Close idle connections now and the others when they are released
+    pub fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        state.open -= state.idle.len();
+        state.idle.clear();
+        self.released.notify_all();
+    }
+}
+
+/// A connection taken from a pool, given back when dropped
+#[derive(Debug)]
+pub struct PooledConnection {
+    pool: Arc<SqlitePool>,
+    connection: Option<Connection>,
+}
+
+impl PooledConnection {
+    fn new(pool: Arc<SqlitePool>, connection: Connection) -> Self {
+        Self {
+            pool,
+            connection: Some(connection),
+        }
+    }
+}
+
+impl std::ops::Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.connection.as_ref().unwrap()
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            self.pool.release(connection);
+        }
+    }
+}
+
+/// The rows of a query, with the names of their columns
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rows {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<SqlValue>>,
+}
+
+/// Run a statement that returns no rows, and count the rows it changed
+pub fn execute(connection: &Connection, sql: &str, params: &[SqlValue]) -> Result<usize, String> {
+    let mut statement = connection.prepare_cached(sql).map_err(|e| e.to_string())?;
+    statement
+        .execute(rusqlite::params_from_iter(params))
+        .map_err(|e| e.to_string())
+}
+
+/// Run a statement and collect the rows it returns
+pub fn query(connection: &Connection, sql: &str, params: &[SqlValue]) -> Result<Rows, String> {
+    let mut statement = connection.prepare_cached(sql).map_err(|e| e.to_string())?;
+    let columns: Vec<String> = statement.column_names().into_iter().map(String::from).collect();
+    let mut rows = statement
+        .query(rusqlite::params_from_iter(params))
+        .map_err(|e| e.to_string())?;
+    let mut result = Vec::new();
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        let values = (0..columns.len())
+            .map(|index| row.get_ref(index).map(SqlValue::from))
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| e.to_string())?;
+        result.push(values);
+    }
+    Ok(Rows { columns, rows: result })
+}
+
+/// Compile a statement on a connection of the pool, to report errors in
+/// its SQL before it first runs
+pub fn prepare(pool: &Arc<SqlitePool>, sql: &str) -> Result<(), String> {
+    let connection = pool.acquire()?;
+    connection.prepare_cached(sql).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// A transaction, holding its connection until it commits or rolls back
+#[derive(Debug)]
+pub struct SqliteTransaction {
+    connection: Mutex<Option<PooledConnection>>,
+}
+
+impl SqliteTransaction {
+    /// Take a connection from the pool and begin a transaction on it
+    pub fn begin(pool: &Arc<SqlitePool>) -> Result<Self, String> {
+        let connection = pool.acquire()?;
+        connection.execute_batch("BEGIN").map_err(|e| e.to_string())?;
+        Ok(Self {
+            connection: Mutex::new(Some(connection)),
+        })
+    }
+
+    fn with_connection<T>(&self, run: impl FnOnce(&Connection) -> Result<T, String>) -> Result<T, String> {
+        match self.connection.lock().unwrap().as_ref() {
+            Some(connection) => run(connection),
+            None => Err("transaction has already been committed or rolled back".to_string()),
+        }
+    }
+
+    /// Run a statement that returns no rows within the transaction
+    pub fn execute(&self, sql: &str, params: &[SqlValue]) -> Result<usize, String> {
+        self.with_connection(|connection| execute(connection, sql, params))
+    }
+
+    /// Run a query within the transaction
+    pub fn query(&self, sql: &str, params: &[SqlValue]) -> Result<Rows, String> {
+        self.with_connection(|connection| query(connection, sql, params))
+    }
+
+    /// Commit the transaction; if that fails, it is rolled back
+    pub fn commit(&self) -> Result<(), String> {
+        self.finish("COMMIT")
+    }
+
+    /// Roll the transaction back
+    pub fn rollback(&self) -> Result<(), String> {
+        self.finish("ROLLBACK")
+    }
+
+    fn finish(&self, sql: &str) -> Result<(), String> {
+        let connection = self
+            .connection
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| "transaction has already been committed or rolled back".to_string())?;
+        // Returning the connection to the pool rolls back what did not commit
+        connection.execute_batch(sql).map_err(|e| e.to_string())
+    }
+}
+
+/// A Bulu value as an SQL parameter: numbers, strings, bools, byte slices,
+/// and None or Some(value)
+pub fn sql_value(value: &RuntimeValue) -> Result<SqlValue, String> {
+    Ok(match value {
+        RuntimeValue::Null => SqlValue::Null,
+        RuntimeValue::Bool(b) => SqlValue::Boolean(*b),
+        RuntimeValue::Float32(f) => SqlValue::Float(*f as f64),
+        RuntimeValue::Float64(f) => SqlValue::Float(*f),
+        RuntimeValue::String(text) => SqlValue::Text(text.clone()),
+        RuntimeValue::Char(c) => SqlValue::Text(c.to_string()),
+        RuntimeValue::Array(_) | RuntimeValue::Slice(_) => SqlValue::Bytes(crate::std::binary::bytes_of(value)?),
+        RuntimeValue::Struct { name, fields } if name == "Option" => match fields.get("isSome") {
+            Some(RuntimeValue::Bool(true)) => sql_value(fields.get("value").unwrap_or(&RuntimeValue::Null))?,
+            _ => SqlValue::Null,
+        },
+        other => match crate::std::binary::integer_of(other).map(i64::try_from) {
+            Some(Ok(i)) => SqlValue::Integer(i),
+            Some(Err(_)) => return Err(format!("{:?} does not fit in an SQLite integer", other)),
+            None => {
+                return Err(format!(
+                    "SQLite cannot store a value of type {}",
+                    crate::runtime::builtins::runtime_type_name(other)
+                ))
+            }
+        },
+    })
+}
+
+/// An SQL value as a Bulu value; blobs become byte slices
+pub fn runtime_value(value: SqlValue) -> RuntimeValue {
+    match value {
+        SqlValue::Null => RuntimeValue::Null,
+        SqlValue::Integer(i) => RuntimeValue::Int64(i),
+        SqlValue::Float(f) => RuntimeValue::Float64(f),
+        SqlValue::Text(text) => RuntimeValue::String(text),
+        SqlValue::Boolean(b) => RuntimeValue::Bool(b),
+        SqlValue::Bytes(bytes) => crate::std::binary::byte_slice(bytes),
+    }
+}
+
+/// The rows of a query as a slice of Row values
+pub fn rows_value(rows: Rows) -> RuntimeValue {
+    let columns = RuntimeValue::Slice(rows.columns.into_iter().map(RuntimeValue::String).collect());
+    RuntimeValue::Slice(
+        rows.rows
+            .into_iter()
+            .map(|values| {
+                let mut fields = HashMap::new();
+                fields.insert("columns".to_string(), columns.clone());
+                fields.insert(
+                    "values".to_string(),
+                    RuntimeValue::Slice(values.into_iter().map(runtime_value).collect()),
+                );
+                RuntimeValue::Struct {
+                    name: ROW.to_string(),
+                    fields,
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Call a method of a Row; columns are named or counted from 0
+pub fn row_method(fields: &HashMap<String, RuntimeValue>, method: &str, args: &[RuntimeValue]) -> Result<RuntimeValue, String> {
+    let (Some(RuntimeValue::Slice(columns)), Some(RuntimeValue::Slice(values))) =
+        (fields.get("columns"), fields.get("values"))
+    else {
+        return Err("invalid Row".to_string());
+    };
+    let column = match (method, args) {
+        ("columns", []) => return Ok(RuntimeValue::Slice(columns.clone())),
+        (_, [column]) => column,
+        _ => return Err(format!("unexpected {} arguments", args.len())),
+    };
+
+    let index = match column {
+        RuntimeValue::String(name) => columns
+            .iter()
+            .position(|column| matches!(column, RuntimeValue::String(column) if column == name))
+            .ok_or_else(|| format!("no column named '{}'", name))?,
+        other => crate::std::binary::integer_of(other)
+            .and_then(|index| usize::try_from(index).ok())
+            .filter(|index| *index < values.len())
+            .ok_or_else(|| format!("no column {:?} among {} columns", other, values.len()))?,
+    };
+    let value = &values[index];
+    let mismatch = |expected: &str| {
+        let name = match &columns[index] {
+            RuntimeValue::String(name) => name.clone(),
+            _ => index.to_string(),
+        };
+        format!("column '{}' is {}, not {}", name, sql_type_name(value), expected)
+    };
+
+    match (method, value) {
+        ("isNull", value) => Ok(RuntimeValue::Bool(matches!(value, RuntimeValue::Null))),
+        ("getInt", RuntimeValue::Int64(_)) => Ok(value.clone()),
+        ("getInt", _) => Err(mismatch("INTEGER")),
+        ("getFloat", RuntimeValue::Float64(_)) => Ok(value.clone()),
+        ("getFloat", RuntimeValue::Int64(i)) => Ok(RuntimeValue::Float64(*i as f64)),
+        ("getFloat", _) => Err(mismatch("REAL")),
+        ("getString", RuntimeValue::String(_)) => Ok(value.clone()),
+        ("getString", _) => Err(mismatch("TEXT")),
+        ("getBool", RuntimeValue::Int64(i)) => Ok(RuntimeValue::Bool(*i != 0)),
+        ("getBool", _) => Err(mismatch("INTEGER")),
+        ("getBytes", RuntimeValue::Slice(_)) => Ok(value.clone()),
+        ("getBytes", RuntimeValue::String(text)) => Ok(crate::std::binary::byte_slice(text.clone().into_bytes())),
+        ("getBytes", _) => Err(mismatch("BLOB")),
+        _ => Err(format!("unknown method '{}'", method)),
+    }
+}
+
+/// The SQLite storage class of a value of a Row
+fn sql_type_name(value: &RuntimeValue) -> &'static str {
+    match value {
+        RuntimeValue::Null => "NULL",
+        RuntimeValue::Int64(_) => "INTEGER",
+        RuntimeValue::Float64(_) => "REAL",
+        RuntimeValue::String(_) => "TEXT",
+        _ => "BLOB",
+    }
+}
+
+/// Databases, statements and transactions of Bulu programs, keyed by handle ID
+#[derive(Debug, Default)]
+pub struct SqliteRegistry {
+    databases: HashMap<u64, Arc<SqlitePool>>,
+    statements: HashMap<u64, (Arc<SqlitePool>, String)>,
+    transactions: HashMap<u64, Arc<SqliteTransaction>>,
+    next_id: u64,
+}
+
+impl SqliteRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn handle(&mut self, type_name: &str) -> (u64, RuntimeValue) {
+        self.next_id += 1;
+        let mut fields = HashMap::new();
+        fields.insert("id".to_string(), RuntimeValue::UInt64(self.next_id));
+        (
+            self.next_id,
+            RuntimeValue::Struct {
+                name: type_name.to_string(),
+                fields,
+            },
+        )
+    }
+
+    fn id(fields: &HashMap<String, RuntimeValue>) -> Option<u64> {
+        match fields.get("id") {
+            Some(RuntimeValue::UInt64(id)) => Some(*id),
+            _ => None,
+        }
+    }
+
+    /// Add a database and return its handle
+    pub fn create_database(&mut self, pool: Arc<SqlitePool>) -> RuntimeValue {
+        let (id, handle) = self.handle(DB);
+        self.databases.insert(id, pool);
+        handle
+    }
+
+    /// The pool behind a DB handle
+    pub fn database(&self, fields: &HashMap<String, RuntimeValue>) -> Option<Arc<SqlitePool>> {
+        self.databases.get(&Self::id(fields)?).cloned()
+    }
+
+    /// Add a prepared statement and return its handle
+    pub fn create_statement(&mut self, pool: Arc<SqlitePool>, sql: String) -> RuntimeValue {
+        let (id, handle) = self.handle(STMT);
+        self.statements.insert(id, (pool, sql));
+        handle
+    }
+
+    /// The pool and SQL behind a Stmt handle
+    pub fn statement(&self, fields: &HashMap<String, RuntimeValue>) -> Option<(Arc<SqlitePool>, String)> {
+        self.statements.get(&Self::id(fields)?).cloned()
+    }
+
+    /// Forget a Stmt handle
+    pub fn remove_statement(&mut self, fields: &HashMap<String, RuntimeValue>) {
+        if let Some(id) = Self::id(fields) {
+            self.statements.remove(&id);
+        }
+    }
+
+    /// Add a transaction and return its handle
+    pub fn create_transaction(&mut self, transaction: SqliteTransaction) -> RuntimeValue {
+        let (id, handle) = self.handle(TX);
+        self.transactions.insert(id, Arc::new(transaction));
+        handle
+    }
+
+    /// The transaction behind a Tx handle
+    pub fn transaction(&self, fields: &HashMap<String, RuntimeValue>) -> Option<Arc<SqliteTransaction>> {
+        self.transactions.get(&Self::id(fields)?).cloned()
+    }
+}
+
 /// Built-in functions for database operations
 pub mod builtins {
     use super::*;
@@ -571,4 +1105,58 @@ mod tests {
         
         assert_eq!(values.len(), 6);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_sqlite_pool_reuses_and_limits_connections() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = SqlitePool::open(dir.path().join("pool.db").to_str().unwrap()).unwrap();
+        pool.set_max_open(2);
+        let first = pool.acquire().unwrap();
+        let second = pool.acquire().unwrap();
+        assert_eq!(pool.stats().active_connections, 2);
+        drop(first);
+        let third = pool.acquire().unwrap();
+        assert_eq!(pool.stats().total_connections, 2);
+        drop((second, third));
+        assert_eq!(pool.stats().idle_connections, 2);
+
+        pool.close();
+        assert_eq!(pool.acquire().unwrap_err(), "database is closed");
+    }
+
+    #[test]
+    fn test_sqlite_round_trip() {
+        let pool = SqlitePool::open(":memory:").unwrap();
+        let connection = pool.acquire().unwrap();
+        execute(&connection, "CREATE TABLE t (a, b, c, d)", &[]).unwrap();
+        let params = [
+            SqlValue::Integer(1),
+            SqlValue::Boolean(true),
+            SqlValue::Null,
+            SqlValue::Bytes(vec![0, 255]),
+        ];
+        assert_eq!(execute(&connection, "INSERT INTO t VALUES (?, ?, ?, ?)", &params), Ok(1));
+        let rows = query(&connection, "SELECT * FROM t", &[]).unwrap();
+        assert_eq!(rows.columns, ["a", "b", "c", "d"]);
+        assert_eq!(
+            rows.rows,
+            [vec![SqlValue::Integer(1), SqlValue::Integer(1), SqlValue::Null, SqlValue::Bytes(vec![0, 255])]]
+        );
+        drop(connection);
+
+        // A transaction that is dropped unfinished rolls back
+        let transaction = SqliteTransaction::begin(&pool).unwrap();
+        transaction.execute("DELETE FROM t", &[]).unwrap();
+        drop(transaction);
+        let connection = pool.acquire().unwrap();
+        assert_eq!(query(&connection, "SELECT a FROM t", &[]).unwrap().rows.len(), 1);
+    }
+
+    #[test]
+    fn test_sql_values_of_bulu_values() {
+        assert_eq!(sql_value(&RuntimeValue::UInt8(7)), Ok(SqlValue::Integer(7)));
+        assert_eq!(sql_value(&RuntimeValue::String("x".to_string())), Ok(SqlValue::Text("x".to_string())));
+        assert!(sql_value(&RuntimeValue::UInt64(u64::MAX)).is_err());
+        assert!(sql_value(&RuntimeValue::Map(HashMap::new().into())).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+// std.encoding module - Base64, hex and URL percent-encoding of byte slices
+//
+//   import { base64Encode, base64Decode, hexEncode, urlEncode, urlDecode } from "std/encoding"
+//
+//   let token = base64Encode("hi?")                // "aGk/"
+//   let bytes = base64Decode(token).unwrap()       // [104, 105, 63]
+//   let digest = hexEncode(sum)                    // "9f86d081..."
+//   let query = "q=" + urlEncode("a&b c")          // "q=a%26b%20c"
+//   let text = urlDecode("a%26b%20c").unwrap()     // "a&b c"
+//
+// Encoders take byte slices, or strings as their UTF-8 bytes, and return
+// strings; decoders return a Result, with the byte offset of the first bad
+// character in the error. `base64Url*` use the URL-safe alphabet without
+// padding, and accept padded input when decoding. `urlEncode` escapes every
+// byte except the unreserved characters of RFC 3986 (letters, digits, `-`,
+// `.`, `_` and `~`), so its output is safe in any part of a URL.
+
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use percent_encoding::{percent_decode, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+/// Functions the `std/encoding` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &[
+    "base64Encode",
+    "base64Decode",
+    "base64UrlEncode",
+    "base64UrlDecode",
+    "hexEncode",
+    "hexDecode",
+    "urlEncode",
+    "urlDecode",
+];
+
+/// Bytes `url_encode` escapes: all but the RFC 3986 unreserved characters
+const URL_RESERVED: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// Standard base64, with padding
+pub fn base64_encode(data: &[u8]) -> String {
+    STANDARD.encode(data)
+}
+
+/// Decode standard base64; padding is required
+pub fn base64_decode(text: &str) -> Result<Vec<u8>, String> {
+    STANDARD.decode(text).map_err(base64_error)
+}
+
+/// URL-safe base64 (`-` and `_` for `+` and `/`), without padding
+pub fn base64_url_encode(data: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(data)
+}
+
+/// Decode URL-safe base64, with or without padding
+pub fn base64_url_decode(text: &str) -> Result<Vec<u8>, String> {
+    URL_SAFE_NO_PAD.decode(text.trim_end_matches('=')).map_err(base64_error)
+}
+
+fn base64_error(error: base64::DecodeError) -> String {
+    match error {
+        base64::DecodeError::InvalidByte(offset, byte) => {
+            format!("invalid base64 character {:?} at byte {}", byte as char, offset)
+        }
+        base64::DecodeError::InvalidLength => "invalid base64 length".to_string(),
+        base64::DecodeError::InvalidLastSymbol(offset, byte) => {
+            format!("invalid last base64 character {:?} at byte {}", byte as char, offset)
+        }
+        base64::DecodeError::InvalidPadding => "invalid base64 padding".to_string(),
+    }
+}
+
+/// Lowercase hex, two digits per byte
+pub fn hex_encode(data: &[u8]) -> String {
+    hex::encode(data)
+}
+
+/// Decode hex digits of either case
+pub fn hex_decode(text: &str) -> Result<Vec<u8>, String> {
+    hex::decode(text).map_err(|error| match error {
+        hex::FromHexError::InvalidHexCharacter { c, index } => {
+            format!("invalid hex character {:?} at byte {}", c, index)
+        }
+        hex::FromHexError::OddLength => "hex text has an odd number of digits".to_string(),
+        other => other.to_string(),
+    })
+}
+
+/// Percent-encode everything but the unreserved characters
+pub fn url_encode(text: &str) -> String {
+    utf8_percent_encode(text, URL_RESERVED).to_string()
+}
+
+/// Decode `%XX` escapes; other characters, `+` included, are kept as they are
+pub fn url_decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    // percent_decode passes malformed escapes through; reject them instead
+    for (offset, _) in text.match_indices('%') {
+        let escape = bytes.get(offset + 1..offset + 3);
+        if !escape.is_some_and(|digits| digits.iter().all(u8::is_ascii_hexdigit)) {
+            return Err(format!("invalid percent escape at byte {}", offset));
+        }
+    }
+    let decoded: Vec<u8> = percent_decode(bytes).collect();
+    String::from_utf8(decoded).map_err(|error| {
+        format!("decoded text is not UTF-8 at byte {}", error.utf8_error().valid_up_to())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        // RFC 4648, section 10
+        for (data, encoded) in [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foobar", "Zm9vYmFy")] {
+            assert_eq!(base64_encode(data.as_bytes()), encoded);
+            assert_eq!(base64_decode(encoded).unwrap(), data.as_bytes());
+        }
+        assert_eq!(base64_url_encode(&[0xfb, 0xff]), "-_8");
+        assert_eq!(base64_url_decode("-_8").unwrap(), [0xfb, 0xff]);
+        assert_eq!(base64_url_decode("-_8=").unwrap(), [0xfb, 0xff]);
+        assert_eq!(base64_decode("Zm9v!mFy").unwrap_err(), "invalid base64 character '!' at byte 4");
+        assert_eq!(base64_decode("Zg").unwrap_err(), "invalid base64 padding");
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex_encode(&[0, 0xab, 0x10]), "00ab10");
+        assert_eq!(hex_decode("00AB10").unwrap(), [0, 0xab, 0x10]);
+        assert_eq!(hex_decode("0g").unwrap_err(), "invalid hex character 'g' at byte 1");
+        assert_eq!(hex_decode("abc").unwrap_err(), "hex text has an odd number of digits");
+    }
+
+    #[test]
+    fn test_url() {
+        assert_eq!(url_encode("a&b c/ü~"), "a%26b%20c%2F%C3%BC~");
+        assert_eq!(url_decode("a%26b%20c%2F%C3%BC~").unwrap(), "a&b c/ü~");
+        assert_eq!(url_decode("a+b").unwrap(), "a+b");
+        assert_eq!(url_decode("100%").unwrap_err(), "invalid percent escape at byte 3");
+        assert_eq!(url_decode("%zz").unwrap_err(), "invalid percent escape at byte 0");
+        assert_eq!(url_decode("a%FF").unwrap_err(), "decoded text is not UTF-8 at byte 1");
+    }
+}
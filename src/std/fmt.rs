@@ -1,8 +1,198 @@
 // std.fmt module - String formatting operations
 // Requirements: 7.1.2
 
+use crate::types::primitive::format_float64;
 use std::collections::HashMap;
 
+/// Functions the `std/fmt` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &["formatNumber", "formatFixed", "formatPercent", "formatCurrency"];
+
+/// Locale used when a formatting call does not name one
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// Number formatting conventions of a locale. Group separators that are spaces
+/// in the locale's own conventions are plain ASCII spaces here.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locale {
+    pub code: &'static str,
+    pub decimal_separator: char,
+    pub group_separator: &'static str,
+    /// Size of the lowest digit group and of the groups above it (en-IN: 12,34,567)
+    pub primary_group: usize,
+    pub secondary_group: usize,
+    /// Digits needed above the lowest group before grouping starts (es-ES: 1234 but 12.345)
+    pub min_grouping_digits: usize,
+    /// Whether the currency symbol comes before the amount
+    pub currency_prefix: bool,
+    /// Whether a space separates the amount from the currency symbol
+    pub currency_space: bool,
+    /// Whether a space separates the number from the percent sign
+    pub percent_space: bool,
+}
+
+/// A currency and the number of minor-unit digits it is formatted with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Currency {
+    pub code: &'static str,
+    pub symbol: &'static str,
+    pub decimals: usize,
+}
+
+const EN_US: Locale = Locale {
+    code: "en-US",
+    decimal_separator: '.',
+    group_separator: ",",
+    primary_group: 3,
+    secondary_group: 3,
+    min_grouping_digits: 1,
+    currency_prefix: true,
+    currency_space: false,
+    percent_space: false,
+};
+
+const DE_DE: Locale = Locale {
+    code: "de-DE",
+    decimal_separator: ',',
+    group_separator: ".",
+    currency_prefix: false,
+    currency_space: true,
+    percent_space: true,
+    ..EN_US
+};
+
+/// Built-in locale data
+pub const LOCALES: &[Locale] = &[
+    EN_US,
+    Locale { code: "en-GB", ..EN_US },
+    Locale { code: "en-IN", secondary_group: 2, ..EN_US },
+    Locale { code: "ja-JP", ..EN_US },
+    DE_DE,
+    Locale { code: "de-CH", decimal_separator: '.', group_separator: "'", currency_prefix: true, percent_space: false, ..DE_DE },
+    Locale { code: "fr-FR", group_separator: " ", ..DE_DE },
+    Locale { code: "es-ES", min_grouping_digits: 2, ..DE_DE },
+    Locale { code: "it-IT", percent_space: false, ..DE_DE },
+    Locale { code: "pt-BR", currency_prefix: true, percent_space: false, ..DE_DE },
+];
+
+/// Built-in currency data
+pub const CURRENCIES: &[Currency] = &[
+    Currency { code: "USD", symbol: "$", decimals: 2 },
+    Currency { code: "EUR", symbol: "€", decimals: 2 },
+    Currency { code: "GBP", symbol: "£", decimals: 2 },
+    Currency { code: "JPY", symbol: "¥", decimals: 0 },
+    Currency { code: "INR", symbol: "₹", decimals: 2 },
+    Currency { code: "BRL", symbol: "R$", decimals: 2 },
+    Currency { code: "CHF", symbol: "CHF", decimals: 2 },
+    Currency { code: "CAD", symbol: "CA$", decimals: 2 },
+    Currency { code: "AUD", symbol: "A$", decimals: 2 },
+    Currency { code: "CNY", symbol: "CN¥", decimals: 2 },
+];
+
+/// Look up a locale by code. `de_DE` and `de-de` name the same locale, and a bare
+/// language such as `de` falls back to the first built-in locale for it.
+pub fn find_locale(code: &str) -> Option<&'static Locale> {
+    let code = code.replace('_', "-");
+    LOCALES
+        .iter()
+        .find(|locale| locale.code.eq_ignore_ascii_case(&code))
+        .or_else(|| {
+            LOCALES.iter().find(|locale| {
+                !code.contains('-') && locale.code.split('-').next().unwrap().eq_ignore_ascii_case(&code)
+            })
+        })
+}
+
+/// Look up a currency by its ISO 4217 code
+pub fn find_currency(code: &str) -> Option<&'static Currency> {
+    CURRENCIES.iter().find(|currency| currency.code.eq_ignore_ascii_case(code))
+}
+
+/// Insert the locale's group separators into a string of ASCII digits
+pub fn group_digits(digits: &str, locale: &Locale) -> String {
+    if digits.len() < locale.primary_group + locale.min_grouping_digits {
+        return digits.to_string();
+    }
+
+    let mut end = digits.len() - locale.primary_group;
+    let mut groups = vec![&digits[end..]];
+    while end > 0 {
+        let start = end.saturating_sub(locale.secondary_group);
+        groups.push(&digits[start..end]);
+        end = start;
+    }
+    groups.reverse();
+    groups.join(locale.group_separator)
+}
+
+/// Assemble a formatted number from its sign and digit strings
+fn assemble_number(negative: bool, integer: &str, fraction: &str, grouped: bool, locale: &Locale) -> String {
+    let mut result = String::new();
+    // A value that rounds to zero is printed without a sign
+    if negative && (integer.bytes().chain(fraction.bytes())).any(|digit| digit != b'0') {
+        result.push('-');
+    }
+    if grouped {
+        result.push_str(&group_digits(integer, locale));
+    } else {
+        result.push_str(integer);
+    }
+    if !fraction.is_empty() {
+        result.push(locale.decimal_separator);
+        result.push_str(fraction);
+    }
+    result
+}
+
+/// Format a float with the locale's separators. With `decimals`, the value is rounded
+/// to that many fraction digits; without, it is printed in its shortest exact form.
+pub fn format_float(value: f64, decimals: Option<usize>, grouped: bool, locale: &Locale) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        return if value < 0.0 { "-∞" } else { "∞" }.to_string();
+    }
+
+    let digits = match decimals {
+        Some(decimals) => format!("{:.*}", decimals, value.abs()),
+        None => value.abs().to_string(),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+    assemble_number(value.is_sign_negative(), integer, fraction, grouped, locale)
+}
+
+/// Format an integer exactly, padding `decimals` zero fraction digits
+pub fn format_integer(value: i64, decimals: usize, grouped: bool, locale: &Locale) -> String {
+    let integer = value.unsigned_abs().to_string();
+    assemble_number(value < 0, &integer, &"0".repeat(decimals), grouped, locale)
+}
+
+/// Format a ratio as a percentage, so 0.256 is 25.6%
+pub fn format_percent(value: f64, decimals: Option<usize>, locale: &Locale) -> String {
+    let number = format_float(value * 100.0, decimals, true, locale);
+    if locale.percent_space {
+        format!("{} %", number)
+    } else {
+        format!("{}%", number)
+    }
+}
+
+/// Format an amount of money with the currency's minor-unit digits and the locale's
+/// symbol placement, such as `-$1,234.50` or `-1.234,50 €`
+pub fn format_currency(amount: f64, currency: &Currency, locale: &Locale) -> String {
+    let number = format_float(amount, Some(currency.decimals), true, locale);
+    let (sign, number) = match number.strip_prefix('-') {
+        Some(number) => ("-", number),
+        None => ("", number.as_str()),
+    };
+    let space = if locale.currency_space { " " } else { "" };
+    if locale.currency_prefix {
+        format!("{}{}{}{}", sign, currency.symbol, space, number)
+    } else {
+        format!("{}{}{}{}", sign, number, space, currency.symbol)
+    }
+}
+
 /// Format specifier for different types
 #[derive(Debug, Clone)]
 pub enum FormatSpec {
@@ -96,7 +286,7 @@ pub fn format_value(value: &str, spec: &FormatSpec) -> String {
                     (Some(w), Some(p)) => format!("{:width$.precision$}", num, width = w, precision = p),
                     (Some(w), None) => format!("{:width$}", num, width = w),
                     (None, Some(p)) => format!("{:.precision$}", num, precision = p),
-                    (None, None) => num.to_string(),
+                    (None, None) => format_float64(num),
                 }
             } else {
                 value.to_string()
@@ -218,77 +408,248 @@ pub fn format_advanced(template: &str, args: &[String]) -> String {
     result
 }
 
-/// Sprintf-style formatting (C-style)
-pub fn sprintf(format: &str, args: &[String]) -> String {
-    let mut result = String::new();
-    let mut chars = format.chars().peekable();
-    let mut arg_index = 0;
-    
-    while let Some(ch) = chars.next() {
-        if ch == '%' {
-            if chars.peek() == Some(&'%') {
-                // Escaped percent
-                chars.next();
-                result.push('%');
-                continue;
-            }
-            
-            // Parse format specifier
-            let mut spec_str = String::new();
-            while let Some(&next_ch) = chars.peek() {
-                if "diouxXeEfFgGaAcspn%".contains(next_ch) {
-                    spec_str.push(chars.next().unwrap());
-                    break;
-                } else if "0123456789.-+ #".contains(next_ch) {
-                    spec_str.push(chars.next().unwrap());
+/// A conversion in a printf-style format string, e.g. `%-8.3f`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintfSpec {
+    pub left_align: bool,
+    pub zero_pad: bool,
+    pub plus_sign: bool,
+    pub space_sign: bool,
+    pub width: Option<usize>,
+    pub precision: Option<usize>,
+    pub verb: char,
+}
+
+/// A piece of a parsed printf-style format string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrintfPiece {
+    Literal(String),
+    Spec(PrintfSpec),
+}
+
+/// The kind of argument a printf verb consumes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrintfArg {
+    Integer,
+    Float,
+    String,
+    Char,
+    Bool,
+    Any,
+}
+
+impl PrintfArg {
+    /// Description used in argument mismatch errors
+    pub fn description(self) -> &'static str {
+        match self {
+            PrintfArg::Integer => "an integer",
+            PrintfArg::Float => "a number",
+            PrintfArg::String => "a string",
+            PrintfArg::Char => "a char",
+            PrintfArg::Bool => "a bool",
+            PrintfArg::Any => "any value",
+        }
+    }
+}
+
+/// Verbs understood by printf and sprintf
+pub const PRINTF_VERBS: &str = "dixXofFeEgGsctbv";
+
+impl PrintfSpec {
+    /// The kind of argument this conversion consumes
+    pub fn arg_kind(&self) -> PrintfArg {
+        match self.verb {
+            'd' | 'i' | 'x' | 'X' | 'o' => PrintfArg::Integer,
+            'f' | 'F' | 'e' | 'E' | 'g' | 'G' => PrintfArg::Float,
+            's' => PrintfArg::String,
+            'c' => PrintfArg::Char,
+            't' | 'b' => PrintfArg::Bool,
+            _ => PrintfArg::Any,
+        }
+    }
+
+    fn sign(&self, negative: bool) -> &'static str {
+        if negative {
+            "-"
+        } else if self.plus_sign {
+            "+"
+        } else if self.space_sign {
+            " "
+        } else {
+            ""
+        }
+    }
+
+    /// Pad a rendered argument out to the field width
+    pub fn pad(&self, body: &str) -> String {
+        let fill = match self.width {
+            Some(width) if width > body.chars().count() => width - body.chars().count(),
+            _ => return body.to_string(),
+        };
+
+        if self.left_align {
+            format!("{}{}", body, " ".repeat(fill))
+        } else if self.zero_pad && matches!(self.arg_kind(), PrintfArg::Integer | PrintfArg::Float) {
+            // Zeros go between the sign and the digits
+            let sign_len = if body.starts_with(['-', '+', ' ']) { 1 } else { 0 };
+            format!("{}{}{}", &body[..sign_len], "0".repeat(fill), &body[sign_len..])
+        } else {
+            format!("{}{}", " ".repeat(fill), body)
+        }
+    }
+
+    /// Render an integer argument; float verbs accept integers too
+    pub fn format_integer(&self, value: i128) -> String {
+        let magnitude = value.unsigned_abs();
+        let digits = match self.verb {
+            'x' => format!("{:x}", magnitude),
+            'X' => format!("{:X}", magnitude),
+            'o' => format!("{:o}", magnitude),
+            'f' | 'F' | 'e' | 'E' | 'g' | 'G' => return self.format_float(value as f64),
+            _ => magnitude.to_string(),
+        };
+        self.pad(&format!("{}{}", self.sign(value < 0), digits))
+    }
+
+    /// Render a float argument
+    pub fn format_float(&self, value: f64) -> String {
+        let negative = value.is_sign_negative() && !value.is_nan();
+        let magnitude = value.abs();
+        let digits = match self.verb {
+            'e' | 'E' => {
+                // C-style exponent: 1.500000e+03
+                let formatted = format!("{:.*e}", self.precision.unwrap_or(6), magnitude);
+                let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+                let exponent: i32 = exponent.parse().unwrap_or(0);
+                let digits = format!("{}e{}{:02}", mantissa, if exponent < 0 { '-' } else { '+' }, exponent.abs());
+                if self.verb == 'E' {
+                    digits.to_uppercase()
                 } else {
-                    break;
+                    digits
                 }
             }
-            
-            if arg_index < args.len() {
-                let formatted = match spec_str.chars().last() {
-                    Some('d') | Some('i') => {
-                        if let Ok(num) = args[arg_index].parse::<i64>() {
-                            num.to_string()
-                        } else {
-                            args[arg_index].clone()
-                        }
-                    },
-                    Some('f') | Some('F') => {
-                        if let Ok(num) = args[arg_index].parse::<f64>() {
-                            format!("{:.6}", num)
-                        } else {
-                            args[arg_index].clone()
-                        }
-                    },
-                    Some('x') => {
-                        if let Ok(num) = args[arg_index].parse::<i64>() {
-                            format!("{:x}", num)
-                        } else {
-                            args[arg_index].clone()
-                        }
-                    },
-                    Some('X') => {
-                        if let Ok(num) = args[arg_index].parse::<i64>() {
-                            format!("{:X}", num)
-                        } else {
-                            args[arg_index].clone()
-                        }
-                    },
-                    Some('s') | _ => args[arg_index].clone(),
+            'g' | 'G' => {
+                // Shortest representation, rounded to `precision` significant digits
+                let rounded = match self.precision {
+                    Some(precision) if magnitude.is_finite() => {
+                        format!("{:.*e}", precision.max(1) - 1, magnitude).parse().unwrap_or(magnitude)
+                    }
+                    _ => magnitude,
                 };
-                result.push_str(&formatted);
-                arg_index += 1;
-            } else {
-                result.push('%');
-                result.push_str(&spec_str);
+                let digits = format_float64(rounded);
+                if self.verb == 'G' {
+                    digits.to_uppercase()
+                } else {
+                    digits
+                }
             }
-        } else {
-            result.push(ch);
+            _ => format!("{:.*}", self.precision.unwrap_or(6), magnitude),
+        };
+        self.pad(&format!("{}{}", self.sign(negative), digits))
+    }
+
+    /// Render a string argument; the precision truncates it
+    pub fn format_str(&self, value: &str) -> String {
+        match self.precision {
+            Some(precision) => self.pad(&value.chars().take(precision).collect::<String>()),
+            None => self.pad(value),
+        }
+    }
+}
+
+/// Parse a printf-style format string into literal text and conversions
+pub fn parse_printf(format: &str) -> Result<Vec<PrintfPiece>, String> {
+    let mut pieces = Vec::new();
+    let mut literal = String::new();
+    let mut chars = format.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            literal.push(ch);
+            continue;
+        }
+        if chars.peek() == Some(&'%') {
+            chars.next();
+            literal.push('%');
+            continue;
+        }
+
+        let mut spec = PrintfSpec {
+            left_align: false,
+            zero_pad: false,
+            plus_sign: false,
+            space_sign: false,
+            width: None,
+            precision: None,
+            verb: 'v',
+        };
+        while let Some(&flag) = chars.peek() {
+            match flag {
+                '-' => spec.left_align = true,
+                '0' => spec.zero_pad = true,
+                '+' => spec.plus_sign = true,
+                ' ' => spec.space_sign = true,
+                _ => break,
+            }
+            chars.next();
+        }
+
+        let read_number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+            let mut digits = String::new();
+            while let Some(digit) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                digits.push(*digit);
+                chars.next();
+            }
+            digits.parse::<usize>().ok()
+        };
+        spec.width = read_number(&mut chars);
+        if chars.peek() == Some(&'.') {
+            chars.next();
+            spec.precision = Some(read_number(&mut chars).unwrap_or(0));
+        }
+
+        match chars.next() {
+            Some(verb) if PRINTF_VERBS.contains(verb) => spec.verb = verb,
+            Some(verb) => return Err(format!("unknown format verb '%{}'", verb)),
+            None => return Err("format string ends with an incomplete '%' specifier".to_string()),
+        }
+
+        if !literal.is_empty() {
+            pieces.push(PrintfPiece::Literal(std::mem::take(&mut literal)));
+        }
+        pieces.push(PrintfPiece::Spec(spec));
+    }
+
+    if !literal.is_empty() {
+        pieces.push(PrintfPiece::Literal(literal));
+    }
+    Ok(pieces)
+}
+
+/// Sprintf-style formatting (C-style) over pre-rendered arguments
+pub fn sprintf(format: &str, args: &[String]) -> String {
+    let Ok(pieces) = parse_printf(format) else {
+        return format.to_string();
+    };
+
+    let mut result = String::new();
+    let mut args = args.iter();
+    for piece in pieces {
+        match piece {
+            PrintfPiece::Literal(text) => result.push_str(&text),
+            PrintfPiece::Spec(spec) => match args.next() {
+                Some(arg) => {
+                    let formatted = match spec.arg_kind() {
+                        PrintfArg::Integer => arg.parse::<i128>().map(|i| spec.format_integer(i)).ok(),
+                        PrintfArg::Float => arg.parse::<f64>().map(|f| spec.format_float(f)).ok(),
+                        _ => None,
+                    };
+                    result.push_str(&formatted.unwrap_or_else(|| spec.format_str(arg)));
+                }
+                None => result.push_str(&format!("%!{}(MISSING)", spec.verb)),
+            },
         }
     }
-    
     result
 }
 
@@ -342,6 +703,30 @@ mod tests {
         assert!(result.contains("String: hello"));
     }
     
+    #[test]
+    fn test_parse_printf() {
+        let pieces = parse_printf("id=%-5d %08.3f%%").unwrap();
+        assert_eq!(pieces.len(), 5);
+        assert_eq!(pieces[0], PrintfPiece::Literal("id=".to_string()));
+        let PrintfPiece::Spec(spec) = &pieces[1] else {
+            panic!("Expected a conversion");
+        };
+        assert!(spec.left_align);
+        assert_eq!((spec.width, spec.precision, spec.verb), (Some(5), None, 'd'));
+        let PrintfPiece::Spec(spec) = &pieces[3] else {
+            panic!("Expected a conversion");
+        };
+        assert!(spec.zero_pad);
+        assert_eq!((spec.width, spec.precision, spec.verb), (Some(8), Some(3), 'f'));
+        assert_eq!(pieces[4], PrintfPiece::Literal("%".to_string()));
+
+        assert_eq!(parse_printf("%q").unwrap_err(), "unknown format verb '%q'");
+        assert!(parse_printf("50%").is_err());
+        assert_eq!(sprintf("[%5s|%-5s|%.2s]", &["ab".into(), "cd".into(), "xyz".into()]), "[   ab|cd   |xy]");
+        assert_eq!(sprintf("%05d %+d %x %e", &["-42".into(), "7".into(), "255".into(), "1500".into()]), "-0042 +7 ff 1.500000e+03");
+        assert_eq!(sprintf("%d %d", &["1".into()]), "1 %!d(MISSING)");
+    }
+
     #[test]
     fn test_format_specs() {
         let spec = parse_format_spec("05d");
@@ -361,6 +746,59 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_locale_lookup() {
+        assert_eq!(find_locale("de_DE").unwrap().code, "de-DE");
+        assert_eq!(find_locale("EN-gb").unwrap().code, "en-GB");
+        assert_eq!(find_locale("fr").unwrap().code, "fr-FR");
+        assert!(find_locale("xx-YY").is_none());
+        assert_eq!(find_currency("eur").unwrap().symbol, "€");
+        assert!(find_currency("XYZ").is_none());
+    }
+
+    #[test]
+    fn test_grouping() {
+        let us = find_locale("en-US").unwrap();
+        assert_eq!(group_digits("1234567", us), "1,234,567");
+        assert_eq!(group_digits("123", us), "123");
+        assert_eq!(group_digits("1234", us), "1,234");
+        assert_eq!(group_digits("1234567", find_locale("en-IN").unwrap()), "12,34,567");
+        let es = find_locale("es-ES").unwrap();
+        assert_eq!(group_digits("1234", es), "1234");
+        assert_eq!(group_digits("12345", es), "12.345");
+    }
+
+    #[test]
+    fn test_locale_number_formatting() {
+        let us = find_locale("en-US").unwrap();
+        let de = find_locale("de-DE").unwrap();
+        assert_eq!(format_float(1234567.891, Some(2), true, us), "1,234,567.89");
+        assert_eq!(format_float(1234567.891, Some(2), true, de), "1.234.567,89");
+        assert_eq!(format_float(1234.5, None, true, us), "1,234.5");
+        assert_eq!(format_float(1234.5, Some(2), false, de), "1234,50");
+        assert_eq!(format_float(-0.001, Some(2), true, us), "0.00");
+        assert_eq!(format_float(f64::NEG_INFINITY, None, true, us), "-∞");
+        assert_eq!(format_integer(-9007199254740993, 0, true, us), "-9,007,199,254,740,993");
+        assert_eq!(format_integer(42, 2, true, de), "42,00");
+    }
+
+    #[test]
+    fn test_percent_and_currency_formatting() {
+        let us = find_locale("en-US").unwrap();
+        let de = find_locale("de-DE").unwrap();
+        let fr = find_locale("fr-FR").unwrap();
+        assert_eq!(format_percent(0.256, Some(1), us), "25.6%");
+        assert_eq!(format_percent(0.256, Some(1), de), "25,6 %");
+
+        let usd = find_currency("USD").unwrap();
+        let eur = find_currency("EUR").unwrap();
+        let jpy = find_currency("JPY").unwrap();
+        assert_eq!(format_currency(-1234.5, usd, us), "-$1,234.50");
+        assert_eq!(format_currency(-1234.5, eur, de), "-1.234,50 €");
+        assert_eq!(format_currency(1234567.0, eur, fr), "1 234 567,00 €");
+        assert_eq!(format_currency(1234.5, jpy, find_locale("ja-JP").unwrap()), "¥1,234");
+    }
+
     #[test]
     fn test_pretty_print() {
         let input = "line1\nline2\nline3";
@@ -0,0 +1,345 @@
+// std.fs module - File and directory access
+//
+//   import { open, readFile, writeFile, readDir, join } from "std/fs"
+//
+//   let written = writeFile(join("out", "notes.txt"), "hello\n")
+//   if written.isError() {
+//       println(written.error())
+//   }
+//   let text = readFile("out/notes.txt").unwrapOr("")
+//
+//   let log = open("out/log.txt", "a").unwrap()
+//   log.write("started\n")
+//   log.close()
+//
+// Operations that touch the file system return a Result whose error is a
+// message naming the path; the path helpers only work on the strings.
+
+use crate::types::primitive::RuntimeValue;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// Functions the `std/fs` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &[
+    "open",
+    "readFile",
+    "writeFile",
+    "appendFile",
+    "readDir",
+    "mkdirAll",
+    "remove",
+    "removeAll",
+    "copy",
+    "metadata",
+    "exists",
+    "join",
+    "baseName",
+    "dirName",
+    "extension",
+    "absPath",
+];
+
+/// Name of the open file handle type
+pub const FILE: &str = "File";
+
+/// Name of the type `metadata` returns
+pub const FILE_INFO: &str = "FileInfo";
+
+/// Modes of `open`: "r", "w" (create or truncate), "a" (create and append)
+/// and "rw" (read and write an existing file)
+pub const OPEN_MODES: &[&str] = &["r", "w", "a", "rw"];
+
+fn open_options(mode: &str) -> Option<OpenOptions> {
+    let mut options = OpenOptions::new();
+    match mode {
+        "r" => options.read(true),
+        "w" => options.write(true).create(true).truncate(true),
+        "a" => options.append(true).create(true),
+        "rw" => options.read(true).write(true),
+        _ => return None,
+    };
+    Some(options)
+}
+
+/// Files opened through `open`, keyed by handle ID
+#[derive(Debug, Default)]
+pub struct FileRegistry {
+    files: HashMap<u64, File>,
+    next_id: u64,
+}
+
+impl FileRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open a file and return its handle
+    pub fn open(&mut self, path: &str, mode: &str) -> Result<RuntimeValue, String> {
+        let options = open_options(mode).ok_or_else(|| {
+            format!("unknown mode '{}', expected one of {}", mode, OPEN_MODES.join(", "))
+        })?;
+        let file = options.open(path).map_err(|e| io_error(path, e))?;
+        self.next_id += 1;
+        self.files.insert(self.next_id, file);
+        let mut fields = HashMap::new();
+        fields.insert("id".to_string(), RuntimeValue::UInt64(self.next_id));
+        Ok(RuntimeValue::Struct {
+            name: FILE.to_string(),
+            fields,
+        })
+    }
+
+    /// The file behind a handle, unless it was closed
+    pub fn get_mut(&mut self, fields: &HashMap<String, RuntimeValue>) -> Option<&mut File> {
+        self.files.get_mut(&handle_id(fields)?)
+    }
+
+    /// Close the file behind a handle; closing it again does nothing
+    pub fn close(&mut self, fields: &HashMap<String, RuntimeValue>) {
+        if let Some(id) = handle_id(fields) {
+            self.files.remove(&id);
+        }
+    }
+}
+
+fn handle_id(fields: &HashMap<String, RuntimeValue>) -> Option<u64> {
+    match fields.get("id") {
+        Some(RuntimeValue::UInt64(id)) => Some(*id),
+        _ => None,
+    }
+}
+
+fn io_error(path: &str, error: std::io::Error) -> String {
+    format!("{}: {}", path, error)
+}
+
+/// Read the rest of an open file
+pub fn read_to_end(file: &mut File) -> Result<String, String> {
+    let mut text = String::new();
+    file.read_to_string(&mut text).map_err(|e| e.to_string())?;
+    Ok(text)
+}
+
+/// Write all of `data` to an open file
+pub fn write_all(file: &mut File, data: &[u8]) -> Result<(), String> {
+    file.write_all(data).map_err(|e| e.to_string())
+}
+
+pub fn read_file(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| io_error(path, e))
+}
+
+/// Replace the contents of a file, creating it if needed
+pub fn write_file(path: &str, data: &[u8]) -> Result<(), String> {
+    fs::write(path, data).map_err(|e| io_error(path, e))
+}
+
+/// Append to a file, creating it if needed
+pub fn append_file(path: &str, data: &[u8]) -> Result<(), String> {
+    let mut file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .map_err(|e| io_error(path, e))?;
+    file.write_all(data).map_err(|e| io_error(path, e))
+}
+
+/// Names of a directory's entries, sorted
+pub fn read_dir(path: &str) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(path).map_err(|e| io_error(path, e))? {
+        let entry = entry.map_err(|e| io_error(path, e))?;
+        names.push(entry.file_name().to_string_lossy().into_owned());
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Create a directory and any missing parents
+pub fn mkdir_all(path: &str) -> Result<(), String> {
+    fs::create_dir_all(path).map_err(|e| io_error(path, e))
+}
+
+/// Remove a file or an empty directory
+pub fn remove(path: &str) -> Result<(), String> {
+    let metadata = fs::symlink_metadata(path).map_err(|e| io_error(path, e))?;
+    let removed = if metadata.is_dir() {
+        fs::remove_dir(path)
+    } else {
+        fs::remove_file(path)
+    };
+    removed.map_err(|e| io_error(path, e))
+}
+
+/// Remove a file or a directory with everything in it
+pub fn remove_all(path: &str) -> Result<(), String> {
+    let metadata = fs::symlink_metadata(path).map_err(|e| io_error(path, e))?;
+    let removed = if metadata.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    removed.map_err(|e| io_error(path, e))
+}
+
+/// Copy a file, returning the number of bytes copied
+pub fn copy(from: &str, to: &str) -> Result<u64, String> {
+    fs::copy(from, to).map_err(|e| io_error(from, e))
+}
+
+/// What `metadata` reports about a path
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileInfo {
+    pub size: u64,
+    /// Last modification, in milliseconds since the Unix epoch
+    pub modified: i64,
+    pub is_dir: bool,
+    pub is_file: bool,
+}
+
+impl FileInfo {
+    /// The `FileInfo` struct Bulu programs see
+    pub fn to_value(&self) -> RuntimeValue {
+        let mut fields = HashMap::new();
+        fields.insert("size".to_string(), RuntimeValue::Int64(self.size as i64));
+        fields.insert("modified".to_string(), RuntimeValue::Int64(self.modified));
+        fields.insert("isDir".to_string(), RuntimeValue::Bool(self.is_dir));
+        fields.insert("isFile".to_string(), RuntimeValue::Bool(self.is_file));
+        RuntimeValue::Struct {
+            name: FILE_INFO.to_string(),
+            fields,
+        }
+    }
+}
+
+pub fn metadata(path: &str) -> Result<FileInfo, String> {
+    let metadata = fs::metadata(path).map_err(|e| io_error(path, e))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |elapsed| elapsed.as_millis() as i64);
+    Ok(FileInfo {
+        size: metadata.len(),
+        modified,
+        is_dir: metadata.is_dir(),
+        is_file: metadata.is_file(),
+    })
+}
+
+pub fn exists(path: &str) -> bool {
+    Path::new(path).exists()
+}
+
+/// Join path components with the platform's separator
+pub fn join(parts: &[&str]) -> String {
+    let mut path = std::path::PathBuf::new();
+    for part in parts {
+        path.push(part);
+    }
+    path.to_string_lossy().into_owned()
+}
+
+/// The last component of a path, "" if it has none
+pub fn base_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+}
+
+/// The path without its last component, "." for a bare name
+pub fn dir_name(path: &str) -> String {
+    match Path::new(path).parent() {
+        Some(parent) if parent.as_os_str().is_empty() => ".".to_string(),
+        Some(parent) => parent.to_string_lossy().into_owned(),
+        None => path.to_string(),
+    }
+}
+
+/// The extension of a path without the dot, "" if it has none
+pub fn extension(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .map_or_else(String::new, |extension| extension.to_string_lossy().into_owned())
+}
+
+/// The path made absolute against the current directory, without resolving links
+pub fn abs_path(path: &str) -> Result<String, String> {
+    std::path::absolute(path)
+        .map(|path| path.to_string_lossy().into_owned())
+        .map_err(|e| io_error(path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn path_in(dir: &TempDir, name: &str) -> String {
+        dir.path().join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_file_operations() {
+        let dir = TempDir::new().unwrap();
+        let nested = path_in(&dir, "a/b");
+        mkdir_all(&nested).unwrap();
+        let file = join(&[&nested, "notes.txt"]);
+
+        write_file(&file, b"one\n").unwrap();
+        append_file(&file, b"two\n").unwrap();
+        assert_eq!(read_file(&file).unwrap(), "one\ntwo\n");
+
+        let copied = join(&[&nested, "copy.txt"]);
+        assert_eq!(copy(&file, &copied).unwrap(), 8);
+        assert_eq!(read_dir(&nested).unwrap(), vec!["copy.txt", "notes.txt"]);
+
+        let info = metadata(&file).unwrap();
+        assert_eq!(info.size, 8);
+        assert!(info.is_file && !info.is_dir);
+        assert!(info.modified > 0);
+
+        assert!(remove(&path_in(&dir, "a")).is_err());
+        remove(&copied).unwrap();
+        assert!(!exists(&copied));
+        remove_all(&path_in(&dir, "a")).unwrap();
+        assert!(!exists(&nested));
+
+        let error = read_file(&file).unwrap_err();
+        assert!(error.starts_with(&file), "{}", error);
+    }
+
+    #[test]
+    fn test_file_handles() {
+        let dir = TempDir::new().unwrap();
+        let path = path_in(&dir, "log.txt");
+        let mut registry = FileRegistry::new();
+
+        let RuntimeValue::Struct { fields, .. } = registry.open(&path, "w").unwrap() else {
+            panic!("expected a File handle");
+        };
+        write_all(registry.get_mut(&fields).unwrap(), b"abc").unwrap();
+        registry.close(&fields);
+        assert!(registry.get_mut(&fields).is_none());
+
+        let RuntimeValue::Struct { fields, .. } = registry.open(&path, "r").unwrap() else {
+            panic!("expected a File handle");
+        };
+        assert_eq!(read_to_end(registry.get_mut(&fields).unwrap()).unwrap(), "abc");
+        assert!(registry.open(&path, "x").unwrap_err().contains("unknown mode 'x'"));
+    }
+
+    #[test]
+    fn test_path_helpers() {
+        assert_eq!(join(&["a", "b", "c.txt"]), Path::new("a").join("b").join("c.txt").to_string_lossy());
+        assert_eq!(base_name("dir/file.tar.gz"), "file.tar.gz");
+        assert_eq!(dir_name("dir/sub/file"), "dir/sub");
+        assert_eq!(dir_name("file"), ".");
+        assert_eq!(extension("dir/file.tar.gz"), "gz");
+        assert_eq!(extension("Makefile"), "");
+        assert!(Path::new(&abs_path("x").unwrap()).is_absolute());
+    }
+}
@@ -1,11 +1,64 @@
 // HTTP client and server functionality for the Bulu programming language
 // Requirements: 7.2.1, 7.2.3, 7.2.4
-
+//
+// Bulu programs serve HTTP through std/http:
+//
+//   import { newServer, response } from "std/http"
+//
+//   func greet(req: Request): Response {
+//       return response(200, "hello " + req.params["name"])
+//   }
+//
+//   let srv = newServer()
+//   srv.get("/hello/:name", greet)
+//   srv.listen("127.0.0.1:8080")
+//
+// `listen` binds and then serves until `shutdown` is called or the current
+// context is done. Every connection is handled by its own goroutine, which
+// calls the handler with a Request; stopping waits for the requests already
+// accepted to be answered.
+//
+// Routes added with `websocket` upgrade GET requests to WebSocket
+// connections; see `super::websocket`.
+
+use super::net::{NetAddr, TcpConnection};
+use super::tls::TlsOptions;
+use super::websocket::WebSocket;
+use crate::runtime::context;
+use crate::runtime::output::{self, Stream};
+use crate::types::primitive::RuntimeValue;
 use std::collections::HashMap;
 use std::io::{Read, Write};
-use std::net::{TcpListener, TcpStream};
-use std::sync::Arc;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
 use std::thread;
+use std::time::Duration;
+use tracing::warn;
+
+/// Functions the `std/http` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &["newServer", "response", "connectWebSocket"];
+
+/// Name of the server handle type
+pub const SERVER: &str = "Server";
+
+/// Name of the type handlers receive
+pub const REQUEST: &str = "Request";
+
+/// Name of the type handlers return
+pub const RESPONSE: &str = "Response";
+
+/// How long the accept loop waits before checking for shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long a client may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest request line and headers accepted
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// Largest request body accepted
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
 
 /// HTTP methods supported by the client and server
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -57,6 +110,7 @@ pub enum HttpStatus {
     Forbidden = 403,
     NotFound = 404,
     MethodNotAllowed = 405,
+    UpgradeRequired = 426,
     InternalServerError = 500,
     NotImplemented = 501,
     BadGateway = 502,
@@ -74,6 +128,7 @@ impl HttpStatus {
             403 => Some(HttpStatus::Forbidden),
             404 => Some(HttpStatus::NotFound),
             405 => Some(HttpStatus::MethodNotAllowed),
+            426 => Some(HttpStatus::UpgradeRequired),
             500 => Some(HttpStatus::InternalServerError),
             501 => Some(HttpStatus::NotImplemented),
             502 => Some(HttpStatus::BadGateway),
@@ -96,6 +151,7 @@ impl HttpStatus {
             HttpStatus::Forbidden => "Forbidden",
             HttpStatus::NotFound => "Not Found",
             HttpStatus::MethodNotAllowed => "Method Not Allowed",
+            HttpStatus::UpgradeRequired => "Upgrade Required",
             HttpStatus::InternalServerError => "Internal Server Error",
             HttpStatus::NotImplemented => "Not Implemented",
             HttpStatus::BadGateway => "Bad Gateway",
@@ -226,9 +282,80 @@ impl HttpResponse {
     }
 }
 
+/// Where a request URL points
+#[derive(Debug, Clone, PartialEq)]
+pub struct Url {
+    pub secure: bool,
+    pub host: String,
+    pub port: u16,
+    /// Path and query, starting with `/`
+    pub path: String,
+}
+
+impl Url {
+    /// Parse an `http://` or `https://` URL; the port defaults to 80 or 443
+    pub fn parse(url: &str) -> Result<Url, String> {
+        let (secure, rest) = if let Some(rest) = url.strip_prefix("https://") {
+            (true, rest)
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            (false, rest)
+        } else {
+            return Err(format!("Unsupported URL '{}': expected http:// or https://", url));
+        };
+
+        let (authority, path) = match rest.find(['/', '?']) {
+            Some(i) if rest[i..].starts_with('?') => (&rest[..i], format!("/{}", &rest[i..])),
+            Some(i) => (&rest[..i], rest[i..].to_string()),
+            None => (rest, "/".to_string()),
+        };
+        let default_port = if secure { 443 } else { 80 };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => {
+                let port = port.parse().map_err(|_| format!("Invalid port in URL '{}'", url))?;
+                (host, port)
+            }
+            _ => (authority, default_port),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            return Err(format!("Missing host in URL '{}'", url));
+        }
+
+        Ok(Url {
+            secure,
+            host: host.to_string(),
+            port,
+            path,
+        })
+    }
+
+    /// The `Host` header value, which names the port only when it is not the default
+    pub fn host_header(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        if self.port == if self.secure { 443 } else { 80 } {
+            host
+        } else {
+            format!("{}:{}", host, self.port)
+        }
+    }
+
+    pub(crate) fn net_addr(&self) -> NetAddr {
+        match self.host.parse() {
+            Ok(std::net::IpAddr::V4(ip)) => NetAddr::Ipv4(ip, self.port),
+            Ok(std::net::IpAddr::V6(ip)) => NetAddr::Ipv6(ip, self.port),
+            Err(_) => NetAddr::Domain(self.host.clone(), self.port),
+        }
+    }
+}
+
 /// HTTP client for making requests
 pub struct HttpClient {
     default_headers: HashMap<String, String>,
+    tls: TlsOptions,
 }
 
 impl HttpClient {
@@ -238,6 +365,7 @@ impl HttpClient {
         
         HttpClient {
             default_headers,
+            tls: TlsOptions::default(),
         }
     }
 
@@ -246,6 +374,12 @@ impl HttpClient {
         self
     }
 
+    /// How `https://` servers are verified
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls = options;
+        self
+    }
+
     pub fn get(&self, url: &str) -> Result<HttpResponse, Box<dyn std::error::Error>> {
         let request = HttpRequest::new(HttpMethod::GET, url.to_string());
         self.send_request(request)
@@ -282,28 +416,22 @@ impl HttpClient {
             }
         }
 
-        // Parse URL (simplified - in real implementation would use proper URL parsing)
-        let url_parts: Vec<&str> = request.path.split('/').collect();
-        if url_parts.len() < 3 {
-            return Err("Invalid URL format".into());
-        }
+        let url = Url::parse(&request.path)?;
 
-        let host = url_parts[2];
-        let path = if url_parts.len() > 3 {
-            format!("/{}", url_parts[3..].join("/"))
+        // Connect to server
+        let mut stream = if url.secure {
+            TcpConnection::connect_tls(url.net_addr(), &self.tls)?
         } else {
-            "/".to_string()
+            TcpConnection::connect(url.net_addr())?
         };
 
-        // Connect to server
-        let mut stream = TcpStream::connect(format!("{}:80", host))?;
-
         // Build HTTP request
         let mut http_request = format!("{} {} {}\r\n", 
-            request.method.as_str(), path, request.version);
+            request.method.as_str(), url.path, request.version);
         
-        // Add Host header
-        request.headers.insert("Host".to_string(), host.to_string());
+        // Add Host header; the response is read until the server closes
+        request.headers.insert("Host".to_string(), url.host_header());
+        request.headers.insert("Connection".to_string(), "close".to_string());
         
         // Add headers
         for (key, value) in &request.headers {
@@ -318,9 +446,14 @@ impl HttpClient {
             stream.write_all(&request.body)?;
         }
 
-        // Read response
+        // Read response. Servers often close TLS connections without a
+        // close_notify once the response is sent, which is not an error here.
         let mut response_data = Vec::new();
-        stream.read_to_end(&mut response_data)?;
+        match stream.read_to_end(&mut response_data) {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof && !response_data.is_empty() => {}
+            Err(e) => return Err(e.into()),
+        }
 
         // Parse response (simplified)
         let response_str = String::from_utf8_lossy(&response_data);
@@ -479,12 +612,12 @@ impl HttpServer {
                     
                     thread::spawn(move || {
                         if let Err(e) = handle_connection(stream, routes, middleware) {
-                            eprintln!("Error handling connection: {}", e);
+                            warn!("Error handling connection: {}", e);
                         }
                     });
                 }
                 Err(e) => {
-                    eprintln!("Error accepting connection: {}", e);
+                    warn!("Error accepting connection: {}", e);
                 }
             }
         }
@@ -586,6 +719,565 @@ fn parse_http_request(request_str: &str) -> Result<HttpRequest, Box<dyn std::err
     })
 }
 
+/// One part of a route pattern
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    /// `:name`, matching any single path segment
+    Param(String),
+}
+
+#[derive(Debug, Clone)]
+struct Route {
+    method: String,
+    segments: Vec<Segment>,
+    handler: RuntimeValue,
+    /// Whether requests are upgraded to WebSocket connections
+    upgrade: bool,
+}
+
+impl Route {
+    /// The parameters bound by matching `path`, if it matches
+    fn matches(&self, path: &[&str]) -> Option<HashMap<String, String>> {
+        if path.len() != self.segments.len() {
+            return None;
+        }
+        let mut params = HashMap::new();
+        for (segment, part) in self.segments.iter().zip(path) {
+            match segment {
+                Segment::Literal(literal) if literal == part => {}
+                Segment::Literal(_) => return None,
+                Segment::Param(name) => {
+                    params.insert(name.clone(), percent_decode(part, false));
+                }
+            }
+        }
+        Some(params)
+    }
+}
+
+/// What a request resolves to
+#[derive(Debug, Clone, PartialEq)]
+pub enum RouteMatch {
+    Found {
+        handler: RuntimeValue,
+        params: HashMap<String, String>,
+    },
+    /// A WebSocket route; the handler takes the connection and the request
+    Upgrade {
+        handler: RuntimeValue,
+        params: HashMap<String, String>,
+    },
+    /// The path has routes, but for these other methods
+    MethodNotAllowed(Vec<String>),
+    NotFound,
+}
+
+/// Routes of a server, tried in the order they were added
+#[derive(Debug, Default)]
+pub struct Router {
+    routes: Vec<Route>,
+}
+
+impl Router {
+    /// Add a route; one for the same method and pattern is replaced
+    pub fn add(&mut self, method: &str, pattern: &str, handler: RuntimeValue) -> Result<(), String> {
+        self.insert(method, pattern, handler, false)
+    }
+
+    /// Add a route upgrading GET requests to WebSocket connections
+    pub fn add_websocket(&mut self, pattern: &str, handler: RuntimeValue) -> Result<(), String> {
+        self.insert("GET", pattern, handler, true)
+    }
+
+    fn insert(&mut self, method: &str, pattern: &str, handler: RuntimeValue, upgrade: bool) -> Result<(), String> {
+        let method = HttpMethod::from_str(method)
+            .ok_or_else(|| format!("unknown method '{}'", method))?
+            .as_str()
+            .to_string();
+        if !pattern.starts_with('/') {
+            return Err(format!("route '{}' must start with '/'", pattern));
+        }
+        let segments: Vec<Segment> = path_segments(pattern)
+            .map(|part| match part.strip_prefix(':') {
+                Some(name) if !name.is_empty() => Segment::Param(name.to_string()),
+                _ => Segment::Literal(part.to_string()),
+            })
+            .collect();
+        self.routes
+            .retain(|route| route.method != method || route.segments != segments);
+        self.routes.push(Route {
+            method,
+            segments,
+            handler,
+            upgrade,
+        });
+        Ok(())
+    }
+
+    pub fn find(&self, method: &str, path: &str) -> RouteMatch {
+        let parts: Vec<&str> = path_segments(path).collect();
+        let mut allowed: Vec<String> = Vec::new();
+        for route in &self.routes {
+            if let Some(params) = route.matches(&parts) {
+                if route.method == method {
+                    let handler = route.handler.clone();
+                    return if route.upgrade {
+                        RouteMatch::Upgrade { handler, params }
+                    } else {
+                        RouteMatch::Found { handler, params }
+                    };
+                }
+                if !allowed.contains(&route.method) {
+                    allowed.push(route.method.clone());
+                }
+            }
+        }
+        if allowed.is_empty() {
+            RouteMatch::NotFound
+        } else {
+            RouteMatch::MethodNotAllowed(allowed)
+        }
+    }
+}
+
+fn path_segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|part| !part.is_empty())
+}
+
+/// Decode `%XX` escapes, and `+` as a space in query strings and forms
+pub fn percent_decode(text: &str, plus_as_space: bool) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes.get(i + 1..i + 3) {
+            Some([high, low]) if bytes[i] == b'%' => {
+                std::str::from_utf8(&[*high, *low]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            }
+            _ => None,
+        };
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+            (None, b'+') if plus_as_space => decoded.push(b' '),
+            (None, byte) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Parse `a=1&b=two` as sent in query strings and form bodies; the last of
+/// repeated keys wins
+pub fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key, true), percent_decode(value, true))
+        })
+        .collect()
+}
+
+/// Read one request: the head up to the blank line, then as many body bytes
+/// as `Content-Length` says
+pub fn read_request(stream: &mut impl Read) -> Result<HttpRequest, String> {
+    let mut data = Vec::new();
+    let mut buffer = [0u8; 4096];
+    let head_end = loop {
+        if let Some(end) = data.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end;
+        }
+        if data.len() > MAX_HEAD_SIZE {
+            return Err("request headers too large".to_string());
+        }
+        let count = stream
+            .read(&mut buffer)
+            .map_err(|e| format!("failed to read request: {}", e))?;
+        if count == 0 {
+            return Err("connection closed before the request ended".to_string());
+        }
+        data.extend_from_slice(&buffer[..count]);
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).into_owned();
+    let mut request = parse_http_request(&head).map_err(|e| e.to_string())?;
+    let length = match request.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case("content-length")) {
+        Some((_, value)) => value
+            .parse::<usize>()
+            .map_err(|_| format!("invalid Content-Length '{}'", value))?,
+        None => 0,
+    };
+    if length > MAX_BODY_SIZE {
+        return Err("request body too large".to_string());
+    }
+    let mut body = data.split_off(head_end + 4);
+    if body.len() < length {
+        let mut rest = vec![0u8; length - body.len()];
+        stream
+            .read_exact(&mut rest)
+            .map_err(|e| format!("failed to read request body: {}", e))?;
+        body.extend_from_slice(&rest);
+    }
+    body.truncate(length);
+    request.body = body;
+    Ok(request)
+}
+
+/// The `Request` struct handlers receive. Header names are lowercase.
+pub fn request_value(request: &HttpRequest, params: HashMap<String, String>) -> RuntimeValue {
+    let (path, query) = request.path.split_once('?').unwrap_or((&request.path, ""));
+    let strings = |map: HashMap<String, String>| {
+        RuntimeValue::Map(map.into_iter().map(|(key, value)| (key, RuntimeValue::String(value))).collect())
+    };
+    let headers = request
+        .headers
+        .iter()
+        .map(|(key, value)| (key.to_lowercase(), value.clone()))
+        .collect();
+
+    let mut fields = HashMap::new();
+    fields.insert("method".to_string(), RuntimeValue::String(request.method.as_str().to_string()));
+    fields.insert("path".to_string(), RuntimeValue::String(percent_decode(path, false)));
+    fields.insert("query".to_string(), strings(parse_query(query)));
+    fields.insert("params".to_string(), strings(params));
+    fields.insert("headers".to_string(), strings(headers));
+    fields.insert(
+        "body".to_string(),
+        RuntimeValue::String(String::from_utf8_lossy(&request.body).into_owned()),
+    );
+    RuntimeValue::Struct {
+        name: REQUEST.to_string(),
+        fields,
+    }
+}
+
+/// A response as handlers build it: any status code, headers in a map
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl ServerResponse {
+    /// A plain text response
+    pub fn text(status: u16, body: &str) -> Self {
+        let mut headers = HashMap::new();
+        headers.insert("Content-Type".to_string(), "text/plain; charset=utf-8".to_string());
+        Self {
+            status,
+            headers,
+            body: body.to_string(),
+        }
+    }
+
+    /// The `Response` struct Bulu programs see
+    pub fn to_value(&self) -> RuntimeValue {
+        let headers = self
+            .headers
+            .iter()
+            .map(|(key, value)| (key.clone(), RuntimeValue::String(value.clone())))
+            .collect();
+        let mut fields = HashMap::new();
+        fields.insert("status".to_string(), RuntimeValue::Int32(self.status as i32));
+        fields.insert("headers".to_string(), RuntimeValue::Map(headers));
+        fields.insert("body".to_string(), RuntimeValue::String(self.body.clone()));
+        RuntimeValue::Struct {
+            name: RESPONSE.to_string(),
+            fields,
+        }
+    }
+
+    /// The response for what a handler returned: a `Response`, or a string
+    /// sent as a 200 text response
+    pub fn from_value(value: &RuntimeValue) -> Result<Self, String> {
+        match value {
+            RuntimeValue::String(body) => Ok(Self::text(200, body)),
+            RuntimeValue::Struct { name, fields } if name == RESPONSE => {
+                let status = match fields.get("status") {
+                    Some(RuntimeValue::Int32(status)) => u16::try_from(*status).ok(),
+                    _ => None,
+                }
+                .filter(|status| (100..=999).contains(status))
+                .ok_or("Response has an invalid status")?;
+                let mut headers = HashMap::new();
+                if let Some(RuntimeValue::Map(map)) = fields.get("headers") {
+                    for (key, value) in map {
+                        if let RuntimeValue::String(value) = value {
+                            headers.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+                let body = match fields.get("body") {
+                    Some(RuntimeValue::String(body)) => body.clone(),
+                    _ => String::new(),
+                };
+                Ok(Self { status, headers, body })
+            }
+            other => Err(format!("handler returned {}, expected a Response or a string", other)),
+        }
+    }
+
+    /// The response as sent; connections are closed after each response
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let reason = HttpStatus::from_code(self.status).map_or("", |status| status.reason_phrase());
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, reason);
+        let mut headers: Vec<_> = self
+            .headers
+            .iter()
+            .filter(|(key, _)| {
+                !key.eq_ignore_ascii_case("content-length") && !key.eq_ignore_ascii_case("connection")
+            })
+            .collect();
+        headers.sort();
+        for (key, value) in headers {
+            head.push_str(&format!("{}: {}\r\n", key, value));
+        }
+        head.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", self.body.len()));
+        let mut bytes = head.into_bytes();
+        bytes.extend_from_slice(self.body.as_bytes());
+        bytes
+    }
+}
+
+/// A server created through std/http. Routes may be added while it serves.
+#[derive(Debug, Default)]
+pub struct Server {
+    router: RwLock<Router>,
+    listener: Mutex<Option<TcpListener>>,
+    stopping: AtomicBool,
+    in_flight: Arc<AtomicUsize>,
+    /// WebSocket connections, closed on shutdown so their handlers return
+    sockets: Mutex<Vec<Weak<WebSocket>>>,
+}
+
+/// Counts a request as in flight until dropped
+#[derive(Debug)]
+pub struct InFlight(Arc<AtomicUsize>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn route(&self, method: &str, pattern: &str, handler: RuntimeValue) -> Result<(), String> {
+        self.router.write().unwrap().add(method, pattern, handler)
+    }
+
+    pub fn websocket(&self, pattern: &str, handler: RuntimeValue) -> Result<(), String> {
+        self.router.write().unwrap().add_websocket(pattern, handler)
+    }
+
+    pub fn find(&self, method: &str, path: &str) -> RouteMatch {
+        self.router.read().unwrap().find(method, path)
+    }
+
+    /// Start listening on `addr` and return the bound address, which tells
+    /// the port picked for port 0
+    pub fn bind(&self, addr: &str) -> Result<SocketAddr, String> {
+        let listener = TcpListener::bind(addr).map_err(|e| format!("failed to listen on {}: {}", addr, e))?;
+        // Accepting in slices lets `serve` notice shutdown
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("failed to listen on {}: {}", addr, e))?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| format!("failed to listen on {}: {}", addr, e))?;
+        *self.listener.lock().unwrap() = Some(listener);
+        Ok(local_addr)
+    }
+
+    /// Pass accepted connections to `handle` until `shutdown` is called or
+    /// the current context is done, then wait for the requests in flight
+    pub fn serve(&self, mut handle: impl FnMut(TcpStream, InFlight)) -> Result<(), String> {
+        let listener = self
+            .listener
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or("server is not listening; call bind first")?;
+        let result = loop {
+            if self.stopping.load(Ordering::SeqCst) {
+                break Ok(());
+            }
+            if let Some(error) = context::current().and_then(|context| context.err()) {
+                break Err(error.message().to_string());
+            }
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let _ = stream.set_nonblocking(false);
+                    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+                    self.in_flight.fetch_add(1, Ordering::SeqCst);
+                    handle(stream, InFlight(self.in_flight.clone()));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                // A client giving up before being accepted does not stop the server
+                Err(_) => thread::sleep(POLL_INTERVAL),
+            }
+        };
+        drop(listener);
+        while self.in_flight.load(Ordering::SeqCst) > 0 {
+            thread::sleep(POLL_INTERVAL);
+        }
+        result
+    }
+
+    /// Stop accepting connections and close WebSocket connections; `serve`
+    /// returns once the requests in flight are answered
+    pub fn shutdown(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        for socket in self.sockets.lock().unwrap().drain(..) {
+            if let Some(socket) = socket.upgrade() {
+                socket.close_going_away();
+            }
+        }
+    }
+
+    /// Read a request from `stream`, answer it and close the connection.
+    /// `call` runs a handler with a `Request` value, or for WebSocket routes
+    /// with the connection `open` makes a handle for and the `Request`. The
+    /// connection is closed once its handler returns.
+    pub fn respond(
+        &self,
+        mut stream: TcpStream,
+        call: impl FnOnce(&RuntimeValue, Vec<RuntimeValue>) -> Result<RuntimeValue, String>,
+        open: impl FnOnce(Arc<WebSocket>) -> RuntimeValue,
+    ) {
+        let response = match read_request(&mut stream) {
+            Err(message) => ServerResponse::text(400, &message),
+            Ok(request) => {
+                let path = request.path.split('?').next().unwrap_or_default();
+                match self.find(request.method.as_str(), path) {
+                    RouteMatch::NotFound => ServerResponse::text(404, "Not Found"),
+                    RouteMatch::MethodNotAllowed(methods) => {
+                        let mut response = ServerResponse::text(405, "Method Not Allowed");
+                        response.headers.insert("Allow".to_string(), methods.join(", "));
+                        response
+                    }
+                    RouteMatch::Upgrade { handler, params } => match WebSocket::accept(stream, &request) {
+                        Err((returned, response)) => {
+                            stream = returned;
+                            response
+                        }
+                        Ok(socket) => {
+                            let socket = Arc::new(socket);
+                            {
+                                let mut sockets = self.sockets.lock().unwrap();
+                                sockets.retain(|socket| socket.strong_count() > 0);
+                                sockets.push(Arc::downgrade(&socket));
+                            }
+                            if self.stopping.load(Ordering::SeqCst) {
+                                socket.close_going_away();
+                            }
+                            let args = vec![open(socket.clone()), request_value(&request, params)];
+                            if let Err(message) = call(&handler, args) {
+                                let _ = output::write(
+                                    Stream::Stderr,
+                                    &format!("Handler error for WebSocket {}: {}\n", path, message),
+                                );
+                            }
+                            socket.finish();
+                            return;
+                        }
+                    },
+                    RouteMatch::Found { handler, params } => {
+                        match call(&handler, vec![request_value(&request, params)])
+                            .and_then(|value| ServerResponse::from_value(&value))
+                        {
+                            Ok(response) => response,
+                            Err(message) => {
+                                let _ = output::write(
+                                    Stream::Stderr,
+                                    &format!("Handler error for {} {}: {}\n", request.method.as_str(), path, message),
+                                );
+                                ServerResponse::text(500, "Internal Server Error")
+                            }
+                        }
+                    }
+                }
+            }
+        };
+        let _ = stream.write_all(&response.to_bytes());
+        let _ = stream.flush();
+    }
+}
+
+/// Servers and WebSocket connections created through std/http, keyed by
+/// handle ID
+#[derive(Debug, Default)]
+pub struct ServerRegistry {
+    servers: HashMap<u64, Arc<Server>>,
+    sockets: HashMap<u64, Arc<WebSocket>>,
+    next_id: u64,
+}
+
+impl ServerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a server and return its handle
+    pub fn create_server(&mut self) -> RuntimeValue {
+        self.next_id += 1;
+        self.servers.insert(self.next_id, Arc::new(Server::new()));
+        let mut fields = HashMap::new();
+        fields.insert("id".to_string(), RuntimeValue::UInt64(self.next_id));
+        RuntimeValue::Struct {
+            name: SERVER.to_string(),
+            fields,
+        }
+    }
+
+    /// The server behind a handle; callers release the registry before
+    /// serving
+    pub fn server(&self, fields: &HashMap<String, RuntimeValue>) -> Option<Arc<Server>> {
+        match fields.get("id") {
+            Some(RuntimeValue::UInt64(id)) => self.servers.get(id).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Add a WebSocket connection and return its handle
+    pub fn add_websocket(&mut self, socket: Arc<WebSocket>) -> RuntimeValue {
+        self.next_id += 1;
+        self.sockets.insert(self.next_id, socket);
+        let mut fields = HashMap::new();
+        fields.insert("id".to_string(), RuntimeValue::UInt64(self.next_id));
+        RuntimeValue::Struct {
+            name: super::websocket::WEBSOCKET.to_string(),
+            fields,
+        }
+    }
+
+    pub fn websocket(&self, fields: &HashMap<String, RuntimeValue>) -> Option<Arc<WebSocket>> {
+        match fields.get("id") {
+            Some(RuntimeValue::UInt64(id)) => self.sockets.get(id).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Forget a connection whose handler has returned
+    pub fn remove_websocket(&mut self, handle: &RuntimeValue) {
+        if let RuntimeValue::Struct { fields, .. } = handle {
+            if let Some(RuntimeValue::UInt64(id)) = fields.get("id") {
+                self.sockets.remove(id);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -653,4 +1345,94 @@ mod tests {
         assert_eq!(response.status, HttpStatus::Ok);
         assert_eq!(response.body_as_string().unwrap(), "Hello, World!");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_router_matches_params_and_methods() {
+        let mut router = Router::default();
+        let handler = RuntimeValue::String("function:show".to_string());
+        router.add("get", "/users/:id", handler.clone()).unwrap();
+        router.add("DELETE", "/users/:id", RuntimeValue::Null).unwrap();
+        assert!(router.add("GET", "users", RuntimeValue::Null).is_err());
+        assert!(router.add("FETCH", "/users", RuntimeValue::Null).is_err());
+
+        let mut params = HashMap::new();
+        params.insert("id".to_string(), "a b".to_string());
+        assert_eq!(router.find("GET", "/users/a%20b/"), RouteMatch::Found { handler, params });
+        assert_eq!(
+            router.find("POST", "/users/7"),
+            RouteMatch::MethodNotAllowed(vec!["GET".to_string(), "DELETE".to_string()])
+        );
+        assert_eq!(router.find("GET", "/users"), RouteMatch::NotFound);
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = parse_query("name=Ada+Lovelace&tag=%E2%9C%93&empty&x=%zz");
+        assert_eq!(query["name"], "Ada Lovelace");
+        assert_eq!(query["tag"], "\u{2713}");
+        assert_eq!(query["empty"], "");
+        assert_eq!(query["x"], "%zz");
+        assert_eq!(percent_decode("a+b%2", false), "a+b%2");
+    }
+
+    #[test]
+    fn test_url_parse() {
+        let url = Url::parse("https://example.com/a/b?c=d").unwrap();
+        assert_eq!((url.secure, url.host.as_str(), url.port, url.path.as_str()), (true, "example.com", 443, "/a/b?c=d"));
+        assert_eq!(url.host_header(), "example.com");
+
+        let url = Url::parse("http://[::1]:8080?q=1").unwrap();
+        assert_eq!((url.secure, url.host.as_str(), url.port, url.path.as_str()), (false, "::1", 8080, "/?q=1"));
+        assert_eq!(url.host_header(), "[::1]:8080");
+
+        assert!(Url::parse("ftp://example.com").unwrap_err().contains("http://"));
+        assert!(Url::parse("http://example.com:http/").unwrap_err().contains("Invalid port"));
+    }
+
+    #[test]
+    fn test_read_request_with_body() {
+        let mut data: &[u8] = b"POST /form?x=1 HTTP/1.1\r\ncontent-length: 5\r\n\r\nhello, and more";
+        let request = read_request(&mut data).unwrap();
+        assert_eq!(request.path, "/form?x=1");
+        assert_eq!(request.body_as_string().unwrap(), "hello");
+
+        let mut truncated: &[u8] = b"GET / HTTP/1.1\r\nHost: x\r\n";
+        assert!(read_request(&mut truncated).unwrap_err().contains("connection closed"));
+    }
+
+    #[test]
+    fn test_server_response_bytes() {
+        let mut response = ServerResponse::text(418, "tea");
+        response.headers.insert("Content-Length".to_string(), "99".to_string());
+        let text = String::from_utf8(response.to_bytes()).unwrap();
+        assert_eq!(
+            text,
+            "HTTP/1.1 418 \r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 3\r\nConnection: close\r\n\r\ntea"
+        );
+        assert_eq!(ServerResponse::from_value(&response.to_value()).unwrap(), response);
+        assert!(ServerResponse::from_value(&RuntimeValue::Int32(1)).is_err());
+    }
+
+    #[test]
+    fn test_shutdown_waits_for_requests_in_flight() {
+        let server = Arc::new(Server::new());
+        assert!(server.serve(|_, _| {}).is_err());
+        let addr = server.bind("127.0.0.1:0").unwrap();
+
+        let serving = server.clone();
+        let started = std::time::Instant::now();
+        let serve = thread::spawn(move || {
+            serving.serve(|_stream, in_flight| {
+                thread::spawn(move || {
+                    thread::sleep(Duration::from_millis(100));
+                    drop(in_flight);
+                });
+            })
+        });
+        TcpStream::connect(addr).unwrap();
+        thread::sleep(Duration::from_millis(30));
+        server.shutdown();
+        serve.join().unwrap().unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,557 @@
+// std.i18n module - Message catalogs with plural rules and placeholders
+//
+// Catalogs use a subset of the Fluent syntax:
+//
+//   # Comments start with a hash
+//   hello = Hello, { $name }!
+//   unread = { $count ->
+//       [0] No new messages
+//       [one] One new message
+//      *[other] { $count } new messages
+//   }
+//
+// Indented lines continue the previous message. A select expression picks the
+// variant whose key equals the selector, then the one named by the selector's
+// CLDR plural category, then the default variant marked with `*`.
+
+use crate::types::primitive::RuntimeValue;
+use std::collections::HashMap;
+
+/// Functions the `std/i18n` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &[
+    "loadCatalog", "loadCatalogFile", "setLocale", "locale", "translate", "pluralCategory",
+];
+
+/// Locale that is active until a program selects one, and the last fallback for lookups
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// CLDR plural categories
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PluralCategory::Zero => "zero",
+            PluralCategory::One => "one",
+            PluralCategory::Two => "two",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other",
+        }
+    }
+}
+
+/// The plural category of a cardinal number in a locale. Fractions are `other`
+/// except in the languages that group 0 and 1 together.
+pub fn plural_category(locale: &str, n: f64) -> PluralCategory {
+    use PluralCategory::*;
+
+    let language = language_of(locale);
+    if n.fract() != 0.0 || !n.is_finite() {
+        return if matches!(language.as_str(), "fr" | "pt") && n.abs() < 2.0 { One } else { Other };
+    }
+    let n = n.abs() as u64;
+    let (mod10, mod100) = (n % 10, n % 100);
+    let few_slavic = (2..=4).contains(&mod10) && !(12..=14).contains(&mod100);
+
+    match language.as_str() {
+        "ja" | "zh" | "ko" | "th" | "vi" | "id" | "ms" => Other,
+        "fr" | "pt" => if n <= 1 { One } else { Other },
+        "ru" | "uk" | "be" => {
+            if mod10 == 1 && mod100 != 11 {
+                One
+            } else if few_slavic {
+                Few
+            } else {
+                Many
+            }
+        }
+        "pl" => {
+            if n == 1 {
+                One
+            } else if few_slavic {
+                Few
+            } else {
+                Many
+            }
+        }
+        "cs" | "sk" => match n {
+            1 => One,
+            2..=4 => Few,
+            _ => Other,
+        },
+        "ar" => match (n, mod100) {
+            (0, _) => Zero,
+            (1, _) => One,
+            (2, _) => Two,
+            (_, 3..=10) => Few,
+            (_, 11..=99) => Many,
+            _ => Other,
+        },
+        _ => if n == 1 { One } else { Other },
+    }
+}
+
+/// Canonical form of a locale code: `pt_br` and `PT-BR` become `pt-BR`
+pub fn normalize_locale(code: &str) -> String {
+    let mut parts = code.trim().split(['-', '_']);
+    let mut normalized = parts.next().unwrap_or("").to_lowercase();
+    for part in parts {
+        normalized.push('-');
+        if part.len() == 2 {
+            normalized.push_str(&part.to_uppercase());
+        } else {
+            normalized.push_str(part);
+        }
+    }
+    normalized
+}
+
+fn language_of(locale: &str) -> String {
+    normalize_locale(locale).split('-').next().unwrap_or("").to_string()
+}
+
+/// A piece of a message
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternElement {
+    Text(String),
+    /// `{ $name }`
+    Variable(String),
+    /// `{ $count -> [one] ... *[other] ... }`
+    Select {
+        selector: String,
+        variants: Vec<Variant>,
+        default: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    pub key: String,
+    pub value: Vec<PatternElement>,
+}
+
+/// The messages of one locale
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Catalog {
+    pub messages: HashMap<String, Vec<PatternElement>>,
+}
+
+impl Catalog {
+    /// Parse catalog source. Errors are prefixed with the line of the offending message.
+    pub fn parse(source: &str) -> Result<Catalog, String> {
+        let mut catalog = Catalog::default();
+        let lines: Vec<&str> = source.lines().collect();
+        let mut index = 0;
+
+        while index < lines.len() {
+            let line = lines[index];
+            let line_number = index + 1;
+            index += 1;
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            if line.starts_with(char::is_whitespace) {
+                return Err(format!("line {}: indented line does not belong to a message", line_number));
+            }
+
+            let (id, first) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected 'name = message'", line_number))?;
+            let id = id.trim();
+            if !is_message_id(id) {
+                return Err(format!("line {}: invalid message name '{}'", line_number, id));
+            }
+
+            // Indented lines, and a `}` closing a select expression, continue the message
+            let mut value = first.trim().to_string();
+            while index < lines.len()
+                && (lines[index].starts_with(char::is_whitespace)
+                    || lines[index].starts_with('}')
+                    || lines[index].trim().is_empty())
+            {
+                if !lines[index].trim().is_empty() {
+                    if !value.is_empty() {
+                        value.push('\n');
+                    }
+                    value.push_str(lines[index].trim());
+                }
+                index += 1;
+            }
+
+            let pattern = PatternParser { chars: value.chars().collect(), position: 0 }
+                .parse_pattern(false)
+                .map_err(|message| format!("line {}: message '{}': {}", line_number, id, message))?;
+            if catalog.messages.insert(id.to_string(), pattern).is_some() {
+                return Err(format!("line {}: duplicate message '{}'", line_number, id));
+            }
+        }
+        Ok(catalog)
+    }
+
+    /// Format a message, or `None` if the catalog does not define it. Variables that
+    /// are not supplied are shown as `{$name}`.
+    pub fn format(&self, id: &str, args: &HashMap<String, RuntimeValue>, locale: &str) -> Option<String> {
+        let pattern = self.messages.get(id)?;
+        let mut output = String::new();
+        format_pattern(pattern, args, locale, &mut output);
+        Some(output)
+    }
+}
+
+fn is_message_id(id: &str) -> bool {
+    let mut chars = id.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+struct PatternParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl PatternParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(c) => Err(format!("expected '{}', found '{}'", expected, c)),
+            None => Err(format!("expected '{}'", expected)),
+        }
+    }
+
+    /// Parse text and placeables up to the end, a closing `}`, or (in a variant) a newline
+    fn parse_pattern(&mut self, in_variant: bool) -> Result<Vec<PatternElement>, String> {
+        let mut elements = Vec::new();
+        let mut text = String::new();
+
+        while let Some(c) = self.peek() {
+            match c {
+                '}' if in_variant => break,
+                '}' => return Err("unmatched '}'".to_string()),
+                '\n' if in_variant => break,
+                '{' => {
+                    self.position += 1;
+                    if !text.is_empty() {
+                        elements.push(PatternElement::Text(std::mem::take(&mut text)));
+                    }
+                    elements.push(self.parse_placeable()?);
+                }
+                _ => {
+                    text.push(c);
+                    self.position += 1;
+                }
+            }
+        }
+        if !text.is_empty() {
+            elements.push(PatternElement::Text(if in_variant { text.trim_end().to_string() } else { text }));
+        }
+        Ok(elements)
+    }
+
+    /// Parse the inside of `{ ... }` after the opening brace
+    fn parse_placeable(&mut self) -> Result<PatternElement, String> {
+        self.skip_whitespace();
+        let element = match self.peek() {
+            Some('"') => {
+                self.position += 1;
+                let mut literal = String::new();
+                loop {
+                    match self.peek() {
+                        Some('"') => break,
+                        Some('\\') if self.chars.get(self.position + 1).is_some() => {
+                            literal.push(self.chars[self.position + 1]);
+                            self.position += 2;
+                        }
+                        Some(c) => {
+                            literal.push(c);
+                            self.position += 1;
+                        }
+                        None => return Err("unterminated string literal".to_string()),
+                    }
+                }
+                self.position += 1;
+                PatternElement::Text(literal)
+            }
+            Some('$') => {
+                self.position += 1;
+                let start = self.position;
+                while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                    self.position += 1;
+                }
+                let name: String = self.chars[start..self.position].iter().collect();
+                if name.is_empty() {
+                    return Err("expected a variable name after '$'".to_string());
+                }
+                self.skip_whitespace();
+                if self.peek() == Some('-') && self.chars.get(self.position + 1) == Some(&'>') {
+                    self.position += 2;
+                    self.parse_variants(name)?
+                } else {
+                    PatternElement::Variable(name)
+                }
+            }
+            _ => return Err("expected '$variable' or a string literal in placeable".to_string()),
+        };
+        self.expect('}')?;
+        Ok(element)
+    }
+
+    fn parse_variants(&mut self, selector: String) -> Result<PatternElement, String> {
+        let mut variants = Vec::new();
+        let mut default = None;
+
+        loop {
+            self.skip_whitespace();
+            let is_default = self.peek() == Some('*');
+            if is_default {
+                self.position += 1;
+            }
+            if self.peek() != Some('[') {
+                break;
+            }
+            self.position += 1;
+            let start = self.position;
+            while self.peek().is_some_and(|c| c != ']' && c != '\n') {
+                self.position += 1;
+            }
+            let key: String = self.chars[start..self.position].iter().collect::<String>().trim().to_string();
+            self.expect(']')?;
+            while self.peek().is_some_and(|c| c == ' ' || c == '\t') {
+                self.position += 1;
+            }
+
+            if is_default {
+                if default.is_some() {
+                    return Err(format!("selector ${} has more than one default variant", selector));
+                }
+                default = Some(variants.len());
+            }
+            let value = self.parse_pattern(true)?;
+            variants.push(Variant { key, value });
+        }
+
+        match default {
+            Some(default) => Ok(PatternElement::Select { selector, variants, default }),
+            None => Err(format!("selector ${} needs a default variant marked with '*'", selector)),
+        }
+    }
+}
+
+fn format_pattern(pattern: &[PatternElement], args: &HashMap<String, RuntimeValue>, locale: &str, output: &mut String) {
+    for element in pattern {
+        match element {
+            PatternElement::Text(text) => output.push_str(text),
+            PatternElement::Variable(name) => match args.get(name) {
+                Some(value) => output.push_str(&format_argument(value, locale)),
+                None => {
+                    output.push_str("{$");
+                    output.push_str(name);
+                    output.push('}');
+                }
+            },
+            PatternElement::Select { selector, variants, default } => {
+                let variant = args
+                    .get(selector)
+                    .and_then(|value| select_variant(variants, value, locale))
+                    .unwrap_or(&variants[*default]);
+                format_pattern(&variant.value, args, locale, output);
+            }
+        }
+    }
+}
+
+fn select_variant<'v>(variants: &'v [Variant], value: &RuntimeValue, locale: &str) -> Option<&'v Variant> {
+    let number = number_of(value);
+    let exact = |variant: &&Variant| match (number, value) {
+        (Some(n), _) => variant.key.parse::<f64>().ok() == Some(n),
+        (None, RuntimeValue::String(s)) => &variant.key == s,
+        (None, other) => variant.key == other.to_string(),
+    };
+    variants.iter().find(exact).or_else(|| {
+        let category = plural_category(locale, number?).as_str();
+        variants.iter().find(|variant| variant.key == category)
+    })
+}
+
+fn number_of(value: &RuntimeValue) -> Option<f64> {
+    match value {
+        RuntimeValue::Int8(n) => Some(*n as f64),
+        RuntimeValue::Int16(n) => Some(*n as f64),
+        RuntimeValue::Int32(n) => Some(*n as f64),
+        RuntimeValue::Int64(n) | RuntimeValue::Integer(n) => Some(*n as f64),
+        RuntimeValue::UInt8(n) | RuntimeValue::Byte(n) => Some(*n as f64),
+        RuntimeValue::UInt16(n) => Some(*n as f64),
+        RuntimeValue::UInt32(n) => Some(*n as f64),
+        RuntimeValue::UInt64(n) => Some(*n as f64),
+        RuntimeValue::Float32(n) => Some(*n as f64),
+        RuntimeValue::Float64(n) => Some(*n),
+        _ => None,
+    }
+}
+
+/// Numbers are written with the locale's separators when std/fmt knows the locale
+fn format_argument(value: &RuntimeValue, locale: &str) -> String {
+    let number_locale = crate::std::fmt::find_locale(locale);
+    match (number_of(value), number_locale, value) {
+        (_, _, RuntimeValue::String(s)) => s.clone(),
+        (Some(n), Some(number_locale), RuntimeValue::Float32(_) | RuntimeValue::Float64(_)) => {
+            crate::std::fmt::format_float(n, None, true, number_locale)
+        }
+        (Some(n), Some(number_locale), _) if n.abs() < i64::MAX as f64 => {
+            crate::std::fmt::format_integer(n as i64, 0, true, number_locale)
+        }
+        _ => value.to_string(),
+    }
+}
+
+/// The catalogs a program has loaded and the locale it has selected
+#[derive(Debug, Clone)]
+pub struct Catalogs {
+    locale: String,
+    catalogs: HashMap<String, Catalog>,
+}
+
+impl Default for Catalogs {
+    fn default() -> Self {
+        Catalogs {
+            locale: DEFAULT_LOCALE.to_string(),
+            catalogs: HashMap::new(),
+        }
+    }
+}
+
+impl Catalogs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse `source` and add its messages to the locale's catalog, replacing messages
+    /// with the same name. Returns the number of messages loaded.
+    pub fn load(&mut self, locale: &str, source: &str) -> Result<usize, String> {
+        let catalog = Catalog::parse(source)?;
+        let count = catalog.messages.len();
+        self.catalogs
+            .entry(normalize_locale(locale))
+            .or_default()
+            .messages
+            .extend(catalog.messages);
+        Ok(count)
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    pub fn set_locale(&mut self, locale: &str) {
+        self.locale = normalize_locale(locale);
+    }
+
+    /// Locales searched for a message: `pt-BR`, then `pt`, then the default locale
+    pub fn fallback_chain(&self) -> Vec<String> {
+        let mut chain = vec![self.locale.clone()];
+        let language = language_of(&self.locale);
+        for fallback in [language, DEFAULT_LOCALE.to_string()] {
+            if !chain.contains(&fallback) {
+                chain.push(fallback);
+            }
+        }
+        chain
+    }
+
+    /// Translate a message in the current locale. Like gettext, a message that no
+    /// catalog defines is returned as its name.
+    pub fn translate(&self, id: &str, args: &HashMap<String, RuntimeValue>) -> String {
+        self.fallback_chain()
+            .iter()
+            .find_map(|locale| {
+                // Plural rules follow the locale the message was found in
+                self.catalogs.get(locale)?.format(id, args, locale)
+            })
+            .unwrap_or_else(|| id.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(entries: &[(&str, RuntimeValue)]) -> HashMap<String, RuntimeValue> {
+        entries.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+    }
+
+    #[test]
+    fn test_plural_categories() {
+        let categories = |locale: &str, numbers: &[f64]| -> Vec<&str> {
+            numbers.iter().map(|n| plural_category(locale, *n).as_str()).collect()
+        };
+        assert_eq!(categories("en-US", &[0.0, 1.0, 2.0, 1.5]), ["other", "one", "other", "other"]);
+        assert_eq!(categories("fr", &[0.0, 1.0, 1.5, 2.0]), ["one", "one", "one", "other"]);
+        assert_eq!(categories("ru", &[1.0, 3.0, 5.0, 11.0, 21.0, 22.0]), ["one", "few", "many", "many", "one", "few"]);
+        assert_eq!(categories("pl", &[1.0, 2.0, 5.0, 21.0, 22.0]), ["one", "few", "many", "many", "few"]);
+        assert_eq!(categories("ar", &[0.0, 1.0, 2.0, 5.0, 11.0, 100.0]), ["zero", "one", "two", "few", "many", "other"]);
+        assert_eq!(categories("ja", &[1.0]), ["other"]);
+    }
+
+    #[test]
+    fn test_parse_and_format() {
+        let catalog = Catalog::parse(
+            "# Greetings\nhello = Hello, { $name }!\nunread = { $count ->\n    [0] No messages\n    [one] One message\n   *[other] { $count } messages\n}\nlong = First line\n    second line\n",
+        )
+        .unwrap();
+
+        let format = |id: &str, args: &HashMap<String, RuntimeValue>| catalog.format(id, args, "en").unwrap();
+        assert_eq!(format("hello", &args(&[("name", RuntimeValue::String("Ada".to_string()))])), "Hello, Ada!");
+        assert_eq!(format("hello", &args(&[])), "Hello, {$name}!");
+        assert_eq!(format("unread", &args(&[("count", RuntimeValue::Int32(0))])), "No messages");
+        assert_eq!(format("unread", &args(&[("count", RuntimeValue::Int32(1))])), "One message");
+        assert_eq!(format("unread", &args(&[("count", RuntimeValue::Int32(1200))])), "1,200 messages");
+        assert_eq!(format("long", &args(&[])), "First line\nsecond line");
+        assert!(catalog.format("missing", &args(&[]), "en").is_none());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Catalog::parse("hello Hello").unwrap_err(), "line 1: expected 'name = message'");
+        assert_eq!(
+            Catalog::parse("a = x\nb = { $n ->\n  [one] x\n  [other] y\n}").unwrap_err(),
+            "line 2: message 'b': selector $n needs a default variant marked with '*'"
+        );
+        assert_eq!(Catalog::parse("a = { $n").unwrap_err(), "line 1: message 'a': expected '}'");
+        assert_eq!(Catalog::parse("a = x\na = y").unwrap_err(), "line 2: duplicate message 'a'");
+    }
+
+    #[test]
+    fn test_locale_fallback() {
+        let mut catalogs = Catalogs::new();
+        catalogs.load("en", "hello = Hello\nbye = Bye").unwrap();
+        catalogs.load("pt", "hello = Olá").unwrap();
+        catalogs.set_locale("pt_br");
+        assert_eq!(catalogs.locale(), "pt-BR");
+        assert_eq!(catalogs.fallback_chain(), ["pt-BR", "pt", "en"]);
+        assert_eq!(catalogs.translate("hello", &HashMap::new()), "Olá");
+        assert_eq!(catalogs.translate("bye", &HashMap::new()), "Bye");
+        assert_eq!(catalogs.translate("unknown", &HashMap::new()), "unknown");
+    }
+}
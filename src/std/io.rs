@@ -2,8 +2,9 @@
 // Provides functions for reading from stdin and accessing command-line arguments
 
 use crate::error::{BuluError, Result};
+use crate::runtime::output::{self, Stream};
 use crate::types::primitive::RuntimeValue;
-use std::io::{self, BufRead, Write};
+use std::io::{self, BufRead};
 use std::sync::Mutex;
 
 // Global storage for command-line arguments
@@ -81,26 +82,18 @@ pub fn read_all() -> Result<RuntimeValue> {
 
 /// Print to stdout without newline
 pub fn print(text: &str) -> Result<RuntimeValue> {
-    print!("{}", text);
-    io::stdout().flush().map_err(|e| BuluError::RuntimeError {
-        file: None,
-        message: format!("Failed to flush stdout: {}", e),
-    })?;
+    output::write(Stream::Stdout, text).map_err(|e| output::write_error(Stream::Stdout, e))?;
     Ok(RuntimeValue::Null)
 }
 
 /// Print to stderr
 pub fn eprint(text: &str) -> Result<RuntimeValue> {
-    eprint!("{}", text);
-    io::stderr().flush().map_err(|e| BuluError::RuntimeError {
-        file: None,
-        message: format!("Failed to flush stderr: {}", e),
-    })?;
+    output::write(Stream::Stderr, text).map_err(|e| output::write_error(Stream::Stderr, e))?;
     Ok(RuntimeValue::Null)
 }
 
 /// Print to stderr with newline
 pub fn eprintln(text: &str) -> Result<RuntimeValue> {
-    eprintln!("{}", text);
+    output::write(Stream::Stderr, &format!("{}\n", text)).map_err(|e| output::write_error(Stream::Stderr, e))?;
     Ok(RuntimeValue::Null)
 }
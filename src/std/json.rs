@@ -1,6 +1,30 @@
 // JSON encoding/decoding functionality for the Bulu programming language
 // Requirements: 7.3.1, 7.3.4, 7.3.5
-
+//
+//   import { decode, encode } from "std/json"
+//
+//   struct User {
+//       @json("user_id") id: int64
+//       name: string
+//       email: string?
+//   }
+//
+//   let user = decode<User>(text).unwrap()
+//   let text = encode(user).unwrap()
+//
+// `decode<T>` builds values of the declared type T from the struct
+// declarations of the program. Fields are read from the key of their
+// `@json("key")` attribute, or from their name; fields of type `T?` or
+// `Option<T>` may be missing. Errors name the path of the value that did not
+// decode, such as `$.items[2].price: expected float64, got string`.
+
+use crate::ast::nodes::{
+    ArrayType, Expression, LiteralExpr, LiteralValue, MapType, SliceType, StructDecl, StructField, StructType, TupleType,
+    Type, UnionType,
+};
+use crate::ast::printer::AstPrinter;
+use crate::runtime::builtins::runtime_type_name;
+use crate::types::primitive::{format_float64, RuntimeValue};
 use std::collections::HashMap;
 use std::fmt;
 
@@ -172,13 +196,7 @@ impl fmt::Display for JsonValue {
         match self {
             JsonValue::Null => write!(f, "null"),
             JsonValue::Bool(b) => write!(f, "{}", b),
-            JsonValue::Number(n) => {
-                if n.fract() == 0.0 {
-                    write!(f, "{}", *n as i64)
-                } else {
-                    write!(f, "{}", n)
-                }
-            }
+            JsonValue::Number(n) => write!(f, "{}", format_float64(*n)),
             JsonValue::String(s) => write!(f, "\"{}\"", escape_string(s)),
             JsonValue::Array(arr) => {
                 write!(f, "[")?;
@@ -562,13 +580,7 @@ impl JsonSerializer {
         match value {
             JsonValue::Null => "null".to_string(),
             JsonValue::Bool(b) => b.to_string(),
-            JsonValue::Number(n) => {
-                if n.fract() == 0.0 {
-                    format!("{}", *n as i64)
-                } else {
-                    n.to_string()
-                }
-            }
+            JsonValue::Number(n) => format_float64(*n),
             JsonValue::String(s) => format!("\"{}\"", escape_string(s)),
             JsonValue::Array(arr) => self.serialize_array(arr, depth),
             JsonValue::Object(obj) => self.serialize_object(obj, depth),
@@ -714,6 +726,458 @@ impl Json {
     pub fn null() -> JsonValue {
         JsonValue::Null
     }
+
+    /// Encode a runtime value. Arrays, slices, tuples and sets become arrays (sets in
+    /// ascending element order), maps and structs become objects.
+    pub fn from_runtime_value(value: &RuntimeValue) -> Result<JsonValue, JsonError> {
+        let number = |n: f64| {
+            if n.is_finite() {
+                Ok(JsonValue::Number(n))
+            } else {
+                Err(JsonError::TypeError(format!("cannot encode {}", n)))
+            }
+        };
+        let elements = |values: &[RuntimeValue]| {
+            values
+                .iter()
+                .map(Json::from_runtime_value)
+                .collect::<Result<Vec<_>, _>>()
+                .map(JsonValue::Array)
+        };
+        let fields = |fields: &HashMap<String, RuntimeValue>| {
+            fields
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), Json::from_runtime_value(value)?)))
+                .collect::<Result<HashMap<_, _>, JsonError>>()
+                .map(JsonValue::Object)
+        };
+
+        match value {
+            RuntimeValue::Null => Ok(JsonValue::Null),
+            RuntimeValue::Bool(b) => Ok(JsonValue::Bool(*b)),
+            RuntimeValue::Int8(i) => Ok(JsonValue::Number(*i as f64)),
+            RuntimeValue::Int16(i) => Ok(JsonValue::Number(*i as f64)),
+            RuntimeValue::Int32(i) => Ok(JsonValue::Number(*i as f64)),
+            RuntimeValue::Int64(i) | RuntimeValue::Integer(i) => Ok(JsonValue::Number(*i as f64)),
+            RuntimeValue::UInt8(i) | RuntimeValue::Byte(i) => Ok(JsonValue::Number(*i as f64)),
+            RuntimeValue::UInt16(i) => Ok(JsonValue::Number(*i as f64)),
+            RuntimeValue::UInt32(i) => Ok(JsonValue::Number(*i as f64)),
+            RuntimeValue::UInt64(i) => Ok(JsonValue::Number(*i as f64)),
+            RuntimeValue::Float32(f) => number(*f as f64),
+            RuntimeValue::Float64(f) => number(*f),
+            RuntimeValue::Char(c) => Ok(JsonValue::String(c.to_string())),
+            RuntimeValue::String(s) => Ok(JsonValue::String(s.clone())),
+            RuntimeValue::Array(values)
+            | RuntimeValue::Slice(values)
+            | RuntimeValue::Tuple(values)
+            | RuntimeValue::Set(values) => elements(values),
+            RuntimeValue::Map(map) => fields(map),
+            RuntimeValue::Struct { fields: struct_fields, .. } => fields(struct_fields),
+            other => Err(JsonError::TypeError(format!(
+                "cannot encode a value of type {}",
+                runtime_type_name(other)
+            ))),
+        }
+    }
+}
+
+/// Functions the `std/json` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &["encode", "decode"];
+
+/// Struct declarations by name, where typed decoding and encoding find
+/// field types and JSON keys
+pub type StructDecls = HashMap<String, StructDecl>;
+
+/// The JSON key of a struct field: the string of its `@json` attribute, or its name
+pub fn field_key(field: &StructField) -> &str {
+    field
+        .attributes
+        .iter()
+        .filter(|attribute| attribute.name == "json")
+        .find_map(|attribute| match attribute.args.first() {
+            Some(Expression::Literal(LiteralExpr {
+                value: LiteralValue::String(key),
+                ..
+            })) => Some(key.as_str()),
+            _ => None,
+        })
+        .unwrap_or(&field.name)
+}
+
+/// Decode JSON text into a value of type `target`, looking struct types up in
+/// `structs`. Errors name the path of the value that did not fit, as in
+/// `$.items[2].price: expected float64, got string`.
+pub fn decode_typed(text: &str, target: &Type, structs: &StructDecls) -> Result<RuntimeValue, String> {
+    let json = Json::parse(text).map_err(|e| e.to_string())?;
+    decode_document(&json, target, structs)
+}
+
+/// Decode a parsed document into a value of type `target`. Other data
+/// formats, such as TOML and YAML, decode through this after reading their
+/// documents into JSON values.
+pub fn decode_document(document: &JsonValue, target: &Type, structs: &StructDecls) -> Result<RuntimeValue, String> {
+    TypedDecoder { structs }.decode(document, target, "$")
+}
+
+/// Encode a value as JSON text. Structs declared in `structs` use the keys of
+/// their `@json` attributes, and Option values become their value or null.
+pub fn encode_typed(value: &RuntimeValue, structs: &StructDecls) -> Result<String, String> {
+    encode_document(value, structs)
+        .map(|json| Json::stringify(&json))
+        .map_err(|e| e.to_string())
+}
+
+/// The document a value encodes to, before it is written out as text
+pub fn encode_document(value: &RuntimeValue, structs: &StructDecls) -> Result<JsonValue, JsonError> {
+    let elements = |values: &[RuntimeValue]| {
+        values
+            .iter()
+            .map(|value| encode_document(value, structs))
+            .collect::<Result<Vec<_>, _>>()
+            .map(JsonValue::Array)
+    };
+    match value {
+        RuntimeValue::Struct { name, fields } => {
+            if let Some(decl) = structs.get(name) {
+                let mut object = HashMap::new();
+                for field in &decl.fields {
+                    let value = fields.get(&field.name).unwrap_or(&RuntimeValue::Null);
+                    object.insert(field_key(field).to_string(), encode_document(value, structs)?);
+                }
+                return Ok(JsonValue::Object(object));
+            }
+            if name == "Option" {
+                if let Some(RuntimeValue::Bool(is_some)) = fields.get("isSome") {
+                    return match (is_some, fields.get("value")) {
+                        (true, Some(value)) => encode_document(value, structs),
+                        _ => Ok(JsonValue::Null),
+                    };
+                }
+            }
+            fields
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), encode_document(value, structs)?)))
+                .collect::<Result<HashMap<_, _>, JsonError>>()
+                .map(JsonValue::Object)
+        }
+        RuntimeValue::Array(values)
+        | RuntimeValue::Slice(values)
+        | RuntimeValue::Tuple(values)
+        | RuntimeValue::Set(values) => elements(values),
+        RuntimeValue::Map(map) => map
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), encode_document(value, structs)?)))
+            .collect::<Result<HashMap<_, _>, JsonError>>()
+            .map(JsonValue::Object),
+        other => Json::from_runtime_value(other),
+    }
+}
+
+/// Converts parsed JSON into values of declared types
+struct TypedDecoder<'a> {
+    structs: &'a StructDecls,
+}
+
+impl TypedDecoder<'_> {
+    fn decode(&self, json: &JsonValue, target: &Type, path: &str) -> Result<RuntimeValue, String> {
+        let mismatch = || format!("{}: expected {}, got {}", path, type_name(target), json_kind(json));
+        match (target, json) {
+            (Type::Any, json) => Ok(dynamic_value(json)),
+            (Type::Null, JsonValue::Null) => Ok(RuntimeValue::Null),
+            (Type::Bool, JsonValue::Bool(b)) => Ok(RuntimeValue::Bool(*b)),
+            (Type::String, JsonValue::String(s)) => Ok(RuntimeValue::String(s.clone())),
+            (Type::Char, JsonValue::String(s)) if s.chars().count() == 1 => {
+                Ok(RuntimeValue::Char(s.chars().next().unwrap()))
+            }
+            (Type::Float32, JsonValue::Number(n)) => Ok(RuntimeValue::Float32(*n as f32)),
+            (Type::Float64, JsonValue::Number(n)) => Ok(RuntimeValue::Float64(*n)),
+            (
+                Type::Int8 | Type::Int16 | Type::Int32 | Type::Int64 | Type::UInt8 | Type::UInt16 | Type::UInt32
+                | Type::UInt64,
+                JsonValue::Number(n),
+            ) => integer_value(*n, target)
+                .map_err(|reason| format!("{}: expected {}, got {}", path, type_name(target), reason)),
+            (Type::Array(array), JsonValue::Array(items)) => {
+                if let Some(size) = array.size.filter(|size| *size != items.len()) {
+                    return Err(format!("{}: expected {} elements, got {}", path, size, items.len()));
+                }
+                self.elements(items, &array.element_type, path).map(RuntimeValue::Array)
+            }
+            (Type::Slice(slice), JsonValue::Array(items)) => {
+                self.elements(items, &slice.element_type, path).map(RuntimeValue::Slice)
+            }
+            (Type::Tuple(tuple), JsonValue::Array(items)) => {
+                if tuple.element_types.len() != items.len() {
+                    return Err(format!(
+                        "{}: expected {} elements, got {}",
+                        path,
+                        tuple.element_types.len(),
+                        items.len()
+                    ));
+                }
+                items
+                    .iter()
+                    .zip(&tuple.element_types)
+                    .enumerate()
+                    .map(|(index, (item, element_type))| self.decode(item, element_type, &format!("{}[{}]", path, index)))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(RuntimeValue::Tuple)
+            }
+            (Type::Map(map), JsonValue::Object(object)) => {
+                if !matches!(*map.key_type, Type::String | Type::Any) {
+                    return Err(format!("{}: cannot decode into {}, JSON keys are strings", path, type_name(target)));
+                }
+                let mut entries = HashMap::new();
+                for (key, value) in object {
+                    let value = self.decode(value, &map.value_type, &format!("{}.{}", path, key))?;
+                    entries.insert(key.clone(), value);
+                }
+                Ok(RuntimeValue::Map(entries.into()))
+            }
+            (Type::Union(union), json) => {
+                if matches!(json, JsonValue::Null) && union.types.contains(&Type::Null) {
+                    return Ok(RuntimeValue::Null);
+                }
+                let members: Vec<&Type> = union.types.iter().filter(|member| **member != Type::Null).collect();
+                match members.as_slice() {
+                    // `T?` reports why the value is not a T
+                    [member] => self.decode(json, member, path),
+                    _ => members
+                        .iter()
+                        .find_map(|member| self.decode(json, member, path).ok())
+                        .ok_or_else(mismatch),
+                }
+            }
+            (Type::Struct(option), json) if is_option(option, self.structs) => {
+                let value = match json {
+                    JsonValue::Null => None,
+                    json => Some(self.decode(json, &option.type_args[0], path)?),
+                };
+                let mut fields = HashMap::new();
+                fields.insert("isSome".to_string(), RuntimeValue::Bool(value.is_some()));
+                fields.insert("value".to_string(), value.unwrap_or(RuntimeValue::Null));
+                Ok(RuntimeValue::Struct {
+                    name: "Option".to_string(),
+                    fields,
+                })
+            }
+            (Type::Named(name), json) => self.decode_struct(json, name, &[], target, path),
+            (Type::Struct(struct_type), json) => {
+                self.decode_struct(json, &struct_type.name, &struct_type.type_args, target, path)
+            }
+            (
+                Type::Null
+                | Type::Bool
+                | Type::String
+                | Type::Char
+                | Type::Float32
+                | Type::Float64
+                | Type::Int8
+                | Type::Int16
+                | Type::Int32
+                | Type::Int64
+                | Type::UInt8
+                | Type::UInt16
+                | Type::UInt32
+                | Type::UInt64
+                | Type::Array(_)
+                | Type::Slice(_)
+                | Type::Tuple(_)
+                | Type::Map(_),
+                _,
+            ) => Err(mismatch()),
+            (other, _) => Err(format!("{}: cannot decode JSON into {}", path, type_name(other))),
+        }
+    }
+
+    fn elements(&self, items: &[JsonValue], element_type: &Type, path: &str) -> Result<Vec<RuntimeValue>, String> {
+        items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| self.decode(item, element_type, &format!("{}[{}]", path, index)))
+            .collect()
+    }
+
+    fn decode_struct(
+        &self,
+        json: &JsonValue,
+        name: &str,
+        type_args: &[Type],
+        target: &Type,
+        path: &str,
+    ) -> Result<RuntimeValue, String> {
+        let decl = self
+            .structs
+            .get(name)
+            .ok_or_else(|| format!("{}: cannot decode JSON into unknown type {}", path, name))?;
+        let object = match json {
+            JsonValue::Object(object) => object,
+            other => return Err(format!("{}: expected {}, got {}", path, type_name(target), json_kind(other))),
+        };
+
+        // Fields of generic structs use the type arguments of `target`
+        let bindings: HashMap<&str, &Type> = decl
+            .type_params
+            .iter()
+            .map(|param| param.name.as_str())
+            .zip(type_args)
+            .collect();
+        let mut fields = HashMap::new();
+        for field in &decl.fields {
+            let key = field_key(field);
+            let field_type = substitute(&field.field_type, &bindings);
+            let field_path = format!("{}.{}", path, key);
+            let value = match object.get(key) {
+                Some(value) => self.decode(value, &field_type, &field_path)?,
+                None if is_optional(&field_type, self.structs) => {
+                    self.decode(&JsonValue::Null, &field_type, &field_path)?
+                }
+                None => return Err(format!("{}: missing required field", field_path)),
+            };
+            fields.insert(field.name.clone(), value);
+        }
+        Ok(RuntimeValue::Struct {
+            name: name.to_string(),
+            fields,
+        })
+    }
+}
+
+/// Whether a struct type is the builtin `Option<T>` rather than a user struct
+fn is_option(struct_type: &StructType, structs: &StructDecls) -> bool {
+    struct_type.name == "Option" && struct_type.type_args.len() == 1 && !structs.contains_key("Option")
+}
+
+/// Whether a field of this type may be missing: `T?`, unions with null and `Option<T>`
+fn is_optional(field_type: &Type, structs: &StructDecls) -> bool {
+    match field_type {
+        Type::Null | Type::Any => true,
+        Type::Union(union) => union.types.contains(&Type::Null),
+        Type::Struct(struct_type) => is_option(struct_type, structs),
+        _ => false,
+    }
+}
+
+/// `field_type` with the type parameters in `bindings` replaced
+fn substitute(field_type: &Type, bindings: &HashMap<&str, &Type>) -> Type {
+    if bindings.is_empty() {
+        return field_type.clone();
+    }
+    let boxed = |element: &Type| Box::new(substitute(element, bindings));
+    match field_type {
+        Type::Named(name) => bindings.get(name.as_str()).map_or_else(|| field_type.clone(), |bound| (*bound).clone()),
+        Type::Array(array) => Type::Array(ArrayType {
+            element_type: boxed(&array.element_type),
+            ..array.clone()
+        }),
+        Type::Slice(slice) => Type::Slice(SliceType {
+            element_type: boxed(&slice.element_type),
+        }),
+        Type::Map(map) => Type::Map(MapType {
+            key_type: boxed(&map.key_type),
+            value_type: boxed(&map.value_type),
+        }),
+        Type::Tuple(tuple) => Type::Tuple(TupleType {
+            element_types: tuple.element_types.iter().map(|element| substitute(element, bindings)).collect(),
+        }),
+        Type::Union(union) => Type::Union(UnionType {
+            types: union.types.iter().map(|member| substitute(member, bindings)).collect(),
+        }),
+        Type::Struct(struct_type) => Type::Struct(StructType {
+            name: struct_type.name.clone(),
+            type_args: struct_type.type_args.iter().map(|arg| substitute(arg, bindings)).collect(),
+        }),
+        other => other.clone(),
+    }
+}
+
+/// A JSON number as a value of the integer type `target`, or why it is not one
+fn integer_value(n: f64, target: &Type) -> Result<RuntimeValue, String> {
+    if n.fract() != 0.0 {
+        return Err(format_float64(n));
+    }
+    let (min, max) = match target {
+        Type::Int8 => (i8::MIN as f64, i8::MAX as f64),
+        Type::Int16 => (i16::MIN as f64, i16::MAX as f64),
+        Type::Int32 => (i32::MIN as f64, i32::MAX as f64),
+        Type::Int64 => (i64::MIN as f64, i64::MAX as f64),
+        Type::UInt8 => (0.0, u8::MAX as f64),
+        Type::UInt16 => (0.0, u16::MAX as f64),
+        Type::UInt32 => (0.0, u32::MAX as f64),
+        _ => (0.0, u64::MAX as f64),
+    };
+    if n < min || n > max {
+        return Err(format!("{}, which is out of range", format_float64(n)));
+    }
+    Ok(match target {
+        Type::Int8 => RuntimeValue::Int8(n as i8),
+        Type::Int16 => RuntimeValue::Int16(n as i16),
+        Type::Int32 => RuntimeValue::Int32(n as i32),
+        Type::Int64 => RuntimeValue::Int64(n as i64),
+        Type::UInt8 => RuntimeValue::UInt8(n as u8),
+        Type::UInt16 => RuntimeValue::UInt16(n as u16),
+        Type::UInt32 => RuntimeValue::UInt32(n as u32),
+        _ => RuntimeValue::UInt64(n as u64),
+    })
+}
+
+/// A JSON value as an untyped runtime value: whole numbers become int64,
+/// arrays slices and objects maps
+fn dynamic_value(json: &JsonValue) -> RuntimeValue {
+    match json {
+        JsonValue::Null => RuntimeValue::Null,
+        JsonValue::Bool(b) => RuntimeValue::Bool(*b),
+        JsonValue::Number(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => RuntimeValue::Int64(*n as i64),
+        JsonValue::Number(n) => RuntimeValue::Float64(*n),
+        JsonValue::String(s) => RuntimeValue::String(s.clone()),
+        JsonValue::Array(items) => RuntimeValue::Slice(items.iter().map(dynamic_value).collect()),
+        JsonValue::Object(object) => RuntimeValue::Map(
+            object
+                .iter()
+                .map(|(key, value)| (key.clone(), dynamic_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn json_kind(json: &JsonValue) -> &'static str {
+    match json {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "bool",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
+
+/// A type as written in Bulu source, for error messages
+fn type_name(target: &Type) -> String {
+    match target {
+        Type::Array(array) => match array.size {
+            Some(size) => format!("[{}]{}", size, type_name(&array.element_type)),
+            None => format!("[]{}", type_name(&array.element_type)),
+        },
+        Type::Slice(slice) => format!("[]{}", type_name(&slice.element_type)),
+        Type::Map(map) => format!("map[{}]{}", type_name(&map.key_type), type_name(&map.value_type)),
+        Type::Tuple(tuple) => format!(
+            "({})",
+            tuple.element_types.iter().map(type_name).collect::<Vec<_>>().join(", ")
+        ),
+        Type::Union(union) => match union.types.as_slice() {
+            [member, Type::Null] => format!("{}?", type_name(member)),
+            members => members.iter().map(type_name).collect::<Vec<_>>().join(" | "),
+        },
+        Type::Struct(struct_type) if !struct_type.type_args.is_empty() => format!(
+            "{}<{}>",
+            struct_type.name,
+            struct_type.type_args.iter().map(type_name).collect::<Vec<_>>().join(", ")
+        ),
+        Type::Struct(struct_type) => struct_type.name.clone(),
+        Type::Named(name) => name.clone(),
+        Type::Null => "null".to_string(),
+        other => AstPrinter::new().print_type(other),
+    }
 }
 
 /// Escape special characters in a string for JSON
@@ -742,6 +1206,116 @@ fn escape_string(s: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ast::nodes::{Attribute, TypeParam};
+    use crate::lexer::token::Position;
+
+    fn field(name: &str, field_type: Type) -> StructField {
+        StructField {
+            name: name.to_string(),
+            field_type,
+            is_private: false,
+            attributes: Vec::new(),
+            position: Position::new(0, 0, 0),
+        }
+    }
+
+    fn optional(inner: Type) -> Type {
+        Type::Union(UnionType {
+            types: vec![inner, Type::Null],
+        })
+    }
+
+    #[test]
+    fn test_decode_typed_values() {
+        let structs = StructDecls::new();
+        let decode = |text: &str, target: &Type| decode_typed(text, target, &structs);
+
+        let pair = Type::Tuple(TupleType {
+            element_types: vec![Type::Char, optional(Type::Int32)],
+        });
+        assert_eq!(
+            decode(r#"["x", null]"#, &pair),
+            Ok(RuntimeValue::Tuple(vec![RuntimeValue::Char('x'), RuntimeValue::Null]))
+        );
+        assert_eq!(decode(r#"["xy", 1]"#, &pair), Err("$[0]: expected char, got string".to_string()));
+        assert_eq!(decode("[1]", &pair), Err("$: expected 2 elements, got 1".to_string()));
+        assert_eq!(decode("1.5", &Type::Int64), Err("$: expected int64, got 1.5".to_string()));
+        assert_eq!(decode("-1", &Type::UInt32), Err("$: expected uint32, got -1, which is out of range".to_string()));
+
+        let either = Type::Union(UnionType {
+            types: vec![Type::Int32, Type::String],
+        });
+        assert_eq!(decode(r#""a""#, &either), Ok(RuntimeValue::String("a".to_string())));
+        assert_eq!(decode("true", &either), Err("$: expected int32 | string, got bool".to_string()));
+
+        let keyed = Type::Map(MapType {
+            key_type: Box::new(Type::Int32),
+            value_type: Box::new(Type::String),
+        });
+        assert_eq!(
+            decode("{}", &keyed),
+            Err("$: cannot decode into map[int32]string, JSON keys are strings".to_string())
+        );
+        assert_eq!(
+            decode(r#"{"a": [1, 2.5]}"#, &Type::Any),
+            Ok(RuntimeValue::Map(HashMap::from([(
+                "a".to_string(),
+                RuntimeValue::Slice(vec![RuntimeValue::Int64(1), RuntimeValue::Float64(2.5)])
+            )]).into()))
+        );
+    }
+
+    #[test]
+    fn test_decode_typed_generic_struct() {
+        let mut id = field("id", Type::Named("K".to_string()));
+        id.attributes.push(Attribute {
+            name: "json".to_string(),
+            args: vec![Expression::Literal(LiteralExpr {
+                value: LiteralValue::String("key".to_string()),
+                position: Position::new(0, 0, 0),
+            })],
+            position: Position::new(0, 0, 0),
+        });
+        let entry = StructDecl {
+            name: "Entry".to_string(),
+            type_params: vec![TypeParam {
+                name: "K".to_string(),
+                constraints: Vec::new(),
+                position: Position::new(0, 0, 0),
+            }],
+            fields: vec![id, field("note", optional(Type::String))],
+            methods: Vec::new(),
+            doc_comment: None,
+            is_exported: false,
+            position: Position::new(0, 0, 0),
+        };
+        let structs = StructDecls::from([("Entry".to_string(), entry)]);
+        let target = Type::Struct(StructType {
+            name: "Entry".to_string(),
+            type_args: vec![Type::UInt8],
+        });
+
+        let value = decode_typed(r#"{"key": 4}"#, &target, &structs).unwrap();
+        assert_eq!(
+            value,
+            RuntimeValue::Struct {
+                name: "Entry".to_string(),
+                fields: HashMap::from([
+                    ("id".to_string(), RuntimeValue::UInt8(4)),
+                    ("note".to_string(), RuntimeValue::Null),
+                ]),
+            }
+        );
+        assert_eq!(encode_typed(&value, &structs), Ok(r#"{"key":4,"note":null}"#.to_string()));
+        assert_eq!(
+            decode_typed(r#"{"key": "4"}"#, &target, &structs),
+            Err("$.key: expected uint8, got string".to_string())
+        );
+        assert_eq!(
+            decode_typed("{}", &Type::Named("Missing".to_string()), &structs),
+            Err("$: cannot decode JSON into unknown type Missing".to_string())
+        );
+    }
 
     #[test]
     fn test_json_value_creation() {
@@ -762,6 +1336,14 @@ mod tests {
         assert_eq!(str_val.as_str(), Some("hello"));
     }
 
+    #[test]
+    fn test_json_from_runtime_value() {
+        let set = RuntimeValue::Set(vec![RuntimeValue::Int32(1), RuntimeValue::Int32(2)]);
+        assert_eq!(Json::stringify(&Json::from_runtime_value(&set).unwrap()), "[1,2]");
+        assert!(Json::from_runtime_value(&RuntimeValue::Float64(f64::NAN)).is_err());
+        assert!(Json::from_runtime_value(&RuntimeValue::Channel(1)).is_err());
+    }
+
     #[test]
     fn test_json_parse_primitives() {
         assert_eq!(Json::parse("null").unwrap(), JsonValue::Null);
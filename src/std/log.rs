@@ -0,0 +1,454 @@
+// std.log module - Leveled, structured logging to pluggable sinks
+//
+//   import { info, warn, logger, setLevel, setSink, addSink, stderrSink, fileSink } from "std/log"
+//
+//   info("server started", {"port": 8080})      // ... INFO server started port=8080
+//   setLevel("debug")                            // or BULU_LOG=debug
+//   let http = logger("http").with({"id": 7})    // named, with context fields
+//   http.warn("slow request", {"ms": 250})       // ... WARN http: slow request id=7 ms=250
+//   addSink(fileSink("app.log", 1048576, 3).json())
+//
+// Records below the global level are dropped, and each sink can raise the
+// level further with `sink.level(name)`. The levels are debug, info, warn and
+// error, and `off` drops everything. The initial level is the last bare level
+// in `BULU_LOG` (`BULU_LOG=warn`, or `BULU_LOG=bulu::package=trace,debug`),
+// and info without one. The initial sink writes text to stderr.
+//
+// Text records are `<time> <LEVEL> <logger>: <message> key=value ...`, with
+// fields sorted by key and values quoted when they contain spaces; JSON lines
+// hold `time`, `level`, `logger` and `message` followed by the fields. A file
+// sink with a size limit renames a full file to `<path>.1`, shifting older
+// files up to `<path>.<maxFiles>` and dropping the oldest.
+
+use crate::std::json::{encode_document, Json, JsonValue, StructDecls};
+use crate::types::primitive::RuntimeValue;
+use chrono::{SecondsFormat, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Functions the `std/log` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &[
+    "debug",
+    "info",
+    "warn",
+    "error",
+    "logger",
+    "setLevel",
+    "level",
+    "setSink",
+    "addSink",
+    "stderrSink",
+    "fileSink",
+];
+
+/// Name of the type of named loggers
+pub const LOGGER: &str = "Logger";
+/// Name of the type of sink descriptions
+pub const SINK: &str = "LogSink";
+
+/// The types of `std/log` values, in the order of their type ids
+pub const TYPES: &[&str] = &[LOGGER, SINK];
+
+/// Whether `name` is one of the types of `std/log` values
+pub fn is_log_type(name: &str) -> bool {
+    TYPES.contains(&name)
+}
+
+/// Severity of a record, or the threshold below which records are dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+    /// Only a threshold: nothing is logged
+    Off,
+}
+
+impl Level {
+    /// The level called `name`
+    pub fn parse(name: &str) -> Result<Level, String> {
+        match name {
+            "debug" => Ok(Level::Debug),
+            "info" => Ok(Level::Info),
+            "warn" => Ok(Level::Warn),
+            "error" => Ok(Level::Error),
+            "off" => Ok(Level::Off),
+            _ => Err(format!(
+                "unknown log level \"{}\", expected debug, info, warn, error or off",
+                name
+            )),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+            Level::Off => "off",
+        }
+    }
+}
+
+/// The level set by a `BULU_LOG` value: its last directive without a target,
+/// with `trace` read as debug. Directives naming a target configure the
+/// compiler's own logging and are ignored.
+pub fn env_level(value: &str) -> Option<Level> {
+    value
+        .split(',')
+        .rev()
+        .map(str::trim)
+        .filter(|directive| !directive.contains('='))
+        .filter_map(|directive| match directive.to_ascii_lowercase().as_str() {
+            "trace" => Some(Level::Debug),
+            name => Level::parse(name).ok(),
+        })
+        .next()
+}
+
+/// How a sink writes records
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// Where a sink writes records
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    Stderr,
+    /// A file, rotated once it would grow past `max_bytes` unless that is 0
+    File { path: PathBuf, max_bytes: u64, max_files: u32 },
+}
+
+/// A destination for records, with its format and threshold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sink {
+    pub target: Target,
+    pub format: Format,
+    pub level: Level,
+}
+
+impl Sink {
+    pub fn stderr() -> Self {
+        Sink {
+            target: Target::Stderr,
+            format: Format::Text,
+            level: Level::Debug,
+        }
+    }
+
+    pub fn file(path: impl Into<PathBuf>, max_bytes: u64, max_files: u32) -> Self {
+        Sink {
+            target: Target::File {
+                path: path.into(),
+                max_bytes,
+                max_files,
+            },
+            format: Format::Text,
+            level: Level::Debug,
+        }
+    }
+
+    /// Write one line, rotating a full file first
+    fn write_line(&self, line: &str) -> Result<(), String> {
+        match &self.target {
+            Target::Stderr => {
+                let _ = writeln!(std::io::stderr(), "{}", line);
+                Ok(())
+            }
+            Target::File { path, max_bytes, max_files } => {
+                let error = |e: std::io::Error| format!("cannot write log file {}: {}", path.display(), e);
+                let size = fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0);
+                if *max_bytes > 0 && size > 0 && size + line.len() as u64 + 1 > *max_bytes {
+                    rotate(path, *max_files).map_err(error)?;
+                }
+                let mut file = OpenOptions::new().create(true).append(true).open(path).map_err(error)?;
+                writeln!(file, "{}", line).map_err(error)
+            }
+        }
+    }
+}
+
+/// Shift `path` to `path.1`, `path.1` to `path.2` and so on, keeping at most
+/// `max_files` old files
+fn rotate(path: &Path, max_files: u32) -> std::io::Result<()> {
+    let numbered = |index: u32| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    };
+    if max_files == 0 {
+        return fs::remove_file(path);
+    }
+    let _ = fs::remove_file(numbered(max_files));
+    for index in (1..max_files).rev() {
+        if numbered(index).exists() {
+            fs::rename(numbered(index), numbered(index + 1))?;
+        }
+    }
+    fs::rename(path, numbered(1))
+}
+
+/// A record to log; fields are sorted by key
+pub struct Record<'a> {
+    pub level: Level,
+    pub logger: &'a str,
+    pub message: &'a str,
+    pub fields: BTreeMap<String, RuntimeValue>,
+}
+
+impl Record<'_> {
+    /// `<time> <LEVEL> <logger>: <message> key=value ...`
+    pub fn to_text(&self, time: &str) -> String {
+        let mut line = format!("{} {:<5} ", time, self.level.name().to_ascii_uppercase());
+        if !self.logger.is_empty() {
+            line.push_str(self.logger);
+            line.push_str(": ");
+        }
+        line.push_str(self.message);
+        for (key, value) in &self.fields {
+            let text = match value {
+                RuntimeValue::String(text)
+                    if text.is_empty() || text.contains(|c: char| c.is_whitespace() || c == '"' || c == '=') =>
+                {
+                    format!("{:?}", text)
+                }
+                value => value.to_string(),
+            };
+            line.push_str(&format!(" {}={}", key, text));
+        }
+        line
+    }
+
+    /// A JSON object with `time`, `level`, `logger` and `message` first.
+    /// Struct fields are written with their JSON keys from `structs`.
+    pub fn to_json(&self, time: &str, structs: &StructDecls) -> String {
+        let string = |text: &str| Json::stringify(&JsonValue::String(text.to_string()));
+        let mut line = format!(
+            "{{\"time\":{},\"level\":{},\"logger\":{},\"message\":{}",
+            string(time),
+            string(self.level.name()),
+            string(self.logger),
+            string(self.message)
+        );
+        for (key, value) in &self.fields {
+            let value = encode_document(value, structs)
+                .map(|json| Json::stringify(&json))
+                .unwrap_or_else(|_| string(&value.to_string()));
+            line.push_str(&format!(",{}:{}", string(key), value));
+        }
+        line.push('}');
+        line
+    }
+}
+
+/// The global level and sinks
+struct LogConfig {
+    level: Level,
+    sinks: Vec<Sink>,
+}
+
+fn config() -> &'static Mutex<LogConfig> {
+    static CONFIG: OnceLock<Mutex<LogConfig>> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        let level = std::env::var(crate::logging::LOG_ENV)
+            .ok()
+            .and_then(|value| env_level(&value))
+            .unwrap_or(Level::Info);
+        Mutex::new(LogConfig {
+            level,
+            sinks: vec![Sink::stderr()],
+        })
+    })
+}
+
+fn with_config<T>(action: impl FnOnce(&mut LogConfig) -> T) -> T {
+    let mut config = config().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    action(&mut config)
+}
+
+/// The global threshold
+pub fn level() -> Level {
+    with_config(|config| config.level)
+}
+
+pub fn set_level(level: Level) {
+    with_config(|config| config.level = level);
+}
+
+/// Replace every sink with `sink`
+pub fn set_sink(sink: Sink) {
+    with_config(|config| config.sinks = vec![sink]);
+}
+
+pub fn add_sink(sink: Sink) {
+    with_config(|config| config.sinks.push(sink));
+}
+
+/// Write `record` to every sink that accepts its level. Every sink is
+/// written to even when one fails; the first failure is returned.
+pub fn emit(record: &Record, structs: &StructDecls) -> Result<(), String> {
+    with_config(|config| {
+        if record.level == Level::Off || record.level < config.level {
+            return Ok(());
+        }
+        let time = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let mut outcome = Ok(());
+        for sink in config.sinks.iter().filter(|sink| record.level >= sink.level) {
+            let line = match sink.format {
+                Format::Text => record.to_text(&time),
+                Format::Json => record.to_json(&time, structs),
+            };
+            if let Err(e) = sink.write_line(&line) {
+                outcome = outcome.and(Err(e));
+            }
+        }
+        outcome
+    })
+}
+
+fn value_struct(name: &str, fields: Vec<(&str, RuntimeValue)>) -> RuntimeValue {
+    RuntimeValue::Struct {
+        name: name.to_string(),
+        fields: fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect(),
+    }
+}
+
+/// A Logger value with its name and context fields
+pub fn logger_value(name: &str, fields: HashMap<String, RuntimeValue>) -> RuntimeValue {
+    value_struct(
+        LOGGER,
+        vec![
+            ("name", RuntimeValue::String(name.to_string())),
+            ("fields", RuntimeValue::Map(fields.into())),
+        ],
+    )
+}
+
+/// The name and context fields of a Logger value's fields
+pub fn logger_of(fields: &HashMap<String, RuntimeValue>) -> Option<(&str, &HashMap<String, RuntimeValue>)> {
+    match (fields.get("name"), fields.get("fields")) {
+        (Some(RuntimeValue::String(name)), Some(RuntimeValue::Map(context))) => Some((name, context)),
+        _ => None,
+    }
+}
+
+/// A LogSink value describing `sink`
+pub fn sink_value(sink: &Sink) -> RuntimeValue {
+    let mut fields = vec![
+        ("format", RuntimeValue::String(if sink.format == Format::Json { "json" } else { "text" }.to_string())),
+        ("level", RuntimeValue::String(sink.level.name().to_string())),
+    ];
+    match &sink.target {
+        Target::Stderr => fields.push(("target", RuntimeValue::String("stderr".to_string()))),
+        Target::File { path, max_bytes, max_files } => {
+            fields.push(("target", RuntimeValue::String("file".to_string())));
+            fields.push(("path", RuntimeValue::String(path.display().to_string())));
+            fields.push(("maxBytes", RuntimeValue::Int64(*max_bytes as i64)));
+            fields.push(("maxFiles", RuntimeValue::Int32(*max_files as i32)));
+        }
+    }
+    value_struct(SINK, fields)
+}
+
+/// The sink a LogSink value's fields describe
+pub fn sink_of(fields: &HashMap<String, RuntimeValue>) -> Option<Sink> {
+    let text = |key: &str| match fields.get(key) {
+        Some(RuntimeValue::String(text)) => Some(text.as_str()),
+        _ => None,
+    };
+    let target = match text("target")? {
+        "stderr" => Target::Stderr,
+        "file" => Target::File {
+            path: PathBuf::from(text("path")?),
+            max_bytes: match fields.get("maxBytes")? {
+                RuntimeValue::Int64(bytes) => u64::try_from(*bytes).ok()?,
+                _ => return None,
+            },
+            max_files: match fields.get("maxFiles")? {
+                RuntimeValue::Int32(files) => u32::try_from(*files).ok()?,
+                _ => return None,
+            },
+        },
+        _ => return None,
+    };
+    let format = match text("format")? {
+        "json" => Format::Json,
+        _ => Format::Text,
+    };
+    Some(Sink {
+        target,
+        format,
+        level: Level::parse(text("level")?).ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(fields: Vec<(&str, RuntimeValue)>) -> Record<'static> {
+        Record {
+            level: Level::Warn,
+            logger: "http",
+            message: "slow request",
+            fields: fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_record_lines() {
+        let record = record(vec![
+            ("path", RuntimeValue::String("/a b".to_string())),
+            ("ms", RuntimeValue::Int32(250)),
+            ("ok", RuntimeValue::Bool(false)),
+        ]);
+        assert_eq!(
+            record.to_text("T"),
+            "T WARN  http: slow request ms=250 ok=false path=\"/a b\""
+        );
+        assert_eq!(
+            record.to_json("T", &StructDecls::new()),
+            r#"{"time":"T","level":"warn","logger":"http","message":"slow request","ms":250,"ok":false,"path":"/a b"}"#
+        );
+    }
+
+    #[test]
+    fn test_env_level() {
+        assert_eq!(env_level("warn"), Some(Level::Warn));
+        assert_eq!(env_level("bulu::package=trace, DEBUG"), Some(Level::Debug));
+        assert_eq!(env_level("error,trace"), Some(Level::Debug));
+        assert_eq!(env_level("bulu=info"), None);
+        assert_eq!(
+            Level::parse("verbose").unwrap_err(),
+            "unknown log level \"verbose\", expected debug, info, warn, error or off"
+        );
+    }
+
+    #[test]
+    fn test_file_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        let sink = Sink::file(&path, 16, 2);
+        for line in ["first line", "second line", "third line", "fourth line"] {
+            sink.write_line(line).unwrap();
+        }
+        let read = |name: &str| fs::read_to_string(dir.path().join(name)).unwrap();
+        assert_eq!(read("app.log"), "fourth line\n");
+        assert_eq!(read("app.log.1"), "third line\n");
+        assert_eq!(read("app.log.2"), "second line\n");
+        assert!(!dir.path().join("app.log.3").exists());
+
+        let RuntimeValue::Struct { fields, .. } = sink_value(&sink) else {
+            panic!("expected a struct");
+        };
+        assert_eq!(sink_of(&fields), Some(sink));
+    }
+}
@@ -15,6 +15,30 @@ pub mod constants {
     pub const LOG10_E: f64 = std::f64::consts::LOG10_E;
 }
 
+/// Functions the `std/math` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &[
+    "abs", "sqrt", "cbrt", "pow", "exp", "log", "sin", "cos", "tan", "hypot",
+    "floor", "ceil", "trunc", "round", "roundHalfEven",
+    "min", "max", "clamp", "gcd", "lcm",
+    "isNaN", "isInfinite", "isFinite",
+    "mean", "median", "stddev",
+];
+
+/// Rounding modes for `Math::round_with`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Round half away from zero (2.5 -> 3, -2.5 -> -3)
+    HalfAwayFromZero,
+    /// Round half to even (2.5 -> 2, 3.5 -> 4)
+    HalfEven,
+    /// Round towards negative infinity
+    Floor,
+    /// Round towards positive infinity
+    Ceil,
+    /// Round towards zero
+    Trunc,
+}
+
 /// Basic mathematical operations
 pub struct Math;
 
@@ -34,11 +58,11 @@ impl Math {
         x.abs()
     }
     
-    /// Sign function (-1, 0, or 1)
+    /// Sign function (-1, 0, or 1). Zeros keep their sign and NaN stays NaN
     pub fn sign(x: f64) -> f64 {
         if x > 0.0 { 1.0 }
         else if x < 0.0 { -1.0 }
-        else { 0.0 }
+        else { x }
     }
     
     /// Maximum of two values
@@ -63,6 +87,11 @@ impl Math {
         x.sqrt()
     }
     
+    /// Length of the hypotenuse, sqrt(x^2 + y^2) without intermediate overflow
+    pub fn hypot(x: f64, y: f64) -> f64 {
+        x.hypot(y)
+    }
+    
     /// Cube root
     pub fn cbrt(x: f64) -> f64 {
         x.cbrt()
@@ -123,6 +152,22 @@ impl Math {
         x.round()
     }
     
+    /// Round half to even (banker's rounding)
+    pub fn round_half_even(x: f64) -> f64 {
+        x.round_ties_even()
+    }
+    
+    /// Round using the given rounding mode
+    pub fn round_with(x: f64, mode: RoundingMode) -> f64 {
+        match mode {
+            RoundingMode::HalfAwayFromZero => x.round(),
+            RoundingMode::HalfEven => x.round_ties_even(),
+            RoundingMode::Floor => x.floor(),
+            RoundingMode::Ceil => x.ceil(),
+            RoundingMode::Trunc => x.trunc(),
+        }
+    }
+    
     /// Truncate (remove fractional part)
     pub fn trunc(x: f64) -> f64 {
         x.trunc()
@@ -252,14 +297,17 @@ impl Stats {
         }
     }
     
-    /// Calculate median
+    /// Calculate median. NaN if any value is NaN
     pub fn median(values: &[f64]) -> Option<f64> {
         if values.is_empty() {
             return None;
         }
+        if values.iter().any(|v| v.is_nan()) {
+            return Some(f64::NAN);
+        }
         
         let mut sorted = values.to_vec();
-        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        sorted.sort_by(|a, b| a.total_cmp(b));
         
         let len = sorted.len();
         if len % 2 == 0 {
@@ -299,7 +347,7 @@ impl Stats {
         Some(sum_squared_diff / (values.len() - 1) as f64)
     }
     
-    /// Calculate standard deviation
+    /// Calculate sample standard deviation
     pub fn std_dev(values: &[f64]) -> Option<f64> {
         Self::variance(values).map(|v| v.sqrt())
     }
@@ -323,7 +371,7 @@ impl Stats {
         }
         
         let mut sorted = values.to_vec();
-        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        sorted.sort_by(|a, b| a.total_cmp(b));
         
         let index = (p / 100.0) * (sorted.len() - 1) as f64;
         let lower = index.floor() as usize;
@@ -558,6 +606,28 @@ mod tests {
         assert_eq!(Math::round(3.5), 4.0);
         assert_eq!(Math::trunc(3.7), 3.0);
         assert!((Math::fract(3.7) - 0.7).abs() < 1e-10);
+        
+        assert_eq!(Math::round(2.5), 3.0);
+        assert_eq!(Math::round_half_even(2.5), 2.0);
+        assert_eq!(Math::round_half_even(3.5), 4.0);
+        assert_eq!(Math::round_with(-2.5, RoundingMode::HalfAwayFromZero), -3.0);
+        assert_eq!(Math::round_with(-2.5, RoundingMode::HalfEven), -2.0);
+        assert_eq!(Math::round_with(-2.5, RoundingMode::Floor), -3.0);
+        assert_eq!(Math::round_with(-2.5, RoundingMode::Ceil), -2.0);
+        assert_eq!(Math::round_with(-2.5, RoundingMode::Trunc), -2.0);
+    }
+    
+    #[test]
+    fn test_float_edge_cases() {
+        assert_eq!(Math::hypot(3.0, 4.0), 5.0);
+        assert!((Math::hypot(1e200, 1e200) / 1e200 - constants::SQRT_2).abs() < 1e-10);
+        
+        assert!(Math::sign(f64::NAN).is_nan());
+        assert!(Math::sign(-0.0).is_sign_negative());
+        assert!(Math::clamp(f64::NAN, 0.0, 1.0).is_nan());
+        
+        assert!(Stats::median(&[1.0, f64::NAN, 3.0]).unwrap().is_nan());
+        assert_eq!(Stats::percentile(&[3.0, 1.0, 2.0], 50.0), Some(2.0));
     }
     
     #[test]
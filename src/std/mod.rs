@@ -3,12 +3,21 @@
 pub mod io;
 pub mod fmt;
 pub mod strings;
+pub mod regex;
 pub mod arrays;
 pub mod math;
 pub mod random;
 pub mod time;
+pub mod log;
+pub mod schedule;
 pub mod os;
+pub mod fs;
+pub mod process;
 pub mod flag;
+pub mod sync;
+pub mod context;
+pub mod collections;
+pub mod builtin;
 
 // Testing module
 pub mod test;
@@ -16,12 +25,61 @@ pub mod test;
 // Networking modules
 pub mod http;
 pub mod net;
+pub mod tls;
+pub mod websocket;
 
 // Data format modules
 pub mod json;
+pub mod toml;
+pub mod yaml;
 pub mod xml;
 pub mod csv;
+pub mod binary;
+pub mod checksum;
+pub mod encoding;
 
 // Cryptography and database modules
 pub mod crypto;
-pub mod db;
\ No newline at end of file
+pub mod db;
+
+// Text templating and internationalization modules
+pub mod template;
+pub mod i18n;
+
+/// Standard library modules whose exports are their `EXPORTED_FUNCTIONS`,
+/// by the name they are imported under (`std/<name>`)
+pub const FUNCTION_MODULES: &[(&str, &[&str])] = &[
+    ("fmt", fmt::EXPORTED_FUNCTIONS),
+    ("strings", strings::EXPORTED_FUNCTIONS),
+    ("regex", regex::EXPORTED_FUNCTIONS),
+    ("arrays", arrays::EXPORTED_FUNCTIONS),
+    ("math", math::EXPORTED_FUNCTIONS),
+    ("time", time::EXPORTED_FUNCTIONS),
+    ("log", log::EXPORTED_FUNCTIONS),
+    ("schedule", schedule::EXPORTED_FUNCTIONS),
+    ("fs", fs::EXPORTED_FUNCTIONS),
+    ("process", process::EXPORTED_FUNCTIONS),
+    ("sync", sync::EXPORTED_FUNCTIONS),
+    ("context", context::EXPORTED_FUNCTIONS),
+    ("collections", collections::EXPORTED_FUNCTIONS),
+    ("http", http::EXPORTED_FUNCTIONS),
+    ("json", json::EXPORTED_FUNCTIONS),
+    ("toml", toml::EXPORTED_FUNCTIONS),
+    ("yaml", yaml::EXPORTED_FUNCTIONS),
+    ("binary", binary::EXPORTED_FUNCTIONS),
+    ("checksum", checksum::EXPORTED_FUNCTIONS),
+    ("encoding", encoding::EXPORTED_FUNCTIONS),
+    ("crypto", crypto::EXPORTED_FUNCTIONS),
+    ("db", db::EXPORTED_FUNCTIONS),
+    ("template", template::EXPORTED_FUNCTIONS),
+    ("i18n", i18n::EXPORTED_FUNCTIONS),
+];
+
+/// The functions exported by the module imported as `std/<name>`, if it is
+/// one of the `FUNCTION_MODULES`
+pub fn module_functions(name: &str) -> Option<&'static [&'static str]> {
+    FUNCTION_MODULES
+        .iter()
+        .find(|(module, _)| *module == name)
+        .map(|(_, functions)| *functions)
+}
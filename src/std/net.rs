@@ -1,6 +1,8 @@
 // TCP/UDP networking functionality for the Bulu programming language
 // Requirements: 7.2.2
 
+use super::tls::{self, TlsAcceptor, TlsOptions};
+use rustls::{ClientConnection, ServerConnection, StreamOwned};
 use std::io::{Read, Write, Result as IoResult};
 use std::net::{
     TcpListener, TcpStream, UdpSocket, SocketAddr, ToSocketAddrs,
@@ -66,9 +68,54 @@ impl NetAddr {
     }
 }
 
+/// The socket under a connection, encrypted or not
+enum Stream {
+    Plain(TcpStream),
+    Client(Box<StreamOwned<ClientConnection, TcpStream>>),
+    Server(Box<StreamOwned<ServerConnection, TcpStream>>),
+}
+
+impl Stream {
+    fn socket(&self) -> &TcpStream {
+        match self {
+            Stream::Plain(stream) => stream,
+            Stream::Client(tls) => &tls.sock,
+            Stream::Server(tls) => &tls.sock,
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        match self {
+            Stream::Plain(stream) => stream.read(buf),
+            Stream::Client(tls) => tls.read(buf),
+            Stream::Server(tls) => tls.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        match self {
+            Stream::Plain(stream) => stream.write(buf),
+            Stream::Client(tls) => tls.write(buf),
+            Stream::Server(tls) => tls.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        match self {
+            Stream::Plain(stream) => stream.flush(),
+            Stream::Client(tls) => tls.flush(),
+            Stream::Server(tls) => tls.flush(),
+        }
+    }
+}
+
 /// TCP connection wrapper
 pub struct TcpConnection {
-    stream: TcpStream,
+    stream: Stream,
     local_addr: SocketAddr,
     peer_addr: SocketAddr,
 }
@@ -81,7 +128,7 @@ impl TcpConnection {
         let peer_addr = stream.peer_addr()?;
 
         Ok(TcpConnection {
-            stream,
+            stream: Stream::Plain(stream),
             local_addr,
             peer_addr,
         })
@@ -94,12 +141,38 @@ impl TcpConnection {
         let peer_addr = stream.peer_addr()?;
 
         Ok(TcpConnection {
-            stream,
+            stream: Stream::Plain(stream),
+            local_addr,
+            peer_addr,
+        })
+    }
+
+    /// Connect and negotiate TLS. The certificate is checked against
+    /// `options.server_name` if set, else the domain or IP connected to.
+    pub fn connect_tls(addr: NetAddr, options: &TlsOptions) -> Result<Self, Box<dyn std::error::Error>> {
+        let server_name = match (&options.server_name, &addr) {
+            (Some(name), _) => name.clone(),
+            (None, NetAddr::Domain(domain, _)) => domain.clone(),
+            (None, NetAddr::Ipv4(ip, _)) => ip.to_string(),
+            (None, NetAddr::Ipv6(ip, _)) => ip.to_string(),
+        };
+        let socket_addr = addr.to_socket_addr()?;
+        let stream = TcpStream::connect(socket_addr)?;
+        let local_addr = stream.local_addr()?;
+        let peer_addr = stream.peer_addr()?;
+        let tls = tls::connect(stream, &server_name, options)?;
+
+        Ok(TcpConnection {
+            stream: Stream::Client(Box::new(tls)),
             local_addr,
             peer_addr,
         })
     }
 
+    pub fn is_tls(&self) -> bool {
+        !matches!(self.stream, Stream::Plain(_))
+    }
+
     pub fn local_addr(&self) -> SocketAddr {
         self.local_addr
     }
@@ -109,15 +182,15 @@ impl TcpConnection {
     }
 
     pub fn set_read_timeout(&mut self, timeout: Option<Duration>) -> IoResult<()> {
-        self.stream.set_read_timeout(timeout)
+        self.stream.socket().set_read_timeout(timeout)
     }
 
     pub fn set_write_timeout(&mut self, timeout: Option<Duration>) -> IoResult<()> {
-        self.stream.set_write_timeout(timeout)
+        self.stream.socket().set_write_timeout(timeout)
     }
 
     pub fn set_nodelay(&mut self, nodelay: bool) -> IoResult<()> {
-        self.stream.set_nodelay(nodelay)
+        self.stream.socket().set_nodelay(nodelay)
     }
 
     pub fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
@@ -148,24 +221,65 @@ impl TcpConnection {
         self.stream.flush()
     }
 
+    /// Shut down the socket; closing the writing side of a TLS connection
+    /// first tells the peer the session ended
     pub fn shutdown(&mut self, how: std::net::Shutdown) -> IoResult<()> {
-        self.stream.shutdown(how)
+        if how != std::net::Shutdown::Read {
+            match &mut self.stream {
+                Stream::Plain(_) => {}
+                Stream::Client(tls) => {
+                    tls.conn.send_close_notify();
+                    tls.flush()?;
+                }
+                Stream::Server(tls) => {
+                    tls.conn.send_close_notify();
+                    tls.flush()?;
+                }
+            }
+        }
+        self.stream.socket().shutdown(how)
     }
 
+    /// Clone a plain connection. TLS sessions cannot be shared between handles.
     pub fn try_clone(&self) -> IoResult<TcpConnection> {
-        let stream = self.stream.try_clone()?;
+        let stream = match &self.stream {
+            Stream::Plain(stream) => stream.try_clone()?,
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "TLS connections cannot be cloned",
+                ))
+            }
+        };
         Ok(TcpConnection {
-            stream,
+            stream: Stream::Plain(stream),
             local_addr: self.local_addr,
             peer_addr: self.peer_addr,
         })
     }
 }
 
+impl Read for TcpConnection {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        self.stream.read(buf)
+    }
+}
+
+impl Write for TcpConnection {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.stream.flush()
+    }
+}
+
 /// TCP server wrapper
 pub struct TcpServer {
     listener: TcpListener,
     local_addr: SocketAddr,
+    tls: Option<TlsAcceptor>,
 }
 
 impl TcpServer {
@@ -177,27 +291,36 @@ impl TcpServer {
         Ok(TcpServer {
             listener,
             local_addr,
+            tls: None,
         })
     }
 
+    /// Bind a server whose connections are encrypted with `acceptor`
+    pub fn bind_tls(addr: NetAddr, acceptor: TlsAcceptor) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut server = Self::bind(addr)?;
+        server.tls = Some(acceptor);
+        Ok(server)
+    }
+
+    pub fn is_tls(&self) -> bool {
+        self.tls.is_some()
+    }
+
     pub fn local_addr(&self) -> SocketAddr {
         self.local_addr
     }
 
+    /// Accept a connection. On a TLS server this also completes the
+    /// handshake, and fails if the client does not.
     pub fn accept(&self) -> IoResult<TcpConnection> {
         let (stream, peer_addr) = self.listener.accept()?;
-        let local_addr = stream.local_addr()?;
-
-        Ok(TcpConnection {
-            stream,
-            local_addr,
-            peer_addr,
-        })
+        wrap_accepted(stream, peer_addr, self.tls.as_ref())
     }
 
     pub fn incoming(&self) -> TcpIncoming {
         TcpIncoming {
             listener: &self.listener,
+            tls: self.tls.as_ref(),
         }
     }
 
@@ -210,13 +333,29 @@ impl TcpServer {
         Ok(TcpServer {
             listener,
             local_addr: self.local_addr,
+            tls: self.tls.clone(),
         })
     }
 }
 
+fn wrap_accepted(stream: TcpStream, peer_addr: SocketAddr, tls: Option<&TlsAcceptor>) -> IoResult<TcpConnection> {
+    let local_addr = stream.local_addr()?;
+    let stream = match tls {
+        Some(acceptor) => Stream::Server(Box::new(acceptor.accept(stream)?)),
+        None => Stream::Plain(stream),
+    };
+
+    Ok(TcpConnection {
+        stream,
+        local_addr,
+        peer_addr,
+    })
+}
+
 /// Iterator for incoming TCP connections
 pub struct TcpIncoming<'a> {
     listener: &'a TcpListener,
+    tls: Option<&'a TlsAcceptor>,
 }
 
 impl<'a> Iterator for TcpIncoming<'a> {
@@ -224,16 +363,7 @@ impl<'a> Iterator for TcpIncoming<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.listener.accept() {
-            Ok((stream, peer_addr)) => {
-                match stream.local_addr() {
-                    Ok(local_addr) => Some(Ok(TcpConnection {
-                        stream,
-                        local_addr,
-                        peer_addr,
-                    })),
-                    Err(e) => Some(Err(e)),
-                }
-            }
+            Ok((stream, peer_addr)) => Some(wrap_accepted(stream, peer_addr, self.tls)),
             Err(e) => Some(Err(e)),
         }
     }
@@ -1,14 +1,135 @@
 // std.os module - Operating system interface
-// Provides access to command-line arguments, environment variables, and system information
+//
+//   import { args, getEnv, setEnv, environ, hostname, pid, signal, atExit, exit } from "std/os"
+//
+//   let home = getEnv("HOME")                   // null when unset
+//   setEnv("MODE", "release")
+//   for name, value in environ() { ... }        // map[string]string
+//   let interrupted = signal("SIGINT")          // receives "SIGINT" on each Ctrl-C
+//   atExit(func() { println("bye") })           // runs when main returns or on exit()
+//   setGoroutineMemoryLimit(64 * 1024 * 1024)   // soft limit of goroutines started from now on
+//   for usage in topAllocators(5) { ... }       // {"goroutine": 3, "bytes": ..., "allocations": ..., "limit": ...}
+//   for usage in stackUsage() { ... }           // {"goroutine": 3, "current": ..., "peak": ..., "segments": ...}
+//   let stuck = shutdown(2000)                  // [{"goroutine": 7, "runningMs": ...}] still running after 2s
+//
+// `args`, `getEnv`, `cwd` and `exit` are also prelude builtins. The
+// signatures in `FUNCTIONS` are what the type checker and the module
+// resolvers know about the module, so a function is added in one place.
+//
+// Signals are SIGINT and SIGTERM. Subscribing installs a handler for the
+// signal, so it no longer ends the process; each delivery sends the signal
+// name to every subscribed channel that has room, dropping it otherwise. Exit
+// hooks run in reverse order of registration.
+//
+// Memory limits are soft: a goroutine whose allocations go over its limit
+// fails once, where it allocated, and may catch the error and carry on.
+// `setMemoryLimit` sets the limit of the calling goroutine; 0 removes a limit.
+// `topAllocators` lists running goroutines by the bytes they allocated, the
+// main goroutine being 0 and `limit` 0 when there is none. `stackUsage`
+// lists the stack bytes of running goroutines, then of the deepest finished
+// ones, whose `current` is 0.
+//
+// `shutdown` cancels the program's root context (`background()` of
+// std/context, which goroutines run under unless given another), closes every
+// channel and waits up to the grace period, `LANG_SHUTDOWN_GRACE` milliseconds
+// or 5 seconds by default, for the other goroutines to return. It returns
+// those that did not; they keep running, but their errors are no longer printed.
 
 use crate::error::{BuluError, Result};
+use crate::runtime::channels::Channel;
 use crate::types::primitive::RuntimeValue;
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
 
 // Global storage for command-line arguments
 static PROGRAM_ARGS: Mutex<Option<Vec<String>>> = Mutex::new(None);
 
+/// The kind of a parameter or result of a std/os function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    String,
+    Int32,
+    Int64,
+    /// An array of strings
+    Strings,
+    /// A map from strings to strings
+    StringMap,
+    /// A function without parameters
+    Function,
+    /// A receive-only channel of strings
+    Channel,
+    /// An array of maps from strings to int64, one per goroutine
+    GoroutineStats,
+    /// No value
+    Void,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Kind::String => "string",
+            Kind::Int32 => "int32",
+            Kind::Int64 => "int64",
+            Kind::Strings => "[]string",
+            Kind::StringMap => "map[string]string",
+            Kind::Function => "function",
+            Kind::Channel => "<-chan string",
+            Kind::GoroutineStats => "[]map[string]int64",
+            Kind::Void => "void",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The signature of a function the `std/os` module exports
+#[derive(Debug, Clone, Copy)]
+pub struct Signature {
+    pub name: &'static str,
+    pub params: &'static [Kind],
+    /// How many of the parameters are required
+    pub required: usize,
+    pub returns: Kind,
+}
+
+const fn signature(name: &'static str, params: &'static [Kind], required: usize, returns: Kind) -> Signature {
+    Signature { name, params, required, returns }
+}
+
+/// Functions the `std/os` module exports to Bulu programs
+pub const FUNCTIONS: &[Signature] = &[
+    signature("args", &[], 0, Kind::Strings),
+    signature("getEnv", &[Kind::String], 1, Kind::String),
+    signature("setEnv", &[Kind::String, Kind::String], 2, Kind::Void),
+    signature("unsetEnv", &[Kind::String], 1, Kind::Void),
+    signature("environ", &[], 0, Kind::StringMap),
+    signature("cwd", &[], 0, Kind::String),
+    signature("hostname", &[], 0, Kind::String),
+    signature("pid", &[], 0, Kind::Int32),
+    signature("platform", &[], 0, Kind::String),
+    signature("arch", &[], 0, Kind::String),
+    signature("signal", &[Kind::String], 1, Kind::Channel),
+    signature("atExit", &[Kind::Function], 1, Kind::Void),
+    signature("exit", &[Kind::Int32], 0, Kind::Void),
+    signature("setMemoryLimit", &[Kind::Int64], 1, Kind::Void),
+    signature("setGoroutineMemoryLimit", &[Kind::Int64], 1, Kind::Void),
+    signature("topAllocators", &[Kind::Int32], 0, Kind::GoroutineStats),
+    signature("stackUsage", &[], 0, Kind::GoroutineStats),
+    signature("shutdown", &[Kind::Int64], 0, Kind::GoroutineStats),
+];
+
+/// The signature of the `std/os` function `name`
+pub fn lookup(name: &str) -> Option<&'static Signature> {
+    FUNCTIONS.iter().find(|function| function.name == name)
+}
+
+/// The names of the functions the `std/os` module exports
+pub fn exported_functions() -> impl Iterator<Item = &'static str> {
+    FUNCTIONS.iter().map(|function| function.name)
+}
+
 /// Initialize the program arguments (called from main before execution)
 pub fn init_args(args: Vec<String>) {
     if let Ok(mut program_args) = PROGRAM_ARGS.lock() {
@@ -48,19 +169,47 @@ pub fn get_env(name: &str) -> Result<RuntimeValue> {
 
 /// Set environment variable
 pub fn set_env(name: &str, value: &str) -> Result<RuntimeValue> {
+    check_env_name(name)?;
+    if value.contains('\0') {
+        return Err(BuluError::RuntimeError {
+            file: None,
+            message: format!("Invalid value for environment variable '{}'", name),
+        });
+    }
     std::env::set_var(name, value);
     Ok(RuntimeValue::Null)
 }
 
-/// Get all environment variables as a map
+/// Remove environment variable
+pub fn unset_env(name: &str) -> Result<RuntimeValue> {
+    check_env_name(name)?;
+    std::env::remove_var(name);
+    Ok(RuntimeValue::Null)
+}
+
+/// `set_var` panics on names it cannot store
+fn check_env_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.contains(['=', '\0']) {
+        return Err(BuluError::RuntimeError {
+            file: None,
+            message: format!("Invalid environment variable name '{}'", name),
+        });
+    }
+    Ok(())
+}
+
+/// Get all environment variables as a map. Variables that are not valid
+/// UTF-8 are left out.
 pub fn get_all_env() -> Result<RuntimeValue> {
     let mut env_map = HashMap::new();
-    
-    for (key, value) in std::env::vars() {
-        env_map.insert(key, RuntimeValue::String(value));
+
+    for (key, value) in std::env::vars_os() {
+        if let (Some(key), Some(value)) = (key.to_str(), value.to_str()) {
+            env_map.insert(key.to_string(), RuntimeValue::String(value.to_string()));
+        }
     }
-    
-    Ok(RuntimeValue::Map(env_map))
+
+    Ok(RuntimeValue::Map(env_map.into()))
 }
 
 /// Get current working directory
@@ -74,9 +223,36 @@ pub fn get_cwd() -> Result<RuntimeValue> {
     }
 }
 
-/// Exit the program with a status code
+/// Get the name of this machine
+pub fn get_hostname() -> Result<RuntimeValue> {
+    #[cfg(unix)]
+    {
+        let mut buffer = [0u8; 256];
+        let status = unsafe { libc::gethostname(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len()) };
+        if status != 0 {
+            return Err(BuluError::RuntimeError {
+                file: None,
+                message: format!("Failed to get the hostname: {}", std::io::Error::last_os_error()),
+            });
+        }
+        let end = buffer.iter().position(|&byte| byte == 0).unwrap_or(buffer.len());
+        Ok(RuntimeValue::String(String::from_utf8_lossy(&buffer[..end]).into_owned()))
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(RuntimeValue::String(std::env::var("COMPUTERNAME").unwrap_or_default()))
+    }
+}
+
+/// Get the id of this process
+pub fn get_pid() -> Result<RuntimeValue> {
+    Ok(RuntimeValue::Int32(std::process::id() as i32))
+}
+
+/// Exit the program with a status code. This unwinds the program as
+/// `BuluError::ExitRequested`; the host running it ends the process.
 pub fn exit(code: i32) -> Result<RuntimeValue> {
-    std::process::exit(code);
+    Err(BuluError::ExitRequested(code))
 }
 
 /// Get the operating system name
@@ -88,3 +264,100 @@ pub fn get_os() -> Result<RuntimeValue> {
 pub fn get_arch() -> Result<RuntimeValue> {
     Ok(RuntimeValue::String(std::env::consts::ARCH.to_string()))
 }
+
+/// The signals programs can subscribe to, with their numbers
+pub const SIGNALS: &[(&str, libc::c_int)] = &[("SIGINT", libc::SIGINT), ("SIGTERM", libc::SIGTERM)];
+
+/// How often the signal watcher forwards deliveries to channels
+const SIGNAL_POLL: Duration = Duration::from_millis(10);
+
+/// Deliveries the watcher has not forwarded yet, by index in `SIGNALS`
+static PENDING: [AtomicUsize; 2] = [AtomicUsize::new(0), AtomicUsize::new(0)];
+
+/// A channel subscribed to the signal at an index in `SIGNALS`
+type Subscriber = (usize, Arc<Channel>);
+
+/// The subscribed channels
+fn subscribers() -> &'static Mutex<Vec<Subscriber>> {
+    static SUBSCRIBERS: OnceLock<Mutex<Vec<Subscriber>>> = OnceLock::new();
+    SUBSCRIBERS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+extern "C" fn on_signal(signal: libc::c_int) {
+    // Only async-signal-safe work here: the watcher thread does the rest
+    if let Some(index) = SIGNALS.iter().position(|&(_, number)| number == signal) {
+        PENDING[index].fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Send the name of `signal` to `channel` each time the process receives
+/// it. Closing the channel unsubscribes it.
+pub fn notify(signal: &str, channel: Arc<Channel>) -> std::result::Result<(), String> {
+    let index = SIGNALS.iter().position(|&(name, _)| name == signal).ok_or_else(|| {
+        let names: Vec<_> = SIGNALS.iter().map(|&(name, _)| name).collect();
+        format!("Unknown signal '{}', expected one of: {}", signal, names.join(", "))
+    })?;
+
+    let mut subscribers = subscribers().lock().unwrap();
+    if !subscribers.iter().any(|(subscribed, _)| *subscribed == index) {
+        let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        if unsafe { libc::signal(SIGNALS[index].1, handler) } == libc::SIG_ERR {
+            return Err(format!("Failed to handle {}: {}", signal, std::io::Error::last_os_error()));
+        }
+    }
+    subscribers.push((index, channel));
+    drop(subscribers);
+
+    static WATCHER: OnceLock<()> = OnceLock::new();
+    WATCHER.get_or_init(|| {
+        std::thread::spawn(watch_signals);
+    });
+    Ok(())
+}
+
+/// Forward pending deliveries to the subscribed channels
+fn watch_signals() {
+    loop {
+        std::thread::sleep(SIGNAL_POLL);
+        for (index, pending) in PENDING.iter().enumerate() {
+            let count = pending.swap(0, Ordering::SeqCst);
+            if count == 0 {
+                continue;
+            }
+            let mut subscribers = subscribers().lock().unwrap();
+            subscribers.retain(|(_, channel)| !channel.is_closed());
+            for (_, channel) in subscribers.iter().filter(|(subscribed, _)| *subscribed == index) {
+                for _ in 0..count {
+                    let _ = channel.try_send(RuntimeValue::String(SIGNALS[index].0.to_string()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signatures_are_unique_and_consistent() {
+        for (index, function) in FUNCTIONS.iter().enumerate() {
+            assert!(function.required <= function.params.len(), "{}", function.name);
+            assert!(
+                FUNCTIONS[..index].iter().all(|other| other.name != function.name),
+                "{} is declared twice",
+                function.name
+            );
+        }
+        assert_eq!(lookup("exit").map(|exit| exit.required), Some(0));
+        assert!(lookup("sleep").is_none());
+    }
+
+    #[test]
+    fn test_invalid_environment_names() {
+        assert!(set_env("", "value").is_err());
+        assert!(set_env("A=B", "value").is_err());
+        assert!(unset_env("NUL\0").is_err());
+        assert!(notify("SIGKILL", Arc::new(Channel::new_buffered(crate::types::primitive::TypeId::String, 1))).is_err());
+    }
+}
@@ -0,0 +1,371 @@
+// std.process module - Running external commands
+//
+//   import { exec, command } from "std/process"
+//
+//   let out = exec("git", ["status", "--short"]).unwrap()
+//   println(out.code, out.stdout)
+//
+//   let cmd = command("sort", [])
+//   cmd.env("LC_ALL", "C")
+//   cmd.timeout(5000)
+//   let p = cmd.spawn().unwrap()
+//   p.write("b\na\n")
+//   p.closeStdin()
+//   let line = p.readLine()
+//   let code = p.wait().unwrap()
+//
+// Spawned processes have their stdin, stdout and stderr piped. Waiting and
+// reading happen in short slices without holding any interpreter lock, so
+// other goroutines keep running, and they give up once the current context
+// is done or the command's timeout passes; the process is killed then.
+
+use crate::runtime::context;
+use crate::types::primitive::RuntimeValue;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Functions the `std/process` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &["exec", "command"];
+
+/// Name of the command builder handle type
+pub const COMMAND: &str = "Command";
+
+/// Name of the running process handle type
+pub const PROCESS: &str = "Process";
+
+/// Name of the type `exec` returns
+pub const OUTPUT: &str = "Output";
+
+/// How long blocking operations wait before checking for cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A command to run: program, arguments and environment
+#[derive(Debug, Clone, Default)]
+pub struct CommandSpec {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Variables set for the command, on top of the inherited ones unless cleared
+    pub env: Vec<(String, String)>,
+    pub clear_env: bool,
+    pub dir: Option<String>,
+    pub timeout: Option<Duration>,
+}
+
+impl CommandSpec {
+    pub fn new(program: &str, args: Vec<String>) -> Self {
+        Self {
+            program: program.to_string(),
+            args,
+            ..Self::default()
+        }
+    }
+
+    /// Start the command with piped standard streams
+    pub fn spawn(&self) -> Result<RunningProcess, String> {
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if self.clear_env {
+            command.env_clear();
+        }
+        command.envs(self.env.iter().map(|(key, value)| (key, value)));
+        if let Some(dir) = &self.dir {
+            command.current_dir(dir);
+        }
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("failed to run '{}': {}", self.program, e))?;
+
+        // Output is drained by threads so a process filling a pipe never stalls
+        let (lines, stdout) = mpsc::channel();
+        if let Some(out) = child.stdout.take() {
+            thread::spawn(move || {
+                let mut reader = BufReader::new(out);
+                loop {
+                    let mut line = String::new();
+                    match reader.read_line(&mut line) {
+                        Ok(0) | Err(_) => break,
+                        Ok(_) => {
+                            if lines.send(line).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            });
+        }
+        let stderr = Arc::new(Mutex::new(String::new()));
+        let stderr_reader = child.stderr.take().map(|mut err| {
+            let stderr = stderr.clone();
+            thread::spawn(move || {
+                let mut buffer = [0u8; 4096];
+                while let Ok(count) = err.read(&mut buffer) {
+                    if count == 0 {
+                        break;
+                    }
+                    stderr.lock().unwrap().push_str(&String::from_utf8_lossy(&buffer[..count]));
+                }
+            })
+        });
+
+        Ok(RunningProcess {
+            stdin: Mutex::new(child.stdin.take()),
+            child: Mutex::new(child),
+            stdout: Mutex::new(stdout),
+            stderr,
+            stderr_reader: Mutex::new(stderr_reader),
+            deadline: self.timeout.map(|timeout| Instant::now() + timeout),
+            timeout: self.timeout,
+        })
+    }
+
+    /// Run the command to completion with an empty stdin
+    pub fn run(&self) -> Result<Output, String> {
+        let process = self.spawn()?;
+        process.close_stdin();
+        let stdout = process.read_all()?;
+        let code = process.wait()?;
+        Ok(Output {
+            code,
+            stdout,
+            stderr: process.stderr(),
+        })
+    }
+}
+
+/// A started process. Each stream has its own lock, so one goroutine can
+/// kill or wait for a process while another reads from it.
+#[derive(Debug)]
+pub struct RunningProcess {
+    child: Mutex<Child>,
+    stdin: Mutex<Option<ChildStdin>>,
+    /// Lines of stdout, sent by the thread reading it
+    stdout: Mutex<Receiver<String>>,
+    stderr: Arc<Mutex<String>>,
+    stderr_reader: Mutex<Option<thread::JoinHandle<()>>>,
+    deadline: Option<Instant>,
+    timeout: Option<Duration>,
+}
+
+impl RunningProcess {
+    pub fn id(&self) -> u32 {
+        self.child.lock().unwrap().id()
+    }
+
+    /// Write to the process's stdin
+    pub fn write(&self, data: &[u8]) -> Result<(), String> {
+        match self.stdin.lock().unwrap().as_mut() {
+            Some(stdin) => stdin.write_all(data).map_err(|e| format!("failed to write to stdin: {}", e)),
+            None => Err("stdin is closed".to_string()),
+        }
+    }
+
+    /// Close stdin so the process sees the end of its input
+    pub fn close_stdin(&self) {
+        self.stdin.lock().unwrap().take();
+    }
+
+    /// The next line of stdout with its line break, or `None` at the end
+    pub fn read_line(&self) -> Result<Option<String>, String> {
+        let stdout = self.stdout.lock().unwrap();
+        loop {
+            match stdout.recv_timeout(POLL_INTERVAL) {
+                Ok(line) => return Ok(Some(line)),
+                Err(RecvTimeoutError::Disconnected) => return Ok(None),
+                Err(RecvTimeoutError::Timeout) => self.check_interrupted()?,
+            }
+        }
+    }
+
+    /// The rest of stdout, once the process closes it
+    pub fn read_all(&self) -> Result<String, String> {
+        let mut text = String::new();
+        while let Some(line) = self.read_line()? {
+            text.push_str(&line);
+        }
+        Ok(text)
+    }
+
+    /// Stderr written so far; all of it once `wait` returns
+    pub fn stderr(&self) -> String {
+        self.stderr.lock().unwrap().clone()
+    }
+
+    /// Wait for the process to exit and return its exit code, -1 if a
+    /// signal ended it
+    pub fn wait(&self) -> Result<i32, String> {
+        loop {
+            let status = self
+                .child
+                .lock()
+                .unwrap()
+                .try_wait()
+                .map_err(|e| format!("failed to wait for process: {}", e))?;
+            if let Some(status) = status {
+                if let Some(reader) = self.stderr_reader.lock().unwrap().take() {
+                    let _ = reader.join();
+                }
+                return Ok(status.code().unwrap_or(-1));
+            }
+            self.check_interrupted()?;
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    pub fn kill(&self) -> Result<(), String> {
+        let mut child = self.child.lock().unwrap();
+        match child.try_wait() {
+            // Killing a process that already exited is not an error
+            Ok(Some(_)) => Ok(()),
+            _ => child.kill().map_err(|e| format!("failed to kill process: {}", e)),
+        }
+    }
+
+    /// Kill the process if its timeout passed or the current context is done
+    fn check_interrupted(&self) -> Result<(), String> {
+        let error = if let Some(error) = context::current().and_then(|context| context.err()) {
+            error.message().to_string()
+        } else if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            format!("process timed out after {} ms", self.timeout.unwrap_or_default().as_millis())
+        } else {
+            return Ok(());
+        };
+        let _ = self.kill();
+        Err(error)
+    }
+}
+
+/// What `run` returns
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+    pub code: i32,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Output {
+    /// The `Output` struct Bulu programs see
+    pub fn to_value(&self) -> RuntimeValue {
+        let mut fields = HashMap::new();
+        fields.insert("code".to_string(), RuntimeValue::Int32(self.code));
+        fields.insert("success".to_string(), RuntimeValue::Bool(self.code == 0));
+        fields.insert("stdout".to_string(), RuntimeValue::String(self.stdout.clone()));
+        fields.insert("stderr".to_string(), RuntimeValue::String(self.stderr.clone()));
+        RuntimeValue::Struct {
+            name: OUTPUT.to_string(),
+            fields,
+        }
+    }
+}
+
+/// Commands and processes created through std/process, keyed by handle ID
+#[derive(Debug, Default)]
+pub struct ProcessRegistry {
+    commands: HashMap<u64, CommandSpec>,
+    processes: HashMap<u64, Arc<RunningProcess>>,
+    next_id: u64,
+}
+
+impl ProcessRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a command and return its handle
+    pub fn create_command(&mut self, command: CommandSpec) -> RuntimeValue {
+        let id = self.next_handle();
+        self.commands.insert(id, command);
+        handle(COMMAND, id)
+    }
+
+    /// Add a started process and return its handle
+    pub fn create_process(&mut self, process: RunningProcess) -> RuntimeValue {
+        let id = self.next_handle();
+        self.processes.insert(id, Arc::new(process));
+        handle(PROCESS, id)
+    }
+
+    pub fn command_mut(&mut self, fields: &HashMap<String, RuntimeValue>) -> Option<&mut CommandSpec> {
+        self.commands.get_mut(&handle_id(fields)?)
+    }
+
+    /// The process behind a handle; callers release the registry before
+    /// blocking on it
+    pub fn process(&self, fields: &HashMap<String, RuntimeValue>) -> Option<Arc<RunningProcess>> {
+        self.processes.get(&handle_id(fields)?).cloned()
+    }
+
+    fn next_handle(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+fn handle(name: &str, id: u64) -> RuntimeValue {
+    let mut fields = HashMap::new();
+    fields.insert("id".to_string(), RuntimeValue::UInt64(id));
+    RuntimeValue::Struct {
+        name: name.to_string(),
+        fields,
+    }
+}
+
+fn handle_id(fields: &HashMap<String, RuntimeValue>) -> Option<u64> {
+    match fields.get("id") {
+        Some(RuntimeValue::UInt64(id)) => Some(*id),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn shell(script: &str) -> CommandSpec {
+        CommandSpec::new("sh", vec!["-c".to_string(), script.to_string()])
+    }
+
+    #[test]
+    fn test_run_collects_output() {
+        let output = shell("echo out; echo err >&2; exit 3").run().unwrap();
+        assert_eq!(output, Output { code: 3, stdout: "out\n".to_string(), stderr: "err\n".to_string() });
+
+        let error = CommandSpec::new("no-such-program-here", vec![]).run().unwrap_err();
+        assert!(error.contains("failed to run 'no-such-program-here'"), "{}", error);
+    }
+
+    #[test]
+    fn test_environment_and_directory() {
+        let mut command = shell("echo \"$GREETING-$HOME\"; pwd");
+        command.env.push(("GREETING".to_string(), "hi".to_string()));
+        command.clear_env = true;
+        command.dir = Some("/".to_string());
+        assert_eq!(command.run().unwrap().stdout, "hi-\n/\n");
+    }
+
+    #[test]
+    fn test_pipes_and_timeout() {
+        let process = shell("while read line; do echo \"got $line\"; done").spawn().unwrap();
+        process.write(b"a\nb\n").unwrap();
+        assert_eq!(process.read_line().unwrap().as_deref(), Some("got a\n"));
+        process.close_stdin();
+        assert_eq!(process.read_all().unwrap(), "got b\n");
+        assert_eq!(process.wait().unwrap(), 0);
+        assert!(process.write(b"late").is_err());
+
+        let mut slow = shell("sleep 5");
+        slow.timeout = Some(Duration::from_millis(50));
+        let started = Instant::now();
+        let error = slow.run().unwrap_err();
+        assert!(error.contains("timed out after 50 ms"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+}
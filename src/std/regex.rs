@@ -0,0 +1,197 @@
+// std.regex module - Regular expressions compiled into Pattern objects
+//
+//   import { compile } from "std/regex"
+//
+//   let date = compile("(?P<year>\\d{4})-(\\d{2})-(\\d{2})").unwrap()
+//   date.match("due 2024-05-01")                   // true
+//   date.find("due 2024-05-01")                    // Some("2024-05-01")
+//   date.findAll("2024-05-01 to 2024-06-01")       // ["2024-05-01", "2024-06-01"]
+//   date.replace("2024-05-01", "$3.$2.${year}")    // "01.05.2024"
+//   date.captures("2024-05-01").unwrap()[2]        // "05"
+//   date.namedCaptures("2024-05-01").unwrap()["year"]
+//
+// The syntax is that of the Rust regex crate: there are no backreferences or
+// lookaround, and matching takes time linear in the text. A pattern that does
+// not compile is an error naming the column in the pattern where it goes
+// wrong; literal patterns are compiled by the type checker as well. Compiled
+// patterns are cached, so compiling the same pattern again, e.g. in a loop,
+// reuses the first compilation and its Pattern handle.
+
+use crate::types::primitive::RuntimeValue;
+use ::regex::Regex;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Functions the `std/regex` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &["compile"];
+
+/// Name of the compiled pattern handle type
+pub const PATTERN: &str = "Pattern";
+
+/// Pattern compilation errors
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegexError {
+    /// The pattern is not valid syntax; line and column are 1-based, in characters
+    Syntax {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The pattern is valid but cannot be compiled, e.g. for being too big
+    Compile(String),
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegexError::Syntax { line: 1, column, message } => {
+                write!(f, "Regex Parse Error at column {}: {}", column, message)
+            }
+            RegexError::Syntax { line, column, message } => {
+                write!(f, "Regex Parse Error at line {}, column {}: {}", line, column, message)
+            }
+            RegexError::Compile(message) => write!(f, "Regex Compile Error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for RegexError {}
+
+/// Compile a pattern, or take it from the runtime's pattern cache
+pub fn compile(pattern: &str) -> Result<Arc<Regex>, RegexError> {
+    (*crate::runtime::pattern_cache::compile_regex(pattern)).clone()
+}
+
+/// Compile a pattern without looking in the cache
+pub fn compile_uncached(pattern: &str) -> Result<Regex, RegexError> {
+    Regex::new(pattern).map_err(|error| {
+        // The regex crate only formats its errors; parse again for the position
+        syntax_error(pattern).unwrap_or_else(|| RegexError::Compile(error.to_string()))
+    })
+}
+
+fn syntax_error(pattern: &str) -> Option<RegexError> {
+    let (span, message) = match ::regex_syntax::Parser::new().parse(pattern).err()? {
+        ::regex_syntax::Error::Parse(error) => (*error.span(), error.kind().to_string()),
+        ::regex_syntax::Error::Translate(error) => (*error.span(), error.kind().to_string()),
+        _ => return None,
+    };
+    Some(RegexError::Syntax {
+        line: span.start.line,
+        column: span.start.column,
+        message,
+    })
+}
+
+/// The text of each group of the first match, the whole match first, or None
+/// without a match. Groups that took no part in the match are empty.
+pub fn captures(regex: &Regex, text: &str) -> Option<Vec<String>> {
+    let captures = regex.captures(text)?;
+    Some(
+        captures
+            .iter()
+            .map(|group| group.map_or(String::new(), |group| group.as_str().to_string()))
+            .collect(),
+    )
+}
+
+/// The text of each named group of the first match, or None without a match
+pub fn named_captures(regex: &Regex, text: &str) -> Option<Vec<(String, String)>> {
+    let captures = regex.captures(text)?;
+    Some(
+        regex
+            .capture_names()
+            .flatten()
+            .map(|name| {
+                let group = captures.name(name).map_or("", |group| group.as_str());
+                (name.to_string(), group.to_string())
+            })
+            .collect(),
+    )
+}
+
+/// Patterns compiled through `compile`, keyed by handle ID. A pattern has one
+/// handle however often it is compiled.
+#[derive(Debug, Default)]
+pub struct PatternRegistry {
+    patterns: HashMap<u64, Arc<Regex>>,
+    ids: HashMap<String, u64>,
+    next_id: u64,
+}
+
+impl PatternRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a pattern and return its handle, the existing one if the pattern
+    /// was added before
+    pub fn create(&mut self, regex: Arc<Regex>) -> RuntimeValue {
+        let id = match self.ids.get(regex.as_str()) {
+            Some(&id) => id,
+            None => {
+                self.next_id += 1;
+                self.ids.insert(regex.as_str().to_string(), self.next_id);
+                self.patterns.insert(self.next_id, regex);
+                self.next_id
+            }
+        };
+        let mut fields = HashMap::new();
+        fields.insert("id".to_string(), RuntimeValue::UInt64(id));
+        RuntimeValue::Struct {
+            name: PATTERN.to_string(),
+            fields,
+        }
+    }
+
+    /// The pattern behind a handle
+    pub fn get(&self, fields: &HashMap<String, RuntimeValue>) -> Option<&Arc<Regex>> {
+        match fields.get("id") {
+            Some(RuntimeValue::UInt64(id)) => self.patterns.get(id),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_give_the_position() {
+        assert_eq!(
+            compile("ab(c").unwrap_err(),
+            RegexError::Syntax {
+                line: 1,
+                column: 3,
+                message: "unclosed group".to_string()
+            }
+        );
+        assert_eq!(
+            compile("a{2,1}").unwrap_err().to_string(),
+            "Regex Parse Error at column 2: invalid repetition count range, the start must be <= the end"
+        );
+        assert_eq!(
+            compile("(?x)\n  a\n  \\q").unwrap_err().to_string(),
+            "Regex Parse Error at line 3, column 3: unrecognized escape sequence"
+        );
+        assert!(matches!(compile("\\w{1000}{1000}"), Err(RegexError::Compile(_))));
+    }
+
+    #[test]
+    fn test_captures() {
+        let regex = compile("(?P<key>\\w+)=(\\d+)?").unwrap();
+        assert_eq!(captures(&regex, "a=1"), Some(vec!["a=1".to_string(), "a".to_string(), "1".to_string()]));
+        assert_eq!(captures(&regex, "b="), Some(vec!["b=".to_string(), "b".to_string(), String::new()]));
+        assert_eq!(named_captures(&regex, "k=2"), Some(vec![("key".to_string(), "k".to_string())]));
+        assert_eq!(captures(&regex, "="), None);
+
+        let mut registry = PatternRegistry::new();
+        let handle = registry.create(regex.clone());
+        let RuntimeValue::Struct { fields, .. } = &handle else { unreachable!() };
+        assert!(registry.get(fields).unwrap().is_match("x=3"));
+        // Adding the same pattern again gives back the same handle
+        assert_eq!(registry.create(compile("(?P<key>\\w+)=(\\d+)?").unwrap()), handle);
+    }
+}
@@ -0,0 +1,440 @@
+// std.schedule module - Recurring jobs on cron expressions and fixed intervals
+//
+//   import { cron, every } from "std/schedule"
+//
+//   let nightly = cron("0 3 * * *")              // 03:00 every day, local time
+//   for t in nightly.channel() { backup(t) }     // ticks are DateTimes
+//   let poll = every(5).minutes()                // every 5 minutes from now
+//   poll.run(refresh)                            // refresh(t) on a goroutine of its own
+//   poll.run(refresh, "queue")                   // overlap policy: skip, queue or concurrent
+//   poll.stop()
+//
+// Cron expressions have five fields, minute, hour, day of month, month and
+// day of week, or six with a leading seconds field. A field is `*`, a value,
+// a range `a-b`, any of those with a step (`*/15`, `9-17/2`), or a list of
+// them separated by commas. Months and days of the week can be named
+// (`jan`, `mon-fri`), and Sunday is 0 or 7. When both the day of month and
+// the day of week are restricted, a day matching either one matches, as in
+// cron. `@yearly`, `@monthly`, `@weekly`, `@daily` and `@hourly` stand for
+// the usual expressions. Times are matched in local time; a time skipped by
+// a daylight saving change does not fire.
+//
+// Schedules are armed on the runtime's timer wheel as soon as they are made.
+// Each tick is the DateTime it fired at, sent on the schedule's channel. The
+// channel holds one tick, so a receiver that falls behind misses ticks
+// instead of queueing them. `run` takes the ticks off the channel and calls a
+// handler with each one, waiting for async handlers; ticks due while the
+// handler still runs are dropped with the `skip` policy (the default), wait
+// for it with `queue` (one at most), or start another run with `concurrent`.
+// `stop` closes the channel, which disarms the timer and ends `run`.
+
+use crate::runtime::channels::Channel;
+use crate::runtime::timers::TimerWheel;
+use crate::std::time;
+use crate::types::primitive::RuntimeValue;
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeDelta, TimeZone, Timelike};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Functions the `std/schedule` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &["cron", "every"];
+
+/// Name of the type of armed schedules
+pub const SCHEDULE: &str = "Schedule";
+/// Name of the type `every(n)` returns, waiting for its unit
+pub const EVERY: &str = "Every";
+
+/// The types of `std/schedule` values, in the order of their type ids
+pub const TYPES: &[&str] = &[SCHEDULE, EVERY];
+
+/// Whether `name` is one of the types of `std/schedule` values
+pub fn is_schedule_type(name: &str) -> bool {
+    TYPES.contains(&name)
+}
+
+/// The units of `every(n)`, and their length in milliseconds
+pub const UNITS: &[(&str, u64)] = &[
+    ("milliseconds", 1),
+    ("seconds", 1_000),
+    ("minutes", 60_000),
+    ("hours", 3_600_000),
+    ("days", 86_400_000),
+];
+
+/// How far ahead a cron expression is searched for its next time
+const SEARCH_YEARS: i32 = 8;
+
+/// One field of a cron expression
+struct Field {
+    name: &'static str,
+    min: u32,
+    max: u32,
+    /// Names accepted for the values from `min` on
+    names: &'static [&'static str],
+}
+
+const SECOND: Field = Field { name: "second", min: 0, max: 59, names: &[] };
+const MINUTE: Field = Field { name: "minute", min: 0, max: 59, names: &[] };
+const HOUR: Field = Field { name: "hour", min: 0, max: 23, names: &[] };
+const DAY: Field = Field { name: "day of month", min: 1, max: 31, names: &[] };
+const MONTH: Field = Field {
+    name: "month",
+    min: 1,
+    max: 12,
+    names: &["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"],
+};
+// 7 is Sunday too
+const WEEKDAY: Field = Field {
+    name: "day of week",
+    min: 0,
+    max: 7,
+    names: &["sun", "mon", "tue", "wed", "thu", "fri", "sat"],
+};
+
+impl Field {
+    fn value(&self, text: &str) -> Result<u32, String> {
+        let value = match self.names.iter().position(|name| name.eq_ignore_ascii_case(text)) {
+            Some(index) => self.min + index as u32,
+            None => text
+                .parse()
+                .map_err(|_| format!("invalid {} '{}'", self.name, text))?,
+        };
+        if value < self.min || value > self.max {
+            return Err(format!(
+                "{} {} is out of range {}-{}",
+                self.name, value, self.min, self.max
+            ));
+        }
+        Ok(value)
+    }
+
+    /// The values `text` matches, as a bit set
+    fn parse(&self, text: &str) -> Result<u64, String> {
+        let mut bits = 0;
+        for part in text.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => match step.parse::<u32>() {
+                    Ok(step) if step > 0 => (range, Some(step)),
+                    _ => return Err(format!("invalid step '{}' in {} field", step, self.name)),
+                },
+                None => (part, None),
+            };
+            let (first, last) = match range.split_once('-') {
+                _ if range == "*" => (self.min, self.max),
+                Some((first, last)) => (self.value(first)?, self.value(last)?),
+                // `5/15` runs from 5 to the end of the range
+                None if step.is_some() => (self.value(range)?, self.max),
+                None => {
+                    let value = self.value(range)?;
+                    (value, value)
+                }
+            };
+            if first > last {
+                return Err(format!("invalid {} range '{}'", self.name, range));
+            }
+            for value in (first..=last).step_by(step.unwrap_or(1) as usize) {
+                bits |= 1 << value;
+            }
+        }
+        Ok(bits)
+    }
+}
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    seconds: u64,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// The day of month field is `*`; only the day of week restricts days
+    any_day: bool,
+    /// The day of week field is `*`
+    any_weekday: bool,
+}
+
+impl Cron {
+    pub fn parse(expression: &str) -> Result<Cron, String> {
+        let expanded = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 0 1 1 *",
+            "@monthly" => "0 0 0 1 * *",
+            "@weekly" => "0 0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 0 * * *",
+            "@hourly" => "0 0 * * * *",
+            other if other.starts_with('@') => return Err(format!("unknown cron macro '{}'", other)),
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let (seconds, rest) = match fields.len() {
+            5 => ("0", &fields[..]),
+            6 => (fields[0], &fields[1..]),
+            count => {
+                return Err(format!(
+                    "a cron expression has 5 or 6 fields, '{}' has {}",
+                    expression, count
+                ))
+            }
+        };
+        let mut weekdays = WEEKDAY.parse(rest[4])?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        let cron = Cron {
+            seconds: SECOND.parse(seconds)?,
+            minutes: MINUTE.parse(rest[0])?,
+            hours: HOUR.parse(rest[1])?,
+            days: DAY.parse(rest[2])?,
+            months: MONTH.parse(rest[3])?,
+            weekdays,
+            any_day: rest[2].starts_with('*'),
+            any_weekday: rest[4].starts_with('*'),
+        };
+
+        // `0 0 30 2 *` would be searched for in vain; with a day of week
+        // it still fires on those days
+        let longest = [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+        let possible = (1..=12).any(|month| {
+            cron.months & (1 << month) != 0 && cron.days & ((1 << (longest[month - 1] + 1)) - 2) != 0
+        });
+        if !possible && cron.any_weekday {
+            return Err(format!("'{}' never matches a date", expression));
+        }
+        Ok(cron)
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+
+    /// The first time after `after` that the expression matches, in the time
+    /// zone of `after`
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let zone = after.timezone();
+        let start = after.naive_local().with_nanosecond(0)? + TimeDelta::seconds(1);
+        let limit = start + TimeDelta::days(366 * SEARCH_YEARS as i64);
+        let mut time = start;
+        while time < limit {
+            let date = time.date();
+            if self.months & (1 << time.month()) == 0 {
+                let (year, month) = match time.month() {
+                    12 => (time.year() + 1, 1),
+                    month => (time.year(), month + 1),
+                };
+                time = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.matches_day(date) {
+                time = date.succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << time.hour()) == 0 {
+                time = date.and_hms_opt(time.hour(), 0, 0)? + TimeDelta::hours(1);
+            } else if self.minutes & (1 << time.minute()) == 0 {
+                time = date.and_hms_opt(time.hour(), time.minute(), 0)? + TimeDelta::minutes(1);
+            } else if self.seconds & (1 << time.second()) == 0 {
+                time += TimeDelta::seconds(1);
+            } else {
+                // The first of two times repeated by a daylight saving change
+                match zone.from_local_datetime(&time).earliest() {
+                    Some(matched) if matched > *after => return Some(matched),
+                    _ => time += TimeDelta::seconds(1),
+                }
+            }
+        }
+        None
+    }
+}
+
+/// When a schedule fires
+#[derive(Debug, Clone)]
+pub enum Spec {
+    Cron { expression: String, cron: Cron },
+    Every(Duration),
+}
+
+impl Spec {
+    pub fn cron(expression: &str) -> Result<Spec, String> {
+        Ok(Spec::Cron {
+            cron: Cron::parse(expression)?,
+            expression: expression.trim().to_string(),
+        })
+    }
+
+    /// How the schedule is shown by `toString`
+    pub fn describe(&self) -> String {
+        match self {
+            Spec::Cron { expression, .. } => expression.clone(),
+            Spec::Every(interval) => {
+                let nanos = i64::try_from(interval.as_nanos()).unwrap_or(i64::MAX);
+                format!("every {}", time::format_duration(nanos))
+            }
+        }
+    }
+
+    /// When the schedule fires next, if it fired or was armed at `previous`
+    fn next(&self, previous: Instant) -> Option<Instant> {
+        let now = Instant::now();
+        match self {
+            Spec::Cron { cron, .. } => {
+                let local = Local::now();
+                let wait = (cron.next_after(&local)? - local).to_std().ok()?;
+                Some(now + wait)
+            }
+            // Kept in step with the first tick; ticks missed while the
+            // process was suspended are not made up for
+            Spec::Every(interval) => {
+                let mut next = previous + *interval;
+                if next <= now {
+                    let behind = (now - next).as_nanos() / interval.as_nanos();
+                    next += Duration::from_nanos(((behind + 1) * interval.as_nanos()) as u64);
+                }
+                Some(next)
+            }
+        }
+    }
+}
+
+/// Arm `spec` on the timer wheel, sending a tick on `channel` each time it
+/// fires until the channel is closed
+pub fn start(spec: Spec, channel: Arc<Channel>) {
+    let Some(first) = spec.next(Instant::now()) else {
+        let _ = channel.close();
+        return;
+    };
+    TimerWheel::global().schedule(first, move |due| {
+        if channel.is_closed() {
+            return None;
+        }
+        let _ = channel.try_send(time::date_time_value(&time::local_now()));
+        let next = spec.next(due);
+        if next.is_none() {
+            let _ = channel.close();
+        }
+        next
+    });
+}
+
+/// What `run` does with ticks that are due while the handler still runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlap {
+    Skip,
+    Queue,
+    Concurrent,
+}
+
+impl Overlap {
+    pub fn parse(name: &str) -> Result<Overlap, String> {
+        match name {
+            "skip" => Ok(Overlap::Skip),
+            "queue" => Ok(Overlap::Queue),
+            "concurrent" => Ok(Overlap::Concurrent),
+            _ => Err(format!(
+                "unknown overlap policy \"{}\", expected skip, queue or concurrent",
+                name
+            )),
+        }
+    }
+}
+
+/// A Schedule value sending its ticks on channel `channel`
+pub fn schedule_value(spec: &Spec, channel: u32) -> RuntimeValue {
+    RuntimeValue::Struct {
+        name: SCHEDULE.to_string(),
+        fields: HashMap::from([
+            ("spec".to_string(), RuntimeValue::String(spec.describe())),
+            ("channel".to_string(), RuntimeValue::Channel(channel)),
+        ]),
+    }
+}
+
+/// The channel and description of a Schedule value's fields
+pub fn schedule_of(fields: &HashMap<String, RuntimeValue>) -> Option<(u32, &str)> {
+    match (fields.get("channel"), fields.get("spec")) {
+        (Some(RuntimeValue::Channel(channel)), Some(RuntimeValue::String(spec))) => Some((*channel, spec)),
+        _ => None,
+    }
+}
+
+/// The value `every(count)` returns
+pub fn every_value(count: u64) -> RuntimeValue {
+    RuntimeValue::Struct {
+        name: EVERY.to_string(),
+        fields: HashMap::from([("count".to_string(), RuntimeValue::Int64(count as i64))]),
+    }
+}
+
+/// The count of an Every value's fields
+pub fn every_of(fields: &HashMap<String, RuntimeValue>) -> Option<u64> {
+    match fields.get("count")? {
+        RuntimeValue::Int64(count) => u64::try_from(*count).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn next(expression: &str, after: &str) -> Option<String> {
+        let after = DateTime::parse_from_rfc3339(after).unwrap().with_timezone(&Utc);
+        let cron = Cron::parse(expression).unwrap();
+        cron.next_after(&after).map(|time| time.to_rfc3339())
+    }
+
+    #[test]
+    fn test_next_cron_times() {
+        // 2026-10-17 is a Saturday
+        let cases = [
+            ("*/15 9-17 * * mon-fri", "2026-10-17T12:00:00Z", "2026-10-19T09:00:00+00:00"),
+            ("0 3 * * *", "2026-10-17T03:00:00Z", "2026-10-18T03:00:00+00:00"),
+            ("0 3 * * *", "2026-10-17T02:59:59.5Z", "2026-10-17T03:00:00+00:00"),
+            ("0 0 29 2 *", "2026-10-17T00:00:00Z", "2028-02-29T00:00:00+00:00"),
+            // The 13th or a Friday
+            ("0 0 13 * fri", "2026-10-17T00:00:00Z", "2026-10-23T00:00:00+00:00"),
+            ("*/10 * * * * *", "2026-10-17T12:00:05Z", "2026-10-17T12:00:10+00:00"),
+            ("30 12 1 jan,jul *", "2026-10-17T00:00:00Z", "2027-01-01T12:30:00+00:00"),
+            ("0 0 * * 7", "2026-10-17T00:00:00Z", "2026-10-18T00:00:00+00:00"),
+            ("5/20 * * * *", "2026-10-17T00:46:00Z", "2026-10-17T01:05:00+00:00"),
+            ("@monthly", "2026-10-17T00:00:00Z", "2026-11-01T00:00:00+00:00"),
+            ("@hourly", "2026-10-17T23:30:00Z", "2026-10-18T00:00:00+00:00"),
+        ];
+        for (expression, after, expected) in cases {
+            assert_eq!(next(expression, after).as_deref(), Some(expected), "{} after {}", expression, after);
+        }
+    }
+
+    #[test]
+    fn test_invalid_cron_expressions() {
+        let cases = [
+            ("61 * * * *", "minute 61 is out of range 0-59"),
+            ("* * *", "a cron expression has 5 or 6 fields, '* * *' has 3"),
+            ("0 0 30 2 *", "'0 0 30 2 *' never matches a date"),
+            ("*/0 * * * *", "invalid step '0' in minute field"),
+            ("0 17-9 * * *", "invalid hour range '17-9'"),
+            ("0 0 * foo *", "invalid month 'foo'"),
+            ("@often", "unknown cron macro '@often'"),
+        ];
+        for (expression, expected) in cases {
+            assert_eq!(Cron::parse(expression).unwrap_err(), expected);
+        }
+        // Feb 30 on Mondays still fires on Mondays
+        assert!(Cron::parse("0 0 30 2 mon").is_ok());
+    }
+
+    #[test]
+    fn test_intervals_stay_in_step() {
+        let spec = Spec::Every(Duration::from_secs(60));
+        assert_eq!(spec.describe(), "every 1m0s");
+        let armed = Instant::now();
+        assert_eq!(spec.next(armed), Some(armed + Duration::from_secs(60)));
+        // Far behind: the next tick is the next one in step, not a burst
+        let stale = armed - Duration::from_secs(150);
+        let next = spec.next(stale).unwrap();
+        assert_eq!(next, stale + Duration::from_secs(180));
+    }
+}
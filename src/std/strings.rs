@@ -1,8 +1,63 @@
 // std.strings module - String manipulation functions
 // Requirements: 7.1.3
+//
+//   import { newBuilder, indexOf, fromBytes } from "std/strings"
+//
+//   let b = newBuilder()
+//   b.write("item ")
+//   b.writeLine(42)
+//   let text = b.toString()
+//
+//   let at = indexOf(text, "42")           // 5, or -1 if absent
+//   let decoded = fromBytes(payload)       // Err for invalid UTF-8
+//
+// A builder appends in amortized constant time per character, where building
+// a string with `+` outside of `s = s + x` statements copies it every time.
+// Searching and decoding use the vectorized primitives of `runtime::bytes`;
+// positions count characters, like string indexing.
 
+use crate::types::primitive::{RuntimeValue, StringBuilder};
 use std::collections::HashMap;
 
+/// Functions the `std/strings` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &["newBuilder", "contains", "indexOf", "lastIndexOf", "count", "fromBytes"];
+
+/// Name of the string builder handle type
+pub const BUILDER: &str = "Builder";
+
+/// Builders created through `newBuilder`, keyed by handle ID
+#[derive(Debug, Default)]
+pub struct BuilderRegistry {
+    builders: HashMap<u64, StringBuilder>,
+    next_id: u64,
+}
+
+impl BuilderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a builder and return its handle
+    pub fn create(&mut self, builder: StringBuilder) -> RuntimeValue {
+        self.next_id += 1;
+        self.builders.insert(self.next_id, builder);
+        let mut fields = HashMap::new();
+        fields.insert("id".to_string(), RuntimeValue::UInt64(self.next_id));
+        RuntimeValue::Struct {
+            name: BUILDER.to_string(),
+            fields,
+        }
+    }
+
+    /// The builder behind a handle
+    pub fn get_mut(&mut self, fields: &HashMap<String, RuntimeValue>) -> Option<&mut StringBuilder> {
+        match fields.get("id") {
+            Some(RuntimeValue::UInt64(id)) => self.builders.get_mut(id),
+            _ => None,
+        }
+    }
+}
+
 /// String manipulation utilities
 pub struct StringUtils;
 
@@ -155,7 +210,7 @@ impl StringUtils {
     
     /// Check if string contains substring
     pub fn contains(s: &str, substring: &str) -> bool {
-        s.contains(substring)
+        crate::runtime::bytes::contains(s, substring)
     }
     
     /// Check if string starts with prefix
@@ -210,19 +265,7 @@ impl StringUtils {
     
     /// Count occurrences of substring
     pub fn count(s: &str, substring: &str) -> usize {
-        if substring.is_empty() {
-            return 0;
-        }
-        
-        let mut count = 0;
-        let mut start = 0;
-        
-        while let Some(pos) = s[start..].find(substring) {
-            count += 1;
-            start += pos + substring.len();
-        }
-        
-        count
+        crate::runtime::bytes::count(s, substring)
     }
     
     /// Check if string is numeric
@@ -336,6 +379,25 @@ mod tests {
         assert_eq!(StringUtils::title_case("hello world"), "Hello World");
     }
     
+    #[test]
+    fn test_builder_registry() {
+        let mut registry = BuilderRegistry::new();
+        let first = registry.create(StringBuilder::from_string("hé".to_string()));
+        let second = registry.create(StringBuilder::new());
+        let fields = |handle: &RuntimeValue| match handle {
+            RuntimeValue::Struct { name, fields } if name == BUILDER => fields.clone(),
+            other => panic!("not a builder handle: {:?}", other),
+        };
+
+        let builder = registry.get_mut(&fields(&first)).unwrap();
+        builder.push_str("llo");
+        builder.push('!');
+        assert_eq!(builder.as_str(), "héllo!");
+        assert_eq!((builder.char_len(), builder.byte_len()), (6, 7));
+        assert!(registry.get_mut(&fields(&second)).unwrap().is_empty());
+        assert!(registry.get_mut(&HashMap::new()).is_none());
+    }
+
     #[test]
     fn test_trimming() {
         assert_eq!(StringUtils::trim("  hello  "), "hello");
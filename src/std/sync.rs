@@ -0,0 +1,67 @@
+// std.sync module - Go-style synchronization primitives
+//
+//   import { newWaitGroup, newMutex } from "std/sync"
+//
+//   let wg = newWaitGroup()
+//   let mu = newMutex()
+//   wg.add(1)
+//   run func() {
+//       mu.withLock(func() { counter = counter + 1 })
+//       wg.done()
+//   }()
+//   wg.wait()
+//
+// The primitives live in the runtime's LockRegistry; Bulu values are small
+// handles that carry the registry ID.
+
+use crate::runtime::sync::LockId;
+use crate::types::primitive::RuntimeValue;
+use std::collections::HashMap;
+
+/// Functions the `std/sync` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &["newWaitGroup", "newMutex", "newRwLock", "newOnce"];
+
+pub const WAIT_GROUP: &str = "WaitGroup";
+pub const MUTEX: &str = "Mutex";
+pub const RW_LOCK: &str = "RwLock";
+pub const ONCE: &str = "Once";
+/// Returned by `acquire()`/`acquireRead()`; `release()` on the guard releases what it locked
+pub const LOCK_GUARD: &str = "LockGuard";
+
+/// Names of the handle types, in the order of their type checker IDs
+pub const HANDLE_TYPES: &[&str] = &[WAIT_GROUP, MUTEX, RW_LOCK, ONCE, LOCK_GUARD];
+
+/// Check whether a struct name is one of the std/sync handle types
+pub fn is_handle_type(name: &str) -> bool {
+    HANDLE_TYPES.contains(&name)
+}
+
+/// Handle for the primitive with the given registry ID
+pub fn handle(type_name: &str, id: LockId) -> RuntimeValue {
+    let mut fields = HashMap::new();
+    fields.insert("id".to_string(), RuntimeValue::UInt64(id as u64));
+    RuntimeValue::Struct {
+        name: type_name.to_string(),
+        fields,
+    }
+}
+
+/// Guard for a lock taken on a Mutex or RwLock; `read` marks a read lock
+pub fn guard(lock_type: &str, id: LockId, read: bool) -> RuntimeValue {
+    let mut fields = HashMap::new();
+    fields.insert("id".to_string(), RuntimeValue::UInt64(id as u64));
+    fields.insert("lock".to_string(), RuntimeValue::String(lock_type.to_string()));
+    fields.insert("read".to_string(), RuntimeValue::Bool(read));
+    RuntimeValue::Struct {
+        name: LOCK_GUARD.to_string(),
+        fields,
+    }
+}
+
+/// Registry ID carried by a handle or guard
+pub fn handle_id(fields: &HashMap<String, RuntimeValue>) -> Option<LockId> {
+    match fields.get("id") {
+        Some(RuntimeValue::UInt64(id)) => Some(*id as LockId),
+        _ => None,
+    }
+}
@@ -0,0 +1,753 @@
+// std.template module - Text templates with interpolation, blocks and filters
+//
+// Syntax:
+//   {{ user.name }}                    interpolation of a dotted path
+//   {{ title | upper | truncate(20) }} filters, applied left to right
+//   {{#if cond}} ... {{else}} ... {{/if}}, {{#unless cond}} ... {{/unless}}
+//   {{#each items as item}} ... {{else}} ... {{/each}}
+//   {{! comment }}
+//   {{- x -}}                          trims the whitespace before/after the tag
+//
+// Inside `each`, `this` is the current item and `@index`, `@first`, `@last`
+// (and `@key` for maps) describe the iteration.
+
+use crate::types::primitive::{sorted_map_entries, RuntimeValue};
+use std::collections::HashMap;
+
+/// Functions the `std/template` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &["render", "renderHtml", "renderFile"];
+
+/// Filters every template can use without registering them
+pub const BUILTIN_FILTERS: &[&str] = &[
+    "upper", "lower", "trim", "capitalize", "length", "escape", "raw", "default", "truncate", "join",
+];
+
+/// The value a template expression starts from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    /// A dotted lookup path; empty for `this`
+    Path(Vec<String>),
+    Literal(RuntimeValue),
+}
+
+/// A filter applied to a value, with its literal arguments
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub name: String,
+    pub args: Vec<RuntimeValue>,
+}
+
+/// An operand followed by its filters
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pipeline {
+    pub operand: Operand,
+    pub filters: Vec<Filter>,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Text(String),
+    Output(Pipeline),
+    If {
+        condition: Pipeline,
+        negate: bool,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    Each {
+        items: Pipeline,
+        binding: Option<String>,
+        body: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+/// A parsed template, ready to be rendered any number of times
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    pub nodes: Vec<Node>,
+}
+
+/// Custom filter hook: receives the filter name, the value and the filter's arguments,
+/// and returns `None` when no filter of that name is registered
+pub type CustomFilters<'a> = dyn FnMut(&str, RuntimeValue, Vec<RuntimeValue>) -> Option<Result<RuntimeValue, String>> + 'a;
+
+impl Template {
+    /// Parse template source. Errors are prefixed with the line they occur on.
+    pub fn parse(source: &str) -> Result<Template, String> {
+        let mut parser = TemplateParser {
+            pieces: split_tags(source)?.into_iter(),
+        };
+        let (nodes, stop) = parser.parse_nodes()?;
+        match stop {
+            Stop::End => Ok(Template { nodes }),
+            Stop::Else(line) => Err(format!("line {}: {{{{else}}}} outside of a block", line)),
+            Stop::Close(name, line) => Err(format!("line {}: unexpected {{{{/{}}}}}", line, name)),
+        }
+    }
+
+    /// Names of the filters that are not built in, in order of first use
+    pub fn custom_filter_names(&self) -> Vec<String> {
+        fn collect(nodes: &[Node], names: &mut Vec<String>) {
+            let add = |pipeline: &Pipeline, names: &mut Vec<String>| {
+                for filter in &pipeline.filters {
+                    if !BUILTIN_FILTERS.contains(&filter.name.as_str()) && !names.contains(&filter.name) {
+                        names.push(filter.name.clone());
+                    }
+                }
+            };
+            for node in nodes {
+                match node {
+                    Node::Text(_) => {}
+                    Node::Output(pipeline) => add(pipeline, names),
+                    Node::If { condition, then, otherwise, .. } => {
+                        add(condition, names);
+                        collect(then, names);
+                        collect(otherwise, names);
+                    }
+                    Node::Each { items, body, otherwise, .. } => {
+                        add(items, names);
+                        collect(body, names);
+                        collect(otherwise, names);
+                    }
+                }
+            }
+        }
+
+        let mut names = Vec::new();
+        collect(&self.nodes, &mut names);
+        names
+    }
+
+    /// Render the template against `data`. In HTML mode every interpolated value is
+    /// escaped unless it went through the `raw` or `escape` filter.
+    pub fn render(&self, data: &RuntimeValue, escape_html: bool, custom: &mut CustomFilters) -> Result<String, String> {
+        let mut renderer = Renderer {
+            scopes: vec![Scope {
+                this: data.clone(),
+                locals: HashMap::new(),
+            }],
+            escape_html,
+            custom,
+        };
+        let mut output = String::new();
+        renderer.render_nodes(&self.nodes, &mut output)?;
+        Ok(output)
+    }
+}
+
+/// Parse and render `source` in one step without custom filters
+pub fn render(source: &str, data: &RuntimeValue, escape_html: bool) -> Result<String, String> {
+    Template::parse(source)?.render(data, escape_html, &mut |_, _, _| None)
+}
+
+/// Whether a template file should be rendered with HTML escaping
+pub fn is_html_path(path: &str) -> bool {
+    let lower = path.to_lowercase();
+    lower.ends_with(".html") || lower.ends_with(".htm")
+}
+
+/// Escape text for use in HTML element content and quoted attributes
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Text or the inside of a `{{ }}` tag
+enum Piece {
+    Text(String),
+    Tag { content: String, line: usize },
+}
+
+/// Split source into text and tags, applying `{{- ` and ` -}}` whitespace trimming
+fn split_tags(source: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = Vec::new();
+    let mut rest = source;
+    let mut line = 1;
+    let mut trim_next = false;
+
+    while let Some(start) = rest.find("{{") {
+        let tag_line = line + rest[..start].matches('\n').count();
+        let after = &rest[start + 2..];
+        let trim_before = after.starts_with("- ") || after.starts_with("-\n");
+        let end = after
+            .find("}}")
+            .ok_or_else(|| format!("line {}: unclosed '{{{{' tag", tag_line))?;
+
+        let mut text = &rest[..start];
+        if trim_next {
+            text = text.trim_start();
+        }
+        if trim_before {
+            text = text.trim_end();
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text.to_string()));
+        }
+
+        let mut content = &after[..end];
+        if trim_before {
+            content = &content[1..];
+        }
+        trim_next = content.ends_with(" -") || content.ends_with("\n-");
+        if trim_next {
+            content = &content[..content.len() - 1];
+        }
+        pieces.push(Piece::Tag {
+            content: content.to_string(),
+            line: tag_line,
+        });
+
+        line += rest[..start + 2 + end + 2].matches('\n').count();
+        rest = &after[end + 2..];
+    }
+
+    let text = if trim_next { rest.trim_start() } else { rest };
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text.to_string()));
+    }
+    Ok(pieces)
+}
+
+/// Why `parse_nodes` stopped
+enum Stop {
+    End,
+    Else(usize),
+    Close(String, usize),
+}
+
+struct TemplateParser {
+    pieces: std::vec::IntoIter<Piece>,
+}
+
+impl TemplateParser {
+    fn parse_nodes(&mut self) -> Result<(Vec<Node>, Stop), String> {
+        let mut nodes = Vec::new();
+        while let Some(piece) = self.pieces.next() {
+            let (content, line) = match piece {
+                Piece::Text(text) => {
+                    nodes.push(Node::Text(text));
+                    continue;
+                }
+                Piece::Tag { content, line } => (content, line),
+            };
+
+            let tag = content.trim();
+            if tag.starts_with('!') {
+                continue;
+            }
+            if tag == "else" {
+                return Ok((nodes, Stop::Else(line)));
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                return Ok((nodes, Stop::Close(name.trim().to_string(), line)));
+            }
+            if let Some(block) = tag.strip_prefix('#') {
+                let (keyword, argument) = block.split_once(char::is_whitespace).unwrap_or((block, ""));
+                nodes.push(self.parse_block(keyword, argument.trim(), line)?);
+                continue;
+            }
+            nodes.push(Node::Output(parse_pipeline(tag, line)?));
+        }
+        Ok((nodes, Stop::End))
+    }
+
+    fn parse_block(&mut self, keyword: &str, argument: &str, line: usize) -> Result<Node, String> {
+        if !matches!(keyword, "if" | "unless" | "each") {
+            return Err(format!("line {}: unknown block '#{}'", line, keyword));
+        }
+        if argument.is_empty() {
+            return Err(format!("line {}: {{{{#{}}}}} needs an argument", line, keyword));
+        }
+
+        let (expression, binding) = match argument.split_once(" as ") {
+            Some((expression, binding)) if keyword == "each" => {
+                let binding = binding.trim();
+                if !is_identifier(binding) {
+                    return Err(format!("line {}: invalid loop variable '{}'", line, binding));
+                }
+                (expression, Some(binding.to_string()))
+            }
+            _ => (argument, None),
+        };
+        let pipeline = parse_pipeline(expression, line)?;
+
+        let (body, mut stop) = self.parse_nodes()?;
+        let mut otherwise = Vec::new();
+        if let Stop::Else(else_line) = stop {
+            let (nodes, next) = self.parse_nodes()?;
+            if matches!(next, Stop::Else(_)) {
+                return Err(format!("line {}: duplicate {{{{else}}}} in {{{{#{}}}}} block", else_line, keyword));
+            }
+            otherwise = nodes;
+            stop = next;
+        }
+        match stop {
+            Stop::Close(name, _) if name == keyword => {}
+            Stop::Close(name, close_line) => {
+                return Err(format!(
+                    "line {}: {{{{/{}}}}} does not close {{{{#{}}}}} from line {}",
+                    close_line, name, keyword, line
+                ))
+            }
+            _ => return Err(format!("line {}: unclosed {{{{#{}}}}} block", line, keyword)),
+        }
+
+        Ok(match keyword {
+            "each" => Node::Each {
+                items: pipeline,
+                binding,
+                body,
+                otherwise,
+            },
+            _ => Node::If {
+                condition: pipeline,
+                negate: keyword == "unless",
+                then: body,
+                otherwise,
+            },
+        })
+    }
+}
+
+fn is_identifier(word: &str) -> bool {
+    let mut chars = word.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ExprToken {
+    Word(String),
+    Literal(RuntimeValue),
+    Pipe,
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+fn tokenize_expression(source: &str, line: usize) -> Result<Vec<ExprToken>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            _ if ch.is_whitespace() => {
+                chars.next();
+            }
+            '|' | '(' | ')' | ',' => {
+                chars.next();
+                tokens.push(match ch {
+                    '|' => ExprToken::Pipe,
+                    '(' => ExprToken::LeftParen,
+                    ')' => ExprToken::RightParen,
+                    _ => ExprToken::Comma,
+                });
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => match chars.next() {
+                            Some('n') => text.push('\n'),
+                            Some('t') => text.push('\t'),
+                            Some(escaped) => text.push(escaped),
+                            None => break,
+                        },
+                        Some(c) if c == ch => {
+                            tokens.push(ExprToken::Literal(RuntimeValue::String(text)));
+                            break;
+                        }
+                        Some(c) => text.push(c),
+                        None => return Err(format!("line {}: unterminated string in template expression", line)),
+                    }
+                }
+            }
+            _ if ch.is_ascii_digit() || ch == '-' => {
+                let mut number = String::new();
+                number.push(ch);
+                chars.next();
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                    number.push(c);
+                    chars.next();
+                }
+                let value = if number.contains('.') {
+                    number.parse::<f64>().ok().map(RuntimeValue::Float64)
+                } else {
+                    number.parse::<i64>().ok().map(RuntimeValue::Integer)
+                };
+                tokens.push(ExprToken::Literal(
+                    value.ok_or_else(|| format!("line {}: invalid number '{}' in template expression", line, number))?,
+                ));
+            }
+            _ if ch.is_alphanumeric() || matches!(ch, '_' | '.' | '@') => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '@')) {
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(match word.as_str() {
+                    "true" => ExprToken::Literal(RuntimeValue::Bool(true)),
+                    "false" => ExprToken::Literal(RuntimeValue::Bool(false)),
+                    "null" => ExprToken::Literal(RuntimeValue::Null),
+                    _ => ExprToken::Word(word),
+                });
+            }
+            _ => return Err(format!("line {}: unexpected '{}' in template expression", line, ch)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parse `operand | filter | filter(arg, ...)`
+fn parse_pipeline(source: &str, line: usize) -> Result<Pipeline, String> {
+    let mut tokens = tokenize_expression(source, line)?.into_iter().peekable();
+
+    let operand = match tokens.next() {
+        Some(ExprToken::Literal(value)) => Operand::Literal(value),
+        Some(ExprToken::Word(word)) => {
+            let mut segments: Vec<String> = word.split('.').map(str::to_string).collect();
+            if word == "." {
+                segments.clear();
+            } else if segments.iter().any(|segment| segment.is_empty()) {
+                return Err(format!("line {}: invalid path '{}'", line, word));
+            } else if segments[0] == "this" {
+                segments.remove(0);
+            }
+            Operand::Path(segments)
+        }
+        _ => return Err(format!("line {}: expected a value in '{{{{{}}}}}'", line, source.trim())),
+    };
+
+    let mut filters = Vec::new();
+    while let Some(token) = tokens.next() {
+        if token != ExprToken::Pipe {
+            return Err(format!("line {}: expected '|' before more of '{}'", line, source.trim()));
+        }
+        let name = match tokens.next() {
+            Some(ExprToken::Word(name)) if is_identifier(&name) => name,
+            _ => return Err(format!("line {}: expected a filter name after '|'", line)),
+        };
+
+        let mut args = Vec::new();
+        if tokens.peek() == Some(&ExprToken::LeftParen) {
+            tokens.next();
+            loop {
+                match tokens.next() {
+                    Some(ExprToken::RightParen) if args.is_empty() => break,
+                    Some(ExprToken::Literal(value)) => args.push(value),
+                    _ => return Err(format!("line {}: filter '{}' arguments must be literals", line, name)),
+                }
+                match tokens.next() {
+                    Some(ExprToken::Comma) => continue,
+                    Some(ExprToken::RightParen) => break,
+                    _ => return Err(format!("line {}: expected ',' or ')' in arguments of filter '{}'", line, name)),
+                }
+            }
+        }
+
+        let arity = match name.as_str() {
+            "default" | "truncate" => Some(1..=1),
+            "join" => Some(0..=1),
+            _ if BUILTIN_FILTERS.contains(&name.as_str()) => Some(0..=0),
+            _ => None,
+        };
+        if let Some(arity) = arity {
+            if !arity.contains(&args.len()) {
+                return Err(format!(
+                    "line {}: filter '{}' expects {} argument{}, got {}",
+                    line,
+                    name,
+                    arity.end(),
+                    if *arity.end() == 1 { "" } else { "s" },
+                    args.len()
+                ));
+            }
+        }
+        if name == "truncate" && !matches!(args[0], RuntimeValue::Integer(n) if n >= 0) {
+            return Err(format!("line {}: filter 'truncate' expects a non-negative integer", line));
+        }
+        filters.push(Filter { name, args });
+    }
+
+    Ok(Pipeline { operand, filters, line })
+}
+
+/// Variables visible at one level of nesting
+struct Scope {
+    this: RuntimeValue,
+    locals: HashMap<String, RuntimeValue>,
+}
+
+struct Renderer<'r, 'c> {
+    scopes: Vec<Scope>,
+    escape_html: bool,
+    custom: &'r mut CustomFilters<'c>,
+}
+
+impl Renderer<'_, '_> {
+    fn render_nodes(&mut self, nodes: &[Node], output: &mut String) -> Result<(), String> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => output.push_str(text),
+                Node::Output(pipeline) => {
+                    let (value, safe) = self.evaluate(pipeline, true)?;
+                    let text = display(&value);
+                    if self.escape_html && !safe {
+                        output.push_str(&escape_html(&text));
+                    } else {
+                        output.push_str(&text);
+                    }
+                }
+                Node::If { condition, negate, then, otherwise } => {
+                    let (value, _) = self.evaluate(condition, false)?;
+                    if value.is_truthy() != *negate {
+                        self.render_nodes(then, output)?;
+                    } else {
+                        self.render_nodes(otherwise, output)?;
+                    }
+                }
+                Node::Each { items, binding, body, otherwise } => {
+                    let (value, _) = self.evaluate(items, false)?;
+                    let entries: Vec<(Option<String>, RuntimeValue)> = match value {
+                        RuntimeValue::Array(items) | RuntimeValue::Slice(items) | RuntimeValue::Tuple(items) => {
+                            items.into_iter().map(|item| (None, item)).collect()
+                        }
+                        RuntimeValue::Map(map) => sorted_map_entries(&map)
+                            .into_iter()
+                            .map(|(key, item)| (Some(key.clone()), item.clone()))
+                            .collect(),
+                        RuntimeValue::Null => Vec::new(),
+                        other => {
+                            return Err(format!(
+                                "line {}: {{{{#each}}}} expects an array or map, got {}",
+                                items.line,
+                                crate::runtime::builtins::runtime_type_name(&other)
+                            ))
+                        }
+                    };
+
+                    if entries.is_empty() {
+                        self.render_nodes(otherwise, output)?;
+                        continue;
+                    }
+                    let count = entries.len();
+                    for (index, (key, item)) in entries.into_iter().enumerate() {
+                        let mut locals = HashMap::new();
+                        locals.insert("@index".to_string(), RuntimeValue::Integer(index as i64));
+                        locals.insert("@first".to_string(), RuntimeValue::Bool(index == 0));
+                        locals.insert("@last".to_string(), RuntimeValue::Bool(index + 1 == count));
+                        if let Some(key) = key {
+                            locals.insert("@key".to_string(), RuntimeValue::String(key));
+                        }
+                        if let Some(binding) = binding {
+                            locals.insert(binding.clone(), item.clone());
+                        }
+                        self.scopes.push(Scope { this: item, locals });
+                        let result = self.render_nodes(body, output);
+                        self.scopes.pop();
+                        result?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluate a pipeline to its value and whether it is already safe HTML. A missing
+    /// variable is an error when it is printed without a `default` filter, and null otherwise.
+    fn evaluate(&mut self, pipeline: &Pipeline, printed: bool) -> Result<(RuntimeValue, bool), String> {
+        let mut value = match &pipeline.operand {
+            Operand::Literal(value) => value.clone(),
+            Operand::Path(path) => match self.lookup(path) {
+                Some(value) => value,
+                None if printed && !pipeline.filters.iter().any(|filter| filter.name == "default") => {
+                    return Err(format!("line {}: undefined variable '{}'", pipeline.line, path.join(".")))
+                }
+                None => RuntimeValue::Null,
+            },
+        };
+
+        let mut safe = false;
+        for filter in &pipeline.filters {
+            value = match filter.name.as_str() {
+                "upper" => RuntimeValue::String(display(&value).to_uppercase()),
+                "lower" => RuntimeValue::String(display(&value).to_lowercase()),
+                "trim" => RuntimeValue::String(display(&value).trim().to_string()),
+                "capitalize" => {
+                    let text = display(&value);
+                    let mut chars = text.chars();
+                    RuntimeValue::String(match chars.next() {
+                        Some(first) => first.to_uppercase().chain(chars).collect(),
+                        None => String::new(),
+                    })
+                }
+                "length" => RuntimeValue::Integer(match &value {
+                    RuntimeValue::Array(items) | RuntimeValue::Slice(items) | RuntimeValue::Tuple(items) => items.len(),
+                    RuntimeValue::Map(map) => map.len(),
+                    RuntimeValue::Null => 0,
+                    other => display(other).chars().count(),
+                } as i64),
+                "escape" => {
+                    safe = true;
+                    RuntimeValue::String(escape_html(&display(&value)))
+                }
+                "raw" => {
+                    safe = true;
+                    value
+                }
+                "default" => match &value {
+                    RuntimeValue::Null => filter.args[0].clone(),
+                    RuntimeValue::String(s) if s.is_empty() => filter.args[0].clone(),
+                    _ => value,
+                },
+                "truncate" => {
+                    let limit = match filter.args[0] {
+                        RuntimeValue::Integer(limit) => limit as usize,
+                        _ => unreachable!("checked when the template was parsed"),
+                    };
+                    let text = display(&value);
+                    if text.chars().count() > limit {
+                        RuntimeValue::String(text.chars().take(limit).collect::<String>() + "...")
+                    } else {
+                        RuntimeValue::String(text)
+                    }
+                }
+                "join" => {
+                    let separator = filter.args.first().map(display).unwrap_or_else(|| ", ".to_string());
+                    match &value {
+                        RuntimeValue::Array(items) | RuntimeValue::Slice(items) | RuntimeValue::Tuple(items) => {
+                            RuntimeValue::String(items.iter().map(display).collect::<Vec<_>>().join(&separator))
+                        }
+                        _ => RuntimeValue::String(display(&value)),
+                    }
+                }
+                name => match (self.custom)(name, value, filter.args.clone()) {
+                    Some(result) => {
+                        // A custom filter's output is escaped again, so it cannot smuggle markup
+                        safe = false;
+                        result.map_err(|message| format!("line {}: filter '{}': {}", pipeline.line, name, message))?
+                    }
+                    None => return Err(format!("line {}: unknown filter '{}'", pipeline.line, name)),
+                },
+            };
+        }
+        Ok((value, safe))
+    }
+
+    /// Resolve a path: the first segment is looked up from the innermost scope outwards,
+    /// first among the loop variables and then among the fields of the scope's `this`
+    fn lookup(&self, path: &[String]) -> Option<RuntimeValue> {
+        let Some((first, rest)) = path.split_first() else {
+            return self.scopes.last().map(|scope| scope.this.clone());
+        };
+        let mut value = self.scopes.iter().rev().find_map(|scope| {
+            scope
+                .locals
+                .get(first)
+                .cloned()
+                .or_else(|| field(&scope.this, first))
+        })?;
+        for segment in rest {
+            value = field(&value, segment)?;
+        }
+        Some(value)
+    }
+}
+
+/// A named field of a map or struct, or an element of an array by index
+fn field(value: &RuntimeValue, name: &str) -> Option<RuntimeValue> {
+    match value {
+        RuntimeValue::Map(entries) => entries.get(name).cloned(),
+        RuntimeValue::Struct { fields, .. } => fields.get(name).cloned(),
+        RuntimeValue::Array(items) | RuntimeValue::Slice(items) | RuntimeValue::Tuple(items) => {
+            name.parse::<usize>().ok().and_then(|index| items.get(index).cloned())
+        }
+        _ => None,
+    }
+}
+
+/// Text of a value as it appears in rendered output; null renders as nothing
+fn display(value: &RuntimeValue) -> String {
+    match value {
+        RuntimeValue::String(s) => s.clone(),
+        RuntimeValue::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(entries: &[(&str, RuntimeValue)]) -> RuntimeValue {
+        RuntimeValue::Map(entries.iter().map(|(k, v)| (k.to_string(), v.clone())).collect())
+    }
+
+    fn string(s: &str) -> RuntimeValue {
+        RuntimeValue::String(s.to_string())
+    }
+
+    #[test]
+    fn test_interpolation_and_filters() {
+        let user = data(&[("name", string("ada")), ("tags", RuntimeValue::Array(vec![string("a"), string("b")]))]);
+        let data = data(&[("user", user)]);
+        assert_eq!(render("Hi {{ user.name | capitalize }}!", &data, false).unwrap(), "Hi Ada!");
+        assert_eq!(render("{{user.tags | join(\"/\") | upper}}", &data, false).unwrap(), "A/B");
+        assert_eq!(render("{{ user.tags.1 }} {{ user.tags | length }}", &data, false).unwrap(), "b 2");
+        assert_eq!(render("{{ missing | default(\"-\") }}", &data, false).unwrap(), "-");
+        assert_eq!(render("{{ \"abcdef\" | truncate(3) }}", &data, false).unwrap(), "abc...");
+        assert!(render("{{ missing }}", &data, false).unwrap_err().contains("line 1: undefined variable 'missing'"));
+    }
+
+    #[test]
+    fn test_blocks_and_trimming() {
+        let data = data(&[
+            ("items", RuntimeValue::Array(vec![string("x"), string("y")])),
+            ("empty", RuntimeValue::Array(vec![])),
+        ]);
+        let source = "{{#each items as item}}{{@index}}={{item}}{{#unless @last}},{{/unless}}{{/each}}";
+        assert_eq!(render(source, &data, false).unwrap(), "0=x,1=y");
+        assert_eq!(render("{{#each empty}}{{.}}{{else}}none{{/each}}", &data, false).unwrap(), "none");
+        assert_eq!(render("{{#if flag}}yes{{else}}no{{/if}}", &data, false).unwrap(), "no");
+        assert_eq!(render("a\n  {{- \"b\" -}}  \nc", &data, false).unwrap(), "abc");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Template::parse("{{#if x}}").unwrap_err(), "line 1: unclosed {{#if}} block");
+        assert_eq!(
+            Template::parse("{{#if x}}\n{{/each}}").unwrap_err(),
+            "line 2: {{/each}} does not close {{#if}} from line 1"
+        );
+        assert_eq!(Template::parse("{{ x | truncate }}").unwrap_err(), "line 1: filter 'truncate' expects 1 argument, got 0");
+        assert_eq!(Template::parse("{{ x").unwrap_err(), "line 1: unclosed '{{' tag");
+        assert_eq!(
+            Template::parse("{{ a | shout }}{{ b | upper | wrap(1) }}").unwrap().custom_filter_names(),
+            vec!["shout", "wrap"]
+        );
+    }
+
+    #[test]
+    fn test_html_escaping() {
+        let data = data(&[("bio", string("<b>\"hi\" & 'bye'</b>"))]);
+        assert_eq!(
+            render("<p>{{ bio }}</p>", &data, true).unwrap(),
+            "<p>&lt;b&gt;&quot;hi&quot; &amp; &#39;bye&#39;&lt;/b&gt;</p>"
+        );
+        assert_eq!(render("{{ bio | raw }}", &data, true).unwrap(), "<b>\"hi\" & 'bye'</b>");
+        assert_eq!(render("{{ bio | escape }}", &data, true).unwrap(), render("{{ bio }}", &data, true).unwrap());
+        assert_eq!(render("{{ bio }}", &data, false).unwrap(), "<b>\"hi\" & 'bye'</b>");
+    }
+}
@@ -124,6 +124,8 @@ pub struct TestResults {
     pub passed: usize,
     pub failed: usize,
     pub skipped: usize,
+    /// Passed tests that were not run because their results were cached
+    pub cached: usize,
     pub duration: Duration,
     pub failed_tests: Vec<String>,
 }
@@ -135,6 +137,7 @@ impl TestResults {
             passed: 0,
             failed: 0,
             skipped: 0,
+            cached: 0,
             duration: Duration::new(0, 0),
             failed_tests: Vec::new(),
         }
@@ -368,6 +371,9 @@ pub fn print_test_summary(results: &TestResults) {
     println!("=============");
     println!("Total: {}", results.total);
     println!("Passed: {} ({:.1}%)", results.passed, results.success_rate());
+    if results.cached > 0 {
+        println!("Cached pass: {} (inputs unchanged)", results.cached);
+    }
     println!("Failed: {}", results.failed);
     println!("Skipped: {}", results.skipped);
     println!("Duration: {:.2}s", results.duration.as_secs_f64());
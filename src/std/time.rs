@@ -1,6 +1,29 @@
 // std.time module - Time and date operations
 // Requirements: 7.1.7
-
+//
+//   import { seconds, milliseconds, instant, now, parse, after, tick } from "std/time"
+//
+//   let timeout = seconds(2).add(milliseconds(500))     // 2.5s
+//   let start = instant()                                // monotonic
+//   let took = start.elapsed()                           // Duration
+//   let today = now().format("%Y-%m-%d %H:%M:%S %:z")   // strftime patterns
+//   let moment = parse("2024-03-01 12:30", "%Y-%m-%d %H:%M").unwrap()
+//   let fired = <-after(milliseconds(10))                // DateTime
+//   let ticker = tick(seconds(1))                        // ticker.channel(), ticker.stop()
+//
+// Durations are signed nanosecond counts, written and parsed like "1h2m3.5s".
+// Instants come from a monotonic clock and only measure intervals; DateTimes
+// are wall-clock times with a fixed offset from UTC in seconds. Patterns are
+// chrono's strftime patterns; a pattern without an offset parses as UTC, and
+// one without a time of day as midnight. `after` and `tick` send DateTimes on
+// channels of capacity 1, so a slow receiver skips ticks instead of queueing
+// them; stopping a ticker closes its channel.
+
+use crate::types::primitive::RuntimeValue;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use std::collections::HashMap;
+use std::sync::OnceLock;
 use std::thread;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
@@ -458,6 +481,318 @@ pub mod parse {
     }
 }
 
+/// Functions the `std/time` module exports to Bulu programs, besides `sleep`
+pub const EXPORTED_FUNCTIONS: &[&str] = &[
+    "nanoseconds",
+    "microseconds",
+    "milliseconds",
+    "seconds",
+    "minutes",
+    "hours",
+    "parseDuration",
+    "instant",
+    "measure",
+    "now",
+    "utcNow",
+    "fromUnix",
+    "fromUnixMillis",
+    "date",
+    "parse",
+    "parseRfc3339",
+    "after",
+    "tick",
+];
+
+/// Name of the type of durations
+pub const DURATION: &str = "Duration";
+/// Name of the type of monotonic instants
+pub const INSTANT: &str = "Instant";
+/// Name of the type of wall-clock times
+pub const DATE_TIME: &str = "DateTime";
+/// Name of the ticker handle type
+pub const TICKER: &str = "Ticker";
+
+/// The types of `std/time` values, in the order of their type ids
+pub const TYPES: &[&str] = &[DURATION, INSTANT, DATE_TIME, TICKER];
+
+/// Whether `name` is one of the types of `std/time` values
+pub fn is_time_type(name: &str) -> bool {
+    TYPES.contains(&name)
+}
+
+/// Nanoseconds in one of each duration unit, by the name of its constructor
+pub const UNITS: &[(&str, i64)] = &[
+    ("nanoseconds", 1),
+    ("microseconds", 1_000),
+    ("milliseconds", 1_000_000),
+    ("seconds", 1_000_000_000),
+    ("minutes", 60_000_000_000),
+    ("hours", 3_600_000_000_000),
+];
+
+fn value_struct(name: &str, fields: Vec<(&str, RuntimeValue)>) -> RuntimeValue {
+    RuntimeValue::Struct {
+        name: name.to_string(),
+        fields: fields.into_iter().map(|(field, value)| (field.to_string(), value)).collect(),
+    }
+}
+
+fn int_field(fields: &HashMap<String, RuntimeValue>, name: &str) -> Option<i64> {
+    match fields.get(name)? {
+        RuntimeValue::Int64(value) => Some(*value),
+        RuntimeValue::Int32(value) => Some(*value as i64),
+        _ => None,
+    }
+}
+
+/// A Duration of `nanos` nanoseconds
+pub fn duration_value(nanos: i64) -> RuntimeValue {
+    value_struct(DURATION, vec![("nanos", RuntimeValue::Int64(nanos))])
+}
+
+/// The nanoseconds of a Duration or Instant value's fields
+pub fn nanos_of(fields: &HashMap<String, RuntimeValue>) -> Option<i64> {
+    int_field(fields, "nanos")
+}
+
+/// The nanoseconds of a Duration value
+pub fn duration_of(value: &RuntimeValue) -> Option<i64> {
+    match value {
+        RuntimeValue::Struct { name, fields } if name == DURATION => nanos_of(fields),
+        _ => None,
+    }
+}
+
+/// An Instant `nanos` nanoseconds after the monotonic origin
+pub fn instant_value(nanos: i64) -> RuntimeValue {
+    value_struct(INSTANT, vec![("nanos", RuntimeValue::Int64(nanos))])
+}
+
+/// The nanoseconds of an Instant value since the monotonic origin
+pub fn instant_of(value: &RuntimeValue) -> Option<i64> {
+    match value {
+        RuntimeValue::Struct { name, fields } if name == INSTANT => nanos_of(fields),
+        _ => None,
+    }
+}
+
+/// Nanoseconds since the monotonic origin, the first time the program asked;
+/// shared by every goroutine
+pub fn monotonic_nanos() -> i64 {
+    static ORIGIN: OnceLock<Instant> = OnceLock::new();
+    let origin = *ORIGIN.get_or_init(Instant::now);
+    i64::try_from(origin.elapsed().as_nanos()).unwrap_or(i64::MAX)
+}
+
+/// A DateTime value: seconds and nanoseconds since the Unix epoch, and the
+/// offset from UTC in seconds
+pub fn date_time_value(time: &DateTime<FixedOffset>) -> RuntimeValue {
+    value_struct(
+        DATE_TIME,
+        vec![
+            ("unix", RuntimeValue::Int64(time.timestamp())),
+            ("nanos", RuntimeValue::Int32(time.timestamp_subsec_nanos() as i32)),
+            ("offset", RuntimeValue::Int32(time.offset().local_minus_utc())),
+        ],
+    )
+}
+
+/// The time of a DateTime value's fields
+pub fn date_time_of(fields: &HashMap<String, RuntimeValue>) -> Option<DateTime<FixedOffset>> {
+    let nanos = u32::try_from(int_field(fields, "nanos")?).ok()?;
+    let offset = fixed_offset(int_field(fields, "offset")?).ok()?;
+    Some(DateTime::from_timestamp(int_field(fields, "unix")?, nanos)?.with_timezone(&offset))
+}
+
+/// The time of a DateTime value
+pub fn date_time_arg(value: &RuntimeValue) -> Option<DateTime<FixedOffset>> {
+    match value {
+        RuntimeValue::Struct { name, fields } if name == DATE_TIME => date_time_of(fields),
+        _ => None,
+    }
+}
+
+/// The current time in UTC
+pub fn utc_now() -> DateTime<FixedOffset> {
+    Utc::now().fixed_offset()
+}
+
+/// The current time at the local offset
+pub fn local_now() -> DateTime<FixedOffset> {
+    Local::now().fixed_offset()
+}
+
+/// The offset `seconds` east of UTC
+pub fn fixed_offset(seconds: i64) -> Result<FixedOffset, String> {
+    i32::try_from(seconds)
+        .ok()
+        .and_then(FixedOffset::east_opt)
+        .ok_or_else(|| format!("offset of {} seconds is out of range, it must be within a day", seconds))
+}
+
+/// The time `seconds` and `nanos` after the Unix epoch, in UTC
+pub fn from_unix(seconds: i64, nanos: u32) -> Result<DateTime<FixedOffset>, String> {
+    DateTime::from_timestamp(seconds, nanos)
+        .map(|time| time.fixed_offset())
+        .ok_or_else(|| format!("{} seconds from the Unix epoch is out of range", seconds))
+}
+
+/// The time with the given calendar fields at `offset` seconds east of UTC
+pub fn make_date(fields: [i64; 6], offset: i64) -> Result<DateTime<FixedOffset>, String> {
+    let [year, month, day, hour, minute, second] = fields;
+    let invalid = || {
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} is not a valid date and time",
+            year, month, day, hour, minute, second
+        )
+    };
+    let year = i32::try_from(year).map_err(|_| invalid())?;
+    let [month, day, hour, minute, second] =
+        [month, day, hour, minute, second].map(|field| u32::try_from(field).unwrap_or(u32::MAX));
+    fixed_offset(offset)?
+        .with_ymd_and_hms(year, month, day, hour, minute, second)
+        .single()
+        .ok_or_else(invalid)
+}
+
+/// The strftime items of `pattern`, or where it is malformed
+fn pattern_items(pattern: &str) -> Result<Vec<Item<'_>>, String> {
+    let items: Vec<Item> = StrftimeItems::new(pattern).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(format!("invalid time pattern \"{}\"", pattern));
+    }
+    Ok(items)
+}
+
+/// Check a pattern for `format` and `parse`
+pub fn check_pattern(pattern: &str) -> Result<(), String> {
+    pattern_items(pattern).map(|_| ())
+}
+
+/// `time` written with the strftime `pattern`
+pub fn format_date_time(time: &DateTime<FixedOffset>, pattern: &str) -> Result<String, String> {
+    let items = pattern_items(pattern)?;
+    Ok(time.format_with_items(items.into_iter()).to_string())
+}
+
+/// `text` read with the strftime `pattern`; without an offset in the pattern
+/// the time is UTC, and without a time of day it is midnight
+pub fn parse_date_time(text: &str, pattern: &str) -> Result<DateTime<FixedOffset>, String> {
+    use chrono::format::ParseErrorKind::NotEnough;
+
+    check_pattern(pattern)?;
+    let error = |e: chrono::ParseError| format!("cannot parse \"{}\" as \"{}\": {}", text, pattern, e);
+    match DateTime::parse_from_str(text, pattern) {
+        Err(e) if e.kind() == NotEnough => {}
+        result => return result.map_err(error),
+    }
+    match NaiveDateTime::parse_from_str(text, pattern) {
+        Err(e) if e.kind() == NotEnough => {}
+        result => return result.map(|time| time.and_utc().fixed_offset()).map_err(error),
+    }
+    let date = NaiveDate::parse_from_str(text, pattern).map_err(error)?;
+    Ok(date.and_time(chrono::NaiveTime::MIN).and_utc().fixed_offset())
+}
+
+/// `text` read as an RFC 3339 time, e.g. "2024-03-01T12:30:00+01:00"
+pub fn parse_rfc3339(text: &str) -> Result<DateTime<FixedOffset>, String> {
+    DateTime::parse_from_rfc3339(text).map_err(|e| format!("cannot parse \"{}\" as RFC 3339: {}", text, e))
+}
+
+/// A duration written with the largest units that fit, e.g. "1h2m3.5s",
+/// "1.5ms" or "0s"
+pub fn format_duration(nanos: i64) -> String {
+    let sign = if nanos < 0 { "-" } else { "" };
+    let nanos = nanos.unsigned_abs();
+    if nanos == 0 {
+        return "0s".to_string();
+    }
+    if nanos < 1_000 {
+        return format!("{}{}ns", sign, nanos);
+    }
+    if nanos < 1_000_000 {
+        return format!("{}{}µs", sign, decimal(nanos, 1_000));
+    }
+    if nanos < 1_000_000_000 {
+        return format!("{}{}ms", sign, decimal(nanos, 1_000_000));
+    }
+
+    let hours = nanos / 3_600_000_000_000;
+    let minutes = nanos / 60_000_000_000 % 60;
+    let mut text = sign.to_string();
+    if hours > 0 {
+        text.push_str(&format!("{}h", hours));
+    }
+    if hours > 0 || minutes > 0 {
+        text.push_str(&format!("{}m", minutes));
+    }
+    text.push_str(&format!("{}s", decimal(nanos % 60_000_000_000, 1_000_000_000)));
+    text
+}
+
+/// `value / unit` with the decimals it needs; `unit` is a power of ten
+fn decimal(value: u64, unit: u64) -> String {
+    let fraction = value % unit;
+    if fraction == 0 {
+        return (value / unit).to_string();
+    }
+    let width = unit.ilog10() as usize;
+    let digits = format!("{:0width$}", fraction, width = width);
+    format!("{}.{}", value / unit, digits.trim_end_matches('0'))
+}
+
+/// Read a duration written as signed decimal numbers with units, e.g.
+/// "1h30m", "-1.5s" or "250ms"; the units are ns, us (or µs), ms, s, m and h
+pub fn parse_duration(text: &str) -> Result<i64, String> {
+    let invalid = || format!("invalid duration \"{}\"", text);
+    let (negative, mut rest) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    if rest == "0" {
+        return Ok(0);
+    }
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+
+    let is_number = |c: char| c.is_ascii_digit() || c == '.';
+    let mut total: i128 = 0;
+    while !rest.is_empty() {
+        let number_end = rest.find(|c: char| !is_number(c)).unwrap_or(rest.len());
+        let (number, after_number) = rest.split_at(number_end);
+        let unit_end = after_number.find(is_number).unwrap_or(after_number.len());
+        let (unit, after_unit) = after_number.split_at(unit_end);
+        rest = after_unit;
+
+        let scale = match unit {
+            "ns" => 1,
+            "us" | "µs" => 1_000,
+            "ms" => 1_000_000,
+            "s" => 1_000_000_000,
+            "m" => 60_000_000_000,
+            "h" => 3_600_000_000_000,
+            "" => return Err(format!("missing unit in duration \"{}\"", text)),
+            unit => return Err(format!("unknown unit \"{}\" in duration \"{}\"", unit, text)),
+        };
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if (whole.is_empty() && fraction.is_empty()) || fraction.contains('.') {
+            return Err(invalid());
+        }
+        let whole: i128 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| invalid())? };
+        let mut value = whole.saturating_mul(scale);
+        let mut place = scale;
+        for digit in fraction.bytes() {
+            place /= 10;
+            value += (digit - b'0') as i128 * place;
+        }
+        total = total.saturating_add(value);
+    }
+
+    let total = if negative { -total } else { total };
+    i64::try_from(total).map_err(|_| format!("duration \"{}\" is out of range", text))
+}
+
 // Helper functions
 fn is_leap_year(year: u64) -> bool {
     (year % 4 == 0 && year % 100 != 0) || (year % 400 == 0)
@@ -645,4 +980,52 @@ mod tests {
         assert!(!is_leap_year(1900)); // Divisible by 100, not by 400
         assert!(!is_leap_year(2001)); // Not divisible by 4
     }
-}
+
+    #[test]
+    fn test_duration_text() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(1_500), "1.5µs");
+        assert_eq!(format_duration(250_000_000), "250ms");
+        assert_eq!(format_duration(3_723_500_000_000), "1h2m3.5s");
+        assert_eq!(format_duration(-90_000_000_000), "-1m30s");
+        for nanos in [1, 999, 1_500_000, 3_723_500_000_000, -42_000_000_001, i64::MAX] {
+            assert_eq!(parse_duration(&format_duration(nanos)), Ok(nanos), "{}", nanos);
+        }
+        assert_eq!(parse_duration("1h30m"), Ok(5_400_000_000_000));
+        assert_eq!(parse_duration(".5s"), Ok(500_000_000));
+        assert_eq!(parse_duration("2us"), Ok(2_000));
+        assert_eq!(parse_duration("5").unwrap_err(), "missing unit in duration \"5\"");
+        assert_eq!(parse_duration("3d").unwrap_err(), "unknown unit \"d\" in duration \"3d\"");
+        assert_eq!(parse_duration("1..2s").unwrap_err(), "invalid duration \"1..2s\"");
+        assert_eq!(parse_duration("3000000h").unwrap_err(), "duration \"3000000h\" is out of range");
+    }
+
+    #[test]
+    fn test_date_time_patterns() {
+        let time = make_date([2024, 2, 29, 13, 5, 9], 3600).unwrap();
+        assert_eq!(format_date_time(&time, "%Y-%m-%d %H:%M:%S %:z").unwrap(), "2024-02-29 13:05:09 +01:00");
+        assert_eq!(time.timestamp(), 1_709_208_309);
+        assert_eq!(
+            make_date([2023, 2, 29, 0, 0, 0], 0).unwrap_err(),
+            "2023-02-29 00:00:00 is not a valid date and time"
+        );
+        assert!(fixed_offset(86_400).is_err());
+
+        assert_eq!(parse_date_time("2024-02-29 13:05:09 +0100", "%Y-%m-%d %H:%M:%S %z"), Ok(time));
+        let utc = parse_date_time("2024-02-29 12:05", "%Y-%m-%d %H:%M").unwrap();
+        assert_eq!((utc.timestamp(), utc.offset().local_minus_utc()), (1_709_208_300, 0));
+        let midnight = parse_date_time("29.02.2024", "%d.%m.%Y").unwrap();
+        assert_eq!(midnight.to_rfc3339(), "2024-02-29T00:00:00+00:00");
+        assert_eq!(
+            parse_date_time("2024-02-30", "%Y-%m-%d").unwrap_err(),
+            "cannot parse \"2024-02-30\" as \"%Y-%m-%d\": input is out of range"
+        );
+        assert_eq!(check_pattern("%Y-%Q").unwrap_err(), "invalid time pattern \"%Y-%Q\"");
+
+        let fields = match date_time_value(&time) {
+            RuntimeValue::Struct { fields, .. } => fields,
+            other => panic!("expected a DateTime, got {:?}", other),
+        };
+        assert_eq!(date_time_of(&fields), Some(time));
+    }
+}
\ No newline at end of file
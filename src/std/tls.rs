@@ -0,0 +1,195 @@
+// TLS for the networking modules, built on rustls
+//
+// Clients verify servers against the bundled Mozilla root certificates, or
+// against a CA file given in `TlsOptions`. Servers are configured from PEM
+// certificate and key files with `TlsAcceptor::from_pem_files`.
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{self, CryptoProvider};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore, ServerConfig, ServerConnection, SignatureScheme, StreamOwned};
+use std::fs::File;
+use std::io::{BufReader, Result as IoResult};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// How a client verifies the server it connects to
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsOptions {
+    /// Check the server's certificate chain and name. Turning this off
+    /// accepts any certificate and should only be used for testing.
+    pub verify: bool,
+    /// PEM file of the CA certificates to trust instead of the bundled roots
+    pub ca_file: Option<PathBuf>,
+    /// Name to verify the certificate against, instead of the host connected to
+    pub server_name: Option<String>,
+}
+
+impl Default for TlsOptions {
+    fn default() -> Self {
+        TlsOptions {
+            verify: true,
+            ca_file: None,
+            server_name: None,
+        }
+    }
+}
+
+impl TlsOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options that accept any server certificate
+    pub fn insecure() -> Self {
+        TlsOptions {
+            verify: false,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_ca_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_file = Some(path.into());
+        self
+    }
+
+    pub fn with_server_name(mut self, name: impl Into<String>) -> Self {
+        self.server_name = Some(name.into());
+        self
+    }
+}
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(crypto::ring::default_provider())
+}
+
+/// Build the rustls client configuration for `options`
+pub fn client_config(options: &TlsOptions) -> Result<Arc<ClientConfig>, Box<dyn std::error::Error>> {
+    let provider = provider();
+    let builder = ClientConfig::builder_with_provider(provider.clone()).with_safe_default_protocol_versions()?;
+
+    let config = if !options.verify {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerification(provider)))
+            .with_no_client_auth()
+    } else {
+        let mut roots = RootCertStore::empty();
+        match &options.ca_file {
+            Some(path) => {
+                for cert in load_certs(path)? {
+                    roots.add(cert)?;
+                }
+            }
+            None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+        }
+        builder.with_root_certificates(roots).with_no_client_auth()
+    };
+    Ok(Arc::new(config))
+}
+
+/// Open a TLS session over `stream` and complete the handshake, so that
+/// certificate errors are reported here rather than on the first read
+pub fn connect(
+    stream: TcpStream,
+    server_name: &str,
+    options: &TlsOptions,
+) -> Result<StreamOwned<ClientConnection, TcpStream>, Box<dyn std::error::Error>> {
+    let name = ServerName::try_from(server_name.to_string())
+        .map_err(|_| format!("Invalid TLS server name '{}'", server_name))?;
+    let connection = ClientConnection::new(client_config(options)?, name)?;
+    let mut tls = StreamOwned::new(connection, stream);
+    handshake(&mut tls.conn, &mut tls.sock)?;
+    Ok(tls)
+}
+
+/// Server-side TLS, configured from a certificate chain and private key
+#[derive(Clone)]
+pub struct TlsAcceptor {
+    config: Arc<ServerConfig>,
+}
+
+impl TlsAcceptor {
+    /// Load a PEM certificate chain and a PEM private key (PKCS#8, PKCS#1 or SEC1)
+    pub fn from_pem_files(cert_file: impl AsRef<Path>, key_file: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let certs = load_certs(cert_file.as_ref())?;
+        let key = load_private_key(key_file.as_ref())?;
+        let config = ServerConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)?;
+        Ok(TlsAcceptor {
+            config: Arc::new(config),
+        })
+    }
+
+    /// Complete the server side of the handshake on an accepted stream
+    pub fn accept(&self, stream: TcpStream) -> IoResult<StreamOwned<ServerConnection, TcpStream>> {
+        let connection = ServerConnection::new(self.config.clone()).map_err(std::io::Error::other)?;
+        let mut tls = StreamOwned::new(connection, stream);
+        handshake(&mut tls.conn, &mut tls.sock)?;
+        Ok(tls)
+    }
+}
+
+fn handshake<D>(connection: &mut rustls::ConnectionCommon<D>, socket: &mut TcpStream) -> IoResult<()> {
+    while connection.is_handshaking() {
+        connection.complete_io(socket)?;
+    }
+    Ok(())
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, Box<dyn std::error::Error>> {
+    let file = File::open(path).map_err(|e| format!("Failed to open certificate file {}: {}", path.display(), e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file)).collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(format!("No certificates found in {}", path.display()).into());
+    }
+    Ok(certs)
+}
+
+fn load_private_key(path: &Path) -> Result<PrivateKeyDer<'static>, Box<dyn std::error::Error>> {
+    let file = File::open(path).map_err(|e| format!("Failed to open key file {}: {}", path.display(), e))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))?
+        .ok_or_else(|| format!("No private key found in {}", path.display()).into())
+}
+
+/// Accepts any certificate, while still checking the handshake signatures
+#[derive(Debug)]
+struct NoVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
@@ -0,0 +1,196 @@
+// TOML parsing and serialization for the Bulu programming language
+//
+//   import { parse, stringify } from "std/toml"
+//
+//   let config = parse<Config>(text).unwrap()
+//   let settings = parse(text).unwrap()  // map[string]any
+//   let text = stringify(config).unwrap()
+//
+// Documents are read into JSON values and decoded into declared types by the
+// same decoder as `std/json`, so `@json("key")` attributes rename fields here
+// too. Datetimes decode as strings in RFC 3339 form, and integers become JSON
+// numbers, so those beyond 2^53 lose precision. TOML has no null: null fields
+// are left out when stringifying, and null array elements are an error.
+
+use crate::ast::nodes::Type;
+use crate::std::json::{self, JsonError, JsonValue, StructDecls};
+use crate::types::primitive::RuntimeValue;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Functions the `std/toml` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &["parse", "stringify"];
+
+/// TOML parsing and serialization errors
+#[derive(Debug, Clone, PartialEq)]
+pub enum TomlError {
+    ParseError {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    TypeError(String),
+}
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TomlError::ParseError { line, column, message } => {
+                write!(f, "TOML Parse Error at line {}, column {}: {}", line, column, message)
+            }
+            TomlError::TypeError(msg) => write!(f, "TOML Type Error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for TomlError {}
+
+/// Parse a TOML document into a JSON object
+pub fn parse(text: &str) -> Result<JsonValue, TomlError> {
+    let table = text.parse::<::toml::Table>().map_err(|error| {
+        let offset = error.span().map_or(0, |span| span.start);
+        let (line, column) = line_column(text, offset);
+        TomlError::ParseError {
+            line,
+            column,
+            message: error.message().trim_end().to_string(),
+        }
+    })?;
+    Ok(from_toml(::toml::Value::Table(table)))
+}
+
+/// Write a JSON object as a TOML document, with keys in sorted order
+pub fn stringify(document: &JsonValue) -> Result<String, TomlError> {
+    match to_toml(document)? {
+        Some(::toml::Value::Table(table)) => {
+            ::toml::to_string(&table).map_err(|error| TomlError::TypeError(error.to_string()))
+        }
+        _ => Err(TomlError::TypeError(format!(
+            "a document must be a table, got {}",
+            kind(document)
+        ))),
+    }
+}
+
+/// Decode TOML text into a value of type `target`, as `json::decode_typed` does for JSON
+pub fn decode_typed(text: &str, target: &Type, structs: &StructDecls) -> Result<RuntimeValue, String> {
+    let document = parse(text).map_err(|e| e.to_string())?;
+    json::decode_document(&document, target, structs)
+}
+
+/// Encode a value as TOML text, as `json::encode_typed` does for JSON
+pub fn encode_typed(value: &RuntimeValue, structs: &StructDecls) -> Result<String, String> {
+    let document = json::encode_document(value, structs).map_err(|error| match error {
+        JsonError::TypeError(message) => TomlError::TypeError(message).to_string(),
+        other => other.to_string(),
+    })?;
+    stringify(&document).map_err(|e| e.to_string())
+}
+
+fn from_toml(value: ::toml::Value) -> JsonValue {
+    match value {
+        ::toml::Value::String(s) => JsonValue::String(s),
+        ::toml::Value::Integer(i) => JsonValue::Number(i as f64),
+        ::toml::Value::Float(f) => JsonValue::Number(f),
+        ::toml::Value::Boolean(b) => JsonValue::Bool(b),
+        ::toml::Value::Datetime(datetime) => JsonValue::String(datetime.to_string()),
+        ::toml::Value::Array(items) => JsonValue::Array(items.into_iter().map(from_toml).collect()),
+        ::toml::Value::Table(table) => JsonValue::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, from_toml(value)))
+                .collect::<HashMap<_, _>>(),
+        ),
+    }
+}
+
+/// The TOML form of a JSON value, or None for null
+fn to_toml(value: &JsonValue) -> Result<Option<::toml::Value>, TomlError> {
+    Ok(Some(match value {
+        JsonValue::Null => return Ok(None),
+        JsonValue::Bool(b) => ::toml::Value::Boolean(*b),
+        JsonValue::Number(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => ::toml::Value::Integer(*n as i64),
+        JsonValue::Number(n) => ::toml::Value::Float(*n),
+        JsonValue::String(s) => ::toml::Value::String(s.clone()),
+        JsonValue::Array(items) => {
+            let mut array = Vec::with_capacity(items.len());
+            for item in items {
+                match to_toml(item)? {
+                    Some(item) => array.push(item),
+                    None => return Err(TomlError::TypeError("arrays cannot contain null".to_string())),
+                }
+            }
+            ::toml::Value::Array(array)
+        }
+        JsonValue::Object(object) => {
+            let mut keys: Vec<&String> = object.keys().collect();
+            keys.sort();
+            let mut table = ::toml::Table::new();
+            for key in keys {
+                if let Some(value) = to_toml(&object[key])? {
+                    table.insert(key.clone(), value);
+                }
+            }
+            ::toml::Value::Table(table)
+        }
+    }))
+}
+
+fn kind(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "bool",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "table",
+    }
+}
+
+/// The 1-based line and column of a byte offset
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |last| last.chars().count()) + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::std::json::Json;
+
+    #[test]
+    fn test_parse_documents() {
+        let document = parse("title = \"app\"\nports = [80, 443]\n\n[owner]\nname = \"Ada\"\nsince = 1979-05-27\n").unwrap();
+        assert_eq!(
+            Json::stringify(&document),
+            r#"{"owner":{"name":"Ada","since":"1979-05-27"},"ports":[80,443],"title":"app"}"#
+        );
+
+        let error = parse("a = 1\nb = \n").unwrap_err();
+        assert!(
+            matches!(&error, TomlError::ParseError { line: 2, .. }),
+            "{:?}",
+            error
+        );
+        assert!(error.to_string().starts_with("TOML Parse Error at line 2"), "{}", error);
+    }
+
+    #[test]
+    fn test_stringify_documents() {
+        let document = Json::parse(r#"{"title": "app", "skip": null, "owner": {"name": "Ada"}, "ratio": 0.5}"#).unwrap();
+        assert_eq!(
+            stringify(&document).unwrap(),
+            "ratio = 0.5\ntitle = \"app\"\n\n[owner]\nname = \"Ada\"\n"
+        );
+        assert_eq!(
+            stringify(&Json::parse("[1]").unwrap()),
+            Err(TomlError::TypeError("a document must be a table, got array".to_string()))
+        );
+        assert_eq!(
+            stringify(&Json::parse(r#"{"a": [1, null]}"#).unwrap()),
+            Err(TomlError::TypeError("arrays cannot contain null".to_string()))
+        );
+    }
+}
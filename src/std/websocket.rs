@@ -0,0 +1,781 @@
+// WebSocket connections (RFC 6455) for std/http
+//
+// A server upgrades requests on the routes added with `websocket`, and
+// clients connect with `connectWebSocket`:
+//
+//   import { newServer, connectWebSocket } from "std/http"
+//
+//   func echo(ws: WebSocket, req: Request) {
+//       for msg in ws.messages() {
+//           ws.send(msg.text)
+//       }
+//   }
+//
+//   srv.websocket("/echo", echo)
+//
+//   let ws = connectWebSocket("ws://127.0.0.1:8080/echo").unwrap()
+//   ws.send("hello")
+//   let reply: Message = <-ws.messages()
+//
+// Each connection is driven by one thread that owns the socket: it writes
+// queued frames, answers pings, pings an idle peer and hands complete
+// messages to the `messages()` channel, which is closed when the connection
+// ends. Text frames arrive as messages of kind "text", binary ones as "binary".
+
+use super::http::{HttpRequest, ServerResponse, Url};
+use super::net::TcpConnection;
+use super::tls::TlsOptions;
+use crate::runtime::channels::{Channel, ChannelResult, SendResult};
+use crate::runtime::output::{self, Stream};
+use crate::types::primitive::{RuntimeValue, TypeId};
+use base64::Engine;
+use sha1::{Digest, Sha1};
+use std::collections::{HashMap, VecDeque};
+use std::io::{ErrorKind, Read, Result as IoResult, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Name of the connection handle type
+pub const WEBSOCKET: &str = "WebSocket";
+
+/// Name of the type received messages have
+pub const MESSAGE: &str = "Message";
+
+/// Appended to the client's key to prove the server speaks WebSocket
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// How long the connection thread waits for data before checking its queue
+const PUMP_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long an idle connection waits before pinging, and then for the pong
+pub const DEFAULT_KEEPALIVE: Duration = Duration::from_secs(30);
+
+/// How long a closing connection waits for the peer's close frame
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long a client waits for the opening handshake to be answered
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Messages held for the program before reading pauses
+const MESSAGE_BUFFER: usize = 64;
+
+/// Largest message accepted, after joining fragments
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Close codes sent by the connection itself
+const CLOSE_NORMAL: u16 = 1000;
+const CLOSE_GOING_AWAY: u16 = 1001;
+const CLOSE_PROTOCOL_ERROR: u16 = 1002;
+const CLOSE_INVALID_DATA: u16 = 1007;
+const CLOSE_TOO_BIG: u16 = 1009;
+
+/// The `Sec-WebSocket-Accept` value answering a `Sec-WebSocket-Key`
+pub fn accept_key(key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(key.trim().as_bytes());
+    hasher.update(ACCEPT_GUID.as_bytes());
+    base64::engine::general_purpose::STANDARD.encode(hasher.finalize())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    Continuation,
+    Text,
+    Binary,
+    Close,
+    Ping,
+    Pong,
+}
+
+impl Opcode {
+    fn from_bits(bits: u8) -> Option<Self> {
+        match bits {
+            0x0 => Some(Opcode::Continuation),
+            0x1 => Some(Opcode::Text),
+            0x2 => Some(Opcode::Binary),
+            0x8 => Some(Opcode::Close),
+            0x9 => Some(Opcode::Ping),
+            0xA => Some(Opcode::Pong),
+            _ => None,
+        }
+    }
+
+    fn bits(self) -> u8 {
+        match self {
+            Opcode::Continuation => 0x0,
+            Opcode::Text => 0x1,
+            Opcode::Binary => 0x2,
+            Opcode::Close => 0x8,
+            Opcode::Ping => 0x9,
+            Opcode::Pong => 0xA,
+        }
+    }
+
+    fn is_control(self) -> bool {
+        matches!(self, Opcode::Close | Opcode::Ping | Opcode::Pong)
+    }
+}
+
+/// One frame as sent on the wire, with its payload unmasked
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub fin: bool,
+    pub opcode: Opcode,
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    /// A final frame
+    pub fn new(opcode: Opcode, payload: Vec<u8>) -> Self {
+        Self {
+            fin: true,
+            opcode,
+            payload,
+        }
+    }
+
+    /// A close frame with a status code and reason
+    pub fn close(code: u16, reason: &str) -> Self {
+        let mut payload = code.to_be_bytes().to_vec();
+        payload.extend_from_slice(reason.as_bytes());
+        Self::new(Opcode::Close, payload)
+    }
+
+    /// The frame as sent; clients mask what they send, servers do not
+    pub fn encode(&self, mask: bool) -> Vec<u8> {
+        let mut bytes = vec![(if self.fin { 0x80 } else { 0 }) | self.opcode.bits()];
+        let mask_bit = if mask { 0x80 } else { 0 };
+        let length = self.payload.len();
+        if length < 126 {
+            bytes.push(mask_bit | length as u8);
+        } else if length <= u16::MAX as usize {
+            bytes.push(mask_bit | 126);
+            bytes.extend_from_slice(&(length as u16).to_be_bytes());
+        } else {
+            bytes.push(mask_bit | 127);
+            bytes.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+        if mask {
+            let key = super::random::Random::new().random_bytes(4);
+            bytes.extend_from_slice(&key);
+            bytes.extend(self.payload.iter().enumerate().map(|(i, byte)| byte ^ key[i % 4]));
+        } else {
+            bytes.extend_from_slice(&self.payload);
+        }
+        bytes
+    }
+
+    /// Decode the frame at the start of `data`, returning it with the number
+    /// of bytes it took, or `None` if more bytes are needed. Frames from
+    /// clients must be masked and frames from servers must not.
+    pub fn decode(data: &[u8], masked: bool) -> Result<Option<(Frame, usize)>, String> {
+        let [first, second, ..] = *data else {
+            return Ok(None);
+        };
+        if first & 0x70 != 0 {
+            return Err("frame uses reserved bits".to_string());
+        }
+        let opcode = Opcode::from_bits(first & 0x0F).ok_or_else(|| format!("unknown opcode {}", first & 0x0F))?;
+        let fin = first & 0x80 != 0;
+        if (second & 0x80 != 0) != masked {
+            return Err(if masked {
+                "client frames must be masked".to_string()
+            } else {
+                "server frames must not be masked".to_string()
+            });
+        }
+
+        let (length, mut offset) = match second & 0x7F {
+            126 => match data.get(2..4) {
+                Some(bytes) => (u16::from_be_bytes([bytes[0], bytes[1]]) as u64, 4),
+                None => return Ok(None),
+            },
+            127 => match data.get(2..10) {
+                Some(bytes) => (u64::from_be_bytes(bytes.try_into().unwrap()), 10),
+                None => return Ok(None),
+            },
+            length => (length as u64, 2),
+        };
+        if opcode.is_control() && (length > 125 || !fin) {
+            return Err("control frames must be short and unfragmented".to_string());
+        }
+        if length > MAX_MESSAGE_SIZE as u64 {
+            return Err("frame too large".to_string());
+        }
+        let length = length as usize;
+
+        let key = if masked {
+            match data.get(offset..offset + 4) {
+                Some(key) => {
+                    offset += 4;
+                    Some([key[0], key[1], key[2], key[3]])
+                }
+                None => return Ok(None),
+            }
+        } else {
+            None
+        };
+        let Some(payload) = data.get(offset..offset + length) else {
+            return Ok(None);
+        };
+        let payload = match key {
+            Some(key) => payload.iter().enumerate().map(|(i, byte)| byte ^ key[i % 4]).collect(),
+            None => payload.to_vec(),
+        };
+        Ok(Some((Frame { fin, opcode, payload }, offset + length)))
+    }
+}
+
+/// A complete message, as sent by `send` or received from `messages()`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl Message {
+    /// The `Message` struct Bulu programs receive
+    pub fn to_value(&self) -> RuntimeValue {
+        let (kind, text, data) = match self {
+            Message::Text(text) => ("text", text.clone(), text.as_bytes().to_vec()),
+            Message::Binary(data) => ("binary", String::new(), data.clone()),
+        };
+        let mut fields = HashMap::new();
+        fields.insert("kind".to_string(), RuntimeValue::String(kind.to_string()));
+        fields.insert("text".to_string(), RuntimeValue::String(text));
+        fields.insert("data".to_string(), super::binary::byte_slice(data));
+        RuntimeValue::Struct {
+            name: MESSAGE.to_string(),
+            fields,
+        }
+    }
+
+    /// The message for a value a program sends: a string is sent as text,
+    /// bytes as binary, and a `Message` as its kind says
+    pub fn from_value(value: &RuntimeValue) -> Result<Self, String> {
+        match value {
+            RuntimeValue::String(text) => Ok(Message::Text(text.clone())),
+            RuntimeValue::Struct { name, fields } if name == MESSAGE => match fields.get("kind") {
+                Some(RuntimeValue::String(kind)) if kind == "text" => match fields.get("text") {
+                    Some(RuntimeValue::String(text)) => Ok(Message::Text(text.clone())),
+                    _ => Err("text Message has no text".to_string()),
+                },
+                _ => fields
+                    .get("data")
+                    .ok_or_else(|| "binary Message has no data".to_string())
+                    .and_then(super::binary::bytes_of)
+                    .map(Message::Binary),
+            },
+            other => super::binary::bytes_of(other).map(Message::Binary),
+        }
+    }
+
+    fn into_frame(self) -> Frame {
+        match self {
+            Message::Text(text) => Frame::new(Opcode::Text, text.into_bytes()),
+            Message::Binary(data) => Frame::new(Opcode::Binary, data),
+        }
+    }
+}
+
+/// A byte stream a WebSocket runs over
+pub trait Transport: Read + Write + Send + 'static {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> IoResult<()>;
+    fn close(&mut self);
+}
+
+impl Transport for TcpStream {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> IoResult<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+
+    fn close(&mut self) {
+        let _ = self.shutdown(Shutdown::Both);
+    }
+}
+
+impl Transport for TcpConnection {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> IoResult<()> {
+        TcpConnection::set_read_timeout(self, timeout)
+    }
+
+    fn close(&mut self) {
+        let _ = self.shutdown(Shutdown::Both);
+    }
+}
+
+/// What the program asks of the connection thread
+#[derive(Debug)]
+enum Command {
+    Send(Message),
+    Close(u16, String),
+}
+
+/// State the connection thread shares with the handles
+#[derive(Debug)]
+struct Shared {
+    messages: Arc<Channel>,
+    outgoing: Mutex<Option<Arc<Channel>>>,
+    keepalive_ms: AtomicU64,
+    open: AtomicBool,
+    /// Set once the program closed the connection, so undelivered messages
+    /// can be dropped
+    closed_locally: AtomicBool,
+}
+
+/// An open WebSocket connection
+#[derive(Debug)]
+pub struct WebSocket {
+    commands: Mutex<Sender<Command>>,
+    shared: Arc<Shared>,
+}
+
+impl WebSocket {
+    /// Start driving a connection whose handshake is done. `buffered` holds
+    /// bytes read past the handshake.
+    fn start(mut transport: impl Transport, client: bool, buffered: Vec<u8>) -> Self {
+        let shared = Arc::new(Shared {
+            messages: Arc::new(Channel::new_buffered(TypeId::Any, MESSAGE_BUFFER)),
+            outgoing: Mutex::new(None),
+            keepalive_ms: AtomicU64::new(DEFAULT_KEEPALIVE.as_millis() as u64),
+            open: AtomicBool::new(true),
+            closed_locally: AtomicBool::new(false),
+        });
+        let (sender, receiver) = mpsc::channel();
+        let _ = transport.set_read_timeout(Some(PUMP_INTERVAL));
+        let pump = Pump {
+            transport,
+            client,
+            buffer: buffered,
+            fragments: None,
+            pending: VecDeque::new(),
+            commands: receiver,
+            shared: shared.clone(),
+            last_seen: Instant::now(),
+            ping_sent: None,
+            closing_since: None,
+        };
+        thread::spawn(move || pump.run());
+        Self {
+            commands: Mutex::new(sender),
+            shared,
+        }
+    }
+
+    /// Answer an upgrade request on `stream` and start the connection, or
+    /// return the response refusing it
+    pub fn accept(mut stream: TcpStream, request: &HttpRequest) -> Result<Self, (TcpStream, ServerResponse)> {
+        let header = |name: &str| {
+            request
+                .headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        };
+        let has_token = |name: &str, token: &str| {
+            header(name).is_some_and(|value| value.split(',').any(|part| part.trim().eq_ignore_ascii_case(token)))
+        };
+        if !has_token("Upgrade", "websocket") || !has_token("Connection", "upgrade") {
+            let mut response = ServerResponse::text(426, "Upgrade Required");
+            response.headers.insert("Upgrade".to_string(), "websocket".to_string());
+            return Err((stream, response));
+        }
+        if header("Sec-WebSocket-Version").map(str::trim) != Some("13") {
+            let mut response = ServerResponse::text(426, "Unsupported WebSocket version");
+            response.headers.insert("Sec-WebSocket-Version".to_string(), "13".to_string());
+            return Err((stream, response));
+        }
+        let Some(key) = header("Sec-WebSocket-Key").filter(|key| !key.trim().is_empty()) else {
+            return Err((stream, ServerResponse::text(400, "Missing Sec-WebSocket-Key")));
+        };
+
+        let response = format!(
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+            accept_key(key)
+        );
+        if stream.write_all(response.as_bytes()).and_then(|_| stream.flush()).is_err() {
+            return Err((stream, ServerResponse::text(500, "Internal Server Error")));
+        }
+        Ok(Self::start(stream, false, Vec::new()))
+    }
+
+    /// Connect to a `ws://` or `wss://` URL
+    pub fn connect(url: &str, tls: &TlsOptions) -> Result<Self, String> {
+        let http_url = if let Some(rest) = url.strip_prefix("wss://") {
+            format!("https://{}", rest)
+        } else if let Some(rest) = url.strip_prefix("ws://") {
+            format!("http://{}", rest)
+        } else {
+            return Err(format!("Unsupported URL '{}': expected ws:// or wss://", url));
+        };
+        let target = Url::parse(&http_url).map_err(|e| e.replace(&http_url, url))?;
+        let failed = |e: &dyn std::fmt::Display| format!("WebSocket connection to {} failed: {}", url, e);
+
+        let mut connection = if target.secure {
+            TcpConnection::connect_tls(target.net_addr(), tls)
+        } else {
+            TcpConnection::connect(target.net_addr())
+        }
+        .map_err(|e| failed(&e))?;
+        connection.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).map_err(|e| failed(&e))?;
+
+        let key = base64::engine::general_purpose::STANDARD.encode(super::random::Random::new().random_bytes(16));
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            target.path,
+            target.host_header(),
+            key
+        );
+        connection.write_all(request.as_bytes()).map_err(|e| failed(&e))?;
+
+        let mut data = Vec::new();
+        let mut buffer = [0u8; 4096];
+        let head_end = loop {
+            if let Some(end) = data.windows(4).position(|window| window == b"\r\n\r\n") {
+                break end;
+            }
+            let count = connection.read(&mut buffer).map_err(|e| failed(&e))?;
+            if count == 0 {
+                return Err(failed(&"connection closed during the handshake"));
+            }
+            data.extend_from_slice(&buffer[..count]);
+        };
+        let head = String::from_utf8_lossy(&data[..head_end]).into_owned();
+        let mut lines = head.split("\r\n");
+        let status = lines.next().unwrap_or_default();
+        if status.split_whitespace().nth(1) != Some("101") {
+            return Err(failed(&format!("server answered '{}'", status)));
+        }
+        let accept = lines
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("Sec-WebSocket-Accept"))
+            .map(|(_, value)| value.trim());
+        if accept != Some(accept_key(&key).as_str()) {
+            return Err(failed(&"server sent a wrong Sec-WebSocket-Accept"));
+        }
+        Ok(Self::start(connection, true, data.split_off(head_end + 4)))
+    }
+
+    /// Queue a message to send
+    pub fn send(&self, message: Message) -> Result<(), String> {
+        if !self.is_open() {
+            return Err("WebSocket is closed".to_string());
+        }
+        self.commands
+            .lock()
+            .unwrap()
+            .send(Command::Send(message))
+            .map_err(|_| "WebSocket is closed".to_string())
+    }
+
+    /// The channel received messages arrive on; it is closed when the
+    /// connection ends
+    pub fn messages(&self) -> Arc<Channel> {
+        self.shared.messages.clone()
+    }
+
+    /// A channel whose values are sent as messages. Closing it closes the
+    /// connection.
+    pub fn outgoing(&self) -> Arc<Channel> {
+        let mut outgoing = self.shared.outgoing.lock().unwrap();
+        if let Some(channel) = outgoing.as_ref() {
+            return channel.clone();
+        }
+        let channel = Arc::new(Channel::new_buffered(TypeId::Any, MESSAGE_BUFFER));
+        if !self.is_open() {
+            let _ = channel.close();
+        }
+        *outgoing = Some(channel.clone());
+
+        let commands = self.commands.lock().unwrap().clone();
+        let shared = self.shared.clone();
+        let source = channel.clone();
+        // Values that are not messages are reported to the program that sent them
+        let sinks = output::current();
+        thread::spawn(move || {
+            let _output = output::enter(&sinks);
+            while let Ok(ChannelResult::Ok(value)) = source.receive() {
+                match Message::from_value(&value) {
+                    Ok(message) => {
+                        let _ = commands.send(Command::Send(message));
+                    }
+                    Err(message) => {
+                        let _ = output::write(Stream::Stderr, &format!("WebSocket outgoing channel: {}\n", message));
+                    }
+                }
+            }
+            if shared.open.load(Ordering::SeqCst) {
+                shared.closed_locally.store(true, Ordering::SeqCst);
+                let _ = commands.send(Command::Close(CLOSE_NORMAL, String::new()));
+            }
+        });
+        channel
+    }
+
+    /// Start the closing handshake
+    pub fn close(&self, code: u16, reason: &str) {
+        self.shared.closed_locally.store(true, Ordering::SeqCst);
+        let _ = self.commands.lock().unwrap().send(Command::Close(code, reason.to_string()));
+    }
+
+    /// Close once the values already sent on `outgoing()` have been sent
+    pub fn finish(&self) {
+        match self.shared.outgoing.lock().unwrap().as_ref() {
+            Some(channel) if self.is_open() => {
+                let _ = channel.close();
+            }
+            _ => self.close(CLOSE_NORMAL, ""),
+        }
+    }
+
+    /// Close as the server is going away
+    pub fn close_going_away(&self) {
+        self.close(CLOSE_GOING_AWAY, "server shutting down");
+    }
+
+    /// Ping after `interval` without hearing from the peer, and drop the
+    /// connection if the pong takes as long again. Zero turns pings off.
+    pub fn set_keepalive(&self, interval: Duration) {
+        self.shared.keepalive_ms.store(interval.as_millis() as u64, Ordering::SeqCst);
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.shared.open.load(Ordering::SeqCst)
+    }
+}
+
+/// The thread driving one connection
+struct Pump<T: Transport> {
+    transport: T,
+    client: bool,
+    /// Bytes read but not yet decoded
+    buffer: Vec<u8>,
+    /// The kind and data of a fragmented message being received
+    fragments: Option<(Opcode, Vec<u8>)>,
+    /// Messages waiting for room in the channel
+    pending: VecDeque<RuntimeValue>,
+    commands: Receiver<Command>,
+    shared: Arc<Shared>,
+    last_seen: Instant,
+    ping_sent: Option<Instant>,
+    closing_since: Option<Instant>,
+}
+
+impl<T: Transport> Pump<T> {
+    fn run(mut self) {
+        while self.step() {}
+        self.transport.close();
+        self.shared.open.store(false, Ordering::SeqCst);
+        if let Some(outgoing) = self.shared.outgoing.lock().unwrap().as_ref() {
+            let _ = outgoing.close();
+        }
+        // Messages received before the end are still delivered, unless the
+        // program has stopped listening
+        while !self.pending.is_empty() && !self.shared.closed_locally.load(Ordering::SeqCst) {
+            self.deliver();
+            thread::sleep(PUMP_INTERVAL);
+        }
+        let _ = self.shared.messages.close();
+    }
+
+    /// Do one round of work; false once the connection is over
+    fn step(&mut self) -> bool {
+        while let Ok(command) = self.commands.try_recv() {
+            let sent = match command {
+                _ if self.closing_since.is_some() => true,
+                Command::Send(message) => self.write(message.into_frame()),
+                Command::Close(code, reason) => self.start_closing(code, &reason),
+            };
+            if !sent {
+                return false;
+            }
+        }
+        self.deliver();
+
+        let now = Instant::now();
+        if let Some(since) = self.closing_since {
+            if now.duration_since(since) > CLOSE_TIMEOUT {
+                return false;
+            }
+        } else {
+            let keepalive = Duration::from_millis(self.shared.keepalive_ms.load(Ordering::SeqCst));
+            let waiting = self.pending.len() >= MESSAGE_BUFFER;
+            match self.ping_sent {
+                // A backed up program is not the peer's fault
+                Some(sent) if !keepalive.is_zero() && !waiting && now.duration_since(sent) > keepalive => {
+                    return false;
+                }
+                None if !keepalive.is_zero() && now.duration_since(self.last_seen) >= keepalive => {
+                    if !self.write(Frame::new(Opcode::Ping, Vec::new())) {
+                        return false;
+                    }
+                    self.ping_sent = Some(now);
+                }
+                _ => {}
+            }
+        }
+
+        if self.pending.len() >= MESSAGE_BUFFER {
+            thread::sleep(PUMP_INTERVAL);
+            return true;
+        }
+        let mut chunk = [0u8; 4096];
+        match self.transport.read(&mut chunk) {
+            Ok(0) => return false,
+            Ok(count) => self.buffer.extend_from_slice(&chunk[..count]),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted) => {
+                return true
+            }
+            Err(_) => return false,
+        }
+
+        loop {
+            match Frame::decode(&self.buffer, !self.client) {
+                Ok(Some((frame, used))) => {
+                    self.buffer.drain(..used);
+                    if !self.receive(frame) {
+                        return false;
+                    }
+                }
+                Ok(None) => return true,
+                Err(message) => {
+                    self.start_closing(CLOSE_PROTOCOL_ERROR, &message);
+                    return false;
+                }
+            }
+        }
+    }
+
+    /// Handle a received frame; false once the connection is over
+    fn receive(&mut self, frame: Frame) -> bool {
+        self.last_seen = Instant::now();
+        self.ping_sent = None;
+        let data = match (frame.opcode, self.fragments.take()) {
+            (Opcode::Ping, fragments) => {
+                self.fragments = fragments;
+                return self.closing_since.is_some() || self.write(Frame::new(Opcode::Pong, frame.payload));
+            }
+            (Opcode::Pong, fragments) => {
+                self.fragments = fragments;
+                return true;
+            }
+            (Opcode::Close, _) => {
+                if self.closing_since.is_none() {
+                    // Echo the code, as the closing handshake asks
+                    let code = frame.payload.get(..2).map_or(Vec::new(), |code| code.to_vec());
+                    self.write(Frame::new(Opcode::Close, code));
+                }
+                return false;
+            }
+            (Opcode::Text | Opcode::Binary, None) => (frame.opcode, frame.payload),
+            (Opcode::Continuation, Some((opcode, mut data))) => {
+                data.extend_from_slice(&frame.payload);
+                (opcode, data)
+            }
+            _ => {
+                self.start_closing(CLOSE_PROTOCOL_ERROR, "unexpected continuation frame");
+                return false;
+            }
+        };
+        if data.1.len() > MAX_MESSAGE_SIZE {
+            self.start_closing(CLOSE_TOO_BIG, "message too large");
+            return false;
+        }
+        if !frame.fin {
+            self.fragments = Some(data);
+            return true;
+        }
+        if self.closing_since.is_some() {
+            return true;
+        }
+        let message = match data {
+            (Opcode::Text, bytes) => match String::from_utf8(bytes) {
+                Ok(text) => Message::Text(text),
+                Err(_) => {
+                    self.start_closing(CLOSE_INVALID_DATA, "text message is not UTF-8");
+                    return false;
+                }
+            },
+            (_, bytes) => Message::Binary(bytes),
+        };
+        self.pending.push_back(message.to_value());
+        self.deliver();
+        true
+    }
+
+    /// Move waiting messages into the channel while it has room
+    fn deliver(&mut self) {
+        while let Some(value) = self.pending.front() {
+            match self.shared.messages.try_send(value.clone()) {
+                Ok(SendResult::Ok) => {
+                    self.pending.pop_front();
+                }
+                Ok(SendResult::WouldBlock) => break,
+                _ => {
+                    self.pending.clear();
+                    break;
+                }
+            }
+        }
+    }
+
+    fn start_closing(&mut self, code: u16, reason: &str) -> bool {
+        self.closing_since = Some(Instant::now());
+        self.write(Frame::close(code, reason))
+    }
+
+    fn write(&mut self, frame: Frame) -> bool {
+        let bytes = frame.encode(self.client);
+        self.transport.write_all(&bytes).and_then(|_| self.transport.flush()).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept_key() {
+        // The example from RFC 6455, section 1.3
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        for length in [0, 5, 125, 126, 65535, 65536] {
+            let frame = Frame::new(Opcode::Binary, vec![7; length]);
+            let masked = frame.encode(true);
+            assert_eq!(Frame::decode(&masked, true).unwrap(), Some((frame.clone(), masked.len())));
+            let plain = frame.encode(false);
+            assert_eq!(Frame::decode(&plain, false).unwrap(), Some((frame.clone(), plain.len())));
+            // Partial frames wait for more bytes
+            assert_eq!(Frame::decode(&plain[..plain.len() - 1], false).unwrap(), None);
+        }
+
+        // "Hello" from a client, from RFC 6455, section 5.7
+        let hello = [0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58];
+        let (frame, used) = Frame::decode(&hello, true).unwrap().unwrap();
+        assert_eq!((frame.opcode, frame.payload.as_slice(), used), (Opcode::Text, &b"Hello"[..], 11));
+
+        assert!(Frame::decode(&hello, false).is_err());
+        assert!(Frame::decode(&[0x89, 0x7e, 0, 200], false).is_err());
+        assert!(Frame::decode(&[0x83, 0], false).is_err());
+    }
+
+    #[test]
+    fn test_message_values() {
+        let text = Message::Text("hi".to_string());
+        assert_eq!(Message::from_value(&text.to_value()).unwrap(), text);
+        let binary = Message::Binary(vec![0, 255]);
+        assert_eq!(Message::from_value(&binary.to_value()).unwrap(), binary);
+        assert_eq!(
+            Message::from_value(&super::super::binary::byte_slice(vec![1, 2])).unwrap(),
+            Message::Binary(vec![1, 2])
+        );
+        assert!(Message::from_value(&RuntimeValue::Bool(true)).is_err());
+    }
+}
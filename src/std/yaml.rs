@@ -0,0 +1,874 @@
+// YAML parsing and serialization for the Bulu programming language
+//
+//   import { parse, stringify } from "std/yaml"
+//
+//   let config = parse<Config>(text).unwrap()
+//   let settings = parse(text).unwrap()  // map[string]any
+//   let text = stringify(config).unwrap()
+//
+// The parser reads the parts of YAML configuration files use: block and flow
+// mappings and sequences, plain and quoted scalars, literal (`|`) and folded
+// (`>`) block scalars and comments. Scalars follow the YAML 1.2 core schema
+// for null, booleans and numbers. Anchors, aliases, tags, directives and
+// multiple documents are reported as unsupported.
+//
+// Documents are read into JSON values and decoded into declared types by the
+// same decoder as `std/json`, so `@json("key")` attributes rename fields here
+// too.
+
+use crate::ast::nodes::Type;
+use crate::std::json::{self, JsonError, JsonValue, StructDecls};
+use crate::types::primitive::{format_float64, RuntimeValue};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Functions the `std/yaml` module exports to Bulu programs
+pub const EXPORTED_FUNCTIONS: &[&str] = &["parse", "stringify"];
+
+/// YAML parsing and serialization errors
+#[derive(Debug, Clone, PartialEq)]
+pub enum YamlError {
+    ParseError {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    TypeError(String),
+}
+
+impl fmt::Display for YamlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            YamlError::ParseError { line, column, message } => {
+                write!(f, "YAML Parse Error at line {}, column {}: {}", line, column, message)
+            }
+            YamlError::TypeError(msg) => write!(f, "YAML Type Error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for YamlError {}
+
+/// Parse a YAML document into a JSON value
+pub fn parse(text: &str) -> Result<JsonValue, YamlError> {
+    YamlParser::new(text).parse_document()
+}
+
+/// Write a JSON value as a block-style YAML document, with keys in sorted order
+pub fn stringify(document: &JsonValue) -> String {
+    let mut out = String::new();
+    match document {
+        JsonValue::Object(object) if !object.is_empty() => emit_mapping(object, 0, &mut out),
+        JsonValue::Array(items) if !items.is_empty() => emit_sequence(items, 0, &mut out),
+        scalar => {
+            out.push_str(&scalar_text(scalar));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Decode YAML text into a value of type `target`, as `json::decode_typed` does for JSON
+pub fn decode_typed(text: &str, target: &Type, structs: &StructDecls) -> Result<RuntimeValue, String> {
+    let document = parse(text).map_err(|e| e.to_string())?;
+    json::decode_document(&document, target, structs)
+}
+
+/// Encode a value as YAML text, as `json::encode_typed` does for JSON
+pub fn encode_typed(value: &RuntimeValue, structs: &StructDecls) -> Result<String, String> {
+    let document = json::encode_document(value, structs).map_err(|error| match error {
+        JsonError::TypeError(message) => YamlError::TypeError(message).to_string(),
+        other => other.to_string(),
+    })?;
+    Ok(stringify(&document))
+}
+
+/// Block-structure parser working line by line
+struct YamlParser {
+    lines: Vec<String>,
+    position: usize,
+}
+
+impl YamlParser {
+    fn new(text: &str) -> Self {
+        Self {
+            lines: text.lines().map(|line| line.trim_end_matches('\r').to_string()).collect(),
+            position: 0,
+        }
+    }
+
+    fn error(&self, column: usize, message: impl Into<String>) -> YamlError {
+        YamlError::ParseError {
+            line: self.position + 1,
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// Skip blank and comment lines, returning the indentation and content of
+    /// the next line without moving past it
+    fn peek(&mut self) -> Result<Option<(usize, String)>, YamlError> {
+        while let Some(line) = self.lines.get(self.position) {
+            let content = strip_comment(line).trim_end();
+            let text = content.trim_start_matches(' ');
+            if text.is_empty() {
+                self.position += 1;
+                continue;
+            }
+            let indent = content.len() - text.len();
+            if text.starts_with('\t') {
+                return Err(self.error(indent + 1, "tabs cannot be used for indentation"));
+            }
+            return Ok(Some((indent, text.to_string())));
+        }
+        Ok(None)
+    }
+
+    fn parse_document(&mut self) -> Result<JsonValue, YamlError> {
+        match self.peek()? {
+            Some((_, text)) if text.starts_with('%') => return Err(self.error(1, "directives are not supported")),
+            Some((0, text)) if text == "---" => self.position += 1,
+            _ => {}
+        }
+        let document = self.parse_node(None)?;
+        match self.peek()? {
+            Some((0, text)) if text == "..." => {
+                self.position += 1;
+                match self.peek()? {
+                    None => Ok(document),
+                    Some((indent, _)) => Err(self.error(indent + 1, "unexpected content after the end of the document")),
+                }
+            }
+            Some((0, text)) if text == "---" => Err(self.error(1, "multiple documents are not supported")),
+            Some((indent, _)) => Err(self.error(indent + 1, "unexpected content; check the indentation")),
+            None => Ok(document),
+        }
+    }
+
+    /// Parse the node starting at the next line, if it is indented further than `parent`
+    fn parse_node(&mut self, parent: Option<usize>) -> Result<JsonValue, YamlError> {
+        let Some((indent, text)) = self.peek()? else {
+            return Ok(JsonValue::Null);
+        };
+        if parent.is_some_and(|parent| indent <= parent) {
+            return Ok(JsonValue::Null);
+        }
+        if is_sequence_item(&text) {
+            self.parse_sequence(indent)
+        } else if find_key_colon(&text).is_some() {
+            self.parse_mapping(indent)
+        } else if text.starts_with('|') || text.starts_with('>') {
+            self.parse_block_scalar(&text, indent + 1, parent)
+        } else {
+            self.parse_inline(&text, indent + 1)
+        }
+    }
+
+    fn parse_sequence(&mut self, indent: usize) -> Result<JsonValue, YamlError> {
+        let mut items = Vec::new();
+        while let Some((line_indent, text)) = self.peek()? {
+            if line_indent != indent || !is_sequence_item(&text) {
+                if line_indent > indent {
+                    return Err(self.error(line_indent + 1, "unexpected indentation in a sequence"));
+                }
+                break;
+            }
+            let rest = &text[1..];
+            let item = rest.trim_start();
+            let item_indent = indent + 1 + (rest.len() - item.len());
+            if item.is_empty() {
+                self.position += 1;
+                items.push(self.parse_node(Some(indent))?);
+            } else if item.starts_with('|') || item.starts_with('>') {
+                items.push(self.parse_block_scalar(item, item_indent + 1, Some(indent))?);
+            } else {
+                // The item starts on this line; read it as if the line were
+                // indented to where the item starts
+                self.lines[self.position] = format!("{}{}", " ".repeat(item_indent), item);
+                items.push(self.parse_node(Some(indent))?);
+            }
+        }
+        Ok(JsonValue::Array(items))
+    }
+
+    fn parse_mapping(&mut self, indent: usize) -> Result<JsonValue, YamlError> {
+        let mut object = HashMap::new();
+        while let Some((line_indent, text)) = self.peek()? {
+            if line_indent < indent || is_document_marker(line_indent, &text) {
+                break;
+            }
+            if line_indent > indent {
+                return Err(self.error(line_indent + 1, "unexpected indentation in a mapping"));
+            }
+            if text.starts_with('?') {
+                return Err(self.error(indent + 1, "complex mapping keys are not supported"));
+            }
+            let Some(colon) = find_key_colon(&text) else {
+                return Err(self.error(indent + 1, "expected a mapping key"));
+            };
+            let key = self.parse_key(text[..colon].trim(), indent + 1)?;
+            if object.contains_key(&key) {
+                return Err(self.error(indent + 1, format!("duplicate key '{}'", key)));
+            }
+
+            let after = &text[colon + 1..];
+            let rest = after.trim();
+            let column = indent + colon + 2 + (after.len() - after.trim_start().len());
+            let value = if rest.is_empty() {
+                self.position += 1;
+                match self.peek()? {
+                    // A sequence may sit at the same indentation as its key
+                    Some((next_indent, next)) if next_indent == indent && is_sequence_item(&next) => {
+                        self.parse_sequence(indent)?
+                    }
+                    _ => self.parse_node(Some(indent))?,
+                }
+            } else if rest.starts_with('|') || rest.starts_with('>') {
+                self.parse_block_scalar(rest, column, Some(indent))?
+            } else {
+                self.parse_inline(rest, column)?
+            };
+            object.insert(key, value);
+        }
+        Ok(JsonValue::Object(object))
+    }
+
+    fn parse_key(&self, text: &str, column: usize) -> Result<String, YamlError> {
+        if text.starts_with('"') || text.starts_with('\'') {
+            let mut flow = FlowParser::new(text);
+            return match flow.parse_value(false) {
+                Ok(JsonValue::String(key)) if flow.at_end() => Ok(key),
+                Ok(_) => Err(self.error(column, "unexpected characters after a quoted key")),
+                Err((offset, message)) => Err(self.error(column + offset, message)),
+            };
+        }
+        if text.starts_with(['&', '*', '!']) {
+            return Err(self.error(column, "anchors, aliases and tags are not supported"));
+        }
+        Ok(text.to_string())
+    }
+
+    /// Parse a scalar or flow collection starting at `text` on the current
+    /// line; flow collections may continue on the lines after it
+    fn parse_inline(&mut self, text: &str, column: usize) -> Result<JsonValue, YamlError> {
+        let start = self.position;
+        let mut text = text.to_string();
+        if text.starts_with(['[', '{']) {
+            while !flow_balanced(&text) {
+                self.position += 1;
+                let Some(line) = self.lines.get(self.position) else {
+                    self.position = start;
+                    return Err(self.error(column, "unterminated flow collection"));
+                };
+                text.push(' ');
+                text.push_str(strip_comment(line).trim());
+            }
+        }
+        self.position += 1;
+
+        let mut flow = FlowParser::new(&text);
+        let value = flow.parse_value(false).and_then(|value| {
+            flow.skip_spaces();
+            if flow.at_end() {
+                Ok(value)
+            } else {
+                Err((flow.position, "unexpected characters after the value".to_string()))
+            }
+        });
+        value.map_err(|(offset, message)| YamlError::ParseError {
+            line: start + 1,
+            column: column + offset,
+            message,
+        })
+    }
+
+    /// Read a literal (`|`) or folded (`>`) block scalar whose header is
+    /// `header`. Its lines are indented further than `parent`.
+    fn parse_block_scalar(&mut self, header: &str, column: usize, parent: Option<usize>) -> Result<JsonValue, YamlError> {
+        let folded = header.starts_with('>');
+        let chomping = header[1..].trim();
+        if !matches!(chomping, "" | "-" | "+") {
+            return Err(self.error(column + 1, "unsupported block scalar header; expected |, |-, |+, >, >- or >+"));
+        }
+        self.position += 1;
+
+        let mut lines: Vec<&str> = Vec::new();
+        let mut content_indent = None;
+        while let Some(line) = self.lines.get(self.position) {
+            if line.trim().is_empty() {
+                lines.push("");
+                self.position += 1;
+                continue;
+            }
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            if parent.is_some_and(|parent| indent <= parent) || indent < *content_indent.get_or_insert(indent) {
+                break;
+            }
+            lines.push(&line[content_indent.unwrap()..]);
+            self.position += 1;
+        }
+
+        let trailing = lines.iter().rev().take_while(|line| line.is_empty()).count();
+        let content = &lines[..lines.len() - trailing];
+        let mut body = String::new();
+        for (index, line) in content.iter().enumerate() {
+            if index > 0 {
+                let previous = content[index - 1];
+                let joins = folded
+                    && !line.is_empty()
+                    && !previous.is_empty()
+                    && !line.starts_with(' ')
+                    && !previous.starts_with(' ');
+                if joins {
+                    body.push(' ');
+                } else if !(folded && previous.is_empty()) {
+                    body.push('\n');
+                }
+            }
+            body.push_str(line);
+        }
+
+        match chomping {
+            "-" => {}
+            "+" => body.push_str(&"\n".repeat(trailing + usize::from(!content.is_empty()))),
+            _ if !content.is_empty() => body.push('\n'),
+            _ => {}
+        }
+        Ok(JsonValue::String(body))
+    }
+}
+
+/// Parser for flow collections and scalars within a single line of text.
+/// Errors carry the character offset they occurred at.
+struct FlowParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+type FlowResult<T> = Result<T, (usize, String)>;
+
+impl FlowParser {
+    fn new(text: &str) -> Self {
+        Self {
+            chars: text.chars().collect(),
+            position: 0,
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.chars.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.position += 1;
+        }
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> FlowResult<T> {
+        Err((self.position, message.into()))
+    }
+
+    /// Parse one value; inside a flow collection, plain scalars end at `,`, `]` and `}`
+    fn parse_value(&mut self, in_flow: bool) -> FlowResult<JsonValue> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('[') => self.parse_sequence(),
+            Some('{') => self.parse_mapping(),
+            Some('"') => self.parse_double_quoted().map(JsonValue::String),
+            Some('\'') => self.parse_single_quoted().map(JsonValue::String),
+            Some('&' | '*' | '!') => self.error("anchors, aliases and tags are not supported"),
+            _ => Ok(resolve_plain(&self.parse_plain(in_flow, false))),
+        }
+    }
+
+    fn parse_sequence(&mut self) -> FlowResult<JsonValue> {
+        self.position += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_spaces();
+            if self.peek() == Some(']') {
+                self.position += 1;
+                return Ok(JsonValue::Array(items));
+            }
+            items.push(self.parse_value(true)?);
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(']') => {}
+                _ => return self.error("expected ',' or ']' in a flow sequence"),
+            }
+        }
+    }
+
+    fn parse_mapping(&mut self) -> FlowResult<JsonValue> {
+        self.position += 1;
+        let mut object = HashMap::new();
+        loop {
+            self.skip_spaces();
+            if self.peek() == Some('}') {
+                self.position += 1;
+                return Ok(JsonValue::Object(object));
+            }
+            let key_position = self.position;
+            let key = match self.peek() {
+                Some('"') => self.parse_double_quoted()?,
+                Some('\'') => self.parse_single_quoted()?,
+                _ => self.parse_plain(true, true),
+            };
+            self.skip_spaces();
+            let value = if self.peek() == Some(':') {
+                self.position += 1;
+                self.skip_spaces();
+                match self.peek() {
+                    Some(',' | '}') => JsonValue::Null,
+                    _ => self.parse_value(true)?,
+                }
+            } else {
+                JsonValue::Null
+            };
+            if object.insert(key.clone(), value).is_some() {
+                return Err((key_position, format!("duplicate key '{}'", key)));
+            }
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some('}') => {}
+                _ => return self.error("expected ',' or '}' in a flow mapping"),
+            }
+        }
+    }
+
+    /// A plain scalar, up to the end of the text or, in flow collections, the
+    /// next indicator; keys also end at `:`
+    fn parse_plain(&mut self, in_flow: bool, is_key: bool) -> String {
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if in_flow && matches!(c, ',' | ']' | '}') {
+                break;
+            }
+            if is_key && c == ':' {
+                break;
+            }
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect::<String>().trim().to_string()
+    }
+
+    fn parse_single_quoted(&mut self) -> FlowResult<String> {
+        self.position += 1;
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some('\'') if self.chars.get(self.position + 1) == Some(&'\'') => {
+                    value.push('\'');
+                    self.position += 2;
+                }
+                Some('\'') => {
+                    self.position += 1;
+                    return Ok(value);
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.position += 1;
+                }
+                None => return self.error("unterminated single-quoted string"),
+            }
+        }
+    }
+
+    fn parse_double_quoted(&mut self) -> FlowResult<String> {
+        self.position += 1;
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some('"') => {
+                    self.position += 1;
+                    return Ok(value);
+                }
+                Some('\\') => {
+                    self.position += 1;
+                    let escaped = match self.peek() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('0') => '\0',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('e') => '\u{1b}',
+                        Some(' ') => ' ',
+                        Some('/') => '/',
+                        Some('\\') => '\\',
+                        Some('"') => '"',
+                        Some(kind @ ('x' | 'u' | 'U')) => {
+                            let digits = match kind {
+                                'x' => 2,
+                                'u' => 4,
+                                _ => 8,
+                            };
+                            let start = self.position + 1;
+                            let hex: String = self.chars.iter().skip(start).take(digits).collect();
+                            let code = u32::from_str_radix(&hex, 16).ok().filter(|_| hex.len() == digits);
+                            match code.and_then(char::from_u32) {
+                                Some(c) => {
+                                    self.position += digits;
+                                    c
+                                }
+                                None => return self.error(format!("invalid escape \\{}{}", kind, hex)),
+                            }
+                        }
+                        Some(other) => return self.error(format!("invalid escape \\{}", other)),
+                        None => return self.error("unterminated double-quoted string"),
+                    };
+                    value.push(escaped);
+                    self.position += 1;
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.position += 1;
+                }
+                None => return self.error("unterminated double-quoted string"),
+            }
+        }
+    }
+}
+
+/// Whether a line starts (`---`) or ends (`...`) a document
+fn is_document_marker(indent: usize, text: &str) -> bool {
+    indent == 0 && (text == "---" || text == "...")
+}
+
+fn is_sequence_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Whether a quote at `index` opens a quoted scalar: quotes inside plain
+/// scalars, as in `it's`, do not
+fn opens_quote(chars: &[char], index: usize) -> bool {
+    index == 0 || matches!(chars[index - 1], ' ' | '\t' | '[' | '{' | ',' | ':')
+}
+
+/// Call `visit` with each character outside quoted scalars and its index,
+/// stopping when it returns false
+fn scan_unquoted(text: &str, mut visit: impl FnMut(&[char], usize) -> bool) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut quote = None;
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        match quote {
+            Some('"') if c == '\\' => index += 1,
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if (c == '"' || c == '\'') && opens_quote(&chars, index) => quote = Some(c),
+            None => {
+                if !visit(&chars, index) {
+                    return;
+                }
+            }
+        }
+        index += 1;
+    }
+}
+
+/// A line without its comment: `#` at the start or after whitespace, outside quotes
+fn strip_comment(line: &str) -> &str {
+    let mut end = None;
+    scan_unquoted(line, |chars, index| {
+        if chars[index] == '#' && (index == 0 || chars[index - 1] == ' ' || chars[index - 1] == '\t') {
+            end = Some(chars[..index].iter().map(|c| c.len_utf8()).sum::<usize>());
+            return false;
+        }
+        true
+    });
+    end.map_or(line, |end| &line[..end])
+}
+
+/// The byte offset of the `:` ending a block mapping key on this line, if it has one
+fn find_key_colon(text: &str) -> Option<usize> {
+    if text.starts_with(['[', '{']) {
+        return None;
+    }
+    let mut depth = 0usize;
+    let mut colon = None;
+    scan_unquoted(text, |chars, index| {
+        match chars[index] {
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth = depth.saturating_sub(1),
+            ':' if depth == 0 && matches!(chars.get(index + 1), None | Some(' ') | Some('\t')) => {
+                colon = Some(chars[..index].iter().map(|c| c.len_utf8()).sum());
+                return false;
+            }
+            _ => {}
+        }
+        true
+    });
+    colon
+}
+
+/// Whether every bracket of a flow collection is closed
+fn flow_balanced(text: &str) -> bool {
+    let mut depth = 0i32;
+    scan_unquoted(text, |chars, index| {
+        match chars[index] {
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth -= 1,
+            _ => {}
+        }
+        true
+    });
+    depth <= 0
+}
+
+/// The value of a plain scalar under the YAML 1.2 core schema
+fn resolve_plain(text: &str) -> JsonValue {
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return JsonValue::Null,
+        "true" | "True" | "TRUE" => return JsonValue::Bool(true),
+        "false" | "False" | "FALSE" => return JsonValue::Bool(false),
+        ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => return JsonValue::Number(f64::INFINITY),
+        "-.inf" | "-.Inf" | "-.INF" => return JsonValue::Number(f64::NEG_INFINITY),
+        ".nan" | ".NaN" | ".NAN" => return JsonValue::Number(f64::NAN),
+        _ => {}
+    }
+    let radix = |prefix: &str, radix: u32| {
+        text.strip_prefix(prefix)
+            .filter(|digits| !digits.is_empty())
+            .and_then(|digits| i64::from_str_radix(digits, radix).ok())
+    };
+    if let Some(n) = radix("0x", 16).or_else(|| radix("0o", 8)) {
+        return JsonValue::Number(n as f64);
+    }
+    if is_decimal(text) {
+        if let Ok(n) = text.parse::<f64>() {
+            return JsonValue::Number(n);
+        }
+    }
+    JsonValue::String(text.to_string())
+}
+
+/// Whether `text` is a decimal number: `[-+]?(digits[.digits?] | .digits)([eE][-+]?digits)?`
+fn is_decimal(text: &str) -> bool {
+    let unsigned = text.strip_prefix(['-', '+']).unwrap_or(text);
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(index) => (&unsigned[..index], Some(&unsigned[index + 1..])),
+        None => (unsigned, None),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    let mantissa_ok = digits(whole) && digits(fraction) && !(whole.is_empty() && fraction.is_empty());
+    let exponent_ok = exponent.is_none_or(|exponent| {
+        let exponent = exponent.strip_prefix(['-', '+']).unwrap_or(exponent);
+        !exponent.is_empty() && digits(exponent)
+    });
+    mantissa_ok && exponent_ok
+}
+
+fn emit_mapping(object: &HashMap<String, JsonValue>, indent: usize, out: &mut String) {
+    let mut keys: Vec<&String> = object.keys().collect();
+    keys.sort();
+    for key in keys {
+        out.push_str(&" ".repeat(indent));
+        out.push_str(&string_text(key));
+        out.push(':');
+        match &object[key] {
+            JsonValue::Object(nested) if !nested.is_empty() => {
+                out.push('\n');
+                emit_mapping(nested, indent + 2, out);
+            }
+            JsonValue::Array(items) if !items.is_empty() => {
+                out.push('\n');
+                emit_sequence(items, indent + 2, out);
+            }
+            scalar => {
+                out.push(' ');
+                out.push_str(&scalar_text(scalar));
+                out.push('\n');
+            }
+        }
+    }
+}
+
+fn emit_sequence(items: &[JsonValue], indent: usize, out: &mut String) {
+    for item in items {
+        out.push_str(&" ".repeat(indent));
+        out.push_str("- ");
+        // Collections start on the item's line, at the indentation after "- "
+        let mut nested = String::new();
+        match item {
+            JsonValue::Object(object) if !object.is_empty() => emit_mapping(object, indent + 2, &mut nested),
+            JsonValue::Array(items) if !items.is_empty() => emit_sequence(items, indent + 2, &mut nested),
+            scalar => {
+                out.push_str(&scalar_text(scalar));
+                out.push('\n');
+                continue;
+            }
+        }
+        out.push_str(&nested[indent + 2..]);
+    }
+}
+
+fn scalar_text(value: &JsonValue) -> String {
+    match value {
+        JsonValue::Null => "null".to_string(),
+        JsonValue::Bool(b) => b.to_string(),
+        JsonValue::Number(n) if n.is_nan() => ".nan".to_string(),
+        JsonValue::Number(n) if n.is_infinite() => if *n > 0.0 { ".inf" } else { "-.inf" }.to_string(),
+        JsonValue::Number(n) => format_float64(*n),
+        JsonValue::String(s) => string_text(s),
+        JsonValue::Array(_) => "[]".to_string(),
+        JsonValue::Object(_) => "{}".to_string(),
+    }
+}
+
+/// A string as a plain scalar when it reads back as the same string, and
+/// double-quoted otherwise
+fn string_text(s: &str) -> String {
+    let plain = !s.is_empty()
+        && resolve_plain(s) == JsonValue::String(s.to_string())
+        && !s.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@`".contains(c) || c.is_whitespace())
+        && !s.ends_with(|c: char| c == ':' || c.is_whitespace())
+        && !s.contains(": ")
+        && !s.contains(" #")
+        && !s.chars().any(char::is_control);
+    if plain {
+        return s.to_string();
+    }
+
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::std::json::Json;
+
+    fn json(text: &str) -> String {
+        Json::stringify(&parse(text).unwrap())
+    }
+
+    #[test]
+    fn test_parse_block_collections() {
+        let text = "\
+# service settings
+name: api   # trailing comment
+port: 8080
+debug: false
+ratio: 0.25
+missing: ~
+tags:
+- web
+- 'it''s'
+owner:
+  name: \"Ada \\u0041\"
+  roles: [admin, \"dev\"]
+servers:
+  - host: a.example
+    ports:
+      - 80
+  - host: b.example
+    ports: []
+";
+        assert_eq!(
+            json(text),
+            r#"{"debug":false,"missing":null,"name":"api","owner":{"name":"Ada A","roles":["admin","dev"]},"port":8080,"ratio":0.25,"servers":[{"host":"a.example","ports":[80]},{"host":"b.example","ports":[]}],"tags":["web","it's"]}"#
+        );
+        assert_eq!(json("---\n- - 1\n  - 2\n- {a: 1, b: [x, y]}\n...\n"), r#"[[1,2],{"a":1,"b":["x","y"]}]"#);
+        assert_eq!(json("url: http://example.com/#top\nlist: [1,\n  2]\n"), r#"{"list":[1,2],"url":"http://example.com/#top"}"#);
+        assert_eq!(json(""), "null");
+        assert_eq!(json("plain text: here"), r#"{"plain text":"here"}"#);
+    }
+
+    #[test]
+    fn test_parse_scalars() {
+        let scalar = |text: &str| resolve_plain(text);
+        assert_eq!(scalar("0x1f"), JsonValue::Number(31.0));
+        assert_eq!(scalar("0o17"), JsonValue::Number(15.0));
+        assert_eq!(scalar("-1.5e3"), JsonValue::Number(-1500.0));
+        assert_eq!(scalar(".5"), JsonValue::Number(0.5));
+        assert_eq!(scalar("TRUE"), JsonValue::Bool(true));
+        assert_eq!(scalar("yes"), JsonValue::String("yes".to_string()));
+        assert_eq!(scalar("inf"), JsonValue::String("inf".to_string()));
+        assert_eq!(scalar("1.2.3"), JsonValue::String("1.2.3".to_string()));
+        assert_eq!(scalar("-.inf"), JsonValue::Number(f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn test_parse_block_scalars() {
+        let text = "literal: |\n  line one\n    indented\n\n  line three\nfolded: >-\n  a\n  b\n\n  c\nkeep: |+\n  x\n\nstrip: |-\n  y\nafter: 1\n";
+        let document = parse(text).unwrap();
+        let field = |name: &str| match &document {
+            JsonValue::Object(object) => object[name].clone(),
+            _ => unreachable!(),
+        };
+        assert_eq!(field("literal"), JsonValue::String("line one\n  indented\n\nline three\n".to_string()));
+        assert_eq!(field("folded"), JsonValue::String("a b\nc".to_string()));
+        assert_eq!(field("keep"), JsonValue::String("x\n\n".to_string()));
+        assert_eq!(field("strip"), JsonValue::String("y".to_string()));
+        assert_eq!(field("after"), JsonValue::Number(1.0));
+        assert_eq!(json("- |\n  text\n- 2\n"), r#"["text\n",2]"#);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |text: &str| parse(text).unwrap_err().to_string();
+        assert_eq!(error("a: 1\na: 2\n"), "YAML Parse Error at line 2, column 1: duplicate key 'a'");
+        assert_eq!(
+            error("a:\n  b: 1\n   c: 2\n"),
+            "YAML Parse Error at line 3, column 4: unexpected indentation in a mapping"
+        );
+        assert_eq!(error("a: &x 1\n"), "YAML Parse Error at line 1, column 4: anchors, aliases and tags are not supported");
+        assert_eq!(error("a: 1\n---\nb: 2\n"), "YAML Parse Error at line 2, column 1: multiple documents are not supported");
+        assert_eq!(error("a: [1, 2\n"), "YAML Parse Error at line 1, column 4: unterminated flow collection");
+        assert_eq!(error("a: \"x\" y\n"), "YAML Parse Error at line 1, column 8: unexpected characters after the value");
+        assert_eq!(error("a: \"\\q\"\n"), "YAML Parse Error at line 1, column 6: invalid escape \\q");
+        assert_eq!(error("a:\n\tb: 1\n"), "YAML Parse Error at line 2, column 1: tabs cannot be used for indentation");
+    }
+
+    #[test]
+    fn test_stringify_round_trips() {
+        let document = Json::parse(
+            r#"{"name": "api", "port": 8080, "empty": "", "flag": "true", "note": "a: b", "lines": "x\ny", "nested": {"list": [1, {"a": null, "b": [true]}, []], "map": {}}}"#,
+        )
+        .unwrap();
+        let text = stringify(&document);
+        assert_eq!(
+            text,
+            "\
+empty: \"\"
+flag: \"true\"
+lines: \"x\\ny\"
+name: api
+nested:
+  list:
+    - 1
+    - a: null
+      b:
+        - true
+    - []
+  map: {}
+note: \"a: b\"
+port: 8080
+"
+        );
+        assert_eq!(parse(&text).unwrap(), document);
+        assert_eq!(stringify(&JsonValue::String("- x".to_string())), "\"- x\"\n");
+    }
+}
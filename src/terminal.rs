@@ -0,0 +1,112 @@
+//! Terminal capabilities and color control for the command-line tools
+//!
+//! The build system, test runner, linter, formatter and package commands style
+//! their output through `colored`. `init` decides once per process whether
+//! those styles are written:
+//!
+//! - `--color always` and `--color never` decide outright
+//! - `--color auto`, the default, colors only when stdout and stderr are both
+//!   terminals, `NO_COLOR` is unset and `TERM` is not `dumb`.
+//!   `CLICOLOR_FORCE` turns colors on anyway.
+
+use std::fmt;
+use std::io::IsTerminal;
+use std::str::FromStr;
+
+/// Values of the `--color` option
+pub const COLOR_CHOICES: &[&str] = &["auto", "always", "never"];
+
+/// When to color output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!(
+                "Invalid color choice '{}', expected one of {}",
+                s,
+                COLOR_CHOICES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ColorChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorChoice::Auto => write!(f, "auto"),
+            ColorChoice::Always => write!(f, "always"),
+            ColorChoice::Never => write!(f, "never"),
+        }
+    }
+}
+
+impl ColorChoice {
+    /// Whether output should be colored on a terminal with these capabilities
+    pub fn should_color(self, capabilities: &Capabilities) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => capabilities.force_color || capabilities.supports_color(),
+        }
+    }
+}
+
+/// What the process's terminal and environment allow
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    pub stdout_is_terminal: bool,
+    pub stderr_is_terminal: bool,
+    /// Value of `TERM`
+    pub term: Option<String>,
+    /// `NO_COLOR` is set to a non-empty value
+    pub no_color: bool,
+    /// `CLICOLOR_FORCE` is set to something other than `0`
+    pub force_color: bool,
+}
+
+impl Capabilities {
+    /// Capabilities of the current process
+    pub fn detect() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        Self {
+            stdout_is_terminal: std::io::stdout().is_terminal(),
+            stderr_is_terminal: std::io::stderr().is_terminal(),
+            term: var("TERM"),
+            no_color: var("NO_COLOR").is_some(),
+            force_color: var("CLICOLOR_FORCE").is_some_and(|value| value != "0"),
+        }
+    }
+
+    /// Whether escape codes can be written without ending up in a file, a
+    /// pipe or a terminal that doesn't understand them
+    pub fn supports_color(&self) -> bool {
+        self.stdout_is_terminal
+            && self.stderr_is_terminal
+            && !self.no_color
+            && self.term.as_deref() != Some("dumb")
+    }
+}
+
+/// Decide whether this process colors its output; returns the decision
+pub fn init(choice: ColorChoice) -> bool {
+    let enabled = choice.should_color(&Capabilities::detect());
+    colored::control::set_override(enabled);
+    enabled
+}
+
+/// Whether output is colored, as decided by `init`
+pub fn colors_enabled() -> bool {
+    colored::control::SHOULD_COLORIZE.should_colorize()
+}
@@ -0,0 +1,422 @@
+//! Language conformance suite
+//!
+//! The suite is a directory of small programs, each tagged with the language
+//! features it exercises and what the language says it must do. Both are
+//! written as comments:
+//!
+//! ```text
+//! // spec: closures, functions
+//! func main() {
+//!     let base = 10
+//!     let add = func(x: int32): int32 { return x + base }
+//!     println(add(5))  // expect: 15
+//! }
+//! ```
+//!
+//! - `// spec: a, b` names the features the program belongs to; every
+//!   program needs one.
+//! - `// expect: text` is the next line the program prints. A program must
+//!   print exactly its expected lines, in order, and nothing else.
+//! - `// expect-error: message` means the program must stop with an error
+//!   containing `message`. Written after code on the same line, an error
+//!   reported with a position must point at that line.
+//! - `// known-failure: reason` marks a program the implementation does not
+//!   get right yet. It does not fail the suite, and is reported once it
+//!   passes so the marker can be removed.
+//!
+//! Programs run on the AST interpreter by default, through the same
+//! machinery as differential testing, and results are reported grouped by
+//! feature.
+
+use crate::testing::differential::{Backend, BackendResult, DifferentialRunner, Outcome, DEFAULT_TIMEOUT};
+use crate::{BuluError, Result};
+use colored::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Where the crate's own conformance programs live
+pub const DEFAULT_DIR: &str = "tests/conformance";
+
+/// An error a program must stop with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedError {
+    pub message: String,
+    /// The line the marker follows code on, if it does
+    pub line: Option<usize>,
+}
+
+/// A conformance program and the markers in its comments
+#[derive(Debug, Clone)]
+pub struct ConformanceTest {
+    pub name: String,
+    pub source: String,
+    pub features: Vec<String>,
+    pub expected_stdout: Vec<String>,
+    pub expected_error: Option<ExpectedError>,
+    pub known_failure: Option<String>,
+}
+
+impl ConformanceTest {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| BuluError::Other(format!("Failed to read {}: {}", path.display(), e)))?;
+        Self::parse(&path.display().to_string(), &source)
+    }
+
+    /// Read the markers of a program, naming it `name` in errors and reports
+    pub fn parse(name: &str, source: &str) -> Result<Self> {
+        let error = |line: usize, message: &str| BuluError::Other(format!("{}:{}: {}", name, line, message));
+        let mut test = Self {
+            name: name.to_string(),
+            source: source.to_string(),
+            features: Vec::new(),
+            expected_stdout: Vec::new(),
+            expected_error: None,
+            known_failure: None,
+        };
+
+        for (index, line) in source.lines().enumerate() {
+            let number = index + 1;
+            let Some((code, comment)) = split_comment(line) else {
+                continue;
+            };
+            let comment = comment.trim_start();
+            if let Some(features) = comment.strip_prefix("spec:") {
+                test.features.extend(
+                    features
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|feature| !feature.is_empty())
+                        .map(str::to_string),
+                );
+            } else if let Some(text) = comment.strip_prefix("expect:") {
+                // One space separates the marker from the text, which may itself start with spaces
+                test.expected_stdout
+                    .push(text.strip_prefix(' ').unwrap_or(text).trim_end().to_string());
+            } else if let Some(message) = comment.strip_prefix("expect-error:") {
+                if test.expected_error.is_some() {
+                    return Err(error(number, "a program can only expect one error"));
+                }
+                test.expected_error = Some(ExpectedError {
+                    message: message.trim().to_string(),
+                    line: (!code.trim().is_empty()).then_some(number),
+                });
+            } else if let Some(reason) = comment.strip_prefix("known-failure:") {
+                test.known_failure = Some(reason.trim().to_string());
+            }
+        }
+
+        if test.features.is_empty() {
+            return Err(BuluError::Other(format!("{}: missing a `// spec:` tag naming its features", name)));
+        }
+        Ok(test)
+    }
+
+    /// Why `result` is not what the markers expect, if it is not
+    pub fn check(&self, result: &BackendResult) -> std::result::Result<(), String> {
+        let outcome = match result {
+            BackendResult::Ran(outcome) => outcome,
+            BackendResult::TimedOut => return Err("timed out".to_string()),
+            BackendResult::Skipped(reason) => return Err(format!("skipped: {}", reason)),
+        };
+        // An unexpected error explains missing output better than the output does
+        if let (None, Some(diagnostic)) = (&self.expected_error, &outcome.diagnostic) {
+            return Err(format!("unexpected error: {}", diagnostic));
+        }
+        self.check_stdout(outcome)?;
+
+        match (&self.expected_error, &outcome.diagnostic) {
+            (None, _) if outcome.exit_code != 0 => Err(format!("exited with code {}", outcome.exit_code)),
+            (None, _) => Ok(()),
+            (Some(expected), None) => Err(format!(
+                "expected error \"{}\", but the program {}",
+                expected.message,
+                match outcome.exit_code {
+                    0 => "succeeded".to_string(),
+                    code => format!("exited with code {}", code),
+                }
+            )),
+            (Some(expected), Some(diagnostic)) => {
+                let line = diagnostic_line(diagnostic);
+                if !diagnostic.contains(&expected.message) {
+                    Err(format!("expected error \"{}\", got: {}", expected.message, diagnostic))
+                } else if expected.line.is_some() && line.is_some() && line != expected.line {
+                    Err(format!(
+                        "expected error on line {}, got: {}",
+                        expected.line.unwrap(),
+                        diagnostic
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn check_stdout(&self, outcome: &Outcome) -> std::result::Result<(), String> {
+        let printed: Vec<&str> = outcome.stdout.lines().map(str::trim_end).collect();
+        for (index, expected) in self.expected_stdout.iter().enumerate() {
+            match printed.get(index) {
+                Some(line) if line == expected => {}
+                Some(line) => {
+                    return Err(format!(
+                        "output line {}: expected \"{}\", got \"{}\"",
+                        index + 1,
+                        expected,
+                        line
+                    ))
+                }
+                None => {
+                    return Err(format!(
+                        "output line {}: expected \"{}\", but the program printed {} lines",
+                        index + 1,
+                        expected,
+                        printed.len()
+                    ))
+                }
+            }
+        }
+        match printed.get(self.expected_stdout.len()) {
+            Some(extra) => Err(format!(
+                "output line {}: unexpected \"{}\"",
+                self.expected_stdout.len() + 1,
+                extra
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The code and comment of a source line, if it has a `//` comment outside
+/// a string literal
+fn split_comment(line: &str) -> Option<(&str, &str)> {
+    let bytes = line.as_bytes();
+    let mut in_string = false;
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' if in_string => index += 1,
+            b'"' => in_string = !in_string,
+            b'/' if !in_string && bytes.get(index + 1) == Some(&b'/') => {
+                return Some((&line[..index], &line[index + 2..]));
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    None
+}
+
+/// The line of a diagnostic written as `line:column: message`
+fn diagnostic_line(diagnostic: &str) -> Option<usize> {
+    let (line, rest) = diagnostic.split_once(':')?;
+    let (column, _) = rest.split_once(':')?;
+    column.parse::<usize>().ok()?;
+    line.parse().ok()
+}
+
+/// How a conformance program did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Passed,
+    Failed(String),
+    /// Failed as its `known-failure` marker says it does
+    KnownFailure(String),
+    /// Passed despite a `known-failure` marker
+    UnexpectedPass,
+}
+
+impl Verdict {
+    /// Whether this verdict fails the suite
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Verdict::Failed(_) | Verdict::UnexpectedPass)
+    }
+}
+
+/// The verdict on one program
+#[derive(Debug, Clone)]
+pub struct TestOutcome {
+    pub name: String,
+    pub features: Vec<String>,
+    pub verdict: Verdict,
+}
+
+/// The verdicts on every program of a suite run
+#[derive(Debug, Clone, Default)]
+pub struct ConformanceReport {
+    pub outcomes: Vec<TestOutcome>,
+}
+
+impl ConformanceReport {
+    /// Outcomes by feature, in feature order; a program with several
+    /// features is listed under each
+    pub fn by_feature(&self) -> BTreeMap<&str, Vec<&TestOutcome>> {
+        let mut features: BTreeMap<&str, Vec<&TestOutcome>> = BTreeMap::new();
+        for outcome in &self.outcomes {
+            for feature in &outcome.features {
+                features.entry(feature.as_str()).or_default().push(outcome);
+            }
+        }
+        features
+    }
+
+    pub fn failures(&self) -> Vec<&TestOutcome> {
+        self.outcomes.iter().filter(|outcome| outcome.verdict.is_failure()).collect()
+    }
+
+    fn count(&self, matches: fn(&Verdict) -> bool) -> usize {
+        self.outcomes.iter().filter(|outcome| matches(&outcome.verdict)).count()
+    }
+
+    /// One line totalling the verdicts
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} programs: {} passed, {} failed",
+            self.outcomes.len(),
+            self.count(|verdict| *verdict == Verdict::Passed),
+            self.count(Verdict::is_failure)
+        );
+        let known = self.count(|verdict| matches!(verdict, Verdict::KnownFailure(_)));
+        if known > 0 {
+            summary.push_str(&format!(", {} known failure{}", known, if known == 1 { "" } else { "s" }));
+        }
+        summary
+    }
+
+    /// Print one line per feature, followed by the programs that did not pass
+    pub fn print(&self) {
+        for (feature, outcomes) in self.by_feature() {
+            let passed = outcomes.iter().filter(|outcome| outcome.verdict == Verdict::Passed).count();
+            let status = format!("{}/{} passed", passed, outcomes.len());
+            let status = if outcomes.iter().any(|outcome| outcome.verdict.is_failure()) {
+                status.red().bold()
+            } else {
+                status.green()
+            };
+            println!("{:<20} {}", feature, status);
+            for outcome in outcomes {
+                match &outcome.verdict {
+                    Verdict::Passed => {}
+                    Verdict::Failed(reason) => println!("  {} {}: {}", "FAIL".red().bold(), outcome.name, reason),
+                    Verdict::KnownFailure(reason) => {
+                        println!("  {} {}: {}", "known failure".yellow(), outcome.name, reason)
+                    }
+                    Verdict::UnexpectedPass => println!(
+                        "  {} {}: remove its known-failure marker",
+                        "UNEXPECTED PASS".red().bold(),
+                        outcome.name
+                    ),
+                }
+            }
+        }
+        println!("\n{}", self.summary());
+    }
+}
+
+/// Runs conformance programs on one backend
+#[derive(Debug, Clone)]
+pub struct ConformanceRunner {
+    backend: Backend,
+    timeout: Duration,
+    feature: Option<String>,
+}
+
+impl Default for ConformanceRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConformanceRunner {
+    /// A runner for the AST interpreter
+    pub fn new() -> Self {
+        Self {
+            backend: Backend::Interpreter,
+            timeout: DEFAULT_TIMEOUT,
+            feature: None,
+        }
+    }
+
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// How long each program may run
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Only run the programs tagged with `feature`
+    pub fn with_feature(mut self, feature: &str) -> Self {
+        self.feature = Some(feature.to_string());
+        self
+    }
+
+    /// Run one program and judge it by its markers
+    pub fn run_test(&self, test: &ConformanceTest) -> TestOutcome {
+        let comparison = DifferentialRunner::new(vec![self.backend])
+            .with_timeout(self.timeout)
+            .run_source(&test.name, &test.source);
+        let checked = match comparison.results.first() {
+            Some((_, result)) => test.check(result),
+            None => Err("no backend to run on".to_string()),
+        };
+        let verdict = match (checked, &test.known_failure) {
+            (Ok(()), None) => Verdict::Passed,
+            (Ok(()), Some(_)) => Verdict::UnexpectedPass,
+            (Err(reason), None) => Verdict::Failed(reason),
+            (Err(_), Some(reason)) => Verdict::KnownFailure(reason.clone()),
+        };
+        TestOutcome {
+            name: test.name.clone(),
+            features: test.features.clone(),
+            verdict,
+        }
+    }
+
+    /// Run every `.bu` program under `dir`. Programs whose markers cannot be
+    /// read fail without running.
+    pub fn run_dir(&self, dir: &Path) -> Result<ConformanceReport> {
+        let mut report = ConformanceReport::default();
+        for path in discover(dir)? {
+            let outcome = match ConformanceTest::from_file(&path) {
+                Ok(test) if !self.selects(&test) => continue,
+                Ok(test) => self.run_test(&test),
+                Err(error) => TestOutcome {
+                    name: path.display().to_string(),
+                    features: vec!["(untagged)".to_string()],
+                    verdict: Verdict::Failed(error.to_string()),
+                },
+            };
+            report.outcomes.push(outcome);
+        }
+        Ok(report)
+    }
+
+    fn selects(&self, test: &ConformanceTest) -> bool {
+        self.feature
+            .as_ref()
+            .is_none_or(|feature| test.features.contains(feature))
+    }
+}
+
+/// The `.bu` files under `dir`, in path order
+pub fn discover(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    let entries = fs::read_dir(dir)
+        .map_err(|e| BuluError::Other(format!("Failed to read {}: {}", dir.display(), e)))?;
+    for entry in entries {
+        let path = entry.map_err(|e| BuluError::Other(e.to_string()))?.path();
+        if path.is_dir() {
+            paths.extend(discover(&path)?);
+        } else if path.extension().is_some_and(|extension| extension == "bu") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
@@ -0,0 +1,436 @@
+//! Differential testing across execution backends
+//!
+//! Runs the same program through the AST interpreter (`bulu run --source`),
+//! the IR virtual machine and the native backend, and compares what each of
+//! them does: stdout, stderr, exit code and the error that stopped the
+//! program. Any difference is a semantic drift between backends.
+//!
+//! Every backend compiles the program with a [`CompileSession`], so compile
+//! errors are reported the same way; a backend whose later stages reject the
+//! program reports that as its diagnostic. The native backend only runs on
+//! x86_64 Linux with `as` and `ld` installed, and is skipped elsewhere.
+
+use crate::compiler::{CompileSession, Diagnostic, Stage};
+use crate::runtime::ast_interpreter::AstInterpreter;
+use crate::runtime::interpreter::Interpreter;
+use crate::runtime::output::{self, Capture, OutputSinks};
+use crate::runtime::simplify::simplify;
+use crate::std::process::CommandSpec;
+use crate::{BuluError, Result};
+use colored::*;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a backend may run a program by default
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The native backend writes its intermediate files to fixed paths under
+/// `target/build`, so programs are built one at a time
+static NATIVE_BUILD: Mutex<()> = Mutex::new(());
+
+/// Numbers the executables of native runs, which go to the temp directory
+static NEXT_EXECUTABLE: AtomicU64 = AtomicU64::new(0);
+
+/// A way of executing Bulu programs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// The AST interpreter `bulu run --source` uses
+    Interpreter,
+    /// The virtual machine executing IR
+    Vm,
+    /// Executables from the native code generator
+    Native,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 3] = [Backend::Interpreter, Backend::Vm, Backend::Native];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Interpreter => "interpreter",
+            Backend::Vm => "vm",
+            Backend::Native => "native",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self> {
+        Backend::ALL
+            .into_iter()
+            .find(|backend| backend.name() == name)
+            .ok_or_else(|| {
+                BuluError::Other(format!(
+                    "Unknown backend: {} (expected interpreter, vm, native or all)",
+                    name
+                ))
+            })
+    }
+
+    /// Parse a comma-separated list of backends, or `all`
+    pub fn parse_list(list: &str) -> Result<Vec<Self>> {
+        if list.trim() == "all" {
+            return Ok(Backend::ALL.to_vec());
+        }
+        let mut backends = Vec::new();
+        for name in list.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let backend = Backend::parse(name)?;
+            if !backends.contains(&backend) {
+                backends.push(backend);
+            }
+        }
+        if backends.is_empty() {
+            return Err(BuluError::Other("No backends given".to_string()));
+        }
+        Ok(backends)
+    }
+
+    /// Why the backend cannot run on this machine, if it cannot
+    pub fn unavailable(self) -> Option<String> {
+        match self {
+            Backend::Interpreter | Backend::Vm => None,
+            Backend::Native => {
+                if !cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+                    return Some("the native backend targets x86_64 Linux".to_string());
+                }
+                ["as", "ld"]
+                    .into_iter()
+                    .find(|tool| std::process::Command::new(tool).arg("--version").output().is_err())
+                    .map(|tool| format!("`{}` is not installed", tool))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// What a program observably did on one backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+    /// The error that stopped the program, with its position when known
+    pub diagnostic: Option<String>,
+}
+
+impl Outcome {
+    /// The outcome of a program stopped by `error` before or while running
+    fn failed(stdout: String, stderr: String, error: &BuluError) -> Self {
+        let (exit_code, diagnostic) = match error {
+            BuluError::ExitRequested(code) => (*code, None),
+            // Without the file name the interpreter adds to runtime errors
+            BuluError::RuntimeError { message, .. } => (1, Some(message.clone())),
+            error => (1, Some(describe(&Diagnostic::from_error("", Stage::Codegen, error)))),
+        };
+        Self {
+            stdout,
+            stderr,
+            exit_code,
+            diagnostic,
+        }
+    }
+}
+
+/// A diagnostic without its file name or stage, which differ between backends
+/// for the same problem
+fn describe(diagnostic: &Diagnostic) -> String {
+    match (diagnostic.line, diagnostic.column) {
+        (Some(line), Some(column)) => format!("{}:{}: {}", line, column, diagnostic.message),
+        (Some(line), None) => format!("{}: {}", line, diagnostic.message),
+        _ => diagnostic.message.clone(),
+    }
+}
+
+/// How running a program on a backend went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackendResult {
+    Ran(Outcome),
+    TimedOut,
+    Skipped(String),
+}
+
+impl fmt::Display for BackendResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendResult::Ran(outcome) => {
+                write!(f, "exit code {}, stdout {:?}", outcome.exit_code, outcome.stdout)?;
+                if !outcome.stderr.is_empty() {
+                    write!(f, ", stderr {:?}", outcome.stderr)?;
+                }
+                if let Some(diagnostic) = &outcome.diagnostic {
+                    write!(f, ", error {:?}", diagnostic)?;
+                }
+                Ok(())
+            }
+            BackendResult::TimedOut => f.write_str("timed out"),
+            BackendResult::Skipped(reason) => write!(f, "skipped: {}", reason),
+        }
+    }
+}
+
+/// The results of one program on every backend it ran on
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub name: String,
+    pub results: Vec<(Backend, BackendResult)>,
+}
+
+impl Comparison {
+    /// Whether every backend that was not skipped did the same thing
+    pub fn agrees(&self) -> bool {
+        let mut ran = self
+            .results
+            .iter()
+            .filter(|(_, result)| !matches!(result, BackendResult::Skipped(_)))
+            .map(|(_, result)| result);
+        match ran.next() {
+            Some(first) => ran.all(|result| result == first),
+            None => true,
+        }
+    }
+
+    pub fn result(&self, backend: Backend) -> Option<&BackendResult> {
+        self.results
+            .iter()
+            .find(|(candidate, _)| *candidate == backend)
+            .map(|(_, result)| result)
+    }
+
+    /// One line per backend, for reports
+    pub fn describe(&self) -> String {
+        self.results
+            .iter()
+            .map(|(backend, result)| format!("  {:<12} {}\n", backend.name(), result))
+            .collect()
+    }
+}
+
+/// Where a program comes from
+#[derive(Debug, Clone)]
+enum Source {
+    File(PathBuf),
+    Text { name: String, source: String },
+}
+
+impl Source {
+    fn name(&self) -> String {
+        match self {
+            Source::File(path) => path.display().to_string(),
+            Source::Text { name, .. } => name.clone(),
+        }
+    }
+
+    fn session(&self) -> CompileSession {
+        match self {
+            Source::File(path) => CompileSession::from_file(path),
+            Source::Text { name, source } => CompileSession::from_source(name.clone(), source.clone()),
+        }
+    }
+}
+
+/// Runs programs on several backends and compares the results
+#[derive(Debug, Clone)]
+pub struct DifferentialRunner {
+    backends: Vec<Backend>,
+    timeout: Duration,
+}
+
+impl DifferentialRunner {
+    pub fn new(backends: Vec<Backend>) -> Self {
+        Self {
+            backends,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// How long each backend may run a program
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn backends(&self) -> &[Backend] {
+        &self.backends
+    }
+
+    /// Run a program file on every backend
+    pub fn run_file(&self, path: &Path) -> Comparison {
+        self.compare(Source::File(path.to_path_buf()))
+    }
+
+    /// Run source code on every backend, naming it `name` in diagnostics
+    pub fn run_source(&self, name: &str, source: &str) -> Comparison {
+        self.compare(Source::Text {
+            name: name.to_string(),
+            source: source.to_string(),
+        })
+    }
+
+    /// Run every program and print a report of the ones the backends
+    /// disagree on, returning how many those are
+    pub fn run_files(&self, paths: &[PathBuf]) -> usize {
+        let mut mismatches = 0;
+        for path in paths {
+            let comparison = self.run_file(path);
+            if comparison.agrees() {
+                println!("{} {}", "agree".green(), comparison.name);
+            } else {
+                mismatches += 1;
+                println!("{} {}", "DIFFER".red().bold(), comparison.name);
+                print!("{}", comparison.describe());
+            }
+        }
+        println!(
+            "\n{} programs on {}: {} agree, {} differ",
+            paths.len(),
+            self.backends.iter().map(|backend| backend.name()).collect::<Vec<_>>().join(", "),
+            paths.len() - mismatches,
+            mismatches
+        );
+        mismatches
+    }
+
+    fn compare(&self, source: Source) -> Comparison {
+        let results = self
+            .backends
+            .iter()
+            .map(|&backend| {
+                let result = match backend.unavailable() {
+                    Some(reason) => BackendResult::Skipped(reason),
+                    None => self.run(backend, &source),
+                };
+                (backend, result)
+            })
+            .collect();
+        Comparison {
+            name: source.name(),
+            results,
+        }
+    }
+
+    fn run(&self, backend: Backend, source: &Source) -> BackendResult {
+        match backend {
+            Backend::Interpreter => self.in_process(source, run_interpreter),
+            Backend::Vm => self.in_process(source, run_vm),
+            Backend::Native => run_native(source, self.timeout),
+        }
+    }
+
+    /// Run a backend that executes in this process on its own thread, so a
+    /// program that never finishes times out. Such a thread is left behind.
+    fn in_process(&self, source: &Source, run: fn(&Source, &Capture, &Capture) -> Result<()>) -> BackendResult {
+        let (done, finished) = mpsc::channel();
+        let source = source.clone();
+        let (stdout, stderr) = (Capture::new(), Capture::new());
+        let (out, err) = (stdout.clone(), stderr.clone());
+        thread::spawn(move || {
+            let _ = done.send(run(&source, &out, &err));
+        });
+        match finished.recv_timeout(self.timeout) {
+            Ok(Ok(())) => BackendResult::Ran(Outcome {
+                stdout: stdout.contents(),
+                stderr: stderr.contents(),
+                exit_code: 0,
+                diagnostic: None,
+            }),
+            Ok(Err(error)) => BackendResult::Ran(Outcome::failed(stdout.contents(), stderr.contents(), &error)),
+            Err(mpsc::RecvTimeoutError::Timeout) => BackendResult::TimedOut,
+            Err(mpsc::RecvTimeoutError::Disconnected) => BackendResult::Ran(Outcome::failed(
+                stdout.contents(),
+                stderr.contents(),
+                &BuluError::Other("the backend panicked".to_string()),
+            )),
+        }
+    }
+}
+
+fn run_interpreter(source: &Source, stdout: &Capture, stderr: &Capture) -> Result<()> {
+    let mut session = source.session();
+    let mut program = session.check()?.clone();
+    simplify(&mut program);
+
+    let mut interpreter = match source {
+        Source::File(path) => AstInterpreter::with_file(path.to_string_lossy().to_string()),
+        Source::Text { .. } => AstInterpreter::new(),
+    };
+    interpreter.set_stdout(Box::new(stdout.clone()));
+    interpreter.set_stderr(Box::new(stderr.clone()));
+    interpreter.execute_program(&program)?;
+    if let Some(main) = interpreter.get_function_definition("main") {
+        interpreter.call_user_function(&main, &[])?;
+    }
+    // An exit in a goroutine that main did not observe still ends the program
+    match interpreter.exit_requested() {
+        Some(code) => Err(BuluError::ExitRequested(code)),
+        None => Ok(()),
+    }
+}
+
+fn run_vm(source: &Source, stdout: &Capture, stderr: &Capture) -> Result<()> {
+    let mut session = source.session();
+    let ir = session.ir()?.clone();
+
+    let _output = output::enter(&OutputSinks {
+        stdout: Some(output::sink(Box::new(stdout.clone()))),
+        stderr: Some(output::sink(Box::new(stderr.clone()))),
+    });
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program(ir);
+    interpreter.execute().map(|_| ())
+}
+
+fn run_native(source: &Source, timeout: Duration) -> BackendResult {
+    let mut session = source.session();
+    let executable = {
+        let _build = NATIVE_BUILD.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        session.executable()
+    };
+    let executable = match executable {
+        Ok(executable) => executable,
+        Err(error) => return BackendResult::Ran(Outcome::failed(String::new(), String::new(), &error)),
+    };
+
+    let path = std::env::temp_dir().join(format!(
+        "bulu-differential-{}-{}",
+        std::process::id(),
+        NEXT_EXECUTABLE.fetch_add(1, Ordering::Relaxed)
+    ));
+    if let Err(error) = write_executable(&path, &executable) {
+        return BackendResult::Skipped(format!("could not write the executable: {}", error));
+    }
+    let mut command = CommandSpec::new(&path.to_string_lossy(), Vec::new());
+    command.timeout = Some(timeout);
+    let started = Instant::now();
+    let result = command.run();
+    let _ = fs::remove_file(&path);
+
+    match result {
+        Ok(output) => BackendResult::Ran(Outcome {
+            stdout: output.stdout,
+            stderr: output.stderr,
+            exit_code: output.code,
+            diagnostic: None,
+        }),
+        Err(_) if started.elapsed() >= timeout => BackendResult::TimedOut,
+        Err(error) => BackendResult::Skipped(format!("could not run the executable: {}", error)),
+    }
+}
+
+fn write_executable(path: &Path, executable: &[u8]) -> std::io::Result<()> {
+    fs::write(path, executable)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
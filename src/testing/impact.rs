@@ -0,0 +1,158 @@
+//! Test impact analysis for incremental `lang test` runs
+//!
+//! A test file's inputs are the file itself and every project module it
+//! imports, directly or through other modules. Results of passing test files
+//! are cached in `target/test/cache.toml` with a hash of their inputs; a
+//! file whose inputs hash the same on the next run is not run again and is
+//! reported as a cached pass. Standard library imports are covered by the
+//! compiler version, and vendored packages by the dependency hashes, both of
+//! which invalidate the whole cache when they change.
+
+use crate::ast::Statement;
+use crate::build::fingerprint::{hash_file, relative_key};
+use crate::build::Fingerprint;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::project::Project;
+use crate::resolver::module_resolver::ModuleResolver;
+use crate::{BuluError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Results recorded for a test file that passed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedTest {
+    /// Hash of the test file and the modules it imports
+    pub inputs: String,
+    pub total: usize,
+    pub passed: usize,
+    pub skipped: usize,
+}
+
+/// What `target/test/cache.toml` holds
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TestCache {
+    compiler_version: String,
+    /// lang.toml, lang.lock and vendored sources, as in build fingerprints
+    dependencies: BTreeMap<String, String>,
+    /// Passing test files, keyed by their path relative to the project root
+    pub files: BTreeMap<String, CachedTest>,
+}
+
+impl TestCache {
+    /// An empty cache for the current compiler and dependencies
+    pub fn current(project: &Project) -> Result<Self> {
+        let fingerprint = Fingerprint::compute(project, "test", "", "")?;
+        Ok(Self {
+            compiler_version: fingerprint.compiler_version,
+            dependencies: fingerprint.dependencies,
+            files: BTreeMap::new(),
+        })
+    }
+
+    /// Path of the results cache
+    pub fn path(project: &Project) -> PathBuf {
+        project.target_dir.join("test").join("cache.toml")
+    }
+
+    /// The recorded results, if they were produced by this compiler with
+    /// these dependencies
+    pub fn load_matching(&self, path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        let cache: Self = toml::from_str(&content).ok()?;
+        (cache.compiler_version == self.compiler_version && cache.dependencies == self.dependencies).then_some(cache)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| BuluError::Other(format!("Failed to create test cache directory: {}", e)))?;
+        }
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| BuluError::Other(format!("Failed to serialize test cache: {}", e)))?;
+        fs::write(path, content).map_err(|e| BuluError::Other(format!("Failed to write test cache: {}", e)))
+    }
+}
+
+/// Maps test files to the project modules they import
+pub struct TestImpact {
+    root: PathBuf,
+    src_dir: PathBuf,
+    /// Project files imported by each file read so far, or `None` when one
+    /// of its imports could not be resolved
+    imports: HashMap<PathBuf, Option<Vec<PathBuf>>>,
+}
+
+impl TestImpact {
+    pub fn new(project: &Project) -> Self {
+        Self {
+            root: canonical(&project.root),
+            src_dir: canonical(&project.src_dir),
+            imports: HashMap::new(),
+        }
+    }
+
+    /// The test file and every module it imports, directly or not. `None`
+    /// when an import can't be resolved, so the file can't be cached.
+    pub fn inputs(&mut self, test_file: &Path) -> Option<BTreeSet<PathBuf>> {
+        let mut inputs = BTreeSet::new();
+        let mut pending = vec![canonical(test_file)];
+        while let Some(file) = pending.pop() {
+            if inputs.insert(file.clone()) {
+                pending.extend(self.imports_of(&file)?.iter().cloned());
+            }
+        }
+        Some(inputs)
+    }
+
+    /// A hash of the contents of the test file's inputs, see [`Self::inputs`]
+    pub fn inputs_hash(&mut self, test_file: &Path) -> Result<Option<String>> {
+        let Some(inputs) = self.inputs(test_file) else {
+            return Ok(None);
+        };
+        let mut manifest = String::new();
+        for input in inputs {
+            manifest.push_str(&format!("{} {}\n", relative_key(&self.root, &input), hash_file(&input)?));
+        }
+        Ok(Some(sha256::digest(manifest)))
+    }
+
+    fn imports_of(&mut self, file: &Path) -> Option<&Vec<PathBuf>> {
+        if !self.imports.contains_key(file) {
+            let imports = self.read_imports(file);
+            self.imports.insert(file.to_path_buf(), imports);
+        }
+        self.imports[file].as_ref()
+    }
+
+    fn read_imports(&self, file: &Path) -> Option<Vec<PathBuf>> {
+        let source = fs::read_to_string(file).ok()?;
+        let tokens = Lexer::new(&source).tokenize().ok()?;
+        let program = Parser::new(tokens).parse().ok()?;
+
+        let mut resolver = ModuleResolver::new();
+        if let Some(dir) = file.parent() {
+            resolver.set_current_dir(dir.to_path_buf());
+        }
+        resolver.add_search_path(self.src_dir.clone());
+        resolver.add_search_path(self.root.clone());
+
+        let mut imports = Vec::new();
+        for statement in &program.statements {
+            if let Statement::Import(import) = statement {
+                if import.path.starts_with("std/") || import.path.starts_with("std.") {
+                    continue;
+                }
+                let path = resolver.resolve_module_path(&import.path, Some(file)).ok()?;
+                imports.push(canonical(&path));
+            }
+        }
+        Some(imports)
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
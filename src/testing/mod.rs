@@ -1,33 +1,41 @@
 //! Testing framework for Bulu projects
 
+pub mod conformance;
+pub mod differential;
+pub mod impact;
+
 use crate::Result;
+use crate::build::fingerprint::relative_key;
 use crate::project::Project;
 use crate::std::test::{TestRunner as StdTestRunner, TestResults, print_test_summary};
 use crate::lexer::Lexer;
 use crate::parser::Parser;
 use crate::runtime::interpreter::Interpreter;
 use colored::*;
+use impact::{CachedTest, TestCache, TestImpact};
+use tracing::{debug, error, info, warn};
 use std::fs;
 use std::path::Path;
 
 /// Test options
 #[derive(Debug, Clone)]
 pub struct TestOptions {
-    pub verbose: bool,
     pub coverage: bool,
     pub filter: Option<String>,
     pub parallel: bool,
     pub timeout: Option<u64>,
+    /// Skip test files whose inputs are unchanged since they last passed
+    pub incremental: bool,
 }
 
 impl Default for TestOptions {
     fn default() -> Self {
         Self {
-            verbose: false,
             coverage: false,
             filter: None,
             parallel: true,
             timeout: Some(30),
+            incremental: true,
         }
     }
 }
@@ -38,6 +46,8 @@ pub struct TestResult {
     pub passed: usize,
     pub failed: usize,
     pub skipped: usize,
+    /// Passed tests whose results came from the cache
+    pub cached: usize,
     pub total: usize,
 }
 
@@ -54,33 +64,59 @@ impl TestRunner {
 
     /// Run tests
     pub fn run_tests(&self) -> Result<TestResult> {
-        if self.options.verbose {
-            println!("{} Running tests for '{}'...", "Testing".green().bold(), self.project.config.package.name);
-        }
+        info!("{} Running tests for '{}'...", "Testing".green().bold(), self.project.config.package.name);
+        debug!(coverage = self.options.coverage, filter = ?self.options.filter, "Test options");
 
         // Use the project's test_files method
         let test_files = self.project.test_files()?;
         
         if test_files.is_empty() {
-            println!("{} No test files found", "Warning".yellow().bold());
+            warn!("{} No test files found", "Warning".yellow().bold());
             return Ok(TestResult {
                 passed: 0,
                 failed: 0,
                 skipped: 0,
+                cached: 0,
                 total: 0,
             });
         }
 
         let mut total_results = TestResults::new();
+        let mut impact = TestImpact::new(&self.project);
+        let mut cache = TestCache::current(&self.project)?;
+        let previous = if self.options.incremental {
+            cache.load_matching(&TestCache::path(&self.project)).unwrap_or_default()
+        } else {
+            TestCache::default()
+        };
 
         // Run tests from each file
         for test_file in test_files {
-            if self.options.verbose {
-                println!("{} Running tests from {}...", "Testing".cyan(), test_file.display());
+            let key = relative_key(&self.project.root, &test_file);
+            let inputs = impact.inputs_hash(&test_file)?;
+            if let Some(cached) = previous.files.get(&key).filter(|cached| inputs.as_ref() == Some(&cached.inputs)) {
+                debug!("{} {} (inputs unchanged)", "Cached".cyan(), test_file.display());
+                total_results.total += cached.total;
+                total_results.passed += cached.passed;
+                total_results.skipped += cached.skipped;
+                total_results.cached += cached.passed;
+                cache.files.insert(key, cached.clone());
+                continue;
             }
 
+            debug!("{} Running tests from {}...", "Testing".cyan(), test_file.display());
+
             match self.run_test_file(&test_file) {
                 Ok(results) => {
+                    // Only passing files are cached, so failures run again
+                    if let Some(inputs) = inputs.filter(|_| results.failed == 0) {
+                        cache.files.insert(key, CachedTest {
+                            inputs,
+                            total: results.total,
+                            passed: results.passed,
+                            skipped: results.skipped,
+                        });
+                    }
                     total_results.total += results.total;
                     total_results.passed += results.passed;
                     total_results.failed += results.failed;
@@ -89,7 +125,7 @@ impl TestRunner {
                     total_results.failed_tests.extend(results.failed_tests);
                 }
                 Err(e) => {
-                    println!("{} Failed to run tests from {}: {}", 
+                    error!("{} Failed to run tests from {}: {}", 
                         "Error".red().bold(), test_file.display(), e);
                     total_results.total += 1;
                     total_results.failed += 1;
@@ -97,6 +133,10 @@ impl TestRunner {
             }
         }
 
+        if self.options.incremental {
+            cache.save(&TestCache::path(&self.project))?;
+        }
+
         // Print summary
         print_test_summary(&total_results);
 
@@ -104,6 +144,7 @@ impl TestRunner {
             passed: total_results.passed,
             failed: total_results.failed,
             skipped: total_results.skipped,
+            cached: total_results.cached,
             total: total_results.total,
         })
     }
@@ -156,9 +197,7 @@ impl TestRunner {
 
     /// Generate coverage report
     pub fn generate_coverage(&self) -> Result<()> {
-        if self.options.verbose {
-            println!("{} Generating coverage report...", "Coverage".cyan().bold());
-        }
+        debug!("{} Generating coverage report...", "Coverage".cyan().bold());
         
         // Create coverage directory
         let coverage_dir = self.project.root.join("coverage");
@@ -169,7 +208,7 @@ impl TestRunner {
         let html_file = coverage_dir.join("index.html");
         fs::write(html_file, html_content)?;
         
-        println!("{} Coverage report generated in coverage/index.html", "Coverage".green().bold());
+        info!("{} Coverage report generated in coverage/index.html", "Coverage".green().bold());
         Ok(())
     }
 
@@ -238,19 +277,19 @@ impl BenchmarkRunner {
 
     /// Run benchmarks
     pub fn run_benchmarks(&self) -> Result<()> {
-        println!("{} Running benchmarks for '{}'...", "Benchmarking".green().bold(), self.project.config.package.name);
+        info!("{} Running benchmarks for '{}'...", "Benchmarking".green().bold(), self.project.config.package.name);
         
         // Find benchmark files
         let bench_files = self.find_benchmark_files()?;
         
         if bench_files.is_empty() {
-            println!("{} No benchmark files found", "Warning".yellow().bold());
+            warn!("{} No benchmark files found", "Warning".yellow().bold());
             return Ok(());
         }
 
         // Run benchmarks from each file
         for bench_file in bench_files {
-            println!("{} Running benchmarks from {}...", "Benchmarking".cyan(), bench_file.display());
+            debug!("{} Running benchmarks from {}...", "Benchmarking".cyan(), bench_file.display());
             self.run_benchmark_file(&bench_file)?;
         }
 
@@ -0,0 +1,454 @@
+//! Tech-debt report for a project (`lang todo`)
+//!
+//! Every comment in the project's sources and tests is searched for the
+//! markers `TODO`, `FIXME`, `HACK` and `XXX`, and for `@deprecated` tags. A
+//! marker counts when it is written in capitals as a word of its own, e.g.
+//! `// TODO: retry` or `/* FIXME(ana) leaks the handle */`; the text after it
+//! up to the end of the line describes the item. Text inside string and
+//! character literals is never a comment.
+//!
+//! Items are attributed to the author of their line according to
+//! `git blame` when the project is in a git repository, and otherwise to the
+//! owner named in parentheses after the marker, if any.
+
+use crate::project::Project;
+use crate::{BuluError, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// What a comment marks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkerKind {
+    Todo,
+    Fixme,
+    Hack,
+    Xxx,
+    Deprecated,
+}
+
+impl MarkerKind {
+    /// The markers, in the order of the report's counts
+    pub const ALL: [MarkerKind; 5] = [
+        MarkerKind::Todo,
+        MarkerKind::Fixme,
+        MarkerKind::Hack,
+        MarkerKind::Xxx,
+        MarkerKind::Deprecated,
+    ];
+
+    /// How the marker is written in comments
+    pub fn marker(self) -> &'static str {
+        match self {
+            MarkerKind::Todo => "TODO",
+            MarkerKind::Fixme => "FIXME",
+            MarkerKind::Hack => "HACK",
+            MarkerKind::Xxx => "XXX",
+            MarkerKind::Deprecated => "@deprecated",
+        }
+    }
+}
+
+impl fmt::Display for MarkerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkerKind::Deprecated => write!(f, "deprecated"),
+            kind => write!(f, "{}", kind.marker()),
+        }
+    }
+}
+
+/// A marker found in a comment
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DebtItem {
+    /// Path relative to the project root, with `/` separators
+    pub file: String,
+    pub line: usize,
+    pub kind: MarkerKind,
+    /// The text after the marker
+    pub text: String,
+    /// Who the marker names, as in `TODO(ana)`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Who last changed the line, according to `git blame`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+}
+
+impl DebtItem {
+    /// The author, or else the owner, or else `unknown`
+    pub fn attributed_to(&self) -> &str {
+        self.author.as_deref().or(self.owner.as_deref()).unwrap_or("unknown")
+    }
+}
+
+/// How a report groups its items
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    File,
+    Author,
+}
+
+/// Options of a tech-debt scan
+#[derive(Debug, Clone)]
+pub struct TodoOptions {
+    /// Attribute items with `git blame`
+    pub blame: bool,
+}
+
+impl Default for TodoOptions {
+    fn default() -> Self {
+        Self { blame: true }
+    }
+}
+
+/// The markers found in a project
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TodoReport {
+    pub files_scanned: usize,
+    /// Items by kind, e.g. `{"todo": 3, "fixme": 1}`
+    pub counts: BTreeMap<MarkerKind, usize>,
+    /// Items sorted by file and line
+    pub items: Vec<DebtItem>,
+}
+
+impl TodoReport {
+    pub fn new(files_scanned: usize, mut items: Vec<DebtItem>) -> Self {
+        items.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+        let mut counts = BTreeMap::new();
+        for item in &items {
+            *counts.entry(item.kind).or_insert(0) += 1;
+        }
+        Self {
+            files_scanned,
+            counts,
+            items,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("todo reports serialize")
+    }
+
+    /// A markdown document with a summary and a table per file or author
+    pub fn to_markdown(&self, group_by: GroupBy) -> String {
+        let mut markdown = String::from("# Tech debt\n\n");
+        markdown.push_str(&self.summary());
+        markdown.push('\n');
+
+        let mut groups: BTreeMap<&str, Vec<&DebtItem>> = BTreeMap::new();
+        for item in &self.items {
+            let key = match group_by {
+                GroupBy::File => item.file.as_str(),
+                GroupBy::Author => item.attributed_to(),
+            };
+            groups.entry(key).or_default().push(item);
+        }
+
+        for (group, items) in groups {
+            markdown.push_str(&format!("\n## {} ({})\n\n", group, items.len()));
+            match group_by {
+                GroupBy::File => markdown.push_str("| Line | Kind | Author | Text |\n|---:|---|---|---|\n"),
+                GroupBy::Author => markdown.push_str("| Location | Kind | Text |\n|---|---|---|\n"),
+            }
+            for item in items {
+                let text = item.text.replace('|', "\\|");
+                match group_by {
+                    GroupBy::File => markdown.push_str(&format!(
+                        "| {} | {} | {} | {} |\n",
+                        item.line,
+                        item.kind,
+                        item.attributed_to(),
+                        text
+                    )),
+                    GroupBy::Author => markdown.push_str(&format!(
+                        "| {}:{} | {} | {} |\n",
+                        item.file, item.line, item.kind, text
+                    )),
+                }
+            }
+        }
+        markdown
+    }
+
+    /// `3 items in 2 of 5 files: 2 TODO, 1 FIXME`
+    pub fn summary(&self) -> String {
+        let files = self
+            .items
+            .iter()
+            .map(|item| item.file.as_str())
+            .collect::<std::collections::BTreeSet<_>>()
+            .len();
+        let mut summary = format!(
+            "{} items in {} of {} files",
+            self.items.len(),
+            files,
+            self.files_scanned
+        );
+        let counts: Vec<String> = MarkerKind::ALL
+            .iter()
+            .filter_map(|kind| self.counts.get(kind).map(|count| format!("{} {}", count, kind)))
+            .collect();
+        if !counts.is_empty() {
+            summary.push_str(": ");
+            summary.push_str(&counts.join(", "));
+        }
+        summary
+    }
+}
+
+/// Scans a project's sources and tests for markers
+pub struct TodoScanner {
+    project: Project,
+    options: TodoOptions,
+}
+
+impl TodoScanner {
+    pub fn new(project: Project, options: TodoOptions) -> Self {
+        Self { project, options }
+    }
+
+    /// Scan every source and test file
+    pub fn scan(&self) -> Result<TodoReport> {
+        let mut files = self.project.source_files()?;
+        for file in self.project.test_files()? {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+
+        let mut items = Vec::new();
+        for path in &files {
+            let content = fs::read_to_string(path)
+                .map_err(|e| BuluError::Other(format!("Failed to read {}: {}", path.display(), e)))?;
+            let relative = path.strip_prefix(&self.project.root).unwrap_or(path);
+            let file = relative.to_string_lossy().replace('\\', "/");
+
+            let found = scan_source(&content);
+            let authors = if self.options.blame && !found.is_empty() {
+                blame(&self.project.root, relative).unwrap_or_default()
+            } else {
+                HashMap::new()
+            };
+            items.extend(found.into_iter().map(|marker| DebtItem {
+                file: file.clone(),
+                author: authors.get(&marker.line).cloned(),
+                line: marker.line,
+                kind: marker.kind,
+                text: marker.text,
+                owner: marker.owner,
+            }));
+        }
+
+        Ok(TodoReport::new(files.len(), items))
+    }
+}
+
+/// A marker found by [`scan_source`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker {
+    pub line: usize,
+    pub kind: MarkerKind,
+    pub text: String,
+    pub owner: Option<String>,
+}
+
+/// The markers in the comments of a source file
+pub fn scan_source(content: &str) -> Vec<Marker> {
+    comment_lines(content)
+        .into_iter()
+        .filter_map(|(line, comment)| find_marker(&comment).map(|(kind, owner, text)| Marker { line, kind, text, owner }))
+        .collect()
+}
+
+/// The text of every comment, one entry per 1-based source line it covers
+fn comment_lines(content: &str) -> Vec<(usize, String)> {
+    #[derive(PartialEq)]
+    enum State {
+        Code,
+        Literal(char),
+        LineComment,
+        BlockComment(usize),
+    }
+
+    let mut comments: Vec<(usize, String)> = Vec::new();
+    let mut state = State::Code;
+    let mut line = 1;
+    let mut chars = content.chars().peekable();
+    while let Some(ch) = chars.next() {
+        let in_comment = matches!(state, State::LineComment | State::BlockComment(_));
+        if ch == '\n' {
+            line += 1;
+            if state == State::LineComment {
+                state = State::Code;
+            }
+            continue;
+        }
+        match state {
+            State::Code => match (ch, chars.peek()) {
+                ('/', Some('/')) => {
+                    chars.next();
+                    state = State::LineComment;
+                }
+                ('/', Some('*')) => {
+                    chars.next();
+                    state = State::BlockComment(1);
+                }
+                ('"' | '\'', _) => state = State::Literal(ch),
+                _ => {}
+            },
+            State::Literal(quote) => {
+                if ch == '\\' {
+                    chars.next();
+                } else if ch == quote {
+                    state = State::Code;
+                }
+            }
+            State::LineComment => {}
+            State::BlockComment(depth) => match (ch, chars.peek()) {
+                ('*', Some('/')) => {
+                    chars.next();
+                    state = if depth == 1 { State::Code } else { State::BlockComment(depth - 1) };
+                }
+                ('/', Some('*')) => {
+                    chars.next();
+                    state = State::BlockComment(depth + 1);
+                }
+                _ => {}
+            },
+        }
+        if in_comment && matches!(state, State::LineComment | State::BlockComment(_)) {
+            match comments.last_mut() {
+                Some((last, text)) if *last == line => text.push(ch),
+                _ => comments.push((line, ch.to_string())),
+            }
+        }
+    }
+    comments
+}
+
+/// The first marker in a line of comment text, with its owner and text
+fn find_marker(comment: &str) -> Option<(MarkerKind, Option<String>, String)> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    MarkerKind::ALL
+        .iter()
+        .filter_map(|&kind| {
+            let marker = kind.marker();
+            comment.match_indices(marker).find_map(|(start, _)| {
+                let before = comment[..start].chars().next_back();
+                let rest = &comment[start + marker.len()..];
+                if before.is_some_and(is_word) || rest.starts_with(is_word) {
+                    return None;
+                }
+                Some((start, kind, rest))
+            })
+        })
+        .min_by_key(|(start, _, _)| *start)
+        .map(|(_, kind, rest)| {
+            let (owner, rest) = match rest.strip_prefix('(').and_then(|rest| rest.split_once(')')) {
+                Some((owner, rest)) => (Some(owner.trim().to_string()).filter(|owner| !owner.is_empty()), rest),
+                None => (None, rest),
+            };
+            let text = rest.trim_start().trim_start_matches([':', '-']).trim().trim_end_matches("*/").trim_end();
+            (kind, owner, text.to_string())
+        })
+}
+
+/// The author of each 1-based line of `file`, relative to `root`, according
+/// to `git blame`; `None` outside a git repository. Lines that are not
+/// committed have no author.
+fn blame(root: &Path, file: &Path) -> Option<HashMap<usize, String>> {
+    let output = Command::new("git")
+        .arg("blame")
+        .arg("--line-porcelain")
+        .arg("--")
+        .arg(file)
+        .current_dir(root)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let mut authors = HashMap::new();
+    let mut line = 0;
+    for entry in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(author) = entry.strip_prefix("author ") {
+            if author != "Not Committed Yet" {
+                authors.insert(line, author.to_string());
+            }
+        } else if !entry.starts_with('\t') {
+            // Each line's entry starts with `<hash> <original line> <final line>`
+            let mut fields = entry.split(' ');
+            let is_header = fields.next().is_some_and(|hash| hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit()));
+            if let (true, Some(_), Some(final_line)) = (is_header, fields.next(), fields.next()) {
+                line = final_line.parse().unwrap_or(0);
+            }
+        }
+    }
+    Some(authors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_source() {
+        let content = r#"// TODO: handle errors
+let url = "http://x // TODO not a comment"
+/* FIXME(ana) leaks
+   the handle
+   XXX */
+let todos = 1 // TODOS are not markers, HACK - retry twice
+/**
+ * @deprecated use open instead
+ */
+"#;
+        let markers = scan_source(content);
+        let markers: Vec<(usize, MarkerKind, Option<&str>, &str)> = markers
+            .iter()
+            .map(|m| (m.line, m.kind, m.owner.as_deref(), m.text.as_str()))
+            .collect();
+        assert_eq!(
+            markers,
+            vec![
+                (1, MarkerKind::Todo, None, "handle errors"),
+                (3, MarkerKind::Fixme, Some("ana"), "leaks"),
+                (5, MarkerKind::Xxx, None, ""),
+                (6, MarkerKind::Hack, None, "retry twice"),
+                (8, MarkerKind::Deprecated, None, "use open instead"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_markdown_report() {
+        let item = |file: &str, line, kind, author: Option<&str>| DebtItem {
+            file: file.to_string(),
+            line,
+            kind,
+            text: "a | b".to_string(),
+            owner: None,
+            author: author.map(str::to_string),
+        };
+        let report = TodoReport::new(
+            3,
+            vec![
+                item("src/b.bu", 4, MarkerKind::Fixme, Some("ana")),
+                item("src/a.bu", 2, MarkerKind::Todo, None),
+            ],
+        );
+        assert_eq!(report.summary(), "2 items in 2 of 3 files: 1 TODO, 1 FIXME");
+        assert_eq!(
+            report.to_markdown(GroupBy::Author),
+            "# Tech debt\n\n2 items in 2 of 3 files: 1 TODO, 1 FIXME\n\n\
+             ## ana (1)\n\n| Location | Kind | Text |\n|---|---|---|\n| src/b.bu:4 | FIXME | a \\| b |\n\n\
+             ## unknown (1)\n\n| Location | Kind | Text |\n|---|---|---|\n| src/a.bu:2 | TODO | a \\| b |\n"
+        );
+    }
+}
@@ -1,11 +1,20 @@
 //! Type checking implementation for the Bulu language
 
 use crate::ast::*;
+use crate::compiler::optimizer::{literal_value, ConstValue};
 use crate::error::{BuluError, Result};
 use crate::lexer::token::Position;
-use crate::types::composite::{ChannelTypeInfo, TypeRegistry};
+use crate::types::closures::{analyze_closures, ClosureAnalysis};
+use crate::types::composite::{ChannelTypeInfo, CompositeTypeId, TypeRegistry};
+use crate::types::generics::{
+    GenericConstraint, GenericFunction, GenericInstantiation, GenericStruct, GenericTypeParam,
+    GenericTypeRegistry,
+};
+use crate::types::patterns::{analyze_match, collect_pattern_types};
 use crate::types::primitive::{PrimitiveType, TypeId};
-use std::collections::HashMap;
+use crate::types::scope::ScopeChain;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
 /// Symbol table entry for type checking
 #[derive(Debug, Clone)]
@@ -15,7 +24,7 @@ pub struct Symbol {
     pub is_mutable: bool,
     pub position: Position,
     pub function_info: Option<FunctionInfo>,
-    pub module_exports: Option<HashMap<String, Symbol>>,
+    pub module_exports: Option<Rc<HashMap<String, Symbol>>>,
 }
 
 /// Function signature information
@@ -29,7 +38,7 @@ pub struct FunctionInfo {
 #[derive(Debug)]
 pub struct TypeChecker {
     /// Symbol table stack for nested scopes
-    pub scopes: Vec<HashMap<String, Symbol>>,
+    pub scopes: ScopeChain<Symbol>,
     /// Function return type stack
     return_types: Vec<Option<TypeId>>,
     /// Current function being checked
@@ -39,9 +48,9 @@ pub struct TypeChecker {
     /// Type registry for composite types
     type_registry: TypeRegistry,
     /// Interface declarations
-    interfaces: HashMap<String, InterfaceDecl>,
+    interfaces: HashMap<String, Rc<InterfaceDecl>>,
     /// Struct declarations
-    structs: HashMap<String, StructDecl>,
+    structs: HashMap<String, Rc<StructDecl>>,
     /// Map from type names to TypeIds
     type_name_to_id: HashMap<String, TypeId>,
     /// Map from TypeIds to type names
@@ -52,13 +61,65 @@ pub struct TypeChecker {
     collecting_functions: bool,
     /// Current file path for error reporting
     current_file: Option<String>,
+    /// Functions imported from std/arrays, local name -> exported name
+    std_array_functions: HashMap<String, String>,
+    /// Functions imported from std/math, local name -> exported name
+    std_math_functions: HashMap<String, String>,
+    /// Functions imported from std/fmt, local name -> exported name
+    std_fmt_functions: HashMap<String, String>,
+    /// Functions imported from std/template, local name -> exported name
+    std_template_functions: HashMap<String, String>,
+    /// Functions imported from std/i18n, local name -> exported name
+    std_i18n_functions: HashMap<String, String>,
+    /// Functions imported from std/binary, local name -> exported name
+    std_binary_functions: HashMap<String, String>,
+    /// Functions imported from std/checksum, local name -> exported name
+    std_checksum_functions: HashMap<String, String>,
+    /// Functions imported from std/crypto, local name -> exported name
+    std_crypto_functions: HashMap<String, String>,
+    /// Functions imported from std/encoding, local name -> exported name
+    std_encoding_functions: HashMap<String, String>,
+    /// Functions imported from std/regex, local name -> exported name
+    std_regex_functions: HashMap<String, String>,
+    /// Functions imported from std/time other than sleep, local name -> exported name
+    std_time_functions: HashMap<String, String>,
+    /// Functions imported from std/log, local name -> exported name
+    std_log_functions: HashMap<String, String>,
+    /// Functions imported from std/schedule, local name -> exported name
+    std_schedule_functions: HashMap<String, String>,
+    /// Functions imported from std/os, local name or `module.function` -> exported name
+    std_os_functions: HashMap<String, String>,
+    /// Functions imported from std/fs, local name -> exported name
+    std_fs_functions: HashMap<String, String>,
+    /// Functions imported from std/process, local name -> exported name
+    std_process_functions: HashMap<String, String>,
+    /// Functions imported from std/collections, local name -> exported name
+    std_collections_functions: HashMap<String, String>,
+    /// Functions imported from std/json, std/toml and std/yaml, local name -> exported name
+    std_data_functions: HashMap<String, String>,
+    /// Whether builtins are in scope without importing them from std/builtin
+    prelude: bool,
+    /// Builtins imported from std/builtin, which stay in scope without the prelude
+    builtin_imports: HashSet<String>,
+    /// Generic function and struct signatures and their instantiations
+    generics: GenericTypeRegistry,
+    /// Generic function declarations, instantiated at each call site
+    generic_functions: HashMap<String, Rc<FunctionDecl>>,
+    /// Instantiated generic structs: instance TypeId -> (struct name, type arguments)
+    struct_instances: HashMap<TypeId, (String, Vec<TypeId>)>,
+    /// Type parameter bindings in scope, innermost last
+    type_param_bindings: Vec<HashMap<String, TypeId>>,
+    /// Non-generic type aliases, such as `type Shape = Circle | Square`
+    type_aliases: HashMap<String, Type>,
+    /// Captures and escape information of every lambda in the checked program
+    closures: ClosureAnalysis,
 }
 
 impl TypeChecker {
     /// Create a new type checker
     pub fn new() -> Self {
         let mut checker = Self {
-            scopes: vec![HashMap::new()], // Global scope
+            scopes: ScopeChain::new(),
             return_types: Vec::new(),
             current_function: None,
             errors: Vec::new(),
@@ -70,6 +131,32 @@ impl TypeChecker {
             next_type_id: 1100, // Start from 1100 to avoid conflicts with std types (1001-1099 reserved)
             collecting_functions: false,
             current_file: None,
+            std_array_functions: HashMap::new(),
+            std_math_functions: HashMap::new(),
+            std_fmt_functions: HashMap::new(),
+            std_template_functions: HashMap::new(),
+            std_i18n_functions: HashMap::new(),
+            std_binary_functions: HashMap::new(),
+            std_checksum_functions: HashMap::new(),
+            std_crypto_functions: HashMap::new(),
+            std_encoding_functions: HashMap::new(),
+            std_regex_functions: HashMap::new(),
+            std_time_functions: HashMap::new(),
+            std_log_functions: HashMap::new(),
+            std_schedule_functions: HashMap::new(),
+            std_os_functions: HashMap::new(),
+            std_fs_functions: HashMap::new(),
+            std_process_functions: HashMap::new(),
+            std_collections_functions: HashMap::new(),
+            std_data_functions: HashMap::new(),
+            prelude: true,
+            builtin_imports: HashSet::new(),
+            generics: GenericTypeRegistry::new(),
+            generic_functions: HashMap::new(),
+            struct_instances: HashMap::new(),
+            type_param_bindings: Vec::new(),
+            type_aliases: HashMap::new(),
+            closures: ClosureAnalysis::default(),
         };
 
         // Add built-in functions to global scope
@@ -82,6 +169,12 @@ impl TypeChecker {
         self.current_file = file_path;
     }
 
+    /// Disable the prelude, as `prelude = false` in lang.toml does for a package;
+    /// files starting with `@no_prelude` disable it themselves
+    pub fn set_prelude(&mut self, prelude: bool) {
+        self.prelude = prelude;
+    }
+
     /// Add built-in functions to the global scope (public method for re-adding after imports)
     pub fn add_builtin_functions_after_import(&mut self) {
         self.add_builtin_functions();
@@ -90,8 +183,6 @@ impl TypeChecker {
     /// Add standard library types and their methods
     pub fn add_std_types(&mut self) {
         self.add_std_net_types();
-        self.add_std_time_types();
-        self.add_result_type_methods();
     }
 
     /// Add built-in functions to the global scope
@@ -135,6 +226,8 @@ impl TypeChecker {
             ("delete", vec![TypeId::Any, TypeId::Any], None),
             // Utility functions
             ("typeof", vec![TypeId::Any], Some(TypeId::String)),
+            ("hash", vec![TypeId::Any], Some(TypeId::Int64)),
+            ("intrinsic", vec![TypeId::String], Some(TypeId::Any)),
             (
                 "instanceof",
                 vec![TypeId::Any, TypeId::String],
@@ -143,6 +236,10 @@ impl TypeChecker {
             ("panic", vec![TypeId::Any], None),
             ("assert", vec![TypeId::Bool], None),
             ("recover", vec![], Some(TypeId::Any)),
+            // Result and Option constructors
+            ("Ok", vec![TypeId::Any], None),
+            ("Err", vec![TypeId::Any], None),
+            ("Some", vec![TypeId::Any], None),
             // Channel functions
             ("close", vec![TypeId::Any], None),
             // Synchronization functions
@@ -214,419 +311,1234 @@ impl TypeChecker {
             ("chan", TypeId::Any), // channel type identifier
         ];
 
-        if let Some(global_scope) = self.scopes.first_mut() {
-            // Add primitive type identifiers
-            for (name, type_id) in primitive_type_identifiers {
-                let symbol = Symbol {
-                    name: name.to_string(),
-                    type_id,
-                    is_mutable: false,
-                    position: Position::new(0, 0, 0),
-                    function_info: None,
-                    module_exports: None,
-                };
-                global_scope.insert(name.to_string(), symbol);
-            }
-
-            // Add builtin functions (force insert to overwrite any conflicting imports)
-            for (name, param_types, return_type) in builtin_functions {
-                let symbol = Symbol {
-                    name: name.to_string(),
-                    type_id: TypeId::Function(0), // Placeholder function type
-                    is_mutable: false,
-                    position: Position::new(0, 0, 0),
-                    function_info: Some(FunctionInfo {
-                        param_types,
-                        return_type,
-                    }),
-                    module_exports: None,
-                };
-                // Force insert to ensure builtin functions are always available
-                global_scope.insert(name.to_string(), symbol);
-            }
-
-            // Add channel type identifiers (generated by make() parser)
-            let channel_types = vec![
-                "chan_int8",
-                "chan_int16",
-                "chan_int32",
-                "chan_int64",
-                "chan_uint8",
-                "chan_uint16",
-                "chan_uint32",
-                "chan_uint64",
-                "chan_float32",
-                "chan_float64",
-                "chan_bool",
-                "chan_char",
-                "chan_string",
-                "chan_any",
-                "chan_unknown",
-                "chan",
-            ];
-
-            for chan_type in channel_types {
-                let symbol = Symbol {
-                    name: chan_type.to_string(),
-                    type_id: TypeId::String, // Channel type identifiers are treated as strings
-                    is_mutable: false,
-                    position: Position::new(0, 0, 0),
-                    function_info: None,
-                    module_exports: None,
-                };
-                global_scope.insert(chan_type.to_string(), symbol);
-            }
-
-            // Add slice type identifiers (generated by make() parser)
-            let slice_types = vec![
-                "slice_int8",
-                "slice_int16",
-                "slice_int32",
-                "slice_int64",
-                "slice_uint8",
-                "slice_uint16",
-                "slice_uint32",
-                "slice_uint64",
-                "slice_float32",
-                "slice_float64",
-                "slice_bool",
-                "slice_char",
-                "slice_string",
-                "slice_any",
-                "slice_unknown",
-            ];
-
-            for slice_type in slice_types {
-                let symbol = Symbol {
-                    name: slice_type.to_string(),
-                    type_id: TypeId::String, // Slice type identifiers are treated as strings
-                    is_mutable: false,
-                    position: Position::new(0, 0, 0),
-                    function_info: None,
-                   module_exports: None,
-                };
-                global_scope.insert(slice_type.to_string(), symbol);
-            }
-        }
-    }
+        // `None` is the empty Option of any inner type
+        let none_type = self.option_type_id(TypeId::Any);
 
-    /// Add std/net types and their methods
-    fn add_std_net_types(&mut self) {
-        if let Some(global_scope) = self.scopes.first_mut() {
-            // Add NetAddr type with static methods
-            let net_addr_symbol = Symbol {
-                name: "NetAddr".to_string(),
-                type_id: TypeId::Struct(1001), // Use a unique ID for NetAddr
+        let global_scope = self.scopes.globals_mut();
+        global_scope.insert(
+            "None".to_string(),
+            Rc::new(Symbol {
+                name: "None".to_string(),
+                type_id: none_type,
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
                 function_info: None,
                 module_exports: None,
-            };
-            global_scope.insert("NetAddr".to_string(), net_addr_symbol);
-
-            // Register type name mappings for std types
-            self.type_id_to_name
-                .insert(TypeId::Struct(1001), "NetAddr".to_string());
-            self.type_name_to_id
-                .insert("NetAddr".to_string(), TypeId::Struct(1001));
-
-            // Add NetAddr instance methods
-            let net_addr_tostring_symbol = Symbol {
-                name: "toString".to_string(),
-                type_id: TypeId::Function(1014),
+            }),
+        );
+
+        // Add primitive type identifiers
+        for (name, type_id) in primitive_type_identifiers {
+            let symbol = Symbol {
+                name: name.to_string(),
+                type_id,
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
-                function_info: Some(FunctionInfo {
-                    param_types: vec![],               // no parameters (method on self)
-                    return_type: Some(TypeId::String), // returns string
-                }),
+                function_info: None,
                 module_exports: None,
             };
-            global_scope.insert("NetAddr.toString".to_string(), net_addr_tostring_symbol);
+            global_scope.insert(name.to_string(), Rc::new(symbol));
+        }
 
-            // Add NetAddr.localhost_ipv4 static method
-            let localhost_ipv4_symbol = Symbol {
-                name: "localhost_ipv4".to_string(),
-                type_id: TypeId::Function(1002),
+        // Add builtin functions (force insert to overwrite any conflicting imports)
+        for (name, param_types, return_type) in builtin_functions {
+            let symbol = Symbol {
+                name: name.to_string(),
+                type_id: TypeId::Function(0), // Placeholder function type
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
                 function_info: Some(FunctionInfo {
-                    param_types: vec![TypeId::Int32],        // port parameter
-                    return_type: Some(TypeId::Struct(1001)), // returns NetAddr
+                    param_types,
+                    return_type,
                 }),
                 module_exports: None,
             };
-            // Add as a method on NetAddr (we'll need to handle this in method resolution)
-            global_scope.insert("NetAddr.localhost_ipv4".to_string(), localhost_ipv4_symbol);
+            // Force insert to ensure builtin functions are always available
+            global_scope.insert(name.to_string(), Rc::new(symbol));
+        }
+
+        // Add channel type identifiers (generated by make() parser)
+        let channel_types = vec![
+            "chan_int8",
+            "chan_int16",
+            "chan_int32",
+            "chan_int64",
+            "chan_uint8",
+            "chan_uint16",
+            "chan_uint32",
+            "chan_uint64",
+            "chan_float32",
+            "chan_float64",
+            "chan_bool",
+            "chan_char",
+            "chan_string",
+            "chan_any",
+            "chan_unknown",
+            "chan",
+        ];
 
-            // Add other networking types
-            let tcp_server_symbol = Symbol {
-                name: "TcpServer".to_string(),
-                type_id: TypeId::Struct(1003),
+        for chan_type in channel_types {
+            let symbol = Symbol {
+                name: chan_type.to_string(),
+                type_id: TypeId::String, // Channel type identifiers are treated as strings
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
                 function_info: None,
                 module_exports: None,
             };
-            global_scope.insert("TcpServer".to_string(), tcp_server_symbol);
+            global_scope.insert(chan_type.to_string(), Rc::new(symbol));
+        }
 
-            self.type_id_to_name
-                .insert(TypeId::Struct(1003), "TcpServer".to_string());
-            self.type_name_to_id
-                .insert("TcpServer".to_string(), TypeId::Struct(1003));
+        // Add slice type identifiers (generated by make() parser)
+        let slice_types = vec![
+            "slice_int8",
+            "slice_int16",
+            "slice_int32",
+            "slice_int64",
+            "slice_uint8",
+            "slice_uint16",
+            "slice_uint32",
+            "slice_uint64",
+            "slice_float32",
+            "slice_float64",
+            "slice_bool",
+            "slice_char",
+            "slice_string",
+            "slice_any",
+            "slice_unknown",
+        ];
 
-            // Add TcpServer instance methods
-            let tcp_server_accept_symbol = Symbol {
-                name: "accept".to_string(),
-                type_id: TypeId::Function(1007),
+        for slice_type in slice_types {
+            let symbol = Symbol {
+                name: slice_type.to_string(),
+                type_id: TypeId::String, // Slice type identifiers are treated as strings
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
-                function_info: Some(FunctionInfo {
-                    param_types: vec![],                     // no parameters (method on self)
-                    return_type: Some(TypeId::Result(1004)), // returns Result<TcpConnection>
-                }),
-                module_exports: None,
+                function_info: None,
+               module_exports: None,
             };
-            global_scope.insert("TcpServer.accept".to_string(), tcp_server_accept_symbol);
+            global_scope.insert(slice_type.to_string(), Rc::new(symbol));
+        }
+    }
+
+    /// Add std/net types and their methods
+    fn add_std_net_types(&mut self) {
+        // Register type name mappings for std types
+        for (type_id, name) in [
+            (TypeId::Struct(1001), "NetAddr"),
+            (TypeId::Struct(1003), "TcpServer"),
+            (TypeId::Struct(1004), "TcpConnection"),
+            (TypeId::Struct(1005), "UdpConnection"),
+        ] {
+            self.type_id_to_name.insert(type_id, name.to_string());
+            self.type_name_to_id.insert(name.to_string(), type_id);
+        }
+
+        // Network operations report failures as an error message
+        let tcp_server_result = self.result_type_id(TypeId::Struct(1003), TypeId::String);
+        let tcp_connection_result = self.result_type_id(TypeId::Struct(1004), TypeId::String);
+        let udp_connection_result = self.result_type_id(TypeId::Struct(1005), TypeId::String);
+        let byte_count_result = self.result_type_id(TypeId::Int64, TypeId::String);
+        let datagram_id = self
+            .type_registry
+            .register_tuple_type(vec![TypeId::Int64, TypeId::Struct(1001)]);
+        let datagram_result = self.result_type_id(TypeId::Tuple(datagram_id), TypeId::String);
+
+        let global_scope = self.scopes.globals_mut();
+        // Add NetAddr type with static methods
+        let net_addr_symbol = Symbol {
+            name: "NetAddr".to_string(),
+            type_id: TypeId::Struct(1001), // Use a unique ID for NetAddr
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: None,
+            module_exports: None,
+        };
+        global_scope.insert("NetAddr".to_string(), Rc::new(net_addr_symbol));
+
+        // Add NetAddr instance methods
+        let net_addr_tostring_symbol = Symbol {
+            name: "toString".to_string(),
+            type_id: TypeId::Function(1014),
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: Some(FunctionInfo {
+                param_types: vec![],               // no parameters (method on self)
+                return_type: Some(TypeId::String), // returns string
+            }),
+            module_exports: None,
+        };
+        global_scope.insert("NetAddr.toString".to_string(), Rc::new(net_addr_tostring_symbol));
+
+        // Add NetAddr.localhost_ipv4 static method
+        let localhost_ipv4_symbol = Symbol {
+            name: "localhost_ipv4".to_string(),
+            type_id: TypeId::Function(1002),
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: Some(FunctionInfo {
+                param_types: vec![TypeId::Int32],        // port parameter
+                return_type: Some(TypeId::Struct(1001)), // returns NetAddr
+            }),
+            module_exports: None,
+        };
+        // Add as a method on NetAddr (we'll need to handle this in method resolution)
+        global_scope.insert("NetAddr.localhost_ipv4".to_string(), Rc::new(localhost_ipv4_symbol));
+
+        // Add other networking types
+        let tcp_server_symbol = Symbol {
+            name: "TcpServer".to_string(),
+            type_id: TypeId::Struct(1003),
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: None,
+            module_exports: None,
+        };
+        global_scope.insert("TcpServer".to_string(), Rc::new(tcp_server_symbol));
+
+        // Add TcpServer instance methods
+        let tcp_server_accept_symbol = Symbol {
+            name: "accept".to_string(),
+            type_id: TypeId::Function(1007),
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: Some(FunctionInfo {
+                param_types: vec![],                     // no parameters (method on self)
+                return_type: Some(tcp_connection_result), // returns Result<TcpConnection>
+            }),
+            module_exports: None,
+        };
+        global_scope.insert("TcpServer.accept".to_string(), Rc::new(tcp_server_accept_symbol));
+
+        // Add TcpServer.bind static method
+        let tcp_server_bind_symbol = Symbol {
+            name: "bind".to_string(),
+            type_id: TypeId::Function(1006),
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: Some(FunctionInfo {
+                param_types: vec![TypeId::Struct(1001)], // NetAddr parameter
+                return_type: Some(tcp_server_result), // returns Result<TcpServer>
+            }),
+            module_exports: None,
+        };
+        global_scope.insert("TcpServer.bind".to_string(), Rc::new(tcp_server_bind_symbol));
+
+        let tcp_connection_symbol = Symbol {
+            name: "TcpConnection".to_string(),
+            type_id: TypeId::Struct(1004),
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: None,
+            module_exports: None,
+        };
+        global_scope.insert("TcpConnection".to_string(), Rc::new(tcp_connection_symbol));
+
+        // Add TcpConnection instance methods
+        let tcp_connection_peer_addr_symbol = Symbol {
+            name: "peer_addr".to_string(),
+            type_id: TypeId::Function(1008),
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: Some(FunctionInfo {
+                param_types: vec![],                     // no parameters (method on self)
+                return_type: Some(TypeId::Struct(1001)), // returns NetAddr
+            }),
+            module_exports: None,
+        };
+        global_scope.insert(
+            "TcpConnection.peer_addr".to_string(),
+            Rc::new(tcp_connection_peer_addr_symbol),
+        );
+
+        let tcp_connection_read_symbol = Symbol {
+            name: "read".to_string(),
+            type_id: TypeId::Function(1009),
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: Some(FunctionInfo {
+                param_types: vec![TypeId::Array(0)], // buffer parameter ([]byte)
+                return_type: Some(byte_count_result), // returns Result<int64> (bytes read)
+            }),
+            module_exports: None,
+        };
+        global_scope.insert("TcpConnection.read".to_string(), Rc::new(tcp_connection_read_symbol));
+
+        let tcp_connection_write_symbol = Symbol {
+            name: "write".to_string(),
+            type_id: TypeId::Function(1010),
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: Some(FunctionInfo {
+                param_types: vec![TypeId::Array(0)], // data parameter ([]byte)
+                return_type: Some(byte_count_result), // returns Result<int64> (bytes written)
+            }),
+            module_exports: None,
+        };
+        global_scope.insert(
+            "TcpConnection.write".to_string(),
+            Rc::new(tcp_connection_write_symbol),
+        );
+
+        let tcp_connection_close_symbol = Symbol {
+            name: "close".to_string(),
+            type_id: TypeId::Function(1011),
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: Some(FunctionInfo {
+                param_types: vec![], // no parameters (method on self)
+                return_type: None,   // returns void
+            }),
+            module_exports: None,
+        };
+        global_scope.insert(
+            "TcpConnection.close".to_string(),
+            Rc::new(tcp_connection_close_symbol),
+        );
+
+        // Add TcpConnection.connect static method
+        let tcp_connection_connect_symbol = Symbol {
+            name: "connect".to_string(),
+            type_id: TypeId::Function(1007),
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: Some(FunctionInfo {
+                param_types: vec![TypeId::Struct(1001)], // NetAddr parameter
+                return_type: Some(tcp_connection_result), // returns Result<TcpConnection>
+            }),
+            module_exports: None,
+        };
+        global_scope.insert(
+            "TcpConnection.connect".to_string(),
+            Rc::new(tcp_connection_connect_symbol),
+        );
+
+        let udp_connection_symbol = Symbol {
+            name: "UdpConnection".to_string(),
+            type_id: TypeId::Struct(1005),
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: None,
+            module_exports: None,
+        };
+        global_scope.insert("UdpConnection".to_string(), Rc::new(udp_connection_symbol));
+
+        // Add UdpConnection.bind static method
+        let udp_connection_bind_symbol = Symbol {
+            name: "bind".to_string(),
+            type_id: TypeId::Function(1008),
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: Some(FunctionInfo {
+                param_types: vec![TypeId::Struct(1001)], // NetAddr parameter
+                return_type: Some(udp_connection_result), // returns Result<UdpConnection>
+            }),
+            module_exports: None,
+        };
+        global_scope.insert("UdpConnection.bind".to_string(), Rc::new(udp_connection_bind_symbol));
+
+        // Add UdpConnection instance methods
+        let udp_connection_recv_from_symbol = Symbol {
+            name: "recv_from".to_string(),
+            type_id: TypeId::Function(1014),
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: Some(FunctionInfo {
+                param_types: vec![TypeId::Array(0)], // buffer parameter ([]byte)
+                return_type: Some(datagram_result), // returns Result<(int64, NetAddr)> tuple
+            }),
+            module_exports: None,
+        };
+        global_scope.insert(
+            "UdpConnection.recv_from".to_string(),
+            Rc::new(udp_connection_recv_from_symbol),
+        );
+
+        // Add UdpConnection.send_to method
+        let udp_connection_send_to_symbol = Symbol {
+            name: "send_to".to_string(),
+            type_id: TypeId::Function(1015),
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: Some(FunctionInfo {
+                param_types: vec![TypeId::Array(0), TypeId::Struct(1001)], // buffer ([]byte), NetAddr
+                return_type: Some(byte_count_result), // returns Result<int64> (bytes sent)
+            }),
+            module_exports: None,
+        };
+        global_scope.insert(
+            "UdpConnection.send_to".to_string(),
+            Rc::new(udp_connection_send_to_symbol),
+        );
+    }
+
+    /// Add the std/sync handle types and their methods
+    fn add_std_sync_types(&mut self) {
+        use crate::std::sync::{HANDLE_TYPES, LOCK_GUARD, MUTEX, ONCE, RW_LOCK, WAIT_GROUP};
 
-            // Add TcpServer.bind static method
-            let tcp_server_bind_symbol = Symbol {
-                name: "bind".to_string(),
-                type_id: TypeId::Function(1006),
+        for (index, name) in HANDLE_TYPES.iter().enumerate() {
+            let type_id = TypeId::Struct(1006 + index as u32);
+            self.type_id_to_name.insert(type_id, name.to_string());
+            self.type_name_to_id.insert(name.to_string(), type_id);
+        }
+        let guard = Some(self.type_name_to_id[LOCK_GUARD]);
+
+        // (type, method, parameters, return type)
+        let methods: &[(&str, &str, Vec<TypeId>, Option<TypeId>)] = &[
+            (WAIT_GROUP, "add", vec![TypeId::Int64], None),
+            (WAIT_GROUP, "done", vec![], None),
+            (WAIT_GROUP, "wait", vec![], None),
+            (MUTEX, "acquire", vec![], guard),
+            (MUTEX, "release", vec![], None),
+            (MUTEX, "tryAcquire", vec![], Some(TypeId::Bool)),
+            (MUTEX, "withLock", vec![TypeId::Any], Some(TypeId::Any)),
+            (RW_LOCK, "acquire", vec![], guard),
+            (RW_LOCK, "release", vec![], None),
+            (RW_LOCK, "acquireRead", vec![], guard),
+            (RW_LOCK, "releaseRead", vec![], None),
+            (RW_LOCK, "tryAcquire", vec![], Some(TypeId::Bool)),
+            (RW_LOCK, "withLock", vec![TypeId::Any], Some(TypeId::Any)),
+            (RW_LOCK, "withReadLock", vec![TypeId::Any], Some(TypeId::Any)),
+            (ONCE, "do", vec![TypeId::Any], None),
+            (ONCE, "done", vec![], Some(TypeId::Bool)),
+            (LOCK_GUARD, "release", vec![], None),
+        ];
+
+        let type_ids: Vec<TypeId> = HANDLE_TYPES.iter().map(|name| self.type_name_to_id[*name]).collect();
+        let global_scope = self.scopes.globals_mut();
+        for (name, type_id) in HANDLE_TYPES.iter().zip(type_ids) {
+            let symbol = Symbol {
+                name: name.to_string(),
+                type_id,
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
-                function_info: Some(FunctionInfo {
-                    param_types: vec![TypeId::Struct(1001)], // NetAddr parameter
-                    return_type: Some(TypeId::Result(1003)), // returns Result<TcpServer>
-                }),
+                function_info: None,
                 module_exports: None,
             };
-            global_scope.insert("TcpServer.bind".to_string(), tcp_server_bind_symbol);
-
-            let tcp_connection_symbol = Symbol {
-                name: "TcpConnection".to_string(),
-                type_id: TypeId::Struct(1004),
+            global_scope.insert(name.to_string(), Rc::new(symbol));
+        }
+        for (type_name, method, param_types, return_type) in methods {
+            let symbol = Symbol {
+                name: method.to_string(),
+                type_id: TypeId::Function(0),
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
-                function_info: None,
+                function_info: Some(FunctionInfo {
+                    param_types: param_types.clone(),
+                    return_type: *return_type,
+                }),
                 module_exports: None,
             };
-            global_scope.insert("TcpConnection".to_string(), tcp_connection_symbol);
+            global_scope.insert(format!("{}.{}", type_name, method), Rc::new(symbol));
+        }
+    }
+
+    /// Add the std/context handle type and its methods
+    fn add_std_context_types(&mut self) {
+        use crate::std::context::CONTEXT;
+
+        let context_type = TypeId::Struct(1011);
+        self.type_id_to_name.insert(context_type, CONTEXT.to_string());
+        self.type_name_to_id.insert(CONTEXT.to_string(), context_type);
+        let done_channel = self.type_registry.register_channel_type(ChannelTypeInfo {
+            element_type: TypeId::Any,
+            direction: crate::types::composite::ChannelDirection::ReceiveOnly,
+            buffered: false,
+            capacity: None,
+        });
 
-            self.type_id_to_name
-                .insert(TypeId::Struct(1004), "TcpConnection".to_string());
-            self.type_name_to_id
-                .insert("TcpConnection".to_string(), TypeId::Struct(1004));
+        // (method, parameters, return type)
+        let methods: &[(&str, Vec<TypeId>, Option<TypeId>)] = &[
+            ("cancel", vec![], None),
+            ("isCancelled", vec![], Some(TypeId::Bool)),
+            ("err", vec![], Some(TypeId::String)),
+            ("value", vec![TypeId::String], Some(TypeId::Any)),
+            ("deadline", vec![], Some(TypeId::Int64)),
+            ("done", vec![], Some(TypeId::Channel(done_channel))),
+            ("call", vec![TypeId::Any], Some(TypeId::Any)),
+        ];
 
-            // Add TcpConnection instance methods
-            let tcp_connection_peer_addr_symbol = Symbol {
-                name: "peer_addr".to_string(),
-                type_id: TypeId::Function(1008),
+        let global_scope = self.scopes.globals_mut();
+        let symbol = Symbol {
+            name: CONTEXT.to_string(),
+            type_id: context_type,
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: None,
+            module_exports: None,
+        };
+        global_scope.insert(CONTEXT.to_string(), Rc::new(symbol));
+        for (method, param_types, return_type) in methods {
+            let symbol = Symbol {
+                name: method.to_string(),
+                type_id: TypeId::Function(0),
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
                 function_info: Some(FunctionInfo {
-                    param_types: vec![],                     // no parameters (method on self)
-                    return_type: Some(TypeId::Struct(1001)), // returns NetAddr
+                    param_types: param_types.clone(),
+                    return_type: *return_type,
                 }),
                 module_exports: None,
             };
-            global_scope.insert(
-                "TcpConnection.peer_addr".to_string(),
-                tcp_connection_peer_addr_symbol,
-            );
+            global_scope.insert(format!("{}.{}", CONTEXT, method), Rc::new(symbol));
+        }
+    }
+
+    /// Add the std/checksum Hasher type and its methods
+    fn add_std_checksum_types(&mut self) {
+        use crate::std::checksum::HASHER;
 
-            let tcp_connection_read_symbol = Symbol {
-                name: "read".to_string(),
-                type_id: TypeId::Function(1009),
+        let hasher_type = TypeId::Struct(1015);
+        self.type_id_to_name.insert(hasher_type, HASHER.to_string());
+        self.type_name_to_id.insert(HASHER.to_string(), hasher_type);
+
+        // (method, parameters, return type)
+        let methods: &[(&str, Vec<TypeId>, Option<TypeId>)] = &[
+            ("write", vec![TypeId::Any], None),
+            ("sum", vec![], Some(TypeId::UInt64)),
+            ("reset", vec![], None),
+        ];
+
+        let global_scope = self.scopes.globals_mut();
+        let symbol = Symbol {
+            name: HASHER.to_string(),
+            type_id: hasher_type,
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: None,
+            module_exports: None,
+        };
+        global_scope.insert(HASHER.to_string(), Rc::new(symbol));
+        for (method, param_types, return_type) in methods {
+            let symbol = Symbol {
+                name: method.to_string(),
+                type_id: TypeId::Function(0),
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
                 function_info: Some(FunctionInfo {
-                    param_types: vec![TypeId::Array(0)], // buffer parameter ([]byte)
-                    return_type: Some(TypeId::Result(1012)), // returns Result<int64> (bytes read)
+                    param_types: param_types.clone(),
+                    return_type: *return_type,
                 }),
                 module_exports: None,
             };
-            global_scope.insert("TcpConnection.read".to_string(), tcp_connection_read_symbol);
+            global_scope.insert(format!("{}.{}", HASHER, method), Rc::new(symbol));
+        }
+    }
+
+    /// Add the std/regex Pattern type and its methods
+    fn add_std_regex_types(&mut self) {
+        use crate::std::regex::PATTERN;
+
+        let pattern_type = TypeId::Struct(1027);
+        self.type_id_to_name.insert(pattern_type, PATTERN.to_string());
+        self.type_name_to_id.insert(PATTERN.to_string(), pattern_type);
+
+        let found = self.option_type_id(TypeId::String);
+        let strings = TypeId::Slice(self.type_registry.register_slice_type(TypeId::String));
+        let groups = self.option_type_id(strings);
+        let string_map = TypeId::Map(self.type_registry.register_map_type(TypeId::String, TypeId::String));
+        let named_groups = self.option_type_id(string_map);
+
+        // (method, parameters, return type)
+        let methods: &[(&str, Vec<TypeId>, Option<TypeId>)] = &[
+            ("match", vec![TypeId::String], Some(TypeId::Bool)),
+            ("find", vec![TypeId::String], Some(found)),
+            ("findAll", vec![TypeId::String], Some(strings)),
+            ("replace", vec![TypeId::String, TypeId::String], Some(TypeId::String)),
+            ("captures", vec![TypeId::String], Some(groups)),
+            ("namedCaptures", vec![TypeId::String], Some(named_groups)),
+        ];
 
-            let tcp_connection_write_symbol = Symbol {
-                name: "write".to_string(),
-                type_id: TypeId::Function(1010),
+        let global_scope = self.scopes.globals_mut();
+        let symbol = Symbol {
+            name: PATTERN.to_string(),
+            type_id: pattern_type,
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: None,
+            module_exports: None,
+        };
+        global_scope.insert(PATTERN.to_string(), Rc::new(symbol));
+        for (method, param_types, return_type) in methods {
+            let symbol = Symbol {
+                name: method.to_string(),
+                type_id: TypeId::Function(0),
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
                 function_info: Some(FunctionInfo {
-                    param_types: vec![TypeId::Array(0)], // data parameter ([]byte)
-                    return_type: Some(TypeId::Result(1013)), // returns Result<int64> (bytes written)
+                    param_types: param_types.clone(),
+                    return_type: *return_type,
                 }),
                 module_exports: None,
             };
-            global_scope.insert(
-                "TcpConnection.write".to_string(),
-                tcp_connection_write_symbol,
-            );
+            global_scope.insert(format!("{}.{}", PATTERN, method), Rc::new(symbol));
+        }
+    }
+
+    /// Add the std/db DB, Stmt, Tx and Row types and their methods. SQL
+    /// parameters follow the SQL, so `exec` and `query` take any number of
+    /// arguments; only their results are typed.
+    fn add_std_db_types(&mut self) {
+        use crate::std::db::{DB, ROW, STMT, TX, TYPES};
+
+        for (index, name) in TYPES.iter().enumerate() {
+            let type_id = TypeId::Struct(1028 + index as u32);
+            self.type_id_to_name.insert(type_id, name.to_string());
+            self.type_name_to_id.insert(name.to_string(), type_id);
+        }
+        let [db_type, stmt_type, tx_type, row_type] = [DB, STMT, TX, ROW].map(|name| self.type_name_to_id[name]);
+
+        let changed = self.result_type_id(TypeId::Int64, TypeId::String);
+        let row_slice = TypeId::Slice(self.type_registry.register_slice_type(row_type));
+        let rows = self.result_type_id(row_slice, TypeId::String);
+        let done = self.result_type_id(TypeId::Void, TypeId::String);
+        let prepared = self.result_type_id(stmt_type, TypeId::String);
+        let begun = self.result_type_id(tx_type, TypeId::String);
+        let strings = TypeId::Slice(self.type_registry.register_slice_type(TypeId::String));
+        let bytes = TypeId::Slice(self.type_registry.register_slice_type(TypeId::UInt8));
+
+        // (type, method, parameters, return type)
+        let methods = [
+            (DB, "exec", vec![TypeId::String], Some(changed)),
+            (DB, "query", vec![TypeId::String], Some(rows)),
+            (DB, "prepare", vec![TypeId::String], Some(prepared)),
+            (DB, "begin", vec![], Some(begun)),
+            (DB, "setMaxOpen", vec![TypeId::Int32], None),
+            (DB, "openConnections", vec![], Some(TypeId::Int32)),
+            (DB, "close", vec![], None),
+            (STMT, "exec", vec![], Some(changed)),
+            (STMT, "query", vec![], Some(rows)),
+            (STMT, "close", vec![], None),
+            (TX, "exec", vec![TypeId::String], Some(changed)),
+            (TX, "query", vec![TypeId::String], Some(rows)),
+            (TX, "commit", vec![], Some(done)),
+            (TX, "rollback", vec![], Some(done)),
+            (ROW, "getInt", vec![TypeId::Any], Some(TypeId::Int64)),
+            (ROW, "getFloat", vec![TypeId::Any], Some(TypeId::Float64)),
+            (ROW, "getString", vec![TypeId::Any], Some(TypeId::String)),
+            (ROW, "getBool", vec![TypeId::Any], Some(TypeId::Bool)),
+            (ROW, "getBytes", vec![TypeId::Any], Some(bytes)),
+            (ROW, "isNull", vec![TypeId::Any], Some(TypeId::Bool)),
+            (ROW, "columns", vec![], Some(strings)),
+        ];
 
-            let tcp_connection_close_symbol = Symbol {
-                name: "close".to_string(),
-                type_id: TypeId::Function(1011),
+        let global_scope = self.scopes.globals_mut();
+        for (name, type_id) in [(DB, db_type), (STMT, stmt_type), (TX, tx_type), (ROW, row_type)] {
+            let symbol = Symbol {
+                name: name.to_string(),
+                type_id,
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
-                function_info: Some(FunctionInfo {
-                    param_types: vec![], // no parameters (method on self)
-                    return_type: None,   // returns void
-                }),
+                function_info: None,
                 module_exports: None,
             };
-            global_scope.insert(
-                "TcpConnection.close".to_string(),
-                tcp_connection_close_symbol,
-            );
-
-            // Add TcpConnection.connect static method
-            let tcp_connection_connect_symbol = Symbol {
-                name: "connect".to_string(),
-                type_id: TypeId::Function(1007),
+            global_scope.insert(name.to_string(), Rc::new(symbol));
+        }
+        for (type_name, method, param_types, return_type) in methods {
+            let symbol = Symbol {
+                name: method.to_string(),
+                type_id: TypeId::Function(0),
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
                 function_info: Some(FunctionInfo {
-                    param_types: vec![TypeId::Struct(1001)], // NetAddr parameter
-                    return_type: Some(TypeId::Result(1004)), // returns Result<TcpConnection>
+                    param_types,
+                    return_type,
                 }),
                 module_exports: None,
             };
-            global_scope.insert(
-                "TcpConnection.connect".to_string(),
-                tcp_connection_connect_symbol,
-            );
+            global_scope.insert(format!("{}.{}", type_name, method), Rc::new(symbol));
+        }
+    }
+
+    /// Add the std/strings Builder type and its methods
+    fn add_std_strings_types(&mut self) {
+        use crate::std::strings::BUILDER;
+
+        let builder_type = TypeId::Struct(1016);
+        self.type_id_to_name.insert(builder_type, BUILDER.to_string());
+        self.type_name_to_id.insert(BUILDER.to_string(), builder_type);
+
+        // (method, parameters, return type)
+        let methods: &[(&str, Vec<TypeId>, Option<TypeId>)] = &[
+            ("write", vec![TypeId::Any], None),
+            ("writeLine", vec![TypeId::Any], None),
+            ("len", vec![], Some(TypeId::Int32)),
+            ("byteLen", vec![], Some(TypeId::Int32)),
+            ("toString", vec![], Some(TypeId::String)),
+            ("reset", vec![], None),
+        ];
 
-            let udp_connection_symbol = Symbol {
-                name: "UdpConnection".to_string(),
-                type_id: TypeId::Struct(1005),
+        let global_scope = self.scopes.globals_mut();
+        let symbol = Symbol {
+            name: BUILDER.to_string(),
+            type_id: builder_type,
+            is_mutable: false,
+            position: Position::new(0, 0, 0),
+            function_info: None,
+            module_exports: None,
+        };
+        global_scope.insert(BUILDER.to_string(), Rc::new(symbol));
+        for (method, param_types, return_type) in methods {
+            let symbol = Symbol {
+                name: method.to_string(),
+                type_id: TypeId::Function(0),
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
-                function_info: None,
+                function_info: Some(FunctionInfo {
+                    param_types: param_types.clone(),
+                    return_type: *return_type,
+                }),
                 module_exports: None,
             };
-            global_scope.insert("UdpConnection".to_string(), udp_connection_symbol);
+            global_scope.insert(format!("{}.{}", BUILDER, method), Rc::new(symbol));
+        }
+    }
 
-            self.type_id_to_name
-                .insert(TypeId::Struct(1005), "UdpConnection".to_string());
-            self.type_name_to_id
-                .insert("UdpConnection".to_string(), TypeId::Struct(1005));
+    /// Add the std/fs File handle with its methods and the FileInfo fields
+    fn add_std_fs_types(&mut self) {
+        use crate::std::fs::{FILE, FILE_INFO};
 
-            // Register tuple type (int64, NetAddr) for recv_from return
-            let tuple_id = self
-                .type_registry
-                .register_tuple_type(vec![TypeId::Int64, TypeId::Struct(1001)]);
+        let file_type = TypeId::Struct(1018);
+        let info_type = TypeId::Struct(1017);
+        for (type_id, name) in [(info_type, FILE_INFO), (file_type, FILE)] {
+            self.type_id_to_name.insert(type_id, name.to_string());
+            self.type_name_to_id.insert(name.to_string(), type_id);
+        }
 
-            // Add UdpConnection.bind static method
-            let udp_connection_bind_symbol = Symbol {
-                name: "bind".to_string(),
-                type_id: TypeId::Function(1008),
+        // (method, parameters, return type)
+        let methods = [
+            ("read", vec![], Some(self.result_type_id(TypeId::String, TypeId::String))),
+            ("write", vec![TypeId::Any], Some(self.result_type_id(TypeId::Int64, TypeId::String))),
+            ("close", vec![], None),
+        ];
+        let fields = [
+            ("size", TypeId::Int64),
+            ("modified", TypeId::Int64),
+            ("isDir", TypeId::Bool),
+            ("isFile", TypeId::Bool),
+        ];
+
+        let global_scope = self.scopes.globals_mut();
+        for (name, type_id) in [(FILE_INFO, info_type), (FILE, file_type)] {
+            let symbol = Symbol {
+                name: name.to_string(),
+                type_id,
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
-                function_info: Some(FunctionInfo {
-                    param_types: vec![TypeId::Struct(1001)], // NetAddr parameter
-                    return_type: Some(TypeId::Result(1005)), // returns Result<UdpConnection>
-                }),
+                function_info: None,
                 module_exports: None,
             };
-            global_scope.insert("UdpConnection.bind".to_string(), udp_connection_bind_symbol);
-
-            // Add UdpConnection instance methods
-            let udp_connection_recv_from_symbol = Symbol {
-                name: "recv_from".to_string(),
-                type_id: TypeId::Function(1014),
+            global_scope.insert(name.to_string(), Rc::new(symbol));
+        }
+        for (method, param_types, return_type) in methods {
+            let symbol = Symbol {
+                name: method.to_string(),
+                type_id: TypeId::Function(0),
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
                 function_info: Some(FunctionInfo {
-                    param_types: vec![TypeId::Array(0)], // buffer parameter ([]byte)
-                    return_type: Some(TypeId::Result(tuple_id)), // returns Result<(int64, NetAddr)> tuple
+                    param_types,
+                    return_type,
                 }),
                 module_exports: None,
             };
-            global_scope.insert(
-                "UdpConnection.recv_from".to_string(),
-                udp_connection_recv_from_symbol,
-            );
-
-            // Add UdpConnection.send_to method
-            let udp_connection_send_to_symbol = Symbol {
-                name: "send_to".to_string(),
-                type_id: TypeId::Function(1015),
+            global_scope.insert(format!("{}.{}", FILE, method), Rc::new(symbol));
+        }
+        // Fields are symbols without function info
+        for (field, type_id) in fields {
+            let symbol = Symbol {
+                name: field.to_string(),
+                type_id,
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
-                function_info: Some(FunctionInfo {
-                    param_types: vec![TypeId::Array(0), TypeId::Struct(1001)], // buffer ([]byte), NetAddr
-                    return_type: Some(TypeId::Result(1013)), // returns Result<int64> (bytes sent)
-                }),
+                function_info: None,
                 module_exports: None,
             };
-            global_scope.insert(
-                "UdpConnection.send_to".to_string(),
-                udp_connection_send_to_symbol,
-            );
+            global_scope.insert(format!("{}.{}", FILE_INFO, field), Rc::new(symbol));
         }
     }
 
-    /// Add std/time types and their methods
-    fn add_std_time_types(&mut self) {
-        if let Some(global_scope) = self.scopes.first_mut() {
-            // sleep function is already handled as a regular function import
-            // We could add Duration type here if needed
+    /// Add the std/process Command and Process handles with their methods and
+    /// the Output fields
+    fn add_std_process_types(&mut self) {
+        use crate::std::process::{COMMAND, OUTPUT, PROCESS};
+
+        let command_type = TypeId::Struct(1019);
+        let process_type = TypeId::Struct(1020);
+        let output_type = TypeId::Struct(1021);
+        let types = [(COMMAND, command_type), (PROCESS, process_type), (OUTPUT, output_type)];
+        for (name, type_id) in types {
+            self.type_id_to_name.insert(type_id, name.to_string());
+            self.type_name_to_id.insert(name.to_string(), type_id);
         }
-    }
 
-    /// Add Result type methods (isError, error, etc.)
-    fn add_result_type_methods(&mut self) {
-        if let Some(global_scope) = self.scopes.first_mut() {
-            // Add isError method for Result types
-            let is_error_symbol = Symbol {
-                name: "isError".to_string(),
-                type_id: TypeId::Function(2000),
+        let output_result = self.result_type_id(output_type, TypeId::String);
+        let process_result = self.result_type_id(process_type, TypeId::String);
+        let byte_count_result = self.result_type_id(TypeId::Int64, TypeId::String);
+        let string_result = self.result_type_id(TypeId::String, TypeId::String);
+        let exit_code_result = self.result_type_id(TypeId::Int32, TypeId::String);
+        let unit_result = self.result_type_id(TypeId::Void, TypeId::String);
+        let line = self.option_type_id(TypeId::String);
+
+        // (type, method, parameters, return type)
+        let methods = [
+            (COMMAND, "env", vec![TypeId::String, TypeId::String], None),
+            (COMMAND, "clearEnv", vec![], None),
+            (COMMAND, "dir", vec![TypeId::String], None),
+            (COMMAND, "timeout", vec![TypeId::Int64], None),
+            (COMMAND, "exec", vec![], Some(output_result)),
+            (COMMAND, "spawn", vec![], Some(process_result)),
+            (PROCESS, "pid", vec![], Some(TypeId::Int64)),
+            (PROCESS, "write", vec![TypeId::Any], Some(byte_count_result)),
+            (PROCESS, "closeStdin", vec![], None),
+            (PROCESS, "readLine", vec![], Some(line)),
+            (PROCESS, "readAll", vec![], Some(string_result)),
+            (PROCESS, "stderr", vec![], Some(TypeId::String)),
+            (PROCESS, "wait", vec![], Some(exit_code_result)),
+            (PROCESS, "kill", vec![], Some(unit_result)),
+        ];
+        let fields = [
+            ("code", TypeId::Int32),
+            ("success", TypeId::Bool),
+            ("stdout", TypeId::String),
+            ("stderr", TypeId::String),
+        ];
+
+        let global_scope = self.scopes.globals_mut();
+        for (name, type_id) in types {
+            let symbol = Symbol {
+                name: name.to_string(),
+                type_id,
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
-                function_info: Some(FunctionInfo {
-                    param_types: vec![],             // no parameters (method on self)
-                    return_type: Some(TypeId::Bool), // returns bool
-                }),
+                function_info: None,
                 module_exports: None,
             };
-            global_scope.insert("Result.isError".to_string(), is_error_symbol);
-
-            // Add error method for Result types
-            let error_symbol = Symbol {
-                name: "error".to_string(),
-                type_id: TypeId::Function(2001),
+            global_scope.insert(name.to_string(), Rc::new(symbol));
+        }
+        for (type_name, method, param_types, return_type) in methods {
+            let symbol = Symbol {
+                name: method.to_string(),
+                type_id: TypeId::Function(0),
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
                 function_info: Some(FunctionInfo {
-                    param_types: vec![],               // no parameters (method on self)
-                    return_type: Some(TypeId::String), // returns error string
+                    param_types,
+                    return_type,
                 }),
                 module_exports: None,
             };
-            global_scope.insert("Result.error".to_string(), error_symbol);
-
-            // Add unwrap method for Result types
-            let unwrap_symbol = Symbol {
-                name: "unwrap".to_string(),
-                type_id: TypeId::Function(2002),
+            global_scope.insert(format!("{}.{}", type_name, method), Rc::new(symbol));
+        }
+        // Fields are symbols without function info
+        for (field, type_id) in fields {
+            let symbol = Symbol {
+                name: field.to_string(),
+                type_id,
                 is_mutable: false,
                 position: Position::new(0, 0, 0),
-                function_info: Some(FunctionInfo {
-                    param_types: vec![], // no parameters (method on self)
-                    return_type: None,   // return type depends on the Result<T> - will be T
-                }),
+                function_info: None,
                 module_exports: None,
             };
-            global_scope.insert("Result.unwrap".to_string(), unwrap_symbol);
+            global_scope.insert(format!("{}.{}", OUTPUT, field), Rc::new(symbol));
         }
     }
 
+    /// Add the std/http Server and WebSocket handles with their methods, the
+    /// Message type WebSockets receive, and the Request and
+    /// Response values handlers take and return
+    fn add_std_http_types(&mut self) {
+        use crate::std::http::{REQUEST, RESPONSE, SERVER};
+        use crate::std::websocket::{MESSAGE, WEBSOCKET};
+
+        let server_type = TypeId::Struct(1022);
+        let request_type = TypeId::Struct(1023);
+        let response_type = TypeId::Struct(1024);
+        let websocket_type = TypeId::Struct(1025);
+        let message_type = TypeId::Struct(1026);
+        let types = [
+            (SERVER, server_type),
+            (REQUEST, request_type),
+            (RESPONSE, response_type),
+            (WEBSOCKET, websocket_type),
+            (MESSAGE, message_type),
+        ];
+        for (name, type_id) in types {
+            self.type_id_to_name.insert(type_id, name.to_string());
+            self.type_name_to_id.insert(name.to_string(), type_id);
+        }
+
+        let addr_result = self.result_type_id(TypeId::String, TypeId::String);
+        let unit_result = self.result_type_id(TypeId::Void, TypeId::String);
+        let header = self.option_type_id(TypeId::String);
+        let string_map = TypeId::Map(self.type_registry.register_map_type(TypeId::String, TypeId::String));
+        let byte_slice = TypeId::Slice(self.type_registry.register_slice_type(TypeId::UInt8));
+        let messages = TypeId::Channel(self.type_registry.register_channel_type(ChannelTypeInfo {
+            element_type: message_type,
+            direction: crate::types::composite::ChannelDirection::ReceiveOnly,
+            buffered: true,
+            capacity: None,
+        }));
+        let outgoing = TypeId::Channel(self.type_registry.register_channel_type(ChannelTypeInfo {
+            element_type: TypeId::Any,
+            direction: crate::types::composite::ChannelDirection::SendOnly,
+            buffered: true,
+            capacity: None,
+        }));
+
+        // (type, method, parameters, return type); handlers are functions
+        // taking a Request and returning a Response or a string, or for
+        // WebSocket routes taking a WebSocket and a Request
+        let route = vec![TypeId::String, TypeId::Any];
+        let methods = [
+            (SERVER, "get", route.clone(), None),
+            (SERVER, "post", route.clone(), None),
+            (SERVER, "put", route.clone(), None),
+            (SERVER, "delete", route.clone(), None),
+            (SERVER, "patch", route, None),
+            (SERVER, "handle", vec![TypeId::String, TypeId::String, TypeId::Any], None),
+            (SERVER, "websocket", vec![TypeId::String, TypeId::Any], None),
+            (SERVER, "bind", vec![TypeId::String], Some(addr_result)),
+            (SERVER, "serve", vec![], Some(unit_result)),
+            (SERVER, "listen", vec![TypeId::String], Some(unit_result)),
+            (SERVER, "shutdown", vec![], None),
+            (REQUEST, "header", vec![TypeId::String], Some(header)),
+            (REQUEST, "form", vec![], Some(string_map)),
+            (RESPONSE, "withHeader", vec![TypeId::String, TypeId::String], Some(response_type)),
+            // Strings are sent as text messages, bytes as binary ones
+            (WEBSOCKET, "send", vec![TypeId::Any], Some(unit_result)),
+            (WEBSOCKET, "messages", vec![], Some(messages)),
+            (WEBSOCKET, "outgoing", vec![], Some(outgoing)),
+            (WEBSOCKET, "close", vec![], None),
+            (WEBSOCKET, "setKeepalive", vec![TypeId::Int32], None),
+            (WEBSOCKET, "isOpen", vec![], Some(TypeId::Bool)),
+        ];
+        let fields = [
+            (REQUEST, "method", TypeId::String),
+            (REQUEST, "path", TypeId::String),
+            (REQUEST, "query", string_map),
+            (REQUEST, "params", string_map),
+            (REQUEST, "headers", string_map),
+            (REQUEST, "body", TypeId::String),
+            (RESPONSE, "status", TypeId::Int32),
+            (RESPONSE, "headers", string_map),
+            (RESPONSE, "body", TypeId::String),
+            (MESSAGE, "kind", TypeId::String),
+            (MESSAGE, "text", TypeId::String),
+            (MESSAGE, "data", byte_slice),
+        ];
+
+        let global_scope = self.scopes.globals_mut();
+        for (name, type_id) in types {
+            let symbol = Symbol {
+                name: name.to_string(),
+                type_id,
+                is_mutable: false,
+                position: Position::new(0, 0, 0),
+                function_info: None,
+                module_exports: None,
+            };
+            global_scope.insert(name.to_string(), Rc::new(symbol));
+        }
+        for (type_name, method, param_types, return_type) in methods {
+            let symbol = Symbol {
+                name: method.to_string(),
+                type_id: TypeId::Function(0),
+                is_mutable: false,
+                position: Position::new(0, 0, 0),
+                function_info: Some(FunctionInfo {
+                    param_types,
+                    return_type,
+                }),
+                module_exports: None,
+            };
+            global_scope.insert(format!("{}.{}", type_name, method), Rc::new(symbol));
+        }
+        // Fields are symbols without function info
+        for (type_name, field, type_id) in fields {
+            let symbol = Symbol {
+                name: field.to_string(),
+                type_id,
+                is_mutable: false,
+                position: Position::new(0, 0, 0),
+                function_info: None,
+                module_exports: None,
+            };
+            global_scope.insert(format!("{}.{}", type_name, field), Rc::new(symbol));
+        }
+    }
+
+    /// Add the std/collections handle types; their methods are checked by
+    /// `check_collection_method_call`
+    fn add_std_collections_types(&mut self) {
+        use crate::std::collections::HANDLE_TYPES;
+
+        // The linked collections came after the IDs following the first three were taken
+        const HANDLE_TYPE_IDS: [u32; 5] = [1012, 1013, 1014, 1038, 1039];
+        for (name, id) in HANDLE_TYPES.iter().zip(HANDLE_TYPE_IDS) {
+            let type_id = TypeId::Struct(id);
+            self.type_id_to_name.insert(type_id, name.to_string());
+            self.type_name_to_id.insert(name.to_string(), type_id);
+            let symbol = Symbol {
+                name: name.to_string(),
+                type_id,
+                is_mutable: false,
+                position: Position::new(0, 0, 0),
+                function_info: None,
+                module_exports: None,
+            };
+            self.scopes.globals_mut().insert(name.to_string(), Rc::new(symbol));
+        }
+    }
+
+    /// Add the std/time Duration, Instant, DateTime and Ticker types and their methods
+    fn add_std_time_types(&mut self) {
+        use crate::std::time::{DATE_TIME, DURATION, INSTANT, TICKER, TYPES};
+
+        for (index, name) in TYPES.iter().enumerate() {
+            let type_id = TypeId::Struct(1032 + index as u32);
+            self.type_id_to_name.insert(type_id, name.to_string());
+            self.type_name_to_id.insert(name.to_string(), type_id);
+        }
+        let [duration, instant, date_time] = [DURATION, INSTANT, DATE_TIME].map(|name| self.type_name_to_id[name]);
+        let ticks = self.time_channel_type();
+        let moved = self.result_type_id(date_time, TypeId::String);
+
+        // (type, method, parameters, return type)
+        let mut methods = vec![
+            (DURATION, "nanoseconds", vec![], Some(TypeId::Int64)),
+            (DURATION, "microseconds", vec![], Some(TypeId::Int64)),
+            (DURATION, "milliseconds", vec![], Some(TypeId::Int64)),
+            (DURATION, "seconds", vec![], Some(TypeId::Float64)),
+            (DURATION, "minutes", vec![], Some(TypeId::Float64)),
+            (DURATION, "hours", vec![], Some(TypeId::Float64)),
+            (DURATION, "add", vec![duration], Some(duration)),
+            (DURATION, "sub", vec![duration], Some(duration)),
+            (DURATION, "mul", vec![TypeId::Int64], Some(duration)),
+            (DURATION, "div", vec![TypeId::Int64], Some(duration)),
+            (DURATION, "compare", vec![duration], Some(TypeId::Int32)),
+            (DURATION, "toString", vec![], Some(TypeId::String)),
+            (INSTANT, "elapsed", vec![], Some(duration)),
+            (INSTANT, "since", vec![instant], Some(duration)),
+            (INSTANT, "add", vec![duration], Some(instant)),
+            (INSTANT, "sub", vec![duration], Some(instant)),
+            (INSTANT, "compare", vec![instant], Some(TypeId::Int32)),
+            (DATE_TIME, "offset", vec![], Some(TypeId::Int32)),
+            (DATE_TIME, "withOffset", vec![TypeId::Int32], Some(moved)),
+            (DATE_TIME, "toUtc", vec![], Some(date_time)),
+            (DATE_TIME, "toLocal", vec![], Some(date_time)),
+            (DATE_TIME, "unix", vec![], Some(TypeId::Int64)),
+            (DATE_TIME, "unixMillis", vec![], Some(TypeId::Int64)),
+            (DATE_TIME, "add", vec![duration], Some(date_time)),
+            (DATE_TIME, "sub", vec![date_time], Some(duration)),
+            (DATE_TIME, "compare", vec![date_time], Some(TypeId::Int32)),
+            (DATE_TIME, "format", vec![TypeId::String], Some(TypeId::String)),
+            (DATE_TIME, "toString", vec![], Some(TypeId::String)),
+            (TICKER, "channel", vec![], Some(ticks)),
+            (TICKER, "stop", vec![], None),
+        ];
+        for field in ["year", "month", "day", "hour", "minute", "second", "nanosecond", "weekday", "yearDay"] {
+            methods.push((DATE_TIME, field, vec![], Some(TypeId::Int32)));
+        }
+
+        let global_scope = self.scopes.globals_mut();
+        for (index, name) in TYPES.iter().enumerate() {
+            let symbol = Symbol {
+                name: name.to_string(),
+                type_id: TypeId::Struct(1032 + index as u32),
+                is_mutable: false,
+                position: Position::new(0, 0, 0),
+                function_info: None,
+                module_exports: None,
+            };
+            global_scope.insert(name.to_string(), Rc::new(symbol));
+        }
+        for (type_name, method, param_types, return_type) in methods {
+            let symbol = Symbol {
+                name: method.to_string(),
+                type_id: TypeId::Function(0),
+                is_mutable: false,
+                position: Position::new(0, 0, 0),
+                function_info: Some(FunctionInfo {
+                    param_types,
+                    return_type,
+                }),
+                module_exports: None,
+            };
+            global_scope.insert(format!("{}.{}", type_name, method), Rc::new(symbol));
+        }
+    }
+
+    /// Add the std/log Logger and LogSink types and their methods
+    fn add_std_log_types(&mut self) {
+        use crate::std::log::{LOGGER, SINK, TYPES};
+
+        for (index, name) in TYPES.iter().enumerate() {
+            let type_id = TypeId::Struct(1036 + index as u32);
+            self.type_id_to_name.insert(type_id, name.to_string());
+            self.type_name_to_id.insert(name.to_string(), type_id);
+        }
+        let [logger, sink] = [LOGGER, SINK].map(|name| self.type_name_to_id[name]);
+        let fields = TypeId::Map(self.type_registry.register_map_type(TypeId::String, TypeId::Any));
+
+        // (type, method, parameters, return type)
+        let mut methods = vec![
+            (LOGGER, "with", vec![fields], Some(logger)),
+            (LOGGER, "name", vec![], Some(TypeId::String)),
+            (SINK, "json", vec![], Some(sink)),
+            (SINK, "level", vec![TypeId::String], Some(sink)),
+        ];
+        for level in ["debug", "info", "warn", "error"] {
+            methods.push((LOGGER, level, vec![TypeId::String, fields], None));
+        }
+
+        let global_scope = self.scopes.globals_mut();
+        for (index, name) in TYPES.iter().enumerate() {
+            let symbol = Symbol {
+                name: name.to_string(),
+                type_id: TypeId::Struct(1036 + index as u32),
+                is_mutable: false,
+                position: Position::new(0, 0, 0),
+                function_info: None,
+                module_exports: None,
+            };
+            global_scope.insert(name.to_string(), Rc::new(symbol));
+        }
+        for (type_name, method, param_types, return_type) in methods {
+            let symbol = Symbol {
+                name: method.to_string(),
+                type_id: TypeId::Function(0),
+                is_mutable: false,
+                position: Position::new(0, 0, 0),
+                function_info: Some(FunctionInfo {
+                    param_types,
+                    return_type,
+                }),
+                module_exports: None,
+            };
+            global_scope.insert(format!("{}.{}", type_name, method), Rc::new(symbol));
+        }
+    }
+
+    /// Add the std/schedule Schedule and Every types and their methods
+    fn add_std_schedule_types(&mut self) {
+        use crate::std::schedule::{EVERY, SCHEDULE, TYPES, UNITS};
+
+        // Ticks are DateTimes, with their methods
+        self.add_std_time_types();
+        for (index, name) in TYPES.iter().enumerate() {
+            let type_id = TypeId::Struct(1040 + index as u32);
+            self.type_id_to_name.insert(type_id, name.to_string());
+            self.type_name_to_id.insert(name.to_string(), type_id);
+        }
+        let schedule = self.type_name_to_id[SCHEDULE];
+        let ticks = self.time_channel_type();
+
+        // (type, method, parameters, return type)
+        let mut methods = vec![
+            (SCHEDULE, "channel", vec![], Some(ticks)),
+            (SCHEDULE, "run", vec![TypeId::Any, TypeId::String], None),
+            (SCHEDULE, "stop", vec![], None),
+            (SCHEDULE, "toString", vec![], Some(TypeId::String)),
+        ];
+        for (unit, _) in UNITS {
+            methods.push((EVERY, unit, vec![], Some(schedule)));
+        }
+
+        let global_scope = self.scopes.globals_mut();
+        for (index, name) in TYPES.iter().enumerate() {
+            let symbol = Symbol {
+                name: name.to_string(),
+                type_id: TypeId::Struct(1040 + index as u32),
+                is_mutable: false,
+                position: Position::new(0, 0, 0),
+                function_info: None,
+                module_exports: None,
+            };
+            global_scope.insert(name.to_string(), Rc::new(symbol));
+        }
+        for (type_name, method, param_types, return_type) in methods {
+            let symbol = Symbol {
+                name: method.to_string(),
+                type_id: TypeId::Function(0),
+                is_mutable: false,
+                position: Position::new(0, 0, 0),
+                function_info: Some(FunctionInfo {
+                    param_types,
+                    return_type,
+                }),
+                module_exports: None,
+            };
+            global_scope.insert(format!("{}.{}", type_name, method), Rc::new(symbol));
+        }
+    }
+
+    /// The type of the channels `after` and tickers send DateTimes on
+    fn time_channel_type(&mut self) -> TypeId {
+        TypeId::Channel(self.type_registry.register_channel_type(ChannelTypeInfo {
+            element_type: TypeId::Struct(1034),
+            direction: crate::types::composite::ChannelDirection::ReceiveOnly,
+            buffered: true,
+            capacity: Some(1),
+        }))
+    }
+
     /// Type check a complete program (alias for check_program)
     pub fn check(&mut self, program: &Program) -> Result<()> {
+        self.check_file_attributes(program)?;
+        if program.has_attribute(crate::std::builtin::NO_PRELUDE) {
+            self.prelude = false;
+        }
+        if !self.prelude {
+            // Only builtins imported from std/builtin stay in scope
+            let imports = &self.builtin_imports;
+            self.scopes.globals_mut().retain(|name, _| {
+                !crate::std::builtin::is_prelude_name(name) || imports.contains(name)
+            });
+        }
         self.check_program(program)
     }
 
+    /// Check the attributes of the file; `@no_prelude` is the only one known
+    fn check_file_attributes(&self, program: &Program) -> Result<()> {
+        for attribute in &program.attributes {
+            let message = if attribute.name != crate::std::builtin::NO_PRELUDE {
+                format!("Unknown file attribute '@{}'", attribute.name)
+            } else if !attribute.args.is_empty() {
+                format!("Attribute '@{}' takes no arguments", attribute.name)
+            } else {
+                continue;
+            };
+            return Err(BuluError::TypeError {
+                stack: Vec::new(),
+                file: None,
+                message,
+                line: attribute.position.line,
+                column: attribute.position.column,
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether the builtin `name` is in scope, from the prelude or an import
+    fn builtin_in_scope(&self, name: &str) -> bool {
+        self.prelude || self.builtin_imports.contains(name)
+    }
+
+    /// The error for a name that is not defined, e.g. "Undefined function 'f'"
+    fn undefined_message(&self, kind: &str, name: &str) -> String {
+        if crate::std::builtin::is_prelude_name(name) && !self.builtin_in_scope(name) {
+            crate::std::builtin::not_imported_message(name)
+        } else {
+            format!("Undefined {} '{}'", kind, name)
+        }
+    }
+
+    /// Variables captured by each lambda of the last checked program, and whether it escapes
+    pub fn closure_analysis(&self) -> &ClosureAnalysis {
+        &self.closures
+    }
+
     /// Convert AST type to TypeId using the type registry
     fn ast_type_to_type_id(&mut self, ast_type: &Type) -> TypeId {
         match ast_type {
@@ -661,6 +1573,11 @@ impl TypeChecker {
                 let map_id = self.type_registry.register_map_type(key_type, value_type);
                 TypeId::Map(map_id)
             }
+            Type::Set(set_type) => {
+                let element_type = self.ast_type_to_type_id(&set_type.element_type);
+                let set_id = self.type_registry.register_set_type(element_type);
+                TypeId::Set(set_id)
+            }
             Type::Promise(promise_type) => {
                 let result_type = self.ast_type_to_type_id(&promise_type.result_type);
                 let promise_id = self.type_registry.register_promise_type(result_type);
@@ -683,12 +1600,38 @@ impl TypeChecker {
                 TypeId::Channel(channel_id)
             }
             Type::Function(_) => TypeId::Function(0), // Placeholder
+            Type::Struct(struct_type) => self.struct_type_to_type_id(struct_type),
+            Type::Union(union_type) => {
+                let members = union_type
+                    .types
+                    .iter()
+                    .map(|member| self.ast_type_to_type_id(member))
+                    .collect();
+                self.union_type_id(members)
+            }
+            Type::Null => TypeId::Null,
+            Type::Generic(generic_type) => self
+                .lookup_type_param(&generic_type.name)
+                .unwrap_or(TypeId::Any),
             Type::Named(name) => {
+                // Type parameters resolve to their current binding
+                if let Some(bound_type) = self.lookup_type_param(name) {
+                    return bound_type;
+                }
+                if let Some(target_type) = self.type_aliases.remove(name) {
+                    // Removed while converting so that a cyclic alias resolves to unknown
+                    let type_id = self.ast_type_to_type_id(&target_type);
+                    self.type_aliases.insert(name.clone(), target_type);
+                    return type_id;
+                }
                 // Check if it's an interface or struct and create/get proper TypeId
                 if self.interfaces.contains_key(name) {
                     self.get_or_create_named_type_id(name, true)
                 } else if self.structs.contains_key(name) {
                     self.get_or_create_named_type_id(name, false)
+                } else if let Some(&std_type) = self.type_name_to_id.get(name) {
+                    // Standard library types such as TcpServer
+                    std_type
                 } else {
                     TypeId::Unknown
                 }
@@ -699,11 +1642,30 @@ impl TypeChecker {
 
     /// Type check a complete program
     pub fn check_program(&mut self, program: &Program) -> Result<()> {
+        self.closures = analyze_closures(program);
+
+        // Non-generic struct and interface names are known before any signature mentions them
+        for statement in &program.statements {
+            match statement {
+                Statement::StructDecl(decl) if decl.type_params.is_empty() => {
+                    self.structs.insert(decl.name.clone(), Rc::new(decl.clone()));
+                }
+                Statement::InterfaceDecl(decl) if decl.type_params.is_empty() => {
+                    self.interfaces.insert(decl.name.clone(), Rc::new(decl.clone()));
+                }
+                _ => {}
+            }
+        }
+
         // First pass: collect all function declarations
         self.collecting_functions = true;
         for statement in &program.statements {
-            if let Statement::FunctionDecl(decl) = statement {
-                self.collect_function_declaration(decl)?;
+            match statement {
+                Statement::FunctionDecl(decl) => self.collect_function_declaration(decl)?,
+                Statement::TypeAlias(decl) if decl.type_params.is_empty() => {
+                    self.type_aliases.insert(decl.name.clone(), decl.target_type.clone());
+                }
+                _ => {}
             }
         }
 
@@ -738,6 +1700,7 @@ impl TypeChecker {
             Statement::Break(_) | Statement::Continue(_) => Ok(TypeId::Any), // No type for control flow
             Statement::Expression(stmt) => self.check_expression(&stmt.expr),
             Statement::Block(stmt) => self.check_block_statement(stmt),
+            Statement::Match(stmt) => self.check_match_statement(stmt),
             _ => {
                 // For now, return Any for unimplemented statement types
                 Ok(TypeId::Any)
@@ -766,7 +1729,7 @@ impl TypeChecker {
                 let explicit_type = self.ast_type_to_type_id(type_ann);
 
                 // Check compatibility with special cases
-                let is_compatible = if PrimitiveType::is_assignable(inferred, explicit_type) {
+                let is_compatible = if self.is_type_compatible(inferred, explicit_type) {
                     // Standard assignability check passes
                     true
                 } else if let Some(ref initializer) = decl.initializer {
@@ -806,8 +1769,8 @@ impl TypeChecker {
                         file: None,
                         message: format!(
                             "Cannot assign {} to variable of type {}",
-                            PrimitiveType::type_name(inferred),
-                            PrimitiveType::type_name(explicit_type)
+                            self.type_name_for_error(inferred),
+                            self.type_name_for_error(explicit_type)
                         ),
                         line: decl.position.line,
                         column: decl.position.column,
@@ -865,13 +1828,13 @@ impl TypeChecker {
                 // Both explicit type and initializer - check compatibility
                 (Some(ref type_ann), Some(inferred)) => {
                     let explicit_type = self.ast_type_to_type_id(type_ann);
-                    if !PrimitiveType::is_assignable(inferred, explicit_type) {
+                    if !self.is_type_compatible(inferred, explicit_type) {
                         return Err(BuluError::TypeError { stack: Vec::new(),
                             file: None,
                             message: format!(
                                 "Cannot assign {} to variable of type {}",
-                                PrimitiveType::type_name(inferred),
-                                PrimitiveType::type_name(explicit_type)
+                                self.type_name_for_error(inferred),
+                                self.type_name_for_error(explicit_type)
                             ),
                             line: decl.position.line,
                             column: decl.position.column,
@@ -961,6 +1924,18 @@ impl TypeChecker {
 
     /// Collect function declaration signature (first pass)
     fn collect_function_declaration(&mut self, decl: &FunctionDecl) -> Result<()> {
+        // Generic functions are instantiated per call; the collected signature keeps them opaque
+        if !decl.type_params.is_empty() {
+            self.generics.register_function(GenericFunction {
+                name: decl.name.clone(),
+                type_parameters: Self::generic_type_params(&decl.type_params),
+                where_clause: None,
+            });
+            self.generic_functions.insert(decl.name.clone(), Rc::new(decl.clone()));
+        }
+        let bindings = self.opaque_type_param_bindings(&decl.type_params);
+        self.type_param_bindings.push(bindings);
+
         // Collect parameter types
         let param_types: Vec<TypeId> = decl
             .params
@@ -990,6 +1965,7 @@ impl TypeChecker {
         } else {
             declared_return_type
         };
+        self.type_param_bindings.pop();
 
         // Add function to current scope (for forward references)
         let func_symbol = Symbol {
@@ -1015,6 +1991,10 @@ impl TypeChecker {
             return Ok(TypeId::Void);
         }
 
+        // Inside a generic body the type parameters are opaque
+        let bindings = self.opaque_type_param_bindings(&decl.type_params);
+        self.type_param_bindings.push(bindings);
+
         // Collect parameter types
         let param_types: Vec<TypeId> = decl
             .params
@@ -1082,6 +2062,7 @@ impl TypeChecker {
         self.return_types.pop();
         self.current_function = None;
         self.exit_scope();
+        self.type_param_bindings.pop();
 
         Ok(TypeId::Function(0)) // Placeholder function type
     }
@@ -1092,7 +2073,7 @@ impl TypeChecker {
         let interface_type_id = self.get_or_create_named_type_id(&decl.name, true);
 
         // Store the interface declaration
-        self.interfaces.insert(decl.name.clone(), decl.clone());
+        self.interfaces.insert(decl.name.clone(), Rc::new(decl.clone()));
 
         // Register the interface name in the symbol table
         let interface_symbol = Symbol {
@@ -1105,16 +2086,62 @@ impl TypeChecker {
         };
 
         self.add_symbol(interface_symbol)?;
+
+        // Default bodies are checked with `this` typed as the interface, so they can only
+        // rely on the interface's own methods
+        for method in &decl.methods {
+            if let Some(default_method) = method.default_method() {
+                self.check_interface_default_method(&default_method, interface_type_id)?;
+            }
+        }
+
         Ok(interface_type_id)
     }
 
+    /// Type check the default implementation of an interface method
+    fn check_interface_default_method(&mut self, decl: &FunctionDecl, interface_type_id: TypeId) -> Result<()> {
+        self.enter_scope();
+        self.current_function = Some(decl.name.clone());
+        let return_type = decl.return_type.as_ref().map(|t| self.ast_type_to_type_id(t));
+        self.return_types.push(return_type);
+
+        let this_symbol = Symbol {
+            name: "this".to_string(),
+            type_id: interface_type_id,
+            is_mutable: true,
+            position: decl.position,
+            function_info: None,
+            module_exports: None,
+        };
+        self.add_symbol(this_symbol)?;
+        for param in &decl.params {
+            let param_type = self.ast_type_to_type_id(&param.param_type);
+            let symbol = Symbol {
+                name: param.name.clone(),
+                type_id: param_type,
+                is_mutable: true,
+                position: param.position,
+                function_info: None,
+                module_exports: None,
+            };
+            self.add_symbol(symbol)?;
+        }
+
+        self.check_block_statement(&decl.body)?;
+
+        self.return_types.pop();
+        self.current_function = None;
+        self.exit_scope();
+        Ok(())
+    }
+
     /// Type check a struct declaration
     fn check_struct_declaration(&mut self, decl: &StructDecl) -> Result<TypeId> {
         // Create a unique TypeId for this struct
         let struct_type_id = self.get_or_create_named_type_id(&decl.name, false);
 
         // Store the struct declaration
-        self.structs.insert(decl.name.clone(), decl.clone());
+        self.structs.insert(decl.name.clone(), Rc::new(decl.clone()));
 
         // Register the struct name in the symbol table
         let struct_symbol = Symbol {
@@ -1127,21 +2154,89 @@ impl TypeChecker {
         };
 
         self.add_symbol(struct_symbol)?;
+        self.check_field_attributes(decl)?;
+
+        if !decl.type_params.is_empty() {
+            self.generics.register_struct(GenericStruct {
+                name: decl.name.clone(),
+                type_parameters: Self::generic_type_params(&decl.type_params),
+                where_clause: None,
+            });
+        }
 
-        // Type check all methods in the struct
+        // Type check all methods in the struct, with the struct's type parameters opaque
+        let bindings = self.opaque_type_param_bindings(&decl.type_params);
+        self.type_param_bindings.push(bindings);
         for method in &decl.methods {
             self.check_struct_method_declaration(method, &decl.name)?;
         }
+        self.type_param_bindings.pop();
 
         Ok(struct_type_id)
     }
 
+    /// Check the attributes on struct fields; `@json("key")` is the only one
+    /// known, and no two fields may share a JSON key
+    fn check_field_attributes(&self, decl: &StructDecl) -> Result<()> {
+        let error = |message: String, position: Position| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: position.line,
+            column: position.column,
+        };
+
+        let mut keys: HashMap<&str, &str> = HashMap::new();
+        for field in &decl.fields {
+            let mut renamed = false;
+            for attribute in &field.attributes {
+                if attribute.name != "json" {
+                    return Err(error(
+                        format!("Unknown attribute '@{}' on field '{}'", attribute.name, field.name),
+                        attribute.position,
+                    ));
+                }
+                if renamed {
+                    return Err(error(
+                        format!("Field '{}' has more than one '@json' attribute", field.name),
+                        attribute.position,
+                    ));
+                }
+                renamed = true;
+                match attribute.args.as_slice() {
+                    [Expression::Literal(LiteralExpr {
+                        value: LiteralValue::String(key),
+                        ..
+                    })] if !key.is_empty() => {}
+                    _ => {
+                        return Err(error(
+                            "Attribute '@json' expects one non-empty string literal".to_string(),
+                            attribute.position,
+                        ))
+                    }
+                }
+            }
+
+            let key = crate::std::json::field_key(field);
+            if let Some(other) = keys.insert(key, &field.name) {
+                return Err(error(
+                    format!("Fields '{}' and '{}' both use the JSON key '{}'", other, field.name, key),
+                    field.position,
+                ));
+            }
+        }
+        Ok(())
+    }
+
     /// Type check a method declaration within a struct context
     fn check_struct_method_declaration(
         &mut self,
         decl: &FunctionDecl,
         struct_name: &str,
     ) -> Result<TypeId> {
+        let bindings = self.opaque_type_param_bindings(&decl.type_params);
+        self.type_param_bindings.push(bindings);
+
         // Collect parameter types
         let param_types: Vec<TypeId> = decl
             .params
@@ -1217,6 +2312,7 @@ impl TypeChecker {
         self.return_types.pop();
         self.current_function = None;
         self.exit_scope();
+        self.type_param_bindings.pop();
 
         Ok(TypeId::Function(0)) // Placeholder function type
     }
@@ -1237,12 +2333,25 @@ impl TypeChecker {
             });
         }
 
+        // Type tests on a union variable narrow it inside each branch
+        let narrowing = self.condition_narrowing(&stmt.condition);
+
         // Check then branch
+        self.enter_scope();
+        if let Some((ref name, then_type, _)) = narrowing {
+            self.add_narrowed_symbol(name, then_type)?;
+        }
         self.check_block_statement(&stmt.then_branch)?;
+        self.exit_scope();
 
         // Check else branch if present
         if let Some(ref else_branch) = stmt.else_branch {
+            self.enter_scope();
+            if let Some((ref name, _, else_type)) = narrowing {
+                self.add_narrowed_symbol(name, else_type)?;
+            }
             self.check_statement(else_branch)?;
+            self.exit_scope();
         }
 
         Ok(TypeId::Any) // If statements don't have a type
@@ -1278,11 +2387,46 @@ impl TypeChecker {
         // Check iterable expression
         let iterable_type = self.check_expression(&stmt.iterable)?;
 
+        // Maps iterate in ascending key order: `for key in m` binds keys,
+        // `for key, value in m` binds both
+        let mut index_type = TypeId::Int32;
+
         // Determine element type based on iterable type
         let element_type = match iterable_type {
             TypeId::String => TypeId::Char,
+            TypeId::Map(_) => {
+                let (key_type, value_type) = self
+                    .type_registry
+                    .get_map_types(iterable_type)
+                    .unwrap_or((TypeId::Any, TypeId::Any));
+                if stmt.index_variable.is_some() {
+                    index_type = key_type;
+                    value_type
+                } else {
+                    key_type
+                }
+            }
             TypeId::Array(_) | TypeId::Slice(_) => TypeId::Any, // Placeholder
-            TypeId::Channel(_) => TypeId::Any, // Channel elements can be any type
+            // Sets iterate in ascending element order
+            TypeId::Set(_) => self
+                .type_registry
+                .get_set_element_type(iterable_type)
+                .unwrap_or(TypeId::Any),
+            TypeId::Channel(_) => self
+                .type_registry
+                .get_channel_info(iterable_type)
+                .map_or(TypeId::Any, |channel| channel.element_type),
+            // Deques iterate front to back, priority queues in priority order, linked
+            // sets in insertion order and ordered and linked maps like maps
+            TypeId::Struct(_) if self.collection_type_args(iterable_type).is_some() => {
+                let (name, type_args) = self.collection_type_args(iterable_type).unwrap();
+                if crate::std::collections::is_keyed(&name) && stmt.index_variable.is_some() {
+                    index_type = type_args[0];
+                    type_args[1]
+                } else {
+                    type_args[0]
+                }
+            }
             TypeId::Any => {
                 // This could be a range (0..5) which returns Any for now
                 // For ranges, the element type is the same as the range bounds
@@ -1306,7 +2450,7 @@ impl TypeChecker {
         if let Some(ref index_var) = stmt.index_variable {
             let index_symbol = Symbol {
                 name: index_var.clone(),
-                type_id: TypeId::Int32, // Index is always int32
+                type_id: index_type,
                 is_mutable: false,      // Loop variables are immutable
                 position: stmt.position,
                 function_info: None,
@@ -1343,13 +2487,13 @@ impl TypeChecker {
             // Return with value
             (Some(ref expr), Some(expected)) => {
                 let actual_type = self.check_expression(expr)?;
-                if !PrimitiveType::is_assignable(actual_type, expected) {
+                if !self.is_type_compatible(actual_type, expected) {
                     return Err(BuluError::TypeError { stack: Vec::new(),
                         file: None,
                         message: format!(
                             "Cannot return {} from function expecting {}",
-                            PrimitiveType::type_name(actual_type),
-                            PrimitiveType::type_name(expected)
+                            self.type_name_for_error(actual_type),
+                            self.type_name_for_error(expected)
                         ),
                         line: stmt.position.line,
                         column: stmt.position.column,
@@ -1390,6 +2534,124 @@ impl TypeChecker {
         Ok(last_type)
     }
 
+    /// Type check a match statement. Statements may leave values unmatched,
+    /// but arms shadowed by an earlier catch-all are rejected.
+    fn check_match_statement(&mut self, stmt: &MatchStmt) -> Result<TypeId> {
+        let scrutinee_type = self.check_expression(&stmt.expr)?;
+
+        for arm in &stmt.arms {
+            self.enter_scope();
+            self.check_pattern_and_add_variables(&arm.pattern, scrutinee_type)?;
+            if let Some(ref guard) = arm.guard {
+                self.check_match_guard(guard)?;
+            }
+            self.check_statement(&arm.body)?;
+            self.exit_scope();
+        }
+
+        let arms: Vec<(&Pattern, bool)> = stmt
+            .arms
+            .iter()
+            .map(|arm| (&arm.pattern, arm.guard.is_some()))
+            .collect();
+        let coverage = analyze_match(&arms, scrutinee_type == TypeId::Bool);
+        if let Some(&index) = coverage.unreachable_arms.first() {
+            let position = stmt.arms[index].position;
+            return Err(BuluError::TypeError { stack: Vec::new(),
+                file: self.current_file.clone(),
+                message: "Unreachable match arm: a previous arm already matches every value".to_string(),
+                line: position.line,
+                column: position.column,
+            });
+        }
+
+        Ok(TypeId::Any)
+    }
+
+    /// Type check a match expression, which must be exhaustive since it produces a value
+    fn check_match_expression(&mut self, expr: &MatchExpr) -> Result<TypeId> {
+        let scrutinee_type = self.check_expression(&expr.expr)?;
+
+        let mut result_type: Option<TypeId> = None;
+        for arm in &expr.arms {
+            self.enter_scope();
+            self.check_pattern_and_add_variables(&arm.pattern, scrutinee_type)?;
+            if let Some(ref guard) = arm.guard {
+                self.check_match_guard(guard)?;
+            }
+            let arm_type = self.check_expression(&arm.expr)?;
+            self.exit_scope();
+
+            result_type = match result_type {
+                None => Some(arm_type),
+                Some(previous) if previous == arm_type => Some(previous),
+                Some(_) => Some(TypeId::Any),
+            };
+        }
+
+        let arms: Vec<(&Pattern, bool)> = expr
+            .arms
+            .iter()
+            .map(|arm| (&arm.pattern, arm.guard.is_some()))
+            .collect();
+        let coverage = analyze_match(&arms, scrutinee_type == TypeId::Bool);
+        if let Some(&index) = coverage.unreachable_arms.first() {
+            let position = expr.arms[index].position;
+            return Err(BuluError::TypeError { stack: Vec::new(),
+                file: self.current_file.clone(),
+                message: "Unreachable match arm: a previous arm already matches every value".to_string(),
+                line: position.line,
+                column: position.column,
+            });
+        }
+        if !coverage.exhaustive && self.type_registry.get_union_members(scrutinee_type).is_some() {
+            // Over a union, type patterns covering every variant are exhaustive too
+            let uncovered = self.uncovered_union_members(scrutinee_type, &arms);
+            if !uncovered.is_empty() {
+                let missing: Vec<String> = uncovered
+                    .into_iter()
+                    .map(|member| self.type_name_for_error(member))
+                    .collect();
+                return Err(BuluError::TypeError { stack: Vec::new(),
+                    file: self.current_file.clone(),
+                    message: format!(
+                        "Non-exhaustive match expression: variants {} of {} are not covered",
+                        missing.join(", "),
+                        self.type_name_for_error(scrutinee_type)
+                    ),
+                    line: expr.position.line,
+                    column: expr.position.column,
+                });
+            }
+        } else if !coverage.exhaustive {
+            return Err(BuluError::TypeError { stack: Vec::new(),
+                file: self.current_file.clone(),
+                message: "Non-exhaustive match expression: add a '_' arm or an unguarded catch-all pattern".to_string(),
+                line: expr.position.line,
+                column: expr.position.column,
+            });
+        }
+
+        Ok(result_type.unwrap_or(TypeId::Any))
+    }
+
+    /// Type check a match arm guard, which must be a boolean condition
+    fn check_match_guard(&mut self, guard: &Expression) -> Result<()> {
+        let guard_type = self.check_expression(guard)?;
+        if guard_type != TypeId::Bool && guard_type != TypeId::Any {
+            return Err(BuluError::TypeError { stack: Vec::new(),
+                file: self.current_file.clone(),
+                message: format!(
+                    "Match guard must be bool, got {}",
+                    PrimitiveType::type_name(guard_type)
+                ),
+                line: guard.position().line,
+                column: guard.position().column,
+            });
+        }
+        Ok(())
+    }
+
     /// Type check an expression
     pub fn check_expression(&mut self, expr: &Expression) -> Result<TypeId> {
         match expr {
@@ -1403,6 +2665,7 @@ impl TypeChecker {
             Expression::Assignment(assign) => self.check_assignment_expression(assign),
             Expression::Array(array) => self.check_array_expression(array),
             Expression::Map(map) => self.check_map_expression(map),
+            Expression::Set(set) => self.check_set_expression(set),
             Expression::StructLiteral(struct_lit) => {
                 self.check_struct_literal_expression(struct_lit)
             }
@@ -1410,9 +2673,11 @@ impl TypeChecker {
             Expression::TypeOf(typeof_expr) => self.check_typeof_expression(typeof_expr),
             Expression::Async(async_expr) => self.check_async_expression(async_expr),
             Expression::Await(await_expr) => self.check_await_expression(await_expr),
+            Expression::Propagate(propagate) => self.check_propagate_expression(propagate),
             Expression::Range(range) => self.check_range_expression(range),
             Expression::Parenthesized(paren) => self.check_expression(&paren.expr),
             Expression::Tuple(tuple) => self.check_tuple_expression(tuple),
+            Expression::Match(match_expr) => self.check_match_expression(match_expr),
             _ => {
                 // For now, return Any for unimplemented expression types
                 Ok(TypeId::Any)
@@ -1421,7 +2686,10 @@ impl TypeChecker {
     }
 
     /// Type check a literal expression
-    fn check_literal_expression(&self, lit: &LiteralExpr) -> TypeId {
+    fn check_literal_expression(&mut self, lit: &LiteralExpr) -> TypeId {
+        if let LiteralValue::ByteString(_) = lit.value {
+            return TypeId::Slice(self.type_registry.register_slice_type(TypeId::UInt8));
+        }
         PrimitiveType::infer_from_literal(&lit.value)
     }
 
@@ -1441,7 +2709,7 @@ impl TypeChecker {
 
                 Err(BuluError::TypeError { stack: Vec::new(),
                     file: None,
-                    message: format!("Undefined identifier '{}'", ident.name),
+                    message: self.undefined_message("identifier", &ident.name),
                     line: ident.position.line,
                     column: ident.position.column,
                 })
@@ -1477,102 +2745,1854 @@ impl TypeChecker {
                     return true;
                 }
 
-                // Check if it's a user-defined interface
-                if self.interfaces.contains_key(type_name) {
-                    return true;
+                // Check if it's a user-defined interface
+                if self.interfaces.contains_key(type_name) {
+                    return true;
+                }
+
+                // Check if it's in the symbol table (could be imported)
+                self.lookup_symbol(type_name).is_some()
+            }
+        }
+    }
+
+    /// Type check a binary expression
+    fn check_binary_expression(&mut self, bin: &BinaryExpr) -> Result<TypeId> {
+        let left_type = self.check_expression(&bin.left)?;
+        let right_type = self.check_expression(&bin.right)?;
+
+        let op_str = match bin.operator {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Modulo => "%",
+            BinaryOperator::Power => "**",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::LessEqual => "<=",
+            BinaryOperator::GreaterEqual => ">=",
+            BinaryOperator::And => "and",
+            BinaryOperator::Or => "or",
+            _ => "unknown",
+        };
+
+        // Comparing a union with a value of one of its variants (such as null) is allowed
+        if matches!(bin.operator, BinaryOperator::Equal | BinaryOperator::NotEqual)
+            && [left_type, right_type]
+                .iter()
+                .any(|operand| self.type_registry.get_union_members(*operand).is_some())
+            && (self.is_type_compatible(left_type, right_type)
+                || self.is_type_compatible(right_type, left_type))
+        {
+            return Ok(TypeId::Bool);
+        }
+
+        PrimitiveType::binary_operation_result_type(left_type, right_type, op_str).map_err(
+            |mut e| {
+                if let BuluError::TypeError {
+                    ref mut stack,
+                    file: None,
+                    ref mut line,
+                    ref mut column,
+                    ..
+                } = e
+                {
+                    if stack.is_empty() {
+                        *line = bin.position.line;
+                        *column = bin.position.column;
+                    }
+                }
+                e
+            },
+        )
+    }
+
+    /// Type check a unary expression
+    fn check_unary_expression(&mut self, unary: &UnaryExpr) -> Result<TypeId> {
+        let operand_type = self.check_expression(&unary.operand)?;
+
+        match unary.operator {
+            UnaryOperator::Plus | UnaryOperator::Minus => {
+                if PrimitiveType::is_numeric_type_id(operand_type) {
+                    Ok(operand_type)
+                } else {
+                    Err(BuluError::TypeError { stack: Vec::new(),
+                        file: None,
+                        message: format!(
+                            "Unary {} operator requires numeric operand, got {}",
+                            match unary.operator {
+                                UnaryOperator::Plus => "+",
+                                UnaryOperator::Minus => "-",
+                                _ => "unknown",
+                            },
+                            PrimitiveType::type_name(operand_type)
+                        ),
+                        line: unary.position.line,
+                        column: unary.position.column,
+                    })
+                }
+            }
+            UnaryOperator::Not => {
+                if operand_type == TypeId::Bool {
+                    Ok(TypeId::Bool)
+                } else {
+                    Err(BuluError::TypeError { stack: Vec::new(),
+                        file: None,
+                        message: format!(
+                            "Unary not operator requires bool operand, got {}",
+                            PrimitiveType::type_name(operand_type)
+                        ),
+                        line: unary.position.line,
+                        column: unary.position.column,
+                    })
+                }
+            }
+            _ => Ok(operand_type), // For other operators, assume same type
+        }
+    }
+
+    /// Type check a call to a std/arrays function. The result type follows the element type of
+    /// the array argument and the return types of the callbacks.
+    fn check_std_arrays_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+
+        let arity = crate::std::arrays::arity(function)
+            .ok_or_else(|| error(format!("Unknown function '{}' in std/arrays", function)))?;
+        if call.args.len() != arity {
+            return Err(error(format!(
+                "Function '{}' expects {} arguments, got {}",
+                name,
+                arity,
+                call.args.len()
+            )));
+        }
+
+        let array_type = self.check_expression(&call.args[0])?;
+        let element_type = match array_type {
+            TypeId::Array(_) | TypeId::Slice(_) => self
+                .type_registry
+                .get_element_type(array_type)
+                .unwrap_or(TypeId::Any),
+            TypeId::Any => TypeId::Any,
+            _ => {
+                return Err(error(format!(
+                    "Argument 1 to function '{}': expected array, got {}",
+                    name,
+                    self.type_name_for_error(array_type)
+                )))
+            }
+        };
+        // Wrap an element type in the same array/slice flavour as the argument
+        let same_flavour = |checker: &mut Self, element: TypeId| match array_type {
+            TypeId::Slice(_) => TypeId::Slice(checker.type_registry.register_slice_type(element)),
+            TypeId::Array(_) => TypeId::Array(checker.type_registry.register_array_type(element)),
+            _ => TypeId::Any,
+        };
+
+        // The return type of the callback at `index` called with `params`
+        let callback = |checker: &mut Self, index: usize, params: &[TypeId], what: &str| {
+            let argument = &call.args[index];
+            let typed = match argument {
+                Expression::Lambda(_) => true,
+                Expression::Identifier(ident) => {
+                    checker.lookup_symbol(&ident.name).is_some_and(|symbol| symbol.function_info.is_some())
+                }
+                _ => false,
+            };
+            if typed {
+                let argument_name = format!("Argument {} to function '{}'", index + 1, name);
+                return checker.callback_return_type(argument, params, &argument_name);
+            }
+            let argument_type = checker.check_expression(argument)?;
+            if !matches!(argument_type, TypeId::Function(_) | TypeId::Any) {
+                return Err(error(format!(
+                    "Argument {} to function '{}': expected {}, got {}",
+                    index + 1,
+                    name,
+                    what,
+                    checker.type_name_for_error(argument_type)
+                )));
+            }
+            Ok(TypeId::Any)
+        };
+        // Callbacks that return a known type must return `expected`
+        let returns = |checker: &Self, index: usize, actual: TypeId, expected: &str, accepted: bool| {
+            if accepted || matches!(actual, TypeId::Any | TypeId::Unknown) {
+                return Ok(());
+            }
+            Err(error(format!(
+                "Argument {} to function '{}': expected a function returning {}, got one returning {}",
+                index + 1,
+                name,
+                expected,
+                checker.type_name_for_error(actual)
+            )))
+        };
+
+        match function {
+            "sort" | "sortStable" => {
+                let order_type = callback(self, 1, &[element_type, element_type], "comparator function")?;
+                returns(self, 1, order_type, "an integer", PrimitiveType::is_integer_type_id(order_type))?;
+                Ok(array_type)
+            }
+            "map" => {
+                let mapped_type = callback(self, 1, &[element_type], "mapping function")?;
+                returns(self, 1, mapped_type, "a value", mapped_type != TypeId::Void)?;
+                Ok(same_flavour(self, mapped_type))
+            }
+            "filter" => {
+                let keep_type = callback(self, 1, &[element_type], "predicate function")?;
+                returns(self, 1, keep_type, "bool", keep_type == TypeId::Bool)?;
+                Ok(array_type)
+            }
+            "reduce" => {
+                let initial_type = self.check_expression(&call.args[1])?;
+                let reduced_type = callback(self, 2, &[initial_type, element_type], "reducing function")?;
+                let accepted = self.is_type_compatible(reduced_type, initial_type);
+                let expected = self.type_name_for_error(initial_type);
+                returns(self, 2, reduced_type, &expected, accepted)?;
+                Ok(initial_type)
+            }
+            "zip" => {
+                let other_type = self.check_expression(&call.args[1])?;
+                let other_element = match other_type {
+                    TypeId::Array(_) | TypeId::Slice(_) => {
+                        self.type_registry.get_element_type(other_type).unwrap_or(TypeId::Any)
+                    }
+                    TypeId::Any => TypeId::Any,
+                    _ => {
+                        return Err(error(format!(
+                            "Argument 2 to function '{}': expected array, got {}",
+                            name,
+                            self.type_name_for_error(other_type)
+                        )))
+                    }
+                };
+                let pair_type = TypeId::Tuple(self.type_registry.register_tuple_type(vec![element_type, other_element]));
+                Ok(same_flavour(self, pair_type))
+            }
+            "binarySearch" => {
+                let target_type = self.check_expression(&call.args[1])?;
+                if !self.is_type_compatible(target_type, element_type) {
+                    return Err(error(format!(
+                        "Argument 2 to function '{}': expected {}, got {}",
+                        name,
+                        self.type_name_for_error(element_type),
+                        self.type_name_for_error(target_type)
+                    )));
+                }
+                Ok(TypeId::Int32)
+            }
+            "chunk" | "shuffle" => {
+                let argument_type = self.check_expression(&call.args[1])?;
+                if !PrimitiveType::is_integer_type_id(argument_type) && argument_type != TypeId::Any {
+                    return Err(error(format!(
+                        "Argument 2 to function '{}': expected integer, got {}",
+                        name,
+                        self.type_name_for_error(argument_type)
+                    )));
+                }
+                if function == "chunk" {
+                    Ok(same_flavour(self, array_type))
+                } else {
+                    Ok(array_type)
+                }
+            }
+            "flatten" => match element_type {
+                TypeId::Array(_) | TypeId::Slice(_) => {
+                    let inner_type = self
+                        .type_registry
+                        .get_element_type(element_type)
+                        .unwrap_or(TypeId::Any);
+                    Ok(same_flavour(self, inner_type))
+                }
+                TypeId::Any => Ok(array_type),
+                _ => Err(error(format!(
+                    "Argument 1 to function '{}': expected array of arrays, got {}",
+                    name,
+                    self.type_name_for_error(array_type)
+                ))),
+            },
+            _ => Ok(array_type),
+        }
+    }
+
+    /// Type check a call to a generic function. Type parameters are bound from explicit type
+    /// arguments (`identity<int32>(5)`) or inferred from the argument types, checked against
+    /// their constraints, and the signature is instantiated with the bindings.
+    fn check_generic_call(&mut self, decl: &FunctionDecl, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+        let type_params = self
+            .generics
+            .get_function(&decl.name)
+            .map(|function| function.type_parameters.clone())
+            .unwrap_or_default();
+
+        if call.args.len() != decl.params.len() {
+            return Err(error(format!(
+                "Function '{}' expects {} arguments, got {}",
+                decl.name,
+                decl.params.len(),
+                call.args.len()
+            )));
+        }
+
+        let mut arg_types = Vec::with_capacity(call.args.len());
+        for arg in &call.args {
+            arg_types.push(self.check_expression(arg)?);
+        }
+
+        let mut bindings = HashMap::new();
+        if !call.type_args.is_empty() {
+            if call.type_args.len() != type_params.len() {
+                return Err(error(format!(
+                    "Function '{}' expects {} type arguments, got {}",
+                    decl.name,
+                    type_params.len(),
+                    call.type_args.len()
+                )));
+            }
+            for (param, type_arg) in type_params.iter().zip(&call.type_args) {
+                let bound_type = self.ast_type_to_type_id(type_arg);
+                bindings.insert(param.name.clone(), bound_type);
+            }
+        } else {
+            let names: Vec<String> = type_params.iter().map(|param| param.name.clone()).collect();
+            for (param, arg_type) in decl.params.iter().zip(&arg_types) {
+                self.infer_type_params(&param.param_type, *arg_type, &names, &mut bindings)
+                    .map_err(|message| error(format!("{} in call to '{}'", message, decl.name)))?;
+            }
+            if let Some(missing) = names.iter().find(|name| !bindings.contains_key(*name)) {
+                return Err(error(format!(
+                    "Cannot infer type parameter '{}' of function '{}'; pass it explicitly as {}<...>()",
+                    missing, decl.name, decl.name
+                )));
+            }
+        }
+
+        self.check_type_param_constraints(
+            &type_params,
+            &bindings,
+            &format!("function '{}'", decl.name),
+            call.position,
+        )?;
+
+        self.type_param_bindings.push(bindings);
+        let result = self.check_instantiated_call(decl, call, &arg_types);
+        self.type_param_bindings.pop();
+        result
+    }
+
+    /// Check call arguments against a generic function's signature under the current bindings
+    fn check_instantiated_call(
+        &mut self,
+        decl: &FunctionDecl,
+        call: &CallExpr,
+        arg_types: &[TypeId],
+    ) -> Result<TypeId> {
+        for (i, (param, actual_type)) in decl.params.iter().zip(arg_types).enumerate() {
+            let expected_type = self.ast_type_to_type_id(&param.param_type);
+            if !self.is_type_compatible(*actual_type, expected_type) {
+                return Err(BuluError::TypeError { stack: Vec::new(),
+                    file: None,
+                    message: format!(
+                        "Argument {} to function '{}': expected {}, got {}",
+                        i + 1,
+                        decl.name,
+                        self.type_name_for_error(expected_type),
+                        self.type_name_for_error(*actual_type)
+                    ),
+                    line: call.position.line,
+                    column: call.position.column,
+                });
+            }
+        }
+
+        let return_type = decl
+            .return_type
+            .as_ref()
+            .map(|return_type| self.ast_type_to_type_id(return_type));
+        if decl.is_async {
+            let promise_id = self
+                .type_registry
+                .register_promise_type(return_type.unwrap_or(TypeId::Void));
+            return Ok(TypeId::Promise(promise_id));
+        }
+        Ok(return_type.unwrap_or(TypeId::Any))
+    }
+
+    /// Infer type parameter bindings by matching a declared type against an actual type
+    fn infer_type_params(
+        &self,
+        declared: &Type,
+        actual: TypeId,
+        names: &[String],
+        inferred: &mut HashMap<String, TypeId>,
+    ) -> std::result::Result<(), String> {
+        match declared {
+            Type::Named(name) | Type::Generic(GenericType { name, .. }) if names.contains(name) => {
+                if actual == TypeId::Unknown {
+                    return Ok(());
+                }
+                match inferred.get(name).copied() {
+                    None | Some(TypeId::Any) => {
+                        inferred.insert(name.clone(), actual);
+                    }
+                    Some(previous) if self.is_type_compatible(actual, previous) => {}
+                    Some(previous) if self.is_type_compatible(previous, actual) => {
+                        // Widen to the larger type, e.g. int32 then int64
+                        inferred.insert(name.clone(), actual);
+                    }
+                    Some(previous) => {
+                        return Err(format!(
+                            "Conflicting types for type parameter '{}': {} and {}",
+                            name,
+                            self.type_name_for_error(previous),
+                            self.type_name_for_error(actual)
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            Type::Array(ArrayType { element_type, .. }) | Type::Slice(SliceType { element_type }) => {
+                match self.type_registry.get_element_type(actual) {
+                    Some(actual_element) => {
+                        self.infer_type_params(element_type, actual_element, names, inferred)
+                    }
+                    None => Ok(()),
+                }
+            }
+            Type::Map(map_type) => match self.type_registry.get_map_types(actual) {
+                Some((actual_key, actual_value)) => {
+                    self.infer_type_params(&map_type.key_type, actual_key, names, inferred)?;
+                    self.infer_type_params(&map_type.value_type, actual_value, names, inferred)
+                }
+                None => Ok(()),
+            },
+            Type::Struct(struct_type) => match self.struct_instances.get(&actual) {
+                Some((name, type_args)) if *name == struct_type.name => {
+                    for (declared_arg, actual_arg) in struct_type.type_args.iter().zip(type_args) {
+                        self.infer_type_params(declared_arg, *actual_arg, names, inferred)?;
+                    }
+                    Ok(())
+                }
+                _ => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that every bound type argument satisfies its type parameter's constraints.
+    /// Constraints naming a declared interface or a concrete type are enforced; other
+    /// names (such as `Clone`) are treated as markers.
+    fn check_type_param_constraints(
+        &mut self,
+        type_params: &[GenericTypeParam],
+        bindings: &HashMap<String, TypeId>,
+        owner: &str,
+        position: Position,
+    ) -> Result<()> {
+        for param in type_params {
+            let bound_type = match bindings.get(&param.name) {
+                Some(&bound_type) if !matches!(bound_type, TypeId::Any | TypeId::Unknown) => bound_type,
+                _ => continue,
+            };
+
+            for constraint in &param.constraints {
+                let required_type = match constraint {
+                    GenericConstraint::Interface(name) if self.interfaces.contains_key(name) => {
+                        self.get_or_create_named_type_id(name, true)
+                    }
+                    GenericConstraint::TypeConstraint(constraint_type) => {
+                        self.ast_type_to_type_id(constraint_type)
+                    }
+                    _ => continue,
+                };
+
+                if !self.is_type_compatible(bound_type, required_type) {
+                    return Err(BuluError::TypeError { stack: Vec::new(),
+                        file: None,
+                        message: format!(
+                            "Type {} does not satisfy constraint {} on type parameter '{}' of {}",
+                            self.type_name_for_error(bound_type),
+                            self.type_name_for_error(required_type),
+                            param.name,
+                            owner
+                        ),
+                        line: position.line,
+                        column: position.column,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Type check a call to a std/math function. Integer-preserving functions (abs, min, max,
+    /// clamp, gcd, lcm) return the common type of their arguments; the rest return float64 or bool.
+    fn check_std_math_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+
+        let arity = match function {
+            "pow" | "hypot" | "min" | "max" | "gcd" | "lcm" => 2,
+            "clamp" => 3,
+            _ => 1,
+        };
+        if call.args.len() != arity {
+            return Err(error(format!(
+                "Function '{}' expects {} arguments, got {}",
+                name,
+                arity,
+                call.args.len()
+            )));
+        }
+
+        let mut arg_types = Vec::with_capacity(call.args.len());
+        for arg in &call.args {
+            arg_types.push(self.check_expression(arg)?);
+        }
+
+        // Statistics take an array or slice of numbers
+        if matches!(function, "mean" | "median" | "stddev") {
+            let element_type = match arg_types[0] {
+                TypeId::Array(_) | TypeId::Slice(_) => self
+                    .type_registry
+                    .get_element_type(arg_types[0])
+                    .unwrap_or(TypeId::Any),
+                TypeId::Any => TypeId::Any,
+                _ => TypeId::Unknown,
+            };
+            if !PrimitiveType::is_numeric_type_id(element_type) && element_type != TypeId::Any {
+                return Err(error(format!(
+                    "Argument 1 to function '{}': expected array of numbers, got {}",
+                    name,
+                    self.type_name_for_error(arg_types[0])
+                )));
+            }
+            return Ok(TypeId::Float64);
+        }
+
+        for (index, &arg_type) in arg_types.iter().enumerate() {
+            let is_integer = PrimitiveType::is_integer_type_id(arg_type);
+            let accepted = match function {
+                "gcd" | "lcm" => is_integer,
+                _ => PrimitiveType::is_numeric_type_id(arg_type),
+            };
+            if !accepted && arg_type != TypeId::Any {
+                return Err(error(format!(
+                    "Argument {} to function '{}': expected {}, got {}",
+                    index + 1,
+                    name,
+                    if matches!(function, "gcd" | "lcm") { "integer" } else { "number" },
+                    self.type_name_for_error(arg_type)
+                )));
+            }
+        }
+
+        match function {
+            "isNaN" | "isInfinite" | "isFinite" => Ok(TypeId::Bool),
+            "abs" | "min" | "max" | "clamp" | "gcd" | "lcm" => {
+                // The common type of the arguments; mixing incompatible integers is an error
+                let mut result = arg_types[0];
+                for &arg_type in &arg_types[1..] {
+                    if arg_type == TypeId::Any || PrimitiveType::is_assignable(arg_type, result) {
+                        continue;
+                    } else if PrimitiveType::is_assignable(result, arg_type) {
+                        result = arg_type;
+                    } else if matches!(arg_type, TypeId::Float32 | TypeId::Float64)
+                        || matches!(result, TypeId::Float32 | TypeId::Float64)
+                    {
+                        result = TypeId::Float64;
+                    } else {
+                        return Err(error(format!(
+                            "Function '{}' cannot mix {} and {}",
+                            name,
+                            self.type_name_for_error(result),
+                            self.type_name_for_error(arg_type)
+                        )));
+                    }
+                }
+                Ok(result)
+            }
+            _ => Ok(TypeId::Float64),
+        }
+    }
+
+    /// Type check `hash(value)` / `hash(value, seed)`. Functions and channels have
+    /// no stable identity, so hashing them is rejected here rather than at runtime.
+    fn check_hash_call(&mut self, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+
+        if call.args.is_empty() || call.args.len() > 2 {
+            return Err(error(format!(
+                "Function 'hash' expects 1 to 2 arguments, got {}",
+                call.args.len()
+            )));
+        }
+
+        let value_type = self.check_expression(&call.args[0])?;
+        if matches!(value_type, TypeId::Function(_) | TypeId::Channel(_)) {
+            return Err(error(format!(
+                "Cannot hash a value of type {}",
+                self.type_name_for_error(value_type)
+            )));
+        }
+
+        if let Some(seed) = call.args.get(1) {
+            let seed_type = self.check_expression(seed)?;
+            if !PrimitiveType::is_integer_type_id(seed_type) && seed_type != TypeId::Any {
+                return Err(error(format!(
+                    "Argument 2 to function 'hash': expected integer, got {}",
+                    self.type_name_for_error(seed_type)
+                )));
+            }
+        }
+        Ok(TypeId::Int64)
+    }
+
+    /// Type check `intrinsic("name", args...)`: the name must be a literal naming
+    /// an intrinsic, and the arguments must match its parameters. Whether the
+    /// target has the intrinsic is up to the native backend.
+    fn check_intrinsic_call(&mut self, call: &CallExpr) -> Result<TypeId> {
+        use crate::compiler::intrinsics;
+
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+
+        let name = match call.args.first() {
+            Some(Expression::Literal(LiteralExpr { value: LiteralValue::String(name), .. })) => name,
+            _ => {
+                return Err(error(
+                    "The first argument to 'intrinsic' must be a string literal naming the intrinsic".to_string(),
+                ))
+            }
+        };
+        let intrinsic = intrinsics::lookup(name).ok_or_else(|| {
+            error(format!("Unknown intrinsic '{}', expected one of {}", name, intrinsics::names().join(", ")))
+        })?;
+        let args = &call.args[1..];
+        if args.len() != intrinsic.params.len() {
+            return Err(error(format!(
+                "Intrinsic '{}' expects {} argument{}, got {}",
+                name,
+                intrinsic.params.len(),
+                if intrinsic.params.len() == 1 { "" } else { "s" },
+                args.len()
+            )));
+        }
+
+        // Every parameter is an int64; narrower integers widen
+        for (index, arg) in args.iter().enumerate() {
+            let arg_type = self.check_expression(arg)?;
+            if !PrimitiveType::is_integer_type_id(arg_type) && arg_type != TypeId::Any {
+                return Err(error(format!(
+                    "Argument {} to intrinsic '{}': expected {}, got {}",
+                    index + 1,
+                    name,
+                    intrinsic.params[index],
+                    self.type_name_for_error(arg_type)
+                )));
+            }
+        }
+        Ok(match intrinsic.result {
+            Some(_) => TypeId::Int64,
+            None => TypeId::Void,
+        })
+    }
+
+    /// Type check a call to a std/fmt function: a number, then the decimals (or for
+    /// formatCurrency the currency code), then an optional locale. Literal locale and
+    /// currency codes are checked against the built-in data.
+    fn check_std_fmt_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+
+        let min_args = if matches!(function, "formatFixed" | "formatCurrency") { 2 } else { 1 };
+        if call.args.len() < min_args || call.args.len() > 3 {
+            return Err(error(format!(
+                "Function '{}' expects {} to 3 arguments, got {}",
+                name,
+                min_args,
+                call.args.len()
+            )));
+        }
+
+        let mut arg_types = Vec::with_capacity(call.args.len());
+        for arg in &call.args {
+            arg_types.push(self.check_expression(arg)?);
+        }
+
+        // The decimals may be left out in favour of the locale: formatNumber(x, "de-DE")
+        let locale_only = function != "formatCurrency" && call.args.len() == 2 && arg_types[1] == TypeId::String;
+        for (index, &arg_type) in arg_types.iter().enumerate() {
+            let (expected, accepted) = match index {
+                0 => ("number", PrimitiveType::is_numeric_type_id(arg_type)),
+                1 if function == "formatCurrency" => ("currency code", arg_type == TypeId::String),
+                1 if !locale_only => ("integer", PrimitiveType::is_integer_type_id(arg_type)),
+                _ => ("locale code", arg_type == TypeId::String),
+            };
+            if !accepted && arg_type != TypeId::Any {
+                return Err(error(format!(
+                    "Argument {} to function '{}': expected {}, got {}",
+                    index + 1,
+                    name,
+                    expected,
+                    self.type_name_for_error(arg_type)
+                )));
+            }
+
+            if let Expression::Literal(LiteralExpr { value: LiteralValue::String(code), .. }) = &call.args[index] {
+                if expected == "locale code" && crate::std::fmt::find_locale(code).is_none() {
+                    return Err(error(format!("Unknown locale '{}' in call to '{}'", code, name)));
+                }
+                if expected == "currency code" && crate::std::fmt::find_currency(code).is_none() {
+                    return Err(error(format!("Unknown currency '{}' in call to '{}'", code, name)));
+                }
+            }
+        }
+
+        Ok(TypeId::String)
+    }
+
+    /// Type check a std/template call. A literal template is parsed here, so syntax errors
+    /// and (without a filters map) unknown filters are reported before the program runs.
+    fn check_std_template_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+
+        if call.args.len() < 2 || call.args.len() > 3 {
+            return Err(error(format!(
+                "Function '{}' expects 2 to 3 arguments, got {}",
+                name,
+                call.args.len()
+            )));
+        }
+
+        for (index, arg) in call.args.iter().enumerate() {
+            let arg_type = self.check_expression(arg)?;
+            let (expected, accepted) = match index {
+                0 => ("string", arg_type == TypeId::String),
+                1 => ("map or struct", matches!(arg_type, TypeId::Map(_) | TypeId::Struct(_))),
+                _ => ("map of filters", matches!(arg_type, TypeId::Map(_))),
+            };
+            if !accepted && arg_type != TypeId::Any {
+                return Err(error(format!(
+                    "Argument {} to function '{}': expected {}, got {}",
+                    index + 1,
+                    name,
+                    expected,
+                    self.type_name_for_error(arg_type)
+                )));
+            }
+        }
+
+        if function == "renderFile" {
+            return Ok(TypeId::String);
+        }
+        if let Expression::Literal(LiteralExpr { value: LiteralValue::String(source), .. }) = &call.args[0] {
+            let template = crate::std::template::Template::parse(source)
+                .map_err(|message| error(format!("Invalid template in call to '{}': {}", name, message)))?;
+            if call.args.len() == 2 {
+                if let Some(filter) = template.custom_filter_names().first() {
+                    return Err(error(format!("Unknown filter '{}' in call to '{}'", filter, name)));
+                }
+            }
+        }
+
+        Ok(TypeId::String)
+    }
+
+    /// Type check a std/i18n call. A literal catalog passed to loadCatalog is parsed here.
+    fn check_std_i18n_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+
+        // Expected argument kinds (the optional ones last) and the result type
+        let (params, required, return_type): (&[&str], usize, TypeId) = match function {
+            "loadCatalog" | "loadCatalogFile" => (&["string", "string"], 2, TypeId::Void),
+            "setLocale" => (&["string"], 1, TypeId::Void),
+            "locale" => (&[], 0, TypeId::String),
+            "translate" => (&["string", "map"], 1, TypeId::String),
+            _ => (&["number", "string"], 1, TypeId::String),
+        };
+        if call.args.len() < required || call.args.len() > params.len() {
+            let expected = if required == params.len() {
+                required.to_string()
+            } else {
+                format!("{} to {}", required, params.len())
+            };
+            return Err(error(format!(
+                "Function '{}' expects {} argument{}, got {}",
+                name,
+                expected,
+                if expected == "1" { "" } else { "s" },
+                call.args.len()
+            )));
+        }
+
+        for (index, (arg, expected)) in call.args.iter().zip(params.iter()).enumerate() {
+            let arg_type = self.check_expression(arg)?;
+            let accepted = match *expected {
+                "string" => arg_type == TypeId::String,
+                "map" => matches!(arg_type, TypeId::Map(_)),
+                _ => PrimitiveType::is_numeric_type_id(arg_type),
+            };
+            if !accepted && arg_type != TypeId::Any {
+                return Err(error(format!(
+                    "Argument {} to function '{}': expected {}, got {}",
+                    index + 1,
+                    name,
+                    expected,
+                    self.type_name_for_error(arg_type)
+                )));
+            }
+        }
+
+        if function == "loadCatalog" {
+            if let Expression::Literal(LiteralExpr { value: LiteralValue::String(source), .. }) = &call.args[1] {
+                crate::std::i18n::Catalog::parse(source)
+                    .map_err(|message| error(format!("Invalid message catalog in call to '{}': {}", name, message)))?;
+            }
+        }
+
+        Ok(return_type)
+    }
+
+    /// Whether a value of `type_id` can be passed where std functions take bytes:
+    /// a string, or an array or slice of integers
+    fn is_byte_data_type(&self, type_id: TypeId) -> bool {
+        type_id == TypeId::String
+            || matches!(type_id, TypeId::Array(_) | TypeId::Slice(_))
+                && self.type_registry.get_element_type(type_id).is_none_or(|element| {
+                    element == TypeId::Any || PrimitiveType::is_integer_type_id(element)
+                })
+    }
+
+    /// Type check a std/checksum call; literal algorithm names are validated at compile time
+    fn check_std_checksum_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        use crate::std::checksum::ALGORITHMS;
+
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+        // The first argument is the data or, for newHasher, the algorithm
+        let return_type = match function {
+            "crc32" | "crc32c" => TypeId::UInt32,
+            "xxhash64" => TypeId::UInt64,
+            "newHasher" => TypeId::Struct(1015),
+            _ => return Err(error(format!("Unknown function '{}' in std/checksum", function))),
+        };
+        let max_args = if matches!(function, "crc32" | "crc32c") { 1 } else { 2 };
+        if call.args.is_empty() || call.args.len() > max_args {
+            let expected = if max_args == 1 { "1 argument" } else { "1 to 2 arguments" };
+            return Err(error(format!(
+                "Function '{}' expects {}, got {}",
+                name,
+                expected,
+                call.args.len()
+            )));
+        }
+
+        let mut arg_types = Vec::with_capacity(call.args.len());
+        for arg in &call.args {
+            arg_types.push(self.check_expression(arg)?);
+        }
+        let first = arg_types[0];
+        if function == "newHasher" {
+            if first != TypeId::String && first != TypeId::Any {
+                return Err(error(format!(
+                    "Argument 1 to function '{}': expected string, got {}",
+                    name,
+                    self.type_name_for_error(first)
+                )));
+            }
+            if let Expression::Literal(LiteralExpr { value: LiteralValue::String(algorithm), .. }) = &call.args[0] {
+                if !ALGORITHMS.contains(&algorithm.as_str()) {
+                    return Err(error(format!(
+                        "Unknown checksum algorithm '{}' in call to '{}', expected one of {}",
+                        algorithm,
+                        name,
+                        ALGORITHMS.join(", ")
+                    )));
+                }
+            }
+        } else {
+            let accepted = self.is_byte_data_type(first);
+            if !accepted && first != TypeId::Any {
+                return Err(error(format!(
+                    "Argument 1 to function '{}': expected byte slice or string, got {}",
+                    name,
+                    self.type_name_for_error(first)
+                )));
+            }
+        }
+        if let Some(&seed) = arg_types.get(1) {
+            if !PrimitiveType::is_integer_type_id(seed) && seed != TypeId::Any {
+                return Err(error(format!(
+                    "Argument 2 to function '{}': expected integer seed, got {}",
+                    name,
+                    self.type_name_for_error(seed)
+                )));
+            }
+        }
+
+        Ok(return_type)
+    }
+
+    /// Type check a std/crypto call. Data, keys, salts and passwords are byte
+    /// slices or strings; counts and lengths are integers.
+    fn check_std_crypto_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+        let bytes = TypeId::Slice(self.type_registry.register_slice_type(TypeId::UInt8));
+        // Whether each parameter is a byte slice (or else an integer), and how many are required
+        let (params, required, return_type): (&[bool], usize, TypeId) = match function {
+            "sha256" | "sha512" => (&[true], 1, bytes),
+            "hmacSha256" => (&[true, true], 2, bytes),
+            "encrypt" | "decrypt" => (&[true, true, true], 2, self.result_type_id(bytes, TypeId::String)),
+            "pbkdf2" => (&[true, true, false, false], 4, bytes),
+            "argon2" => (&[true, true, false], 3, self.result_type_id(bytes, TypeId::String)),
+            "constantTimeEquals" => (&[true, true], 2, TypeId::Bool),
+            "randomBytes" => (&[false], 1, bytes),
+            _ => return Err(error(format!("Unknown function '{}' in std/crypto", function))),
+        };
+        if call.args.len() < required || call.args.len() > params.len() {
+            let expected = match (required, params.len()) {
+                (1, 1) => "1 argument".to_string(),
+                (min, max) if min == max => format!("{} arguments", min),
+                (min, max) => format!("{} to {} arguments", min, max),
+            };
+            return Err(error(format!(
+                "Function '{}' expects {}, got {}",
+                name,
+                expected,
+                call.args.len()
+            )));
+        }
+
+        for (index, (arg, &is_bytes)) in call.args.iter().zip(params).enumerate() {
+            let arg_type = self.check_expression(arg)?;
+            let accepted = if is_bytes {
+                self.is_byte_data_type(arg_type)
+            } else {
+                PrimitiveType::is_integer_type_id(arg_type)
+            };
+            if !accepted && arg_type != TypeId::Any {
+                return Err(error(format!(
+                    "Argument {} to function '{}': expected {}, got {}",
+                    index + 1,
+                    name,
+                    if is_bytes { "byte slice or string" } else { "integer" },
+                    self.type_name_for_error(arg_type)
+                )));
+            }
+        }
+        if function == "pbkdf2" {
+            if let Expression::Literal(LiteralExpr { value: LiteralValue::Integer(iterations), .. }) = &call.args[2] {
+                if *iterations < 1 {
+                    return Err(error(format!(
+                        "Iteration count in call to '{}' must be at least 1, got {}",
+                        name, iterations
+                    )));
+                }
+            }
+        }
+
+        Ok(return_type)
+    }
+
+    /// Type check a std/encoding call. Encoders take byte slices or strings,
+    /// decoders take strings.
+    fn check_std_encoding_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+        let bytes = TypeId::Slice(self.type_registry.register_slice_type(TypeId::UInt8));
+        let (takes_bytes, return_type) = match function {
+            "base64Encode" | "base64UrlEncode" | "hexEncode" => (true, TypeId::String),
+            "base64Decode" | "base64UrlDecode" | "hexDecode" => (false, self.result_type_id(bytes, TypeId::String)),
+            "urlEncode" => (false, TypeId::String),
+            "urlDecode" => (false, self.result_type_id(TypeId::String, TypeId::String)),
+            _ => return Err(error(format!("Unknown function '{}' in std/encoding", function))),
+        };
+        if call.args.len() != 1 {
+            return Err(error(format!(
+                "Function '{}' expects 1 argument, got {}",
+                name,
+                call.args.len()
+            )));
+        }
+
+        let arg_type = self.check_expression(&call.args[0])?;
+        let accepted = if takes_bytes {
+            self.is_byte_data_type(arg_type)
+        } else {
+            arg_type == TypeId::String
+        };
+        if !accepted && arg_type != TypeId::Any {
+            return Err(error(format!(
+                "Argument 1 to function '{}': expected {}, got {}",
+                name,
+                if takes_bytes { "byte slice or string" } else { "string" },
+                self.type_name_for_error(arg_type)
+            )));
+        }
+
+        Ok(return_type)
+    }
+
+    /// Type check a std/time call; literal parse patterns are checked at compile time
+    fn check_std_time_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        use crate::std::time::UNITS;
+
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+        let [duration, instant, date_time] = [1032, 1033, 1034].map(TypeId::Struct);
+        let ticker = TypeId::Struct(1035);
+        // The kind of each parameter, and how many are required
+        let (params, required, return_type): (&[&str], usize, TypeId) = match function {
+            _ if UNITS.iter().any(|(unit, _)| *unit == function) => (&["integer"], 1, duration),
+            "parseDuration" => (&["string"], 1, self.result_type_id(duration, TypeId::String)),
+            "instant" => (&[], 0, instant),
+            "measure" => (&["function"], 1, duration),
+            "now" | "utcNow" => (&[], 0, date_time),
+            "fromUnix" | "fromUnixMillis" => (&["integer"], 1, date_time),
+            "date" => (&["integer"; 7], 3, self.result_type_id(date_time, TypeId::String)),
+            "parse" => (&["string", "string"], 2, self.result_type_id(date_time, TypeId::String)),
+            "parseRfc3339" => (&["string"], 1, self.result_type_id(date_time, TypeId::String)),
+            "after" => (&["Duration"], 1, self.time_channel_type()),
+            "tick" => (&["Duration"], 1, ticker),
+            _ => return Err(error(format!("Unknown function '{}' in std/time", function))),
+        };
+        if call.args.len() < required || call.args.len() > params.len() {
+            let expected = match (required, params.len()) {
+                (1, 1) => "1 argument".to_string(),
+                (min, max) if min == max => format!("{} arguments", min),
+                (min, max) => format!("{} to {} arguments", min, max),
+            };
+            return Err(error(format!(
+                "Function '{}' expects {}, got {}",
+                name,
+                expected,
+                call.args.len()
+            )));
+        }
+
+        for (index, (arg, &kind)) in call.args.iter().zip(params).enumerate() {
+            let arg_type = self.check_expression(arg)?;
+            let accepted = match kind {
+                "integer" => PrimitiveType::is_integer_type_id(arg_type),
+                "string" => arg_type == TypeId::String,
+                "function" => matches!(arg_type, TypeId::Function(_)),
+                _ => arg_type == duration,
+            };
+            if !accepted && arg_type != TypeId::Any {
+                return Err(error(format!(
+                    "Argument {} to function '{}': expected {}, got {}",
+                    index + 1,
+                    name,
+                    kind,
+                    self.type_name_for_error(arg_type)
+                )));
+            }
+        }
+        if function == "parse" {
+            if let Expression::Literal(LiteralExpr { value: LiteralValue::String(pattern), .. }) = &call.args[1] {
+                crate::std::time::check_pattern(pattern)
+                    .map_err(|message| error(format!("{} in call to '{}'", message, name)))?;
+            }
+        }
+
+        Ok(return_type)
+    }
+
+    /// Type check a std/log call; literal level names are checked at compile time
+    fn check_std_log_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+        let [logger, sink] = [1036, 1037].map(TypeId::Struct);
+        // The kind of each parameter, and how many are required
+        let (params, required, return_type): (&[&str], usize, TypeId) = match function {
+            "debug" | "info" | "warn" | "error" => (&["string", "map"], 1, TypeId::Void),
+            "logger" => (&["string"], 1, logger),
+            "setLevel" => (&["string"], 1, TypeId::Void),
+            "level" => (&[], 0, TypeId::String),
+            "setSink" | "addSink" => (&["LogSink"], 1, TypeId::Void),
+            "stderrSink" => (&[], 0, sink),
+            "fileSink" => (&["string", "integer", "integer"], 1, sink),
+            _ => return Err(error(format!("Unknown function '{}' in std/log", function))),
+        };
+        if call.args.len() < required || call.args.len() > params.len() {
+            let expected = match (required, params.len()) {
+                (1, 1) => "1 argument".to_string(),
+                (min, max) if min == max => format!("{} arguments", min),
+                (min, max) => format!("{} to {} arguments", min, max),
+            };
+            return Err(error(format!(
+                "Function '{}' expects {}, got {}",
+                name,
+                expected,
+                call.args.len()
+            )));
+        }
+
+        for (index, (arg, &kind)) in call.args.iter().zip(params).enumerate() {
+            let arg_type = self.check_expression(arg)?;
+            let accepted = match kind {
+                "string" => arg_type == TypeId::String,
+                "integer" => PrimitiveType::is_integer_type_id(arg_type),
+                "map" => matches!(arg_type, TypeId::Map(_)),
+                _ => arg_type == sink,
+            };
+            if !accepted && arg_type != TypeId::Any {
+                return Err(error(format!(
+                    "Argument {} to function '{}': expected {}, got {}",
+                    index + 1,
+                    name,
+                    kind,
+                    self.type_name_for_error(arg_type)
+                )));
+            }
+        }
+        if function == "setLevel" {
+            if let Expression::Literal(LiteralExpr { value: LiteralValue::String(level), .. }) = &call.args[0] {
+                crate::std::log::Level::parse(level)
+                    .map_err(|message| error(format!("{} in call to '{}'", message, name)))?;
+            }
+        }
+
+        Ok(return_type)
+    }
+
+    /// Type check a std/schedule call; literal cron expressions are parsed at compile time
+    fn check_std_schedule_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+        let [schedule, every] = [1040, 1041].map(TypeId::Struct);
+        let (kind, return_type) = match function {
+            "cron" => ("string", schedule),
+            "every" => ("integer", every),
+            _ => return Err(error(format!("Unknown function '{}' in std/schedule", function))),
+        };
+        if call.args.len() != 1 {
+            return Err(error(format!(
+                "Function '{}' expects 1 argument, got {}",
+                name,
+                call.args.len()
+            )));
+        }
+
+        let arg_type = self.check_expression(&call.args[0])?;
+        let accepted = match kind {
+            "string" => arg_type == TypeId::String,
+            _ => PrimitiveType::is_integer_type_id(arg_type),
+        };
+        if !accepted && arg_type != TypeId::Any {
+            return Err(error(format!(
+                "Argument 1 to function '{}': expected {}, got {}",
+                name,
+                kind,
+                self.type_name_for_error(arg_type)
+            )));
+        }
+        match &call.args[0] {
+            Expression::Literal(LiteralExpr { value: LiteralValue::String(expression), .. }) => {
+                crate::std::schedule::Cron::parse(expression)
+                    .map_err(|message| error(format!("{} in call to '{}'", message, name)))?;
+            }
+            Expression::Literal(LiteralExpr { value: LiteralValue::Integer(count), .. }) if *count <= 0 => {
+                return Err(error(format!("The count passed to '{}' must be positive", name)));
+            }
+            _ => {}
+        }
+
+        Ok(return_type)
+    }
+
+    /// The checker's type for a kind of std/os parameter or result
+    fn os_kind_type(&mut self, kind: crate::std::os::Kind) -> TypeId {
+        use crate::std::os::Kind;
+        match kind {
+            Kind::String => TypeId::String,
+            Kind::Int32 => TypeId::Int32,
+            Kind::Int64 => TypeId::Int64,
+            Kind::Strings => TypeId::Slice(self.type_registry.register_slice_type(TypeId::String)),
+            Kind::StringMap => TypeId::Map(self.type_registry.register_map_type(TypeId::String, TypeId::String)),
+            Kind::Function => TypeId::Function(0),
+            Kind::Channel => TypeId::Channel(self.type_registry.register_channel_type(ChannelTypeInfo {
+                element_type: TypeId::String,
+                direction: crate::types::composite::ChannelDirection::ReceiveOnly,
+                buffered: true,
+                capacity: Some(1),
+            })),
+            Kind::GoroutineStats => {
+                let usage = TypeId::Map(self.type_registry.register_map_type(TypeId::String, TypeId::Int64));
+                TypeId::Slice(self.type_registry.register_slice_type(usage))
+            }
+            Kind::Void => TypeId::Void,
+        }
+    }
+
+    /// Type check a std/os call against the module's signatures
+    fn check_std_os_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        use crate::std::os::Kind;
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+        let signature = crate::std::os::lookup(function)
+            .ok_or_else(|| error(format!("Unknown function '{}' in std/os", function)))?;
+        let (required, params) = (signature.required, signature.params);
+        if call.args.len() < required || call.args.len() > params.len() {
+            let expected = match (required, params.len()) {
+                (1, 1) => "1 argument".to_string(),
+                (min, max) if min == max => format!("{} arguments", min),
+                (min, max) => format!("{} to {} arguments", min, max),
+            };
+            return Err(error(format!(
+                "Function '{}' expects {}, got {}",
+                name,
+                expected,
+                call.args.len()
+            )));
+        }
+
+        for (index, (arg, &kind)) in call.args.iter().zip(params).enumerate() {
+            let arg_type = self.check_expression(arg)?;
+            let accepted = match kind {
+                Kind::Int32 | Kind::Int64 => PrimitiveType::is_integer_type_id(arg_type),
+                Kind::Function => matches!(arg_type, TypeId::Function(_)),
+                _ => arg_type == self.os_kind_type(kind),
+            };
+            if !accepted && arg_type != TypeId::Any {
+                return Err(error(format!(
+                    "Argument {} to function '{}': expected {}, got {}",
+                    index + 1,
+                    name,
+                    kind,
+                    self.type_name_for_error(arg_type)
+                )));
+            }
+        }
+        if function == "signal" {
+            if let Expression::Literal(LiteralExpr { value: LiteralValue::String(signal), .. }) = &call.args[0] {
+                if !crate::std::os::SIGNALS.iter().any(|&(known, _)| known == signal) {
+                    return Err(error(format!("Unknown signal '{}' in call to '{}'", signal, name)));
+                }
+            }
+        }
+
+        Ok(self.os_kind_type(signature.returns))
+    }
+
+    /// Type check a std/regex call; literal patterns are compiled at compile time
+    fn check_std_regex_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+        if function != "compile" {
+            return Err(error(format!("Unknown function '{}' in std/regex", function)));
+        }
+        if call.args.len() != 1 {
+            return Err(error(format!(
+                "Function '{}' expects 1 argument, got {}",
+                name,
+                call.args.len()
+            )));
+        }
+
+        let pattern_type = self.check_expression(&call.args[0])?;
+        if pattern_type != TypeId::String && pattern_type != TypeId::Any {
+            return Err(error(format!(
+                "Argument 1 to function '{}': expected string, got {}",
+                name,
+                self.type_name_for_error(pattern_type)
+            )));
+        }
+        if let Expression::Literal(LiteralExpr { value: LiteralValue::String(pattern), .. }) = &call.args[0] {
+            if let Err(e) = crate::std::regex::compile(pattern) {
+                return Err(error(format!("Invalid pattern in call to '{}': {}", name, e)));
+            }
+        }
+
+        Ok(self.result_type_id(TypeId::Struct(1027), TypeId::String))
+    }
+
+    /// Type check a std/fs call; `join` takes any number of path components
+    fn check_std_fs_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        use crate::std::fs::OPEN_MODES;
+
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+        let string_result = self.result_type_id(TypeId::String, TypeId::String);
+        let unit_result = self.result_type_id(TypeId::Void, TypeId::String);
+
+        // Expected argument kinds and the result type; data is a string or byte slice
+        let (params, return_type): (&[&str], TypeId) = match function {
+            "open" => (&["string", "string"], self.result_type_id(TypeId::Struct(1018), TypeId::String)),
+            "readFile" | "absPath" => (&["string"], string_result),
+            "writeFile" | "appendFile" => (&["string", "data"], unit_result),
+            "readDir" => {
+                let names = TypeId::Array(self.type_registry.register_array_type(TypeId::String));
+                (&["string"], self.result_type_id(names, TypeId::String))
+            }
+            "mkdirAll" | "remove" | "removeAll" => (&["string"], unit_result),
+            "copy" => (&["string", "string"], self.result_type_id(TypeId::Int64, TypeId::String)),
+            "metadata" => (&["string"], self.result_type_id(TypeId::Struct(1017), TypeId::String)),
+            "exists" => (&["string"], TypeId::Bool),
+            "baseName" | "dirName" | "extension" => (&["string"], TypeId::String),
+            "join" => (&[], TypeId::String),
+            _ => return Err(error(format!("Unknown function '{}' in std/fs", function))),
+        };
+        let arity_ok = if function == "join" { !call.args.is_empty() } else { call.args.len() == params.len() };
+        if !arity_ok {
+            let expected = if function == "join" { "at least 1".to_string() } else { params.len().to_string() };
+            return Err(error(format!(
+                "Function '{}' expects {} argument{}, got {}",
+                name,
+                expected,
+                if expected == "1" { "" } else { "s" },
+                call.args.len()
+            )));
+        }
+
+        for (index, arg) in call.args.iter().enumerate() {
+            let arg_type = self.check_expression(arg)?;
+            let expected = params.get(index).copied().unwrap_or("string");
+            let accepted = arg_type == TypeId::String
+                || expected == "data"
+                    && matches!(arg_type, TypeId::Array(_) | TypeId::Slice(_))
+                    && self.type_registry.get_element_type(arg_type).is_none_or(|element| {
+                        element == TypeId::Any || PrimitiveType::is_integer_type_id(element)
+                    });
+            if !accepted && arg_type != TypeId::Any {
+                return Err(error(format!(
+                    "Argument {} to function '{}': expected {}, got {}",
+                    index + 1,
+                    name,
+                    if expected == "data" { "string or byte slice" } else { expected },
+                    self.type_name_for_error(arg_type)
+                )));
+            }
+        }
+        if function == "open" {
+            if let Expression::Literal(LiteralExpr { value: LiteralValue::String(mode), .. }) = &call.args[1] {
+                if !OPEN_MODES.contains(&mode.as_str()) {
+                    return Err(error(format!(
+                        "Unknown mode '{}' in call to '{}', expected one of {}",
+                        mode,
+                        name,
+                        OPEN_MODES.join(", ")
+                    )));
+                }
+            }
+        }
+
+        Ok(return_type)
+    }
+
+    /// Type check a std/process call: a program name and an array of string arguments
+    fn check_std_process_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+        if call.args.len() != 2 {
+            return Err(error(format!("Function '{}' expects 2 arguments, got {}", name, call.args.len())));
+        }
+
+        let program_type = self.check_expression(&call.args[0])?;
+        if program_type != TypeId::String && program_type != TypeId::Any {
+            return Err(error(format!(
+                "Argument 1 to function '{}': expected string, got {}",
+                name,
+                self.type_name_for_error(program_type)
+            )));
+        }
+        let args_type = self.check_expression(&call.args[1])?;
+        let accepted = matches!(args_type, TypeId::Array(_) | TypeId::Slice(_))
+            && self
+                .type_registry
+                .get_element_type(args_type)
+                .is_none_or(|element| element == TypeId::String || element == TypeId::Any);
+        if !accepted && args_type != TypeId::Any {
+            return Err(error(format!(
+                "Argument 2 to function '{}': expected array of strings, got {}",
+                name,
+                self.type_name_for_error(args_type)
+            )));
+        }
+
+        Ok(match function {
+            "exec" => self.result_type_id(TypeId::Struct(1021), TypeId::String),
+            _ => TypeId::Struct(1019),
+        })
+    }
+
+    /// Type check a std/json, std/toml or std/yaml call. `decode<T>(text)` and
+    /// `parse<T>(text)` return `Result<T, string>`, where `parse` decodes into
+    /// maps and arrays without a type argument; `encode(value)` and
+    /// `stringify(value)` return `Result<string, string>`.
+    fn check_std_data_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+        if call.args.len() != 1 {
+            return Err(error(format!("Function '{}' expects 1 argument, got {}", name, call.args.len())));
+        }
+        let arg_type = self.check_expression(&call.args[0])?;
+
+        if function == "encode" || function == "stringify" {
+            if !call.type_args.is_empty() {
+                return Err(error(format!("Function '{}' is not generic", name)));
+            }
+            return Ok(self.result_type_id(TypeId::String, TypeId::String));
+        }
+
+        if call.type_args.len() > 1 || (call.type_args.is_empty() && function == "decode") {
+            return Err(error(format!(
+                "Function '{}' expects 1 type argument, the type to decode into, got {}",
+                name,
+                call.type_args.len()
+            )));
+        }
+        if arg_type != TypeId::String && arg_type != TypeId::Any {
+            return Err(error(format!(
+                "Argument 1 to function '{}': expected string, got {}",
+                name,
+                self.type_name_for_error(arg_type)
+            )));
+        }
+        let target = match call.type_args.first() {
+            Some(type_arg) => self.ast_type_to_type_id(type_arg),
+            None => TypeId::Any,
+        };
+        Ok(self.result_type_id(target, TypeId::String))
+    }
+
+    /// Type check a std/binary call; literal pack formats fix the values `pack` takes
+    fn check_std_binary_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        use crate::std::binary::{accessor, Field, Format};
+
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+        let field_type = |field: Field| match field {
+            Field::Int { size: 1, signed: true } => TypeId::Int8,
+            Field::Int { size: 2, signed: true } => TypeId::Int16,
+            Field::Int { size: 4, signed: true } => TypeId::Int32,
+            Field::Int { signed: true, .. } => TypeId::Int64,
+            Field::Int { size: 1, .. } => TypeId::UInt8,
+            Field::Int { size: 2, .. } => TypeId::UInt16,
+            Field::Int { size: 4, .. } => TypeId::UInt32,
+            Field::Int { .. } => TypeId::UInt64,
+            Field::Float { size: 4 } => TypeId::Float32,
+            Field::Float { .. } => TypeId::Float64,
+            Field::Bool => TypeId::Bool,
+            Field::Padding => TypeId::Void,
+            Field::String(_) => TypeId::String,
+        };
+        let byte_slice = TypeId::Slice(self.type_registry.register_slice_type(TypeId::UInt8));
+
+        // Expected argument kinds (the optional ones last) and the result type
+        let (params, required, return_type): (Vec<&str>, usize, TypeId) = match function {
+            "pack" => (vec!["string"], 1, byte_slice),
+            "unpack" => {
+                let values = TypeId::Array(self.type_registry.register_array_type(TypeId::Any));
+                (vec!["string", "bytes", "integer"], 2, values)
+            }
+            "packedSize" => (vec!["string"], 1, TypeId::Int32),
+            "appendUvarint" | "appendVarint" => (vec!["bytes", "integer"], 2, byte_slice),
+            "readUvarint" | "readVarint" => {
+                let value = if function == "readUvarint" { TypeId::UInt64 } else { TypeId::Int64 };
+                let tuple = self.type_registry.register_tuple_type(vec![value, TypeId::Int32]);
+                (vec!["bytes", "integer"], 2, TypeId::Tuple(tuple))
+            }
+            _ => match accessor(function) {
+                Some(accessor) if accessor.write => {
+                    let value = if matches!(accessor.field, Field::Float { .. }) { "number" } else { "integer" };
+                    (vec!["bytes", "integer", value], 3, byte_slice)
+                }
+                Some(accessor) => (vec!["bytes", "integer"], 2, field_type(accessor.field)),
+                None => return Err(error(format!("Unknown function '{}' in std/binary", function))),
+            },
+        };
+        let max_args = if function == "pack" { usize::MAX } else { params.len() };
+        if call.args.len() < required || call.args.len() > max_args {
+            let expected = if function == "pack" {
+                "at least 1".to_string()
+            } else if required == params.len() {
+                required.to_string()
+            } else {
+                format!("{} to {}", required, params.len())
+            };
+            return Err(error(format!(
+                "Function '{}' expects {} argument{}, got {}",
+                name,
+                expected,
+                if expected == "1" { "" } else { "s" },
+                call.args.len()
+            )));
+        }
+
+        let mut arg_types = Vec::with_capacity(call.args.len());
+        for arg in &call.args {
+            arg_types.push(self.check_expression(arg)?);
+        }
+        for (index, (&arg_type, expected)) in arg_types.iter().zip(params.iter()).enumerate() {
+            let accepted = match *expected {
+                "string" => arg_type == TypeId::String,
+                "bytes" => {
+                    matches!(arg_type, TypeId::Array(_) | TypeId::Slice(_))
+                        && self.type_registry.get_element_type(arg_type).is_none_or(|element| {
+                            element == TypeId::Any || PrimitiveType::is_integer_type_id(element)
+                        })
+                }
+                "integer" => PrimitiveType::is_integer_type_id(arg_type),
+                _ => PrimitiveType::is_numeric_type_id(arg_type),
+            };
+            if !accepted && arg_type != TypeId::Any {
+                return Err(error(format!(
+                    "Argument {} to function '{}': expected {}, got {}",
+                    index + 1,
+                    name,
+                    if *expected == "bytes" { "byte slice" } else { expected },
+                    self.type_name_for_error(arg_type)
+                )));
+            }
+        }
+
+        // A literal format is parsed now, and for `pack` fixes the values that follow it
+        if matches!(function, "pack" | "unpack" | "packedSize") {
+            if let Expression::Literal(LiteralExpr { value: LiteralValue::String(source), .. }) = &call.args[0] {
+                let format = Format::parse(source)
+                    .map_err(|message| error(format!("Invalid format in call to '{}': {}", name, message)))?;
+                if function == "pack" {
+                    let fields: Vec<Field> =
+                        format.fields.iter().copied().filter(|field| *field != Field::Padding).collect();
+                    if fields.len() != arg_types.len() - 1 {
+                        return Err(error(format!(
+                            "Format \"{}\" packs {} value{}, got {}",
+                            source,
+                            fields.len(),
+                            if fields.len() == 1 { "" } else { "s" },
+                            arg_types.len() - 1
+                        )));
+                    }
+                    for (index, (field, &arg_type)) in fields.into_iter().zip(&arg_types[1..]).enumerate() {
+                        let accepted = match field {
+                            Field::Int { .. } => PrimitiveType::is_integer_type_id(arg_type),
+                            Field::Float { .. } => PrimitiveType::is_numeric_type_id(arg_type),
+                            _ => arg_type == field_type(field),
+                        };
+                        if !accepted && arg_type != TypeId::Any {
+                            return Err(error(format!(
+                                "Argument {} to function '{}': expected {}, got {}",
+                                index + 2,
+                                name,
+                                field.type_name(),
+                                self.type_name_for_error(arg_type)
+                            )));
+                        }
+                    }
                 }
+            }
+        }
 
-                // Check if it's in the symbol table (could be imported)
-                self.lookup_symbol(type_name).is_some()
+        Ok(return_type)
+    }
+
+    /// Type check a std/collections constructor; the type arguments default to `any`
+    fn check_std_collections_call(&mut self, name: &str, function: &str, call: &CallExpr) -> Result<TypeId> {
+        use crate::std::collections::{type_param_count, DEQUE, LINKED_MAP, LINKED_SET, ORDERED_MAP, PRIORITY_QUEUE};
+
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+
+        let (type_name, max_args) = match function {
+            "newDeque" => (DEQUE, 0),
+            "newPriorityQueue" => (PRIORITY_QUEUE, 1),
+            "newLinkedMap" => (LINKED_MAP, 0),
+            "newLinkedSet" => (LINKED_SET, 0),
+            _ => (ORDERED_MAP, 0),
+        };
+        let param_count = type_param_count(type_name);
+        if !call.type_args.is_empty() && call.type_args.len() != param_count {
+            return Err(error(format!(
+                "Function '{}' expects {} type argument{}, got {}",
+                name,
+                param_count,
+                if param_count == 1 { "" } else { "s" },
+                call.type_args.len()
+            )));
+        }
+        if call.args.len() > max_args {
+            let expected = if max_args == 0 { "0".to_string() } else { format!("0 to {}", max_args) };
+            return Err(error(format!(
+                "Function '{}' expects {} arguments, got {}",
+                name,
+                expected,
+                call.args.len()
+            )));
+        }
+
+        let mut type_args = Vec::with_capacity(param_count);
+        for type_arg in &call.type_args {
+            type_args.push(self.ast_type_to_type_id(type_arg));
+        }
+        type_args.resize(param_count, TypeId::Any);
+
+        // The priority queue comparator reports whether its first argument comes out first
+        if let Some(comparator) = call.args.first() {
+            let comparator_type = self.check_expression(comparator)?;
+            if !matches!(comparator_type, TypeId::Function(_) | TypeId::Any) {
+                return Err(error(format!(
+                    "Argument 1 to function '{}': expected a comparator function, got {}",
+                    name,
+                    self.type_name_for_error(comparator_type)
+                )));
             }
         }
+
+        self.add_std_collections_types();
+        Ok(self.intern_struct_instance(type_name, type_args))
     }
 
-    /// Type check a binary expression
-    fn check_binary_expression(&mut self, bin: &BinaryExpr) -> Result<TypeId> {
-        let left_type = self.check_expression(&bin.left)?;
-        let right_type = self.check_expression(&bin.right)?;
+    /// Type check a printf call; a literal format string fixes the argument count and types
+    fn check_printf_call(&mut self, call: &CallExpr) -> Result<TypeId> {
+        use crate::std::fmt::{parse_printf, PrintfArg, PrintfPiece};
 
-        let op_str = match bin.operator {
-            BinaryOperator::Add => "+",
-            BinaryOperator::Subtract => "-",
-            BinaryOperator::Multiply => "*",
-            BinaryOperator::Divide => "/",
-            BinaryOperator::Modulo => "%",
-            BinaryOperator::Power => "**",
-            BinaryOperator::Equal => "==",
-            BinaryOperator::NotEqual => "!=",
-            BinaryOperator::Less => "<",
-            BinaryOperator::Greater => ">",
-            BinaryOperator::LessEqual => "<=",
-            BinaryOperator::GreaterEqual => ">=",
-            BinaryOperator::And => "and",
-            BinaryOperator::Or => "or",
-            _ => "unknown",
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
         };
 
-        PrimitiveType::binary_operation_result_type(left_type, right_type, op_str).map_err(
-            |mut e| {
-                if let BuluError::TypeError {
-                    ref mut stack,
-                    file: None,
-                    ref mut line,
-                    ref mut column,
-                    ..
-                } = e
-                {
-                    if stack.is_empty() {
-                        *line = bin.position.line;
-                        *column = bin.position.column;
-                    }
-                }
-                e
-            },
-        )
+        if call.args.is_empty() {
+            return Err(error("printf() expects at least 1 argument (format string)".to_string()));
+        }
+
+        let mut arg_types = Vec::with_capacity(call.args.len());
+        for arg in &call.args {
+            arg_types.push(self.check_expression(arg)?);
+        }
+        if !self.is_type_compatible(arg_types[0], TypeId::String) {
+            return Err(error(format!(
+                "Argument 1 to function 'printf': expected string, got {}",
+                self.type_name_for_error(arg_types[0])
+            )));
+        }
+
+        let Expression::Literal(LiteralExpr { value: LiteralValue::String(format), .. }) = &call.args[0] else {
+            return Ok(TypeId::Any);
+        };
+        let specs: Vec<_> = parse_printf(format)
+            .map_err(|e| error(format!("Invalid format string in call to 'printf': {}", e)))?
+            .into_iter()
+            .filter_map(|piece| match piece {
+                PrintfPiece::Spec(spec) => Some(spec),
+                PrintfPiece::Literal(_) => None,
+            })
+            .collect();
+
+        if specs.len() != call.args.len() - 1 {
+            return Err(error(format!(
+                "Format string in call to 'printf' expects {} arguments, got {}",
+                specs.len(),
+                call.args.len() - 1
+            )));
+        }
+
+        for (index, (spec, &arg_type)) in specs.iter().zip(&arg_types[1..]).enumerate() {
+            let accepted = match spec.arg_kind() {
+                PrintfArg::Integer => PrimitiveType::is_integer_type_id(arg_type),
+                PrintfArg::Float => PrimitiveType::is_numeric_type_id(arg_type),
+                PrintfArg::String => arg_type == TypeId::String,
+                PrintfArg::Char => arg_type == TypeId::Char || PrimitiveType::is_integer_type_id(arg_type),
+                PrintfArg::Bool => arg_type == TypeId::Bool,
+                PrintfArg::Any => true,
+            };
+            if !accepted && arg_type != TypeId::Any {
+                return Err(error(format!(
+                    "Argument {} to function 'printf': %{} expects {}, got {}",
+                    index + 2,
+                    spec.verb,
+                    spec.arg_kind().description(),
+                    self.type_name_for_error(arg_type)
+                )));
+            }
+        }
+
+        Ok(TypeId::Any)
     }
 
-    /// Type check a unary expression
-    fn check_unary_expression(&mut self, unary: &UnaryExpr) -> Result<TypeId> {
-        let operand_type = self.check_expression(&unary.operand)?;
+    /// Type check `make(chan T)` or `make(chan T, capacity)`. A literal capacity is
+    /// recorded in the channel type; capacity 0 makes an unbuffered channel.
+    fn check_make_channel(&mut self, call: &CallExpr, element_name: &str) -> Result<TypeId> {
+        if call.args.len() > 2 {
+            return Err(BuluError::TypeError { stack: Vec::new(),
+                file: None,
+                message: format!("make() of a channel expects at most a capacity, got {} arguments", call.args.len()),
+                line: call.position.line,
+                column: call.position.column,
+            });
+        }
 
-        match unary.operator {
-            UnaryOperator::Plus | UnaryOperator::Minus => {
-                if PrimitiveType::is_numeric_type_id(operand_type) {
-                    Ok(operand_type)
-                } else {
-                    Err(BuluError::TypeError { stack: Vec::new(),
-                        file: None,
-                        message: format!(
-                            "Unary {} operator requires numeric operand, got {}",
-                            match unary.operator {
-                                UnaryOperator::Plus => "+",
-                                UnaryOperator::Minus => "-",
-                                _ => "unknown",
-                            },
-                            PrimitiveType::type_name(operand_type)
-                        ),
-                        line: unary.position.line,
-                        column: unary.position.column,
-                    })
+        let size = call.args.get(1).and_then(literal_value);
+        let (buffered, capacity) = match (call.args.get(1), size) {
+            (None, _) => (false, None),
+            (Some(arg), Some(ConstValue::Integer(size))) => {
+                match usize::try_from(size) {
+                    Ok(0) => (false, None),
+                    Ok(size) => (true, Some(size)),
+                    Err(_) => {
+                        let position = arg.position();
+                        return Err(BuluError::TypeError { stack: Vec::new(),
+                            file: None,
+                            message: format!("Channel capacity cannot be negative, got {}", size),
+                            line: position.line,
+                            column: position.column,
+                        });
+                    }
                 }
             }
-            UnaryOperator::Not => {
-                if operand_type == TypeId::Bool {
-                    Ok(TypeId::Bool)
-                } else {
-                    Err(BuluError::TypeError { stack: Vec::new(),
+            (Some(arg), _) => {
+                let arg_type = self.check_expression(arg)?;
+                if !matches!(
+                    arg_type,
+                    TypeId::Int32 | TypeId::Int64 | TypeId::UInt32 | TypeId::UInt64 | TypeId::Any
+                ) {
+                    return Err(BuluError::TypeError { stack: Vec::new(),
                         file: None,
-                        message: format!(
-                            "Unary not operator requires bool operand, got {}",
-                            PrimitiveType::type_name(operand_type)
-                        ),
-                        line: unary.position.line,
-                        column: unary.position.column,
-                    })
+                        message: "make() size/capacity arguments must be integers".to_string(),
+                        line: call.position.line,
+                        column: call.position.column,
+                    });
                 }
+                // Only known when the program runs
+                (true, None)
             }
-            _ => Ok(operand_type), // For other operators, assume same type
-        }
+        };
+
+        let element_type = match PrimitiveType::from_str(element_name) {
+            Some(primitive) => primitive.to_type_id(),
+            None => match self.ast_type_to_type_id(&Type::Named(element_name.to_string())) {
+                TypeId::Unknown => TypeId::Any,
+                type_id => type_id,
+            },
+        };
+        let channel_id = self.type_registry.register_channel_type(ChannelTypeInfo {
+            element_type,
+            direction: crate::types::composite::ChannelDirection::Bidirectional,
+            buffered,
+            capacity,
+        });
+        Ok(TypeId::Channel(channel_id))
     }
 
     /// Type check a function call expression
@@ -1582,7 +4602,7 @@ impl TypeChecker {
             Expression::Identifier(ident) => {
                 // Handle make built-in function FIRST (before symbol lookup)
 
-                if ident.name == "make" {
+                if ident.name == "make" && self.builtin_in_scope("make") {
                     // make() takes 1-3 arguments depending on type
                     if call.args.is_empty() || call.args.len() > 3 {
                         return Err(BuluError::TypeError { stack: Vec::new(),
@@ -1600,6 +4620,11 @@ impl TypeChecker {
                     // For make(), the first argument can be a type identifier
                     match &call.args[0] {
                         Expression::Identifier(type_ident) => {
+                            // make(chan T) and make(chan T, capacity)
+                            if let Some(element_name) = type_ident.name.strip_prefix("chan_") {
+                                return self.check_make_channel(call, element_name);
+                            }
+
                             // Check if it's a valid type for make()
                             let valid_types = vec![
                                 "int8",
@@ -1807,13 +4832,120 @@ impl TypeChecker {
                     }
                 }
 
+                // Generic functions are instantiated at each call
+                if let Some(decl) = self.generic_functions.get(&ident.name).cloned() {
+                    return self.check_generic_call(&decl, call);
+                }
+
+                // Constructors from std/collections take the element types as type arguments
+                if let Some(function) = self.std_collections_functions.get(&ident.name).cloned() {
+                    return self.check_std_collections_call(&ident.name, &function, call);
+                }
+
+                // Decoding functions from std/json, std/toml and std/yaml take the type to
+                // decode into as their type argument
+                if let Some(function) = self.std_data_functions.get(&ident.name).cloned() {
+                    return self.check_std_data_call(&ident.name, &function, call);
+                }
+
+                if !call.type_args.is_empty() {
+                    return Err(BuluError::TypeError { stack: Vec::new(),
+                        file: None,
+                        message: format!("Function '{}' is not generic", ident.name),
+                        line: call.position.line,
+                        column: call.position.column,
+                    });
+                }
+
+                // Functions from std/arrays have generic signatures
+                if let Some(function) = self.std_array_functions.get(&ident.name).cloned() {
+                    return self.check_std_arrays_call(&ident.name, &function, call);
+                }
+
+                // Functions from std/math are overloaded over the numeric types
+                if let Some(function) = self.std_math_functions.get(&ident.name).cloned() {
+                    return self.check_std_math_call(&ident.name, &function, call);
+                }
+
+                // Functions from std/fmt take optional decimals and locale arguments
+                if let Some(function) = self.std_fmt_functions.get(&ident.name).cloned() {
+                    return self.check_std_fmt_call(&ident.name, &function, call);
+                }
+
+                // Functions from std/template check literal templates at compile time
+                if let Some(function) = self.std_template_functions.get(&ident.name).cloned() {
+                    return self.check_std_template_call(&ident.name, &function, call);
+                }
+
+                // Functions from std/binary check literal pack formats at compile time
+                if let Some(function) = self.std_binary_functions.get(&ident.name).cloned() {
+                    return self.check_std_binary_call(&ident.name, &function, call);
+                }
+
+                // Functions from std/checksum take byte slices or strings and an optional seed
+                if let Some(function) = self.std_checksum_functions.get(&ident.name).cloned() {
+                    return self.check_std_checksum_call(&ident.name, &function, call);
+                }
+
+                // Functions from std/crypto take byte slices or strings and return byte slices
+                if let Some(function) = self.std_crypto_functions.get(&ident.name).cloned() {
+                    return self.check_std_crypto_call(&ident.name, &function, call);
+                }
+
+                // Functions from std/encoding encode byte slices and decode into Results
+                if let Some(function) = self.std_encoding_functions.get(&ident.name).cloned() {
+                    return self.check_std_encoding_call(&ident.name, &function, call);
+                }
+
+                // Functions from std/time take Durations and check literal patterns at compile time
+                if let Some(function) = self.std_time_functions.get(&ident.name).cloned() {
+                    return self.check_std_time_call(&ident.name, &function, call);
+                }
+
+                // Functions from std/os are checked against the module's signatures
+                if let Some(function) = self.std_os_functions.get(&ident.name).cloned() {
+                    return self.check_std_os_call(&ident.name, &function, call);
+                }
+
+                // Functions from std/log check literal level names at compile time
+                if let Some(function) = self.std_log_functions.get(&ident.name).cloned() {
+                    return self.check_std_log_call(&ident.name, &function, call);
+                }
+
+                // Functions from std/schedule parse literal cron expressions at compile time
+                if let Some(function) = self.std_schedule_functions.get(&ident.name).cloned() {
+                    return self.check_std_schedule_call(&ident.name, &function, call);
+                }
+
+                // Functions from std/regex compile literal patterns at compile time
+                if let Some(function) = self.std_regex_functions.get(&ident.name).cloned() {
+                    return self.check_std_regex_call(&ident.name, &function, call);
+                }
+
+                // Functions from std/fs return Results and check literal open modes
+                if let Some(function) = self.std_fs_functions.get(&ident.name).cloned() {
+                    return self.check_std_fs_call(&ident.name, &function, call);
+                }
+
+                // Functions from std/process take a program and its arguments
+                if let Some(function) = self.std_process_functions.get(&ident.name).cloned() {
+                    return self.check_std_process_call(&ident.name, &function, call);
+                }
+
+                // Functions from std/i18n check literal catalogs at compile time
+                if let Some(function) = self.std_i18n_functions.get(&ident.name).cloned() {
+                    return self.check_std_i18n_call(&ident.name, &function, call);
+                }
+
                 // Look up function in symbol table and clone the info to avoid borrow issues
                 let symbol_opt = self.lookup_symbol(&ident.name);
                 let func_info_opt = symbol_opt.and_then(|s| s.function_info.clone());
 
                 if let Some(func_info) = func_info_opt {
-                    // For built-in functions like print, we're more lenient
-                    if ident.name == "print" {
+                    // For built-in functions like print, we're more lenient; a
+                    // file without the prelude may declare functions of the same names
+                    let builtin = self.builtin_in_scope(&ident.name);
+                    if builtin && ident.name == "print" {
                         // Print can take any number of arguments of any type
                         for arg in &call.args {
                             self.check_expression(arg)?;
@@ -1822,7 +4954,7 @@ impl TypeChecker {
                     }
 
                     // Handle println built-in function
-                    if ident.name == "println" {
+                    if builtin && ident.name == "println" {
                         // println can take any number of arguments of any type
                         for arg in &call.args {
                             self.check_expression(arg)?;
@@ -1830,8 +4962,18 @@ impl TypeChecker {
                         return Ok(TypeId::Any); // println doesn't return a value
                     }
 
+                    // printf validates its arguments against a literal format string
+                    if builtin && ident.name == "printf" {
+                        return self.check_printf_call(call);
+                    }
+
+                    // Result and Option constructors take their type from the wrapped value
+                    if builtin && matches!(ident.name.as_str(), "Ok" | "Err" | "Some") {
+                        return self.check_wrapper_constructor(&ident.name, call);
+                    }
+
                     // Handle typeof built-in function
-                    if ident.name == "typeof" {
+                    if builtin && ident.name == "typeof" {
                         // typeof takes exactly one argument of any type
                         if call.args.len() != 1 {
                             return Err(BuluError::TypeError { stack: Vec::new(),
@@ -1849,6 +4991,16 @@ impl TypeChecker {
                         return Ok(TypeId::String); // typeof returns string
                     }
 
+                    // hash takes a hashable value and an optional integer seed
+                    if builtin && ident.name == "hash" {
+                        return self.check_hash_call(call);
+                    }
+
+                    // intrinsic takes a literal name and that intrinsic's arguments
+                    if builtin && ident.name == "intrinsic" {
+                        return self.check_intrinsic_call(call);
+                    }
+
                     // Check argument count
                     if call.args.len() != func_info.param_types.len() {
                         return Err(BuluError::TypeError { stack: Vec::new(),
@@ -1902,7 +5054,7 @@ impl TypeChecker {
                 } else {
                     return Err(BuluError::TypeError { stack: Vec::new(),
                         file: None,
-                        message: format!("Undefined function '{}'", ident.name),
+                        message: self.undefined_message("function", &ident.name),
                         line: call.position.line,
                         column: call.position.column,
                     });
@@ -1915,6 +5067,11 @@ impl TypeChecker {
                         .and_then(|s| s.module_exports.clone());
                     
                     if let Some(exports) = module_exports_opt {
+                        let qualified = format!("{}.{}", module_ident.name, member_access.member);
+                        if let Some(function) = self.std_os_functions.get(&qualified).cloned() {
+                            return self.check_std_os_call(&qualified, &function, call);
+                        }
+
                         // This is a module, look up the function in its exports
                         if let Some(export_symbol) = exports.get(&member_access.member) {
                             // Check arguments
@@ -1935,7 +5092,7 @@ impl TypeChecker {
                 if let Expression::Identifier(type_ident) = &*member_access.object {
                     let static_method_name =
                         format!("{}.{}", type_ident.name, member_access.member);
-                    if let Some(method_symbol) = self.lookup_symbol(&static_method_name).cloned() {
+                    if let Some(method_symbol) = self.lookup_shared_symbol(&static_method_name).cloned() {
                         // This is a static method call
                         // Check arguments
                         for arg in &call.args {
@@ -1958,8 +5115,12 @@ impl TypeChecker {
                     self.check_expression(arg)?;
                 }
 
-                // Look up the method in the object's type
-                let type_name = self.get_type_name_from_expression(&member_access.object)?;
+                // Look up the method in the object's type, or from its type when the
+                // expression has none, e.g. the result of a std function
+                let type_name = match self.get_type_name_from_expression(&member_access.object)? {
+                    Some(type_name) => Some(type_name),
+                    None => self.get_type_name_from_id(object_type).cloned(),
+                };
 
                 // For error messages, prefer the type name from expression over TypeId lookup
                 // This avoids confusion when TypeIds get reused or mismatched
@@ -1970,13 +5131,26 @@ impl TypeChecker {
                 };
 
                 match object_type {
+                    TypeId::Union(_) => {
+                        return self.union_member_type(object_type, &member_access.member, call.position);
+                    }
+                    TypeId::Struct(_) if self.collection_type_args(object_type).is_some() => {
+                        return self.check_collection_method_call(object_type, &member_access.member, call);
+                    }
+                    TypeId::Struct(_) if self.struct_instances.contains_key(&object_type) => {
+                        if let Some(return_type) =
+                            self.instance_member_type(object_type, &member_access.member)
+                        {
+                            return Ok(return_type);
+                        }
+                    }
                     TypeId::Struct(_) => {
                         if let Some(struct_name) = type_name.as_ref() {
                             // Check for std type methods first
                             let std_method_name =
                                 format!("{}.{}", struct_name, member_access.member);
                             if let Some(method_symbol) =
-                                self.lookup_symbol(&std_method_name).cloned()
+                                self.lookup_shared_symbol(&std_method_name).cloned()
                             {
                                 if let Some(function_info) = &method_symbol.function_info {
                                     return match &function_info.return_type {
@@ -2000,11 +5174,13 @@ impl TypeChecker {
                                 }
 
                                 // Check if struct implements any interface with this method
-                                let interfaces_clone = self.interfaces.clone();
-                                for (interface_name, interface_decl) in &interfaces_clone {
-                                    if self
-                                        .struct_implements_interface(&struct_name, interface_name)
-                                    {
+                                let interfaces: Vec<Rc<InterfaceDecl>> =
+                                    self.interfaces.values().cloned().collect();
+                                for interface_decl in &interfaces {
+                                    if self.struct_implements_interface(
+                                        struct_name,
+                                        &interface_decl.name,
+                                    ) {
                                         for method in &interface_decl.methods {
                                             if method.name == member_access.member {
                                                 return match &method.return_type {
@@ -2038,40 +5214,31 @@ impl TypeChecker {
                             }
                         }
                     }
-                    TypeId::Result(inner_type) => {
-                        // Handle methods on Result types
+                    TypeId::Result(_) | TypeId::Option(_) => {
+                        return self.check_wrapper_method_call(object_type, &member_access.member, call);
+                    }
+                    TypeId::Set(_) => {
+                        return self.check_set_method_call(object_type, &member_access.member, call);
+                    }
+                    TypeId::Map(_) => {
+                        // Deterministic views of a map, in ascending key order
+                        let (key_type, value_type) = self
+                            .type_registry
+                            .get_map_types(object_type)
+                            .unwrap_or((TypeId::Any, TypeId::Any));
                         match member_access.member.as_str() {
-                            "isError" => return Ok(TypeId::Bool),
-                            "error" => return Ok(TypeId::String),
-                            "unwrap" => {
-                                // Result.unwrap() returns the inner type T from Result<T>
-                                // Map the inner_type ID to the correct TypeId
-                                match inner_type {
-                                    1001 => return Ok(TypeId::Struct(1001)), // NetAddr
-                                    1002 => return Ok(TypeId::Struct(1002)), // Result (shouldn't happen)
-                                    1003 => return Ok(TypeId::Struct(1003)), // TcpServer
-                                    1004 => return Ok(TypeId::Struct(1004)), // TcpConnection
-                                    1005 => return Ok(TypeId::Struct(1005)), // UdpConnection
-                                    1012 => return Ok(TypeId::Int64),        // bytes read (int64)
-                                    1013 => return Ok(TypeId::Int64), // bytes written (int64)
-                                    _ => {
-                                        // For other IDs, check if it's a registered tuple type
-                                        if let Some(composite_type) =
-                                            self.type_registry.get_composite_type(inner_type)
-                                        {
-                                            match composite_type {
-                                                crate::types::composite::CompositeTypeId::Tuple(
-                                                    _,
-                                                ) => {
-                                                    return Ok(TypeId::Tuple(inner_type));
-                                                }
-                                                _ => {}
-                                            }
-                                        }
-                                        // Default to struct for unknown IDs
-                                        return Ok(TypeId::Struct(inner_type));
-                                    }
-                                }
+                            "sortedKeys" => {
+                                let array_id = self.type_registry.register_array_type(key_type);
+                                return Ok(TypeId::Array(array_id));
+                            }
+                            "entriesSorted" => {
+                                let entry_id = self
+                                    .type_registry
+                                    .register_tuple_type(vec![key_type, value_type]);
+                                let array_id = self
+                                    .type_registry
+                                    .register_array_type(TypeId::Tuple(entry_id));
+                                return Ok(TypeId::Array(array_id));
                             }
                             _ => {}
                         }
@@ -2165,10 +5332,16 @@ impl TypeChecker {
         
         let object_type = self.check_expression(&access.object)?;
 
-        // Get the type name from the object
-        let type_name = self.get_type_name_from_expression(&access.object)?;
+        // Get the type name from the object, or from its type when the expression has none
+        let type_name = match self.get_type_name_from_expression(&access.object)? {
+            Some(type_name) => Some(type_name),
+            None => self.get_type_name_from_id(object_type).cloned(),
+        };
 
         match object_type {
+            TypeId::Union(_) => {
+                return self.union_member_type(object_type, &access.member, access.position);
+            }
             TypeId::Interface(_) => {
                 // Look up the method in the specific interface
                 if let Some(interface_name) = type_name {
@@ -2184,9 +5357,23 @@ impl TypeChecker {
                     }
                 }
             }
+            TypeId::Struct(_) if self.struct_instances.contains_key(&object_type) => {
+                // Members of an instantiated generic struct use its type arguments
+                if let Some(member_type) = self.instance_member_type(object_type, &access.member) {
+                    return Ok(member_type);
+                }
+            }
             TypeId::Struct(_) => {
                 // Look up the field or method in the struct
                 if let Some(struct_name) = type_name {
+                    // Fields of std types are registered as `Type.field`
+                    if !self.structs.contains_key(&struct_name) {
+                        if let Some(field) = self.lookup_shared_symbol(&format!("{}.{}", struct_name, access.member)) {
+                            if field.function_info.is_none() {
+                                return Ok(field.type_id);
+                            }
+                        }
+                    }
                     if let Some(struct_decl) = self.structs.get(&struct_name).cloned() {
                         // First check struct fields
                         for field in &struct_decl.fields {
@@ -2206,9 +5393,10 @@ impl TypeChecker {
                         }
 
                         // Finally check if struct implements any interface with this method
-                        let interfaces_clone = self.interfaces.clone();
-                        for (interface_name, interface_decl) in &interfaces_clone {
-                            if self.struct_implements_interface(&struct_name, interface_name) {
+                        let interfaces: Vec<Rc<InterfaceDecl>> =
+                            self.interfaces.values().cloned().collect();
+                        for interface_decl in &interfaces {
+                            if self.struct_implements_interface(&struct_name, &interface_decl.name) {
                                 for method in &interface_decl.methods {
                                     if method.name == access.member {
                                         return match &method.return_type {
@@ -2224,19 +5412,9 @@ impl TypeChecker {
                     }
                 }
             }
-            TypeId::Result(_) => {
-                // Handle Result type methods
-                match access.member.as_str() {
-                    "isError" => return Ok(TypeId::Bool),
-                    "error" => return Ok(TypeId::String),
-                    "unwrap" => {
-                        // For Result<T>, unwrap() returns T
-                        if let TypeId::Result(inner_type_id) = object_type {
-                            return Ok(TypeId::Tuple(inner_type_id));
-                        }
-                        return Ok(TypeId::Void);
-                    }
-                    _ => {}
+            TypeId::Result(_) | TypeId::Option(_) => {
+                if let Some(member_type) = self.wrapper_accessor_type(object_type, &access.member) {
+                    return Ok(member_type);
                 }
             }
             _ => {
@@ -2509,24 +5687,217 @@ impl TypeChecker {
                 });
             }
 
-            if !PrimitiveType::is_assignable(entry_value_type, value_type) {
-                let value_type_name = self.type_registry.get_type_name(value_type);
-                let entry_value_type_name = self.type_registry.get_type_name(entry_value_type);
-                return Err(BuluError::TypeError { stack: Vec::new(),
-                    file: None,
-                    message: format!(
-                        "Map values must have the same type, expected {}, got {}",
-                        value_type_name, entry_value_type_name
-                    ),
-                    line: entry.position.line,
-                    column: entry.position.column,
-                });
+            if !PrimitiveType::is_assignable(entry_value_type, value_type) {
+                let value_type_name = self.type_registry.get_type_name(value_type);
+                let entry_value_type_name = self.type_registry.get_type_name(entry_value_type);
+                return Err(BuluError::TypeError { stack: Vec::new(),
+                    file: None,
+                    message: format!(
+                        "Map values must have the same type, expected {}, got {}",
+                        value_type_name, entry_value_type_name
+                    ),
+                    line: entry.position.line,
+                    column: entry.position.column,
+                });
+            }
+        }
+
+        // Register the map type and return it
+        let map_type_id = self.type_registry.register_map_type(key_type, value_type);
+        Ok(TypeId::Map(map_type_id))
+    }
+
+    /// Type check a set literal. The element type is the explicit type argument
+    /// (`set<T>{...}`) or else the type of the first element. Elements must be
+    /// hashable, so functions and channels are rejected.
+    fn check_set_expression(&mut self, set: &SetExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: set.position.line,
+            column: set.position.column,
+        };
+
+        let mut element_type = set.element_type.as_ref().map(|element_type| self.ast_type_to_type_id(element_type));
+        if let Some(declared) = element_type {
+            if matches!(declared, TypeId::Function(_) | TypeId::Channel(_)) {
+                return Err(error(format!(
+                    "Set elements must be hashable, got {}",
+                    self.type_registry.get_type_name(declared)
+                )));
+            }
+        }
+
+        for element in &set.elements {
+            let actual = self.check_expression(element)?;
+            if matches!(actual, TypeId::Function(_) | TypeId::Channel(_)) {
+                return Err(error(format!(
+                    "Set elements must be hashable, got {}",
+                    self.type_registry.get_type_name(actual)
+                )));
+            }
+            match element_type {
+                Some(expected) if !self.is_type_compatible(actual, expected) => {
+                    return Err(error(format!(
+                        "Set elements must have the same type, expected {}, got {}",
+                        self.type_registry.get_type_name(expected),
+                        self.type_registry.get_type_name(actual)
+                    )));
+                }
+                Some(_) => {}
+                None => element_type = Some(actual),
+            }
+        }
+
+        match element_type {
+            Some(element_type) => {
+                let set_id = self.type_registry.register_set_type(element_type);
+                Ok(TypeId::Set(set_id))
+            }
+            None => Ok(TypeId::Set(0)), // Empty set
+        }
+    }
+
+    /// Type check a method call on a set
+    fn check_set_method_call(&mut self, set_type: TypeId, method: &str, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+        let element_type = self
+            .type_registry
+            .get_set_element_type(set_type)
+            .unwrap_or(TypeId::Any);
+
+        let (expected, result) = match method {
+            "add" | "remove" | "contains" => (Some(element_type), TypeId::Bool),
+            "union" | "intersection" | "difference" => (Some(set_type), set_type),
+            "isSubset" => (Some(set_type), TypeId::Bool),
+            "len" => (None, TypeId::Int32),
+            "toArray" => {
+                let array_id = self.type_registry.register_array_type(element_type);
+                (None, TypeId::Array(array_id))
+            }
+            _ => {
+                return Err(error(format!(
+                    "Method '{}' not found on type {}",
+                    method,
+                    self.type_registry.get_type_name(set_type)
+                )))
+            }
+        };
+
+        let expected_count = usize::from(expected.is_some());
+        if call.args.len() != expected_count {
+            return Err(error(format!(
+                "Method '{}' expects {} argument{}, got {}",
+                method,
+                expected_count,
+                if expected_count == 1 { "" } else { "s" },
+                call.args.len()
+            )));
+        }
+        if let Some(expected) = expected {
+            let actual = self.check_expression(&call.args[0])?;
+            if !self.is_type_compatible(actual, expected) {
+                return Err(error(format!(
+                    "Argument 1 to method '{}': expected {}, got {}",
+                    method,
+                    self.type_registry.get_type_name(expected),
+                    self.type_registry.get_type_name(actual)
+                )));
             }
         }
+        Ok(result)
+    }
 
-        // Register the map type and return it
-        let map_type_id = self.type_registry.register_map_type(key_type, value_type);
-        Ok(TypeId::Map(map_type_id))
+    /// Type check a method call on a std/collections handle
+    fn check_collection_method_call(&mut self, handle_type: TypeId, method: &str, call: &CallExpr) -> Result<TypeId> {
+        use crate::std::collections::{DEQUE, LINKED_MAP, LINKED_SET, ORDERED_MAP, PRIORITY_QUEUE};
+
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+        let (name, type_args) = self.collection_type_args(handle_type).unwrap_or_default();
+        let element_type = type_args.first().copied().unwrap_or(TypeId::Any);
+        let value_type = type_args.get(1).copied().unwrap_or(TypeId::Any);
+
+        // (parameter types, return type) of the methods the handle type has
+        let signature: Option<(Vec<TypeId>, TypeId)> = match (name.as_str(), method) {
+            (_, "len") => Some((vec![], TypeId::Int32)),
+            (_, "isEmpty") => Some((vec![], TypeId::Bool)),
+            (_, "clear" | "free") => Some((vec![], TypeId::Void)),
+            (DEQUE, "pushBack" | "pushFront") => Some((vec![element_type], TypeId::Void)),
+            (DEQUE, "popBack" | "popFront" | "peekBack" | "peekFront") => {
+                Some((vec![], self.option_type_id(element_type)))
+            }
+            (DEQUE, "get") => Some((vec![TypeId::Int32], self.option_type_id(element_type))),
+            (ORDERED_MAP | LINKED_MAP, "set") => Some((vec![element_type, value_type], TypeId::Void)),
+            (ORDERED_MAP | LINKED_MAP, "get") => Some((vec![element_type], self.option_type_id(value_type))),
+            (ORDERED_MAP | LINKED_MAP | LINKED_SET, "has" | "delete") => Some((vec![element_type], TypeId::Bool)),
+            (ORDERED_MAP | LINKED_MAP, "firstKey" | "lastKey") => Some((vec![], self.option_type_id(element_type))),
+            (ORDERED_MAP, "floorKey" | "ceilingKey") => {
+                Some((vec![element_type], self.option_type_id(element_type)))
+            }
+            (ORDERED_MAP | LINKED_MAP, "keys") => {
+                Some((vec![], TypeId::Array(self.type_registry.register_array_type(element_type))))
+            }
+            (ORDERED_MAP | LINKED_MAP, "values") => {
+                Some((vec![], TypeId::Array(self.type_registry.register_array_type(value_type))))
+            }
+            (ORDERED_MAP | LINKED_MAP, "entries") => {
+                let entry = TypeId::Tuple(self.type_registry.register_tuple_type(vec![element_type, value_type]));
+                Some((vec![], TypeId::Array(self.type_registry.register_array_type(entry))))
+            }
+            (LINKED_SET, "add") => Some((vec![element_type], TypeId::Bool)),
+            // Set operations take and return sets of the same element type
+            (LINKED_SET, "union" | "intersection" | "difference") => Some((vec![handle_type], handle_type)),
+            (LINKED_SET, "isSubset") => Some((vec![handle_type], TypeId::Bool)),
+            (DEQUE | PRIORITY_QUEUE | LINKED_SET, "toArray") => {
+                Some((vec![], TypeId::Array(self.type_registry.register_array_type(element_type))))
+            }
+            (PRIORITY_QUEUE, "push") => Some((vec![element_type], TypeId::Void)),
+            (PRIORITY_QUEUE, "pop" | "peek") => Some((vec![], self.option_type_id(element_type))),
+            _ => None,
+        };
+        let Some((param_types, return_type)) = signature else {
+            return Err(error(format!(
+                "Method '{}' not found on type {}",
+                method,
+                self.type_name_for_error(handle_type)
+            )));
+        };
+
+        if call.args.len() != param_types.len() {
+            return Err(error(format!(
+                "Method '{}' expects {} argument{}, got {}",
+                method,
+                param_types.len(),
+                if param_types.len() == 1 { "" } else { "s" },
+                call.args.len()
+            )));
+        }
+        for (index, (arg, expected)) in call.args.iter().zip(param_types).enumerate() {
+            let actual = self.check_expression(arg)?;
+            if !self.is_type_compatible(actual, expected) {
+                return Err(error(format!(
+                    "Argument {} to method '{}': expected {}, got {}",
+                    index + 1,
+                    method,
+                    self.type_name_for_error(expected),
+                    self.type_name_for_error(actual)
+                )));
+            }
+        }
+        Ok(return_type)
     }
 
     /// Type check a struct literal expression
@@ -2536,75 +5907,153 @@ impl TypeChecker {
     ) -> Result<TypeId> {
         // Check if the struct type exists
         if let Some(struct_decl) = self.structs.get(&struct_lit.type_name).cloned() {
-            // Get or create the TypeId for this struct
-            let struct_type_id = self.get_or_create_named_type_id(&struct_lit.type_name, false);
-
-            // Verify that all required fields are provided and have correct types
-            for field in &struct_decl.fields {
-                let mut field_found = false;
+            // Get or create the TypeId for this struct; generic structs are instantiated
+            let (struct_type_id, bindings) = if struct_decl.type_params.is_empty() {
+                if !struct_lit.type_args.is_empty() {
+                    return Err(BuluError::TypeError { stack: Vec::new(),
+                        message: format!("Struct '{}' is not generic", struct_lit.type_name),
+                        line: struct_lit.position.line,
+                        column: struct_lit.position.column,
+                        file: None,
+                    });
+                }
+                let struct_type_id = self.get_or_create_named_type_id(&struct_lit.type_name, false);
+                (struct_type_id, HashMap::new())
+            } else {
+                self.instantiate_struct_literal(&struct_decl, struct_lit)?
+            };
+            self.type_param_bindings.push(bindings);
+            let result = self.check_struct_literal_fields(&struct_decl, struct_lit);
+            self.type_param_bindings.pop();
+            result?;
 
-                for field_init in &struct_lit.fields {
-                    if field_init.name == field.name {
-                        field_found = true;
+            Ok(struct_type_id)
+        } else {
+            Err(BuluError::TypeError { stack: Vec::new(),
+                message: format!("Unknown struct type '{}'", struct_lit.type_name),
+                line: struct_lit.position.line,
+                column: struct_lit.position.column,
+                file: None,
+            })
+        }
+    }
 
-                        // Check that the field value has the correct type
-                        let value_type = self.check_expression(&field_init.value)?;
-                        let expected_type = self.ast_type_to_type_id(&field.field_type);
+    /// Instantiate the generic struct of a struct literal, from its explicit type arguments
+    /// (`Box<string>{...}`) or from the types of the field values
+    fn instantiate_struct_literal(
+        &mut self,
+        struct_decl: &StructDecl,
+        struct_lit: &StructLiteralExpr,
+    ) -> Result<(TypeId, HashMap<String, TypeId>)> {
+        let names: Vec<String> = Self::generic_type_params(&struct_decl.type_params)
+            .into_iter()
+            .map(|param| param.name)
+            .collect();
 
-                        if !PrimitiveType::is_assignable(value_type, expected_type) {
-                            return Err(BuluError::TypeError { stack: Vec::new(),
-                                message: format!(
-                                    "Field '{}' expects type {}, got {}",
-                                    field.name,
-                                    PrimitiveType::type_name(expected_type),
-                                    PrimitiveType::type_name(value_type)
-                                ),
-                                line: field_init.position.line,
-                                column: field_init.position.column,
-                                file: None,
-                            });
-                        }
-                        break;
+        let type_args = if !struct_lit.type_args.is_empty() {
+            let mut type_args = Vec::with_capacity(struct_lit.type_args.len());
+            for type_arg in &struct_lit.type_args {
+                type_args.push(self.ast_type_to_type_id(type_arg));
+            }
+            type_args
+        } else {
+            let mut inferred = HashMap::new();
+            for field_init in &struct_lit.fields {
+                if let Some(field) = struct_decl.fields.iter().find(|f| f.name == field_init.name) {
+                    let value_type = self.check_expression(&field_init.value)?;
+                    self.infer_type_params(&field.field_type, value_type, &names, &mut inferred)
+                        .map_err(|message| BuluError::TypeError { stack: Vec::new(),
+                            message: format!("{} in struct '{}'", message, struct_decl.name),
+                            line: field_init.position.line,
+                            column: field_init.position.column,
+                            file: None,
+                        })?;
+                }
+            }
+            let mut type_args = Vec::with_capacity(names.len());
+            for name in &names {
+                match inferred.get(name) {
+                    Some(&inferred_type) => type_args.push(inferred_type),
+                    None => {
+                        return Err(BuluError::TypeError { stack: Vec::new(),
+                            message: format!(
+                                "Cannot infer type parameter '{}' of struct '{}'; write {}<...>{{...}}",
+                                name, struct_decl.name, struct_decl.name
+                            ),
+                            line: struct_lit.position.line,
+                            column: struct_lit.position.column,
+                            file: None,
+                        })
                     }
                 }
-
-                // Field not found is OK - it will get a default value
-                // We don't need to error here anymore
             }
+            type_args
+        };
+
+        let struct_type_id =
+            self.instantiate_generic_struct(&struct_decl.name, type_args.clone(), struct_lit.position)?;
+        let bindings = names.into_iter().zip(type_args).collect();
+        Ok((struct_type_id, bindings))
+    }
 
-            // Check for extra fields that don't exist in the struct
+    /// Check the field initializers of a struct literal against the struct declaration
+    fn check_struct_literal_fields(
+        &mut self,
+        struct_decl: &StructDecl,
+        struct_lit: &StructLiteralExpr,
+    ) -> Result<()> {
+        // Check the types of the fields provided; missing fields get their
+        // default values
+        for field in &struct_decl.fields {
             for field_init in &struct_lit.fields {
-                let mut field_exists = false;
+                if field_init.name == field.name {
+                    // Check that the field value has the correct type
+                    let value_type = self.check_expression(&field_init.value)?;
+                    let expected_type = self.ast_type_to_type_id(&field.field_type);
 
-                for field in &struct_decl.fields {
-                    if field.name == field_init.name {
-                        field_exists = true;
-                        break;
+                    if !self.is_type_compatible(value_type, expected_type) {
+                        return Err(BuluError::TypeError { stack: Vec::new(),
+                            message: format!(
+                                "Field '{}' expects type {}, got {}",
+                                field.name,
+                                self.type_name_for_error(expected_type),
+                                self.type_name_for_error(value_type)
+                            ),
+                            line: field_init.position.line,
+                            column: field_init.position.column,
+                            file: None,
+                        });
                     }
+                    break;
                 }
+            }
+        }
 
-                if !field_exists {
-                    return Err(BuluError::TypeError { stack: Vec::new(),
-                        message: format!(
-                            "Unknown field '{}' in struct '{}'",
-                            field_init.name, struct_lit.type_name
-                        ),
-                        line: field_init.position.line,
-                        column: field_init.position.column,
-                        file: None,
-                    });
+        // Check for extra fields that don't exist in the struct
+        for field_init in &struct_lit.fields {
+            let mut field_exists = false;
+
+            for field in &struct_decl.fields {
+                if field.name == field_init.name {
+                    field_exists = true;
+                    break;
                 }
             }
 
-            Ok(struct_type_id)
-        } else {
-            Err(BuluError::TypeError { stack: Vec::new(),
-                message: format!("Unknown struct type '{}'", struct_lit.type_name),
-                line: struct_lit.position.line,
-                column: struct_lit.position.column,
-                file: None,
-            })
+            if !field_exists {
+                return Err(BuluError::TypeError { stack: Vec::new(),
+                    message: format!(
+                        "Unknown field '{}' in struct '{}'",
+                        field_init.name, struct_lit.type_name
+                    ),
+                    line: field_init.position.line,
+                    column: field_init.position.column,
+                    file: None,
+                });
+            }
         }
+
+        Ok(())
     }
 
     /// Get the default value for a type
@@ -2785,9 +6234,59 @@ impl TypeChecker {
         }
     }
 
+    /// Type check `expr?`: the operand must be a Result whose error type fits the
+    /// Result returned by the enclosing function. The expression has the ok type.
+    fn check_propagate_expression(&mut self, propagate: &PropagateExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: propagate.position.line,
+            column: propagate.position.column,
+        };
+
+        let operand_type = self.check_expression(&propagate.expr)?;
+        if !matches!(operand_type, TypeId::Result(_)) {
+            return Err(error(format!(
+                "The '?' operator can only be applied to a Result, got {}",
+                self.type_name_for_error(operand_type)
+            )));
+        }
+        let (ok_type, error_type) = self.wrapper_types(operand_type);
+        let error_type = error_type.unwrap_or(TypeId::Any);
+
+        let return_type = match self.return_types.last() {
+            Some(Some(return_type @ TypeId::Result(_))) => *return_type,
+            Some(Some(return_type)) => {
+                return Err(error(format!(
+                    "The '?' operator can only be used in a function returning Result, not {}",
+                    self.type_name_for_error(*return_type)
+                )))
+            }
+            Some(None) => {
+                return Err(error(
+                    "The '?' operator can only be used in a function with a declared Result return type"
+                        .to_string(),
+                ))
+            }
+            None => return Err(error("The '?' operator can only be used inside a function".to_string())),
+        };
+        let (_, expected_error_type) = self.wrapper_types(return_type);
+        let expected_error_type = expected_error_type.unwrap_or(TypeId::Any);
+        if !self.is_type_compatible(error_type, expected_error_type) {
+            return Err(error(format!(
+                "The '?' operator cannot propagate error type {} from a function returning {}",
+                self.type_name_for_error(error_type),
+                self.type_name_for_error(return_type)
+            )));
+        }
+
+        Ok(ok_type)
+    }
+
     /// Enter a new scope
     fn enter_scope(&mut self) {
-        self.scopes.push(HashMap::new());
+        self.scopes.push();
     }
 
     /// Exit the current scope
@@ -2797,31 +6296,29 @@ impl TypeChecker {
 
     /// Add a symbol to the current scope
     fn add_symbol(&mut self, symbol: Symbol) -> Result<()> {
-        if let Some(current_scope) = self.scopes.last_mut() {
-            if current_scope.contains_key(&symbol.name) {
-                return Err(BuluError::TypeError { stack: Vec::new(),
-                    file: None,
-                    message: format!(
-                        "Variable '{}' is already defined in this scope",
-                        symbol.name
-                    ),
-                    line: symbol.position.line,
-                    column: symbol.position.column,
-                });
-            }
-            current_scope.insert(symbol.name.clone(), symbol);
+        if self.scopes.declared_in_current(&symbol.name) {
+            return Err(BuluError::TypeError { stack: Vec::new(),
+                file: None,
+                message: format!(
+                    "Variable '{}' is already defined in this scope",
+                    symbol.name
+                ),
+                line: symbol.position.line,
+                column: symbol.position.column,
+            });
         }
+        self.scopes.insert(symbol.name.clone(), Rc::new(symbol));
         Ok(())
     }
 
     /// Look up a symbol in the scope stack
     fn lookup_symbol(&self, name: &str) -> Option<&Symbol> {
-        for scope in self.scopes.iter().rev() {
-            if let Some(symbol) = scope.get(name) {
-                return Some(symbol);
-            }
-        }
-        None
+        self.lookup_shared_symbol(name).map(Rc::as_ref)
+    }
+
+    /// Look up a symbol, sharing it with the scope that declares it
+    fn lookup_shared_symbol(&self, name: &str) -> Option<&Rc<Symbol>> {
+        self.scopes.get(name)
     }
 
     /// Get all errors accumulated during type checking
@@ -2843,13 +6340,13 @@ impl TypeChecker {
                     Statement::StructDecl(struct_decl) if struct_decl.is_exported => {
                         // Add the struct declaration to our structs collection
                         self.structs
-                            .insert(struct_decl.name.clone(), struct_decl.clone());
+                            .insert(struct_decl.name.clone(), Rc::new(struct_decl.clone()));
                     }
                     Statement::Export(export_stmt) => {
                         // Check if this is an exported struct
                         if let Statement::StructDecl(struct_decl) = export_stmt.item.as_ref() {
                             self.structs
-                                .insert(struct_decl.name.clone(), struct_decl.clone());
+                                .insert(struct_decl.name.clone(), Rc::new(struct_decl.clone()));
                         }
                     }
                     _ => {}
@@ -2859,6 +6356,11 @@ impl TypeChecker {
 
         // Only import imported symbols (not local symbols, as they are handled by the TypeChecker itself)
         for (name, imported_symbol) in &symbol_table.imported_symbols {
+            // Builtins are added by `add_builtin_functions`; importing them keeps them in scope
+            if crate::std::builtin::is_module_path(&imported_symbol.module_path) {
+                self.builtin_imports.insert(imported_symbol.original_name.clone());
+                continue;
+            }
             let symbol = match imported_symbol.symbol_type {
                 crate::compiler::symbol_resolver::SymbolType::Function => {
                     let function_info =
@@ -2880,6 +6382,241 @@ impl TypeChecker {
                                 param_types,
                                 return_type,
                             })
+                        } else if imported_symbol.module_path == "std/arrays" || imported_symbol.module_path == "std.arrays" {
+                            // std/arrays functions are generic over the element type, so calls are
+                            // checked by `check_std_arrays_call`; only the arity is recorded here
+                            self.std_array_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            let arity = crate::std::arrays::arity(&imported_symbol.original_name).unwrap_or(0);
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any; arity],
+                                return_type: Some(TypeId::Any),
+                            })
+                        } else if imported_symbol.module_path == "std/math" || imported_symbol.module_path == "std.math" {
+                            // Calls are checked by `check_std_math_call`
+                            self.std_math_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            let arity = match imported_symbol.original_name.as_str() {
+                                "pow" | "hypot" | "min" | "max" | "gcd" | "lcm" => 2,
+                                "clamp" => 3,
+                                _ => 1,
+                            };
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any; arity],
+                                return_type: Some(TypeId::Any),
+                            })
+                        } else if imported_symbol.module_path == "std/fmt" || imported_symbol.module_path == "std.fmt" {
+                            // Calls are checked by `check_std_fmt_call`
+                            self.std_fmt_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any; 3],
+                                return_type: Some(TypeId::String),
+                            })
+                        } else if imported_symbol.module_path == "std/template" || imported_symbol.module_path == "std.template" {
+                            // Calls are checked by `check_std_template_call`
+                            self.std_template_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any; 3],
+                                return_type: Some(TypeId::String),
+                            })
+                        } else if imported_symbol.module_path == "std/sync" || imported_symbol.module_path == "std.sync" {
+                            // Constructors return handles whose methods come from `add_std_sync_types`
+                            self.add_std_sync_types();
+                            let handle_type = match imported_symbol.original_name.as_str() {
+                                "newWaitGroup" => crate::std::sync::WAIT_GROUP,
+                                "newMutex" => crate::std::sync::MUTEX,
+                                "newRwLock" => crate::std::sync::RW_LOCK,
+                                _ => crate::std::sync::ONCE,
+                            };
+                            Some(FunctionInfo {
+                                param_types: vec![],
+                                return_type: self.type_name_to_id.get(handle_type).copied(),
+                            })
+                        } else if imported_symbol.module_path == "std/context" || imported_symbol.module_path == "std.context" {
+                            // Constructors return a Context whose methods come from `add_std_context_types`
+                            self.add_std_context_types();
+                            let context_type = TypeId::Struct(1011);
+                            let param_types = match imported_symbol.original_name.as_str() {
+                                "withCancel" => vec![context_type],
+                                "withTimeout" | "withDeadline" => vec![context_type, TypeId::Int64],
+                                "withValue" => vec![context_type, TypeId::String, TypeId::Any],
+                                _ => vec![],
+                            };
+                            Some(FunctionInfo {
+                                param_types,
+                                return_type: Some(context_type),
+                            })
+                        } else if imported_symbol.module_path == "std/collections" || imported_symbol.module_path == "std.collections" {
+                            // Calls are checked by `check_std_collections_call`
+                            self.add_std_collections_types();
+                            self.std_collections_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![],
+                                return_type: Some(TypeId::Any),
+                            })
+                        } else if ["std/json", "std.json", "std/toml", "std.toml", "std/yaml", "std.yaml"]
+                            .contains(&imported_symbol.module_path.as_str())
+                        {
+                            // Calls are checked by `check_std_data_call`
+                            self.std_data_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any],
+                                return_type: Some(TypeId::Any),
+                            })
+                        } else if imported_symbol.module_path == "std/binary" || imported_symbol.module_path == "std.binary" {
+                            // Calls are checked by `check_std_binary_call`
+                            self.std_binary_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any; 3],
+                                return_type: Some(TypeId::Any),
+                            })
+                        } else if imported_symbol.module_path == "std/checksum" || imported_symbol.module_path == "std.checksum" {
+                            // Calls are checked by `check_std_checksum_call`
+                            self.add_std_checksum_types();
+                            self.std_checksum_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any; 2],
+                                return_type: Some(TypeId::Any),
+                            })
+                        } else if imported_symbol.module_path == "std/crypto" || imported_symbol.module_path == "std.crypto" {
+                            // Calls are checked by `check_std_crypto_call`
+                            self.std_crypto_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any; 4],
+                                return_type: Some(TypeId::Any),
+                            })
+                        } else if imported_symbol.module_path == "std/encoding" || imported_symbol.module_path == "std.encoding" {
+                            // Calls are checked by `check_std_encoding_call`
+                            self.std_encoding_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any],
+                                return_type: Some(TypeId::Any),
+                            })
+                        } else if (imported_symbol.module_path == "std/time" || imported_symbol.module_path == "std.time")
+                            && imported_symbol.original_name != "sleep"
+                        {
+                            // Calls are checked by `check_std_time_call`; values get
+                            // their methods from `add_std_time_types`
+                            self.add_std_time_types();
+                            self.std_time_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any; 3],
+                                return_type: Some(TypeId::Any),
+                            })
+                        } else if imported_symbol.module_path == "std/os" || imported_symbol.module_path == "std.os" {
+                            // Calls are checked by `check_std_os_call`
+                            self.std_os_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any; 2],
+                                return_type: Some(TypeId::Any),
+                            })
+                        } else if imported_symbol.module_path == "std/log" || imported_symbol.module_path == "std.log" {
+                            // Calls are checked by `check_std_log_call`; loggers and sinks
+                            // get their methods from `add_std_log_types`
+                            self.add_std_log_types();
+                            self.std_log_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any; 3],
+                                return_type: Some(TypeId::Any),
+                            })
+                        } else if imported_symbol.module_path == "std/schedule" || imported_symbol.module_path == "std.schedule" {
+                            // Calls are checked by `check_std_schedule_call`; schedules
+                            // get their methods from `add_std_schedule_types`
+                            self.add_std_schedule_types();
+                            self.std_schedule_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any],
+                                return_type: Some(TypeId::Any),
+                            })
+                        } else if imported_symbol.module_path == "std/regex" || imported_symbol.module_path == "std.regex" {
+                            // Calls are checked by `check_std_regex_call`; patterns get
+                            // their methods from `add_std_regex_types`
+                            self.add_std_regex_types();
+                            self.std_regex_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::String],
+                                return_type: Some(TypeId::Any),
+                            })
+                        } else if imported_symbol.module_path == "std/db" || imported_symbol.module_path == "std.db" {
+                            // open returns a DB whose methods come from `add_std_db_types`
+                            self.add_std_db_types();
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::String],
+                                return_type: Some(self.result_type_id(TypeId::Struct(1028), TypeId::String)),
+                            })
+                        } else if imported_symbol.module_path == "std/strings" || imported_symbol.module_path == "std.strings" {
+                            // newBuilder returns a Builder whose methods come from `add_std_strings_types`
+                            self.add_std_strings_types();
+                            let (param_types, return_type) = match imported_symbol.original_name.as_str() {
+                                "contains" => (vec![TypeId::String; 2], TypeId::Bool),
+                                "indexOf" | "lastIndexOf" | "count" => (vec![TypeId::String; 2], TypeId::Int32),
+                                "fromBytes" => {
+                                    let bytes = TypeId::Slice(self.type_registry.register_slice_type(TypeId::UInt8));
+                                    (vec![bytes], self.result_type_id(TypeId::String, TypeId::String))
+                                }
+                                _ => (vec![], TypeId::Struct(1016)),
+                            };
+                            Some(FunctionInfo {
+                                param_types,
+                                return_type: Some(return_type),
+                            })
+                        } else if imported_symbol.module_path == "std/fs" || imported_symbol.module_path == "std.fs" {
+                            // Calls are checked by `check_std_fs_call`
+                            self.add_std_fs_types();
+                            self.std_fs_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any; 2],
+                                return_type: Some(TypeId::Any),
+                            })
+                        } else if imported_symbol.module_path == "std/process" || imported_symbol.module_path == "std.process" {
+                            // Calls are checked by `check_std_process_call`; commands and
+                            // processes get their methods from `add_std_process_types`
+                            self.add_std_process_types();
+                            self.std_process_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any; 2],
+                                return_type: Some(TypeId::Any),
+                            })
+                        } else if imported_symbol.module_path == "std/http" || imported_symbol.module_path == "std.http" {
+                            // Servers, requests, responses and WebSockets get their members from `add_std_http_types`
+                            self.add_std_http_types();
+                            match imported_symbol.original_name.as_str() {
+                                "newServer" => Some(FunctionInfo {
+                                    param_types: vec![],
+                                    return_type: Some(TypeId::Struct(1022)),
+                                }),
+                                "connectWebSocket" => Some(FunctionInfo {
+                                    param_types: vec![TypeId::String],
+                                    return_type: Some(self.result_type_id(TypeId::Struct(1025), TypeId::String)),
+                                }),
+                                _ => Some(FunctionInfo {
+                                    param_types: vec![TypeId::Int32, TypeId::String],
+                                    return_type: Some(TypeId::Struct(1024)),
+                                }),
+                            }
+                        } else if imported_symbol.module_path == "std/i18n" || imported_symbol.module_path == "std.i18n" {
+                            // Calls are checked by `check_std_i18n_call`
+                            self.std_i18n_functions
+                                .insert(name.clone(), imported_symbol.original_name.clone());
+                            Some(FunctionInfo {
+                                param_types: vec![TypeId::Any; 2],
+                                return_type: Some(TypeId::Any),
+                            })
                         } else if imported_symbol.module_path == "std/flag" || imported_symbol.module_path == "std.flag" {
                             // Special handling for std/flag functions - use original_name for aliases
                             match imported_symbol.original_name.as_str() {
@@ -3055,61 +6792,28 @@ impl TypeChecker {
                             .strip_prefix("std/")
                             .or_else(|| imported_symbol.module_path.strip_prefix("std."))
                             .unwrap_or(&imported_symbol.module_path);
-                        
-                        match module_name {
-                            "os" => {
-                                // Add os module exports with proper signatures
-                                let args_symbol = Symbol {
-                                    name: "args".to_string(),
-                                    type_id: TypeId::Function(0),
-                                    is_mutable: false,
-                                    position: imported_symbol.position,
-                                    function_info: Some(FunctionInfo {
-                                        param_types: vec![],
-                                        return_type: Some(TypeId::Array(0)),
-                                    }),
-                                    module_exports: None,
-                                };
-                                exports_map.insert("args".to_string(), args_symbol);
-                                
-                                let getenv_symbol = Symbol {
-                                    name: "getEnv".to_string(),
-                                    type_id: TypeId::Function(0),
-                                    is_mutable: false,
-                                    position: imported_symbol.position,
-                                    function_info: Some(FunctionInfo {
-                                        param_types: vec![TypeId::String],
-                                        return_type: Some(TypeId::String),
-                                    }),
-                                    module_exports: None,
-                                };
-                                exports_map.insert("getEnv".to_string(), getenv_symbol);
-                                
-                                let cwd_symbol = Symbol {
-                                    name: "cwd".to_string(),
-                                    type_id: TypeId::Function(0),
-                                    is_mutable: false,
-                                    position: imported_symbol.position,
-                                    function_info: Some(FunctionInfo {
-                                        param_types: vec![],
-                                        return_type: Some(TypeId::String),
-                                    }),
-                                    module_exports: None,
-                                };
-                                exports_map.insert("cwd".to_string(), cwd_symbol);
-                                
-                                let exit_symbol = Symbol {
-                                    name: "exit".to_string(),
-                                    type_id: TypeId::Function(0),
-                                    is_mutable: false,
-                                    position: imported_symbol.position,
-                                    function_info: Some(FunctionInfo {
-                                        param_types: vec![TypeId::Int32],
-                                        return_type: None,
-                                    }),
-                                    module_exports: None,
-                                };
-                                exports_map.insert("exit".to_string(), exit_symbol);
+                        
+                        match module_name {
+                            "os" => {
+                                // Exports come from the module's signatures; calls are
+                                // checked by `check_std_os_call`
+                                for function in crate::std::os::FUNCTIONS {
+                                    let param_types =
+                                        function.params.iter().map(|&kind| self.os_kind_type(kind)).collect();
+                                    let return_type = Some(self.os_kind_type(function.returns))
+                                        .filter(|&return_type| return_type != TypeId::Void);
+                                    let symbol = Symbol {
+                                        name: function.name.to_string(),
+                                        type_id: TypeId::Function(0),
+                                        is_mutable: false,
+                                        position: imported_symbol.position,
+                                        function_info: Some(FunctionInfo { param_types, return_type }),
+                                        module_exports: None,
+                                    };
+                                    exports_map.insert(function.name.to_string(), symbol);
+                                    self.std_os_functions
+                                        .insert(format!("{}.{}", name, function.name), function.name.to_string());
+                                }
                             }
                             "net" => {
                                 // Add net module exports
@@ -3298,15 +7002,14 @@ impl TypeChecker {
                         is_mutable: false,
                         position: imported_symbol.position,
                         function_info: None,
-                        module_exports: Some(exports_map),
+                        module_exports: Some(Rc::new(exports_map)),
                     }
                 },
             };
 
             // Add to global scope (first scope in the stack)
-            if let Some(global_scope) = self.scopes.first_mut() {
-                global_scope.insert(name.clone(), symbol);
-            }
+            let global_scope = self.scopes.globals_mut();
+            global_scope.insert(name.clone(), Rc::new(symbol));
         }
     }
 
@@ -3347,7 +7050,7 @@ impl TypeChecker {
                 crate::ast::LiteralValue::String(_) => TypeId::String,
                 crate::ast::LiteralValue::Boolean(_) => TypeId::Bool,
                 crate::ast::LiteralValue::Char(_) => TypeId::Char,
-                crate::ast::LiteralValue::Null => TypeId::Any,
+                crate::ast::LiteralValue::ByteString(_) | crate::ast::LiteralValue::Null => TypeId::Any,
             },
             _ => TypeId::Any, // For complex expressions, default to Any
         }
@@ -3387,6 +7090,11 @@ impl TypeChecker {
                 let map_id = self.type_registry.register_map_type(key_type, value_type);
                 TypeId::Map(map_id)
             }
+            Type::Set(set_type) => {
+                let element_type = self.convert_ast_type_to_type_id(&set_type.element_type);
+                let set_id = self.type_registry.register_set_type(element_type);
+                TypeId::Set(set_id)
+            }
             Type::Channel(channel_type) => {
                 let element_type = self.convert_ast_type_to_type_id(&channel_type.element_type);
                 let direction = match channel_type.direction {
@@ -3400,166 +7108,971 @@ impl TypeChecker {
                     buffered: false,
                     capacity: None,
                 };
-                let channel_id = self.type_registry.register_channel_type(channel_info);
-                TypeId::Channel(channel_id)
+                let channel_id = self.type_registry.register_channel_type(channel_info);
+                TypeId::Channel(channel_id)
+            }
+            Type::Promise(promise_type) => {
+                let result_type = self.convert_ast_type_to_type_id(&promise_type.result_type);
+                let promise_id = self.type_registry.register_promise_type(result_type);
+                TypeId::Promise(promise_id)
+            }
+            Type::Function(_) => TypeId::Function(0), // Placeholder for function types
+            Type::Struct(struct_type) => self.struct_type_to_type_id(struct_type),
+            Type::Union(union_type) => {
+                let members = union_type
+                    .types
+                    .iter()
+                    .map(|member| self.convert_ast_type_to_type_id(member))
+                    .collect();
+                self.union_type_id(members)
+            }
+            Type::Null => TypeId::Null,
+            Type::Named(name) => {
+                if let Some(bound_type) = self.lookup_type_param(name) {
+                    return bound_type;
+                }
+                if let Some(target_type) = self.type_aliases.remove(name) {
+                    let type_id = self.convert_ast_type_to_type_id(&target_type);
+                    self.type_aliases.insert(name.clone(), target_type);
+                    return type_id;
+                }
+                if self.interfaces.contains_key(name) {
+                    self.get_or_create_named_type_id(name, true)
+                } else if self.structs.contains_key(name) {
+                    self.get_or_create_named_type_id(name, false)
+                } else if let Some(&std_type) = self.type_name_to_id.get(name) {
+                    // Standard library types such as TcpServer
+                    std_type
+                } else {
+                    TypeId::Unknown
+                }
+            }
+            Type::Generic(generic_type) => self
+                .lookup_type_param(&generic_type.name)
+                .unwrap_or(TypeId::Any),
+            _ => TypeId::Any,
+        }
+    }
+
+    /// Find an interface declaration by name
+    fn find_interface_declaration(&self, interface_name: &str) -> Option<&InterfaceDecl> {
+        self.interfaces.get(interface_name).map(Rc::as_ref)
+    }
+
+    /// Find a struct declaration by name
+    fn find_struct_declaration(&self, struct_name: &str) -> Option<&StructDecl> {
+        self.structs.get(struct_name).map(Rc::as_ref)
+    }
+
+    /// Check if a struct implements an interface
+    fn struct_implements_interface(&self, struct_name: &str, interface_name: &str) -> bool {
+        let struct_decl = match self.find_struct_declaration(struct_name) {
+            Some(decl) => decl,
+            None => return false,
+        };
+
+        let interface_decl = match self.find_interface_declaration(interface_name) {
+            Some(decl) => decl,
+            None => return false,
+        };
+
+        // Check if the struct has all the methods required by the interface. Methods with a
+        // default body are inherited unless the struct overrides them, and an override must
+        // keep the interface's signature.
+        for interface_method in &interface_decl.methods {
+            let mut found_method = false;
+            let mut overridden = false;
+
+            for struct_method in &struct_decl.methods {
+                if struct_method.name == interface_method.name {
+                    overridden = true;
+                    // Check if the method signatures match
+                    if self.method_signatures_match(struct_method, interface_method) {
+                        found_method = true;
+                        break;
+                    }
+                }
+            }
+
+            if !found_method && (overridden || interface_method.default_body.is_none()) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Check if two method signatures match
+    fn method_signatures_match(
+        &self,
+        struct_method: &FunctionDecl,
+        interface_method: &InterfaceMethod,
+    ) -> bool {
+        // Check parameter count
+        if struct_method.params.len() != interface_method.params.len() {
+            return false;
+        }
+
+        // Check parameter types
+        for (struct_param, interface_param) in struct_method
+            .params
+            .iter()
+            .zip(interface_method.params.iter())
+        {
+            if !self.types_match(&struct_param.param_type, &interface_param.param_type) {
+                return false;
+            }
+        }
+
+        // Check return types
+        match (&struct_method.return_type, &interface_method.return_type) {
+            (Some(struct_ret), Some(interface_ret)) => {
+                if !self.types_match(struct_ret, interface_ret) {
+                    return false;
+                }
+            }
+            (None, None) => {} // Both void
+            _ => return false, // One void, one not
+        }
+
+        true
+    }
+
+    /// Check if two types match
+    fn types_match(&self, type1: &Type, type2: &Type) -> bool {
+        // For now, do a simple comparison
+        // In a full implementation, this would handle type equivalence more thoroughly
+        type1 == type2
+    }
+
+    /// Create or get a TypeId for a named type
+    fn get_or_create_named_type_id(&mut self, name: &str, is_interface: bool) -> TypeId {
+        if let Some(&type_id) = self.type_name_to_id.get(name) {
+            return type_id;
+        }
+
+        let type_id = if is_interface {
+            TypeId::Interface(self.next_type_id)
+        } else {
+            TypeId::Struct(self.next_type_id)
+        };
+
+        self.next_type_id += 1;
+        self.type_name_to_id.insert(name.to_string(), type_id);
+        self.type_id_to_name.insert(type_id, name.to_string());
+        type_id
+    }
+
+    /// Collect the type parameters of a generic declaration. Where clauses repeat a
+    /// parameter name, so constraints are merged per name in declaration order.
+    fn generic_type_params(type_params: &[TypeParam]) -> Vec<GenericTypeParam> {
+        let mut params: Vec<GenericTypeParam> = Vec::new();
+        for type_param in type_params {
+            let constraints = type_param.constraints.iter().map(|constraint| match constraint {
+                Type::Named(name) => GenericConstraint::Interface(name.clone()),
+                other => GenericConstraint::TypeConstraint(other.clone()),
+            });
+            match params.iter_mut().find(|param| param.name == type_param.name) {
+                Some(param) => param.constraints.extend(constraints),
+                None => {
+                    let mut param = GenericTypeParam::new(type_param.name.clone());
+                    param.constraints.extend(constraints);
+                    params.push(param);
+                }
+            }
+        }
+        params
+    }
+
+    /// Bindings for checking a generic body, where type parameters are opaque: a parameter
+    /// constrained by a declared interface checks as that interface, any other as any
+    fn opaque_type_param_bindings(&mut self, type_params: &[TypeParam]) -> HashMap<String, TypeId> {
+        let mut bindings = HashMap::new();
+        for param in Self::generic_type_params(type_params) {
+            let interface = param.constraints.iter().find_map(|constraint| match constraint {
+                GenericConstraint::Interface(name) if self.interfaces.contains_key(name) => {
+                    Some(name.clone())
+                }
+                _ => None,
+            });
+            let opaque_type = match interface {
+                Some(name) => self.get_or_create_named_type_id(&name, true),
+                None => TypeId::Any,
+            };
+            bindings.insert(param.name, opaque_type);
+        }
+        bindings
+    }
+
+    /// Look up the binding of a type parameter in the innermost enclosing generic scope
+    fn lookup_type_param(&self, name: &str) -> Option<TypeId> {
+        self.type_param_bindings
+            .iter()
+            .rev()
+            .find_map(|bindings| bindings.get(name).copied())
+    }
+
+    /// Type parameters of a generic struct, registering imported declarations on first use
+    fn generic_struct_params(&mut self, name: &str) -> Option<Vec<GenericTypeParam>> {
+        if let Some(generic_struct) = self.generics.get_struct(name) {
+            return Some(generic_struct.type_parameters.clone());
+        }
+
+        let struct_decl = self.structs.get(name)?;
+        if struct_decl.type_params.is_empty() {
+            return None;
+        }
+        let type_parameters = Self::generic_type_params(&struct_decl.type_params);
+        self.generics.register_struct(GenericStruct {
+            name: name.to_string(),
+            type_parameters: type_parameters.clone(),
+            where_clause: None,
+        });
+        Some(type_parameters)
+    }
+
+    /// Convert an instantiated type such as `Box<string>` to its TypeId
+    fn struct_type_to_type_id(&mut self, struct_type: &StructType) -> TypeId {
+        if self.interfaces.contains_key(&struct_type.name) {
+            return self.get_or_create_named_type_id(&struct_type.name, true);
+        }
+
+        let mut type_args = Vec::with_capacity(struct_type.type_args.len());
+        for type_arg in &struct_type.type_args {
+            type_args.push(self.ast_type_to_type_id(type_arg));
+        }
+
+        // Result and Option are built in, unless the program declares its own
+        if !self.structs.contains_key(&struct_type.name) {
+            let expected = match struct_type.name.as_str() {
+                "Result" => 2,
+                "Option" => 1,
+                _ => 0,
+            };
+            if expected > 0 && type_args.len() != expected {
+                self.errors.push(BuluError::TypeError {
+                    stack: Vec::new(),
+                    file: None,
+                    message: format!(
+                        "Type '{}' expects {} type arguments, got {}",
+                        struct_type.name,
+                        expected,
+                        type_args.len()
+                    ),
+                    line: 0,
+                    column: 0,
+                });
+                return TypeId::Unknown;
+            }
+            match struct_type.name.as_str() {
+                "Result" => return self.result_type_id(type_args[0], type_args[1]),
+                "Option" => return self.option_type_id(type_args[0]),
+                _ => {}
+            }
+
+            // std/collections handle types, once imported
+            let name = struct_type.name.as_str();
+            if crate::std::collections::is_handle_type(name) && self.type_name_to_id.contains_key(name) {
+                let expected = crate::std::collections::type_param_count(name);
+                if type_args.len() != expected {
+                    self.errors.push(BuluError::TypeError {
+                        stack: Vec::new(),
+                        file: None,
+                        message: format!(
+                            "Type '{}' expects {} type argument{}, got {}",
+                            name,
+                            expected,
+                            if expected == 1 { "" } else { "s" },
+                            type_args.len()
+                        ),
+                        line: 0,
+                        column: 0,
+                    });
+                    return TypeId::Unknown;
+                }
+                return self.intern_struct_instance(name, type_args);
+            }
+        }
+        match self.instantiate_generic_struct(&struct_type.name, type_args, Position::new(0, 0, 0)) {
+            Ok(type_id) => type_id,
+            Err(error) => {
+                self.errors.push(error);
+                TypeId::Unknown
+            }
+        }
+    }
+
+    /// Instantiate a generic struct with concrete type arguments. Each distinct
+    /// instantiation (e.g. `Box<string>`) gets its own TypeId.
+    fn instantiate_generic_struct(
+        &mut self,
+        name: &str,
+        type_args: Vec<TypeId>,
+        position: Position,
+    ) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: position.line,
+            column: position.column,
+        };
+
+        if !self.structs.contains_key(name) {
+            // Not declared (yet): stay lenient, as for other unknown named types
+            return Ok(TypeId::Unknown);
+        }
+        let type_params = match self.generic_struct_params(name) {
+            Some(type_params) => type_params,
+            None if type_args.is_empty() => return Ok(self.get_or_create_named_type_id(name, false)),
+            None => return Err(error(format!("Struct '{}' is not generic", name))),
+        };
+        if type_args.len() != type_params.len() {
+            return Err(error(format!(
+                "Struct '{}' expects {} type arguments, got {}",
+                name,
+                type_params.len(),
+                type_args.len()
+            )));
+        }
+
+        let bindings = type_params
+            .iter()
+            .map(|param| param.name.clone())
+            .zip(type_args.iter().copied())
+            .collect();
+        self.check_type_param_constraints(&type_params, &bindings, &format!("struct '{}'", name), position)?;
+
+        Ok(self.intern_struct_instance(name, type_args))
+    }
+
+    /// The TypeId of `name<type_args>`, allocating it on first use
+    fn intern_struct_instance(&mut self, name: &str, type_args: Vec<TypeId>) -> TypeId {
+        let instantiation = GenericInstantiation {
+            base_type: name.to_string(),
+            type_args: type_args.clone(),
+        };
+        if let Some(&type_id) = self.generics.instantiations.get(&instantiation) {
+            return type_id;
+        }
+
+        let type_id = TypeId::Struct(self.next_type_id);
+        self.next_type_id += 1;
+        let arg_names: Vec<String> = type_args
+            .iter()
+            .map(|type_arg| match self.get_type_name_from_id(*type_arg) {
+                Some(type_name) => type_name.clone(),
+                None => PrimitiveType::type_name(*type_arg).to_string(),
+            })
+            .collect();
+        self.type_id_to_name
+            .insert(type_id, format!("{}<{}>", name, arg_names.join(", ")));
+        self.struct_instances
+            .insert(type_id, (name.to_string(), type_args));
+        self.generics.instantiations.insert(instantiation, type_id);
+        type_id
+    }
+
+    /// Name and type arguments of a std/collections handle type; a bare handle
+    /// type has `any` type arguments
+    fn collection_type_args(&self, type_id: TypeId) -> Option<(String, Vec<TypeId>)> {
+        use crate::std::collections::{is_handle_type, type_param_count};
+
+        let (name, type_args) = match self.struct_instances.get(&type_id) {
+            Some((name, type_args)) => (name.clone(), type_args.clone()),
+            None => {
+                let name = self.type_id_to_name.get(&type_id)?.clone();
+                let type_args = vec![TypeId::Any; type_param_count(&name)];
+                (name, type_args)
+            }
+        };
+        if is_handle_type(&name) && !self.structs.contains_key(&name) {
+            Some((name, type_args))
+        } else {
+            None
+        }
+    }
+
+    /// Type of a field, or a method's return type, on an instantiated generic struct
+    fn instance_member_type(&mut self, instance: TypeId, member: &str) -> Option<TypeId> {
+        let (name, type_args) = self.struct_instances.get(&instance).cloned()?;
+        let struct_decl = self.structs.get(&name).cloned()?;
+        let type_params = self.generic_struct_params(&name)?;
+
+        let bindings = type_params
+            .into_iter()
+            .map(|param| param.name)
+            .zip(type_args)
+            .collect();
+        self.type_param_bindings.push(bindings);
+        let member_type = if let Some(field) = struct_decl.fields.iter().find(|f| f.name == member) {
+            Some(self.ast_type_to_type_id(&field.field_type))
+        } else {
+            let return_type = match struct_decl.methods.iter().find(|method| method.name == member) {
+                Some(method) => Some(method.return_type.clone()),
+                None => self.inherited_method(&name, member).map(|method| method.return_type),
+            };
+            return_type.map(|return_type| match &return_type {
+                Some(return_type) => self.ast_type_to_type_id(return_type),
+                None => TypeId::Void,
+            })
+        };
+        self.type_param_bindings.pop();
+        member_type
+    }
+
+    /// Whether two struct types are compatible through generic instantiation: instances of
+    /// the same struct whose arguments match (any matches everything), or an instance and
+    /// the uninstantiated struct
+    fn struct_instances_compatible(&self, actual_type: TypeId, expected_type: TypeId) -> bool {
+        let is_wildcard = |type_id: &TypeId| matches!(type_id, TypeId::Any | TypeId::Unknown);
+        match (
+            self.struct_instances.get(&actual_type),
+            self.struct_instances.get(&expected_type),
+        ) {
+            (Some((actual_name, actual_args)), Some((expected_name, expected_args))) => {
+                actual_name == expected_name
+                    && actual_args.iter().zip(expected_args).all(|(actual, expected)| {
+                        actual == expected || is_wildcard(actual) || is_wildcard(expected)
+                    })
+            }
+            (Some((name, _)), None) => self.type_name_to_id.get(name) == Some(&expected_type),
+            (None, Some((name, _))) => self.type_name_to_id.get(name) == Some(&actual_type),
+            (None, None) => false,
+        }
+    }
+
+    /// Get the type name from a TypeId
+    fn get_type_name_from_id(&self, type_id: TypeId) -> Option<&String> {
+        self.type_id_to_name.get(&type_id)
+    }
+
+    /// Check if actual_type is compatible with expected_type (including interface implementation)
+    fn is_type_compatible(&self, actual_type: TypeId, expected_type: TypeId) -> bool {
+        // Direct type match
+        if actual_type == expected_type {
+            return true;
+        }
+
+        // Check primitive type compatibility
+        if PrimitiveType::is_assignable(actual_type, expected_type) {
+            return true;
+        }
+
+        // A union fits where each of its members fits; a value fits a union when it fits a member
+        if let Some(members) = self.type_registry.get_union_members(actual_type) {
+            return members
+                .iter()
+                .all(|member| self.is_type_compatible(*member, expected_type));
+        }
+        if let Some(members) = self.type_registry.get_union_members(expected_type) {
+            return members
+                .iter()
+                .any(|member| self.is_type_compatible(actual_type, *member));
+        }
+
+        // Check if a struct implements an interface
+        match (actual_type, expected_type) {
+            (TypeId::Struct(_), TypeId::Interface(_)) => {
+                // Get the struct and interface names; instances implement what their struct does
+                let struct_name = match self.struct_instances.get(&actual_type) {
+                    Some((name, _)) => Some(name),
+                    None => self.get_type_name_from_id(actual_type),
+                };
+                if let (Some(struct_name), Some(interface_name)) =
+                    (struct_name, self.get_type_name_from_id(expected_type))
+                {
+                    return self.struct_implements_interface(struct_name, interface_name);
+                }
             }
-            Type::Promise(promise_type) => {
-                let result_type = self.convert_ast_type_to_type_id(&promise_type.result_type);
-                let promise_id = self.type_registry.register_promise_type(result_type);
-                TypeId::Promise(promise_id)
+            (TypeId::Struct(_), TypeId::Struct(_)) => {
+                return self.struct_instances_compatible(actual_type, expected_type);
             }
-            Type::Function(_) => TypeId::Function(0), // Placeholder for function types
-            Type::Named(name) => {
-                if self.interfaces.contains_key(name) {
-                    self.get_or_create_named_type_id(name, true)
-                } else if self.structs.contains_key(name) {
-                    self.get_or_create_named_type_id(name, false)
-                } else {
-                    TypeId::Unknown
+            (TypeId::Result(_), TypeId::Result(_)) => {
+                if let (Some((actual_ok, actual_error)), Some((expected_ok, expected_error))) = (
+                    self.type_registry.get_result_types(actual_type),
+                    self.type_registry.get_result_types(expected_type),
+                ) {
+                    return self.is_type_compatible(actual_ok, expected_ok)
+                        && self.is_type_compatible(actual_error, expected_error);
                 }
             }
-            Type::Generic(_) => TypeId::Any,
-            _ => TypeId::Any,
+            // Buffering is a property of the channel value, not of what it may be assigned to
+            (TypeId::Channel(_), TypeId::Channel(_)) => {
+                if let (Some(actual), Some(expected)) = (
+                    self.type_registry.get_channel_info(actual_type),
+                    self.type_registry.get_channel_info(expected_type),
+                ) {
+                    return CompositeTypeId::Channel(actual.clone())
+                        .is_assignable_to(&CompositeTypeId::Channel(expected.clone()));
+                }
+            }
+            // The empty literal `set{}` fits any set type
+            (TypeId::Set(_), TypeId::Set(_)) => {
+                return match (
+                    self.type_registry.get_set_element_type(actual_type),
+                    self.type_registry.get_set_element_type(expected_type),
+                ) {
+                    (Some(actual_element), Some(expected_element)) => {
+                        self.is_type_compatible(actual_element, expected_element)
+                    }
+                    _ => true,
+                };
+            }
+            (TypeId::Option(_), TypeId::Option(_)) => {
+                if let (Some(actual_inner), Some(expected_inner)) = (
+                    self.type_registry.get_option_type(actual_type),
+                    self.type_registry.get_option_type(expected_type),
+                ) {
+                    return self.is_type_compatible(actual_inner, expected_inner);
+                }
+            }
+            _ => {}
         }
-    }
 
-    /// Find an interface declaration by name
-    fn find_interface_declaration(&self, interface_name: &str) -> Option<&InterfaceDecl> {
-        self.interfaces.get(interface_name)
+        false
     }
 
-    /// Find a struct declaration by name
-    fn find_struct_declaration(&self, struct_name: &str) -> Option<&StructDecl> {
-        self.structs.get(struct_name)
+    /// Build the union of `members`: nested unions are flattened and duplicates removed, a
+    /// single remaining member stands for itself and `any` absorbs everything else
+    fn union_type_id(&mut self, members: Vec<TypeId>) -> TypeId {
+        let mut flattened: Vec<TypeId> = Vec::new();
+        for member in members {
+            let nested = self.type_registry.get_union_members(member).map(<[TypeId]>::to_vec);
+            for member in nested.unwrap_or_else(|| vec![member]) {
+                if member == TypeId::Any {
+                    return TypeId::Any;
+                }
+                if !flattened.contains(&member) {
+                    flattened.push(member);
+                }
+            }
+        }
+        if flattened.len() == 1 {
+            return flattened[0];
+        }
+
+        // Canonical member order, so `int32 | string` and `string | int32` are the same type
+        flattened.sort_by_key(|member| (*member == TypeId::Null, format!("{:?}", member)));
+        let union_type = TypeId::Union(self.type_registry.register_union_type(flattened.clone()));
+        if !self.type_id_to_name.contains_key(&union_type) {
+            let member_names: Vec<String> = flattened
+                .iter()
+                .map(|member| self.component_type_name(*member))
+                .collect();
+            self.type_id_to_name.insert(union_type, member_names.join(" | "));
+        }
+        union_type
     }
 
-    /// Check if a struct implements an interface
-    fn struct_implements_interface(&self, struct_name: &str, interface_name: &str) -> bool {
-        let struct_decl = match self.find_struct_declaration(struct_name) {
-            Some(decl) => decl,
-            None => return false,
-        };
+    /// Name of a type appearing inside another type's name, e.g. the members of a union
+    fn component_type_name(&self, type_id: TypeId) -> String {
+        match self.get_type_name_from_id(type_id) {
+            Some(type_name) => type_name.clone(),
+            None => self.type_registry.get_type_name(type_id),
+        }
+    }
 
-        let interface_decl = match self.find_interface_declaration(interface_name) {
-            Some(decl) => decl,
-            None => return false,
-        };
+    /// The `Result<T, E>` type for the given ok and error types
+    fn result_type_id(&mut self, ok_type: TypeId, error_type: TypeId) -> TypeId {
+        let result_type = TypeId::Result(self.type_registry.register_result_type(ok_type, error_type));
+        if !self.type_id_to_name.contains_key(&result_type) {
+            let name = format!(
+                "Result<{}, {}>",
+                self.component_type_name(ok_type),
+                self.component_type_name(error_type)
+            );
+            self.type_id_to_name.insert(result_type, name);
+        }
+        result_type
+    }
 
-        // Check if the struct has all the methods required by the interface
-        for interface_method in &interface_decl.methods {
-            let mut found_method = false;
+    /// The `Option<T>` type for the given inner type
+    fn option_type_id(&mut self, inner_type: TypeId) -> TypeId {
+        let option_type = TypeId::Option(self.type_registry.register_option_type(inner_type));
+        if !self.type_id_to_name.contains_key(&option_type) {
+            let name = format!("Option<{}>", self.component_type_name(inner_type));
+            self.type_id_to_name.insert(option_type, name);
+        }
+        option_type
+    }
 
-            for struct_method in &struct_decl.methods {
-                if struct_method.name == interface_method.name {
-                    // Check if the method signatures match
-                    if self.method_signatures_match(struct_method, interface_method) {
-                        found_method = true;
-                        break;
-                    }
-                }
+    /// The value type of a Result or Option, and the error type of a Result
+    fn wrapper_types(&self, wrapper_type: TypeId) -> (TypeId, Option<TypeId>) {
+        match wrapper_type {
+            TypeId::Result(_) => {
+                let (ok_type, error_type) = self
+                    .type_registry
+                    .get_result_types(wrapper_type)
+                    .unwrap_or((TypeId::Any, TypeId::Any));
+                (ok_type, Some(error_type))
             }
+            _ => (
+                self.type_registry.get_option_type(wrapper_type).unwrap_or(TypeId::Any),
+                None,
+            ),
+        }
+    }
 
-            if !found_method {
-                return false;
-            }
+    /// Type check `Ok(value)`, `Err(error)` or `Some(value)`. The side that is not given
+    /// stays `any`, so the value fits any Result or Option with a compatible wrapped type.
+    fn check_wrapper_constructor(&mut self, name: &str, call: &CallExpr) -> Result<TypeId> {
+        if call.args.len() != 1 {
+            return Err(BuluError::TypeError {
+                stack: Vec::new(),
+                file: None,
+                message: format!("Function '{}' expects 1 arguments, got {}", name, call.args.len()),
+                line: call.position.line,
+                column: call.position.column,
+            });
         }
 
-        true
+        let value_type = self.check_expression(&call.args[0])?;
+        Ok(match name {
+            "Ok" => self.result_type_id(value_type, TypeId::Any),
+            "Err" => self.result_type_id(TypeId::Any, value_type),
+            _ => self.option_type_id(value_type),
+        })
     }
 
-    /// Check if two method signatures match
-    fn method_signatures_match(
-        &self,
-        struct_method: &FunctionDecl,
-        interface_method: &InterfaceMethod,
-    ) -> bool {
-        // Check parameter count
-        if struct_method.params.len() != interface_method.params.len() {
-            return false;
+    /// Type of a no-argument Result or Option method, e.g. `unwrap` or `isError`
+    fn wrapper_accessor_type(&self, wrapper_type: TypeId, method: &str) -> Option<TypeId> {
+        let (value_type, error_type) = self.wrapper_types(wrapper_type);
+        match (method, error_type) {
+            ("isOk" | "isError", Some(_)) | ("isSome" | "isNone", None) => Some(TypeId::Bool),
+            ("unwrap", _) => Some(value_type),
+            ("error", Some(error_type)) => Some(error_type),
+            _ => None,
         }
+    }
 
-        // Check parameter types
-        for (struct_param, interface_param) in struct_method
-            .params
-            .iter()
-            .zip(interface_method.params.iter())
-        {
-            if !self.types_match(&struct_param.param_type, &interface_param.param_type) {
-                return false;
+    /// Type check a method call on a Result or Option. The methods are typed by the
+    /// wrapped types, so `Result<User, string>.unwrap()` is a `User`.
+    fn check_wrapper_method_call(&mut self, wrapper_type: TypeId, method: &str, call: &CallExpr) -> Result<TypeId> {
+        let error = |message: String| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: call.position.line,
+            column: call.position.column,
+        };
+
+        let (value_type, error_type) = self.wrapper_types(wrapper_type);
+        let arity = match method {
+            "unwrapOr" | "map" => 1,
+            _ => 0,
+        };
+        let accessor_type = self.wrapper_accessor_type(wrapper_type, method);
+        if accessor_type.is_none() && arity == 0 {
+            return Err(error(format!(
+                "Method '{}' not found on {}",
+                method,
+                self.type_name_for_error(wrapper_type)
+            )));
+        }
+        if call.args.len() != arity {
+            return Err(error(format!(
+                "Method '{}' expects {} arguments, got {}",
+                method,
+                arity,
+                call.args.len()
+            )));
+        }
+
+        match method {
+            "unwrapOr" => {
+                let default_type = self.check_expression(&call.args[0])?;
+                if !self.is_type_compatible(default_type, value_type) {
+                    return Err(error(format!(
+                        "Argument 1 to method 'unwrapOr': expected {}, got {}",
+                        self.type_name_for_error(value_type),
+                        self.type_name_for_error(default_type)
+                    )));
+                }
+                Ok(value_type)
+            }
+            "map" => {
+                let argument = format!("Argument 1 to method '{}'", method);
+                let mapped_type = self.callback_return_type(&call.args[0], &[value_type], &argument)?;
+                Ok(match error_type {
+                    Some(error_type) => self.result_type_id(mapped_type, error_type),
+                    None => self.option_type_id(mapped_type),
+                })
             }
+            _ => Ok(accessor_type.unwrap_or(TypeId::Any)),
         }
+    }
 
-        // Check return types
-        match (&struct_method.return_type, &interface_method.return_type) {
-            (Some(struct_ret), Some(interface_ret)) => {
-                if !self.types_match(struct_ret, interface_ret) {
-                    return false;
+    /// Result type of calling `callback` with arguments of `param_types`; `argument` names the
+    /// callback in errors. Lambdas are checked with their parameters bound to `param_types`;
+    /// other function values fall back to `any`.
+    fn callback_return_type(&mut self, callback: &Expression, param_types: &[TypeId], argument: &str) -> Result<TypeId> {
+        let error = |message: String, position: Position| BuluError::TypeError {
+            stack: Vec::new(),
+            file: None,
+            message,
+            line: position.line,
+            column: position.column,
+        };
+        let arity = match param_types.len() {
+            1 => "one parameter".to_string(),
+            2 => "two parameters".to_string(),
+            count => format!("{} parameters", count),
+        };
+        let wrong_arity = |position: Position| {
+            error(format!("{}: expected a function of {}", argument, arity), position)
+        };
+        let mismatch = |checker: &Self, param_type: TypeId, declared: TypeId, position: Position| {
+            error(
+                format!(
+                    "{}: expected a function taking {}, got one taking {}",
+                    argument,
+                    checker.type_name_for_error(param_type),
+                    checker.type_name_for_error(declared)
+                ),
+                position,
+            )
+        };
+
+        match callback {
+            Expression::Lambda(lambda) => {
+                if lambda.params.len() != param_types.len() {
+                    return Err(wrong_arity(lambda.position));
+                }
+                let mut bound_types = Vec::with_capacity(param_types.len());
+                for (param, &param_type) in lambda.params.iter().zip(param_types) {
+                    let declared = self.ast_type_to_type_id(&param.param_type);
+                    bound_types.push(match declared {
+                        TypeId::Any | TypeId::Unknown => param_type,
+                        _ if self.is_type_compatible(param_type, declared) => declared,
+                        _ => return Err(mismatch(self, param_type, declared, lambda.position)),
+                    });
+                }
+
+                self.enter_scope();
+                let body_type = lambda
+                    .params
+                    .iter()
+                    .zip(bound_types)
+                    .try_for_each(|(param, bound_type)| {
+                        self.add_symbol(Symbol {
+                            name: param.name.clone(),
+                            type_id: bound_type,
+                            is_mutable: false,
+                            position: param.position,
+                            function_info: None,
+                            module_exports: None,
+                        })
+                    })
+                    .and_then(|_| self.check_expression(&lambda.body));
+                self.exit_scope();
+                let body_type = body_type?;
+
+                Ok(match &lambda.return_type {
+                    Some(return_type) => self.ast_type_to_type_id(return_type),
+                    None => body_type,
+                })
+            }
+            Expression::Identifier(ident) => {
+                let function_info = self.lookup_symbol(&ident.name).and_then(|s| s.function_info.clone());
+                let Some(function_info) = function_info else {
+                    self.check_expression(callback)?;
+                    return Ok(TypeId::Any);
+                };
+                if function_info.param_types.len() != param_types.len() {
+                    return Err(wrong_arity(ident.position));
                 }
+                for (&param_type, &declared) in param_types.iter().zip(&function_info.param_types) {
+                    if !self.is_type_compatible(param_type, declared) {
+                        return Err(mismatch(self, param_type, declared, ident.position));
+                    }
+                }
+                Ok(function_info.return_type.unwrap_or(TypeId::Void))
+            }
+            _ => {
+                self.check_expression(callback)?;
+                Ok(TypeId::Any)
             }
-            (None, None) => {} // Both void
-            _ => return false, // One void, one not
         }
+    }
 
-        true
+    /// Members of a union type, or the type itself for any other type
+    fn union_members(&self, type_id: TypeId) -> Vec<TypeId> {
+        match self.type_registry.get_union_members(type_id) {
+            Some(members) => members.to_vec(),
+            None => vec![type_id],
+        }
     }
 
-    /// Check if two types match
-    fn types_match(&self, type1: &Type, type2: &Type) -> bool {
-        // For now, do a simple comparison
-        // In a full implementation, this would handle type equivalence more thoroughly
-        type1 == type2
+    /// Whether a value of `value_type` can ever match a type pattern for `pattern_type`
+    fn type_pattern_can_match(&self, pattern_type: TypeId, value_type: TypeId) -> bool {
+        matches!(value_type, TypeId::Any | TypeId::Unknown)
+            || self.is_type_compatible(pattern_type, value_type)
+            || self.is_type_compatible(value_type, pattern_type)
     }
 
-    /// Create or get a TypeId for a named type
-    fn get_or_create_named_type_id(&mut self, name: &str, is_interface: bool) -> TypeId {
-        if let Some(&type_id) = self.type_name_to_id.get(name) {
-            return type_id;
+    /// The name `typeof` reports at runtime for values of a (non-union) type
+    fn runtime_type_name(&self, type_id: TypeId) -> String {
+        match (type_id, self.get_type_name_from_id(type_id)) {
+            (TypeId::Struct(_) | TypeId::Interface(_), Some(type_name)) => type_name.clone(),
+            _ => PrimitiveType::type_name(type_id).to_string(),
         }
+    }
 
-        let type_id = if is_interface {
-            TypeId::Interface(self.next_type_id)
-        } else {
-            TypeId::Struct(self.next_type_id)
-        };
-
-        self.next_type_id += 1;
-        self.type_name_to_id.insert(name.to_string(), type_id);
-        self.type_id_to_name.insert(type_id, name.to_string());
-        type_id
+    /// Type of a field, or a method's return type, on a single variant of a union
+    fn variant_member_type(&mut self, variant: TypeId, member: &str) -> Option<TypeId> {
+        if self.struct_instances.contains_key(&variant) {
+            return self.instance_member_type(variant, member);
+        }
+        match variant {
+            TypeId::Any | TypeId::Unknown => Some(TypeId::Any),
+            TypeId::Struct(_) => {
+                let struct_name = self.get_type_name_from_id(variant)?.clone();
+                let struct_decl = self.structs.get(&struct_name)?.clone();
+                if let Some(field) = struct_decl.fields.iter().find(|f| f.name == member) {
+                    return Some(self.ast_type_to_type_id(&field.field_type));
+                }
+                let return_type = match struct_decl.methods.iter().find(|method| method.name == member) {
+                    Some(method) => method.return_type.clone(),
+                    None => self.inherited_method(&struct_name, member)?.return_type,
+                };
+                Some(match &return_type {
+                    Some(return_type) => self.ast_type_to_type_id(return_type),
+                    None => TypeId::Void,
+                })
+            }
+            TypeId::Interface(_) => {
+                let interface_name = self.get_type_name_from_id(variant)?.clone();
+                let interface_decl = self.interfaces.get(&interface_name)?.clone();
+                let method = interface_decl.methods.iter().find(|method| method.name == member)?;
+                Some(match &method.return_type {
+                    Some(return_type) => self.ast_type_to_type_id(return_type),
+                    None => TypeId::Void,
+                })
+            }
+            TypeId::Null => None,
+            _ if member == "toString" => Some(TypeId::String),
+            _ => None,
+        }
     }
 
-    /// Get the type name from a TypeId
-    fn get_type_name_from_id(&self, type_id: TypeId) -> Option<&String> {
-        self.type_id_to_name.get(&type_id)
+    /// An interface method available on a struct that implements the interface
+    fn inherited_method(&self, struct_name: &str, member: &str) -> Option<InterfaceMethod> {
+        self.interfaces
+            .iter()
+            .filter(|(interface_name, _)| self.struct_implements_interface(struct_name, interface_name))
+            .flat_map(|(_, interface_decl)| interface_decl.methods.iter())
+            .find(|method| method.name == member)
+            .cloned()
     }
 
-    /// Check if actual_type is compatible with expected_type (including interface implementation)
-    fn is_type_compatible(&self, actual_type: TypeId, expected_type: TypeId) -> bool {
-        // Direct type match
-        if actual_type == expected_type {
-            return true;
+    /// Type of a member accessed on a union: it must exist on every variant, and its
+    /// type is the union of the variants' member types
+    fn union_member_type(&mut self, union_type: TypeId, member: &str, position: Position) -> Result<TypeId> {
+        let mut member_types = Vec::new();
+        for variant in self.union_members(union_type) {
+            match self.variant_member_type(variant, member) {
+                Some(member_type) => member_types.push(member_type),
+                None => {
+                    return Err(BuluError::TypeError { stack: Vec::new(),
+                        message: format!(
+                            "Member '{}' does not exist on all variants of {} (missing on {})",
+                            member,
+                            self.type_name_for_error(union_type),
+                            self.type_name_for_error(variant)
+                        ),
+                        line: position.line,
+                        column: position.column,
+                        file: self.current_file.clone(),
+                    });
+                }
+            }
         }
+        Ok(self.union_type_id(member_types))
+    }
 
-        // Check primitive type compatibility
-        if PrimitiveType::is_assignable(actual_type, expected_type) {
-            return true;
+    /// Union members of a match scrutinee that no unguarded type pattern covers
+    fn uncovered_union_members(&mut self, scrutinee_type: TypeId, arms: &[(&Pattern, bool)]) -> Vec<TypeId> {
+        let mut covered = Vec::new();
+        for (pattern, has_guard) in arms {
+            if *has_guard {
+                continue;
+            }
+            let mut pattern_types = Vec::new();
+            collect_pattern_types(pattern, &mut pattern_types);
+            for pattern_type in pattern_types {
+                covered.push(self.ast_type_to_type_id(pattern_type));
+            }
         }
+        self.union_members(scrutinee_type)
+            .into_iter()
+            .filter(|member| !covered.iter().any(|pattern_type| self.is_type_compatible(*member, *pattern_type)))
+            .collect()
+    }
 
-        // Check if a struct implements an interface
-        match (actual_type, expected_type) {
-            (TypeId::Struct(_), TypeId::Interface(_)) => {
-                // Get the struct and interface names
-                if let (Some(struct_name), Some(interface_name)) = (
-                    self.get_type_name_from_id(actual_type),
-                    self.get_type_name_from_id(expected_type),
-                ) {
-                    return self.struct_implements_interface(struct_name, interface_name);
-                }
-            }
-            _ => {}
+    /// Narrowing implied by an if condition of the form `typeof(x) == "name"` or
+    /// `x == null` (or `!=`), where `x` is a union-typed variable. Returns the variable
+    /// with its type in the then-branch and in the else-branch.
+    fn condition_narrowing(&mut self, condition: &Expression) -> Option<(String, TypeId, TypeId)> {
+        let Expression::Binary(binary) = condition else {
+            return None;
+        };
+        let negated = match binary.operator {
+            BinaryOperator::Equal => false,
+            BinaryOperator::NotEqual => true,
+            _ => return None,
+        };
+
+        enum Test {
+            TypeName(String),
+            Null,
+        }
+        let typeof_subject = |expr: &Expression| match expr {
+            Expression::TypeOf(typeof_expr) => Some((*typeof_expr.expr).clone()),
+            Expression::Call(call) => match (&*call.callee, call.args.as_slice()) {
+                (Expression::Identifier(callee), [arg]) if callee.name == "typeof" => Some(arg.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let test_of = |subject: &Expression, other: &Expression| match (typeof_subject(subject), other) {
+            (Some(Expression::Identifier(variable)), Expression::Literal(literal)) => match &literal.value {
+                LiteralValue::String(type_name) => Some((variable.name, Test::TypeName(type_name.clone()))),
+                _ => None,
+            },
+            (None, Expression::Literal(literal)) if matches!(literal.value, LiteralValue::Null) => match subject {
+                Expression::Identifier(variable) => Some((variable.name.clone(), Test::Null)),
+                _ => None,
+            },
+            _ => None,
+        };
+        let (variable, test) = test_of(&binary.left, &binary.right).or_else(|| test_of(&binary.right, &binary.left))?;
+
+        let variable_type = self.lookup_symbol(&variable)?.type_id;
+        self.type_registry.get_union_members(variable_type)?;
+        let (matching, rest): (Vec<TypeId>, Vec<TypeId>) =
+            self.union_members(variable_type).into_iter().partition(|member| match &test {
+                Test::TypeName(type_name) => self.runtime_type_name(*member) == *type_name,
+                Test::Null => *member == TypeId::Null,
+            });
+        if matching.is_empty() || rest.is_empty() {
+            return None;
         }
+        let (when_equal, when_not_equal) = (self.union_type_id(matching), self.union_type_id(rest));
+        Some(if negated {
+            (variable, when_not_equal, when_equal)
+        } else {
+            (variable, when_equal, when_not_equal)
+        })
+    }
 
-        false
+    /// Shadow `name` with a narrowed type in the current scope
+    fn add_narrowed_symbol(&mut self, name: &str, narrowed_type: TypeId) -> Result<()> {
+        if let Some(mut symbol) = self.lookup_symbol(name).cloned() {
+            symbol.type_id = narrowed_type;
+            self.add_symbol(symbol)?;
+        }
+        Ok(())
     }
 
     /// Get a user-friendly type name for error messages
@@ -3598,7 +8111,7 @@ impl TypeChecker {
                     if let Some(struct_name) = type_name.as_ref() {
                         // Check for std type methods first
                         let std_method_name = format!("{}.{}", struct_name, member_access.member);
-                        if let Some(method_symbol) = self.lookup_symbol(&std_method_name).cloned() {
+                        if let Some(method_symbol) = self.lookup_shared_symbol(&std_method_name).cloned() {
                             if let Some(function_info) = &method_symbol.function_info {
                                 if let Some(return_type) = &function_info.return_type {
                                     return Ok(self.get_type_name_from_id(*return_type).cloned());
@@ -3607,6 +8120,16 @@ impl TypeChecker {
                         }
                     }
                 }
+                // Handle function calls like background(), using the declared return type
+                if let Expression::Identifier(ident) = &*call.callee {
+                    let return_type = self
+                        .lookup_symbol(&ident.name)
+                        .and_then(|symbol| symbol.function_info.as_ref())
+                        .and_then(|info| info.return_type);
+                    if let Some(return_type) = return_type {
+                        return Ok(self.get_type_name_from_id(return_type).cloned());
+                    }
+                }
                 Ok(None)
             }
             _ => Ok(None),
@@ -3637,8 +8160,21 @@ impl TypeChecker {
                 // and extract field types
                 match value_type {
                     TypeId::Struct(_struct_id) => {
+                        // The pattern must name the scrutinee's struct, or it could never match
+                        if let Some(actual) = self.get_type_name_from_id(value_type) {
+                            if *actual != struct_pattern.name {
+                                return Err(BuluError::TypeError { stack: Vec::new(),
+                                    message: format!(
+                                        "Struct pattern '{}' can never match a value of struct {}",
+                                        struct_pattern.name, actual
+                                    ),
+                                    line: struct_pattern.position.line,
+                                    column: struct_pattern.position.column,
+                                    file: self.current_file.clone(),
+                                });
+                            }
+                        }
                         // Get the struct definition to know field types
-                        // For now, we'll look up by struct name since we don't have a direct ID->struct mapping
                         let struct_def = self.structs.get(&struct_pattern.name).cloned();
                         if let Some(struct_def) = struct_def {
                             for field_pattern in &struct_pattern.fields {
@@ -3657,21 +8193,21 @@ impl TypeChecker {
                                 } else {
                                     return Err(BuluError::TypeError { stack: Vec::new(),
                                         message: format!(
-                                            "Field '{}' not found in struct",
-                                            field_pattern.name
+                                            "Field '{}' not found in struct {}",
+                                            field_pattern.name, struct_pattern.name
                                         ),
                                         line: field_pattern.position.line,
                                         column: field_pattern.position.column,
-                                        file: None,
+                                        file: self.current_file.clone(),
                                     });
                                 }
                             }
                         } else {
                             return Err(BuluError::TypeError { stack: Vec::new(),
-                                message: "Unknown struct type in destructuring".to_string(),
-                                line: 0,
-                                column: 0,
-                                file: None,
+                                message: format!("Unknown struct type '{}' in destructuring", struct_pattern.name),
+                                line: struct_pattern.position.line,
+                                column: struct_pattern.position.column,
+                                file: self.current_file.clone(),
                             });
                         }
                     }
@@ -3695,10 +8231,14 @@ impl TypeChecker {
                     }
                     _ => {
                         return Err(BuluError::TypeError { stack: Vec::new(),
-                            message: format!("Cannot destructure non-struct type"),
-                            line: 0,
-                            column: 0,
-                            file: None,
+                            message: format!(
+                                "Cannot destructure non-struct type {} with struct pattern '{}'",
+                                self.type_name_for_error(value_type),
+                                struct_pattern.name
+                            ),
+                            line: struct_pattern.position.line,
+                            column: struct_pattern.position.column,
+                            file: self.current_file.clone(),
                         });
                     }
                 }
@@ -3787,9 +8327,57 @@ impl TypeChecker {
                 }
             }
             Pattern::Or(or_pattern) => {
-                // For OR patterns, all alternatives should bind the same variables with the same types
-                for alternative in &or_pattern.patterns {
-                    self.check_pattern_and_add_variables(alternative, value_type)?;
+                // For OR patterns, all alternatives should bind the same variables with the same types.
+                // Only the first alternative's bindings are kept; the others are checked in a scratch scope.
+                for (index, alternative) in or_pattern.patterns.iter().enumerate() {
+                    if index == 0 {
+                        self.check_pattern_and_add_variables(alternative, value_type)?;
+                    } else {
+                        self.enter_scope();
+                        let result = self.check_pattern_and_add_variables(alternative, value_type);
+                        self.exit_scope();
+                        result?;
+                    }
+                }
+            }
+            Pattern::Binding(binding) => {
+                // The binding name receives the whole value, the inner pattern destructures it
+                let symbol = Symbol {
+                    name: binding.name.clone(),
+                    type_id: value_type,
+                    is_mutable: true,
+                    position: binding.position,
+                    function_info: None,
+                    module_exports: None,
+                };
+                self.add_symbol(symbol)?;
+                self.check_pattern_and_add_variables(&binding.pattern, value_type)?;
+            }
+            Pattern::Type(type_pattern) => {
+                // The name is bound to the tested type, which narrows a union scrutinee
+                let pattern_type = self.ast_type_to_type_id(&type_pattern.pattern_type);
+                if !self.type_pattern_can_match(pattern_type, value_type) {
+                    return Err(BuluError::TypeError { stack: Vec::new(),
+                        message: format!(
+                            "Type pattern '{}' can never match a value of type {}",
+                            self.type_name_for_error(pattern_type),
+                            self.type_name_for_error(value_type)
+                        ),
+                        line: type_pattern.position.line,
+                        column: type_pattern.position.column,
+                        file: self.current_file.clone(),
+                    });
+                }
+                if let Some(name) = &type_pattern.name {
+                    let symbol = Symbol {
+                        name: name.clone(),
+                        type_id: pattern_type,
+                        is_mutable: true,
+                        position: type_pattern.position,
+                        function_info: None,
+                        module_exports: None,
+                    };
+                    self.add_symbol(symbol)?;
                 }
             }
             Pattern::Wildcard(_) | Pattern::Literal(_, _) | Pattern::Range(_) => {
@@ -0,0 +1,584 @@
+//! Closure capture and escape analysis
+//!
+//! Every lambda is given the list of local variables it captures from the
+//! functions around it. A capture is by reference when the variable is
+//! mutable and assigned somewhere, so the closure and its defining scope
+//! observe each other's writes; everything else is copied by value.
+//!
+//! A lambda escapes when it may outlive the expression that created it.
+//! Lambdas called on the spot or passed straight to an imported or built-in
+//! function are borrowed for that call; lambdas that are returned, stored,
+//! handed to user code or run as goroutines escape.
+
+use crate::ast::*;
+use crate::lexer::token::Position;
+use std::collections::HashMap;
+
+/// Captures and escape information for one lambda expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosureInfo {
+    /// Captured variables in order of first use, positioned at that use
+    pub captures: Vec<Capture>,
+    /// The closure may outlive the expression that created it
+    pub escapes: bool,
+}
+
+impl ClosureInfo {
+    /// The capture of `name`, if the lambda captures it
+    pub fn capture(&self, name: &str) -> Option<&Capture> {
+        self.captures.iter().find(|capture| capture.name == name)
+    }
+}
+
+/// Closure information for every lambda of a program, keyed by source position
+#[derive(Debug, Clone, Default)]
+pub struct ClosureAnalysis {
+    closures: HashMap<(usize, usize), ClosureInfo>,
+}
+
+impl ClosureAnalysis {
+    /// Information for the lambda starting at `position`
+    pub fn get(&self, position: Position) -> Option<&ClosureInfo> {
+        self.closures.get(&(position.line, position.column))
+    }
+
+    /// Number of analyzed lambdas
+    pub fn len(&self) -> usize {
+        self.closures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.closures.is_empty()
+    }
+
+    /// Add the lambdas of another analysis, e.g. for a module executed later
+    pub fn extend(&mut self, other: ClosureAnalysis) {
+        self.closures.extend(other.closures);
+    }
+}
+
+/// Analyze every lambda of a program
+pub fn analyze_closures(program: &Program) -> ClosureAnalysis {
+    let mut analyzer = ClosureAnalyzer::new();
+    analyzer.hoist(&program.statements);
+    for statement in &program.statements {
+        analyzer.walk_statement(statement);
+    }
+    analyzer.report()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BindingKind {
+    Variable { mutable: bool },
+    /// Functions, types and imports, which are looked up globally at runtime
+    Declaration,
+    Import,
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    kind: BindingKind,
+    /// Number of functions and lambdas around the declaration; 0 for globals
+    depth: usize,
+    writes: usize,
+}
+
+/// A lambda whose body is being walked
+#[derive(Debug)]
+struct OpenLambda {
+    position: Position,
+    depth: usize,
+    escapes: bool,
+    /// Captured bindings with the name and position of their first use
+    captures: Vec<(usize, String, Position)>,
+}
+
+struct ClosureAnalyzer {
+    bindings: Vec<Binding>,
+    scopes: Vec<HashMap<String, usize>>,
+    depth: usize,
+    open_lambdas: Vec<OpenLambda>,
+    finished: Vec<OpenLambda>,
+}
+
+impl ClosureAnalyzer {
+    fn new() -> Self {
+        Self {
+            bindings: Vec::new(),
+            scopes: vec![HashMap::new()],
+            depth: 0,
+            open_lambdas: Vec::new(),
+            finished: Vec::new(),
+        }
+    }
+
+    fn declare(&mut self, name: &str, kind: BindingKind) {
+        let index = self.bindings.len();
+        self.bindings.push(Binding {
+            kind,
+            depth: self.depth,
+            writes: 0,
+        });
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), index);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Record a use of `name`, capturing it in every open lambda declared inside its function
+    fn reference(&mut self, name: &str, position: Position, write: bool) {
+        let Some(index) = self.lookup(name) else {
+            return;
+        };
+        if write {
+            self.bindings[index].writes += 1;
+        }
+
+        let binding = &self.bindings[index];
+        if binding.depth == 0 || !matches!(binding.kind, BindingKind::Variable { .. }) {
+            return;
+        }
+        for lambda in self.open_lambdas.iter_mut().filter(|lambda| lambda.depth > binding.depth) {
+            if !lambda.captures.iter().any(|(captured, _, _)| *captured == index) {
+                lambda.captures.push((index, name.to_string(), position));
+            }
+        }
+    }
+
+    /// Declare functions and types before walking a block so they can be used before their definition
+    fn hoist(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            let statement = match statement {
+                Statement::Export(export) => export.item.as_ref(),
+                other => other,
+            };
+            match statement {
+                Statement::FunctionDecl(decl) => self.declare(&decl.name, BindingKind::Declaration),
+                Statement::StructDecl(decl) => self.declare(&decl.name, BindingKind::Declaration),
+                Statement::InterfaceDecl(decl) => self.declare(&decl.name, BindingKind::Declaration),
+                Statement::TypeAlias(decl) => self.declare(&decl.name, BindingKind::Declaration),
+                Statement::Import(import) => self.declare_import(import),
+                _ => {}
+            }
+        }
+    }
+
+    fn declare_import(&mut self, import: &ImportStmt) {
+        if let Some(items) = &import.items {
+            for item in items {
+                self.declare(item.alias.as_ref().unwrap_or(&item.name), BindingKind::Import);
+            }
+        } else if let Some(alias) = &import.alias {
+            self.declare(alias, BindingKind::Import);
+        } else if let Some(module_name) = import.path.rsplit('/').next() {
+            self.declare(module_name.trim_end_matches(".bu"), BindingKind::Import);
+        }
+    }
+
+    fn walk_block(&mut self, statements: &[Statement]) {
+        self.push_scope();
+        self.hoist(statements);
+        for statement in statements {
+            self.walk_statement(statement);
+        }
+        self.pop_scope();
+    }
+
+    fn walk_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::VariableDecl(decl) => {
+                self.walk_optional(decl.initializer.as_ref());
+                self.declare(&decl.name, BindingKind::Variable { mutable: !decl.is_const });
+            }
+            Statement::DestructuringDecl(decl) => {
+                self.walk_expression(&decl.initializer);
+                self.declare_pattern(&decl.pattern, BindingKind::Variable { mutable: !decl.is_const });
+            }
+            Statement::MultipleVariableDecl(decl) => {
+                for single in &decl.declarations {
+                    self.walk_optional(single.initializer.as_ref());
+                }
+                for single in &decl.declarations {
+                    self.declare(&single.name, BindingKind::Variable { mutable: !decl.is_const });
+                }
+            }
+            Statement::MultipleAssignment(stmt) => {
+                for value in &stmt.values {
+                    self.walk_expression(value);
+                }
+                for target in &stmt.targets {
+                    self.walk_assignment_target(target);
+                }
+            }
+            Statement::FunctionDecl(decl) => self.walk_function(decl, false),
+            Statement::StructDecl(decl) => {
+                for method in &decl.methods {
+                    self.walk_function(method, true);
+                }
+            }
+            Statement::InterfaceDecl(decl) => {
+                for method in &decl.methods {
+                    if let Some(default_method) = method.default_method() {
+                        self.walk_function(&default_method, true);
+                    }
+                }
+            }
+            Statement::TypeAlias(_) | Statement::Import(_) => {}
+            Statement::If(stmt) => {
+                self.walk_expression(&stmt.condition);
+                self.walk_block(&stmt.then_branch.statements);
+                if let Some(else_branch) = &stmt.else_branch {
+                    self.walk_statement(else_branch);
+                }
+            }
+            Statement::While(stmt) => {
+                self.walk_expression(&stmt.condition);
+                self.walk_block(&stmt.body.statements);
+            }
+            Statement::For(stmt) => {
+                self.walk_expression(&stmt.iterable);
+                self.push_scope();
+                if let Some(index_variable) = &stmt.index_variable {
+                    self.declare(index_variable, BindingKind::Variable { mutable: false });
+                }
+                self.declare(&stmt.variable, BindingKind::Variable { mutable: false });
+                self.walk_block(&stmt.body.statements);
+                self.pop_scope();
+            }
+            Statement::Match(stmt) => {
+                self.walk_expression(&stmt.expr);
+                for arm in &stmt.arms {
+                    self.push_scope();
+                    self.declare_pattern(&arm.pattern, BindingKind::Variable { mutable: false });
+                    self.walk_optional(arm.guard.as_ref());
+                    self.walk_statement(&arm.body);
+                    self.pop_scope();
+                }
+            }
+            Statement::Select(stmt) => {
+                for arm in &stmt.arms {
+                    self.push_scope();
+                    if let Some(op) = &arm.channel_op {
+                        self.walk_channel_operation(op);
+                    }
+                    self.walk_statement(&arm.body);
+                    self.pop_scope();
+                }
+            }
+            Statement::Return(stmt) => self.walk_optional(stmt.value.as_ref()),
+            Statement::Break(_) | Statement::Continue(_) => {}
+            Statement::Defer(stmt) => self.walk_statement(&stmt.stmt),
+            Statement::Try(stmt) => {
+                self.walk_block(&stmt.body.statements);
+                if let Some(catch_clause) = &stmt.catch_clause {
+                    self.push_scope();
+                    if let Some(error_var) = &catch_clause.error_var {
+                        self.declare(error_var, BindingKind::Variable { mutable: false });
+                    }
+                    self.walk_block(&catch_clause.body.statements);
+                    self.pop_scope();
+                }
+            }
+            Statement::Fail(stmt) => self.walk_expression(&stmt.message),
+            Statement::Export(stmt) => self.walk_statement(&stmt.item),
+            Statement::Expression(stmt) => self.walk_expression(&stmt.expr),
+            Statement::Block(stmt) => self.walk_block(&stmt.statements),
+        }
+    }
+
+    fn walk_function(&mut self, decl: &FunctionDecl, is_method: bool) {
+        self.depth += 1;
+        self.push_scope();
+        if is_method {
+            self.declare("this", BindingKind::Variable { mutable: false });
+        }
+        for param in &decl.params {
+            self.walk_optional(param.default_value.as_ref());
+            self.declare(&param.name, BindingKind::Variable { mutable: true });
+        }
+        self.walk_block(&decl.body.statements);
+        self.pop_scope();
+        self.depth -= 1;
+    }
+
+    fn walk_lambda(&mut self, lambda: &LambdaExpr, escapes: bool) {
+        self.depth += 1;
+        self.open_lambdas.push(OpenLambda {
+            position: lambda.position,
+            depth: self.depth,
+            escapes,
+            captures: Vec::new(),
+        });
+        self.push_scope();
+        for param in &lambda.params {
+            self.walk_optional(param.default_value.as_ref());
+            self.declare(&param.name, BindingKind::Variable { mutable: true });
+        }
+        self.walk_expression(&lambda.body);
+        self.pop_scope();
+        if let Some(finished) = self.open_lambdas.pop() {
+            self.finished.push(finished);
+        }
+        self.depth -= 1;
+    }
+
+    fn walk_channel_operation(&mut self, op: &ChannelOperation) {
+        self.walk_expression(&op.channel);
+        self.walk_optional(op.value.as_ref());
+        if let Some(variable) = &op.variable {
+            self.declare(variable, BindingKind::Variable { mutable: false });
+        }
+    }
+
+    fn declare_pattern(&mut self, pattern: &Pattern, kind: BindingKind) {
+        match pattern {
+            Pattern::Identifier(name, _) => self.declare(name, kind),
+            Pattern::Binding(binding) => {
+                self.declare(&binding.name, kind);
+                self.declare_pattern(&binding.pattern, kind);
+            }
+            Pattern::Struct(struct_pattern) => {
+                for field in &struct_pattern.fields {
+                    self.declare_pattern(&field.pattern, kind);
+                }
+            }
+            Pattern::Array(array_pattern) => {
+                for element in &array_pattern.elements {
+                    self.declare_pattern(element, kind);
+                }
+            }
+            Pattern::Tuple(tuple_pattern) => {
+                for element in &tuple_pattern.elements {
+                    self.declare_pattern(element, kind);
+                }
+            }
+            Pattern::Or(or_pattern) => {
+                for alternative in &or_pattern.patterns {
+                    self.declare_pattern(alternative, kind);
+                }
+            }
+            Pattern::Type(type_pattern) => {
+                if let Some(name) = &type_pattern.name {
+                    self.declare(name, kind);
+                }
+            }
+            Pattern::Wildcard(_) | Pattern::Literal(_, _) | Pattern::Range(_) => {}
+        }
+    }
+
+    fn walk_optional(&mut self, expression: Option<&Expression>) {
+        if let Some(expression) = expression {
+            self.walk_expression(expression);
+        }
+    }
+
+    /// Assigning to a plain name is a write; other targets read their object
+    fn walk_assignment_target(&mut self, target: &Expression) {
+        match target {
+            Expression::Identifier(ident) => self.reference(&ident.name, ident.position, true),
+            other => self.walk_expression(other),
+        }
+    }
+
+    /// Whether lambdas passed straight to this callee outlive the call. Imported
+    /// and built-in functions only call their callbacks; user code may keep them.
+    fn callee_keeps_arguments(&self, callee: &Expression) -> bool {
+        match callee {
+            Expression::Identifier(ident) => match self.lookup(&ident.name) {
+                Some(index) => self.bindings[index].kind != BindingKind::Import,
+                None => false,
+            },
+            Expression::Parenthesized(expr) => self.callee_keeps_arguments(&expr.expr),
+            _ => true,
+        }
+    }
+
+    /// Walk an expression in a position that does not let a lambda escape
+    fn walk_borrowed(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Lambda(lambda) => self.walk_lambda(lambda, false),
+            Expression::Parenthesized(expr) => self.walk_borrowed(&expr.expr),
+            other => self.walk_expression(other),
+        }
+    }
+
+    fn walk_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Literal(_) => {}
+            Expression::Identifier(ident) => self.reference(&ident.name, ident.position, false),
+            Expression::Binary(expr) => {
+                self.walk_expression(&expr.left);
+                self.walk_expression(&expr.right);
+            }
+            Expression::Unary(expr) => self.walk_expression(&expr.operand),
+            Expression::Call(expr) => {
+                self.walk_borrowed(&expr.callee);
+                let keeps_arguments = self.callee_keeps_arguments(&expr.callee);
+                for arg in &expr.args {
+                    if keeps_arguments {
+                        self.walk_expression(arg);
+                    } else {
+                        self.walk_borrowed(arg);
+                    }
+                }
+            }
+            Expression::MemberAccess(expr) => self.walk_expression(&expr.object),
+            Expression::Index(expr) => {
+                self.walk_expression(&expr.object);
+                self.walk_expression(&expr.index);
+            }
+            Expression::Assignment(expr) => {
+                self.walk_expression(&expr.value);
+                self.walk_assignment_target(&expr.target);
+            }
+            Expression::If(expr) => {
+                self.walk_expression(&expr.condition);
+                self.walk_expression(&expr.then_expr);
+                self.walk_expression(&expr.else_expr);
+            }
+            Expression::Match(expr) => {
+                self.walk_expression(&expr.expr);
+                for arm in &expr.arms {
+                    self.push_scope();
+                    self.declare_pattern(&arm.pattern, BindingKind::Variable { mutable: false });
+                    self.walk_optional(arm.guard.as_ref());
+                    self.walk_expression(&arm.expr);
+                    self.pop_scope();
+                }
+            }
+            Expression::Array(expr) => {
+                for element in &expr.elements {
+                    self.walk_expression(element);
+                }
+            }
+            Expression::Set(expr) => {
+                for element in &expr.elements {
+                    self.walk_expression(element);
+                }
+            }
+            Expression::Map(expr) => {
+                for entry in &expr.entries {
+                    self.walk_expression(&entry.key);
+                    self.walk_expression(&entry.value);
+                }
+            }
+            Expression::StructLiteral(expr) => {
+                for field in &expr.fields {
+                    self.walk_expression(&field.value);
+                }
+            }
+            Expression::Lambda(expr) => self.walk_lambda(expr, true),
+            Expression::Async(expr) => self.walk_expression(&expr.expr),
+            Expression::Await(expr) => self.walk_expression(&expr.expr),
+            Expression::Run(expr) => self.walk_expression(&expr.expr),
+            Expression::Channel(expr) => {
+                self.walk_expression(&expr.channel);
+                if let Some(value) = &expr.value {
+                    self.walk_expression(value);
+                }
+            }
+            Expression::Select(expr) => {
+                for arm in &expr.arms {
+                    self.push_scope();
+                    if let Some(op) = &arm.channel_op {
+                        self.walk_channel_operation(op);
+                    }
+                    self.walk_expression(&arm.expr);
+                    self.pop_scope();
+                }
+            }
+            Expression::Cast(expr) => self.walk_expression(&expr.expr),
+            Expression::TypeOf(expr) => self.walk_expression(&expr.expr),
+            Expression::Propagate(expr) => self.walk_expression(&expr.expr),
+            Expression::Range(expr) => {
+                self.walk_expression(&expr.start);
+                self.walk_expression(&expr.end);
+                if let Some(step) = &expr.step {
+                    self.walk_expression(step);
+                }
+            }
+            Expression::Yield(expr) => {
+                if let Some(value) = &expr.value {
+                    self.walk_expression(value);
+                }
+            }
+            Expression::Parenthesized(expr) => self.walk_expression(&expr.expr),
+            Expression::Block(expr) => self.walk_block(&expr.statements),
+            Expression::Tuple(expr) => {
+                for element in &expr.elements {
+                    self.walk_expression(element);
+                }
+            }
+        }
+    }
+
+    fn report(self) -> ClosureAnalysis {
+        let mut analysis = ClosureAnalysis::default();
+        for lambda in self.finished {
+            let captures = lambda
+                .captures
+                .into_iter()
+                .map(|(index, name, position)| {
+                    let binding = &self.bindings[index];
+                    let capture_type = match binding.kind {
+                        BindingKind::Variable { mutable: true } if binding.writes > 0 => CaptureType::ByReference,
+                        _ => CaptureType::ByValue,
+                    };
+                    Capture {
+                        name,
+                        capture_type,
+                        position,
+                    }
+                })
+                .collect();
+            analysis.closures.insert(
+                (lambda.position.line, lambda.position.column),
+                ClosureInfo {
+                    captures,
+                    escapes: lambda.escapes,
+                },
+            );
+        }
+        analysis
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze(source: &str) -> ClosureAnalysis {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        analyze_closures(&Parser::new(tokens).parse().unwrap())
+    }
+
+    #[test]
+    fn test_shadowed_names_are_not_captured() {
+        let analysis = analyze("func f(x: int32) {\n    let g = (x: int32) => x + 1\n}\n");
+        let info = analysis.get(Position::new(2, 13, 0)).unwrap();
+        assert!(info.captures.is_empty());
+        assert!(info.escapes);
+    }
+
+    #[test]
+    fn test_globals_and_functions_are_not_captured() {
+        let analysis = analyze("let limit = 3\nfunc h(): int32 { return 1 }\nfunc f() {\n    let g = () => limit + h()\n}\n");
+        assert!(analysis.get(Position::new(4, 13, 0)).unwrap().captures.is_empty());
+    }
+}
@@ -10,11 +10,15 @@ pub enum CompositeTypeId {
     Array(Box<TypeId>),
     Slice(Box<TypeId>),
     Map(Box<TypeId>, Box<TypeId>), // key type, value type
+    Set(Box<TypeId>), // element type
     Tuple(Vec<TypeId>), // tuple element types
     Struct(StructTypeInfo),
     Interface(InterfaceTypeInfo),
     Channel(ChannelTypeInfo),
     Promise(Box<TypeId>), // result type
+    Union(Vec<TypeId>), // member types, flattened and deduplicated
+    Result(Box<TypeId>, Box<TypeId>), // ok type, error type
+    Option(Box<TypeId>), // inner type
 }
 
 /// Struct type information
@@ -123,6 +127,12 @@ impl TypeRegistry {
         self.register_composite_type(composite_type)
     }
 
+    /// Register a set type
+    pub fn register_set_type(&mut self, element_type: TypeId) -> u32 {
+        let composite_type = CompositeTypeId::Set(Box::new(element_type));
+        self.register_composite_type(composite_type)
+    }
+
     /// Register a tuple type
     pub fn register_tuple_type(&mut self, element_types: Vec<TypeId>) -> u32 {
         let composite_type = CompositeTypeId::Tuple(element_types);
@@ -153,6 +163,57 @@ impl TypeRegistry {
         self.register_composite_type(composite_type)
     }
 
+    /// Register a union type. Members should already be flattened and in canonical order
+    pub fn register_union_type(&mut self, member_types: Vec<TypeId>) -> u32 {
+        let composite_type = CompositeTypeId::Union(member_types);
+        self.register_composite_type(composite_type)
+    }
+
+    /// Register a `Result<T, E>` type
+    pub fn register_result_type(&mut self, ok_type: TypeId, error_type: TypeId) -> u32 {
+        let composite_type = CompositeTypeId::Result(Box::new(ok_type), Box::new(error_type));
+        self.register_composite_type(composite_type)
+    }
+
+    /// Register an `Option<T>` type
+    pub fn register_option_type(&mut self, inner_type: TypeId) -> u32 {
+        let composite_type = CompositeTypeId::Option(Box::new(inner_type));
+        self.register_composite_type(composite_type)
+    }
+
+    /// Get the ok and error types of a result
+    pub fn get_result_types(&self, type_id: TypeId) -> Option<(TypeId, TypeId)> {
+        match type_id {
+            TypeId::Result(id) => match self.get_composite_type(id) {
+                Some(CompositeTypeId::Result(ok_type, error_type)) => Some((**ok_type, **error_type)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Get the inner type of an option
+    pub fn get_option_type(&self, type_id: TypeId) -> Option<TypeId> {
+        match type_id {
+            TypeId::Option(id) => match self.get_composite_type(id) {
+                Some(CompositeTypeId::Option(inner_type)) => Some(**inner_type),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Get the member types of a union
+    pub fn get_union_members(&self, type_id: TypeId) -> Option<&[TypeId]> {
+        match type_id {
+            TypeId::Union(id) => match self.get_composite_type(id) {
+                Some(CompositeTypeId::Union(members)) => Some(members),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     /// Get the element type of an array or slice
     pub fn get_element_type(&self, type_id: TypeId) -> Option<TypeId> {
         match type_id {
@@ -190,6 +251,17 @@ impl TypeRegistry {
         }
     }
 
+    /// Get the element type of a set
+    pub fn get_set_element_type(&self, type_id: TypeId) -> Option<TypeId> {
+        match type_id {
+            TypeId::Set(id) => match self.get_composite_type(id) {
+                Some(CompositeTypeId::Set(element_type)) => Some(**element_type),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     /// Get struct information by type ID
     pub fn get_struct_info(&self, type_id: TypeId) -> Option<&StructTypeInfo> {
         match type_id {
@@ -320,6 +392,10 @@ impl TypeRegistry {
                     "map".to_string()
                 }
             }
+            TypeId::Set(_) => match self.get_set_element_type(type_id) {
+                Some(element_type) => format!("set<{}>", self.get_type_name(element_type)),
+                None => "set".to_string(),
+            },
             TypeId::Struct(id) => {
                 if let Some(composite_type) = self.get_composite_type(id) {
                     if let CompositeTypeId::Struct(struct_info) = composite_type {
@@ -363,6 +439,26 @@ impl TypeRegistry {
                     "channel".to_string()
                 }
             }
+            TypeId::Union(_) => match self.get_union_members(type_id) {
+                Some(members) => members
+                    .iter()
+                    .map(|member| self.get_type_name(*member))
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+                None => "union".to_string(),
+            },
+            TypeId::Result(_) => match self.get_result_types(type_id) {
+                Some((ok_type, error_type)) => format!(
+                    "Result<{}, {}>",
+                    self.get_type_name(ok_type),
+                    self.get_type_name(error_type)
+                ),
+                None => "result".to_string(),
+            },
+            TypeId::Option(_) => match self.get_option_type(type_id) {
+                Some(inner_type) => format!("Option<{}>", self.get_type_name(inner_type)),
+                None => "option".to_string(),
+            },
             _ => PrimitiveType::type_name(type_id).to_string(),
         }
     }
@@ -389,6 +485,10 @@ impl CompositeTypeId {
             (CompositeTypeId::Map(k1, v1), CompositeTypeId::Map(k2, v2)) => {
                 PrimitiveType::is_assignable(**k1, **k2) && PrimitiveType::is_assignable(**v1, **v2)
             }
+            // Same set types
+            (CompositeTypeId::Set(a), CompositeTypeId::Set(b)) => {
+                PrimitiveType::is_assignable(**a, **b)
+            }
             // Same struct types
             (CompositeTypeId::Struct(s1), CompositeTypeId::Struct(s2)) => {
                 s1.name == s2.name && s1.type_params == s2.type_params
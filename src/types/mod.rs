@@ -6,6 +6,7 @@
 //! - User-defined types (structs, interfaces)
 //! - Generic types and type parameters
 //! - Type checking and inference
+//! - Closure capture and escape analysis
 //! - Type casting and conversions
 
 pub mod primitive;
@@ -14,10 +15,16 @@ pub mod checker;
 pub mod casting;
 pub mod generics;
 pub mod async_types;
+pub mod patterns;
+pub mod closures;
+pub mod scope;
 
 pub use primitive::*;
 pub use composite::*;
 pub use checker::*;
 pub use casting::*;
 pub use generics::*;
-pub use async_types::*;
\ No newline at end of file
+pub use async_types::*;
+pub use patterns::*;
+pub use closures::*;
+pub use scope::ScopeChain;
\ No newline at end of file
@@ -0,0 +1,127 @@
+//! Match exhaustiveness and reachability analysis
+//!
+//! Arms with a guard never contribute to coverage, since the guard may
+//! reject any value. An `name @ pattern` binding covers exactly what its
+//! inner pattern covers. Type patterns (`name: Type`) cover one member of a
+//! union; whether a union is fully covered is decided by the type checker.
+
+use crate::ast::nodes::{LiteralValue, Pattern, Type};
+
+/// Coverage information for the arms of a match
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MatchCoverage {
+    /// Every possible value is handled by some unguarded arm
+    pub exhaustive: bool,
+    /// Indices of arms that can never be selected
+    pub unreachable_arms: Vec<usize>,
+}
+
+/// Whether a pattern matches every value of the scrutinee type
+pub fn is_irrefutable(pattern: &Pattern) -> bool {
+    match pattern {
+        Pattern::Wildcard(_) | Pattern::Identifier(_, _) => true,
+        Pattern::Binding(binding) => is_irrefutable(&binding.pattern),
+        Pattern::Or(or_pattern) => or_pattern.patterns.iter().any(is_irrefutable),
+        Pattern::Tuple(tuple_pattern) => tuple_pattern.elements.iter().all(is_irrefutable),
+        Pattern::Type(type_pattern) => matches!(type_pattern.pattern_type, Type::Any),
+        Pattern::Literal(_, _) | Pattern::Struct(_) | Pattern::Array(_) | Pattern::Range(_) => false,
+    }
+}
+
+/// Collect the boolean literals a pattern matches
+fn collect_bool_literals(pattern: &Pattern, seen: &mut [bool; 2]) {
+    match pattern {
+        Pattern::Literal(LiteralValue::Boolean(value), _) => seen[*value as usize] = true,
+        Pattern::Binding(binding) => collect_bool_literals(&binding.pattern, seen),
+        Pattern::Or(or_pattern) => {
+            for alternative in &or_pattern.patterns {
+                collect_bool_literals(alternative, seen);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Analyze match arms given as `(pattern, has_guard)` pairs, in source order
+pub fn analyze_match(arms: &[(&Pattern, bool)], scrutinee_is_bool: bool) -> MatchCoverage {
+    let mut coverage = MatchCoverage::default();
+    let mut seen_bools = [false; 2];
+
+    for (index, (pattern, has_guard)) in arms.iter().enumerate() {
+        if coverage.exhaustive {
+            coverage.unreachable_arms.push(index);
+            continue;
+        }
+
+        if *has_guard {
+            continue;
+        }
+
+        if is_irrefutable(pattern) {
+            coverage.exhaustive = true;
+        } else if scrutinee_is_bool {
+            collect_bool_literals(pattern, &mut seen_bools);
+            coverage.exhaustive = seen_bools[0] && seen_bools[1];
+        }
+    }
+
+    coverage
+}
+
+/// Collect the types a pattern matches as a whole: type patterns contribute their
+/// type and a `null` literal contributes `Type::Null`
+pub fn collect_pattern_types<'a>(pattern: &'a Pattern, types: &mut Vec<&'a Type>) {
+    const NULL: &Type = &Type::Null;
+    match pattern {
+        Pattern::Type(type_pattern) => types.push(&type_pattern.pattern_type),
+        Pattern::Literal(LiteralValue::Null, _) => types.push(NULL),
+        Pattern::Binding(binding) => collect_pattern_types(&binding.pattern, types),
+        Pattern::Or(or_pattern) => {
+            for alternative in &or_pattern.patterns {
+                collect_pattern_types(alternative, types);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The names `typeof` reports for runtime values of a type, or `None` if any value matches.
+/// Integer literals are untyped at runtime and report `integer`, so every integer type accepts it.
+pub fn runtime_type_names(ty: &Type) -> Option<Vec<String>> {
+    let names: &[&str] = match ty {
+        Type::Any => return None,
+        Type::Int8 => &["int8", "integer"],
+        Type::Int16 => &["int16", "integer"],
+        Type::Int32 => &["int32", "integer"],
+        Type::Int64 => &["int64", "integer"],
+        Type::UInt8 => &["uint8", "byte", "integer"],
+        Type::UInt16 => &["uint16", "integer"],
+        Type::UInt32 => &["uint32", "integer"],
+        Type::UInt64 => &["uint64", "integer"],
+        Type::Float32 => &["float32"],
+        Type::Float64 => &["float64"],
+        Type::Bool => &["bool"],
+        Type::Char => &["char"],
+        Type::String => &["string"],
+        Type::Null | Type::Void => &["null"],
+        Type::Array(_) | Type::Slice(_) => &["array", "slice"],
+        Type::Map(_) => &["map"],
+        Type::Set(_) => &["set"],
+        Type::Tuple(_) => &["tuple"],
+        Type::Function(_) => &["function"],
+        Type::Channel(_) => &["channel"],
+        Type::Promise(_) => &["promise"],
+        Type::Union(union) => {
+            let mut names = Vec::new();
+            for member in &union.types {
+                names.extend(runtime_type_names(member)?);
+            }
+            return Some(names);
+        }
+        Type::Struct(struct_type) => return Some(vec![struct_type.name.clone()]),
+        Type::Interface(interface_type) => return Some(vec![interface_type.name.clone()]),
+        Type::Generic(generic) => return Some(vec![generic.name.clone()]),
+        Type::Named(name) => return Some(vec![name.clone()]),
+    };
+    Some(names.iter().map(|name| name.to_string()).collect())
+}
@@ -29,6 +29,7 @@ pub enum TypeId {
     Array(u32),
     Slice(u32),
     Map(u32),      // composite type ID
+    Set(u32),      // composite type ID
     Function(u32), // placeholder for function signature
 
     // User-defined types
@@ -41,12 +42,17 @@ pub enum TypeId {
     // Async types
     Promise(u32), // promise type ID
 
-    // Result types
-    Result(u32), // result type ID
+    // Result and option types
+    Result(u32), // result type ID (ok and error types)
+    Option(u32), // option type ID (inner type)
 
     // Tuple types
     Tuple(u32), // tuple type ID
 
+    // Union types
+    Union(u32), // union type ID
+    Null,       // the null member of optional types
+
     // Special types
     Unknown,
 }
@@ -294,6 +300,7 @@ impl PrimitiveType {
             Type::Array(_) => TypeId::Array(0), // Placeholder - needs type registry
             Type::Slice(_) => TypeId::Slice(0), // Placeholder - needs type registry
             Type::Map(_) => TypeId::Map(0),     // Placeholder - needs type registry
+            Type::Set(_) => TypeId::Set(0),     // Placeholder - needs type registry
             Type::Function(_) => TypeId::Function(0), // Placeholder
             Type::Struct(_) => TypeId::Struct(0), // Placeholder - needs type registry
             Type::Interface(_) => TypeId::Interface(0), // Placeholder - needs type registry
@@ -356,6 +363,7 @@ impl PrimitiveType {
             TypeId::Array(_) => "array",
             TypeId::Slice(_) => "slice",
             TypeId::Map(_) => "map",
+            TypeId::Set(_) => "set",
             TypeId::Function(_) => "function",
             TypeId::Struct(_) => "struct",
             TypeId::Interface(_) => "interface",
@@ -364,7 +372,10 @@ impl PrimitiveType {
             TypeId::Void => "void",
             TypeId::Promise(_) => "promise",
             TypeId::Result(_) => "result",
+            TypeId::Option(_) => "option",
             TypeId::Tuple(_) => "tuple",
+            TypeId::Union(_) => "union",
+            TypeId::Null => "null",
         }
     }
 
@@ -374,6 +385,8 @@ impl PrimitiveType {
             LiteralValue::Integer(_) => TypeId::Int32, // Default integer type
             LiteralValue::Float(_) => TypeId::Float64, // Default float type
             LiteralValue::String(_) => TypeId::String,
+            // `[]byte` needs a slice id from the type registry, see TypeChecker
+            LiteralValue::ByteString(_) => TypeId::Any,
             LiteralValue::Char(_) => TypeId::Char,
             LiteralValue::Boolean(_) => TypeId::Bool,
             LiteralValue::Null => TypeId::Any,
@@ -600,7 +613,8 @@ pub enum RuntimeValue {
     Array(Vec<RuntimeValue>),                             // Array of values
     Slice(Vec<RuntimeValue>),                             // Slice of values (dynamic array)
     Tuple(Vec<RuntimeValue>),                             // Tuple of values
-    Map(std::collections::HashMap<String, RuntimeValue>), // Map/dictionary
+    Map(MapValue),                                        // Map/dictionary, see `sorted_map_entries`
+    Set(Vec<RuntimeValue>), // Distinct elements in ascending order, see `runtime::sets`
     Range(i64, i64, Option<i64>),                         // Range (start, end, step)
     Integer(i64),                                         // Generic integer for compatibility
     Byte(u8),
@@ -659,6 +673,7 @@ impl RuntimeValue {
             RuntimeValue::Slice(_) => PrimitiveType::Any, // Slices are treated as Any type
             RuntimeValue::Tuple(_) => PrimitiveType::Any, // Tuples are treated as Any type
             RuntimeValue::Map(_) => PrimitiveType::Any,  // Maps are treated as Any type
+            RuntimeValue::Set(_) => PrimitiveType::Any,  // Sets are treated as Any type
             RuntimeValue::Range(_, _, _) => PrimitiveType::Any, // Ranges are treated as Any type
             RuntimeValue::Integer(_) => PrimitiveType::Int64, // Generic integer maps to Int64
             RuntimeValue::Byte(_) => PrimitiveType::UInt8, // Byte maps to UInt8
@@ -695,6 +710,7 @@ impl RuntimeValue {
             RuntimeValue::Slice(slice) => !slice.is_empty(), // Slices are truthy if not empty
             RuntimeValue::Tuple(tuple) => !tuple.is_empty(), // Tuples are truthy if not empty
             RuntimeValue::Map(map) => !map.is_empty(), // Maps are truthy if not empty
+            RuntimeValue::Set(set) => !set.is_empty(), // Sets are truthy if not empty
             RuntimeValue::Range(start, end, _) => start != end, // Ranges are truthy if not empty
             RuntimeValue::Integer(i) => *i != 0, // Generic integer
             RuntimeValue::Byte(b) => *b != 0, // Byte is truthy if not zero
@@ -718,8 +734,8 @@ impl RuntimeValue {
             RuntimeValue::UInt16(i) => i.to_string(),
             RuntimeValue::UInt32(i) => i.to_string(),
             RuntimeValue::UInt64(i) => i.to_string(),
-            RuntimeValue::Float32(f) => f.to_string(),
-            RuntimeValue::Float64(f) => f.to_string(),
+            RuntimeValue::Float32(f) => format_float32(*f),
+            RuntimeValue::Float64(f) => format_float64(*f),
             RuntimeValue::Bool(b) => b.to_string(),
             RuntimeValue::Char(c) => c.to_string(),
             RuntimeValue::String(s) => s.clone(),
@@ -775,12 +791,16 @@ impl RuntimeValue {
                 format!("({})", elements.join(", "))
             }
             RuntimeValue::Map(map) => {
-                let pairs: Vec<String> = map
-                    .iter()
+                let pairs: Vec<String> = sorted_map_entries(map)
+                    .into_iter()
                     .map(|(k, v)| format!("{}: {}", k, v.to_string()))
                     .collect();
                 format!("{{{}}}", pairs.join(", "))
             }
+            RuntimeValue::Set(set) => {
+                let elements: Vec<String> = set.iter().map(|v| v.to_string()).collect();
+                format!("set{{{}}}", elements.join(", "))
+            }
             RuntimeValue::Integer(i) => i.to_string(),
             RuntimeValue::Byte(b) => b.to_string(),
             RuntimeValue::Function(name) => format!("Function({})", name),
@@ -1081,6 +1101,236 @@ impl RuntimeValue {
     }
 }
 
+/// Shortest decimal form of a float64 that parses back to the same value.
+///
+/// Every place a float is turned into text (printing, string conversion, map
+/// keys, JSON) goes through this function or `format_float32`, so the same value
+/// always prints the same way. Integral values have no fraction (`2`), and
+/// exponents below -7 or from 21 up use scientific notation (`1e21`, `2.5e-8`)
+/// instead of long runs of zeros.
+pub fn format_float64(value: f64) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    layout_float(value.to_string(), format!("{:e}", value))
+}
+
+/// Shortest decimal form of a float32 that parses back to the same float32,
+/// so `float32(0.1)` prints as `0.1` rather than its float64 widening
+pub fn format_float32(value: f32) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    layout_float(value.to_string(), format!("{:e}", value))
+}
+
+/// The float64 nearest to a float32's shortest decimal form, for formatting
+/// a float32 with float64 routines without exposing widening noise
+pub fn widen_float32(value: f32) -> f64 {
+    format_float32(value).parse().unwrap_or(value as f64)
+}
+
+/// Pick the plain or scientific form; both are already shortest round-trip
+fn layout_float(plain: String, scientific: String) -> String {
+    let exponent: i32 = scientific
+        .split_once('e')
+        .and_then(|(_, exponent)| exponent.parse().ok())
+        .unwrap_or(0);
+    if (-7..21).contains(&exponent) {
+        plain
+    } else {
+        scientific
+    }
+}
+
+/// Entries of a runtime map.
+///
+/// Keys are stored as text so that maps of every key type share one
+/// representation. `key_kind` records what the keys were, so iteration
+/// hands back keys of the map's key type instead of strings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapValue {
+    entries: HashMap<String, RuntimeValue>,
+    key_kind: MapKeyKind,
+}
+
+/// What the keys of a map were before they were stored as text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MapKeyKind {
+    #[default]
+    String,
+    Integer,
+    Float,
+    Bool,
+    Char,
+}
+
+impl MapKeyKind {
+    /// The kind of key `key` is
+    pub fn of(key: &RuntimeValue) -> Self {
+        match key {
+            RuntimeValue::Float32(_) | RuntimeValue::Float64(_) => MapKeyKind::Float,
+            RuntimeValue::Bool(_) => MapKeyKind::Bool,
+            RuntimeValue::Char(_) => MapKeyKind::Char,
+            key if key.get_type().is_integer() => MapKeyKind::Integer,
+            _ => MapKeyKind::String,
+        }
+    }
+}
+
+impl MapValue {
+    /// A map whose keys are of `key_kind`
+    pub fn with_key_kind(entries: HashMap<String, RuntimeValue>, key_kind: MapKeyKind) -> Self {
+        Self { entries, key_kind }
+    }
+
+    pub fn key_kind(&self) -> MapKeyKind {
+        self.key_kind
+    }
+
+    /// A stored key as a value of the map's key kind
+    pub fn key_value(&self, key: &str) -> RuntimeValue {
+        let parsed = match self.key_kind {
+            MapKeyKind::String => None,
+            MapKeyKind::Integer => key.parse().ok().map(RuntimeValue::Integer),
+            MapKeyKind::Float => key.parse().ok().map(RuntimeValue::Float64),
+            MapKeyKind::Bool => key.parse().ok().map(RuntimeValue::Bool),
+            MapKeyKind::Char => key.parse().ok().map(RuntimeValue::Char),
+        };
+        parsed.unwrap_or_else(|| RuntimeValue::String(key.to_string()))
+    }
+
+    pub fn into_entries(self) -> HashMap<String, RuntimeValue> {
+        self.entries
+    }
+}
+
+impl Default for MapValue {
+    fn default() -> Self {
+        Self::with_key_kind(HashMap::new(), MapKeyKind::String)
+    }
+}
+
+/// A map with string keys
+impl From<HashMap<String, RuntimeValue>> for MapValue {
+    fn from(entries: HashMap<String, RuntimeValue>) -> Self {
+        Self::with_key_kind(entries, MapKeyKind::String)
+    }
+}
+
+impl FromIterator<(String, RuntimeValue)> for MapValue {
+    fn from_iter<I: IntoIterator<Item = (String, RuntimeValue)>>(entries: I) -> Self {
+        HashMap::from_iter(entries).into()
+    }
+}
+
+impl IntoIterator for MapValue {
+    type Item = (String, RuntimeValue);
+    type IntoIter = std::collections::hash_map::IntoIter<String, RuntimeValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a MapValue {
+    type Item = (&'a String, &'a RuntimeValue);
+    type IntoIter = std::collections::hash_map::Iter<'a, String, RuntimeValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl std::ops::Deref for MapValue {
+    type Target = HashMap<String, RuntimeValue>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+impl std::ops::DerefMut for MapValue {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.entries
+    }
+}
+
+/// Entries of a runtime map in ascending key order.
+///
+/// Maps make no promise about insertion order. Every observable traversal
+/// (printing, `for` loops, `sortedKeys()` and `entriesSorted()`) goes through
+/// this function so that output is the same from run to run. Integer keys
+/// come first in numeric order, followed by every other key in text order.
+pub fn sorted_map_entries(map: &HashMap<String, RuntimeValue>) -> Vec<(&String, &RuntimeValue)> {
+    let mut entries: Vec<(&String, &RuntimeValue)> = map.iter().collect();
+    // Keys are unique, so the text breaks ties such as "1" and "01"
+    entries.sort_by_cached_key(|(key, _)| (key.parse::<i64>().map_err(|_| ()), key.as_str()));
+    entries
+}
+
+/// Text built up by appending, as done by `std/strings` builders.
+///
+/// Appending copies only the new text into a buffer that grows geometrically,
+/// so building a string of n characters costs O(n) however it is split up.
+/// The character count is kept alongside, so the length of a long text is
+/// known without rescanning it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StringBuilder {
+    text: String,
+    chars: usize,
+}
+
+impl StringBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A builder starting with `text`
+    pub fn from_string(text: String) -> Self {
+        let chars = text.chars().count();
+        Self { text, chars }
+    }
+
+    pub fn push_str(&mut self, text: &str) {
+        self.text.push_str(text);
+        self.chars += text.chars().count();
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.text.push(c);
+        self.chars += 1;
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Length in characters
+    pub fn char_len(&self) -> usize {
+        self.chars
+    }
+
+    /// Length in bytes of the UTF-8 text
+    pub fn byte_len(&self) -> usize {
+        self.text.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Empty the builder, keeping its buffer for reuse
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.chars = 0;
+    }
+
+    pub fn into_string(self) -> String {
+        self.text
+    }
+}
+
 impl fmt::Display for RuntimeValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -1092,8 +1342,8 @@ impl fmt::Display for RuntimeValue {
             RuntimeValue::UInt16(i) => write!(f, "{}", i),
             RuntimeValue::UInt32(i) => write!(f, "{}", i),
             RuntimeValue::UInt64(i) => write!(f, "{}", i),
-            RuntimeValue::Float32(f_val) => write!(f, "{}", f_val),
-            RuntimeValue::Float64(f_val) => write!(f, "{}", f_val),
+            RuntimeValue::Float32(f_val) => write!(f, "{}", format_float32(*f_val)),
+            RuntimeValue::Float64(f_val) => write!(f, "{}", format_float64(*f_val)),
             RuntimeValue::Bool(b) => write!(f, "{}", b),
             RuntimeValue::Char(c) => write!(f, "{}", c),
             RuntimeValue::String(s) => write!(f, "{}", s),
@@ -1121,12 +1371,16 @@ impl fmt::Display for RuntimeValue {
                 write!(f, "({})", elements.join(", "))
             }
             RuntimeValue::Map(map) => {
-                let pairs: Vec<String> = map
-                    .iter()
+                let pairs: Vec<String> = sorted_map_entries(map)
+                    .into_iter()
                     .map(|(k, v)| format!("{}: {}", k, v.to_string()))
                     .collect();
                 write!(f, "{{{}}}", pairs.join(", "))
             }
+            RuntimeValue::Set(set) => {
+                let elements: Vec<String> = set.iter().map(|v| v.to_string()).collect();
+                write!(f, "set{{{}}}", elements.join(", "))
+            }
             RuntimeValue::Integer(i) => write!(f, "{}", i),
             RuntimeValue::Byte(b) => write!(f, "{}", b),
             RuntimeValue::Function(name) => write!(f, "function({})", name),
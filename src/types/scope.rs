@@ -0,0 +1,140 @@
+//! Lexical scopes for the type checker
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Nested scopes kept as one chain of bindings per name, innermost last.
+///
+/// A lookup is a single hash probe however deep the nesting is, bindings are
+/// shared with `Rc` instead of cloned, and leaving a scope only touches the
+/// names it declared.
+#[derive(Debug)]
+pub struct ScopeChain<T> {
+    globals: HashMap<String, Rc<T>>,
+    /// Live local bindings of each name with the depth of their scope
+    locals: HashMap<String, Vec<(usize, Rc<T>)>>,
+    /// Names declared by each open local scope
+    frames: Vec<Vec<String>>,
+}
+
+impl<T> Default for ScopeChain<T> {
+    fn default() -> Self {
+        Self {
+            globals: HashMap::new(),
+            locals: HashMap::new(),
+            frames: Vec::new(),
+        }
+    }
+}
+
+impl<T> ScopeChain<T> {
+    /// A chain holding only the global scope
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of open local scopes
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn push(&mut self) {
+        self.frames.push(Vec::new());
+    }
+
+    /// Leave the innermost local scope; the global scope is never popped
+    pub fn pop(&mut self) {
+        let Some(names) = self.frames.pop() else {
+            return;
+        };
+        for name in names {
+            if let Entry::Occupied(mut bindings) = self.locals.entry(name) {
+                bindings.get_mut().pop();
+                if bindings.get().is_empty() {
+                    bindings.remove();
+                }
+            }
+        }
+    }
+
+    /// The innermost binding of `name`
+    pub fn get(&self, name: &str) -> Option<&Rc<T>> {
+        self.locals
+            .get(name)
+            .and_then(|bindings| bindings.last())
+            .map(|(_, value)| value)
+            .or_else(|| self.globals.get(name))
+    }
+
+    /// Whether the innermost scope itself declares `name`
+    pub fn declared_in_current(&self, name: &str) -> bool {
+        let depth = self.depth();
+        if depth == 0 {
+            return self.globals.contains_key(name);
+        }
+        self.locals
+            .get(name)
+            .and_then(|bindings| bindings.last())
+            .is_some_and(|(scope, _)| *scope == depth)
+    }
+
+    /// Bind `name` in the innermost scope, replacing a binding of that scope
+    pub fn insert(&mut self, name: String, value: Rc<T>) {
+        let depth = self.depth();
+        if depth == 0 {
+            self.globals.insert(name, value);
+            return;
+        }
+        let bindings = self.locals.entry(name.clone()).or_default();
+        match bindings.last_mut() {
+            Some((scope, binding)) if *scope == depth => *binding = value,
+            _ => {
+                bindings.push((depth, value));
+                if let Some(frame) = self.frames.last_mut() {
+                    frame.push(name);
+                }
+            }
+        }
+    }
+
+    /// The bindings of the global scope
+    pub fn globals(&self) -> &HashMap<String, Rc<T>> {
+        &self.globals
+    }
+
+    /// The bindings of the global scope, whatever scopes are open
+    pub fn globals_mut(&mut self) -> &mut HashMap<String, Rc<T>> {
+        &mut self.globals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shadowing_and_pop() {
+        let mut scopes = ScopeChain::new();
+        scopes.insert("x".to_string(), Rc::new(1));
+        scopes.push();
+        assert!(!scopes.declared_in_current("x"));
+        scopes.insert("x".to_string(), Rc::new(2));
+        scopes.insert("y".to_string(), Rc::new(3));
+        scopes.push();
+        scopes.globals_mut().insert("z".to_string(), Rc::new(4));
+
+        assert_eq!(scopes.get("x").map(|v| **v), Some(2));
+        assert!(!scopes.declared_in_current("z"));
+        scopes.pop();
+        assert!(scopes.declared_in_current("x"));
+        scopes.pop();
+
+        assert_eq!(scopes.get("x").map(|v| **v), Some(1));
+        assert_eq!(scopes.get("y"), None);
+        assert_eq!(scopes.get("z").map(|v| **v), Some(4));
+        scopes.pop();
+        assert_eq!(scopes.depth(), 0);
+        assert!(scopes.declared_in_current("x"));
+    }
+}
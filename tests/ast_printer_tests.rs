@@ -78,13 +78,15 @@ fn test_ast_printer_struct_declaration() {
                 name: "x".to_string(),
                 field_type: Type::Float64,
                 position: AstBuilder::dummy_pos(),
-                is_private: false
+                is_private: false,
+                attributes: Vec::new()
             },
             StructField {
                 name: "y".to_string(),
                 field_type: Type::Float64,
                 position: AstBuilder::dummy_pos(),
-                is_private: false
+                is_private: false,
+                attributes: Vec::new()
             },
         ],
         methods: vec![],
@@ -332,6 +334,7 @@ fn test_ast_printer_complex_types() {
     let array_type = Type::Array(ArrayType {
         element_type: Box::new(Type::String),
         size: Some(10),
+        size_expr: None,
     });
 
     let mut printer = AstPrinter::new();
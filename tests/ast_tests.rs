@@ -110,13 +110,15 @@ fn test_struct_declaration() {
                 name: "x".to_string(),
                 field_type: Type::Float64,
                 position: dummy_pos(),
-                is_private: false
+                is_private: false,
+                attributes: Vec::new()
             },
             StructField {
                 name: "y".to_string(),
                 field_type: Type::Float64,
                 position: dummy_pos(),
-                is_private: false
+                is_private: false,
+                attributes: Vec::new()
             },
         ],
         methods: vec![],
@@ -142,6 +144,7 @@ fn test_interface_declaration() {
                 name: "area".to_string(),
                 params: vec![],
                 return_type: Some(Type::Float64),
+                default_body: None,
                 position: dummy_pos(),
                 is_private: true,
             },
@@ -149,6 +152,7 @@ fn test_interface_declaration() {
                 name: "perimeter".to_string(),
                 params: vec![],
                 return_type: Some(Type::Float64),
+                default_body: None,
                 position: dummy_pos(),
                 is_private: true,
             },
@@ -342,6 +346,7 @@ fn test_type_system() {
     let array_type = Type::Array(ArrayType {
         element_type: Box::new(Type::String),
         size: Some(10),
+        size_expr: None,
     });
 
     if let Type::Array(arr) = array_type {
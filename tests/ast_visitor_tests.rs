@@ -223,6 +223,10 @@ impl Visitor<()> for NodeCounter {
         self.visit_expression(&expr.expr);
     }
 
+    fn visit_propagate_expr(&mut self, expr: &PropagateExpr) {
+        self.visit_expression(&expr.expr);
+    }
+
     fn visit_range_expr(&mut self, expr: &RangeExpr) {
         self.visit_expression(&expr.start);
         self.visit_expression(&expr.end);
@@ -559,6 +563,10 @@ impl MutVisitor for IdentifierRenamer {
         self.visit_expression(&mut expr.expr);
     }
 
+    fn visit_propagate_expr(&mut self, expr: &mut PropagateExpr) {
+        self.visit_expression(&mut expr.expr);
+    }
+
     fn visit_range_expr(&mut self, expr: &mut RangeExpr) {
         self.visit_expression(&mut expr.start);
         self.visit_expression(&mut expr.end);
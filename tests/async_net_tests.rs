@@ -0,0 +1,279 @@
+//! Tests for network I/O driven by the netpoller reactor
+
+mod common;
+
+use bulu::compiler::ir::{IrOpcode, IrProgram};
+use bulu::compiler::IrGenerator;
+use bulu::runtime::async_executor::{net_reactor, wait_net_op, NetOp, NetOutput};
+use bulu::runtime::builtins::{async_net_op, builtin_tcpserver_bind, net_output_to_result};
+use bulu::runtime::context::{self, Context};
+use bulu::runtime::goroutine::{GoroutineRuntime, GoroutineTask};
+use bulu::runtime::netpoller;
+use bulu::types::primitive::RuntimeValue;
+use common::parse_source;
+use std::collections::HashMap;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Submit `op` to the reactor and hand back a receiver for its result
+fn submit(op: NetOp) -> mpsc::Receiver<io::Result<NetOutput>> {
+    let (sender, receiver) = mpsc::channel();
+    net_reactor().submit(
+        op,
+        Box::new(move |output| {
+            let _ = sender.send(output);
+        }),
+    );
+    receiver
+}
+
+/// Helper function to connect a client and return both ends of the connection
+fn connected_pair() -> (TcpStream, Arc<Mutex<TcpStream>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (server, _) = listener.accept().unwrap();
+    (client, Arc::new(Mutex::new(server)))
+}
+
+fn result_value(result: &RuntimeValue) -> RuntimeValue {
+    match result {
+        RuntimeValue::Struct { name, fields } if name == "Result" => {
+            assert_eq!(fields.get("is_ok"), Some(&RuntimeValue::Bool(true)), "{:?}", fields);
+            fields.get("value").cloned().unwrap()
+        }
+        other => panic!("expected a Result, got {:?}", other),
+    }
+}
+
+/// A goroutine task calling function `name` of `program`
+fn function_task(program: &Arc<IrProgram>, name: &str, args: Vec<RuntimeValue>) -> GoroutineTask {
+    GoroutineTask::Function {
+        name: name.to_string(),
+        args,
+        program: Arc::clone(program),
+        globals: HashMap::new(),
+        struct_definitions: HashMap::new(),
+    }
+}
+
+/// Wait until `count` goroutines of `runtime` have completed
+fn wait_for_completed(runtime: &GoroutineRuntime, count: u64) {
+    let start = Instant::now();
+    while runtime.stats().completed_goroutines < count {
+        assert!(start.elapsed() < Duration::from_secs(5), "{:?}", runtime.stats());
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Bind a Bulu-level server, connect to it, and return the client and the
+/// accepted connection as a Bulu value
+fn accepted_connection() -> (TcpStream, RuntimeValue) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    drop(listener);
+    let server = result_value(&builtin_tcpserver_bind(&[RuntimeValue::String(address.clone())]).unwrap());
+    let accepted = submit(async_net_op("accept", &[server]).unwrap().unwrap());
+    let client = TcpStream::connect(&address).unwrap();
+    let connection = result_value(&net_output_to_result(
+        accepted.recv_timeout(Duration::from_secs(5)).unwrap(),
+    ));
+    (client, connection)
+}
+
+const WAITER_SOURCE: &str = r#"
+func waiter(conn: any, buf: any) {
+    let n = await conn.read(buf)
+}
+
+func quick() {
+}
+"#;
+
+#[test]
+fn test_awaited_tcp_methods_complete_through_the_reactor() {
+    // Find a free port for the Bulu-level server
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let address = format!("127.0.0.1:{}", port);
+    let server = result_value(&builtin_tcpserver_bind(&[RuntimeValue::String(address.clone())]).unwrap());
+
+    let op = async_net_op("accept", &[server]).unwrap().expect("accept is a network method");
+    let accepted = submit(op);
+    let mut client = TcpStream::connect(&address).unwrap();
+    let connection = result_value(&net_output_to_result(
+        accepted.recv_timeout(Duration::from_secs(5)).unwrap(),
+    ));
+
+    let buffer = RuntimeValue::Array(vec![RuntimeValue::Int32(0); 16]);
+    let op = async_net_op("read", &[connection.clone(), buffer]).unwrap().unwrap();
+    let read = submit(op);
+    // Nothing has been sent yet, so the read stays pending without holding a thread
+    assert!(read.recv_timeout(Duration::from_millis(50)).is_err());
+    client.write_all(b"ping").unwrap();
+    let read = net_output_to_result(read.recv_timeout(Duration::from_secs(5)).unwrap());
+    assert_eq!(result_value(&read), RuntimeValue::Int64(4));
+
+    let data = RuntimeValue::String("pong".to_string());
+    let op = async_net_op("write", &[connection, data]).unwrap().unwrap();
+    let written = net_output_to_result(submit(op).recv_timeout(Duration::from_secs(5)).unwrap());
+    assert_eq!(result_value(&written), RuntimeValue::Int64(4));
+    let mut reply = [0u8; 4];
+    client.read_exact(&mut reply).unwrap();
+    assert_eq!(&reply, b"pong");
+
+    // Anything else is left to the regular call path
+    assert!(async_net_op("peer_addr", &[RuntimeValue::Int32(1)]).unwrap().is_none());
+}
+
+#[test]
+fn test_pending_reads_do_not_block_each_other() {
+    let (mut first_client, first) = connected_pair();
+    let (mut second_client, second) = connected_pair();
+
+    let first_read = submit(NetOp::Read { stream: first, buffer_size: 64 });
+    let second_read = submit(NetOp::Read { stream: second, buffer_size: 64 });
+
+    // The second read finishes while the first is still waiting for data
+    second_client.write_all(b"second").unwrap();
+    match second_read.recv_timeout(Duration::from_secs(5)).unwrap() {
+        Ok(NetOutput::Read(bytes)) => assert_eq!(bytes, b"second"),
+        other => panic!("unexpected read result: {:?}", other.map(|_| ())),
+    }
+    assert!(first_read.try_recv().is_err());
+
+    first_client.write_all(b"first").unwrap();
+    match first_read.recv_timeout(Duration::from_secs(5)).unwrap() {
+        Ok(NetOutput::Read(bytes)) => assert_eq!(bytes, b"first"),
+        other => panic!("unexpected read result: {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_udp_round_trip_through_the_reactor() {
+    let receiver = Arc::new(std::net::UdpSocket::bind("127.0.0.1:0").unwrap());
+    let sender = Arc::new(std::net::UdpSocket::bind("127.0.0.1:0").unwrap());
+    let target = receiver.local_addr().unwrap().to_string();
+
+    let received = submit(NetOp::RecvFrom { socket: receiver, buffer_size: 64 });
+    let sent = wait_net_op(NetOp::SendTo {
+        socket: sender.clone(),
+        data: b"hello".to_vec(),
+        addr: target,
+    });
+    assert!(matches!(sent, Ok(NetOutput::Sent(5))));
+
+    match received.recv_timeout(Duration::from_secs(5)).unwrap() {
+        Ok(NetOutput::Received(bytes, from)) => {
+            assert_eq!(bytes, b"hello");
+            assert_eq!(from, sender.local_addr().unwrap());
+        }
+        other => panic!("unexpected recv result: {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_waiting_goroutine_gives_up_when_its_context_is_cancelled() {
+    let (_client, server) = connected_pair();
+    let context = Context::background().with_cancel();
+    let canceller = context.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        canceller.cancel();
+    });
+
+    let _scope = context::enter(Some(context));
+    let start = Instant::now();
+    let error = wait_net_op(NetOp::Read { stream: server, buffer_size: 64 }).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Interrupted);
+    assert!(error.to_string().contains("context canceled"));
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn test_await_on_a_method_call_is_lowered_to_call_async() {
+    let source = r#"
+func handle(conn: any) {
+    let n = await conn.read(buf)
+    let m = conn.write(buf)
+}
+"#;
+    let program = parse_source(source).unwrap();
+    let ir_program = IrGenerator::new().generate(&program).unwrap();
+    let handle = ir_program.functions.iter().find(|f| f.name == "handle").unwrap();
+    let opcodes: Vec<&IrOpcode> = handle
+        .basic_blocks
+        .iter()
+        .flat_map(|block| block.instructions.iter().map(|i| &i.opcode))
+        .collect();
+
+    assert_eq!(opcodes.iter().filter(|op| ***op == IrOpcode::CallAsync).count(), 1);
+    assert_eq!(opcodes.iter().filter(|op| ***op == IrOpcode::Call).count(), 1);
+    assert!(opcodes.contains(&&IrOpcode::Await));
+}
+
+#[test]
+fn test_reactor_readiness_never_reaches_the_scheduler_poller() {
+    netpoller::init_netpoller();
+    let scheduler_poller = netpoller::get_netpoller().unwrap();
+    let (mut client, server) = connected_pair();
+
+    let read = submit(NetOp::Read { stream: server, buffer_size: 64 });
+    // Let the reactor find the socket empty and register it before data arrives
+    thread::sleep(Duration::from_millis(50));
+    client.write_all(b"ready").unwrap();
+    assert_eq!(scheduler_poller.poll(Duration::from_millis(50)).unwrap(), Vec::<u64>::new());
+
+    match read.recv_timeout(Duration::from_secs(5)).unwrap() {
+        Ok(NetOutput::Read(bytes)) => assert_eq!(bytes, b"ready"),
+        other => panic!("unexpected read result: {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_awaiting_goroutine_lets_its_worker_run_others() {
+    let program = Arc::new(IrGenerator::new().generate(&parse_source(WAITER_SOURCE).unwrap()).unwrap());
+    let (mut client, connection) = accepted_connection();
+
+    // One worker: the quick goroutine only runs if the waiting one gives it up
+    let runtime = GoroutineRuntime::new(1);
+    let buffer = RuntimeValue::Array(vec![RuntimeValue::Int32(0); 16]);
+    runtime.spawn(function_task(&program, "waiter", vec![connection, buffer]));
+    thread::sleep(Duration::from_millis(50));
+    runtime.spawn(function_task(&program, "quick", Vec::new()));
+    wait_for_completed(&runtime, 1);
+    // The waiter is still suspended on its read
+    thread::sleep(Duration::from_millis(50));
+    let stats = runtime.stats();
+    assert_eq!((stats.completed_goroutines, stats.active_goroutines), (1, 1));
+
+    client.write_all(b"ping").unwrap();
+    wait_for_completed(&runtime, 2);
+    assert_eq!(runtime.stats().active_goroutines, 0);
+    runtime.shutdown();
+}
+
+#[test]
+fn test_cancelling_its_context_resumes_a_suspended_goroutine() {
+    let program = Arc::new(IrGenerator::new().generate(&parse_source(WAITER_SOURCE).unwrap()).unwrap());
+    let (_client, connection) = accepted_connection();
+
+    // The goroutine inherits the context it is spawned under; nothing is ever
+    // written, so only the cancellation can end its read
+    let runtime = GoroutineRuntime::new(1);
+    let context = Context::background().with_cancel();
+    {
+        let _scope = context::enter(Some(context.clone()));
+        let buffer = RuntimeValue::Array(vec![RuntimeValue::Int32(0); 16]);
+        runtime.spawn(function_task(&program, "waiter", vec![connection, buffer]));
+    }
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(runtime.stats().active_goroutines, 1);
+
+    context.cancel();
+    wait_for_completed(&runtime, 1);
+    assert_eq!(runtime.stats().active_goroutines, 0);
+    runtime.shutdown();
+}
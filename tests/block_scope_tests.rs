@@ -0,0 +1,56 @@
+//! Tests for the scopes of block statements
+
+mod common;
+
+use bulu::types::primitive::RuntimeValue;
+use common::{assert_error, call_main, parse_source, run_main};
+
+#[test]
+fn test_assignments_in_a_block_stay_visible_after_it() {
+    let source = r#"
+func main(): any {
+    let x = 1
+    {
+        let y = 2
+        x = x + y
+    }
+    return x
+}
+"#;
+    assert_eq!(run_main(source).unwrap(), RuntimeValue::Integer(3));
+}
+
+#[test]
+fn test_variables_declared_in_a_block_end_with_it() {
+    let source = r#"
+func main(): any {
+    {
+        let y = 2
+    }
+    return y
+}
+"#;
+    assert_error(run_main(source), "Undefined identifier 'y'");
+    // The interpreter drops the block's variables too
+    let program = parse_source(source).unwrap();
+    assert_error(call_main(&program), "Undefined variable 'y'");
+}
+
+#[test]
+fn test_a_failing_block_drops_its_scope() {
+    let source = r#"
+func main(): any {
+    let x = 1
+    try {
+        {
+            let x = 5
+            fail "boom"
+        }
+    } fail on err {
+        return x
+    }
+    return 0
+}
+"#;
+    assert_eq!(run_main(source).unwrap(), RuntimeValue::Integer(1));
+}
@@ -0,0 +1,111 @@
+//! Tests for the target/<profile>/<target> layout and fingerprint-based reuse
+
+use bulu::build::{host_target, BuildOptions, Builder, Fingerprint};
+use bulu::project::{create_project, Project};
+use std::fs;
+use tempfile::TempDir;
+
+fn builder(project: &Project, release: bool, target: Option<&str>) -> Builder {
+    let options = BuildOptions {
+        release,
+        target: target.map(|t| t.to_string()),
+        ..BuildOptions::default()
+    };
+    Builder::new(project.clone(), options)
+}
+
+/// Pretend `builder` produced its executable from the current state of the project
+fn record_build(builder: &Builder) {
+    let output = builder.output_path();
+    fs::create_dir_all(output.parent().unwrap()).unwrap();
+    fs::write(&output, "binary").unwrap();
+    builder.fingerprint().unwrap().save(&builder.fingerprint_path()).unwrap();
+}
+
+fn new_project(temp_dir: &TempDir) -> Project {
+    create_project("app", Some(temp_dir.path())).unwrap();
+    Project::load_from_path(temp_dir.path().join("app")).unwrap()
+}
+
+#[test]
+fn test_output_layout() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = new_project(&temp_dir);
+
+    let debug = builder(&project, false, None);
+    assert_eq!(debug.output_path(), project.target_dir.join("debug").join(host_target()).join("app"));
+    assert_eq!(debug.output_path(), project.executable_path(false));
+
+    let release = builder(&project, true, Some("linux-arm64"));
+    assert_eq!(release.output_path(), project.target_dir.join("release").join("linux-arm64").join("app"));
+    assert_eq!(
+        release.fingerprint_path(),
+        project.target_dir.join("release/linux-arm64/.fingerprint/app.toml")
+    );
+}
+
+#[test]
+fn test_fingerprint_invalidation() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = new_project(&temp_dir);
+    let debug = builder(&project, false, None);
+
+    assert!(!debug.is_fresh().unwrap());
+    record_build(&debug);
+    assert!(debug.is_fresh().unwrap());
+
+    // Other profiles and targets have their own artifacts
+    assert!(!builder(&project, true, None).is_fresh().unwrap());
+    assert!(!builder(&project, false, Some("wasm")).is_fresh().unwrap());
+
+    // Editing a source file invalidates the artifact
+    let main = project.main_source_file();
+    fs::write(&main, fs::read_to_string(&main).unwrap() + "\n// edited\n").unwrap();
+    assert!(!debug.is_fresh().unwrap());
+    record_build(&debug);
+
+    // So do dependency changes
+    fs::write(project.root.join("lang.lock"), "version = \"1\"\n").unwrap();
+    assert!(!debug.is_fresh().unwrap());
+    record_build(&debug);
+    let vendored = project.root.join("vendor").join("http").join("src");
+    fs::create_dir_all(&vendored).unwrap();
+    fs::write(vendored.join("lib.bu"), "export func get() {}\n").unwrap();
+    assert!(!debug.is_fresh().unwrap());
+    record_build(&debug);
+
+    // And artifacts from another compiler version are never reused
+    let mut fingerprint = Fingerprint::load(&debug.fingerprint_path()).unwrap();
+    assert!(fingerprint.dependencies.contains_key("vendor/http/src/lib.bu"));
+    fingerprint.compiler_version = "0.0.0-old".to_string();
+    fingerprint.save(&debug.fingerprint_path()).unwrap();
+    assert!(!debug.is_fresh().unwrap());
+
+    // Non-incremental builds always rebuild
+    record_build(&debug);
+    let options = BuildOptions {
+        incremental: false,
+        ..BuildOptions::default()
+    };
+    assert!(!Builder::new(project.clone(), options).is_fresh().unwrap());
+}
+
+#[test]
+fn test_clean_profile() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = new_project(&temp_dir);
+    let debug = builder(&project, false, None);
+    let release = builder(&project, true, None);
+    record_build(&debug);
+    record_build(&release);
+
+    release.clean_profile("release").unwrap();
+    assert!(!project.target_dir.join("release").exists());
+    assert!(debug.is_fresh().unwrap());
+
+    assert!(debug.clean_profile("../src").is_err());
+    assert!(project.src_dir.exists());
+
+    debug.clean().unwrap();
+    assert!(!project.target_dir.exists());
+}
@@ -0,0 +1,78 @@
+//! Tests for raw string and byte-string literals in the checker and AST interpreter
+
+mod common;
+
+use bulu::ast::*;
+use bulu::types::primitive::RuntimeValue;
+use common::{check_source, run_main};
+
+#[test]
+fn test_byte_string_is_a_byte_slice() {
+    let source = r#"
+    func main(): []byte {
+        return b"AB\x01"
+    }
+    "#;
+    assert_eq!(
+        run_main(source).unwrap(),
+        RuntimeValue::Slice(vec![RuntimeValue::UInt8(65), RuntimeValue::UInt8(66), RuntimeValue::UInt8(1)])
+    );
+
+    let source = r#"
+    func count(data: []byte): int32 {
+        let seen = 0
+        for b in data {
+            seen = seen + 1
+        }
+        return seen
+    }
+
+    func main(): (int32, int32) {
+        let data: []uint8 = b"GET /"
+        return (count(data), len(br"\x"))
+    }
+    "#;
+    assert_eq!(
+        run_main(source).unwrap(),
+        RuntimeValue::Tuple(vec![RuntimeValue::Integer(5), RuntimeValue::Int32(2)])
+    );
+
+    let program = check_source("let data = b\"\\xff\"\n").unwrap();
+    let Statement::VariableDecl(decl) = &program.statements[0] else {
+        panic!("Expected variable declaration");
+    };
+    assert_eq!(
+        decl.initializer.as_ref().map(|init| match init {
+            Expression::Literal(literal) => literal.value.clone(),
+            _ => panic!("Expected literal"),
+        }),
+        Some(LiteralValue::ByteString(vec![0xff]))
+    );
+
+    let error = check_source("let text: string = b\"hi\"\n").unwrap_err();
+    assert!(
+        error.to_string().contains("Cannot assign slice to variable of type string"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_raw_strings_and_byte_string_patterns() {
+    let source = r##"
+    func main(): string {
+        let pattern = r"\d+\.\d+"
+        let quoted = r#"{"key": "value"}"#
+        let kind = match b"GET" {
+            b"POST" -> "write"
+            b"GET" -> "read"
+            _ -> "other"
+        }
+        return pattern + " " + quoted + " " + kind
+    }
+    "##;
+    assert_eq!(
+        run_main(source).unwrap(),
+        RuntimeValue::String(r#"\d+\.\d+ {"key": "value"} read"#.to_string())
+    );
+}
@@ -0,0 +1,114 @@
+//! Tests for `lang changelog` and the release notes sent by `lang publish`
+
+use bulu::changelog::{ChangeEntry, ChangeSource, ChangelogManager, CHANGES_DIR};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn git(root: &Path, args: &[&str]) -> bool {
+    Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+#[test]
+fn test_release_from_changes_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    let manager = ChangelogManager::new(temp_dir.path());
+
+    // Without a changelog there are no notes, and publishing only warns
+    assert_eq!(manager.release_notes("1.0.0").unwrap(), None);
+
+    manager
+        .add(&ChangeEntry::new("fix", Some("http"), false, "retry on reset").unwrap())
+        .unwrap();
+    manager
+        .add(&ChangeEntry::new("feat", None, true, "drop the v1 API").unwrap())
+        .unwrap();
+    let pending = manager.pending().unwrap();
+    assert_eq!(pending.len(), 2);
+    assert_eq!(pending[0].entry.to_string(), "fix(http): retry on reset");
+
+    let section = manager.release("1.0.0", "2026-10-17", ChangeSource::Directory).unwrap();
+    assert!(section.starts_with("## [1.0.0] - 2026-10-17\n"), "{}", section);
+    assert_eq!(
+        fs::read_dir(temp_dir.path().join(CHANGES_DIR)).unwrap().count(),
+        0,
+        "released changes are removed"
+    );
+
+    let changelog = fs::read_to_string(temp_dir.path().join("CHANGELOG.md")).unwrap();
+    assert!(changelog.starts_with("# Changelog\n"), "{}", changelog);
+    assert_eq!(
+        manager.release_notes("1.0.0").unwrap().as_deref(),
+        Some("### Breaking Changes\n\n- drop the v1 API\n\n### Fixes\n\n- **http:** retry on reset")
+    );
+
+    // A version without a section can't be published, and needs new changes
+    let err = manager.release_notes("1.1.0").unwrap_err().to_string();
+    assert!(err.contains("no section for version 1.1.0"), "{}", err);
+    assert!(manager.release("1.1.0", "2026-10-18", ChangeSource::Directory).is_err());
+    assert!(manager.release("1.0.0", "2026-10-18", ChangeSource::Directory).is_err());
+
+    manager
+        .add(&ChangeEntry::new("perf", None, false, "cache lookups").unwrap())
+        .unwrap();
+    manager.release("1.1.0", "2026-10-18", ChangeSource::Directory).unwrap();
+    let changelog = fs::read_to_string(temp_dir.path().join("CHANGELOG.md")).unwrap();
+    let newer = changelog.find("## [1.1.0]").unwrap();
+    let older = changelog.find("## [1.0.0]").unwrap();
+    assert!(newer < older, "{}", changelog);
+}
+
+#[test]
+fn test_invalid_pending_change_is_reported() {
+    let temp_dir = TempDir::new().unwrap();
+    let changes = temp_dir.path().join(CHANGES_DIR);
+    fs::create_dir_all(&changes).unwrap();
+    fs::write(changes.join("1-oops.md"), "feat: fine\nnot an entry\n").unwrap();
+
+    let err = ChangelogManager::new(temp_dir.path()).pending().unwrap_err().to_string();
+    assert!(err.contains("1-oops.md:2"), "{}", err);
+    assert!(ChangeEntry::new("feat", None, false, "  ").is_err());
+    assert!(ChangeEntry::new("new feature", None, false, "x").is_err());
+}
+
+#[test]
+fn test_release_from_git_commits_since_last_tag() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    if !git(root, &["init", "-q"]) {
+        return;
+    }
+    let commit = |message: &str| {
+        git(
+            root,
+            &[
+                "-c", "user.name=Ana", "-c", "user.email=ana@example.com",
+                "commit", "-q", "--allow-empty", "-m", message,
+            ],
+        )
+    };
+    assert!(commit("feat: before the tag"));
+    assert!(git(root, &["tag", "v0.1.0"]));
+    assert!(commit("fix(cli): quote paths"));
+    assert!(commit("Update README"));
+    assert!(commit("feat!: new config format"));
+
+    let manager = ChangelogManager::new(root);
+    let entries: Vec<String> = manager
+        .commits_since_last_tag()
+        .unwrap()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(entries, vec!["fix(cli): quote paths", "feat!: new config format"]);
+
+    manager.release("0.2.0", "2026-10-17", ChangeSource::Git).unwrap();
+    let notes = manager.release_notes("0.2.0").unwrap().unwrap();
+    assert!(notes.contains("- **cli:** quote paths"), "{}", notes);
+    assert!(!notes.contains("before the tag"), "{}", notes);
+}
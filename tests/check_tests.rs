@@ -0,0 +1,107 @@
+//! Tests for type checking projects without code generation
+
+use bulu::build::check::{Diagnostic, Stage};
+use bulu::build::{CheckOptions, Checker};
+use bulu::project::{create_project, Project};
+use std::fs;
+use tempfile::TempDir;
+
+fn new_project(temp_dir: &TempDir) -> Project {
+    create_project("app", Some(temp_dir.path())).unwrap();
+    let project = Project::load_from_path(temp_dir.path().join("app")).unwrap();
+    fs::write(
+        project.src_dir.join("util.bu"),
+        "export func add(a: int32, b: int32): int32 {\n    return a + b\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        project.main_source_file(),
+        "import { add } from \"./util\"\n\nfunc main() {\n    let x: int32 = add(1, 2)\n    println(x)\n}\n",
+    )
+    .unwrap();
+    project
+}
+
+fn checker(project: &Project) -> Checker {
+    Checker::new(project.clone(), CheckOptions::default())
+}
+
+#[test]
+fn test_check_reports_errors_from_every_stage() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = new_project(&temp_dir);
+    fs::write(project.src_dir.join("types.bu"), "func f() {\n    let x: int32 = \"no\"\n}\n").unwrap();
+    fs::write(project.src_dir.join("syntax.bu"), "func g( {\n").unwrap();
+
+    let report = checker(&project).check().unwrap();
+    assert_eq!(report.files_checked, 4);
+    assert!(!report.success());
+    let stages: Vec<(&str, Stage)> = report
+        .diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.file.as_str(), diagnostic.stage))
+        .collect();
+    assert_eq!(stages, vec![("src/syntax.bu", Stage::Parse), ("src/types.bu", Stage::Type)]);
+    assert_eq!(
+        report.diagnostics[1],
+        Diagnostic {
+            file: "src/types.bu".to_string(),
+            line: Some(2),
+            column: Some(5),
+            stage: Stage::Type,
+            message: "Cannot assign string to variable of type int32".to_string(),
+        }
+    );
+    assert_eq!(
+        report.diagnostics[1].to_human().replace("\u{1b}[1;31m", "").replace("\u{1b}[0m", ""),
+        "src/types.bu:2:5: error: Cannot assign string to variable of type int32"
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+    assert_eq!(json["files_checked"], 4);
+    assert_eq!(json["diagnostics"][1]["stage"], "type");
+    assert_eq!(json["diagnostics"][1]["line"], 2);
+}
+
+#[test]
+fn test_unchanged_files_are_not_checked_again() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = new_project(&temp_dir);
+
+    let report = checker(&project).check().unwrap();
+    assert!(report.success(), "{:?}", report.diagnostics);
+    assert_eq!((report.files_checked, report.files_cached), (2, 0));
+    assert!(checker(&project).cache_path().exists());
+
+    let report = checker(&project).check().unwrap();
+    assert_eq!((report.files_checked, report.files_cached), (2, 2));
+
+    // Changing an imported module rechecks the files importing it
+    fs::write(
+        project.src_dir.join("util.bu"),
+        "export func add(a: int32, b: int32): string {\n    return \"sum\"\n}\n",
+    )
+    .unwrap();
+    let report = checker(&project).check().unwrap();
+    assert_eq!((report.files_checked, report.files_cached), (2, 0));
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(report.diagnostics[0].file, "src/main.bu");
+
+    // Files importing only the standard library keep their results
+    fs::write(project.src_dir.join("alone.bu"), "import { sqrt } from \"std/math\"\n\nfunc h() {}\n").unwrap();
+    checker(&project).check().unwrap();
+    fs::write(project.src_dir.join("util.bu"), "export func add(a: int32, b: int32): int32 {\n    return b\n}\n").unwrap();
+    let report = checker(&project).check().unwrap();
+    assert!(report.success(), "{:?}", report.diagnostics);
+    assert_eq!((report.files_checked, report.files_cached), (3, 1));
+
+    // Without incremental checking nothing is reused or recorded
+    let options = CheckOptions {
+        incremental: false,
+        ..CheckOptions::default()
+    };
+    fs::remove_file(checker(&project).cache_path()).unwrap();
+    let report = Checker::new(project.clone(), options).check().unwrap();
+    assert_eq!(report.files_cached, 0);
+    assert!(!checker(&project).cache_path().exists());
+}
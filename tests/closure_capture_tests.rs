@@ -0,0 +1,190 @@
+//! Tests for closure capture in the AST interpreter and the checker's escape analysis
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::lexer::token::Position;
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use bulu::types::checker::TypeChecker;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_main, check_resolved, parse_source, resolve_source};
+
+const IMPORTS: &str = "import { sort } from \"std/arrays\"\n";
+
+/// Helper function to parse, resolve imports and type check source code,
+/// keeping the checker for its escape analysis
+fn check_source(source: &str) -> Result<(Program, TypeChecker), BuluError> {
+    let (program, symbol_resolver) = resolve_source(&format!("{}{}", IMPORTS, source))?;
+    let type_checker = check_resolved(&program, &symbol_resolver)?;
+    Ok((program, type_checker))
+}
+
+/// Helper function to type check and run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(source)?.0)
+}
+
+#[test]
+fn test_returned_closures_keep_their_own_state() {
+    let source = r#"
+    func makeCounter(start: int32): func(): int32 {
+        let count = start
+        return () => {
+            count = count + 1
+            return count
+        }
+    }
+
+    func main(): int32 {
+        let a = makeCounter(0)
+        let b = makeCounter(100)
+        a()
+        a()
+        b()
+        let first: int32 = a()
+        let second: int32 = b()
+        return first * 1000 + second
+    }
+    "#;
+    assert_eq!(run_main(source).unwrap(), RuntimeValue::Integer(3102));
+}
+
+#[test]
+fn test_callbacks_write_through_to_the_defining_scope() {
+    let source = r#"
+    func each(items: [3]int32, callback: func(int32)) {
+        for item in items {
+            callback(item)
+        }
+    }
+
+    func main(): int32 {
+        let calls = 0
+        let total = 0
+        let sorted = sort([3, 1, 2], (a: int32, b: int32) => {
+            calls = calls + 1
+            return b - a
+        })
+        each(sorted, (n: int32) => {
+            total = total * 10 + n
+        })
+        return calls * 1000 + total
+    }
+    "#;
+    let result = run_main(source).unwrap();
+    let RuntimeValue::Integer(result) = result else {
+        panic!("Expected an integer, got {:?}", result);
+    };
+    assert_eq!(result % 1000, 321);
+    assert!(result / 1000 >= 2, "the comparator ran {} times", result / 1000);
+}
+
+#[test]
+fn test_by_value_captures_and_shared_cells() {
+    // `offset` is never reassigned, so each adder keeps the value it was created with;
+    // `log` is shared, so writes made by either closure are seen by both and by the caller
+    let source = r#"
+    func main(): string {
+        let log = ""
+        let first = makeAdder(1)
+        let second = makeAdder(10)
+        let record = (text: string) => {
+            log = log + text
+        }
+        record("a")
+        let peek = () => log
+        record("b")
+        let six: int32 = first(5)
+        let fifteen: int32 = second(5)
+        return log + " " + peek() + " " + (six * 100 + fifteen)
+    }
+
+    func makeAdder(offset: int32): func(int32): int32 {
+        return (n: int32) => n + offset
+    }
+    "#;
+    assert_eq!(run_main(source).unwrap(), RuntimeValue::String("ab ab 615".to_string()));
+}
+
+#[test]
+fn test_checker_reports_captures_and_escapes() {
+    let source = r#"
+    func register(callback: func(int32): int32) {
+    }
+
+    func main() {
+        let factor = 2
+        let count = 0
+        const limit = 10
+        let kept = (n: int32) => n * factor + limit
+        sort([2, 1], (a: int32, b: int32) => {
+            count = count + 1
+            return a - b
+        })
+        register((n: int32) => n + count)
+        let inner = (n: int32) => (m: int32) => n + m + factor
+    }
+    "#;
+    let (_, checker) = check_source(source).unwrap();
+    let analysis = checker.closure_analysis();
+    assert_eq!(analysis.len(), 5);
+
+    // Lines are offset by the import prepended by check_source
+    let kept = analysis.get(Position::new(10, 20, 0)).unwrap();
+    let names: Vec<&str> = kept.captures.iter().map(|capture| capture.name.as_str()).collect();
+    assert_eq!(names, vec!["factor", "limit"]);
+    assert!(kept.captures.iter().all(|capture| capture.capture_type == CaptureType::ByValue));
+    assert_eq!((kept.captures[0].position.line, kept.captures[0].position.column), (10, 38));
+    assert!(kept.escapes);
+
+    let comparator = analysis.get(Position::new(11, 22, 0)).unwrap();
+    assert_eq!(comparator.capture("count").unwrap().capture_type, CaptureType::ByReference);
+    assert!(!comparator.escapes);
+
+    // User functions may keep their callbacks
+    let registered = analysis.get(Position::new(15, 18, 0)).unwrap();
+    assert_eq!(registered.capture("count").unwrap().capture_type, CaptureType::ByReference);
+    assert!(registered.escapes);
+
+    // The outer lambda captures `factor` on behalf of the inner one
+    let outer = analysis.get(Position::new(16, 21, 0)).unwrap();
+    let inner = analysis.get(Position::new(16, 35, 0)).unwrap();
+    assert_eq!(outer.captures.len(), 1);
+    assert_eq!(outer.captures[0].name, "factor");
+    let inner_names: Vec<&str> = inner.captures.iter().map(|capture| capture.name.as_str()).collect();
+    assert_eq!(inner_names, vec!["n", "factor"]);
+}
+
+#[test]
+fn test_lambdas_of_different_files_do_not_collide() {
+    // Both lambdas sit at line 2, column 12 of their file
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("double.bu"),
+        "export func makeA(): func(int32): int32 {\n    return (n: int32) => n * 2\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("offset.bu"),
+        "export func makeB(): func(int32): int32 {\n    return (n: int32) => n + 100\n}\n",
+    )
+    .unwrap();
+    let main_path = dir.path().join("main.bu");
+    let source = r#"
+import { makeA } from "./double"
+import { makeB } from "./offset"
+
+func main(): int32 {
+    let a = makeA()
+    let b = makeB()
+    return a(1) * 1000 + b(1)
+}
+"#;
+
+    let program = parse_source(source).unwrap();
+    let mut interpreter = AstInterpreter::with_file(main_path.to_string_lossy().to_string());
+    interpreter.execute_program(&program).unwrap();
+    let main_func = interpreter.get_function_definition("main").unwrap();
+    assert_eq!(interpreter.call_user_function(&main_func, &[]).unwrap(), RuntimeValue::Integer(2101));
+}
@@ -0,0 +1,103 @@
+//! Helpers shared by the integration tests
+
+#![allow(dead_code)]
+
+use bulu::ast::*;
+use bulu::compiler::symbol_resolver::SymbolResolver;
+use bulu::error::BuluError;
+use bulu::lexer::Lexer;
+use bulu::parser::Parser;
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use bulu::types::checker::TypeChecker;
+use bulu::types::primitive::RuntimeValue;
+
+/// Helper function to parse source code
+pub fn parse_source(source: &str) -> Result<Program, BuluError> {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize()?;
+    let mut parser = Parser::new(tokens);
+    parser.parse()
+}
+
+/// Helper function to parse and type check source code
+pub fn check_source(source: &str) -> Result<Program, BuluError> {
+    let program = parse_source(source)?;
+    TypeChecker::new().check(&program)?;
+    Ok(program)
+}
+
+/// Helper function to parse source code and resolve its imports
+pub fn resolve_source(source: &str) -> Result<(Program, SymbolResolver), BuluError> {
+    let mut program = parse_source(source)?;
+    let mut symbol_resolver = SymbolResolver::new();
+    symbol_resolver.resolve_program(&mut program)?;
+    Ok((program, symbol_resolver))
+}
+
+/// Helper function to type check a program whose imports `symbol_resolver` resolved
+pub fn check_resolved(program: &Program, symbol_resolver: &SymbolResolver) -> Result<TypeChecker, BuluError> {
+    let mut type_checker = TypeChecker::new();
+    type_checker.import_symbols_from_resolver(symbol_resolver);
+    type_checker.add_builtin_functions_after_import();
+    type_checker.check(program)?;
+    Ok(type_checker)
+}
+
+/// Helper function to parse, resolve imports and type check source code,
+/// with `imports` prepended to `source`
+pub fn check_with_imports(imports: &str, source: &str) -> Result<Program, BuluError> {
+    let (program, symbol_resolver) = resolve_source(&format!("{}{}", imports, source))?;
+    check_resolved(&program, &symbol_resolver)?;
+    Ok(program)
+}
+
+/// Helper function to run the top level of a checked program with the AST interpreter
+pub fn interpreter_for(program: &Program) -> Result<AstInterpreter, BuluError> {
+    let mut interpreter = AstInterpreter::new();
+    interpreter.execute_program(program)?;
+    Ok(interpreter)
+}
+
+/// Helper function to call function `name` of a program `interpreter` has run
+pub fn call_in(interpreter: &mut AstInterpreter, name: &str, args: &[RuntimeValue]) -> Result<RuntimeValue, BuluError> {
+    let function = interpreter
+        .get_function_definition(name)
+        .unwrap_or_else(|| panic!("{} should be defined", name));
+    interpreter.call_user_function(&function, args)
+}
+
+/// Helper function to run a checked program and call its function `name` with `args`
+pub fn call_function(program: &Program, name: &str, args: &[RuntimeValue]) -> Result<RuntimeValue, BuluError> {
+    call_in(&mut interpreter_for(program)?, name, args)
+}
+
+/// Helper function to run `main` of a checked program with the AST interpreter
+pub fn call_main(program: &Program) -> Result<RuntimeValue, BuluError> {
+    call_function(program, "main", &[])
+}
+
+/// Helper function to type check and run `main` with the AST interpreter
+pub fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(source)?)
+}
+
+/// A string runtime value
+pub fn string(value: &str) -> RuntimeValue {
+    RuntimeValue::String(value.to_string())
+}
+
+/// Helper function that expects `result` to fail with an error containing `expected`
+pub fn assert_error<T: std::fmt::Debug>(result: Result<T, BuluError>, expected: &str) {
+    let error = result.expect_err("expected a type error");
+    assert!(
+        error.to_string().contains(expected),
+        "expected error containing {:?}, got {}",
+        expected,
+        error
+    );
+}
+
+/// Helper function that expects a type error containing `expected`
+pub fn assert_type_error(source: &str, expected: &str) {
+    assert_error(check_source(source), expected);
+}
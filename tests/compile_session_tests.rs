@@ -0,0 +1,124 @@
+//! Tests for the compiler driver used by external tools
+
+use bulu::compiler::mangle::{demangle, mangle};
+use bulu::compiler::{CompileSession, Diagnostic, OptLevel, Stage};
+use bulu::project::{create_project, Project};
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+use tempfile::TempDir;
+
+const PROGRAM: &str = "const SCALE = 2 * 3\n\nfunc scale(x: int32): int32 {\n    return x * SCALE\n}\n\nfunc main() {\n    println(scale(7))\n}\n";
+
+#[test]
+fn test_stages_run_one_at_a_time() {
+    let mut session = CompileSession::from_source("main.bu", PROGRAM).with_opt_level(OptLevel::O2);
+    assert!(!session.tokens().unwrap().is_empty());
+    assert_eq!(session.parse().unwrap().statements.len(), 3);
+    session.resolve().unwrap();
+    session.check().unwrap();
+
+    let ir = session.ir().unwrap();
+    let mut functions: Vec<&str> = ir.functions.iter().map(|function| function.name.as_str()).collect();
+    functions.sort();
+    assert_eq!(functions, vec!["main", "scale"]);
+    assert!(session.diagnostics().is_empty());
+
+    // A later stage runs the earlier ones itself
+    let mut session = CompileSession::from_source("main.bu", PROGRAM);
+    assert_eq!(session.ir().unwrap().functions.len(), 2);
+}
+
+#[test]
+fn test_functions_are_named_after_the_package() {
+    let assembly = CompileSession::from_source("main.bu", PROGRAM)
+        .with_package("geo/plane")
+        .assembly()
+        .unwrap();
+    let scale = mangle("geo/plane", "scale", "(int32)int32");
+    assert!(assembly.contains(&format!(".globl {}\n", scale)), "{}", assembly);
+    assert_eq!(demangle(&scale).unwrap().to_string(), "geo/plane.scale");
+
+    // Outside of a package functions belong to `main`
+    let assembly = CompileSession::from_source("main.bu", PROGRAM).assembly().unwrap();
+    assert!(assembly.contains(&mangle("main", "main", "()")), "{}", assembly);
+    assert!(!assembly.contains(&scale));
+}
+
+#[test]
+fn test_failures_reach_the_diagnostics_sink() {
+    let reported = Rc::new(RefCell::new(Vec::new()));
+    let sink = reported.clone();
+    let mut session = CompileSession::from_source("bad.bu", "func main() {\n    let x: int32 = \"no\"\n}\n")
+        .with_diagnostics(move |diagnostic: &Diagnostic| sink.borrow_mut().push(diagnostic.clone()));
+
+    // Stages before the failing one succeed
+    session.resolve().unwrap();
+    assert!(session.ir().is_err());
+    let expected = Diagnostic {
+        file: "bad.bu".to_string(),
+        line: Some(2),
+        column: Some(5),
+        stage: Stage::Type,
+        message: "Cannot assign string to variable of type int32".to_string(),
+    };
+    assert_eq!(*reported.borrow(), vec![expected.clone()]);
+    assert_eq!(session.diagnostics(), &[expected]);
+
+    let mut session = CompileSession::from_source("syntax.bu", "func main( {\n");
+    assert!(session.check().is_err());
+    assert_eq!(session.diagnostics()[0].stage, Stage::Parse);
+
+    let mut session = CompileSession::from_file("/nonexistent/main.bu");
+    assert!(session.tokens().is_err());
+    assert!(session.diagnostics()[0].message.contains("Failed to read /nonexistent/main.bu"));
+}
+
+#[test]
+fn test_imports_are_resolved() {
+    let temp_dir = TempDir::new().unwrap();
+    let app_dir = temp_dir.path().join("app");
+    let lib_dir = temp_dir.path().join("lib");
+    fs::create_dir_all(&app_dir).unwrap();
+    fs::create_dir_all(&lib_dir).unwrap();
+    fs::write(lib_dir.join("shapes.bu"), "export func area(w: int32, h: int32): int32 {\n    return w * h\n}\n").unwrap();
+    let main = app_dir.join("main.bu");
+    fs::write(&main, "import { area } from \"shapes\"\n\nfunc main() {\n    let a: int32 = area(2, \"3\")\n}\n").unwrap();
+
+    // Modules are found in the search paths, and their signatures are checked
+    let mut session = CompileSession::from_file(&main).with_search_path(&lib_dir).with_file_name("app/main.bu");
+    assert!(!session.imports_only_std().unwrap());
+    assert!(session.check().is_err());
+    assert_eq!(session.diagnostics()[0].file, "app/main.bu");
+    assert_eq!(session.diagnostics()[0].stage, Stage::Type);
+
+    // Unsaved sources replace the files on disk
+    let source = fs::read_to_string(&main).unwrap().replace("\"3\"", "3");
+    let mut session = CompileSession::from_source("main.bu", source)
+        .with_module("shapes", "export func area(w: int32, h: int32): int32 {\n    return w + h\n}\n");
+    session.check().unwrap();
+}
+
+#[test]
+fn test_session_for_project() {
+    let temp_dir = TempDir::new().unwrap();
+    create_project("app", Some(temp_dir.path())).unwrap();
+    let root = temp_dir.path().join("app");
+    let manifest = fs::read_to_string(root.join("lang.toml")).unwrap();
+    let manifest = manifest
+        .replace("optimization = \"2\"", "optimization = \"3\"")
+        .replace("features = []", "features = [\"tracing\"]");
+    fs::write(root.join("lang.toml"), &manifest).unwrap();
+
+    let project = Project::load_from_path(&root).unwrap();
+    let mut session = CompileSession::for_project(&project).unwrap();
+    assert_eq!(session.opt_level(), OptLevel::O3);
+    assert!(session.has_feature("tracing"));
+    assert!(!session.has_feature("metrics"));
+    session.check().unwrap();
+
+    fs::write(root.join("lang.toml"), manifest.replace("optimization = \"3\"", "optimization = \"fast\"")).unwrap();
+    let project = Project::load_from_path(&root).unwrap();
+    let error = CompileSession::for_project(&project).err().unwrap();
+    assert!(error.to_string().contains("Invalid optimization level: fast"), "{}", error);
+}
@@ -0,0 +1,116 @@
+//! Tests for the layered configuration
+
+use bulu::config::{parse_override, Config, Source};
+use bulu::linter::LintLevel;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn write(path: PathBuf, content: &str) -> PathBuf {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn test_layers_override_in_order() {
+    let dir = TempDir::new().unwrap();
+    let user = write(
+        dir.path().join("home/config.toml"),
+        "default = \"bulu\"\n[fmt]\nindent_size = 8\nmax_line_length = 120\nbrace_style = \"next_line\"\n",
+    );
+    let project = dir.path().join("project");
+    let project_config = write(project.join(".bulu/config.toml"), "[fmt]\nindent_size = 2\n");
+
+    let mut config = Config::defaults();
+    config.load_user(&user).unwrap();
+    config.load_project(&project).unwrap();
+    config.apply_env(vec![
+        ("BULU_FMT_MAX_LINE_LENGTH".to_string(), "90".to_string()),
+        ("BULU_FMT_NO_SUCH_KEY".to_string(), "1".to_string()),
+        ("BULU_REGISTRY".to_string(), "https://example.com".to_string()),
+    ]);
+    config.apply_overrides(&[parse_override("fmt.preserve_comments=false").unwrap()]).unwrap();
+
+    let fmt = config.format_config().unwrap();
+    assert_eq!(fmt.indent_size, 2);
+    assert_eq!(fmt.max_line_length, 90);
+    assert!(!fmt.preserve_comments);
+    assert!(fmt.space_after_commas);
+
+    assert_eq!(config.source("fmt.indent_size"), Some(&Source::Project(project_config)));
+    assert_eq!(config.source("fmt.brace_style"), Some(&Source::User(user)));
+    assert_eq!(
+        config.source("fmt.max_line_length"),
+        Some(&Source::Env("BULU_FMT_MAX_LINE_LENGTH".to_string()))
+    );
+    assert_eq!(config.source("fmt.preserve_comments"), Some(&Source::CommandLine));
+    assert_eq!(config.source("fmt.space_after_commas"), Some(&Source::Default));
+    // Unrelated tables of the user configuration and unknown variables are not settings
+    assert!(config.get("default").is_none());
+    assert!(config.get("fmt.no_such_key").is_none());
+
+    let listing = config.show();
+    assert!(listing.contains("fmt.indent_size = 2  # project "), "{}", listing);
+    assert!(listing.contains("fmt.preserve_comments = false  # command line\n"), "{}", listing);
+}
+
+#[test]
+fn test_project_files_merge_lint_rules() {
+    let dir = TempDir::new().unwrap();
+    write(dir.path().join(".langlint.toml"), "max_line_length = 80\n[rules]\nunused-variable = \"error\"\nlong-line = \"allow\"\n");
+    write(dir.path().join("lang.toml"), "[package]\nname = \"demo\"\n\n[lint.rules]\nlong-line = \"warn\"\n");
+    write(dir.path().join(".bulu/config.toml"), "[lint]\nmax_complexity = 3\n");
+
+    let mut config = Config::defaults();
+    config.load_project(dir.path()).unwrap();
+    config
+        .apply_overrides(&[parse_override("lint.rules.magic-number=allow").unwrap()])
+        .unwrap();
+
+    let rules = config.lint_rules().unwrap();
+    assert_eq!(rules.max_line_length, 80);
+    assert_eq!(rules.max_complexity, 3);
+    assert_eq!(rules.overrides.get("unused-variable"), Some(&LintLevel::Error));
+    assert_eq!(rules.overrides.get("long-line"), Some(&LintLevel::Warn));
+    assert_eq!(rules.overrides.get("magic-number"), Some(&LintLevel::Allow));
+    // The package table of lang.toml describes the project, not the package manager
+    assert_eq!(config.source("package.vendor_dir"), Some(&Source::Default));
+}
+
+#[test]
+fn test_package_and_build_settings() {
+    let dir = TempDir::new().unwrap();
+    write(
+        dir.path().join(".bulu/config.toml"),
+        "[package]\nvendor_dir = \"third_party\"\n\n[build]\nparallel = false\n",
+    );
+
+    let mut config = Config::defaults();
+    config.load_project(dir.path()).unwrap();
+    config.apply_env(vec![("BULU_BUILD_INCREMENTAL".to_string(), "false".to_string())]);
+
+    assert_eq!(config.package_config().unwrap().vendor_dir, PathBuf::from("third_party"));
+    let options = config.build_options().unwrap();
+    assert!(!options.parallel);
+    assert!(!options.incremental);
+}
+
+#[test]
+fn test_invalid_settings_are_reported() {
+    let mut config = Config::defaults();
+    let error = config
+        .apply_overrides(&[parse_override("fmt.indent=2").unwrap()])
+        .unwrap_err();
+    assert!(error.to_string().contains("Unknown configuration key 'fmt.indent'"), "{}", error);
+    assert!(parse_override("fmt.indent_size").is_err());
+
+    config.apply_overrides(&[parse_override("fmt.indent_size=wide").unwrap()]).unwrap();
+    let error = config.format_config().unwrap_err();
+    assert!(error.to_string().contains("Invalid [fmt] configuration"), "{}", error);
+
+    let dir = TempDir::new().unwrap();
+    write(dir.path().join(".bulu/config.toml"), "fmt = 2\n");
+    let error = Config::defaults().load_project(dir.path()).unwrap_err();
+    assert!(error.to_string().contains("The [fmt] section"), "{}", error);
+}
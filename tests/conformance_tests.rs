@@ -0,0 +1,100 @@
+//! Tests for the language conformance suite and its runner
+
+use bulu::testing::conformance::{ConformanceRunner, ConformanceTest, ExpectedError, Verdict, DEFAULT_DIR};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn verdict(source: &str) -> Verdict {
+    let test = ConformanceTest::parse("test.bu", source).unwrap();
+    ConformanceRunner::new().run_test(&test).verdict
+}
+
+#[test]
+fn test_suite_passes() {
+    let report = ConformanceRunner::new().run_dir(Path::new(DEFAULT_DIR)).unwrap();
+    assert!(report.outcomes.len() > 20, "{}", report.summary());
+    let failures: Vec<String> = report
+        .failures()
+        .iter()
+        .map(|outcome| format!("{}: {:?}", outcome.name, outcome.verdict))
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn test_markers_are_read() {
+    let source = "// spec: strings, closures,\nfunc main() {\n    println(\"a // b\")  // expect: a // b\n    // expect:   indented\n    let x: int32 = \"s\"  // expect-error: Cannot assign\n}\n";
+    let test = ConformanceTest::parse("markers.bu", source).unwrap();
+    assert_eq!(test.features, vec!["strings", "closures"]);
+    assert_eq!(test.expected_stdout, vec!["a // b", "  indented"]);
+    assert_eq!(
+        test.expected_error,
+        Some(ExpectedError {
+            message: "Cannot assign".to_string(),
+            line: Some(5),
+        })
+    );
+    assert_eq!(test.known_failure, None);
+
+    let error = ConformanceTest::parse("untagged.bu", "func main() {}\n").unwrap_err();
+    assert!(error.to_string().contains("untagged.bu: missing a `// spec:` tag"), "{}", error);
+    let error = ConformanceTest::parse("twice.bu", "// spec: a\n// expect-error: x\n// expect-error: y\n").unwrap_err();
+    assert!(error.to_string().contains("twice.bu:3: a program can only expect one error"), "{}", error);
+}
+
+#[test]
+fn test_verdicts() {
+    assert_eq!(verdict("// spec: a\nfunc main() {\n    println(1)  // expect: 1\n}\n"), Verdict::Passed);
+    assert_eq!(
+        verdict("// spec: a\nfunc main() {\n    println(1)  // expect: 2\n}\n"),
+        Verdict::Failed("output line 1: expected \"2\", got \"1\"".to_string())
+    );
+    assert_eq!(
+        verdict("// spec: a\nfunc main() {\n    println(1)\n}\n"),
+        Verdict::Failed("output line 1: unexpected \"1\"".to_string())
+    );
+    assert_eq!(
+        verdict("// spec: a\nfunc main() {\n    let x = 1  // expect-error: boom\n}\n"),
+        Verdict::Failed("expected error \"boom\", but the program succeeded".to_string())
+    );
+
+    // Errors written after code must be reported on that line
+    let source = "// spec: a\nfunc main() {\n    let x = 1  // expect-error: Cannot assign\n    let y: int32 = \"s\"\n}\n";
+    assert_eq!(
+        verdict(source),
+        Verdict::Failed(
+            "expected error on line 3, got: 4:5: Cannot assign string to variable of type int32".to_string()
+        )
+    );
+    assert_eq!(verdict(&source.replace("  // expect-error", "\n    // expect-error")), Verdict::Passed);
+
+    let source = "// spec: a\n// known-failure: prints the wrong number\nfunc main() {\n    println(1)  // expect: 2\n}\n";
+    assert_eq!(verdict(source), Verdict::KnownFailure("prints the wrong number".to_string()));
+    assert_eq!(verdict(&source.replace("expect: 2", "expect: 1")), Verdict::UnexpectedPass);
+}
+
+#[test]
+fn test_report_groups_by_feature() {
+    let temp_dir = TempDir::new().unwrap();
+    let nested = temp_dir.path().join("nested");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(temp_dir.path().join("a.bu"), "// spec: alpha\nfunc main() {\n    println(1)  // expect: 1\n}\n").unwrap();
+    fs::write(nested.join("b.bu"), "// spec: alpha, beta\nfunc main() {\n    println(1)  // expect: 2\n}\n").unwrap();
+    fs::write(nested.join("c.bu"), "func main() {}\n").unwrap();
+    fs::write(nested.join("notes.txt"), "not a program").unwrap();
+
+    let report = ConformanceRunner::new().run_dir(temp_dir.path()).unwrap();
+    assert_eq!(report.summary(), "3 programs: 1 passed, 2 failed");
+    let features = report.by_feature();
+    let names: Vec<&str> = features.keys().copied().collect();
+    assert_eq!(names, vec!["(untagged)", "alpha", "beta"]);
+    assert_eq!(features["alpha"].len(), 2);
+    assert!(features["beta"][0].name.ends_with("b.bu"));
+
+    // Programs whose tags cannot be read are reported whatever the filter
+    let report = ConformanceRunner::new().with_feature("beta").run_dir(temp_dir.path()).unwrap();
+    let names: Vec<&str> = report.outcomes.iter().map(|outcome| outcome.name.as_str()).collect();
+    assert_eq!(names.len(), 2);
+    assert!(names[0].ends_with("b.bu") && names[1].ends_with("c.bu"), "{:?}", names);
+}
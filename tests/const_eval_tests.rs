@@ -0,0 +1,209 @@
+//! Tests for compile-time constant evaluation in the optimizer
+
+use bulu::ast::*;
+use bulu::compiler::optimizer::ConstValue;
+use bulu::compiler::Optimizer;
+use bulu::error::BuluError;
+use bulu::lexer::Lexer;
+use bulu::parser::Parser;
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use bulu::types::checker::TypeChecker;
+use bulu::types::primitive::RuntimeValue;
+
+/// Helper function to parse and run constant evaluation on source code
+fn optimize_source(source: &str) -> Result<Program, BuluError> {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize()?;
+    let mut parser = Parser::new(tokens);
+    let program = parser.parse()?;
+    Optimizer::new().optimize(program)
+}
+
+/// Helper function to optimize, type check and run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    let program = optimize_source(source)?;
+    let mut type_checker = TypeChecker::new();
+    type_checker.check(&program)?;
+
+    let mut interpreter = AstInterpreter::new();
+    interpreter.execute_program(&program)?;
+    let main_func = interpreter.get_function_definition("main").expect("main should be defined");
+    interpreter.call_user_function(&main_func, &[])
+}
+
+fn initializer(program: &Program, index: usize) -> &Expression {
+    match &program.statements[index] {
+        Statement::VariableDecl(decl) => decl.initializer.as_ref().expect("initializer"),
+        other => panic!("Expected variable declaration, got {:?}", other),
+    }
+}
+
+fn literal(expr: &Expression) -> &LiteralValue {
+    match expr {
+        Expression::Literal(literal) => &literal.value,
+        other => panic!("Expected folded literal, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_fold_const_initializers() {
+    let program = optimize_source(
+        r#"
+        const BASE = 10
+        const LIMIT = BASE * 4 + 2
+        const NAME = "bu" + "lu"
+        const NAME_LEN = len(NAME) + len([1, 2, 3])
+        const RATE: float64 = 2
+        const HALF = 7 / 2 == 3 && -LIMIT < 0
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(literal(initializer(&program, 1)), &LiteralValue::Integer(42));
+    assert_eq!(literal(initializer(&program, 2)), &LiteralValue::String("bulu".to_string()));
+    assert_eq!(literal(initializer(&program, 3)), &LiteralValue::Integer(7));
+    assert_eq!(literal(initializer(&program, 4)), &LiteralValue::Float(2.0));
+    assert_eq!(literal(initializer(&program, 5)), &LiteralValue::Boolean(true));
+}
+
+#[test]
+fn test_fold_literal_expressions_in_function_bodies() {
+    let program = optimize_source(
+        r#"
+        func main() {
+            let x = (1 + 2) * 3
+            let y = x + 1
+        }
+        "#,
+    )
+    .unwrap();
+
+    let Statement::FunctionDecl(main) = &program.statements[0] else {
+        panic!("Expected function");
+    };
+    let Statement::VariableDecl(x) = &main.body.statements[0] else {
+        panic!("Expected variable declaration");
+    };
+    assert_eq!(literal(x.initializer.as_ref().unwrap()), &LiteralValue::Integer(9));
+    // Only literals fold outside constant contexts
+    let Statement::VariableDecl(y) = &main.body.statements[1] else {
+        panic!("Expected variable declaration");
+    };
+    assert!(matches!(y.initializer, Some(Expression::Binary(_))));
+}
+
+#[test]
+fn test_const_initializer_must_be_constant() {
+    let err = optimize_source(
+        r#"
+        func compute(): int32 { return 3 }
+        const VALUE = compute() + 1
+        "#,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("must be a constant expression"), "{}", err);
+
+    let err = optimize_source(
+        r#"
+        func main() {
+            let n = 2
+            const DOUBLE = n * 2
+        }
+        "#,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("'n' is not a constant"), "{}", err);
+
+    // A local shadowing a constant is not constant either
+    let err = optimize_source(
+        r#"
+        const N = 1
+        func main(N: int32) {
+            const M = N + 1
+        }
+        "#,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("'N' is not a constant"), "{}", err);
+}
+
+#[test]
+fn test_invalid_constant_expressions() {
+    let err = optimize_source("const Z = 1 / 0").unwrap_err();
+    assert!(err.to_string().contains("Division by zero"), "{}", err);
+
+    let err = optimize_source("const BIG = 9223372036854775807 + 1").unwrap_err();
+    assert!(err.to_string().contains("Integer overflow"), "{}", err);
+
+    let err = optimize_source(r#"const MIXED = "a" + 1"#).unwrap_err();
+    assert!(err.to_string().contains("cannot be applied to string and int"), "{}", err);
+}
+
+#[test]
+fn test_const_array_sizes() {
+    let program = optimize_source(
+        r#"
+        func main() {
+            let grid: [ROWS * COLS]int32 = [0, 0, 0, 0, 0, 0]
+        }
+        const ROWS = 2
+        const COLS = 3
+        "#,
+    )
+    .unwrap();
+
+    let Statement::FunctionDecl(main) = &program.statements[0] else {
+        panic!("Expected function");
+    };
+    let Statement::VariableDecl(grid) = &main.body.statements[0] else {
+        panic!("Expected variable declaration");
+    };
+    match &grid.type_annotation {
+        Some(Type::Array(array)) => {
+            assert_eq!(array.size, Some(6));
+            assert!(array.size_expr.is_none());
+        }
+        other => panic!("Expected array type, got {:?}", other),
+    }
+
+    let err = optimize_source(
+        r#"
+        const N = 3
+        func main() {
+            let values: [N]int32 = [1, 2]
+        }
+        "#,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Array of size 3 cannot be initialized with 2 elements"), "{}", err);
+
+    let err = optimize_source("func first(values: [size]int32): int32 { return values[0] }").unwrap_err();
+    assert!(err.to_string().contains("Array size must be a constant expression"), "{}", err);
+
+    let err = optimize_source("const N = 0 - 1\nfunc main() { let a: [N]int32 = [] }").unwrap_err();
+    assert!(err.to_string().contains("non-negative integer"), "{}", err);
+}
+
+#[test]
+fn test_run_with_constants() {
+    let source = r#"
+    const SIZE = 2 * 2
+    const PREFIX = "item-"
+
+    func main(): string {
+        let items: [SIZE]int32 = [1, 2, 3, 4]
+        return PREFIX + "count"
+    }
+    "#;
+    assert_eq!(run_main(source).unwrap(), RuntimeValue::String("item-count".to_string()));
+}
+
+#[test]
+fn test_const_value_to_expression() {
+    let position = bulu::lexer::token::Position::new(1, 1, 0);
+    let array = ConstValue::Array(vec![ConstValue::Integer(1), ConstValue::Bool(false)]);
+    match array.to_expression(position) {
+        Expression::Array(array) => assert_eq!(array.elements.len(), 2),
+        other => panic!("Expected array, got {:?}", other),
+    }
+}
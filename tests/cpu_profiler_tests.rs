@@ -0,0 +1,55 @@
+//! Tests for the CPU sampling profiler. The profiler is process-wide, so this
+//! file holds a single test.
+
+use bulu::lexer::Lexer;
+use bulu::parser::Parser;
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use bulu::runtime::profiler::{cpu_profile_running, start_cpu_profile, stop_cpu_profile};
+use bulu::types::checker::TypeChecker;
+use std::time::Duration;
+
+#[test]
+fn test_samples_bulu_call_stacks() {
+    let source = r#"
+func work(n: int32): int32 {
+    let total = 0
+    let i = 0
+    while i < n {
+        total = total + i
+        i = i + 1
+    }
+    return total
+}
+
+func main() {
+    let round = 0
+    while round < 20 {
+        let x = work(2000)
+        round = round + 1
+    }
+}
+"#;
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize().unwrap();
+    let mut parser = Parser::new(tokens);
+    let program = parser.parse().unwrap();
+    TypeChecker::new().check(&program).unwrap();
+
+    start_cpu_profile(Duration::from_millis(1)).unwrap();
+    assert!(start_cpu_profile(Duration::from_millis(1)).is_err());
+
+    // Interpreters created while the profile runs are sampled
+    let mut interpreter = AstInterpreter::new();
+    interpreter.execute_program(&program).unwrap();
+    let main_func = interpreter.get_function_definition("main").expect("main should be defined");
+    interpreter.call_user_function(&main_func, &[]).unwrap();
+
+    let profile = stop_cpu_profile().expect("a profile was running");
+    assert!(!cpu_profile_running());
+    assert!(profile.total_samples() > 0);
+    let (name, own, total) = &profile.by_function()[0];
+    assert_eq!(name, "work");
+    assert!(own <= total);
+    assert!(profile.folded().lines().all(|line| line.starts_with("main")), "{}", profile.folded());
+    assert!(stop_cpu_profile().is_none());
+}
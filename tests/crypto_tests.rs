@@ -1,6 +1,34 @@
-// Unit tests for the cryptography module
+//! Tests for std/crypto: the CryptoContext hashes and builtins, and the
+//! MACs, AES-GCM encryption and key derivation
 
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
 use bulu::std::crypto::*;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_function, check_with_imports, string};
+
+const IMPORTS: &str = "import { sha256, hmacSha256, encrypt, decrypt, pbkdf2, argon2, constantTimeEquals, randomBytes } from \"std/crypto\"\n";
+
+/// Helper function to type check source code that imports std/crypto
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Run `source` and call its function `name` with one string argument
+fn call(source: &str, name: &str, arg: &str) -> RuntimeValue {
+    call_function(&check_source(source).unwrap(), name, &[string(arg)]).unwrap()
+}
+
+fn bytes(hex: &str) -> RuntimeValue {
+    RuntimeValue::Slice(
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| RuntimeValue::UInt8(u8::from_str_radix(&hex[i..i + 2], 16).unwrap()))
+            .collect(),
+    )
+}
 
 #[test]
 fn test_crypto_context_creation() {
@@ -263,4 +291,127 @@ fn test_binary_data_hashing() {
     // Should handle binary data correctly
     assert_eq!(result.to_hex().len(), 32);
     assert_eq!(result.to_bytes().len(), 16);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_hmac_and_comparison() {
+    let source = r#"
+        func mac(message: string): []byte {
+            return hmacSha256("Jefe", message)
+        }
+
+        func verify(message: string): bool {
+            let expected = hmacSha256("Jefe", "what do ya want for nothing?")
+            return constantTimeEquals(hmacSha256("Jefe", message), expected)
+        }
+    "#;
+    // RFC 4231, test case 2
+    assert_eq!(
+        call(source, "mac", "what do ya want for nothing?"),
+        bytes("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+    );
+    assert_eq!(call(source, "verify", "what do ya want for nothing?"), RuntimeValue::Bool(true));
+    assert_eq!(call(source, "verify", "what do ya want for nothing!"), RuntimeValue::Bool(false));
+}
+
+#[test]
+fn test_encrypt_and_decrypt() {
+    let source = r#"
+        func roundTrip(text: string): []byte {
+            let key = randomBytes(32)
+            let sealed = encrypt(key, text, "v1").unwrap()
+            return decrypt(key, sealed, "v1").unwrap()
+        }
+
+        func sealedLength(text: string): int32 {
+            return len(encrypt(randomBytes(16), text).unwrap())
+        }
+
+        func wrongKey(text: string): string {
+            let sealed = encrypt(randomBytes(16), text).unwrap()
+            return decrypt(randomBytes(16), sealed).error
+        }
+
+        func wrongData(text: string): string {
+            let key = randomBytes(16)
+            return decrypt(key, encrypt(key, text, "v1").unwrap(), "v2").error
+        }
+
+        func shortKey(text: string): string {
+            return encrypt(text, "secret").error
+        }
+    "#;
+    assert_eq!(call(source, "roundTrip", "hi"), bytes("6869"));
+    // 12 byte nonce, the ciphertext and a 16 byte tag
+    assert_eq!(call(source, "sealedLength", "hello"), RuntimeValue::Int32(33));
+    assert_eq!(call(source, "wrongKey", "hello"), string("message authentication failed"));
+    assert_eq!(call(source, "wrongData", "hello"), string("message authentication failed"));
+    assert_eq!(call(source, "shortKey", "too short"), string("AES-GCM keys are 16 or 32 bytes, got 9"));
+}
+
+#[test]
+fn test_key_derivation() {
+    let source = r#"
+        func derive(password: string): []byte {
+            return pbkdf2(password, "salt", 1, 16)
+        }
+
+        func stretch(password: string): []byte {
+            return argon2(password, sha256("salt"), 16).unwrap()
+        }
+
+        func same(password: string): bool {
+            return constantTimeEquals(stretch(password), stretch(password))
+        }
+
+        func shortSalt(password: string): string {
+            return argon2(password, "salt", 32).error
+        }
+    "#;
+    // RFC 7914, section 11
+    assert_eq!(call(source, "derive", "passwd"), bytes("55ac046e56e3089fec1691c22544b605"));
+    let RuntimeValue::Slice(key) = call(source, "stretch", "hunter2") else {
+        panic!("expected a byte slice");
+    };
+    assert_eq!(key.len(), 16);
+    assert_eq!(call(source, "same", "hunter2"), RuntimeValue::Bool(true));
+    assert_eq!(call(source, "shortSalt", "hunter2"), string("salt is 4 bytes, the minimum is 8"));
+}
+
+#[test]
+fn test_checker_errors() {
+    let cases = [
+        (
+            "func f(): any {\n    return hmacSha256(\"key\")\n}\n",
+            "Function 'hmacSha256' expects 2 arguments, got 1",
+        ),
+        (
+            "func f(): any {\n    return encrypt(\"key\")\n}\n",
+            "Function 'encrypt' expects 2 to 3 arguments, got 1",
+        ),
+        (
+            "func f(): any {\n    return sha256(42)\n}\n",
+            "Argument 1 to function 'sha256': expected byte slice or string, got int32",
+        ),
+        (
+            "func f(): any {\n    return randomBytes(\"16\")\n}\n",
+            "Argument 1 to function 'randomBytes': expected integer, got string",
+        ),
+        (
+            "func f(): any {\n    return pbkdf2(\"pw\", \"salt\", 0, 32)\n}\n",
+            "Iteration count in call to 'pbkdf2' must be at least 1, got 0",
+        ),
+        (
+            "func f(): []byte {\n    return decrypt(\"k\", \"c\")\n}\n",
+            "Cannot return Result",
+        ),
+        (
+            "func f(): string {\n    return constantTimeEquals(\"a\", \"b\")\n}\n",
+            "Cannot return bool from function expecting string",
+        ),
+    ];
+    for (source, expected) in cases {
+        let error = check_source(source).unwrap_err();
+        assert!(error.to_string().contains(expected), "{}: {}", source, error);
+    }
+}
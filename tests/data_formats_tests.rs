@@ -0,0 +1,192 @@
+//! Tests for std/toml and std/yaml, which share the typed decoder of std/json
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_function, check_with_imports, string};
+
+const TYPES: &str = r#"
+    struct Server {
+        host: string
+        port: uint16
+    }
+
+    struct Config {
+        @json("app_name") name: string
+        debug: bool
+        servers: []Server
+        owner: string?
+    }
+"#;
+
+/// Helper function to type check source code that imports `module` and declares the test types
+fn check_source(module: &str, source: &str) -> Result<Program, BuluError> {
+    let imports = format!("import {{ parse, stringify }} from \"std/{}\"\n{}", module, TYPES);
+    check_with_imports(&imports, source)
+}
+
+/// Run `source` and call its function `name` with one string argument
+fn call(module: &str, source: &str, name: &str, arg: &str) -> RuntimeValue {
+    call_function(&check_source(module, source).unwrap(), name, &[string(arg)]).unwrap()
+}
+
+const CONFIG_TOML: &str = r#"
+app_name = "shop"
+debug = true
+
+[[servers]]
+host = "a.example"
+port = 8080
+
+[[servers]]
+host = "b.example"
+port = 8081
+"#;
+
+const CONFIG_YAML: &str = r#"
+app_name: shop
+debug: true
+servers:
+  - host: a.example
+    port: 8080
+  - host: b.example   # standby
+    port: 8081
+"#;
+
+const DESCRIBE: &str = r#"
+    func describe(text: string): string {
+        let config: Config = parse<Config>(text).unwrap()
+        return config.name + " " + config.servers[1].host
+    }
+
+    func port(text: string): uint16 {
+        let config: Config = parse<Config>(text).unwrap()
+        return config.servers[0].port
+    }
+"#;
+
+#[test]
+fn test_parse_into_structs() {
+    for (module, text) in [("toml", CONFIG_TOML), ("yaml", CONFIG_YAML)] {
+        assert_eq!(call(module, DESCRIBE, "describe", text), string("shop b.example"), "{}", module);
+        assert_eq!(call(module, DESCRIBE, "port", text), RuntimeValue::UInt16(8080), "{}", module);
+    }
+}
+
+#[test]
+fn test_untyped_parse_gives_maps() {
+    let source = r#"
+        func document(text: string): any {
+            return parse(text).unwrap()
+        }
+    "#;
+    for (module, text) in [("toml", CONFIG_TOML), ("yaml", CONFIG_YAML)] {
+        let RuntimeValue::Map(document) = call(module, source, "document", text) else {
+            panic!("{}: expected a map", module);
+        };
+        assert_eq!(document["app_name"], string("shop"), "{}", module);
+        assert_eq!(document["debug"], RuntimeValue::Bool(true), "{}", module);
+        let RuntimeValue::Slice(servers) = &document["servers"] else {
+            panic!("{}: expected a slice", module);
+        };
+        let RuntimeValue::Map(server) = &servers[1] else {
+            panic!("{}: expected a map", module);
+        };
+        assert_eq!(server["host"], string("b.example"), "{}", module);
+        assert_eq!(server["port"], RuntimeValue::Int64(8081), "{}", module);
+    }
+}
+
+#[test]
+fn test_stringify_round_trips() {
+    let source = r#"
+        func roundTrip(text: string): string {
+            let config: Config = parse<Config>(text).unwrap()
+            return stringify(config).unwrap()
+        }
+    "#;
+    let toml = call("toml", source, "roundTrip", CONFIG_TOML);
+    assert_eq!(
+        toml,
+        string(concat!(
+            "app_name = \"shop\"\ndebug = true\n\n",
+            "[[servers]]\nhost = \"a.example\"\nport = 8080\n\n",
+            "[[servers]]\nhost = \"b.example\"\nport = 8081\n"
+        ))
+    );
+    let yaml = call("yaml", source, "roundTrip", CONFIG_YAML);
+    assert_eq!(
+        yaml,
+        string(concat!(
+            "app_name: shop\ndebug: true\nowner: null\nservers:\n",
+            "  - host: a.example\n    port: 8080\n",
+            "  - host: b.example\n    port: 8081\n"
+        ))
+    );
+
+    // The written documents read back to the same configuration
+    for (module, text) in [("toml", toml), ("yaml", yaml)] {
+        let RuntimeValue::String(text) = text else { unreachable!() };
+        assert_eq!(call(module, DESCRIBE, "describe", &text), string("shop b.example"), "{}", module);
+    }
+}
+
+#[test]
+fn test_errors_name_the_path_or_line() {
+    let source = r#"
+        func attempt(text: string): string {
+            return parse<Config>(text).error
+        }
+    "#;
+    let cases = [
+        ("toml", "app_name = \"shop\"\ndebug = 1\nservers = []\n", "$.debug: expected bool, got number"),
+        (
+            "yaml",
+            "app_name: shop\ndebug: false\nservers:\n  - host: a\n    port: 70000\n",
+            "$.servers[0].port: expected uint16, got 70000, which is out of range",
+        ),
+        ("yaml", "name: shop\ndebug: false\nservers: []\n", "$.app_name: missing required field"),
+    ];
+    for (module, text, expected) in cases {
+        assert_eq!(call(module, source, "attempt", text), string(expected), "{}", text);
+    }
+
+    let error = call("toml", source, "attempt", "app_name = \"shop\"\ndebug = \n");
+    assert!(
+        matches!(&error, RuntimeValue::String(message) if message.starts_with("TOML Parse Error at line 2")),
+        "{:?}",
+        error
+    );
+    let error = call("yaml", source, "attempt", "app_name: shop\n  debug: true\n");
+    assert!(
+        matches!(&error, RuntimeValue::String(message) if message.starts_with("YAML Parse Error at line 2")),
+        "{:?}",
+        error
+    );
+}
+
+#[test]
+fn test_checker_errors() {
+    let cases = [
+        (
+            "func f(): any {\n    return parse<Config, Server>(\"\")\n}\n",
+            "Function 'parse' expects 1 type argument",
+        ),
+        (
+            "func f(): any {\n    return parse<Config>(1)\n}\n",
+            "Argument 1 to function 'parse': expected string, got int32",
+        ),
+        (
+            "func f(): int32 {\n    return parse<Config>(\"\").unwrap()\n}\n",
+            "Cannot return struct Config from function expecting int32",
+        ),
+    ];
+    for module in ["toml", "yaml"] {
+        for (source, expected) in cases {
+            let error = check_source(module, source).unwrap_err();
+            assert!(error.to_string().contains(expected), "{}: {}", source, error);
+        }
+    }
+}
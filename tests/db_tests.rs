@@ -0,0 +1,171 @@
+//! Tests for the SQLite driver of std/db
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_function, check_with_imports, string};
+
+const IMPORTS: &str = "import { open } from \"std/db\"\n";
+
+/// Helper function to type check source code that imports std/db
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Run `source` and call its function `name` with one string argument
+fn call(source: &str, name: &str, arg: &str) -> RuntimeValue {
+    call_function(&check_source(source).unwrap(), name, &[string(arg)]).unwrap()
+}
+
+const USERS: &str = r#"
+    func setup(path: string): DB {
+        let db = open(path).unwrap()
+        db.exec("CREATE TABLE users (name TEXT NOT NULL, age INTEGER, score REAL, avatar BLOB)").unwrap()
+        let insert = db.prepare("INSERT INTO users VALUES (?, ?, ?, ?)").unwrap()
+        insert.exec("ada", 36, 9.5, [1, 2, 3]).unwrap()
+        insert.exec("bob", 41, 7, None).unwrap()
+        return db
+    }
+
+    func describe(path: string): any {
+        let rows = setup(path).query("SELECT name, age, score, avatar FROM users WHERE age > ? ORDER BY age", 30).unwrap()
+        let ada = rows[0]
+        let bob = rows[1]
+        let first = (ada.getString("name"), ada.getInt("age"), ada.getFloat(2), ada.getBytes("avatar"))
+        return (first, bob.getString(0), bob.getFloat("score"), bob.isNull("avatar"), len(rows))
+    }
+
+    func updated(path: string): int64 {
+        return setup(path).exec("UPDATE users SET age = age + 1").unwrap()
+    }
+
+    func columns(path: string): []string {
+        return setup(path).query("SELECT name AS who, age FROM users").unwrap()[0].columns()
+    }
+
+    func failure(path: string): string {
+        return setup(path).query("SELECT nope FROM users").error
+    }
+
+    func mismatch(path: string): int64 {
+        return setup(path).query("SELECT name FROM users").unwrap()[0].getInt("name")
+    }
+"#;
+
+#[test]
+fn test_statements_and_typed_rows() {
+    assert_eq!(
+        call(USERS, "describe", ":memory:"),
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::Tuple(vec![
+                string("ada"),
+                RuntimeValue::Int64(36),
+                RuntimeValue::Float64(9.5),
+                RuntimeValue::Slice(vec![RuntimeValue::UInt8(1), RuntimeValue::UInt8(2), RuntimeValue::UInt8(3)]),
+            ]),
+            string("bob"),
+            // Integers read as floats
+            RuntimeValue::Float64(7.0),
+            RuntimeValue::Bool(true),
+            RuntimeValue::Int32(2),
+        ])
+    );
+    assert_eq!(call(USERS, "updated", ":memory:"), RuntimeValue::Int64(2));
+    assert_eq!(
+        call(USERS, "columns", ":memory:"),
+        RuntimeValue::Slice(vec![string("who"), string("age")])
+    );
+    assert_eq!(call(USERS, "failure", ":memory:"), string("no such column: nope in SELECT nope FROM users at offset 7"));
+
+    let error = call_function(&check_source(USERS).unwrap(), "mismatch", &[string(":memory:")]).unwrap_err();
+    assert!(
+        error.to_string().contains("Row.getInt(): column 'name' is TEXT, not INTEGER"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_transactions() {
+    let source = r#"
+        func count(db: DB): int64 {
+            return db.query("SELECT count(*) AS n FROM items").unwrap()[0].getInt("n")
+        }
+
+        func program(path: string): any {
+            let db = open(path).unwrap()
+            db.exec("CREATE TABLE items (name TEXT)").unwrap()
+
+            let tx = db.begin().unwrap()
+            tx.exec("INSERT INTO items VALUES (?)", "kept").unwrap()
+            let seen = len(tx.query("SELECT name FROM items").unwrap())
+            tx.commit().unwrap()
+
+            let undone = db.begin().unwrap()
+            undone.exec("INSERT INTO items VALUES (?)", "dropped").unwrap()
+            undone.rollback().unwrap()
+
+            return (seen, count(db), undone.commit().error)
+        }
+    "#;
+    assert_eq!(
+        call(source, "program", ":memory:"),
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::Int32(1),
+            RuntimeValue::Int64(1),
+            string("transaction has already been committed or rolled back"),
+        ])
+    );
+}
+
+#[test]
+fn test_goroutines_share_the_pool() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("shared.db");
+    let source = r#"
+        func insert(db: DB, n: int32, done: chan int32) {
+            db.exec("INSERT INTO events VALUES (?)", n).unwrap()
+            done <- n
+        }
+
+        func program(path: string): any {
+            let db = open(path).unwrap()
+            db.setMaxOpen(2)
+            db.exec("CREATE TABLE events (n INTEGER)").unwrap()
+            let done = make(chan int32, 8)
+            for i in [0, 1, 2, 3, 4, 5, 6, 7] {
+                run insert(db, i, done)
+            }
+            for i in [0, 1, 2, 3, 4, 5, 6, 7] {
+                <-done
+            }
+            let total = db.query("SELECT count(*), sum(n) FROM events").unwrap()[0]
+            return (total.getInt(0), total.getInt(1), db.openConnections())
+        }
+    "#;
+    let RuntimeValue::Tuple(values) = call(source, "program", path.to_str().unwrap()) else {
+        panic!("expected a tuple");
+    };
+    assert_eq!(values[..2], [RuntimeValue::Int64(8), RuntimeValue::Int64(28)]);
+    assert!(matches!(values[2], RuntimeValue::Int32(1..=2)), "{:?}", values[2]);
+}
+
+#[test]
+fn test_open_errors() {
+    let source = r#"
+        func attempt(path: string): string {
+            return open(path).error
+        }
+    "#;
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing").join("app.db");
+    let RuntimeValue::String(message) = call(source, "attempt", missing.to_str().unwrap()) else {
+        panic!("expected an error message");
+    };
+    assert!(message.starts_with("cannot open database '"), "{}", message);
+
+    let error = check_source("func f(): any {\n    return open(1)\n}\n").unwrap_err();
+    assert!(error.to_string().contains("expected string"), "{}", error);
+}
@@ -0,0 +1,87 @@
+//! Tests for running programs on several backends and comparing the results
+
+use bulu::testing::differential::{Backend, BackendResult, Comparison, DifferentialRunner, Outcome};
+use std::fs;
+use std::path::PathBuf;
+
+/// Programs every backend is expected to agree on
+fn fixtures() -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir("tests/fixtures/differential")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |extension| extension == "bu"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn ran(stdout: &str, exit_code: i32, diagnostic: Option<&str>) -> BackendResult {
+    BackendResult::Ran(Outcome {
+        stdout: stdout.to_string(),
+        stderr: String::new(),
+        exit_code,
+        diagnostic: diagnostic.map(str::to_string),
+    })
+}
+
+#[test]
+fn test_fixtures_agree_on_every_backend() {
+    let runner = DifferentialRunner::new(Backend::ALL.to_vec());
+    let paths = fixtures();
+    assert!(!paths.is_empty());
+    for path in paths {
+        let comparison = runner.run_file(&path);
+        assert!(comparison.agrees(), "{}\n{}", comparison.name, comparison.describe());
+        // The interpreter and the VM run everywhere
+        assert!(matches!(comparison.result(Backend::Interpreter), Some(BackendResult::Ran(_))));
+        assert!(matches!(comparison.result(Backend::Vm), Some(BackendResult::Ran(_))));
+    }
+}
+
+#[test]
+fn test_outcomes_are_observed() {
+    let runner = DifferentialRunner::new(vec![Backend::Interpreter, Backend::Vm]);
+    let comparison = runner.run_file(&PathBuf::from("tests/fixtures/differential/functions.bu"));
+    assert_eq!(comparison.result(Backend::Interpreter), Some(&ran("5\n42\n", 0, None)));
+    assert_eq!(comparison.result(Backend::Native), None);
+
+    let comparison = runner.run_source("bad.bu", "func main() {\n    let x: int32 = \"no\"\n}\n");
+    let rejected = ran("", 1, Some("2:5: Cannot assign string to variable of type int32"));
+    assert_eq!(comparison.result(Backend::Vm), Some(&rejected));
+    assert!(comparison.agrees());
+}
+
+#[test]
+fn test_skipped_backends_do_not_count() {
+    let comparison = Comparison {
+        name: "main.bu".to_string(),
+        results: vec![
+            (Backend::Interpreter, ran("1\n", 0, None)),
+            (Backend::Vm, ran("1\n", 0, None)),
+            (Backend::Native, BackendResult::Skipped("`ld` is not installed".to_string())),
+        ],
+    };
+    assert!(comparison.agrees());
+
+    let mut differing = comparison.clone();
+    differing.results[1].1 = ran("", 1, Some("Register 2 not found"));
+    assert!(!differing.agrees());
+    let report = differing.describe();
+    assert!(report.contains("vm           exit code 1, stdout \"\", error \"Register 2 not found\""), "{}", report);
+    assert!(report.contains("native       skipped: `ld` is not installed"), "{}", report);
+
+    differing.results[1].1 = BackendResult::TimedOut;
+    assert!(!differing.agrees());
+}
+
+#[test]
+fn test_backend_lists() {
+    assert_eq!(Backend::parse_list("all").unwrap(), Backend::ALL.to_vec());
+    assert_eq!(
+        Backend::parse_list("vm, interpreter,vm").unwrap(),
+        vec![Backend::Vm, Backend::Interpreter]
+    );
+    let error = Backend::parse_list("interpreter,gpu").unwrap_err();
+    assert!(error.to_string().contains("Unknown backend: gpu"), "{}", error);
+    assert!(Backend::parse_list("").is_err());
+}
@@ -165,7 +165,7 @@ let x = 42
             format: DocFormat::Html,
             serve: false,
             port: 8080,
-            verbose: false,
+            document_private_items: false,
         };
         
         let generator = DocGenerator::new(project, options);
@@ -221,7 +221,7 @@ let x = 42
             format: DocFormat::Markdown,
             serve: false,
             port: 8080,
-            verbose: false,
+            document_private_items: false,
         };
         
         let generator = DocGenerator::new(project, options);
@@ -272,7 +272,7 @@ let x = 42
             format: DocFormat::Json,
             serve: false,
             port: 8080,
-            verbose: false,
+            document_private_items: false,
         };
         
         let generator = DocGenerator::new(project, options);
@@ -323,7 +323,7 @@ let y = 24
             format: DocFormat::Html,
             serve: false,
             port: 8080,
-            verbose: false,
+            document_private_items: false,
         };
         
         let generator = DocGenerator::new(project, options);
@@ -338,6 +338,233 @@ let y = 24
         let html_content = fs::read_to_string(output_dir.join("index.html")).unwrap();
         assert!(html_content.contains("no-docs-project"));
     }
+
+    #[test]
+    fn test_private_items_and_example_coverage() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path();
+        fs::create_dir_all(project_dir.join("src")).unwrap();
+        fs::write(project_dir.join("lang.toml"), "[package]\nname = \"coverage-project\"\nversion = \"0.1.0\"\nauthors = [\"Test Author\"]\n\n[dependencies]\n").unwrap();
+
+        let source_content = r#"
+/**
+ * Adds two numbers together
+ * @example
+ * assert(add(5, 3) == 8)
+ * @example
+ * assert(add(1, 1) == 3)
+ */
+export func add(a: int32, b: int32): int32 {
+    return a + b
+}
+
+export func undocumented(): int32 {
+    return 1
+}
+
+/**
+ * Doubles a number
+ * @example
+ * assert(double(2) == 4)
+ */
+func double(a: int32): int32 {
+    return a * 2
+}
+"#;
+        fs::write(project_dir.join("src").join("main.bu"), source_content).unwrap();
+
+        let generate = |document_private_items: bool| {
+            let project = Project::load_from_path(project_dir).unwrap();
+            let output_dir = project.root.join(if document_private_items { "internal" } else { "docs" });
+            let options = DocOptions {
+                output_dir: output_dir.clone(),
+                format: DocFormat::Markdown,
+                serve: false,
+                port: 8080,
+                document_private_items,
+            };
+            DocGenerator::new(project, options).generate().unwrap();
+            fs::read_to_string(output_dir.join("README.md")).unwrap()
+        };
+
+        // The failing example is counted but not verified
+        let public = generate(false);
+        assert!(public.contains("1 of 2 public items documented, 1 of 2 examples verified by doctests"), "{}", public);
+        assert!(public.contains("### add"));
+        assert!(!public.contains("### double"));
+
+        let internal = generate(true);
+        assert!(internal.contains("1 of 2 public items documented, 2 of 3 examples verified by doctests"), "{}", internal);
+        assert!(internal.contains("### double\n\n```bulu\nfunc double(a: int32): int32\n```\n\n_Private_"), "{}", internal);
+    }
+
+    #[test]
+    fn test_type_links_search_index_and_module_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path();
+        fs::create_dir_all(project_dir.join("src").join("geometry")).unwrap();
+        fs::write(project_dir.join("lang.toml"), "[package]\nname = \"shapes\"\nversion = \"0.1.0\"\nauthors = [\"Test Author\"]\n\n[dependencies]\n").unwrap();
+        fs::write(
+            project_dir.join("src").join("geometry").join("shapes.bu"),
+            "/**\n * A point in the plane\n */\nexport struct Point {\n    x: float64\n    y: float64\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            project_dir.join("src").join("main.bu"),
+            "/**\n * Distance from the origin\n */\nexport func norm(p: Point): float64 {\n    return p.x\n}\n",
+        )
+        .unwrap();
+
+        let project = Project::load_from_path(project_dir).unwrap();
+        let output_dir = project.root.join("docs");
+        let options = DocOptions {
+            output_dir: output_dir.clone(),
+            format: DocFormat::Html,
+            serve: false,
+            port: 8080,
+            document_private_items: false,
+        };
+        DocGenerator::new(project, options).generate().unwrap();
+
+        let html = fs::read_to_string(output_dir.join("index.html")).unwrap();
+        assert!(html.contains("<a class=\"type-link\" href=\"#geometry-shapes-point\">Point</a>"), "{}", html);
+        assert!(html.contains("id=\"geometry-shapes-point\""));
+        assert!(html.contains("<summary>geometry</summary>\n<ul class=\"modules\">\n<li><details open><summary>shapes</summary>"), "{}", html);
+        assert!(html.contains("<li><a href=\"#main-norm\">norm</a></li>"));
+
+        let index: serde_json::Value = serde_json::from_str(&fs::read_to_string(output_dir.join("search-index.json")).unwrap()).unwrap();
+        let point = index
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entry| entry["name"] == "Point")
+            .expect("Point should be in the search index");
+        assert_eq!(point["module"], "geometry/shapes");
+        assert_eq!(point["summary"], "A point in the plane");
+        assert_eq!(point["url"], "index.html#geometry-shapes-point");
+    }
+
+    #[test]
+    fn test_markdown_in_doc_comments_is_rendered_and_sanitized() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path();
+        fs::create_dir_all(project_dir.join("src")).unwrap();
+        fs::write(project_dir.join("lang.toml"), "[package]\nname = \"md\"\nversion = \"0.1.0\"\nauthors = [\"Test Author\"]\n\n[dependencies]\n").unwrap();
+        fs::write(
+            project_dir.join("src").join("main.bu"),
+            "/**\n * Adds **two** numbers, see [docs](https://example.com) or [this](javascript:alert(1)).\n *\n * # Notes\n *\n * - never `fails`\n * - <script>alert(1)</script>\n *\n * ```\n * if a > 0 {\n *     return \"pos\"\n * }\n * ```\n * @param a - the `first` number\n * @example\n * let total = add(1, 2)\n */\nexport func add(a: int32, b: int32): int32 {\n    return a + b\n}\n",
+        )
+        .unwrap();
+
+        let project = Project::load_from_path(project_dir).unwrap();
+        let output_dir = project.root.join("docs");
+        let options = DocOptions {
+            output_dir: output_dir.clone(),
+            format: DocFormat::Html,
+            serve: false,
+            port: 8080,
+            document_private_items: false,
+        };
+        DocGenerator::new(project, options).generate().unwrap();
+
+        let html = fs::read_to_string(output_dir.join("index.html")).unwrap();
+        assert!(html.contains("<p>Adds <strong>two</strong> numbers, see <a href=\"https://example.com\">docs</a> or <a href=\"#\">this</a>.</p>"), "{}", html);
+        assert!(html.contains("<h4>Notes</h4>"), "{}", html);
+        assert!(html.contains("<li>never <code>fails</code></li>"), "{}", html);
+        assert!(html.contains("<li>&lt;script&gt;alert(1)&lt;/script&gt;</li>"), "{}", html);
+        assert!(!html.contains("<script>alert"), "{}", html);
+        // Code keeps its indentation and is highlighted
+        assert!(html.contains("<span class=\"tok-keyword\">if</span> a &gt; <span class=\"tok-number\">0</span> {\n    <span class=\"tok-keyword\">return</span> <span class=\"tok-string\">&quot;pos&quot;</span>\n}"), "{}", html);
+        assert!(html.contains("<span class=\"param-name\">a</span>: the <code>first</code> number"), "{}", html);
+        assert!(html.contains("<pre class=\"example\"><code class=\"language-bulu\"><span class=\"tok-keyword\">let</span> total = add(<span class=\"tok-number\">1</span>, <span class=\"tok-number\">2</span>)</code></pre>"), "{}", html);
+    }
+
+    #[test]
+    fn test_coverage_report_per_module() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path();
+        fs::create_dir_all(project_dir.join("src").join("geometry")).unwrap();
+        fs::write(project_dir.join("lang.toml"), "[package]\nname = \"coverage-report\"\nversion = \"0.1.0\"\nauthors = [\"Test Author\"]\n\n[dependencies]\n").unwrap();
+        fs::write(
+            project_dir.join("src").join("math.bu"),
+            "/**\n * Adds two numbers\n */\nexport func add(a: int32, b: int32): int32 {\n    return a + b\n}\n\nexport func sub(a: int32, b: int32): int32 {\n    return a - b\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            project_dir.join("src").join("geometry").join("shapes.bu"),
+            "/**\n * A point\n */\nexport struct Point {\n    x: int32\n}\n",
+        )
+        .unwrap();
+        fs::write(project_dir.join("src").join("main.bu"), "func main() {\n}\n").unwrap();
+
+        let project = Project::load_from_path(project_dir).unwrap();
+        let report = DocGenerator::new(project, DocOptions::default()).coverage_report().unwrap();
+
+        // main.bu exports nothing, so it has no row
+        let rows: Vec<(&str, usize, usize, Vec<String>)> = report
+            .modules
+            .iter()
+            .map(|m| (m.module.as_str(), m.documented_public_items, m.public_items, m.undocumented.clone()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("geometry/shapes", 1, 1, vec![]),
+                ("math", 1, 2, vec!["sub".to_string()]),
+            ]
+        );
+        assert!((report.percentage - 200.0 / 3.0).abs() < 1e-9);
+
+        let text = report.to_text();
+        assert!(text.contains("math                    1/2     50.0%\n"), "{}", text);
+        assert!(text.contains("missing: sub\n"), "{}", text);
+        assert!(text.ends_with("Total                   2/3     66.7%\n"), "{}", text);
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&report.to_json()).unwrap()["public_items"], 3);
+    }
+
+    #[test]
+    fn test_doctests_run_on_pooled_interpreters() {
+        use bulu::docs::doctest::DoctestRunner;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("math.bu");
+        let source = r#"
+/**
+ * Adds two numbers together
+ * @example
+ * let total = add(5, 3)
+ * assert(total == 8)
+ * @example
+ * assert(add(1, 1) == 3)
+ */
+export func add(a: int32, b: int32): int32 {
+    return a + b
+}
+
+/**
+ * Doubles a number
+ * @example
+ * let total = double(2)
+ * assert(total == 4)
+ */
+export func double(a: int32): int32 {
+    return a * 2
+}
+"#;
+        fs::write(&path, source).unwrap();
+
+        let runner = DoctestRunner::new();
+        let failures = runner.run_file(source, &path).unwrap();
+        assert_eq!(failures.len(), 1, "{:?}", failures);
+        assert_eq!(failures[0].item, "add");
+        assert_eq!(failures[0].line, 10);
+
+        // Each example after the first runs on the interpreter the previous
+        // one returned, with none of its variables left behind
+        let stats = runner.pool().stats();
+        assert_eq!((stats.created, stats.reused), (1, 2));
+        assert_eq!(runner.pool().idle(), 1);
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,113 @@
+//! Tests for the base64, hex and URL encodings of std/encoding
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_function, check_with_imports, string};
+
+const IMPORTS: &str = "import { base64Encode, base64Decode, base64UrlEncode, base64UrlDecode, hexEncode, hexDecode, urlEncode, urlDecode } from \"std/encoding\"\n";
+
+/// Helper function to type check source code that imports std/encoding
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Run `source` and call its function `name` with one string argument
+fn call(source: &str, name: &str, arg: &str) -> RuntimeValue {
+    call_function(&check_source(source).unwrap(), name, &[string(arg)]).unwrap()
+}
+
+const ROUND_TRIPS: &str = r#"
+    func base64(text: string): any {
+        let encoded = base64Encode(text)
+        return (encoded, base64Decode(encoded).unwrap())
+    }
+
+    func base64Url(text: string): any {
+        let encoded = base64UrlEncode(text)
+        return (encoded, base64UrlDecode(encoded).unwrap())
+    }
+
+    func hex(text: string): any {
+        let encoded = hexEncode(text)
+        return (encoded, hexDecode(encoded).unwrap())
+    }
+
+    func url(text: string): any {
+        let encoded = urlEncode(text)
+        return (encoded, urlDecode(encoded).unwrap())
+    }
+
+    func bytes(text: string): string {
+        return hexEncode(b"\x00\xff") + "/" + base64Encode(b"\xfb\xff") + "/" + base64UrlEncode(b"\xfb\xff")
+    }
+"#;
+
+fn round_trip(encoded: &str, decoded: &[u8]) -> RuntimeValue {
+    RuntimeValue::Tuple(vec![
+        string(encoded),
+        RuntimeValue::Slice(decoded.iter().map(|byte| RuntimeValue::UInt8(*byte)).collect()),
+    ])
+}
+
+#[test]
+fn test_round_trips() {
+    assert_eq!(call(ROUND_TRIPS, "base64", "hi?>"), round_trip("aGk/Pg==", b"hi?>"));
+    assert_eq!(call(ROUND_TRIPS, "base64Url", "hi?>"), round_trip("aGk_Pg", b"hi?>"));
+    assert_eq!(call(ROUND_TRIPS, "hex", "Az"), round_trip("417a", b"Az"));
+    assert_eq!(
+        call(ROUND_TRIPS, "url", "a&b c/ü"),
+        RuntimeValue::Tuple(vec![string("a%26b%20c%2F%C3%BC"), string("a&b c/ü")])
+    );
+    assert_eq!(call(ROUND_TRIPS, "bytes", ""), string("00ff/+/8=/-_8"));
+}
+
+#[test]
+fn test_decode_errors() {
+    let source = r#"
+        func base64(text: string): string {
+            return base64Decode(text).error
+        }
+
+        func hex(text: string): string {
+            return hexDecode(text).error
+        }
+
+        func url(text: string): string {
+            return urlDecode(text).error
+        }
+    "#;
+    assert_eq!(call(source, "base64", "aGk*"), string("invalid base64 character '*' at byte 3"));
+    assert_eq!(call(source, "hex", "12x4"), string("invalid hex character 'x' at byte 2"));
+    assert_eq!(call(source, "hex", "123"), string("hex text has an odd number of digits"));
+    assert_eq!(call(source, "url", "50%"), string("invalid percent escape at byte 2"));
+    assert_eq!(call(source, "url", "%C3"), string("decoded text is not UTF-8 at byte 0"));
+}
+
+#[test]
+fn test_checker_errors() {
+    let cases = [
+        (
+            "func f(): any {\n    return hexEncode(true)\n}\n",
+            "Argument 1 to function 'hexEncode': expected byte slice or string, got bool",
+        ),
+        (
+            "func f(): any {\n    return base64Decode(b\"aGk=\")\n}\n",
+            "Argument 1 to function 'base64Decode': expected string",
+        ),
+        (
+            "func f(): any {\n    return urlEncode(\"a\", \"b\")\n}\n",
+            "Function 'urlEncode' expects 1 argument, got 2",
+        ),
+        (
+            "func f(): string {\n    return hexDecode(\"00\")\n}\n",
+            "Cannot return Result",
+        ),
+    ];
+    for (source, expected) in cases {
+        let error = check_source(source).unwrap_err();
+        assert!(error.to_string().contains(expected), "{}: {}", source, error);
+    }
+}
@@ -0,0 +1,196 @@
+//! Tests for the `?` error propagation operator on Result values
+
+mod common;
+
+use bulu::ast::*;
+use bulu::compiler::ir::{IrOpcode, IrTerminator};
+use bulu::compiler::IrGenerator;
+use bulu::types::primitive::RuntimeValue;
+use common::{assert_type_error, check_source, parse_source, run_main};
+
+const PARSERS: &str = r#"
+    func parsePort(text: string): Result<int32, string> {
+        return Ok(8080)
+    }
+
+    func badPort(text: string): Result<int32, string> {
+        return Err("invalid port " + text)
+    }
+"#;
+
+#[test]
+fn test_question_mark_is_parsed_as_postfix() {
+    let program = parse_source("func f(): Result<int32, string> {\n    return parsePort(\"80\")? + 1\n}\n").unwrap();
+    let Statement::FunctionDecl(function) = &program.statements[0] else {
+        panic!("Expected function declaration");
+    };
+    let Statement::Return(ReturnStmt { value: Some(Expression::Binary(sum)), .. }) = &function.body.statements[0] else {
+        panic!("Expected a return of a binary expression");
+    };
+    let Expression::Propagate(propagate) = sum.left.as_ref() else {
+        panic!("Expected a propagate expression, got {:?}", sum.left);
+    };
+    assert!(matches!(propagate.expr.as_ref(), Expression::Call(_)));
+    assert_eq!(propagate.position.column, 27);
+
+    // Chains bind like any other postfix operator
+    let program = parse_source("func f() {\n    load()?.name\n}\n").unwrap();
+    let Statement::FunctionDecl(function) = &program.statements[0] else {
+        panic!("Expected function declaration");
+    };
+    let Statement::Expression(ExpressionStmt { expr: Expression::MemberAccess(access), .. }) = &function.body.statements[0] else {
+        panic!("Expected a member access statement");
+    };
+    assert!(matches!(access.object.as_ref(), Expression::Propagate(_)));
+}
+
+#[test]
+fn test_enclosing_function_must_return_result() {
+    let source = format!(
+        r#"{}
+    func total(a: string, b: string): Result<int32, string> {{
+        let first: int32 = parsePort(a)?
+        return Ok(first + badPort(b)?)
+    }}
+    "#,
+        PARSERS
+    );
+    assert!(check_source(&source).is_ok(), "{:?}", check_source(&source));
+
+    assert_type_error(
+        &format!("{}\nfunc main(): int32 {{\n    return parsePort(\"1\")?\n}}\n", PARSERS),
+        "The '?' operator can only be used in a function returning Result, not int32",
+    );
+    assert_type_error(
+        &format!("{}\nfunc main() {{\n    parsePort(\"1\")?\n}}\n", PARSERS),
+        "The '?' operator can only be used in a function with a declared Result return type",
+    );
+    assert_type_error(
+        &format!("{}\nfunc main(): Result<int32, int32> {{\n    return Ok(parsePort(\"1\")?)\n}}\n", PARSERS),
+        "The '?' operator cannot propagate error type string from a function returning Result<int32, int32>",
+    );
+    assert_type_error(
+        "func main(): Result<int32, string> {\n    let n = 5\n    return Ok(n?)\n}\n",
+        "The '?' operator can only be applied to a Result, got int32",
+    );
+    assert_type_error(
+        &format!("{}\nfunc main(): Result<string, string> {{\n    let port: string = parsePort(\"1\")?\n    return Ok(port)\n}}\n", PARSERS),
+        "Cannot assign int32 to variable of type string",
+    );
+}
+
+#[test]
+fn test_interpreter_short_circuits_on_error() {
+    let source = format!(
+        r#"{}
+    func total(a: string, b: string): Result<int32, string> {{
+        let first = parsePort(a)?
+        let second = badPort(b)?
+        return Ok(first + second)
+    }}
+
+    func double(a: string): Result<int32, string> {{
+        return Ok(parsePort(a)? * 2)
+    }}
+
+    func main(): string {{
+        return total("1", "x").error() + " / " + typeof(double("1").unwrap())
+    }}
+    "#,
+        PARSERS
+    );
+    assert_eq!(
+        run_main(&source).unwrap(),
+        RuntimeValue::String("invalid port x / int32".to_string())
+    );
+
+    let source = format!(
+        r#"{}
+    func double(a: string): Result<int32, string> {{
+        return Ok(parsePort(a)? * 2)
+    }}
+
+    func main(): int32 {{
+        return double("1").unwrap()
+    }}
+    "#,
+        PARSERS
+    );
+    assert_eq!(run_main(&source).unwrap(), RuntimeValue::Integer(16160));
+}
+
+#[test]
+fn test_ir_branches_to_an_early_return() {
+    let source = format!(
+        "{}\nfunc double(a: string): Result<int32, string> {{\n    return Ok(parsePort(a)? * 2)\n}}\n",
+        PARSERS
+    );
+    let program = parse_source(&source).unwrap();
+    let ir_program = IrGenerator::new().generate(&program).unwrap();
+    let double = ir_program.functions.iter().find(|f| f.name == "double").unwrap();
+
+    assert!(double.basic_blocks.len() >= 3);
+    assert!(matches!(
+        double.basic_blocks[0].terminator,
+        IrTerminator::ConditionalBranch { .. }
+    ));
+    let returns = double
+        .basic_blocks
+        .iter()
+        .filter(|block| matches!(block.terminator, IrTerminator::Return(Some(_))))
+        .count();
+    assert_eq!(returns, 2);
+    assert!(double.basic_blocks[0]
+        .instructions
+        .iter()
+        .any(|instruction| instruction.opcode == IrOpcode::StructAccess));
+}
+
+#[test]
+fn test_propagated_value_feeds_later_arithmetic() {
+    let source = format!(
+        r#"{}
+    func offset(a: string): Result<int32, string> {{
+        let n = parsePort(a)?
+        let doubled = n * 2
+        return Ok(doubled - n + 1)
+    }}
+
+    func main(): int32 {{
+        return offset("80").unwrap()
+    }}
+    "#,
+        PARSERS
+    );
+    assert_eq!(run_main(&source).unwrap(), RuntimeValue::Integer(8081));
+}
+
+#[test]
+fn test_error_skips_side_effects_after_the_operator() {
+    let source = format!(
+        r#"{}
+    func record(port: Result<int32, string>, events: chan int32): Result<int32, string> {{
+        events <- 1
+        let n = port?
+        events <- 10
+        return Ok(n)
+    }}
+
+    func main(): int32 {{
+        let events = make(chan_int32, 4)
+        let failed = record(badPort("x"), events)
+        let passed = record(parsePort("80"), events)
+        close(events)
+
+        let total = 0
+        for event in events {{
+            total = total + event
+        }}
+        return total
+    }}
+    "#,
+        PARSERS
+    );
+    // Both calls send before the `?`, only the Ok one reaches the send after it
+    assert_eq!(run_main(&source).unwrap(), RuntimeValue::Integer(12));
+}
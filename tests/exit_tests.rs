@@ -0,0 +1,102 @@
+//! Tests for exit(): it unwinds to the host instead of ending the process
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::lexer::Lexer;
+use bulu::parser::Parser;
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use bulu::runtime::builtins::builtin_exit;
+use bulu::types::primitive::RuntimeValue;
+
+fn parse(source: &str) -> Program {
+    let tokens = Lexer::new(source).tokenize().unwrap();
+    Parser::new(tokens).parse().unwrap()
+}
+
+/// Helper function to run `main` with the AST interpreter
+fn run_main(source: &str, interpreter: &mut AstInterpreter) -> Result<RuntimeValue, BuluError> {
+    let program = parse(source);
+    interpreter.execute_program(&program)?;
+    let main_func = interpreter.get_function_definition("main").expect("main should be defined");
+    interpreter.call_user_function(&main_func, &[])
+}
+
+#[test]
+fn test_exit_unwinds_nested_calls_and_loops() {
+    let source = r#"
+    import { exit } from "std/os"
+
+    func check(i: int32) {
+        println("check", i)
+        while true {
+            exit(3)
+        }
+        println("unreachable")
+    }
+
+    func main() {
+        let i = 0
+        while i < 5 {
+            check(i)
+            i = i + 1
+        }
+        println("after")
+    }
+    "#;
+    let mut interpreter = AstInterpreter::new();
+    let stdout = interpreter.capture_stdout();
+    match run_main(source, &mut interpreter) {
+        Err(BuluError::ExitRequested(code)) => assert_eq!(code, 3),
+        other => panic!("expected an exit request, got {:?}", other),
+    }
+    assert_eq!(stdout.contents(), "check 0\n");
+
+    // The interpreter stays usable after the program exits
+    let result = run_main("func main(): any { return 7 }", &mut interpreter).unwrap();
+    assert!(matches!(result, RuntimeValue::Integer(7)));
+}
+
+#[test]
+fn test_exit_defaults_to_zero_and_checks_its_code() {
+    let mut interpreter = AstInterpreter::new();
+    match run_main("func main() { exit() }", &mut interpreter) {
+        Err(BuluError::ExitRequested(code)) => assert_eq!(code, 0),
+        other => panic!("expected an exit request, got {:?}", other),
+    }
+
+    let result = run_main("func main() { exit(\"no\") }", &mut interpreter);
+    assert!(matches!(result, Err(BuluError::RuntimeError { .. })), "{:?}", result);
+
+    match builtin_exit(&[RuntimeValue::Int32(2)]) {
+        Err(BuluError::ExitRequested(code)) => assert_eq!(code, 2),
+        other => panic!("expected an exit request, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_exit_in_goroutine_stops_the_program() {
+    let source = r#"
+    import { exit } from "std/os"
+
+    func step_once() {
+        println("step")
+    }
+
+    func main() {
+        run func() {
+            exit(4)
+        }()
+        sleep(50)
+        step_once()
+        println("after")
+    }
+    "#;
+    let mut interpreter = AstInterpreter::new();
+    let stdout = interpreter.capture_stdout();
+    match run_main(source, &mut interpreter) {
+        Err(BuluError::ExitRequested(code)) => assert_eq!(code, 4),
+        other => panic!("expected an exit request, got {:?}", other),
+    }
+    assert_eq!(interpreter.exit_requested(), Some(4));
+    assert_eq!(stdout.contents(), "");
+}
@@ -0,0 +1,62 @@
+//! Tests for shortest round-trip float printing across the runtime
+
+use bulu::runtime::builtins::{format_runtime_value, format_string_with_args};
+use bulu::std::json::{Json, JsonValue};
+use bulu::types::primitive::{format_float32, format_float64, widen_float32, RuntimeValue};
+
+#[test]
+fn test_shortest_round_trip_digits() {
+    assert_eq!(format_float64(0.1 + 0.2), "0.30000000000000004");
+    assert_eq!(format_float64(1.0 / 3.0), "0.3333333333333333");
+    assert_eq!(format_float64(2.0), "2");
+    assert_eq!(format_float64(-1.5), "-1.5");
+    assert_eq!(format_float32(0.1), "0.1");
+    assert_eq!(format_float32(16777216.0), "16777216");
+    assert_eq!(widen_float32(0.1), 0.1);
+
+    for value in [0.1 + 0.2, 1e-300, 123456.789, f64::MAX, f64::MIN_POSITIVE, 5e-324] {
+        assert_eq!(format_float64(value).parse::<f64>().unwrap(), value);
+    }
+}
+
+#[test]
+fn test_exponent_notation_outside_plain_range() {
+    assert_eq!(format_float64(1e20), "100000000000000000000");
+    assert_eq!(format_float64(1e21), "1e21");
+    assert_eq!(format_float64(1e-7), "0.0000001");
+    assert_eq!(format_float64(2.5e-8), "2.5e-8");
+    assert_eq!(format_float64(-6.02e23), "-6.02e23");
+    assert_eq!(format_float32(3e38), "3e38");
+    assert_eq!(format_float64(f64::NAN), "NaN");
+    assert_eq!(format_float64(f64::NEG_INFINITY), "-inf");
+}
+
+#[test]
+fn test_every_display_path_agrees() {
+    let values = [
+        (RuntimeValue::Float64(0.1 + 0.2), "0.30000000000000004"),
+        (RuntimeValue::Float32(0.1), "0.1"),
+        (RuntimeValue::Float64(1e21), "1e21"),
+    ];
+    for (value, expected) in values {
+        assert_eq!(value.to_string(), expected);
+        assert_eq!(format!("{}", value), expected);
+        assert_eq!(format_runtime_value(&value), expected);
+        assert_eq!(format_string_with_args("%v", &[value.clone()]).unwrap(), expected);
+    }
+
+    // Explicit precision still applies when it is asked for
+    assert_eq!(format_string_with_args("%.2f", &[RuntimeValue::Float32(0.1)]).unwrap(), "0.10");
+    assert_eq!(format_string_with_args("%g", &[RuntimeValue::Float32(0.1)]).unwrap(), "0.1");
+    assert_eq!(format_string_with_args("%G", &[RuntimeValue::Float64(1e21)]).unwrap(), "1E21");
+}
+
+#[test]
+fn test_json_numbers_are_not_truncated() {
+    assert_eq!(Json::stringify(&JsonValue::Number(42.0)), "42");
+    assert_eq!(Json::stringify(&JsonValue::Number(0.1 + 0.2)), "0.30000000000000004");
+    // Integral values beyond i64 used to saturate
+    assert_eq!(Json::stringify(&JsonValue::Number(1e21)), "1e21");
+    assert_eq!(JsonValue::Number(1e300).to_string(), "1e300");
+    assert_eq!(Json::parse("1e21").unwrap(), JsonValue::Number(1e21));
+}
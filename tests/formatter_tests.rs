@@ -1,8 +1,8 @@
 //! Unit tests for the Bulu code formatter
 
 use bulu::formatter::{
-    create_default_format_config, load_format_config, validate_format_config, BraceStyle,
-    FormatConfig, FormatOptions, Formatter, IndentStyle, TrailingCommaStyle,
+    create_default_format_config, load_format_config, unified_diff, validate_format_config,
+    BraceStyle, FormatConfig, FormatOptions, Formatter, IndentStyle, TrailingCommaStyle,
 };
 use bulu::project::Project;
 use std::fs;
@@ -344,4 +344,121 @@ func test() {
 
     assert_eq!(result1, result2);
     assert_eq!(result2, result3);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_format_range() {
+    let formatter = Formatter::for_source(FormatOptions::default());
+
+    let input = "func test() {\nif true {\nlet x=42\n}\nlet y=1\n}";
+    let result = formatter.format_range(input, 2, 3).expect("Failed to format range");
+    assert_eq!(result, "        let x = 42\n    }");
+
+    // Lines outside the range are never touched
+    let result = formatter.format_range(input, 4, 4).expect("Failed to format range");
+    assert_eq!(result, "    let y = 1");
+
+    assert!(formatter.format_range(input, 10, 12).is_err());
+}
+
+#[test]
+fn test_unified_diff() {
+    assert_eq!(unified_diff("a\nb\n", "a\nb\n", "main.bu"), "");
+
+    let diff = unified_diff("let x=1\nlet y = 2\n", "let x = 1\nlet y = 2\n", "main.bu");
+    assert_eq!(
+        diff,
+        "--- main.bu\n+++ main.bu (formatted)\n@@ -1,2 +1,2 @@\n-let x=1\n+let x = 1\n let y = 2\n"
+    );
+}
+
+/// Apply a unified diff of `original` made by `unified_diff`
+fn apply_diff(original: &str, diff: &str) -> String {
+    let old: Vec<&str> = original.lines().collect();
+    let mut result = Vec::new();
+    let mut next = 0;
+    for line in diff.lines().skip(2) {
+        if let Some(header) = line.strip_prefix("@@ -") {
+            let (start, count) = header.split_once(' ').unwrap().0.split_once(',').unwrap();
+            let start: usize = start.parse().unwrap();
+            // An empty range names the line before it
+            let start = if count == "0" { start } else { start - 1 };
+            result.extend_from_slice(&old[next..start]);
+            next = start;
+        } else if let Some(line) = line.strip_prefix('+') {
+            result.push(line);
+        } else {
+            assert_eq!(&line[1..], old[next], "context and removals follow the original");
+            if line.starts_with(' ') {
+                result.push(old[next]);
+            }
+            next += 1;
+        }
+    }
+    result.extend_from_slice(&old[next..]);
+    result.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+#[test]
+fn test_unified_diff_is_a_shortest_edit_script() {
+    let lcs = |a: &[&str], b: &[&str]| {
+        let mut row = vec![0usize; b.len() + 1];
+        for x in a {
+            let mut diagonal = 0;
+            for (j, y) in b.iter().enumerate() {
+                let above = row[j + 1];
+                row[j + 1] = if x == y { diagonal + 1 } else { row[j + 1].max(row[j]) };
+                diagonal = above;
+            }
+        }
+        row[b.len()]
+    };
+
+    // Pseudo-random files over a few distinct lines, so there are many ways to match
+    let mut seed = 7u64;
+    let mut file = |len: u64| {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let len = (seed >> 33) % len;
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                format!("line {}\n", (seed >> 33) % 4)
+            })
+            .collect::<String>()
+    };
+    for _ in 0..300 {
+        let (original, formatted) = (file(30), file(30));
+        let diff = unified_diff(&original, &formatted, "main.bu");
+        assert_eq!(apply_diff(&original, &diff), formatted, "{}", diff);
+
+        let old: Vec<&str> = original.lines().collect();
+        let new: Vec<&str> = formatted.lines().collect();
+        let edits = diff.lines().skip(2).filter(|line| line.starts_with('+') || line.starts_with('-')).count();
+        assert_eq!(edits, old.len() + new.len() - 2 * lcs(&old, &new), "{}", diff);
+    }
+
+    // Large files with a few changes diff without a table of all line pairs
+    let original: String = (0..20_000).map(|i| format!("let x{} = {}\n", i, i)).collect();
+    let formatted = original.replace("let x5000 = 5000\n", "").replace("x15000 = 15000", "x15000 = 0");
+    let diff = unified_diff(&original, &formatted, "main.bu");
+    assert_eq!(apply_diff(&original, &diff), formatted);
+    assert_eq!(diff.matches("\n-").count() + diff.matches("\n+").count() - 1, 3);
+}
+
+#[test]
+fn test_check_mode_reports_diff_without_writing() {
+    let (_temp_dir, project) = create_test_project();
+    let file = project.src_dir.join("main.bu");
+    fs::write(&file, "let x=42\n").expect("Failed to write source");
+
+    let mut options = FormatOptions::default();
+    options.check_only = true;
+    let formatter = Formatter::new(project, options);
+
+    let result = formatter.format_file(&file).expect("Failed to check file");
+    assert!(result.changed);
+    let diff = result.diff.expect("Check mode should produce a diff");
+    assert!(diff.contains("-let x=42"));
+    assert!(diff.contains("+let x = 42"));
+    assert_eq!(fs::read_to_string(&file).unwrap(), "let x=42\n");
+}
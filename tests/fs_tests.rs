@@ -0,0 +1,156 @@
+//! Tests for the std/fs module
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_main, check_with_imports, string};
+use std::collections::HashMap;
+use tempfile::TempDir;
+
+const IMPORTS: &str = "import { open, readFile, writeFile, appendFile, readDir, mkdirAll, remove, removeAll, copy, metadata, exists, join, baseName, dirName, extension } from \"std/fs\"\n";
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Helper function to type check and run `main` with the AST interpreter,
+/// with `DIR` in the source replaced by a temporary directory
+fn run_main(source: &str, dir: &TempDir) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(&source.replace("DIR", &dir.path().to_string_lossy()))?)
+}
+
+/// The `isSuccess` flag and the value or error of a Result
+fn outcome(result: &RuntimeValue) -> (bool, RuntimeValue) {
+    let RuntimeValue::Struct { name, fields } = result else {
+        panic!("expected a Result, got {:?}", result);
+    };
+    assert_eq!(name, "Result");
+    let success = fields.get("isSuccess") == Some(&RuntimeValue::Bool(true));
+    let key = if success { "value" } else { "error" };
+    (success, fields[key].clone())
+}
+
+fn struct_fields(value: &RuntimeValue) -> &HashMap<String, RuntimeValue> {
+    match value {
+        RuntimeValue::Struct { fields, .. } => fields,
+        other => panic!("expected a struct, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_files_and_directories() {
+    let dir = TempDir::new().unwrap();
+    let source = r#"
+    func main(): any {
+        let sub = join("DIR", "a", "b")
+        mkdirAll(sub).unwrap()
+        let path = join(sub, "notes.txt")
+        writeFile(path, "one\n").unwrap()
+        appendFile(path, "two\n").unwrap()
+        let text = readFile(path).unwrap()
+        let copied = copy(path, join(sub, "copy.txt")).unwrap()
+        let names = readDir(sub).unwrap()
+        let info = metadata(path).unwrap()
+        let size = info.size
+        let isDir = info.isDir
+        remove(join(sub, "copy.txt")).unwrap()
+        let stillThere = exists(join(sub, "copy.txt"))
+        let notEmpty = remove(join("DIR", "a"))
+        removeAll(join("DIR", "a")).unwrap()
+        return (text, copied, names, size, isDir, stillThere, notEmpty.isError(), exists(sub))
+    }
+    "#;
+    let result = run_main(source, &dir).unwrap();
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![
+            string("one\ntwo\n"),
+            RuntimeValue::Int64(8),
+            RuntimeValue::Array(vec![string("copy.txt"), string("notes.txt")]),
+            RuntimeValue::Int64(8),
+            RuntimeValue::Bool(false),
+            RuntimeValue::Bool(false),
+            RuntimeValue::Bool(true),
+            RuntimeValue::Bool(false),
+        ])
+    );
+}
+
+#[test]
+fn test_file_handles_and_errors() {
+    let dir = TempDir::new().unwrap();
+    let source = r#"
+    func main(): any {
+        let path = join("DIR", "log.txt")
+        let log = open(path, "w").unwrap()
+        log.write("started\n")
+        log.write([104, 105])
+        log.close()
+        let reader = open(path, "r").unwrap()
+        let text = reader.read()
+        reader.close()
+        let missing = readFile(join("DIR", "missing.txt"))
+        let meta = metadata(path).unwrap()
+        return (text, missing, meta)
+    }
+    "#;
+    let RuntimeValue::Tuple(values) = run_main(source, &dir).unwrap() else {
+        panic!("expected a tuple");
+    };
+    assert_eq!(outcome(&values[0]), (true, string("started\nhi")));
+    let (success, error) = outcome(&values[1]);
+    assert!(!success);
+    let RuntimeValue::String(error) = error else {
+        panic!("expected an error message");
+    };
+    assert!(error.contains("missing.txt"), "{}", error);
+    let meta = struct_fields(&values[2]);
+    assert_eq!(meta["size"], RuntimeValue::Int64(10));
+    assert_eq!(meta["isFile"], RuntimeValue::Bool(true));
+    assert!(matches!(meta["modified"], RuntimeValue::Int64(ms) if ms > 0));
+
+    // A closed file can't be used
+    let error = run_main(
+        "func main() { let f = open(join(\"DIR\", \"x\"), \"w\").unwrap()\n f.close()\n f.write(\"no\") }",
+        &dir,
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("the file is closed"), "{}", error);
+}
+
+#[test]
+fn test_path_helpers() {
+    let dir = TempDir::new().unwrap();
+    let source = r#"
+    func main(): any {
+        let p = "docs/guide/intro.md"
+        return (baseName(p), dirName(p), extension(p), dirName("intro.md"))
+    }
+    "#;
+    let result = run_main(source, &dir).unwrap();
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![string("intro.md"), string("docs/guide"), string("md"), string(".")])
+    );
+}
+
+#[test]
+fn test_fs_calls_are_checked() {
+    let error = check_source("func main() { let n: string = readFile(\"x\") }").unwrap_err();
+    assert!(error.to_string().contains("Result<string, string>"), "{}", error);
+
+    let error = check_source("func main() { open(\"x\", \"z\") }").unwrap_err();
+    assert!(error.to_string().contains("Unknown mode 'z'"), "{}", error);
+
+    let error = check_source("func main() { mkdirAll(3) }").unwrap_err();
+    assert!(error.to_string().contains("expected string, got"), "{}", error);
+
+    let error = check_source("func main() { join() }").unwrap_err();
+    assert!(error.to_string().contains("expects at least 1 argument"), "{}", error);
+
+    let error = check_source("func main() { let info = metadata(\"x\").unwrap()\n let s: string = info.size }").unwrap_err();
+    assert!(error.to_string().contains("int64"), "{}", error);
+}
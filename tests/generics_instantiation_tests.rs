@@ -0,0 +1,205 @@
+//! Tests for generic function and struct instantiation in the type checker
+
+mod common;
+
+use bulu::ast::*;
+use common::{assert_type_error, check_source, parse_source};
+
+const BOX: &str = r#"
+    struct Box<T> {
+        value: T
+
+        func get(): T {
+            return this.value
+        }
+    }
+"#;
+
+#[test]
+fn test_explicit_type_arguments_are_parsed() {
+    let program = parse_source(
+        r#"
+    func main() {
+        let x = identity<int32>(5)
+        let b = Box<string>{ value: "hi" }
+        let c = a < b
+    }
+    "#,
+    )
+    .unwrap();
+
+    let Statement::FunctionDecl(main) = &program.statements[0] else {
+        panic!("Expected function declaration");
+    };
+    let initializer = |index: usize| match &main.body.statements[index] {
+        Statement::VariableDecl(decl) => decl.initializer.clone().unwrap(),
+        other => panic!("Expected variable declaration, got {:?}", other),
+    };
+
+    match initializer(0) {
+        Expression::Call(call) => {
+            assert_eq!(call.type_args, vec![Type::Int32]);
+            assert_eq!(call.args.len(), 1);
+        }
+        other => panic!("Expected call expression, got {:?}", other),
+    }
+    match initializer(1) {
+        Expression::StructLiteral(literal) => {
+            assert_eq!(literal.type_name, "Box");
+            assert_eq!(literal.type_args, vec![Type::String]);
+        }
+        other => panic!("Expected struct literal, got {:?}", other),
+    }
+    assert!(matches!(initializer(2), Expression::Binary(_)));
+}
+
+#[test]
+fn test_generic_function_calls() {
+    let source = r#"
+    func identity<T>(x: T): T {
+        return x
+    }
+
+    func pick<T>(a: T, b: T): T {
+        return b
+    }
+
+    func main() {
+        let a: int32 = identity<int32>(5)
+        let b: string = identity("hi")
+        let c: int32 = pick(1, 2)
+    }
+    "#;
+    assert!(check_source(source).is_ok());
+
+    assert_type_error(
+        r#"
+    func identity<T>(x: T): T {
+        return x
+    }
+
+    func main() {
+        let s: string = identity<int32>(5)
+    }
+    "#,
+        "Cannot assign int32 to variable of type string",
+    );
+    assert_type_error(
+        r#"
+    func identity<T>(x: T): T {
+        return x
+    }
+
+    func main() {
+        identity<int32, string>(5)
+    }
+    "#,
+        "Function 'identity' expects 1 type arguments, got 2",
+    );
+    assert_type_error(
+        r#"
+    func double(x: int32): int32 {
+        return x * 2
+    }
+
+    func main() {
+        double<int32>(5)
+    }
+    "#,
+        "Function 'double' is not generic",
+    );
+}
+
+#[test]
+fn test_generic_struct_instances() {
+    let source = format!(
+        r#"{}
+    func main() {{
+        let b: Box<string> = Box<string>{{ value: "hi" }}
+        let inferred = Box{{ value: 42 }}
+        let n: int32 = inferred.value
+        let s: string = b.get()
+        let nested: Box<Box<int32>> = Box{{ value: inferred }}
+    }}
+    "#,
+        BOX
+    );
+    assert!(check_source(&source).is_ok());
+
+    let mismatch = format!(
+        r#"{}
+    func main() {{
+        let b: Box<int32> = Box<string>{{ value: "hi" }}
+    }}
+    "#,
+        BOX
+    );
+    assert_type_error(
+        &mismatch,
+        "Cannot assign struct Box<string> to variable of type struct Box<int32>",
+    );
+
+    let wrong_field = format!(
+        r#"{}
+    func main() {{
+        let b = Box<string>{{ value: 42 }}
+    }}
+    "#,
+        BOX
+    );
+    assert_type_error(&wrong_field, "value");
+
+    let arity = format!(
+        r#"{}
+    func main() {{
+        let b: Box<int32, string> = Box{{ value: 1 }}
+    }}
+    "#,
+        BOX
+    );
+    assert_type_error(&arity, "Struct 'Box' expects 1 type arguments, got 2");
+}
+
+#[test]
+fn test_interface_constraints_are_checked() {
+    let prelude = r#"
+    interface Shape {
+        func area(): float64
+    }
+
+    struct Square {
+        side: float64
+
+        func area(): float64 {
+            return this.side * this.side
+        }
+    }
+
+    func measure<T: Shape>(shape: T): float64 {
+        return shape.area()
+    }
+    "#;
+
+    let ok = format!(
+        r#"{}
+    func main() {{
+        let a: float64 = measure(Square{{ side: 2.0 }})
+    }}
+    "#,
+        prelude
+    );
+    assert!(check_source(&ok).is_ok());
+
+    let violation = format!(
+        r#"{}
+    func main() {{
+        measure(42)
+    }}
+    "#,
+        prelude
+    );
+    assert_type_error(
+        &violation,
+        "does not satisfy constraint interface Shape on type parameter 'T' of function 'measure'",
+    );
+}
@@ -0,0 +1,109 @@
+//! Tests for the `hash` builtin
+
+mod common;
+
+use bulu::runtime::builtins::{builtin_hash, stable_hash};
+use bulu::types::primitive::RuntimeValue;
+use common::{check_source, run_main};
+use std::collections::HashMap;
+
+fn seeded(value: RuntimeValue) -> RuntimeValue {
+    builtin_hash(&[value, RuntimeValue::Int64(42)]).unwrap()
+}
+
+fn map(entries: &[(&str, RuntimeValue)]) -> RuntimeValue {
+    let map: HashMap<String, RuntimeValue> =
+        entries.iter().map(|(key, value)| (key.to_string(), value.clone())).collect();
+    RuntimeValue::Map(map.into())
+}
+
+#[test]
+fn test_seeded_hashes_are_fixed_across_runs() {
+    // Pinned values: a seeded hash must never change between runs or releases
+    assert_eq!(stable_hash(&RuntimeValue::String("bulu".to_string()), 42).unwrap(), 0xfde9_3a80_d6ad_c9a4);
+    assert_eq!(stable_hash(&RuntimeValue::Int32(7), 0).unwrap(), 0xaea0_1fc2_b550_3d45);
+
+    let hash = seeded(RuntimeValue::String("bulu".to_string()));
+    assert_eq!(hash, RuntimeValue::Int64((0xfde9_3a80_d6ad_c9a4_u64 >> 1) as i64));
+    assert_ne!(hash, builtin_hash(&[RuntimeValue::String("bulu".to_string()), RuntimeValue::Int64(43)]).unwrap());
+
+    // Unseeded hashes are stable within the process and never negative
+    let unseeded = builtin_hash(&[RuntimeValue::Int32(-5)]).unwrap();
+    assert_eq!(unseeded, builtin_hash(&[RuntimeValue::Int32(-5)]).unwrap());
+    assert!(matches!(unseeded, RuntimeValue::Int64(h) if h >= 0));
+}
+
+#[test]
+fn test_equal_values_hash_equally() {
+    // Integers hash by value whatever their width
+    assert_eq!(seeded(RuntimeValue::Int32(300)), seeded(RuntimeValue::Int64(300)));
+    assert_eq!(seeded(RuntimeValue::UInt8(7)), seeded(RuntimeValue::Integer(7)));
+    assert_eq!(seeded(RuntimeValue::Float64(0.0)), seeded(RuntimeValue::Float64(-0.0)));
+    assert_eq!(seeded(RuntimeValue::Float32(0.1)), seeded(RuntimeValue::Float64(0.1)));
+
+    // Maps and structs do not depend on iteration order
+    let a = map(&[("x", RuntimeValue::Int32(1)), ("y", RuntimeValue::Int32(2))]);
+    let b = map(&[("y", RuntimeValue::Int32(2)), ("x", RuntimeValue::Int32(1))]);
+    assert_eq!(seeded(a.clone()), seeded(b));
+    assert_ne!(seeded(a), seeded(map(&[("x", RuntimeValue::Int32(2)), ("y", RuntimeValue::Int32(1))])));
+
+    // Different shapes with the same contents stay apart
+    let items = vec![RuntimeValue::Int32(1), RuntimeValue::Int32(2)];
+    assert_eq!(seeded(RuntimeValue::Array(items.clone())), seeded(RuntimeValue::Slice(items.clone())));
+    assert_ne!(seeded(RuntimeValue::Array(items.clone())), seeded(RuntimeValue::Tuple(items)));
+    assert_ne!(seeded(RuntimeValue::String("1".to_string())), seeded(RuntimeValue::Int32(1)));
+    assert_ne!(seeded(RuntimeValue::Char('a')), seeded(RuntimeValue::String("a".to_string())));
+}
+
+#[test]
+fn test_unhashable_values_are_rejected() {
+    let error = builtin_hash(&[RuntimeValue::Channel(1)]).unwrap_err();
+    assert!(error.to_string().contains("hash() of unhashable type channel"), "{}", error);
+
+    // Also when nested inside a hashable container
+    let nested = RuntimeValue::Array(vec![RuntimeValue::Int32(1), RuntimeValue::Function("main".to_string())]);
+    let error = builtin_hash(&[nested]).unwrap_err();
+    assert!(error.to_string().contains("unhashable type function"), "{}", error);
+
+    let error = builtin_hash(&[RuntimeValue::Float64(f64::NAN)]).unwrap_err();
+    assert!(error.to_string().contains("NaN"), "{}", error);
+
+    let error = builtin_hash(&[RuntimeValue::Int32(1), RuntimeValue::String("seed".to_string())]).unwrap_err();
+    assert!(error.to_string().contains("seed must be an integer, got string"), "{}", error);
+    assert!(builtin_hash(&[]).is_err());
+}
+
+#[test]
+fn test_hash_of_equal_program_values() {
+    let source = r#"
+struct Point {
+    x: int32
+    y: int32
+}
+
+func main(): [4]int64 {
+    return [hash(Point{x: 1, y: 2}, 7), hash(Point{x: 1, y: 2}, 7), hash(Point{x: 2, y: 1}, 7), hash("bulu", 42)]
+}
+"#;
+    let RuntimeValue::Array(hashes) = run_main(source).unwrap() else {
+        panic!("expected an array of hashes");
+    };
+    assert_eq!(hashes[0], hashes[1]);
+    assert_ne!(hashes[0], hashes[2]);
+    assert_eq!(hashes[3], seeded(RuntimeValue::String("bulu".to_string())));
+}
+
+#[test]
+fn test_checker_rejects_misuse() {
+    let cases = [
+        ("func main() { let ch = make(chan int32)\n let h = hash(ch) }", "Cannot hash a value of type"),
+        ("func f() {}\nfunc main() { let h = hash(f) }", "Cannot hash a value of type"),
+        ("func main() { let h = hash(1, \"seed\") }", "expected integer, got string"),
+        ("func main() { let h = hash() }", "expects 1 to 2 arguments, got 0"),
+    ];
+    for (source, expected) in cases {
+        let error = check_source(source).expect_err(source);
+        assert!(error.to_string().contains(expected), "{}: {}", source, error);
+    }
+    assert!(check_source("func main() { let h: int64 = hash(\"x\", 1) }").is_ok());
+}
@@ -0,0 +1,60 @@
+//! Tests for heap profiling in the AST interpreter
+
+use bulu::lexer::Lexer;
+use bulu::parser::Parser;
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use bulu::runtime::memory::HeapProfile;
+use bulu::types::checker::TypeChecker;
+
+/// Helper function to type check and run `main` with heap profiling enabled
+fn profile_main(source: &str) -> HeapProfile {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize().unwrap();
+    let mut parser = Parser::new(tokens);
+    let program = parser.parse().unwrap();
+    TypeChecker::new().check(&program).unwrap();
+
+    let mut interpreter = AstInterpreter::new();
+    interpreter.enable_heap_profile();
+    interpreter.execute_program(&program).unwrap();
+    let main_func = interpreter.get_function_definition("main").expect("main should be defined");
+    interpreter.call_user_function(&main_func, &[]).unwrap();
+    interpreter.heap_profile().expect("profiling is enabled")
+}
+
+#[test]
+fn test_allocations_are_attributed_to_call_stacks() {
+    let source = r#"
+struct Point {
+    x: int32
+    y: int32
+}
+
+func build(n: int32): [3]int32 {
+    return [n, n, n]
+}
+
+func main() {
+    let i = 0
+    while i < 4 {
+        let xs = build(i)
+        i = i + 1
+    }
+    let p = Point{x: 1, y: 2}
+}
+"#;
+    let profile = profile_main(source);
+    let functions = profile.by_function();
+    assert_eq!(functions[0].0, "build");
+    assert_eq!(functions[0].1.count, 4);
+    assert_eq!(profile.total().count, 5);
+
+    let folded = profile.folded();
+    assert!(folded.contains("main;build;build:8 "), "{}", folded);
+    assert!(folded.contains("main;main:17 "), "{}", folded);
+}
+
+#[test]
+fn test_profiling_is_off_by_default() {
+    assert!(AstInterpreter::new().heap_profile().is_none());
+}
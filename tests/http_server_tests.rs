@@ -0,0 +1,170 @@
+//! Tests for the std/http server
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use bulu::types::primitive::RuntimeValue;
+use common::check_with_imports;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+const IMPORTS: &str = "import { newServer, response } from \"std/http\"\n";
+
+const SERVER: &str = r#"
+    func greet(req: Request): Response {
+        let name = req.params["name"]
+        let greeting = req.query["greeting"]
+        return response(200, greeting + " " + name).withHeader("X-Greeted", name)
+    }
+
+    func echo(req: Request): Response {
+        let form = req.form()
+        let agent = req.header("User-Agent").unwrap()
+        return response(201, req.method + " " + form["a"] + " " + agent)
+    }
+
+    func plain(req: Request): string {
+        return "plain " + req.path
+    }
+
+    func slow(req: Request): string {
+        sleep(200)
+        return "done"
+    }
+
+    func broken(req: Request): int32 {
+        return 1
+    }
+
+    func start(): any {
+        let srv = newServer()
+        srv.get("/hello/:name", greet)
+        srv.post("/echo", echo)
+        srv.handle("PUT", "/plain", plain)
+        srv.get("/slow", slow)
+        srv.get("/broken", broken)
+        let addr = srv.bind("127.0.0.1:0").unwrap()
+        run srv.serve()
+        return (srv, addr)
+    }
+
+    func stop(srv: Server) {
+        srv.shutdown()
+    }
+"#;
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Start the test server, returning the interpreter, the server handle and its address
+fn start_server() -> (AstInterpreter, RuntimeValue, String) {
+    let program = check_source(SERVER).unwrap();
+    let mut interpreter = AstInterpreter::new();
+    interpreter.execute_program(&program).unwrap();
+    let start = interpreter.get_function_definition("start").unwrap();
+    match interpreter.call_user_function(&start, &[]).unwrap() {
+        RuntimeValue::Tuple(values) => match values.as_slice() {
+            [server, RuntimeValue::String(addr)] => (interpreter, server.clone(), addr.clone()),
+            other => panic!("unexpected start() result {:?}", other),
+        },
+        other => panic!("unexpected start() result {:?}", other),
+    }
+}
+
+fn stop_server(interpreter: &mut AstInterpreter, server: RuntimeValue) {
+    let stop = interpreter.get_function_definition("stop").unwrap();
+    interpreter.call_user_function(&stop, &[server]).unwrap();
+}
+
+/// Send a raw request and return the whole response
+fn send(addr: &str, request: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn test_routes_call_handlers() {
+    let (mut interpreter, server, addr) = start_server();
+
+    let response = send(&addr, "GET /hello/Ada%20L?greeting=hi HTTP/1.1\r\nHost: x\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+    assert!(response.contains("X-Greeted: Ada L\r\n"), "{}", response);
+    assert!(response.ends_with("\r\n\r\nhi Ada L"), "{}", response);
+
+    let response = send(
+        &addr,
+        "POST /echo HTTP/1.1\r\nuser-agent: tester\r\nContent-Length: 9\r\n\r\na=1+2&b=3",
+    );
+    assert!(response.starts_with("HTTP/1.1 201 Created\r\n"), "{}", response);
+    assert!(response.ends_with("POST 1 2 tester"), "{}", response);
+
+    let response = send(&addr, "PUT /plain/ HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+    assert!(response.ends_with("plain /plain/"), "{}", response);
+
+    stop_server(&mut interpreter, server);
+}
+
+#[test]
+fn test_unmatched_and_failing_requests() {
+    let (mut interpreter, server, addr) = start_server();
+
+    let response = send(&addr, "GET /missing HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"), "{}", response);
+
+    let response = send(&addr, "DELETE /echo HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"), "{}", response);
+    assert!(response.contains("Allow: POST\r\n"), "{}", response);
+
+    let response = send(&addr, "GET /broken HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 500 Internal Server Error\r\n"), "{}", response);
+
+    let response = send(&addr, "NONSENSE\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"), "{}", response);
+
+    stop_server(&mut interpreter, server);
+}
+
+#[test]
+fn test_shutdown_finishes_requests_in_flight() {
+    let (mut interpreter, server, addr) = start_server();
+
+    let client_addr = addr.clone();
+    let started = Instant::now();
+    let client = std::thread::spawn(move || send(&client_addr, "GET /slow HTTP/1.1\r\n\r\n"));
+    std::thread::sleep(Duration::from_millis(50));
+    stop_server(&mut interpreter, server);
+
+    // The request accepted before shutdown is still answered
+    let response = client.join().unwrap();
+    assert!(response.ends_with("done"), "{}", response);
+    assert!(started.elapsed() >= Duration::from_millis(200));
+
+    // Once serving stops no new connections are accepted
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while TcpStream::connect(&addr).is_ok() {
+        assert!(Instant::now() < deadline, "server still accepting connections");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn test_http_calls_are_checked() {
+    let error = check_source("func main() { let r = response(\"200\", \"ok\") }").unwrap_err();
+    assert!(error.to_string().contains("int32"), "{}", error);
+
+    let error = check_source("func main() { let r = response(200, \"ok\")\n let s: string = r.status }").unwrap_err();
+    assert!(error.to_string().contains("int32"), "{}", error);
+
+    let error = check_source("func main() { let n: int32 = newServer().bind(\":0\") }").unwrap_err();
+    assert!(error.to_string().contains("Result<string, string>"), "{}", error);
+}
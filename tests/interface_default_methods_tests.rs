@@ -0,0 +1,174 @@
+//! Tests for default method implementations on interfaces
+
+mod common;
+
+use bulu::ast::*;
+use bulu::compiler::IrGenerator;
+use bulu::types::primitive::RuntimeValue;
+use common::{check_source, parse_source, run_main};
+
+const SHAPES: &str = r#"
+    interface Shape {
+        func area(): float64
+
+        func name(): string {
+            return "shape"
+        }
+
+        func doubleArea(): float64 {
+            return this.area() * 2.0
+        }
+    }
+
+    struct Square {
+        side: float64
+
+        func area(): float64 {
+            return this.side * this.side
+        }
+    }
+
+    struct Circle {
+        radius: float64
+
+        func area(): float64 {
+            return 3.0 * this.radius * this.radius
+        }
+
+        func name(): string {
+            return "circle"
+        }
+    }
+"#;
+
+#[test]
+fn test_default_bodies_are_parsed() {
+    let program = parse_source(SHAPES).unwrap();
+    let Statement::InterfaceDecl(shape) = &program.statements[0] else {
+        panic!("Expected interface declaration");
+    };
+
+    assert!(shape.methods[0].default_body.is_none());
+    assert!(shape.methods[1].default_body.is_some());
+    let default_method = shape.methods[2].default_method().unwrap();
+    assert_eq!(default_method.name, "doubleArea");
+    assert_eq!(default_method.return_type, Some(Type::Float64));
+
+    let Statement::StructDecl(square) = &program.statements[1] else {
+        panic!("Expected struct declaration");
+    };
+    let inherited: Vec<String> = shape
+        .inherited_defaults(square)
+        .into_iter()
+        .map(|method| method.name)
+        .collect();
+    assert_eq!(inherited, vec!["name", "doubleArea"]);
+}
+
+#[test]
+fn test_default_methods_satisfy_interfaces() {
+    let source = format!(
+        r#"{}
+    func describe(shape: Shape): string {{
+        return shape.name()
+    }}
+
+    func main() {{
+        let s = Square{{ side: 2.0 }}
+        let label: string = s.name()
+        let twice: float64 = s.doubleArea()
+        let shape: Shape = s
+        describe(shape)
+        describe(Circle{{ radius: 1.0 }})
+    }}
+    "#,
+        SHAPES
+    );
+    assert!(check_source(&source).is_ok());
+
+    // An override must keep the interface's signature
+    let bad_override = format!(
+        r#"{}
+    struct Label {{
+        func area(): float64 {{
+            return 0.0
+        }}
+
+        func name(): int32 {{
+            return 1
+        }}
+    }}
+
+    func main() {{
+        let shape: Shape = Label{{}}
+    }}
+    "#,
+        SHAPES
+    );
+    let error = check_source(&bad_override).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Cannot assign struct Label to variable of type interface Shape"));
+
+    // Default bodies are checked against the interface
+    let error = check_source(
+        r#"
+    interface Named {
+        func name(): string {
+            return 42
+        }
+    }
+    "#,
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("int32"), "unexpected error: {}", error);
+}
+
+#[test]
+fn test_interpreter_dispatches_to_defaults() {
+    let source = format!(
+        r#"{}
+    func main(): string {{
+        let s = Square{{ side: 2.0 }}
+        let c = Circle{{ radius: 1.0 }}
+        return s.name() + " " + c.name()
+    }}
+    "#,
+        SHAPES
+    );
+    assert_eq!(
+        run_main(&source).unwrap(),
+        RuntimeValue::String("shape circle".to_string())
+    );
+
+    let source = format!(
+        r#"{}
+    func main(): float64 {{
+        let s = Square{{ side: 3.0 }}
+        return s.doubleArea()
+    }}
+    "#,
+        SHAPES
+    );
+    assert_eq!(run_main(&source).unwrap(), RuntimeValue::Float64(18.0));
+}
+
+#[test]
+fn test_ir_generates_inherited_methods() {
+    let program = parse_source(SHAPES).unwrap();
+    let ir_program = IrGenerator::new().generate(&program).unwrap();
+    let function_names: Vec<&str> = ir_program
+        .functions
+        .iter()
+        .map(|function| function.name.as_str())
+        .collect();
+
+    assert!(function_names.contains(&"Square.name"));
+    assert!(function_names.contains(&"Square.doubleArea"));
+    assert!(function_names.contains(&"Circle.doubleArea"));
+    // Circle's own name() wins over the default
+    assert_eq!(function_names.iter().filter(|name| **name == "Circle.name").count(), 1);
+
+    let square = ir_program.structs.iter().find(|s| s.name == "Square").unwrap();
+    assert_eq!(square.methods, vec!["area", "name", "doubleArea"]);
+}
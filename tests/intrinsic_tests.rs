@@ -0,0 +1,84 @@
+//! Tests for `intrinsic`, the native backend's escape hatch to machine instructions
+
+use bulu::compiler::CompileSession;
+use bulu::testing::differential::{Backend, BackendResult, DifferentialRunner};
+
+const PROGRAM: &str = r#"func main() {
+    let mask: int64 = 255
+    println(intrinsic("popcount", mask))
+    println(intrinsic("clz", 1))
+    println(intrinsic("ctz", 0))
+    println(intrinsic("rotl", 1, 4))
+    println(intrinsic("bswap", intrinsic("bswap", 258)))
+    intrinsic("fence")
+}
+"#;
+
+fn check_error(source: &str) -> String {
+    CompileSession::from_source("main.bu", source).check().unwrap_err().to_string()
+}
+
+#[test]
+fn test_intrinsics_run_natively_and_are_rejected_when_interpreted() {
+    let runner = DifferentialRunner::new(vec![Backend::Native, Backend::Interpreter, Backend::Vm]);
+    let comparison = runner.run_source("main.bu", PROGRAM);
+    match comparison.result(Backend::Native).unwrap() {
+        BackendResult::Ran(outcome) => assert_eq!(outcome.stdout, "8\n63\n64\n16\n258\n", "{}", comparison.describe()),
+        BackendResult::Skipped(_) => {}
+        BackendResult::TimedOut => panic!("{}", comparison.describe()),
+    }
+    for backend in [Backend::Interpreter, Backend::Vm] {
+        let Some(BackendResult::Ran(outcome)) = comparison.result(backend) else {
+            panic!("{}", comparison.describe());
+        };
+        let diagnostic = outcome.diagnostic.as_deref().unwrap_or_default();
+        assert!(
+            diagnostic.contains("Intrinsic 'popcount' needs the native backend"),
+            "{}",
+            comparison.describe()
+        );
+    }
+}
+
+#[test]
+fn test_intrinsics_the_target_lacks_are_rejected() {
+    let error = CompileSession::from_source("main.bu", PROGRAM)
+        .with_target("linux-arm64")
+        .executable()
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("Intrinsic 'popcount' is not available on aarch64; it is available on x86_64"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_calls_are_type_checked() {
+    let cases = [
+        (
+            "func main() {\n    intrinsic(\"cpuid\")\n}\n",
+            "Unknown intrinsic 'cpuid', expected one of popcount, clz, ctz",
+        ),
+        (
+            "func main() {\n    let name = \"rdtsc\"\n    intrinsic(name)\n}\n",
+            "The first argument to 'intrinsic' must be a string literal naming the intrinsic",
+        ),
+        ("func main() {\n    intrinsic(\"rotl\", 1)\n}\n", "Intrinsic 'rotl' expects 2 arguments, got 1"),
+        (
+            "func main() {\n    intrinsic(\"popcount\", \"ff\")\n}\n",
+            "Argument 1 to intrinsic 'popcount': expected int64, got string",
+        ),
+        (
+            "func main() {\n    let s: string = intrinsic(\"rdtsc\")\n}\n",
+            "Cannot assign int64 to variable of type string",
+        ),
+    ];
+    for (source, expected) in cases {
+        let error = check_error(source);
+        assert!(error.contains(expected), "{}: {}", source, error);
+    }
+
+    let source = "func main() {\n    let x: int32 = 6\n    let n: int64 = intrinsic(\"popcount\", x)\n    intrinsic(\"pause\")\n}\n";
+    assert!(CompileSession::from_source("main.bu", source).check().is_ok());
+}
@@ -181,6 +181,7 @@ fn test_type_conversion() {
     let array_type = Type::Array(ArrayType {
         element_type: Box::new(Type::Int32),
         size: Some(10),
+        size_expr: None,
     });
     let ir_array_type = generator.convert_type(&array_type).unwrap();
     assert_eq!(
@@ -210,12 +211,14 @@ fn test_struct_generation() {
                 field_type: Type::Float64,
                 position: test_pos(),
                 is_private: false,
+                attributes: Vec::new(),
             },
             StructField {
                 name: "y".to_string(),
                 field_type: Type::Float64,
                 position: test_pos(),
                 is_private: false,
+                attributes: Vec::new(),
             },
         ],
         doc_comment: None,
@@ -295,6 +298,7 @@ fn test_program_generation() {
                 position: test_pos(),
             }),
         ],
+        attributes: vec![],
         position: test_pos(),
     };
 
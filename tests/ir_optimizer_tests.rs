@@ -0,0 +1,180 @@
+//! Tests for the IR optimizer passes enabled at O2 and O3
+
+use bulu::compiler::ir::{IrFunction, IrOpcode, IrProgram, IrTerminator, IrValue};
+use bulu::compiler::{IrGenerator, IrOptimizer, OptLevel};
+use bulu::lexer::Lexer;
+use bulu::parser::Parser;
+
+/// Helper function to generate and optimize IR for source code
+fn optimize(source: &str, level: OptLevel) -> IrProgram {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize().unwrap();
+    let mut parser = Parser::new(tokens);
+    let ast = parser.parse().unwrap();
+
+    let program = IrGenerator::new().generate(&ast).unwrap();
+    let mut optimizer = IrOptimizer::new();
+    optimizer.set_level(level);
+    optimizer.optimize(program).unwrap()
+}
+
+fn function<'a>(program: &'a IrProgram, name: &str) -> &'a IrFunction {
+    program.functions.iter().find(|f| f.name == name).expect("function exists")
+}
+
+/// Names of the functions called directly by `function`
+fn callees(function: &IrFunction) -> Vec<String> {
+    function
+        .basic_blocks
+        .iter()
+        .flat_map(|block| &block.instructions)
+        .filter(|instruction| instruction.opcode == IrOpcode::Call)
+        .filter_map(|instruction| match instruction.operands.first() {
+            Some(IrValue::Global(name)) | Some(IrValue::Function(name)) => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Every branch target names a block of the function
+fn assert_labels_resolve(function: &IrFunction) {
+    let labels: Vec<&str> = function.basic_blocks.iter().map(|block| block.label.as_str()).collect();
+    for block in &function.basic_blocks {
+        let targets = match &block.terminator {
+            IrTerminator::Branch(label) => vec![label],
+            IrTerminator::ConditionalBranch { true_label, false_label, .. } => vec![true_label, false_label],
+            _ => vec![],
+        };
+        for target in targets {
+            assert!(labels.contains(&target.as_str()), "dangling branch to {} in {}", target, function.name);
+        }
+    }
+}
+
+const PROGRAM: &str = r#"
+func add(a: int32, b: int32): int32 {
+    return a + b
+}
+
+func abs(x: int32): int32 {
+    if x < 0 {
+        return -x
+    }
+    return x
+}
+
+func fib(n: int32): int32 {
+    if n < 2 {
+        return n
+    }
+    return fib(n - 1) + fib(n - 2)
+}
+
+func main() {
+    let unused = 5
+    let x = add(1, 2)
+    println(abs(x - 10))
+    println(fib(x))
+    if false {
+        println(999)
+    }
+}
+"#;
+
+#[test]
+fn test_o1_keeps_calls_and_blocks() {
+    let program = optimize(PROGRAM, OptLevel::O1);
+    let main = function(&program, "main");
+
+    assert_eq!(callees(main), vec!["add", "abs", "println", "fib", "println", "println"]);
+    assert_eq!(main.locals.len(), 2);
+}
+
+#[test]
+fn test_inline_small_functions() {
+    let program = optimize(PROGRAM, OptLevel::O2);
+    let main = function(&program, "main");
+
+    // `add` and the multi-block `abs` are inlined; the recursive `fib` is not
+    let calls = callees(main);
+    assert!(!calls.contains(&"add".to_string()), "{:?}", calls);
+    assert!(!calls.contains(&"abs".to_string()), "{:?}", calls);
+    assert!(calls.contains(&"fib".to_string()), "{:?}", calls);
+    assert_eq!(callees(function(&program, "fib")), vec!["fib", "fib"]);
+
+    assert!(main.basic_blocks.iter().any(|block| block.label.ends_with("_cont")));
+    assert!(main.locals.iter().any(|local| local.name == "abs.x"));
+    assert_labels_resolve(main);
+}
+
+#[test]
+fn test_remove_dead_blocks_and_unused_locals() {
+    let program = optimize(PROGRAM, OptLevel::O2);
+    let main = function(&program, "main");
+
+    // `if false` no longer branches and its body is gone
+    assert_eq!(callees(main).iter().filter(|name| *name == "println").count(), 2);
+    assert!(!main
+        .basic_blocks
+        .iter()
+        .any(|block| matches!(block.terminator, IrTerminator::ConditionalBranch { condition: IrValue::Constant(_), .. })));
+
+    assert!(!main.locals.iter().any(|local| local.name == "unused"));
+    assert!(main.locals.iter().any(|local| local.name == "x"));
+    assert_labels_resolve(main);
+}
+
+#[test]
+fn test_size_and_aggressive_levels() {
+    let source = r#"
+    func scale(v: int32): int32 {
+        let a = v * 2
+        let b = a + 1
+        let c = b * 3
+        let d = c - v
+        let e = d + a
+        let f = e * b
+        let g = f - c
+        return g + d
+    }
+
+    func main() {
+        println(scale(4))
+    }
+    "#;
+
+    // Too large for O2, inlined at O3, never inlined when optimizing for size
+    assert!(callees(function(&optimize(source, OptLevel::O2), "main")).contains(&"scale".to_string()));
+    assert_eq!(callees(function(&optimize(source, OptLevel::O3), "main")), vec!["println"]);
+    assert!(callees(function(&optimize(source, OptLevel::Os), "main")).contains(&"scale".to_string()));
+}
+
+#[test]
+fn test_reassigned_parameters_stay_local_to_the_inlined_body() {
+    let source = r#"
+    func bump(n: int32): int32 {
+        n = n + 1
+        return n
+    }
+
+    func main() {
+        let n = 1
+        let m = bump(n)
+        println(n)
+        println(m)
+    }
+    "#;
+    let program = optimize(source, OptLevel::O2);
+    let main = function(&program, "main");
+    assert_eq!(callees(main), vec!["println", "println"]);
+
+    // The callee's `n` is a fresh register, so the caller's `n` is only written once
+    let caller_n = main.locals.iter().find(|local| local.name == "n").unwrap().register;
+    let writes = main
+        .basic_blocks
+        .iter()
+        .flat_map(|block| &block.instructions)
+        .filter(|instruction| instruction.result == Some(caller_n))
+        .count();
+    assert_eq!(writes, 1);
+}
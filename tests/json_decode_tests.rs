@@ -0,0 +1,188 @@
+//! Tests for typed JSON decoding and encoding in std/json
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_function, check_with_imports, parse_source, string};
+
+const IMPORTS: &str = "import { decode, encode } from \"std/json\"\n";
+
+const TYPES: &str = r#"
+    struct Address {
+        street: string
+        @json("zip_code") zip: string?
+    }
+
+    struct Item {
+        name: string
+        price: float64
+        quantity: uint8
+    }
+
+    struct Page<T> {
+        items: []T
+        next: Option<int32>
+    }
+
+    struct User {
+        @json("user_id")
+        id: int64
+        name: string
+        tags: []string
+        address: Address
+        scores: map[string]int32
+    }
+"#;
+
+/// Helper function to type check source code that imports std/json and declares the test types
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(&format!("{}{}", IMPORTS, TYPES), source)
+}
+
+/// Run `source` and call its function `name` with one string argument
+fn call(source: &str, name: &str, arg: &str) -> RuntimeValue {
+    call_function(&check_source(source).unwrap(), name, &[string(arg)]).unwrap()
+}
+
+const USER: &str = r#"{"user_id": 7, "name": "Ada", "tags": ["admin"], "address": {"street": "Main St", "zip_code": "12345"}, "scores": {"go": 3}, "extra": true}"#;
+
+#[test]
+fn test_decode_into_structs() {
+    let source = r#"
+        func describe(text: string): string {
+            let user: User = decode<User>(text).unwrap()
+            let zip: string? = user.address.zip
+            return user.name + " " + user.tags[0] + " " + user.address.street + " " + zip
+        }
+
+        func id(text: string): int64 {
+            let user: User = decode<User>(text).unwrap()
+            return user.id
+        }
+
+        func score(text: string): int32 {
+            let user: User = decode<User>(text).unwrap()
+            return user.scores["go"]
+        }
+    "#;
+    assert_eq!(call(source, "describe", USER), string("Ada admin Main St 12345"));
+    assert_eq!(call(source, "id", USER), RuntimeValue::Int64(7));
+    assert_eq!(call(source, "score", USER), RuntimeValue::Int32(3));
+
+    // Optional fields may be missing, and generic structs decode their type arguments
+    let source = r#"
+        func count(text: string): int32 {
+            let page: Page<Item> = decode<Page<Item>>(text).unwrap()
+            return len(page.items)
+        }
+    "#;
+    let text = r#"{"items": [{"name": "pen", "price": 1.5, "quantity": 2}, {"name": "ink", "price": 3, "quantity": 1}]}"#;
+    assert_eq!(call(source, "count", text), RuntimeValue::Int32(2));
+}
+
+#[test]
+fn test_decode_errors_name_the_path() {
+    let source = r#"
+        func attempt(text: string): string {
+            let result = decode<Page<Item>>(text)
+            return result.error
+        }
+    "#;
+    let cases = [
+        (
+            r#"{"items": [{"name": "pen", "price": 1, "quantity": 2}, {"name": "ink", "price": "3", "quantity": 1}]}"#,
+            "$.items[1].price: expected float64, got string",
+        ),
+        (
+            r#"{"items": [{"name": "pen", "price": 1, "quantity": 300}]}"#,
+            "$.items[0].quantity: expected uint8, got 300, which is out of range",
+        ),
+        (r#"{"items": [{"price": 1, "quantity": 2}]}"#, "$.items[0].name: missing required field"),
+        (r#"{"items": [], "next": "x"}"#, "$.next: expected int32, got string"),
+        (r#"[1, 2]"#, "$: expected Page<Item>, got array"),
+    ];
+    for (text, expected) in cases {
+        assert_eq!(call(source, "attempt", text), string(expected), "{}", text);
+    }
+
+    let error = call(source, "attempt", "{\"items\": [");
+    assert!(matches!(&error, RuntimeValue::String(message) if message.starts_with("JSON Parse Error")), "{:?}", error);
+
+    // Renamed fields are read from their JSON key only
+    let source = r#"
+        func attempt(text: string): string {
+            return decode<User>(text).error
+        }
+    "#;
+    let text = USER.replace("user_id", "id");
+    assert_eq!(call(source, "attempt", &text), string("$.user_id: missing required field"));
+}
+
+#[test]
+fn test_encode_uses_json_keys() {
+    let source = r#"
+        func roundTrip(text: string): string {
+            let user: User = decode<User>(text).unwrap()
+            return encode(user).unwrap()
+        }
+    "#;
+    let encoded = r#"{"address":{"street":"Main St","zip_code":"12345"},"name":"Ada","scores":{"go":3},"tags":["admin"],"user_id":7}"#;
+    assert_eq!(call(source, "roundTrip", USER), string(encoded));
+
+    let source = r#"
+        func encodePage(text: string): string {
+            let page: Page<Item> = decode<Page<Item>>(text).unwrap()
+            return encode(page).unwrap()
+        }
+    "#;
+    assert_eq!(
+        call(source, "encodePage", r#"{"items": [], "next": 4}"#),
+        string(r#"{"items":[],"next":4}"#)
+    );
+    assert_eq!(call(source, "encodePage", r#"{"items": []}"#), string(r#"{"items":[],"next":null}"#));
+}
+
+#[test]
+fn test_checker_errors() {
+    let cases = [
+        (
+            "struct A {\n    @yaml(\"a\") a: int32\n}\n",
+            "Unknown attribute '@yaml' on field 'a'",
+        ),
+        ("struct A {\n    @json(1) a: int32\n}\n", "Attribute '@json' expects one non-empty string literal"),
+        ("struct A {\n    @json a: int32\n}\n", "Attribute '@json' expects one non-empty string literal"),
+        (
+            "struct A {\n    @json(\"b\") a: int32\n    b: int32\n}\n",
+            "Fields 'a' and 'b' both use the JSON key 'b'",
+        ),
+        (
+            "func f(): any {\n    return decode(\"1\")\n}\n",
+            "Function 'decode' expects 1 type argument, the type to decode into, got 0",
+        ),
+        (
+            "func f(): any {\n    return decode<User>(1)\n}\n",
+            "Argument 1 to function 'decode': expected string, got int32",
+        ),
+        (
+            "func f(): int32 {\n    return decode<User>(\"{}\").unwrap()\n}\n",
+            "Cannot return struct User from function expecting int32",
+        ),
+    ];
+    for (source, expected) in cases {
+        let error = check_source(source).unwrap_err();
+        assert!(error.to_string().contains(expected), "{}: {}", source, error);
+    }
+
+    let error = check_source("struct A {\n    @json(\"x\")\n    func f() {}\n}\n").unwrap_err();
+    assert!(error.to_string().contains("Attribute '@json' must come before a field"), "{}", error);
+}
+
+#[test]
+fn test_attributes_are_printed() {
+    let source = "struct User {\n    @json(\"user_id\") id: int64\n}\n";
+    let program = parse_source(source).unwrap();
+    let printed = AstPrinter::new().print_program(&program);
+    assert!(printed.contains("@json(\"user_id\")"), "{}", printed);
+}
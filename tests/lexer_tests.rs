@@ -128,4 +128,87 @@ fn test_comments() {
     assert_eq!(non_newline_tokens[1].token_type, TokenType::Identifier);
     assert_eq!(non_newline_tokens[2].token_type, TokenType::Assign);
     assert_eq!(non_newline_tokens[3].token_type, TokenType::IntegerLiteral);
-}
\ No newline at end of file
+}
+#[test]
+fn test_raw_strings() {
+    let source = "r\"C:\\dir\\n\" r#\"say \"hi\"\"# r##\"a \"# b\"## r\"line1\nline2\" rest";
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize().unwrap();
+
+    let values: Vec<&Literal> = tokens
+        .iter()
+        .filter(|t| t.token_type == TokenType::StringLiteral)
+        .map(|t| t.literal.as_ref().unwrap())
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            &Literal::String("C:\\dir\\n".to_string()),
+            &Literal::String("say \"hi\"".to_string()),
+            &Literal::String("a \"# b".to_string()),
+            &Literal::String("line1\nline2".to_string()),
+        ]
+    );
+    assert_eq!(tokens[1].lexeme, "r#\"say \"hi\"\"#");
+
+    // Tokens after a multiline raw string are on the right line
+    let rest = tokens.iter().find(|t| t.lexeme == "rest").unwrap();
+    assert_eq!((rest.position.line, rest.position.column), (2, 8));
+
+    // `r` alone is still an identifier
+    let tokens = Lexer::new("r + r2").tokenize().unwrap();
+    assert_eq!(tokens[0].token_type, TokenType::Identifier);
+    assert_eq!(tokens[2].lexeme, "r2");
+
+    assert!(Lexer::new("r#\"never closed\"").tokenize().is_err());
+}
+
+#[test]
+fn test_byte_strings() {
+    let source = r##"b"hi\n\x00\xFF\"" br"\d+" br#"a"b"# b"##;
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize().unwrap();
+
+    assert_eq!(tokens[0].token_type, TokenType::ByteStringLiteral);
+    assert_eq!(tokens[0].literal, Some(Literal::ByteString(vec![b'h', b'i', b'\n', 0, 0xff, b'"'])));
+    assert_eq!(tokens[0].lexeme, r#"b"hi\n\x00\xFF\"""#);
+    assert_eq!(tokens[1].literal, Some(Literal::ByteString(b"\\d+".to_vec())));
+    assert_eq!(tokens[2].literal, Some(Literal::ByteString(b"a\"b".to_vec())));
+    assert_eq!(tokens[3].token_type, TokenType::Identifier);
+
+    for (source, message) in [
+        ("b\"caf\u{e9}\"", "Non-ASCII character 'é' in byte string"),
+        ("br\"\u{e9}\"", "Non-ASCII character 'é' in raw byte string"),
+        ("b\"\\x4\"", "Invalid byte escape"),
+        ("b\"open", "Unterminated byte string"),
+    ] {
+        let error = Lexer::new(source).tokenize().unwrap_err();
+        assert!(error.to_string().contains(message), "{}: {}", source, error);
+    }
+}
+
+#[test]
+fn test_interned_lexemes() {
+    let source = "let count = count + count\nlet total = count";
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize().unwrap();
+
+    // `let`, `count`, `=`, `+`, newline, `total` and the empty Eof lexeme
+    assert_eq!(lexer.string_table().len(), 7);
+    assert_eq!(tokens[1].lexeme, "count");
+    assert_eq!(tokens[1].lexeme, tokens[3].lexeme);
+    assert_eq!(tokens[1].lexeme.len(), 5);
+}
+
+#[test]
+fn test_token_stream() {
+    let source = "let x = 1 // comment\nx";
+    let streamed: Vec<_> = Lexer::new(source).map(|token| token.unwrap()).collect();
+    assert_eq!(streamed, Lexer::new(source).tokenize().unwrap());
+    assert_eq!(streamed.last().unwrap().token_type, TokenType::Eof);
+
+    // The stream stops after the first error
+    let mut lexer = Lexer::new("let s = \"open");
+    assert!(lexer.by_ref().any(|token| token.is_err()));
+    assert!(lexer.next().is_none());
+}
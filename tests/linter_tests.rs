@@ -1,10 +1,13 @@
 //! Unit tests for the Bulu code linter
 
+use bulu::ast::{Expression, LiteralValue};
+use bulu::build::{BuildOptions, Builder};
 use bulu::linter::{
-    create_default_lint_config, load_lint_config, validate_lint_config, LintLevel, LintOptions,
-    LintRules, Linter,
+    create_default_lint_config, load_lint_config, validate_lint_config, LintContext, LintLevel,
+    LintOptions, LintPolicy, LintRules, Linter, Rule, RuleRegistry, Suppressions,
 };
 use bulu::project::Project;
+use std::collections::HashSet;
 use std::fs;
 
 use tempfile::TempDir;
@@ -65,7 +68,6 @@ fn test_lint_rules_defaults() {
 #[test]
 fn test_lint_options_defaults() {
     let options = LintOptions::default();
-    assert!(!options.verbose);
     assert!(!options.fix);
     assert!(options.max_warnings.is_none());
 }
@@ -246,7 +248,7 @@ struct bad_struct_name {  // Should be PascalCase
 fn test_detect_missing_docs() {
     let (_temp_dir, project) = create_test_project();
     let content = r#"
-func undocumented_function() {
+export func undocumented_function() {
     // This function has no documentation
 }
 
@@ -269,6 +271,54 @@ func documented_function() {
     assert!(doc_issues[0].message.contains("undocumented_function"));
 }
 
+#[test]
+fn test_missing_docs_only_flags_exported_items() {
+    let (_temp_dir, project) = create_test_project();
+    let content = r#"
+/**
+ * A point in the plane
+ */
+export struct Point {
+    x: int32
+
+    func norm(): int32 {
+        return this.x
+    }
+}
+
+export struct Size {
+    width: int32
+}
+
+// A line comment is not documentation
+export const ORIGIN = 0
+
+func helper() {
+}
+"#;
+    let mut options = LintOptions::default();
+    options.rules.missing_docs = LintLevel::Warn;
+    let linter = Linter::new(project.clone(), options);
+
+    let test_file = project.root.join("src").join("shapes.bu");
+    fs::write(&test_file, content).expect("Failed to write test file");
+    let (issues, _) = linter.lint_file(&test_file).expect("Failed to lint file");
+
+    let mut messages: Vec<&str> = issues
+        .iter()
+        .filter(|i| i.rule == "missing-docs")
+        .map(|i| i.message.as_str())
+        .collect();
+    messages.sort();
+    assert_eq!(
+        messages,
+        vec![
+            "Constant 'ORIGIN' is exported but has no documentation",
+            "Struct 'Size' is exported but has no documentation",
+        ]
+    );
+}
+
 #[test]
 fn test_detect_high_complexity() {
     let (_temp_dir, project) = create_test_project();
@@ -426,3 +476,350 @@ fn test_custom_max_line_length() {
     assert!(!long_line_issues.is_empty());
     assert!(long_line_issues[0].message.contains("50"));
 }
+
+#[test]
+fn test_builtin_rule_registry() {
+    let registry = RuleRegistry::with_builtin_rules();
+    let ids: Vec<&str> = registry.rules().map(|rule| rule.id()).collect();
+    let unique: HashSet<&str> = ids.iter().copied().collect();
+    assert_eq!(ids.len(), unique.len());
+
+    for id in ["unused-variable", "long-line", "naming-convention", "security-sql-injection"] {
+        assert!(registry.get(id).is_some(), "missing rule {}", id);
+    }
+
+    let rules = LintRules::default();
+    let long_line = registry.get("long-line").unwrap();
+    assert_eq!(registry.level_for(long_line, &rules), LintLevel::Warn);
+    assert!(!long_line.description().is_empty());
+}
+
+#[test]
+fn test_per_rule_severity_overrides() {
+    let (_temp_dir, project) = create_test_project();
+    let content = r#"
+func security_issues() {
+    let password = "hardcoded_secret"
+    let query = "SELECT * FROM users WHERE id = " + user_id
+}
+"#;
+    let mut options = LintOptions::default();
+    options
+        .rules
+        .overrides
+        .insert("security-hardcoded-secret".to_string(), LintLevel::Allow);
+    options
+        .rules
+        .overrides
+        .insert("unused-variable".to_string(), LintLevel::Error);
+    let linter = Linter::new(project.clone(), options);
+
+    let test_file = project.root.join("src").join("test.bu");
+    fs::write(&test_file, content).expect("Failed to write test file");
+    let (issues, _) = linter.lint_file(&test_file).expect("Failed to lint file");
+
+    // The other security rule keeps its category level
+    assert!(!issues.iter().any(|i| i.rule == "security-hardcoded-secret"));
+    assert!(issues
+        .iter()
+        .any(|i| i.rule == "security-sql-injection" && i.level == LintLevel::Error));
+    assert!(issues
+        .iter()
+        .filter(|i| i.rule == "unused-variable")
+        .all(|i| i.level == LintLevel::Error));
+}
+
+#[test]
+fn test_inline_suppression_comments() {
+    let (_temp_dir, project) = create_test_project();
+    let content = r#"
+func test() {
+    let ignored = 1 // bulu-lint: allow(unused-variable)
+    // bulu-lint: allow(unused-variable, long-line)
+    let also_ignored = 2
+    let reported = 3
+}
+"#;
+    let (linter, test_file) = create_linter_and_file(&project, content);
+    let (issues, _) = linter.lint_file(&test_file).expect("Failed to lint file");
+
+    let unused: Vec<_> = issues
+        .iter()
+        .filter(|i| i.rule == "unused-variable")
+        .collect();
+    assert_eq!(unused.len(), 1);
+    assert!(unused[0].message.contains("reported"));
+}
+
+#[test]
+fn test_suppression_comments_ignore_slashes_in_strings() {
+    let (_temp_dir, project) = create_test_project();
+    let content = r#"
+func test() {
+    let url = "http://x" // bulu-lint: allow(unused-variable)
+    let quoted = "// bulu-lint: allow(unused-variable)"
+    let raw = r"a // b"
+    let after_raw = 1 // bulu-lint: allow(unused-variable)
+}
+"#;
+    let (linter, test_file) = create_linter_and_file(&project, content);
+    let (issues, _) = linter.lint_file(&test_file).expect("Failed to lint file");
+
+    let unused: Vec<_> = issues
+        .iter()
+        .filter(|i| i.rule == "unused-variable")
+        .map(|i| i.message.as_str())
+        .collect();
+    assert_eq!(unused.len(), 2, "{:?}", unused);
+    assert!(unused.iter().any(|message| message.contains("quoted")));
+    assert!(unused.iter().any(|message| message.contains("raw")));
+
+    let suppressions = Suppressions::parse("let u = \"http://x\" // bulu-lint: allow(long-line)\n");
+    assert!(suppressions.is_suppressed(1, "long-line"));
+    assert!(!suppressions.is_suppressed(2, "long-line"));
+}
+
+#[test]
+fn test_lint_section_in_lang_toml() {
+    let (temp_dir, _project) = create_test_project();
+    let project_path = temp_dir.path();
+
+    fs::write(
+        project_path.join(".langlint.toml"),
+        "max_line_length = 80\n\n[rules]\nlong-line = \"error\"\nmissing-docs = \"warn\"\n",
+    )
+    .expect("Failed to write config");
+
+    let manifest = fs::read_to_string(project_path.join("lang.toml")).unwrap();
+    fs::write(
+        project_path.join("lang.toml"),
+        format!(
+            "{}\n[lint]\nmax_complexity = 6\n\n[lint.rules]\nlong-line = \"allow\"\n",
+            manifest
+        ),
+    )
+    .expect("Failed to write lang.toml");
+
+    // The project still loads with a [lint] section present
+    assert!(Project::load_from_path(project_path).is_ok());
+
+    let options = load_lint_config(project_path).expect("Failed to load config");
+    assert_eq!(options.rules.max_line_length, 80);
+    assert_eq!(options.rules.max_complexity, 6);
+    assert_eq!(options.rules.overrides.get("long-line"), Some(&LintLevel::Allow));
+    assert_eq!(options.rules.overrides.get("missing-docs"), Some(&LintLevel::Warn));
+}
+
+/// Flags integer literals larger than 1000
+struct MagicNumberRule;
+
+impl Rule for MagicNumberRule {
+    fn id(&self) -> &'static str {
+        "magic-number"
+    }
+
+    fn description(&self) -> &'static str {
+        "Large unnamed integer literals"
+    }
+
+    fn default_level(&self, _config: &LintRules) -> LintLevel {
+        LintLevel::Warn
+    }
+
+    fn check_expression(&self, expression: &Expression, ctx: &mut LintContext) {
+        if let Expression::Literal(literal) = expression {
+            if let LiteralValue::Integer(value) = literal.value {
+                if value > 1000 {
+                    ctx.report(
+                        literal.position.line,
+                        literal.position.column,
+                        format!("Magic number {}", value),
+                        None,
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_custom_rule_with_ast_hooks() {
+    let (_temp_dir, project) = create_test_project();
+    let content = r#"
+func timeout(): int32 {
+    if ready() {
+        return 30000
+    }
+    return 5
+}
+"#;
+    let (mut linter, test_file) = create_linter_and_file(&project, content);
+    linter.register_rule(Box::new(MagicNumberRule));
+    let (issues, _) = linter.lint_file(&test_file).expect("Failed to lint file");
+
+    let magic: Vec<_> = issues.iter().filter(|i| i.rule == "magic-number").collect();
+    assert_eq!(magic.len(), 1);
+    assert_eq!(magic[0].line, 4);
+    assert_eq!(magic[0].level, LintLevel::Warn);
+}
+
+#[test]
+fn test_flow_aware_unused_detection() {
+    let (_temp_dir, project) = create_test_project();
+    let content = r#"import { max, min } from "std/math"
+import "std/io" as io
+
+struct Unused {
+    value: int32
+}
+
+struct Point {
+    x: int32
+}
+
+export func area(p: Point): int32 {
+    return p.x
+}
+
+func helper(n: int32): int32 {
+    return helper(n - 1)
+}
+
+func main() {
+    let total = 0
+    total = max(1, 2)
+    let declared = 5
+    let io = 3
+    println(io)
+}
+"#;
+    let (linter, test_file) = create_linter_and_file(&project, content);
+    let (issues, _) = linter.lint_file(&test_file).expect("Failed to lint file");
+
+    let messages: HashSet<String> = issues
+        .iter()
+        .filter(|i| matches!(i.rule.as_str(), "unused-import" | "unused-variable" | "dead-code"))
+        .map(|i| format!("{}:{}", i.line, i.message))
+        .collect();
+
+    let expected: HashSet<String> = [
+        "1:Import 'min' is not used",
+        "2:Import 'io' is not used",
+        "4:Struct 'Unused' is never used",
+        "16:Function 'helper' is never used",
+        "21:Variable 'total' is assigned but never read",
+        "23:Variable 'declared' is declared but never used",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    assert_eq!(messages, expected);
+
+    let variable = issues
+        .iter()
+        .find(|i| i.message.contains("'declared'"))
+        .unwrap();
+    assert_eq!(variable.column, 9);
+    assert!(variable.fix.is_some());
+}
+
+#[test]
+fn test_unused_code_fixes_are_applied() {
+    let (_temp_dir, project) = create_test_project();
+    let content = r#"import { max, min } from "std/math"
+import "std/io" as io
+
+func helper(): int32 {
+    return 1
+}
+
+func main() {
+    let declared = max(1, 2)
+}
+"#;
+    let test_file = project.root.join("src").join("test.bu");
+    fs::write(&test_file, content).expect("Failed to write test file");
+    let options = LintOptions {
+        fix: true,
+        ..LintOptions::default()
+    };
+    let linter = Linter::new(project.clone(), options);
+
+    let (issues, fixed) = linter.lint_file(&test_file).expect("Failed to lint file");
+    assert_eq!(fixed, 4);
+    assert!(issues.iter().all(|i| i.fix.is_none()));
+
+    let fixed_content = fs::read_to_string(&test_file).unwrap();
+    assert_eq!(
+        fixed_content,
+        r#"import { max } from "std/math"
+
+func main() {
+    let _declared = max(1, 2)
+}
+"#
+    );
+}
+
+#[test]
+fn test_lint_policy_levels() {
+    let policy = LintPolicy::from_deny(["unused_import"]);
+    assert_eq!(policy.deny, vec!["unused-import".to_string()]);
+    assert_eq!(policy.level("unused-import", LintLevel::Allow), LintLevel::Error);
+    assert_eq!(policy.level("long-line", LintLevel::Warn), LintLevel::Warn);
+
+    let policy = LintPolicy::from_deny(["warnings"]);
+    assert!(policy.deny_warnings);
+    assert_eq!(policy.level("long-line", LintLevel::Warn), LintLevel::Error);
+    assert_eq!(policy.level("missing-docs", LintLevel::Allow), LintLevel::Allow);
+
+    let error = LintPolicy::from_deny(["unused-imports"])
+        .validate(&RuleRegistry::with_builtin_rules())
+        .unwrap_err();
+    assert!(error.to_string().contains("Unknown lint 'unused-imports'"), "{}", error);
+}
+
+#[test]
+fn test_lint_policy_counts() {
+    let (_temp_dir, project) = create_test_project();
+    let content = r#"import { max } from "std/math"
+
+func main() {
+    let ignored = 1 // bulu-lint: allow(unused-variable)
+    let unused = 2
+}
+
+export func helper() {
+}
+"#;
+    fs::write(project.root.join("src").join("main.bu"), content).unwrap();
+    let options = LintOptions {
+        policy: LintPolicy::from_deny(["unused-import"]),
+        ..LintOptions::default()
+    };
+    let result = Linter::new(project.clone(), options).lint_project().unwrap();
+
+    // The import is denied, the variable still warns, one variable is
+    // suppressed and helper has no doc comment, which is allowed by default
+    assert_eq!((result.errors, result.denied, result.warnings), (1, 1, 1));
+    assert_eq!((result.allowed, result.suppressed), (1, 1));
+    let denied = result.issues.iter().find(|issue| issue.level == LintLevel::Error).unwrap();
+    assert_eq!(denied.rule, "unused-import");
+}
+
+#[test]
+fn test_denied_lints_fail_the_build() {
+    let (_temp_dir, project) = create_test_project();
+    let content = "func main() {\n    let unused = 2\n}\n";
+    fs::write(project.root.join("src").join("main.bu"), content).unwrap();
+
+    let options = BuildOptions {
+        lint_policy: LintPolicy::from_deny(["warnings"]),
+        ..BuildOptions::default()
+    };
+    let result = Builder::new(project.clone(), options).build().unwrap();
+    assert!(!result.success);
+    assert_eq!(result.errors.len(), 1);
+    assert!(result.errors[0].ends_with("[unused-variable]"), "{:?}", result.errors);
+    assert!(result.output_path.is_none());
+}
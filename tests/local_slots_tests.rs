@@ -0,0 +1,168 @@
+//! Tests for slot resolution of function locals in the AST interpreter
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::lexer::token::Position;
+use bulu::lexer::Lexer;
+use bulu::parser::Parser;
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use bulu::runtime::locals::{resolve_closure_locals, resolve_locals, LocalSlot, LocalSlots};
+use bulu::types::primitive::RuntimeValue;
+
+fn parse(source: &str) -> Program {
+    let tokens = Lexer::new(source).tokenize().unwrap();
+    Parser::new(tokens).parse().unwrap()
+}
+
+fn function<'a>(program: &'a Program, name: &str) -> &'a FunctionDecl {
+    program
+        .statements
+        .iter()
+        .find_map(|statement| match statement {
+            Statement::FunctionDecl(decl) if decl.name == name => Some(decl),
+            _ => None,
+        })
+        .expect("function exists")
+}
+
+/// Slot of the identifier at 1-based `line` and `column` of the source
+fn slot_at(locals: &LocalSlots, line: usize, column: usize) -> Option<LocalSlot> {
+    locals.get(Position::new(line, column, 0))
+}
+
+/// Helper function to run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    let program = parse(source);
+    let mut interpreter = AstInterpreter::new();
+    interpreter.execute_program(&program)?;
+    let main_func = interpreter.get_function_definition("main").expect("main should be defined");
+    interpreter.call_user_function(&main_func, &[])
+}
+
+#[test]
+fn test_resolve_params_blocks_and_shadowing() {
+    let source = "func f(a: int32, b: int32): int32 {
+let c = a + b
+{
+let a = c
+return a + b
+}
+}";
+    let program = parse(source);
+    let locals = resolve_locals(function(&program, "f"));
+
+    // `a` and `b` are the parameters, two scopes out from the body
+    assert_eq!(slot_at(&locals, 2, 9), Some(LocalSlot { depth: 1, slot: 0 }));
+    assert_eq!(slot_at(&locals, 2, 13), Some(LocalSlot { depth: 1, slot: 1 }));
+    // Inside the block, `c` is in the body scope and `a` is shadowed
+    assert_eq!(slot_at(&locals, 4, 9), Some(LocalSlot { depth: 1, slot: 0 }));
+    assert_eq!(slot_at(&locals, 5, 8), Some(LocalSlot { depth: 0, slot: 0 }));
+    assert_eq!(slot_at(&locals, 5, 12), Some(LocalSlot { depth: 2, slot: 1 }));
+}
+
+#[test]
+fn test_unpredictable_scopes_fall_back_to_names() {
+    let source = "func f(n: int32) {
+let i = 0
+while i < n {
+i = i + 1
+let last = i
+}
+println(i)
+let g = () => n
+println(global)
+}";
+    let program = parse(source);
+    let locals = resolve_locals(function(&program, "f"));
+
+    // Before the loop declares `last` the layout is known
+    assert_eq!(slot_at(&locals, 3, 7), Some(LocalSlot { depth: 0, slot: 0 }));
+    assert_eq!(slot_at(&locals, 4, 5), Some(LocalSlot { depth: 0, slot: 0 }));
+    // After it, whether `last` exists depends on how often the loop ran
+    assert_eq!(slot_at(&locals, 7, 9), None);
+    // Lambda bodies and globals are looked up by name
+    assert_eq!(slot_at(&locals, 8, 15), None);
+    assert_eq!(slot_at(&locals, 9, 9), None);
+}
+
+#[test]
+fn test_functions_run_with_resolved_locals() {
+    let source = r#"
+    func fib(n: int32): int32 {
+        let a = 0
+        let b = 1
+        let i = 0
+        while i < n {
+            let next = a + b
+            a = b
+            b = next
+            i = i + 1
+        }
+        return a
+    }
+
+    func shadow(x: int32): int32 {
+        let total = x
+        for x in [10, 20] {
+            let y = x
+            total = total + y
+        }
+        return total + x
+    }
+
+    func main(): int32 {
+        return fib(10) * 1000 + shadow(1)
+    }
+    "#;
+    assert_eq!(run_main(source).unwrap(), RuntimeValue::Integer(55032));
+}
+
+#[test]
+fn test_closure_captures_come_before_params() {
+    let source = "func f(delta: int32, total: int32): int32 {
+let next = total + delta
+return next + offset
+}";
+    let program = parse(source);
+    let captures = vec!["offset".to_string(), "total".to_string()];
+    let locals = resolve_closure_locals(function(&program, "f"), &captures);
+
+    // A parameter named like a capture takes over its slot
+    assert_eq!(slot_at(&locals, 2, 12), Some(LocalSlot { depth: 1, slot: 1 }));
+    assert_eq!(slot_at(&locals, 2, 20), Some(LocalSlot { depth: 1, slot: 2 }));
+    assert_eq!(slot_at(&locals, 3, 15), Some(LocalSlot { depth: 1, slot: 0 }));
+    assert_eq!(slot_at(&locals, 3, 8), Some(LocalSlot { depth: 0, slot: 0 }));
+}
+
+#[test]
+fn test_closures_run_with_resolved_locals() {
+    let source = r#"
+    func counter(start: int32): any {
+        let count = start
+        return (delta: int32) => {
+            let before = count
+            count = count + delta
+            return before
+        }
+    }
+
+    func main(): any {
+        let a = counter(10)
+        let b = counter(100)
+        a(1)
+        a(2)
+        let scale = 3
+        let scaled = (x: int32) => x * scale
+        return (a(0), b(5), b(0), scaled(7))
+    }
+    "#;
+    assert_eq!(
+        run_main(source).unwrap(),
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::Integer(13),
+            RuntimeValue::Integer(100),
+            RuntimeValue::Integer(105),
+            RuntimeValue::Integer(21),
+        ])
+    );
+}
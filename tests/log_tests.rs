@@ -0,0 +1,106 @@
+//! Tests for the leveled loggers, fields and sinks of std/log
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_function, check_with_imports, string};
+use std::fs;
+use tempfile::TempDir;
+
+const IMPORTS: &str = "import { debug, info, warn, error, logger, setLevel, level, setSink, addSink, fileSink } from \"std/log\"\n";
+
+/// Helper function to type check source code that imports std/log
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Run `source` and call its function `name` with one string argument
+fn call(source: &str, name: &str, arg: &str) -> RuntimeValue {
+    call_function(&check_source(source).unwrap(), name, &[string(arg)]).unwrap()
+}
+
+/// The lines of a log file without their timestamps
+fn records(path: &std::path::Path) -> Vec<String> {
+    fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| match line.split_once("\"level\"") {
+            Some((_, rest)) => format!("{{\"level\"{}", rest),
+            None => line.split_once(' ').unwrap().1.to_string(),
+        })
+        .collect()
+}
+
+// The logger configuration is global, so one test exercises all of it
+#[test]
+fn test_levels_fields_and_sinks() {
+    let temp_dir = TempDir::new().unwrap();
+    let text_path = temp_dir.path().join("app.log");
+    let json_path = temp_dir.path().join("app.jsonl");
+    let source = r#"
+        func configure(dir: string): string {
+            setSink(fileSink(dir + "/app.log"))
+            addSink(fileSink(dir + "/app.jsonl", 0, 0).json().level("warn"))
+            setLevel("info")
+            return level()
+        }
+
+        func emit(text: string): any {
+            debug("dropped")
+            info("server started", {"port": 8080})
+            let http = logger("http").with({"id": 7})
+            http.warn("slow request", {"path": text})
+            setLevel("error")
+            warn("dropped too")
+            error("failed")
+            return http.name()
+        }
+    "#;
+    let dir = temp_dir.path().to_str().unwrap();
+    assert_eq!(call(source, "configure", dir), RuntimeValue::String("info".to_string()));
+    assert_eq!(call(source, "emit", "/a b"), RuntimeValue::String("http".to_string()));
+
+    assert_eq!(
+        records(&text_path),
+        vec![
+            "INFO  server started port=8080",
+            "WARN  http: slow request id=7 path=\"/a b\"",
+            "ERROR failed",
+        ]
+    );
+    assert_eq!(
+        records(&json_path),
+        vec![
+            r#"{"level":"warn","logger":"http","message":"slow request","id":7,"path":"/a b"}"#,
+            r#"{"level":"error","logger":"","message":"failed"}"#,
+        ]
+    );
+}
+
+#[test]
+fn test_checker_errors() {
+    let cases = [
+        (
+            "func f() {\n    setLevel(\"verbose\")\n}\n",
+            "unknown log level \"verbose\", expected debug, info, warn, error or off in call to 'setLevel'",
+        ),
+        (
+            "func f() {\n    info(42)\n}\n",
+            "Argument 1 to function 'info': expected string, got int32",
+        ),
+        (
+            "func f() {\n    addSink(\"stderr\")\n}\n",
+            "Argument 1 to function 'addSink': expected LogSink, got string",
+        ),
+        (
+            "func f() {\n    fileSink()\n}\n",
+            "Function 'fileSink' expects 1 to 3 arguments, got 0",
+        ),
+    ];
+    for (source, expected) in cases {
+        let error = check_source(source).unwrap_err();
+        assert!(error.to_string().contains(expected), "{}: {}", source, error);
+    }
+}
@@ -0,0 +1,61 @@
+//! Tests for verbosity flags and log filtering
+
+use bulu::formatter::create_default_format_config;
+use bulu::logging::{self, Verbosity};
+use tempfile::TempDir;
+use tracing::level_filters::LevelFilter;
+
+#[test]
+fn test_verbosity_from_flags() {
+    assert_eq!(Verbosity::from_flags(false, 0), Verbosity::Normal);
+    assert_eq!(Verbosity::from_flags(false, 1), Verbosity::Verbose);
+    assert_eq!(Verbosity::from_flags(false, 2), Verbosity::Trace);
+    assert_eq!(Verbosity::from_flags(false, 5), Verbosity::Trace);
+    // Quiet wins over any number of -v
+    assert_eq!(Verbosity::from_flags(true, 2), Verbosity::Quiet);
+
+    assert_eq!(Verbosity::Quiet.level(), LevelFilter::WARN);
+    assert_eq!(Verbosity::default().level(), LevelFilter::INFO);
+    assert_eq!(Verbosity::Verbose.level(), LevelFilter::DEBUG);
+    assert_eq!(Verbosity::Trace.level(), LevelFilter::TRACE);
+}
+
+#[test]
+fn test_log_env_directives() {
+    let filter = logging::filter(Verbosity::Normal, None).unwrap();
+    assert_eq!(filter.max_level_hint(), Some(LevelFilter::INFO));
+
+    // A module directive raises the level for that module only
+    let filter = logging::filter(Verbosity::Quiet, Some("bulu::package=trace")).unwrap();
+    assert_eq!(filter.max_level_hint(), Some(LevelFilter::TRACE));
+    assert!(filter.to_string().contains("warn"), "{}", filter);
+
+    // A bare level replaces the one from the flags
+    let filter = logging::filter(Verbosity::Trace, Some("error")).unwrap();
+    assert_eq!(filter.max_level_hint(), Some(LevelFilter::ERROR));
+
+    let error = logging::filter(Verbosity::Normal, Some("bulu=loud")).unwrap_err();
+    assert!(error.contains("Invalid BULU_LOG value 'bulu=loud'"), "{}", error);
+}
+
+#[test]
+fn test_progress_messages_follow_verbosity() {
+    let temp_dir = TempDir::new().unwrap();
+    let (result, output) = logging::capture(Verbosity::Normal, || create_default_format_config(temp_dir.path()));
+    result.unwrap();
+    assert_eq!(output, "Created default .langfmt.toml configuration file\n");
+
+    let temp_dir = TempDir::new().unwrap();
+    let (result, output) = logging::capture(Verbosity::Quiet, || create_default_format_config(temp_dir.path()));
+    result.unwrap();
+    assert_eq!(output, "");
+
+    let (_, output) = logging::capture(Verbosity::Normal, || {
+        tracing::warn!("disk almost full");
+        tracing::debug!("checked 3 files");
+    });
+    assert_eq!(output, "disk almost full\n");
+
+    let (_, output) = logging::capture(Verbosity::Verbose, || tracing::debug!(files = 3, "checked"));
+    assert_eq!(output, "checked files=3\n");
+}
@@ -224,3 +224,328 @@ fn test_lsp_capabilities() {
         assert!(!capability.is_empty());
     }
 }
+
+#[test]
+fn test_undefined_symbol_name_extraction() {
+    use bulu::lsp::diagnostics::DiagnosticsProvider;
+
+    assert_eq!(
+        DiagnosticsProvider::undefined_symbol_name("Undefined identifier 'sleep'"),
+        Some("sleep")
+    );
+    assert_eq!(
+        DiagnosticsProvider::undefined_symbol_name("Undefined function 'helper'"),
+        Some("helper")
+    );
+    assert_eq!(
+        DiagnosticsProvider::undefined_symbol_name(
+            "'len' is a builtin, and the prelude is disabled; import it from \"std/builtin\""
+        ),
+        Some("len")
+    );
+    assert_eq!(DiagnosticsProvider::undefined_symbol_name("Type mismatch"), None);
+}
+
+#[test]
+fn test_auto_import_code_actions() {
+    use bulu::lsp::backend::DocumentState;
+    use bulu::lsp::diagnostics::DiagnosticsProvider;
+    use dashmap::DashMap;
+    use std::fs;
+    use std::sync::Arc;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::create_dir_all(project_dir.join("src/util")).unwrap();
+    fs::write(
+        project_dir.join("lang.toml"),
+        "[package]\nname = \"auto-import\"\nversion = \"0.1.0\"\nauthors = []\n",
+    )
+    .unwrap();
+    fs::write(
+        project_dir.join("src/util/strings.bu"),
+        "export func shout(s: string): string {\n    return s\n}\n",
+    )
+    .unwrap();
+
+    let main_path = project_dir.join("src/main.bu");
+    let text = "import { args } from \"std/os\"\n\nfunc main() {\n    shout(\"hi\")\n    sleep(10)\n}\n";
+    fs::write(&main_path, text).unwrap();
+    let uri = Url::from_file_path(&main_path).unwrap();
+
+    let documents = Arc::new(DashMap::new());
+    documents.insert(
+        uri.to_string(),
+        DocumentState { uri: uri.clone(), text: text.to_string(), version: 1 },
+    );
+    let provider = DiagnosticsProvider::new(documents);
+
+    let diagnostic = |message: &str| Diagnostic {
+        range: Range::default(),
+        severity: Some(DiagnosticSeverity::ERROR),
+        code: None,
+        code_description: None,
+        source: Some("bulu".to_string()),
+        message: message.to_string(),
+        related_information: None,
+        tags: None,
+        data: None,
+    };
+
+    let actions = provider.import_code_actions(
+        &uri,
+        text,
+        &[
+            diagnostic("Undefined function 'shout'"),
+            diagnostic("Undefined identifier 'sleep'"),
+            diagnostic("Type mismatch"),
+        ],
+    );
+    assert_eq!(actions.len(), 2);
+
+    let new_texts: Vec<(String, u32)> = actions
+        .iter()
+        .map(|action| {
+            let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+            (edits[0].new_text.clone(), edits[0].range.start.line)
+        })
+        .collect();
+    assert_eq!(new_texts[0], ("import { shout } from \"./util/strings\"\n".to_string(), 1));
+    assert_eq!(new_texts[1], ("import { sleep } from \"std/time\"\n".to_string(), 1));
+    assert_eq!(actions[0].is_preferred, Some(true));
+
+    // A builtin used without the prelude is imported from std/builtin first
+    let actions = provider.import_code_actions(
+        &uri,
+        text,
+        &[diagnostic("'sleep' is a builtin, and the prelude is disabled; import it from \"std/builtin\"")],
+    );
+    let titles: Vec<&str> = actions.iter().map(|action| action.title.as_str()).collect();
+    assert_eq!(
+        titles,
+        ["Import 'sleep' from \"std/builtin\"", "Import 'sleep' from \"std/time\""]
+    );
+}
+
+#[test]
+fn test_auto_import_extends_existing_import() {
+    use bulu::lsp::diagnostics::DiagnosticsProvider;
+    use dashmap::DashMap;
+    use std::sync::Arc;
+
+    let provider = DiagnosticsProvider::new(Arc::new(DashMap::new()));
+    let uri = Url::parse("file:///tmp/extend_import/main.bu").unwrap();
+    let text = "import { now } from \"std/time\"\n\nfunc main() {\n    sleep(10)\n}\n";
+    let diagnostic = Diagnostic {
+        message: "Undefined identifier 'sleep'".to_string(),
+        ..Default::default()
+    };
+
+    let actions = provider.import_code_actions(&uri, text, &[diagnostic]);
+    let action = actions
+        .iter()
+        .find(|action| action.title == "Import 'sleep' from \"std/time\"")
+        .unwrap();
+    let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+    assert_eq!(edits.len(), 1);
+    assert_eq!(edits[0].new_text, ", sleep");
+    assert_eq!(edits[0].range.start, Position { line: 0, character: 12 });
+    assert_eq!(edits[0].range.end, edits[0].range.start);
+}
+
+#[test]
+fn test_import_candidates_follow_module_changes() {
+    use bulu::lsp::backend::DocumentState;
+    use bulu::lsp::diagnostics::DiagnosticsProvider;
+    use dashmap::DashMap;
+    use std::fs;
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::create_dir_all(project_dir.join("src")).unwrap();
+    fs::write(
+        project_dir.join("lang.toml"),
+        "[package]\nname = \"import-cache\"\nversion = \"0.1.0\"\nauthors = []\n",
+    )
+    .unwrap();
+    let helpers = project_dir.join("src/helpers.bu");
+    fs::write(&helpers, "export func first(): int32 {\n    return 1\n}\n").unwrap();
+    let uri = Url::from_file_path(project_dir.join("src/main.bu")).unwrap();
+
+    let documents = Arc::new(DashMap::new());
+    let provider = DiagnosticsProvider::new(documents.clone());
+    let modules = |name: &str| -> Vec<String> {
+        provider
+            .find_import_candidates(&uri, name)
+            .into_iter()
+            .map(|candidate| candidate.module_path)
+            .collect()
+    };
+    assert_eq!(modules("first"), ["./helpers"]);
+    assert!(modules("second").is_empty());
+
+    // A change on disk is picked up
+    fs::write(&helpers, "export func second(): int32 {\n    return 2\n}\n").unwrap();
+    fs::File::options()
+        .write(true)
+        .open(&helpers)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(60))
+        .unwrap();
+    assert!(modules("first").is_empty());
+    assert_eq!(modules("second"), ["./helpers"]);
+
+    // So is an edit to the open document
+    let helpers_uri = Url::from_file_path(&helpers).unwrap();
+    let open = |version: i32, text: &str| {
+        documents.insert(
+            helpers_uri.to_string(),
+            DocumentState { uri: helpers_uri.clone(), text: text.to_string(), version },
+        );
+    };
+    open(1, "export func third(): int32 {\n    return 3\n}\n");
+    assert_eq!(modules("third"), ["./helpers"]);
+    open(2, "func third(): int32 {\n    return 3\n}\n");
+    assert!(modules("third").is_empty());
+}
+
+#[test]
+fn test_lint_diagnostics_report_rule_ids() {
+    use bulu::lsp::diagnostics::DiagnosticsProvider;
+    use dashmap::DashMap;
+    use std::fs;
+    use std::sync::Arc;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::create_dir_all(project_dir.join("src")).unwrap();
+    fs::write(
+        project_dir.join("lang.toml"),
+        "[package]\nname = \"lint\"\nversion = \"0.1.0\"\nauthors = []\n\n[lint.rules]\nnaming-convention = \"error\"\n",
+    )
+    .unwrap();
+
+    let main_path = project_dir.join("src/main.bu");
+    let text = "export func Bad_name() {\n    let secret = \"hunter2\" // bulu-lint: allow(security-hardcoded-secret)\n    println(secret)\n}\n";
+    fs::write(&main_path, text).unwrap();
+    let uri = Url::from_file_path(&main_path).unwrap();
+
+    let provider = DiagnosticsProvider::new(Arc::new(DashMap::new()));
+    let diagnostics = provider.lint_diagnostics(&uri, text);
+
+    let codes: Vec<String> = diagnostics
+        .iter()
+        .filter_map(|d| match &d.code {
+            Some(NumberOrString::String(code)) => Some(code.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(codes, vec!["naming-convention".to_string()]);
+    assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(diagnostics[0].source.as_deref(), Some("bulu-lint"));
+    assert_eq!(diagnostics[0].range.start.line, 0);
+}
+
+#[test]
+fn test_range_formatting_edits() {
+    use bulu::lsp::formatting::FormattingProvider;
+
+    let text = "func main() {\nlet x=1\nlet y=2\n}\n";
+    let options = FormattingOptions {
+        tab_size: 2,
+        insert_spaces: true,
+        ..Default::default()
+    };
+    let range = Range {
+        start: Position { line: 1, character: 0 },
+        end: Position { line: 2, character: 0 },
+    };
+
+    let edits = FormattingProvider::range_edits(text, range, &options).unwrap();
+    assert_eq!(edits.len(), 1);
+    assert_eq!(edits[0].new_text, "  let x = 1");
+    assert_eq!(edits[0].range.start, Position { line: 1, character: 0 });
+    assert_eq!(edits[0].range.end, Position { line: 1, character: 7 });
+}
+
+#[test]
+fn test_lint_quick_fixes_for_unused_code() {
+    use bulu::lsp::diagnostics::DiagnosticsProvider;
+    use dashmap::DashMap;
+    use std::fs;
+    use std::sync::Arc;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::create_dir_all(project_dir.join("src")).unwrap();
+    fs::write(
+        project_dir.join("lang.toml"),
+        "[package]\nname = \"lint-fixes\"\nversion = \"0.1.0\"\nauthors = []\n",
+    )
+    .unwrap();
+
+    let main_path = project_dir.join("src/main.bu");
+    let text = "import { max, min } from \"std/math\"\n\nfunc main() {\n    let unused = max(1, 2)\n}\n";
+    fs::write(&main_path, text).unwrap();
+    let uri = Url::from_file_path(&main_path).unwrap();
+
+    let provider = DiagnosticsProvider::new(Arc::new(DashMap::new()));
+    let diagnostics = provider.lint_diagnostics(&uri, text);
+    let actions = provider.lint_code_actions(&uri, text, &diagnostics);
+
+    let edits: Vec<(String, TextEdit)> = actions
+        .iter()
+        .map(|action| {
+            assert_eq!(action.kind, Some(CodeActionKind::QUICKFIX));
+            let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+            (action.title.clone(), edits[0].clone())
+        })
+        .collect();
+    assert_eq!(edits.len(), 2);
+
+    let (_, import_edit) = edits
+        .iter()
+        .find(|(title, _)| title == "Remove the unused imported names")
+        .unwrap();
+    assert_eq!(import_edit.new_text, "import { max } from \"std/math\"");
+    assert_eq!(import_edit.range.start, Position { line: 0, character: 0 });
+    assert_eq!(import_edit.range.end, Position { line: 0, character: 35 });
+
+    let (_, rename_edit) = edits
+        .iter()
+        .find(|(title, _)| title == "Rename to '_unused'")
+        .unwrap();
+    assert_eq!(rename_edit.new_text, "_");
+    assert_eq!(rename_edit.range.start, Position { line: 3, character: 8 });
+
+    // Only the requested diagnostics get actions
+    let actions = provider.lint_code_actions(&uri, text, &diagnostics[..1]);
+    assert_eq!(actions.len(), 1);
+}
+
+#[test]
+fn test_doctest_diagnostics_on_save() {
+    use bulu::docs::doctest::DoctestRunner;
+    use bulu::lsp::diagnostics::DiagnosticsProvider;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("main.bu");
+    let text = "/**\n * Squares a number\n * @example\n * assert(square(3) == 6)\n */\nexport func square(a: int32): int32 {\n    return a * a\n}\n";
+    std::fs::write(&path, text).unwrap();
+    let uri = Url::from_file_path(&path).unwrap();
+
+    let runner = DoctestRunner::new();
+    let diagnostics = DiagnosticsProvider::doctest_diagnostics(&runner, &uri, text);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].source.as_deref(), Some("bulu-doctest"));
+    assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+    assert_eq!(diagnostics[0].range.start, Position { line: 5, character: 0 });
+    assert!(diagnostics[0].message.starts_with("Example of square failed"), "{}", diagnostics[0].message);
+
+    // Saving the fixed example clears the warning, on the same interpreter
+    let fixed = text.replace("== 6", "== 9");
+    assert!(DiagnosticsProvider::doctest_diagnostics(&runner, &uri, &fixed).is_empty());
+    assert_eq!(runner.pool().stats().reused, 1);
+}
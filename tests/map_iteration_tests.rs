@@ -0,0 +1,217 @@
+//! Tests for deterministic map iteration and the sorted map helpers
+
+mod common;
+
+use bulu::types::checker::TypeChecker;
+use bulu::types::primitive::{sorted_map_entries, RuntimeValue};
+use common::{call_main, parse_source, run_main};
+use std::collections::HashMap;
+
+#[test]
+fn test_sorted_map_entries_order() {
+    let mut map = HashMap::new();
+    for key in ["b", "10", "a", "2", "B"] {
+        map.insert(key.to_string(), RuntimeValue::String(key.to_string()));
+    }
+
+    let keys: Vec<&str> = sorted_map_entries(&map).into_iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(keys, vec!["2", "10", "B", "a", "b"]);
+
+    // Printing goes through the same ordering, so it is stable across runs
+    let printed = RuntimeValue::Map(map.into()).to_string();
+    assert_eq!(printed, "{2: 2, 10: 10, B: B, a: a, b: b}");
+}
+
+#[test]
+fn test_mixed_keys_put_integers_first() {
+    // Every insertion order gives the same result
+    for keys in [["2", "10", "1a"], ["10", "1a", "2"], ["1a", "2", "10"]] {
+        let mut map = HashMap::new();
+        for key in keys {
+            map.insert(key.to_string(), RuntimeValue::Null);
+        }
+        let sorted: Vec<&str> = sorted_map_entries(&map).into_iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(sorted, vec!["2", "10", "1a"]);
+    }
+
+    let mut map = HashMap::new();
+    for key in ["-3", "01", "1", "x", "", "9223372036854775808"] {
+        map.insert(key.to_string(), RuntimeValue::Null);
+    }
+    let sorted: Vec<&str> = sorted_map_entries(&map).into_iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(sorted, vec!["-3", "01", "1", "", "9223372036854775808", "x"]);
+}
+
+#[test]
+fn test_for_loop_visits_keys_in_order() {
+    let source = r#"
+    func main(): string {
+        let m = {"pear": "p", "apple": "a", "fig": "f"}
+        let order = ""
+        for key in m {
+            order = order + key + " "
+        }
+        for key, value in m {
+            order = order + key + "=" + value + " "
+        }
+        return order
+    }
+    "#;
+
+    assert_eq!(
+        run_main(source).unwrap(),
+        RuntimeValue::String("apple fig pear apple=a fig=f pear=p ".to_string())
+    );
+}
+
+#[test]
+fn test_sorted_keys_and_entries_sorted() {
+    let string = |s: &str| RuntimeValue::String(s.to_string());
+
+    let keys = run_main(
+        r#"
+    func main() {
+        let m = {"b": 2, "c": 3, "a": 1}
+        return m.sortedKeys()
+    }
+    "#,
+    )
+    .unwrap();
+    assert_eq!(
+        keys,
+        RuntimeValue::Array(vec![string("a"), string("b"), string("c")])
+    );
+
+    let entries = run_main(
+        r#"
+    func main() {
+        let m = {"b": "2", "a": "1"}
+        return m.entriesSorted()
+    }
+    "#,
+    )
+    .unwrap();
+    assert_eq!(
+        entries,
+        RuntimeValue::Array(vec![
+            RuntimeValue::Tuple(vec![string("a"), string("1")]),
+            RuntimeValue::Tuple(vec![string("b"), string("2")]),
+        ])
+    );
+}
+
+#[test]
+fn test_map_iteration_type_checking() {
+    let source = r#"
+    func main() {
+        let m = {2: "b", 1: "a"}
+        for key, value in m {
+            let n: int32 = key
+            let s: string = value
+        }
+        for key in m {
+            let n: int32 = key
+        }
+        let keys = m.sortedKeys()
+        let entries = m.entriesSorted()
+    }
+    "#;
+    let program = parse_source(source).unwrap();
+    assert!(TypeChecker::new().check(&program).is_ok());
+
+    let source = r#"
+    func main() {
+        let m = {"a": 1}
+        m.sortedValues()
+    }
+    "#;
+    let program = parse_source(source).unwrap();
+    let error = TypeChecker::new().check(&program).unwrap_err();
+    assert!(error.to_string().contains("Method 'sortedValues' not found on type"));
+}
+
+#[test]
+fn test_integer_keys_keep_their_type() {
+    let source = r#"
+    func main(): any {
+        let m: map[int32]string = {1: "a", 10: "b", 2: "c"}
+        let total: int32 = 0
+        for k in m {
+            let n: int32 = k + 1
+            total = total + n * 2
+        }
+        for k, v in m {
+            total = total + k
+        }
+        return (total, m.sortedKeys(), m.entriesSorted())
+    }
+    "#;
+
+    let string = |s: &str| RuntimeValue::String(s.to_string());
+    // (2 + 11 + 3) * 2 from the first loop, 1 + 10 + 2 from the second
+    assert_eq!(
+        run_main(source).unwrap(),
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::Integer(45),
+            RuntimeValue::Array(vec![
+                RuntimeValue::Integer(1),
+                RuntimeValue::Integer(2),
+                RuntimeValue::Integer(10),
+            ]),
+            RuntimeValue::Array(vec![
+                RuntimeValue::Tuple(vec![RuntimeValue::Integer(1), string("a")]),
+                RuntimeValue::Tuple(vec![RuntimeValue::Integer(2), string("c")]),
+                RuntimeValue::Tuple(vec![RuntimeValue::Integer(10), string("b")]),
+            ]),
+        ])
+    );
+}
+
+#[test]
+fn test_key_type_comes_from_every_key() {
+    // The checker rejects such literals, so this runs unchecked to reach the
+    // interpreter: with keys of different types they all come back as text
+    let source = r#"
+    func main(): any {
+        return {"b": "x", 1: "y"}.sortedKeys()
+    }
+    "#;
+    let program = parse_source(source).unwrap();
+    assert_eq!(
+        call_main(&program).unwrap(),
+        RuntimeValue::Array(vec![RuntimeValue::String("1".to_string()), RuntimeValue::String("b".to_string())])
+    );
+}
+
+#[test]
+fn test_map_literals_evaluate_to_maps() {
+    let source = r#"
+func main(): any {
+    return {"b": 2, "a": 1}
+}
+"#;
+    match run_main(source).unwrap() {
+        RuntimeValue::Map(map) => assert_eq!(
+            map.into_entries(),
+            HashMap::from([
+                ("a".to_string(), RuntimeValue::Integer(1)),
+                ("b".to_string(), RuntimeValue::Integer(2)),
+            ])
+        ),
+        other => panic!("expected a map, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_struct_patterns_destructure_map_literals() {
+    let source = r#"
+func main(): any {
+    let {a, b} = {"a": 1, "b": 2}
+    return (a, b)
+}
+"#;
+    assert_eq!(
+        run_main(source).unwrap(),
+        RuntimeValue::Tuple(vec![RuntimeValue::Integer(1), RuntimeValue::Integer(2)])
+    );
+}
@@ -0,0 +1,295 @@
+//! Tests for the environment, process, signal, exit hook, memory limit,
+//! stack and shutdown functions of std/os
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_in, check_with_imports, interpreter_for, string};
+
+const IMPORTS: &str = "import { getEnv, setEnv, unsetEnv, environ, hostname, pid, signal, atExit, exit, setMemoryLimit, setGoroutineMemoryLimit, topAllocators, stackUsage, shutdown } from \"std/os\"\nimport \"std/os\" as os\n";
+
+/// Helper function to type check source code that imports std/os
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Type check and run the top level of `source`
+fn run_source(source: &str) -> AstInterpreter {
+    interpreter_for(&check_source(source).unwrap()).unwrap()
+}
+
+#[test]
+fn test_environment_and_process() {
+    let source = r#"
+    func roundTrip(name: string): string {
+        setEnv(name, "one")
+        let first = getEnv(name)
+        os.setEnv(name, "two")
+        let all = environ()
+        return first + "," + all[name] + "," + os.getEnv(name)
+    }
+
+    func removed(name: string): any {
+        unsetEnv(name)
+        return getEnv(name)
+    }
+
+    func process(): any {
+        return (pid(), hostname(), os.platform())
+    }
+    "#;
+    let mut interpreter = run_source(source);
+    let name = string("BULU_OS_TEST_VARIABLE");
+    assert_eq!(call_in(&mut interpreter, "roundTrip", &[name.clone()]).unwrap(), string("one,two,two"));
+    assert_eq!(call_in(&mut interpreter, "removed", &[name]).unwrap(), RuntimeValue::Null);
+    match call_in(&mut interpreter, "process", &[]).unwrap() {
+        RuntimeValue::Tuple(values) => match values.as_slice() {
+            [RuntimeValue::Int32(pid), RuntimeValue::String(hostname), RuntimeValue::String(platform)] => {
+                assert_eq!(*pid, std::process::id() as i32);
+                assert!(!hostname.is_empty());
+                assert_eq!(platform, std::env::consts::OS);
+            }
+            other => panic!("unexpected process values {:?}", other),
+        },
+        other => panic!("expected a tuple, got {:?}", other),
+    }
+
+    // Invalid names are runtime errors instead of panics
+    let result = call_in(&mut interpreter, "removed", &[string("A=B")]);
+    assert!(matches!(result, Err(BuluError::RuntimeError { .. })), "{:?}", result);
+}
+
+#[test]
+fn test_calls_are_checked_against_signatures() {
+    for (source, message) in [
+        ("func f() { setEnv(\"A\", 1) }", "Argument 2 to function 'setEnv': expected string, got"),
+        ("func f() { os.setEnv(\"A\") }", "Function 'os.setEnv' expects 2 arguments, got 1"),
+        ("func f() { exit(1, 2) }", "Function 'exit' expects 0 to 1 arguments, got 2"),
+        ("func f() { atExit(\"bye\") }", "Argument 1 to function 'atExit': expected function, got"),
+        ("func f() { signal(\"SIGKILL\") }", "Unknown signal 'SIGKILL' in call to 'signal'"),
+        ("func f() { let g = os.nothing }", "Module 'os' does not export 'nothing'"),
+    ] {
+        match check_source(source) {
+            Err(error) => assert!(error.to_string().contains(message), "{}: {}", source, error),
+            Ok(_) => panic!("expected '{}' to fail to check", source),
+        }
+    }
+    check_source("func f(): string { let port: string = getEnv(\"PORT\")\n return port }").unwrap();
+    check_source("func f(): []string { return os.args() }").unwrap();
+}
+
+#[test]
+fn test_signal_sends_its_name_to_the_channel() {
+    let source = r#"
+    func subscribe(): any {
+        return signal("SIGTERM")
+    }
+
+    func receive(signals: chan string): string {
+        return <-signals
+    }
+    "#;
+    let mut interpreter = run_source(source);
+    let signals = call_in(&mut interpreter, "subscribe", &[]).unwrap();
+    assert!(matches!(signals, RuntimeValue::Channel(_)));
+    unsafe { libc::raise(libc::SIGTERM) };
+    assert_eq!(call_in(&mut interpreter, "receive", &[signals]).unwrap(), string("SIGTERM"));
+}
+
+#[test]
+fn test_exit_hooks_run_last_registered_first() {
+    let source = r#"
+    func main() {
+        atExit(func() { println("first") })
+        os.atExit(func() { println("second") })
+        println("main")
+        exit(2)
+    }
+    "#;
+    let mut interpreter = run_source(source);
+    let stdout = interpreter.capture_stdout();
+    match call_in(&mut interpreter, "main", &[]) {
+        Err(BuluError::ExitRequested(code)) => assert_eq!(code, 2),
+        other => panic!("expected an exit request, got {:?}", other),
+    }
+    interpreter.run_exit_hooks().unwrap();
+    assert_eq!(stdout.contents(), "main\nsecond\nfirst\n");
+
+    // Hooks run once
+    interpreter.run_exit_hooks().unwrap();
+    assert_eq!(stdout.contents(), "main\nsecond\nfirst\n");
+}
+
+#[test]
+fn test_goroutines_over_their_memory_limit_fail_where_they_allocate() {
+    let source = r#"
+    func main(): string {
+        setGoroutineMemoryLimit(4096)
+        let results = make(chan_string)
+        run func() {
+            try {
+                let i = 0
+                while i < 10000 {
+                    let chunk = [i, i, i, i]
+                    i = i + 1
+                }
+                results <- "no error"
+            } fail on err {
+                results <- err
+            }
+        }()
+        return <-results
+    }
+
+    func limitedMain(): string {
+        setMemoryLimit(2048)
+        try {
+            let i = 0
+            while i < 10000 {
+                let chunk = [i, i, i, i]
+                i = i + 1
+            }
+        } fail on err {
+            // The limit is soft: after the error the goroutine may allocate again
+            let more = [1, 2, 3]
+            return err
+        }
+        return "no error"
+    }
+    "#;
+    let mut interpreter = run_source(source);
+    let message = match call_in(&mut interpreter, "main", &[]).unwrap() {
+        RuntimeValue::String(message) => message,
+        other => panic!("expected a message, got {:?}", other),
+    };
+    assert!(message.starts_with("goroutine "), "{}", message);
+    assert!(message.contains("exceeded its memory limit of 4096 bytes"), "{}", message);
+
+    let message = call_in(&mut interpreter, "limitedMain", &[]).unwrap();
+    assert!(
+        matches!(&message, RuntimeValue::String(text) if text.starts_with("goroutine 0 exceeded its memory limit of 2048 bytes")),
+        "{:?}",
+        message
+    );
+}
+
+#[test]
+fn test_top_allocators_lists_running_goroutines() {
+    let source = r#"
+    func main(): any {
+        let i = 0
+        while i < 10 {
+            let chunk = [i, i]
+            i = i + 1
+        }
+        return topAllocators(1)
+    }
+    "#;
+    let mut interpreter = run_source(source);
+    match call_in(&mut interpreter, "main", &[]).unwrap() {
+        RuntimeValue::Array(usages) => match usages.as_slice() {
+            [RuntimeValue::Map(usage)] => {
+                assert_eq!(usage["goroutine"], RuntimeValue::Int64(0));
+                assert_eq!(usage["allocations"], RuntimeValue::Int64(10));
+                assert_eq!(usage["limit"], RuntimeValue::Int64(0));
+                assert!(matches!(usage["bytes"], RuntimeValue::Int64(bytes) if bytes > 0));
+            }
+            other => panic!("expected one goroutine, got {:?}", other),
+        },
+        other => panic!("expected an array, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_deep_calls_in_goroutines_grow_their_stack() {
+    let source = r#"
+    func depth(n: int32): int32 {
+        while n > 0 {
+            return depth(n - 1) + 1
+        }
+        return 0
+    }
+
+    func main(): any {
+        let done = make(chan_int32)
+        run func() {
+            done <- depth(300)
+        }()
+        return (<-done, stackUsage())
+    }
+    "#;
+    let mut interpreter = run_source(source);
+    let initial = bulu::runtime::stack::stack_config().initial_size as i64;
+    match call_in(&mut interpreter, "main", &[]).unwrap() {
+        RuntimeValue::Tuple(values) => match values.as_slice() {
+            [RuntimeValue::Integer(300), RuntimeValue::Array(usages)] => {
+                let grown = usages.iter().any(|usage| match usage {
+                    RuntimeValue::Map(usage) => {
+                        matches!(usage["segments"], RuntimeValue::Int64(segments) if segments > 0)
+                            && matches!(usage["peak"], RuntimeValue::Int64(peak) if peak > initial)
+                    }
+                    _ => false,
+                });
+                assert!(grown, "no goroutine grew its stack: {:?}", usages);
+            }
+            other => panic!("expected the depth and stack usage, got {:?}", other),
+        },
+        other => panic!("expected a tuple, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_shutdown_ends_blocked_goroutines_and_reports_the_rest() {
+    let source = r#"
+    func main(): any {
+        let jobs = make(chan_int32)
+        let done = make(chan_int32, 1)
+        run func() {
+            let job = <-jobs
+        }()
+        run func() {
+            while true {
+                sleep(10)
+            }
+        }()
+        let stuck = shutdown(5000)
+        // Closed by the shutdown, so receiving no longer blocks
+        return (len(stuck), <-done)
+    }
+
+    func busy(): any {
+        run func() {
+            let i = 0
+            while i < 20000 {
+                i = i + 1
+            }
+        }()
+        return shutdown(0)
+    }
+    "#;
+    let mut interpreter = run_source(source);
+    let started = std::time::Instant::now();
+    match call_in(&mut interpreter, "main", &[]).unwrap() {
+        RuntimeValue::Tuple(values) => assert_eq!(values, vec![RuntimeValue::Int32(0), RuntimeValue::Int32(0)]),
+        other => panic!("expected a tuple, got {:?}", other),
+    }
+    assert!(started.elapsed() < std::time::Duration::from_secs(5), "{:?}", started.elapsed());
+
+    // Computing goroutines ignore the cancellation and are reported
+    let mut interpreter = run_source(source);
+    match call_in(&mut interpreter, "busy", &[]).unwrap() {
+        RuntimeValue::Array(stuck) => match stuck.as_slice() {
+            [RuntimeValue::Map(straggler)] => {
+                assert!(matches!(straggler["goroutine"], RuntimeValue::Int64(id) if id > 0));
+                assert!(matches!(straggler["runningMs"], RuntimeValue::Int64(_)));
+            }
+            other => panic!("expected one straggler, got {:?}", other),
+        },
+        other => panic!("expected an array, got {:?}", other),
+    }
+    let report = interpreter.shutdown(std::time::Duration::from_secs(30));
+    assert!(report.is_clean(), "{:?}", report);
+}
@@ -0,0 +1,78 @@
+//! Tests for redirecting and capturing program output
+
+use bulu::ast::*;
+use bulu::lexer::Lexer;
+use bulu::parser::Parser;
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use bulu::runtime::output::{reset_process_output, set_process_stdout, Capture};
+
+fn parse(source: &str) -> Program {
+    let tokens = Lexer::new(source).tokenize().unwrap();
+    Parser::new(tokens).parse().unwrap()
+}
+
+/// Helper function to run `main` with the AST interpreter
+fn run_main(source: &str, interpreter: &mut AstInterpreter) {
+    let program = parse(source);
+    interpreter.execute_program(&program).unwrap();
+    let main_func = interpreter.get_function_definition("main").expect("main should be defined");
+    interpreter.call_user_function(&main_func, &[]).unwrap();
+}
+
+#[test]
+fn test_interpreter_captures_stdout_and_stderr() {
+    let source = r#"
+    func greet(name: string) {
+        printf("hello %s\n", name)
+    }
+
+    func main() {
+        println("a", 1)
+        print("b")
+        print("c\n")
+        greet("there")
+    }
+    "#;
+    let mut interpreter = AstInterpreter::new();
+    let stdout = interpreter.capture_stdout();
+    let stderr = interpreter.capture_stderr();
+    run_main(source, &mut interpreter);
+
+    assert_eq!(stdout.take(), "a 1\nbc\nhello there\n");
+    assert_eq!(stderr.contents(), "");
+
+    // Captures keep collecting across runs
+    run_main("func main() { println(\"again\") }", &mut interpreter);
+    assert_eq!(stdout.contents(), "again\n");
+}
+
+#[test]
+fn test_interpreters_have_separate_sinks() {
+    let mut first = AstInterpreter::new();
+    let mut second = AstInterpreter::new();
+    let first_out = first.capture_stdout();
+    let second_out = Capture::new();
+    second.set_stdout(Box::new(second_out.clone()));
+
+    run_main("func main() { println(\"first\") }", &mut first);
+    run_main("func main() { println(\"second\") }", &mut second);
+    assert_eq!(first_out.contents(), "first\n");
+    assert_eq!(second_out.contents(), "second\n");
+}
+
+#[test]
+fn test_process_wide_stdout() {
+    let process = Capture::new();
+    set_process_stdout(Box::new(process.clone()));
+
+    let mut plain = AstInterpreter::new();
+    run_main("func main() { printf(\"%d items\\n\", 3) }", &mut plain);
+    // An interpreter's own sink takes precedence
+    let mut redirected = AstInterpreter::new();
+    let own = redirected.capture_stdout();
+    run_main("func main() { println(\"mine\") }", &mut redirected);
+    reset_process_output();
+
+    assert_eq!(process.contents(), "3 items\n");
+    assert_eq!(own.contents(), "mine\n");
+}
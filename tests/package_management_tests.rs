@@ -2,7 +2,8 @@
 
 use bulu::package::commands::PackageOptions;
 use bulu::package::lockfile::{LockFile, LockFileManager, RootPackageInfo};
-use bulu::package::{PackageMetadata, VersionConstraint, DependencySource, ResolvedDependency};
+use bulu::package::resolver::ResolutionMode;
+use bulu::package::{PackageMetadata, PackageName, VersionConstraint, DependencySource, ResolvedDependency};
 use bulu::project::{create_project, Project, DependencySpec};
 use std::collections::HashMap;
 use tempfile::TempDir;
@@ -54,6 +55,7 @@ async fn test_lock_file_creation_and_serialization() {
         },
         dependencies: HashMap::new(),
         checksum: Some("abc123".to_string()),
+        patched: false,
     };
     
     dependencies.insert("test-lib".to_string(), resolved_dep);
@@ -97,6 +99,7 @@ async fn test_lock_file_resolution_order() {
         },
         checksum: Some("c123".to_string()),
         dependencies: vec![],
+        patched: false,
     };
     
     let dep_b = bulu::package::lockfile::LockedDependency {
@@ -108,6 +111,7 @@ async fn test_lock_file_resolution_order() {
         },
         checksum: Some("b123".to_string()),
         dependencies: vec!["c".to_string()],
+        patched: false,
     };
     
     let dep_a = bulu::package::lockfile::LockedDependency {
@@ -119,6 +123,7 @@ async fn test_lock_file_resolution_order() {
         },
         checksum: Some("a123".to_string()),
         dependencies: vec!["b".to_string()],
+        patched: false,
     };
 
     dependencies.insert("a".to_string(), dep_a);
@@ -160,6 +165,7 @@ async fn test_lock_file_validation() {
         },
         checksum: Some("a123".to_string()),
         dependencies: vec!["b".to_string()], // References missing dependency
+        patched: false,
     };
 
     dependencies.insert("a".to_string(), dep_a);
@@ -228,6 +234,190 @@ async fn test_project_integration() {
     assert_eq!(empty_lock.dependencies.len(), 0);
 }
 
+#[tokio::test]
+async fn test_scoped_package_names_in_manifest() {
+    let temp_dir = TempDir::new().unwrap();
+    let manifest = r#"
+[package]
+name = "@acme/test-project"
+version = "0.1.0"
+authors = []
+
+[dependencies]
+"@acme/http-utils" = "^1.0.0"
+json = "1.2.0"
+"#;
+    std::fs::write(temp_dir.path().join("lang.toml"), manifest).unwrap();
+
+    let project = Project::load_from_path(temp_dir.path()).unwrap();
+    assert_eq!(project.config.package.name, "@acme/test-project");
+    assert!(project.config.dependencies.contains_key("@acme/http-utils"));
+
+    let package = PackageName::parse(&project.config.package.name).unwrap();
+    assert_eq!(package.scope.as_deref(), Some("acme"));
+    assert_eq!(package.url_segment(), "@acme%2Ftest-project");
+
+    // A malformed scope is rejected when the manifest is loaded
+    std::fs::write(temp_dir.path().join("lang.toml"), manifest.replace("@acme/http-utils", "@acme")).unwrap();
+    assert!(Project::load_from_path(temp_dir.path()).is_err());
+}
+
+#[tokio::test]
+async fn test_patch_overrides_dependency_source() {
+    use bulu::package::registry::RegistryClient;
+    use bulu::package::resolver::{ConflictStrategy, DependencyResolver};
+
+    let temp_dir = TempDir::new().unwrap();
+    let fork_dir = temp_dir.path().join("http-fork");
+    std::fs::create_dir_all(&fork_dir).unwrap();
+    std::fs::write(
+        fork_dir.join("lang.toml"),
+        "[package]\nname = \"http\"\nversion = \"1.4.1\"\nauthors = []\n",
+    )
+    .unwrap();
+
+    let manifest = format!(
+        r#"
+[package]
+name = "app"
+version = "0.1.0"
+authors = []
+
+[dependencies]
+http = "^1.4.0"
+
+[patch]
+http = {{ path = "{}" }}
+json = {{ git = "https://github.com/me/json", branch = "fix-escapes" }}
+"#,
+        fork_dir.display().to_string().replace('\\', "/")
+    );
+    std::fs::write(temp_dir.path().join("lang.toml"), &manifest).unwrap();
+    let project = Project::load_from_path(temp_dir.path()).unwrap();
+
+    let warnings = project.config.patch_warnings();
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].contains("'http' is patched to path:"));
+    assert!(warnings[1].ends_with("git:https://github.com/me/json#fix-escapes"));
+
+    // The registry is never contacted for a patched package
+    let mut resolver = DependencyResolver::new(RegistryClient::new(bulu::package::PackageConfig::default()))
+        .with_patches(project.config.patch.clone());
+    let resolved = resolver
+        .resolve_dependencies(&project.config.dependencies, ConflictStrategy::HighestCompatible)
+        .await
+        .unwrap();
+    let http = &resolved["http"];
+    assert!(http.patched);
+    assert_eq!(http.version, "1.4.1");
+    assert!(matches!(http.source, DependencySource::Path { .. }));
+
+    let lock_file = LockFile::from_resolved_dependencies(&resolved, None);
+    let serialized = toml::to_string_pretty(&lock_file).unwrap();
+    assert!(serialized.contains("patched = true"));
+    assert!(lock_file.patches_match(&project.config.patch));
+    assert!(!lock_file.patches_match(&Default::default()));
+    assert_eq!(lock_file.get_patched_dependencies().len(), 1);
+
+    // A patch has to replace the source, not just the version
+    std::fs::write(temp_dir.path().join("lang.toml"), manifest.replace("json = {", "json = { version = \"2.0\" } #")).unwrap();
+    let err = Project::load_from_path(temp_dir.path()).unwrap_err();
+    assert!(err.to_string().contains("patch for 'json' must set `path` or `git`"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_locked_and_frozen_resolution() {
+    use bulu::package::commands::PackageManager;
+
+    let temp_dir = TempDir::new().unwrap();
+    let fork_dir = temp_dir.path().join("http-fork");
+    std::fs::create_dir_all(&fork_dir).unwrap();
+    let fork_manifest = "[package]\nname = \"http\"\nversion = \"1.4.1\"\nauthors = []\n";
+    std::fs::write(fork_dir.join("lang.toml"), fork_manifest).unwrap();
+
+    // A patched dependency resolves without a registry
+    let manifest = format!(
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\nauthors = []\n\n[dependencies]\nhttp = \"^1.4.0\"\n\n[patch]\nhttp = {{ path = \"{}\" }}\n",
+        fork_dir.display().to_string().replace('\\', "/")
+    );
+    std::fs::write(temp_dir.path().join("lang.toml"), &manifest).unwrap();
+    let manager = || PackageManager::new(Project::load_from_path(temp_dir.path()).unwrap()).unwrap();
+    let options = |resolution| PackageOptions { resolution, ..PackageOptions::default() };
+    let locked = ResolutionMode { locked: true, ..ResolutionMode::default() };
+
+    let err = manager().install_dependencies(&options(ResolutionMode::frozen())).await.unwrap_err();
+    assert!(err.to_string().contains("--frozen"), "{}", err);
+    let err = manager().install_dependencies(&options(locked)).await.unwrap_err();
+    assert!(err.to_string().contains("lang.lock does not exist"), "{}", err);
+
+    manager().install_dependencies(&options(ResolutionMode::default())).await.unwrap();
+    manager().install_dependencies(&options(ResolutionMode::frozen())).await.unwrap();
+    manager().update_dependencies(&options(locked)).await.unwrap();
+
+    // A new version of the package would change the lock file
+    std::fs::write(fork_dir.join("lang.toml"), fork_manifest.replace("1.4.1", "1.4.2")).unwrap();
+    let err = manager().update_dependencies(&options(locked)).await.unwrap_err();
+    assert!(err.to_string().contains("update http 1.4.1 -> 1.4.2"), "{}", err);
+
+    // The same modes can be turned on from lang.toml
+    std::fs::write(temp_dir.path().join("lang.toml"), format!("{}\n[resolver]\nfrozen = true\n", manifest)).unwrap();
+    let project = Project::load_from_path(temp_dir.path()).unwrap();
+    assert!(project.config.resolver.frozen);
+    let err = manager().update_dependencies(&options(ResolutionMode::default())).await.unwrap_err();
+    assert!(err.to_string().contains("--frozen"), "{}", err);
+
+    let lock_file = LockFileManager::new(temp_dir.path()).load_or_create().unwrap();
+    assert_eq!(lock_file.dependencies["http"].version, "1.4.1");
+}
+
+#[tokio::test]
+async fn test_private_registries_from_user_and_project_config() {
+    use bulu::package::registries::{RegistrySettings, UserConfig};
+
+    let temp_dir = TempDir::new().unwrap();
+    let user_config_path = temp_dir.path().join("config.toml");
+    std::fs::write(
+        &user_config_path,
+        r#"
+[registries.internal]
+url = "https://registry.acme.corp"
+token = "acme-token"
+"#,
+    )
+    .unwrap();
+    std::fs::write(
+        temp_dir.path().join("lang.toml"),
+        r#"
+[package]
+name = "app"
+version = "0.1.0"
+authors = []
+
+[dependencies]
+"@acme/http-utils" = "^1.0.0"
+json = "1.2.0"
+
+[scopes]
+"@acme" = "internal"
+"#,
+    )
+    .unwrap();
+
+    let project = Project::load_from_path(temp_dir.path()).unwrap();
+    let user = UserConfig::load_from_path(&user_config_path).unwrap();
+    let registries = RegistrySettings::merge(user, Some(&project.config)).unwrap();
+
+    let private = registries.registry_for("@acme/http-utils").unwrap();
+    assert_eq!(private.url, "https://registry.acme.corp");
+    assert_eq!(private.token.as_deref(), Some("acme-token"));
+    assert_eq!(registries.registry_for("json").unwrap().token, None);
+
+    // Routing survives a rewrite of lang.toml, without leaking tokens into it
+    let rewritten = toml::to_string_pretty(&project.config).unwrap();
+    assert!(rewritten.contains("[scopes]"));
+    assert!(!rewritten.contains("acme-token"));
+}
+
 #[tokio::test]
 async fn test_vendor_status_calculation() {
     use bulu::package::vendor::VendorStatus;
@@ -273,6 +463,7 @@ async fn test_dependency_spec_parsing() {
         tag: None,
         features: Some(vec!["feature1".to_string(), "feature2".to_string()]),
         optional: Some(true),
+        registry: None,
     };
     
     match detailed {
@@ -290,18 +481,19 @@ async fn test_dependency_spec_parsing() {
 #[tokio::test]
 async fn test_package_options() {
     let default_options = PackageOptions::default();
-    assert!(!default_options.verbose);
     assert!(!default_options.dry_run);
     assert!(!default_options.force);
     
+    assert_eq!(default_options.resolution, ResolutionMode::default());
+    
     let custom_options = PackageOptions {
-        verbose: true,
         dry_run: true,
         force: true,
+        resolution: ResolutionMode::frozen(),
     };
-    assert!(custom_options.verbose);
     assert!(custom_options.dry_run);
     assert!(custom_options.force);
+    assert!(custom_options.resolution.locked && custom_options.resolution.offline);
 }
 
 // Integration test for the complete package management workflow
@@ -327,6 +519,7 @@ async fn test_complete_package_workflow() {
         },
         dependencies: HashMap::new(),
         checksum: Some("def456".to_string()),
+        patched: false,
     };
     resolved_deps.insert("example-lib".to_string(), resolved_dep);
     
@@ -362,4 +555,36 @@ async fn test_complete_package_workflow() {
     // 7. Test with missing dependency
     project_deps.insert("missing-lib".to_string(), DependencySpec::Simple("^2.0.0".to_string()));
     assert!(!loaded_lock.is_up_to_date(&project_deps));
-}
\ No newline at end of file
+}
+#[test]
+fn test_lang_cache_verify_quarantines_corrupted_tarballs() {
+    use bulu::package::PackageCache;
+
+    let temp_dir = TempDir::new().unwrap();
+    let cache = PackageCache::new(temp_dir.path().join("cache"));
+    let intact = cache.insert("json", "1.0.0", b"json tarball").unwrap();
+    let corrupted = cache.insert("http", "2.0.0", b"http tarball").unwrap();
+    let path = temp_dir.path().join("cache/store/sha256").join(&corrupted[..2]).join(&corrupted);
+    std::fs::write(&path, b"http tarbalL").unwrap();
+
+    let verify = || {
+        std::process::Command::new(env!("CARGO_BIN_EXE_lang"))
+            .args(["cache", "verify"])
+            .current_dir(temp_dir.path())
+            .env("BULU_CONFIG", temp_dir.path().join("config.toml"))
+            .env("BULU_PACKAGE_CACHE_DIR", temp_dir.path().join("cache"))
+            .output()
+            .unwrap()
+    };
+    let output = verify();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(stderr.contains(&corrupted), "{}", stderr);
+    assert!(!path.exists());
+    assert_eq!(cache.quarantined().unwrap().len(), 1);
+    assert_eq!(cache.get(&intact).unwrap(), Some(b"json tarball".to_vec()));
+
+    // Once the corrupted tarball is set aside the rest of the cache verifies
+    let output = verify();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
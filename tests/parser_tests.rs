@@ -687,6 +687,40 @@ mod program_tests {
         let program = parse_source(source).unwrap();
         assert_eq!(program.statements.len(), 2);
     }
+
+    #[test]
+    fn test_parse_stream_matches_parse() {
+        let source = r#"
+            import "std/io"
+
+            /// Adds two numbers
+            func add(a: int32, b: int32): int32 {
+                return a + b
+            }
+
+            struct Point { x: int32, y: int32 }
+            let origin = Point {
+                x: 0,
+                y: 0
+            }
+            let total = add(origin.x, 2)
+            println(total)
+        "#;
+
+        let program = parse_source(source).unwrap();
+        let streamed = Parser::new(Vec::new()).parse_stream(Lexer::new(source)).unwrap();
+        assert_eq!(streamed, program);
+        assert_eq!(streamed.statements.len(), 6);
+    }
+
+    #[test]
+    fn test_parse_stream_errors() {
+        let result = Parser::new(Vec::new()).parse_stream(Lexer::new("let x = 1\nlet = 2\n"));
+        assert!(result.is_err());
+
+        let result = Parser::new(Vec::new()).parse_stream(Lexer::new("let x = 1\nlet s = \"open"));
+        assert!(matches!(result, Err(BuluError::LexError { .. })));
+    }
 }
 
 #[cfg(test)]
@@ -4,6 +4,10 @@ use bulu::lexer::Lexer;
 use bulu::parser::Parser;
 use bulu::ast::*;
 use bulu::error::BuluError;
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use bulu::types::checker::TypeChecker;
+use bulu::types::patterns::analyze_match;
+use bulu::types::primitive::RuntimeValue;
 
 /// Helper function to parse source code
 fn parse_source(source: &str) -> Result<Program, BuluError> {
@@ -13,6 +17,22 @@ fn parse_source(source: &str) -> Result<Program, BuluError> {
     parser.parse()
 }
 
+/// Helper function to type check source code
+fn check_source(source: &str) -> Result<(), BuluError> {
+    let program = parse_source(source)?;
+    let mut checker = TypeChecker::new();
+    checker.check(&program)
+}
+
+/// Helper function to run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    let program = parse_source(source)?;
+    let mut interpreter = AstInterpreter::new();
+    interpreter.execute_program(&program)?;
+    let main_func = interpreter.get_function_definition("main").expect("main should be defined");
+    interpreter.call_user_function(&main_func, &[])
+}
+
 #[cfg(test)]
 mod pattern_matching_tests {
     use super::*;
@@ -421,4 +441,306 @@ mod pattern_matching_tests {
             _ => panic!("Expected function declaration"),
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_binding_pattern_parsing() {
+        let source = r#"
+        func test() {
+            match value {
+                n @ 1...9 if n != 5 -> print("digit")
+                whole @ (0 | 10) -> print("edge")
+                _ -> print("other")
+            }
+        }
+        "#;
+
+        let program = parse_source(source).unwrap();
+
+        match &program.statements[0] {
+            Statement::FunctionDecl(func) => match &func.body.statements[0] {
+                Statement::Match(match_stmt) => {
+                    match &match_stmt.arms[0].pattern {
+                        Pattern::Binding(binding) => {
+                            assert_eq!(binding.name, "n");
+                            assert!(matches!(*binding.pattern, Pattern::Range(_)));
+                        }
+                        _ => panic!("Expected binding pattern"),
+                    }
+                    assert!(match_stmt.arms[0].guard.is_some());
+
+                    match &match_stmt.arms[1].pattern {
+                        Pattern::Binding(binding) => {
+                            assert_eq!(binding.name, "whole");
+                            assert!(matches!(*binding.pattern, Pattern::Or(_)));
+                        }
+                        _ => panic!("Expected binding pattern"),
+                    }
+                    assert!(match_stmt.arms[1].guard.is_none());
+                }
+                _ => panic!("Expected match statement"),
+            },
+            _ => panic!("Expected function declaration"),
+        }
+    }
+
+    #[test]
+    fn test_match_exhaustiveness_analysis() {
+        let source = r#"
+        match flag {
+            true -> print("yes")
+            x if x -> print("guarded")
+            false -> print("no")
+            _ -> print("unreachable")
+        }
+        "#;
+
+        let program = parse_source(source).unwrap();
+        let match_stmt = match &program.statements[0] {
+            Statement::Match(match_stmt) => match_stmt,
+            _ => panic!("Expected match statement"),
+        };
+        let arms: Vec<(&Pattern, bool)> = match_stmt
+            .arms
+            .iter()
+            .map(|arm| (&arm.pattern, arm.guard.is_some()))
+            .collect();
+
+        // Both booleans are covered once the `false` arm is reached
+        let coverage = analyze_match(&arms, true);
+        assert!(coverage.exhaustive);
+        assert_eq!(coverage.unreachable_arms, vec![3]);
+
+        // Without knowing the scrutinee is a bool only the wildcard covers everything
+        let coverage = analyze_match(&arms[..3], false);
+        assert!(!coverage.exhaustive);
+        assert!(coverage.unreachable_arms.is_empty());
+    }
+
+    #[test]
+    fn test_match_expression_must_be_exhaustive() {
+        let result = check_source(r#"
+        let n = 3
+        let size = match n {
+            x if x > 10 -> "big"
+            1 | 2 | 3 -> "small"
+        }
+        "#);
+        match result {
+            Err(BuluError::TypeError { message, .. }) => {
+                assert!(message.contains("Non-exhaustive"), "unexpected message: {}", message)
+            }
+            other => panic!("Expected non-exhaustive error, got {:?}", other),
+        }
+
+        let result = check_source(r#"
+        let n = 3
+        let size = match n {
+            x if x > 10 -> "big"
+            small @ (1 | 2 | 3) -> "small"
+            _ -> "other"
+        }
+        "#);
+        assert!(result.is_ok(), "{:?}", result);
+    }
+
+    #[test]
+    fn test_unreachable_arm_and_guard_type_errors() {
+        let result = check_source(r#"
+        let n = 3
+        match n {
+            other -> print("any")
+            1 -> print("one")
+        }
+        "#);
+        match result {
+            Err(BuluError::TypeError { message, line, .. }) => {
+                assert!(message.contains("Unreachable match arm"));
+                assert_eq!(line, 5);
+            }
+            other => panic!("Expected unreachable arm error, got {:?}", other),
+        }
+
+        let result = check_source(r#"
+        let n = 3
+        match n {
+            x if x + 1 -> print("bad guard")
+            _ -> print("other")
+        }
+        "#);
+        match result {
+            Err(BuluError::TypeError { message, .. }) => {
+                assert!(message.contains("Match guard must be bool"), "unexpected message: {}", message)
+            }
+            other => panic!("Expected guard type error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_guard_and_binding_evaluation() {
+        let source = r#"
+        func classify(n: int32): string {
+            return match n {
+                0 -> "zero"
+                x if x > 10 -> "big"
+                small @ (1 | 2) if small != 2 -> "one"
+                small @ 2...5 -> "small"
+                _ -> "other"
+            }
+        }
+
+        func main(): string {
+            let seen = ""
+            match 7 {
+                v @ 7 if v < 3 -> seen = "wrong"
+                v @ 7 -> seen = "seven"
+                _ -> seen = "none"
+            }
+            return classify(0) + "," + classify(42) + "," + classify(1) + "," + classify(2) + "," + classify(8) + "," + seen
+        }
+        "#;
+
+        match run_main(source).unwrap() {
+            RuntimeValue::String(result) => assert_eq!(result, "zero,big,one,small,other,seven"),
+            other => panic!("Expected string result, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_struct_field_shorthand_parsing() {
+        let program = parse_source(r#"
+        match p {
+            Point { x, y: 0 } -> print(x)
+        }
+        "#).unwrap();
+
+        let Statement::Match(match_stmt) = &program.statements[0] else {
+            panic!("Expected match statement");
+        };
+        let Pattern::Struct(struct_pattern) = &match_stmt.arms[0].pattern else {
+            panic!("Expected struct pattern");
+        };
+        assert_eq!((struct_pattern.position.line, struct_pattern.position.column), (3, 13));
+        assert_eq!(struct_pattern.fields.len(), 2);
+        assert!(matches!(&*struct_pattern.fields[0].pattern, Pattern::Identifier(name, _) if name == "x"));
+        assert!(matches!(&*struct_pattern.fields[1].pattern, Pattern::Literal(LiteralValue::Integer(0), _)));
+
+        assert!(parse_source("match p {\n    Point { x 1 } -> print(1)\n}\n").is_err());
+    }
+
+    #[test]
+    fn test_nested_struct_and_tuple_patterns() {
+        let source = r#"
+        struct Point { x: int32, y: int32 }
+        struct Line { start: Point, end: Point }
+
+        func describe(pair: (Point, int32)): string {
+            return match pair {
+                (Point { x: 0, y: 0 }, _) -> "origin"
+                (Point { x, y }, n) if x == y -> "diagonal " + n
+                (p @ Point { x: 1...9, y }, n) -> "near " + p.x + y + n
+                _ -> "far"
+            }
+        }
+
+        func main(): string {
+            let line = Line { start: Point { x: 1, y: 2 }, end: Point { x: 3, y: 4 } }
+            let ends = ""
+            match line {
+                Line { start: Point { x: 0, y }, end } -> ends = "vertical"
+                Line { start: Point { x: a, y: b }, end: e @ Point { x: 3, y } } -> ends = "" + a + b + e.x + y
+                _ -> ends = "other"
+            }
+            let nested = match (1, (2, 3)) {
+                (a, inner @ (b, c)) if a + b + c > 100 -> "big"
+                (a, (b, c)) -> "sum " + (a + b + c)
+            }
+            let described = describe((Point { x: 0, y: 0 }, 1)) + "," + describe((Point { x: 5, y: 5 }, 2))
+            let more = describe((Point { x: 4, y: 7 }, 3)) + "," + describe((Point { x: 40, y: 7 }, 4))
+            return described + "," + more + "," + ends + "," + nested
+        }
+        "#;
+
+        match run_main(source).unwrap() {
+            RuntimeValue::String(result) => {
+                assert_eq!(result, "origin,diagonal 2,near 473,far,1234,sum 6")
+            }
+            other => panic!("Expected string result, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_nested_pattern_type_errors() {
+        let result = check_source(r#"
+        struct Point { x: int32, y: int32 }
+        struct Size { w: int32 }
+        let p = Point { x: 1, y: 2 }
+        match (p, 3) {
+            (Size { w }, n) -> print(w)
+            _ -> print(0)
+        }
+        "#);
+        match result {
+            Err(BuluError::TypeError { message, line, column, .. }) => {
+                assert_eq!(message, "Struct pattern 'Size' can never match a value of struct Point");
+                assert_eq!((line, column), (6, 14));
+            }
+            other => panic!("Expected struct mismatch error, got {:?}", other),
+        }
+
+        let result = check_source(r#"
+        struct Point { x: int32, y: int32 }
+        let p = Point { x: 1, y: 2 }
+        match p {
+            Point { x, z } -> print(x)
+            _ -> print(0)
+        }
+        "#);
+        match result {
+            Err(BuluError::TypeError { message, .. }) => {
+                assert_eq!(message, "Field 'z' not found in struct Point")
+            }
+            other => panic!("Expected missing field error, got {:?}", other),
+        }
+
+        // Bindings inside nested patterns take the field's type
+        let result = check_source(r#"
+        struct Point { x: int32, y: int32 }
+        let pair = (Point { x: 1, y: 2 }, "label")
+        match pair {
+            (Point { x: n @ 1...5, y }, label) if n > y -> { let s: string = n }
+            _ -> print(0)
+        }
+        "#);
+        match result {
+            Err(BuluError::TypeError { message, .. }) => {
+                assert_eq!(message, "Cannot assign int32 to variable of type string")
+            }
+            other => panic!("Expected assignment error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_failed_or_alternatives_do_not_leak_bindings() {
+        let source = r#"
+        func main(): int32 {
+            let x = 0
+            return match (5, 2) {
+                (x, 1) | (_, 2) -> x
+                _ -> 0 - 1
+            }
+        }
+        "#;
+        // The first alternative binds `x` before failing on `1`; the arm must see the outer `x`
+        assert_eq!(run_main(source).unwrap(), RuntimeValue::Integer(0));
+
+        let source = r#"
+        func main(): int32 {
+            return match (5, 2) {
+                (0, n) | (n, 2) -> n
+                _ -> 0
+            }
+        }
+        "#;
+        assert_eq!(run_main(source).unwrap(), RuntimeValue::Integer(5));
+    }
+}
@@ -0,0 +1,141 @@
+//! Tests for `@no_prelude` files and explicit imports from std/builtin
+
+mod common;
+
+use bulu::ast::*;
+use bulu::compiler::{CompileSession, Optimizer};
+use bulu::error::BuluError;
+use bulu::project::ProjectConfig;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_function, check_resolved, parse_source, resolve_source};
+
+/// Helper function to parse, resolve imports, fold constants and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    let (program, symbol_resolver) = resolve_source(source)?;
+    let program = Optimizer::new().optimize(program)?;
+    check_resolved(&program, &symbol_resolver)?;
+    Ok(program)
+}
+
+/// Run `source` and call its function `name` without arguments
+fn call(source: &str, name: &str) -> RuntimeValue {
+    call_function(&check_source(source).unwrap(), name, &[]).unwrap()
+}
+
+fn error(source: &str) -> String {
+    check_source(source).unwrap_err().to_string()
+}
+
+#[test]
+fn test_files_without_prelude_declare_builtin_names() {
+    let source = r#"
+        @no_prelude
+
+        func delete(s: string): string {
+            return s + " deleted"
+        }
+
+        func len(s: string): string {
+            return "length of " + s
+        }
+
+        func compute(): string {
+            return delete(len("abc"))
+        }
+    "#;
+    assert_eq!(call(source, "compute"), RuntimeValue::String("length of abc deleted".to_string()));
+
+    // With the prelude the names are taken
+    let with_prelude = source.replace("@no_prelude", "");
+    assert!(error(&with_prelude).contains("'delete' is already defined"), "{}", error(&with_prelude));
+}
+
+#[test]
+fn test_builtins_must_be_imported() {
+    let message = error("@no_prelude\n\nfunc compute() {\n    println(\"hi\")\n}\n");
+    assert!(
+        message.contains("'println' is a builtin, and the prelude is disabled; import it from \"std/builtin\""),
+        "{}",
+        message
+    );
+    let message = error("@no_prelude\nimport { print } from \"std/builtin\"\n\nfunc compute(): int32 {\n    return len(\"a\")\n}\n");
+    assert!(message.contains("'len' is a builtin"), "{}", message);
+
+    let source = r#"
+        @no_prelude
+        import { len } from "std/builtin"
+
+        func print(s: string): string {
+            return "printed " + s
+        }
+
+        func compute(): string {
+            let xs: []int32 = [1, 2, 3]
+            return match len(xs) {
+                3 -> print("three")
+                _ -> print("other")
+            }
+        }
+    "#;
+    assert_eq!(call(source, "compute"), RuntimeValue::String("printed three".to_string()));
+
+    // Importing the module brings every builtin, values included
+    let source = r#"
+        @no_prelude
+        import "std/builtin"
+
+        func compute(): string {
+            let o: Option<int32> = None
+            return typeof(o)
+        }
+    "#;
+    assert_eq!(call(source, "compute"), RuntimeValue::String("Option".to_string()));
+}
+
+#[test]
+fn test_attribute_and_import_errors() {
+    let cases = [
+        (
+            "@no_prelude\nimport { delete as remove } from \"std/builtin\"\n",
+            "Builtin 'delete' cannot be imported under another name",
+        ),
+        (
+            "@no_prelude\nimport \"std/builtin\" as builtins\n",
+            "Builtins cannot be imported as a module; import them by name",
+        ),
+        ("func f() {}\n@no_prelude\n", "File attributes must come before any statement"),
+        ("@strict\nfunc f() {}\n", "Unknown file attribute '@strict'"),
+        ("@no_prelude(true)\nfunc f() {}\n", "Attribute '@no_prelude' takes no arguments"),
+    ];
+    for (source, expected) in cases {
+        assert!(error(source).contains(expected), "{}: {}", source, error(source));
+    }
+
+    // Importing builtins is allowed, and changes nothing, with the prelude
+    assert!(check_source("import { len } from \"std/builtin\"\nfunc f(): int32 {\n    return len(\"a\")\n}\n").is_ok());
+}
+
+#[test]
+fn test_package_setting_disables_the_prelude() {
+    let config: ProjectConfig = toml::from_str("[package]\nname = \"app\"\nversion = \"0.1.0\"\nauthors = []\nprelude = false\n").unwrap();
+    assert!(!config.package.prelude);
+    let config: ProjectConfig = toml::from_str("[package]\nname = \"app\"\nversion = \"0.1.0\"\nauthors = []\n").unwrap();
+    assert!(config.package.prelude);
+    assert!(!toml::to_string(&config).unwrap().contains("prelude"));
+
+    let source = "func main() {\n    println(\"hi\")\n}\n";
+    let mut session = CompileSession::from_source("main.bu", source).with_prelude(false);
+    let message = session.check().unwrap_err().to_string();
+    assert!(message.contains("'println' is a builtin"), "{}", message);
+
+    let source = format!("import {{ println }} from \"std/builtin\"\n{}", source);
+    assert!(CompileSession::from_source("main.bu", source).with_prelude(false).check().is_ok());
+}
+
+#[test]
+fn test_file_attributes_are_printed() {
+    let program = parse_source("@no_prelude\n\nfunc f() {}\n").unwrap();
+    assert!(program.has_attribute("no_prelude"));
+    let printed = AstPrinter::new().print_program(&program);
+    assert!(printed.contains("@no_prelude"), "{}", printed);
+}
@@ -0,0 +1,127 @@
+//! Tests for printf format specifiers and compile-time format string checking
+
+mod common;
+
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use bulu::runtime::builtins::format_string_with_args;
+use bulu::types::checker::TypeChecker;
+use bulu::types::primitive::RuntimeValue;
+use common::{assert_type_error, check_source, parse_source};
+
+/// Helper function to format runtime values with a printf format string
+fn format(format: &str, args: &[RuntimeValue]) -> String {
+    format_string_with_args(format, args).unwrap()
+}
+
+fn string(s: &str) -> RuntimeValue {
+    RuntimeValue::String(s.to_string())
+}
+
+#[test]
+fn test_integer_and_string_specifiers() {
+    assert_eq!(format("%d items", &[RuntimeValue::Int32(3)]), "3 items");
+    assert_eq!(format("[%5d]", &[RuntimeValue::Int32(42)]), "[   42]");
+    assert_eq!(format("[%-5d]", &[RuntimeValue::Int32(42)]), "[42   ]");
+    assert_eq!(format("[%05d]", &[RuntimeValue::Int64(-42)]), "[-0042]");
+    assert_eq!(format("%+d % d", &[RuntimeValue::Integer(7), RuntimeValue::Integer(7)]), "+7  7");
+    assert_eq!(format("%x %X %o", &[RuntimeValue::Int32(255), RuntimeValue::UInt8(255), RuntimeValue::Int32(8)]), "ff FF 10");
+    assert_eq!(format("[%8s|%-6s|%.3s]", &[string("right"), string("left"), string("truncate")]), "[   right|left  |tru]");
+    assert_eq!(format("%c%c", &[RuntimeValue::Char('o'), RuntimeValue::Int32(107)]), "ok");
+    assert_eq!(format("%t 100%%", &[RuntimeValue::Bool(true)]), "true 100%");
+}
+
+#[test]
+fn test_float_and_any_specifiers() {
+    assert_eq!(format("%f", &[RuntimeValue::Float64(3.14159)]), "3.141590");
+    assert_eq!(format("%.2f", &[RuntimeValue::Float64(3.14159)]), "3.14");
+    assert_eq!(format("[%8.3f]", &[RuntimeValue::Float64(-2.5)]), "[  -2.500]");
+    assert_eq!(format("[%08.3f]", &[RuntimeValue::Float64(-2.5)]), "[-002.500]");
+    assert_eq!(format("%.1f", &[RuntimeValue::Int32(2)]), "2.0");
+    assert_eq!(format("%e", &[RuntimeValue::Float64(1500.0)]), "1.500000e+03");
+    assert_eq!(format("%.3g", &[RuntimeValue::Float64(3.14159)]), "3.14");
+    assert_eq!(
+        format("%v %v %v", &[RuntimeValue::Int32(1), string("two"), RuntimeValue::Array(vec![RuntimeValue::Int32(3)])]),
+        "1 two [3]"
+    );
+}
+
+#[test]
+fn test_runtime_argument_errors() {
+    let error = format_string_with_args("%d", &[string("x")]).unwrap_err();
+    assert!(error.to_string().contains("printf: %d expects an integer, got string"));
+
+    let error = format_string_with_args("%d %d", &[RuntimeValue::Int32(1)]).unwrap_err();
+    assert!(error.to_string().contains("printf: not enough arguments for format string"));
+
+    let error = format_string_with_args("%d", &[RuntimeValue::Int32(1), RuntimeValue::Int32(2)]).unwrap_err();
+    assert!(error.to_string().contains("printf: too many arguments for format string (expected 1, got 2)"));
+
+    let error = format_string_with_args("%q", &[RuntimeValue::Int32(1)]).unwrap_err();
+    assert!(error.to_string().contains("printf: unknown format verb '%q'"));
+}
+
+#[test]
+fn test_literal_format_strings_are_checked() {
+    let source = r#"
+    func main() {
+        let name = "bulu"
+        let count = 3
+        let ratio = 0.5
+        printf("%s has %d items (%.1f%%)\n", name, count, ratio)
+        printf("%-10s|%5.2f|%v\n", name, count, [1, 2])
+        printf("no arguments\n")
+    }
+    "#;
+    assert!(check_source(source).is_ok());
+
+    assert_type_error(
+        "func main() {\n    printf(\"%d items\\n\", \"three\")\n}\n",
+        "Argument 2 to function 'printf': %d expects an integer, got string",
+    );
+    assert_type_error(
+        "func main() {\n    printf(\"%s and %s\\n\", \"one\")\n}\n",
+        "Format string in call to 'printf' expects 2 arguments, got 1",
+    );
+    assert_type_error(
+        "func main() {\n    printf(\"done\\n\", 1)\n}\n",
+        "Format string in call to 'printf' expects 0 arguments, got 1",
+    );
+    assert_type_error(
+        "func main() {\n    printf(\"%z\\n\", 1)\n}\n",
+        "Invalid format string in call to 'printf': unknown format verb '%z'",
+    );
+    assert_type_error("func main() {\n    printf(42)\n}\n", "Argument 1 to function 'printf': expected string, got int32");
+
+    // A format string that is only known at runtime is checked when printf runs
+    let dynamic = r#"
+    func main() {
+        let format = "%d\n"
+        printf(format, "x")
+    }
+    "#;
+    let program = parse_source(dynamic).unwrap();
+    TypeChecker::new().check(&program).unwrap();
+    let mut interpreter = AstInterpreter::new();
+    interpreter.execute_program(&program).unwrap();
+    let main_func = interpreter.get_function_definition("main").unwrap();
+    let error = interpreter.call_user_function(&main_func, &[]).unwrap_err();
+    assert!(error.to_string().contains("printf: %d expects an integer, got string"));
+}
+
+#[test]
+fn test_repeated_formats_are_parsed_once() {
+    use bulu::runtime::pattern_cache::{compile_printf, pattern_cache_metrics};
+
+    // Other tests share the cache, so only look at how the counters move
+    let before = pattern_cache_metrics().printf_formats;
+    let first = compile_printf("cache %d of %s\n");
+    for i in 0..3 {
+        assert_eq!(format("cache %d of %s\n", &[RuntimeValue::Int32(i), string("x")]), format!("cache {} of x\n", i));
+    }
+    let after = pattern_cache_metrics().printf_formats;
+
+    assert!(std::sync::Arc::ptr_eq(&first, &compile_printf("cache %d of %s\n")));
+    assert!(after.hits >= before.hits + 3);
+    assert!(after.misses > before.misses);
+    assert!(after.entries <= after.capacity);
+}
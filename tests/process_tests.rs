@@ -0,0 +1,123 @@
+//! Tests for the std/process module
+#![cfg(unix)]
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_main, check_with_imports, string};
+use std::time::{Duration, Instant};
+
+const IMPORTS: &str = "import { exec, command } from \"std/process\"\n";
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Helper function to type check and run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(source)?)
+}
+
+#[test]
+fn test_run_returns_exit_code_and_output() {
+    let source = r#"
+    func main(): any {
+        let out = exec("sh", ["-c", "echo hello; echo oops >&2; exit 2"]).unwrap()
+        let missing = exec("no-such-program-here", [])
+        return (out.code, out.success, out.stdout, out.stderr, missing.isError())
+    }
+    "#;
+    let result = run_main(source).unwrap();
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::Int32(2),
+            RuntimeValue::Bool(false),
+            string("hello\n"),
+            string("oops\n"),
+            RuntimeValue::Bool(true),
+        ])
+    );
+}
+
+#[test]
+fn test_process_pipes_and_environment() {
+    let source = r#"
+    func main(): any {
+        let cmd = command("sh", ["-c", "echo \"$PREFIX\"; while read line; do echo \"$PREFIX$line\"; done"])
+        cmd.env("PREFIX", "> ")
+        let p = cmd.spawn().unwrap()
+        let first = p.readLine().unwrap()
+        p.write("a\nb\n")
+        p.closeStdin()
+        let rest = p.readAll().unwrap()
+        let code = p.wait().unwrap()
+        let end = p.readLine().isNone()
+        return (first, rest, code, end)
+    }
+    "#;
+    let result = run_main(source).unwrap();
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![
+            string("> \n"),
+            string("> a\n> b\n"),
+            RuntimeValue::Int32(0),
+            RuntimeValue::Bool(true),
+        ])
+    );
+}
+
+#[test]
+fn test_timeout_and_kill() {
+    let source = r#"
+    func main(): any {
+        let slow = command("sleep", ["5"])
+        slow.timeout(50)
+        let timedOut = slow.exec()
+        let p = command("sleep", ["5"]).spawn().unwrap()
+        p.kill().unwrap()
+        return (timedOut.error(), p.wait().unwrap())
+    }
+    "#;
+    let started = Instant::now();
+    let result = run_main(source).unwrap();
+    assert!(started.elapsed() < Duration::from_secs(4));
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![string("process timed out after 50 ms"), RuntimeValue::Int32(-1)])
+    );
+}
+
+#[test]
+fn test_waiting_goroutine_does_not_block_others() {
+    let source = r#"
+    func main(): any {
+        let slow = command("sleep", ["2"]).spawn().unwrap()
+        run func() {
+            slow.wait()
+        }()
+        sleep(50)
+        return command("echo", ["quick"]).exec().unwrap().stdout
+    }
+    "#;
+    let started = Instant::now();
+    let result = run_main(source).unwrap();
+    assert!(started.elapsed() < Duration::from_millis(1500), "{:?}", started.elapsed());
+    assert_eq!(result, string("quick\n"));
+}
+
+#[test]
+fn test_process_calls_are_checked() {
+    let error = check_source("func main() { let n: int32 = exec(\"ls\", []) }").unwrap_err();
+    assert!(error.to_string().contains("Result<Output, string>"), "{}", error);
+
+    let error = check_source("func main() { exec(\"ls\") }").unwrap_err();
+    assert!(error.to_string().contains("expects 2 arguments"), "{}", error);
+
+    let error = check_source("func main() { let out = exec(\"ls\", []).unwrap()\n let s: string = out.code }").unwrap_err();
+    assert!(error.to_string().contains("int32"), "{}", error);
+}
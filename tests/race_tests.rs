@@ -0,0 +1,125 @@
+//! Tests for the race detector of `bulu run --race`
+
+mod common;
+
+use bulu::error::BuluError;
+use bulu::runtime::race::{start_race_detector, stop_race_detector, RaceReport};
+use bulu::types::primitive::RuntimeValue;
+use common::{call_in, call_main, check_with_imports, interpreter_for};
+use std::sync::Mutex;
+
+const IMPORTS: &str = "import { newWaitGroup, newMutex, newRwLock } from \"std/sync\"\n";
+
+/// There is one detector per process, so tests using it take turns
+static DETECTOR: Mutex<()> = Mutex::new(());
+
+/// Run `main` under the race detector, returning its result and the races found
+fn run_with_race_detector(source: &str) -> Result<(RuntimeValue, Vec<RaceReport>), BuluError> {
+    let _turn = DETECTOR.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let program = check_with_imports(IMPORTS, source)?;
+
+    start_race_detector().map_err(BuluError::Other)?;
+    let result = call_main(&program);
+    let races = stop_race_detector().expect("the detector was started");
+    Ok((result?, races))
+}
+
+#[test]
+fn test_unsynchronized_increments_are_reported() {
+    let source = r#"
+    func main(): int32 {
+        let wg = newWaitGroup()
+        let count = 0
+        wg.add(2)
+        run func() {
+            count = count + 1
+            wg.done()
+        }()
+        run func() {
+            count = count + 1
+            wg.done()
+        }()
+        wg.wait()
+        return count
+    }
+    "#;
+    let (_, races) = run_with_race_detector(source).unwrap();
+    assert!(!races.is_empty(), "expected a race on count");
+
+    let race = &races[0];
+    assert_eq!(race.variable, "count");
+    assert_ne!(race.current.goroutine, race.previous.goroutine);
+    assert!(race.current.write || race.previous.write);
+    // Both accesses are in a goroutine's closure, at the line that increments
+    assert!(race.current.stack[0].starts_with("at line 8,") || race.current.stack[0].starts_with("at line 12,"));
+    assert!(race.previous.stack[0].starts_with("at line 8,") || race.previous.stack[0].starts_with("at line 12,"));
+    assert_eq!(race.created_at.len(), 2);
+    assert!(race.to_string().contains("WARNING: DATA RACE"));
+}
+
+#[test]
+fn test_races_are_reported_on_the_program_stderr() {
+    let source = r#"
+    func main() {
+        let wg = newWaitGroup()
+        let count = 0
+        wg.add(1)
+        run func() {
+            count = 1
+            wg.done()
+        }()
+        count = 2
+        wg.wait()
+    }
+    "#;
+    let _turn = DETECTOR.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut interpreter = interpreter_for(&check_with_imports(IMPORTS, source).unwrap()).unwrap();
+    let stderr = interpreter.capture_stderr();
+
+    start_race_detector().unwrap();
+    let result = call_in(&mut interpreter, "main", &[]);
+    let races = stop_race_detector().expect("the detector was started");
+    result.unwrap();
+
+    assert_eq!(races.len(), 1);
+    assert_eq!(stderr.contents(), format!("{}\n", races[0]));
+}
+
+#[test]
+fn test_synchronized_accesses_are_not_reported() {
+    let source = r#"
+    func main(): int32 {
+        let wg = newWaitGroup()
+        let mu = newMutex()
+        let rw = newRwLock()
+        let count = 0
+        let total = 0
+        let i = 0
+        while i < 4 {
+            wg.add(1)
+            run func() {
+                mu.withLock(func() { count = count + 1 })
+                let guard = rw.acquire()
+                total = total + 1
+                guard.release()
+                wg.done()
+            }()
+            i = i + 1
+        }
+        wg.wait()
+
+        let message = 0
+        let done = make(chan_int32)
+        run func() {
+            message = 34
+            done <- 1
+        }()
+        <-done
+        return count + total + message
+    }
+    "#;
+    let (result, races) = run_with_race_detector(source).unwrap();
+    assert_eq!(result, RuntimeValue::Integer(42));
+    assert!(races.is_empty(), "unexpected races:\n{}", races.iter().map(|r| r.to_string()).collect::<Vec<_>>().join("\n"));
+}
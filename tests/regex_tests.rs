@@ -0,0 +1,155 @@
+//! Tests for std/regex and its compiled Pattern objects
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_function, check_with_imports, string};
+
+const IMPORTS: &str = "import { compile } from \"std/regex\"\n";
+
+/// Helper function to type check source code that imports std/regex
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Run `source` and call its function `name` with one string argument
+fn call(source: &str, name: &str, arg: &str) -> RuntimeValue {
+    call_function(&check_source(source).unwrap(), name, &[string(arg)]).unwrap()
+}
+
+const DATES: &str = r#"
+    func date(): Pattern {
+        return compile("(?P<year>\\d{4})-(\\d{2})-(?P<day>\\d{2})?").unwrap()
+    }
+
+    func matches(text: string): bool {
+        return date().match(text)
+    }
+
+    func first(text: string): string {
+        return date().find(text).unwrapOr("none")
+    }
+
+    func all(text: string): []string {
+        return date().findAll(text)
+    }
+
+    func reformat(text: string): string {
+        return date().replace(text, "$2/${day}/$year")
+    }
+
+    func month(text: string): string {
+        return date().captures(text).unwrap()[2]
+    }
+
+    func year(text: string): string {
+        return date().namedCaptures(text).unwrap()["year"]
+    }
+"#;
+
+#[test]
+fn test_pattern_methods() {
+    assert_eq!(call(DATES, "matches", "due 2024-05-01"), RuntimeValue::Bool(true));
+    assert_eq!(call(DATES, "matches", "due 05/01"), RuntimeValue::Bool(false));
+    assert_eq!(call(DATES, "first", "due 2024-05-01."), string("2024-05-01"));
+    assert_eq!(call(DATES, "first", "none due"), string("none"));
+    assert_eq!(
+        call(DATES, "all", "2024-05-01 to 2024-06-30"),
+        RuntimeValue::Slice(vec![string("2024-05-01"), string("2024-06-30")])
+    );
+    assert_eq!(call(DATES, "all", "never"), RuntimeValue::Slice(vec![]));
+    assert_eq!(
+        call(DATES, "reformat", "from 2024-05-01 to 2024-06-30"),
+        string("from 05/01/2024 to 06/30/2024")
+    );
+}
+
+#[test]
+fn test_capture_groups() {
+    assert_eq!(call(DATES, "month", "on 2024-05-01"), string("05"));
+    assert_eq!(call(DATES, "year", "on 2024-05-01"), string("2024"));
+
+    let source = r#"
+        func groups(text: string): Option<[]string> {
+            return compile("(\\w+)@(\\w+)?").unwrap().captures(text)
+        }
+    "#;
+    let RuntimeValue::Struct { fields, .. } = call(source, "groups", "mail ada@") else {
+        panic!("expected an Option");
+    };
+    // Groups without a part in the match are empty
+    assert_eq!(fields["value"], RuntimeValue::Slice(vec![string("ada@"), string("ada"), string("")]));
+    let RuntimeValue::Struct { fields, .. } = call(source, "groups", "no mail") else {
+        panic!("expected an Option");
+    };
+    assert_eq!(fields["isSome"], RuntimeValue::Bool(false));
+}
+
+#[test]
+fn test_compile_errors_give_the_column() {
+    let source = r#"
+        func attempt(pattern: string): string {
+            return compile(pattern).error
+        }
+    "#;
+    let cases = [
+        ("ab(c", "Regex Parse Error at column 3: unclosed group"),
+        ("[z-a]", "Regex Parse Error at column 2: invalid character class range, the start must be <= the end"),
+        ("a\\k", "Regex Parse Error at column 2: unrecognized escape sequence"),
+    ];
+    for (pattern, expected) in cases {
+        assert_eq!(call(source, "attempt", pattern), string(expected), "{}", pattern);
+    }
+}
+
+#[test]
+fn test_checker_errors() {
+    let cases = [
+        (
+            "func f(): any {\n    return compile(\"(?P<x>a\")\n}\n",
+            "Invalid pattern in call to 'compile': Regex Parse Error at column 1: unclosed group",
+        ),
+        (
+            "func f(): any {\n    return compile(1)\n}\n",
+            "Argument 1 to function 'compile': expected string, got int32",
+        ),
+        (
+            "func f(): any {\n    return compile(\"a\", \"b\")\n}\n",
+            "Function 'compile' expects 1 argument, got 2",
+        ),
+        (
+            "func f(): int32 {\n    return compile(\"a\").unwrap().match(\"a\")\n}\n",
+            "Cannot return bool from function expecting int32",
+        ),
+    ];
+    for (source, expected) in cases {
+        let error = check_source(source).unwrap_err();
+        assert!(error.to_string().contains(expected), "{}: {}", source, error);
+    }
+}
+
+#[test]
+fn test_compiling_a_pattern_again_hits_the_cache() {
+    use bulu::runtime::pattern_cache::pattern_cache_metrics;
+
+    let source = r#"
+    func twice(): (Pattern, Pattern) {
+        return (compile("cached-(\\d+)").unwrap(), compile("cached-(\\d+)").unwrap())
+    }
+    "#;
+    let program = check_source(source).unwrap();
+
+    // Other tests share the cache, so only look at how the counters move
+    let before = pattern_cache_metrics().regexes;
+    let handles = call_function(&program, "twice", &[]).unwrap();
+    let after = pattern_cache_metrics().regexes;
+    assert!(after.hits >= before.hits + 2, "{:?} then {:?}", before, after);
+
+    // Both compilations share one handle rather than leaking a new one each
+    match handles {
+        RuntimeValue::Tuple(handles) => assert_eq!(handles[0], handles[1]),
+        other => panic!("expected a tuple, got {:?}", other),
+    }
+}
@@ -0,0 +1,183 @@
+//! Tests for the generic Result<T, E> and Option<T> types
+
+mod common;
+
+use bulu::error::BuluError;
+use bulu::types::checker::TypeChecker;
+use bulu::types::primitive::RuntimeValue;
+use common::{assert_error, parse_source, run_main};
+
+/// Helper function to parse and type check source code
+fn check_source(source: &str) -> Result<(), BuluError> {
+    let program = parse_source(source)?;
+    let mut checker = TypeChecker::new();
+    checker.add_std_types();
+    checker.check(&program)
+}
+
+/// Helper function that expects a type error containing `expected`
+fn assert_type_error(source: &str, expected: &str) {
+    assert_error(check_source(source), expected);
+}
+
+const USERS: &str = r#"
+    struct User {
+        name: string
+        age: int32
+    }
+
+    func findUser(name: string): Result<User, string> {
+        return Ok(User{ name: name, age: 30 })
+    }
+
+    func missingUser(name: string): Result<User, string> {
+        return Err("no user named " + name)
+    }
+
+    func nickname(user: User): Option<string> {
+        return Some(user.name)
+    }
+"#;
+
+#[test]
+fn test_user_types_inside_result_and_option() {
+    let source = format!(
+        r#"{}
+    func main() {{
+        let result = findUser("ada")
+        let failed: bool = result.isError()
+        let user: User = result.unwrap()
+        let name: string = findUser("ada").unwrap().name
+        let message: string = missingUser("bob").error()
+        let fallback: User = missingUser("bob").unwrapOr(User{{ name: "guest", age: 0 }})
+        let nick: string = nickname(user).unwrapOr("anonymous")
+        let empty: Option<int32> = None
+        let present: bool = empty.isSome()
+    }}
+    "#,
+        USERS
+    );
+    assert!(check_source(&source).is_ok(), "{:?}", check_source(&source));
+
+    assert_type_error(
+        &format!("{}\nfunc main() {{\n    let age: int32 = findUser(\"ada\").unwrap()\n}}\n", USERS),
+        "Cannot assign struct User to variable of type int32",
+    );
+    assert_type_error(
+        &format!("{}\nfunc main() {{\n    let code: int32 = missingUser(\"bob\").error()\n}}\n", USERS),
+        "Cannot assign string to variable of type int32",
+    );
+    assert_type_error(
+        &format!("{}\nfunc main() {{\n    nickname(User{{ name: \"a\", age: 1 }}).unwrapOr(5)\n}}\n", USERS),
+        "Argument 1 to method 'unwrapOr': expected string, got int32",
+    );
+    assert_type_error(
+        &format!("{}\nfunc main() {{\n    findUser(\"ada\").isSome()\n}}\n", USERS),
+        "Method 'isSome' not found on Result<User, string>",
+    );
+}
+
+#[test]
+fn test_constructors_must_match_declared_types() {
+    assert_type_error(
+        "func parse(): Result<int32, string> {\n    return Ok(\"seven\")\n}\n",
+        "Result<string, any>",
+    );
+    assert_type_error(
+        "func parse(): Result<int32, string> {\n    return Err(404)\n}\n",
+        "Result<any, int32>",
+    );
+    assert_type_error(
+        "func main() {\n    let value: Option<string> = Some(1)\n}\n",
+        "Cannot assign Option<int32> to variable of type Option<string>",
+    );
+    assert_type_error(
+        "func main() {\n    let value: Result<int32> = Ok(1)\n}\n",
+        "Type 'Result' expects 2 type arguments, got 1",
+    );
+}
+
+#[test]
+fn test_map_is_generically_typed() {
+    let source = r#"
+    func double(n: int32): int32 {
+        return n * 2
+    }
+
+    func main() {
+        let parsed: Result<int32, string> = Ok(21)
+        let doubled: Result<int32, string> = parsed.map(double)
+        let label: Result<string, string> = parsed.map(func(n: int32): string { return "n" })
+        let length: Option<int32> = Some("four").map(func(s: string) len(s))
+    }
+    "#;
+    assert!(check_source(source).is_ok(), "{:?}", check_source(source));
+
+    assert_type_error(
+        r#"
+    func main() {
+        let parsed: Result<int32, string> = Ok(21)
+        let label: Result<int32, string> = parsed.map(func(n: int32): string { return "n" })
+    }
+    "#,
+        "Cannot assign Result<string, string> to variable of type Result<int32, string>",
+    );
+    assert_type_error(
+        r#"
+    func shout(s: string): string {
+        return s
+    }
+
+    func main() {
+        let parsed: Result<int32, string> = Ok(21)
+        parsed.map(shout)
+    }
+    "#,
+        "Argument 1 to method 'map': expected a function taking int32, got one taking string",
+    );
+}
+
+#[test]
+fn test_std_results_are_generic() {
+    let source = r#"
+    func main() {
+        let server: TcpServer = TcpServer.bind(NetAddr.localhost_ipv4(8080)).unwrap()
+        let connection: TcpConnection = server.accept().unwrap()
+        let failed: bool = server.accept().isError()
+    }
+    "#;
+    assert!(check_source(source).is_ok(), "{:?}", check_source(source));
+}
+
+#[test]
+fn test_result_and_option_at_runtime() {
+    let source = format!(
+        r#"{}
+    func main(): string {{
+        let user = findUser("ada").unwrap()
+        let missing = missingUser("bob")
+        let fallback = missing.unwrapOr(User{{ name: "guest", age: 0 }})
+        let none: Option<string> = None
+        return user.name + " " + missing.error() + " " + fallback.name + " " + none.unwrapOr("-")
+    }}
+    "#,
+        USERS
+    );
+    assert_eq!(
+        run_main(&source).unwrap(),
+        RuntimeValue::String("ada no user named bob guest -".to_string())
+    );
+
+    let source = r#"
+    func main(): int32 {
+        let mapped = Some(4).map(func(n: int32): int32 { return n * 10 })
+        let untouched: Result<int32, string> = Err("bad")
+        return mapped.unwrap() + untouched.map(func(n: int32): int32 { return n * 10 }).unwrapOr(2)
+    }
+    "#;
+    assert_eq!(run_main(source).unwrap(), RuntimeValue::Integer(42));
+
+    let error = run_main(&format!("{}\nfunc main() {{\n    return missingUser(\"bob\").unwrap()\n}}\n", USERS))
+        .unwrap_err();
+    assert!(error.to_string().contains("Attempted to unwrap error result"));
+}
@@ -0,0 +1,180 @@
+//! Tests for the cron and interval schedules of std/schedule
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_function, call_in, check_with_imports, interpreter_for};
+
+const IMPORTS: &str = "import { cron, every } from \"std/schedule\"\n";
+
+/// Helper function to type check source code that imports std/schedule
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Run `source` and call its function `name`
+fn call(source: &str, name: &str, args: &[RuntimeValue]) -> Result<RuntimeValue, BuluError> {
+    call_function(&check_source(source).unwrap(), name, args)
+}
+
+const SOURCE: &str = r#"
+    func interval(): any {
+        let poll = every(30).milliseconds()
+        let ticks = poll.channel()
+        let first: DateTime = <-ticks
+        let second: DateTime = <-ticks
+        poll.stop()
+        return (second.sub(first).milliseconds(), poll.toString(), cron("@daily").toString())
+    }
+
+    // Runs of a 60ms job every 20ms for 300ms
+    func overlapping(policy: string): int32 {
+        let runs = make(chan_int32, 100)
+        let poll = every(20).milliseconds()
+        poll.run(func(tick: any) {
+            runs <- 1
+            sleep(60)
+        }, policy)
+        sleep(300)
+        poll.stop()
+        sleep(150)
+        close(runs)
+
+        let total = 0
+        for done in runs {
+            total = total + done
+        }
+        return total
+    }
+
+    func failing() {
+        let poll = every(20).milliseconds()
+        poll.run(func(tick: any) {
+            let zero = 0
+            let ratio = 1 / zero
+        })
+        sleep(70)
+        poll.stop()
+        sleep(40)
+    }
+
+    func invalid(expression: string): any {
+        return cron(expression)
+    }
+"#;
+
+#[test]
+fn test_interval_ticks_arrive_on_the_channel() {
+    match call(SOURCE, "interval", &[]).unwrap() {
+        RuntimeValue::Tuple(values) => match values.as_slice() {
+            [RuntimeValue::Int64(apart), description, daily] => {
+                assert!((20..=100).contains(apart), "ticks {}ms apart", apart);
+                assert_eq!(description, &RuntimeValue::String("every 30ms".to_string()));
+                assert_eq!(daily, &RuntimeValue::String("@daily".to_string()));
+            }
+            other => panic!("unexpected values {:?}", other),
+        },
+        other => panic!("expected a tuple, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_overlap_policies() {
+    let runs = |policy: &str| match call(SOURCE, "overlapping", &[RuntimeValue::String(policy.to_string())]) {
+        Ok(RuntimeValue::Integer(runs)) => runs,
+        other => panic!("{}: {:?}", policy, other),
+    };
+    let skipped = runs("skip");
+    let queued = runs("queue");
+    let concurrent = runs("concurrent");
+    // A run takes three intervals, so skipping leaves about one in four ticks
+    assert!((2..=6).contains(&skipped), "skip: {}", skipped);
+    assert!((2..=8).contains(&queued), "queue: {}", queued);
+    assert!(concurrent >= 8 && concurrent > skipped, "concurrent: {} skip: {}", concurrent, skipped);
+
+    let error = call(SOURCE, "overlapping", &[RuntimeValue::String("parallel".to_string())]).unwrap_err();
+    assert!(error.to_string().contains("unknown overlap policy \"parallel\""), "{}", error);
+}
+
+#[test]
+fn test_skip_drops_every_tick_due_during_a_run() {
+    // How old its tick is when each run of a 50ms job every 20ms starts
+    let source = r#"
+    import { milliseconds, now } from "std/time"
+
+    func ages(policy: string): any {
+        let waited = milliseconds(0)
+        let runs = make(chan_int32, 100)
+        let poll = every(20).milliseconds()
+        poll.run(func(tick: DateTime) {
+            waited = waited.add(now().sub(tick))
+            runs <- 1
+            sleep(50)
+        }, policy)
+        sleep(300)
+        poll.stop()
+        sleep(100)
+        close(runs)
+
+        let total = 0
+        for done in runs {
+            total = total + done
+        }
+        return (waited.milliseconds(), total)
+    }
+    "#;
+    let average_age = |policy: &str| match call(source, "ages", &[RuntimeValue::String(policy.to_string())]) {
+        Ok(RuntimeValue::Tuple(values)) => match values.as_slice() {
+            [RuntimeValue::Int64(total), RuntimeValue::Integer(runs)] if *runs > 1 => total / runs,
+            other => panic!("{}: unexpected values {:?}", policy, other),
+        },
+        other => panic!("{}: {:?}", policy, other),
+    };
+    // A queued tick waits out the rest of the run it came due in
+    let skipped = average_age("skip");
+    let queued = average_age("queue");
+    assert!(skipped < 15, "skip: runs started on ticks {}ms old on average", skipped);
+    assert!(queued >= 20, "queue: runs started on ticks {}ms old on average", queued);
+}
+
+#[test]
+fn test_failed_runs_are_reported_on_stderr() {
+    let mut interpreter = interpreter_for(&check_source(SOURCE).unwrap()).unwrap();
+    let stderr = interpreter.capture_stderr();
+    call_in(&mut interpreter, "failing", &[]).unwrap();
+    let reported = stderr.contents();
+    let first = reported.lines().next().unwrap_or_default();
+    assert!(first.starts_with("Scheduled job error: "), "{:?}", reported);
+    assert!(first.contains("zero") && !first.contains("RuntimeError {"), "{:?}", reported);
+}
+
+#[test]
+fn test_invalid_expressions() {
+    let error = call(SOURCE, "invalid", &[RuntimeValue::String("0 25 * * *".to_string())]).unwrap_err();
+    assert!(error.to_string().contains("hour 25 is out of range 0-23"), "{}", error);
+
+    let cases = [
+        (
+            "func f() {\n    cron(\"*/5 * * *\")\n}\n",
+            "a cron expression has 5 or 6 fields, '*/5 * * *' has 4 in call to 'cron'",
+        ),
+        (
+            "func f() {\n    cron(\"0 0 31 feb *\")\n}\n",
+            "'0 0 31 feb *' never matches a date in call to 'cron'",
+        ),
+        (
+            "func f() {\n    every(0).seconds()\n}\n",
+            "The count passed to 'every' must be positive",
+        ),
+        (
+            "func f() {\n    every(\"5m\")\n}\n",
+            "Argument 1 to function 'every': expected integer, got string",
+        ),
+    ];
+    for (source, expected) in cases {
+        let error = check_source(source).unwrap_err();
+        assert!(error.to_string().contains(expected), "{}: {}", source, error);
+    }
+}
@@ -0,0 +1,155 @@
+//! Tests for the `set<T>` collection type
+
+mod common;
+
+use bulu::runtime::builtins::builtin_hash;
+use bulu::runtime::interpreter::Interpreter;
+use bulu::runtime::sets;
+use bulu::std::json::Json;
+use bulu::types::primitive::RuntimeValue;
+use common::{check_source, run_main};
+
+fn ints(values: &[i64]) -> RuntimeValue {
+    RuntimeValue::Set(values.iter().map(|v| RuntimeValue::Integer(*v)).collect())
+}
+
+#[test]
+fn test_set_literals_methods_and_iteration() {
+    let source = r#"
+func main(): any {
+    let s: set<int32> = set{3, 1, 2, 3}
+    let added = s.add(5)
+    let again = s.add(1)
+    let removed = s.remove(2)
+    let t = set<int32>{1, 9}
+    let order = 0
+    for x in s.union(t) {
+        order = order * 10 + x
+    }
+    return (s, s.intersection(t), s.difference(t), order, added, again, removed, s.contains(5), len(s))
+}
+"#;
+    let RuntimeValue::Tuple(values) = run_main(source).unwrap() else {
+        panic!("expected a tuple");
+    };
+    assert_eq!(values[0], ints(&[1, 3, 5]));
+    assert_eq!(values[1], ints(&[1]));
+    assert_eq!(values[2], ints(&[3, 5]));
+    // Iteration is in ascending element order
+    assert_eq!(values[3], RuntimeValue::Integer(1359));
+    assert_eq!(
+        &values[4..8],
+        &[RuntimeValue::Bool(true), RuntimeValue::Bool(false), RuntimeValue::Bool(true), RuntimeValue::Bool(true)]
+    );
+    assert_eq!(values[8], RuntimeValue::Int32(3));
+}
+
+#[test]
+fn test_sets_in_compiled_code() {
+    let source = r#"
+func main(): any {
+    let s = set{"b", "a", "c"}
+    s.remove("b")
+    s.add("d")
+    let seen = 0
+    for x in s {
+        seen = seen + 1
+    }
+    return (s, s.isSubset(set{"a", "c", "d", "e"}), seen)
+}
+"#;
+    let result = Interpreter::new().execute_source(source).unwrap();
+    let strings = |values: &[&str]| {
+        RuntimeValue::Set(values.iter().map(|v| RuntimeValue::String(v.to_string())).collect())
+    };
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![strings(&["a", "c", "d"]), RuntimeValue::Bool(true), RuntimeValue::Int64(3)])
+    );
+}
+
+#[test]
+fn test_checker_rejects_misuse() {
+    let cases = [
+        ("func main() { let s = set{1, \"two\"} }", "Set elements must have the same type"),
+        ("func main() { let s = set<string>{1} }", "expected string, got int"),
+        ("func f() {}\nfunc main() { let s = set{f} }", "Set elements must be hashable"),
+        ("func main() { let s = set{1, 2}\n s.add(\"x\") }", "Argument 1 to method 'add'"),
+        ("func main() { let s = set{1}\n let u = s.union([1]) }", "Argument 1 to method 'union'"),
+        ("func main() { let s = set{1}\n s.push(2) }", "Method 'push' not found on type set<"),
+    ];
+    for (source, expected) in cases {
+        let error = check_source(source).expect_err(source);
+        assert!(error.to_string().contains(expected), "{}: {}", source, error);
+    }
+
+    // An empty literal fits any declared set type, and `set` is still an ordinary name
+    assert!(check_source("func main() { let s: set<string> = set{} }").is_ok());
+    let result = check_source("func main() {\n let set = [1, 2]\n for x in set {\n }\n}");
+    assert!(result.is_ok(), "{:?}", result.err());
+}
+
+#[test]
+fn test_elements_are_distinct_by_value() {
+    // Integers of different widths are the same element
+    let set = sets::from_elements(vec![
+        RuntimeValue::Int64(2),
+        RuntimeValue::Int32(2),
+        RuntimeValue::UInt8(1),
+    ])
+    .unwrap();
+    assert_eq!(set, vec![RuntimeValue::UInt8(1), RuntimeValue::Int64(2)]);
+
+    let error = sets::from_elements(vec![RuntimeValue::Channel(1)]).unwrap_err();
+    assert!(error.to_string().contains("set element of unhashable type channel"), "{}", error);
+
+    // Equal sets hash equally however they were built
+    let a = RuntimeValue::Set(sets::from_elements(vec![RuntimeValue::Int32(2), RuntimeValue::Int32(1)]).unwrap());
+    let b = RuntimeValue::Set(sets::from_elements(vec![RuntimeValue::Int64(1), RuntimeValue::Int64(2)]).unwrap());
+    assert_eq!(builtin_hash(&[a]).unwrap(), builtin_hash(&[b]).unwrap());
+}
+
+#[test]
+fn test_sets_encode_to_json_as_arrays() {
+    let source = r#"
+func main(): any {
+    return {tags: set{"b", "a", "b"}}
+}
+"#;
+    let value = run_main(source).unwrap();
+    let json = Json::from_runtime_value(&value).unwrap();
+    assert_eq!(Json::stringify(&json), r#"{"tags":["a","b"]}"#);
+}
+
+#[test]
+fn test_add_and_remove_update_variables_and_captures() {
+    let source = r#"
+func main(): any {
+    let s: set<int32> = set{0}
+    let i = 0
+    while i < 20 {
+        s.add(i)
+        s.add(i - 10)
+        i = i + 1
+    }
+    i = 0
+    while i < 20 {
+        s.remove(i)
+        i = i + 2
+    }
+    let captured: set<int32> = set{1}
+    let reset = () => { captured = set{0} }
+    let add = (x: int32) => { captured.add(x) }
+    reset()
+    add(7)
+    add(7)
+    return (s, len(s), captured)
+}
+"#;
+    let RuntimeValue::Tuple(values) = run_main(source).unwrap() else {
+        panic!("expected a tuple");
+    };
+    assert_eq!(values[0], ints(&[-10, -9, -8, -7, -6, -5, -4, -3, -2, -1, 1, 3, 5, 7, 9, 11, 13, 15, 17, 19]));
+    assert_eq!(values[1], RuntimeValue::Int32(20));
+    assert_eq!(values[2], ints(&[0, 7]));
+}
@@ -0,0 +1,147 @@
+//! Tests for the simplification pass run before the AST interpreter
+
+use bulu::ast::*;
+use bulu::lexer::Lexer;
+use bulu::parser::Parser;
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use bulu::runtime::simplify::simplify;
+use bulu::types::primitive::RuntimeValue;
+
+fn simplified(source: &str) -> Program {
+    let tokens = Lexer::new(source).tokenize().unwrap();
+    let mut program = Parser::new(tokens).parse().unwrap();
+    simplify(&mut program);
+    program
+}
+
+fn body<'a>(program: &'a Program, name: &str) -> &'a [Statement] {
+    program
+        .statements
+        .iter()
+        .find_map(|statement| match statement {
+            Statement::FunctionDecl(decl) if decl.name == name => Some(&decl.body.statements[..]),
+            _ => None,
+        })
+        .expect("function exists")
+}
+
+fn initializer(statement: &Statement) -> &Expression {
+    match statement {
+        Statement::VariableDecl(decl) => decl.initializer.as_ref().expect("initializer"),
+        other => panic!("expected a variable declaration, got {:?}", other),
+    }
+}
+
+fn literal(expr: &Expression) -> &LiteralValue {
+    match expr {
+        Expression::Literal(literal) => &literal.value,
+        other => panic!("expected a literal, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_fold_literal_operators() {
+    let program = simplified(
+        r#"func main() {
+    let n = 0
+    let a = (1 + 2) * 3
+    let b = "a" + "b"
+    let c = !(1 < 2) || false
+    let d = (n)
+    let e = n + 2 * 4
+    let f = 1 / 0
+}"#,
+    );
+    let body = body(&program, "main");
+    assert_eq!(literal(initializer(&body[1])), &LiteralValue::Integer(9));
+    assert_eq!(literal(initializer(&body[2])), &LiteralValue::String("ab".to_string()));
+    assert_eq!(literal(initializer(&body[3])), &LiteralValue::Boolean(false));
+    assert!(matches!(initializer(&body[4]), Expression::Identifier(ident) if ident.name == "n"));
+    match initializer(&body[5]) {
+        Expression::Binary(binary) => assert_eq!(literal(&binary.right), &LiteralValue::Integer(8)),
+        other => panic!("expected a binary expression, got {:?}", other),
+    }
+    // Left for the interpreter to report
+    assert!(matches!(initializer(&body[6]), Expression::Binary(_)));
+}
+
+#[test]
+fn test_prune_constant_branches() {
+    let program = simplified(
+        r#"func main() {
+    if 1 > 2 {
+        println("never")
+    }
+    if true {
+        println("always")
+    } else {
+        println("never")
+    }
+    if false {
+        println("never")
+    } else if 2 > 1 {
+        println("else")
+    }
+    while false {
+        println("never")
+    }
+    let x = 1
+    if x > 0 {
+        if false {
+            println("never")
+        }
+    }
+}"#,
+    );
+    let body = body(&program, "main");
+    assert_eq!(body.len(), 4);
+    assert!(matches!(&body[0], Statement::Block(block) if block.statements.len() == 1));
+    assert!(matches!(&body[1], Statement::Block(block) if block.statements.len() == 1));
+    assert!(matches!(&body[2], Statement::VariableDecl(_)));
+    match &body[3] {
+        Statement::If(stmt) => assert!(stmt.then_branch.statements.is_empty()),
+        other => panic!("expected an if statement, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_hoist_loop_invariant_literals() {
+    let source = r#"func main(): int32 {
+    let total = 0
+    let i = 0
+    while i < 3 {
+        let xs = [1, 2, 3]
+        for x in [4, 5] {
+            total = total + xs[i] * x
+        }
+        let f = func(): int32 { return [6, 7][0] }
+        let ys = [i, 1]
+        i = i + 1
+    }
+    return total
+}"#;
+    let program = simplified(source);
+    let body = body(&program, "main");
+
+    // Both literals are built once, before the outermost loop
+    assert_eq!(body.len(), 6);
+    assert!(matches!(initializer(&body[2]), Expression::Array(array) if array.elements.len() == 3));
+    assert!(matches!(initializer(&body[3]), Expression::Array(array) if array.elements.len() == 2));
+    let Statement::While(stmt) = &body[4] else {
+        panic!("expected the loop after the hoisted literals");
+    };
+    assert!(matches!(initializer(&stmt.body.statements[0]), Expression::Identifier(_)));
+    match &stmt.body.statements[1] {
+        Statement::For(inner) => assert!(matches!(inner.iterable, Expression::Identifier(_))),
+        other => panic!("expected a for loop, got {:?}", other),
+    }
+    // Lambda bodies and literals with variables stay in place
+    assert!(matches!(initializer(&stmt.body.statements[2]), Expression::Lambda(_)));
+    assert!(matches!(initializer(&stmt.body.statements[3]), Expression::Array(_)));
+
+    let mut interpreter = AstInterpreter::new();
+    interpreter.execute_program(&program).unwrap();
+    let main_func = interpreter.get_function_definition("main").expect("main should be defined");
+    let result = interpreter.call_user_function(&main_func, &[]).unwrap();
+    assert_eq!(result, RuntimeValue::Integer(54));
+}
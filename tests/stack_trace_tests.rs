@@ -0,0 +1,55 @@
+//! Tests for the call stacks runtime errors are reported with
+
+mod common;
+
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use common::check_with_imports;
+
+/// Run `main`, returning the error it fails with, with its stack trace
+fn failure(source: &str, capture_arguments: bool) -> String {
+    let program = check_with_imports("", source).unwrap();
+    let mut interpreter = AstInterpreter::new();
+    interpreter.set_capture_arguments(capture_arguments);
+    interpreter.execute_program(&program).unwrap();
+    let main_func = interpreter.get_function_definition("main").unwrap();
+    let error = interpreter.call_user_function(&main_func, &[]).unwrap_err();
+    assert!(interpreter.call_stack().is_empty());
+    interpreter.with_stack_trace(error).to_string()
+}
+
+const DIVIDE: &str = r#"struct Ledger {
+    name: string
+    totals: [5]int32
+
+    func share(parts: int32): int32 {
+        return divide(this.totals[0], parts)
+    }
+}
+
+func divide(a: int32, b: int32): int32 {
+    return a / b
+}
+
+func main() {
+    let ledger = Ledger{name: "q3", totals: [70, 80, 90, 100, 110]}
+    let ok = divide(10, 2)
+    ledger.share(0)
+}
+"#;
+
+#[test]
+fn test_trace_has_frames_innermost_first() {
+    let message = failure(DIVIDE, false);
+    let trace = message.split_once("\nStack trace:").expect(&message).1;
+    assert_eq!(
+        trace,
+        "\n  at divide (line 6, column 16)\n  at Ledger.share (line 17, column 5)\n  at main (line 14, column 1)"
+    );
+}
+
+#[test]
+fn test_trace_summarises_arguments() {
+    let message = failure(DIVIDE, true);
+    assert!(message.contains("\n  at divide(a=70, b=0) (line 6, column 16)"), "{}", message);
+    assert!(message.contains("\n  at Ledger.share(parts=0) (line 17, column 5)"), "{}", message);
+}
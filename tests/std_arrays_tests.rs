@@ -0,0 +1,232 @@
+//! Tests for the std/arrays algorithms: sorting, searching, dedup, chunking, flattening, shuffling
+//! and the functional helpers
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_main, check_with_imports};
+
+const IMPORTS: &str =
+    "import { sort, sortStable, binarySearch, dedup, chunk, flatten, shuffle, map, filter, reduce, zip } from \"std/arrays\"\n";
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Helper function to type check and run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(source)?)
+}
+
+fn ints(values: &[i64]) -> RuntimeValue {
+    RuntimeValue::Array(values.iter().map(|v| RuntimeValue::Integer(*v)).collect())
+}
+
+#[test]
+fn test_sort_with_comparator() {
+    let ascending = run_main(
+        r#"
+    func main() {
+        return sort([5, 3, 9, 1, 7], func(a: int32, b: int32): int32 { return a - b })
+    }
+    "#,
+    )
+    .unwrap();
+    assert_eq!(ascending, ints(&[1, 3, 5, 7, 9]));
+
+    let descending = run_main(
+        r#"
+    func main() {
+        let byDesc = (a: int32, b: int32) => b - a
+        return sort([5, 3, 9, 1, 7], byDesc)
+    }
+    "#,
+    )
+    .unwrap();
+    assert_eq!(descending, ints(&[9, 7, 5, 3, 1]));
+}
+
+#[test]
+fn test_sort_stable_keeps_equal_elements_in_order() {
+    let sorted = run_main(
+        r#"
+    func main() {
+        // Only the tens digit is compared
+        return sortStable([31, 12, 35, 17, 33], (a: int32, b: int32) => a / 10 - b / 10)
+    }
+    "#,
+    )
+    .unwrap();
+    assert_eq!(sorted, ints(&[12, 17, 31, 35, 33]));
+}
+
+#[test]
+fn test_sort_survives_an_inconsistent_comparator() {
+    // Claiming every element is greater than every other is no total order
+    let values: Vec<i64> = (0..60).map(|i| (i * 37) % 61).collect();
+    let list = values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ");
+    let source = format!("func main() {{ return sort([{}], (a: int32, b: int32) => 1) }}", list);
+    let RuntimeValue::Array(mut sorted) = run_main(&source).unwrap() else {
+        panic!("sort should return an array");
+    };
+    sorted.sort_by_key(|value| match value {
+        RuntimeValue::Integer(value) => *value,
+        other => panic!("unexpected element {:?}", other),
+    });
+    let mut expected = values.clone();
+    expected.sort();
+    assert_eq!(RuntimeValue::Array(sorted), ints(&expected));
+}
+
+#[test]
+fn test_binary_search_dedup_chunk_and_flatten() {
+    assert_eq!(
+        run_main("func main() { return binarySearch([1, 3, 5, 7, 9], 7) }").unwrap(),
+        RuntimeValue::Int32(3)
+    );
+    assert_eq!(
+        run_main("func main() { return binarySearch([1, 3, 5, 7, 9], 4) }").unwrap(),
+        RuntimeValue::Int32(-1)
+    );
+    assert_eq!(
+        run_main("func main() { return dedup([1, 1, 2, 2, 3, 1]) }").unwrap(),
+        ints(&[1, 2, 3, 1])
+    );
+    assert_eq!(
+        run_main("func main() { return chunk([1, 2, 3, 4, 5], 2) }").unwrap(),
+        RuntimeValue::Array(vec![ints(&[1, 2]), ints(&[3, 4]), ints(&[5])])
+    );
+    assert_eq!(
+        run_main("func main() { return flatten([[1, 2], [3], [4, 5]]) }").unwrap(),
+        ints(&[1, 2, 3, 4, 5])
+    );
+
+    let error = run_main("func main() { return chunk([1, 2], 0) }").unwrap_err();
+    assert!(error.to_string().contains("size must be positive"));
+}
+
+#[test]
+fn test_shuffle_is_seeded() {
+    let first = run_main("func main() { return shuffle([1, 2, 3, 4, 5, 6, 7, 8], 42) }").unwrap();
+    let second = run_main("func main() { return shuffle([1, 2, 3, 4, 5, 6, 7, 8], 42) }").unwrap();
+    assert_eq!(first, second);
+
+    let RuntimeValue::Array(mut items) = first else {
+        panic!("shuffle should return an array");
+    };
+    items.sort_by_key(|item| match item {
+        RuntimeValue::Integer(i) => *i,
+        _ => 0,
+    });
+    assert_eq!(RuntimeValue::Array(items), ints(&[1, 2, 3, 4, 5, 6, 7, 8]));
+}
+
+#[test]
+fn test_std_arrays_type_checking() {
+    let source = r#"
+    func main() {
+        let nums = [3, 1, 2]
+        let sorted: [3]int32 = sort(nums, (a: int32, b: int32) => a - b)
+        let index: int32 = binarySearch(sorted, 2)
+        let chunks = chunk(nums, 2)
+        let flat = flatten(chunks)
+        let first: int32 = flat[0]
+    }
+    "#;
+    assert!(check_source(source).is_ok());
+
+    let error = check_source("func main() { sort(42, (a: int32, b: int32) => a - b) }").unwrap_err();
+    assert!(error.to_string().contains("expected array"));
+
+    let error = check_source("func main() { binarySearch([1, 2], \"x\") }").unwrap_err();
+    assert!(error.to_string().contains("Argument 2 to function 'binarySearch'"));
+
+    let error = check_source("func main() { shuffle([1, 2], \"seed\") }").unwrap_err();
+    assert!(error.to_string().contains("expected integer"));
+
+    let error = check_source("func main() { dedup([1, 2], 3) }").unwrap_err();
+    assert!(error.to_string().contains("expects 1 arguments, got 2"));
+}
+
+#[test]
+fn test_map_filter_reduce_and_zip() {
+    assert_eq!(
+        run_main("func main() { return map([1, 2, 3], (x: int32) => x * 10) }").unwrap(),
+        ints(&[10, 20, 30])
+    );
+    assert_eq!(
+        run_main("func main() { return filter([4, 1, 5, 2, 3], (x: int32) => x > 2) }").unwrap(),
+        ints(&[4, 5, 3])
+    );
+    assert_eq!(
+        run_main(
+            r#"
+    func main() {
+        let offset = 100
+        return reduce([1, 2, 3], offset, func(sum: int32, x: int32): int32 { return sum + x })
+    }
+    "#
+        )
+        .unwrap(),
+        RuntimeValue::Integer(106)
+    );
+    assert_eq!(
+        run_main("func main() { return zip([1, 2, 3], [\"a\", \"b\"]) }").unwrap(),
+        RuntimeValue::Array(vec![
+            RuntimeValue::Tuple(vec![RuntimeValue::Integer(1), RuntimeValue::String("a".to_string())]),
+            RuntimeValue::Tuple(vec![RuntimeValue::Integer(2), RuntimeValue::String("b".to_string())]),
+        ])
+    );
+
+    // A predicate whose type is unknown to the checker is checked when it runs
+    let error = run_main("func main() { let keep: any = (x: int32) => x\n return filter([1], keep) }").unwrap_err();
+    assert!(error.to_string().contains("predicate must return a bool"), "{}", error);
+}
+
+#[test]
+fn test_functional_helpers_keep_element_types() {
+    let source = r#"
+    func main() {
+        let nums = [3, 1, 2]
+        let labels: [3]string = map(nums, (x: int32) => "n" + string(x))
+        let first: string = labels[0]
+        let odd: [3]int32 = filter(nums, (x: int32) => x % 2 == 1)
+        let total: int64 = reduce(nums, int64(0), (sum: int64, x: int32) => sum + int64(x))
+        let pair = zip(labels, nums)[0]
+    }
+    "#;
+    check_source(source).unwrap();
+
+    for (source, message) in [
+        (
+            "func main() { map([1, 2], (a: int32, b: int32) => a) }",
+            "Argument 2 to function 'map': expected a function of one parameter",
+        ),
+        (
+            "func main() { filter([1, 2], (x: string) => true) }",
+            "Argument 2 to function 'filter': expected a function taking int32, got one taking string",
+        ),
+        (
+            "func main() { filter([1, 2], (x: int32) => x) }",
+            "expected a function returning bool, got one returning int32",
+        ),
+        (
+            "func main() { reduce([1, 2], 0, (sum: int32, x: int32) => \"s\") }",
+            "Argument 3 to function 'reduce': expected a function returning int32, got one returning string",
+        ),
+        (
+            "func main() { sort([1, 2], (a: int32, b: int32) => true) }",
+            "expected a function returning an integer, got one returning bool",
+        ),
+        ("func main() { zip([1, 2], 3) }", "Argument 2 to function 'zip': expected array"),
+        ("func main() { map([1, 2], 3) }", "Argument 2 to function 'map': expected mapping function"),
+    ] {
+        match check_source(source) {
+            Err(error) => assert!(error.to_string().contains(message), "{}: {}", source, error),
+            Ok(_) => panic!("expected '{}' to fail to check", source),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Tests for the byte slice accessors, varints and pack codec of std/binary
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_main, check_with_imports};
+
+const IMPORTS: &str = "import { readUint16LE, readUint32BE, readInt64LE, writeUint32BE, writeInt64LE, readFloat64BE, writeFloat64BE, appendUvarint, appendVarint, readUvarint, readVarint, pack, unpack, packedSize } from \"std/binary\"\n";
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Helper function to type check and run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(source)?)
+}
+
+fn bytes(values: &[u8]) -> RuntimeValue {
+    RuntimeValue::Slice(values.iter().map(|b| RuntimeValue::UInt8(*b)).collect())
+}
+
+#[test]
+fn test_fixed_width_accessors() {
+    let source = r#"
+    func main(): any {
+        let buf = b"\x00\x00\x00\x00\x00\x00\x00\x00"
+        buf = writeUint32BE(buf, 0, 258)
+        let be = readUint32BE(buf, 0)
+        let le = readUint16LE(buf, 2)
+        buf = writeInt64LE(buf, 0, -2)
+        let f = readFloat64BE(writeFloat64BE(buf, 0, 1.5), 0)
+        return (be, le, readInt64LE(buf, 0), f, buf)
+    }
+    "#;
+    let result = run_main(source).unwrap();
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::UInt32(258),
+            RuntimeValue::UInt16(0x0201),
+            RuntimeValue::Int64(-2),
+            RuntimeValue::Float64(1.5),
+            bytes(&[0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]),
+        ])
+    );
+}
+
+#[test]
+fn test_varints_and_codec() {
+    let source = r#"
+    func main(): any {
+        let buf = appendVarint(appendUvarint(b"", 300), -3)
+        let (big, size) = readUvarint(buf, 0)
+        let (small, _) = readVarint(buf, size)
+        let packet = pack("<HxI4s", 7, 65536, "ok")
+        return (buf, big, small, packet, unpack("<HxI4s", packet), packedSize("<HxI4s"))
+    }
+    "#;
+    let result = run_main(source).unwrap();
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![
+            bytes(&[0xac, 0x02, 0x05]),
+            RuntimeValue::UInt64(300),
+            RuntimeValue::Int64(-3),
+            bytes(&[7, 0, 0, 0, 0, 1, 0, b'o', b'k', 0, 0]),
+            RuntimeValue::Array(vec![
+                RuntimeValue::UInt16(7),
+                RuntimeValue::UInt32(65536),
+                RuntimeValue::String("ok".to_string()),
+            ]),
+            RuntimeValue::Int32(11),
+        ])
+    );
+}
+
+#[test]
+fn test_out_of_bounds_access_is_an_error() {
+    let error = run_main("func main() { let x = readUint32BE(b\"\\x01\\x02\", 0) }").unwrap_err();
+    assert!(error.to_string().contains("offset 0 out of bounds: need 4 bytes, slice has 2"), "{}", error);
+
+    let error = run_main("func main() { let x = writeUint32BE(b\"\\x00\\x00\\x00\\x00\", 0, -1) }").unwrap_err();
+    assert!(error.to_string().contains("value -1 out of range for uint32"), "{}", error);
+
+    let error = run_main("func main() { let x = unpack(\">Q\", b\"\\x00\", 0) }").unwrap_err();
+    assert!(error.to_string().contains("out of bounds"), "{}", error);
+}
+
+#[test]
+fn test_checker_rejects_misuse() {
+    let cases = [
+        ("func main() { let x = readUint32BE(\"abc\", 0) }", "expected byte slice, got string"),
+        ("func main() { let x = readUint32BE(b\"abcd\") }", "expects 2 arguments, got 1"),
+        ("func main() { let x = pack(\">HH\", 1) }", "packs 2 values, got 1"),
+        ("func main() { let x = pack(\">H?\", 1, 2) }", "Argument 3 to function 'pack': expected bool"),
+        ("func main() { let x = packedSize(\">Hz\") }", "unknown field code 'z'"),
+    ];
+    for (source, expected) in cases {
+        let error = check_source(source).expect_err(source);
+        assert!(error.to_string().contains(expected), "{}: {}", source, error);
+    }
+    assert!(check_source("func main() { let n: uint32 = readUint32BE(b\"abcd\", 0) }").is_ok());
+}
@@ -0,0 +1,85 @@
+//! Tests for the checksums and streaming hashers of std/checksum
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_main, check_with_imports};
+
+const IMPORTS: &str = "import { crc32, crc32c, xxhash64, newHasher } from \"std/checksum\"\n";
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Helper function to type check and run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(source)?)
+}
+
+#[test]
+fn test_one_shot_checksums() {
+    let source = r#"
+    func main(): any {
+        let a: uint32 = crc32("123456789")
+        let b = crc32c(b"123456789")
+        let c: uint64 = xxhash64("")
+        return (a, b, c, xxhash64("abc", 1))
+    }
+    "#;
+    let result = run_main(source).unwrap();
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::UInt32(0xCBF4_3926),
+            RuntimeValue::UInt32(0xE306_9283),
+            RuntimeValue::UInt64(0xEF46_DB37_51D8_E999),
+            RuntimeValue::UInt64(bulu::std::checksum::xxhash64(b"abc", 1)),
+        ])
+    );
+}
+
+#[test]
+fn test_streaming_hashers() {
+    let source = r#"
+    func main(): any {
+        let crc = newHasher("crc32")
+        crc.write("12345")
+        crc.write(b"6789")
+        let first = crc.sum()
+        crc.reset()
+        crc.write("abc")
+
+        let xx = newHasher("xxhash64", 7)
+        for chunk in ["Nobody inspects ", "the spammish ", "repetition"] {
+            xx.write(chunk)
+        }
+        return (first, crc.sum(), crc32("abc"), xx.sum(), xxhash64("Nobody inspects the spammish repetition", 7))
+    }
+    "#;
+    let result = run_main(source).unwrap();
+    let RuntimeValue::Tuple(values) = result else { panic!("expected a tuple, got {:?}", result) };
+    assert_eq!(values[0], RuntimeValue::UInt64(0xCBF4_3926));
+    let RuntimeValue::UInt32(expected) = values[2] else { panic!("expected a uint32, got {:?}", values[2]) };
+    assert_eq!(values[1], RuntimeValue::UInt64(expected as u64));
+    assert_eq!(values[3], values[4]);
+}
+
+#[test]
+fn test_checker_rejects_misuse() {
+    let cases = [
+        ("func main() { let x = crc32(42) }", "expected byte slice or string, got"),
+        ("func main() { let x = crc32(\"a\", 1) }", "expects 1 argument, got 2"),
+        ("func main() { let x = xxhash64(\"a\", \"seed\") }", "expected integer seed, got string"),
+        ("func main() { let h = newHasher(\"md5\") }", "Unknown checksum algorithm 'md5'"),
+    ];
+    for (source, expected) in cases {
+        let error = check_source(source).expect_err(source);
+        assert!(error.to_string().contains(expected), "{}: {}", source, error);
+    }
+
+    let error = run_main("func main() { let x = xxhash64(\"a\", -1) }").unwrap_err();
+    assert!(error.to_string().contains("checksum.xxhash64(): expected a uint64 seed"), "{}", error);
+}
@@ -0,0 +1,267 @@
+//! Tests for the deques, priority queues, ordered maps and linked maps and sets of std/collections
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_main, check_with_imports};
+
+const IMPORTS: &str = "import { newDeque, newPriorityQueue, newOrderedMap, newLinkedMap, newLinkedSet } from \"std/collections\"\n";
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Helper function to type check and run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(source)?)
+}
+
+fn ints(values: &[i64]) -> RuntimeValue {
+    RuntimeValue::Array(values.iter().map(|v| RuntimeValue::Integer(*v)).collect())
+}
+
+#[test]
+fn test_deque_operations_and_iteration() {
+    let source = r#"
+    func main(): any {
+        let d = newDeque<int32>()
+        d.pushBack(2)
+        d.pushBack(3)
+        d.pushFront(1)
+        let order = 0
+        for x in d {
+            order = order * 10 + x
+        }
+        let front = d.popFront().unwrapOr(0)
+        let back = d.popBack().unwrapOr(0)
+        let middle = d.get(0).unwrapOr(0)
+        d.clear()
+        return (order, front, back, middle, d.isEmpty(), d.popFront().isSome())
+    }
+    "#;
+    let result = run_main(source).unwrap();
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::Integer(123),
+            RuntimeValue::Integer(1),
+            RuntimeValue::Integer(3),
+            RuntimeValue::Integer(2),
+            RuntimeValue::Bool(true),
+            RuntimeValue::Bool(false),
+        ])
+    );
+}
+
+#[test]
+fn test_priority_queue_orders() {
+    let source = r#"
+    func main(): any {
+        let smallest = newPriorityQueue<int32>()
+        let largest = newPriorityQueue<int32>(func(a: int32, b: int32): bool { return a > b })
+        for x in [5, 1, 4, 2, 3] {
+            smallest.push(x)
+            largest.push(x)
+        }
+        let first = smallest.pop().unwrapOr(0)
+        return (first, smallest.toArray(), largest.peek().unwrapOr(0), largest.len())
+    }
+    "#;
+    let result = run_main(source).unwrap();
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::Integer(1),
+            ints(&[2, 3, 4, 5]),
+            RuntimeValue::Integer(5),
+            RuntimeValue::Int32(5),
+        ])
+    );
+}
+
+#[test]
+fn test_ordered_map_keeps_keys_sorted() {
+    let source = r#"
+    func main(): any {
+        let m = newOrderedMap<int32, int32>()
+        m.set(30, 3)
+        m.set(10, 1)
+        m.set(20, 2)
+        let sum = 0
+        for key, value in m {
+            sum = sum * 100 + key + value
+        }
+        m.delete(20)
+        return (sum, m.keys(), m.floorKey(25).unwrapOr(0), m.ceilingKey(25).unwrapOr(0), m.get(30).unwrapOr(0), m.has(20))
+    }
+    "#;
+    let result = run_main(source).unwrap();
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::Integer(112233),
+            ints(&[10, 30]),
+            RuntimeValue::Integer(10),
+            RuntimeValue::Integer(30),
+            RuntimeValue::Integer(3),
+            RuntimeValue::Bool(false),
+        ])
+    );
+}
+
+#[test]
+fn test_linked_map_iterates_in_insertion_order() {
+    let source = r#"
+    func main(): any {
+        let m = newLinkedMap<int32, int32>()
+        m.set(30, 3)
+        m.set(10, 1)
+        m.set(20, 2)
+        m.set(30, 4)
+        let sum = 0
+        for key, value in m {
+            sum = sum * 100 + key + value
+        }
+        m.delete(10)
+        m.set(5, 0)
+        return (sum, m.keys(), m.firstKey().unwrapOr(0), m.lastKey().unwrapOr(0), m.get(30).unwrapOr(0), m.has(10))
+    }
+    "#;
+    let result = run_main(source).unwrap();
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::Integer(341122),
+            ints(&[30, 20, 5]),
+            RuntimeValue::Integer(30),
+            RuntimeValue::Integer(5),
+            RuntimeValue::Integer(4),
+            RuntimeValue::Bool(false),
+        ])
+    );
+}
+
+#[test]
+fn test_linked_set_operations_keep_insertion_order() {
+    let source = r#"
+    func main(): any {
+        let a = newLinkedSet<int32>()
+        a.add(3)
+        a.add(1)
+        a.add(2)
+        let added = a.add(1)
+        let b = newLinkedSet<int32>()
+        b.add(2)
+        b.add(4)
+        b.add(3)
+        let order = 0
+        for x in a.union(b) {
+            order = order * 10 + x
+        }
+        let common = a.intersection(b)
+        return (order, common.toArray(), a.difference(b).toArray(), common.isSubset(b), a.isSubset(b), added, a.union(a).len())
+    }
+    "#;
+    let result = run_main(source).unwrap();
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::Integer(3124),
+            ints(&[3, 2]),
+            ints(&[1]),
+            RuntimeValue::Bool(true),
+            RuntimeValue::Bool(false),
+            RuntimeValue::Bool(false),
+            RuntimeValue::Int32(3),
+        ])
+    );
+}
+
+#[test]
+fn test_checker_rejects_misuse() {
+    let cases = [
+        ("func main() { let d = newDeque<int32>()\n d.pushBack(\"x\") }", "Argument 1 to method 'pushBack'"),
+        ("func main() { let d = newDeque<int32>()\n d.push(1) }", "Method 'push' not found"),
+        ("func main() { let m = newOrderedMap<string>() }", "expects 2 type arguments, got 1"),
+        ("func main() { let q = newPriorityQueue<int32>(1) }", "expected a comparator function"),
+        ("func main() { let m = newOrderedMap<string, int32>()\n m.set(1, 2) }", "Argument 1 to method 'set'"),
+        ("func main() { let m = newLinkedMap<string, int32>()\n m.floorKey(\"a\") }", "Method 'floorKey' not found"),
+        ("func main() { let s = newLinkedSet<int32>()\n s.add(\"x\") }", "Argument 1 to method 'add'"),
+        (
+            "func main() { let s = newLinkedSet<int32>()\n let t = newLinkedSet<string>()\n s.union(t) }",
+            "Argument 1 to method 'union'",
+        ),
+        ("func main() { let s = newLinkedSet<int32>()\n for k, v in newLinkedMap<string, int32>() { s.add(k) } }", "Argument 1 to method 'add'"),
+    ];
+    for (source, expected) in cases {
+        let error = check_source(source).expect_err(source);
+        assert!(error.to_string().contains(expected), "{}: {}", source, error);
+    }
+    assert!(check_source("func take(d: Deque<string>) {}\nfunc main() { take(newDeque<string>()) }").is_ok());
+    assert!(check_source("func take(s: LinkedSet<string>): int32 { return s.len() }\nfunc main() { take(newLinkedSet<string>()) }").is_ok());
+}
+
+#[test]
+fn test_free_releases_a_collection() {
+    let source = r#"
+    func main(): any {
+        let d = newDeque<int32>()
+        let q = newPriorityQueue<int32>()
+        let m = newOrderedMap<string, int32>()
+        d.pushBack(1)
+        q.push(2)
+        m.set("a", 3)
+        q.free()
+        m.free()
+        return d.len()
+    }
+    "#;
+    assert_eq!(run_main(source).unwrap(), RuntimeValue::Int32(1));
+
+    let cases = [
+        ("func main() { let d = newDeque<int32>()\n d.free()\n d.pushBack(1) }", "Invalid Deque handle"),
+        ("func main() { let q = newPriorityQueue<int32>()\n q.free()\n q.free() }", "Invalid PriorityQueue handle"),
+        ("func main() { let m = newOrderedMap<string, int32>()\n let n = m\n m.free()\n n.len() }", "Invalid OrderedMap handle"),
+    ];
+    for (source, expected) in cases {
+        let error = run_main(source).expect_err(source);
+        assert!(error.to_string().contains(expected), "{}: {}", source, error);
+    }
+}
+
+#[test]
+fn test_free_releases_linked_maps_and_sets() {
+    let source = r#"
+    func main(): any {
+        let m = newLinkedMap<string, int32>()
+        m.set("a", 1)
+        m.free()
+        let s = newLinkedSet<int32>()
+        let t = newLinkedSet<int32>()
+        s.add(1)
+        s.add(2)
+        t.add(2)
+        let both = s.intersection(t)
+        s.free()
+        let sizes = (both.len(), t.len())
+        both.free()
+        return sizes
+    }
+    "#;
+    assert_eq!(
+        run_main(source).unwrap(),
+        RuntimeValue::Tuple(vec![RuntimeValue::Int32(1), RuntimeValue::Int32(1)])
+    );
+
+    let cases = [
+        ("func main() { let m = newLinkedMap<string, int32>()\n m.free()\n m.set(\"a\", 1) }", "Invalid LinkedMap handle"),
+        ("func main() { let s = newLinkedSet<int32>()\n let t = newLinkedSet<int32>()\n t.free()\n s.union(t) }", "Invalid LinkedSet handle"),
+    ];
+    for (source, expected) in cases {
+        let error = run_main(source).expect_err(source);
+        assert!(error.to_string().contains(expected), "{}: {}", source, error);
+    }
+}
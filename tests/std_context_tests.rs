@@ -0,0 +1,93 @@
+//! Tests for cancellation, deadlines and values of std/context
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_main, check_with_imports};
+use std::time::{Duration, Instant};
+
+const IMPORTS: &str =
+    "import { background, current, withCancel, withTimeout, withValue } from \"std/context\"\n";
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Helper function to type check and run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(source)?)
+}
+
+#[test]
+fn test_goroutines_inherit_cancellation() {
+    let source = r#"
+    func main(): string {
+        let ctx = withCancel(background())
+        let results = make(chan_string, 1)
+        ctx.call(func() {
+            run func() {
+                let inherited = current()
+                <-inherited.done()
+                results <- inherited.err()
+            }()
+        })
+        ctx.cancel()
+        return <-results
+    }
+    "#;
+    assert_eq!(run_main(source).unwrap(), RuntimeValue::String("context canceled".to_string()));
+}
+
+#[test]
+fn test_blocking_operations_return_when_deadline_passes() {
+    let receive = r#"
+    func main() {
+        let never = make(chan_int32)
+        withTimeout(background(), 30).call(func() { <-never })
+    }
+    "#;
+    let sleep = r#"
+    func main() {
+        let ctx = withTimeout(background(), 30)
+        ctx.call(func() { sleep(5000) })
+    }
+    "#;
+    for source in [receive, sleep] {
+        let start = Instant::now();
+        let error = run_main(source).unwrap_err();
+        assert!(error.to_string().contains("context deadline exceeded"), "{}", error);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+}
+
+#[test]
+fn test_values_and_cancellation_flow_to_children() {
+    let source = r#"
+    func main(): [3]bool {
+        let parent = withValue(withCancel(background()), "user", "ada")
+        let child = withTimeout(parent, 60000)
+        let user: string = child.value("user")
+        let found = user == "ada"
+        let has_deadline = child.deadline() > parent.deadline()
+        parent.cancel()
+        return [found, has_deadline, child.isCancelled()]
+    }
+    "#;
+    let flags = [true, true, true].map(RuntimeValue::Bool).to_vec();
+    assert_eq!(run_main(source).unwrap(), RuntimeValue::Array(flags));
+
+    let source = "func main(): int64 {\n    return background().deadline()\n}\n";
+    assert_eq!(run_main(source).unwrap(), RuntimeValue::Int64(0));
+}
+
+#[test]
+fn test_misuse_is_reported() {
+    let error = check_source("func main() {\n    let ctx = background()\n    ctx.stop()\n}\n").unwrap_err();
+    assert!(error.to_string().contains("Method 'stop' not found in struct 'Context'"), "{}", error);
+
+    let error = check_source("func main() {\n    background().cancel()\n    withCancel(42)\n}\n").unwrap_err();
+    assert!(error.to_string().contains("expected struct Context, got int32"), "{}", error);
+}
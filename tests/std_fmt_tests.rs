@@ -0,0 +1,92 @@
+//! Tests for locale-aware number, percent and currency formatting in std/fmt
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_main, check_with_imports};
+
+const IMPORTS: &str = "import { formatNumber, formatFixed, formatPercent, formatCurrency } from \"std/fmt\"\n";
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Helper function to type check and run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(source)?)
+}
+
+/// Helper function to run a single expression as the body of `main`
+fn eval(expression: &str) -> String {
+    match run_main(&format!("func main() {{\n    return {}\n}}\n", expression)).unwrap() {
+        RuntimeValue::String(s) => s,
+        other => panic!("Expected string, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_format_number() {
+    assert_eq!(eval("formatNumber(1234567)"), "1,234,567");
+    assert_eq!(eval("formatNumber(-1234567)"), "-1,234,567");
+    assert_eq!(eval("formatNumber(1234.5)"), "1,234.5");
+    assert_eq!(eval("formatNumber(1234.567, 2)"), "1,234.57");
+    assert_eq!(eval("formatNumber(1234567, 2, \"de-DE\")"), "1.234.567,00");
+    assert_eq!(eval("formatNumber(1234567.5, \"fr-FR\")"), "1 234 567,5");
+    assert_eq!(eval("formatNumber(1234567, \"en-IN\")"), "12,34,567");
+    assert_eq!(eval("formatNumber(1234, \"es-ES\")"), "1234");
+}
+
+#[test]
+fn test_format_fixed_and_percent() {
+    assert_eq!(eval("formatFixed(1234.5, 2)"), "1234.50");
+    assert_eq!(eval("formatFixed(1234.5, 1, \"de_DE\")"), "1234,5");
+    assert_eq!(eval("formatFixed(-0.004, 2)"), "0.00");
+    assert_eq!(eval("formatPercent(0.256, 1)"), "25.6%");
+    assert_eq!(eval("formatPercent(0.256, 1, \"de-DE\")"), "25,6 %");
+    assert_eq!(eval("formatPercent(1.5)"), "150%");
+}
+
+#[test]
+fn test_format_currency() {
+    assert_eq!(eval("formatCurrency(1234.5, \"USD\")"), "$1,234.50");
+    assert_eq!(eval("formatCurrency(-1234.5, \"USD\")"), "-$1,234.50");
+    assert_eq!(eval("formatCurrency(1234.5, \"EUR\", \"de-DE\")"), "1.234,50 €");
+    assert_eq!(eval("formatCurrency(1234.5, \"JPY\", \"ja-JP\")"), "¥1,234");
+    assert_eq!(eval("formatCurrency(1234567, \"INR\", \"en-IN\")"), "₹12,34,567.00");
+
+    let error = run_main("func main() {\n    let code = \"XYZ\"\n    return formatCurrency(1.0, code)\n}\n").unwrap_err();
+    assert!(error.to_string().contains("fmt.formatCurrency(): unknown currency 'XYZ'"));
+    let error = run_main("func main() {\n    return formatNumber(1.0, 25)\n}\n").unwrap_err();
+    assert!(error.to_string().contains("fmt.formatNumber() decimals must be an integer from 0 to 20"));
+}
+
+#[test]
+fn test_fmt_type_checking() {
+    let source = r#"
+    func main() {
+        let a: string = formatNumber(1234)
+        let b: string = formatNumber(1.5, 2, "en-GB")
+        let c: string = formatPercent(0.5, "it-IT")
+        let d: string = formatCurrency(9.99, "EUR", "fr")
+    }
+    "#;
+    assert!(check_source(source).is_ok());
+
+    let error = check_source("func main() {\n    formatNumber(\"12\")\n}\n").unwrap_err();
+    assert!(error.to_string().contains("Argument 1 to function 'formatNumber': expected number, got string"));
+
+    let error = check_source("func main() {\n    formatFixed(1.5, 2.0)\n}\n").unwrap_err();
+    assert!(error.to_string().contains("Argument 2 to function 'formatFixed': expected integer, got float64"));
+
+    let error = check_source("func main() {\n    formatNumber(1.5, 2, \"xx-YY\")\n}\n").unwrap_err();
+    assert!(error.to_string().contains("Unknown locale 'xx-YY' in call to 'formatNumber'"));
+
+    let error = check_source("func main() {\n    formatCurrency(1.5, \"ABC\")\n}\n").unwrap_err();
+    assert!(error.to_string().contains("Unknown currency 'ABC' in call to 'formatCurrency'"));
+
+    let error = check_source("func main() {\n    formatCurrency(1.5)\n}\n").unwrap_err();
+    assert!(error.to_string().contains("Function 'formatCurrency' expects 2 to 3 arguments, got 1"));
+}
@@ -0,0 +1,149 @@
+//! Tests for message catalogs, plural rules and locale selection in std/i18n
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{assert_error, call_main, check_with_imports};
+
+const IMPORTS: &str =
+    "import { loadCatalog, loadCatalogFile, setLocale, locale, translate, pluralCategory } from \"std/i18n\"\n";
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Helper function that expects a type error containing `expected`
+fn assert_type_error(source: &str, expected: &str) {
+    assert_error(check_source(source), expected);
+}
+
+/// Helper function to type check and run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(source)?)
+}
+
+const CATALOGS: &str = r#"
+    func loadMessages() {
+        loadCatalog("en", "
+hello = Hello, { $name }!
+files = { $count ->
+    [0] No files
+    [one] One file
+   *[other] { $count } files
+}
+bye = Goodbye
+")
+        loadCatalog("ru", "
+files = { $count ->
+    [one] { $count } файл
+    [few] { $count } файла
+   *[many] { $count } файлов
+}
+")
+        loadCatalog("de", "hello = Hallo, { $name }!")
+    }
+"#;
+
+#[test]
+fn test_translate_with_plurals_and_placeholders() {
+    let source = format!(
+        r#"{}
+    func main(): string {{
+        loadMessages()
+        let english = translate("hello", {{ "name": "Ada" }}) + " " + translate("files", {{ "count": 0 }}) + ", " + translate("files", {{ "count": 1 }}) + ", " + translate("files", {{ "count": 1500 }})
+        setLocale("ru_RU")
+        let russian = translate("files", {{ "count": 1 }}) + ", " + translate("files", {{ "count": 3 }}) + ", " + translate("files", {{ "count": 11 }})
+        return english + " | " + russian + " | " + locale()
+    }}
+    "#,
+        CATALOGS
+    );
+    assert_eq!(
+        run_main(&source).unwrap(),
+        RuntimeValue::String(
+            "Hello, Ada! No files, One file, 1,500 files | 1 файл, 3 файла, 11 файлов | ru-RU".to_string()
+        )
+    );
+}
+
+#[test]
+fn test_locale_fallback_and_missing_messages() {
+    let source = format!(
+        r#"{}
+    func main(): string {{
+        loadMessages()
+        setLocale("de-AT")
+        return translate("hello", {{ "name": "Ada" }}) + " / " + translate("bye") + " / " + translate("missing.key") + " / " + translate("hello")
+    }}
+    "#,
+        CATALOGS
+    );
+    assert_eq!(
+        run_main(&source).unwrap(),
+        RuntimeValue::String("Hallo, Ada! / Goodbye / missing.key / Hallo, {$name}!".to_string())
+    );
+}
+
+#[test]
+fn test_plural_category_and_catalog_files() {
+    let path = std::env::temp_dir().join(format!("bulu_i18n_{}.ftl", std::process::id()));
+    std::fs::write(&path, "apples = { $n ->\n    [one] une pomme\n   *[other] { $n } pommes\n}\n").unwrap();
+
+    let source = format!(
+        r#"
+    func main(): string {{
+        loadCatalogFile("fr", {:?})
+        setLocale("fr")
+        return translate("apples", {{ "n": 0 }}) + ", " + translate("apples", {{ "n": 2 }}) + " " + pluralCategory(5, "ar") + " " + pluralCategory(1)
+    }}
+    "#,
+        path.to_str().unwrap()
+    );
+    let result = run_main(&source);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        result.unwrap(),
+        RuntimeValue::String("une pomme, 2 pommes few one".to_string())
+    );
+
+    let error = run_main("func main() {\n    let source = \"a = { $n\"\n    loadCatalog(\"en\", source)\n}\n").unwrap_err();
+    assert!(
+        error.to_string().contains("i18n.loadCatalog(): line 1: message 'a': expected '}'"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_i18n_type_checking() {
+    let source = r#"
+    func main() {
+        loadCatalog("en", "greeting = Hi { $name }")
+        setLocale("en-GB")
+        let current: string = locale()
+        let text: string = translate("greeting", { "name": "Bo" })
+        let category: string = pluralCategory(2.5)
+    }
+    "#;
+    assert!(check_source(source).is_ok(), "{:?}", check_source(source));
+
+    assert_type_error(
+        "func main() {\n    loadCatalog(\"en\", \"items = { $n ->\n  [one] one item\n  [other] items\n}\")\n}\n",
+        "Invalid message catalog in call to 'loadCatalog': line 1: message 'items': selector $n needs a default variant marked with '*'",
+    );
+    assert_type_error(
+        "func main() {\n    translate(\"hello\", 5)\n}\n",
+        "Argument 2 to function 'translate': expected map, got int32",
+    );
+    assert_type_error(
+        "func main() {\n    pluralCategory(\"one\")\n}\n",
+        "Argument 1 to function 'pluralCategory': expected number, got string",
+    );
+    assert_type_error(
+        "func main() {\n    setLocale()\n}\n",
+        "Function 'setLocale' expects 1 argument, got 0",
+    );
+}
@@ -0,0 +1,121 @@
+//! Tests for std/math: integer math, statistics, float utilities and float edge cases
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_main, check_with_imports};
+
+const IMPORTS: &str = "import { gcd, lcm, clamp, hypot, min, max, abs, isNaN, isInfinite, round, roundHalfEven, floor, mean, median, stddev } from \"std/math\"\n";
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Helper function to type check and run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(source)?)
+}
+
+/// Helper function to run a single expression as the body of `main`
+fn eval(expression: &str) -> RuntimeValue {
+    run_main(&format!("func main() {{\n    return {}\n}}\n", expression)).unwrap()
+}
+
+fn float(value: RuntimeValue) -> f64 {
+    match value {
+        RuntimeValue::Float64(f) => f,
+        other => panic!("Expected float64, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_integer_math() {
+    assert_eq!(eval("gcd(12, 18)"), RuntimeValue::Integer(6));
+    assert_eq!(eval("gcd(-12, 18)"), RuntimeValue::Integer(6));
+    assert_eq!(eval("lcm(4, 6)"), RuntimeValue::Integer(12));
+    assert_eq!(eval("lcm(0, 6)"), RuntimeValue::Integer(0));
+    assert_eq!(eval("clamp(15, 1, 10)"), RuntimeValue::Integer(10));
+    assert_eq!(eval("abs(-7)"), RuntimeValue::Integer(7));
+    assert_eq!(eval("min(2, 3)"), RuntimeValue::Integer(2));
+    assert_eq!(eval("max(2, 3.5)"), RuntimeValue::Float64(3.5));
+
+    let error = run_main("func main() {\n    return clamp(5, 10, 1)\n}\n").unwrap_err();
+    assert!(error.to_string().contains("math.clamp() lower bound is greater than upper bound"));
+}
+
+#[test]
+fn test_float_utilities() {
+    assert_eq!(eval("hypot(3.0, 4.0)"), RuntimeValue::Float64(5.0));
+    assert_eq!(eval("clamp(0.5, 1.0, 2.0)"), RuntimeValue::Float64(1.0));
+    assert_eq!(eval("round(2.5)"), RuntimeValue::Float64(3.0));
+    assert_eq!(eval("roundHalfEven(2.5)"), RuntimeValue::Float64(2.0));
+    assert_eq!(eval("roundHalfEven(3.5)"), RuntimeValue::Float64(4.0));
+    assert_eq!(eval("floor(-2.5)"), RuntimeValue::Float64(-3.0));
+    assert_eq!(eval("isNaN(0.0 / 0.0)"), RuntimeValue::Bool(true));
+    assert_eq!(eval("isNaN(1.0)"), RuntimeValue::Bool(false));
+    assert_eq!(eval("isInfinite(1.0 / 0.0)"), RuntimeValue::Bool(true));
+    assert!(float(eval("max(0.0 / 0.0, 1.0)")).is_nan());
+}
+
+#[test]
+fn test_statistics() {
+    assert_eq!(eval("mean([1, 2, 3, 4])"), RuntimeValue::Float64(2.5));
+    assert_eq!(eval("median([5.0, 1.0, 3.0])"), RuntimeValue::Float64(3.0));
+    assert_eq!(eval("median([4, 1, 3, 2])"), RuntimeValue::Float64(2.5));
+    let deviation = float(eval("stddev([2, 4, 4, 4, 5, 5, 7, 9])"));
+    assert!((deviation - 2.138089935299395).abs() < 1e-12);
+
+    let error = run_main("func main() {\n    return mean([])\n}\n").unwrap_err();
+    assert!(error.to_string().contains("math.mean() of an empty array"));
+    let error = run_main("func main() {\n    return stddev([1.0])\n}\n").unwrap_err();
+    assert!(error.to_string().contains("math.stddev() needs at least two values"));
+}
+
+#[test]
+fn test_float_edge_cases_in_arithmetic() {
+    // NaN compares unequal to everything, including itself
+    assert_eq!(eval("0.0 / 0.0 == 0.0 / 0.0"), RuntimeValue::Bool(false));
+    assert_eq!(eval("0.0 / 0.0 != 0.0 / 0.0"), RuntimeValue::Bool(true));
+    assert_eq!(eval("0.0 / 0.0 < 1.0"), RuntimeValue::Bool(false));
+    assert_eq!(eval("0.0 / 0.0 >= 1.0"), RuntimeValue::Bool(false));
+
+    // Negative zero equals zero but keeps its sign
+    let negative_zero = float(eval("-0.0"));
+    assert!(negative_zero == 0.0 && negative_zero.is_sign_negative());
+    assert_eq!(eval("-0.0 == 0.0"), RuntimeValue::Bool(true));
+    assert_eq!(eval("1.0 / -0.0"), RuntimeValue::Float64(f64::NEG_INFINITY));
+
+    // Integer division by zero is still an error
+    let error = run_main("func main() {\n    return 1 / 0\n}\n").unwrap_err();
+    assert!(error.to_string().contains("Division by zero"));
+}
+
+#[test]
+fn test_math_type_checking() {
+    let source = r#"
+    func main() {
+        let g: int32 = gcd(12, 18)
+        let c: int32 = clamp(15, 1, 10)
+        let f: float64 = clamp(1, 0.5, 2.0)
+        let h: float64 = hypot(3, 4)
+        let n: bool = isNaN(1.5)
+        let m: float64 = mean([1, 2, 3])
+    }
+    "#;
+    assert!(check_source(source).is_ok());
+
+    let error = check_source("func main() {\n    gcd(1.5, 2)\n}\n").unwrap_err();
+    assert!(error.to_string().contains("Argument 1 to function 'gcd': expected integer, got float64"));
+
+    let error = check_source("func main() {\n    let s: int32 = clamp(1, 0.5, 2.0)\n}\n").unwrap_err();
+    assert!(error.to_string().contains("Cannot assign float64 to variable of type int32"));
+
+    let error = check_source("func main() {\n    mean([\"a\", \"b\"])\n}\n").unwrap_err();
+    assert!(error.to_string().contains("Argument 1 to function 'mean': expected array of numbers"));
+
+    let error = check_source("func main() {\n    hypot(3.0)\n}\n").unwrap_err();
+    assert!(error.to_string().contains("Function 'hypot' expects 2 arguments, got 1"));
+}
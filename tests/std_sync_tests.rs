@@ -0,0 +1,103 @@
+//! Tests for the wait groups, mutexes, rwlocks and onces of std/sync
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_main, check_with_imports};
+
+const IMPORTS: &str = "import { newWaitGroup, newMutex, newRwLock, newOnce } from \"std/sync\"\n";
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Helper function to type check and run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(source)?)
+}
+
+#[test]
+fn test_wait_group_waits_for_goroutines() {
+    let source = r#"
+    func main(): int32 {
+        let wg = newWaitGroup()
+        let mu = newMutex()
+        let once = newOnce()
+        let results = make(chan_int32, 8)
+        let i = 0
+        while i < 4 {
+            wg.add(1)
+            run func() {
+                once.do(func() { results <- 100 })
+                mu.withLock(func() { results <- 1 })
+                wg.done()
+            }()
+            i = i + 1
+        }
+        wg.wait()
+        close(results)
+
+        let total = 0
+        for result in results {
+            total = total + result
+        }
+        return total
+    }
+    "#;
+    assert_eq!(run_main(source).unwrap(), RuntimeValue::Integer(104));
+}
+
+#[test]
+fn test_lock_guards_release_their_lock() {
+    let source = r#"
+    func main(): [4]bool {
+        let mu = newMutex()
+        let guard = mu.acquire()
+        let held = !mu.tryAcquire()
+        guard.release()
+
+        let rw = newRwLock()
+        let first = rw.acquireRead()
+        let second = rw.acquireRead()
+        let blocked = rw.tryAcquire()
+        first.release()
+        second.release()
+        let mutex_free = mu.tryAcquire()
+        let rw_free = rw.tryAcquire()
+        return [held, blocked, mutex_free, rw_free]
+    }
+    "#;
+    let flags = [true, false, true, true].map(RuntimeValue::Bool).to_vec();
+    assert_eq!(run_main(source).unwrap(), RuntimeValue::Array(flags));
+}
+
+#[test]
+fn test_with_lock_returns_the_function_result() {
+    let source = r#"
+    func main(): int32 {
+        let rw = newRwLock()
+        let once = newOnce()
+        let value: int32 = rw.withReadLock(func(): int32 { return 20 })
+        once.do(func() { value = value + 1 })
+        once.do(func() { value = value + 100 })
+        let locked: int32 = rw.withLock(func(): int32 { return 21 })
+        return value + locked
+    }
+    "#;
+    assert_eq!(run_main(source).unwrap(), RuntimeValue::Integer(42));
+}
+
+#[test]
+fn test_misuse_is_reported() {
+    let error = run_main("func main() {\n    let wg = newWaitGroup()\n    wg.done()\n}\n").unwrap_err();
+    assert!(error.to_string().contains("WaitGroup.done(): Negative WaitGroup counter"), "{}", error);
+
+    let error = run_main("func main() {\n    let mu = newMutex()\n    mu.release()\n}\n").unwrap_err();
+    assert!(error.to_string().contains("Mutex.release(): Unlock of unlocked lock"), "{}", error);
+
+    let error = check_source("func main() {\n    let wg = newWaitGroup()\n    wg.signal()\n}\n").unwrap_err();
+    assert!(error.to_string().contains("Method 'signal' not found in struct 'WaitGroup'"), "{}", error);
+}
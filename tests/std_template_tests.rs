@@ -0,0 +1,139 @@
+//! Tests for the std/template text templating module
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{assert_error, call_main, check_with_imports};
+
+const IMPORTS: &str = "import { render, renderHtml, renderFile } from \"std/template\"\n";
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Helper function that expects a type error containing `expected`
+fn assert_type_error(source: &str, expected: &str) {
+    assert_error(check_source(source), expected);
+}
+
+/// Helper function to type check and run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(source)?)
+}
+
+fn string(s: &str) -> RuntimeValue {
+    RuntimeValue::String(s.to_string())
+}
+
+#[test]
+fn test_render_with_maps_and_structs() {
+    let source = r#"
+    struct Page {
+        title: string
+        author: string
+    }
+
+    func main(): string {
+        let page = Page{ title: "todo", author: "" }
+        let list = { "items": ["write", "test"] }
+        let text = "{{ title | upper }} by {{ author | default(\"nobody\") }}:"
+        let items = "{{#each items as item}} {{ @index }}.{{ item }}{{/each}}"
+        return render(text, page) + render(items, list) + " / " + render("{{ user.name }} is {{ user.age }}", { "user": { "name": "ada", "age": 36 } })
+    }
+    "#;
+    assert_eq!(
+        run_main(source).unwrap(),
+        string("TODO by nobody: 0.write 1.test / ada is 36")
+    );
+}
+
+#[test]
+fn test_html_mode_escapes_values() {
+    let source = r#"
+    func main(): string {
+        let data = { "comment": "<script>alert('x')</script>", "trusted": "<em>ok</em>" }
+        return renderHtml("<p>{{ comment }}</p>{{ trusted | raw }}", data)
+    }
+    "#;
+    assert_eq!(
+        run_main(source).unwrap(),
+        string("<p>&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;</p><em>ok</em>")
+    );
+}
+
+#[test]
+fn test_custom_filters() {
+    let source = r#"
+    func shout(text: string): string {
+        return text + "!"
+    }
+
+    func wrap(text: string, tag: string): string {
+        return "<" + tag + ">" + text
+    }
+
+    func main(): string {
+        let filters = { "shout": shout, "wrap": wrap }
+        return renderHtml("{{ name | shout | wrap(\"b\") }}", { "name": "<hi>" }, filters)
+    }
+    "#;
+    assert_eq!(run_main(source).unwrap(), string("&lt;b&gt;&lt;hi&gt;!"));
+
+    // Templates built at runtime report unknown filters when they are rendered
+    let source = r#"
+    func main(): string {
+        let text = "{{ name | shout }}"
+        return render(text, { "name": "x" })
+    }
+    "#;
+    let error = run_main(source).unwrap_err();
+    assert!(error.to_string().contains("template.render(): line 1: unknown filter 'shout'"), "{}", error);
+}
+
+#[test]
+fn test_render_file() {
+    let directory = std::env::temp_dir().join(format!("bulu_template_{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let html = directory.join("greeting.html");
+    let text = directory.join("greeting.txt");
+    std::fs::write(&html, "<h1>{{ name }}</h1>\n").unwrap();
+    std::fs::write(&text, "<h1>{{ name }}</h1>\n").unwrap();
+
+    let source = format!(
+        "func main(): string {{\n    let data = {{ \"name\": \"a & b\" }}\n    return renderFile({:?}, data) + renderFile({:?}, data)\n}}\n",
+        html.to_str().unwrap(),
+        text.to_str().unwrap()
+    );
+    let result = run_main(&source);
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(result.unwrap(), string("<h1>a &amp; b</h1>\n<h1>a & b</h1>\n"));
+}
+
+#[test]
+fn test_literal_templates_are_checked() {
+    assert!(check_source("func main() {\n    let s: string = render(\"{{#if ok}}yes{{/if}}\", { \"ok\": true })\n}\n").is_ok());
+
+    assert_type_error(
+        "func main() {\n    render(\"{{#if ok}}yes\", { \"ok\": true })\n}\n",
+        "Invalid template in call to 'render': line 1: unclosed {{#if}} block",
+    );
+    assert_type_error(
+        "func main() {\n    renderHtml(\"{{ name | shout }}\", { \"name\": \"x\" })\n}\n",
+        "Unknown filter 'shout' in call to 'renderHtml'",
+    );
+    assert_type_error(
+        "func main() {\n    render(\"{{ name | truncate(\\\"x\\\") }}\", { \"name\": \"x\" })\n}\n",
+        "filter 'truncate' expects a non-negative integer",
+    );
+    assert_type_error(
+        "func main() {\n    render(\"{{ x }}\", 5)\n}\n",
+        "Argument 2 to function 'render': expected map or struct, got int32",
+    );
+    assert_type_error(
+        "func main() {\n    render(\"{{ x }}\")\n}\n",
+        "Function 'render' expects 2 to 3 arguments, got 1",
+    );
+}
@@ -0,0 +1,103 @@
+//! Tests for std/strings builders and in-place string appends
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_main, check_with_imports, string};
+
+const IMPORTS: &str = "import { newBuilder } from \"std/strings\"\n";
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Helper function to type check and run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(source)?)
+}
+
+#[test]
+fn test_builder_methods() {
+    let source = r#"
+    func main(): any {
+        let b = newBuilder()
+        b.write("héllo")
+        b.write(" ")
+        b.writeLine(42)
+        let n: int32 = b.len()
+        let first: string = b.toString()
+        let bytes = b.byteLen()
+        b.reset()
+        b.write(true)
+        return (first, n, bytes, b.toString())
+    }
+    "#;
+    let result = run_main(source).unwrap();
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![
+            string("héllo 42\n"),
+            RuntimeValue::Int32(9),
+            RuntimeValue::Int32(10),
+            string("true"),
+        ])
+    );
+}
+
+#[test]
+fn test_builder_method_types_are_checked() {
+    let error = check_source("func main() { let b = newBuilder()\n let n: string = b.len() }").unwrap_err();
+    assert!(error.to_string().contains("Cannot assign int32"), "{}", error);
+}
+
+#[test]
+fn test_appends_in_loops() {
+    let source = r#"
+    func main(): any {
+        let s = ""
+        let t = "<"
+        let i = 0
+        while i < 2000 {
+            s += "ab"
+            t = t + "x" + "y"
+            i = i + 1
+        }
+        let u = "a"
+        u = u + u + u
+        let n = 1
+        n += 2
+        n *= 5
+        return (len(s), len(t), u, n)
+    }
+    "#;
+    let result = run_main(source).unwrap();
+    assert_eq!(
+        result,
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::Int32(4000),
+            RuntimeValue::Int32(4001),
+            string("aaa"),
+            RuntimeValue::Integer(15),
+        ])
+    );
+}
+
+#[test]
+fn test_appends_keep_closure_semantics() {
+    // A variable shared with a closure is not extended behind the closure's back
+    let source = r#"
+    func main(): any {
+        let s = "a"
+        let add = func(x: string) { s = s + x }
+        add("b")
+        s = s + "c"
+        add("d")
+        return s
+    }
+    "#;
+    let result = run_main(source).unwrap();
+    assert_eq!(result, string("abcd"));
+}
@@ -0,0 +1,111 @@
+//! Tests for string search, UTF-8 decoding and slice equality in the runtime
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::testing::differential::{Backend, BackendResult, DifferentialRunner};
+use bulu::types::primitive::RuntimeValue;
+use common::{call_main, check_with_imports, string};
+
+const IMPORTS: &str = "import { contains, indexOf, lastIndexOf, count, fromBytes } from \"std/strings\"\n";
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Helper function to type check and run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    call_main(&check_source(source)?)
+}
+
+#[test]
+fn test_search_functions() {
+    let source = r#"
+    func main(): any {
+        let text = "größer als größte"
+        return (contains(text, "als"), indexOf(text, "größ"), lastIndexOf(text, "größ"), indexOf(text, "kleiner"), count(text, "ö"))
+    }
+    "#;
+    // Positions count characters, not bytes
+    assert_eq!(
+        run_main(source).unwrap(),
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::Bool(true),
+            RuntimeValue::Int32(0),
+            RuntimeValue::Int32(11),
+            RuntimeValue::Int32(-1),
+            RuntimeValue::Int32(2),
+        ])
+    );
+
+    let message = check_source("func f(): int32 {\n    return indexOf(\"a\", 1)\n}\n").unwrap_err().to_string();
+    assert!(message.contains("int32"), "{}", message);
+}
+
+#[test]
+fn test_from_bytes_validates_utf8() {
+    let source = r#"
+    func main(): any {
+        return (fromBytes(b"h\xc3\xa9").unwrap(), fromBytes(b"hi\xff!").error)
+    }
+    "#;
+    assert_eq!(
+        run_main(source).unwrap(),
+        RuntimeValue::Tuple(vec![string("hé"), string("invalid UTF-8 at byte 2")])
+    );
+}
+
+#[test]
+fn test_slice_equality() {
+    let source = r#"
+    func main(): any {
+        let a = b"abc"
+        let b = b"abc"
+        let c = b"ab"
+        let pairs = [("a", [1]), ("b", [2])]
+        return (a == b, a != b, a == c, a != c, pairs == [("a", [1]), ("b", [2])], pairs == [("a", [1]), ("b", [3])])
+    }
+    "#;
+    assert_eq!(
+        run_main(source).unwrap(),
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::Bool(true),
+            RuntimeValue::Bool(false),
+            RuntimeValue::Bool(false),
+            RuntimeValue::Bool(true),
+            RuntimeValue::Bool(true),
+            RuntimeValue::Bool(false),
+        ])
+    );
+
+    let runner = DifferentialRunner::new(vec![Backend::Interpreter, Backend::Vm]);
+    let program = r#"
+func describe(equal: bool): string {
+    return match equal {
+        true -> "equal"
+        _ -> "different"
+    }
+}
+
+func main() {
+    let a = [1, 2, 3]
+    println(describe(a == [1, 2, 3]))
+    println(describe(a == [3, 2, 1]))
+    println(describe(a == [1, 2]))
+    let nested = [[1, 2], [3]]
+    println(describe(nested == [[1, 2], [3]]))
+    println(describe(nested == [[1, 2], [4]]))
+    let words = ["a", "b"]
+    println(describe(words == ["a", "b"]))
+    println(describe(words != ["a", "c"]))
+}
+"#;
+    let comparison = runner.run_source("slices.bu", program);
+    assert!(comparison.agrees(), "{}", comparison.describe());
+    match comparison.result(Backend::Vm) {
+        Some(BackendResult::Ran(outcome)) => assert_eq!(outcome.stdout, "equal\ndifferent\ndifferent\nequal\ndifferent\nequal\nequal\n"),
+        other => panic!("expected the VM to run the program, got {:?}", other),
+    }
+}
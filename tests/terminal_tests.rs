@@ -0,0 +1,44 @@
+//! Tests for color control and terminal capability detection
+
+use bulu::terminal::{Capabilities, ColorChoice};
+
+fn terminal() -> Capabilities {
+    Capabilities {
+        stdout_is_terminal: true,
+        stderr_is_terminal: true,
+        term: Some("xterm-256color".to_string()),
+        no_color: false,
+        force_color: false,
+    }
+}
+
+#[test]
+fn test_auto_colors_only_capable_terminals() {
+    assert!(ColorChoice::Auto.should_color(&terminal()));
+
+    let redirected = [
+        Capabilities { stdout_is_terminal: false, ..terminal() },
+        Capabilities { stderr_is_terminal: false, ..terminal() },
+        Capabilities { no_color: true, ..terminal() },
+        Capabilities { term: Some("dumb".to_string()), ..terminal() },
+    ];
+    for capabilities in &redirected {
+        assert!(!ColorChoice::Auto.should_color(capabilities), "{:?}", capabilities);
+        assert!(ColorChoice::Always.should_color(capabilities), "{:?}", capabilities);
+    }
+
+    // CI systems force colors into their logs with CLICOLOR_FORCE
+    let forced = Capabilities { stdout_is_terminal: false, force_color: true, ..terminal() };
+    assert!(ColorChoice::Auto.should_color(&forced));
+    assert!(!ColorChoice::Never.should_color(&forced));
+}
+
+#[test]
+fn test_color_choice_parsing() {
+    for choice in [ColorChoice::Auto, ColorChoice::Always, ColorChoice::Never] {
+        assert_eq!(choice.to_string().parse::<ColorChoice>(), Ok(choice));
+    }
+    assert_eq!(ColorChoice::default(), ColorChoice::Auto);
+    let error = "sometimes".parse::<ColorChoice>().unwrap_err();
+    assert!(error.contains("expected one of auto, always, never"), "{}", error);
+}
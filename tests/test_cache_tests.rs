@@ -0,0 +1,88 @@
+//! Tests for skipping unaffected test files on incremental `lang test` runs
+
+use bulu::project::{create_project, Project};
+use bulu::testing::impact::TestImpact;
+use bulu::testing::{TestOptions, TestRunner};
+use std::fs;
+use tempfile::TempDir;
+
+fn new_project(temp_dir: &TempDir) -> Project {
+    create_project("app", Some(temp_dir.path())).unwrap();
+    let project = Project::load_from_path(temp_dir.path().join("app")).unwrap();
+    fs::write(project.src_dir.join("helper.bu"), "export func one(): int32 {\n    return 1\n}\n").unwrap();
+    fs::write(
+        project.src_dir.join("util.bu"),
+        "import { one } from \"./helper\"\n\nexport func two(): int32 {\n    return one() + one()\n}\n",
+    )
+    .unwrap();
+    fs::write(project.src_dir.join("other.bu"), "export func three(): int32 {\n    return 3\n}\n").unwrap();
+
+    let tests_dir = project.root.join("tests");
+    fs::create_dir_all(&tests_dir).unwrap();
+    fs::write(
+        tests_dir.join("util_test.bu"),
+        "import { two } from \"../src/util\"\nimport \"std/io\"\n\nfunc test_two() {\n    let x = two()\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        tests_dir.join("other_test.bu"),
+        "import { three } from \"../src/other\"\n\nfunc test_three() {\n    let x = three()\n}\n",
+    )
+    .unwrap();
+    project
+}
+
+fn run(project: &Project, incremental: bool) -> (usize, usize, usize) {
+    let options = TestOptions {
+        incremental,
+        ..TestOptions::default()
+    };
+    let result = TestRunner::new(project.clone(), options).run_tests().unwrap();
+    (result.total, result.passed, result.cached)
+}
+
+#[test]
+fn test_inputs_follow_imports_transitively() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = new_project(&temp_dir);
+
+    let mut impact = TestImpact::new(&project);
+    let names: Vec<String> = impact
+        .inputs(&project.root.join("tests").join("util_test.bu"))
+        .unwrap()
+        .iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(names.len(), 3, "{:?}", names);
+    for name in ["util_test.bu", "util.bu", "helper.bu"] {
+        assert!(names.contains(&name.to_string()), "{:?}", names);
+    }
+
+    fs::write(project.root.join("tests").join("broken_test.bu"), "import \"../src/missing\"\n").unwrap();
+    assert!(impact.inputs(&project.root.join("tests").join("broken_test.bu")).is_none());
+}
+
+#[test]
+fn test_unaffected_test_files_are_cached_passes() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = new_project(&temp_dir);
+    let test_files = project.test_files().unwrap().len();
+    assert_eq!(test_files, 2);
+
+    let (total, passed, cached) = run(&project, true);
+    assert_eq!((passed, cached), (total, 0));
+    assert!(project.target_dir.join("test").join("cache.toml").exists());
+
+    let (_, passed, cached) = run(&project, true);
+    assert_eq!(cached, passed);
+
+    // A change to a module imported through util.bu reruns only util_test.bu
+    fs::write(project.src_dir.join("helper.bu"), "export func one(): int32 {\n    return 2 - 1\n}\n").unwrap();
+    let (total, passed, cached) = run(&project, true);
+    assert_eq!(passed, total);
+    assert_eq!(cached, total / 2);
+
+    // --no-cache runs everything
+    let (_, _, cached) = run(&project, false);
+    assert_eq!(cached, 0);
+}
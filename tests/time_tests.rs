@@ -0,0 +1,148 @@
+//! Tests for the durations, instants, dates and timers of std/time
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::types::primitive::RuntimeValue;
+use common::{call_function, check_with_imports, string};
+
+const IMPORTS: &str = "import { milliseconds, seconds, minutes, parseDuration, instant, measure, utcNow, fromUnix, date, parse, parseRfc3339, after, tick } from \"std/time\"\n";
+
+/// Helper function to type check source code that imports std/time
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Run `source` and call its function `name` with one string argument
+fn call(source: &str, name: &str, arg: &str) -> RuntimeValue {
+    call_function(&check_source(source).unwrap(), name, &[string(arg)]).unwrap()
+}
+
+#[test]
+fn test_durations() {
+    let source = r#"
+        func sum(text: string): string {
+            return seconds(2).add(milliseconds(500)).toString()
+        }
+
+        func scaled(text: string): any {
+            let d = parseDuration(text).unwrap()
+            return (d.mul(3).toString(), d.div(2).milliseconds(), d.minutes(), d.compare(minutes(1)))
+        }
+
+        func invalid(text: string): string {
+            return parseDuration(text).error
+        }
+    "#;
+    assert_eq!(call(source, "sum", ""), string("2.5s"));
+    assert_eq!(
+        call(source, "scaled", "1m30s"),
+        RuntimeValue::Tuple(vec![
+            string("4m30s"),
+            RuntimeValue::Int64(45_000),
+            RuntimeValue::Float64(1.5),
+            RuntimeValue::Int32(1),
+        ])
+    );
+    assert_eq!(call(source, "invalid", "3d"), string("unknown unit \"d\" in duration \"3d\""));
+}
+
+#[test]
+fn test_dates() {
+    let source = r#"
+        func fields(text: string): any {
+            let t = parse(text, "%Y-%m-%d %H:%M:%S %z").unwrap()
+            return (t.year(), t.month(), t.day(), t.hour(), t.weekday(), t.yearDay(), t.offset())
+        }
+
+        func shifted(text: string): string {
+            let t = parseRfc3339(text).unwrap()
+            let utc = t.withOffset(0).unwrap()
+            return utc.add(minutes(90)).format("%Y-%m-%d %H:%M %:z") + " / " + utc.sub(fromUnix(0)).toString()
+        }
+
+        func built(text: string): any {
+            let t = date(2024, 2, 29, 13, 5, 9, 3600).unwrap()
+            return (t.toString(), t.unix(), date(2023, 2, 29).error, t.withOffset(90000).error)
+        }
+    "#;
+    assert_eq!(
+        call(source, "fields", "2024-03-01 23:30:00 -0500"),
+        RuntimeValue::Tuple(
+            [2024, 3, 1, 23, 5, 61, -18_000].into_iter().map(RuntimeValue::Int32).collect()
+        )
+    );
+    assert_eq!(
+        call(source, "shifted", "1970-01-02T01:00:00+01:00"),
+        string("1970-01-02 01:30 +00:00 / 24h0m0s")
+    );
+    assert_eq!(
+        call(source, "built", ""),
+        RuntimeValue::Tuple(vec![
+            string("2024-02-29T13:05:09+01:00"),
+            RuntimeValue::Int64(1_709_208_309),
+            string("2023-02-29 00:00:00 is not a valid date and time"),
+            string("offset of 90000 seconds is out of range, it must be within a day"),
+        ])
+    );
+}
+
+#[test]
+fn test_instants_and_timers() {
+    let source = r#"
+        func timed(text: string): any {
+            let start = instant()
+            let took = measure(func() { <-after(milliseconds(20)) })
+            return (took.compare(milliseconds(20)), start.elapsed().compare(took))
+        }
+
+        func ticks(text: string): any {
+            let ticker = tick(milliseconds(5))
+            let first: DateTime = <-ticker.channel()
+            let second: DateTime = <-ticker.channel()
+            ticker.stop()
+            return (second.compare(first), first.compare(utcNow()))
+        }
+    "#;
+    // The timer fired after its duration, and both clocks are monotonic
+    let RuntimeValue::Tuple(comparisons) = call(source, "timed", "") else {
+        panic!("expected a tuple");
+    };
+    assert!(comparisons.iter().all(|ordering| *ordering != RuntimeValue::Int32(-1)), "{:?}", comparisons);
+    // The second tick is later than the first, which is earlier than now
+    assert_eq!(
+        call(source, "ticks", ""),
+        RuntimeValue::Tuple(vec![RuntimeValue::Int32(1), RuntimeValue::Int32(-1)])
+    );
+}
+
+#[test]
+fn test_checker_errors() {
+    let cases = [
+        (
+            "func f(): any {\n    return seconds(\"2\")\n}\n",
+            "Argument 1 to function 'seconds': expected integer, got string",
+        ),
+        (
+            "func f(): any {\n    return after(5)\n}\n",
+            "Argument 1 to function 'after': expected Duration, got int32",
+        ),
+        (
+            "func f(): any {\n    return parse(\"2024\", \"%Y-%Q\")\n}\n",
+            "invalid time pattern \"%Y-%Q\" in call to 'parse'",
+        ),
+        (
+            "func f(): any {\n    return date(2024)\n}\n",
+            "Function 'date' expects 3 to 7 arguments, got 1",
+        ),
+        (
+            "func f(): string {\n    return parseDuration(\"1s\")\n}\n",
+            "Cannot return Result",
+        ),
+    ];
+    for (source, expected) in cases {
+        let error = check_source(source).unwrap_err();
+        assert!(error.to_string().contains(expected), "{}: {}", source, error);
+    }
+}
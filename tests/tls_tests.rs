@@ -0,0 +1,137 @@
+//! Tests for TLS connections and the HTTPS client
+//!
+//! `tests/fixtures/tls` holds a test CA (`ca.pem`) and a certificate it
+//! signed for `localhost` and `127.0.0.1` (`server.pem`, `server.key`).
+
+use bulu::std::http::HttpClient;
+use bulu::std::net::{NetAddr, TcpConnection, TcpServer};
+use bulu::std::tls::{TlsAcceptor, TlsOptions};
+use std::path::PathBuf;
+use std::thread::{self, JoinHandle};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls").join(name)
+}
+
+/// Bind a TLS server on a free local port
+fn tls_server() -> TcpServer {
+    let acceptor = TlsAcceptor::from_pem_files(fixture("server.pem"), fixture("server.key")).unwrap();
+    TcpServer::bind_tls(NetAddr::localhost_ipv4(0), acceptor).unwrap()
+}
+
+/// Read from `connection` until what was read ends with `end`
+fn read_until(connection: &mut TcpConnection, end: &[u8]) -> String {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !data.ends_with(end) {
+        let n = connection.read(&mut buf).unwrap();
+        assert!(n > 0, "peer closed before sending {:?}", end);
+        data.extend_from_slice(&buf[..n]);
+    }
+    String::from_utf8(data).unwrap()
+}
+
+/// Answer one connection by echoing a line back in upper case
+fn echo_once(server: TcpServer) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let Ok(mut connection) = server.accept() else {
+            return;
+        };
+        assert!(connection.is_tls());
+        let line = read_until(&mut connection, b"\n");
+        connection.write_all(line.to_uppercase().as_bytes()).unwrap();
+        connection.shutdown(std::net::Shutdown::Write).unwrap();
+    })
+}
+
+#[test]
+fn test_tls_round_trip() {
+    let server = tls_server();
+    let port = server.local_addr().port();
+    let handle = echo_once(server);
+
+    let options = TlsOptions::new().with_ca_file(fixture("ca.pem"));
+    let mut connection = TcpConnection::connect_tls(NetAddr::new_domain("localhost".to_string(), port), &options).unwrap();
+    assert!(connection.is_tls());
+    connection.write_all(b"hello over tls\n").unwrap();
+    let mut reply = String::new();
+    connection.read_to_string(&mut reply).unwrap();
+    assert_eq!(reply, "HELLO OVER TLS\n");
+    handle.join().unwrap();
+
+    // Cloning would let two handles drive one session
+    assert!(connection.try_clone().is_err());
+}
+
+#[test]
+fn test_tls_verification() {
+    // The test CA is not among the bundled roots
+    let server = tls_server();
+    let port = server.local_addr().port();
+    let handle = echo_once(server);
+    let error = TcpConnection::connect_tls(NetAddr::localhost_ipv4(port), &TlsOptions::new())
+        .err()
+        .expect("an untrusted certificate is rejected");
+    assert!(error.to_string().contains("certificate"), "{}", error);
+    handle.join().unwrap();
+
+    // The certificate is not valid for this name
+    let server = tls_server();
+    let port = server.local_addr().port();
+    let handle = echo_once(server);
+    let options = TlsOptions::new().with_ca_file(fixture("ca.pem")).with_server_name("example.com");
+    let error = TcpConnection::connect_tls(NetAddr::localhost_ipv4(port), &options)
+        .err()
+        .expect("a certificate for another name is rejected");
+    assert!(error.to_string().contains("certificate"), "{}", error);
+    handle.join().unwrap();
+
+    // Without verification any certificate is accepted
+    let server = tls_server();
+    let port = server.local_addr().port();
+    let handle = echo_once(server);
+    let mut connection = TcpConnection::connect_tls(NetAddr::localhost_ipv4(port), &TlsOptions::insecure()).unwrap();
+    connection.write_all(b"trust me\n").unwrap();
+    let mut reply = String::new();
+    connection.read_to_string(&mut reply).unwrap();
+    assert_eq!(reply, "TRUST ME\n");
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_https_client() {
+    let server = tls_server();
+    let port = server.local_addr().port();
+    let handle = thread::spawn(move || {
+        let mut connection = server.accept().unwrap();
+        let head = read_until(&mut connection, b"\r\n\r\n");
+        let body = "secure hello";
+        let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+        connection.write_all(response.as_bytes()).unwrap();
+        // Dropped without a close_notify, as many servers do
+        head
+    });
+
+    let client = HttpClient::new().with_tls_options(TlsOptions::new().with_ca_file(fixture("ca.pem")));
+    let response = client.get(&format!("https://localhost:{}/greet?who=tls", port)).unwrap();
+    assert_eq!(response.status.code(), 200);
+    assert_eq!(response.body_as_string().unwrap(), "secure hello");
+
+    let request = handle.join().unwrap();
+    assert!(request.starts_with("GET /greet?who=tls HTTP/1.1\r\n"), "{}", request);
+    assert!(request.contains(&format!("Host: localhost:{}\r\n", port)), "{}", request);
+}
+
+#[test]
+fn test_acceptor_reports_bad_files() {
+    let error = TlsAcceptor::from_pem_files(fixture("missing.pem"), fixture("server.key"))
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("missing.pem"), "{}", error);
+
+    // A certificate file holds no private key
+    let error = TlsAcceptor::from_pem_files(fixture("server.pem"), fixture("ca.pem"))
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("No private key"), "{}", error);
+}
@@ -0,0 +1,84 @@
+//! Tests for the `lang todo` tech-debt report
+
+use bulu::project::{create_project, Project};
+use bulu::todo::{GroupBy, MarkerKind, TodoOptions, TodoScanner};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn new_project(temp_dir: &TempDir) -> Project {
+    create_project("app", Some(temp_dir.path())).unwrap();
+    let root = temp_dir.path().join("app");
+    fs::write(
+        root.join("src").join("main.bu"),
+        "// TODO(ana): read the port from flags\nfunc main() {\n    println(\"FIXME is only text here\")\n}\n",
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("tests")).unwrap();
+    fs::write(
+        root.join("tests").join("main_test.bu"),
+        "/**\n * @deprecated use checkAll\n */\nfunc check() {} // HACK: sleeps\n",
+    )
+    .unwrap();
+    Project::load_from_path(root).unwrap()
+}
+
+fn git(root: &Path, args: &[&str]) -> bool {
+    Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+#[test]
+fn test_scan_project_without_blame() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = new_project(&temp_dir);
+    let report = TodoScanner::new(project, TodoOptions { blame: false }).scan().unwrap();
+
+    let items: Vec<(&str, usize, MarkerKind, &str)> = report
+        .items
+        .iter()
+        .map(|item| (item.file.as_str(), item.line, item.kind, item.text.as_str()))
+        .collect();
+    assert_eq!(
+        items,
+        vec![
+            ("src/main.bu", 1, MarkerKind::Todo, "read the port from flags"),
+            ("tests/main_test.bu", 2, MarkerKind::Deprecated, "use checkAll"),
+            ("tests/main_test.bu", 4, MarkerKind::Hack, "sleeps"),
+        ]
+    );
+    assert_eq!(report.summary(), "3 items in 2 of 2 files: 1 TODO, 1 HACK, 1 deprecated");
+
+    // Without blame, items belong to the owner they name
+    let markdown = report.to_markdown(GroupBy::Author);
+    assert!(markdown.contains("## ana (1)\n"), "{}", markdown);
+    assert!(markdown.contains("## unknown (2)\n"), "{}", markdown);
+
+    let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+    assert_eq!(json["counts"]["deprecated"], 1);
+    assert_eq!(json["items"][0]["owner"], "ana");
+}
+
+#[test]
+fn test_scan_project_with_blame() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = new_project(&temp_dir);
+    let root = project.root.clone();
+    let committed = git(&root, &["init", "-q"])
+        && git(&root, &["add", "src"])
+        && git(&root, &["-c", "user.name=Bea", "-c", "user.email=bea@example.com", "commit", "-qm", "init"]);
+    if !committed {
+        eprintln!("git is not available, skipping");
+        return;
+    }
+
+    let report = TodoScanner::new(project, TodoOptions::default()).scan().unwrap();
+    let authors: Vec<Option<&str>> = report.items.iter().map(|item| item.author.as_deref()).collect();
+    // Uncommitted files have no author
+    assert_eq!(authors, vec![Some("Bea"), None, None]);
+    assert_eq!(report.items[0].attributed_to(), "Bea");
+}
@@ -872,6 +872,32 @@ mod scope_tests {
             }
         "#);
     }
+
+    #[test]
+    fn test_shadowing_restored_after_block() {
+        expect_type_check_success(r#"
+            let x = 42
+            {
+                let x = "inner"
+                let s: string = x
+            }
+            let y: int32 = x + 1
+        "#);
+
+        expect_type_check_failure(r#"
+            {
+                let inner = 1
+            }
+            let y = inner
+        "#);
+
+        expect_type_check_failure(r#"
+            func test() {
+                let x = 1
+                let x = 2
+            }
+        "#);
+    }
 }
 
 #[cfg(test)]
@@ -954,6 +980,25 @@ mod composite_type_tests {
         "#);
     }
 
+    #[test]
+    fn test_buffered_channel_types() {
+        expect_type_check_success(r#"
+            let jobs = make(chan int32, 4)
+            let unbuffered: chan int32 = make(chan int32)
+            let sink: chan<- int32 = jobs
+            for job in jobs {
+                let next: int32 = job + 1
+            }
+        "#);
+
+        expect_type_check_failure("let jobs = make(chan int32, -1)");
+        expect_type_check_failure(r#"let jobs = make(chan int32, "four")"#);
+        expect_type_check_failure(r#"
+            let names = make(chan string, 2)
+            let numbers: chan int32 = names
+        "#);
+    }
+
     #[test]
     fn test_nested_composite_types() {
         // Test simple nested arrays first
@@ -0,0 +1,222 @@
+//! Tests for union and optional types: parsing, assignability, member access,
+//! narrowing through `match` and `typeof`/null checks, and runtime type patterns
+
+mod common;
+
+use bulu::ast::*;
+use bulu::types::primitive::RuntimeValue;
+use common::{assert_type_error, check_source, parse_source, run_main};
+
+const SHAPES: &str = r#"
+    struct Circle {
+        radius: float64
+
+        func area(): float64 {
+            return 3.0 * this.radius * this.radius
+        }
+    }
+
+    struct Square {
+        side: float64
+
+        func area(): float64 {
+            return this.side * this.side
+        }
+    }
+"#;
+
+#[test]
+fn test_union_and_optional_types_are_parsed() {
+    let program = parse_source(
+        r#"
+    func main() {
+        let a: int32 | string | bool = 1
+        let b: string? = null
+        let c: int32 | string? = 2
+    }
+    "#,
+    )
+    .unwrap();
+
+    let Statement::FunctionDecl(main) = &program.statements[0] else {
+        panic!("Expected function declaration");
+    };
+    let annotation = |index: usize| match &main.body.statements[index] {
+        Statement::VariableDecl(decl) => decl.type_annotation.clone().unwrap(),
+        other => panic!("Expected variable declaration, got {:?}", other),
+    };
+
+    let union = |types: Vec<Type>| Type::Union(UnionType { types });
+    assert_eq!(annotation(0), union(vec![Type::Int32, Type::String, Type::Bool]));
+    assert_eq!(annotation(1), union(vec![Type::String, Type::Null]));
+    assert_eq!(annotation(2), union(vec![Type::Int32, Type::String, Type::Null]));
+}
+
+#[test]
+fn test_union_assignability() {
+    let source = r#"
+    type Id = int32 | string
+
+    func describe(id: Id): string {
+        return "id"
+    }
+
+    func main() {
+        let a: int32 | string = 1
+        let b: int32 | string = "one"
+        let c: string? = null
+        let d: string? = "set"
+        let e: string | int32 = a
+        let f: int32 | string | bool = b
+        describe(5)
+        describe("five")
+    }
+    "#;
+    assert!(check_source(source).is_ok());
+
+    assert_type_error(
+        "func main() {\n    let a: int32 | string = true\n}\n",
+        "Cannot assign bool to variable of type int32 | string",
+    );
+    assert_type_error(
+        "func main() {\n    let a: int32 | string = 1\n    let b: int32 = a\n}\n",
+        "Cannot assign int32 | string to variable of type int32",
+    );
+}
+
+#[test]
+fn test_union_member_access_requires_every_variant() {
+    let ok = format!(
+        r#"{}
+    func total(shape: Circle | Square): float64 {{
+        return shape.area()
+    }}
+    "#,
+        SHAPES
+    );
+    assert!(check_source(&ok).is_ok());
+
+    let missing = format!(
+        r#"{}
+    func size(shape: Circle | Square): float64 {{
+        return shape.radius
+    }}
+    "#,
+        SHAPES
+    );
+    assert_type_error(
+        &missing,
+        "Member 'radius' does not exist on all variants of Circle | Square (missing on struct Square)",
+    );
+}
+
+#[test]
+fn test_match_narrows_and_checks_exhaustiveness() {
+    let ok = r#"
+    func describe(value: int32 | string): string {
+        return match value {
+            n: int32 -> "number",
+            s: string -> s,
+        }
+    }
+    "#;
+    assert!(check_source(ok).is_ok());
+
+    assert_type_error(
+        r#"
+    func describe(value: int32 | string | bool): string {
+        return match value {
+            n: int32 -> "number",
+            s: string -> s,
+        }
+    }
+    "#,
+        "variants bool of bool | int32 | string are not covered",
+    );
+    assert_type_error(
+        r#"
+    func describe(value: int32 | string): string {
+        return match value {
+            b: bool -> "bool",
+            _ -> "other",
+        }
+    }
+    "#,
+        "Type pattern 'bool' can never match a value of type int32 | string",
+    );
+    assert_type_error(
+        r#"
+    func twice(n: int32): int32 {
+        return n * 2
+    }
+
+    func describe(value: int32 | string): int32 {
+        return match value {
+            n: int32 -> twice(n),
+            s: string -> twice(s),
+        }
+    }
+    "#,
+        "expected int32, got string",
+    );
+}
+
+#[test]
+fn test_typeof_and_null_checks_narrow() {
+    let ok = r#"
+    func length(value: int32 | string): int32 {
+        if typeof(value) == "string" {
+            let s: string = value
+        } else {
+            let n: int32 = value
+        }
+        return 0
+    }
+
+    func orEmpty(name: string?): string {
+        if name != null {
+            let s: string = name
+        } else {
+            let missing: string? = name
+        }
+        return ""
+    }
+    "#;
+    assert!(check_source(ok).is_ok());
+
+    assert_type_error(
+        r#"
+    func length(value: int32 | string): int32 {
+        if typeof(value) == "string" {
+            let n: int32 = value
+        }
+        return 0
+    }
+    "#,
+        "Cannot assign string to variable of type int32",
+    );
+}
+
+#[test]
+fn test_runtime_type_patterns() {
+    let source = format!(
+        r#"{}
+    func name(value: int32 | string | Circle): string {{
+        return match value {{
+            n: int32 -> "int",
+            s: string -> "string:" + s,
+            c: Circle -> "circle",
+        }}
+    }}
+
+    func main(): string {{
+        return name(1) + " " + name("x") + " " + name(Circle{{ radius: 1.0 }}) + " " + typeof(2)
+    }}
+    "#,
+        SHAPES
+    );
+    assert_eq!(
+        run_main(&source).unwrap(),
+        RuntimeValue::String("int string:x circle int32".to_string())
+    );
+}
@@ -0,0 +1,242 @@
+//! Tests for the method tables of user-defined structs
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::lexer::token::Position;
+use bulu::lexer::Lexer;
+use bulu::parser::Parser;
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use bulu::runtime::vtable::VTables;
+use bulu::types::primitive::RuntimeValue;
+use std::collections::HashMap;
+
+fn parse(source: &str) -> Program {
+    let tokens = Lexer::new(source).tokenize().unwrap();
+    Parser::new(tokens).parse().unwrap()
+}
+
+/// Build the tables of the structs and interfaces a program declares
+fn vtables(program: &Program) -> VTables {
+    let mut structs = HashMap::new();
+    let mut interfaces = HashMap::new();
+    for statement in &program.statements {
+        match statement {
+            Statement::StructDecl(decl) => {
+                structs.insert(decl.name.clone(), decl.clone());
+            }
+            Statement::InterfaceDecl(decl) => {
+                interfaces.insert(decl.name.clone(), decl.clone());
+            }
+            _ => {}
+        }
+    }
+    VTables::build(&structs, &interfaces)
+}
+
+/// Helper function to run `main` with the AST interpreter
+fn run_main(source: &str) -> Result<RuntimeValue, BuluError> {
+    let program = parse(source);
+    let mut interpreter = AstInterpreter::new();
+    interpreter.execute_program(&program)?;
+    let main_func = interpreter.get_function_definition("main").expect("main should be defined");
+    interpreter.call_user_function(&main_func, &[])
+}
+
+const SHAPES: &str = r#"
+interface Named {
+    func name(): string {
+        return "named"
+    }
+}
+
+interface Labeled {
+    func name(): string {
+        return "labeled"
+    }
+
+    func label(): string {
+        return "label"
+    }
+}
+
+interface Sized {
+    func size(): int32
+
+    func describe(): string {
+        return "sized"
+    }
+}
+
+struct Box {
+    width: int32
+
+    func label(): string {
+        return "box"
+    }
+}
+
+struct Point {
+    x: int32
+
+    func size(): int32 {
+        return 1
+    }
+}
+"#;
+
+#[test]
+fn test_tables_prefer_own_methods_then_interfaces_by_name() {
+    let program = parse(SHAPES);
+    let vtables = vtables(&program);
+
+    let method = |struct_name: &str, method: &str| {
+        vtables.lookup(struct_name, method).map(|decl| match &decl.body.statements[0] {
+            Statement::Return(ReturnStmt { value: Some(Expression::Literal(literal)), .. }) => {
+                format!("{:?}", literal.value)
+            }
+            other => format!("{:?}", other),
+        })
+    };
+    // A struct's own method wins over an inherited default
+    assert_eq!(method("Box", "label").as_deref(), Some("String(\"box\")"));
+    // Of two interfaces with a `name` default, the first by name supplies it
+    assert_eq!(method("Box", "name").as_deref(), Some("String(\"labeled\")"));
+    // Defaults only come from interfaces the struct conforms to
+    assert!(method("Box", "describe").is_none());
+    assert_eq!(method("Point", "describe").as_deref(), Some("String(\"sized\")"));
+    assert!(vtables.lookup("Box", "size").is_none());
+    assert!(vtables.lookup("Missing", "label").is_none());
+    assert!(vtables.selector("undefined").is_none());
+
+    // Selectors are shared between tables
+    let selector = vtables.selector("name").unwrap();
+    assert!(vtables.table("Point").unwrap().get(selector).is_some());
+}
+
+#[test]
+fn test_interpreter_dispatches_through_tables() {
+    let source = format!(
+        "{}{}",
+        SHAPES,
+        r#"
+func main(): any {
+    let b = Box { width: 2 }
+    let p = Point { x: 3 }
+    return (b.label(), b.name(), p.describe(), p.size())
+}
+"#
+    );
+    assert_eq!(
+        run_main(&source).unwrap(),
+        RuntimeValue::Tuple(vec![
+            RuntimeValue::String("box".to_string()),
+            RuntimeValue::String("labeled".to_string()),
+            RuntimeValue::String("sized".to_string()),
+            RuntimeValue::Integer(1),
+        ])
+    );
+
+    let error = run_main(&format!("{}func main() {{ let b = Box {{ width: 1 }}\nb.size() }}", SHAPES)).unwrap_err();
+    assert!(error.to_string().contains("Method 'size' not found on struct 'Box'"), "{}", error);
+}
+
+/// Positions of the `object.method(...)` calls among a function's statements
+fn method_calls(program: &Program, function: &str) -> Vec<(String, Position)> {
+    let decl = program
+        .statements
+        .iter()
+        .find_map(|statement| match statement {
+            Statement::FunctionDecl(decl) if decl.name == function => Some(decl),
+            _ => None,
+        })
+        .unwrap();
+    decl.body
+        .statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Expression(ExpressionStmt { expr: Expression::Call(call), .. }) => match call.callee.as_ref() {
+                Expression::MemberAccess(member_access) => {
+                    Some((member_access.member.clone(), member_access.position))
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_call_sites_resolve_to_selectors_before_running() {
+    let program = parse(&format!(
+        "{}{}",
+        SHAPES,
+        r#"
+func main() {
+    let b = Box { width: 2 }
+    b.label()
+    b.name()
+    b.missing()
+}
+"#
+    ));
+    let mut vtables = vtables(&program);
+    vtables.resolve_call_sites(&program);
+
+    let calls = method_calls(&program, "main");
+    assert_eq!(calls.len(), 3);
+    let (_, label) = &calls[0];
+    let (_, name) = &calls[1];
+    let (_, missing) = &calls[2];
+    assert_eq!(vtables.call_site(*label), vtables.selector("label"));
+    assert_eq!(vtables.call_site(*name), vtables.selector("name"));
+    // No struct defines `missing`, so its call has no selector
+    assert_eq!(vtables.call_site(*missing), None);
+
+    // A site remembers the struct it dispatched on
+    let site = vtables.dispatch(*label, "Box", "label").unwrap();
+    assert_eq!(site.get("Box", "label").unwrap().name, "label");
+    assert!(site.get("Point", "label").is_none());
+    assert!(vtables.dispatch(*label, "Missing", "label").is_none());
+    assert!(vtables.dispatch(*missing, "Box", "missing").is_none());
+}
+
+#[test]
+fn test_structs_declared_while_running_rebuild_tables() {
+    let program = parse(
+        r#"
+struct Counter {
+    count: int32
+
+    func get(): int32 {
+        return 1
+    }
+}
+
+func main(): int32 {
+    let c = Counter { count: 0 }
+    return c.get()
+}
+"#,
+    );
+    let mut interpreter = AstInterpreter::new();
+    interpreter.execute_program(&program).unwrap();
+    let main_func = interpreter.get_function_definition("main").unwrap();
+    assert_eq!(interpreter.call_user_function(&main_func, &[]).unwrap(), RuntimeValue::Integer(1));
+
+    // Redeclaring the struct replaces the method the call site found before
+    let redeclared = parse(
+        r#"
+struct Counter {
+    count: int32
+
+    func get(): int32 {
+        return 2
+    }
+}
+"#,
+    );
+    for statement in &redeclared.statements {
+        interpreter.execute_statement(statement).unwrap();
+    }
+    assert_eq!(interpreter.call_user_function(&main_func, &[]).unwrap(), RuntimeValue::Integer(2));
+}
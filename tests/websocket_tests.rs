@@ -0,0 +1,213 @@
+//! Tests for WebSocket routes and clients in std/http
+
+mod common;
+
+use bulu::ast::*;
+use bulu::error::BuluError;
+use bulu::runtime::ast_interpreter::AstInterpreter;
+use bulu::runtime::channels::ChannelResult;
+use bulu::std::tls::TlsOptions;
+use bulu::std::websocket::{accept_key, Frame, Message, Opcode, WebSocket};
+use bulu::types::primitive::RuntimeValue;
+use common::check_with_imports;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+const IMPORTS: &str = "import { newServer, connectWebSocket } from \"std/http\"\n";
+
+const SERVER: &str = r#"
+    func echo(ws: WebSocket, req: Request) {
+        let prefix = req.query["prefix"]
+        for msg in ws.messages() {
+            match msg.kind {
+                "text" -> ws.send(prefix + msg.text)
+                _ -> ws.send(msg.data)
+            }
+        }
+    }
+
+    func greet(ws: WebSocket, req: Request) {
+        let out = ws.outgoing()
+        out <- "hello " + req.params["name"]
+        close(out)
+    }
+
+    func start(): any {
+        let srv = newServer()
+        srv.websocket("/echo", echo)
+        srv.websocket("/greet/:name", greet)
+        let addr = srv.bind("127.0.0.1:0").unwrap()
+        run srv.serve()
+        return (srv, addr)
+    }
+
+    func stop(srv: Server) {
+        srv.shutdown()
+    }
+
+    func ask(url: string, question: string): string {
+        let ws = connectWebSocket(url).unwrap()
+        ws.send(question)
+        let reply: Message = <-ws.messages()
+        ws.close()
+        return reply.text
+    }
+"#;
+
+/// Helper function to parse, resolve imports and type check source code
+fn check_source(source: &str) -> Result<Program, BuluError> {
+    check_with_imports(IMPORTS, source)
+}
+
+/// Start the test server, returning the interpreter, the server handle and its address
+fn start_server() -> (AstInterpreter, RuntimeValue, String) {
+    let program = check_source(SERVER).unwrap();
+    let mut interpreter = AstInterpreter::new();
+    interpreter.execute_program(&program).unwrap();
+    let start = interpreter.get_function_definition("start").unwrap();
+    match interpreter.call_user_function(&start, &[]).unwrap() {
+        RuntimeValue::Tuple(values) => match values.as_slice() {
+            [server, RuntimeValue::String(addr)] => (interpreter, server.clone(), addr.clone()),
+            other => panic!("unexpected start() result {:?}", other),
+        },
+        other => panic!("unexpected start() result {:?}", other),
+    }
+}
+
+fn stop_server(interpreter: &mut AstInterpreter, server: RuntimeValue) {
+    let stop = interpreter.get_function_definition("stop").unwrap();
+    interpreter.call_user_function(&stop, &[server]).unwrap();
+}
+
+/// The next message received, as a Rust value
+fn receive(socket: &WebSocket) -> Option<Message> {
+    match socket.messages().receive_timeout(Duration::from_secs(5)).unwrap() {
+        ChannelResult::Ok(value) => Some(Message::from_value(&value).unwrap()),
+        ChannelResult::Closed => None,
+        ChannelResult::WouldBlock => panic!("no message within 5s"),
+    }
+}
+
+#[test]
+fn test_server_echoes_messages() {
+    let (mut interpreter, server, addr) = start_server();
+
+    let socket = WebSocket::connect(&format!("ws://{}/echo?prefix=re:", addr), &TlsOptions::new()).unwrap();
+    socket.send(Message::Text("hello".to_string())).unwrap();
+    assert_eq!(receive(&socket), Some(Message::Text("re:hello".to_string())));
+    socket.send(Message::Binary(vec![0, 1, 255])).unwrap();
+    assert_eq!(receive(&socket), Some(Message::Binary(vec![0, 1, 255])));
+    // Large messages use the longer length encodings
+    let large = "x".repeat(70_000);
+    socket.send(Message::Text(large.clone())).unwrap();
+    assert_eq!(receive(&socket), Some(Message::Text(format!("re:{}", large))));
+
+    // Closing the client ends the handler's loop, and the server closes back
+    socket.close(1000, "bye");
+    assert_eq!(receive(&socket), None);
+    assert!(!socket.is_open());
+
+    // Values sent on the outgoing channel become messages, and closing it
+    // closes the connection
+    let socket = WebSocket::connect(&format!("ws://{}/greet/Ada", addr), &TlsOptions::new()).unwrap();
+    assert_eq!(receive(&socket), Some(Message::Text("hello Ada".to_string())));
+    assert_eq!(receive(&socket), None);
+
+    // Shutting down closes open connections so their handlers return
+    let socket = WebSocket::connect(&format!("ws://{}/echo", addr), &TlsOptions::new()).unwrap();
+    stop_server(&mut interpreter, server);
+    assert_eq!(receive(&socket), None);
+}
+
+#[test]
+fn test_bulu_client() {
+    let (mut interpreter, server, addr) = start_server();
+
+    let ask = interpreter.get_function_definition("ask").unwrap();
+    let url = RuntimeValue::String(format!("ws://{}/echo?prefix=bulu:", addr));
+    let reply = interpreter
+        .call_user_function(&ask, &[url, RuntimeValue::String("hi".to_string())])
+        .unwrap();
+    assert_eq!(reply, RuntimeValue::String("bulu:hi".to_string()));
+
+    stop_server(&mut interpreter, server);
+}
+
+#[test]
+fn test_plain_requests_are_refused() {
+    let (mut interpreter, server, addr) = start_server();
+
+    let mut stream = TcpStream::connect(&addr).unwrap();
+    stream.write_all(b"GET /echo HTTP/1.1\r\nHost: x\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 426 Upgrade Required\r\n"), "{}", response);
+
+    let error = WebSocket::connect(&format!("ws://{}/missing", addr), &TlsOptions::new()).unwrap_err();
+    assert!(error.contains("404"), "{}", error);
+    let error = WebSocket::connect(&format!("http://{}/echo", addr), &TlsOptions::new()).unwrap_err();
+    assert!(error.contains("expected ws:// or wss://"), "{}", error);
+
+    stop_server(&mut interpreter, server);
+}
+
+/// Accept one connection and answer its handshake
+fn accept_raw(listener: &TcpListener) -> TcpStream {
+    let (mut stream, _) = listener.accept().unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        stream.read_exact(&mut byte).unwrap();
+        head.push(byte[0]);
+    }
+    let head = String::from_utf8(head).unwrap();
+    let key = head
+        .lines()
+        .find_map(|line| line.strip_prefix("Sec-WebSocket-Key: "))
+        .unwrap();
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    );
+    stream.write_all(response.as_bytes()).unwrap();
+    stream
+}
+
+/// Read one frame sent by a client
+fn read_frame(stream: &mut TcpStream) -> Frame {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        if let Some((frame, _)) = Frame::decode(&data, true).unwrap() {
+            return frame;
+        }
+        let count = stream.read(&mut buf).unwrap();
+        assert!(count > 0, "client closed the connection");
+        data.extend_from_slice(&buf[..count]);
+    }
+}
+
+#[test]
+fn test_keepalive() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}/", listener.local_addr().unwrap());
+
+    // Pings from the peer are answered with the same payload
+    let server = std::thread::spawn(move || accept_raw(&listener));
+    let socket = WebSocket::connect(&url, &TlsOptions::new()).unwrap();
+    let mut stream = server.join().unwrap();
+    stream
+        .write_all(&Frame::new(Opcode::Ping, b"beat".to_vec()).encode(false))
+        .unwrap();
+    let pong = read_frame(&mut stream);
+    assert_eq!((pong.opcode, pong.payload.as_slice()), (Opcode::Pong, &b"beat"[..]));
+
+    // An idle connection is pinged, and dropped when no pong comes back
+    socket.set_keepalive(Duration::from_millis(50));
+    let ping = read_frame(&mut stream);
+    assert_eq!(ping.opcode, Opcode::Ping);
+    assert_eq!(receive(&socket), None);
+    assert!(!socket.is_open());
+}